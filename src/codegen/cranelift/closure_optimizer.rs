@@ -68,21 +68,20 @@ impl ClosureOptimizer {
         translator: &mut FunctionTranslator<'a>,
         instruction: &Instruction,
         value_id: ValueId,
-        builder: &mut FunctionBuilder,
+        _builder: &mut FunctionBuilder,
     ) -> CodegenResult<bool> {
         if let Instruction::CreateClosure {
             function_id,
             parameters,
             captured_vars,
-            captures_by_ref,
+            ..
         } = instruction
         {
             // Track closure creation for direct call optimization
             self.closure_values.insert(value_id, function_id.clone());
 
             // Check if we have a Cranelift function for this closure
-            let closure_func_name = format!("closure_{}", function_id);
-            if let Some(func_id) = translator.func_ids.get(&closure_func_name) {
+            if let Some(func_id) = translator.func_ids.get(function_id) {
                 self.known_closures.insert(function_id.clone(), *func_id);
             }
 
@@ -97,21 +96,9 @@ impl ClosureOptimizer {
                 self.inlinable_closures
                     .insert(function_id.clone(), inline_info);
             }
-
-            // Check if this is an optimized closure candidate
-            if self.is_optimizable_closure(parameters, captured_vars) {
-                // Use optimized creation path
-                return self.create_optimized_closure(
-                    translator,
-                    value_id,
-                    function_id,
-                    parameters,
-                    captured_vars,
-                    *captures_by_ref,
-                    builder,
-                );
-            }
         }
+
+        // The closure record itself is built by the standard translation
         Ok(false)
     }
 
@@ -153,6 +140,7 @@ impl ClosureOptimizer {
                     translator,
                     value_id,
                     target_func,
+                    *closure,
                     args,
                     return_type,
                     builder,
@@ -173,119 +161,10 @@ impl ClosureOptimizer {
                     return Ok(tail_optimized);
                 }
             }
-
-            // Try fast-path for small argument counts
-            if args.len() <= 4 {
-                self.stats.fast_path_calls += 1;
-                return self.emit_fast_path_call(
-                    translator,
-                    value_id,
-                    *closure,
-                    args,
-                    return_type,
-                    builder,
-                );
-            }
         }
         Ok(false)
     }
 
-    /// Check if a closure is optimizable
-    fn is_optimizable_closure(
-        &self,
-        parameters: &[String],
-        captured_vars: &[(String, ValueId)],
-    ) -> bool {
-        // Optimize closures with:
-        // - 4 or fewer parameters (fits in registers)
-        // - 4 or fewer captures (uses inline storage)
-        parameters.len() <= 4 && captured_vars.len() <= 4
-    }
-
-    /// Create an optimized closure using fast allocation
-    fn create_optimized_closure<'a>(
-        &mut self,
-        translator: &mut FunctionTranslator<'a>,
-        value_id: ValueId,
-        function_id: &str,
-        parameters: &[String],
-        captured_vars: &[(String, ValueId)],
-        captures_by_ref: bool,
-        builder: &mut FunctionBuilder,
-    ) -> CodegenResult<bool> {
-        // Get the runtime allocation function
-        let alloc_func = translator.import_runtime_function(
-            "script_alloc",
-            &[types::I64],    // size
-            Some(types::I64), // returns pointer
-            builder,
-        )?;
-
-        // Calculate closure size:
-        // - 8 bytes for function pointer/ID
-        // - 4 bytes for parameter count
-        // - 4 bytes for capture count
-        // - 8 bytes per capture (inline storage for up to 4 captures)
-        let base_size = 8 + 4 + 4;
-        let capture_size = captured_vars.len().min(4) * 8;
-        let total_size = base_size + capture_size;
-
-        // Allocate the closure
-        let size_val = builder.ins().iconst(types::I64, total_size as i64);
-        let closure_ptr = builder.ins().call(alloc_func, &[size_val]);
-        let closure_ptr = builder.inst_results(closure_ptr)[0];
-
-        // Store function ID (interned)
-        let func_id_val = self.intern_function_id(function_id, builder);
-        let memflags = MemFlags::new();
-        builder.ins().store(memflags, func_id_val, closure_ptr, 0);
-
-        // Store parameter count
-        let param_count = builder.ins().iconst(types::I32, parameters.len() as i64);
-        builder.ins().store(memflags, param_count, closure_ptr, 8);
-
-        // Store capture count
-        let capture_count = builder.ins().iconst(types::I32, captured_vars.len() as i64);
-        builder
-            .ins()
-            .store(memflags, capture_count, closure_ptr, 12);
-
-        // Store captured values inline (up to 4)
-        let mut offset = 16;
-        for (i, (_name, capture_id)) in captured_vars.iter().enumerate() {
-            if i >= 4 {
-                break; // Only store first 4 captures inline
-            }
-
-            let capture_val = translator.get_value(*capture_id)?;
-
-            // If capturing by reference, store the address; otherwise store the value
-            let stored_val = if captures_by_ref {
-                // Create a stack slot to hold the reference
-                let slot = builder.create_sized_stack_slot(StackSlotData::new(
-                    StackSlotKind::ExplicitSlot,
-                    8,
-                    8,
-                ));
-                let addr = builder.ins().stack_addr(types::I64, slot, 0);
-                builder.ins().store(memflags, capture_val, addr, 0);
-                addr
-            } else {
-                capture_val
-            };
-
-            builder
-                .ins()
-                .store(memflags, stored_val, closure_ptr, offset);
-            offset += 8;
-        }
-
-        // Store the closure pointer as the result
-        translator.insert_value(value_id, closure_ptr);
-
-        Ok(true)
-    }
-
     /// Try to resolve a direct call target at compile time
     fn try_resolve_direct_call<'a>(
         &self,
@@ -299,8 +178,7 @@ impl ClosureOptimizer {
                 return Some(*func_id);
             }
             // Try to find it in the module
-            let closure_func_name = format!("closure_{}", function_id);
-            if let Some(func_id) = translator.func_ids.get(&closure_func_name) {
+            if let Some(func_id) = translator.func_ids.get(function_id) {
                 return Some(*func_id);
             }
         }
@@ -313,6 +191,7 @@ impl ClosureOptimizer {
         translator: &mut FunctionTranslator<'a>,
         value_id: ValueId,
         target_func: FuncId,
+        closure_id: ValueId,
         args: &[ValueId],
         return_type: &Type,
         builder: &mut FunctionBuilder,
@@ -322,8 +201,15 @@ impl ClosureOptimizer {
             .module
             .declare_func_in_func(target_func, builder.func);
 
+        // The closure function takes its environment first
+        let closure_ptr = translator.get_value(closure_id)?;
+        let env_ptr = builder
+            .ins()
+            .load(types::I64, MemFlags::new(), closure_ptr, 8);
+
         // Translate arguments
-        let mut arg_values = Vec::with_capacity(args.len());
+        let mut arg_values = Vec::with_capacity(args.len() + 1);
+        arg_values.push(env_ptr);
         for arg in args {
             arg_values.push(translator.get_value(*arg)?);
         }
@@ -336,7 +222,11 @@ impl ClosureOptimizer {
         if !results.is_empty() {
             let result = results[0];
             translator.insert_value(value_id, result);
-        } else if !matches!(return_type, Type::Tuple(v) if v.is_empty()) {
+        } else if *return_type == Type::Unknown {
+            // The closure returns nothing, so create a dummy value
+            let result = builder.ins().iconst(types::I32, 0);
+            translator.insert_value(value_id, result);
+        } else {
             // For non-void returns, we need a result
             return Err(Error::new(
                 ErrorKind::RuntimeError,
@@ -347,107 +237,6 @@ impl ClosureOptimizer {
         Ok(true)
     }
 
-    /// Emit a fast-path call for closures with ≤4 arguments
-    fn emit_fast_path_call<'a>(
-        &mut self,
-        translator: &mut FunctionTranslator<'a>,
-        value_id: ValueId,
-        closure_id: ValueId,
-        args: &[ValueId],
-        return_type: &Type,
-        builder: &mut FunctionBuilder,
-    ) -> CodegenResult<bool> {
-        // Import the fast-path invocation function
-        let invoke_func = translator.import_runtime_function(
-            "script_invoke_closure_fast",
-            &[
-                types::I64, // closure pointer
-                types::I64, // arg0 (or 0)
-                types::I64, // arg1 (or 0)
-                types::I64, // arg2 (or 0)
-                types::I64, // arg3 (or 0)
-                types::I32, // actual arg count
-            ],
-            Some(types::I64),
-            builder,
-        )?;
-
-        // Get the closure pointer
-        let closure_ptr = translator.get_value(closure_id)?;
-
-        // Prepare arguments (up to 4 with padding)
-        let mut arg_values = Vec::with_capacity(4);
-        for i in 0..4 {
-            if i < args.len() {
-                arg_values.push(translator.get_value(args[i])?);
-            } else {
-                // Pad with null pointers
-                arg_values.push(builder.ins().iconst(types::I64, 0));
-            }
-        }
-
-        // Add actual argument count
-        let arg_count = builder.ins().iconst(types::I32, args.len() as i64);
-
-        // Call the fast-path runtime function
-        let mut call_args = vec![closure_ptr];
-        call_args.extend(arg_values);
-        call_args.push(arg_count);
-
-        let call_inst = builder.ins().call(invoke_func, &call_args);
-        let result_ptr = builder.inst_results(call_inst)[0];
-
-        // Load the result based on return type
-        let result = match return_type {
-            Type::I32 => builder
-                .ins()
-                .load(types::I32, MemFlags::new(), result_ptr, 0),
-            Type::F32 => builder
-                .ins()
-                .load(types::F32, MemFlags::new(), result_ptr, 0),
-            Type::Bool => builder
-                .ins()
-                .load(types::I8, MemFlags::new(), result_ptr, 0),
-            _ => result_ptr, // For other types, return the pointer itself
-        };
-
-        translator.insert_value(value_id, result);
-        Ok(true)
-    }
-
-    /// Intern a function ID for fast comparison
-    fn intern_function_id(&mut self, function_id: &str, builder: &mut FunctionBuilder) -> Value {
-        // In a real implementation, this would use the global function ID cache
-        // For now, return a hash of the function ID
-        let hash = function_id
-            .bytes()
-            .fold(0u64, |acc, b| acc.wrapping_mul(31).wrapping_add(b as u64));
-        builder.ins().iconst(types::I64, hash as i64)
-    }
-
-    /// Pack captured values into an inline array
-    fn pack_inline_captures<'a>(
-        &self,
-        _translator: &mut FunctionTranslator<'a>,
-        _captured_vars: &[(String, ValueId)],
-        builder: &mut FunctionBuilder,
-    ) -> CodegenResult<Value> {
-        // Allocate space for up to 4 captures
-        let array_size = 4 * 8; // 4 values * 8 bytes each
-        let stack_slot = builder.create_sized_stack_slot(StackSlotData::new(
-            StackSlotKind::ExplicitSlot,
-            array_size,
-            8,
-        ));
-        let array_ptr = builder.ins().stack_addr(types::I64, stack_slot, 0);
-
-        // In a real implementation, this would:
-        // 1. Get values from translator's value mapping
-        // 2. Store them in the allocated array
-        // For now, just return the array pointer
-        Ok(array_ptr)
-    }
-
     /// Get optimization statistics
     pub fn stats(&self) -> &OptimizationStats {
         &self.stats
@@ -625,29 +414,21 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_is_optimizable_closure() {
+    fn test_is_inlinable_closure() {
         let optimizer = ClosureOptimizer::new();
 
-        // Optimizable: few parameters and captures
-        assert!(optimizer.is_optimizable_closure(
+        // Inlinable: few parameters and captures
+        assert!(optimizer.is_inlinable_closure(
             &["x".to_string(), "y".to_string()],
             &[("a".to_string(), ValueId(1)), ("b".to_string(), ValueId(2))]
         ));
 
-        // Not optimizable: too many parameters
-        assert!(!optimizer.is_optimizable_closure(
-            &[
-                "a".to_string(),
-                "b".to_string(),
-                "c".to_string(),
-                "d".to_string(),
-                "e".to_string()
-            ],
-            &[]
-        ));
+        // Not inlinable: too many parameters
+        assert!(!optimizer
+            .is_inlinable_closure(&["a".to_string(), "b".to_string(), "c".to_string()], &[]));
 
-        // Not optimizable: too many captures
-        let many_captures: Vec<_> = (0..5).map(|i| (format!("var{}", i), ValueId(i))).collect();
-        assert!(!optimizer.is_optimizable_closure(&[], &many_captures));
+        // Not inlinable: too many captures
+        let many_captures: Vec<_> = (0..3).map(|i| (format!("var{}", i), ValueId(i))).collect();
+        assert!(!optimizer.is_inlinable_closure(&[], &many_captures));
     }
 }
@@ -6,9 +6,16 @@ use cranelift::prelude::*;
 use cranelift_jit::{JITBuilder, JITModule};
 use cranelift_module::{FuncId, Linkage, Module};
 
-use crate::codegen::debug::{DebugContext, DebugFlags};
+use crate::codegen::debug::{
+    DebugContext, DebugFlags, DwarfLocation, DwarfSections, FunctionDebugInfo, LineRange,
+    VariableDebugInfo,
+};
+use crate::compilation::SourceMap;
 use crate::error::{Error, ErrorKind};
-use crate::ir::{Function as IrFunction, Module as IrModule};
+use crate::ir::{
+    intrinsic, Function as IrFunction, Module as IrModule, Probe, ProbePolicy, ProbeSite, ValueId,
+};
+use crate::source::Span;
 use crate::types::Type as ScriptType;
 
 use super::{CodegenBackend, CodegenResult, ExecutableModule};
@@ -29,16 +36,26 @@ pub struct CraneliftBackend {
     module: JITModule,
     /// Cranelift context
     ctx: codegen::Context,
+    /// Target the functions are compiled for
+    isa: std::sync::Arc<dyn codegen::isa::TargetIsa>,
     /// Function name to ID mapping
     func_ids: HashMap<String, FuncId>,
     /// Debug information context
     debug_context: Option<DebugContext>,
+    /// Source map of the module being compiled, kept alongside debug info
+    source_map: Option<SourceMap>,
     /// Debug compilation flags
     debug_flags: DebugFlags,
     /// Field layout registry for struct types
     field_layouts: crate::codegen::FieldLayoutRegistry,
     /// Bounds checker for array operations
     bounds_checker: crate::codegen::BoundsChecker,
+    /// Overflow behavior of integer arithmetic
+    overflow_mode: crate::codegen::OverflowMode,
+    /// Whether pointer stores call the GC write barrier
+    write_barriers: bool,
+    /// Whether functions call the profiler on entry and exit
+    profiling: bool,
     /// Closure optimizer for performance enhancements
     closure_optimizer: ClosureOptimizer,
 }
@@ -57,6 +74,7 @@ impl CraneliftBackend {
             .expect("Failed to create ISA");
 
         // Create JIT builder
+        let isa = target_isa.clone();
         let mut jit_builder =
             JITBuilder::with_isa(target_isa, cranelift_module::default_libcall_names());
 
@@ -72,13 +90,18 @@ impl CraneliftBackend {
         CraneliftBackend {
             module,
             ctx,
+            isa,
             func_ids: HashMap::new(),
             debug_context: None,
+            source_map: None,
             debug_flags: DebugFlags::default(),
             field_layouts: crate::codegen::FieldLayoutRegistry::new(),
             bounds_checker: crate::codegen::BoundsChecker::new(
                 crate::codegen::BoundsCheckMode::Always,
             ),
+            overflow_mode: crate::codegen::OverflowMode::default(),
+            write_barriers: false,
+            profiling: false,
             closure_optimizer: ClosureOptimizer::new(),
         }
     }
@@ -90,13 +113,7 @@ impl CraneliftBackend {
 
         // Initialize debug context if debug info is enabled
         if backend.debug_flags.debug_info {
-            backend.debug_context = Some(DebugContext::new(
-                std::env::current_dir()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                "Script Language Compiler 0.1.0".to_string(),
-            ));
+            backend.debug_context = Some(new_debug_context());
         }
 
         backend
@@ -108,20 +125,39 @@ impl CraneliftBackend {
 
         // Initialize or clear debug context based on flags
         if self.debug_flags.debug_info && self.debug_context.is_none() {
-            self.debug_context = Some(DebugContext::new(
-                std::env::current_dir()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string(),
-                "Script Language Compiler 0.1.0".to_string(),
-            ));
+            self.debug_context = Some(new_debug_context());
         } else if !self.debug_flags.debug_info {
             self.debug_context = None;
         }
     }
 
+    /// Set the overflow behavior of integer arithmetic
+    pub fn set_overflow_mode(&mut self, mode: crate::codegen::OverflowMode) {
+        self.overflow_mode = mode;
+    }
+
+    /// Emit the write barriers the runtime's garbage collector needs
+    pub fn set_gc_mode(&mut self, mode: crate::runtime::GcMode) {
+        self.write_barriers = mode.needs_write_barriers();
+    }
+
+    /// Instrument function entry and exit for the function profiler
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+    }
+
     /// Compile an IR module
     fn compile_module(&mut self, ir_module: &IrModule) -> CodegenResult<()> {
+        let profiled;
+        let ir_module = if self.profiling {
+            let mut module = ir_module.clone();
+            crate::ir::instrument(&mut module, &mut ProfilePolicy);
+            profiled = module;
+            &profiled
+        } else {
+            ir_module
+        };
+
         // Declare runtime functions
         self.declare_runtime_functions()?;
 
@@ -130,8 +166,12 @@ impl CraneliftBackend {
             self.declare_function(func)?;
         }
 
-        // Second pass: compile function bodies
+        // Second pass: compile function bodies. Trait method stubs have none;
+        // monomorphization redirects their calls to the concrete impls
         for (_, func) in ir_module.functions() {
+            if func.trait_method.is_some() {
+                continue;
+            }
             self.compile_function(func, ir_module)?;
         }
 
@@ -143,6 +183,27 @@ impl CraneliftBackend {
             )
         })?;
 
+        // Functions only have addresses once finalized
+        if let Some(ref mut debug_ctx) = self.debug_context {
+            let names: Vec<String> = debug_ctx
+                .functions()
+                .iter()
+                .map(|function| function.name.clone())
+                .collect();
+            for name in names {
+                if let Some(func_id) = self.func_ids.get(&name) {
+                    let address = self.module.get_finalized_function(*func_id) as u64;
+                    debug_ctx.set_function_address(&name, address);
+                }
+            }
+        }
+        if let Some(ref mut source_map) = self.source_map {
+            for (name, func_id) in &self.func_ids {
+                let address = self.module.get_finalized_function(*func_id) as u64;
+                source_map.set_function_address(name, address);
+            }
+        }
+
         Ok(())
     }
 
@@ -226,6 +287,28 @@ impl CraneliftBackend {
             self.bounds_checker.set_panic_handler(func_id);
         }
 
+        // Declare script_gc_write_barrier(object: i64)
+        {
+            let mut sig = self.module.make_signature();
+            sig.params.push(AbiParam::new(types::I64)); // object ptr
+
+            let func_id = self
+                .module
+                .declare_function("script_gc_write_barrier", Linkage::Import, &sig)
+                .map_err(|e| {
+                    Error::new(
+                        ErrorKind::RuntimeError,
+                        format!(
+                            "Failed to declare runtime function script_gc_write_barrier: {}",
+                            e
+                        ),
+                    )
+                })?;
+
+            self.func_ids
+                .insert("script_gc_write_barrier".to_string(), func_id);
+        }
+
         Ok(())
     }
 
@@ -284,21 +367,16 @@ impl CraneliftBackend {
         self.ctx.func.signature = sig;
 
         // Create function translator
-        let mut translator = FunctionTranslator::new(&mut self.module, &self.func_ids, ir_module);
+        let overflow_checks = self.overflow_mode.traps(self.debug_flags.optimize);
+        let debug_info = self.debug_context.is_some() && self.debug_flags.debug_info;
+        let mut translator = FunctionTranslator::new(&mut self.module, &self.func_ids, ir_module)
+            .with_overflow_checks(overflow_checks)
+            .with_debug_info(debug_info)
+            .with_write_barriers(self.write_barriers);
 
         // Translate the function
         translator.translate_function(func, &mut self.ctx.func, &mut self.closure_optimizer)?;
-
-        // Add debug information for the function if enabled
-        if let Some(ref mut debug_ctx) = self.debug_context {
-            if self.debug_flags.debug_info {
-                // Get function address range (placeholder for now)
-                let start_address = 0u64; // This would be filled in by the JIT
-                let end_address = 0u64; // This would be filled in by the JIT
-
-                debug_ctx.add_function(func, start_address, end_address)?;
-            }
-        }
+        let source_locations = translator.take_source_locations();
 
         // Compile the function
         self.module
@@ -310,6 +388,29 @@ impl CraneliftBackend {
                 )
             })?;
 
+        // Add debug information for the function if enabled
+        if let Some(ref mut debug_ctx) = self.debug_context {
+            if debug_info {
+                if let Some(compiled) = self.ctx.compiled_code() {
+                    debug_ctx.add_function(function_debug_info(
+                        func,
+                        compiled,
+                        &source_locations,
+                        &*self.isa,
+                    ))?;
+                }
+            }
+        }
+        if let Some(ref mut source_map) = self.source_map {
+            if let Some(compiled) = self.ctx.compiled_code() {
+                source_map.set_machine_code(
+                    &func.name,
+                    compiled.code_info().total_size,
+                    instruction_code_ranges(compiled, &source_locations),
+                );
+            }
+        }
+
         Ok(())
     }
 }
@@ -324,14 +425,27 @@ impl CodegenBackend for CraneliftBackend {
     type Output = ExecutableModule;
 
     fn generate(&mut self, module: &IrModule) -> CodegenResult<Self::Output> {
+        if let Some(ref mut debug_ctx) = self.debug_context {
+            let source_file = module
+                .source_file
+                .clone()
+                .unwrap_or_else(|| format!("{}.script", module.name));
+            debug_ctx.set_current_file(source_file)?;
+            self.source_map = Some(SourceMap::from_ir(module));
+        }
+
         // Compile the module
         self.compile_module(module)?;
 
-        // Generate debug information if enabled
-        let debug_info = if let Some(ref mut debug_ctx) = self.debug_context {
-            Some(debug_ctx.generate_sections()?)
-        } else {
-            None
+        // Generate debug information if enabled; the context moves to the
+        // executable module for address lookups
+        let (debug_info, debug_context) = match self.debug_context.take() {
+            Some(mut debug_ctx) => {
+                let sections = debug_ctx.generate_sections()?;
+                self.debug_context = Some(new_debug_context());
+                (Some(sections), Some(debug_ctx))
+            }
+            None => (None, None),
         };
 
         // Create executable module
@@ -353,6 +467,8 @@ impl CodegenBackend for CraneliftBackend {
             module: std::mem::replace(&mut self.module, empty_module),
             func_ids: std::mem::take(&mut self.func_ids),
             debug_info,
+            debug_context,
+            source_map: self.source_map.take(),
         });
 
         // Find entry point (prefer __script_main__ for top-level code, then user's main)
@@ -377,14 +493,140 @@ impl CodegenBackend for CraneliftBackend {
 pub struct CraneliftModuleData {
     pub module: JITModule,
     pub func_ids: HashMap<String, FuncId>,
-    pub debug_info: Option<Vec<u8>>,
+    pub debug_info: Option<DwarfSections>,
+    pub debug_context: Option<DebugContext>,
+    pub source_map: Option<SourceMap>,
+}
+
+/// Create an empty debug context for the current directory
+fn new_debug_context() -> DebugContext {
+    DebugContext::new(
+        std::env::current_dir()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        "Script Language Compiler 0.1.0".to_string(),
+    )
+}
+
+/// Reports every function entry and exit to the function profiler
+struct ProfilePolicy;
+
+impl ProbePolicy for ProfilePolicy {
+    fn probe(&mut self, function: &IrFunction, site: ProbeSite) -> Option<Probe> {
+        // Trait method stubs are never compiled
+        if function.trait_method.is_some() {
+            return None;
+        }
+        let hook = match site {
+            ProbeSite::FunctionEntry => "script_profile_enter",
+            ProbeSite::FunctionExit => "script_profile_exit",
+            _ => return None,
+        };
+        let id = crate::runtime::function_profiler::function_id(&function.name);
+        Some(Probe::new(hook, id))
+    }
+}
+
+/// Machine code ranges of a compiled function, by the IR instruction they
+/// were translated from
+///
+/// `source_locations` are the instructions the translator's `SourceLoc`s
+/// index.
+fn instruction_code_ranges(
+    compiled: &codegen::CompiledCode,
+    source_locations: &[(ValueId, Span)],
+) -> Vec<(ValueId, std::ops::Range<u32>)> {
+    compiled
+        .buffer
+        .get_srclocs_sorted()
+        .iter()
+        .filter(|srcloc| !srcloc.loc.is_default())
+        .filter_map(|srcloc| {
+            source_locations
+                .get(srcloc.loc.bits() as usize)
+                .map(|(value, _)| (*value, srcloc.start..srcloc.end))
+        })
+        .collect()
+}
+
+/// Collect the debug information of a compiled function
+///
+/// `source_locations` are the instructions and spans the translator's
+/// `SourceLoc`s index, and variables are labelled by their index in
+/// `func.debug_variables`.
+fn function_debug_info(
+    func: &IrFunction,
+    compiled: &codegen::CompiledCode,
+    source_locations: &[(ValueId, Span)],
+    isa: &dyn codegen::isa::TargetIsa,
+) -> FunctionDebugInfo {
+    let lines = compiled
+        .buffer
+        .get_srclocs_sorted()
+        .iter()
+        .filter(|srcloc| !srcloc.loc.is_default())
+        .filter_map(|srcloc| {
+            source_locations
+                .get(srcloc.loc.bits() as usize)
+                .map(|(_, span)| LineRange {
+                    start: srcloc.start,
+                    end: srcloc.end,
+                    span: *span,
+                })
+        })
+        .collect();
+
+    let variables = func
+        .debug_variables
+        .iter()
+        .enumerate()
+        .map(|(index, variable)| {
+            let label = codegen::ir::ValueLabel::from_u32(index as u32);
+            let ranges = compiled
+                .value_labels_ranges
+                .get(&label)
+                .into_iter()
+                .flatten()
+                .filter_map(|range| {
+                    let location = match range.loc {
+                        codegen::LabelValueLoc::Reg(reg) => {
+                            DwarfLocation::Register(isa.map_regalloc_reg_to_dwarf(reg).ok()?.into())
+                        }
+                        codegen::LabelValueLoc::CFAOffset(offset) => {
+                            DwarfLocation::FrameOffset(i32::try_from(offset).ok()?)
+                        }
+                    };
+                    Some((range.start, range.end, location))
+                })
+                .collect();
+            VariableDebugInfo {
+                name: variable.name.clone(),
+                ty: variable.ty.clone(),
+                is_param: variable.is_param,
+                span: variable.span,
+                ranges,
+            }
+        })
+        .collect();
+
+    FunctionDebugInfo {
+        name: func.name.clone(),
+        span: func.span,
+        address: None,
+        code_size: compiled.code_info().total_size,
+        lines,
+        variables,
+    }
 }
 
 /// Convert Script type to Cranelift type
 fn script_type_to_cranelift(ty: &ScriptType) -> types::Type {
     match ty {
-        ScriptType::I32 => types::I32,
+        ScriptType::I32 | ScriptType::U32 => types::I32,
+        ScriptType::I64 | ScriptType::U64 => types::I64,
         ScriptType::F32 => types::F32,
+        ScriptType::F64 => types::F64,
         ScriptType::Bool => types::I8, // Booleans are represented as i8
         ScriptType::String => types::I64, // Pointer type
         ScriptType::Unknown => types::I64, // Default to pointer-sized
@@ -395,6 +637,8 @@ fn script_type_to_cranelift(ty: &ScriptType) -> types::Type {
         ScriptType::Option(_) => types::I64, // Pointer to option
         ScriptType::Never => types::I64, // Never type (should not occur at runtime)
         ScriptType::Struct { .. } => types::I64, // Pointer to struct
+        ScriptType::Named(name) if name == intrinsic::F32X4 => types::F32X4,
+        ScriptType::Named(name) if name == intrinsic::I32X4 => types::I32X4,
         ScriptType::Named(_) => types::I64, // Pointer to named type
         ScriptType::TypeVar(_) => types::I64, // Should be resolved by now
         ScriptType::Generic { .. } => types::I64, // Should be resolved by now
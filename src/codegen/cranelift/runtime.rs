@@ -319,6 +319,32 @@ pub unsafe extern "C" fn script_panic(msg: *const u8, len: usize) -> ! {
     std::process::exit(101);
 }
 
+/// Record a reference stored into a heap object for the garbage collector
+#[no_mangle]
+pub extern "C" fn script_gc_write_barrier(object: *const u8) {
+    if !object.is_null() {
+        crate::runtime::gc::write_barrier(object as usize);
+    }
+}
+
+/// Record entry into a function compiled with profiling
+#[no_mangle]
+pub extern "C" fn script_profile_enter(function: i64) {
+    crate::runtime::function_profiler::enter(function as u32);
+}
+
+/// Record a return from a function compiled with profiling
+#[no_mangle]
+pub extern "C" fn script_profile_exit(function: i64) {
+    crate::runtime::function_profiler::exit(function as u32);
+}
+
+/// Count one execution of a block compiled with coverage counters
+#[no_mangle]
+pub extern "C" fn script_coverage_hit(counter: i64) {
+    crate::runtime::coverage::hit(counter as u32);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -491,6 +517,19 @@ pub fn register_runtime_functions(builder: &mut JITBuilder) {
     // Register panic handler
     builder.symbol("script_panic", script_panic as *const u8);
 
+    // Register garbage collector support
+    builder.symbol(
+        "script_gc_write_barrier",
+        script_gc_write_barrier as *const u8,
+    );
+
+    // Register profiler hooks
+    builder.symbol("script_profile_enter", script_profile_enter as *const u8);
+    builder.symbol("script_profile_exit", script_profile_exit as *const u8);
+
+    // Register coverage counters
+    builder.symbol("script_coverage_hit", script_coverage_hit as *const u8);
+
     // Register closure functions
     builder.symbol("script_create_closure", script_create_closure as *const u8);
     builder.symbol("script_invoke_closure", script_invoke_closure as *const u8);
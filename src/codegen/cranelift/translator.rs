@@ -1,12 +1,19 @@
-use cranelift::codegen::ir::{FuncRef, Function};
+use cranelift::codegen::ir::{
+    AtomicRmwOp, FuncRef, Function, InstructionData, SourceLoc, ValueLabel,
+};
 use cranelift::prelude::*;
 use cranelift_module::{DataDescription, FuncId, Linkage as ModuleLinkage, Linkage, Module};
 
 use crate::error::{Error, ErrorKind};
-use crate::ir::{BasicBlock, BlockId, Constant, Function as IrFunction, Instruction, ValueId};
+use crate::ir::optimizer::analysis::{EscapeAnalysis, EscapeInfo};
+use crate::ir::{
+    BasicBlock, BlockId, Constant, Function as IrFunction, Instruction, Intrinsic, Probe, ValueId,
+};
 use crate::ir::{BinaryOp, ComparisonOp, LayoutCalculator, UnaryOp, VariantDataLayout};
 
 use super::{script_type_to_cranelift, ClosureOptimizer, CodegenResult};
+use crate::codegen::OverflowChecker;
+use crate::source::Span;
 use std::collections::HashMap;
 
 /// Translates IR functions to Cranelift IR
@@ -23,15 +30,33 @@ pub struct FunctionTranslator<'a> {
     value_types: HashMap<ValueId, crate::types::Type>,
     /// Block mapping from IR to Cranelift
     blocks: HashMap<BlockId, Block>,
-    /// Variable counter for SSA construction
-    #[allow(dead_code)]
-    var_counter: usize,
+    /// Incoming values of each block's phi nodes, in phi order
+    phi_incoming: HashMap<BlockId, Vec<Vec<(ValueId, BlockId)>>>,
+    /// IR block currently being translated
+    current_block: Option<BlockId>,
     /// Track which blocks have been processed
     processed_blocks: std::collections::HashSet<BlockId>,
     /// String constants for this function
     string_constants: Vec<(String, cranelift_module::DataId)>,
     /// Layout calculator for struct/enum layouts
     layout_calculator: LayoutCalculator,
+    /// Emits traps for integer arithmetic overflow
+    overflow_checker: OverflowChecker,
+    /// Which allocations of the current function may outlive it
+    escape_info: EscapeInfo,
+    /// Whether instructions are tagged with their source locations
+    debug_info: bool,
+    /// IR instructions of the function that carry a source span, indexed
+    /// by Cranelift `SourceLoc`
+    source_locations: Vec<(ValueId, Span)>,
+    /// Value label of the allocation behind each source-level variable
+    variable_labels: HashMap<ValueId, u32>,
+    /// Whether pointer stores into heap objects call the GC write barrier
+    write_barriers: bool,
+    /// Object each element or field pointer was derived from
+    derived_pointers: HashMap<ValueId, ValueId>,
+    /// Probes to run before every return of the function being translated
+    exit_probes: Vec<Probe>,
 }
 
 impl<'a> FunctionTranslator<'a> {
@@ -48,13 +73,52 @@ impl<'a> FunctionTranslator<'a> {
             values: HashMap::new(),
             value_types: HashMap::new(),
             blocks: HashMap::new(),
-            var_counter: 0,
+            phi_incoming: HashMap::new(),
+            current_block: None,
             processed_blocks: std::collections::HashSet::new(),
             string_constants: Vec::new(),
             layout_calculator: LayoutCalculator::new(),
+            overflow_checker: OverflowChecker::default(),
+            escape_info: EscapeInfo::default(),
+            debug_info: false,
+            source_locations: Vec::new(),
+            variable_labels: HashMap::new(),
+            write_barriers: false,
+            derived_pointers: HashMap::new(),
+            exit_probes: Vec::new(),
         }
     }
 
+    /// Trap on integer arithmetic overflow in translated functions
+    pub fn with_overflow_checks(mut self, enabled: bool) -> Self {
+        self.overflow_checker = OverflowChecker::new(enabled);
+        self
+    }
+
+    /// Tag translated instructions with the source spans of their IR
+    pub fn with_debug_info(mut self, enabled: bool) -> Self {
+        self.debug_info = enabled;
+        self
+    }
+
+    /// Report references stored into heap objects to the garbage collector
+    pub fn with_write_barriers(mut self, enabled: bool) -> Self {
+        self.write_barriers = enabled;
+        self
+    }
+
+    /// IR instructions and spans referenced by the translated function's
+    /// `SourceLoc`s
+    pub fn take_source_locations(&mut self) -> Vec<(ValueId, Span)> {
+        std::mem::take(&mut self.source_locations)
+    }
+
+    /// Cranelift source location standing for the IR instruction `value`
+    fn source_loc(&mut self, value: ValueId, span: Span) -> SourceLoc {
+        self.source_locations.push((value, span));
+        SourceLoc::new((self.source_locations.len() - 1) as u32)
+    }
+
     /// Translate an IR function to Cranelift IR
     pub fn translate_function(
         &mut self,
@@ -62,6 +126,21 @@ impl<'a> FunctionTranslator<'a> {
         cranelift_func: &mut Function,
         closure_optimizer: &mut ClosureOptimizer,
     ) -> CodegenResult<()> {
+        // Allocations that never leave the function are kept on the stack
+        self.escape_info = EscapeAnalysis::new().analyze(ir_func);
+
+        // Label the allocation of each variable, so the locations it moves
+        // through can be reported; labels index `ir_func.debug_variables`
+        if self.debug_info {
+            cranelift_func.collect_debug_info();
+            self.variable_labels = ir_func
+                .debug_variables
+                .iter()
+                .enumerate()
+                .map(|(index, variable)| (variable.ptr, index as u32))
+                .collect();
+        }
+
         let mut fn_builder_ctx = FunctionBuilderContext::new();
         let mut builder = FunctionBuilder::new(cranelift_func, &mut fn_builder_ctx);
 
@@ -85,11 +164,24 @@ impl<'a> FunctionTranslator<'a> {
         // Switch to entry block (but don't seal it yet)
         builder.switch_to_block(entry_block);
 
-        // First pass: create all blocks
-        for (block_id, _ir_block) in ir_func.blocks() {
+        self.exit_probes = ir_func.exit_probes.clone();
+
+        // First pass: create all blocks, turning phi nodes into block parameters
+        for (block_id, ir_block) in ir_func.blocks() {
             if Some(*block_id) != ir_func.entry_block {
                 let cranelift_block = builder.create_block();
                 self.blocks.insert(*block_id, cranelift_block);
+
+                let mut phis = Vec::new();
+                for (value_id, inst_with_loc) in &ir_block.instructions {
+                    if let Instruction::Phi { incoming, ty } = &inst_with_loc.instruction {
+                        let param = builder
+                            .append_block_param(cranelift_block, script_type_to_cranelift(ty));
+                        self.values.insert(*value_id, param);
+                        phis.push(incoming.clone());
+                    }
+                }
+                self.phi_incoming.insert(*block_id, phis);
             } else {
                 self.blocks.insert(*block_id, entry_block);
             }
@@ -125,6 +217,7 @@ impl<'a> FunctionTranslator<'a> {
 
         // Switch to this block
         builder.switch_to_block(cranelift_block);
+        self.current_block = Some(block.id);
 
         // Translate instructions
         for (value_id, inst_with_loc) in &block.instructions {
@@ -133,12 +226,25 @@ impl<'a> FunctionTranslator<'a> {
                 self.value_types.insert(*value_id, result_type);
             }
 
+            if self.debug_info {
+                if let Some(span) = inst_with_loc.source_location {
+                    let loc = self.source_loc(*value_id, span);
+                    builder.set_srcloc(loc);
+                }
+            }
+
             self.translate_instruction(
                 *value_id,
                 &inst_with_loc.instruction,
                 builder,
                 closure_optimizer,
             )?;
+
+            if let Some(&label) = self.variable_labels.get(value_id) {
+                if let Some(&value) = self.values.get(value_id) {
+                    builder.set_val_label(value, ValueLabel::from_u32(label));
+                }
+            }
         }
 
         // Mark this block as processed
@@ -172,15 +278,10 @@ impl<'a> FunctionTranslator<'a> {
                 self.values.insert(value_id, val);
             }
 
-            Instruction::Binary {
-                op,
-                lhs,
-                rhs,
-                ty: _,
-            } => {
+            Instruction::Binary { op, lhs, rhs, ty } => {
                 let lhs_val = self.get_value(*lhs)?;
                 let rhs_val = self.get_value(*rhs)?;
-                let result = self.translate_binary_op(*op, lhs_val, rhs_val, builder)?;
+                let result = self.translate_binary_op(*op, lhs_val, rhs_val, ty, builder)?;
                 self.values.insert(value_id, result);
             }
 
@@ -200,15 +301,16 @@ impl<'a> FunctionTranslator<'a> {
             Instruction::Return(value) => {
                 if let Some(val_id) = value {
                     let val = self.get_value(*val_id)?;
-                    builder.ins().return_(&[val]);
+                    self.emit_return(&[val], builder)?;
                 } else {
-                    builder.ins().return_(&[]);
+                    self.emit_return(&[], builder)?;
                 }
             }
 
             Instruction::Branch(target) => {
                 let target_block = self.get_block(*target)?;
-                builder.ins().jump(target_block, &[]);
+                let args = self.phi_arguments(*target)?;
+                builder.ins().jump(target_block, &args);
             }
 
             Instruction::CondBranch {
@@ -219,7 +321,11 @@ impl<'a> FunctionTranslator<'a> {
                 let cond_val = self.get_value(*condition)?;
                 let then_blk = self.get_block(*then_block)?;
                 let else_blk = self.get_block(*else_block)?;
-                builder.ins().brif(cond_val, then_blk, &[], else_blk, &[]);
+                let then_args = self.phi_arguments(*then_block)?;
+                let else_args = self.phi_arguments(*else_block)?;
+                builder
+                    .ins()
+                    .brif(cond_val, then_blk, &then_args, else_blk, &else_args);
             }
 
             Instruction::Cast {
@@ -376,7 +482,7 @@ impl<'a> FunctionTranslator<'a> {
             }
 
             Instruction::Alloc { ty } => {
-                let result = self.translate_alloc(ty, builder)?;
+                let result = self.translate_allocation(value_id, ty, builder)?;
                 self.values.insert(value_id, result);
             }
 
@@ -391,6 +497,8 @@ impl<'a> FunctionTranslator<'a> {
                 let val = self.get_value(*value)?;
                 self.translate_store(ptr_val, val, builder)?;
                 // Store doesn't produce a value, so we don't insert into values map
+                let object = self.derived_pointers.get(ptr).copied().unwrap_or(*ptr);
+                self.emit_write_barrier(object, *value, builder)?;
             }
 
             Instruction::GetElementPtr {
@@ -402,6 +510,7 @@ impl<'a> FunctionTranslator<'a> {
                 let index_val = self.get_value(*index)?;
                 let result = self.translate_gep(ptr_val, index_val, elem_ty, builder)?;
                 self.values.insert(value_id, result);
+                self.record_derived_pointer(value_id, *ptr);
             }
 
             Instruction::GetFieldPtr {
@@ -413,6 +522,7 @@ impl<'a> FunctionTranslator<'a> {
                 let result =
                     self.translate_get_field_ptr(object_val, field_name, field_ty, builder)?;
                 self.values.insert(value_id, result);
+                self.record_derived_pointer(value_id, *object);
             }
 
             Instruction::LoadField {
@@ -435,12 +545,11 @@ impl<'a> FunctionTranslator<'a> {
                 let val = self.get_value(*value)?;
                 self.translate_store_field(object_val, field_name, val, builder)?;
                 // Store doesn't produce a value, so we don't insert into values map
+                self.emit_write_barrier(*object, *value, builder)?;
             }
 
-            Instruction::Phi { incoming, ty } => {
-                let result = self.translate_phi(incoming, ty, builder)?;
-                self.values.insert(value_id, result);
-            }
+            // Phi nodes are block parameters, bound when the blocks were created
+            Instruction::Phi { .. } => {}
 
             // Async instructions
             Instruction::Suspend {
@@ -453,7 +562,7 @@ impl<'a> FunctionTranslator<'a> {
                 // Store state (implementation-specific)
                 // For now, just return a constant representing Poll::Pending
                 let pending = builder.ins().iconst(types::I32, 1); // Poll::Pending = 1
-                builder.ins().return_(&[pending]);
+                self.emit_return(&[pending], builder)?;
             }
 
             Instruction::PollFuture {
@@ -590,7 +699,7 @@ impl<'a> FunctionTranslator<'a> {
 
             // These might already be implemented, but including for completeness
             Instruction::AllocStruct { struct_name: _, ty } => {
-                let result = self.translate_alloc(ty, builder)?;
+                let result = self.translate_allocation(value_id, ty, builder)?;
                 self.values.insert(value_id, result);
             }
 
@@ -600,7 +709,7 @@ impl<'a> FunctionTranslator<'a> {
                 ty,
             } => {
                 // Allocate struct and initialize fields
-                let struct_ptr = self.translate_alloc(ty, builder)?;
+                let struct_ptr = self.translate_allocation(value_id, ty, builder)?;
 
                 // Initialize each field
                 for (_field_name, field_value) in fields {
@@ -620,13 +729,7 @@ impl<'a> FunctionTranslator<'a> {
             } => {
                 // Allocate space for enum (discriminant + largest variant)
                 let total_size = 4 + variant_size; // 4 bytes for discriminant
-                let stack_slot = builder.create_sized_stack_slot(StackSlotData::new(
-                    StackSlotKind::ExplicitSlot,
-                    total_size,
-                    3, // 8-byte alignment
-                ));
-
-                let enum_ptr = builder.ins().stack_addr(types::I64, stack_slot, 0);
+                let enum_ptr = self.allocate(value_id, total_size, builder)?;
                 self.values.insert(value_id, enum_ptr);
             }
 
@@ -749,182 +852,221 @@ impl<'a> FunctionTranslator<'a> {
             }
 
             Instruction::CreateClosure {
-                function_id,
-                parameters,
+                function,
                 captured_vars,
-                captures_by_ref,
+                ..
             } => {
-                // Use the runtime heap allocation function for closures
-                // This ensures proper memory management and reference counting
-
-                // Import the closure creation function if not already imported
-                let create_closure_func = self.import_runtime_function(
-                    "script_create_closure",
-                    &[
-                        types::I64, // function_id_ptr
-                        types::I64, // function_id_len
-                        types::I64, // param_names
-                        types::I64, // param_lengths
-                        types::I64, // param_count
-                        types::I64, // capture_names
-                        types::I64, // capture_name_lengths
-                        types::I64, // capture_values
-                        types::I64, // capture_count
-                        types::I8,  // captures_by_ref
-                    ],
-                    Some(types::I64), // returns pointer to Value
-                    builder,
-                )?;
-
-                // Prepare function ID string
-                let func_id_bytes = function_id.as_bytes();
-                let func_id_ptr = self.translate_byte_array(func_id_bytes, builder)?;
-                let func_id_len = builder.ins().iconst(types::I64, func_id_bytes.len() as i64);
-
-                // Prepare parameter names array
-                let param_count = parameters.len();
-                let (param_names_ptr, param_lengths_ptr) = if param_count > 0 {
-                    self.translate_string_array(parameters, builder)?
-                } else {
-                    (
-                        builder.ins().iconst(types::I64, 0),
-                        builder.ins().iconst(types::I64, 0),
+                // A closure is a heap record of [function pointer, environment]
+                // where the environment holds one 8-byte slot per capture
+                let alloc_id = self.func_ids.get("script_alloc").ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::RuntimeError,
+                        "Runtime function 'script_alloc' not found",
                     )
-                };
-                let param_count_val = builder.ins().iconst(types::I64, param_count as i64);
-
-                // Prepare captured variables arrays
-                let capture_count = captured_vars.len();
-                let (capture_names_ptr, capture_lengths_ptr, capture_values_ptr) = if capture_count
-                    > 0
-                {
-                    // Extract names and values
-                    let mut names = Vec::new();
-                    let mut values = Vec::new();
-                    for (name, value_id) in captured_vars {
-                        names.push(name.clone());
-                        values.push(self.get_value(*value_id)?);
-                    }
+                })?;
+                let alloc_func = self.module.declare_func_in_func(*alloc_id, builder.func);
 
-                    let (names_ptr, lengths_ptr) = self.translate_string_array(&names, builder)?;
-                    let values_ptr = self.translate_value_array(&values, builder)?;
-                    (names_ptr, lengths_ptr, values_ptr)
+                let env_ptr = if captured_vars.is_empty() {
+                    builder.ins().iconst(types::I64, 0)
                 } else {
-                    let null = builder.ins().iconst(types::I64, 0);
-                    (null, null, null)
+                    let env_size = builder
+                        .ins()
+                        .iconst(types::I64, (captured_vars.len() * 8) as i64);
+                    let call = builder.ins().call(alloc_func, &[env_size]);
+                    let env_ptr = builder.inst_results(call)[0];
+                    for (i, (_, captured)) in captured_vars.iter().enumerate() {
+                        let value = self.get_value(*captured)?;
+                        builder
+                            .ins()
+                            .store(MemFlags::new(), value, env_ptr, (i * 8) as i32);
+                    }
+                    env_ptr
                 };
-                let capture_count_val = builder.ins().iconst(types::I64, capture_count as i64);
 
-                // Convert boolean to i8
-                let captures_by_ref_val = builder
+                let ir_func = self.ir_module.get_function(*function).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::RuntimeError,
+                        format!("Internal error: Function ID {:?} not found", function),
+                    )
+                })?;
+                let cranelift_func_id = self.func_ids.get(&ir_func.name).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::RuntimeError,
+                        format!("Closure function '{}' is not declared", ir_func.name),
+                    )
+                })?;
+                let func_ref = self
+                    .module
+                    .declare_func_in_func(*cranelift_func_id, builder.func);
+                let fn_ptr = builder.ins().func_addr(types::I64, func_ref);
+
+                let record_size = builder.ins().iconst(types::I64, 16);
+                let call = builder.ins().call(alloc_func, &[record_size]);
+                let closure_ptr = builder.inst_results(call)[0];
+                builder.ins().store(MemFlags::new(), fn_ptr, closure_ptr, 0);
+                builder
                     .ins()
-                    .iconst(types::I8, if *captures_by_ref { 1 } else { 0 });
-
-                // Call the runtime function
-                let args = vec![
-                    func_id_ptr,
-                    func_id_len,
-                    param_names_ptr,
-                    param_lengths_ptr,
-                    param_count_val,
-                    capture_names_ptr,
-                    capture_lengths_ptr,
-                    capture_values_ptr,
-                    capture_count_val,
-                    captures_by_ref_val,
-                ];
-
-                let call_inst = builder.ins().call(create_closure_func, &args);
-                let closure_ptr = builder.inst_results(call_inst)[0];
+                    .store(MemFlags::new(), env_ptr, closure_ptr, 8);
 
                 self.values.insert(value_id, closure_ptr);
             }
 
+            Instruction::LoadCapture {
+                environment,
+                index,
+                ty,
+            } => {
+                let env_ptr = self.get_value(*environment)?;
+                let value = builder.ins().load(
+                    script_type_to_cranelift(ty),
+                    MemFlags::new(),
+                    env_ptr,
+                    (*index * 8) as i32,
+                );
+                self.values.insert(value_id, value);
+            }
+
             Instruction::InvokeClosure {
                 closure,
                 args,
                 return_type,
             } => {
-                // Import the closure invocation runtime function
-                let invoke_func = self.import_runtime_function(
-                    "script_invoke_closure",
-                    &[
-                        types::I64, // closure pointer
-                        types::I64, // args array pointer
-                        types::I32, // args count
-                    ],
-                    Some(types::I64), // returns pointer to result value
-                    builder,
-                )?;
-
+                // Call the closure's function pointer with its environment
+                // as the first argument
                 let closure_ptr = self.get_value(*closure)?;
+                let fn_ptr = builder
+                    .ins()
+                    .load(types::I64, MemFlags::new(), closure_ptr, 0);
+                let env_ptr = builder
+                    .ins()
+                    .load(types::I64, MemFlags::new(), closure_ptr, 8);
+
+                let mut sig = self.module.make_signature();
+                sig.params.push(AbiParam::new(types::I64));
+                let mut call_args = vec![env_ptr];
+                for arg in args {
+                    let value = self.get_value(*arg)?;
+                    sig.params
+                        .push(AbiParam::new(builder.func.dfg.value_type(value)));
+                    call_args.push(value);
+                }
+                if return_type != &crate::types::Type::Unknown {
+                    sig.returns
+                        .push(AbiParam::new(script_type_to_cranelift(return_type)));
+                }
 
-                // Prepare arguments array
-                let arg_count = args.len();
-                let args_array = if arg_count > 0 {
-                    // Allocate space for arguments on stack
-                    let args_size = (arg_count * 8) as u32; // 8 bytes per argument
-                    let stack_slot = builder.create_sized_stack_slot(StackSlotData::new(
-                        StackSlotKind::ExplicitSlot,
-                        args_size,
-                        3,
-                    ));
-                    let args_ptr = builder.ins().stack_addr(types::I64, stack_slot, 0);
+                let sig_ref = builder.import_signature(sig);
+                let call = builder.ins().call_indirect(sig_ref, fn_ptr, &call_args);
 
-                    // Store arguments in the array
-                    for (i, arg_id) in args.iter().enumerate() {
-                        let arg_val = self.get_value(*arg_id)?;
-                        let offset = (i * 8) as i32;
-                        builder
-                            .ins()
-                            .store(MemFlags::new(), arg_val, args_ptr, offset);
-                    }
-
-                    args_ptr
+                let result = if return_type == &crate::types::Type::Unknown {
+                    // The closure returns nothing, so create a dummy value
+                    builder.ins().iconst(types::I32, 0)
                 } else {
-                    // No arguments, pass null pointer
-                    builder.ins().iconst(types::I64, 0)
+                    builder.inst_results(call)[0]
                 };
+                self.values.insert(value_id, result);
+            }
 
-                let arg_count_val = builder.ins().iconst(types::I32, arg_count as i64);
-
-                // Call the runtime invocation function
-                let call_args = vec![closure_ptr, args_array, arg_count_val];
-                let call_inst = builder.ins().call(invoke_func, &call_args);
-
-                // Get the result
-                let result_ptr = builder.inst_results(call_inst)[0];
-
-                // Load the result based on return type
-                let result = match return_type {
-                    crate::types::Type::I32 => {
-                        builder
-                            .ins()
-                            .load(types::I32, MemFlags::new(), result_ptr, 0)
-                    }
-                    crate::types::Type::F32 => {
-                        builder
-                            .ins()
-                            .load(types::F32, MemFlags::new(), result_ptr, 0)
-                    }
-                    crate::types::Type::Bool => {
-                        builder
-                            .ins()
-                            .load(types::I8, MemFlags::new(), result_ptr, 0)
-                    }
-                    _ => {
-                        // For other types, return the pointer itself
-                        result_ptr
-                    }
-                };
+            Instruction::Probe(probe) => self.emit_probe(probe, builder)?,
 
-                self.values.insert(value_id, result);
+            Instruction::Intrinsic {
+                intrinsic,
+                args,
+                ty,
+            } => {
+                if let Some(result) =
+                    self.translate_intrinsic(*intrinsic, args, ty.as_ref(), builder)?
+                {
+                    self.values.insert(value_id, result);
+                }
             }
         }
 
         Ok(())
     }
 
+    /// Emit the machine instructions of an intrinsic, returning its result
+    fn translate_intrinsic(
+        &mut self,
+        intrinsic: Intrinsic,
+        args: &[ValueId],
+        ty: Option<&crate::types::Type>,
+        builder: &mut FunctionBuilder,
+    ) -> CodegenResult<Option<Value>> {
+        let args = args
+            .iter()
+            .map(|arg| self.get_value(*arg))
+            .collect::<CodegenResult<Vec<_>>>()?;
+        let ty = ty.map(script_type_to_cranelift).unwrap_or(types::I64);
+        let flags = MemFlags::trusted();
+        let rmw = |builder: &mut FunctionBuilder, op| {
+            builder.ins().atomic_rmw(ty, flags, op, args[0], args[1])
+        };
+
+        let result = match intrinsic {
+            Intrinsic::AtomicLoad => builder.ins().atomic_load(ty, flags, args[0]),
+            Intrinsic::AtomicStore => {
+                builder.ins().atomic_store(flags, args[1], args[0]);
+                return Ok(None);
+            }
+            Intrinsic::AtomicAdd => rmw(builder, AtomicRmwOp::Add),
+            Intrinsic::AtomicSub => rmw(builder, AtomicRmwOp::Sub),
+            Intrinsic::AtomicAnd => rmw(builder, AtomicRmwOp::And),
+            Intrinsic::AtomicOr => rmw(builder, AtomicRmwOp::Or),
+            Intrinsic::AtomicXor => rmw(builder, AtomicRmwOp::Xor),
+            Intrinsic::AtomicXchg => rmw(builder, AtomicRmwOp::Xchg),
+            Intrinsic::AtomicCas => builder.ins().atomic_cas(flags, args[0], args[1], args[2]),
+            Intrinsic::Fence => {
+                builder.ins().fence();
+                return Ok(None);
+            }
+            Intrinsic::Popcnt => builder.ins().popcnt(args[0]),
+            Intrinsic::Clz => builder.ins().clz(args[0]),
+            Intrinsic::Ctz => builder.ins().ctz(args[0]),
+            Intrinsic::Sqrt => builder.ins().sqrt(args[0]),
+            Intrinsic::Fma => builder.ins().fma(args[0], args[1], args[2]),
+            Intrinsic::SimdSplat => builder.ins().splat(ty, args[0]),
+            Intrinsic::SimdAdd if ty.lane_type().is_float() => builder.ins().fadd(args[0], args[1]),
+            Intrinsic::SimdAdd => builder.ins().iadd(args[0], args[1]),
+            Intrinsic::SimdSub if ty.lane_type().is_float() => builder.ins().fsub(args[0], args[1]),
+            Intrinsic::SimdSub => builder.ins().isub(args[0], args[1]),
+            Intrinsic::SimdMul if ty.lane_type().is_float() => builder.ins().fmul(args[0], args[1]),
+            Intrinsic::SimdMul => builder.ins().imul(args[0], args[1]),
+            Intrinsic::SimdExtract => {
+                let lane = builder
+                    .func
+                    .dfg
+                    .value_def(args[1])
+                    .inst()
+                    .and_then(|inst| match builder.func.dfg.insts[inst] {
+                        InstructionData::UnaryImm { imm, .. } => u8::try_from(imm.bits()).ok(),
+                        _ => None,
+                    })
+                    .filter(|lane| *lane < 4)
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::CompilationError,
+                            "simd_extract needs a constant lane from 0 to 3",
+                        )
+                    })?;
+                builder.ins().extractlane(args[0], lane)
+            }
+            Intrinsic::SimdSum => {
+                let lanes: Vec<Value> = (0..4)
+                    .map(|lane| builder.ins().extractlane(args[0], lane))
+                    .collect();
+                lanes[1..].iter().fold(lanes[0], |sum, lane| {
+                    if ty.is_float() {
+                        builder.ins().fadd(sum, *lane)
+                    } else {
+                        builder.ins().iadd(sum, *lane)
+                    }
+                })
+            }
+        };
+        Ok(Some(result))
+    }
+
     /// Translate a constant
     fn translate_constant(
         &mut self,
@@ -933,7 +1075,11 @@ impl<'a> FunctionTranslator<'a> {
     ) -> CodegenResult<Value> {
         match constant {
             Constant::I32(n) => Ok(builder.ins().iconst(types::I32, *n as i64)),
+            Constant::I64(n) => Ok(builder.ins().iconst(types::I64, *n)),
+            Constant::U32(n) => Ok(builder.ins().iconst(types::I32, *n as i64)),
+            Constant::U64(n) => Ok(builder.ins().iconst(types::I64, *n as i64)),
             Constant::F32(f) => Ok(builder.ins().f32const(*f)),
+            Constant::F64(f) => Ok(builder.ins().f64const(*f)),
             Constant::Bool(b) => Ok(builder.ins().iconst(types::I8, if *b { 1 } else { 0 })),
             Constant::String(s) => {
                 // For now, we'll create a simple string pointer
@@ -950,8 +1096,16 @@ impl<'a> FunctionTranslator<'a> {
         op: BinaryOp,
         lhs: Value,
         rhs: Value,
+        ty: &crate::types::Type,
         builder: &mut FunctionBuilder,
     ) -> CodegenResult<Value> {
+        if let Some(result) = self
+            .overflow_checker
+            .build_checked_arith(builder, op, lhs, rhs, ty)
+        {
+            return Ok(result);
+        }
+
         Ok(match op {
             BinaryOp::Add => builder.ins().iadd(lhs, rhs),
             BinaryOp::Sub => builder.ins().isub(lhs, rhs),
@@ -1085,21 +1239,57 @@ impl<'a> FunctionTranslator<'a> {
     ) -> CodegenResult<Value> {
         use crate::types::Type;
 
+        let signed = |ty: &Type| matches!(ty, Type::I32 | Type::I64);
+
         match (from_ty, to_ty) {
+            // Same representation casts
+            (from, to) if from == to => Ok(value), // No-op
+
             // Integer to integer casts
-            (Type::I32, Type::I32) => Ok(value), // No-op
+            (from, to) if from.is_integer() && to.is_integer() => {
+                let from_cl = script_type_to_cranelift(from);
+                let to_cl = script_type_to_cranelift(to);
+                if to_cl.bits() < from_cl.bits() {
+                    Ok(builder.ins().ireduce(to_cl, value))
+                } else if to_cl.bits() > from_cl.bits() {
+                    if signed(from) {
+                        Ok(builder.ins().sextend(to_cl, value))
+                    } else {
+                        Ok(builder.ins().uextend(to_cl, value))
+                    }
+                } else {
+                    Ok(value) // Same width, only signedness differs
+                }
+            }
 
             // Integer to float casts
-            (Type::I32, Type::F32) => Ok(builder.ins().fcvt_from_sint(types::F32, value)),
+            (from, to) if from.is_integer() && to.is_float() => {
+                let to_cl = script_type_to_cranelift(to);
+                if signed(from) {
+                    Ok(builder.ins().fcvt_from_sint(to_cl, value))
+                } else {
+                    Ok(builder.ins().fcvt_from_uint(to_cl, value))
+                }
+            }
 
             // Float to integer casts
-            (Type::F32, Type::I32) => Ok(builder.ins().fcvt_to_sint_sat(types::I32, value)),
+            (from, to) if from.is_float() && to.is_integer() => {
+                let to_cl = script_type_to_cranelift(to);
+                if signed(to) {
+                    Ok(builder.ins().fcvt_to_sint_sat(to_cl, value))
+                } else {
+                    Ok(builder.ins().fcvt_to_uint_sat(to_cl, value))
+                }
+            }
 
             // Float to float casts
-            (Type::F32, Type::F32) => Ok(value), // No-op
+            (Type::F32, Type::F64) => Ok(builder.ins().fpromote(types::F64, value)),
+            (Type::F64, Type::F32) => Ok(builder.ins().fdemote(types::F32, value)),
 
             // Boolean to integer casts
-            (Type::Bool, Type::I32) => Ok(builder.ins().uextend(types::I32, value)),
+            (Type::Bool, to) if to.is_integer() => {
+                Ok(builder.ins().uextend(script_type_to_cranelift(to), value))
+            }
 
             // Integer to boolean casts (non-zero = true)
             (Type::I32, Type::Bool) => {
@@ -1133,6 +1323,65 @@ impl<'a> FunctionTranslator<'a> {
         Ok(builder.ins().stack_addr(types::I64, stack_slot, 0))
     }
 
+    /// Translate an allocating instruction, on the stack unless escape
+    /// analysis found that the allocation may outlive the function
+    fn translate_allocation(
+        &mut self,
+        value_id: ValueId,
+        ty: &crate::types::Type,
+        builder: &mut FunctionBuilder,
+    ) -> CodegenResult<Value> {
+        let size = self.allocation_size(ty);
+        self.allocate(value_id, size, builder)
+    }
+
+    /// Allocate `size` bytes for the result of `value_id`
+    fn allocate(
+        &mut self,
+        value_id: ValueId,
+        size: u32,
+        builder: &mut FunctionBuilder,
+    ) -> CodegenResult<Value> {
+        if !self.escape_info.escapes(value_id) {
+            let stack_slot = builder.create_sized_stack_slot(StackSlotData::new(
+                StackSlotKind::ExplicitSlot,
+                size,
+                3, // alignment (8-byte alignment = 2^3)
+            ));
+            return Ok(builder.ins().stack_addr(types::I64, stack_slot, 0));
+        }
+
+        let alloc_id = self.func_ids.get("script_alloc").ok_or_else(|| {
+            Error::new(
+                ErrorKind::RuntimeError,
+                "Runtime function 'script_alloc' not found",
+            )
+        })?;
+        let alloc_func = self.module.declare_func_in_func(*alloc_id, builder.func);
+        let size = builder.ins().iconst(types::I64, size as i64);
+        let call = builder.ins().call(alloc_func, &[size]);
+        Ok(builder.inst_results(call)[0])
+    }
+
+    /// Number of bytes needed to hold a value of `ty`
+    fn allocation_size(&self, ty: &crate::types::Type) -> u32 {
+        use crate::types::Type;
+
+        let layout_size = match ty {
+            Type::Named(name) | Type::Struct { name, .. } | Type::Generic { name, .. } => self
+                .ir_module
+                .get_struct(name)
+                .map(|layout| layout.total_size)
+                .or_else(|| {
+                    self.ir_module
+                        .get_enum(name)
+                        .map(|layout| layout.total_size)
+                }),
+            _ => None,
+        };
+        layout_size.unwrap_or_else(|| script_type_to_cranelift(ty).bytes())
+    }
+
     /// Translate memory load
     fn translate_load(
         &mut self,
@@ -1194,40 +1443,30 @@ impl<'a> FunctionTranslator<'a> {
         Ok(builder.ins().iadd(ptr, offset))
     }
 
-    /// Translate phi node
-    fn translate_phi(
-        &mut self,
-        incoming: &[(ValueId, BlockId)],
-        ty: &crate::types::Type,
-        builder: &mut FunctionBuilder,
-    ) -> CodegenResult<Value> {
-        // For phi nodes, we need to use Cranelift's variable system
-        // This is a simplified implementation - a full implementation would
-        // need proper SSA variable management
+    /// Values passed to the phi nodes of `target` along the edge from the current block
+    fn phi_arguments(&self, target: BlockId) -> CodegenResult<Vec<Value>> {
+        let source = self
+            .current_block
+            .ok_or_else(|| Error::new(ErrorKind::RuntimeError, "Branch outside of a block"))?;
+        let phis = match self.phi_incoming.get(&target) {
+            Some(phis) => phis,
+            None => return Ok(Vec::new()),
+        };
 
-        let cranelift_ty = script_type_to_cranelift(ty);
-        let var = Variable::new(self.var_counter);
-        self.var_counter += 1;
-
-        builder.declare_var(var, cranelift_ty);
-
-        // For now, just use the first incoming value as a placeholder
-        // A proper implementation would set up the phi properly
-        if let Some((first_val_id, _)) = incoming.first() {
-            let first_val = self.get_value(*first_val_id)?;
-            builder.def_var(var, first_val);
-            Ok(builder.use_var(var))
-        } else {
-            // No incoming values - create a default
-            let default_val = match ty {
-                crate::types::Type::I32 => builder.ins().iconst(types::I32, 0),
-                crate::types::Type::F32 => builder.ins().f32const(0.0),
-                crate::types::Type::Bool => builder.ins().iconst(types::I8, 0),
-                _ => builder.ins().iconst(types::I64, 0),
-            };
-            builder.def_var(var, default_val);
-            Ok(builder.use_var(var))
-        }
+        phis.iter()
+            .map(|incoming| {
+                let (value, _) = incoming
+                    .iter()
+                    .find(|(_, pred)| *pred == source)
+                    .ok_or_else(|| {
+                        Error::new(
+                            ErrorKind::RuntimeError,
+                            format!("Phi in {} has no value for edge from {}", target, source),
+                        )
+                    })?;
+                self.get_value(*value)
+            })
+            .collect()
     }
 
     /// Translate a string constant
@@ -1397,6 +1636,69 @@ impl<'a> FunctionTranslator<'a> {
         self.translate_store(field_ptr, value, builder)
     }
 
+    /// Remember the object an interior pointer points into
+    fn record_derived_pointer(&mut self, pointer: ValueId, base: ValueId) {
+        let object = self.derived_pointers.get(&base).copied().unwrap_or(base);
+        self.derived_pointers.insert(pointer, object);
+    }
+
+    /// Call the GC write barrier after `value` was stored into `object`
+    ///
+    /// Stores into stack allocations and stores of values that cannot hold
+    /// a reference need no barrier.
+    fn emit_write_barrier(
+        &mut self,
+        object: ValueId,
+        value: ValueId,
+        builder: &mut FunctionBuilder,
+    ) -> CodegenResult<()> {
+        if !self.write_barriers || !self.escape_info.escapes(object) {
+            return Ok(());
+        }
+        let may_hold_reference = match self.value_types.get(&value) {
+            Some(ty) => may_hold_reference(ty),
+            None => true,
+        };
+        if !may_hold_reference {
+            return Ok(());
+        }
+
+        let barrier_id = self
+            .func_ids
+            .get("script_gc_write_barrier")
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::RuntimeError,
+                    "Runtime function 'script_gc_write_barrier' not found",
+                )
+            })?;
+        let barrier_func = self.module.declare_func_in_func(*barrier_id, builder.func);
+        let object_val = self.get_value(object)?;
+        builder.ins().call(barrier_func, &[object_val]);
+        Ok(())
+    }
+
+    /// Return from the function, running its exit probes first
+    fn emit_return(
+        &mut self,
+        values: &[Value],
+        builder: &mut FunctionBuilder,
+    ) -> CodegenResult<()> {
+        for probe in self.exit_probes.clone() {
+            self.emit_probe(&probe, builder)?;
+        }
+        builder.ins().return_(values);
+        Ok(())
+    }
+
+    /// Call the runtime hook of `probe` with its id
+    fn emit_probe(&mut self, probe: &Probe, builder: &mut FunctionBuilder) -> CodegenResult<()> {
+        let hook = self.import_runtime_function(&probe.hook, &[types::I64], None, builder)?;
+        let id = builder.ins().iconst(types::I64, probe.id as i64);
+        builder.ins().call(hook, &[id]);
+        Ok(())
+    }
+
     /// Import a runtime function for use in generated code
     pub fn import_runtime_function(
         &mut self,
@@ -1425,98 +1727,6 @@ impl<'a> FunctionTranslator<'a> {
         Ok(func_ref)
     }
 
-    /// Translate a byte array to memory
-    fn translate_byte_array(
-        &mut self,
-        bytes: &[u8],
-        builder: &mut FunctionBuilder,
-    ) -> CodegenResult<Value> {
-        // Allocate space on stack for the byte array
-        let stack_slot = builder.create_sized_stack_slot(StackSlotData::new(
-            StackSlotKind::ExplicitSlot,
-            bytes.len() as u32,
-            0, // byte alignment
-        ));
-        let ptr = builder.ins().stack_addr(types::I64, stack_slot, 0);
-
-        // Store bytes
-        let memflags = MemFlags::new();
-        for (i, &byte) in bytes.iter().enumerate() {
-            let byte_val = builder.ins().iconst(types::I8, byte as i64);
-            builder.ins().store(memflags, byte_val, ptr, i as i32);
-        }
-
-        Ok(ptr)
-    }
-
-    /// Translate an array of strings to memory
-    fn translate_string_array(
-        &mut self,
-        strings: &[String],
-        builder: &mut FunctionBuilder,
-    ) -> CodegenResult<(Value, Value)> {
-        let count = strings.len();
-
-        // Allocate arrays for pointers and lengths
-        let ptr_size = 8;
-        let ptrs_slot = builder.create_sized_stack_slot(StackSlotData::new(
-            StackSlotKind::ExplicitSlot,
-            (count * ptr_size) as u32,
-            3, // 8-byte alignment
-        ));
-        let lengths_slot = builder.create_sized_stack_slot(StackSlotData::new(
-            StackSlotKind::ExplicitSlot,
-            (count * ptr_size) as u32,
-            3, // 8-byte alignment
-        ));
-
-        let ptrs_array = builder.ins().stack_addr(types::I64, ptrs_slot, 0);
-        let lengths_array = builder.ins().stack_addr(types::I64, lengths_slot, 0);
-
-        // Store each string
-        let memflags = MemFlags::new();
-        for (i, string) in strings.iter().enumerate() {
-            let str_ptr = self.translate_byte_array(string.as_bytes(), builder)?;
-            let str_len = builder.ins().iconst(types::I64, string.len() as i64);
-
-            let offset = (i * ptr_size) as i32;
-            builder.ins().store(memflags, str_ptr, ptrs_array, offset);
-            builder
-                .ins()
-                .store(memflags, str_len, lengths_array, offset);
-        }
-
-        Ok((ptrs_array, lengths_array))
-    }
-
-    /// Translate an array of values to memory
-    fn translate_value_array(
-        &mut self,
-        values: &[Value],
-        builder: &mut FunctionBuilder,
-    ) -> CodegenResult<Value> {
-        let count = values.len();
-        let ptr_size = 8;
-
-        // Allocate array for value pointers
-        let values_slot = builder.create_sized_stack_slot(StackSlotData::new(
-            StackSlotKind::ExplicitSlot,
-            (count * ptr_size) as u32,
-            3, // 8-byte alignment
-        ));
-
-        let values_array = builder.ins().stack_addr(types::I64, values_slot, 0);
-
-        // Store each value pointer
-        let memflags = MemFlags::new();
-        for (i, &value) in values.iter().enumerate() {
-            let offset = (i * ptr_size) as i32;
-            builder.ins().store(memflags, value, values_array, offset);
-        }
-
-        Ok(values_array)
-    }
-
     /// Translate enum constructor with proper memory layout and safety
     fn translate_enum_constructor(
         &mut self,
@@ -1714,7 +1924,7 @@ impl<'a> FunctionTranslator<'a> {
                 builder.ins().store(memflags, err_tag, return_result, 0);
                 // Store error value
                 builder.ins().store(memflags, error_val, return_result, 8);
-                builder.ins().return_(&[return_result]);
+                self.emit_return(&[return_result], builder)?;
 
                 // Ok case - extract success value and continue
                 builder.switch_to_block(ok_block);
@@ -1772,7 +1982,7 @@ impl<'a> FunctionTranslator<'a> {
 
                 // Set tag to None (0)
                 builder.ins().store(memflags, none_tag, return_option, 0);
-                builder.ins().return_(&[return_option]);
+                self.emit_return(&[return_option], builder)?;
 
                 // Some case - extract value and continue
                 builder.switch_to_block(some_block);
@@ -1797,3 +2007,20 @@ impl<'a> FunctionTranslator<'a> {
         }
     }
 }
+
+/// Whether values of `ty` may point to heap objects
+fn may_hold_reference(ty: &crate::types::Type) -> bool {
+    use crate::types::Type;
+
+    !matches!(
+        ty,
+        Type::I32
+            | Type::I64
+            | Type::U32
+            | Type::U64
+            | Type::F32
+            | Type::F64
+            | Type::Bool
+            | Type::Never
+    )
+}
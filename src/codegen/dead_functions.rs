@@ -0,0 +1,238 @@
+//! Whole-program dead function elimination
+//!
+//! Runs at link time, after monomorphization, on the module handed to a
+//! backend. Every function not reachable from an entry point or an exported
+//! function through calls or closure creation is dropped, including generic
+//! templates whose instantiations replaced them and trait method stubs whose
+//! calls were redirected to concrete impls.
+
+use crate::ir::{FunctionId, Instruction, Module as IrModule};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Functions execution may start from, in order of preference
+pub const ENTRY_POINTS: &[&str] = &["__script_main__", "main"];
+
+/// Statistics from dead function elimination
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeadFunctionStats {
+    /// Number of functions before elimination
+    pub functions_before: usize,
+    /// Names of the removed functions, sorted
+    pub removed_functions: Vec<String>,
+    /// Names of the removed runtime shim declarations, sorted
+    pub removed_shims: Vec<String>,
+}
+
+impl DeadFunctionStats {
+    /// Number of functions left in the module
+    pub fn functions_after(&self) -> usize {
+        self.functions_before - self.removed_functions.len()
+    }
+}
+
+impl fmt::Display for DeadFunctionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Dead function elimination: {} of {} functions kept, {} removed",
+            self.functions_after(),
+            self.functions_before,
+            self.removed_functions.len()
+        )?;
+        for name in &self.removed_functions {
+            writeln!(f, "  removed function {}", name)?;
+        }
+        for name in &self.removed_shims {
+            writeln!(f, "  removed runtime shim {}", name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Remove every function unreachable from the module's entry points and
+/// exported functions
+///
+/// A module with neither any of [`ENTRY_POINTS`] nor exports gives no hint
+/// of which functions its users call, so it is left untouched. Calls to ids
+/// without a definition go to the runtime; the module's external declarations
+/// are only dropped when no kept function makes such a call.
+pub fn eliminate_dead_functions(module: &mut IrModule) -> DeadFunctionStats {
+    eliminate_unreachable(module, true)
+}
+
+/// Remove every function unreachable from the module's entry points
+///
+/// Used with link-time optimization, when the module is the whole program:
+/// exported functions the program never calls are dropped too. A module
+/// without an entry point is left untouched.
+pub fn eliminate_dead_functions_whole_program(module: &mut IrModule) -> DeadFunctionStats {
+    eliminate_unreachable(module, false)
+}
+
+fn eliminate_unreachable(module: &mut IrModule, keep_exports: bool) -> DeadFunctionStats {
+    let mut stats = DeadFunctionStats {
+        functions_before: module.functions().len(),
+        ..Default::default()
+    };
+
+    let exports = if keep_exports {
+        module.exported_function_names()
+    } else {
+        Vec::new()
+    };
+    let roots: Vec<FunctionId> = ENTRY_POINTS
+        .iter()
+        .map(|name| name.to_string())
+        .chain(exports)
+        .filter_map(|name| module.get_function_id(&name))
+        .collect();
+    if roots.is_empty() {
+        return stats;
+    }
+
+    let mut reachable = HashSet::new();
+    let mut calls_runtime = false;
+    let mut worklist = roots;
+    while let Some(func_id) = worklist.pop() {
+        if !reachable.insert(func_id) {
+            continue;
+        }
+        let Some(function) = module.get_function(func_id) else {
+            continue;
+        };
+        for block in function.blocks().values() {
+            for (_, inst) in &block.instructions {
+                let callee = match &inst.instruction {
+                    Instruction::Call { func, .. } => *func,
+                    Instruction::CreateClosure { function, .. } => *function,
+                    _ => continue,
+                };
+                if module.has_function(callee) {
+                    worklist.push(callee);
+                } else {
+                    calls_runtime = true;
+                }
+            }
+        }
+    }
+
+    for func_id in module.function_ids() {
+        if reachable.contains(&func_id) {
+            continue;
+        }
+        if let Ok(function) = module.remove_function(func_id) {
+            stats.removed_functions.push(function.name);
+        }
+    }
+    stats.removed_functions.sort();
+
+    if !calls_runtime {
+        for name in module.external_function_names() {
+            module.remove_external_function(&name);
+            stats.removed_shims.push(name);
+        }
+        stats.removed_shims.sort();
+    }
+
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Constant, IrBuilder};
+    use crate::types::Type;
+
+    fn function_returning(builder: &mut IrBuilder, name: &str) -> FunctionId {
+        let id = builder.create_function(name.to_string(), vec![], Type::I32);
+        let value = builder.const_value(Constant::I32(0));
+        builder.build_return(Some(value));
+        id
+    }
+
+    #[test]
+    fn test_removes_functions_unreachable_from_main() {
+        let mut builder = IrBuilder::new();
+        let helper = function_returning(&mut builder, "helper");
+        function_returning(&mut builder, "unused");
+        builder.create_function("main".to_string(), vec![], Type::I32);
+        let result = builder.build_call(helper, vec![], Type::I32);
+        builder.build_return(result);
+
+        let mut module = builder.build();
+        let stats = eliminate_dead_functions(&mut module);
+
+        assert_eq!(stats.functions_before, 3);
+        assert_eq!(stats.removed_functions, vec!["unused".to_string()]);
+        assert!(module.has_function_by_name("main"));
+        assert!(module.has_function_by_name("helper"));
+        assert!(!module.has_function_by_name("unused"));
+        // Nothing calls into the runtime
+        assert_eq!(stats.removed_shims, vec!["print".to_string()]);
+        assert!(!module.is_external_function("print"));
+    }
+
+    #[test]
+    fn test_runtime_calls_keep_shims() {
+        let mut builder = IrBuilder::new();
+        builder.create_function("__script_main__".to_string(), vec![], Type::Unknown);
+        let runtime = builder.module_mut().reserve_function_id();
+        builder.build_call(runtime, vec![], Type::Unknown);
+        builder.build_return(None);
+
+        let mut module = builder.build();
+        let stats = eliminate_dead_functions(&mut module);
+
+        assert!(stats.removed_shims.is_empty());
+        assert!(module.is_external_function("print"));
+    }
+
+    #[test]
+    fn test_exported_functions_are_roots() {
+        let mut builder = IrBuilder::new();
+        let helper = function_returning(&mut builder, "helper");
+        function_returning(&mut builder, "unused");
+        builder.create_function("api".to_string(), vec![], Type::I32);
+        let result = builder.build_call(helper, vec![], Type::I32);
+        builder.build_return(result);
+        builder.module_mut().mark_exported("api".to_string());
+
+        let mut module = builder.build();
+        let stats = eliminate_dead_functions(&mut module);
+
+        assert_eq!(stats.removed_functions, vec!["unused".to_string()]);
+        assert!(module.has_function_by_name("api"));
+        assert!(module.has_function_by_name("helper"));
+    }
+
+    #[test]
+    fn test_library_without_entry_point_is_untouched() {
+        let mut builder = IrBuilder::new();
+        function_returning(&mut builder, "a");
+        function_returning(&mut builder, "b");
+
+        let mut module = builder.build();
+        let stats = eliminate_dead_functions(&mut module);
+
+        assert!(stats.removed_functions.is_empty());
+        assert_eq!(module.functions().len(), 2);
+    }
+
+    #[test]
+    fn test_whole_program_drops_uncalled_exports() {
+        let mut builder = IrBuilder::new();
+        let helper = function_returning(&mut builder, "helper");
+        function_returning(&mut builder, "api");
+        builder.module_mut().mark_exported("api".to_string());
+        builder.create_function("main".to_string(), vec![], Type::I32);
+        let result = builder.build_call(helper, vec![], Type::I32);
+        builder.build_return(result);
+
+        let mut module = builder.build();
+        let stats = eliminate_dead_functions_whole_program(&mut module);
+
+        assert_eq!(stats.removed_functions, vec!["api".to_string()]);
+        assert!(module.has_function_by_name("helper"));
+    }
+}
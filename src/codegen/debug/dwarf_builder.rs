@@ -191,7 +191,11 @@ impl DwarfBuilder {
 
         let (name, byte_size, encoding) = match script_type {
             ScriptType::I32 => ("i32", 4, DwarfEncoding::SignedInt),
+            ScriptType::I64 => ("i64", 8, DwarfEncoding::SignedInt),
+            ScriptType::U32 => ("u32", 4, DwarfEncoding::UnsignedInt),
+            ScriptType::U64 => ("u64", 8, DwarfEncoding::UnsignedInt),
             ScriptType::F32 => ("f32", 4, DwarfEncoding::Float),
+            ScriptType::F64 => ("f64", 8, DwarfEncoding::Float),
             ScriptType::Bool => ("bool", 1, DwarfEncoding::Boolean),
             ScriptType::String => ("string", 8, DwarfEncoding::Address), // Pointer to string data
             // No null type in Script - use unknown instead
@@ -2,11 +2,24 @@
 //!
 //! This module handles the generation of DWARF debug information
 //! for Script language programs compiled with Cranelift.
+//!
+//! The backend records, for every compiled function, which ranges of its
+//! machine code came from which source spans and where its variables live.
+//! Once the functions have addresses, `DebugContext` turns this into DWARF
+//! line tables and `DW_TAG_subprogram`/`DW_TAG_variable` entries, and
+//! answers address-to-source queries for the built-in debugger.
 
-use self::safe_conversions::{usize_to_u64, validate_file_count};
-use crate::error::Error;
-use crate::ir::Function as IrFunction;
-use crate::source::SourceLocation;
+use self::safe_conversions::{
+    usize_to_u64, validate_column_number, validate_file_count, validate_line_number,
+};
+use crate::error::{Error, ErrorKind};
+use crate::source::{SourceLocation, Span};
+use crate::types::Type as ScriptType;
+use gimli::write::{
+    Address, AttributeValue, DwarfUnit, EndianVec, Expression, FileId, LineProgram, LineString,
+    Location, LocationList, Range, RangeList, Sections, UnitEntryId,
+};
+use gimli::{Encoding, Format, LineEncoding, Register, RunTimeEndian};
 use std::collections::HashMap;
 
 pub mod dwarf_builder;
@@ -14,10 +27,82 @@ pub mod line_table;
 pub mod safe_conversions;
 pub mod type_info;
 
-pub use dwarf_builder::DwarfBuilder;
+pub use dwarf_builder::{DwarfBuilder, DwarfLocation};
 pub use line_table::LineTableBuilder;
 pub use type_info::TypeInfoBuilder;
 
+/// Machine code of a function and the source information it came from
+#[derive(Debug, Clone)]
+pub struct FunctionDebugInfo {
+    /// Function name
+    pub name: String,
+    /// Source span of the function's declaration
+    pub span: Option<Span>,
+    /// Address of the function's first instruction, once finalized
+    pub address: Option<u64>,
+    /// Size of the function's machine code in bytes
+    pub code_size: u32,
+    /// Source span of each range of machine code, sorted by offset
+    pub lines: Vec<LineRange>,
+    /// Source-level variables of the function
+    pub variables: Vec<VariableDebugInfo>,
+}
+
+/// Range of a function's machine code generated from one source span
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineRange {
+    /// Offset of the first byte from the start of the function
+    pub start: u32,
+    /// Offset one past the last byte
+    pub end: u32,
+    /// Source span the code was generated from
+    pub span: Span,
+}
+
+/// Source-level variable and where its storage can be found
+#[derive(Debug, Clone)]
+pub struct VariableDebugInfo {
+    /// Variable name
+    pub name: String,
+    /// Variable type
+    pub ty: ScriptType,
+    /// Whether the variable is a function parameter
+    pub is_param: bool,
+    /// Where the variable is declared
+    pub span: Option<Span>,
+    /// Code ranges, as offsets from the start of the function, over which
+    /// the variable's address is known, and where that address is held
+    pub ranges: Vec<(u32, u32, DwarfLocation)>,
+}
+
+/// Encoded DWARF sections, keyed by their ELF section names
+#[derive(Debug, Clone, Default)]
+pub struct DwarfSections {
+    sections: Vec<(&'static str, Vec<u8>)>,
+}
+
+impl DwarfSections {
+    /// Get the contents of a section such as `.debug_line`
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.sections
+            .iter()
+            .find(|(section, _)| *section == name)
+            .map(|(_, data)| data.as_slice())
+    }
+
+    /// Iterate over the non-empty sections
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &[u8])> {
+        self.sections
+            .iter()
+            .map(|(name, data)| (*name, data.as_slice()))
+    }
+
+    /// Check if no debug information was generated
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+}
+
 /// Debug information context for a compilation unit
 #[derive(Debug)]
 pub struct DebugContext {
@@ -29,6 +114,8 @@ pub struct DebugContext {
     file_map: HashMap<String, u64>,
     /// Current source file being processed
     current_file: Option<String>,
+    /// Functions compiled so far
+    functions: Vec<FunctionDebugInfo>,
 }
 
 impl DebugContext {
@@ -39,6 +126,7 @@ impl DebugContext {
             producer,
             file_map: HashMap::new(),
             current_file: None,
+            functions: Vec::new(),
         }
     }
 
@@ -65,32 +153,305 @@ impl DebugContext {
         Ok(())
     }
 
-    /// Add line information for an instruction
-    pub fn add_line_info(&mut self, _address: u64, _location: &SourceLocation) {
-        // Placeholder implementation for line info
-        // In a full implementation, this would build line number tables
+    /// Add the debug information of a compiled function
+    pub fn add_function(&mut self, function: FunctionDebugInfo) -> Result<(), Error> {
+        validate_entry_count(self.functions.len())?;
+        self.functions.push(function);
+        Ok(())
     }
 
-    /// Add function debug information
-    pub fn add_function(
-        &mut self,
-        _function: &IrFunction,
-        _start_address: u64,
-        _end_address: u64,
-    ) -> Result<(), Error> {
-        // Placeholder implementation for function debug info
-        // In a full implementation, this would create DWARF function entries
-        Ok(())
+    /// Record the address a function was placed at
+    pub fn set_function_address(&mut self, name: &str, address: u64) {
+        if let Some(function) = self.functions.iter_mut().find(|f| f.name == name) {
+            function.address = Some(address);
+        }
     }
 
-    /// Generate DWARF sections (simplified)
-    pub fn generate_sections(&mut self) -> Result<Vec<u8>, Error> {
-        // Placeholder implementation that returns empty debug info
-        // In a full implementation, this would generate proper DWARF sections
-        Ok(Vec::new())
+    /// Functions compiled so far
+    pub fn functions(&self) -> &[FunctionDebugInfo] {
+        &self.functions
+    }
+
+    /// Find the function whose machine code contains `address`
+    pub fn function_at(&self, address: u64) -> Option<&FunctionDebugInfo> {
+        self.functions.iter().find(|function| {
+            function.address.is_some_and(|start| {
+                address >= start && address < start + u64::from(function.code_size)
+            })
+        })
+    }
+
+    /// Find the source location the instruction at `address` was
+    /// generated from
+    pub fn source_location(&self, address: u64) -> Option<SourceLocation> {
+        let function = self.function_at(address)?;
+        let offset = address - function.address?;
+        function
+            .lines
+            .iter()
+            .find(|line| offset >= u64::from(line.start) && offset < u64::from(line.end))
+            .map(|line| line.span.start)
+    }
+
+    /// Generate the DWARF sections for the functions placed so far
+    ///
+    /// Functions without an address are skipped.
+    pub fn generate_sections(&mut self) -> Result<DwarfSections, Error> {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 8,
+        };
+        let mut dwarf = DwarfUnit::new(encoding);
+
+        let comp_dir = if self.comp_dir.is_empty() {
+            "."
+        } else {
+            self.comp_dir.as_str()
+        };
+        let file_name = self.current_file.as_deref().unwrap_or("<unknown>");
+        dwarf.unit.line_program = LineProgram::new(
+            encoding,
+            LineEncoding::default(),
+            LineString::new(comp_dir, encoding, &mut dwarf.line_strings),
+            LineString::new(file_name, encoding, &mut dwarf.line_strings),
+            None,
+        );
+        let directory = dwarf.unit.line_program.default_directory();
+        let file = dwarf.unit.line_program.add_file(
+            LineString::new(file_name, encoding, &mut dwarf.line_strings),
+            directory,
+            None,
+        );
+
+        let root = dwarf.unit.root();
+        let producer = dwarf.strings.add(self.producer.as_str());
+        let name = dwarf.strings.add(file_name);
+        let dir = dwarf.strings.add(comp_dir);
+        let cu = dwarf.unit.get_mut(root);
+        cu.set(gimli::DW_AT_producer, AttributeValue::StringRef(producer));
+        cu.set(gimli::DW_AT_name, AttributeValue::StringRef(name));
+        cu.set(gimli::DW_AT_comp_dir, AttributeValue::StringRef(dir));
+
+        let mut ranges = Vec::new();
+        let mut base_types = HashMap::new();
+        for function in &self.functions {
+            let Some(address) = function.address else {
+                continue;
+            };
+            ranges.push(Range::StartLength {
+                begin: Address::Constant(address),
+                length: u64::from(function.code_size),
+            });
+
+            write_line_rows(&mut dwarf, file, address, function)?;
+            write_subprogram(&mut dwarf, file, address, function, &mut base_types)?;
+        }
+
+        let ranges = dwarf.unit.ranges.add(RangeList(ranges));
+        let cu = dwarf.unit.get_mut(root);
+        cu.set(
+            gimli::DW_AT_low_pc,
+            AttributeValue::Address(Address::Constant(0)),
+        );
+        cu.set(gimli::DW_AT_ranges, AttributeValue::RangeListRef(ranges));
+
+        let mut sections = Sections::new(EndianVec::new(RunTimeEndian::default()));
+        dwarf.write(&mut sections).map_err(dwarf_error)?;
+
+        let mut output = DwarfSections::default();
+        sections
+            .for_each(|id, data| {
+                if !data.slice().is_empty() {
+                    output.sections.push((id.name(), data.slice().to_vec()));
+                }
+                Ok::<(), Error>(())
+            })
+            .map(|_| output)
     }
 }
 
+/// Emit the line table sequence of a function
+fn write_line_rows(
+    dwarf: &mut DwarfUnit,
+    file: FileId,
+    address: u64,
+    function: &FunctionDebugInfo,
+) -> Result<(), Error> {
+    let program = &mut dwarf.unit.line_program;
+    program.begin_sequence(Some(Address::Constant(address)));
+    for line in &function.lines {
+        let row = program.row();
+        row.address_offset = u64::from(line.start);
+        row.file = file;
+        row.line = u64::from(validate_line_number(line.span.start.line)?);
+        row.column = u64::from(validate_column_number(line.span.start.column)?);
+        program.generate_row();
+    }
+    program.end_sequence(u64::from(function.code_size));
+    Ok(())
+}
+
+/// Emit the `DW_TAG_subprogram` of a function and its variables
+fn write_subprogram(
+    dwarf: &mut DwarfUnit,
+    file: FileId,
+    address: u64,
+    function: &FunctionDebugInfo,
+    base_types: &mut HashMap<String, UnitEntryId>,
+) -> Result<(), Error> {
+    let root = dwarf.unit.root();
+    let subprogram = dwarf.unit.add(root, gimli::DW_TAG_subprogram);
+    let name = dwarf.strings.add(function.name.as_str());
+    let entry = dwarf.unit.get_mut(subprogram);
+    entry.set(gimli::DW_AT_name, AttributeValue::StringRef(name));
+    entry.set(
+        gimli::DW_AT_low_pc,
+        AttributeValue::Address(Address::Constant(address)),
+    );
+    entry.set(
+        gimli::DW_AT_high_pc,
+        AttributeValue::Udata(u64::from(function.code_size)),
+    );
+    if let Some(span) = function.span {
+        entry.set(
+            gimli::DW_AT_decl_file,
+            AttributeValue::FileIndex(Some(file)),
+        );
+        entry.set(
+            gimli::DW_AT_decl_line,
+            AttributeValue::Udata(u64::from(validate_line_number(span.start.line)?)),
+        );
+    }
+
+    for variable in &function.variables {
+        let tag = if variable.is_param {
+            gimli::DW_TAG_formal_parameter
+        } else {
+            gimli::DW_TAG_variable
+        };
+        let ty = base_type(dwarf, &variable.ty, base_types);
+        let die = dwarf.unit.add(subprogram, tag);
+        let name = dwarf.strings.add(variable.name.as_str());
+
+        let locations = variable
+            .ranges
+            .iter()
+            .map(|(start, end, location)| Location::StartEnd {
+                begin: Address::Constant(address + u64::from(*start)),
+                end: Address::Constant(address + u64::from(*end)),
+                data: location_expression(location),
+            })
+            .collect::<Vec<_>>();
+        let locations = if locations.is_empty() {
+            None
+        } else {
+            Some(dwarf.unit.locations.add(LocationList(locations)))
+        };
+
+        let entry = dwarf.unit.get_mut(die);
+        entry.set(gimli::DW_AT_name, AttributeValue::StringRef(name));
+        entry.set(gimli::DW_AT_type, AttributeValue::UnitRef(ty));
+        if let Some(span) = variable.span {
+            entry.set(
+                gimli::DW_AT_decl_file,
+                AttributeValue::FileIndex(Some(file)),
+            );
+            entry.set(
+                gimli::DW_AT_decl_line,
+                AttributeValue::Udata(u64::from(validate_line_number(span.start.line)?)),
+            );
+        }
+        if let Some(locations) = locations {
+            entry.set(
+                gimli::DW_AT_location,
+                AttributeValue::LocationListRef(locations),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// DWARF expression computing a variable's address
+///
+/// Variables live in stack slots, so their locations hold the slot's
+/// address rather than the value itself.
+fn location_expression(location: &DwarfLocation) -> Expression {
+    let mut expression = Expression::new();
+    match location {
+        DwarfLocation::Register(register) => {
+            expression.op_breg(Register(*register as u16), 0);
+        }
+        DwarfLocation::FrameOffset(offset) => {
+            expression.op(gimli::DW_OP_call_frame_cfa);
+            expression.op_consts(i64::from(*offset));
+            expression.op(gimli::DW_OP_plus);
+            expression.op(gimli::DW_OP_deref);
+        }
+        DwarfLocation::GlobalAddress(address) => {
+            expression.op_addr(Address::Constant(*address));
+        }
+    }
+    expression
+}
+
+/// Get or emit the `DW_TAG_base_type` describing a Script type
+fn base_type(
+    dwarf: &mut DwarfUnit,
+    ty: &ScriptType,
+    base_types: &mut HashMap<String, UnitEntryId>,
+) -> UnitEntryId {
+    let name = ty.to_string();
+    if let Some(&id) = base_types.get(&name) {
+        return id;
+    }
+
+    let (byte_size, encoding) = match ty {
+        ScriptType::I32 => (4, gimli::DW_ATE_signed),
+        ScriptType::I64 => (8, gimli::DW_ATE_signed),
+        ScriptType::U32 => (4, gimli::DW_ATE_unsigned),
+        ScriptType::U64 => (8, gimli::DW_ATE_unsigned),
+        ScriptType::F32 => (4, gimli::DW_ATE_float),
+        ScriptType::F64 => (8, gimli::DW_ATE_float),
+        ScriptType::Bool => (1, gimli::DW_ATE_boolean),
+        // Everything else is represented by a pointer
+        _ => (8, gimli::DW_ATE_address),
+    };
+
+    let root = dwarf.unit.root();
+    let id = dwarf.unit.add(root, gimli::DW_TAG_base_type);
+    let name_ref = dwarf.strings.add(name.as_str());
+    let entry = dwarf.unit.get_mut(id);
+    entry.set(gimli::DW_AT_name, AttributeValue::StringRef(name_ref));
+    entry.set(gimli::DW_AT_byte_size, AttributeValue::Data1(byte_size));
+    entry.set(gimli::DW_AT_encoding, AttributeValue::Encoding(encoding));
+    base_types.insert(name, id);
+    id
+}
+
+/// Check that another debug entry fits within the limits
+fn validate_entry_count(count: usize) -> Result<(), Error> {
+    if count >= safe_conversions::limits::MAX_DEBUG_ENTRIES {
+        return Err(Error::new(
+            ErrorKind::InvalidConversion,
+            format!(
+                "Too many debug entries: {} exceeds maximum of {}",
+                count,
+                safe_conversions::limits::MAX_DEBUG_ENTRIES
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn dwarf_error(error: gimli::write::Error) -> Error {
+    Error::new(
+        ErrorKind::RuntimeError,
+        format!("Failed to write DWARF sections: {}", error),
+    )
+}
+
 /// Debug compilation flags
 #[derive(Debug, Clone)]
 pub struct DebugFlags {
@@ -186,6 +547,71 @@ mod tests {
         assert!(release_flags.optimize);
     }
 
+    #[test]
+    fn test_function_line_info() -> Result<(), Error> {
+        let mut debug_ctx = DebugContext::new(
+            "/test/project".to_string(),
+            "Script Language Compiler 0.1.0".to_string(),
+        );
+        debug_ctx.set_current_file("/test/project/main.script".to_string())?;
+
+        let span = |line| {
+            Span::new(
+                SourceLocation::new(line, 5, 0),
+                SourceLocation::new(line, 15, 0),
+            )
+        };
+        debug_ctx.add_function(FunctionDebugInfo {
+            name: "main".to_string(),
+            span: Some(span(1)),
+            address: None,
+            code_size: 16,
+            lines: vec![
+                LineRange {
+                    start: 0,
+                    end: 8,
+                    span: span(2),
+                },
+                LineRange {
+                    start: 8,
+                    end: 16,
+                    span: span(3),
+                },
+            ],
+            variables: vec![VariableDebugInfo {
+                name: "x".to_string(),
+                ty: ScriptType::I32,
+                is_param: false,
+                span: Some(span(2)),
+                ranges: vec![(4, 16, DwarfLocation::FrameOffset(-8))],
+            }],
+        })?;
+
+        // Unplaced functions have no addresses to look up
+        assert!(debug_ctx.function_at(0x1000).is_none());
+        debug_ctx.set_function_address("main", 0x1000);
+
+        assert_eq!(
+            debug_ctx.source_location(0x1004).map(|loc| loc.line),
+            Some(2)
+        );
+        assert_eq!(
+            debug_ctx.source_location(0x100c).map(|loc| loc.line),
+            Some(3)
+        );
+        assert!(debug_ctx.source_location(0x1010).is_none());
+
+        let sections = debug_ctx.generate_sections()?;
+        assert!(!sections.is_empty());
+        assert!(sections
+            .get(".debug_info")
+            .is_some_and(|data| !data.is_empty()));
+        assert!(sections
+            .get(".debug_line")
+            .is_some_and(|data| !data.is_empty()));
+        Ok(())
+    }
+
     #[test]
     fn test_file_count_limit() {
         let mut debug_ctx = DebugContext::new(
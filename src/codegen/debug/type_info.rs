@@ -37,7 +37,11 @@ impl TypeInfoBuilder {
     fn get_or_create_type_id(&mut self, script_type: &ScriptType) -> u32 {
         match script_type {
             ScriptType::I32 => self.get_or_insert_primitive("i32", script_type),
+            ScriptType::I64 => self.get_or_insert_primitive("i64", script_type),
+            ScriptType::U32 => self.get_or_insert_primitive("u32", script_type),
+            ScriptType::U64 => self.get_or_insert_primitive("u64", script_type),
             ScriptType::F32 => self.get_or_insert_primitive("f32", script_type),
+            ScriptType::F64 => self.get_or_insert_primitive("f64", script_type),
             ScriptType::Bool => self.get_or_insert_primitive("bool", script_type),
             ScriptType::String => self.get_or_insert_primitive("string", script_type),
             ScriptType::Unknown => self.get_or_insert_primitive("unknown", script_type),
@@ -86,8 +86,10 @@ impl FieldLayout {
     /// Calculate size and alignment for a type
     fn get_type_size_and_align(ty: &Type) -> (usize, usize) {
         match ty {
-            Type::I32 => (4, 4),
+            Type::I32 | Type::U32 => (4, 4),
+            Type::I64 | Type::U64 => (8, 8),
             Type::F32 => (4, 4),
+            Type::F64 => (8, 8),
             Type::Bool => (1, 1),
             Type::String => (16, 8),          // String struct: ptr + len
             Type::Reference { .. } => (8, 8), // Pointer
@@ -1,24 +1,34 @@
 //! Code generation module
 //!
 //! This module is responsible for generating executable code from the IR.
-//! It supports multiple backends, starting with Cranelift for JIT compilation.
+//! It supports multiple backends: Cranelift for JIT compilation and a
+//! WebAssembly backend for ahead-of-time `wasm32` builds.
 
 use crate::codegen::debug::DebugFlags;
 use crate::error::Error;
 use crate::inference::InferenceContext;
 use crate::ir::Module as IrModule;
+use crate::runtime::GcMode;
 use crate::semantic::analyzer::SemanticAnalyzer;
+use crate::source::SourceLocation;
 use std::time::Instant;
 
 pub mod bounds_check;
 pub mod cranelift;
+pub mod dead_functions;
 pub mod debug;
 pub mod field_layout;
 pub mod monomorphization;
+pub mod overflow_check;
+pub mod wasm;
 
 pub use bounds_check::{BoundsCheckMode, BoundsChecker};
+pub use dead_functions::{
+    eliminate_dead_functions, eliminate_dead_functions_whole_program, DeadFunctionStats,
+};
 pub use field_layout::{FieldLayout, FieldLayoutRegistry};
-pub use monomorphization::{MonomorphizationContext, MonomorphizationStats};
+pub use monomorphization::{BoxedFallbackPolicy, MonomorphizationContext, MonomorphizationStats};
+pub use overflow_check::{OverflowChecker, OverflowMode};
 
 /// Result type for code generation
 pub type CodegenResult<T> = Result<T, Error>;
@@ -84,6 +94,36 @@ impl CodeGenerator {
         }
     }
 
+    /// Create a new code generator with the given integer overflow behavior
+    pub fn with_overflow_mode(overflow_mode: OverflowMode) -> Self {
+        let mut backend = cranelift::CraneliftBackend::new();
+        backend.set_overflow_mode(overflow_mode);
+        CodeGenerator {
+            backend: Box::new(backend),
+            monomorphization_ctx: MonomorphizationContext::new(),
+            stats: CodegenStats::default(),
+        }
+    }
+
+    /// Create a new code generator for the given integer overflow behavior,
+    /// emitting the write barriers `gc_mode` needs and, with `profile`, calls
+    /// to the function profiler on every function entry and exit
+    pub fn with_runtime_options(
+        overflow_mode: OverflowMode,
+        gc_mode: GcMode,
+        profile: bool,
+    ) -> Self {
+        let mut backend = cranelift::CraneliftBackend::new();
+        backend.set_overflow_mode(overflow_mode);
+        backend.set_gc_mode(gc_mode);
+        backend.set_profiling(profile);
+        CodeGenerator {
+            backend: Box::new(backend),
+            monomorphization_ctx: MonomorphizationContext::new(),
+            stats: CodegenStats::default(),
+        }
+    }
+
     /// Create a new code generator with semantic analyzer integration
     pub fn with_semantic_analyzer(semantic_analyzer: SemanticAnalyzer) -> Self {
         CodeGenerator {
@@ -124,6 +164,18 @@ impl CodeGenerator {
         }
     }
 
+    /// Generate a WebAssembly module from IR
+    pub fn generate_wasm(&mut self, ir_module: &IrModule) -> CodegenResult<Vec<u8>> {
+        let start_time = Instant::now();
+        let result = wasm::WasmBackend::new().generate(ir_module);
+        self.stats.compilation_time_ms = start_time.elapsed().as_millis() as u64;
+
+        let bytes = result?;
+        self.stats.functions_generated = ir_module.functions().len();
+        self.stats.code_size = bytes.len();
+        Ok(bytes)
+    }
+
     /// Get compilation statistics
     pub fn stats(&self) -> &CodegenStats {
         &self.stats
@@ -238,6 +290,43 @@ impl ExecutableModule {
         }
     }
 
+    /// Get the DWARF sections generated for the module, if debug info was
+    /// enabled
+    pub fn debug_sections(&self) -> Option<&debug::DwarfSections> {
+        use crate::codegen::cranelift::CraneliftModuleData;
+
+        self.backend_data
+            .downcast_ref::<CraneliftModuleData>()
+            .and_then(|data| data.debug_info.as_ref())
+    }
+
+    /// Map a machine code address back to the function and source location
+    /// it was generated from
+    pub fn source_location(&self, address: u64) -> Option<(&str, SourceLocation)> {
+        use crate::codegen::cranelift::CraneliftModuleData;
+
+        let debug_ctx = self
+            .backend_data
+            .downcast_ref::<CraneliftModuleData>()?
+            .debug_context
+            .as_ref()?;
+        let function = debug_ctx.function_at(address)?;
+        let location = debug_ctx.source_location(address)?;
+        Some((function.name.as_str(), location))
+    }
+
+    /// Source map of the module, if debug info was enabled
+    ///
+    /// Unlike `source_location`, it also relates machine code and source
+    /// spans to the IR instructions in between.
+    pub fn source_map(&self) -> Option<&crate::compilation::SourceMap> {
+        use crate::codegen::cranelift::CraneliftModuleData;
+
+        self.backend_data
+            .downcast_ref::<CraneliftModuleData>()
+            .and_then(|data| data.source_map.as_ref())
+    }
+
     /// Get a function pointer by name
     pub fn get_function<T>(&self, name: &str) -> Option<*const T> {
         use crate::codegen::cranelift::CraneliftModuleData;
@@ -313,4 +402,14 @@ mod tests {
         assert!(stats.compilation_time_ms > 0);
         assert_eq!(stats.functions_generated, module.functions().len());
     }
+
+    #[test]
+    fn test_generate_wasm_records_code_size() {
+        let mut gen = CodeGenerator::new();
+        let module = IrBuilder::new().build();
+
+        let bytes = gen.generate_wasm(&module).unwrap();
+        assert!(bytes.starts_with(b"\0asm"));
+        assert_eq!(gen.stats().code_size, bytes.len());
+    }
 }
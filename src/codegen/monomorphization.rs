@@ -1,6 +1,6 @@
 use crate::error::{Error, ErrorKind};
 use crate::inference::InferenceContext;
-use crate::ir::{Function, Module};
+use crate::ir::{Function, GenericDispatch, Instruction, Module};
 use crate::semantic::analyzer::{GenericInstantiation, SemanticAnalyzer};
 use crate::types::{
     definitions::{EnumDefinition, StructDefinition, TypeDefinitionRegistry},
@@ -46,6 +46,63 @@ pub struct MonomorphizationContext {
     mangle_cache: HashMap<Vec<Type>, String>,
     /// Batch size for processing items
     batch_size: usize,
+    /// When rarely used instantiations share a boxed version
+    boxed_fallback: BoxedFallbackPolicy,
+    /// Number of call sites using each instantiation
+    instantiation_uses: HashMap<(String, Vec<Type>), usize>,
+    /// Number of distinct instantiations of each generic function
+    instantiation_counts: HashMap<String, usize>,
+    /// Shared boxed versions, keyed by generic function name
+    boxed_function_cache: HashMap<String, Function>,
+    /// Mangled instantiation name to the boxed version that implements it
+    boxed_instantiations: HashMap<String, String>,
+    /// Signatures of the instantiations queued for specialization
+    queued_signatures: HashSet<InstantiationSignature>,
+    /// Instantiations dropped because an identical one was already queued
+    deduplicated_instantiations: usize,
+}
+
+/// Heuristics for compiling rare instantiations as one boxed version
+///
+/// Instead of a specialized copy per type, a generic function can be compiled
+/// once with its type parameters erased to boxed (`Unknown`) values. This is
+/// slower, so it is only worth it for large functions with many
+/// instantiations, most of which are barely used. Functions opt in or out
+/// regardless of these heuristics with `@monomorphize(never)` and
+/// `@monomorphize(always)`.
+#[derive(Debug, Clone)]
+pub struct BoxedFallbackPolicy {
+    /// Whether the heuristics apply to functions without an attribute
+    pub enabled: bool,
+    /// Instantiations used at no more than this many call sites are rare
+    pub max_rare_uses: usize,
+    /// Smaller functions are always specialized: copies are cheap and
+    /// specialization lets them be inlined
+    pub min_instructions: usize,
+    /// Rare instantiations are only boxed once the function has at least
+    /// this many instantiations
+    pub min_instantiations: usize,
+}
+
+impl Default for BoxedFallbackPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_rare_uses: 1,
+            min_instructions: 32,
+            min_instantiations: 4,
+        }
+    }
+}
+
+impl BoxedFallbackPolicy {
+    /// The default heuristics, enabled
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Self::default()
+        }
+    }
 }
 
 /// Statistics for the monomorphization process
@@ -71,6 +128,13 @@ pub struct MonomorphizationStats {
     pub processing_time_ms: u64,
     /// Number of items processed in batches
     pub batched_items: usize,
+    /// Number of shared boxed versions generated
+    pub boxed_functions: usize,
+    /// Number of instantiations compiled as a boxed version
+    pub boxed_instantiations: usize,
+    /// Number of instantiations requested more than once, usually by
+    /// different modules, that share a single specialization
+    pub deduplicated_instantiations: usize,
 }
 
 impl MonomorphizationContext {
@@ -97,6 +161,13 @@ impl MonomorphizationContext {
             monomorphization_start: None,
             mangle_cache: HashMap::new(),
             batch_size: Self::DEFAULT_BATCH_SIZE,
+            boxed_fallback: BoxedFallbackPolicy::default(),
+            instantiation_uses: HashMap::new(),
+            instantiation_counts: HashMap::new(),
+            boxed_function_cache: HashMap::new(),
+            boxed_instantiations: HashMap::new(),
+            queued_signatures: HashSet::new(),
+            deduplicated_instantiations: 0,
         }
     }
 
@@ -118,6 +189,12 @@ impl MonomorphizationContext {
         self
     }
 
+    /// Set when rare instantiations fall back to a shared boxed version
+    pub fn with_boxed_fallback(mut self, policy: BoxedFallbackPolicy) -> Self {
+        self.boxed_fallback = policy;
+        self
+    }
+
     /// Initialize monomorphization from semantic analysis results
     pub fn initialize_from_semantic_analysis(
         &mut self,
@@ -127,12 +204,35 @@ impl MonomorphizationContext {
         // Build dependency graph first
         self.build_dependency_graph(generic_instantiations)?;
 
-        // Process all generic instantiations
+        // Count call sites per instantiation for the boxed fallback
         for instantiation in generic_instantiations {
-            self.add_instantiation_to_levels(
-                instantiation.function_name.clone(),
-                instantiation.type_args.clone(),
-            )?;
+            let uses = self
+                .instantiation_uses
+                .entry((
+                    instantiation.function_name.clone(),
+                    canonical_type_args(&instantiation.type_args),
+                ))
+                .or_insert(0);
+            if *uses == 0 {
+                *self
+                    .instantiation_counts
+                    .entry(instantiation.function_name.clone())
+                    .or_insert(0) += 1;
+            }
+            *uses += 1;
+        }
+
+        // Queue each distinct instantiation once. Every module that calls a
+        // generic function reports its own instantiations, so the same
+        // signature typically arrives many times
+        for instantiation in generic_instantiations {
+            let type_args = canonical_type_args(&instantiation.type_args);
+            let signature = instantiation_signature(&instantiation.function_name, &type_args);
+            if !self.queued_signatures.insert(signature) {
+                self.deduplicated_instantiations += 1;
+                continue;
+            }
+            self.add_instantiation_to_levels(instantiation.function_name.clone(), type_args)?;
         }
 
         // Store type information for use during monomorphization
@@ -277,7 +377,10 @@ impl MonomorphizationContext {
         self.monomorphization_start = Some(start_time);
 
         // Reset stats for this monomorphization run
-        self.stats = MonomorphizationStats::default();
+        self.stats = MonomorphizationStats {
+            deduplicated_instantiations: self.deduplicated_instantiations,
+            ..MonomorphizationStats::default()
+        };
 
         // Process dependency levels in order (topological sort)
         for (_level, items) in &self.dependency_levels.clone() {
@@ -331,7 +434,9 @@ impl MonomorphizationContext {
         let mangled_name = self.mangle_function_name_cached(item_name, type_args);
 
         // Check cache first
-        if self.specialized_function_cache.contains_key(&mangled_name) {
+        if self.specialized_function_cache.contains_key(&mangled_name)
+            || self.boxed_instantiations.contains_key(&mangled_name)
+        {
             self.stats.cache_hits += 1;
             return Ok(());
         }
@@ -363,7 +468,12 @@ impl MonomorphizationContext {
     ) -> Result<(), Error> {
         if let Some(generic_function) = self.find_generic_function_in_module(module, function_name)
         {
-            let specialized_function = self.specialize_function(generic_function, type_args)?;
+            if self.should_box(generic_function, type_args) {
+                return self.process_boxed_instantiation(generic_function, type_args);
+            }
+
+            let specialized_function =
+                self.specialize_function(generic_function, type_args, module)?;
             let mangled_name = specialized_function.name.clone();
 
             // Cache the specialized function
@@ -377,6 +487,83 @@ impl MonomorphizationContext {
         Ok(())
     }
 
+    /// Whether an instantiation should use the shared boxed version
+    fn should_box(&self, generic_function: &Function, type_args: &[Type]) -> bool {
+        match generic_function.generic_dispatch {
+            GenericDispatch::Specialize => false,
+            GenericDispatch::Boxed => true,
+            GenericDispatch::Auto => {
+                let policy = &self.boxed_fallback;
+                let name = &generic_function.name;
+                let uses = self
+                    .instantiation_uses
+                    .get(&(name.clone(), type_args.to_vec()))
+                    .copied()
+                    .unwrap_or(0);
+                let instantiations = self.instantiation_counts.get(name).copied().unwrap_or(0);
+
+                policy.enabled
+                    && uses <= policy.max_rare_uses
+                    && instantiations >= policy.min_instantiations
+                    && instruction_count(generic_function) >= policy.min_instructions
+            }
+        }
+    }
+
+    /// Route an instantiation to the boxed version of its function,
+    /// generating that version the first time it is needed
+    fn process_boxed_instantiation(
+        &mut self,
+        generic_function: &Function,
+        type_args: &[Type],
+    ) -> Result<(), Error> {
+        let mangled_name = self.mangle_function_name_cached(&generic_function.name, type_args);
+
+        if !self
+            .boxed_function_cache
+            .contains_key(&generic_function.name)
+        {
+            let boxed_function = self.box_function(generic_function);
+            self.boxed_function_cache
+                .insert(generic_function.name.clone(), boxed_function);
+            self.stats.boxed_functions += 1;
+        }
+
+        let boxed_name = Self::boxed_function_name(&generic_function.name);
+        self.boxed_instantiations.insert(mangled_name, boxed_name);
+        self.stats.boxed_instantiations += 1;
+        self.stats.type_instantiations += 1;
+
+        Ok(())
+    }
+
+    /// Name of the boxed version of a generic function
+    fn boxed_function_name(base_name: &str) -> String {
+        format!("{}__boxed", base_name)
+    }
+
+    /// Erase a generic function's type parameters to boxed values
+    fn box_function(&self, generic_function: &Function) -> Function {
+        let mut boxed = generic_function.clone();
+        boxed.name = Self::boxed_function_name(&generic_function.name);
+        for param in &mut boxed.params {
+            param.ty = erase_type_params(&param.ty);
+        }
+        boxed.return_type = erase_type_params(&boxed.return_type);
+        boxed
+    }
+
+    /// Name of the function that implements an instantiation: its boxed
+    /// version if it was routed to one, otherwise its specialized copy
+    pub fn instantiation_target(&mut self, function_name: &str, type_args: &[Type]) -> String {
+        let type_args = canonical_type_args(type_args);
+        let mangled_name = self.mangle_function_name_cached(function_name, &type_args);
+        self.boxed_instantiations
+            .get(&mangled_name)
+            .cloned()
+            .unwrap_or(mangled_name)
+    }
+
     /// Process struct instantiation with caching
     fn process_struct_instantiation(
         &mut self,
@@ -482,7 +669,11 @@ impl MonomorphizationContext {
     fn mangle_type(&self, type_: &Type) -> String {
         match type_ {
             Type::I32 => "i32".to_string(),
+            Type::I64 => "i64".to_string(),
+            Type::U32 => "u32".to_string(),
+            Type::U64 => "u64".to_string(),
             Type::F32 => "f32".to_string(),
+            Type::F64 => "f64".to_string(),
             Type::Bool => "bool".to_string(),
             Type::String => "string".to_string(),
             Type::Array(elem) => format!("array_{}", self.mangle_type(elem)),
@@ -593,11 +784,64 @@ impl MonomorphizationContext {
         &mut self,
         generic_function: &Function,
         type_args: &[Type],
+        module: &Module,
     ) -> Result<Function, Error> {
-        // This would implement the same logic as the original specialize_function
-        // but with caching and optimizations
         let mut specialized = generic_function.clone();
         specialized.name = self.mangle_function_name_cached(&generic_function.name, type_args);
+
+        // Type arguments are the argument types at the call site, so they
+        // bind the type parameters of the parameters they are passed to
+        let substitutions: HashMap<String, Type> = generic_function
+            .params
+            .iter()
+            .zip(type_args)
+            .filter_map(|(param, arg)| match &param.ty {
+                Type::TypeParam(name) => Some((name.clone(), arg.clone())),
+                _ => None,
+            })
+            .collect();
+        for param in &mut specialized.params {
+            param.ty = substitute_type_params(&param.ty, &substitutions);
+        }
+        specialized.return_type = substitute_type_params(&specialized.return_type, &substitutions);
+
+        // Calls of trait methods through a bound go to the concrete impl
+        let block_ids: Vec<_> = specialized.blocks().keys().copied().collect();
+        for block_id in block_ids {
+            let Some(block) = specialized.get_block_mut(block_id) else {
+                continue;
+            };
+            for (_, inst) in &mut block.instructions {
+                let Instruction::Call { func, ty, .. } = &mut inst.instruction else {
+                    continue;
+                };
+                let Some(trait_method) = module
+                    .get_function(*func)
+                    .and_then(|callee| callee.trait_method.as_ref())
+                else {
+                    continue;
+                };
+                let Some(type_name) = substitutions
+                    .get(&trait_method.type_param)
+                    .and_then(impl_type_name)
+                else {
+                    continue;
+                };
+                let impl_method = format!("{}::{}", type_name, trait_method.method);
+                let impl_id = module.get_function_id(&impl_method).ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::TypeError,
+                        format!(
+                            "Type {} does not implement '{}' required by {}",
+                            type_name, trait_method.method, generic_function.name
+                        ),
+                    )
+                })?;
+                *func = impl_id;
+                *ty = substitute_type_params(ty, &substitutions);
+            }
+        }
+
         Ok(specialized)
     }
 
@@ -653,12 +897,158 @@ impl MonomorphizationContext {
     /// Clear all caches
     pub fn clear_caches(&mut self) {
         self.specialized_function_cache.clear();
+        self.boxed_function_cache.clear();
+        self.boxed_instantiations.clear();
         self.specialized_struct_cache.clear();
         self.specialized_enum_cache.clear();
         self.mangle_cache.clear();
     }
 }
 
+/// Number of instructions in a function, used as a proxy for code size
+fn instruction_count(function: &Function) -> usize {
+    function
+        .blocks()
+        .values()
+        .map(|block| block.instructions.len())
+        .sum()
+}
+
+/// Replace every type parameter in `ty` with the boxed `Unknown` type
+fn erase_type_params(ty: &Type) -> Type {
+    match ty {
+        Type::TypeParam(_) => Type::Unknown,
+        Type::Array(elem) => Type::Array(Box::new(erase_type_params(elem))),
+        Type::Option(inner) => Type::Option(Box::new(erase_type_params(inner))),
+        Type::Future(inner) => Type::Future(Box::new(erase_type_params(inner))),
+        Type::Result { ok, err } => Type::Result {
+            ok: Box::new(erase_type_params(ok)),
+            err: Box::new(erase_type_params(err)),
+        },
+        Type::Function { params, ret } => Type::Function {
+            params: params.iter().map(erase_type_params).collect(),
+            ret: Box::new(erase_type_params(ret)),
+        },
+        Type::Generic { name, args } => Type::Generic {
+            name: name.clone(),
+            args: args.iter().map(erase_type_params).collect(),
+        },
+        Type::Tuple(types) => Type::Tuple(types.iter().map(erase_type_params).collect()),
+        Type::Reference { mutable, inner } => Type::Reference {
+            mutable: *mutable,
+            inner: Box::new(erase_type_params(inner)),
+        },
+        other => other.clone(),
+    }
+}
+
+/// Generic function name and canonical type arguments of an instantiation
+pub type InstantiationSignature = (String, Vec<Type>);
+
+/// Signature identifying an instantiation of a generic function
+///
+/// Two instantiations with the same signature share one specialized copy,
+/// whichever module requested them.
+pub fn instantiation_signature(function_name: &str, type_args: &[Type]) -> InstantiationSignature {
+    (function_name.to_string(), canonical_type_args(type_args))
+}
+
+/// Type arguments with equivalent spellings of a type collapsed
+///
+/// A struct is `Named` in modules that only import it and `Struct` in the
+/// module declaring it; both refer to the same type.
+fn canonical_type_args(type_args: &[Type]) -> Vec<Type> {
+    type_args.iter().map(canonical_type).collect()
+}
+
+fn canonical_type(ty: &Type) -> Type {
+    match ty {
+        Type::Struct { name, .. } => Type::Named(name.clone()),
+        Type::Array(elem) => Type::Array(Box::new(canonical_type(elem))),
+        Type::Option(inner) => Type::Option(Box::new(canonical_type(inner))),
+        Type::Future(inner) => Type::Future(Box::new(canonical_type(inner))),
+        Type::Result { ok, err } => Type::Result {
+            ok: Box::new(canonical_type(ok)),
+            err: Box::new(canonical_type(err)),
+        },
+        Type::Function { params, ret } => Type::Function {
+            params: canonical_type_args(params),
+            ret: Box::new(canonical_type(ret)),
+        },
+        Type::Generic { name, args } => Type::Generic {
+            name: name.clone(),
+            args: canonical_type_args(args),
+        },
+        Type::Tuple(types) => Type::Tuple(canonical_type_args(types)),
+        Type::Reference { mutable, inner } => Type::Reference {
+            mutable: *mutable,
+            inner: Box::new(canonical_type(inner)),
+        },
+        other => other.clone(),
+    }
+}
+
+/// Replace type parameters with the types bound to them
+fn substitute_type_params(ty: &Type, substitutions: &HashMap<String, Type>) -> Type {
+    match ty {
+        Type::TypeParam(name) => substitutions
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| ty.clone()),
+        Type::Array(elem) => Type::Array(Box::new(substitute_type_params(elem, substitutions))),
+        Type::Option(inner) => Type::Option(Box::new(substitute_type_params(inner, substitutions))),
+        Type::Future(inner) => Type::Future(Box::new(substitute_type_params(inner, substitutions))),
+        Type::Result { ok, err } => Type::Result {
+            ok: Box::new(substitute_type_params(ok, substitutions)),
+            err: Box::new(substitute_type_params(err, substitutions)),
+        },
+        Type::Function { params, ret } => Type::Function {
+            params: params
+                .iter()
+                .map(|param| substitute_type_params(param, substitutions))
+                .collect(),
+            ret: Box::new(substitute_type_params(ret, substitutions)),
+        },
+        Type::Generic { name, args } => Type::Generic {
+            name: name.clone(),
+            args: args
+                .iter()
+                .map(|arg| substitute_type_params(arg, substitutions))
+                .collect(),
+        },
+        Type::Tuple(types) => Type::Tuple(
+            types
+                .iter()
+                .map(|ty| substitute_type_params(ty, substitutions))
+                .collect(),
+        ),
+        Type::Reference { mutable, inner } => Type::Reference {
+            mutable: *mutable,
+            inner: Box::new(substitute_type_params(inner, substitutions)),
+        },
+        other => other.clone(),
+    }
+}
+
+/// Name of the impl block type providing the methods of a concrete type
+fn impl_type_name(ty: &Type) -> Option<String> {
+    match ty {
+        Type::I32 => Some("i32".to_string()),
+        Type::I64 => Some("i64".to_string()),
+        Type::U32 => Some("u32".to_string()),
+        Type::U64 => Some("u64".to_string()),
+        Type::F32 => Some("f32".to_string()),
+        Type::F64 => Some("f64".to_string()),
+        Type::Bool => Some("bool".to_string()),
+        Type::String => Some("string".to_string()),
+        Type::Named(name) | Type::Struct { name, .. } | Type::Generic { name, .. } => {
+            Some(name.clone())
+        }
+        Type::Reference { inner, .. } => impl_type_name(inner),
+        _ => None,
+    }
+}
+
 impl Default for MonomorphizationContext {
     fn default() -> Self {
         Self::new()
@@ -735,6 +1125,182 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    fn instantiation(name: &str, type_arg: Type) -> GenericInstantiation {
+        GenericInstantiation {
+            function_name: name.to_string(),
+            type_args: vec![type_arg],
+            span: crate::source::Span::dummy(),
+        }
+    }
+
+    fn module_with_identity(dispatch: GenericDispatch) -> Module {
+        let mut module = Module::new();
+        let id = module.create_function(
+            "id".to_string(),
+            vec![crate::ir::Parameter {
+                name: "x".to_string(),
+                ty: Type::TypeParam("T".to_string()),
+            }],
+            Type::TypeParam("T".to_string()),
+        );
+        module.get_function_mut(id).unwrap().generic_dispatch = dispatch;
+        module
+    }
+
+    #[test]
+    fn test_boxed_attribute_shares_one_version() {
+        let mut module = module_with_identity(GenericDispatch::Boxed);
+        let mut ctx = MonomorphizationContext::new();
+        ctx.initialize_from_semantic_analysis(
+            &[
+                instantiation("id", Type::I32),
+                instantiation("id", Type::String),
+            ],
+            &HashMap::new(),
+        )
+        .unwrap();
+        ctx.monomorphize(&mut module).unwrap();
+
+        assert_eq!(ctx.stats().boxed_functions, 1);
+        assert_eq!(ctx.stats().boxed_instantiations, 2);
+        assert_eq!(ctx.stats().functions_monomorphized, 0);
+        assert_eq!(ctx.instantiation_target("id", &[Type::I32]), "id__boxed");
+
+        let boxed = &ctx.boxed_function_cache["id"];
+        assert_eq!(boxed.params[0].ty, Type::Unknown);
+        assert_eq!(boxed.return_type, Type::Unknown);
+    }
+
+    #[test]
+    fn test_boxed_fallback_heuristics() {
+        let policy = BoxedFallbackPolicy {
+            min_instructions: 0,
+            min_instantiations: 2,
+            ..BoxedFallbackPolicy::enabled()
+        };
+        let mut instantiations = vec![instantiation("id", Type::I32); 3];
+        instantiations.push(instantiation("id", Type::String));
+        instantiations.push(instantiation("id", Type::Bool));
+
+        // Only the instantiations used at a single call site are boxed
+        let mut module = module_with_identity(GenericDispatch::Auto);
+        let mut ctx = MonomorphizationContext::new().with_boxed_fallback(policy.clone());
+        ctx.initialize_from_semantic_analysis(&instantiations, &HashMap::new())
+            .unwrap();
+        ctx.monomorphize(&mut module).unwrap();
+
+        assert_eq!(ctx.stats().functions_monomorphized, 1);
+        assert_eq!(ctx.stats().boxed_instantiations, 2);
+        assert_ne!(ctx.instantiation_target("id", &[Type::I32]), "id__boxed");
+        assert_eq!(ctx.instantiation_target("id", &[Type::Bool]), "id__boxed");
+
+        // `@monomorphize(always)` overrides the heuristics
+        let mut module = module_with_identity(GenericDispatch::Specialize);
+        let mut ctx = MonomorphizationContext::new().with_boxed_fallback(policy);
+        ctx.initialize_from_semantic_analysis(&instantiations, &HashMap::new())
+            .unwrap();
+        ctx.monomorphize(&mut module).unwrap();
+
+        assert_eq!(ctx.stats().functions_monomorphized, 3);
+        assert_eq!(ctx.stats().boxed_instantiations, 0);
+    }
+
+    #[test]
+    fn test_identical_instantiations_are_deduplicated() {
+        let point = Type::Struct {
+            name: "Point".to_string(),
+            fields: vec![("x".to_string(), Type::I32)],
+        };
+        assert_eq!(
+            instantiation_signature("id", &[point.clone()]),
+            instantiation_signature("id", &[Type::Named("Point".to_string())])
+        );
+        assert_ne!(
+            instantiation_signature("id", &[Type::I32]),
+            instantiation_signature("first", &[Type::I32])
+        );
+
+        // Two modules calling `id` with the same type arguments
+        let mut module = module_with_identity(GenericDispatch::Specialize);
+        let mut ctx = MonomorphizationContext::new();
+        ctx.initialize_from_semantic_analysis(
+            &[
+                instantiation("id", Type::I32),
+                instantiation("id", point),
+                instantiation("id", Type::I32),
+                instantiation("id", Type::Named("Point".to_string())),
+            ],
+            &HashMap::new(),
+        )
+        .unwrap();
+        ctx.monomorphize(&mut module).unwrap();
+
+        assert_eq!(ctx.stats().functions_monomorphized, 2);
+        assert_eq!(ctx.stats().deduplicated_instantiations, 2);
+        assert_eq!(ctx.stats().batched_items, 2);
+    }
+
+    #[test]
+    fn test_trait_method_calls_resolve_to_impl() {
+        let mut module = Module::new();
+        let t = Type::TypeParam("T".to_string());
+        let param = |ty: Type| crate::ir::Parameter {
+            name: "self".to_string(),
+            ty,
+        };
+        let stub = module.create_function(
+            "<T as Describe>::describe".to_string(),
+            vec![param(t.clone())],
+            Type::String,
+        );
+        module.get_function_mut(stub).unwrap().trait_method = Some(crate::ir::TraitMethodRef {
+            type_param: "T".to_string(),
+            trait_name: "Describe".to_string(),
+            method: "describe".to_string(),
+        });
+        let impl_method = module.create_function(
+            "i32::describe".to_string(),
+            vec![param(Type::I32)],
+            Type::String,
+        );
+
+        let show = module.create_function(
+            "show".to_string(),
+            vec![crate::ir::Parameter {
+                name: "x".to_string(),
+                ty: t.clone(),
+            }],
+            Type::String,
+        );
+        let function = module.get_function_mut(show).unwrap();
+        let entry = function.create_block("entry".to_string());
+        function.get_block_mut(entry).unwrap().instructions.push((
+            crate::ir::ValueId(0),
+            crate::ir::InstructionWithLocation::new(Instruction::Call {
+                func: stub,
+                args: vec![crate::ir::ValueId(1000)],
+                ty: Type::String,
+            }),
+        ));
+
+        let mut ctx = MonomorphizationContext::new();
+        let specialized = ctx
+            .specialize_function(module.get_function(show).unwrap(), &[Type::I32], &module)
+            .unwrap();
+
+        assert_eq!(specialized.params[0].ty, Type::I32);
+        let calls: Vec<_> = specialized
+            .blocks()
+            .values()
+            .flat_map(|block| &block.instructions)
+            .filter_map(|(_, inst)| match &inst.instruction {
+                Instruction::Call { func, .. } => Some(*func),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls, vec![impl_method]);
+    }
+
     #[test]
     fn test_type_dependency_extraction() {
         let ctx = MonomorphizationContext::new();
@@ -0,0 +1,119 @@
+//! Integer overflow checking for arithmetic operations
+//!
+//! Integer arithmetic either traps on overflow or wraps around in two's
+//! complement. By default overflow traps in debug builds, so it is caught
+//! while developing, and wraps in release builds. Code that relies on a
+//! particular behavior in every build uses the `checked_*`, `wrapping_*`
+//! and `saturating_*` stdlib intrinsics instead.
+
+use crate::ir::BinaryOp;
+use crate::types::Type;
+use cranelift::prelude::*;
+
+/// Overflow behavior of integer arithmetic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowMode {
+    /// Trap in debug builds and wrap in release builds
+    #[default]
+    Debug,
+    /// Always trap on overflow
+    Trap,
+    /// Always wrap around on overflow
+    Wrap,
+}
+
+impl OverflowMode {
+    /// Parse a mode as given on the command line
+    pub fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "debug" => Some(OverflowMode::Debug),
+            "trap" => Some(OverflowMode::Trap),
+            "wrap" => Some(OverflowMode::Wrap),
+            _ => None,
+        }
+    }
+
+    /// Whether overflow traps in a build with the given optimization setting
+    pub fn traps(&self, optimized: bool) -> bool {
+        match self {
+            OverflowMode::Debug => !optimized,
+            OverflowMode::Trap => true,
+            OverflowMode::Wrap => false,
+        }
+    }
+}
+
+/// Emits overflow traps for integer arithmetic
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OverflowChecker {
+    enabled: bool,
+}
+
+impl OverflowChecker {
+    /// Create a new overflow checker
+    pub fn new(enabled: bool) -> Self {
+        OverflowChecker { enabled }
+    }
+
+    /// Check if overflow traps are emitted
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Generate `lhs op rhs`, trapping if the result overflows `ty`
+    ///
+    /// Returns `None` when the operation cannot overflow or is not checked,
+    /// leaving it to the caller to emit the plain instruction. Division
+    /// needs no check here since Cranelift's `sdiv` already traps on
+    /// `MIN / -1`.
+    pub fn build_checked_arith(
+        &self,
+        builder: &mut FunctionBuilder,
+        op: BinaryOp,
+        lhs: Value,
+        rhs: Value,
+        ty: &Type,
+    ) -> Option<Value> {
+        if !self.enabled || !ty.is_integer() {
+            return None;
+        }
+
+        let unsigned = matches!(ty, Type::U32 | Type::U64);
+        let (result, overflowed) = match (op, unsigned) {
+            (BinaryOp::Add, false) => builder.ins().sadd_overflow(lhs, rhs),
+            (BinaryOp::Add, true) => builder.ins().uadd_overflow(lhs, rhs),
+            (BinaryOp::Sub, false) => builder.ins().ssub_overflow(lhs, rhs),
+            (BinaryOp::Sub, true) => builder.ins().usub_overflow(lhs, rhs),
+            (BinaryOp::Mul, false) => builder.ins().smul_overflow(lhs, rhs),
+            (BinaryOp::Mul, true) => builder.ins().umul_overflow(lhs, rhs),
+            _ => return None,
+        };
+        builder.ins().trapnz(overflowed, TrapCode::IntegerOverflow);
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overflow_mode_default() {
+        assert_eq!(OverflowMode::default(), OverflowMode::Debug);
+    }
+
+    #[test]
+    fn test_overflow_mode_traps() {
+        assert!(OverflowMode::Debug.traps(false));
+        assert!(!OverflowMode::Debug.traps(true));
+        assert!(OverflowMode::Trap.traps(true));
+        assert!(!OverflowMode::Wrap.traps(false));
+    }
+
+    #[test]
+    fn test_overflow_mode_parse() {
+        assert_eq!(OverflowMode::parse("trap"), Some(OverflowMode::Trap));
+        assert_eq!(OverflowMode::parse("wrap"), Some(OverflowMode::Wrap));
+        assert_eq!(OverflowMode::parse("saturate"), None);
+    }
+}
@@ -0,0 +1,431 @@
+//! WebAssembly binary format encoding
+//!
+//! Only the parts of the format the backend emits are covered: value types,
+//! function types, imports, a single linear memory, mutable globals,
+//! exports, function bodies and active data segments.
+
+/// WebAssembly value types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValType {
+    I32,
+    F32,
+}
+
+impl ValType {
+    fn byte(self) -> u8 {
+        match self {
+            ValType::I32 => 0x7F,
+            ValType::F32 => 0x7D,
+        }
+    }
+}
+
+/// A function type: parameters and an optional result
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuncType {
+    pub params: Vec<ValType>,
+    pub result: Option<ValType>,
+}
+
+/// Opcodes used by the backend
+pub mod op {
+    pub const UNREACHABLE: u8 = 0x00;
+    pub const BLOCK: u8 = 0x02;
+    pub const LOOP: u8 = 0x03;
+    pub const IF: u8 = 0x04;
+    pub const ELSE: u8 = 0x05;
+    pub const END: u8 = 0x0B;
+    pub const BR: u8 = 0x0C;
+    pub const BR_TABLE: u8 = 0x0E;
+    pub const RETURN: u8 = 0x0F;
+    pub const CALL: u8 = 0x10;
+    pub const DROP: u8 = 0x1A;
+    pub const LOCAL_GET: u8 = 0x20;
+    pub const LOCAL_SET: u8 = 0x21;
+    pub const GLOBAL_GET: u8 = 0x23;
+    pub const GLOBAL_SET: u8 = 0x24;
+    pub const I32_LOAD: u8 = 0x28;
+    pub const F32_LOAD: u8 = 0x2A;
+    pub const I32_STORE: u8 = 0x36;
+    pub const F32_STORE: u8 = 0x38;
+    pub const I32_CONST: u8 = 0x41;
+    pub const F32_CONST: u8 = 0x43;
+    pub const I32_EQZ: u8 = 0x45;
+    pub const I32_EQ: u8 = 0x46;
+    pub const I32_NE: u8 = 0x47;
+    pub const I32_LT_S: u8 = 0x48;
+    pub const I32_GT_S: u8 = 0x4A;
+    pub const I32_LE_S: u8 = 0x4C;
+    pub const I32_GE_S: u8 = 0x4E;
+    pub const F32_EQ: u8 = 0x5B;
+    pub const F32_NE: u8 = 0x5C;
+    pub const F32_LT: u8 = 0x5D;
+    pub const F32_GT: u8 = 0x5E;
+    pub const F32_LE: u8 = 0x5F;
+    pub const F32_GE: u8 = 0x60;
+    pub const I32_ADD: u8 = 0x6A;
+    pub const I32_SUB: u8 = 0x6B;
+    pub const I32_MUL: u8 = 0x6C;
+    pub const I32_DIV_S: u8 = 0x6D;
+    pub const I32_REM_S: u8 = 0x6F;
+    pub const I32_AND: u8 = 0x71;
+    pub const I32_OR: u8 = 0x72;
+    pub const I32_CLZ: u8 = 0x67;
+    pub const I32_CTZ: u8 = 0x68;
+    pub const I32_POPCNT: u8 = 0x69;
+    pub const F32_SQRT: u8 = 0x91;
+    pub const F32_NEG: u8 = 0x8C;
+    pub const F32_ADD: u8 = 0x92;
+    pub const F32_SUB: u8 = 0x93;
+    pub const F32_MUL: u8 = 0x94;
+    pub const F32_DIV: u8 = 0x95;
+    pub const F32_CONVERT_I32_S: u8 = 0xB2;
+    /// Prefix of the non-trapping float to int conversions
+    pub const MISC_PREFIX: u8 = 0xFC;
+    pub const I32_TRUNC_SAT_F32_S: u32 = 0x00;
+
+    /// Block type of a block, loop or if that yields no value
+    pub const EMPTY_BLOCK: u8 = 0x40;
+}
+
+/// Instruction stream of a single function body
+#[derive(Debug, Default, Clone)]
+pub struct CodeBuffer {
+    bytes: Vec<u8>,
+}
+
+impl CodeBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn op(&mut self, opcode: u8) -> &mut Self {
+        self.bytes.push(opcode);
+        self
+    }
+
+    /// Emit an instruction with one unsigned immediate (local, global,
+    /// function or label index)
+    pub fn op_u32(&mut self, opcode: u8, immediate: u32) -> &mut Self {
+        self.bytes.push(opcode);
+        write_u32(&mut self.bytes, immediate);
+        self
+    }
+
+    pub fn i32_const(&mut self, value: i32) -> &mut Self {
+        self.bytes.push(op::I32_CONST);
+        write_i32(&mut self.bytes, value);
+        self
+    }
+
+    pub fn f32_const(&mut self, value: f32) -> &mut Self {
+        self.bytes.push(op::F32_CONST);
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+        self
+    }
+
+    /// Emit a load or store with natural alignment and no offset
+    pub fn memory(&mut self, opcode: u8) -> &mut Self {
+        self.bytes.push(opcode);
+        write_u32(&mut self.bytes, 2); // log2(4)
+        write_u32(&mut self.bytes, 0);
+        self
+    }
+
+    /// Open a `block`, `loop` or `if` that yields no value
+    pub fn open(&mut self, opcode: u8) -> &mut Self {
+        self.bytes.push(opcode);
+        self.bytes.push(op::EMPTY_BLOCK);
+        self
+    }
+
+    pub fn br_table(&mut self, targets: &[u32], default: u32) -> &mut Self {
+        self.bytes.push(op::BR_TABLE);
+        write_u32(&mut self.bytes, targets.len() as u32);
+        for &target in targets {
+            write_u32(&mut self.bytes, target);
+        }
+        write_u32(&mut self.bytes, default);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+}
+
+/// A function body: its extra locals and code
+#[derive(Debug, Clone)]
+pub struct FunctionBody {
+    /// Locals beyond the parameters, in index order
+    pub locals: Vec<ValType>,
+    pub code: CodeBuffer,
+}
+
+/// An exported item
+#[derive(Debug, Clone, PartialEq)]
+pub enum Export {
+    Function { name: String, index: u32 },
+    Memory { name: String },
+}
+
+/// Builder for a complete module
+#[derive(Debug, Default)]
+pub struct ModuleEncoder {
+    types: Vec<FuncType>,
+    /// `(module, field, type index)` of imported functions
+    imports: Vec<(String, String, u32)>,
+    /// Type index of each defined function
+    functions: Vec<u32>,
+    memory_pages: u32,
+    /// Initial values of mutable i32 globals
+    globals: Vec<i32>,
+    exports: Vec<Export>,
+    bodies: Vec<FunctionBody>,
+    /// `(offset, bytes)` of data segments
+    data: Vec<(u32, Vec<u8>)>,
+}
+
+impl ModuleEncoder {
+    pub fn new() -> Self {
+        ModuleEncoder {
+            memory_pages: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Index of a function type, adding it if it is new
+    pub fn type_index(&mut self, ty: FuncType) -> u32 {
+        match self.types.iter().position(|t| *t == ty) {
+            Some(index) => index as u32,
+            None => {
+                self.types.push(ty);
+                (self.types.len() - 1) as u32
+            }
+        }
+    }
+
+    /// Import a function; imports must be added before any function is
+    /// defined since they share the function index space
+    pub fn import_function(&mut self, module: &str, field: &str, ty: FuncType) -> u32 {
+        debug_assert!(self.functions.is_empty());
+        let type_index = self.type_index(ty);
+        self.imports
+            .push((module.to_string(), field.to_string(), type_index));
+        (self.imports.len() - 1) as u32
+    }
+
+    /// Declare a function, returning its index; its body is added later
+    /// with `define_function` in declaration order
+    pub fn declare_function(&mut self, ty: FuncType) -> u32 {
+        let type_index = self.type_index(ty);
+        self.functions.push(type_index);
+        (self.imports.len() + self.functions.len() - 1) as u32
+    }
+
+    pub fn define_function(&mut self, body: FunctionBody) {
+        self.bodies.push(body);
+    }
+
+    pub fn set_memory_pages(&mut self, pages: u32) {
+        self.memory_pages = pages;
+    }
+
+    pub fn add_global(&mut self, initial: i32) -> u32 {
+        self.globals.push(initial);
+        (self.globals.len() - 1) as u32
+    }
+
+    pub fn export(&mut self, export: Export) {
+        self.exports.push(export);
+    }
+
+    pub fn add_data(&mut self, offset: u32, bytes: Vec<u8>) {
+        self.data.push((offset, bytes));
+    }
+
+    /// Encode the module in the binary format
+    pub fn finish(self) -> Vec<u8> {
+        debug_assert_eq!(self.functions.len(), self.bodies.len());
+        let mut out = Vec::new();
+        out.extend_from_slice(b"\0asm");
+        out.extend_from_slice(&1u32.to_le_bytes());
+
+        section(&mut out, 1, self.types.len(), |buf| {
+            for ty in &self.types {
+                buf.push(0x60);
+                write_u32(buf, ty.params.len() as u32);
+                buf.extend(ty.params.iter().map(|p| p.byte()));
+                match ty.result {
+                    Some(result) => {
+                        write_u32(buf, 1);
+                        buf.push(result.byte());
+                    }
+                    None => write_u32(buf, 0),
+                }
+            }
+        });
+
+        section(&mut out, 2, self.imports.len(), |buf| {
+            for (module, field, type_index) in &self.imports {
+                write_name(buf, module);
+                write_name(buf, field);
+                buf.push(0x00);
+                write_u32(buf, *type_index);
+            }
+        });
+
+        section(&mut out, 3, self.functions.len(), |buf| {
+            for type_index in &self.functions {
+                write_u32(buf, *type_index);
+            }
+        });
+
+        section(&mut out, 5, 1, |buf| {
+            buf.push(0x00);
+            write_u32(buf, self.memory_pages);
+        });
+
+        section(&mut out, 6, self.globals.len(), |buf| {
+            for initial in &self.globals {
+                buf.push(ValType::I32.byte());
+                buf.push(0x01);
+                buf.push(op::I32_CONST);
+                write_i32(buf, *initial);
+                buf.push(op::END);
+            }
+        });
+
+        section(&mut out, 7, self.exports.len(), |buf| {
+            for export in &self.exports {
+                match export {
+                    Export::Function { name, index } => {
+                        write_name(buf, name);
+                        buf.push(0x00);
+                        write_u32(buf, *index);
+                    }
+                    Export::Memory { name } => {
+                        write_name(buf, name);
+                        buf.push(0x02);
+                        write_u32(buf, 0);
+                    }
+                }
+            }
+        });
+
+        section(&mut out, 10, self.bodies.len(), |buf| {
+            for body in &self.bodies {
+                let mut function = Vec::new();
+                // Run-length encode the locals
+                let mut groups: Vec<(u32, ValType)> = Vec::new();
+                for &local in &body.locals {
+                    match groups.last_mut() {
+                        Some((count, ty)) if *ty == local => *count += 1,
+                        _ => groups.push((1, local)),
+                    }
+                }
+                write_u32(&mut function, groups.len() as u32);
+                for (count, ty) in groups {
+                    write_u32(&mut function, count);
+                    function.push(ty.byte());
+                }
+                function.extend_from_slice(&body.code.bytes);
+                function.push(op::END);
+
+                write_u32(buf, function.len() as u32);
+                buf.extend_from_slice(&function);
+            }
+        });
+
+        section(&mut out, 11, self.data.len(), |buf| {
+            for (offset, bytes) in &self.data {
+                buf.push(0x00);
+                buf.push(op::I32_CONST);
+                write_i32(buf, *offset as i32);
+                buf.push(op::END);
+                write_u32(buf, bytes.len() as u32);
+                buf.extend_from_slice(bytes);
+            }
+        });
+
+        out
+    }
+}
+
+/// Write a section holding `count` entries, skipping it if there are none
+fn section(out: &mut Vec<u8>, id: u8, count: usize, entries: impl FnOnce(&mut Vec<u8>)) {
+    if count == 0 {
+        return;
+    }
+    let mut contents = Vec::new();
+    write_u32(&mut contents, count as u32);
+    entries(&mut contents);
+
+    out.push(id);
+    write_u32(out, contents.len() as u32);
+    out.extend_from_slice(&contents);
+}
+
+fn write_name(out: &mut Vec<u8>, name: &str) {
+    write_u32(out, name.len() as u32);
+    out.extend_from_slice(name.as_bytes());
+}
+
+/// Unsigned LEB128
+pub fn write_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Signed LEB128
+pub fn write_i32(out: &mut Vec<u8>, mut value: i32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leb128() {
+        let mut out = Vec::new();
+        write_u32(&mut out, 624485);
+        assert_eq!(out, [0xE5, 0x8E, 0x26]);
+
+        out.clear();
+        write_i32(&mut out, -123456);
+        assert_eq!(out, [0xC0, 0xBB, 0x78]);
+
+        out.clear();
+        write_i32(&mut out, 64);
+        assert_eq!(out, [0xC0, 0x00]);
+    }
+
+    #[test]
+    fn test_empty_module_header() {
+        let bytes = ModuleEncoder::new().finish();
+        // Header followed by the memory section only
+        assert_eq!(&bytes[..8], b"\0asm\x01\0\0\0");
+        assert_eq!(&bytes[8..], [5, 3, 1, 0, 1]);
+    }
+}
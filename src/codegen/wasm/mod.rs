@@ -0,0 +1,750 @@
+//! WebAssembly code generation backend
+//!
+//! Translates an IR module into a standalone `wasm32` module that runs in
+//! browsers and serverless runtimes. The module exports its linear memory as
+//! `memory` and every function under its IR name; top-level code is in
+//! `__script_main__`. `print` is imported as `env.print(ptr: i32, len: i32)`
+//! and receives a pointer to UTF-8 bytes in the exported memory.
+//!
+//! # Layout
+//!
+//! - Values are `i32` (integers, booleans and pointers) or `f32`. Each SSA
+//!   value is stored in its own local.
+//! - String constants live in a data segment as a 4-byte little-endian
+//!   length followed by the bytes; a string value points at the length.
+//! - Memory from `Alloc` comes from a shadow stack: a function reserves one
+//!   8-byte slot per `Alloc` on entry and releases the frame on return.
+//! - WebAssembly only has structured control flow, so each function body is
+//!   a loop around a `br_table` that dispatches on the index of the next
+//!   basic block. Phi nodes become copies on the edges that enter a block.
+//!
+//! Instructions for structs, enums, closures and async functions are not
+//! supported yet and are reported as compilation errors.
+
+pub mod encoder;
+
+use super::{CodegenBackend, CodegenResult};
+use crate::error::{Error, ErrorKind};
+use crate::ir::{
+    BasicBlock, BinaryOp, BlockId, ComparisonOp, Constant, Function, FunctionId, Instruction,
+    Intrinsic, Module as IrModule, UnaryOp, ValueId,
+};
+use crate::types::Type;
+use encoder::{op, CodeBuffer, Export, FuncType, FunctionBody, ModuleEncoder, ValType};
+use std::collections::{BTreeMap, HashMap};
+
+/// First address used for data; address 0 stays unused so it can act as null
+const DATA_START: u32 = 8;
+/// Bytes reserved for each `Alloc` slot
+const SLOT_SIZE: u32 = 8;
+/// Size of the shadow stack that follows the data
+const STACK_SIZE: u32 = 64 * 1024;
+const PAGE_SIZE: u32 = 64 * 1024;
+/// Parameters are referred to by `ValueId(PARAM_VALUE_BASE + index)`
+const PARAM_VALUE_BASE: u32 = 1000;
+/// Global holding the shadow stack pointer
+const STACK_POINTER: u32 = 0;
+
+/// Backend producing WebAssembly binaries
+#[derive(Debug, Default)]
+pub struct WasmBackend;
+
+impl WasmBackend {
+    pub fn new() -> Self {
+        WasmBackend
+    }
+}
+
+impl CodegenBackend for WasmBackend {
+    type Output = Vec<u8>;
+
+    fn generate(&mut self, module: &IrModule) -> CodegenResult<Vec<u8>> {
+        let mut functions: Vec<&Function> = module.functions().values().collect();
+        functions.sort_by_key(|f| f.id.0);
+
+        let mut encoder = ModuleEncoder::new();
+
+        // Calls to ids without a definition go to the runtime; the only one
+        // the lowerer emits is `script_print`
+        let print = if functions.iter().any(|f| calls_runtime(f, module)) {
+            Some(encoder.import_function(
+                "env",
+                "print",
+                FuncType {
+                    params: vec![ValType::I32, ValType::I32],
+                    result: None,
+                },
+            ))
+        } else {
+            None
+        };
+
+        let mut signatures = HashMap::new();
+        for function in &functions {
+            let ty = function_type(function);
+            let index = encoder.declare_function(ty.clone());
+            signatures.insert(function.id, (index, ty));
+        }
+
+        let (strings, data_end) = layout_strings(&functions);
+        for (text, offset) in &strings {
+            let mut bytes = (text.len() as u32).to_le_bytes().to_vec();
+            bytes.extend_from_slice(text.as_bytes());
+            encoder.add_data(*offset, bytes);
+        }
+
+        let stack_start = align(data_end, SLOT_SIZE);
+        encoder.add_global(stack_start as i32);
+        encoder.set_memory_pages((stack_start + STACK_SIZE).div_ceil(PAGE_SIZE));
+        encoder.export(Export::Memory {
+            name: "memory".to_string(),
+        });
+
+        for function in &functions {
+            let translator = FunctionTranslator::new(function, &signatures, &strings, print);
+            encoder.define_function(translator.translate()?);
+            encoder.export(Export::Function {
+                name: function.name.clone(),
+                index: signatures[&function.id].0,
+            });
+        }
+
+        Ok(encoder.finish())
+    }
+}
+
+fn unsupported(what: impl std::fmt::Display) -> Error {
+    Error::new(
+        ErrorKind::CompilationError,
+        format!("{} is not supported by the wasm32 backend", what),
+    )
+}
+
+/// The WebAssembly type of a Script type, if it is known
+fn val_type(ty: &Type) -> Option<ValType> {
+    match ty {
+        Type::F32 => Some(ValType::F32),
+        // 64-bit values have no wasm32 representation yet
+        Type::I64 | Type::U64 | Type::F64 => None,
+        Type::Unknown | Type::TypeVar(_) | Type::TypeParam(_) | Type::Never => None,
+        _ => Some(ValType::I32),
+    }
+}
+
+fn function_type(function: &Function) -> FuncType {
+    let params = function
+        .params
+        .iter()
+        .map(|p| val_type(&p.ty).unwrap_or(ValType::I32))
+        .collect();
+    // Functions without an annotated return type only have a result if they
+    // return a value somewhere
+    let result = val_type(&function.return_type).or_else(|| {
+        let returns_value = function.blocks().values().any(|block| {
+            block
+                .instructions
+                .iter()
+                .any(|(_, inst)| matches!(inst.instruction, Instruction::Return(Some(_))))
+        });
+        returns_value.then_some(ValType::I32)
+    });
+    FuncType { params, result }
+}
+
+fn calls_runtime(function: &Function, module: &IrModule) -> bool {
+    function.blocks().values().any(|block| {
+        block
+            .instructions
+            .iter()
+            .any(|(_, inst)| match &inst.instruction {
+                Instruction::Call { func, .. } => !module.has_function(*func),
+                _ => false,
+            })
+    })
+}
+
+/// Assign data addresses to every string constant, returning the end of data
+fn layout_strings(functions: &[&Function]) -> (BTreeMap<String, u32>, u32) {
+    let mut strings = BTreeMap::new();
+    let mut next = DATA_START;
+    for function in functions {
+        for block in ordered_blocks(function) {
+            for (_, inst) in &block.instructions {
+                if let Instruction::Const(Constant::String(text)) = &inst.instruction {
+                    if !strings.contains_key(text) {
+                        strings.insert(text.clone(), next);
+                        next = align(next + 4 + text.len() as u32, 4);
+                    }
+                }
+            }
+        }
+    }
+    (strings, next)
+}
+
+fn align(value: u32, to: u32) -> u32 {
+    value.div_ceil(to) * to
+}
+
+/// Entry block first, then the rest by id
+fn ordered_blocks(function: &Function) -> Vec<&BasicBlock> {
+    let mut blocks: Vec<&BasicBlock> = function.blocks().values().collect();
+    blocks.sort_by_key(|b| (Some(b.id) != function.entry_block, b.id.0));
+    blocks
+}
+
+struct FunctionTranslator<'a> {
+    function: &'a Function,
+    signatures: &'a HashMap<FunctionId, (u32, FuncType)>,
+    strings: &'a BTreeMap<String, u32>,
+    print: Option<u32>,
+    result: Option<ValType>,
+    blocks: Vec<&'a BasicBlock>,
+    block_index: HashMap<BlockId, u32>,
+    value_types: HashMap<ValueId, ValType>,
+    locals: HashMap<ValueId, u32>,
+    extra_locals: Vec<ValType>,
+    /// Local holding the index of the next block to run
+    label_local: u32,
+    /// Local holding the base address of this call's stack frame
+    frame_local: u32,
+    alloc_offsets: HashMap<ValueId, u32>,
+    frame_size: u32,
+    code: CodeBuffer,
+}
+
+impl<'a> FunctionTranslator<'a> {
+    fn new(
+        function: &'a Function,
+        signatures: &'a HashMap<FunctionId, (u32, FuncType)>,
+        strings: &'a BTreeMap<String, u32>,
+        print: Option<u32>,
+    ) -> Self {
+        let blocks = ordered_blocks(function);
+        let block_index = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| (b.id, i as u32))
+            .collect();
+        FunctionTranslator {
+            function,
+            signatures,
+            strings,
+            print,
+            result: signatures[&function.id].1.result,
+            blocks,
+            block_index,
+            value_types: HashMap::new(),
+            locals: HashMap::new(),
+            extra_locals: Vec::new(),
+            label_local: 0,
+            frame_local: 0,
+            alloc_offsets: HashMap::new(),
+            frame_size: 0,
+            code: CodeBuffer::new(),
+        }
+    }
+
+    fn translate(mut self) -> CodegenResult<FunctionBody> {
+        self.infer_value_types();
+        self.allocate_locals();
+
+        if self.frame_size > 0 {
+            self.code
+                .op_u32(op::GLOBAL_GET, STACK_POINTER)
+                .op_u32(op::LOCAL_SET, self.frame_local)
+                .op_u32(op::GLOBAL_GET, STACK_POINTER)
+                .i32_const(self.frame_size as i32)
+                .op(op::I32_ADD)
+                .op_u32(op::GLOBAL_SET, STACK_POINTER);
+        }
+
+        // The entry block has index 0, which is the label local's initial value
+        let count = self.blocks.len() as u32;
+        self.code.open(op::LOOP);
+        for _ in 0..count {
+            self.code.open(op::BLOCK);
+        }
+        let targets: Vec<u32> = (0..count).collect();
+        self.code
+            .op_u32(op::LOCAL_GET, self.label_local)
+            .br_table(&targets, 0);
+        for index in 0..count {
+            self.code.op(op::END);
+            // Blocks still open around this one, excluding the loop
+            let loop_depth = count - 1 - index;
+            self.translate_block(index as usize, loop_depth)?;
+        }
+        self.code.op(op::END).op(op::UNREACHABLE);
+
+        Ok(FunctionBody {
+            locals: self.extra_locals,
+            code: self.code,
+        })
+    }
+
+    fn infer_value_types(&mut self) {
+        for (i, param) in self.function.params.iter().enumerate() {
+            self.value_types.insert(
+                ValueId(PARAM_VALUE_BASE + i as u32),
+                val_type(&param.ty).unwrap_or(ValType::I32),
+            );
+        }
+
+        // A second pass picks up operands defined in blocks visited later
+        for _ in 0..2 {
+            for block in self.blocks.clone() {
+                for (value, inst) in &block.instructions {
+                    if let Some(ty) = self.instruction_type(&inst.instruction) {
+                        self.value_types.insert(*value, ty);
+                    }
+                }
+            }
+        }
+    }
+
+    fn instruction_type(&self, inst: &Instruction) -> Option<ValType> {
+        let known = |value: &ValueId, ty: &Type| {
+            self.value_types
+                .get(value)
+                .copied()
+                .or_else(|| val_type(ty))
+                .unwrap_or(ValType::I32)
+        };
+        Some(match inst {
+            Instruction::Const(Constant::F32(_)) => ValType::F32,
+            Instruction::Const(_) => ValType::I32,
+            Instruction::Binary { lhs, ty, .. } => known(lhs, ty),
+            Instruction::Unary { operand, ty, .. } => known(operand, ty),
+            Instruction::Compare { .. } | Instruction::Alloc { .. } => ValType::I32,
+            Instruction::Cast { to_ty, .. } => val_type(to_ty).unwrap_or(ValType::I32),
+            Instruction::Load { ty, .. } => val_type(ty).unwrap_or(ValType::I32),
+            Instruction::Intrinsic { ty: Some(ty), .. } => val_type(ty).unwrap_or(ValType::I32),
+            Instruction::Call { func, .. } => self
+                .signatures
+                .get(func)
+                .and_then(|(_, ty)| ty.result)
+                .unwrap_or(ValType::I32),
+            Instruction::Phi { incoming, ty } => val_type(ty)
+                .or_else(|| {
+                    incoming
+                        .iter()
+                        .find_map(|(value, _)| self.value_types.get(value).copied())
+                })
+                .unwrap_or(ValType::I32),
+            _ => return None,
+        })
+    }
+
+    fn allocate_locals(&mut self) {
+        let param_count = self.function.params.len() as u32;
+        for i in 0..param_count {
+            self.locals.insert(ValueId(PARAM_VALUE_BASE + i), i);
+        }
+
+        let mut values: Vec<(ValueId, ValType)> = self
+            .value_types
+            .iter()
+            .filter(|(value, _)| !self.locals.contains_key(value))
+            .map(|(value, ty)| (*value, *ty))
+            .collect();
+        values.sort_by_key(|(value, _)| value.0);
+        for (value, ty) in values {
+            self.locals
+                .insert(value, param_count + self.extra_locals.len() as u32);
+            self.extra_locals.push(ty);
+        }
+
+        self.label_local = param_count + self.extra_locals.len() as u32;
+        self.frame_local = self.label_local + 1;
+        self.extra_locals.push(ValType::I32);
+        self.extra_locals.push(ValType::I32);
+
+        for block in &self.blocks {
+            for (value, inst) in &block.instructions {
+                if matches!(inst.instruction, Instruction::Alloc { .. }) {
+                    self.alloc_offsets.insert(*value, self.frame_size);
+                    self.frame_size += SLOT_SIZE;
+                }
+            }
+        }
+    }
+
+    fn local(&self, value: ValueId) -> CodegenResult<u32> {
+        self.locals.get(&value).copied().ok_or_else(|| {
+            Error::new(
+                ErrorKind::CompilationError,
+                format!(
+                    "Value {} used in '{}' has no WebAssembly local",
+                    value, self.function.name
+                ),
+            )
+        })
+    }
+
+    fn value_type(&self, value: ValueId) -> ValType {
+        self.value_types
+            .get(&value)
+            .copied()
+            .unwrap_or(ValType::I32)
+    }
+
+    /// Push a value, converting it to `ty` if needed
+    fn get(&mut self, value: ValueId, ty: ValType) -> CodegenResult<()> {
+        let local = self.local(value)?;
+        self.code.op_u32(op::LOCAL_GET, local);
+        self.convert(self.value_type(value), ty);
+        Ok(())
+    }
+
+    fn set(&mut self, value: ValueId) -> CodegenResult<()> {
+        let local = self.local(value)?;
+        self.code.op_u32(op::LOCAL_SET, local);
+        Ok(())
+    }
+
+    fn convert(&mut self, from: ValType, to: ValType) {
+        match (from, to) {
+            (ValType::I32, ValType::F32) => {
+                self.code.op(op::F32_CONVERT_I32_S);
+            }
+            // Saturates like the Cranelift backend instead of trapping
+            (ValType::F32, ValType::I32) => {
+                self.code.op_u32(op::MISC_PREFIX, op::I32_TRUNC_SAT_F32_S);
+            }
+            _ => {}
+        }
+    }
+
+    fn translate_block(&mut self, index: usize, loop_depth: u32) -> CodegenResult<()> {
+        let block = self.blocks[index];
+        for (value, inst) in &block.instructions {
+            match &inst.instruction {
+                Instruction::Return(returned) => {
+                    match (self.result, returned) {
+                        (Some(ty), Some(returned)) => self.get(*returned, ty)?,
+                        (Some(ValType::I32), None) => {
+                            self.code.i32_const(0);
+                        }
+                        (Some(ValType::F32), None) => {
+                            self.code.f32_const(0.0);
+                        }
+                        (None, _) => {}
+                    }
+                    if self.frame_size > 0 {
+                        self.code
+                            .op_u32(op::LOCAL_GET, self.frame_local)
+                            .op_u32(op::GLOBAL_SET, STACK_POINTER);
+                    }
+                    self.code.op(op::RETURN);
+                    return Ok(());
+                }
+                Instruction::Branch(target) => {
+                    self.jump(block.id, *target)?;
+                    self.code.op_u32(op::BR, loop_depth);
+                    return Ok(());
+                }
+                Instruction::CondBranch {
+                    condition,
+                    then_block,
+                    else_block,
+                } => {
+                    self.get(*condition, ValType::I32)?;
+                    self.code.open(op::IF);
+                    self.jump(block.id, *then_block)?;
+                    self.code.op(op::ELSE);
+                    self.jump(block.id, *else_block)?;
+                    self.code.op(op::END).op_u32(op::BR, loop_depth);
+                    return Ok(());
+                }
+                // Phi values are written by the predecessor's jump
+                Instruction::Phi { .. } => {}
+                inst => self.translate_instruction(*value, inst)?,
+            }
+        }
+
+        // Falling off the end of a block is a lowering bug
+        self.code.op(op::UNREACHABLE);
+        Ok(())
+    }
+
+    /// Copy phi inputs and select `to` as the next block to run
+    fn jump(&mut self, from: BlockId, to: BlockId) -> CodegenResult<()> {
+        let target = self
+            .function
+            .get_block(to)
+            .ok_or_else(|| unsupported(format!("Branch to missing block {}", to)))?;
+
+        // All inputs are read before any phi is written, as phis on the same
+        // edge are evaluated simultaneously
+        let mut phis = Vec::new();
+        for (value, inst) in &target.instructions {
+            if let Instruction::Phi { incoming, .. } = &inst.instruction {
+                if let Some((input, _)) = incoming.iter().find(|(_, block)| *block == from) {
+                    self.get(*input, self.value_type(*value))?;
+                    phis.push(*value);
+                }
+            }
+        }
+        for phi in phis.into_iter().rev() {
+            self.set(phi)?;
+        }
+
+        self.code
+            .i32_const(self.block_index[&to] as i32)
+            .op_u32(op::LOCAL_SET, self.label_local);
+        Ok(())
+    }
+
+    fn translate_instruction(&mut self, value: ValueId, inst: &Instruction) -> CodegenResult<()> {
+        match inst {
+            Instruction::Const(constant) => {
+                match constant {
+                    Constant::I32(n) => self.code.i32_const(*n),
+                    Constant::U32(n) => self.code.i32_const(*n as i32),
+                    Constant::F32(n) => self.code.f32_const(*n),
+                    Constant::I64(_) | Constant::U64(_) | Constant::F64(_) => {
+                        return Err(unsupported(format!("64-bit constant {}", constant)))
+                    }
+                    Constant::Bool(b) => self.code.i32_const(*b as i32),
+                    Constant::String(text) => self.code.i32_const(self.strings[text] as i32),
+                    Constant::Null => self.code.i32_const(0),
+                };
+                self.set(value)
+            }
+            Instruction::Binary {
+                op: bin_op,
+                lhs,
+                rhs,
+                ..
+            } => {
+                let ty = self.value_type(value);
+                self.get(*lhs, ty)?;
+                self.get(*rhs, ty)?;
+                let opcode = match (bin_op, ty) {
+                    (BinaryOp::Add, ValType::I32) => op::I32_ADD,
+                    (BinaryOp::Sub, ValType::I32) => op::I32_SUB,
+                    (BinaryOp::Mul, ValType::I32) => op::I32_MUL,
+                    (BinaryOp::Div, ValType::I32) => op::I32_DIV_S,
+                    (BinaryOp::Mod, ValType::I32) => op::I32_REM_S,
+                    (BinaryOp::And, ValType::I32) => op::I32_AND,
+                    (BinaryOp::Or, ValType::I32) => op::I32_OR,
+                    (BinaryOp::Add, ValType::F32) => op::F32_ADD,
+                    (BinaryOp::Sub, ValType::F32) => op::F32_SUB,
+                    (BinaryOp::Mul, ValType::F32) => op::F32_MUL,
+                    (BinaryOp::Div, ValType::F32) => op::F32_DIV,
+                    (other, ValType::F32) => return Err(unsupported(format!("f32 {}", other))),
+                };
+                self.code.op(opcode);
+                self.set(value)
+            }
+            Instruction::Unary {
+                op: un_op, operand, ..
+            } => {
+                let ty = self.value_type(value);
+                match (un_op, ty) {
+                    (UnaryOp::Neg, ValType::I32) => {
+                        self.code.i32_const(0);
+                        self.get(*operand, ty)?;
+                        self.code.op(op::I32_SUB);
+                    }
+                    (UnaryOp::Neg, ValType::F32) => {
+                        self.get(*operand, ty)?;
+                        self.code.op(op::F32_NEG);
+                    }
+                    (UnaryOp::Not, _) => {
+                        self.get(*operand, ValType::I32)?;
+                        self.code.op(op::I32_EQZ);
+                    }
+                }
+                self.set(value)
+            }
+            Instruction::Compare {
+                op: cmp_op,
+                lhs,
+                rhs,
+            } => {
+                let ty = self.value_type(*lhs);
+                self.get(*lhs, ty)?;
+                self.get(*rhs, ty)?;
+                let opcode = match (cmp_op, ty) {
+                    (ComparisonOp::Eq, ValType::I32) => op::I32_EQ,
+                    (ComparisonOp::Ne, ValType::I32) => op::I32_NE,
+                    (ComparisonOp::Lt, ValType::I32) => op::I32_LT_S,
+                    (ComparisonOp::Le, ValType::I32) => op::I32_LE_S,
+                    (ComparisonOp::Gt, ValType::I32) => op::I32_GT_S,
+                    (ComparisonOp::Ge, ValType::I32) => op::I32_GE_S,
+                    (ComparisonOp::Eq, ValType::F32) => op::F32_EQ,
+                    (ComparisonOp::Ne, ValType::F32) => op::F32_NE,
+                    (ComparisonOp::Lt, ValType::F32) => op::F32_LT,
+                    (ComparisonOp::Le, ValType::F32) => op::F32_LE,
+                    (ComparisonOp::Gt, ValType::F32) => op::F32_GT,
+                    (ComparisonOp::Ge, ValType::F32) => op::F32_GE,
+                };
+                self.code.op(opcode);
+                self.set(value)
+            }
+            Instruction::Cast { value: input, .. } => {
+                self.get(*input, self.value_type(value))?;
+                self.set(value)
+            }
+            Instruction::Call { func, args, .. } => {
+                let Some((index, ty)) = self.signatures.get(func).cloned() else {
+                    return self.translate_print(args);
+                };
+                if args.len() != ty.params.len() {
+                    return Err(Error::new(
+                        ErrorKind::CompilationError,
+                        format!(
+                            "Call in '{}' passes {} arguments to a function taking {}",
+                            self.function.name,
+                            args.len(),
+                            ty.params.len()
+                        ),
+                    ));
+                }
+                for (arg, param) in args.iter().zip(&ty.params) {
+                    self.get(*arg, *param)?;
+                }
+                self.code.op_u32(op::CALL, index);
+                match ty.result {
+                    Some(result) => {
+                        self.convert(result, self.value_type(value));
+                        self.set(value)
+                    }
+                    None => Ok(()),
+                }
+            }
+            Instruction::Alloc { .. } => {
+                let offset = self.alloc_offsets[&value];
+                self.code
+                    .op_u32(op::LOCAL_GET, self.frame_local)
+                    .i32_const(offset as i32)
+                    .op(op::I32_ADD);
+                self.set(value)
+            }
+            Instruction::Load { ptr, .. } => {
+                self.get(*ptr, ValType::I32)?;
+                self.code.memory(match self.value_type(value) {
+                    ValType::I32 => op::I32_LOAD,
+                    ValType::F32 => op::F32_LOAD,
+                });
+                self.set(value)
+            }
+            Instruction::Store { ptr, value: stored } => {
+                let ty = self.value_type(*stored);
+                self.get(*ptr, ValType::I32)?;
+                self.get(*stored, ty)?;
+                self.code.memory(match ty {
+                    ValType::I32 => op::I32_STORE,
+                    ValType::F32 => op::F32_STORE,
+                });
+                Ok(())
+            }
+            Instruction::Intrinsic {
+                intrinsic,
+                args,
+                ty: result,
+            } => {
+                // Atomics and SIMD need wasm proposals this backend does not emit
+                let (opcode, ty) = match intrinsic {
+                    Intrinsic::Popcnt => (op::I32_POPCNT, ValType::I32),
+                    Intrinsic::Clz => (op::I32_CLZ, ValType::I32),
+                    Intrinsic::Ctz => (op::I32_CTZ, ValType::I32),
+                    Intrinsic::Sqrt => (op::F32_SQRT, ValType::F32),
+                    other => return Err(unsupported(format!("Intrinsic '{}'", other))),
+                };
+                if result.as_ref().and_then(val_type) != Some(ty) {
+                    return Err(unsupported(format!("64-bit {}", intrinsic)));
+                }
+                self.get(args[0], ty)?;
+                self.code.op(opcode);
+                self.set(value)
+            }
+            other => Err(unsupported(format!("'{}'", other))),
+        }
+    }
+
+    /// `script_print(string, len)` becomes `env.print(bytes, len)`
+    fn translate_print(&mut self, args: &[ValueId]) -> CodegenResult<()> {
+        let (Some(print), [string, len]) = (self.print, args) else {
+            return Err(unsupported("Calling an undefined runtime function"));
+        };
+        self.get(*string, ValType::I32)?;
+        self.code.i32_const(4).op(op::I32_ADD);
+        self.get(*len, ValType::I32)?;
+        self.code.op_u32(op::CALL, print);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{IrBuilder, Parameter};
+
+    fn generate(module: &IrModule) -> Vec<u8> {
+        WasmBackend::new().generate(module).unwrap()
+    }
+
+    #[test]
+    fn test_generates_valid_header_and_exports() {
+        let mut builder = IrBuilder::new();
+        let params = vec![
+            Parameter {
+                name: "a".to_string(),
+                ty: Type::I32,
+            },
+            Parameter {
+                name: "b".to_string(),
+                ty: Type::I32,
+            },
+        ];
+        builder.create_function("add".to_string(), params, Type::I32);
+        let sum = builder
+            .build_binary(
+                BinaryOp::Add,
+                ValueId(PARAM_VALUE_BASE),
+                ValueId(PARAM_VALUE_BASE + 1),
+                Type::I32,
+            )
+            .unwrap();
+        builder.build_return(Some(sum));
+
+        let bytes = generate(&builder.build());
+        assert_eq!(&bytes[..8], b"\0asm\x01\0\0\0");
+        let contains = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"\x06memory\x02\x00"));
+        assert!(contains(b"\x03add\x00\x00"));
+        // (i32, i32) -> i32
+        assert!(contains(&[0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F]));
+    }
+
+    #[test]
+    fn test_print_is_imported() {
+        let mut builder = IrBuilder::new();
+        builder.create_function("__script_main__".to_string(), vec![], Type::Unknown);
+        let text = builder.const_value(Constant::String("hi".to_string()));
+        let len = builder.const_value(Constant::I32(2));
+        let runtime = builder.module_mut().reserve_function_id();
+        builder.build_call(runtime, vec![text, len], Type::Unknown);
+        builder.build_return(None);
+
+        let bytes = generate(&builder.build());
+        let contains = |needle: &[u8]| bytes.windows(needle.len()).any(|w| w == needle);
+        assert!(contains(b"\x03env\x05print\x00"));
+        // Length-prefixed string in the data segment
+        assert!(contains(b"\x02\x00\x00\x00hi"));
+    }
+
+    #[test]
+    fn test_unsupported_instruction_is_an_error() {
+        let mut builder = IrBuilder::new();
+        builder.create_function("f".to_string(), vec![], Type::Unknown);
+        builder.build_alloc_struct("Point".to_string(), Type::Named("Point".to_string()));
+        builder.build_return(None);
+
+        let error = WasmBackend::new().generate(&builder.build()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::CompilationError);
+    }
+}
@@ -0,0 +1,203 @@
+//! Build profiles
+//!
+//! A profile decides how hard the optimizer works and what a build keeps
+//! for debuggers. `script build` and `manuscript build` use `dev` by
+//! default and `release` with `--release`. A project changes either one
+//! with a `[profile.<name>]` table in its `script.toml`; settings it leaves
+//! out keep their built-in value:
+//!
+//! ```toml
+//! [profile.release]
+//! opt-level = 3
+//! lto = true
+//! debug-info = false
+//! ```
+//!
+//! With `lto`, the modules of an executable are linked before the optimizer
+//! runs rather than after, so functions are inlined across modules and
+//! exported functions the program never calls are removed.
+
+use crate::codegen::debug::DebugFlags;
+use crate::codegen::{
+    eliminate_dead_functions, eliminate_dead_functions_whole_program, DeadFunctionStats,
+};
+use crate::error::Result;
+use crate::ir::optimizer::OptimizationLevel;
+use crate::ir::{link_modules, Module as IrModule};
+use crate::package::PackageManifest;
+use std::fmt;
+
+/// Resolved settings of the profile a build uses
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildProfile {
+    /// `dev` or `release`
+    pub name: &'static str,
+    /// Passes run on the IR before code generation
+    pub opt_level: OptimizationLevel,
+    /// Treat the module as the whole program, so exported functions it
+    /// never calls are removed too
+    pub lto: bool,
+    /// Emit line and variable information
+    pub debug_info: bool,
+}
+
+impl BuildProfile {
+    /// Built-in profile for everyday builds: no optimization, full debug info
+    pub fn dev() -> Self {
+        BuildProfile {
+            name: "dev",
+            opt_level: OptimizationLevel::None,
+            lto: false,
+            debug_info: true,
+        }
+    }
+
+    /// Built-in profile for shipping builds
+    pub fn release() -> Self {
+        BuildProfile {
+            name: "release",
+            opt_level: OptimizationLevel::O2,
+            lto: false,
+            debug_info: false,
+        }
+    }
+
+    /// The built-in profile for a release or dev build
+    pub fn for_mode(release: bool) -> Self {
+        if release {
+            Self::release()
+        } else {
+            Self::dev()
+        }
+    }
+
+    /// The profile for a release or dev build of `manifest`'s package
+    ///
+    /// The manifest has been validated, so its opt-level is in range.
+    pub fn from_manifest(manifest: &PackageManifest, release: bool) -> Self {
+        let mut profile = Self::for_mode(release);
+        let Some(config) = manifest.profile.get(profile.name) else {
+            return profile;
+        };
+        if let Some(level) = config.opt_level.and_then(OptimizationLevel::from_number) {
+            profile.opt_level = level;
+        }
+        if let Some(lto) = config.lto {
+            profile.lto = lto;
+        }
+        if let Some(debug_info) = config.debug_info {
+            profile.debug_info = debug_info;
+        }
+        profile
+    }
+
+    /// Debug flags for the code generator
+    pub fn debug_flags(&self) -> DebugFlags {
+        let optimize = self.opt_level != OptimizationLevel::None;
+        DebugFlags {
+            debug_info: self.debug_info,
+            line_info: self.debug_info,
+            variable_info: self.debug_info,
+            optimize,
+        }
+    }
+
+    /// Run the profile's optimizer pipeline on `module`, then drop the
+    /// functions it no longer reaches
+    pub fn optimize(&self, module: &mut IrModule) -> DeadFunctionStats {
+        self.opt_level.create_pass_manager().optimize(module);
+        if self.lto {
+            eliminate_dead_functions_whole_program(module)
+        } else {
+            eliminate_dead_functions(module)
+        }
+    }
+
+    /// Link separately lowered `modules` into one program and optimize it
+    ///
+    /// With `lto` the modules are linked first, so the optimizer sees across
+    /// them. Otherwise each module is optimized on its own and only dead
+    /// function elimination runs on the linked program.
+    pub fn link_and_optimize(
+        &self,
+        mut modules: Vec<IrModule>,
+    ) -> Result<(IrModule, DeadFunctionStats)> {
+        if self.lto {
+            let mut program = link_modules(modules)?;
+            let stats = self.optimize(&mut program);
+            return Ok((program, stats));
+        }
+
+        for module in &mut modules {
+            self.opt_level.create_pass_manager().optimize(module);
+        }
+        let mut program = link_modules(modules)?;
+        let stats = eliminate_dead_functions(&mut program);
+        Ok((program, stats))
+    }
+}
+
+impl Default for BuildProfile {
+    fn default() -> Self {
+        Self::dev()
+    }
+}
+
+impl fmt::Display for BuildProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [opt-level {}", self.name, self.opt_level.number())?;
+        if self.lto {
+            write!(f, ", lto")?;
+        }
+        if self.debug_info {
+            write!(f, ", debug info")?;
+        }
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_overrides_built_in_profiles() {
+        let manifest = PackageManifest::from_str(
+            r#"
+            [package]
+            name = "game"
+            version = "0.1.0"
+
+            [profile.release]
+            opt-level = 3
+            lto = true
+
+            [profile.dev]
+            debug-info = false
+            "#,
+        )
+        .unwrap();
+
+        let release = BuildProfile::from_manifest(&manifest, true);
+        assert_eq!(release.opt_level, OptimizationLevel::O3);
+        assert!(release.lto);
+        assert!(!release.debug_info);
+        assert_eq!(release.to_string(), "release [opt-level 3, lto]");
+
+        let dev = BuildProfile::from_manifest(&manifest, false);
+        assert_eq!(dev.opt_level, OptimizationLevel::None);
+        assert!(!dev.debug_info);
+        assert!(!dev.debug_flags().line_info);
+
+        for bad in [
+            "[profile.release]\nopt-level = 4",
+            "[profile.fast]\nlto = true",
+        ] {
+            let source = format!(
+                "[package]\nname = \"game\"\nversion = \"0.1.0\"\n\n{}\n",
+                bad
+            );
+            assert!(PackageManifest::from_str(&source).is_err());
+        }
+    }
+}
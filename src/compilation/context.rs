@@ -1,11 +1,21 @@
 use crate::codegen::debug::DebugFlags;
+use crate::codegen::BoxedFallbackPolicy;
+use crate::compilation::hermetic;
+use crate::compilation::plugin::{
+    AnalyzedModule, CompilerPlugin, PluginDiagnostic, PluginRegistry,
+};
 use crate::compilation::resource_limits::{ResourceLimits, ResourceMonitor};
+use crate::compilation::time_passes::{self, Phase};
+use crate::compilation::{BuildProfile, SourceMap};
+use crate::edition::{self, Edition};
 use crate::error::{Error, ErrorKind, Result};
+use crate::index::SymbolIndex;
 use crate::ir::Module as IrModule;
 use crate::lexer::Lexer;
 use crate::lowering::AstLowerer;
+use crate::package::PackageManifest;
 use crate::parser::{Parser, Program};
-use crate::semantic::SymbolTable;
+use crate::semantic::{SemanticWarning, SymbolTable};
 use crate::source::SourceLocation;
 use std::collections::HashMap;
 use std::fs;
@@ -24,11 +34,14 @@ pub struct CompilationUnit {
     pub symbols: Option<SymbolTable>,
     /// Module name (derived from file path)
     pub module_name: String,
+    /// Language edition the source is written for
+    pub edition: Edition,
 }
 
 impl CompilationUnit {
     /// Create a new compilation unit from a file path
     pub fn from_file(path: &Path) -> Result<Self> {
+        hermetic::check_read(path)?;
         let source = fs::read_to_string(path).map_err(|e| {
             Error::new(
                 ErrorKind::FileError,
@@ -48,13 +61,18 @@ impl CompilationUnit {
             ast: None,
             symbols: None,
             module_name,
+            edition: Edition::default(),
         })
     }
 
     /// Parse the source code
     pub fn parse(&mut self) -> Result<()> {
-        let lexer = Lexer::new(&self.source)?;
-        let (tokens, lex_errors) = lexer.scan_tokens();
+        let module = Some(self.module_name.as_str());
+        let (tokens, lex_errors) = time_passes::time(module, Phase::Lex, || {
+            let mut lexer = Lexer::new(&self.source)?;
+            lexer.set_edition(self.edition);
+            Ok::<_, Error>(lexer.scan_tokens())
+        })?;
 
         if !lex_errors.is_empty() {
             // Return the first error for now
@@ -71,8 +89,7 @@ impl CompilationUnit {
         }
 
         let mut parser = Parser::new(tokens);
-        let ast = parser
-            .parse()
+        let ast = time_passes::time(module, Phase::Parse, || parser.parse())
             .map_err(|e| e.with_file_name(self.path.to_string_lossy().to_string()))?;
 
         self.ast = Some(ast);
@@ -100,10 +117,24 @@ pub struct CompilationContext {
     package_root: Option<PathBuf>,
     /// Whether to compile in release mode
     release_mode: bool,
+    /// Optimizer and debug settings of the build
+    build_profile: BuildProfile,
     /// Debug compilation flags
     debug_flags: DebugFlags,
     /// Resource monitor for DoS protection
     resource_monitor: ResourceMonitor,
+    /// Persistent symbol index updated as units are parsed
+    symbol_index: Option<SymbolIndex>,
+    /// Warnings from semantic analysis, with the file they occurred in
+    warnings: Vec<(PathBuf, SemanticWarning)>,
+    /// When rare generic instantiations share a boxed version
+    boxed_fallback: BoxedFallbackPolicy,
+    /// Source spans of the instructions of the last lowered module
+    source_map: Option<SourceMap>,
+    /// Plugins run after parsing, analysis and lowering
+    plugins: PluginRegistry,
+    /// Language edition of the package being compiled
+    edition: Edition,
 }
 
 impl CompilationContext {
@@ -124,8 +155,15 @@ impl CompilationContext {
             closure_captures: HashMap::new(),
             package_root: None,
             release_mode: false,
+            build_profile: BuildProfile::dev(),
             debug_flags: DebugFlags::default(),
             resource_monitor: ResourceMonitor::new(limits),
+            symbol_index: None,
+            warnings: Vec::new(),
+            boxed_fallback: BoxedFallbackPolicy::default(),
+            source_map: None,
+            plugins: PluginRegistry::new(),
+            edition: Edition::default(),
         }
     }
 
@@ -153,6 +191,42 @@ impl CompilationContext {
         } else {
             self.debug_flags = DebugFlags::debug();
         }
+        self.build_profile = BuildProfile::for_mode(release);
+    }
+
+    /// The profile of the build, from the release mode and the manifest
+    pub fn build_profile(&self) -> &BuildProfile {
+        &self.build_profile
+    }
+
+    /// Set when rare generic instantiations are compiled as a shared boxed
+    /// version instead of being specialized
+    pub fn set_boxed_fallback(&mut self, policy: BoxedFallbackPolicy) {
+        self.boxed_fallback = policy;
+    }
+
+    /// Set the language edition every unit is parsed and analyzed with
+    pub fn set_edition(&mut self, edition: Edition) {
+        self.edition = edition;
+    }
+
+    /// Get the language edition
+    pub fn edition(&self) -> Edition {
+        self.edition
+    }
+
+    /// Apply a package's edition and build profile for the current release
+    /// mode, and check its `language_version`, `min_script_version` and
+    /// `platforms` against this compiler
+    pub fn apply_manifest(&mut self, manifest: &PackageManifest) -> Result<()> {
+        if let Some(requirement) = &manifest.package.language_version {
+            edition::check_language_version(requirement)?;
+        }
+        manifest.check_host()?;
+        self.edition = Edition::parse(&manifest.package.edition)?;
+        self.build_profile = BuildProfile::from_manifest(manifest, self.release_mode);
+        self.debug_flags = self.build_profile.debug_flags();
+        Ok(())
     }
 
     /// Set debug flags
@@ -165,6 +239,50 @@ impl CompilationContext {
         &self.debug_flags
     }
 
+    /// Attach a symbol index to be updated incrementally during compilation
+    pub fn set_symbol_index(&mut self, index: SymbolIndex) {
+        self.symbol_index = Some(index);
+    }
+
+    /// Get the attached symbol index
+    pub fn symbol_index(&self) -> Option<&SymbolIndex> {
+        self.symbol_index.as_ref()
+    }
+
+    /// Detach and return the symbol index
+    pub fn take_symbol_index(&mut self) -> Option<SymbolIndex> {
+        self.symbol_index.take()
+    }
+
+    /// Warnings collected while compiling, such as uses of deprecated APIs
+    pub fn warnings(&self) -> &[(PathBuf, SemanticWarning)] {
+        &self.warnings
+    }
+
+    /// Source map of the module produced by the last compilation
+    ///
+    /// It maps spans to IR instructions only; the code generator extends
+    /// its own copy with machine code offsets.
+    pub fn source_map(&self) -> Option<&SourceMap> {
+        self.source_map.as_ref()
+    }
+
+    /// Register a plugin to run on every compiled module
+    pub fn add_plugin(&mut self, plugin: Box<dyn CompilerPlugin>) {
+        self.plugins.register(plugin);
+    }
+
+    /// Load a plugin from a shared library
+    #[cfg(feature = "dynamic-plugins")]
+    pub fn load_plugin(&mut self, path: &Path) -> Result<()> {
+        self.plugins.load(path)
+    }
+
+    /// Lints and errors reported by plugins
+    pub fn plugin_diagnostics(&self) -> &[PluginDiagnostic] {
+        self.plugins.diagnostics()
+    }
+
     /// Compile a single file
     pub fn compile_file(&mut self, path: &Path) -> Result<IrModule> {
         if !path.exists() {
@@ -175,7 +293,9 @@ impl CompilationContext {
         }
 
         let mut unit = CompilationUnit::from_file(path)?;
+        unit.edition = self.edition;
         unit.parse()?;
+        self.plugins.after_parse(&mut unit)?;
 
         let module_name = unit.module_name.clone();
         self.units.insert(module_name.clone(), unit);
@@ -206,7 +326,9 @@ impl CompilationContext {
         // Load and parse all files
         for file_path in script_files {
             let mut unit = CompilationUnit::from_file(&file_path)?;
+            unit.edition = self.edition;
             unit.parse()?;
+            self.plugins.after_parse(&mut unit)?;
 
             let module_name = unit.module_name.clone();
             if self.units.contains_key(&module_name) {
@@ -316,11 +438,28 @@ impl CompilationContext {
         }
     }
 
+    /// Record every parsed unit in the attached symbol index and persist it
+    fn update_symbol_index(&mut self) -> Result<()> {
+        if let Some(index) = self.symbol_index.as_mut() {
+            for unit in self.units.values() {
+                if let Some(ast) = &unit.ast {
+                    index.update_program(&unit.path, &unit.source, ast);
+                }
+            }
+            index.save()?;
+        }
+        Ok(())
+    }
+
     /// Compile all loaded modules
     fn compile_all(&mut self) -> Result<IrModule> {
         // Start resource monitoring for compilation
         self.resource_monitor.start_phase("compilation")?;
 
+        // Keep the symbol index in sync before analysis so queries work even
+        // when later phases fail
+        self.update_symbol_index()?;
+
         // Perform semantic analysis on all modules with resource monitoring
         self.resource_monitor.start_phase("semantic_analysis")?;
         for (i, module_name) in self.compilation_order.clone().iter().enumerate() {
@@ -364,21 +503,36 @@ impl CompilationContext {
 
         let combined_program = Program {
             statements: combined_statements,
+            comments: Vec::new(),
         };
 
-        let mut ir_module = lowerer.lower_program(&combined_program)?;
+        let mut ir_module = time_passes::time(None, Phase::Lowering, || {
+            lowerer.lower_program(&combined_program)
+        })?;
         self.resource_monitor.end_phase("lowering");
 
+        // Spans don't record their file, so debug line tables can only name
+        // the source when a single file is compiled
+        if let [module_name] = self.compilation_order.as_slice() {
+            ir_module.source_file = self
+                .units
+                .get(module_name)
+                .map(|unit| unit.path.to_string_lossy().to_string());
+        }
+
         // Monomorphize generic functions if any exist
         if !self.generic_instantiations.is_empty() || self.has_generic_functions(&ir_module) {
             use crate::codegen::MonomorphizationContext;
 
             self.resource_monitor.start_phase("monomorphization")?;
-            let mut mono_context = MonomorphizationContext::new();
+            let mut mono_context =
+                MonomorphizationContext::new().with_boxed_fallback(self.boxed_fallback.clone());
             mono_context
                 .initialize_from_semantic_analysis(&self.generic_instantiations, &self.type_info);
 
-            mono_context.monomorphize(&mut ir_module)?;
+            time_passes::time(None, Phase::Monomorphization, || {
+                mono_context.monomorphize(&mut ir_module)
+            })?;
             self.resource_monitor.end_phase("monomorphization");
 
             // Report monomorphization statistics
@@ -386,11 +540,23 @@ impl CompilationContext {
             if stats.functions_monomorphized > 0 {
                 println!(
                     "Monomorphized {} generic functions ({} instantiations, {} duplicates avoided)",
-                    stats.functions_monomorphized, stats.type_instantiations, stats.cache_hits
+                    stats.functions_monomorphized,
+                    stats.type_instantiations,
+                    stats.cache_hits + stats.deduplicated_instantiations
+                );
+            }
+            if stats.boxed_instantiations > 0 {
+                println!(
+                    "Compiled {} rarely used instantiations as {} boxed generic functions",
+                    stats.boxed_instantiations, stats.boxed_functions
                 );
             }
         }
 
+        self.plugins.after_lowering(&mut ir_module)?;
+
+        self.source_map = Some(SourceMap::from_ir(&ir_module));
+
         // Complete resource monitoring for compilation
         self.resource_monitor.end_phase("compilation");
 
@@ -457,6 +623,8 @@ impl CompilationContext {
             let mut analyzer =
                 crate::semantic::SemanticAnalyzer::with_symbol_table(self.global_symbols.clone());
 
+            analyzer.set_edition(self.edition);
+
             // Set the current file path for resolving relative imports
             analyzer.set_current_file(Some(file_path.clone()));
 
@@ -476,9 +644,8 @@ impl CompilationContext {
             }
 
             // Analyze the module's AST
-            if let Err(error) = analyzer.analyze_program(ast) {
-                return Err(error);
-            }
+            let module = Some(module_name);
+            time_passes::time(module, Phase::Semantic, || analyzer.analyze_program(ast))?;
 
             // Check for semantic errors
             let errors = analyzer.errors();
@@ -487,16 +654,34 @@ impl CompilationContext {
                 return Err(errors[0].clone().into_error());
             }
 
+            self.warnings.extend(
+                analyzer
+                    .warnings()
+                    .iter()
+                    .map(|warning| (file_path.clone(), warning.clone())),
+            );
+
             // Extract type information and merge with global type info
-            let module_types = analyzer.extract_type_info();
+            let (module_types, module_instantiations, module_captures) =
+                time_passes::time(module, Phase::Inference, || {
+                    (
+                        analyzer.extract_type_info(),
+                        analyzer.generic_instantiations().to_vec(),
+                        analyzer.extract_closure_captures(),
+                    )
+                });
+            self.plugins.after_analysis(&AnalyzedModule {
+                name: module_name,
+                path: &file_path,
+                program: ast,
+                symbols: analyzer.symbol_table(),
+                types: &module_types,
+            })?;
             self.type_info.extend(module_types);
 
-            // Collect generic instantiations from this module
-            let module_instantiations = analyzer.generic_instantiations().to_vec();
+            // Collect generic instantiations and closure captures from this
+            // module
             self.generic_instantiations.extend(module_instantiations);
-
-            // Extract closure captures from this module
-            let module_captures = analyzer.extract_closure_captures();
             self.closure_captures.extend(module_captures);
 
             // Save this module's symbol table for future imports
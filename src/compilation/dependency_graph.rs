@@ -1,4 +1,5 @@
 use crate::error::{Error, ErrorKind, Result};
+use crate::ir::{Instruction, Module as IrModule};
 use crate::parser::{Program, StmtKind};
 use std::collections::{HashMap, HashSet, VecDeque};
 
@@ -109,6 +110,29 @@ impl DependencyGraph {
         self.topological_sort().is_err()
     }
 
+    /// Check whether a module can reach itself through its dependencies
+    pub fn is_in_cycle(&self, module: &str) -> bool {
+        let mut visited = HashSet::new();
+        let mut stack: Vec<&str> = self
+            .dependencies
+            .get(module)
+            .map(|deps| deps.iter().map(String::as_str).collect())
+            .unwrap_or_default();
+
+        while let Some(current) = stack.pop() {
+            if current == module {
+                return true;
+            }
+            if visited.insert(current) {
+                if let Some(deps) = self.dependencies.get(current) {
+                    stack.extend(deps.iter().map(String::as_str));
+                }
+            }
+        }
+
+        false
+    }
+
     /// Find a cycle if one exists (for better error reporting)
     pub fn find_cycle(&self) -> Option<Vec<String>> {
         let mut visited = HashSet::new();
@@ -336,6 +360,33 @@ impl DependencyAnalyzer {
         graph
     }
 
+    /// Build the call graph of an IR module
+    ///
+    /// Each function is a node depending on the functions it calls
+    /// directly. Calls to runtime functions without a definition in the
+    /// module are left out.
+    pub fn build_call_graph(module: &IrModule) -> DependencyGraph {
+        let mut graph = DependencyGraph::new();
+
+        for function in module.functions().values() {
+            graph.add_module(function.name.clone());
+        }
+
+        for function in module.functions().values() {
+            for block in function.blocks().values() {
+                for (_, inst_with_loc) in &block.instructions {
+                    if let Instruction::Call { func, .. } = &inst_with_loc.instruction {
+                        if let Some(callee) = module.get_function(*func) {
+                            graph.add_dependency(function.name.clone(), callee.name.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        graph
+    }
+
     /// Build a complete dependency graph from multiple modules (legacy method)
     pub fn build_graph(modules: &HashMap<String, Program>) -> DependencyGraph {
         let mut graph = DependencyGraph::new();
@@ -397,5 +448,32 @@ mod tests {
 
         let cycle = graph.find_cycle().unwrap();
         assert_eq!(cycle.len(), 3);
+        assert!(graph.is_in_cycle("b"));
+    }
+
+    #[test]
+    fn test_call_graph() {
+        use crate::ir::IrBuilder;
+        use crate::types::Type;
+
+        let mut builder = IrBuilder::new();
+        let helper = builder.create_function("helper".to_string(), vec![], Type::I32);
+        let one = builder.const_value(crate::ir::Constant::I32(1));
+        builder.build_return(Some(one));
+
+        let looping = builder.create_function("looping".to_string(), vec![], Type::I32);
+        let result = builder.build_call(looping, vec![], Type::I32).unwrap();
+        builder.build_return(Some(result));
+
+        builder.create_function("main".to_string(), vec![], Type::I32);
+        let result = builder.build_call(helper, vec![], Type::I32).unwrap();
+        builder.build_call(looping, vec![], Type::I32);
+        builder.build_return(Some(result));
+
+        let graph = DependencyAnalyzer::build_call_graph(&builder.build());
+        assert_eq!(graph.get_dependencies("main").unwrap().len(), 2);
+        assert!(graph.get_dependencies("helper").unwrap().is_empty());
+        assert!(graph.is_in_cycle("looping"));
+        assert!(!graph.is_in_cycle("main"));
     }
 }
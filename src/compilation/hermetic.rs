@@ -0,0 +1,262 @@
+//! Hermetic builds
+//!
+//! A hermetic build may only read its declared inputs: the package's
+//! `script.toml` and `script.lock`, its source directory, its library and
+//! binary entry points, the directories of its dependencies, and whatever
+//! `[build] inputs` lists. Reading anything else fails the build, so its
+//! result depends on nothing a remote build system does not know about and
+//! can be cached by the hash of those inputs.
+//!
+//! The inputs are granted as `FileRead` capabilities of the package's
+//! security context, and the compiler checks that context every time it
+//! reads a source file. Hermetic mode applies to the thread that `start`s
+//! it, which runs the whole compilation.
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::module::{
+    installed_packages_dir, ModuleCapability, ModulePath, ModuleSecurityContext, PackageLocator,
+    TrustLevel,
+};
+use crate::package::PackageManifest;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    static BUILD: RefCell<Option<HermeticBuild>> = const { RefCell::new(None) };
+}
+
+/// The files a hermetic build may read
+#[derive(Debug, Clone)]
+pub struct DeclaredInputs {
+    context: ModuleSecurityContext,
+}
+
+impl DeclaredInputs {
+    /// Inputs of a build with nothing declared yet
+    pub fn new(package_name: &str) -> Self {
+        let module = ModulePath::from_string(package_name.replace('-', "_"))
+            .or_else(|_| ModulePath::from_string("package"))
+            .expect("'package' is a valid module path");
+        Self {
+            context: ModuleSecurityContext::new(module, TrustLevel::Untrusted),
+        }
+    }
+
+    /// The source set, dependencies and `[build] inputs` of the package at
+    /// `root`
+    pub fn for_package(root: &Path, manifest: &PackageManifest) -> Result<Self> {
+        let mut inputs = Self::new(&manifest.package.name);
+        inputs.declare(root.join("script.toml"));
+        inputs.declare(root.join("script.lock"));
+        inputs.declare(root.join("src"));
+        if let Some(lib) = &manifest.lib {
+            inputs.declare(root.join(&lib.path));
+        }
+        for bin in &manifest.bin {
+            inputs.declare(root.join(&bin.path));
+        }
+        if let Some(build) = &manifest.build {
+            for input in &build.inputs {
+                inputs.declare(root.join(input));
+            }
+        }
+
+        let locator = PackageLocator::new(Some(root.to_path_buf()), installed_packages_dir())?;
+        for dir in locator.dependency_dirs()? {
+            inputs.declare(dir);
+        }
+        Ok(inputs)
+    }
+
+    /// The inputs of the package `path` is in, or only `path` and the
+    /// files next to it outside a package
+    pub fn discover(path: &Path) -> Result<Self> {
+        let path = absolute(path);
+        let dir = path.parent().unwrap_or(&path);
+        let Some(root) = dir
+            .ancestors()
+            .find(|dir| dir.join("script.toml").is_file())
+        else {
+            let mut inputs = Self::new("script");
+            inputs.declare(dir);
+            return Ok(inputs);
+        };
+        let manifest = PackageManifest::from_file(root.join("script.toml"))?;
+        Self::for_package(root, &manifest)
+    }
+
+    /// Allow reading `path`, and everything in it if it is a directory
+    pub fn declare(&mut self, path: impl AsRef<Path>) {
+        let capability = ModuleCapability::FileRead(absolute(path.as_ref()));
+        self.context
+            .grant_capability(capability)
+            .expect("untrusted modules may read files");
+    }
+
+    /// Whether `path` is a declared input
+    pub fn allows(&self, path: &Path) -> bool {
+        let capability = ModuleCapability::FileRead(absolute(path));
+        self.context.check_capability(&capability).is_ok()
+    }
+}
+
+/// An absolute path without symlinks, so the same file has one name
+fn absolute(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| {
+        std::env::current_dir()
+            .map(|dir| dir.join(path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    })
+}
+
+#[derive(Debug)]
+struct HermeticBuild {
+    inputs: DeclaredInputs,
+    report: HermeticReport,
+}
+
+/// The files a hermetic build read
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HermeticReport {
+    /// Declared inputs that were read, in the order they were first read
+    pub reads: Vec<PathBuf>,
+    /// Files that were not declared inputs, which the build was refused
+    pub violations: Vec<PathBuf>,
+}
+
+impl HermeticReport {
+    /// The inputs read, or an error naming the undeclared files
+    pub fn into_result(self) -> Result<Vec<PathBuf>> {
+        if self.violations.is_empty() {
+            return Ok(self.reads);
+        }
+        let files: Vec<String> = self
+            .violations
+            .iter()
+            .map(|path| format!("'{}'", path.display()))
+            .collect();
+        Err(Error::new(
+            ErrorKind::SecurityViolation,
+            format!(
+                "Hermetic build read {}, which {} not a declared input",
+                files.join(", "),
+                if files.len() == 1 { "is" } else { "are" }
+            ),
+        )
+        .with_note(
+            "declare it as a dependency, or list it in `inputs` under [build] in script.toml"
+                .to_string(),
+        ))
+    }
+}
+
+/// Only allow the current thread to read `inputs` until `stop`
+pub fn start(inputs: DeclaredInputs) {
+    BUILD.with(|build| {
+        *build.borrow_mut() = Some(HermeticBuild {
+            inputs,
+            report: HermeticReport::default(),
+        });
+    });
+}
+
+/// End hermetic mode and return what the build read
+pub fn stop() -> HermeticReport {
+    BUILD.with(|build| {
+        build
+            .borrow_mut()
+            .take()
+            .map(|build| build.report)
+            .unwrap_or_default()
+    })
+}
+
+/// Whether the current thread is running a hermetic build
+pub fn is_enabled() -> bool {
+    BUILD.with(|build| build.borrow().is_some())
+}
+
+/// Check that the compiler may read `path`
+///
+/// Outside hermetic mode every read is allowed.
+pub fn check_read(path: &Path) -> Result<()> {
+    BUILD.with(|build| {
+        let mut build = build.borrow_mut();
+        let Some(build) = build.as_mut() else {
+            return Ok(());
+        };
+        let path = absolute(path);
+        if build.inputs.allows(&path) {
+            if !build.report.reads.contains(&path) {
+                build.report.reads.push(path);
+            }
+            return Ok(());
+        }
+
+        if !build.report.violations.contains(&path) {
+            build.report.violations.push(path.clone());
+        }
+        Err(Error::new(
+            ErrorKind::SecurityViolation,
+            format!(
+                "Hermetic build cannot read '{}', which is not a declared input",
+                path.display()
+            ),
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_only_declared_inputs_are_read() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path().join("game");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(root.join("vendor/math")).unwrap();
+        fs::create_dir_all(root.join("assets")).unwrap();
+        fs::write(
+            root.join("script.toml"),
+            r#"
+            [package]
+            name = "game"
+            version = "0.1.0"
+
+            [dependencies]
+            math = { path = "vendor/math" }
+
+            [build]
+            inputs = ["assets"]
+            "#,
+        )
+        .unwrap();
+        let main = root.join("src/main.script");
+        let math = root.join("vendor/math/lib.script");
+        let level = root.join("assets/level.txt");
+        let secret = dir.path().join("secret.script");
+        for file in [&main, &math, &level, &secret] {
+            fs::write(file, "").unwrap();
+        }
+
+        assert!(check_read(&secret).is_ok());
+
+        start(DeclaredInputs::discover(&main).unwrap());
+        assert!(is_enabled());
+        for file in [&main, &math, &level, &main] {
+            check_read(file).unwrap();
+        }
+        let error = check_read(&secret).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::SecurityViolation);
+        let report = stop();
+        assert!(!is_enabled());
+
+        assert_eq!(report.reads.len(), 3);
+        assert_eq!(report.violations, vec![secret.canonicalize().unwrap()]);
+        let error = report.into_result().unwrap_err();
+        assert!(error.message.contains("secret.script"));
+    }
+}
@@ -2,17 +2,32 @@
 ///
 /// This module provides the infrastructure for compiling multiple Script files
 /// as a cohesive project, handling module dependencies and cross-module references.
+mod build_profile;
 mod context;
 mod dependency_graph;
+pub mod hermetic;
 pub mod module_loader;
 mod optimized_context;
+mod plugin;
+pub mod remote_cache;
 pub mod resource_limits;
+mod source_map;
+pub mod time_passes;
 
+pub use build_profile::BuildProfile;
 pub use context::{CompilationContext, CompilationUnit};
 pub use dependency_graph::{DependencyAnalyzer, DependencyGraph};
+pub use hermetic::{DeclaredInputs, HermeticReport};
 pub use module_loader::{CompilationModulePath, ModuleLoader};
 pub use optimized_context::{CacheStats, OptimizationConfig, OptimizedCompilationContext};
+pub use plugin::{
+    AnalyzedModule, CompilerPlugin, PluginContext, PluginDiagnostic, PluginRegistry,
+    PluginSeverity, PLUGIN_API_VERSION,
+};
+pub use remote_cache::{ArtifactKey, RemoteCache, RemoteCacheConfig};
 pub use resource_limits::{ResourceLimits, ResourceLimitsBuilder, ResourceMonitor, ResourceStats};
+pub use source_map::{FunctionSourceMap, InstructionMapping, SourceMap, SourceMapping};
+pub use time_passes::{PassReport, Phase};
 
 use crate::error::Result;
 use crate::ir::Module as IrModule;
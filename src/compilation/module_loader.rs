@@ -1,4 +1,5 @@
 use crate::error::{Error, ErrorKind, Result};
+use crate::module::{PackageImport, PackageLocator};
 use std::path::{Path, PathBuf};
 
 /// Represents a module path in Script compilation context
@@ -62,6 +63,11 @@ impl ModuleLoader {
 
     /// Resolve a module import to a file path
     pub fn resolve_module(&self, import_path: &str, from_file: Option<&Path>) -> Result<PathBuf> {
+        // Handle package imports (pkg:json) through the project's manifest
+        if PackageImport::is_package_import(import_path) {
+            return self.resolve_package_import(import_path, from_file);
+        }
+
         // Handle relative imports (starting with . or ..)
         if import_path.starts_with('.') {
             return self.resolve_relative_import(import_path, from_file);
@@ -95,6 +101,22 @@ impl ModuleLoader {
         ))
     }
 
+    /// Resolve a package import for the project `from_file` is in, or the
+    /// current directory is in without a file
+    fn resolve_package_import(
+        &self,
+        import_path: &str,
+        from_file: Option<&Path>,
+    ) -> Result<PathBuf> {
+        let import = PackageImport::parse(import_path)?;
+        let dir = match from_file.and_then(Path::parent) {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let dir = dir.canonicalize().unwrap_or(dir);
+        Ok(PackageLocator::discover(&dir)?.locate(&import)?)
+    }
+
     /// Resolve a relative import
     fn resolve_relative_import(
         &self,
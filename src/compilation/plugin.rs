@@ -0,0 +1,405 @@
+//! Compiler plugins for project-specific passes and lints
+//!
+//! A `CompilerPlugin` is called at three points of a compilation:
+//! - after each file is parsed, with its AST open for rewriting
+//! - after each module passes semantic analysis, with its symbols and types
+//! - after the program is lowered and monomorphized, with the IR module
+//!
+//! Plugins report lints through the `PluginContext` they are given. Warnings
+//! are collected on the compilation context; an error fails the compilation
+//! once the hook that reported it returns.
+//!
+//! Embedding hosts register plugins with `CompilationContext::add_plugin`.
+//! With the `dynamic-plugins` feature, `CompilationContext::load_plugin`
+//! loads one from a shared library that exports it with `declare_plugin!`.
+//! Rust trait objects have no stable ABI, so a plugin library must be built
+//! with the same compiler and version of this crate as the host.
+
+use crate::compilation::CompilationUnit;
+use crate::error::{Error, ErrorKind, Result};
+use crate::ir::Module as IrModule;
+use crate::parser::Program;
+use crate::semantic::SymbolTable;
+use crate::source::Span;
+use crate::types::Type;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Version of the plugin interface, checked when loading a plugin library
+pub const PLUGIN_API_VERSION: u32 = 1;
+
+/// A module that passed semantic analysis
+pub struct AnalyzedModule<'a> {
+    /// Module name, derived from the file name
+    pub name: &'a str,
+    /// File the module was compiled from
+    pub path: &'a Path,
+    pub program: &'a Program,
+    /// Symbols visible in the module, including those it imports
+    pub symbols: &'a SymbolTable,
+    /// Inferred type of each expression, by expression id
+    pub types: &'a HashMap<usize, Type>,
+}
+
+/// Hooks called by the compiler as it processes a program
+///
+/// Every hook does nothing by default.
+pub trait CompilerPlugin {
+    /// Name identifying the plugin in diagnostics
+    fn name(&self) -> &str;
+
+    /// Inspect or rewrite a file's AST before semantic analysis
+    fn after_parse(&mut self, _unit: &mut CompilationUnit, _cx: &mut PluginContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Inspect a module once its types are known
+    fn after_analysis(&mut self, _module: &AnalyzedModule, _cx: &mut PluginContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Inspect or extend the program's IR before optimization and code
+    /// generation
+    fn after_lowering(&mut self, _module: &mut IrModule, _cx: &mut PluginContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Severity of a plugin diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginSeverity {
+    Warning,
+    /// Fails the compilation
+    Error,
+}
+
+/// A lint or error reported by a plugin
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginDiagnostic {
+    /// Name of the reporting plugin
+    pub plugin: String,
+    pub severity: PluginSeverity,
+    pub message: String,
+    /// File the diagnostic applies to; none after lowering
+    pub file: Option<PathBuf>,
+    pub span: Option<Span>,
+}
+
+impl PluginDiagnostic {
+    /// Convert an error-level diagnostic into a compilation error
+    fn into_error(self) -> Error {
+        let mut error = Error::new(
+            ErrorKind::CompilationError,
+            format!("[{}] {}", self.plugin, self.message),
+        );
+        if let Some(span) = self.span {
+            error = error.with_location(span.start);
+        }
+        if let Some(file) = self.file {
+            error = error.with_file_name(file.display().to_string());
+        }
+        error
+    }
+}
+
+impl fmt::Display for PluginDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            PluginSeverity::Warning => "warning",
+            PluginSeverity::Error => "error",
+        };
+        write!(f, "{}[{}]: {}", severity, self.plugin, self.message)?;
+        if let Some(file) = &self.file {
+            write!(f, " at {}", file.display())?;
+            if let Some(span) = &self.span {
+                write!(f, ":{}:{}", span.start.line, span.start.column)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Diagnostics sink handed to a plugin hook
+pub struct PluginContext<'a> {
+    plugin: &'a str,
+    file: Option<&'a Path>,
+    diagnostics: &'a mut Vec<PluginDiagnostic>,
+}
+
+impl PluginContext<'_> {
+    /// File being processed; none after lowering
+    pub fn file(&self) -> Option<&Path> {
+        self.file
+    }
+
+    /// Report a lint that does not stop the compilation
+    pub fn warning(&mut self, message: impl Into<String>, span: Option<Span>) {
+        self.report(PluginSeverity::Warning, message.into(), span);
+    }
+
+    /// Report a problem that fails the compilation
+    pub fn error(&mut self, message: impl Into<String>, span: Option<Span>) {
+        self.report(PluginSeverity::Error, message.into(), span);
+    }
+
+    fn report(&mut self, severity: PluginSeverity, message: String, span: Option<Span>) {
+        self.diagnostics.push(PluginDiagnostic {
+            plugin: self.plugin.to_string(),
+            severity,
+            message,
+            file: self.file.map(Path::to_path_buf),
+            span,
+        });
+    }
+}
+
+/// The plugins of a compilation and the diagnostics they reported
+#[derive(Default)]
+pub struct PluginRegistry {
+    plugins: Vec<Box<dyn CompilerPlugin>>,
+    diagnostics: Vec<PluginDiagnostic>,
+    /// Libraries the loaded plugins' code lives in; declared after
+    /// `plugins` so they are unloaded only once the plugins are dropped
+    #[cfg(feature = "dynamic-plugins")]
+    libraries: Vec<libloading::Library>,
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a plugin; plugins run in registration order
+    pub fn register(&mut self, plugin: Box<dyn CompilerPlugin>) {
+        self.plugins.push(plugin);
+    }
+
+    /// Names of the registered plugins
+    pub fn names(&self) -> Vec<&str> {
+        self.plugins.iter().map(|plugin| plugin.name()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Diagnostics reported so far
+    pub fn diagnostics(&self) -> &[PluginDiagnostic] {
+        &self.diagnostics
+    }
+
+    /// Load a plugin from a shared library exporting it with
+    /// `declare_plugin!`
+    #[cfg(feature = "dynamic-plugins")]
+    pub fn load(&mut self, path: &Path) -> Result<()> {
+        type VersionFn = extern "C" fn() -> u32;
+        type CreateFn = extern "C" fn() -> *mut Box<dyn CompilerPlugin>;
+
+        let load_error = |message: String| {
+            Error::new(
+                ErrorKind::CompilationError,
+                format!("Failed to load plugin '{}': {}", path.display(), message),
+            )
+        };
+
+        // SAFETY: the library is trusted to export the symbols with the
+        // signatures `declare_plugin!` gives them, and is kept loaded for as
+        // long as the plugin it created
+        unsafe {
+            let library = libloading::Library::new(path).map_err(|e| load_error(e.to_string()))?;
+
+            let version = library
+                .get::<VersionFn>(b"script_plugin_api_version\0")
+                .map_err(|e| load_error(e.to_string()))?();
+            if version != PLUGIN_API_VERSION {
+                return Err(load_error(format!(
+                    "built for plugin API version {}, expected {}",
+                    version, PLUGIN_API_VERSION
+                )));
+            }
+
+            let create = library
+                .get::<CreateFn>(b"script_plugin_create\0")
+                .map_err(|e| load_error(e.to_string()))?;
+            let plugin = create();
+            if plugin.is_null() {
+                return Err(load_error("the library returned no plugin".to_string()));
+            }
+
+            self.plugins.push(*Box::from_raw(plugin));
+            self.libraries.push(library);
+        }
+        Ok(())
+    }
+
+    /// Run the `after_parse` hooks on a freshly parsed unit
+    pub fn after_parse(&mut self, unit: &mut CompilationUnit) -> Result<()> {
+        let path = unit.path.clone();
+        self.run(Some(&path), |plugin, cx| plugin.after_parse(unit, cx))
+    }
+
+    /// Run the `after_analysis` hooks on an analyzed module
+    pub fn after_analysis(&mut self, module: &AnalyzedModule) -> Result<()> {
+        self.run(Some(module.path), |plugin, cx| {
+            plugin.after_analysis(module, cx)
+        })
+    }
+
+    /// Run the `after_lowering` hooks on the program's IR
+    pub fn after_lowering(&mut self, module: &mut IrModule) -> Result<()> {
+        self.run(None, |plugin, cx| plugin.after_lowering(module, cx))
+    }
+
+    /// Call `hook` on every plugin, stopping at the first one that fails or
+    /// reports an error
+    fn run<F>(&mut self, file: Option<&Path>, mut hook: F) -> Result<()>
+    where
+        F: FnMut(&mut dyn CompilerPlugin, &mut PluginContext) -> Result<()>,
+    {
+        for plugin in &mut self.plugins {
+            let reported = self.diagnostics.len();
+            let name = plugin.name().to_string();
+            let mut cx = PluginContext {
+                plugin: &name,
+                file,
+                diagnostics: &mut self.diagnostics,
+            };
+            hook(plugin.as_mut(), &mut cx)?;
+
+            if let Some(error) = self.diagnostics[reported..]
+                .iter()
+                .find(|diagnostic| diagnostic.severity == PluginSeverity::Error)
+            {
+                return Err(error.clone().into_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Export a plugin from a shared library for `PluginRegistry::load`
+///
+/// `$constructor` is called to create the plugin when the library is
+/// loaded:
+///
+/// ```ignore
+/// script::declare_plugin!(NamingLint::default);
+/// ```
+#[macro_export]
+macro_rules! declare_plugin {
+    ($constructor:path) => {
+        #[no_mangle]
+        pub extern "C" fn script_plugin_api_version() -> u32 {
+            $crate::compilation::PLUGIN_API_VERSION
+        }
+
+        #[no_mangle]
+        pub extern "C" fn script_plugin_create(
+        ) -> *mut Box<dyn $crate::compilation::CompilerPlugin> {
+            let plugin: Box<dyn $crate::compilation::CompilerPlugin> =
+                Box::new($constructor());
+            Box::into_raw(Box::new(plugin))
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edition::Edition;
+    use crate::parser::StmtKind;
+
+    /// Warns about functions whose names are not snake case, and rejects
+    /// functions named `forbidden`
+    struct NamingLint;
+
+    impl CompilerPlugin for NamingLint {
+        fn name(&self) -> &str {
+            "naming"
+        }
+
+        fn after_parse(
+            &mut self,
+            unit: &mut CompilationUnit,
+            cx: &mut PluginContext,
+        ) -> Result<()> {
+            let Some(program) = &unit.ast else {
+                return Ok(());
+            };
+            for stmt in &program.statements {
+                if let StmtKind::Function { name, .. } = &stmt.kind {
+                    if name == "forbidden" {
+                        cx.error("`forbidden` may not be defined", Some(stmt.span));
+                    } else if name.chars().any(char::is_uppercase) {
+                        cx.warning(format!("`{}` is not snake case", name), Some(stmt.span));
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Adds an empty function to every lowered program
+    struct Generator;
+
+    impl CompilerPlugin for Generator {
+        fn name(&self) -> &str {
+            "generator"
+        }
+
+        fn after_lowering(&mut self, module: &mut IrModule, _cx: &mut PluginContext) -> Result<()> {
+            module.create_function("generated".to_string(), vec![], Type::I32);
+            Ok(())
+        }
+    }
+
+    fn parsed_unit(source: &str) -> CompilationUnit {
+        let mut unit = CompilationUnit {
+            path: PathBuf::from("lints.script"),
+            source: source.to_string(),
+            ast: None,
+            symbols: None,
+            module_name: "lints".to_string(),
+            edition: Edition::default(),
+        };
+        unit.parse().unwrap();
+        unit
+    }
+
+    #[test]
+    fn test_lint_diagnostics() {
+        let mut registry = PluginRegistry::new();
+        registry.register(Box::new(NamingLint));
+        assert_eq!(registry.names(), vec!["naming"]);
+
+        let mut unit = parsed_unit("fn loadLevel() {}\nfn save_level() {}\n");
+        registry.after_parse(&mut unit).unwrap();
+        assert_eq!(registry.diagnostics().len(), 1);
+        let warning = &registry.diagnostics()[0];
+        assert_eq!(warning.severity, PluginSeverity::Warning);
+        assert_eq!(warning.span.unwrap().start.line, 1);
+        assert_eq!(
+            warning.to_string(),
+            "warning[naming]: `loadLevel` is not snake case at lints.script:1:1"
+        );
+
+        let mut unit = parsed_unit("fn forbidden() {}\n");
+        let error = registry.after_parse(&mut unit).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::CompilationError);
+        assert!(error
+            .to_string()
+            .contains("[naming] `forbidden` may not be defined"));
+    }
+
+    #[test]
+    fn test_after_lowering_extends_module() {
+        let mut registry = PluginRegistry::new();
+        registry.register(Box::new(Generator));
+
+        let mut module = IrModule::new();
+        registry.after_lowering(&mut module).unwrap();
+        assert!(module.get_function_by_name("generated").is_some());
+        assert!(registry.diagnostics().is_empty());
+    }
+}
@@ -0,0 +1,229 @@
+//! Remote build cache
+//!
+//! Compiled artifacts are shared through a content-addressed HTTP cache, so
+//! CI and teammates only compile a workspace once per change. The cache has
+//! two tables, served by `manuscript registry serve` or anything speaking
+//! the same protocol:
+//!
+//! ```text
+//! GET/PUT <url>/cache/v1/ac/<key>       SHA-256 digest of the artifact for a key
+//! GET/PUT <url>/cache/v1/cas/<digest>   the artifact, named by its digest
+//! ```
+//!
+//! An [`ArtifactKey`] hashes everything a build depends on: the compiler
+//! version, every source file and every flag. Artifacts are checked against
+//! their digest when they are downloaded, so a bad cache entry is a miss,
+//! never a wrong build. Writes need a bearer token; reads send it too, for
+//! caches that are not public.
+
+use crate::error::{Error, Result};
+use crate::package::{HttpClient, PackageError};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Environment variable holding the token for the remote cache
+pub const TOKEN_ENV: &str = "SCRIPT_REMOTE_CACHE_TOKEN";
+
+/// Hash of the sources and flags of a build
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArtifactKey(String);
+
+impl ArtifactKey {
+    /// Start a key for artifacts of this compiler version
+    pub fn builder() -> ArtifactKeyBuilder {
+        let mut builder = ArtifactKeyBuilder {
+            hasher: Sha256::new(),
+        };
+        builder.add(b'v', "compiler", env!("CARGO_PKG_VERSION").as_bytes());
+        builder
+    }
+
+    /// The key as lowercase hex
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ArtifactKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Builder for an [`ArtifactKey`]
+///
+/// Every entry is tagged and length-prefixed, so different builds never
+/// hash the same bytes. Sources and flags should be added in a stable order.
+pub struct ArtifactKeyBuilder {
+    hasher: Sha256,
+}
+
+impl ArtifactKeyBuilder {
+    /// Add the source file `name` with `contents`
+    pub fn source(mut self, name: &str, contents: &[u8]) -> Self {
+        self.add(b's', name, contents);
+        self
+    }
+
+    /// Add a compiler flag, such as the target or the build profile
+    pub fn flag(mut self, name: &str, value: &str) -> Self {
+        self.add(b'f', name, value.as_bytes());
+        self
+    }
+
+    pub fn finish(self) -> ArtifactKey {
+        ArtifactKey(format!("{:x}", self.hasher.finalize()))
+    }
+
+    fn add(&mut self, tag: u8, name: &str, value: &[u8]) {
+        self.hasher.update([tag]);
+        self.hasher.update((name.len() as u64).to_le_bytes());
+        self.hasher.update(name.as_bytes());
+        self.hasher.update((value.len() as u64).to_le_bytes());
+        self.hasher.update(value);
+    }
+}
+
+/// Where the remote cache is and how to use it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteCacheConfig {
+    pub url: String,
+    pub token: Option<String>,
+    /// Only download artifacts, for builds that should not share theirs
+    pub read_only: bool,
+}
+
+impl RemoteCacheConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into().trim_end_matches('/').to_string(),
+            token: None,
+            read_only: false,
+        }
+    }
+
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+}
+
+/// Client for a remote build cache
+pub struct RemoteCache {
+    config: RemoteCacheConfig,
+    client: HttpClient,
+}
+
+impl RemoteCache {
+    pub fn new(config: RemoteCacheConfig) -> Result<Self> {
+        Ok(Self {
+            config,
+            client: HttpClient::new()?,
+        })
+    }
+
+    pub fn config(&self) -> &RemoteCacheConfig {
+        &self.config
+    }
+
+    /// The artifact built for `key`, or `None` on a miss
+    pub fn get(&self, key: &ArtifactKey) -> Result<Option<Vec<u8>>> {
+        let headers = self.headers();
+        let Some(digest) = self
+            .client
+            .get_if_found(&self.url("ac", key.as_str()), &headers)?
+        else {
+            return Ok(None);
+        };
+        let digest = String::from_utf8_lossy(&digest).trim().to_string();
+        if !is_hash(&digest) {
+            return Ok(None);
+        }
+        let Some(artifact) = self
+            .client
+            .get_if_found(&self.url("cas", &digest), &headers)?
+        else {
+            return Ok(None);
+        };
+        if format!("{:x}", Sha256::digest(&artifact)) != digest {
+            return Err(Error::from(PackageError::Registry(format!(
+                "Remote cache returned an artifact that does not match {}",
+                digest
+            ))));
+        }
+        Ok(Some(artifact))
+    }
+
+    /// Share the artifact built for `key`; does nothing when read-only
+    pub fn put(&self, key: &ArtifactKey, artifact: &[u8]) -> Result<()> {
+        if self.config.read_only {
+            return Ok(());
+        }
+        let headers = self.headers();
+        let digest = format!("{:x}", Sha256::digest(artifact));
+        // The artifact goes first, so a key never names a missing artifact
+        self.client
+            .put_with_headers(&self.url("cas", &digest), artifact, &headers)?;
+        self.client
+            .put_with_headers(&self.url("ac", key.as_str()), digest.as_bytes(), &headers)?;
+        Ok(())
+    }
+
+    fn url(&self, table: &str, id: &str) -> String {
+        format!("{}/cache/v1/{}/{}", self.config.url, table, id)
+    }
+
+    fn headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        if let Some(token) = &self.config.token {
+            headers.insert("Authorization".to_string(), format!("Bearer {}", token));
+        }
+        headers
+    }
+}
+
+fn is_hash(id: &str) -> bool {
+    id.len() == 64
+        && id
+            .chars()
+            .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(source: &str, target: &str) -> ArtifactKey {
+        ArtifactKey::builder()
+            .source("src/main.script", source.as_bytes())
+            .flag("target", target)
+            .finish()
+    }
+
+    #[test]
+    fn test_artifact_key() {
+        let k = key("fn main() {}", "wasm32");
+        assert_eq!(k, key("fn main() {}", "wasm32"));
+        assert!(is_hash(k.as_str()));
+        assert_ne!(k, key("fn main() { 1 }", "wasm32"));
+        assert_ne!(k, key("fn main() {}", "wasm64"));
+
+        // Entries are length-prefixed, so moving bytes between them changes the key
+        let a = ArtifactKey::builder().flag("ab", "c").finish();
+        let b = ArtifactKey::builder().flag("a", "bc").finish();
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_config_trims_url() {
+        let config = RemoteCacheConfig::new("https://cache.example.com/").read_only(true);
+        assert_eq!(config.url, "https://cache.example.com");
+        assert!(config.read_only);
+    }
+}
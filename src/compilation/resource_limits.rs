@@ -336,7 +336,7 @@ impl ResourceMonitor {
 
     /// Get current process memory usage in bytes
     #[cfg(target_os = "linux")]
-    fn get_current_memory_usage() -> usize {
+    pub(crate) fn get_current_memory_usage() -> usize {
         // Try to read from /proc/self/status for Linux
         if let Ok(status) = fs::read_to_string("/proc/self/status") {
             for line in status.lines() {
@@ -354,7 +354,7 @@ impl ResourceMonitor {
 
     /// Get current process memory usage in bytes (fallback for non-Linux)
     #[cfg(not(target_os = "linux"))]
-    fn get_current_memory_usage() -> usize {
+    pub(crate) fn get_current_memory_usage() -> usize {
         // On non-Linux systems, we can't easily get memory usage
         // Return 0 to effectively disable memory checking
         0
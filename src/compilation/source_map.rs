@@ -0,0 +1,288 @@
+//! Mappings between source spans, IR instructions and machine code
+//!
+//! A `SourceMap` is built from a lowered IR module: every instruction that
+//! carries a source span is recorded under its function. When a module is
+//! compiled with debug information, the code generator adds the machine
+//! code ranges each instruction was translated to and, once the code is in
+//! memory, the address of each function. Profilers, debuggers and coverage
+//! tools query a map through the `SourceMapping` trait to attribute runtime
+//! events to `.script` lines.
+
+use crate::ir::{BlockId, Module as IrModule, ValueId};
+use crate::source::Span;
+use std::ops::Range;
+
+/// An IR instruction with the source and machine code it corresponds to
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstructionMapping {
+    /// Value the instruction defines
+    pub value: ValueId,
+    /// Block holding the instruction
+    pub block: BlockId,
+    /// Span of the innermost statement or expression it was lowered from
+    pub span: Span,
+    /// Machine code generated for it, as offsets from the function start
+    pub code: Vec<Range<u32>>,
+}
+
+/// Source mappings of a single function
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSourceMap {
+    pub name: String,
+    /// Span of the function's declaration
+    pub span: Option<Span>,
+    /// Address of the function's machine code, once placed in memory
+    pub address: Option<u64>,
+    /// Size of the function's machine code in bytes; zero until compiled
+    pub code_size: u32,
+    /// Instructions with a source span, in block layout order
+    pub instructions: Vec<InstructionMapping>,
+}
+
+impl FunctionSourceMap {
+    /// Whether `address` lies within the function's machine code
+    fn contains_address(&self, address: u64) -> bool {
+        self.address
+            .is_some_and(|start| address >= start && address < start + u64::from(self.code_size))
+    }
+}
+
+/// Source map of a compiled module
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SourceMap {
+    /// File the module was compiled from, if it came from a single file
+    source_file: Option<String>,
+    /// Functions sorted by name
+    functions: Vec<FunctionSourceMap>,
+}
+
+impl SourceMap {
+    /// Record the source span of every instruction of a lowered module
+    pub fn from_ir(module: &IrModule) -> Self {
+        let mut functions: Vec<FunctionSourceMap> = module
+            .functions()
+            .values()
+            .map(|func| FunctionSourceMap {
+                name: func.name.clone(),
+                span: func.span,
+                address: None,
+                code_size: 0,
+                instructions: func
+                    .blocks_in_order()
+                    .into_iter()
+                    .flat_map(|block| {
+                        block.instructions.iter().filter_map(|(value, inst)| {
+                            inst.source_location.map(|span| InstructionMapping {
+                                value: *value,
+                                block: block.id,
+                                span,
+                                code: Vec::new(),
+                            })
+                        })
+                    })
+                    .collect(),
+            })
+            .collect();
+        functions.sort_by(|a, b| a.name.cmp(&b.name));
+
+        SourceMap {
+            source_file: module.source_file.clone(),
+            functions,
+        }
+    }
+
+    /// All functions of the module
+    pub fn functions(&self) -> &[FunctionSourceMap] {
+        &self.functions
+    }
+
+    /// Look up a function by name
+    pub fn function(&self, name: &str) -> Option<&FunctionSourceMap> {
+        self.functions
+            .binary_search_by(|function| function.name.as_str().cmp(name))
+            .ok()
+            .map(|index| &self.functions[index])
+    }
+
+    fn function_mut(&mut self, name: &str) -> Option<&mut FunctionSourceMap> {
+        self.functions
+            .binary_search_by(|function| function.name.as_str().cmp(name))
+            .ok()
+            .map(|index| &mut self.functions[index])
+    }
+
+    /// Record the machine code a function was compiled to
+    ///
+    /// `ranges` gives the code offsets translated from each IR instruction;
+    /// ranges of instructions without a source span are ignored.
+    pub fn set_machine_code(
+        &mut self,
+        function: &str,
+        code_size: u32,
+        ranges: impl IntoIterator<Item = (ValueId, Range<u32>)>,
+    ) {
+        let Some(function) = self.function_mut(function) else {
+            return;
+        };
+        function.code_size = code_size;
+        for instruction in &mut function.instructions {
+            instruction.code.clear();
+        }
+        for (value, range) in ranges {
+            if let Some(instruction) = function
+                .instructions
+                .iter_mut()
+                .find(|instruction| instruction.value == value)
+            {
+                instruction.code.push(range);
+            }
+        }
+    }
+
+    /// Record the address a function was placed at
+    pub fn set_function_address(&mut self, function: &str, address: u64) {
+        if let Some(function) = self.function_mut(function) {
+            function.address = Some(address);
+        }
+    }
+}
+
+/// Queries attributing IR instructions and machine code to source lines
+///
+/// Lines are 1-based; an instruction belongs to the line its span starts on.
+pub trait SourceMapping {
+    /// File the mapped code was compiled from, if known
+    fn source_file(&self) -> Option<&str>;
+
+    /// Span the IR instruction defining `value` in `function` came from
+    fn instruction_span(&self, function: &str, value: ValueId) -> Option<Span>;
+
+    /// IR instructions lowered from `line`, as `(function, value)` pairs
+    fn instructions_at_line(&self, line: usize) -> Vec<(&str, ValueId)>;
+
+    /// Function and span of the machine code at `address`
+    fn span_at_address(&self, address: u64) -> Option<(&str, Span)>;
+
+    /// Machine code address ranges generated from `line`
+    fn addresses_at_line(&self, line: usize) -> Vec<Range<u64>>;
+}
+
+impl SourceMapping for SourceMap {
+    fn source_file(&self) -> Option<&str> {
+        self.source_file.as_deref()
+    }
+
+    fn instruction_span(&self, function: &str, value: ValueId) -> Option<Span> {
+        self.function(function)?
+            .instructions
+            .iter()
+            .find(|instruction| instruction.value == value)
+            .map(|instruction| instruction.span)
+    }
+
+    fn instructions_at_line(&self, line: usize) -> Vec<(&str, ValueId)> {
+        self.functions
+            .iter()
+            .flat_map(|function| {
+                function
+                    .instructions
+                    .iter()
+                    .filter(move |instruction| instruction.span.start.line == line)
+                    .map(move |instruction| (function.name.as_str(), instruction.value))
+            })
+            .collect()
+    }
+
+    fn span_at_address(&self, address: u64) -> Option<(&str, Span)> {
+        let function = self
+            .functions
+            .iter()
+            .find(|function| function.contains_address(address))?;
+        let offset = address - function.address?;
+        function
+            .instructions
+            .iter()
+            .find(|instruction| {
+                instruction
+                    .code
+                    .iter()
+                    .any(|range| u64::from(range.start) <= offset && offset < u64::from(range.end))
+            })
+            .map(|instruction| (function.name.as_str(), instruction.span))
+    }
+
+    fn addresses_at_line(&self, line: usize) -> Vec<Range<u64>> {
+        let mut ranges: Vec<Range<u64>> = self
+            .functions
+            .iter()
+            .filter_map(|function| function.address.map(|address| (function, address)))
+            .flat_map(|(function, address)| {
+                function
+                    .instructions
+                    .iter()
+                    .filter(move |instruction| instruction.span.start.line == line)
+                    .flat_map(move |instruction| {
+                        instruction.code.iter().map(move |range| {
+                            address + u64::from(range.start)..address + u64::from(range.end)
+                        })
+                    })
+            })
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Constant, IrBuilder};
+    use crate::source::SourceLocation;
+    use crate::types::Type;
+
+    fn line_span(line: usize) -> Span {
+        Span::new(
+            SourceLocation::new(line, 5, 0),
+            SourceLocation::new(line, 10, 0),
+        )
+    }
+
+    fn test_map() -> (SourceMap, ValueId, ValueId) {
+        let mut builder = IrBuilder::new();
+        builder.create_function("main".to_string(), vec![], Type::I32);
+
+        builder.set_location(Some(line_span(2)));
+        let first = builder.const_value(Constant::I32(1));
+        builder.set_location(Some(line_span(3)));
+        let second = builder.const_value(Constant::I32(2));
+        builder.set_location(None);
+        builder.build_return(Some(second));
+
+        (SourceMap::from_ir(&builder.build()), first, second)
+    }
+
+    #[test]
+    fn test_map_from_ir() {
+        let (map, first, second) = test_map();
+
+        assert_eq!(map.functions().len(), 1);
+        assert_eq!(map.function("main").unwrap().instructions.len(), 2);
+        assert_eq!(map.instruction_span("main", first), Some(line_span(2)));
+        assert_eq!(map.instructions_at_line(3), vec![("main", second)]);
+        assert!(map.instructions_at_line(4).is_empty());
+    }
+
+    #[test]
+    fn test_machine_code_mapping() {
+        let (mut map, first, second) = test_map();
+
+        // Unplaced code has no addresses
+        map.set_machine_code("main", 16, vec![(first, 0..4), (second, 4..12)]);
+        assert!(map.span_at_address(0x2004).is_none());
+
+        map.set_function_address("main", 0x2000);
+        assert_eq!(map.span_at_address(0x2006), Some(("main", line_span(3))));
+        assert!(map.span_at_address(0x200c).is_none());
+        assert_eq!(map.addresses_at_line(2), vec![0x2000..0x2004]);
+    }
+}
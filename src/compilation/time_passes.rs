@@ -0,0 +1,238 @@
+//! Compiler self-profiling
+//!
+//! With `--time-passes`, the compiler measures every phase it runs: lexing,
+//! parsing, semantic analysis and inference for each module, and lowering,
+//! monomorphization, optimization and code generation. Tracking starts with
+//! `start`; `stop` ends it and returns a `PassReport` with the wall time of
+//! each phase and the resident memory of the compiler after it.
+//!
+//! Type inference happens during semantic analysis, so the inference phase
+//! only covers collecting the inferred types for lowering. Memory is only
+//! measured on Linux and reads as zero elsewhere.
+
+use crate::compilation::ResourceMonitor;
+use serde::Serialize;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static TIMINGS: Mutex<Option<Vec<PhaseTiming>>> = Mutex::new(None);
+
+fn timings() -> MutexGuard<'static, Option<Vec<PhaseTiming>>> {
+    TIMINGS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A phase of compilation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Phase {
+    Lex,
+    Parse,
+    Semantic,
+    Inference,
+    Lowering,
+    Monomorphization,
+    Opt,
+    Codegen,
+}
+
+impl Phase {
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Lex => "lex",
+            Phase::Parse => "parse",
+            Phase::Semantic => "semantic",
+            Phase::Inference => "inference",
+            Phase::Lowering => "lowering",
+            Phase::Monomorphization => "monomorphization",
+            Phase::Opt => "opt",
+            Phase::Codegen => "codegen",
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// One run of a phase
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PhaseTiming {
+    /// Module the phase ran on, `None` for phases run on the whole program
+    pub module: Option<String>,
+    pub phase: Phase,
+    #[serde(rename = "seconds", serialize_with = "as_seconds")]
+    pub duration: Duration,
+    /// Resident memory of the compiler after the phase, in bytes
+    pub memory: usize,
+    /// How much the phase changed resident memory, in bytes
+    pub memory_delta: i64,
+}
+
+fn as_seconds<S: serde::Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// Clear earlier timings and start timing phases
+pub fn start() {
+    *timings() = Some(Vec::new());
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Stop timing and return the phases timed since `start`
+pub fn stop() -> PassReport {
+    ENABLED.store(false, Ordering::SeqCst);
+    PassReport {
+        phases: timings().take().unwrap_or_default(),
+    }
+}
+
+/// Whether phases are being timed
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Run `f` as `phase` of `module`, or of the whole program without one
+pub fn time<T>(module: Option<&str>, phase: Phase, f: impl FnOnce() -> T) -> T {
+    if !is_enabled() {
+        return f();
+    }
+
+    let memory_before = ResourceMonitor::get_current_memory_usage();
+    let started = Instant::now();
+    let result = f();
+    let duration = started.elapsed();
+    let memory = ResourceMonitor::get_current_memory_usage();
+
+    if let Some(timings) = timings().as_mut() {
+        timings.push(PhaseTiming {
+            module: module.map(str::to_string),
+            phase,
+            duration,
+            memory,
+            memory_delta: memory as i64 - memory_before as i64,
+        });
+    }
+    result
+}
+
+/// Phases timed between `start` and `stop`, in the order they ran
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PassReport {
+    pub phases: Vec<PhaseTiming>,
+}
+
+impl PassReport {
+    /// Wall time of all phases
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|timing| timing.duration).sum()
+    }
+
+    /// Wall time of `phase` over all modules
+    pub fn phase_total(&self, phase: Phase) -> Duration {
+        self.phases
+            .iter()
+            .filter(|timing| timing.phase == phase)
+            .map(|timing| timing.duration)
+            .sum()
+    }
+
+    /// Highest resident memory after any phase, in bytes
+    pub fn peak_memory(&self) -> usize {
+        self.phases
+            .iter()
+            .map(|timing| timing.memory)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The report as a single line of JSON
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("pass reports always serialize")
+    }
+}
+
+fn megabytes(bytes: i64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+impl fmt::Display for PassReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total();
+        writeln!(
+            f,
+            "Time passes: {:.3}ms, peak memory {:.1} MB",
+            total.as_secs_f64() * 1000.0,
+            megabytes(self.peak_memory() as i64)
+        )?;
+        writeln!(
+            f,
+            "{:<20} {:<17} {:>11} {:>6} {:>11} {:>11}",
+            "Module", "Phase", "Time", "Time%", "Memory", "Change"
+        )?;
+        for timing in &self.phases {
+            let percent = if total.is_zero() {
+                0.0
+            } else {
+                100.0 * timing.duration.as_secs_f64() / total.as_secs_f64()
+            };
+            writeln!(
+                f,
+                "{:<20} {:<17} {:>9.3}ms {:>5.1}% {:>8.1} MB {:>+8.1} MB",
+                timing.module.as_deref().unwrap_or("(program)"),
+                timing.phase,
+                timing.duration.as_secs_f64() * 1000.0,
+                percent,
+                megabytes(timing.memory as i64),
+                megabytes(timing.memory_delta)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_times_phases_between_start_and_stop() {
+        assert_eq!(time(Some("timed"), Phase::Lex, || 1), 1);
+
+        start();
+        let tokens = time(Some("timed"), Phase::Lex, || vec!["let", "x"]);
+        time(Some("timed"), Phase::Parse, || tokens.len());
+        time(None, Phase::Codegen, || ());
+        let report = stop();
+        time(Some("timed"), Phase::Opt, || ());
+
+        // Other tests may compile while this one times
+        let phases: Vec<Phase> = report
+            .phases
+            .iter()
+            .filter(|timing| timing.module.as_deref() == Some("timed"))
+            .map(|timing| timing.phase)
+            .collect();
+        assert_eq!(phases, vec![Phase::Lex, Phase::Parse]);
+        assert!(report
+            .phases
+            .iter()
+            .any(|timing| timing.module.is_none() && timing.phase == Phase::Codegen));
+        assert!(report.total() >= report.phase_total(Phase::Parse));
+
+        let text = report.to_string();
+        assert!(text.contains("(program)"));
+        assert!(text.contains("timed"));
+        let json: serde_json::Value = serde_json::from_str(&report.to_json_line()).unwrap();
+        let first = &json["phases"][0];
+        assert!(first["seconds"].is_f64());
+        assert!(first["memory_delta"].is_i64());
+    }
+}
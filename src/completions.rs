@@ -0,0 +1,149 @@
+//! Shell completion for the `script` and `manuscript` command lines
+//!
+//! `script completions <shell>` prints a small registration script. When the
+//! shell asks for completions it runs the binary again with `COMPLETE=<shell>`
+//! set, and [`complete_from_env`] answers from the clap definitions before
+//! any other argument handling happens. Values that depend on the
+//! filesystem, `.script` files and the `[scripts]` of the nearest
+//! `script.toml`, are completed by the functions below.
+
+use crate::manuscript::find_package_root;
+use crate::package::PackageManifest;
+use clap::ValueEnum;
+use clap_complete::env::Shells;
+use clap_complete::{ArgValueCompleter, CompleteEnv, CompletionCandidate, PathCompleter};
+use std::ffi::OsStr;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Environment variable the registration scripts set when asking for
+/// completions
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Shells that completion scripts can be generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl Shell {
+    pub fn name(self) -> &'static str {
+        match self {
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+            Shell::Powershell => "powershell",
+        }
+    }
+}
+
+/// Write the script that registers completions for `bin` with `shell`
+///
+/// The script calls `bin` through `PATH`, so it keeps working after the
+/// binary is upgraded as long as both come from the same release.
+pub fn write_registration(shell: Shell, bin: &str, out: &mut dyn Write) -> io::Result<()> {
+    let shells = Shells::builtins();
+    let completer = shells.completer(shell.name()).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("No completion support for {}", shell.name()),
+        )
+    })?;
+    completer.write_registration(COMPLETE_VAR, bin, bin, bin, out)
+}
+
+/// Answer a completion request from a registration script and exit
+///
+/// Returns without doing anything on a normal run, so it must be called
+/// before the program writes to stdout.
+pub fn complete_from_env(command: impl Fn() -> clap::Command) {
+    CompleteEnv::with_factory(command)
+        .var(COMPLETE_VAR)
+        .complete();
+}
+
+/// Complete `.script` files and the directories that may contain them
+pub fn script_files() -> ArgValueCompleter {
+    ArgValueCompleter::new(
+        PathCompleter::any()
+            .filter(|path| path.is_dir() || path.extension().is_some_and(|ext| ext == "script")),
+    )
+}
+
+/// Complete the names of the `[scripts]` in the nearest `script.toml`
+pub fn manifest_scripts(current: &OsStr) -> Vec<CompletionCandidate> {
+    match std::env::current_dir() {
+        Ok(dir) => scripts_in(&dir, &current.to_string_lossy()),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn scripts_in(dir: &Path, prefix: &str) -> Vec<CompletionCandidate> {
+    let Some(root) = find_package_root(Some(dir)) else {
+        return Vec::new();
+    };
+    let Ok(manifest) = PackageManifest::from_file(root.join("script.toml")) else {
+        return Vec::new();
+    };
+
+    let mut scripts: Vec<_> = manifest
+        .scripts
+        .iter()
+        .filter(|(name, _)| name.starts_with(prefix))
+        .collect();
+    scripts.sort_by(|a, b| a.0.cmp(b.0));
+    scripts
+        .into_iter()
+        .map(|(name, spec)| {
+            let help = spec.description().unwrap_or(spec.command()).to_string();
+            CompletionCandidate::new(name).help(Some(help.into()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registration_names_the_binary() {
+        for shell in Shell::value_variants() {
+            let mut out = Vec::new();
+            write_registration(*shell, "manuscript", &mut out).unwrap();
+            let script = String::from_utf8(out).unwrap();
+            assert!(script.contains("manuscript"), "{}", shell.name());
+            assert!(script.contains(COMPLETE_VAR), "{}", shell.name());
+        }
+    }
+
+    #[test]
+    fn test_manifest_script_names() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("script.toml"),
+            "[package]\nname = \"demo\"\nversion = \"0.1.0\"\n\n\
+             [scripts]\ntest = \"script tests/all.script\"\n\n\
+             [scripts.serve]\ncommand = \"script src/server.script\"\n\
+             description = \"Start the server\"\n\n\
+             [scripts.setup]\ncommand = \"script tools/setup.script\"\n",
+        )
+        .unwrap();
+        let nested = dir.path().join("src");
+        std::fs::create_dir(&nested).unwrap();
+
+        let candidates = scripts_in(&nested, "se");
+        let names: Vec<_> = candidates
+            .iter()
+            .map(|candidate| candidate.get_value().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["serve", "setup"]);
+        assert_eq!(
+            candidates[0].get_help().map(|help| help.to_string()),
+            Some("Start the server".to_string())
+        );
+        assert_eq!(scripts_in(&nested, "").len(), 3);
+    }
+}
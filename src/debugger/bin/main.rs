@@ -1,39 +1,557 @@
 //! Standalone Debugger for Script Language
 //!
 //! This binary provides debugging capabilities for Script programs.
+//! The program is checked and lowered exactly like `script <file>` so that
+//! compile errors are reported up front, then executed statement by
+//! statement under the control of the global `Debugger`.
 
+use colored::*;
+use script::debugger::{
+    get_debugger, initialize_debugger_with, BreakpointCondition, DapServer, DebugInterpreter,
+    Debugger, PauseReason, PauseState, StepAction, StepController, DEFAULT_RECORDING_CAPACITY,
+};
+use script::error::ErrorReporter;
+use script::{AstLowerer, Lexer, Parser, Program, SemanticAnalyzer};
 use std::env;
+use std::fs;
+use std::io::{self, BufReader, Write};
+use std::net::TcpListener;
 use std::process;
+use std::sync::Arc;
 
 fn main() {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
 
-    println!("Script Language Debugger v0.5.0-alpha");
+    // Stdout carries the protocol, so no banner in DAP mode
+    if args.get(1).map(String::as_str) == Some("--dap") {
+        run_dap(&args[2..]);
+        return;
+    }
+
+    println!("Script Language Debugger v{}", env!("CARGO_PKG_VERSION"));
     println!("Interactive debugging for Script programs");
     println!();
 
+    // Saved breakpoints are restored unless `--no-restore` comes first
+    let restore = args.get(1).map(String::as_str) != Some("--no-restore");
+    if !restore {
+        args.remove(1);
+    }
+
     if args.len() < 2 {
-        eprintln!("Usage: {} <script-file> [args...]", args[0]);
+        eprintln!("Usage: {} [--no-restore] <script-file>", args[0]);
+        eprintln!("       {} --dap [--port <port>]", args[0]);
         eprintln!();
-        eprintln!("Commands:");
-        eprintln!("  help     - Show debugger commands");
-        eprintln!("  break    - Set breakpoint");
-        eprintln!("  run      - Run program");
-        eprintln!("  step     - Step through code");
-        eprintln!("  continue - Continue execution");
-        eprintln!("  print    - Print variable value");
-        eprintln!("  backtrace - Show call stack");
+        print_help();
+        process::exit(1);
+    }
+
+    let file = &args[1];
+    let source = match fs::read_to_string(file) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!(
+                "{}: Could not read file '{}': {}",
+                "Error".red().bold(),
+                file,
+                e
+            );
+            process::exit(1);
+        }
+    };
+
+    let program = match check_program(&source, file) {
+        Some(program) => program,
+        None => process::exit(1),
+    };
+
+    if let Err(e) = initialize_debugger_with(restore) {
+        eprintln!("{}: {}", "Error".red().bold(), e);
         process::exit(1);
     }
+    let debugger = match get_debugger() {
+        Ok(debugger) => debugger,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red().bold(), e);
+            process::exit(1);
+        }
+    };
+    let _ = debugger.create_session(file.clone(), Some(file.clone()));
+    let restored = debugger.breakpoint_manager().get_all_breakpoints().len();
+    if restored > 0 {
+        println!(
+            "Restored {} breakpoint(s) from {}",
+            restored,
+            script::debugger::BREAKPOINTS_FILE
+        );
+    }
+
+    println!(
+        "Loaded {} ({} lines). Type 'help' for commands.",
+        file.cyan(),
+        source.lines().count()
+    );
+
+    let mut cli = DebugCli::new(debugger.clone(), file.clone(), &source);
+    cli.command_loop(&program);
+}
 
-    // TODO: Integrate with debugger module
-    // - Load and parse Script file
-    // - Set up debugging session
-    // - Handle breakpoints
-    // - Provide interactive debugging
+/// Serve the Debug Adapter Protocol on stdio, or on a TCP port with `--port`
+fn run_dap(args: &[String]) {
+    let port = match args {
+        [] => None,
+        [flag, port] if flag == "--port" => match port.parse::<u16>() {
+            Ok(port) => Some(port),
+            Err(_) => {
+                eprintln!("{}: Invalid port '{}'", "Error".red().bold(), port);
+                process::exit(1);
+            }
+        },
+        _ => {
+            eprintln!("Usage: script-debug --dap [--port <port>]");
+            process::exit(1);
+        }
+    };
 
-    eprintln!("Debugger integration pending. The debugger module is functional");
-    eprintln!("but needs to be exposed through this standalone binary.");
+    // The editor sends its own breakpoints, so none are restored or saved
+    let debugger = match initialize_debugger_with(false).and_then(|_| get_debugger()) {
+        Ok(debugger) => debugger,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red().bold(), e);
+            process::exit(1);
+        }
+    };
 
-    process::exit(1);
+    let result = match port {
+        None => DapServer::new(io::stdin().lock(), io::stdout(), debugger).run(),
+        Some(port) => TcpListener::bind(("127.0.0.1", port))
+            .and_then(|listener| {
+                eprintln!("Debug adapter listening on 127.0.0.1:{}", port);
+                listener.accept()
+            })
+            .and_then(|(stream, _)| {
+                let reader = BufReader::new(stream.try_clone()?);
+                DapServer::new(reader, stream, debugger).run()
+            }),
+    };
+    if let Err(e) = result {
+        eprintln!("{}: {}", "Error".red().bold(), e);
+        process::exit(1);
+    }
+}
+
+/// Parse, analyze and lower the program, reporting any errors
+fn check_program(source: &str, file: &str) -> Option<Program> {
+    let report = |error: script::Error| {
+        let mut reporter = ErrorReporter::new();
+        reporter.report(error.with_file_name(file));
+        reporter.print_all();
+    };
+
+    let lexer = match Lexer::new(source) {
+        Ok(lexer) => lexer,
+        Err(error) => {
+            report(error);
+            return None;
+        }
+    };
+    let (tokens, lex_errors) = lexer.scan_tokens();
+    if let Some(error) = lex_errors.into_iter().next() {
+        report(error);
+        return None;
+    }
+
+    let program = match Parser::new(tokens).parse() {
+        Ok(program) => program,
+        Err(error) => {
+            report(error);
+            return None;
+        }
+    };
+
+    let mut analyzer = SemanticAnalyzer::new();
+    if let Err(error) = analyzer.analyze_program(&program) {
+        report(error);
+        return None;
+    }
+    if let Some(error) = analyzer.errors().first() {
+        report(error.clone().into_error());
+        return None;
+    }
+
+    let type_info = analyzer.extract_type_info();
+    let generic_instantiations = analyzer.generic_instantiations().to_vec();
+    let closure_captures = analyzer.extract_closure_captures();
+    let symbol_table = analyzer.into_symbol_table();
+    let mut lowerer = AstLowerer::new(
+        symbol_table,
+        type_info,
+        generic_instantiations,
+        closure_captures,
+    );
+    if let Err(error) = lowerer.lower_program(&program) {
+        report(error);
+        return None;
+    }
+
+    Some(program)
+}
+
+fn print_help() {
+    println!("Commands:");
+    println!("  run                 - Run program until a breakpoint");
+    println!("  start               - Run program, stopping at the first statement");
+    println!("  break <line|func>   - Set breakpoint at a line or function");
+    println!(
+        "    ... if <cond>     - Stop only when an expression holds or on hit counts like '>= 3'"
+    );
+    println!("  watch <var>         - Stop when a variable or field (player.health) is written");
+    println!("  record [on|off|<n>] - Record the last n statements of the next run");
+    println!("  delete <id>         - Remove a breakpoint");
+    println!("  breakpoints         - List breakpoints");
+    println!("  step (s)            - Step into the next statement");
+    println!("  next (n)            - Step over calls to the next statement");
+    println!("  finish              - Run until the current function returns");
+    println!("  continue (c)        - Continue to the next breakpoint");
+    println!("  step-back (sb)      - Go back to the previous recorded statement");
+    println!("  reverse-continue (rc) - Go back to the previous breakpoint or watched write");
+    println!("  history <var>       - Show recorded changes to a variable");
+    println!("  print (p) <var>     - Print variable value");
+    println!("  locals              - Print all local variables");
+    println!("  backtrace (bt)      - Show call stack");
+    println!("  list (l)            - Show source around the current line");
+    println!("  quit (q)            - Exit debugger");
+}
+
+/// Interactive command interface driving the debug interpreter
+struct DebugCli {
+    debugger: Arc<Debugger>,
+    file: String,
+    lines: Vec<String>,
+    /// Statements to record for stepping back, if recording is on
+    record: Option<usize>,
+    quit: bool,
+}
+
+impl DebugCli {
+    fn new(debugger: Arc<Debugger>, file: String, source: &str) -> Self {
+        Self {
+            debugger,
+            file,
+            lines: source.lines().map(String::from).collect(),
+            record: None,
+            quit: false,
+        }
+    }
+
+    /// Prompt loop used before the program runs and after it finishes
+    fn command_loop(&mut self, program: &Program) {
+        while !self.quit {
+            let Some(input) = read_command("(sdb) ") else {
+                break;
+            };
+            let (command, arg) = split_command(&input);
+
+            match command {
+                "run" | "r" | "start" => {
+                    let stop_on_entry = command == "start";
+                    let debugger = self.debugger.clone();
+                    let file = self.file.clone();
+                    let record = self.record;
+                    let mut interpreter = DebugInterpreter::new(debugger, file, self);
+                    if let Some(capacity) = record {
+                        interpreter.set_recording(capacity);
+                    }
+                    let result = interpreter.run(program, stop_on_entry);
+                    match result {
+                        Ok(Some(_)) => println!("{}", "Program finished.".green()),
+                        Ok(None) => println!("{}", "Program terminated.".yellow()),
+                        Err(e) => eprintln!("{}: {}", "Runtime Error".red().bold(), e),
+                    }
+                }
+                "break" | "b" => self.set_breakpoint(arg),
+                "watch" | "w" => self.set_watchpoint(arg),
+                "record" => self.set_recording(arg),
+                "delete" | "d" => self.delete_breakpoint(arg),
+                "breakpoints" | "info" => self.list_breakpoints(),
+                "help" | "h" => print_help(),
+                "quit" | "q" | "exit" => self.quit = true,
+                "" => {}
+                "step" | "s" | "next" | "n" | "continue" | "c" | "finish" | "print" | "p"
+                | "backtrace" | "bt" | "locals" | "step-back" | "sb" | "reverse-continue"
+                | "rc" | "history" => {
+                    println!("The program is not running. Use 'run' or 'start'.")
+                }
+                other => println!("Unknown command: {}. Type 'help' for commands.", other),
+            }
+        }
+    }
+
+    fn set_breakpoint(&self, arg: &str) {
+        if arg.is_empty() {
+            println!("Usage: break <line|function> [if <condition>]");
+            return;
+        }
+        let (target, condition) = match arg.split_once(" if ") {
+            Some((target, condition)) => (target.trim(), Some(condition.trim())),
+            None => (arg, None),
+        };
+        let manager = self.debugger.breakpoint_manager();
+        let result = match target.parse::<usize>() {
+            Ok(line) => manager.add_line_breakpoint(self.file.clone(), line),
+            Err(_) => manager.add_function_breakpoint(target.to_string(), None),
+        };
+        let id = match result {
+            Ok(id) => id,
+            Err(e) => {
+                println!("Error setting breakpoint: {}", e);
+                return;
+            }
+        };
+
+        match condition {
+            None => println!("Breakpoint {} set at {}", id, target),
+            Some(expression) => {
+                let condition = BreakpointCondition::new(expression.to_string(), true);
+                match manager.set_breakpoint_condition(id, condition) {
+                    Ok(()) => println!("Breakpoint {} set at {} if {}", id, target, expression),
+                    Err(e) => {
+                        let _ = manager.remove_breakpoint(id);
+                        println!("Error setting breakpoint: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_watchpoint(&self, arg: &str) {
+        if arg.is_empty() {
+            println!("Usage: watch <variable|variable.field> [if <condition>]");
+            return;
+        }
+        let (path, condition) = match arg.split_once(" if ") {
+            Some((path, condition)) => (path.trim(), Some(condition.trim())),
+            None => (arg, None),
+        };
+        let manager = self.debugger.breakpoint_manager();
+        let id = match manager.add_watchpoint(path.to_string()) {
+            Ok(id) => id,
+            Err(e) => {
+                println!("Error setting watchpoint: {}", e);
+                return;
+            }
+        };
+
+        match condition {
+            None => println!("Watchpoint {} set on {}", id, path),
+            Some(expression) => {
+                let condition = BreakpointCondition::new(expression.to_string(), true);
+                match manager.set_breakpoint_condition(id, condition) {
+                    Ok(()) => println!("Watchpoint {} set on {} if {}", id, path, expression),
+                    Err(e) => {
+                        let _ = manager.remove_breakpoint(id);
+                        println!("Error setting watchpoint: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    fn set_recording(&mut self, arg: &str) {
+        self.record = match arg {
+            "" | "on" => Some(DEFAULT_RECORDING_CAPACITY),
+            "off" => None,
+            n => match n.parse::<usize>() {
+                Ok(n) if n > 0 => Some(n),
+                _ => {
+                    println!("Usage: record [on|off|<statements to keep>]");
+                    return;
+                }
+            },
+        };
+        match self.record {
+            Some(n) => println!("Recording the last {} statements of the next run", n),
+            None => println!("Recording off"),
+        }
+    }
+
+    fn delete_breakpoint(&self, arg: &str) {
+        match arg.parse() {
+            Ok(id) => match self.debugger.breakpoint_manager().remove_breakpoint(id) {
+                Ok(()) => println!("Breakpoint {} removed", id),
+                Err(e) => println!("Error: {}", e),
+            },
+            Err(_) => println!("Usage: delete <breakpoint id>"),
+        }
+    }
+
+    fn list_breakpoints(&self) {
+        let breakpoints = self.debugger.breakpoint_manager().get_all_breakpoints();
+        if breakpoints.is_empty() {
+            println!("No breakpoints set.");
+        }
+        for bp in breakpoints {
+            let status = if bp.enabled { "enabled" } else { "disabled" };
+            println!(
+                "  {} {} ({}, hits: {})",
+                bp.id,
+                bp.description(),
+                status,
+                bp.hit_count
+            );
+            if let Some(condition) = &bp.condition {
+                println!("      if {}", condition.expression);
+            }
+        }
+    }
+
+    fn list_source(&self, line: usize) {
+        let start = line.saturating_sub(3).max(1);
+        let end = (line + 3).min(self.lines.len());
+        for n in start..=end {
+            let marker = if n == line { "=>" } else { "  " };
+            let text = self.lines.get(n - 1).map(String::as_str).unwrap_or("");
+            if n == line {
+                println!("{} {:>4} {}", marker.green().bold(), n, text.bold());
+            } else {
+                println!("{} {:>4} {}", marker, n, text);
+            }
+        }
+    }
+}
+
+impl StepController for DebugCli {
+    fn on_pause(&mut self, state: &PauseState<'_>) -> StepAction {
+        let location = state.context.location;
+        let function = state.context.function_name.as_deref().unwrap_or("<main>");
+        match state.reason {
+            PauseReason::Entry => println!("Stopped at program entry"),
+            PauseReason::Watchpoint => {
+                if let Some(watch) = state.watch {
+                    let show = |value: Option<String>| value.unwrap_or_else(|| "<none>".into());
+                    println!("Watchpoint {}: {} written", watch.id, watch.path.bold());
+                    println!(
+                        "  Old value = {}",
+                        show(watch.old_value.as_ref().map(ToString::to_string))
+                    );
+                    println!(
+                        "  New value = {}",
+                        show(watch.new_value.as_ref().map(ToString::to_string))
+                    );
+                }
+            }
+            PauseReason::RecordingStart => println!("Reached the oldest recorded statement"),
+            PauseReason::Breakpoint | PauseReason::Step => {}
+        }
+        if state.steps_back > 0 {
+            println!(
+                "{}",
+                format!("(replaying, {} statements back)", state.steps_back).yellow()
+            );
+        }
+        println!(
+            "{} {}:{} in {}",
+            "→".cyan(),
+            self.file,
+            location.line,
+            function.cyan()
+        );
+        if let Some(text) = self.lines.get(location.line.saturating_sub(1)) {
+            println!("{:>6} {}", location.line, text.trim_end());
+        }
+
+        loop {
+            let Some(input) = read_command("(sdb) ") else {
+                self.quit = true;
+                return StepAction::Quit;
+            };
+            let (command, arg) = split_command(&input);
+
+            match command {
+                "step" | "s" => return StepAction::StepInto,
+                "next" | "n" => return StepAction::StepOver,
+                "finish" => return StepAction::StepOut,
+                "continue" | "c" => return StepAction::Continue,
+                "step-back" | "sb" | "reverse-continue" | "rc" if state.recording.is_none() => {
+                    println!("Recording is off. Use 'record' before 'run' to step back.")
+                }
+                "step-back" | "sb" => return StepAction::StepBack,
+                "reverse-continue" | "rc" => return StepAction::ReverseContinue,
+                "history" => match state.recording {
+                    Some(recording) => {
+                        let changes = recording.history(arg);
+                        if changes.is_empty() {
+                            println!("No recorded changes to '{}'", arg);
+                        }
+                        for (step, change) in changes {
+                            let show = |value: Option<String>| {
+                                value.unwrap_or_else(|| "<none>".to_string())
+                            };
+                            println!(
+                                "  #{} line {} in {}: {} -> {}",
+                                step.index,
+                                step.location().line,
+                                step.function(),
+                                show(change.before.as_ref().map(ToString::to_string)),
+                                show(change.after.as_ref().map(ToString::to_string))
+                            );
+                        }
+                    }
+                    None => println!("Recording is off. Use 'record' before 'run'."),
+                },
+                "quit" | "q" | "exit" => {
+                    self.quit = true;
+                    return StepAction::Quit;
+                }
+                "print" | "p" => match state.lookup(arg) {
+                    Some(value) => println!("{} = {}", arg, value),
+                    None => println!("No variable named '{}' in the current scope", arg),
+                },
+                "locals" => {
+                    let mut locals: Vec<_> = state.context.local_variables.iter().collect();
+                    locals.sort_by(|a, b| a.0.cmp(b.0));
+                    if locals.is_empty() {
+                        println!("No local variables.");
+                    }
+                    for (name, value) in locals {
+                        println!("  {} = {}", name, value);
+                    }
+                }
+                "backtrace" | "bt" => {
+                    for (depth, frame) in state.frames.iter().rev().enumerate() {
+                        println!(
+                            "  #{} {} at {}:{}",
+                            depth, frame.function, self.file, frame.location.line
+                        );
+                    }
+                }
+                "list" | "l" => self.list_source(location.line),
+                "break" | "b" => self.set_breakpoint(arg),
+                "watch" | "w" => self.set_watchpoint(arg),
+                "delete" | "d" => self.delete_breakpoint(arg),
+                "breakpoints" | "info" => self.list_breakpoints(),
+                "help" | "h" => print_help(),
+                "run" | "r" | "start" => println!("The program is already running."),
+                "" => {}
+                other => println!("Unknown command: {}. Type 'help' for commands.", other),
+            }
+        }
+    }
+}
+
+fn read_command(prompt: &str) -> Option<String> {
+    print!("{}", prompt);
+    io::stdout().flush().ok()?;
+    let mut input = String::new();
+    match io::stdin().read_line(&mut input) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(input.trim().to_string()),
+    }
+}
+
+fn split_command(input: &str) -> (&str, &str) {
+    match input.split_once(char::is_whitespace) {
+        Some((command, arg)) => (command, arg.trim()),
+        None => (input, ""),
+    }
 }
@@ -6,8 +6,11 @@
 //! and conditional breakpoints.
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 
+use crate::debugger::condition::Condition;
+use crate::runtime::value::Value;
 use crate::source::SourceLocation;
 
 /// Unique identifier for breakpoints
@@ -59,12 +62,18 @@ pub enum BreakpointType {
         /// Exception type to break on (None for all exceptions)
         exception_type: Option<String>,
     },
+    /// Watchpoint - breaks when a variable or struct field is written
+    Watch {
+        /// Variable name, optionally followed by fields (`player.health`)
+        path: String,
+    },
 }
 
 /// Condition for conditional breakpoints
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BreakpointCondition {
-    /// Script expression that must evaluate to true
+    /// Script expression that must evaluate to true, or a hit count test
+    /// such as `>= 3`
     pub expression: String,
     /// Whether the condition should be evaluated in the current scope
     pub use_current_scope: bool,
@@ -138,6 +147,19 @@ impl Breakpoint {
         }
     }
 
+    /// Create a new watchpoint
+    pub fn watch(id: BreakpointId, path: String) -> Self {
+        Breakpoint {
+            id,
+            breakpoint_type: BreakpointType::Watch { path },
+            enabled: true,
+            condition: None,
+            hit_count: 0,
+            message: None,
+            log_message: false,
+        }
+    }
+
     /// Enable this breakpoint
     pub fn enable(&mut self) {
         self.enabled = true;
@@ -230,6 +252,47 @@ impl Breakpoint {
                 // Exception breakpoints are handled differently
                 false
             }
+            BreakpointType::Watch { .. } => {
+                // Watchpoints are checked when a value is written
+                false
+            }
+        }
+    }
+
+    /// Check if this is an enabled watchpoint affected by a write to
+    /// `target`, a variable or field path
+    ///
+    /// Writing a variable affects watchpoints on its fields, and writing a
+    /// field affects a watchpoint on the variable holding it.
+    pub fn matches_write(&self, target: &str) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        let Some(path) = self.watch_path() else {
+            return false;
+        };
+        let contains = |outer: &str, inner: &str| {
+            inner
+                .strip_prefix(outer)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+        };
+        contains(path, target) || contains(target, path)
+    }
+
+    /// Check if `path` names something a watchpoint can watch: a variable
+    /// optionally followed by fields, such as `player.health`
+    pub fn is_valid_watch_path(path: &str) -> bool {
+        path.split('.').all(|part| {
+            part.starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_alphanumeric() || c == '_')
+        })
+    }
+
+    /// Get the watched path of a watchpoint
+    pub fn watch_path(&self) -> Option<&str> {
+        match &self.breakpoint_type {
+            BreakpointType::Watch { path } => Some(path),
+            _ => None,
         }
     }
 
@@ -256,6 +319,7 @@ impl Breakpoint {
                     "Exception breakpoint for all exceptions".to_string()
                 }
             }
+            BreakpointType::Watch { path } => format!("Watchpoint on {}", path),
         }
     }
 
@@ -286,27 +350,32 @@ impl BreakpointCondition {
         }
     }
 
-    /// Evaluate the condition (placeholder for now)
+    /// Check that the condition parses
+    pub fn validate(&self) -> Result<(), String> {
+        Condition::parse(&self.expression).map(|_| ())
+    }
+
+    /// Evaluate the condition against the state where the breakpoint was
+    /// reached
     ///
-    /// In a full implementation, this would compile and evaluate
-    /// the condition expression against the current runtime state.
-    pub fn evaluate(&self, _context: &BreakpointEvaluationContext) -> Result<bool, String> {
-        // TODO: Implement condition evaluation
-        // For now, always return true
-        println!("Evaluating condition: {}", self.expression);
-        Ok(true)
+    /// Expressions are evaluated with the variables in the context and hit
+    /// count tests such as `>= 3` with its hit count.
+    pub fn evaluate(&self, context: &BreakpointEvaluationContext) -> Result<bool, String> {
+        Condition::parse(&self.expression)?.holds(&context.variables, context.hit_count)
     }
 }
 
 /// Context for evaluating breakpoint conditions
 #[derive(Debug, Clone)]
 pub struct BreakpointEvaluationContext {
-    /// Current variables in scope
-    pub variables: std::collections::HashMap<String, String>, // Simplified for now
+    /// Variables in scope, locals shadowing globals
+    pub variables: HashMap<String, Value>,
     /// Current location
     pub location: SourceLocation,
     /// Current function name
     pub function_name: Option<String>,
+    /// Times the breakpoint has been reached, including this one
+    pub hit_count: usize,
 }
 
 impl BreakpointHit {
@@ -372,6 +441,7 @@ impl fmt::Display for BreakpointType {
                     write!(f, "all exceptions")
                 }
             }
+            BreakpointType::Watch { path } => write!(f, "write to {}", path),
         }
     }
 }
@@ -475,6 +545,26 @@ mod tests {
         assert!(!func_bp.matches_location(location, Some("test.script"), None));
     }
 
+    #[test]
+    fn test_watchpoint_write_matching() {
+        let mut watch_bp = Breakpoint::watch(1, "player.health".to_string());
+
+        assert!(watch_bp.matches_write("player.health"));
+        assert!(watch_bp.matches_write("player"));
+        assert!(watch_bp.matches_write("player.health.max"));
+        assert!(!watch_bp.matches_write("player.name"));
+        assert!(!watch_bp.matches_write("player.healthy"));
+        assert!(!watch_bp.matches_write("players"));
+        assert!(Breakpoint::is_valid_watch_path("_cache.entries2"));
+        assert!(!Breakpoint::is_valid_watch_path("items[0]"));
+        assert!(!Breakpoint::is_valid_watch_path("2fast"));
+        assert!(!watch_bp.matches_location(SourceLocation::new(1, 1, 0), None, None));
+        assert_eq!(watch_bp.description(), "Watchpoint on player.health");
+
+        watch_bp.disable();
+        assert!(!watch_bp.matches_write("player.health"));
+    }
+
     #[test]
     fn test_breakpoint_description() {
         let line_bp = Breakpoint::line(1, "test.script".to_string(), 42);
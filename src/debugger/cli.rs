@@ -493,6 +493,7 @@ mod tests {
                     attributes: vec![],
                 },
             ],
+            comments: Vec::new(),
         }
     }
 
@@ -0,0 +1,254 @@
+//! Breakpoint conditions
+//!
+//! A condition is either a Script expression or a hit-count test. An
+//! expression such as `x > 5 && player.name == "bob"` is parsed with the
+//! regular `Parser` and evaluated against the variables in scope where the
+//! breakpoint is reached; the breakpoint stops when the result is truthy.
+//! Expressions may use literals, variables, field access, indexing, arrays
+//! and the arithmetic, comparison and logical operators. Calls and
+//! assignments are rejected so that checking a condition can't change the
+//! program.
+//!
+//! A hit-count test starts with an operator and compares the number of
+//! times the breakpoint has been reached, counting this time: `>= 3` stops
+//! from the third time on, `== 5` only the fifth time and `% 2` every
+//! second time.
+
+use std::collections::HashMap;
+
+use crate::debugger::interpreter::{as_index, binary_op, literal_value};
+use crate::lexer::Lexer;
+use crate::parser::{BinaryOp, Expr, ExprKind, Parser, UnaryOp};
+use crate::runtime::value::Value;
+use crate::runtime::ScriptRc;
+
+/// A parsed breakpoint condition
+#[derive(Debug, Clone)]
+pub enum Condition {
+    /// Stop when the expression is truthy
+    Expression(Expr),
+    /// Stop when the hit count compares true against the number
+    HitCount(BinaryOp, usize),
+    /// Stop on every n-th hit
+    EveryNth(usize),
+}
+
+const HIT_COUNT_OPERATORS: [(&str, BinaryOp); 6] = [
+    (">=", BinaryOp::GreaterEqual),
+    ("<=", BinaryOp::LessEqual),
+    ("==", BinaryOp::Equal),
+    ("!=", BinaryOp::NotEqual),
+    (">", BinaryOp::Greater),
+    ("<", BinaryOp::Less),
+];
+
+impl Condition {
+    /// Parse a condition, describing why it is invalid
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let source = source.trim();
+        if let Some(count) = source.strip_prefix('%') {
+            return match parse_count(count)? {
+                0 => Err("Cannot stop every 0th hit".to_string()),
+                n => Ok(Condition::EveryNth(n)),
+            };
+        }
+        for (operator, op) in HIT_COUNT_OPERATORS {
+            if let Some(count) = source.strip_prefix(operator) {
+                return Ok(Condition::HitCount(op, parse_count(count)?));
+            }
+        }
+
+        let (tokens, errors) = Lexer::new(source).map_err(|e| e.message)?.scan_tokens();
+        if let Some(error) = errors.into_iter().next() {
+            return Err(error.message);
+        }
+        let mut parser = Parser::new(tokens);
+        let expr = parser.parse_expression().map_err(|e| e.message)?;
+        if !parser.is_at_end() {
+            return Err("Unexpected input after the condition".to_string());
+        }
+        check_side_effects(&expr)?;
+        Ok(Condition::Expression(expr))
+    }
+
+    /// Whether a breakpoint reached for the `hit_count`-th time with
+    /// `variables` in scope should stop
+    pub fn holds(
+        &self,
+        variables: &HashMap<String, Value>,
+        hit_count: usize,
+    ) -> Result<bool, String> {
+        match self {
+            Condition::Expression(expr) => Ok(eval(expr, variables)?.is_truthy()),
+            Condition::HitCount(op, count) => Ok(match op {
+                BinaryOp::GreaterEqual => hit_count >= *count,
+                BinaryOp::LessEqual => hit_count <= *count,
+                BinaryOp::Equal => hit_count == *count,
+                BinaryOp::NotEqual => hit_count != *count,
+                BinaryOp::Greater => hit_count > *count,
+                BinaryOp::Less => hit_count < *count,
+                _ => false,
+            }),
+            Condition::EveryNth(n) => Ok(hit_count % n == 0),
+        }
+    }
+}
+
+fn parse_count(text: &str) -> Result<usize, String> {
+    text.trim().parse().map_err(|_| {
+        format!(
+            "Expected a hit count after the operator, found '{}'",
+            text.trim()
+        )
+    })
+}
+
+/// Reject expressions that could change the program
+fn check_side_effects(expr: &Expr) -> Result<(), String> {
+    match &expr.kind {
+        ExprKind::Literal(_) | ExprKind::Identifier(_) => Ok(()),
+        ExprKind::Binary { left, right, .. } => {
+            check_side_effects(left)?;
+            check_side_effects(right)
+        }
+        ExprKind::Unary { expr, .. } => check_side_effects(expr),
+        ExprKind::Index { object, index } => {
+            check_side_effects(object)?;
+            check_side_effects(index)
+        }
+        ExprKind::Member { object, .. } => check_side_effects(object),
+        ExprKind::Array(elements) => elements.iter().try_for_each(check_side_effects),
+        ExprKind::Call { .. } => Err("Conditions cannot call functions".to_string()),
+        ExprKind::Assign { .. } => Err("Conditions cannot assign to variables".to_string()),
+        _ => Err(
+            "Conditions may only use variables, literals, fields, indexing and operators"
+                .to_string(),
+        ),
+    }
+}
+
+fn eval(expr: &Expr, variables: &HashMap<String, Value>) -> Result<Value, String> {
+    match &expr.kind {
+        ExprKind::Literal(literal) => Ok(literal_value(literal)),
+        ExprKind::Identifier(name) => variables
+            .get(name)
+            .cloned()
+            .ok_or_else(|| format!("Undefined variable '{}'", name)),
+        ExprKind::Binary { left, op, right } => match op {
+            BinaryOp::And => Ok(Value::Bool(
+                eval(left, variables)?.is_truthy() && eval(right, variables)?.is_truthy(),
+            )),
+            BinaryOp::Or => Ok(Value::Bool(
+                eval(left, variables)?.is_truthy() || eval(right, variables)?.is_truthy(),
+            )),
+            _ => binary_op(&eval(left, variables)?, *op, &eval(right, variables)?)
+                .map_err(|e| e.message),
+        },
+        ExprKind::Unary { op, expr } => {
+            let value = eval(expr, variables)?;
+            match op {
+                UnaryOp::Not => Ok(Value::Bool(!value.is_truthy())),
+                UnaryOp::Minus => binary_op(&Value::I32(0), BinaryOp::Sub, &value)
+                    .map_err(|_| format!("Cannot negate a value of type {}", value.type_name())),
+            }
+        }
+        ExprKind::Index { object, index } => {
+            let object = eval(object, variables)?;
+            let index = eval(index, variables)?;
+            match (&object, as_index(&index)) {
+                (Value::Array(items), Some(i)) => {
+                    items.get(i).map(|item| (**item).clone()).ok_or_else(|| {
+                        format!("Index {} is out of bounds for length {}", i, items.len())
+                    })
+                }
+                (Value::Object(fields), _) => Ok(fields
+                    .get(&index.to_string())
+                    .map(|v| (**v).clone())
+                    .unwrap_or(Value::Null)),
+                _ => Err(format!(
+                    "Cannot index a value of type {}",
+                    object.type_name()
+                )),
+            }
+        }
+        ExprKind::Member { object, property } => match eval(object, variables)? {
+            Value::Object(fields) => fields
+                .get(property)
+                .map(|v| (**v).clone())
+                .ok_or_else(|| format!("No field '{}'", property)),
+            Value::Array(items) if property == "length" => Ok(Value::I32(items.len() as i32)),
+            other => Err(format!(
+                "Cannot access field '{}' on a value of type {}",
+                property,
+                other.type_name()
+            )),
+        },
+        ExprKind::Array(elements) => elements
+            .iter()
+            .map(|element| eval(element, variables).map(ScriptRc::new))
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        _ => Err("Unsupported expression in condition".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> HashMap<String, Value> {
+        let mut player = HashMap::new();
+        player.insert(
+            "name".to_string(),
+            ScriptRc::new(Value::String("bob".into())),
+        );
+        player.insert("health".to_string(), ScriptRc::new(Value::I32(20)));
+
+        let mut variables = HashMap::new();
+        variables.insert("x".to_string(), Value::I32(7));
+        variables.insert("player".to_string(), Value::Object(player));
+        variables.insert(
+            "items".to_string(),
+            Value::Array(vec![
+                ScriptRc::new(Value::I32(1)),
+                ScriptRc::new(Value::I32(2)),
+            ]),
+        );
+        variables
+    }
+
+    fn holds(source: &str, hit_count: usize) -> Result<bool, String> {
+        Condition::parse(source)?.holds(&variables(), hit_count)
+    }
+
+    #[test]
+    fn test_expression_conditions() {
+        assert_eq!(holds("x > 5", 1), Ok(true));
+        assert_eq!(holds("x > 5 && player.name == \"alice\"", 1), Ok(false));
+        assert_eq!(holds("player.health * 2 == 40 || missing", 1), Ok(true));
+        assert_eq!(holds("items[1] - items[0] == 1", 1), Ok(true));
+        assert_eq!(holds("!(items.length == 2)", 1), Ok(false));
+        assert_eq!(holds("-x < 0", 1), Ok(true));
+
+        assert!(holds("y > 1", 1)
+            .unwrap_err()
+            .contains("Undefined variable 'y'"));
+        assert!(Condition::parse("launch()").is_err());
+        assert!(Condition::parse("x = 1").is_err());
+        assert!(Condition::parse("x > 1 y").is_err());
+    }
+
+    #[test]
+    fn test_hit_count_conditions() {
+        let hits = |source: &str| -> Vec<usize> {
+            (1..=6).filter(|n| holds(source, *n).unwrap()).collect()
+        };
+        assert_eq!(hits(">= 3"), vec![3, 4, 5, 6]);
+        assert_eq!(hits("==5"), vec![5]);
+        assert_eq!(hits("< 3"), vec![1, 2]);
+        assert_eq!(hits("% 2"), vec![2, 4, 6]);
+
+        assert!(Condition::parse(">= three").is_err());
+        assert!(Condition::parse("% 0").is_err());
+    }
+}
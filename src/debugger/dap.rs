@@ -0,0 +1,918 @@
+//! Debug Adapter Protocol server
+//!
+//! Lets editors such as VS Code drive the debugger. The server reads DAP
+//! requests framed with `Content-Length` headers and runs the launched
+//! program under a `DebugInterpreter`, acting as its `StepController`:
+//! - `setBreakpoints`, `setFunctionBreakpoints` and `setDataBreakpoints`
+//!   replace breakpoints in the debugger's `BreakpointManager`; data
+//!   breakpoints are watchpoints on variables and their fields
+//! - `configurationDone` starts the program, stopping on entry if `launch`
+//!   asked for it
+//! - While paused, `stackTrace`, `scopes`, `variables` and `evaluate`
+//!   inspect the interpreter's frames, and `continue`, `next`, `stepIn` and
+//!   `stepOut` resume it
+//! - A `launch` with `"record": true` (or a number of statements to keep)
+//!   records execution, so that `stepBack` and `reverseContinue` can move
+//!   back through it
+//!
+//! The interpreter runs on the thread serving requests, so requests sent
+//! while the program runs are handled at the next pause. Program output is
+//! forwarded as `output` events. There is a single thread with id 1.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use serde_json::{json, Value as Json};
+
+use crate::debugger::{
+    Breakpoint, BreakpointCondition, BreakpointId, BreakpointType, DebugInterpreter, Debugger,
+    PauseReason, PauseState, StepAction, StepController, DEFAULT_RECORDING_CAPACITY,
+};
+use crate::error::{self, Error};
+use crate::lexer::Lexer;
+use crate::parser::{Parser, Program};
+use crate::runtime::value::Value;
+use crate::semantic::SemanticAnalyzer;
+
+/// Id of the only thread reported to the client
+const THREAD_ID: i64 = 1;
+
+/// A program loaded by `launch`, waiting for `configurationDone`
+struct Launch {
+    file: String,
+    program: Program,
+    stop_on_entry: bool,
+    /// Statements to record for stepping back, if any
+    record: Option<usize>,
+}
+
+/// Serves one DAP client over a pair of streams
+pub struct DapServer<R, W> {
+    reader: R,
+    writer: W,
+    debugger: Arc<Debugger>,
+    seq: i64,
+    launch: Option<Launch>,
+    /// Variables behind each `variablesReference` handed out during the
+    /// current pause, numbered from 1, with the path of the value they
+    /// belong to (`None` for scopes)
+    handles: Vec<(Option<String>, Vec<(String, Value)>)>,
+    disconnected: bool,
+}
+
+impl<R: BufRead, W: Write> DapServer<R, W> {
+    /// Create a server reporting to `debugger`
+    pub fn new(reader: R, writer: W, debugger: Arc<Debugger>) -> Self {
+        Self {
+            reader,
+            writer,
+            debugger,
+            seq: 1,
+            launch: None,
+            handles: Vec::new(),
+            disconnected: false,
+        }
+    }
+
+    /// Handle requests until the client disconnects or closes the stream
+    pub fn run(&mut self) -> io::Result<()> {
+        while !self.disconnected {
+            let Some(request) = self.read_message()? else {
+                break;
+            };
+            let command = request["command"].as_str().unwrap_or_default().to_string();
+            match command.as_str() {
+                "configurationDone" => {
+                    self.respond(&request, json!({}))?;
+                    self.run_program()?;
+                }
+                "stackTrace" | "scopes" | "variables" | "evaluate" | "continue" | "next"
+                | "stepIn" | "stepOut" | "pause" => {
+                    self.fail(&request, "The program is not paused")?;
+                }
+                _ => self.handle_common(&request)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle a request that means the same whether or not the program is
+    /// paused
+    fn handle_common(&mut self, request: &Json) -> io::Result<()> {
+        let arguments = &request["arguments"];
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => {
+                self.respond(
+                    request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsFunctionBreakpoints": true,
+                        "supportsConditionalBreakpoints": true,
+                        "supportsHitConditionalBreakpoints": true,
+                        "supportsDataBreakpoints": true,
+                        "supportsStepBack": true,
+                        "supportsEvaluateForHovers": true,
+                        "supportsTerminateRequest": true,
+                    }),
+                )?;
+                self.event("initialized", json!({}))
+            }
+            "launch" => match self.load(arguments) {
+                Ok(()) => self.respond(request, json!({})),
+                Err(message) => {
+                    self.output("stderr", &format!("{}\n", message))?;
+                    self.fail(request, &message)
+                }
+            },
+            "setBreakpoints" => {
+                let body = self.set_breakpoints(arguments);
+                self.respond(request, body)
+            }
+            "setFunctionBreakpoints" => {
+                let body = self.set_function_breakpoints(arguments);
+                self.respond(request, body)
+            }
+            "dataBreakpointInfo" => {
+                let body = self.data_breakpoint_info(arguments);
+                self.respond(request, body)
+            }
+            "setDataBreakpoints" => {
+                let body = self.set_data_breakpoints(arguments);
+                self.respond(request, body)
+            }
+            "setExceptionBreakpoints" => self.respond(request, json!({})),
+            "threads" => self.respond(
+                request,
+                json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+            ),
+            "disconnect" | "terminate" => {
+                self.disconnected = true;
+                self.respond(request, json!({}))
+            }
+            "configurationDone" => self.fail(request, "The program is already running"),
+            command => self.fail(request, &format!("Unsupported request '{}'", command)),
+        }
+    }
+
+    /// Parse and check the program named by a `launch` request
+    fn load(&mut self, arguments: &Json) -> Result<(), String> {
+        let path = arguments["program"]
+            .as_str()
+            .ok_or("launch needs the path of the program to debug")?;
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read file '{}': {}", path, e))?;
+        let program = check_program(&source, path)?;
+
+        self.launch = Some(Launch {
+            file: normalize_path(path),
+            program,
+            stop_on_entry: arguments["stopOnEntry"].as_bool().unwrap_or(false),
+            record: match &arguments["record"] {
+                Json::Bool(true) => Some(DEFAULT_RECORDING_CAPACITY),
+                Json::Number(n) => n.as_u64().map(|n| n as usize),
+                _ => None,
+            },
+        });
+        let _ = self
+            .debugger
+            .create_session(path.to_string(), Some(path.to_string()));
+        Ok(())
+    }
+
+    /// Run the launched program to completion, serving requests at pauses
+    fn run_program(&mut self) -> io::Result<()> {
+        let Some(launch) = self.launch.take() else {
+            return Ok(());
+        };
+
+        let mut interpreter = DebugInterpreter::new(self.debugger.clone(), launch.file, self);
+        if let Some(capacity) = launch.record {
+            interpreter.set_recording(capacity);
+        }
+        let result = interpreter.run(&launch.program, launch.stop_on_entry);
+        self.handles.clear();
+        if self.disconnected {
+            return Ok(());
+        }
+
+        let exit_code = match result {
+            Ok(_) => 0,
+            Err(error) => {
+                self.output("stderr", &format!("Runtime error: {}\n", error))?;
+                1
+            }
+        };
+        self.event("exited", json!({ "exitCode": exit_code }))?;
+        self.event("terminated", json!({}))
+    }
+
+    fn set_breakpoints(&self, arguments: &Json) -> Json {
+        let Some(path) = arguments["source"]["path"].as_str() else {
+            return json!({ "breakpoints": [] });
+        };
+        let file = normalize_path(path);
+        let manager = self.debugger.breakpoint_manager();
+        for breakpoint in manager.get_breakpoints_for_file(&file) {
+            let _ = manager.remove_breakpoint(breakpoint.id);
+        }
+
+        let breakpoints: Vec<Json> = arguments["breakpoints"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|requested| {
+                let line = requested["line"].as_u64().unwrap_or(0) as usize;
+                let added = manager
+                    .add_line_breakpoint(file.clone(), line)
+                    .and_then(|id| self.set_condition(id, requested).map(|_| id));
+                match added {
+                    Ok(id) => json!({ "id": id, "verified": true, "line": line }),
+                    Err(error) => json!({
+                        "verified": false,
+                        "line": line,
+                        "message": error.to_string(),
+                    }),
+                }
+            })
+            .collect();
+        json!({ "breakpoints": breakpoints })
+    }
+
+    fn set_function_breakpoints(&self, arguments: &Json) -> Json {
+        let manager = self.debugger.breakpoint_manager();
+        for breakpoint in manager.get_all_breakpoints() {
+            if matches!(breakpoint.breakpoint_type, BreakpointType::Function { .. }) {
+                let _ = manager.remove_breakpoint(breakpoint.id);
+            }
+        }
+
+        let breakpoints: Vec<Json> = arguments["breakpoints"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|requested| {
+                let name = requested["name"].as_str().unwrap_or_default();
+                let added = manager
+                    .add_function_breakpoint(name.to_string(), None)
+                    .and_then(|id| self.set_condition(id, requested).map(|_| id));
+                match added {
+                    Ok(id) => json!({ "id": id, "verified": true }),
+                    Err(error) => json!({ "verified": false, "message": error.to_string() }),
+                }
+            })
+            .collect();
+        json!({ "breakpoints": breakpoints })
+    }
+
+    /// Describe whether a variable, or a field of one shown under a
+    /// `variablesReference`, can be watched
+    fn data_breakpoint_info(&self, arguments: &Json) -> Json {
+        let name = arguments["name"].as_str().unwrap_or_default();
+        let parent = arguments["variablesReference"]
+            .as_u64()
+            .and_then(|reference| self.handles.get((reference as usize).checked_sub(1)?))
+            .and_then(|(path, _)| path.as_deref());
+        let path = match parent {
+            Some(parent) => format!("{}.{}", parent, name),
+            None => name.to_string(),
+        };
+
+        if Breakpoint::is_valid_watch_path(&path) {
+            json!({
+                "dataId": path,
+                "description": format!("Write to {}", path),
+                "accessTypes": ["write"],
+                "canPersist": true,
+            })
+        } else {
+            json!({
+                "dataId": null,
+                "description": "Only variables and their fields can be watched",
+            })
+        }
+    }
+
+    fn set_data_breakpoints(&self, arguments: &Json) -> Json {
+        let manager = self.debugger.breakpoint_manager();
+        for breakpoint in manager.get_all_breakpoints() {
+            if matches!(breakpoint.breakpoint_type, BreakpointType::Watch { .. }) {
+                let _ = manager.remove_breakpoint(breakpoint.id);
+            }
+        }
+
+        let breakpoints: Vec<Json> = arguments["breakpoints"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|requested| {
+                let path = requested["dataId"].as_str().unwrap_or_default();
+                let added = manager
+                    .add_watchpoint(path.to_string())
+                    .and_then(|id| self.set_condition(id, requested).map(|_| id));
+                match added {
+                    Ok(id) => json!({ "id": id, "verified": true }),
+                    Err(error) => json!({ "verified": false, "message": error.to_string() }),
+                }
+            })
+            .collect();
+        json!({ "breakpoints": breakpoints })
+    }
+
+    /// Apply a requested breakpoint's `condition` or `hitCondition`,
+    /// removing the breakpoint if it is invalid
+    ///
+    /// A bare hit count such as `3` stops from the third hit on.
+    fn set_condition(&self, id: BreakpointId, requested: &Json) -> error::Result<()> {
+        let expression = match (
+            requested["condition"].as_str(),
+            requested["hitCondition"].as_str(),
+        ) {
+            (None, None) => return Ok(()),
+            (Some(condition), None) => condition.to_string(),
+            (None, Some(hits)) if hits.trim().parse::<usize>().is_ok() => {
+                format!(">= {}", hits.trim())
+            }
+            (None, Some(hits)) => hits.to_string(),
+            (Some(_), Some(_)) => {
+                let _ = self.debugger.breakpoint_manager().remove_breakpoint(id);
+                return Err(Error::parse(
+                    "A breakpoint can have a condition or a hit count, not both",
+                ));
+            }
+        };
+
+        let manager = self.debugger.breakpoint_manager();
+        let result =
+            manager.set_breakpoint_condition(id, BreakpointCondition::new(expression, true));
+        if result.is_err() {
+            let _ = manager.remove_breakpoint(id);
+        }
+        result
+    }
+
+    /// Serve requests while paused until one resumes execution
+    fn pause_loop(&mut self, state: &PauseState<'_>) -> io::Result<StepAction> {
+        let reason = match state.reason {
+            PauseReason::Entry => "entry",
+            PauseReason::Breakpoint => "breakpoint",
+            PauseReason::Step | PauseReason::RecordingStart => "step",
+            PauseReason::Watchpoint => "data breakpoint",
+        };
+        self.handles.clear();
+        let mut body = json!({
+            "reason": reason,
+            "threadId": THREAD_ID,
+            "allThreadsStopped": true,
+        });
+        if let Some(watch) = state.watch {
+            let show =
+                |value: &Option<Value>| value.as_ref().map_or("nothing".to_string(), display);
+            body["description"] = json!(format!(
+                "{} changed from {} to {}",
+                watch.path,
+                show(&watch.old_value),
+                show(&watch.new_value)
+            ));
+            body["hitBreakpointIds"] = json!([watch.id]);
+        }
+        if state.reason == PauseReason::RecordingStart {
+            body["description"] = json!("Reached the oldest recorded statement");
+        }
+        self.event("stopped", body)?;
+
+        loop {
+            let Some(request) = self.read_message()? else {
+                return Ok(StepAction::Quit);
+            };
+            let arguments = &request["arguments"];
+            let action = match request["command"].as_str().unwrap_or_default() {
+                "continue" => Some(StepAction::Continue),
+                "next" => Some(StepAction::StepOver),
+                "stepIn" => Some(StepAction::StepInto),
+                "stepOut" => Some(StepAction::StepOut),
+                "stepBack" | "reverseContinue" if state.recording.is_none() => {
+                    self.fail(&request, "Launch with \"record\": true to step back")?;
+                    continue;
+                }
+                "stepBack" => Some(StepAction::StepBack),
+                "reverseContinue" => Some(StepAction::ReverseContinue),
+                "pause" => {
+                    self.respond(&request, json!({}))?;
+                    continue;
+                }
+                "stackTrace" => {
+                    let body = self.stack_trace(state, arguments);
+                    self.respond(&request, body)?;
+                    continue;
+                }
+                "scopes" => {
+                    let body = self.scopes(state, arguments);
+                    self.respond(&request, body)?;
+                    continue;
+                }
+                "variables" => {
+                    let reference = arguments["variablesReference"].as_u64().unwrap_or(0);
+                    match self.variables(reference as usize) {
+                        Some(body) => self.respond(&request, body)?,
+                        None => self.fail(&request, "Unknown variables reference")?,
+                    }
+                    continue;
+                }
+                "evaluate" => {
+                    let expression = arguments["expression"].as_str().unwrap_or_default();
+                    let frame = arguments["frameId"].as_u64().map(|id| id as usize);
+                    match lookup_path(state, frame, expression) {
+                        Some(value) => {
+                            let body = json!({
+                                "result": display(&value),
+                                "type": value.type_name(),
+                                "variablesReference": self
+                                    .children_handle(expression.trim().to_string(), &value),
+                            });
+                            self.respond(&request, body)?;
+                        }
+                        None => self.fail(
+                            &request,
+                            &format!("No variable named '{}' in this frame", expression),
+                        )?,
+                    }
+                    continue;
+                }
+                _ => None,
+            };
+
+            match action {
+                Some(action) => {
+                    let body = if action == StepAction::Continue {
+                        json!({ "allThreadsContinued": true })
+                    } else {
+                        json!({})
+                    };
+                    self.respond(&request, body)?;
+                    self.handles.clear();
+                    return Ok(action);
+                }
+                None => {
+                    self.handle_common(&request)?;
+                    if self.disconnected {
+                        return Ok(StepAction::Quit);
+                    }
+                }
+            }
+        }
+    }
+
+    fn stack_trace(&self, state: &PauseState<'_>, arguments: &Json) -> Json {
+        let source = source_json(state.context.file.as_deref().unwrap_or_default());
+        let start = arguments["startFrame"].as_u64().unwrap_or(0) as usize;
+        let levels = match arguments["levels"].as_u64() {
+            Some(0) | None => usize::MAX,
+            Some(levels) => levels as usize,
+        };
+
+        // Frames are numbered from the outermost, listed innermost first
+        let frames: Vec<Json> = state
+            .frames
+            .iter()
+            .enumerate()
+            .rev()
+            .skip(start)
+            .take(levels)
+            .map(|(id, frame)| {
+                json!({
+                    "id": id,
+                    "name": frame.function,
+                    "source": source,
+                    "line": frame.location.line,
+                    "column": frame.location.column,
+                })
+            })
+            .collect();
+        json!({ "stackFrames": frames, "totalFrames": state.frames.len() })
+    }
+
+    fn scopes(&mut self, state: &PauseState<'_>, arguments: &Json) -> Json {
+        let frame = arguments["frameId"]
+            .as_u64()
+            .and_then(|id| state.frames.get(id as usize))
+            .or(state.frames.last());
+        let locals = frame.map(|frame| frame.locals()).unwrap_or_default();
+        let locals = self.handle(None, sorted(&locals));
+        let globals = self.handle(None, sorted(state.globals));
+        json!({
+            "scopes": [
+                { "name": "Locals", "variablesReference": locals, "expensive": false },
+                { "name": "Globals", "variablesReference": globals, "expensive": false },
+            ]
+        })
+    }
+
+    fn variables(&mut self, reference: usize) -> Option<Json> {
+        let (parent, entries) = self.handles.get(reference.checked_sub(1)?)?.clone();
+        let variables: Vec<Json> = entries
+            .iter()
+            .map(|(name, value)| {
+                let path = match &parent {
+                    Some(parent) => format!("{}.{}", parent, name),
+                    None => name.clone(),
+                };
+                json!({
+                    "name": name,
+                    "value": display(value),
+                    "type": value.type_name(),
+                    "variablesReference": self.children_handle(path, value),
+                })
+            })
+            .collect();
+        Some(json!({ "variables": variables }))
+    }
+
+    /// Hand out a reference to a list of variables
+    fn handle(&mut self, path: Option<String>, entries: Vec<(String, Value)>) -> usize {
+        self.handles.push((path, entries));
+        self.handles.len()
+    }
+
+    /// A reference to the fields or items of the value at `path`, or 0 if
+    /// it has none
+    fn children_handle(&mut self, path: String, value: &Value) -> usize {
+        let children = children(value);
+        if children.is_empty() {
+            0
+        } else {
+            self.handle(Some(path), children)
+        }
+    }
+
+    fn read_message(&mut self) -> io::Result<Option<Json>> {
+        let mut length = None;
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                if length.is_some() {
+                    break;
+                }
+                continue;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length:") {
+                length = Some(value.trim().parse::<usize>().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Invalid Content-Length '{}'", value.trim()),
+                    )
+                })?);
+            }
+        }
+
+        let mut body = vec![0; length.unwrap_or_default()];
+        self.reader.read_exact(&mut body)?;
+        serde_json::from_slice(&body)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn send(&mut self, mut message: Json) -> io::Result<()> {
+        message["seq"] = json!(self.seq);
+        self.seq += 1;
+        let body = message.to_string();
+        write!(
+            self.writer,
+            "Content-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        self.writer.flush()
+    }
+
+    fn respond(&mut self, request: &Json, body: Json) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": true,
+            "command": request["command"],
+            "body": body,
+        }))
+    }
+
+    fn fail(&mut self, request: &Json, message: &str) -> io::Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": false,
+            "command": request["command"],
+            "message": message,
+        }))
+    }
+
+    fn event(&mut self, event: &str, body: Json) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn output(&mut self, category: &str, text: &str) -> io::Result<()> {
+        self.event("output", json!({ "category": category, "output": text }))
+    }
+}
+
+impl<R: BufRead, W: Write> StepController for DapServer<R, W> {
+    fn on_pause(&mut self, state: &PauseState<'_>) -> StepAction {
+        // A broken connection ends the session
+        self.pause_loop(state).unwrap_or_else(|_| {
+            self.disconnected = true;
+            StepAction::Quit
+        })
+    }
+
+    fn on_output(&mut self, text: &str) {
+        if self.output("stdout", text).is_err() {
+            self.disconnected = true;
+        }
+    }
+}
+
+/// Parse and analyze a program, describing the first error
+fn check_program(source: &str, file: &str) -> Result<Program, String> {
+    let describe = |error: Error| error.with_file_name(file).to_string();
+
+    let (tokens, lex_errors) = Lexer::new(source).map_err(describe)?.scan_tokens();
+    if let Some(error) = lex_errors.into_iter().next() {
+        return Err(describe(error));
+    }
+    let program = Parser::new(tokens).parse().map_err(describe)?;
+
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze_program(&program).map_err(describe)?;
+    if let Some(error) = analyzer.errors().first() {
+        return Err(describe(error.clone().into_error()));
+    }
+    Ok(program)
+}
+
+/// The form of a path breakpoints and the interpreter agree on
+fn normalize_path(path: &str) -> String {
+    fs::canonicalize(path)
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+fn source_json(path: &str) -> Json {
+    let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string());
+    json!({ "name": name, "path": path })
+}
+
+fn sorted(variables: &HashMap<String, Value>) -> Vec<(String, Value)> {
+    let mut entries: Vec<_> = variables
+        .iter()
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries
+}
+
+/// Fields of objects, items of arrays and the data of enum variants
+fn children(value: &Value) -> Vec<(String, Value)> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| (index.to_string(), (**item).clone()))
+            .collect(),
+        Value::Object(fields) => {
+            let mut entries: Vec<_> = fields
+                .iter()
+                .map(|(name, value)| (name.clone(), (**value).clone()))
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            entries
+        }
+        Value::Enum {
+            variant,
+            data: Some(data),
+            ..
+        } => vec![(variant.clone(), (**data).clone())],
+        _ => Vec::new(),
+    }
+}
+
+/// Resolve a variable and its fields or items, as in `player.pos.x` or
+/// `items[0]`, in a frame or the innermost one
+fn lookup_path(state: &PauseState<'_>, frame: Option<usize>, expression: &str) -> Option<Value> {
+    let normalized = expression.trim().replace('[', ".").replace(']', "");
+    let mut parts = normalized.split('.');
+    let name = parts.next()?;
+    let frame = frame
+        .and_then(|id| state.frames.get(id))
+        .or(state.frames.last());
+    let mut value = frame
+        .and_then(|frame| frame.lookup(name))
+        .or_else(|| state.globals.get(name))?
+        .clone();
+    for part in parts {
+        value = children(&value)
+            .into_iter()
+            .find(|(name, _)| name == part)?
+            .1;
+    }
+    Some(value)
+}
+
+/// Values as the editor shows them, with strings quoted
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(text) => format!("{:?}", text),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn frame(message: Json) -> String {
+        let body = message.to_string();
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    fn request(seq: i64, command: &str, arguments: Json) -> String {
+        frame(json!({
+            "seq": seq,
+            "type": "request",
+            "command": command,
+            "arguments": arguments,
+        }))
+    }
+
+    /// Split the server's output into messages
+    fn messages(output: &[u8]) -> Vec<Json> {
+        let mut reader = Cursor::new(output.to_vec());
+        let mut server = DapServer::new(&mut reader, Vec::new(), Arc::new(Debugger::new()));
+        let mut messages = Vec::new();
+        while let Some(message) = server.read_message().unwrap() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    fn response<'a>(messages: &'a [Json], command: &str) -> &'a Json {
+        messages
+            .iter()
+            .find(|m| m["type"] == "response" && m["command"] == command)
+            .unwrap_or_else(|| panic!("no response to {}", command))
+    }
+
+    #[test]
+    fn test_breakpoint_session() {
+        let path = std::env::temp_dir().join(format!("dap_test_{}.script", std::process::id()));
+        fs::write(
+            &path,
+            "let items = [1, 2];\nlet total = items[0] + items[1];\nprintln(total);\n",
+        )
+        .unwrap();
+        let program = path.to_string_lossy().into_owned();
+
+        let input = [
+            request(1, "initialize", json!({ "adapterID": "script" })),
+            request(2, "launch", json!({ "program": program })),
+            request(
+                3,
+                "setBreakpoints",
+                json!({ "source": { "path": program }, "breakpoints": [{ "line": 3 }] }),
+            ),
+            request(4, "configurationDone", json!({})),
+            request(5, "stackTrace", json!({ "threadId": 1 })),
+            request(6, "scopes", json!({ "frameId": 0 })),
+            request(7, "variables", json!({ "variablesReference": 1 })),
+            request(8, "evaluate", json!({ "expression": "items[1]" })),
+            request(9, "continue", json!({ "threadId": 1 })),
+            request(10, "disconnect", json!({})),
+        ]
+        .concat();
+
+        let debugger = Arc::new(Debugger::new());
+        let mut output = Vec::new();
+        DapServer::new(Cursor::new(input), &mut output, debugger)
+            .run()
+            .unwrap();
+        let _ = fs::remove_file(&path);
+        let messages = messages(&output);
+
+        assert_eq!(response(&messages, "initialize")["success"], true);
+        assert_eq!(response(&messages, "launch")["success"], true);
+        assert_eq!(
+            response(&messages, "setBreakpoints")["body"]["breakpoints"][0]["verified"],
+            true
+        );
+
+        let stopped = messages.iter().find(|m| m["event"] == "stopped").unwrap();
+        assert_eq!(stopped["body"]["reason"], "breakpoint");
+        let frames = &response(&messages, "stackTrace")["body"]["stackFrames"];
+        assert_eq!(frames[0]["line"], 3);
+        assert_eq!(frames[0]["name"], "<main>");
+
+        let variables = &response(&messages, "variables")["body"]["variables"];
+        assert_eq!(variables[0]["name"], "items");
+        assert_eq!(variables[0]["value"], "[1, 2]");
+        assert_ne!(variables[0]["variablesReference"], 0);
+        assert_eq!(variables[1]["name"], "total");
+        assert_eq!(variables[1]["value"], "3");
+        assert_eq!(response(&messages, "evaluate")["body"]["result"], "2");
+
+        let output_event = messages.iter().find(|m| m["event"] == "output").unwrap();
+        assert_eq!(output_event["body"]["output"], "3\n");
+        assert!(messages.iter().any(|m| m["event"] == "exited"));
+        assert_eq!(response(&messages, "disconnect")["success"], true);
+    }
+
+    #[test]
+    fn test_data_breakpoint_session() {
+        let path = std::env::temp_dir().join(format!("dap_watch_{}.script", std::process::id()));
+        fs::write(
+            &path,
+            "let score = 1;\nscore = score + 4;\nprintln(score);\n",
+        )
+        .unwrap();
+        let program = path.to_string_lossy().into_owned();
+
+        let input = [
+            request(1, "initialize", json!({ "adapterID": "script" })),
+            request(2, "launch", json!({ "program": program, "record": true })),
+            request(3, "dataBreakpointInfo", json!({ "name": "score" })),
+            request(4, "dataBreakpointInfo", json!({ "name": "items[0]" })),
+            request(
+                5,
+                "setDataBreakpoints",
+                json!({ "breakpoints": [{ "dataId": "score" }] }),
+            ),
+            request(6, "configurationDone", json!({})),
+            request(7, "stepBack", json!({ "threadId": 1 })),
+            request(8, "evaluate", json!({ "expression": "score" })),
+            request(9, "continue", json!({ "threadId": 1 })),
+            request(10, "disconnect", json!({})),
+        ]
+        .concat();
+
+        let mut output = Vec::new();
+        DapServer::new(Cursor::new(input), &mut output, Arc::new(Debugger::new()))
+            .run()
+            .unwrap();
+        let _ = fs::remove_file(&path);
+        let messages = messages(&output);
+
+        let infos: Vec<_> = messages
+            .iter()
+            .filter(|m| m["command"] == "dataBreakpointInfo")
+            .collect();
+        assert_eq!(infos[0]["body"]["dataId"], "score");
+        assert_eq!(infos[1]["body"]["dataId"], Json::Null);
+        assert_eq!(
+            response(&messages, "setDataBreakpoints")["body"]["breakpoints"][0]["verified"],
+            true
+        );
+
+        let stopped = messages.iter().find(|m| m["event"] == "stopped").unwrap();
+        assert_eq!(stopped["body"]["reason"], "data breakpoint");
+        assert_eq!(stopped["body"]["description"], "score changed from 1 to 5");
+        // Stepping back shows the value before the write
+        assert_eq!(response(&messages, "stepBack")["success"], true);
+        assert_eq!(response(&messages, "evaluate")["body"]["result"], "1");
+        assert!(messages.iter().any(|m| m["event"] == "exited"));
+    }
+
+    #[test]
+    fn test_rejects_requests_out_of_order() {
+        let input = [
+            request(1, "stackTrace", json!({ "threadId": 1 })),
+            request(2, "launch", json!({ "program": "/no/such/file.script" })),
+            request(3, "flyToTheMoon", json!({})),
+        ]
+        .concat();
+        let mut output = Vec::new();
+        DapServer::new(Cursor::new(input), &mut output, Arc::new(Debugger::new()))
+            .run()
+            .unwrap();
+        let messages = messages(&output);
+
+        assert_eq!(response(&messages, "stackTrace")["success"], false);
+        assert!(response(&messages, "launch")["message"]
+            .as_str()
+            .unwrap()
+            .contains("Could not read file"));
+        assert_eq!(
+            response(&messages, "flyToTheMoon")["message"],
+            "Unsupported request 'flyToTheMoon'"
+        );
+    }
+}
@@ -321,6 +321,7 @@ mod tests {
                     attributes: vec![],
                 },
             ],
+            comments: Vec::new(),
         }
     }
 
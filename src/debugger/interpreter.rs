@@ -0,0 +1,1900 @@
+//! Statement-level interpreter used for interactive debugging
+//!
+//! JIT-compiled code cannot be paused between source lines, so the
+//! debugger executes the checked AST directly. Before every statement the
+//! interpreter builds an `ExecutionContext`, consults the debugger's
+//! `BreakpointManager` and the current stepping mode, and hands control to
+//! a `StepController` whenever execution pauses. The controller decides how
+//! to resume (continue, step into, step over, step out or quit).
+//!
+//! With recording enabled, every statement reached is also logged in an
+//! `ExecutionRecording`. While paused, the controller can then step back
+//! or reverse-continue through the recorded statements; stepping forward
+//! replays them until the present is reached and execution goes on live.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::debugger::recording::{self, ExecutionRecording, RecordedStep};
+use crate::debugger::{BreakpointId, Debugger, DebuggerState, ExecutionContext};
+use crate::error::{Error, ErrorKind, Result};
+use crate::lexer::NumberSuffix;
+use crate::parser::{
+    BinaryOp, Block, Expr, ExprKind, Literal, Param, PatternKind, Program, Stmt, StmtKind, UnaryOp,
+};
+use crate::runtime::value::Value;
+use crate::runtime::ScriptRc;
+use crate::source::{SourceLocation, Span};
+use crate::testing::ExpectMismatch;
+
+/// Maximum call depth before the interpreter reports a stack overflow
+const MAX_CALL_DEPTH: usize = 512;
+
+/// How execution should resume after a pause
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepAction {
+    /// Run until the next breakpoint
+    Continue,
+    /// Pause at the next statement, entering calls
+    StepInto,
+    /// Pause at the next statement in the current or a calling frame
+    StepOver,
+    /// Pause once the current function returns
+    StepOut,
+    /// Go back to the previous recorded statement
+    StepBack,
+    /// Go back to the nearest recorded statement a breakpoint stops at, or
+    /// that wrote to a watched variable
+    ReverseContinue,
+    /// Abort execution
+    Quit,
+}
+
+/// Why execution paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    /// Paused before the first statement
+    Entry,
+    /// A breakpoint matched
+    Breakpoint,
+    /// A step command completed
+    Step,
+    /// A watched variable or field was written
+    Watchpoint,
+    /// Stepping back reached the oldest recorded statement
+    RecordingStart,
+}
+
+/// A write that triggered a watchpoint
+#[derive(Debug, Clone)]
+pub struct WatchHit {
+    /// The watchpoint that triggered
+    pub id: BreakpointId,
+    /// The watched variable or field path
+    pub path: String,
+    /// Value before the write, `None` if the path didn't exist
+    pub old_value: Option<Value>,
+    /// Value after the write, `None` if the path no longer exists
+    pub new_value: Option<Value>,
+}
+
+/// A call frame in the interpreted program
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Function name (`<main>` for top-level code)
+    pub function: String,
+    /// Location of the statement being executed in this frame
+    pub location: SourceLocation,
+    /// Lexical scopes, innermost last
+    scopes: Vec<HashMap<String, Value>>,
+}
+
+impl Frame {
+    fn new(function: impl Into<String>, location: SourceLocation) -> Self {
+        Self {
+            function: function.into(),
+            location,
+            scopes: vec![HashMap::new()],
+        }
+    }
+
+    /// Look up a variable visible in this frame
+    pub fn lookup(&self, name: &str) -> Option<&Value> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    /// All variables visible in this frame, inner scopes shadowing outer ones
+    pub fn locals(&self) -> HashMap<String, Value> {
+        let mut locals = HashMap::new();
+        for scope in &self.scopes {
+            for (name, value) in scope {
+                locals.insert(name.clone(), value.clone());
+            }
+        }
+        locals
+    }
+
+    fn assign(&mut self, name: &str, value: Value) -> bool {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(slot) = scope.get_mut(name) {
+                *slot = value;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Snapshot handed to the controller when execution pauses
+pub struct PauseState<'a> {
+    pub reason: PauseReason,
+    pub context: &'a ExecutionContext,
+    /// Call stack, outermost first
+    pub frames: &'a [Frame],
+    pub globals: &'a HashMap<String, Value>,
+    /// The write that paused execution, for `PauseReason::Watchpoint`
+    pub watch: Option<&'a WatchHit>,
+    /// Statements recorded so far, if recording is enabled
+    pub recording: Option<&'a ExecutionRecording>,
+    /// How many recorded statements before the present this pause shows;
+    /// the frames and variables are rebuilt from the recording unless 0
+    pub steps_back: usize,
+}
+
+impl<'a> PauseState<'a> {
+    /// Resolve a variable the way the program would at this point
+    pub fn lookup(&self, name: &str) -> Option<&Value> {
+        self.frames
+            .last()
+            .and_then(|frame| frame.lookup(name))
+            .or_else(|| self.globals.get(name))
+    }
+}
+
+/// Receives control whenever the interpreter pauses
+pub trait StepController {
+    /// Decide how to resume from a pause
+    fn on_pause(&mut self, state: &PauseState<'_>) -> StepAction;
+
+    /// Receive program output from `print`/`println`
+    fn on_output(&mut self, text: &str) {
+        print!("{}", text);
+    }
+
+    /// Decide whether a failed `expect(value, "literal")` may continue
+    ///
+    /// Returning `false` turns the mismatch into a runtime error.
+    fn on_expect_mismatch(&mut self, mismatch: &ExpectMismatch) -> bool {
+        let _ = mismatch;
+        false
+    }
+}
+
+/// Internal stepping mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StepMode {
+    Run,
+    StepInto,
+    StepOver(usize),
+    StepOut(usize),
+}
+
+/// Result of executing a statement
+enum Flow {
+    Normal,
+    Return(Value),
+    Break,
+    Continue,
+    Halt,
+}
+
+/// A user-defined function known to the interpreter
+#[derive(Debug, Clone)]
+struct FunctionDef {
+    params: Vec<Param>,
+    body: Block,
+}
+
+/// Functions and module-level variables that outlive a single run
+#[derive(Debug, Clone, Default)]
+pub struct InterpreterState {
+    functions: HashMap<String, FunctionDef>,
+    globals: HashMap<String, Value>,
+}
+
+impl InterpreterState {
+    pub fn globals(&self) -> &HashMap<String, Value> {
+        &self.globals
+    }
+
+    pub fn has_function(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+}
+
+/// Executes a program one statement at a time under debugger control
+pub struct DebugInterpreter<'c> {
+    debugger: Arc<Debugger>,
+    file: String,
+    functions: HashMap<String, FunctionDef>,
+    globals: HashMap<String, Value>,
+    frames: Vec<Frame>,
+    mode: StepMode,
+    halted: bool,
+    /// Set until the first statement has been reached
+    at_entry: bool,
+    /// A `return`, `break` or `continue` executed inside a block expression,
+    /// unwinding to the enclosing function or loop
+    pending_flow: Option<Flow>,
+    time_limit: Option<Duration>,
+    deadline: Option<Instant>,
+    timed_out: bool,
+    recording: Option<ExecutionRecording>,
+    controller: &'c mut dyn StepController,
+}
+
+impl<'c> DebugInterpreter<'c> {
+    /// Create an interpreter that reports to `debugger` and `controller`
+    pub fn new(
+        debugger: Arc<Debugger>,
+        file: impl Into<String>,
+        controller: &'c mut dyn StepController,
+    ) -> Self {
+        Self {
+            debugger,
+            file: file.into(),
+            functions: HashMap::new(),
+            globals: HashMap::new(),
+            frames: Vec::new(),
+            mode: StepMode::Run,
+            halted: false,
+            at_entry: false,
+            pending_flow: None,
+            time_limit: None,
+            deadline: None,
+            timed_out: false,
+            recording: None,
+            controller,
+        }
+    }
+
+    /// Abort execution once it has run for longer than `limit`
+    pub fn set_time_limit(&mut self, limit: Duration) {
+        self.time_limit = Some(limit);
+    }
+
+    /// Record the last `capacity` statements executed so that the
+    /// controller can step back through them
+    pub fn set_recording(&mut self, capacity: usize) {
+        self.recording = Some(ExecutionRecording::new(capacity));
+    }
+
+    /// Statements recorded during the last run
+    pub fn recording(&self) -> Option<&ExecutionRecording> {
+        self.recording.as_ref()
+    }
+
+    /// Execute a program
+    ///
+    /// Module-level statements run first; if there are none, `main` is
+    /// called, matching the entry point the code generator selects. With
+    /// `stop_on_entry` the controller is consulted before the first
+    /// statement. Returns `Ok(None)` if the controller quit early.
+    pub fn run(&mut self, program: &Program, stop_on_entry: bool) -> Result<Option<Value>> {
+        self.begin(stop_on_entry);
+        let top_level = self.register_functions(program);
+        let result = if top_level.is_empty() && self.functions.contains_key("main") {
+            self.call_function("main", Vec::new(), SourceLocation::initial())
+        } else {
+            self.exec_top_level(&top_level, false)
+        };
+        self.finish(result)
+    }
+
+    /// Execute a program on top of the state left by earlier runs
+    ///
+    /// Used by the REPL: no entry point is selected, module-level
+    /// statements simply run in order, and a trailing expression statement
+    /// becomes the result.
+    pub fn run_incremental(&mut self, program: &Program) -> Result<Option<Value>> {
+        self.begin(false);
+        let top_level = self.register_functions(program);
+        let result = self.exec_top_level(&top_level, true);
+        self.finish(result)
+    }
+
+    /// Replace functions and module-level variables with those of an
+    /// earlier interpreter
+    pub fn with_state(mut self, state: InterpreterState) -> Self {
+        self.functions = state.functions;
+        self.globals = state.globals;
+        self
+    }
+
+    /// Take the functions and module-level variables defined so far
+    pub fn into_state(self) -> InterpreterState {
+        InterpreterState {
+            functions: self.functions,
+            globals: self.globals,
+        }
+    }
+
+    fn begin(&mut self, stop_on_entry: bool) {
+        self.mode = if stop_on_entry {
+            StepMode::StepInto
+        } else {
+            StepMode::Run
+        };
+        self.halted = false;
+        self.timed_out = false;
+        self.deadline = self
+            .time_limit
+            .and_then(|limit| Instant::now().checked_add(limit));
+        self.at_entry = stop_on_entry;
+        if let Some(recording) = &mut self.recording {
+            recording.clear();
+        }
+        self.debugger.set_enabled(true);
+        self.debugger.set_state(DebuggerState::Running);
+    }
+
+    fn finish(&mut self, result: Result<Value>) -> Result<Option<Value>> {
+        self.debugger.set_state(DebuggerState::Stopped);
+        match result {
+            _ if self.timed_out => Err(Error::new(
+                ErrorKind::SecurityViolation,
+                format!(
+                    "Execution exceeded the time limit of {} ms",
+                    self.time_limit.unwrap_or_default().as_millis()
+                ),
+            )),
+            Ok(_) if self.halted => Ok(None),
+            Ok(value) => Ok(Some(value)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Record function definitions, returning the statements to execute
+    fn register_functions<'p>(&mut self, program: &'p Program) -> Vec<&'p Stmt> {
+        let mut top_level = Vec::new();
+        for stmt in &program.statements {
+            match &stmt.kind {
+                StmtKind::Function {
+                    name, params, body, ..
+                } => {
+                    self.functions.insert(
+                        name.clone(),
+                        FunctionDef {
+                            params: params.clone(),
+                            body: body.clone(),
+                        },
+                    );
+                }
+                StmtKind::Struct { .. }
+                | StmtKind::Enum { .. }
+                | StmtKind::Impl(_)
+                | StmtKind::Trait(_) => {}
+                StmtKind::Import { .. } => {}
+                _ => top_level.push(stmt),
+            }
+        }
+        top_level
+    }
+
+    /// Run module-level statements in a `<main>` frame
+    ///
+    /// With `trailing_value` a final expression statement produces the
+    /// result instead of `null`.
+    fn exec_top_level(&mut self, statements: &[&Stmt], trailing_value: bool) -> Result<Value> {
+        self.frames
+            .push(Frame::new("<main>", SourceLocation::initial()));
+        let mut value = Ok(Value::Null);
+        for (i, stmt) in statements.iter().enumerate() {
+            let is_last = i + 1 == statements.len();
+            let flow = match &stmt.kind {
+                StmtKind::Expression(expr) if trailing_value && is_last => {
+                    self.checkpoint(stmt.span, None);
+                    if self.halted {
+                        break;
+                    }
+                    match self.eval(expr) {
+                        Ok(result) => {
+                            value = Ok(match self.pending_flow.take() {
+                                Some(Flow::Return(v)) => v,
+                                _ => result,
+                            });
+                            break;
+                        }
+                        Err(e) => Err(e),
+                    }
+                }
+                _ => self.exec_stmt(stmt),
+            };
+            match flow {
+                // Loop control outside a loop is rejected by semantic analysis
+                Ok(Flow::Normal | Flow::Break | Flow::Continue) => {}
+                Ok(Flow::Return(v)) => {
+                    value = Ok(v);
+                    break;
+                }
+                Ok(Flow::Halt) => break,
+                Err(e) => {
+                    value = Err(e);
+                    break;
+                }
+            }
+        }
+        // Top-level bindings stay inspectable after the program finishes
+        if let Some(frame) = self.frames.pop() {
+            self.globals.extend(frame.locals());
+        }
+        value
+    }
+
+    /// Variables left at module level after execution
+    pub fn globals(&self) -> &HashMap<String, Value> {
+        &self.globals
+    }
+
+    /// Decide whether to pause before executing the statement at `span`
+    ///
+    /// `entered_function` is set for the first statement of a call so that
+    /// function breakpoints fire once per call rather than on every line.
+    fn checkpoint(&mut self, span: Span, entered_function: Option<&str>) {
+        self.check_deadline();
+        if self.halted || self.pending_flow.is_some() {
+            return;
+        }
+
+        let depth = self.frames.len();
+        if let Some(frame) = self.frames.last_mut() {
+            frame.location = span.start;
+        }
+        if self.recording.is_some() {
+            let stack = self
+                .frames
+                .iter()
+                .map(|frame| (frame.function.clone(), frame.location))
+                .collect();
+            let variables = self.scope_variables();
+            if let Some(recording) = &mut self.recording {
+                recording.record(stack, variables);
+            }
+        }
+
+        let hit_breakpoint = self.debugger.breakpoint_manager().should_break_in_scope(
+            &self.file,
+            span.start,
+            entered_function,
+            &|| self.scope_variables(),
+        );
+
+        let reason = if hit_breakpoint {
+            Some(PauseReason::Breakpoint)
+        } else {
+            match self.mode {
+                StepMode::Run => None,
+                StepMode::StepInto if self.at_entry => Some(PauseReason::Entry),
+                StepMode::StepInto => Some(PauseReason::Step),
+                StepMode::StepOver(d) if depth <= d => Some(PauseReason::Step),
+                StepMode::StepOut(d) if depth < d => Some(PauseReason::Step),
+                _ => None,
+            }
+        };
+
+        self.at_entry = false;
+        if let Some(reason) = reason {
+            self.pause(reason, span.start, None);
+        }
+    }
+
+    /// Variables visible at this point, globals shadowed by the current
+    /// frame's locals
+    fn scope_variables(&self) -> HashMap<String, Value> {
+        let mut variables = self.globals.clone();
+        if let Some(frame) = self.frames.last() {
+            variables.extend(frame.locals());
+        }
+        variables
+    }
+
+    /// Hand control to the controller and apply the action it chooses
+    ///
+    /// Actions that move through the recording are handled here, so only
+    /// an action that resumes live execution is applied.
+    fn pause(&mut self, reason: PauseReason, location: SourceLocation, watch: Option<&WatchHit>) {
+        let depth = self.frames.len();
+        let function_name = self.frames.last().map(|f| f.function.clone());
+        if reason == PauseReason::Breakpoint {
+            let _ = self
+                .debugger
+                .handle_breakpoint(location, function_name.as_deref());
+        } else {
+            self.debugger.set_state(DebuggerState::Paused);
+        }
+
+        let context = ExecutionContext {
+            location,
+            file: Some(self.file.clone()),
+            function_name,
+            local_variables: self.frames.last().map(Frame::locals).unwrap_or_default(),
+            stack_depth: depth.saturating_sub(1),
+            thread_id: None,
+        };
+
+        // The newest recorded statement is the present, unless it has
+        // already written to a watched variable
+        let live = self.recording.as_ref().map_or(0, |recording| {
+            recording.len().saturating_sub(usize::from(watch.is_none()))
+        });
+        let mut position = live;
+        let mut reason = reason;
+        let mut watch = watch.cloned();
+        let action = loop {
+            let action = if position == live {
+                self.controller.on_pause(&PauseState {
+                    reason,
+                    context: &context,
+                    frames: &self.frames,
+                    globals: &self.globals,
+                    watch: watch.as_ref(),
+                    recording: self.recording.as_ref(),
+                    steps_back: 0,
+                })
+            } else {
+                self.pause_replayed(position, live, reason, watch.as_ref())
+            };
+
+            if self.recording.is_none() {
+                match action {
+                    // Nothing to go back to, ask again
+                    StepAction::StepBack | StepAction::ReverseContinue => continue,
+                    action => break action,
+                }
+            }
+            match self.replay_target(action, position, live) {
+                Some((target, target_reason, target_watch)) => {
+                    position = target;
+                    reason = target_reason;
+                    watch = target_watch;
+                }
+                None => break action,
+            }
+        };
+
+        let (mode, state) = match action {
+            StepAction::Continue => (StepMode::Run, DebuggerState::Running),
+            StepAction::StepInto => (StepMode::StepInto, DebuggerState::SteppingInto),
+            StepAction::StepOver => (StepMode::StepOver(depth), DebuggerState::Stepping),
+            StepAction::StepOut => (StepMode::StepOut(depth), DebuggerState::SteppingOut),
+            StepAction::Quit => {
+                self.halted = true;
+                (StepMode::Run, DebuggerState::Stopped)
+            }
+            StepAction::StepBack | StepAction::ReverseContinue => {
+                unreachable!("reverse actions are handled while paused")
+            }
+        };
+        self.mode = mode;
+        self.debugger.set_state(state);
+    }
+
+    /// Ask the controller how to go on from the recorded statement at
+    /// `position`, `live - position` statements before the present
+    fn pause_replayed(
+        &mut self,
+        position: usize,
+        live: usize,
+        reason: PauseReason,
+        watch: Option<&WatchHit>,
+    ) -> StepAction {
+        let Some(recording) = &self.recording else {
+            return StepAction::Continue;
+        };
+        let Some(step) = recording.step(position) else {
+            return StepAction::Continue;
+        };
+        let variables = recording.variables_at(position).unwrap_or_default();
+
+        let mut frames: Vec<Frame> = step
+            .stack
+            .iter()
+            .map(|(function, location)| Frame::new(function.clone(), *location))
+            .collect();
+        if let Some(frame) = frames.last_mut() {
+            frame.scopes = vec![variables.clone()];
+        }
+        let context = ExecutionContext {
+            location: step.location(),
+            file: Some(self.file.clone()),
+            function_name: Some(step.function().to_string()),
+            local_variables: variables,
+            stack_depth: step.depth().saturating_sub(1),
+            thread_id: None,
+        };
+
+        self.controller.on_pause(&PauseState {
+            reason,
+            context: &context,
+            frames: &frames,
+            globals: &HashMap::new(),
+            watch,
+            recording: Some(recording),
+            steps_back: live - position,
+        })
+    }
+
+    /// Where in the recording an action moves to from `position`, with the
+    /// reason to report there
+    ///
+    /// Returns `None` for actions that resume live execution: `Continue`,
+    /// `Quit` and steps taken at the present. Forward steps from an earlier
+    /// statement replay the recording and stop at the present at the latest.
+    fn replay_target(
+        &self,
+        action: StepAction,
+        position: usize,
+        live: usize,
+    ) -> Option<(usize, PauseReason, Option<WatchHit>)> {
+        let recording = self.recording.as_ref()?;
+        let depth_at = |p: usize| {
+            recording
+                .step(p)
+                .map_or(self.frames.len(), RecordedStep::depth)
+        };
+
+        match action {
+            StepAction::StepBack => Some(match position.checked_sub(1) {
+                Some(target) => (target, PauseReason::Step, None),
+                None => (0, PauseReason::RecordingStart, None),
+            }),
+            StepAction::ReverseContinue => Some(self.reverse_continue(recording, position)),
+            StepAction::StepInto | StepAction::StepOver | StepAction::StepOut
+                if position < live =>
+            {
+                let depth = depth_at(position);
+                let target = (position + 1..live)
+                    .find(|&p| match action {
+                        StepAction::StepOver => depth_at(p) <= depth,
+                        StepAction::StepOut => depth_at(p) < depth,
+                        _ => true,
+                    })
+                    .unwrap_or(live);
+                Some((target, PauseReason::Step, None))
+            }
+            _ => None,
+        }
+    }
+
+    /// Search the recording backwards from `position` for the nearest
+    /// statement a breakpoint stops at or that wrote to a watched path
+    ///
+    /// Stops at the oldest recorded statement if there is none.
+    fn reverse_continue(
+        &self,
+        recording: &ExecutionRecording,
+        position: usize,
+    ) -> (usize, PauseReason, Option<WatchHit>) {
+        let manager = self.debugger.breakpoint_manager();
+        let mut after = recording
+            .variables_at(position)
+            .unwrap_or_else(|| self.scope_variables());
+
+        for p in (0..position).rev() {
+            let Some(step) = recording.step(p) else {
+                break;
+            };
+            let before = match recording.step(p + 1) {
+                Some(next) => {
+                    let mut before = after.clone();
+                    recording::undo(&mut before, next);
+                    before
+                }
+                None => recording.variables_at(p).unwrap_or_default(),
+            };
+
+            if let Some(watchpoint) = manager.replayed_watchpoints(&before, &after).first() {
+                let path = watchpoint.watch_path().unwrap_or_default();
+                let hit = WatchHit {
+                    id: watchpoint.id,
+                    path: path.to_string(),
+                    old_value: recording::path_value(&before, path),
+                    new_value: recording::path_value(&after, path),
+                };
+                return (p, PauseReason::Watchpoint, Some(hit));
+            }
+
+            let entered_function = p
+                .checked_sub(1)
+                .and_then(|previous| recording.step(previous))
+                .filter(|previous| step.depth() > previous.depth())
+                .map(|_| step.function());
+            let breakpoints = manager.replayed_breakpoints(
+                &self.file,
+                step.location(),
+                entered_function,
+                &before,
+            );
+            if !breakpoints.is_empty() {
+                return (p, PauseReason::Breakpoint, None);
+            }
+            after = before;
+        }
+        (0, PauseReason::RecordingStart, None)
+    }
+
+    /// Halt if the time limit has passed
+    fn check_deadline(&mut self) {
+        if let Some(deadline) = self.deadline {
+            if Instant::now() >= deadline {
+                self.timed_out = true;
+                self.halted = true;
+            }
+        }
+    }
+
+    fn exec_block(
+        &mut self,
+        block: &Block,
+        entered_function: Option<&str>,
+    ) -> Result<(Flow, Value)> {
+        self.push_scope();
+        let result = self.exec_block_inner(block, entered_function);
+        self.pop_scope();
+        result
+    }
+
+    fn exec_block_inner(
+        &mut self,
+        block: &Block,
+        mut entered_function: Option<&str>,
+    ) -> Result<(Flow, Value)> {
+        for stmt in &block.statements {
+            self.checkpoint(stmt.span, entered_function.take());
+            match self.exec_stmt_unchecked(stmt)? {
+                Flow::Normal => {}
+                flow => return Ok((flow, Value::Null)),
+            }
+        }
+
+        if let Some(expr) = &block.final_expr {
+            self.checkpoint(expr.span, entered_function.take());
+            if self.halted {
+                return Ok((Flow::Halt, Value::Null));
+            }
+            let value = self.eval(expr)?;
+            if self.halted {
+                return Ok((Flow::Halt, Value::Null));
+            }
+            if let Some(flow) = self.pending_flow.take() {
+                return Ok((flow, Value::Null));
+            }
+            return Ok((Flow::Normal, value));
+        }
+
+        Ok((Flow::Normal, Value::Null))
+    }
+
+    fn exec_stmt(&mut self, stmt: &Stmt) -> Result<Flow> {
+        self.checkpoint(stmt.span, None);
+        self.exec_stmt_unchecked(stmt)
+    }
+
+    fn exec_stmt_unchecked(&mut self, stmt: &Stmt) -> Result<Flow> {
+        if self.halted {
+            return Ok(Flow::Halt);
+        }
+
+        let flow = match &stmt.kind {
+            StmtKind::Let { name, init, .. } => {
+                let value = match init {
+                    Some(expr) => self.eval(expr)?,
+                    None => Value::Null,
+                };
+                self.define(name, value);
+                Flow::Normal
+            }
+            StmtKind::Expression(expr) => {
+                self.eval(expr)?;
+                Flow::Normal
+            }
+            StmtKind::Return(expr) => {
+                let value = match expr {
+                    Some(expr) => self.eval(expr)?,
+                    None => Value::Null,
+                };
+                Flow::Return(value)
+            }
+            StmtKind::While { condition, body } => loop {
+                self.check_deadline();
+                if self.halted || !self.eval(condition)?.is_truthy() {
+                    break Flow::Normal;
+                }
+                match self.exec_block(body, None)?.0 {
+                    Flow::Normal | Flow::Continue => {}
+                    Flow::Break => break Flow::Normal,
+                    flow => break flow,
+                }
+            },
+            StmtKind::For {
+                variable,
+                iterable,
+                body,
+            } => {
+                let items = match self.eval(iterable)? {
+                    Value::Array(items) => items,
+                    other => {
+                        return Err(Error::runtime(format!(
+                            "Cannot iterate over a value of type {}",
+                            other.type_name()
+                        )))
+                    }
+                };
+                let mut flow = Flow::Normal;
+                for item in items {
+                    self.push_scope();
+                    self.define(variable, (*item).clone());
+                    let result = self.exec_block_inner(body, None);
+                    self.pop_scope();
+                    match result?.0 {
+                        Flow::Normal | Flow::Continue => {}
+                        Flow::Break => break,
+                        other => {
+                            flow = other;
+                            break;
+                        }
+                    }
+                }
+                flow
+            }
+            StmtKind::Function {
+                name, params, body, ..
+            } => {
+                self.functions.insert(
+                    name.clone(),
+                    FunctionDef {
+                        params: params.clone(),
+                        body: body.clone(),
+                    },
+                );
+                Flow::Normal
+            }
+            StmtKind::Break => Flow::Break,
+            StmtKind::Continue => Flow::Continue,
+            StmtKind::Struct { .. }
+            | StmtKind::Enum { .. }
+            | StmtKind::Impl(_)
+            | StmtKind::Trait(_)
+            | StmtKind::Import { .. }
+            | StmtKind::Export { .. } => Flow::Normal,
+        };
+
+        if self.halted {
+            Ok(Flow::Halt)
+        } else if let Some(flow) = self.pending_flow.take() {
+            Ok(flow)
+        } else {
+            Ok(flow)
+        }
+    }
+
+    fn eval(&mut self, expr: &Expr) -> Result<Value> {
+        if self.halted || self.pending_flow.is_some() {
+            return Ok(Value::Null);
+        }
+
+        match &expr.kind {
+            ExprKind::Literal(literal) => Ok(literal_value(literal)),
+            ExprKind::Identifier(name) => self.lookup(name).ok_or_else(|| {
+                Error::runtime(format!("Undefined variable '{}'", name))
+                    .with_location(expr.span.start)
+            }),
+            ExprKind::Binary { left, op, right } => match op {
+                BinaryOp::And => {
+                    let left = self.eval(left)?;
+                    if !left.is_truthy() {
+                        return Ok(Value::Bool(false));
+                    }
+                    Ok(Value::Bool(self.eval(right)?.is_truthy()))
+                }
+                BinaryOp::Or => {
+                    let left = self.eval(left)?;
+                    if left.is_truthy() {
+                        return Ok(Value::Bool(true));
+                    }
+                    Ok(Value::Bool(self.eval(right)?.is_truthy()))
+                }
+                _ => {
+                    let left = self.eval(left)?;
+                    let right = self.eval(right)?;
+                    binary_op(&left, *op, &right).map_err(|e| e.with_location(expr.span.start))
+                }
+            },
+            ExprKind::Unary { op, expr: inner } => {
+                let value = self.eval(inner)?;
+                match op {
+                    UnaryOp::Not => Ok(Value::Bool(!value.is_truthy())),
+                    UnaryOp::Minus => match value {
+                        Value::I32(i) => Ok(Value::I32(i.wrapping_neg())),
+                        Value::I64(i) => Ok(Value::I64(i.wrapping_neg())),
+                        Value::F32(f) => Ok(Value::F32(-f)),
+                        Value::F64(f) => Ok(Value::F64(-f)),
+                        Value::Number(n) => Ok(Value::Number(-n)),
+                        other => Err(Error::runtime(format!(
+                            "Cannot negate a value of type {}",
+                            other.type_name()
+                        ))),
+                    },
+                }
+            }
+            ExprKind::Call { callee, args } => {
+                let name = match &callee.kind {
+                    ExprKind::Identifier(name) => name.clone(),
+                    _ => {
+                        return Err(Error::runtime(
+                            "Only direct function calls are supported while debugging",
+                        )
+                        .with_location(expr.span.start))
+                    }
+                };
+                if name == "expect" && !self.functions.contains_key(&name) {
+                    return self.eval_expect(args, expr.span);
+                }
+                let mut values = Vec::with_capacity(args.len());
+                for arg in args {
+                    values.push(self.eval(arg)?);
+                }
+                self.call_function(&name, values, expr.span.start)
+            }
+            ExprKind::Index { object, index } => {
+                let object = self.eval(object)?;
+                let index = self.eval(index)?;
+                match (&object, as_index(&index)) {
+                    (Value::Array(items), Some(i)) => items
+                        .get(i)
+                        .map(|item| (**item).clone())
+                        .ok_or_else(|| Error::index_out_of_bounds(i, items.len())),
+                    (Value::Object(fields), _) => Ok(fields
+                        .get(&index.to_string())
+                        .map(|v| (**v).clone())
+                        .unwrap_or(Value::Null)),
+                    _ => Err(Error::runtime(format!(
+                        "Cannot index a value of type {}",
+                        object.type_name()
+                    ))),
+                }
+            }
+            ExprKind::Member { object, property } => match self.eval(object)? {
+                Value::Object(fields) => fields
+                    .get(property)
+                    .map(|v| (**v).clone())
+                    .ok_or_else(|| Error::runtime(format!("No field '{}'", property))),
+                Value::Array(items) if property == "length" => Ok(Value::I32(items.len() as i32)),
+                other => Err(Error::runtime(format!(
+                    "Cannot access field '{}' on a value of type {}",
+                    property,
+                    other.type_name()
+                ))),
+            },
+            ExprKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if self.eval(condition)?.is_truthy() {
+                    self.eval(then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.eval(else_branch)
+                } else {
+                    Ok(Value::Null)
+                }
+            }
+            ExprKind::Block(block) => {
+                let (flow, value) = self.exec_block(block, None)?;
+                if let Flow::Return(_) | Flow::Break | Flow::Continue = flow {
+                    // Unwinds through the enclosing statements to the
+                    // caller or loop
+                    self.pending_flow = Some(flow);
+                    return Ok(Value::Null);
+                }
+                Ok(value)
+            }
+            ExprKind::Array(elements) => {
+                let mut items = Vec::with_capacity(elements.len());
+                for element in elements {
+                    items.push(ScriptRc::new(self.eval(element)?));
+                }
+                Ok(Value::Array(items))
+            }
+            ExprKind::Assign { target, value } => {
+                let value = self.eval(value)?;
+                let watched = self.watched_values(target);
+                self.assign(target, value.clone())?;
+                if !watched.is_empty() {
+                    self.check_watchpoints(target, watched);
+                }
+                Ok(value)
+            }
+            ExprKind::StructConstructor { fields, .. } => {
+                let mut object = HashMap::new();
+                for (name, value) in fields {
+                    object.insert(name.clone(), ScriptRc::new(self.eval(value)?));
+                }
+                Ok(Value::Object(object))
+            }
+            ExprKind::Match {
+                expr: scrutinee,
+                arms,
+            } => {
+                let value = self.eval(scrutinee)?;
+                for arm in arms {
+                    let mut bindings = HashMap::new();
+                    if !match_pattern(&arm.pattern.kind, &value, &mut bindings)? {
+                        continue;
+                    }
+                    self.push_scope();
+                    for (name, bound) in bindings {
+                        self.define(&name, bound);
+                    }
+                    let guard_ok = match &arm.guard {
+                        Some(guard) => self.eval(guard).map(|v| v.is_truthy()),
+                        None => Ok(true),
+                    };
+                    let result = match guard_ok {
+                        Ok(true) => Some(self.eval(&arm.body)),
+                        Ok(false) => None,
+                        Err(e) => Some(Err(e)),
+                    };
+                    self.pop_scope();
+                    if let Some(result) = result {
+                        return result;
+                    }
+                }
+                Err(Error::runtime("No match arm matched the value").with_location(expr.span.start))
+            }
+            _ => Err(
+                Error::runtime("This expression is not supported by the debug interpreter")
+                    .with_location(expr.span.start),
+            ),
+        }
+    }
+
+    fn call_function(
+        &mut self,
+        name: &str,
+        args: Vec<Value>,
+        call_site: SourceLocation,
+    ) -> Result<Value> {
+        if let Some(value) = self.call_builtin(name, &args)? {
+            return Ok(value);
+        }
+
+        let function = self.functions.get(name).cloned().ok_or_else(|| {
+            Error::runtime(format!("Undefined function '{}'", name)).with_location(call_site)
+        })?;
+
+        if function.params.len() != args.len() {
+            return Err(Error::runtime(format!(
+                "Function '{}' expects {} arguments but got {}",
+                name,
+                function.params.len(),
+                args.len()
+            ))
+            .with_location(call_site));
+        }
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(Error::runtime("Stack overflow").with_location(call_site));
+        }
+
+        let mut frame = Frame::new(name, call_site);
+        for (param, value) in function.params.iter().zip(args) {
+            frame.scopes[0].insert(param.name.clone(), value);
+        }
+        self.frames.push(frame);
+
+        let result = self.exec_block_inner(&function.body, Some(name));
+        self.frames.pop();
+
+        match result? {
+            (Flow::Return(value), _) => Ok(value),
+            (_, value) => Ok(value),
+        }
+    }
+
+    /// Evaluate `expect(value, "expected")`
+    ///
+    /// The expected text must be a string literal so that its span can be
+    /// rewritten when expectations are updated.
+    fn eval_expect(&mut self, args: &[Expr], span: Span) -> Result<Value> {
+        let [value, expected] = args else {
+            return Err(
+                Error::runtime("expect() takes a value and an expected string literal")
+                    .with_location(span.start),
+            );
+        };
+        let ExprKind::Literal(Literal::String(expected_text)) = &expected.kind else {
+            return Err(
+                Error::runtime("expect() needs a string literal as its expected value")
+                    .with_location(expected.span.start),
+            );
+        };
+
+        let actual = self.eval(value)?.to_string();
+        if actual == *expected_text {
+            return Ok(Value::Null);
+        }
+
+        let mismatch = ExpectMismatch::new(expected.span, expected_text.clone(), actual);
+        if self.controller.on_expect_mismatch(&mismatch) {
+            Ok(Value::Null)
+        } else {
+            Err(Error::runtime(mismatch.to_string()).with_location(expected.span.start))
+        }
+    }
+
+    fn call_builtin(&mut self, name: &str, args: &[Value]) -> Result<Option<Value>> {
+        let joined = || {
+            args.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        match name {
+            "print" if !self.functions.contains_key(name) => {
+                self.controller.on_output(&joined());
+                Ok(Some(Value::Null))
+            }
+            "println" if !self.functions.contains_key(name) => {
+                self.controller.on_output(&format!("{}\n", joined()));
+                Ok(Some(Value::Null))
+            }
+            "len" if !self.functions.contains_key(name) => match args.first() {
+                Some(Value::Array(items)) => Ok(Some(Value::I32(items.len() as i32))),
+                Some(Value::String(s)) => Ok(Some(Value::I32(s.chars().count() as i32))),
+                _ => Err(Error::runtime("len() expects an array or string")),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn assign(&mut self, target: &Expr, value: Value) -> Result<()> {
+        match &target.kind {
+            ExprKind::Identifier(name) => {
+                let assigned = self
+                    .frames
+                    .last_mut()
+                    .map(|frame| frame.assign(name, value.clone()))
+                    .unwrap_or(false);
+                if !assigned {
+                    if let Some(slot) = self.globals.get_mut(name) {
+                        *slot = value;
+                    } else {
+                        return Err(Error::runtime(format!("Undefined variable '{}'", name))
+                            .with_location(target.span.start));
+                    }
+                }
+                Ok(())
+            }
+            ExprKind::Index { object, index } => {
+                let index = self.eval(index)?;
+                let mut container = self.eval(object)?;
+                match (&mut container, as_index(&index)) {
+                    (Value::Array(items), Some(i)) if i < items.len() => {
+                        items[i] = ScriptRc::new(value);
+                    }
+                    (Value::Array(items), Some(i)) => {
+                        return Err(Error::index_out_of_bounds(i, items.len()))
+                    }
+                    (Value::Object(fields), _) => {
+                        fields.insert(index.to_string(), ScriptRc::new(value));
+                    }
+                    _ => return Err(Error::runtime("Invalid assignment target")),
+                }
+                self.assign(object, container)
+            }
+            ExprKind::Member { object, property } => {
+                let mut container = self.eval(object)?;
+                match &mut container {
+                    Value::Object(fields) => {
+                        fields.insert(property.clone(), ScriptRc::new(value));
+                    }
+                    _ => return Err(Error::runtime("Invalid assignment target")),
+                }
+                self.assign(object, container)
+            }
+            _ => Err(Error::runtime("Invalid assignment target").with_location(target.span.start)),
+        }
+    }
+
+    /// Current values of the watched paths a write to `target` affects
+    fn watched_values(&self, target: &Expr) -> Vec<(String, Option<Value>)> {
+        let Some(path) = target_path(target) else {
+            return Vec::new();
+        };
+        self.debugger
+            .breakpoint_manager()
+            .watched_paths(&path)
+            .into_iter()
+            .map(|watched| {
+                let value = self.lookup_path(&watched);
+                (watched, value)
+            })
+            .collect()
+    }
+
+    /// Pause if a watchpoint breaks on the write to `target` that just
+    /// happened, reporting the value it had before
+    fn check_watchpoints(&mut self, target: &Expr, old_values: Vec<(String, Option<Value>)>) {
+        let Some(path) = target_path(target) else {
+            return;
+        };
+        let function_name = self.frames.last().map(|f| f.function.clone());
+        let hits = self.debugger.breakpoint_manager().watchpoints_hit(
+            &path,
+            target.span.start,
+            function_name.as_deref(),
+            &|| self.scope_variables(),
+        );
+        let Some(breakpoint) = hits.first() else {
+            return;
+        };
+        let Some(watched) = breakpoint.watch_path() else {
+            return;
+        };
+
+        let hit = WatchHit {
+            id: breakpoint.id,
+            path: watched.to_string(),
+            old_value: old_values
+                .into_iter()
+                .find(|(path, _)| path == watched)
+                .and_then(|(_, value)| value),
+            new_value: self.lookup_path(watched),
+        };
+        self.pause(PauseReason::Watchpoint, target.span.start, Some(&hit));
+    }
+
+    /// Look up a variable or a field path such as `player.health`
+    fn lookup_path(&self, path: &str) -> Option<Value> {
+        let mut parts = path.split('.');
+        let mut value = self.lookup(parts.next()?)?;
+        for field in parts {
+            value = match &value {
+                Value::Object(fields) => (**fields.get(field)?).clone(),
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    fn lookup(&self, name: &str) -> Option<Value> {
+        self.frames
+            .last()
+            .and_then(|frame| frame.lookup(name))
+            .or_else(|| self.globals.get(name))
+            .cloned()
+            .or_else(|| {
+                self.functions
+                    .contains_key(name)
+                    .then(|| Value::Function(name.to_string()))
+            })
+            .or_else(|| {
+                crate::stdlib::constants::constant_value(name)
+                    .map(|value| crate::runtime::script_value_to_value(&value))
+            })
+    }
+
+    fn define(&mut self, name: &str, value: Value) {
+        match self.frames.last_mut().and_then(|f| f.scopes.last_mut()) {
+            Some(scope) => {
+                scope.insert(name.to_string(), value);
+            }
+            None => {
+                self.globals.insert(name.to_string(), value);
+            }
+        }
+    }
+
+    fn push_scope(&mut self) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.scopes.push(HashMap::new());
+        }
+    }
+
+    fn pop_scope(&mut self) {
+        if let Some(frame) = self.frames.last_mut() {
+            if frame.scopes.len() > 1 {
+                frame.scopes.pop();
+            }
+        }
+    }
+}
+
+/// The variable or field path an assignment writes to
+///
+/// Writing an element of an array or map counts as writing the container.
+fn target_path(target: &Expr) -> Option<String> {
+    match &target.kind {
+        ExprKind::Identifier(name) => Some(name.clone()),
+        ExprKind::Member { object, property } => {
+            Some(format!("{}.{}", target_path(object)?, property))
+        }
+        ExprKind::Index { object, .. } => target_path(object),
+        _ => None,
+    }
+}
+
+pub(super) fn literal_value(literal: &Literal) -> Value {
+    match literal {
+        Literal::Number(n) if n.fract() == 0.0 && n.abs() <= i32::MAX as f64 => {
+            Value::I32(*n as i32)
+        }
+        Literal::Number(n) => Value::F64(*n),
+        Literal::TypedNumber(n, suffix) => match suffix {
+            NumberSuffix::I32 => Value::I32(*n as i32),
+            NumberSuffix::I64 | NumberSuffix::U32 | NumberSuffix::U64 => Value::I64(*n as i64),
+            NumberSuffix::F32 => Value::F32(*n as f32),
+            NumberSuffix::F64 => Value::F64(*n),
+        },
+        Literal::String(s) => Value::String(s.clone()),
+        Literal::Boolean(b) => Value::Bool(*b),
+        Literal::Null => Value::Null,
+    }
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::I32(i) => Some(*i as f64),
+        Value::I64(i) => Some(*i as f64),
+        Value::F32(f) => Some(*f as f64),
+        Value::F64(f) => Some(*f),
+        Value::Number(n) => Some(*n),
+        _ => None,
+    }
+}
+
+pub(super) fn as_index(value: &Value) -> Option<usize> {
+    match value {
+        Value::I32(i) if *i >= 0 => Some(*i as usize),
+        Value::I64(i) if *i >= 0 => Some(*i as usize),
+        _ => None,
+    }
+}
+
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (as_number(left), as_number(right)) {
+        (Some(a), Some(b)) => a == b,
+        _ => match (left, right) {
+            (Value::Null, Value::Null) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            _ => left.to_string() == right.to_string() && left.type_name() == right.type_name(),
+        },
+    }
+}
+
+pub(super) fn binary_op(left: &Value, op: BinaryOp, right: &Value) -> Result<Value> {
+    match op {
+        BinaryOp::Equal => return Ok(Value::Bool(values_equal(left, right))),
+        BinaryOp::NotEqual => return Ok(Value::Bool(!values_equal(left, right))),
+        _ => {}
+    }
+
+    if let (BinaryOp::Add, Value::String(a)) = (op, left) {
+        return Ok(Value::String(format!("{}{}", a, right)));
+    }
+
+    if let (Value::I32(a), Value::I32(b)) = (left, right) {
+        let (a, b) = (*a, *b);
+        return match op {
+            BinaryOp::Add => Ok(Value::I32(a.wrapping_add(b))),
+            BinaryOp::Sub => Ok(Value::I32(a.wrapping_sub(b))),
+            BinaryOp::Mul => Ok(Value::I32(a.wrapping_mul(b))),
+            BinaryOp::Div | BinaryOp::Mod if b == 0 => Err(Error::runtime("Division by zero")),
+            BinaryOp::Div => a.checked_div(b).map(Value::I32).ok_or_else(|| overflow(op)),
+            BinaryOp::Mod => a.checked_rem(b).map(Value::I32).ok_or_else(|| overflow(op)),
+            BinaryOp::Less => Ok(Value::Bool(a < b)),
+            BinaryOp::Greater => Ok(Value::Bool(a > b)),
+            BinaryOp::LessEqual => Ok(Value::Bool(a <= b)),
+            BinaryOp::GreaterEqual => Ok(Value::Bool(a >= b)),
+            _ => unreachable!("logical and equality operators are handled above"),
+        };
+    }
+
+    // Integers wider than i32 stay integers; an i32 operand is widened
+    let wide = match (left, right) {
+        (Value::I64(a), Value::I64(b)) => Some((*a, *b)),
+        (Value::I64(a), Value::I32(b)) => Some((*a, *b as i64)),
+        (Value::I32(a), Value::I64(b)) => Some((*a as i64, *b)),
+        _ => None,
+    };
+    if let Some((a, b)) = wide {
+        return match op {
+            BinaryOp::Add => Ok(Value::I64(a.wrapping_add(b))),
+            BinaryOp::Sub => Ok(Value::I64(a.wrapping_sub(b))),
+            BinaryOp::Mul => Ok(Value::I64(a.wrapping_mul(b))),
+            BinaryOp::Div | BinaryOp::Mod if b == 0 => Err(Error::runtime("Division by zero")),
+            BinaryOp::Div => a.checked_div(b).map(Value::I64).ok_or_else(|| overflow(op)),
+            BinaryOp::Mod => a.checked_rem(b).map(Value::I64).ok_or_else(|| overflow(op)),
+            BinaryOp::Less => Ok(Value::Bool(a < b)),
+            BinaryOp::Greater => Ok(Value::Bool(a > b)),
+            BinaryOp::LessEqual => Ok(Value::Bool(a <= b)),
+            BinaryOp::GreaterEqual => Ok(Value::Bool(a >= b)),
+            _ => unreachable!("logical and equality operators are handled above"),
+        };
+    }
+
+    match (as_number(left), as_number(right)) {
+        (Some(a), Some(b)) => match op {
+            BinaryOp::Add => Ok(Value::F64(a + b)),
+            BinaryOp::Sub => Ok(Value::F64(a - b)),
+            BinaryOp::Mul => Ok(Value::F64(a * b)),
+            BinaryOp::Div => Ok(Value::F64(a / b)),
+            BinaryOp::Mod => Ok(Value::F64(a % b)),
+            BinaryOp::Less => Ok(Value::Bool(a < b)),
+            BinaryOp::Greater => Ok(Value::Bool(a > b)),
+            BinaryOp::LessEqual => Ok(Value::Bool(a <= b)),
+            BinaryOp::GreaterEqual => Ok(Value::Bool(a >= b)),
+            _ => unreachable!("logical and equality operators are handled above"),
+        },
+        _ => Err(Error::runtime(format!(
+            "Unsupported operands for '{}': {} and {}",
+            op,
+            left.type_name(),
+            right.type_name()
+        ))),
+    }
+}
+
+fn overflow(op: BinaryOp) -> Error {
+    Error::runtime(format!("Integer overflow in '{}'", op))
+}
+
+fn match_pattern(
+    pattern: &PatternKind,
+    value: &Value,
+    bindings: &mut HashMap<String, Value>,
+) -> Result<bool> {
+    match pattern {
+        PatternKind::Wildcard => Ok(true),
+        PatternKind::Identifier(name) => {
+            bindings.insert(name.clone(), value.clone());
+            Ok(true)
+        }
+        PatternKind::Literal(literal) => Ok(values_equal(&literal_value(literal), value)),
+        PatternKind::Or(patterns) => {
+            for pattern in patterns {
+                if match_pattern(&pattern.kind, value, bindings)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }
+        PatternKind::Array(patterns) => match value {
+            Value::Array(items) if items.len() == patterns.len() => {
+                for (pattern, item) in patterns.iter().zip(items) {
+                    if !match_pattern(&pattern.kind, item, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            _ => Ok(false),
+        },
+        PatternKind::Binding { name, pattern } => {
+            if !match_pattern(&pattern.kind, value, bindings)? {
+                return Ok(false);
+            }
+            bindings.insert(name.clone(), value.clone());
+            Ok(true)
+        }
+        _ => Err(Error::runtime(
+            "This pattern is not supported by the debug interpreter",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debugger::BreakpointCondition;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    /// Replays a fixed list of actions and records where it paused
+    struct Scripted {
+        actions: Vec<StepAction>,
+        pauses: Vec<(PauseReason, usize, Option<String>)>,
+        /// Watchpoint writes as `path: old -> new`
+        writes: Vec<String>,
+        /// Variable looked up at every pause
+        inspect: &'static str,
+        /// Statements back from the present and value of `inspect` at
+        /// every pause
+        inspected: Vec<(usize, Option<String>)>,
+        output: String,
+    }
+
+    impl Scripted {
+        fn new(actions: Vec<StepAction>) -> Self {
+            Self {
+                actions,
+                pauses: Vec::new(),
+                writes: Vec::new(),
+                inspect: "",
+                inspected: Vec::new(),
+                output: String::new(),
+            }
+        }
+    }
+
+    impl StepController for Scripted {
+        fn on_pause(&mut self, state: &PauseState<'_>) -> StepAction {
+            self.pauses.push((
+                state.reason,
+                state.context.location.line,
+                state.context.function_name.clone(),
+            ));
+            self.inspected.push((
+                state.steps_back,
+                state.lookup(self.inspect).map(ToString::to_string),
+            ));
+            if let Some(watch) = state.watch {
+                let show = |value: &Option<Value>| match value {
+                    Some(value) => value.to_string(),
+                    None => "<none>".to_string(),
+                };
+                self.writes.push(format!(
+                    "{}: {} -> {}",
+                    watch.path,
+                    show(&watch.old_value),
+                    show(&watch.new_value)
+                ));
+            }
+            if self.actions.is_empty() {
+                StepAction::Continue
+            } else {
+                self.actions.remove(0)
+            }
+        }
+
+        fn on_output(&mut self, text: &str) {
+            self.output.push_str(text);
+        }
+    }
+
+    fn parse(source: &str) -> Program {
+        let lexer = Lexer::new(source).unwrap();
+        let (tokens, errors) = lexer.scan_tokens();
+        assert!(errors.is_empty());
+        Parser::new(tokens).parse().unwrap()
+    }
+
+    const PROGRAM: &str = "fn double(x: i32) -> i32 {\n    let y = x * 2;\n    y\n}\nlet a = 1;\nlet b = double(a);\nprintln(b);\n";
+
+    #[test]
+    fn test_runs_to_completion() {
+        let debugger = Arc::new(Debugger::new());
+        let mut controller = Scripted::new(vec![]);
+        let mut interp = DebugInterpreter::new(debugger, "test.script", &mut controller);
+        let program = parse(PROGRAM);
+        interp.run(&program, false).unwrap();
+        assert_eq!(
+            interp.globals().get("b").map(|v| v.to_string()),
+            Some("2".into())
+        );
+        assert_eq!(controller.output, "2\n");
+    }
+
+    #[test]
+    fn test_stdlib_constants_resolve() {
+        let debugger = Arc::new(Debugger::new());
+        let mut controller = Scripted::new(vec![]);
+        let mut interp = DebugInterpreter::new(debugger, "test.script", &mut controller);
+        let program = parse("let limit = MAX_I32;\nlet E = 1;\n");
+        interp.run(&program, false).unwrap();
+        assert_eq!(interp.globals().get("limit"), Some(&Value::I32(i32::MAX)));
+        assert_eq!(
+            interp.globals().get("E").map(|v| v.to_string()),
+            Some("1".into())
+        );
+    }
+
+    #[test]
+    fn test_line_breakpoint_and_step_over() {
+        let debugger = Arc::new(Debugger::new());
+        debugger
+            .breakpoint_manager()
+            .add_line_breakpoint("test.script".to_string(), 6)
+            .unwrap();
+
+        let mut controller = Scripted::new(vec![StepAction::StepOver, StepAction::Continue]);
+        let program = parse(PROGRAM);
+        DebugInterpreter::new(debugger, "test.script", &mut controller)
+            .run(&program, false)
+            .unwrap();
+
+        // Stepping over the call to `double` lands on the next top-level line
+        assert_eq!(controller.pauses[0].0, PauseReason::Breakpoint);
+        assert_eq!(controller.pauses[0].1, 6);
+        assert_eq!(controller.pauses[1].1, 7);
+        assert_eq!(controller.pauses.len(), 2);
+    }
+
+    #[test]
+    fn test_step_into_and_out() {
+        let debugger = Arc::new(Debugger::new());
+        debugger
+            .breakpoint_manager()
+            .add_line_breakpoint("test.script".to_string(), 6)
+            .unwrap();
+
+        let mut controller = Scripted::new(vec![
+            StepAction::StepInto,
+            StepAction::StepOut,
+            StepAction::Continue,
+        ]);
+        let program = parse(PROGRAM);
+        DebugInterpreter::new(debugger, "test.script", &mut controller)
+            .run(&program, false)
+            .unwrap();
+
+        assert_eq!(controller.pauses[1].1, 2);
+        assert_eq!(controller.pauses[1].2.as_deref(), Some("double"));
+        assert_eq!(controller.pauses[2].1, 7);
+    }
+
+    #[test]
+    fn test_quit_halts_execution() {
+        let debugger = Arc::new(Debugger::new());
+        let mut controller = Scripted::new(vec![StepAction::Quit]);
+        let program = parse(PROGRAM);
+        let result = DebugInterpreter::new(debugger, "test.script", &mut controller)
+            .run(&program, true)
+            .unwrap();
+        assert!(result.is_none());
+        assert_eq!(controller.pauses[0].0, PauseReason::Entry);
+        assert!(controller.output.is_empty());
+    }
+
+    #[test]
+    fn test_early_return_from_nested_block() {
+        let source =
+            "fn sign(x: i32) -> i32 {\n    if x < 0 { return -1; }\n    1\n}\nlet s = sign(-5);\n";
+        let debugger = Arc::new(Debugger::new());
+        let mut controller = Scripted::new(vec![]);
+        let mut interp = DebugInterpreter::new(debugger, "test.script", &mut controller);
+        interp.run(&parse(source), false).unwrap();
+        assert_eq!(
+            interp.globals().get("s").map(|v| v.to_string()),
+            Some("-1".into())
+        );
+    }
+
+    #[test]
+    fn test_conditional_breakpoint_sees_loop_variable() {
+        let source = "let sum = 0;\nfor i in 0..10 {\n    sum = sum + i;\n}\n";
+        let debugger = Arc::new(Debugger::new());
+        let manager = debugger.breakpoint_manager();
+        let id = manager
+            .add_line_breakpoint("test.script".to_string(), 3)
+            .unwrap();
+        let condition = BreakpointCondition::new("i % 4 == 3 && sum > 0".to_string(), true);
+        manager.set_breakpoint_condition(id, condition).unwrap();
+
+        let mut controller = Scripted::new(vec![]);
+        DebugInterpreter::new(debugger.clone(), "test.script", &mut controller)
+            .run(&parse(source), false)
+            .unwrap();
+        // Stops before adding 3 and 7
+        assert_eq!(controller.pauses.len(), 2);
+        assert_eq!(manager.get_breakpoint(id).unwrap().hit_count, 10);
+    }
+
+    #[test]
+    fn test_watchpoints_report_writes() {
+        let source = "struct Player { health: i32, name: string }\nlet p = Player { health: 10, name: \"a\" };\np.name = \"b\";\np.health = p.health - 3;\nlet x = 1;\nx = 2;\np = Player { health: 1, name: \"c\" };\n";
+        let debugger = Arc::new(Debugger::new());
+        let manager = debugger.breakpoint_manager();
+        manager.add_watchpoint("p.health".to_string()).unwrap();
+        let x = manager.add_watchpoint("x".to_string()).unwrap();
+        let condition = BreakpointCondition::new("x > 5".to_string(), true);
+        manager.set_breakpoint_condition(x, condition).unwrap();
+
+        let mut controller = Scripted::new(vec![]);
+        DebugInterpreter::new(debugger.clone(), "test.script", &mut controller)
+            .run(&parse(source), false)
+            .unwrap();
+        let lines: Vec<_> = controller.pauses.iter().map(|p| (p.0, p.1)).collect();
+        assert_eq!(
+            lines,
+            vec![(PauseReason::Watchpoint, 4), (PauseReason::Watchpoint, 7)]
+        );
+        assert_eq!(
+            controller.writes,
+            vec!["p.health: 10 -> 7", "p.health: 7 -> 1"]
+        );
+        assert_eq!(manager.get_breakpoint(x).unwrap().hit_count, 1);
+    }
+
+    #[test]
+    fn test_step_back_through_recording() {
+        let source = "let x = 1;\nx = x + 1;\nx = x * 10;\nlet y = x;\n";
+        let debugger = Arc::new(Debugger::new());
+        debugger
+            .breakpoint_manager()
+            .add_line_breakpoint("test.script".to_string(), 4)
+            .unwrap();
+
+        let mut controller = Scripted::new(vec![
+            StepAction::StepBack,
+            StepAction::StepBack,
+            StepAction::StepBack,
+            StepAction::StepBack,
+            StepAction::StepOver,
+            StepAction::Continue,
+        ]);
+        controller.inspect = "x";
+        let mut interp = DebugInterpreter::new(debugger, "test.script", &mut controller);
+        interp.set_recording(100);
+        interp.run(&parse(source), false).unwrap();
+        assert_eq!(interp.recording().unwrap().len(), 4);
+        assert_eq!(interp.globals().get("y"), Some(&Value::I32(20)));
+
+        let reasons: Vec<_> = controller.pauses.iter().map(|p| (p.0, p.1)).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (PauseReason::Breakpoint, 4),
+                (PauseReason::Step, 3),
+                (PauseReason::Step, 2),
+                (PauseReason::Step, 1),
+                (PauseReason::RecordingStart, 1),
+                (PauseReason::Step, 2),
+            ]
+        );
+        let show = |steps_back: usize, x: Option<&str>| (steps_back, x.map(String::from));
+        assert_eq!(
+            controller.inspected,
+            vec![
+                show(0, Some("20")),
+                show(1, Some("2")),
+                show(2, Some("1")),
+                show(3, None),
+                show(3, None),
+                show(2, Some("1")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reverse_continue_evaluates_conditions() {
+        let source = "let hp = 10;\nlet i = 0;\nwhile i < 3 {\n    hp = hp - 1;\n    i = i + 1;\n}\nlet done = hp;\n";
+        let debugger = Arc::new(Debugger::new());
+        let manager = debugger.breakpoint_manager();
+        let id = manager
+            .add_line_breakpoint("test.script".to_string(), 4)
+            .unwrap();
+        let condition = BreakpointCondition::new("i == 1".to_string(), true);
+        manager.set_breakpoint_condition(id, condition).unwrap();
+        manager
+            .add_line_breakpoint("test.script".to_string(), 7)
+            .unwrap();
+
+        let mut controller = Scripted::new(vec![
+            StepAction::Continue,
+            StepAction::ReverseContinue,
+            StepAction::ReverseContinue,
+        ]);
+        controller.inspect = "i";
+        let mut interp = DebugInterpreter::new(debugger, "test.script", &mut controller);
+        interp.set_recording(100);
+        interp.run(&parse(source), false).unwrap();
+
+        let reasons: Vec<_> = controller.pauses.iter().map(|p| (p.0, p.1)).collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (PauseReason::Breakpoint, 4),
+                (PauseReason::Breakpoint, 7),
+                (PauseReason::Breakpoint, 4),
+                (PauseReason::RecordingStart, 1),
+            ]
+        );
+        let values: Vec<_> = controller
+            .inspected
+            .iter()
+            .map(|(_, i)| i.clone())
+            .collect();
+        assert_eq!(
+            values,
+            vec![Some("1".into()), Some("3".into()), Some("1".into()), None]
+        );
+        // Replaying doesn't count hits
+        assert_eq!(manager.get_breakpoint(id).unwrap().hit_count, 3);
+    }
+
+    #[test]
+    fn test_break_and_continue() {
+        let source = "let sum = 0;\nfor i in 0..10 {\n    if i == 2 { continue }\n    if i == 5 { break }\n    sum = sum + i;\n}\n";
+        let debugger = Arc::new(Debugger::new());
+        let mut controller = Scripted::new(vec![]);
+        let mut interp = DebugInterpreter::new(debugger, "test.script", &mut controller);
+        interp.run(&parse(source), false).unwrap();
+        assert_eq!(
+            interp.globals().get("sum").map(|v| v.to_string()),
+            Some("8".into())
+        );
+    }
+
+    #[test]
+    fn test_time_limit_stops_infinite_loop() {
+        let debugger = Arc::new(Debugger::new());
+        let mut controller = Scripted::new(vec![]);
+        let mut interp = DebugInterpreter::new(debugger, "test.script", &mut controller);
+        interp.set_time_limit(Duration::from_millis(20));
+        let error = interp.run(&parse("while true {}\n"), false).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::SecurityViolation);
+    }
+
+    #[test]
+    fn test_incremental_runs_share_state() {
+        let debugger = Arc::new(Debugger::new());
+        let mut controller = Scripted::new(vec![]);
+        let mut state = InterpreterState::default();
+
+        for (line, expected) in [
+            ("let x = 40;", None),
+            ("fn add(a: i32, b: i32) -> i32 { a + b }", None),
+            ("x = add(x, 2);", None),
+            ("x", Some("42")),
+        ] {
+            let mut interp = DebugInterpreter::new(debugger.clone(), "<repl>", &mut controller)
+                .with_state(state);
+            let value = interp.run_incremental(&parse(line)).unwrap();
+            state = interp.into_state();
+            if let Some(expected) = expected {
+                assert_eq!(value.map(|v| v.to_string()), Some(expected.to_string()));
+            }
+        }
+
+        assert!(state.has_function("add"));
+    }
+
+    #[test]
+    fn test_expect_compares_display_form() {
+        let debugger = Arc::new(Debugger::new());
+        let mut controller = Scripted::new(vec![]);
+        let mut interp = DebugInterpreter::new(debugger, "test.script", &mut controller);
+        let program = parse("let a = 1 + 2;\nexpect(a, \"3\");\nexpect(a * 2, \"5\");\n");
+
+        let error = interp.run(&program, false).unwrap_err();
+        assert!(error.message.contains(r#"expected "5", got "6""#));
+        assert_eq!(error.location.map(|loc| loc.line), Some(3));
+    }
+
+    #[test]
+    fn test_integer_edge_cases() {
+        let min = Value::I32(i32::MIN);
+        assert!(binary_op(&min, BinaryOp::Div, &Value::I32(-1)).is_err());
+        assert!(binary_op(&min, BinaryOp::Mod, &Value::I32(-1)).is_err());
+        assert!(binary_op(&Value::I64(i64::MIN), BinaryOp::Div, &Value::I64(-1)).is_err());
+
+        // i64 arithmetic keeps every bit instead of going through f64
+        let big = Value::I64(i64::MAX - 1);
+        let sum = binary_op(&big, BinaryOp::Add, &Value::I32(1)).unwrap();
+        assert!(matches!(sum, Value::I64(i64::MAX)));
+
+        let debugger = Arc::new(Debugger::new());
+        let mut controller = Scripted::new(vec![]);
+        let mut interp = DebugInterpreter::new(debugger, "test.script", &mut controller);
+        interp
+            .run(&parse("let a = -2147483647 - 1;\nlet b = -a;\n"), false)
+            .unwrap();
+        assert_eq!(
+            interp.globals().get("b").map(|v| v.to_string()),
+            Some(i32::MIN.to_string())
+        );
+    }
+}
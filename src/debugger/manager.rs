@@ -5,13 +5,18 @@
 //! thread-safe and efficient for runtime breakpoint checking.
 
 use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::{Mutex, RwLock};
 
 use crate::debugger::breakpoint::{
     Breakpoint, BreakpointCondition, BreakpointEvaluationContext, BreakpointHit, BreakpointId,
     BreakpointType,
 };
+use crate::debugger::condition::Condition;
+use crate::debugger::persist::{load_breakpoints, save_breakpoints};
+use crate::debugger::recording::path_value;
 use crate::error::{Error, Result};
+use crate::runtime::value::Value;
 use crate::source::SourceLocation;
 
 /// Manages all breakpoints in the debugger
@@ -28,6 +33,8 @@ pub struct BreakpointManager {
     hit_history: Mutex<Vec<BreakpointHit>>,
     /// Maximum number of hits to keep in history
     max_history_size: usize,
+    /// File the breakpoints are saved to whenever they change
+    persist_path: Mutex<Option<PathBuf>>,
 }
 
 impl BreakpointManager {
@@ -40,9 +47,90 @@ impl BreakpointManager {
             next_id: Mutex::new(1),
             hit_history: Mutex::new(Vec::new()),
             max_history_size: 1000, // Keep last 1000 hits
+            persist_path: Mutex::new(None),
         }
     }
 
+    /// Save the breakpoints to `path` every time they change, or stop
+    /// saving them with `None`
+    pub fn persist_to(&self, path: Option<PathBuf>) {
+        if let Ok(mut persist_path) = self.persist_path.lock() {
+            *persist_path = path;
+        }
+    }
+
+    /// Add the breakpoints saved in `path`, returning how many were added
+    ///
+    /// Saved breakpoints that are no longer valid are skipped with a
+    /// warning rather than failing the whole restore.
+    pub fn restore_from(&self, path: &Path) -> Result<usize> {
+        let mut restored = 0;
+        for saved in load_breakpoints(path)? {
+            let id = match (&saved.watch, &saved.function, &saved.file, saved.line) {
+                (Some(watch), _, _, _) => self.add_watchpoint(watch.clone()),
+                (None, Some(function), file, _) => {
+                    self.add_function_breakpoint(function.clone(), file.clone())
+                }
+                (None, None, Some(file), Some(line)) => {
+                    self.add_line_breakpoint(file.clone(), line)
+                }
+                _ => Err(Error::parse(
+                    "Saved breakpoint needs a file and line, a function or a watch",
+                )),
+            };
+            let restore = id.and_then(|id| {
+                if let Some(condition) = &saved.condition {
+                    self.set_breakpoint_condition(
+                        id,
+                        BreakpointCondition::new(condition.clone(), true),
+                    )?;
+                }
+                if !saved.enabled {
+                    self.disable_breakpoint(id)?;
+                }
+                Ok(())
+            });
+            match restore {
+                Ok(()) => restored += 1,
+                Err(e) => eprintln!("Warning: skipping saved breakpoint: {}", e),
+            }
+        }
+        Ok(restored)
+    }
+
+    /// Write the breakpoints to the persist path, if there is one
+    fn save(&self) {
+        let path = match self.persist_path.lock() {
+            Ok(path) => path.clone(),
+            Err(_) => return,
+        };
+        if let Some(path) = path {
+            if let Err(e) = save_breakpoints(&path, &self.get_all_breakpoints()) {
+                eprintln!(
+                    "Warning: failed to save breakpoints to {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+    }
+
+    /// Change the breakpoint with `id` and save the result
+    fn update<T>(&self, id: BreakpointId, change: impl FnOnce(&mut Breakpoint) -> T) -> Result<T> {
+        let result = {
+            let mut breakpoints = self
+                .breakpoints
+                .write()
+                .map_err(|_| Error::lock_poisoned("Failed to acquire write lock on breakpoints"))?;
+            let breakpoint = breakpoints
+                .get_mut(&id)
+                .ok_or_else(|| Error::key_not_found(format!("Breakpoint {}", id)))?;
+            change(breakpoint)
+        };
+        self.save();
+        Ok(result)
+    }
+
     /// Add a line breakpoint
     pub fn add_line_breakpoint(&self, file: String, line: usize) -> Result<BreakpointId> {
         // Validate the file path
@@ -79,6 +167,7 @@ impl BreakpointManager {
                 .insert(id);
         }
 
+        self.save();
         Ok(id)
     }
 
@@ -115,6 +204,7 @@ impl BreakpointManager {
                 .insert(id);
         }
 
+        self.save();
         Ok(id)
     }
 
@@ -146,6 +236,34 @@ impl BreakpointManager {
         Ok(id)
     }
 
+    /// Add a watchpoint that breaks when a variable or struct field is
+    /// written
+    ///
+    /// `path` is a variable name optionally followed by fields, such as
+    /// `player.health`.
+    pub fn add_watchpoint(&self, path: String) -> Result<BreakpointId> {
+        if !Breakpoint::is_valid_watch_path(&path) {
+            return Err(Error::invalid_conversion(format!(
+                "Cannot watch '{}': expected a variable or field such as 'player.health'",
+                path
+            )));
+        }
+
+        let id = self.get_next_id()?;
+        let breakpoint = Breakpoint::watch(id, path);
+
+        {
+            let mut breakpoints = self
+                .breakpoints
+                .write()
+                .map_err(|_| Error::lock_poisoned("Failed to acquire write lock on breakpoints"))?;
+            breakpoints.insert(id, breakpoint);
+        }
+
+        self.save();
+        Ok(id)
+    }
+
     /// Remove a breakpoint by ID
     pub fn remove_breakpoint(&self, id: BreakpointId) -> Result<()> {
         let breakpoint = {
@@ -188,6 +306,7 @@ impl BreakpointManager {
             _ => {} // Other types don't have special indexes
         }
 
+        self.save();
         Ok(())
     }
 
@@ -269,125 +388,66 @@ impl BreakpointManager {
 
     /// Enable a breakpoint
     pub fn enable_breakpoint(&self, id: BreakpointId) -> Result<()> {
-        let mut breakpoints = self
-            .breakpoints
-            .write()
-            .map_err(|_| Error::lock_poisoned("Failed to acquire write lock on breakpoints"))?;
-        if let Some(breakpoint) = breakpoints.get_mut(&id) {
-            breakpoint.enable();
-            Ok(())
-        } else {
-            Err(Error::key_not_found(format!("Breakpoint {}", id)))
-        }
+        self.update(id, Breakpoint::enable)
     }
 
     /// Disable a breakpoint
     pub fn disable_breakpoint(&self, id: BreakpointId) -> Result<()> {
-        let mut breakpoints = self
-            .breakpoints
-            .write()
-            .map_err(|_| Error::lock_poisoned("Failed to acquire write lock on breakpoints"))?;
-        if let Some(breakpoint) = breakpoints.get_mut(&id) {
-            breakpoint.disable();
-            Ok(())
-        } else {
-            Err(Error::key_not_found(format!("Breakpoint {}", id)))
-        }
+        self.update(id, Breakpoint::disable)
     }
 
     /// Toggle a breakpoint's enabled state
     pub fn toggle_breakpoint(&self, id: BreakpointId) -> Result<bool> {
-        let mut breakpoints = self
-            .breakpoints
-            .write()
-            .map_err(|_| Error::lock_poisoned("Failed to acquire write lock on breakpoints"))?;
-        if let Some(breakpoint) = breakpoints.get_mut(&id) {
+        self.update(id, |breakpoint| {
             breakpoint.toggle();
-            Ok(breakpoint.enabled)
-        } else {
-            Err(Error::key_not_found(format!("Breakpoint {}", id)))
-        }
+            breakpoint.enabled
+        })
     }
 
     /// Set a condition on a breakpoint
+    ///
+    /// Fails if the condition is not a valid expression or hit count test.
     pub fn set_breakpoint_condition(
         &self,
         id: BreakpointId,
         condition: BreakpointCondition,
     ) -> Result<()> {
-        let mut breakpoints = self
-            .breakpoints
-            .write()
-            .map_err(|_| Error::lock_poisoned("Failed to acquire write lock on breakpoints"))?;
-        if let Some(breakpoint) = breakpoints.get_mut(&id) {
-            breakpoint.set_condition(condition);
-            Ok(())
-        } else {
-            Err(Error::key_not_found(format!("Breakpoint {}", id)))
-        }
+        condition.validate().map_err(|e| {
+            Error::parse(format!(
+                "Invalid breakpoint condition '{}': {}",
+                condition.expression, e
+            ))
+        })?;
+
+        self.update(id, |breakpoint| breakpoint.set_condition(condition))
     }
 
     /// Clear a condition from a breakpoint
     pub fn clear_breakpoint_condition(&self, id: BreakpointId) -> Result<()> {
-        let mut breakpoints = self
-            .breakpoints
-            .write()
-            .map_err(|_| Error::lock_poisoned("Failed to acquire write lock on breakpoints"))?;
-        if let Some(breakpoint) = breakpoints.get_mut(&id) {
-            breakpoint.clear_condition();
-            Ok(())
-        } else {
-            Err(Error::key_not_found(format!("Breakpoint {}", id)))
-        }
+        self.update(id, Breakpoint::clear_condition)
     }
 
     /// Check if execution should break at the given location
     ///
     /// This is the main runtime integration point. It's called frequently
-    /// during execution, so it's optimized for performance.
+    /// during execution, so it's optimized for performance. Without a file,
+    /// only function breakpoints can match.
     pub fn should_break_at_location(
         &self,
         location: SourceLocation,
         function_name: Option<&str>,
     ) -> bool {
-        // Quick check: if no breakpoints are set, return false immediately
-        let breakpoints = self
-            .breakpoints
-            .read()
-            .map_err(|_| Error::lock_poisoned("Failed to acquire read lock on breakpoints"));
-
-        let breakpoints = match breakpoints {
-            Ok(bps) => bps,
+        let Some(func_name) = function_name else {
+            return false;
+        };
+        let ids = match self.function_breakpoints_by_name.read() {
+            Ok(index) => index.get(func_name).cloned().unwrap_or_default(),
             Err(_) => return false, // Return false on lock failure
         };
-
-        if breakpoints.is_empty() {
-            return false;
-        }
-
-        // Check all breakpoints to see if any match
-        for breakpoint in breakpoints.values() {
-            if breakpoint.matches_location(location, None, function_name) {
-                // If there's a condition, evaluate it
-                if let Some(condition) = &breakpoint.condition {
-                    let context = BreakpointEvaluationContext {
-                        variables: std::collections::HashMap::new(), // TODO: Fill with actual variables
-                        location,
-                        function_name: function_name.map(String::from),
-                    };
-
-                    match condition.evaluate(&context) {
-                        Ok(true) => return true,
-                        Ok(false) => continue,
-                        Err(_) => continue, // Skip breakpoint if condition evaluation fails
-                    }
-                } else {
-                    return true;
-                }
-            }
-        }
-
-        false
+        let matches = |bp: &Breakpoint| bp.matches_location(location, None, function_name);
+        !self
+            .check_breakpoints(&ids, &matches, location, function_name, &HashMap::new)
+            .is_empty()
     }
 
     /// Check if execution should break at the given location in a specific file
@@ -397,98 +457,201 @@ impl BreakpointManager {
         location: SourceLocation,
         function_name: Option<&str>,
     ) -> bool {
-        // Check line breakpoints for this file first (most common case)
-        let line_index = self.line_breakpoints_by_file.read().map_err(|_| {
-            Error::lock_poisoned("Failed to acquire read lock on line breakpoints index")
-        });
+        self.should_break_in_scope(file, location, function_name, &HashMap::new)
+    }
 
-        let line_index = match line_index {
-            Ok(idx) => idx,
+    /// Check if execution should break at the given location in a specific
+    /// file, evaluating conditions against the variables in scope
+    ///
+    /// `variables` is only called when a conditional breakpoint matches, so
+    /// collecting them costs nothing on unconditional paths.
+    pub fn should_break_in_scope(
+        &self,
+        file: &str,
+        location: SourceLocation,
+        function_name: Option<&str>,
+        variables: &dyn Fn() -> HashMap<String, Value>,
+    ) -> bool {
+        // Copy the candidate ids so no index lock is held with the
+        // breakpoints lock
+        let mut ids = match self.line_breakpoints_by_file.read() {
+            Ok(index) => index.get(file).cloned().unwrap_or_default(),
             Err(_) => return false, // Return false on lock failure
         };
-
-        if let Some(breakpoint_ids) = line_index.get(file) {
-            let breakpoints = self
-                .breakpoints
-                .read()
-                .map_err(|_| Error::lock_poisoned("Failed to acquire read lock on breakpoints"));
-
-            let breakpoints = match breakpoints {
-                Ok(bps) => bps,
-                Err(_) => return false, // Return false on lock failure
-            };
-
-            for id in breakpoint_ids {
-                if let Some(breakpoint) = breakpoints.get(id) {
-                    if breakpoint.matches_location(location, Some(file), function_name) {
-                        // Handle conditions
-                        if let Some(condition) = &breakpoint.condition {
-                            let context = BreakpointEvaluationContext {
-                                variables: std::collections::HashMap::new(),
-                                location,
-                                function_name: function_name.map(String::from),
-                            };
-
-                            match condition.evaluate(&context) {
-                                Ok(true) => return true,
-                                Ok(false) => continue,
-                                Err(_) => continue,
-                            }
-                        } else {
-                            return true;
-                        }
-                    }
-                }
+        if let Some(func_name) = function_name {
+            match self.function_breakpoints_by_name.read() {
+                Ok(index) => ids.extend(index.get(func_name).into_iter().flatten()),
+                Err(_) => return false,
             }
         }
+        if ids.is_empty() {
+            return false;
+        }
+        let matches = |bp: &Breakpoint| bp.matches_location(location, Some(file), function_name);
+        !self
+            .check_breakpoints(&ids, &matches, location, function_name, variables)
+            .is_empty()
+    }
 
-        // Check function breakpoints if we have a function name
-        if let Some(func_name) = function_name {
-            let function_index = self.function_breakpoints_by_name.read().map_err(|_| {
-                Error::lock_poisoned("Failed to acquire read lock on function breakpoints index")
-            });
+    /// Paths of the enabled watchpoints affected by a write to `target`, a
+    /// variable or field path
+    pub fn watched_paths(&self, target: &str) -> Vec<String> {
+        let breakpoints = match self.breakpoints.read() {
+            Ok(bps) => bps,
+            Err(_) => return Vec::new(),
+        };
+        let mut paths: Vec<String> = breakpoints
+            .values()
+            .filter(|bp| bp.matches_write(target))
+            .filter_map(|bp| bp.watch_path().map(String::from))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        paths
+    }
 
-            let function_index = match function_index {
-                Ok(idx) => idx,
-                Err(_) => return false, // Return false on lock failure
-            };
+    /// Count a write to `target` against the watchpoints it affects and
+    /// return the ones that break, lowest id first
+    ///
+    /// Conditions are evaluated after the write, so they see the new value.
+    pub fn watchpoints_hit(
+        &self,
+        target: &str,
+        location: SourceLocation,
+        function_name: Option<&str>,
+        variables: &dyn Fn() -> HashMap<String, Value>,
+    ) -> Vec<Breakpoint> {
+        let ids = match self.breakpoints.read() {
+            Ok(bps) => bps
+                .values()
+                .filter(|bp| bp.matches_write(target))
+                .map(|bp| bp.id)
+                .collect(),
+            Err(_) => return Vec::new(),
+        };
+        let matches = |bp: &Breakpoint| bp.matches_write(target);
+        self.check_breakpoints(&ids, &matches, location, function_name, variables)
+    }
 
-            if let Some(breakpoint_ids) = function_index.get(func_name) {
-                let breakpoints = self.breakpoints.read().map_err(|_| {
-                    Error::lock_poisoned("Failed to acquire read lock on breakpoints")
-                });
+    /// Breakpoints that would stop at a statement replayed from an
+    /// execution recording, lowest id first
+    ///
+    /// Nothing is counted. Expression conditions are evaluated against
+    /// `variables`; hit-count tests can't be answered for a past statement
+    /// and are taken to hold.
+    pub fn replayed_breakpoints(
+        &self,
+        file: &str,
+        location: SourceLocation,
+        function_name: Option<&str>,
+        variables: &HashMap<String, Value>,
+    ) -> Vec<Breakpoint> {
+        self.replayed(
+            &|bp| bp.matches_location(location, Some(file), function_name),
+            variables,
+        )
+    }
 
-                let breakpoints = match breakpoints {
-                    Ok(bps) => bps,
-                    Err(_) => return false, // Return false on lock failure
-                };
+    /// Enabled watchpoints whose value differs between two replayed
+    /// statements, lowest id first
+    ///
+    /// Conditions are evaluated against `after`, as for a live write.
+    pub fn replayed_watchpoints(
+        &self,
+        before: &HashMap<String, Value>,
+        after: &HashMap<String, Value>,
+    ) -> Vec<Breakpoint> {
+        self.replayed(
+            &|bp| {
+                bp.enabled
+                    && bp
+                        .watch_path()
+                        .is_some_and(|path| path_value(before, path) != path_value(after, path))
+            },
+            after,
+        )
+    }
 
-                for id in breakpoint_ids {
-                    if let Some(breakpoint) = breakpoints.get(id) {
-                        if breakpoint.matches_location(location, Some(file), Some(func_name)) {
-                            // Handle conditions
-                            if let Some(condition) = &breakpoint.condition {
-                                let context = BreakpointEvaluationContext {
-                                    variables: std::collections::HashMap::new(),
-                                    location,
-                                    function_name: Some(func_name.to_string()),
-                                };
-
-                                match condition.evaluate(&context) {
-                                    Ok(true) => return true,
-                                    Ok(false) => continue,
-                                    Err(_) => continue,
-                                }
-                            } else {
-                                return true;
-                            }
-                        }
+    fn replayed(
+        &self,
+        matches: &dyn Fn(&Breakpoint) -> bool,
+        variables: &HashMap<String, Value>,
+    ) -> Vec<Breakpoint> {
+        let breakpoints = match self.breakpoints.read() {
+            Ok(bps) => bps,
+            Err(_) => return Vec::new(),
+        };
+        let mut replayed: Vec<Breakpoint> = breakpoints
+            .values()
+            .filter(|bp| matches(bp))
+            .filter(|bp| match &bp.condition {
+                Some(condition) => match Condition::parse(&condition.expression) {
+                    Ok(condition @ Condition::Expression(_)) => {
+                        condition.holds(variables, 0).unwrap_or(true)
                     }
+                    _ => true,
+                },
+                None => true,
+            })
+            .cloned()
+            .collect();
+        replayed.sort_by_key(|bp| bp.id);
+        replayed
+    }
+
+    /// Count a hit of each candidate breakpoint that `matches` and return
+    /// the ones that break, lowest id first
+    fn check_breakpoints(
+        &self,
+        ids: &HashSet<BreakpointId>,
+        matches: &dyn Fn(&Breakpoint) -> bool,
+        location: SourceLocation,
+        function_name: Option<&str>,
+        variables: &dyn Fn() -> HashMap<String, Value>,
+    ) -> Vec<Breakpoint> {
+        let mut breakpoints = match self.breakpoints.write() {
+            Ok(bps) => bps,
+            Err(_) => return Vec::new(), // Don't break on lock failure
+        };
+
+        let mut ids: Vec<_> = ids.iter().collect();
+        ids.sort();
+        let mut stopping = Vec::new();
+        for id in ids {
+            let Some(breakpoint) = breakpoints.get_mut(id) else {
+                continue;
+            };
+            if !matches(breakpoint) {
+                continue;
+            }
+            breakpoint.hit();
+
+            let Some(condition) = &breakpoint.condition else {
+                stopping.push(breakpoint.clone());
+                continue;
+            };
+            let context = BreakpointEvaluationContext {
+                variables: variables(),
+                location,
+                function_name: function_name.map(String::from),
+                hit_count: breakpoint.hit_count,
+            };
+            let stop = match condition.evaluate(&context) {
+                Ok(holds) => holds,
+                Err(error) => {
+                    // Stop anyway so a broken condition gets noticed
+                    eprintln!(
+                        "Breakpoint {}: cannot evaluate condition '{}': {}",
+                        breakpoint.id, condition.expression, error
+                    );
+                    true
                 }
+            };
+            if stop {
+                stopping.push(breakpoint.clone());
             }
         }
-
-        false
+        stopping
     }
 
     /// Record a breakpoint hit
@@ -578,6 +741,7 @@ impl BreakpointManager {
             function_index.clear();
         }
 
+        self.save();
         Ok(())
     }
 
@@ -604,6 +768,7 @@ impl BreakpointManager {
                     function_breakpoints: 0,
                     address_breakpoints: 0,
                     exception_breakpoints: 0,
+                    watchpoints: 0,
                     conditional_breakpoints: 0,
                     total_hits: 0,
                 };
@@ -618,6 +783,7 @@ impl BreakpointManager {
             function_breakpoints: 0,
             address_breakpoints: 0,
             exception_breakpoints: 0,
+            watchpoints: 0,
             conditional_breakpoints: 0,
             total_hits: history.len(),
         };
@@ -638,6 +804,7 @@ impl BreakpointManager {
                 BreakpointType::Function { .. } => stats.function_breakpoints += 1,
                 BreakpointType::Address { .. } => stats.address_breakpoints += 1,
                 BreakpointType::Exception { .. } => stats.exception_breakpoints += 1,
+                BreakpointType::Watch { .. } => stats.watchpoints += 1,
             }
         }
 
@@ -679,6 +846,8 @@ pub struct BreakpointStatistics {
     pub address_breakpoints: usize,
     /// Number of exception breakpoints
     pub exception_breakpoints: usize,
+    /// Number of watchpoints
+    pub watchpoints: usize,
     /// Number of conditional breakpoints
     pub conditional_breakpoints: usize,
     /// Total number of hits recorded
@@ -695,6 +864,7 @@ impl std::fmt::Display for BreakpointStatistics {
         writeln!(f, "  Function breakpoints: {}", self.function_breakpoints)?;
         writeln!(f, "  Address breakpoints: {}", self.address_breakpoints)?;
         writeln!(f, "  Exception breakpoints: {}", self.exception_breakpoints)?;
+        writeln!(f, "  Watchpoints: {}", self.watchpoints)?;
         writeln!(
             f,
             "  Conditional breakpoints: {}",
@@ -813,6 +983,70 @@ mod tests {
         assert!(bp.condition.is_none());
     }
 
+    #[test]
+    fn test_conditions_use_scope_and_hit_count() {
+        let manager = BreakpointManager::new();
+        let location = SourceLocation::new(10, 1, 0);
+        let scope = |x: i32| move || HashMap::from([("x".to_string(), Value::I32(x))]);
+
+        let id = manager
+            .add_line_breakpoint("test.script".to_string(), 10)
+            .unwrap();
+        let condition = BreakpointCondition::new("x > 5".to_string(), true);
+        manager.set_breakpoint_condition(id, condition).unwrap();
+        assert!(!manager.should_break_in_scope("test.script", location, None, &scope(3)));
+        assert!(manager.should_break_in_scope("test.script", location, None, &scope(6)));
+
+        let condition = BreakpointCondition::new(">= 4".to_string(), true);
+        manager.set_breakpoint_condition(id, condition).unwrap();
+        assert!(!manager.should_break_at_file_location("test.script", location, None));
+        assert!(manager.should_break_at_file_location("test.script", location, None));
+        assert_eq!(manager.get_breakpoint(id).unwrap().hit_count, 4);
+
+        let invalid = BreakpointCondition::new("x >".to_string(), true);
+        assert!(manager.set_breakpoint_condition(id, invalid).is_err());
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let manager = BreakpointManager::new();
+        let location = SourceLocation::new(3, 1, 0);
+        let scope = |hp: i32| move || HashMap::from([("hp".to_string(), Value::I32(hp))]);
+
+        let player = manager.add_watchpoint("player".to_string()).unwrap();
+        let hp = manager.add_watchpoint("hp".to_string()).unwrap();
+        assert!(manager.add_watchpoint("player.".to_string()).is_err());
+        assert!(manager.add_watchpoint("items[0]".to_string()).is_err());
+
+        assert_eq!(manager.watched_paths("player.health"), vec!["player"]);
+        assert!(manager.watched_paths("score").is_empty());
+        let hits = manager.watchpoints_hit("player.health", location, None, &HashMap::new);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, player);
+
+        let condition = BreakpointCondition::new("hp < 10".to_string(), true);
+        manager.set_breakpoint_condition(hp, condition).unwrap();
+        assert!(manager
+            .watchpoints_hit("hp", location, None, &scope(50))
+            .is_empty());
+        assert_eq!(
+            manager.watchpoints_hit("hp", location, None, &scope(5))[0].id,
+            hp
+        );
+        assert_eq!(manager.get_breakpoint(hp).unwrap().hit_count, 2);
+
+        // Replaying a recording compares values and counts nothing
+        let before = HashMap::from([("hp".to_string(), Value::I32(12))]);
+        let after = HashMap::from([("hp".to_string(), Value::I32(8))]);
+        assert_eq!(manager.replayed_watchpoints(&before, &after)[0].id, hp);
+        assert!(manager.replayed_watchpoints(&after, &after).is_empty());
+        assert_eq!(manager.get_breakpoint(hp).unwrap().hit_count, 2);
+
+        // Watchpoints never match a location
+        assert!(!manager.should_break_at_file_location("test.script", location, None));
+        assert_eq!(manager.get_statistics().watchpoints, 2);
+    }
+
     #[test]
     fn test_should_break_at_location() {
         let manager = BreakpointManager::new();
@@ -913,6 +1147,44 @@ mod tests {
         assert_eq!(manager.get_all_breakpoints().len(), 0);
     }
 
+    #[test]
+    fn test_breakpoints_persist_across_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(crate::debugger::persist::BREAKPOINTS_FILE);
+
+        let manager = BreakpointManager::new();
+        manager.persist_to(Some(path.clone()));
+        let line = manager
+            .add_line_breakpoint("main.script".to_string(), 12)
+            .unwrap();
+        manager
+            .set_breakpoint_condition(line, BreakpointCondition::new("x > 1".to_string(), true))
+            .unwrap();
+        let function = manager
+            .add_function_breakpoint("main".to_string(), None)
+            .unwrap();
+        manager.disable_breakpoint(function).unwrap();
+        manager.add_watchpoint("player.health".to_string()).unwrap();
+        manager.add_address_breakpoint(0x40).unwrap();
+        assert!(path.exists());
+
+        // A new session picks up everything but the address breakpoint
+        let restored = BreakpointManager::new();
+        assert_eq!(restored.restore_from(&path).unwrap(), 3);
+        let breakpoints = restored.get_breakpoints_for_file("main.script");
+        assert_eq!(breakpoints.len(), 1);
+        assert_eq!(
+            breakpoints[0].condition.as_ref().unwrap().expression,
+            "x > 1"
+        );
+        assert!(!restored.get_breakpoints_for_function("main")[0].enabled);
+        assert_eq!(restored.watched_paths("player").len(), 1);
+
+        // Removing the last saved breakpoint removes the file
+        manager.clear_all_breakpoints().unwrap();
+        assert!(!path.exists());
+    }
+
     #[test]
     fn test_invalid_breakpoint_creation() {
         let manager = BreakpointManager::new();
@@ -1,20 +1,35 @@
 //! Debugger module for the Script programming language
 //!
 //! This module provides comprehensive debugging capabilities including:
-//! - Breakpoint management (line, function, conditional breakpoints)
-//! - Runtime execution control and stepping
+//! - Breakpoint management (line, function, conditional breakpoints and
+//!   watchpoints on variable writes), saved between sessions
+//! - Runtime execution control and stepping, including stepping back
+//!   through a recording of the statements executed
 //! - Integration with the runtime execution system
 //! - Thread-safe operations for concurrent debugging
+//! - A Debug Adapter Protocol server so editors can drive the debugger
 //!
 //! The debugger is designed to integrate seamlessly with the Script runtime
 //! and provide hooks for IDE integration and command-line debugging.
 
 pub mod breakpoint;
+pub mod condition;
+pub mod dap;
+pub mod interpreter;
 pub mod manager;
+pub mod persist;
+pub mod recording;
 pub mod runtime_hooks;
 
 pub use breakpoint::{Breakpoint, BreakpointCondition, BreakpointId, BreakpointType};
+pub use dap::DapServer;
+pub use interpreter::{
+    DebugInterpreter, Frame, InterpreterState, PauseReason, PauseState, StepAction, StepController,
+    WatchHit,
+};
 pub use manager::BreakpointManager;
+pub use persist::{breakpoints_path, BREAKPOINTS_FILE};
+pub use recording::{ExecutionRecording, RecordedStep, VariableChange, DEFAULT_RECORDING_CAPACITY};
 pub use runtime_hooks::{
     DebugEvent, DebugHook, DebuggerState, ExecutionContext, RuntimeDebugInterface,
 };
@@ -79,8 +94,19 @@ static DEBUGGER: RwLock<Option<Arc<Debugger>>> = RwLock::new(None);
 /// Initialize the global debugger
 ///
 /// This must be called before any debugging operations can be performed.
-/// Returns an error if the debugger is already initialized.
+/// Returns an error if the debugger is already initialized. Breakpoints
+/// saved in the project's `.script-debug.toml` are restored, and changes
+/// to them are saved there again.
 pub fn initialize_debugger() -> Result<()> {
+    initialize_debugger_with(true)
+}
+
+/// Initialize the global debugger, restoring and saving breakpoints only
+/// when `restore_breakpoints` is set
+///
+/// Without it the debugger starts with no breakpoints and leaves
+/// `.script-debug.toml` untouched.
+pub fn initialize_debugger_with(restore_breakpoints: bool) -> Result<()> {
     let mut debugger_lock = DEBUGGER
         .write()
         .map_err(|_| Error::lock_poisoned("Failed to acquire write lock on global debugger"))?;
@@ -89,6 +115,17 @@ pub fn initialize_debugger() -> Result<()> {
     }
 
     let debugger = Arc::new(Debugger::new());
+    if restore_breakpoints {
+        let dir = std::env::current_dir()?;
+        let path = persist::breakpoints_path(&dir);
+        let manager = debugger.breakpoint_manager();
+        if path.is_file() {
+            if let Err(e) = manager.restore_from(&path) {
+                eprintln!("Warning: failed to restore breakpoints: {}", e);
+            }
+        }
+        manager.persist_to(Some(path));
+    }
     *debugger_lock = Some(debugger);
 
     Ok(())
@@ -307,7 +344,8 @@ impl Debugger {
         let function_info = function_name
             .map(|name| format!(" in function '{}'", name))
             .unwrap_or_default();
-        println!("Breakpoint hit at {}{}", location, function_info);
+        // Stdout belongs to the program (or to the DAP client)
+        eprintln!("Breakpoint hit at {}{}", location, function_info);
 
         Ok(())
     }
@@ -363,7 +401,7 @@ impl Debugger {
     /// This method prepares the debugger for debugging a specific program.
     /// It stores the source code and file information for later use.
     pub fn load_program(
-        &mut self,
+        &self,
         _program: crate::parser::Program,
         source: String,
         file_name: Option<String>,
@@ -381,7 +419,7 @@ impl Debugger {
     ///
     /// This method starts an interactive debugging session where the user
     /// can set breakpoints, step through code, inspect variables, etc.
-    pub fn start_session(&mut self) -> Result<()> {
+    pub fn start_session(&self) -> Result<()> {
         self.set_enabled(true);
         self.set_state(DebuggerState::Running);
 
@@ -495,11 +533,11 @@ mod tests {
 
         // Test initialization
         assert!(!is_debugger_initialized());
-        assert!(initialize_debugger().is_ok());
+        assert!(initialize_debugger_with(false).is_ok());
         assert!(is_debugger_initialized());
 
         // Test double initialization
-        let err = initialize_debugger().unwrap_err();
+        let err = initialize_debugger_with(false).unwrap_err();
         assert!(err.to_string().contains("already initialized"));
 
         // Test getting debugger
@@ -518,7 +556,7 @@ mod tests {
     #[test]
     fn test_debugger_sessions() {
         let _ = shutdown_debugger();
-        initialize_debugger().unwrap();
+        initialize_debugger_with(false).unwrap();
 
         let debugger = get_debugger().unwrap();
 
@@ -548,7 +586,7 @@ mod tests {
     #[test]
     fn test_debugger_state_management() {
         let _ = shutdown_debugger();
-        initialize_debugger().unwrap();
+        initialize_debugger_with(false).unwrap();
 
         let debugger = get_debugger().unwrap();
 
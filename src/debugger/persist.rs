@@ -0,0 +1,178 @@
+//! Breakpoints saved between debug sessions
+//!
+//! Line and function breakpoints and watchpoints are kept in
+//! `.script-debug.toml` in the project root, the nearest directory with a
+//! `script.toml`, so that `script debug break main` still applies the next
+//! time the debugger starts:
+//!
+//! ```toml
+//! [[breakpoint]]
+//! file = "src/main.script"
+//! line = 12
+//! condition = "player.health < 10"
+//!
+//! [[breakpoint]]
+//! function = "update"
+//! enabled = false
+//!
+//! [[breakpoint]]
+//! watch = "player.health"
+//! ```
+//!
+//! Hit counts are not saved, and address and exception breakpoints only
+//! last for the session that set them.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::debugger::breakpoint::{Breakpoint, BreakpointType};
+use crate::error::{Error, Result};
+
+/// Name of the file breakpoints are saved to
+pub const BREAKPOINTS_FILE: &str = ".script-debug.toml";
+
+/// A breakpoint as written to `.script-debug.toml`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedBreakpoint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(default = "enabled", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SavedBreakpoints {
+    #[serde(default, rename = "breakpoint")]
+    breakpoints: Vec<SavedBreakpoint>,
+}
+
+impl SavedBreakpoint {
+    /// Describe a breakpoint for saving, `None` for the kinds that are not
+    /// saved
+    pub fn from_breakpoint(breakpoint: &Breakpoint) -> Option<Self> {
+        let mut saved = SavedBreakpoint {
+            file: None,
+            line: None,
+            function: None,
+            watch: None,
+            condition: breakpoint
+                .condition
+                .as_ref()
+                .map(|condition| condition.expression.clone()),
+            enabled: breakpoint.enabled,
+        };
+        match &breakpoint.breakpoint_type {
+            BreakpointType::Line { file, line } => {
+                saved.file = Some(file.clone());
+                saved.line = Some(*line);
+            }
+            BreakpointType::Function { name, file } => {
+                saved.function = Some(name.clone());
+                saved.file = file.clone();
+            }
+            BreakpointType::Watch { path } => saved.watch = Some(path.clone()),
+            BreakpointType::Address { .. } | BreakpointType::Exception { .. } => return None,
+        }
+        Some(saved)
+    }
+}
+
+/// Where breakpoints are saved for the project containing `dir`: next to
+/// the nearest `script.toml`, or in `dir` itself outside a project
+pub fn breakpoints_path(dir: &Path) -> PathBuf {
+    let root = dir
+        .ancestors()
+        .find(|ancestor| ancestor.join("script.toml").is_file())
+        .unwrap_or(dir);
+    root.join(BREAKPOINTS_FILE)
+}
+
+/// Write the breakpoints that can be saved to `path`, lowest id first
+///
+/// The file is removed once there is nothing left to save.
+pub fn save_breakpoints(path: &Path, breakpoints: &[Breakpoint]) -> Result<()> {
+    let mut breakpoints: Vec<&Breakpoint> = breakpoints.iter().collect();
+    breakpoints.sort_by_key(|breakpoint| breakpoint.id);
+    let saved = SavedBreakpoints {
+        breakpoints: breakpoints
+            .into_iter()
+            .filter_map(SavedBreakpoint::from_breakpoint)
+            .collect(),
+    };
+
+    if saved.breakpoints.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+    let text = toml::to_string(&saved)
+        .map_err(|e| Error::internal(format!("Cannot serialize breakpoints: {}", e)))?;
+    fs::write(path, text)?;
+    Ok(())
+}
+
+/// Read the breakpoints saved in `path`
+pub fn load_breakpoints(path: &Path) -> Result<Vec<SavedBreakpoint>> {
+    let text = fs::read_to_string(path)?;
+    let saved: SavedBreakpoints = toml::from_str(&text)
+        .map_err(|e| Error::parse(format!("Invalid {}: {}", path.display(), e)))?;
+    Ok(saved.breakpoints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::debugger::breakpoint::BreakpointCondition;
+
+    #[test]
+    fn test_saved_breakpoints_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("script.toml"), "").unwrap();
+        let nested = dir.path().join("src");
+        fs::create_dir(&nested).unwrap();
+        let path = breakpoints_path(&nested);
+        assert_eq!(path, dir.path().join(BREAKPOINTS_FILE));
+
+        let mut line = Breakpoint::line(2, "main.script".to_string(), 12);
+        line.set_condition(BreakpointCondition::new("x > 1".to_string(), true));
+        let mut function = Breakpoint::function(1, "update".to_string(), None);
+        function.disable();
+        let breakpoints = vec![
+            line,
+            function,
+            Breakpoint::address(3, 0x40),
+            Breakpoint::watch(4, "player.health".to_string()),
+        ];
+        save_breakpoints(&path, &breakpoints).unwrap();
+
+        let loaded = load_breakpoints(&path).unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[0].function.as_deref(), Some("update"));
+        assert!(!loaded[0].enabled);
+        assert_eq!(loaded[1].line, Some(12));
+        assert_eq!(loaded[1].condition.as_deref(), Some("x > 1"));
+        assert_eq!(loaded[2].watch.as_deref(), Some("player.health"));
+
+        save_breakpoints(&path, &[]).unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,258 @@
+//! Execution recording for reverse debugging
+//!
+//! With recording enabled, the interpreter logs every statement it reaches
+//! in a ring buffer, together with the visible variables that changed since
+//! the statement before. Undoing those changes from the newest statement
+//! backwards rebuilds the variables at any recorded statement, which is
+//! what `step-back` and `reverse-continue` show. Only the most recent
+//! statements are kept; older ones are dropped as new ones arrive.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::runtime::value::Value;
+use crate::source::SourceLocation;
+
+/// Statements kept when no capacity is given
+pub const DEFAULT_RECORDING_CAPACITY: usize = 10_000;
+
+/// A visible variable that changed between two recorded statements
+#[derive(Debug, Clone)]
+pub struct VariableChange {
+    pub name: String,
+    /// Value at the previous statement, `None` if it wasn't visible
+    pub before: Option<Value>,
+    /// Value at this statement, `None` if it is no longer visible
+    pub after: Option<Value>,
+}
+
+/// A statement reached while recording
+#[derive(Debug, Clone)]
+pub struct RecordedStep {
+    /// Number of statements recorded before this one
+    pub index: usize,
+    /// Function and location of each frame on the call stack, outermost
+    /// first
+    pub stack: Vec<(String, SourceLocation)>,
+    /// Variables that changed since the previous statement, by name
+    pub changes: Vec<VariableChange>,
+}
+
+impl RecordedStep {
+    /// Location of the statement
+    pub fn location(&self) -> SourceLocation {
+        self.stack
+            .last()
+            .map(|(_, location)| *location)
+            .unwrap_or_else(SourceLocation::initial)
+    }
+
+    /// Function the statement is in
+    pub fn function(&self) -> &str {
+        self.stack
+            .last()
+            .map(|(function, _)| function.as_str())
+            .unwrap_or("<main>")
+    }
+
+    /// Number of frames on the call stack
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+}
+
+/// Ring buffer of the statements most recently executed
+#[derive(Debug, Clone)]
+pub struct ExecutionRecording {
+    capacity: usize,
+    steps: VecDeque<RecordedStep>,
+    /// Variables visible at the newest step
+    variables: HashMap<String, Value>,
+    recorded: usize,
+}
+
+impl ExecutionRecording {
+    /// Create a recording keeping the last `capacity` statements
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            steps: VecDeque::new(),
+            variables: HashMap::new(),
+            recorded: 0,
+        }
+    }
+
+    /// Log a statement about to execute with the variables visible there
+    pub fn record(
+        &mut self,
+        stack: Vec<(String, SourceLocation)>,
+        variables: HashMap<String, Value>,
+    ) {
+        let mut changes: Vec<VariableChange> = variables
+            .iter()
+            .filter(|(name, value)| self.variables.get(*name) != Some(value))
+            .map(|(name, value)| VariableChange {
+                name: name.clone(),
+                before: self.variables.get(name).cloned(),
+                after: Some(value.clone()),
+            })
+            .collect();
+        changes.extend(
+            self.variables
+                .iter()
+                .filter(|(name, _)| !variables.contains_key(*name))
+                .map(|(name, value)| VariableChange {
+                    name: name.clone(),
+                    before: Some(value.clone()),
+                    after: None,
+                }),
+        );
+        changes.sort_by(|a, b| a.name.cmp(&b.name));
+
+        self.steps.push_back(RecordedStep {
+            index: self.recorded,
+            stack,
+            changes,
+        });
+        if self.steps.len() > self.capacity {
+            self.steps.pop_front();
+        }
+        self.variables = variables;
+        self.recorded += 1;
+    }
+
+    /// Forget everything recorded so far
+    pub fn clear(&mut self) {
+        self.steps.clear();
+        self.variables.clear();
+        self.recorded = 0;
+    }
+
+    /// Number of statements kept
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Number of statements recorded, including those dropped
+    pub fn recorded(&self) -> usize {
+        self.recorded
+    }
+
+    /// The statement at `position`, counting from the oldest kept
+    pub fn step(&self, position: usize) -> Option<&RecordedStep> {
+        self.steps.get(position)
+    }
+
+    /// Statements kept, oldest first
+    pub fn steps(&self) -> impl Iterator<Item = &RecordedStep> {
+        self.steps.iter()
+    }
+
+    /// Variables visible at the statement at `position`
+    pub fn variables_at(&self, position: usize) -> Option<HashMap<String, Value>> {
+        if position >= self.steps.len() {
+            return None;
+        }
+        let mut variables = self.variables.clone();
+        for step in self.steps.iter().skip(position + 1).rev() {
+            undo(&mut variables, step);
+        }
+        Some(variables)
+    }
+
+    /// Changes to `name`, oldest first, with the statement that saw the
+    /// new value
+    pub fn history(&self, name: &str) -> Vec<(&RecordedStep, &VariableChange)> {
+        self.steps
+            .iter()
+            .filter_map(|step| {
+                step.changes
+                    .iter()
+                    .find(|change| change.name == name)
+                    .map(|change| (step, change))
+            })
+            .collect()
+    }
+}
+
+/// Turn the variables at `step` into those at the statement before it
+pub(super) fn undo(variables: &mut HashMap<String, Value>, step: &RecordedStep) {
+    for change in &step.changes {
+        match &change.before {
+            Some(value) => {
+                variables.insert(change.name.clone(), value.clone());
+            }
+            None => {
+                variables.remove(&change.name);
+            }
+        }
+    }
+}
+
+/// Look up a variable or a field path such as `player.health`
+pub(super) fn path_value(variables: &HashMap<String, Value>, path: &str) -> Option<Value> {
+    let mut parts = path.split('.');
+    let mut value = variables.get(parts.next()?)?.clone();
+    for field in parts {
+        value = match &value {
+            Value::Object(fields) => (**fields.get(field)?).clone(),
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(line: usize) -> Vec<(String, SourceLocation)> {
+        vec![("<main>".to_string(), SourceLocation::new(line, 1, 0))]
+    }
+
+    fn variables(pairs: &[(&str, i32)]) -> HashMap<String, Value> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), Value::I32(*value)))
+            .collect()
+    }
+
+    #[test]
+    fn test_recording_rebuilds_earlier_variables() {
+        let mut recording = ExecutionRecording::new(3);
+        recording.record(at(1), variables(&[]));
+        recording.record(at(2), variables(&[("x", 1)]));
+        recording.record(at(3), variables(&[("x", 2), ("y", 5)]));
+        recording.record(at(4), variables(&[("y", 6)]));
+
+        // The first statement fell out of the buffer
+        assert_eq!(recording.len(), 3);
+        assert_eq!(recording.recorded(), 4);
+        assert_eq!(recording.step(0).unwrap().location().line, 2);
+
+        assert_eq!(recording.variables_at(0), Some(variables(&[("x", 1)])));
+        assert_eq!(
+            recording.variables_at(1),
+            Some(variables(&[("x", 2), ("y", 5)]))
+        );
+        assert_eq!(recording.variables_at(2), Some(variables(&[("y", 6)])));
+        assert_eq!(recording.variables_at(3), None);
+
+        let history: Vec<_> = recording
+            .history("x")
+            .iter()
+            .map(|(step, change)| (step.index, change.after.clone()))
+            .collect();
+        assert_eq!(
+            history,
+            vec![
+                (1, Some(Value::I32(1))),
+                (2, Some(Value::I32(2))),
+                (3, None)
+            ]
+        );
+    }
+}
@@ -150,10 +150,11 @@ impl DebugHook for DefaultDebugHook {
             DebuggerState::Running => {
                 // Check for breakpoints
                 if let Some(file) = &context.file {
-                    if debugger.breakpoint_manager().should_break_at_file_location(
+                    if debugger.breakpoint_manager().should_break_in_scope(
                         file,
                         context.location,
                         context.function_name.as_deref(),
+                        &|| context.local_variables.clone(),
                     ) {
                         // Breakpoint hit
                         if let Err(e) = debugger
@@ -539,7 +540,7 @@ impl ExecutionContext {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::debugger::{initialize_debugger, shutdown_debugger};
+    use crate::debugger::{initialize_debugger_with, shutdown_debugger};
     use crate::runtime::value::Value;
 
     #[test]
@@ -615,7 +616,7 @@ mod tests {
         let _ = shutdown_debugger();
 
         // Initialize debugger
-        initialize_debugger().unwrap();
+        initialize_debugger_with(false).unwrap();
 
         let debugger = get_debugger().unwrap();
         debugger.set_enabled(true);
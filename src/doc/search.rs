@@ -135,6 +135,35 @@ impl SearchEngine {
         self.add_search_terms(&const_doc.name, result);
     }
 
+    /// Add definitions from a project symbol index to the search index
+    ///
+    /// Items already documented keep their doc summary; undocumented items
+    /// fall back to their signature.
+    pub fn add_symbol_index(&mut self, index: &crate::index::SymbolIndex) {
+        use crate::index::IndexedSymbolKind;
+
+        for (_, symbol) in index.symbols() {
+            let kind = match symbol.kind {
+                IndexedSymbolKind::Function => ItemKind::Function,
+                IndexedSymbolKind::Method => ItemKind::Method,
+                IndexedSymbolKind::Struct | IndexedSymbolKind::Enum | IndexedSymbolKind::Trait => {
+                    ItemKind::Type
+                }
+                IndexedSymbolKind::Variable if symbol.exported => ItemKind::Constant,
+                _ => continue,
+            };
+
+            let result = SearchResult {
+                path: symbol.qualified_name.clone(),
+                name: symbol.name.clone(),
+                kind,
+                summary: symbol.signature.clone(),
+            };
+
+            self.add_search_terms(&symbol.name, result);
+        }
+    }
+
     /// Add search terms for an item
     fn add_search_terms(&mut self, name: &str, result: SearchResult) {
         // Index by full name
@@ -286,4 +315,23 @@ mod tests {
         let parts = engine.split_identifier("hello_worldTest");
         assert_eq!(parts, vec!["hello", "world", "Test"]);
     }
+
+    #[test]
+    fn test_symbol_index_results() {
+        let mut index = crate::index::SymbolIndex::new("/project");
+        index
+            .update_file(
+                std::path::Path::new("/project/physics.script"),
+                "fn apply_gravity(dt: f32) {}",
+            )
+            .unwrap();
+
+        let mut engine = SearchEngine::new(&DocDatabase::default());
+        engine.add_symbol_index(&index);
+
+        let results = engine.search("gravity");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].path, "physics::apply_gravity");
+        assert_eq!(results[0].summary, "fn apply_gravity(dt: f32)");
+    }
 }
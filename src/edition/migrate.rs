@@ -0,0 +1,227 @@
+//! Edition migrations for `script fix --edition`
+//!
+//! Moving to a newer edition can turn names into keywords. Migration lexes
+//! each file with the rules of the edition it was written for and prefixes
+//! every identifier that is reserved in the target edition with `r#`, which
+//! keeps it a plain name in both editions. Comments and strings are left
+//! untouched, since the lexer never reports identifiers inside them.
+
+use super::Edition;
+use crate::error::{Error, ErrorKind, Result};
+use crate::lexer::{Lexer, TokenKind};
+use crate::package::PackageManifest;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The result of migrating a single source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Migration {
+    /// The migrated source
+    pub source: String,
+    /// Names rewritten as raw identifiers, in source order
+    pub renamed: Vec<String>,
+}
+
+impl Migration {
+    /// Whether migration changed anything
+    pub fn is_changed(&self) -> bool {
+        !self.renamed.is_empty()
+    }
+}
+
+/// The result of migrating a whole package
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackageMigration {
+    /// The edition the package was written for
+    pub from: Edition,
+    /// The edition the package was migrated to
+    pub to: Edition,
+    /// Files that were rewritten, with the names renamed in each
+    pub changed_files: Vec<(PathBuf, Vec<String>)>,
+}
+
+/// Rewrite `source`, written for edition `from`, so it means the same in
+/// edition `to`
+pub fn migrate_source(source: &str, from: Edition, to: Edition) -> Result<Migration> {
+    let mut lexer = Lexer::new(source)?;
+    lexer.set_edition(from);
+    let (tokens, errors) = lexer.scan_tokens();
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+
+    let mut insertions = Vec::new();
+    let mut renamed = Vec::new();
+    for token in &tokens {
+        let TokenKind::Identifier(name) = &token.kind else {
+            continue;
+        };
+        // Raw identifiers are already names in every edition
+        if token.lexeme.starts_with("r#") {
+            continue;
+        }
+        if to.is_reserved(name) && !from.is_reserved(name) {
+            insertions.push(token.span.start.byte_offset);
+            renamed.push(name.clone());
+        }
+    }
+
+    let mut migrated = String::with_capacity(source.len() + insertions.len() * 2);
+    let mut copied = 0;
+    for offset in insertions {
+        migrated.push_str(&source[copied..offset]);
+        migrated.push_str("r#");
+        copied = offset;
+    }
+    migrated.push_str(&source[copied..]);
+
+    Ok(Migration {
+        source: migrated,
+        renamed,
+    })
+}
+
+/// Migrate the package rooted at `root` to edition `to`
+///
+/// Every `.script` file under `root` is rewritten in place, then the
+/// `edition` in `script.toml` is updated. Nothing is written if any file
+/// fails to lex.
+pub fn migrate_package(root: &Path, to: Edition) -> Result<PackageMigration> {
+    let manifest_path = root.join("script.toml");
+    let manifest = PackageManifest::from_file(&manifest_path)?;
+    let from = Edition::parse(&manifest.package.edition)?;
+    if to < from {
+        return Err(Error::new(
+            ErrorKind::Configuration,
+            format!(
+                "Cannot migrate from edition {} back to edition {}",
+                from, to
+            ),
+        ));
+    }
+
+    let mut changed_files = Vec::new();
+    let mut rewrites = Vec::new();
+    for entry in walkdir::WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| !crate::index::is_ignored_dir(e.path()))
+    {
+        let entry = entry.map_err(|e| {
+            Error::new(
+                ErrorKind::FileError,
+                format!("Failed to walk project directory: {}", e),
+            )
+        })?;
+        let path = entry.path();
+        if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some("script") {
+            continue;
+        }
+
+        let source = fs::read_to_string(path)?;
+        let migration = migrate_source(&source, from, to)
+            .map_err(|e| e.with_file_name(path.display().to_string()))?;
+        if migration.is_changed() {
+            changed_files.push((path.to_path_buf(), migration.renamed));
+            rewrites.push((path.to_path_buf(), migration.source));
+        }
+    }
+
+    for (path, source) in rewrites {
+        fs::write(path, source)?;
+    }
+    let manifest_source = fs::read_to_string(&manifest_path)?;
+    fs::write(&manifest_path, set_manifest_edition(&manifest_source, to))?;
+
+    Ok(PackageMigration {
+        from,
+        to,
+        changed_files,
+    })
+}
+
+/// Set `edition` in the `[package]` section of a manifest, keeping the rest
+/// of the file as written
+fn set_manifest_edition(manifest: &str, edition: Edition) -> String {
+    let edition_line = format!("edition = \"{}\"", edition);
+    let mut lines: Vec<String> = manifest.lines().map(str::to_string).collect();
+
+    let Some(header) = lines.iter().position(|line| line.trim() == "[package]") else {
+        return manifest.to_string();
+    };
+    let section_end = lines[header + 1..]
+        .iter()
+        .position(|line| line.trim_start().starts_with('['))
+        .map_or(lines.len(), |i| header + 1 + i);
+    let existing = lines[header + 1..section_end].iter().position(|line| {
+        line.split('=')
+            .next()
+            .is_some_and(|key| key.trim() == "edition")
+    });
+
+    match existing {
+        Some(i) => lines[header + 1 + i] = edition_line,
+        None => lines.insert(header + 1, edition_line),
+    }
+
+    let mut updated = lines.join("\n");
+    if manifest.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_migrate_source_renames_new_keywords() {
+        let source = "// yield stays in comments\nlet yield = 1\nfn defer(x: i32) -> i32 { x + yield }\nlet s = \"yield\"\nlet r#macro = 2\n";
+        let migration = migrate_source(source, Edition::E2024, Edition::E2026).unwrap();
+
+        assert_eq!(
+            migration.source,
+            "// yield stays in comments\nlet r#yield = 1\nfn r#defer(x: i32) -> i32 { x + r#yield }\nlet s = \"yield\"\nlet r#macro = 2\n"
+        );
+        assert_eq!(migration.renamed, vec!["yield", "defer", "yield"]);
+
+        // The migrated source lexes cleanly in the new edition
+        let mut lexer = Lexer::new(&migration.source).unwrap();
+        lexer.set_edition(Edition::E2026);
+        assert!(lexer.scan_tokens().1.is_empty());
+
+        let unchanged = migrate_source(source, Edition::E2024, Edition::E2024).unwrap();
+        assert!(!unchanged.is_changed());
+        assert_eq!(unchanged.source, source);
+    }
+
+    #[test]
+    fn test_migrate_package_updates_files_and_manifest() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("script.toml"),
+            "# My package\n[package]\nname = \"demo\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n[dependencies]\n",
+        )
+        .unwrap();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("src/main.script"), "let yield = 1\n").unwrap();
+        fs::write(root.join("src/lib.script"), "let x = 1\n").unwrap();
+
+        let migration = migrate_package(root, Edition::E2026).unwrap();
+        assert_eq!(migration.from, Edition::E2024);
+        assert_eq!(migration.changed_files.len(), 1);
+        assert_eq!(
+            fs::read_to_string(root.join("src/main.script")).unwrap(),
+            "let r#yield = 1\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("script.toml")).unwrap(),
+            "# My package\n[package]\nname = \"demo\"\nversion = \"0.1.0\"\nedition = \"2026\"\n\n[dependencies]\n"
+        );
+
+        assert!(migrate_package(root, Edition::E2024).is_err());
+    }
+}
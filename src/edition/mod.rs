@@ -0,0 +1,192 @@
+//! Language editions and versioning
+//!
+//! An edition is the set of syntax rules a package is written against,
+//! selected with `edition = "..."` in the `[package]` section of
+//! `script.toml`. New keywords are only reserved from the edition that
+//! introduces them, so code written for an older edition keeps compiling
+//! when the compiler is upgraded; `script fix --edition <edition>` migrates
+//! it forward by rewriting names that became keywords as raw identifiers
+//! (`r#yield`).
+//!
+//! `language_version` in `script.toml` is a separate, semver requirement on
+//! the compiler itself (for example `">=0.5"`), checked before a package is
+//! compiled with `check_language_version`. `min_script_version` is the
+//! plain lower bound `check_min_script_version` enforces.
+
+pub mod migrate;
+
+pub use migrate::{migrate_package, migrate_source, Migration, PackageMigration};
+
+use crate::error::{Error, ErrorKind, Result};
+use std::fmt;
+
+/// Version of the Script language implemented by this compiler
+pub const LANGUAGE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Words reserved as keywords from a given edition on
+///
+/// Keywords of the 2024 edition are not listed: they are reserved in every
+/// edition.
+const EDITION_KEYWORDS: &[(&str, Edition)] = &[
+    ("yield", Edition::E2026),
+    ("defer", Edition::E2026),
+    ("macro", Edition::E2026),
+];
+
+/// A Script language edition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Edition {
+    /// The original edition
+    #[default]
+    E2024,
+    /// Reserves `yield`, `defer` and `macro`
+    E2026,
+}
+
+impl Edition {
+    /// The newest edition supported by this compiler
+    pub const LATEST: Edition = Edition::E2026;
+
+    /// All editions, oldest first
+    pub const ALL: &'static [Edition] = &[Edition::E2024, Edition::E2026];
+
+    /// Parse an edition name such as `"2024"`
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim() {
+            "2024" => Ok(Edition::E2024),
+            "2026" => Ok(Edition::E2026),
+            other => Err(Error::new(
+                ErrorKind::Configuration,
+                format!(
+                    "Unknown edition '{}' (supported editions: {})",
+                    other,
+                    Edition::ALL
+                        .iter()
+                        .map(|edition| edition.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            )),
+        }
+    }
+
+    /// The edition's name as written in `script.toml`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Edition::E2024 => "2024",
+            Edition::E2026 => "2026",
+        }
+    }
+
+    /// Whether `word` is a keyword introduced by this edition or an earlier one
+    ///
+    /// Only covers keywords added after the first edition.
+    pub fn is_reserved(&self, word: &str) -> bool {
+        keyword_since(word).is_some_and(|since| since <= *self)
+    }
+}
+
+impl fmt::Display for Edition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The edition from which `word` is a keyword, if it was added after the
+/// first edition
+pub fn keyword_since(word: &str) -> Option<Edition> {
+    EDITION_KEYWORDS
+        .iter()
+        .find(|(keyword, _)| *keyword == word)
+        .map(|(_, edition)| *edition)
+}
+
+/// Check that this compiler satisfies a package's `language_version`
+/// requirement
+pub fn check_language_version(requirement: &str) -> Result<()> {
+    let requirement = semver::VersionReq::parse(requirement).map_err(|e| {
+        Error::new(
+            ErrorKind::Configuration,
+            format!("Invalid language_version '{}': {}", requirement, e),
+        )
+    })?;
+
+    // Pre-release compilers count as their release so that `>=0.5` accepts
+    // 0.5.0-alpha
+    let mut version = semver::Version::parse(LANGUAGE_VERSION)
+        .map_err(|e| Error::internal(format!("Invalid compiler version: {}", e)))?;
+    version.pre = semver::Prerelease::EMPTY;
+
+    if requirement.matches(&version) {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::Configuration,
+            format!(
+                "This package requires language version {}, but this compiler implements {}",
+                requirement, LANGUAGE_VERSION
+            ),
+        ))
+    }
+}
+
+/// Check that this compiler is at least a package's `min_script_version`
+pub fn check_min_script_version(minimum: &str) -> Result<()> {
+    let minimum = semver::Version::parse(minimum).map_err(|e| {
+        Error::new(
+            ErrorKind::Configuration,
+            format!("Invalid min_script_version '{}': {}", minimum, e),
+        )
+    })?;
+
+    let mut version = semver::Version::parse(LANGUAGE_VERSION)
+        .map_err(|e| Error::internal(format!("Invalid compiler version: {}", e)))?;
+    version.pre = semver::Prerelease::EMPTY;
+
+    if version >= minimum {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::Configuration,
+            format!(
+                "This package requires Script {} or newer, but this compiler implements {}",
+                minimum, LANGUAGE_VERSION
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edition_parse_and_reserved_words() {
+        assert_eq!(Edition::parse("2024").unwrap(), Edition::E2024);
+        assert_eq!(Edition::parse("2026").unwrap(), Edition::LATEST);
+        assert!(Edition::parse("2025").is_err());
+        assert_eq!(Edition::default().to_string(), "2024");
+
+        assert!(!Edition::E2024.is_reserved("yield"));
+        assert!(Edition::E2026.is_reserved("yield"));
+        assert!(!Edition::E2026.is_reserved("let"));
+        assert_eq!(keyword_since("defer"), Some(Edition::E2026));
+    }
+
+    #[test]
+    fn test_check_language_version() {
+        assert!(check_language_version(">=0.1").is_ok());
+        assert!(check_language_version(">=99.0").is_err());
+        assert!(check_language_version("not a version").is_err());
+    }
+
+    #[test]
+    fn test_check_min_script_version() {
+        assert!(check_min_script_version("0.1.0").is_ok());
+        assert!(check_min_script_version("99.0.0")
+            .unwrap_err()
+            .message
+            .contains("requires Script 99.0.0 or newer"));
+        assert!(check_min_script_version(">=0.1").is_err());
+    }
+}
@@ -0,0 +1,1314 @@
+//! Stable error codes and their explanations
+//!
+//! Every kind of error and semantic diagnostic has a code that never changes
+//! meaning, so messages can stay short and `script explain <code>` prints the
+//! longer story. Codes are grouped by area:
+//!
+//! - `E00xx`: errors outside semantic analysis, one per `ErrorKind`
+//! - `E03xx`: types
+//! - `E04xx`: names: duplicates and undefined names
+//! - `E05xx`: assignment, control flow and memory safety
+//! - `E06xx`: members, structs and enums
+//! - `E07xx`: traits
+//! - `E08xx`: patterns
+//! - `E09xx`: `@const` functions
+//! - `E10xx`: modules
+//! - `E11xx`: actors
+//! - `W00xx`: warnings
+//!
+//! A retired code keeps its entry so old diagnostics can still be explained.
+
+/// A code and the explanation `script explain` prints for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    pub code: &'static str,
+    /// One line summary
+    pub title: &'static str,
+    /// What causes the diagnostic and how to fix it, with examples
+    pub explanation: &'static str,
+}
+
+/// The explanation for `code`, ignoring case
+pub fn explain(code: &str) -> Option<&'static ErrorCode> {
+    ERROR_CODES
+        .iter()
+        .find(|entry| entry.code.eq_ignore_ascii_case(code.trim()))
+}
+
+/// Every code with an explanation, in code order
+pub const ERROR_CODES: &[ErrorCode] = &[
+    ErrorCode {
+        code: "E0001",
+        title: "the source could not be tokenized",
+        explanation: r#"The lexer found text that is not part of any token, such as a stray
+character or a string literal that never ends.
+
+Erroneous code example:
+
+    let greeting = "hello
+    let price = 4 # 2
+
+Close every string on the line it starts (use "\n" for line breaks), and
+only use characters that belong to an operator, literal or name:
+
+    let greeting = "hello\n"
+    let price = 4 * 2
+"#,
+    },
+    ErrorCode {
+        code: "E0002",
+        title: "the source could not be parsed",
+        explanation: r#"The tokens do not form a valid program: a bracket is missing, a keyword
+is in the wrong place, or an expression ends early.
+
+Erroneous code example:
+
+    fn add(a: i32, b: i32 -> i32 {
+        a + b
+    }
+
+The message names the token the parser expected. Here the parameter list
+is missing its closing parenthesis:
+
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0003",
+        title: "type inference failed",
+        explanation: r#"Type inference found two uses of a value that require different types,
+or a value whose type it could not work out.
+
+Erroneous code example:
+
+    let items = []
+    items.push(1)
+    items.push("two")
+
+Keep every element of an array the same type, or annotate the variable so
+the intended type is clear:
+
+    let items: Array<i32> = []
+    items.push(1)
+    items.push(2)
+"#,
+    },
+    ErrorCode {
+        code: "E0004",
+        title: "the program failed while running",
+        explanation: r#"A running program hit an error it did not handle, such as a division by
+zero or a panic.
+
+Erroneous code example:
+
+    fn average(total: i32, count: i32) -> i32 {
+        total / count
+    }
+
+    average(10, 0)
+
+Check the inputs that can fail, or return a `Result` so callers handle the
+failure:
+
+    fn average(total: i32, count: i32) -> Result<i32, String> {
+        if count == 0 {
+            return Err("no values")
+        }
+        Ok(total / count)
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0005",
+        title: "an input or output operation failed",
+        explanation: r#"Reading or writing a file, socket or stream failed. The message carries
+the operating system's reason, such as a missing file or a denied
+permission.
+
+Check that the path exists and is readable or writable by the user running
+the compiler or program.
+"#,
+    },
+    ErrorCode {
+        code: "E0006",
+        title: "the package could not be loaded",
+        explanation: r#"The package manifest or one of the package's dependencies is invalid:
+`script.toml` does not parse, names an unknown field, or a dependency
+cannot be resolved.
+
+Erroneous manifest example:
+
+    [package]
+    name = "game"
+
+Every package needs a name and a version:
+
+    [package]
+    name = "game"
+    version = "0.1.0"
+"#,
+    },
+    ErrorCode {
+        code: "E0007",
+        title: "a module could not be loaded or linked",
+        explanation: r#"A module could not be resolved, loaded or linked with the rest of the
+program, for example because two modules define the same function.
+
+Give functions in different modules distinct names, or keep one definition
+and import it where it is needed.
+"#,
+    },
+    ErrorCode {
+        code: "E0008",
+        title: "compilation failed",
+        explanation: r#"A later compilation phase such as lowering, monomorphization or code
+generation could not handle the program. The message names the construct
+that failed.
+
+When the construct is valid Script, this is a bug in the compiler; please
+report it with a small program that reproduces it.
+"#,
+    },
+    ErrorCode {
+        code: "E0009",
+        title: "a source file could not be read",
+        explanation: r#"A source file or directory given to the compiler is missing, is not a
+`.script` file, or could not be read.
+
+Check the path passed on the command line and that the file has the
+`.script` extension.
+"#,
+    },
+    ErrorCode {
+        code: "E0010",
+        title: "semantic analysis failed",
+        explanation: r#"Semantic analysis rejected the program. Most semantic errors have a more
+specific code between E0300 and E1199; this one is used when the analysis
+itself could not run.
+
+Fix the other errors reported with it first.
+"#,
+    },
+    ErrorCode {
+        code: "E0011",
+        title: "a security check failed",
+        explanation: r#"The program, or compiling it, crossed a security limit: a sandbox
+permission, a resource limit guarding against runaway compilation, or a
+bounds check.
+
+If the limit is expected to be reached, raise it in the configuration that
+sets it; otherwise look for unbounded recursion or allocation.
+"#,
+    },
+    ErrorCode {
+        code: "E0012",
+        title: "a lock was poisoned",
+        explanation: r#"A thread panicked while holding a lock, so the data the lock protects may
+be inconsistent.
+
+Find the panic reported before this error and fix it.
+"#,
+    },
+    ErrorCode {
+        code: "E0013",
+        title: "a key was not found",
+        explanation: r#"A map lookup used a key that is not in the map.
+
+Erroneous code example:
+
+    let scores = {"ada": 3}
+    scores["bob"]
+
+Check whether the key exists before reading it:
+
+    if scores.contains_key("bob") {
+        println(scores["bob"])
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0014",
+        title: "an index was out of bounds",
+        explanation: r#"An array or string was indexed past its end.
+
+Erroneous code example:
+
+    let items = [1, 2, 3]
+    items[3]
+
+Indices start at 0, so the last element of an array of length `n` is at
+`n - 1`. Check the index against `len()` first:
+
+    if i < items.len() {
+        println(items[i])
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0015",
+        title: "a value could not be converted",
+        explanation: r#"A value could not be converted to the requested type, such as a string
+that is not a number.
+
+Erroneous code example:
+
+    let count = parse_int("twelve").unwrap()
+
+Handle the failure instead of assuming the conversion works:
+
+    match parse_int(input) {
+        Ok(count) => println(count),
+        Err(error) => println("not a number: " + error),
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0016",
+        title: "an async task failed",
+        explanation: r#"An async task failed, was cancelled, or its future was polled after it
+completed.
+
+Await each future once, and handle the error results of tasks that can
+fail.
+"#,
+    },
+    ErrorCode {
+        code: "E0017",
+        title: "a resource was not found",
+        explanation: r#"A named resource, such as an asset, a registered function or a breakpoint,
+does not exist.
+
+Check the spelling of the name and that the resource is registered before
+it is used.
+"#,
+    },
+    ErrorCode {
+        code: "E0018",
+        title: "internal compiler error",
+        explanation: r#"The compiler reached a state it should never be in. This is a bug in the
+compiler, not in your program.
+
+Please report it with the message and a small program that reproduces it.
+"#,
+    },
+    ErrorCode {
+        code: "E0019",
+        title: "invalid configuration",
+        explanation: r#"A configuration value, from `script.toml`, `.scriptlint.toml` or the
+command line, is out of range or not one of the accepted values.
+
+Erroneous manifest example:
+
+    [profile.release]
+    opt-level = 5
+
+The message lists the accepted values; here opt-level goes from 0 to 3.
+"#,
+    },
+    ErrorCode {
+        code: "E0301",
+        title: "mismatched types",
+        explanation: r#"A value has a different type than the place it is used in expects.
+
+Erroneous code example:
+
+    let count: i32 = "three"
+
+Use a value of the expected type, or change the annotation:
+
+    let count: i32 = 3
+"#,
+    },
+    ErrorCode {
+        code: "E0302",
+        title: "wrong number of arguments",
+        explanation: r#"A function was called with more or fewer arguments than it has
+parameters.
+
+Erroneous code example:
+
+    fn add(a: i32, b: i32) -> i32 {
+        a + b
+    }
+
+    add(1)
+
+Pass one argument for every parameter:
+
+    add(1, 2)
+"#,
+    },
+    ErrorCode {
+        code: "E0303",
+        title: "returned value has the wrong type",
+        explanation: r#"A `return` statement, or the final expression of a function body, has a
+different type than the function's declared return type.
+
+Erroneous code example:
+
+    fn name() -> String {
+        return 42
+    }
+
+Return a value of the declared type, or change the declaration:
+
+    fn name() -> String {
+        return "Ada"
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0304",
+        title: "function does not return a value",
+        explanation: r#"A function declares a return type, but some path through its body ends
+without returning a value.
+
+Erroneous code example:
+
+    fn sign(n: i32) -> i32 {
+        if n < 0 {
+            return -1
+        }
+    }
+
+Return a value on every path:
+
+    fn sign(n: i32) -> i32 {
+        if n < 0 {
+            return -1
+        }
+        1
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0305",
+        title: "operator not supported by the type",
+        explanation: r#"A unary operator was applied to a type that does not support it.
+
+Erroneous code example:
+
+    let flag = -true
+
+Use an operator the type supports, here `!` for booleans:
+
+    let flag = !true
+"#,
+    },
+    ErrorCode {
+        code: "E0306",
+        title: "binary operator not supported by the types",
+        explanation: r#"A binary operator was applied to operand types it does not support.
+
+Erroneous code example:
+
+    let total = 10 - "2"
+
+Convert one operand so both have a type the operator supports:
+
+    let total = 10 - 2
+"#,
+    },
+    ErrorCode {
+        code: "E0307",
+        title: "function used as a value",
+        explanation: r#"A function name was used where a value is expected, in a position that
+does not accept functions.
+
+Erroneous code example:
+
+    fn tick() {}
+
+    let count: i32 = tick
+
+Call the function to use its result, or wrap it in a closure to pass it
+around:
+
+    let callback = || tick()
+"#,
+    },
+    ErrorCode {
+        code: "E0308",
+        title: "value is not callable",
+        explanation: r#"Something that is not a function or closure was called.
+
+Erroneous code example:
+
+    let speed = 10
+    speed(2)
+
+Only functions and closures can be called. Use an operator, or a method
+with `value.method()`:
+
+    let doubled = speed * 2
+"#,
+    },
+    ErrorCode {
+        code: "E0309",
+        title: "value cannot be indexed",
+        explanation: r#"Square-bracket indexing was used on a type that does not support it.
+
+Erroneous code example:
+
+    let health = 100
+    health[0]
+
+Only arrays, strings and maps can be indexed.
+"#,
+    },
+    ErrorCode {
+        code: "E0310",
+        title: "index has the wrong type",
+        explanation: r#"An array or string was indexed with something that is not an integer.
+
+Erroneous code example:
+
+    let items = [1, 2, 3]
+    items["1"]
+
+Index with an integer, parsing strings first:
+
+    items[1]
+"#,
+    },
+    ErrorCode {
+        code: "E0311",
+        title: "`?` used in a function that does not return Result or Option",
+        explanation: r#"The `?` operator returns early with the error or `None` it finds, so the
+function using it must return a `Result` or `Option` itself.
+
+Erroneous code example:
+
+    fn load() -> String {
+        let text = read_file("save.txt")?
+        text
+    }
+
+Return a `Result` from the function:
+
+    fn load() -> Result<String, String> {
+        let text = read_file("save.txt")?
+        Ok(text)
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0312",
+        title: "`?` used on a value that is not Result or Option",
+        explanation: r#"The `?` operator only works on `Result` and `Option` values.
+
+Erroneous code example:
+
+    fn double(n: i32) -> Result<i32, String> {
+        Ok(n? * 2)
+    }
+
+Remove the `?` from values that cannot fail:
+
+    fn double(n: i32) -> Result<i32, String> {
+        Ok(n * 2)
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0313",
+        title: "trait bound not satisfied",
+        explanation: r#"A generic function or type was used with a type argument that does not
+implement a trait its type parameter requires.
+
+Erroneous code example:
+
+    trait Shape {
+        fn area(self) -> f32
+    }
+
+    fn total_area<T: Shape>(shapes: Array<T>) -> f32 { 0.0 }
+
+    total_area([1, 2, 3])
+
+Implement the trait for the type, or pass a type that implements it:
+
+    impl Shape for Circle {
+        fn area(self) -> f32 { 3.14 * self.radius * self.radius }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0401",
+        title: "variable already defined",
+        explanation: r#"Two variables with the same name were declared in the same scope.
+
+Erroneous code example:
+
+    fn main() {
+        let score = 1
+        let score: String = "one"
+    }
+
+Rename one of them, or assign to the existing variable:
+
+    fn main() {
+        let score = 1
+        let label = "one"
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0402",
+        title: "function already defined",
+        explanation: r#"Two functions with the same name and parameter types were defined.
+
+Erroneous code example:
+
+    fn area(width: f32) -> f32 { width * width }
+    fn area(side: f32) -> f32 { side * side }
+
+Remove one of them, or give them different names or parameter types.
+"#,
+    },
+    ErrorCode {
+        code: "E0403",
+        title: "type already defined",
+        explanation: r#"Two structs, enums or type aliases share a name.
+
+Erroneous code example:
+
+    struct Point { x: f32, y: f32 }
+    enum Point { Origin }
+
+Give each type its own name.
+"#,
+    },
+    ErrorCode {
+        code: "E0425",
+        title: "cannot find a variable",
+        explanation: r#"A name was used that is not defined in the current scope: no `let`,
+parameter, constant or import introduces it.
+
+Erroneous code example:
+
+    fn main() {
+        let total = 1
+        println(totl)
+    }
+
+The error suggests defined names spelled similarly. Fix the spelling,
+declare the variable before using it, or import it from the module that
+exports it:
+
+    fn main() {
+        let total = 1
+        println(total)
+    }
+
+Variables declared inside a block are not visible after the block ends.
+"#,
+    },
+    ErrorCode {
+        code: "E0426",
+        title: "cannot find a function",
+        explanation: r#"A function was called that is not defined or imported.
+
+Erroneous code example:
+
+    fn main() {
+        prnt("hello")
+    }
+
+Fix the spelling, define the function, or import it:
+
+    import { format_score } from "./ui"
+
+    fn main() {
+        println(format_score(10))
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0427",
+        title: "cannot find a type",
+        explanation: r#"A type name was used that no struct, enum, alias or import defines.
+
+Erroneous code example:
+
+    fn spawn() -> Enemy {
+        Enemy { health: 10 }
+    }
+
+Define the type, or import it from the module that exports it:
+
+    struct Enemy {
+        health: i32
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0428",
+        title: "cannot find a type parameter",
+        explanation: r#"A generic type parameter was used that the surrounding function, struct
+or impl does not declare.
+
+Erroneous code example:
+
+    fn first(items: Array<T>) -> T {
+        items[0]
+    }
+
+Declare the parameter in angle brackets after the name:
+
+    fn first<T>(items: Array<T>) -> T {
+        items[0]
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0429",
+        title: "cannot find a trait",
+        explanation: r#"A trait name was used in an `impl` or a bound, but no trait with that name
+is defined or imported.
+
+Erroneous code example:
+
+    impl Drawable for Sprite {}
+
+Define the trait, or import it:
+
+    trait Drawable {
+        fn draw(self)
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0501",
+        title: "assignment to an immutable variable",
+        explanation: r#"A variable declared without `mut` was assigned a new value.
+
+Erroneous code example:
+
+    let lives = 3
+    lives = lives - 1
+
+Declare the variable with `let mut`, or shadow it with a new `let`:
+
+    let mut lives = 3
+    lives = lives - 1
+"#,
+    },
+    ErrorCode {
+        code: "E0502",
+        title: "invalid assignment target",
+        explanation: r#"The left side of `=` is not something that can be assigned to.
+
+Erroneous code example:
+
+    5 = count
+    get_score() = 10
+
+Assign to a variable, a field or an indexed element:
+
+    count = 5
+    player.score = 10
+"#,
+    },
+    ErrorCode {
+        code: "E0503",
+        title: "`break` outside of a loop",
+        explanation: r#"`break` was used outside of a `while`, `for` or `loop` body.
+
+Erroneous code example:
+
+    fn check(n: i32) {
+        if n > 10 {
+            break
+        }
+    }
+
+Use `return` to leave a function early:
+
+    fn check(n: i32) {
+        if n > 10 {
+            return
+        }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0504",
+        title: "`continue` outside of a loop",
+        explanation: r#"`continue` was used outside of a `while`, `for` or `loop` body.
+
+Erroneous code example:
+
+    fn skip(n: i32) {
+        if n == 0 {
+            continue
+        }
+    }
+
+`continue` only skips to the next iteration of the loop around it; use
+`return` to leave a function.
+"#,
+    },
+    ErrorCode {
+        code: "E0505",
+        title: "`return` outside of a function",
+        explanation: r#"`return` was used in top-level code, outside of any function.
+
+Erroneous code example:
+
+    let ready = true
+    if !ready {
+        return
+    }
+
+Move the code into a function, or restructure it with `if`/`else`.
+"#,
+    },
+    ErrorCode {
+        code: "E0506",
+        title: "memory safety violation",
+        explanation: r#"Memory safety analysis found a use of a value after it was moved or
+freed, a use of an uninitialized variable, or conflicting borrows.
+
+Erroneous code example:
+
+    let name: String
+    println(name)
+
+Initialize variables before reading them, and don't use a value after
+moving it elsewhere; clone it if both places need it:
+
+    let name: String = "Ada"
+    println(name)
+"#,
+    },
+    ErrorCode {
+        code: "E0601",
+        title: "member access on a type without members",
+        explanation: r#"`.` was used to access a field or method of a type that has none.
+
+Erroneous code example:
+
+    let speed = 10
+    speed.value
+
+Member access works on structs, and on types with methods.
+"#,
+    },
+    ErrorCode {
+        code: "E0602",
+        title: "unknown member",
+        explanation: r#"The type has no field or method with this name.
+
+Erroneous code example:
+
+    struct Player { name: String }
+
+    fn greet(player: Player) {
+        println(player.nmae)
+    }
+
+Check the spelling against the type's definition:
+
+    println(player.name)
+"#,
+    },
+    ErrorCode {
+        code: "E0603",
+        title: "method not found",
+        explanation: r#"The type has no method with this name, neither in its own `impl` blocks
+nor in the traits it implements.
+
+Erroneous code example:
+
+    let items = [3, 1, 2]
+    items.sorted_by_size()
+
+Check the spelling and that the method is defined for this type, and
+define it in an `impl` block if it is missing.
+"#,
+    },
+    ErrorCode {
+        code: "E0604",
+        title: "field given twice",
+        explanation: r#"A struct or enum variant constructor sets the same field twice.
+
+Erroneous code example:
+
+    Point { x: 1.0, x: 2.0 }
+
+Set each field once:
+
+    Point { x: 1.0, y: 2.0 }
+"#,
+    },
+    ErrorCode {
+        code: "E0605",
+        title: "missing field",
+        explanation: r#"A struct constructor leaves out a field the struct declares.
+
+Erroneous code example:
+
+    struct Point { x: f32, y: f32 }
+
+    let origin = Point { x: 0.0 }
+
+Give every field a value:
+
+    let origin = Point { x: 0.0, y: 0.0 }
+"#,
+    },
+    ErrorCode {
+        code: "E0606",
+        title: "unknown field",
+        explanation: r#"A struct constructor or pattern names a field the struct doesn't declare.
+
+Erroneous code example:
+
+    struct Point { x: f32, y: f32 }
+
+    let origin = Point { x: 0.0, y: 0.0, z: 0.0 }
+
+Use only the fields in the struct's definition, or add the field to it.
+"#,
+    },
+    ErrorCode {
+        code: "E0607",
+        title: "not a struct",
+        explanation: r#"Struct constructor syntax `Name { ... }` was used with a name that is not
+a struct.
+
+Erroneous code example:
+
+    enum Shape { Circle }
+
+    let shape = Shape { radius: 1.0 }
+
+Construct enums through their variants:
+
+    let shape = Shape::Circle
+"#,
+    },
+    ErrorCode {
+        code: "E0608",
+        title: "not an enum",
+        explanation: r#"Variant syntax `Name::Variant` was used with a name that is not an enum.
+
+Erroneous code example:
+
+    struct Color { red: i32 }
+
+    let color = Color::Red
+
+Define `Color` as an enum if it should have variants:
+
+    enum Color { Red, Green }
+"#,
+    },
+    ErrorCode {
+        code: "E0609",
+        title: "unknown enum variant",
+        explanation: r#"The enum has no variant with this name.
+
+Erroneous code example:
+
+    enum Direction { North, South }
+
+    let heading = Direction::East
+
+Use one of the enum's variants, or add the missing one to its definition.
+"#,
+    },
+    ErrorCode {
+        code: "E0610",
+        title: "enum variant used without its enum",
+        explanation: r#"An enum variant was named without the enum it belongs to, and it is not
+imported on its own.
+
+Erroneous code example:
+
+    enum Direction { North, South }
+
+    let heading = North
+
+Qualify the variant with its enum:
+
+    let heading = Direction::North
+"#,
+    },
+    ErrorCode {
+        code: "E0611",
+        title: "variant used in the wrong form",
+        explanation: r#"A variant was constructed or matched in a different form than it is
+declared in: unit, tuple or struct.
+
+Erroneous code example:
+
+    enum Shape { Circle(f32), Empty }
+
+    let shape = Shape::Circle
+    let none = Shape::Empty(0.0)
+
+Use the form of the declaration:
+
+    let shape = Shape::Circle(1.0)
+    let none = Shape::Empty
+"#,
+    },
+    ErrorCode {
+        code: "E0701",
+        title: "trait method not implemented",
+        explanation: r#"An `impl Trait for Type` block does not define a method the trait
+declares without a default body.
+
+Erroneous code example:
+
+    trait Shape {
+        fn area(self) -> f32
+        fn name(self) -> String
+    }
+
+    impl Shape for Square {
+        fn area(self) -> f32 { self.side * self.side }
+    }
+
+Define every required method:
+
+    impl Shape for Square {
+        fn area(self) -> f32 { self.side * self.side }
+        fn name(self) -> String { "square" }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0702",
+        title: "method is not part of the trait",
+        explanation: r#"An `impl Trait for Type` block defines a method the trait does not
+declare.
+
+Erroneous code example:
+
+    trait Shape {
+        fn area(self) -> f32
+    }
+
+    impl Shape for Square {
+        fn area(self) -> f32 { self.side * self.side }
+        fn perimeter(self) -> f32 { 4.0 * self.side }
+    }
+
+Declare the method in the trait, or move it to an inherent `impl Square`
+block.
+"#,
+    },
+    ErrorCode {
+        code: "E0801",
+        title: "non-exhaustive patterns",
+        explanation: r#"A `match` does not handle every possible value of what it matches on.
+
+Erroneous code example:
+
+    fn describe(flag: bool) -> String {
+        match flag {
+            true => "on",
+        }
+    }
+
+Add arms for the missing cases, or a `_` arm that catches the rest:
+
+    fn describe(flag: bool) -> String {
+        match flag {
+            true => "on",
+            false => "off",
+        }
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0802",
+        title: "unreachable pattern",
+        explanation: r#"A `match` arm can never be reached because earlier arms already cover
+every value it matches.
+
+Erroneous code example:
+
+    match n {
+        _ => "many",
+        0 => "none",
+    }
+
+Remove the arm, or move more specific patterns before general ones:
+
+    match n {
+        0 => "none",
+        _ => "many",
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E0901",
+        title: "`@const` function does something not allowed at compile time",
+        explanation: r#"A function marked `@const` is evaluated at compile time, so its body may
+only use constant expressions, parameters and other `@const` functions.
+
+Erroneous code example:
+
+    @const
+    fn seed() -> i32 {
+        random_int(0, 100)
+    }
+
+Remove `@const`, or compute the value from constants only.
+"#,
+    },
+    ErrorCode {
+        code: "E0902",
+        title: "`@const` function calls a function that isn't `@const`",
+        explanation: r#"An `@const` function called a function that can only run at runtime.
+
+Erroneous code example:
+
+    fn double(n: i32) -> i32 { n * 2 }
+
+    @const
+    fn four() -> i32 { double(2) }
+
+Mark the callee `@const` too, if its body allows it:
+
+    @const
+    fn double(n: i32) -> i32 { n * 2 }
+"#,
+    },
+    ErrorCode {
+        code: "E0903",
+        title: "I/O in an `@const` function",
+        explanation: r#"An `@const` function printed, read a file or did other input or output,
+which cannot happen at compile time.
+
+Erroneous code example:
+
+    @const
+    fn banner() -> String {
+        read_file("banner.txt")
+    }
+
+Do the I/O in a regular function at runtime.
+"#,
+    },
+    ErrorCode {
+        code: "E0904",
+        title: "mutation in an `@const` function",
+        explanation: r#"An `@const` function mutated state, such as a `let mut` variable or a
+field, which compile-time evaluation does not support.
+
+Erroneous code example:
+
+    @const
+    fn sum(n: i32) -> i32 {
+        let mut total = 0
+        for i in 0..n { total = total + i }
+        total
+    }
+
+Express the computation with recursion or arithmetic instead:
+
+    @const
+    fn sum(n: i32) -> i32 {
+        n * (n - 1) / 2
+    }
+"#,
+    },
+    ErrorCode {
+        code: "E1001",
+        title: "module error",
+        explanation: r#"A module could not be analyzed for a reason without a more specific code;
+the message gives the details.
+"#,
+    },
+    ErrorCode {
+        code: "E1002",
+        title: "imported name not found in module",
+        explanation: r#"An `import` names something the module does not define.
+
+Erroneous code example:
+
+    import { area_cirle } from "./geometry"
+
+The error suggests similar names the module defines. Fix the spelling, or
+export the item from the module:
+
+    import { area_circle } from "./geometry"
+"#,
+    },
+    ErrorCode {
+        code: "E1003",
+        title: "name imported twice",
+        explanation: r#"Two imports bring the same local name into scope.
+
+Erroneous code example:
+
+    import { draw } from "./sprites"
+    import { draw } from "./shapes"
+
+Rename one of them with `as`:
+
+    import { draw } from "./sprites"
+    import { draw as draw_shape } from "./shapes"
+"#,
+    },
+    ErrorCode {
+        code: "E1004",
+        title: "exported name not defined",
+        explanation: r#"An `export` names something the module doesn't define.
+
+Erroneous code example:
+
+    export { area }
+
+Define the item before exporting it, or export it where it is declared:
+
+    export fn area(side: f32) -> f32 { side * side }
+"#,
+    },
+    ErrorCode {
+        code: "E1005",
+        title: "name exported twice",
+        explanation: r#"A module exports two items under the same name.
+
+Erroneous code example:
+
+    export fn load() {}
+    export { load_all as load }
+
+Give every export a distinct name.
+"#,
+    },
+    ErrorCode {
+        code: "E1006",
+        title: "module not found",
+        explanation: r#"An `import` refers to a module that could not be found.
+
+Erroneous code example:
+
+    import { Vec2 } from "./mathz"
+
+Relative paths are resolved from the importing file; other names are looked
+up in the package's dependencies. Check the path, or add the package to
+`[dependencies]` in `script.toml`.
+"#,
+    },
+    ErrorCode {
+        code: "E1007",
+        title: "circular import",
+        explanation: r#"Modules import each other in a cycle, so none of them can be analyzed
+first.
+
+Erroneous example, across two files:
+
+    // a.script
+    import { b } from "./b"
+
+    // b.script
+    import { a } from "./a"
+
+Move what both modules need into a third module they both import.
+"#,
+    },
+    ErrorCode {
+        code: "E1008",
+        title: "private item used from another module",
+        explanation: r#"An item was imported or used from another module that does not export it.
+
+Erroneous code example:
+
+    // geometry.script
+    fn helper() -> i32 { 42 }
+
+    // main.script
+    import { helper } from "./geometry"
+
+Export the item from its module:
+
+    export fn helper() -> i32 { 42 }
+"#,
+    },
+    ErrorCode {
+        code: "E1101",
+        title: "actor error",
+        explanation: r#"An actor was declared or used in a way the actor model does not allow;
+the message gives the details.
+"#,
+    },
+    ErrorCode {
+        code: "W0001",
+        title: "use of a deprecated item",
+        explanation: r#"The program uses a function or type marked `@deprecated`. It still works,
+but may be removed in a later version.
+
+The warning repeats the item's deprecation note, which usually names the
+replacement to use instead.
+"#,
+    },
+    ErrorCode {
+        code: "W0002",
+        title: "use of an unstable item",
+        explanation: r#"The program uses a function or type marked `@unstable`, whose behavior or
+signature may still change.
+
+Pin the Script version the program is built with, or avoid the item in code
+that must keep working across upgrades.
+"#,
+    },
+    ErrorCode {
+        code: "W0003",
+        title: "unreachable match arm",
+        explanation: r#"A `match` arm is covered by earlier arms, so it never runs.
+
+Example:
+
+    match n {
+        _ => "many",
+        0 => "none",
+    }
+
+Remove the arm, or move it before the arms that cover it.
+"#,
+    },
+    ErrorCode {
+        code: "W0004",
+        title: "name is a keyword in a newer edition",
+        explanation: r#"A variable, function or type is named after a word that a newer edition
+of the language reserves as a keyword, so the code will stop compiling when
+the package moves to that edition.
+
+Run `script fix --edition <edition>` to rename it to a raw identifier such
+as `r#async`, or rename it yourself.
+"#,
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_codes_are_unique_and_explained() {
+        let mut seen = HashSet::new();
+        for entry in ERROR_CODES {
+            assert!(seen.insert(entry.code), "{} is listed twice", entry.code);
+            assert_eq!(entry.code.len(), 5);
+            assert!(!entry.title.is_empty() && !entry.explanation.is_empty());
+        }
+
+        assert_eq!(explain("E0425").unwrap().title, "cannot find a variable");
+        assert_eq!(explain(" e0425 ").unwrap().code, "E0425");
+        assert!(explain("E9999").is_none());
+        for kind in [ErrorKind::LexerError, ErrorKind::Configuration] {
+            assert!(explain(kind.code()).is_some());
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Machine-readable diagnostics
+//!
+//! With `--error-format=json` every diagnostic is printed as one JSON object
+//! per line:
+//!
+//! ```json
+//! {"code":"E0002","severity":"error","message":"Expected ')'","file":"app.script",
+//!  "span":{"start":{"line":3,"column":7,"offset":41},"end":{"line":3,"column":7,"offset":41}},
+//!  "notes":[],"fixes":[]}
+//! ```
+//!
+//! Lines and columns are 1-based and count characters; offsets count bytes.
+//! An error that only knows where it starts has a span that ends there too.
+
+use super::{Error, Fix};
+use crate::source::{SourceLocation, Span};
+use serde::Serialize;
+
+/// How bad a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A position in a source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct JsonPosition {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl From<SourceLocation> for JsonPosition {
+    fn from(location: SourceLocation) -> Self {
+        JsonPosition {
+            line: location.line,
+            column: location.column,
+            offset: location.byte_offset,
+        }
+    }
+}
+
+/// The source a diagnostic or fix covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct JsonSpan {
+    pub start: JsonPosition,
+    pub end: JsonPosition,
+}
+
+impl From<Span> for JsonSpan {
+    fn from(span: Span) -> Self {
+        JsonSpan {
+            start: span.start.into(),
+            end: span.end.into(),
+        }
+    }
+}
+
+/// A suggested fix
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JsonFix {
+    /// Replace the source in `span` with `replacement`
+    Edit {
+        title: String,
+        span: JsonSpan,
+        replacement: String,
+    },
+    /// Import `name` from a module that exports it
+    Import { title: String, name: String },
+}
+
+impl From<&Fix> for JsonFix {
+    fn from(fix: &Fix) -> Self {
+        match fix {
+            Fix::Edit { title, span, text } => JsonFix::Edit {
+                title: title.clone(),
+                span: (*span).into(),
+                replacement: text.clone(),
+            },
+            Fix::Import { name } => JsonFix::Import {
+                title: format!("Import '{}'", name),
+                name: name.clone(),
+            },
+        }
+    }
+}
+
+/// One diagnostic as printed by the JSON error format
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonDiagnostic {
+    /// Stable name of the kind of diagnostic, such as `type_error` or a
+    /// lint's name
+    pub code: String,
+    pub severity: Severity,
+    pub message: String,
+    pub file: Option<String>,
+    pub span: Option<JsonSpan>,
+    pub notes: Vec<String>,
+    pub fixes: Vec<JsonFix>,
+}
+
+impl JsonDiagnostic {
+    /// A diagnostic without notes or fixes
+    pub fn new(
+        code: impl Into<String>,
+        severity: Severity,
+        message: impl Into<String>,
+        file: Option<&str>,
+        span: Option<Span>,
+    ) -> Self {
+        JsonDiagnostic {
+            code: code.into(),
+            severity,
+            message: message.into(),
+            file: file.map(str::to_string),
+            span: span.map(JsonSpan::from),
+            notes: Vec::new(),
+            fixes: Vec::new(),
+        }
+    }
+
+    pub fn with_fixes<'a>(mut self, fixes: impl IntoIterator<Item = &'a Fix>) -> Self {
+        self.fixes.extend(fixes.into_iter().map(JsonFix::from));
+        self
+    }
+
+    /// The diagnostic as a single line of JSON
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).expect("diagnostics always serialize")
+    }
+}
+
+impl From<&Error> for JsonDiagnostic {
+    fn from(error: &Error) -> Self {
+        let mut diagnostic = JsonDiagnostic::new(
+            error.code,
+            Severity::Error,
+            error.message.clone(),
+            error.file_name.as_deref(),
+            error.location.map(Span::single),
+        )
+        .with_fixes(&error.fixes);
+        diagnostic.notes = error.notes.clone();
+        diagnostic
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn test_error_as_json() {
+        let location = SourceLocation::new(2, 5, 12);
+        let error = Error::new(ErrorKind::TypeError, "expected i32, found string")
+            .with_location(location)
+            .with_file_name("app.script")
+            .with_note("the variable was declared as i32")
+            .with_fix(Fix::Edit {
+                title: "Convert to a number".to_string(),
+                span: Span::single(location),
+                text: "int(".to_string(),
+            });
+
+        let json: serde_json::Value =
+            serde_json::from_str(&JsonDiagnostic::from(&error).to_json_line()).unwrap();
+        assert_eq!(json["code"], "E0003");
+        assert_eq!(json["severity"], "error");
+        assert_eq!(json["file"], "app.script");
+        assert_eq!(json["span"]["start"]["line"], 2);
+        assert_eq!(json["span"]["end"]["offset"], 12);
+        assert_eq!(json["notes"][0], "the variable was declared as i32");
+        assert_eq!(json["fixes"][0]["kind"], "edit");
+        assert_eq!(json["fixes"][0]["replacement"], "int(");
+
+        let bare = JsonDiagnostic::from(&Error::io("disk full"));
+        assert_eq!(bare.span, None);
+        assert!(bare.to_json_line().contains("\"file\":null"));
+    }
+}
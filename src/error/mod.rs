@@ -1,21 +1,45 @@
+mod codes;
+mod json;
 pub mod module_context;
 mod reporter;
 
-use crate::source::SourceLocation;
+use crate::source::{SourceLocation, Span};
 use colored::*;
 use std::fmt;
 
-pub use reporter::ErrorReporter;
+pub use codes::{explain, ErrorCode, ERROR_CODES};
+pub use json::{JsonDiagnostic, JsonFix, JsonPosition, JsonSpan, Severity};
+pub use reporter::{error_format, set_error_format, ErrorFormat, ErrorReporter};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, Clone)]
 pub struct Error {
     pub kind: ErrorKind,
+    /// Stable code of the error, explained by `script explain`
+    pub code: &'static str,
     pub message: String,
     pub location: Option<SourceLocation>,
     pub source_line: Option<String>,
     pub file_name: Option<String>,
+    /// Further explanation printed after the error
+    pub notes: Vec<String>,
+    /// Fixes a tool can offer for the error
+    pub fixes: Vec<Fix>,
+}
+
+/// A machine-applicable fix for an error
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fix {
+    /// Replace the source in `span` with `text`; an empty span inserts it
+    Edit {
+        title: String,
+        span: Span,
+        text: String,
+    },
+    /// Import `name`, which is not defined in the file, from a module that
+    /// exports it; which modules do is left to the tool applying the fix
+    Import { name: String },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -45,11 +69,14 @@ pub enum ErrorKind {
 impl Error {
     pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
         Self {
+            code: kind.code(),
             kind,
             message: message.into(),
             location: None,
             source_line: None,
             file_name: None,
+            notes: Vec::new(),
+            fixes: Vec::new(),
         }
     }
 
@@ -152,6 +179,52 @@ impl Error {
         self.file_name = Some(name.into());
         self
     }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fixes.push(fix);
+        self
+    }
+
+    /// Use a more specific code than the kind's
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = code;
+        self
+    }
+}
+
+impl ErrorKind {
+    /// Stable code of the kind, such as `E0003`
+    ///
+    /// Errors with a more specific code, like most semantic errors, carry
+    /// it in `Error::code` instead.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::LexerError => "E0001",
+            ErrorKind::ParseError => "E0002",
+            ErrorKind::TypeError => "E0003",
+            ErrorKind::RuntimeError => "E0004",
+            ErrorKind::IoError => "E0005",
+            ErrorKind::PackageError => "E0006",
+            ErrorKind::ModuleError => "E0007",
+            ErrorKind::CompilationError => "E0008",
+            ErrorKind::FileError => "E0009",
+            ErrorKind::SemanticError => "E0010",
+            ErrorKind::SecurityViolation => "E0011",
+            ErrorKind::LockPoisoned => "E0012",
+            ErrorKind::KeyNotFound => "E0013",
+            ErrorKind::IndexOutOfBounds => "E0014",
+            ErrorKind::InvalidConversion => "E0015",
+            ErrorKind::AsyncError => "E0016",
+            ErrorKind::ResourceNotFound => "E0017",
+            ErrorKind::InternalError => "E0018",
+            ErrorKind::Configuration => "E0019",
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -182,7 +255,7 @@ impl fmt::Display for Error {
         write!(
             f,
             "{}{} {}",
-            error_type.red().bold(),
+            format!("{}[{}]", error_type, self.code).red().bold(),
             ":".white().bold(),
             self.message
         )?;
@@ -255,6 +328,10 @@ impl fmt::Display for Error {
             }
         }
 
+        for note in &self.notes {
+            write!(f, "\n    {} {}", "note:".bold(), note)?;
+        }
+
         Ok(())
     }
 }
@@ -1,13 +1,60 @@
-use super::Error;
+use super::{explain, Error, JsonDiagnostic};
 use colored::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How diagnostics are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorFormat {
+    /// Colored text for people
+    #[default]
+    Human,
+    /// One JSON object per diagnostic, for editors and CI
+    Json,
+}
+
+impl ErrorFormat {
+    /// Parse a format as given to `--error-format`
+    pub fn parse(format: &str) -> Option<Self> {
+        match format {
+            "human" => Some(ErrorFormat::Human),
+            "json" => Some(ErrorFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// Set the format new reporters print in
+pub fn set_error_format(format: ErrorFormat) {
+    JSON_ERRORS.store(format == ErrorFormat::Json, Ordering::Relaxed);
+}
+
+/// The format new reporters print in
+pub fn error_format() -> ErrorFormat {
+    if JSON_ERRORS.load(Ordering::Relaxed) {
+        ErrorFormat::Json
+    } else {
+        ErrorFormat::Human
+    }
+}
 
 pub struct ErrorReporter {
     errors: Vec<Error>,
+    format: ErrorFormat,
 }
 
 impl ErrorReporter {
+    /// A reporter printing in the format set with [`set_error_format`]
     pub fn new() -> Self {
-        Self { errors: Vec::new() }
+        Self::with_format(error_format())
+    }
+
+    pub fn with_format(format: ErrorFormat) -> Self {
+        Self {
+            errors: Vec::new(),
+            format,
+        }
     }
 
     pub fn report(&mut self, error: Error) {
@@ -22,7 +69,26 @@ impl ErrorReporter {
         self.errors.len()
     }
 
+    /// Codes of the reported errors that `script explain` knows, in the
+    /// order they were first reported
+    pub fn explained_codes(&self) -> Vec<&'static str> {
+        let mut codes = Vec::new();
+        for error in &self.errors {
+            if explain(error.code).is_some() && !codes.contains(&error.code) {
+                codes.push(error.code);
+            }
+        }
+        codes
+    }
+
     pub fn print_all(&self) {
+        if self.format == ErrorFormat::Json {
+            for error in &self.errors {
+                eprintln!("{}", JsonDiagnostic::from(error).to_json_line());
+            }
+            return;
+        }
+
         for error in &self.errors {
             eprintln!("{}\n", error);
         }
@@ -37,6 +103,25 @@ impl ErrorReporter {
                 plural
             );
         }
+
+        let codes = self.explained_codes();
+        match codes.as_slice() {
+            [] => {}
+            [only] => eprintln!(
+                "For more information about this error, try `script explain {}`.",
+                only
+            ),
+            [first, ..] => {
+                eprintln!(
+                    "Some errors have detailed explanations: {}.",
+                    codes.join(", ")
+                );
+                eprintln!(
+                    "For more information about an error, try `script explain {}`.",
+                    first
+                );
+            }
+        }
     }
 }
 
@@ -45,3 +130,20 @@ impl Default for ErrorReporter {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[test]
+    fn test_explained_codes_are_listed_once() {
+        let mut reporter = ErrorReporter::with_format(ErrorFormat::Human);
+        reporter.report(Error::new(ErrorKind::ParseError, "Expected ')'"));
+        reporter.report(Error::new(ErrorKind::SemanticError, "unknown").with_code("E0425"));
+        reporter.report(Error::new(ErrorKind::ParseError, "Expected '}'"));
+        reporter.report(Error::new(ErrorKind::SemanticError, "made up").with_code("E9999"));
+
+        assert_eq!(reporter.explained_codes(), vec!["E0002", "E0425"]);
+    }
+}
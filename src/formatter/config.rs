@@ -0,0 +1,199 @@
+//! Formatter configuration and `script-fmt.toml` loading
+//!
+//! A project configures the formatter with a `script-fmt.toml` file; the
+//! nearest one found walking up from a source file's directory applies to
+//! it. Every key is optional:
+//!
+//! ```toml
+//! indent_width = 2
+//! use_tabs = false
+//! max_line_length = 80
+//! trailing_comma = "never"     # or "vertical"
+//! brace_style = "next_line"    # or "same_line"
+//! ```
+
+use crate::error::{Error, ErrorKind, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name of the formatter configuration
+pub const CONFIG_FILE: &str = "script-fmt.toml";
+
+/// Trailing comma placement in lists laid out one item per line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrailingComma {
+    /// After every item, including the last
+    Vertical,
+    /// Between items only
+    Never,
+}
+
+/// Placement of the opening brace of a declaration
+///
+/// Applies to functions, methods, structs, enums, impls and traits; braces
+/// of control flow always stay on the line that opens them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BraceStyle {
+    /// `fn main() {`
+    SameLine,
+    /// The brace on a line of its own, aligned with the declaration
+    NextLine,
+}
+
+/// Configuration for the Script formatter
+#[derive(Debug, Clone)]
+pub struct FormatterConfig {
+    /// Number of spaces for indentation
+    pub indent_size: usize,
+    /// Use spaces instead of tabs
+    pub use_spaces: bool,
+    /// Maximum line length before wrapping
+    ///
+    /// Line breaks are not yet allowed inside expressions, so lines that
+    /// stay too long after formatting are left as they are.
+    pub max_line_length: usize,
+    /// Whether to insert spaces around operators
+    pub spaces_around_operators: bool,
+    /// Trailing comma placement in multiline contexts
+    pub trailing_comma: TrailingComma,
+    /// Placement of the opening brace of declarations
+    pub brace_style: BraceStyle,
+    /// Whether to collapse empty blocks
+    pub collapse_empty_blocks: bool,
+    /// Whether to sort imports
+    pub sort_imports: bool,
+}
+
+impl Default for FormatterConfig {
+    fn default() -> Self {
+        Self {
+            indent_size: 4,
+            use_spaces: true,
+            max_line_length: 100,
+            spaces_around_operators: true,
+            trailing_comma: TrailingComma::Vertical,
+            brace_style: BraceStyle::SameLine,
+            collapse_empty_blocks: true,
+            sort_imports: true,
+        }
+    }
+}
+
+/// Keys accepted in `script-fmt.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    indent_width: Option<usize>,
+    use_tabs: Option<bool>,
+    max_line_length: Option<usize>,
+    trailing_comma: Option<TrailingComma>,
+    brace_style: Option<BraceStyle>,
+}
+
+impl FormatterConfig {
+    /// Parse a `script-fmt.toml` document; missing keys keep their defaults
+    pub fn from_toml(content: &str) -> Result<Self> {
+        let file: ConfigFile = toml::from_str(content).map_err(|e| {
+            Error::new(
+                ErrorKind::Configuration,
+                format!("Invalid {}: {}", CONFIG_FILE, e),
+            )
+        })?;
+
+        let mut config = Self::default();
+        if let Some(width) = file.indent_width {
+            if width == 0 {
+                return Err(Error::new(
+                    ErrorKind::Configuration,
+                    "indent_width must be at least 1",
+                ));
+            }
+            config.indent_size = width;
+        }
+        if let Some(use_tabs) = file.use_tabs {
+            config.use_spaces = !use_tabs;
+        }
+        if let Some(length) = file.max_line_length {
+            if length == 0 {
+                return Err(Error::new(
+                    ErrorKind::Configuration,
+                    "max_line_length must be at least 1",
+                ));
+            }
+            config.max_line_length = length;
+        }
+        if let Some(trailing_comma) = file.trailing_comma {
+            config.trailing_comma = trailing_comma;
+        }
+        if let Some(brace_style) = file.brace_style {
+            config.brace_style = brace_style;
+        }
+        Ok(config)
+    }
+
+    /// Load a configuration file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| {
+            Error::new(
+                ErrorKind::FileError,
+                format!("Failed to read '{}': {}", path.display(), e),
+            )
+        })?;
+        Self::from_toml(&content).map_err(|e| e.with_file_name(path.display().to_string()))
+    }
+
+    /// Load the configuration applying to files in `dir`, if any
+    ///
+    /// Searches `dir` and its ancestors for a `script-fmt.toml`.
+    pub fn discover(dir: &Path) -> Result<Option<Self>> {
+        match find_config_file(dir) {
+            Some(path) => Self::load(&path).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Nearest `script-fmt.toml` in `dir` or its ancestors
+pub fn find_config_file(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_from_toml() {
+        let config = FormatterConfig::from_toml(
+            "indent_width = 2\nmax_line_length = 80\ntrailing_comma = \"never\"\nbrace_style = \"next_line\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.indent_size, 2);
+        assert_eq!(config.max_line_length, 80);
+        assert_eq!(config.trailing_comma, TrailingComma::Never);
+        assert_eq!(config.brace_style, BraceStyle::NextLine);
+        assert!(config.use_spaces);
+
+        assert!(FormatterConfig::from_toml("indent = 2").is_err());
+        assert!(FormatterConfig::from_toml("brace_style = \"k&r\"").is_err());
+        assert!(FormatterConfig::from_toml("indent_width = 0").is_err());
+    }
+
+    #[test]
+    fn test_discover_walks_up() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("src").join("game");
+        fs::create_dir_all(&nested).unwrap();
+        assert!(FormatterConfig::discover(&nested).unwrap().is_none());
+
+        fs::write(dir.path().join(CONFIG_FILE), "use_tabs = true\n").unwrap();
+        let config = FormatterConfig::discover(&nested).unwrap().unwrap();
+        assert!(!config.use_spaces);
+    }
+}
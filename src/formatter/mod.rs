@@ -1,44 +1,31 @@
 //! Script Language Formatter
 //!
 //! This module provides production-quality formatting for Script language code.
+//! Whole programs are formatted with [`format_program`]; [`format_range`]
+//! reformats only the top-level declarations overlapping a range of lines,
+//! which backs range and on-type formatting in the language server, and
+//! [`format_source`] applies it to every declaration of a file for
+//! `script fmt`.
+//!
+//! Comments are not part of the syntax tree. The lexer hands them to the
+//! parser as trivia and the formatter prints each one back before the
+//! statement, field or method it precedes, or at the end of the line it
+//! trails. A region is only rewritten when its tokens and comments come out
+//! in the same order, so a comment the formatter cannot place, such as one
+//! in the middle of an expression, leaves its statement as written.
+
+mod config;
+
+pub use config::{find_config_file, BraceStyle, FormatterConfig, TrailingComma, CONFIG_FILE};
 
+use crate::error::Result;
+use crate::lexer::{Comment, Lexer, TokenKind};
 use crate::parser::{
     BinaryOp, Block, EnumVariant, ExportSpec, Expr, ExprKind, ImportSpecifier, Literal, MatchArm,
-    Method, Param, Pattern, PatternKind, Program, Stmt, StmtKind, TypeAnn, TypeKind, UnaryOp,
+    Method, Param, Parser, Pattern, PatternKind, Program, Stmt, StmtKind, TraitMethod, TypeAnn,
+    TypeKind, UnaryOp,
 };
-
-/// Configuration for the Script formatter
-#[derive(Debug, Clone)]
-pub struct FormatterConfig {
-    /// Number of spaces for indentation
-    pub indent_size: usize,
-    /// Use spaces instead of tabs
-    pub use_spaces: bool,
-    /// Maximum line length before wrapping
-    pub max_line_length: usize,
-    /// Whether to insert spaces around operators
-    pub spaces_around_operators: bool,
-    /// Whether to insert trailing commas in multiline contexts
-    pub trailing_commas: bool,
-    /// Whether to collapse empty blocks
-    pub collapse_empty_blocks: bool,
-    /// Whether to sort imports
-    pub sort_imports: bool,
-}
-
-impl Default for FormatterConfig {
-    fn default() -> Self {
-        Self {
-            indent_size: 4,
-            use_spaces: true,
-            max_line_length: 100,
-            spaces_around_operators: true,
-            trailing_commas: true,
-            collapse_empty_blocks: true,
-            sort_imports: true,
-        }
-    }
-}
+use std::ops::RangeInclusive;
 
 /// Production-quality Script language formatter
 pub struct Formatter {
@@ -46,6 +33,16 @@ pub struct Formatter {
     output: String,
     current_indent: usize,
     at_line_start: bool,
+    /// Comments of the source being formatted, in order
+    comments: Vec<Comment>,
+    /// Index of the first comment not printed yet
+    next_comment: usize,
+    /// Source line of the last statement or comment printed, unless a
+    /// brace has been opened since
+    last_line: Option<usize>,
+    /// End offsets of the statements and methods being printed, innermost
+    /// last; comments before the end of a block belong inside it
+    ends: Vec<usize>,
 }
 
 impl Formatter {
@@ -61,19 +58,35 @@ impl Formatter {
             output: String::new(),
             current_indent: 0,
             at_line_start: true,
+            comments: Vec::new(),
+            next_comment: 0,
+            last_line: None,
+            ends: Vec::new(),
         }
     }
 
     /// Format a complete program
     pub fn format_program(&mut self, program: &Program) -> String {
+        self.format_statements(&program.statements, program.comments.clone())
+    }
+
+    /// Format a sequence of top-level statements with the comments among
+    /// them
+    fn format_statements(&mut self, statements: &[Stmt], comments: Vec<Comment>) -> String {
         self.output.clear();
         self.current_indent = 0;
         self.at_line_start = true;
+        self.comments = comments;
+        self.next_comment = 0;
+        self.last_line = None;
+        self.ends.clear();
 
-        for (i, stmt) in program.statements.iter().enumerate() {
+        for (i, stmt) in statements.iter().enumerate() {
+            self.leading_comments(stmt.span.start.byte_offset);
             self.format_statement(stmt);
+            self.trailing_comment(stmt.span.end.line);
 
-            if i < program.statements.len() - 1 {
+            if i < statements.len() - 1 {
                 self.write_newline();
 
                 // Add extra spacing between function/struct/enum declarations
@@ -87,12 +100,19 @@ impl Formatter {
                 self.write_newline();
             }
         }
+        self.leading_comments(usize::MAX);
 
         self.output.clone()
     }
 
     /// Format a statement
     fn format_statement(&mut self, stmt: &Stmt) {
+        self.ends.push(stmt.span.end.byte_offset);
+        self.format_statement_kind(stmt);
+        self.ends.pop();
+    }
+
+    fn format_statement_kind(&mut self, stmt: &Stmt) {
         self.write_indent();
 
         match &stmt.kind {
@@ -140,8 +160,7 @@ impl Formatter {
                     self.format_type_annotation(ret);
                 }
 
-                self.write(" ");
-                self.format_block(body);
+                self.format_declaration_body(body);
             }
 
             StmtKind::Return(expr) => {
@@ -178,6 +197,10 @@ impl Formatter {
                 self.format_block(body);
             }
 
+            StmtKind::Break => self.write("break;"),
+
+            StmtKind::Continue => self.write("continue;"),
+
             StmtKind::Import { imports, module } => {
                 self.write("import ");
                 self.format_import_specifiers(imports);
@@ -196,21 +219,25 @@ impl Formatter {
                 self.write("struct ");
                 self.write(name);
 
-                self.write(" {\n");
+                self.open_declaration_brace();
                 self.increase_indent();
 
                 for (i, field) in fields.iter().enumerate() {
+                    self.leading_comments(field.span.start.byte_offset);
                     self.write_indent();
                     self.write(&field.name);
                     self.write(": ");
                     self.format_type_annotation(&field.type_ann);
 
-                    if i < fields.len() - 1 || self.config.trailing_commas {
+                    if i < fields.len() - 1 || self.config.trailing_comma == TrailingComma::Vertical
+                    {
                         self.write(",");
                     }
+                    self.trailing_comment(field.span.end.line);
                     self.write("\n");
                 }
 
+                self.closing_comments();
                 self.decrease_indent();
                 self.write_indent();
                 self.write("}");
@@ -220,19 +247,24 @@ impl Formatter {
                 self.write("enum ");
                 self.write(name);
 
-                self.write(" {\n");
+                self.open_declaration_brace();
                 self.increase_indent();
 
                 for (i, variant) in variants.iter().enumerate() {
+                    self.leading_comments(variant.span.start.byte_offset);
                     self.write_indent();
                     self.format_enum_variant(variant);
 
-                    if i < variants.len() - 1 || self.config.trailing_commas {
+                    if i < variants.len() - 1
+                        || self.config.trailing_comma == TrailingComma::Vertical
+                    {
                         self.write(",");
                     }
+                    self.trailing_comment(variant.span.end.line);
                     self.write("\n");
                 }
 
+                self.closing_comments();
                 self.decrease_indent();
                 self.write_indent();
                 self.write("}");
@@ -240,18 +272,49 @@ impl Formatter {
 
             StmtKind::Impl(impl_block) => {
                 self.write("impl ");
+                if let Some(trait_name) = &impl_block.trait_name {
+                    self.write(trait_name);
+                    self.write(" for ");
+                }
                 self.write(&impl_block.type_name);
 
-                self.write(" {\n");
+                self.open_declaration_brace();
                 self.increase_indent();
 
                 for (i, method) in impl_block.methods.iter().enumerate() {
                     if i > 0 {
                         self.write("\n");
                     }
+                    self.leading_comments(method.span.start.byte_offset);
                     self.format_method(method);
+                    self.trailing_comment(method.span.end.line);
+                    self.write("\n");
                 }
 
+                self.closing_comments();
+                self.decrease_indent();
+                self.write_indent();
+                self.write("}");
+            }
+
+            StmtKind::Trait(trait_decl) => {
+                self.write("trait ");
+                self.write(&trait_decl.name);
+
+                self.open_declaration_brace();
+                self.increase_indent();
+
+                for (i, method) in trait_decl.methods.iter().enumerate() {
+                    if i > 0 {
+                        self.write("\n");
+                    }
+                    self.leading_comments(method.span.start.byte_offset);
+                    self.format_trait_method(method);
+                    self.trailing_comment(method.span.end.line);
+                    self.write("\n");
+                }
+
+                self.closing_comments();
                 self.decrease_indent();
                 self.write_indent();
                 self.write("}");
@@ -351,8 +414,11 @@ impl Formatter {
                 self.write(" {\n");
                 self.increase_indent();
 
-                for arm in arms {
+                for (i, arm) in arms.iter().enumerate() {
                     self.format_match_arm(arm);
+                    if i < arms.len() - 1 || self.config.trailing_comma == TrailingComma::Vertical {
+                        self.write(",");
+                    }
                     self.write("\n");
                 }
 
@@ -370,24 +436,125 @@ impl Formatter {
 
     /// Format a block
     fn format_block(&mut self, block: &Block) {
-        if block.statements.is_empty() && self.config.collapse_empty_blocks {
+        if self.is_empty_block(block) && self.config.collapse_empty_blocks {
             self.write("{}");
             return;
         }
 
         self.write("{\n");
+        self.last_line = None;
         self.increase_indent();
 
         for stmt in &block.statements {
+            self.leading_comments(stmt.span.start.byte_offset);
             self.format_statement(stmt);
+            self.trailing_comment(stmt.span.end.line);
             self.write("\n");
         }
 
+        if let Some(expr) = &block.final_expr {
+            self.leading_comments(expr.span.start.byte_offset);
+            self.write_indent();
+            self.format_expression(expr);
+            self.trailing_comment(expr.span.end.line);
+            self.write("\n");
+        }
+
+        self.closing_comments();
         self.decrease_indent();
         self.write_indent();
         self.write("}");
     }
 
+    /// Format the body of a function or method, placing its opening brace
+    /// according to the brace style
+    fn format_declaration_body(&mut self, body: &Block) {
+        let empty = self.is_empty_block(body);
+        if self.config.brace_style == BraceStyle::NextLine
+            && !(empty && self.config.collapse_empty_blocks)
+        {
+            self.write_newline();
+            self.write_indent();
+        } else {
+            self.write(" ");
+        }
+        self.format_block(body);
+    }
+
+    /// Whether a block has neither code nor comments to print
+    fn is_empty_block(&self, block: &Block) -> bool {
+        block.statements.is_empty()
+            && block.final_expr.is_none()
+            && !self
+                .comments
+                .get(self.next_comment)
+                .is_some_and(|comment| comment.span.start.byte_offset < self.enclosing_end())
+    }
+
+    /// Open the brace of a struct, enum, impl or trait
+    fn open_declaration_brace(&mut self) {
+        match self.config.brace_style {
+            BraceStyle::SameLine => self.write(" {\n"),
+            BraceStyle::NextLine => {
+                self.write_newline();
+                self.write_indent();
+                self.write("{\n");
+            }
+        }
+        self.last_line = None;
+    }
+
+    /// Where the innermost statement or method being printed ends
+    fn enclosing_end(&self) -> usize {
+        self.ends.last().copied().unwrap_or(usize::MAX)
+    }
+
+    /// Print the comments that start before `offset`, each on its own line
+    fn leading_comments(&mut self, offset: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.span.start.byte_offset >= offset {
+                break;
+            }
+            let comment = comment.clone();
+            self.next_comment += 1;
+
+            if !self.at_line_start {
+                self.write_newline();
+            }
+            // Keep a blank line the source had above the comment
+            let after_gap = self
+                .last_line
+                .is_some_and(|line| comment.span.start.line > line + 1);
+            if after_gap && !self.output.ends_with("\n\n") {
+                self.write_newline();
+            }
+            self.write_indent();
+            self.write(&comment.text);
+            self.write_newline();
+            self.last_line = Some(comment.span.end.line);
+        }
+    }
+
+    /// Print the comments left before the closing brace of the innermost
+    /// statement or method
+    fn closing_comments(&mut self) {
+        self.leading_comments(self.enclosing_end());
+    }
+
+    /// Print a comment that follows code on `line` at the end of that line
+    fn trailing_comment(&mut self, line: usize) {
+        self.last_line = Some(line);
+        let Some(comment) = self.comments.get(self.next_comment) else {
+            return;
+        };
+        if comment.span.start.line == line && comment.span.end.line == line {
+            let text = comment.text.clone();
+            self.next_comment += 1;
+            self.write(" ");
+            self.write(&text);
+        }
+    }
+
     /// Format function parameters
     fn format_params(&mut self, params: &[Param]) {
         for (i, param) in params.iter().enumerate() {
@@ -431,6 +598,7 @@ impl Formatter {
     fn format_literal(&mut self, lit: &Literal) {
         match lit {
             Literal::Number(n) => self.write(&n.to_string()),
+            Literal::TypedNumber(n, suffix) => self.write(&format!("{}{}", n, suffix)),
             Literal::String(s) => {
                 self.write("\"");
                 self.write(&s.replace('\\', "\\\\").replace('"', "\\\""));
@@ -521,6 +689,7 @@ impl Formatter {
 
     /// Format method (simplified)
     fn format_method(&mut self, method: &Method) {
+        self.ends.push(method.span.end.byte_offset);
         self.write_indent();
         if method.is_async {
             self.write("async ");
@@ -537,8 +706,31 @@ impl Formatter {
             self.format_type_annotation(ret);
         }
 
-        self.write(" ");
-        self.format_block(&method.body);
+        self.format_declaration_body(&method.body);
+        self.ends.pop();
+    }
+
+    /// Format a trait method signature and its default body, if any
+    fn format_trait_method(&mut self, method: &TraitMethod) {
+        self.ends.push(method.span.end.byte_offset);
+        self.write_indent();
+        self.write("fn ");
+        self.write(&method.name);
+
+        self.write("(");
+        self.format_params(&method.params);
+        self.write(")");
+
+        if let Some(ret) = &method.ret_type {
+            self.write(" -> ");
+            self.format_type_annotation(ret);
+        }
+
+        match &method.default_body {
+            Some(body) => self.format_declaration_body(body),
+            None => self.write(";"),
+        }
+        self.ends.pop();
     }
 
     /// Format match arm (simplified)
@@ -553,7 +745,6 @@ impl Formatter {
 
         self.write(" => ");
         self.format_expression(&arm.body);
-        self.write(",");
     }
 
     /// Format pattern (simplified)
@@ -562,6 +753,24 @@ impl Formatter {
             PatternKind::Wildcard => self.write("_"),
             PatternKind::Literal(lit) => self.format_literal(lit),
             PatternKind::Identifier(name) => self.write(name),
+            PatternKind::Tuple(patterns) => {
+                self.write("(");
+                for (i, pattern) in patterns.iter().enumerate() {
+                    if i > 0 {
+                        self.write(", ");
+                    }
+                    self.format_pattern(pattern);
+                }
+                if patterns.len() == 1 {
+                    self.write(",");
+                }
+                self.write(")");
+            }
+            PatternKind::Binding { name, pattern } => {
+                self.write(name);
+                self.write(" @ ");
+                self.format_pattern(pattern);
+            }
             _ => {
                 // Simplified pattern formatting
                 self.write("/* pattern */");
@@ -572,10 +781,12 @@ impl Formatter {
     // Helper methods for indentation and output
 
     fn write(&mut self, text: &str) {
-        if self.at_line_start && !text.is_empty() && text != "\n" {
-            self.at_line_start = false;
+        if text.is_empty() {
+            return;
         }
         self.output.push_str(text);
+        // Text ending a line leaves the next write to indent itself
+        self.at_line_start = text.ends_with('\n');
     }
 
     fn write_newline(&mut self) {
@@ -623,3 +834,263 @@ pub fn format_program_with_config(program: &Program, config: FormatterConfig) ->
     let mut formatter = Formatter::with_config(config);
     formatter.format_program(program)
 }
+
+/// Top-level statements reformatted by [`format_range`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormattedRange {
+    /// First replaced line (1-based)
+    pub start_line: usize,
+    /// Last replaced line (1-based, inclusive)
+    pub end_line: usize,
+    /// Replacement for the lines, without a trailing newline
+    pub text: String,
+}
+
+/// Reformat the top-level statements overlapping `lines` (1-based, inclusive)
+///
+/// The replaced region grows to whole statements, including any statement
+/// sharing a line with them. Returns `None` when no statement overlaps the
+/// range, when the region is already formatted, or when formatting would not
+/// reproduce its tokens and comments in order: constructs the formatter
+/// cannot print yet, and comments it cannot place, are left alone.
+pub fn format_range(
+    source: &str,
+    config: &FormatterConfig,
+    lines: RangeInclusive<usize>,
+) -> Result<Option<FormattedRange>> {
+    let lexer = Lexer::new(source)?;
+    let (tokens, comments, errors) = lexer.scan_tokens_with_comments();
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+    let program = Parser::new(tokens).with_comments(comments).parse()?;
+    let statements = &program.statements;
+
+    let mut overlapping = statements.iter().enumerate().filter(|(_, stmt)| {
+        stmt.span.start.line <= *lines.end() && stmt.span.end.line >= *lines.start()
+    });
+    let Some((mut first, _)) = overlapping.next() else {
+        return Ok(None);
+    };
+    let mut last = overlapping.last().map_or(first, |(i, _)| i);
+
+    while first > 0 && statements[first - 1].span.end.line >= statements[first].span.start.line {
+        first -= 1;
+    }
+    while last + 1 < statements.len()
+        && statements[last + 1].span.start.line <= statements[last].span.end.line
+    {
+        last += 1;
+    }
+
+    let start_line = statements[first].span.start.line;
+    let end_line = statements[last].span.end.line;
+    let original = source
+        .lines()
+        .skip(start_line - 1)
+        .take(end_line - start_line + 1)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // A block comment reaching past the region would be cut in two
+    let comments: Vec<Comment> = program
+        .comments
+        .iter()
+        .filter(|comment| {
+            comment.span.end.line >= start_line && comment.span.start.line <= end_line
+        })
+        .cloned()
+        .collect();
+    if comments
+        .iter()
+        .any(|comment| comment.span.start.line < start_line || comment.span.end.line > end_line)
+    {
+        return Ok(None);
+    }
+
+    let mut formatter = Formatter::with_config(config.clone());
+    let formatted = formatter.format_statements(&statements[first..=last], comments);
+    let text = formatted.trim_end_matches('\n');
+
+    if text == original || !same_tokens(&original, text) {
+        return Ok(None);
+    }
+
+    Ok(Some(FormattedRange {
+        start_line,
+        end_line,
+        text: text.to_string(),
+    }))
+}
+
+/// Reformat each top-level statement of `source` that [`format_range`]
+/// can, leaving the rest as written
+///
+/// Statements are formatted one at a time from the end of the file, so a
+/// comment only keeps the statement holding it from being formatted.
+pub fn format_source(source: &str, config: &FormatterConfig) -> Result<String> {
+    let lexer = Lexer::new(source)?;
+    let (tokens, errors) = lexer.scan_tokens();
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+    let program = Parser::new(tokens).parse()?;
+
+    let mut text = source.to_string();
+    for stmt in program.statements.iter().rev() {
+        let lines = stmt.span.start.line..=stmt.span.end.line;
+        let Some(formatted) = format_range(&text, config, lines)? else {
+            continue;
+        };
+        let mut lines: Vec<&str> = text.lines().collect();
+        lines.splice(
+            formatted.start_line - 1..formatted.end_line,
+            [formatted.text.as_str()],
+        );
+        let mut next = lines.join("\n");
+        if text.ends_with('\n') {
+            next.push('\n');
+        }
+        text = next;
+    }
+    Ok(text)
+}
+
+/// Whether two sources lex to the same tokens and comments in the same
+/// order, ignoring line breaks and statement-terminating semicolons
+fn same_tokens(original: &str, formatted: &str) -> bool {
+    #[derive(PartialEq)]
+    enum Piece {
+        Token(TokenKind),
+        Comment(String),
+    }
+
+    fn significant(source: &str) -> Option<Vec<Piece>> {
+        let (tokens, comments, errors) = Lexer::new(source).ok()?.scan_tokens_with_comments();
+        if !errors.is_empty() {
+            return None;
+        }
+        let mut pieces: Vec<(usize, Piece)> = tokens
+            .into_iter()
+            .filter(|token| {
+                !matches!(
+                    token.kind,
+                    TokenKind::Newline | TokenKind::Semicolon | TokenKind::Eof
+                )
+            })
+            .map(|token| (token.span.start.byte_offset, Piece::Token(token.kind)))
+            .chain(
+                comments
+                    .into_iter()
+                    .map(|comment| (comment.span.start.byte_offset, Piece::Comment(comment.text))),
+            )
+            .collect();
+        pieces.sort_by_key(|(offset, _)| *offset);
+        Some(pieces.into_iter().map(|(_, piece)| piece).collect())
+    }
+
+    match (significant(original), significant(formatted)) {
+        (Some(original), Some(formatted)) => original == formatted,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Program {
+        let (tokens, errors) = Lexer::new(source).unwrap().scan_tokens();
+        assert!(errors.is_empty());
+        Parser::new(tokens).parse().unwrap()
+    }
+
+    #[test]
+    fn test_format_range_selects_whole_statements() {
+        let source = "fn  add(a: i32,b: i32) -> i32 {\n  return a+b;\n}\n\nlet   x = 1;\n";
+        let config = FormatterConfig::default();
+
+        let range = format_range(source, &config, 5..=5).unwrap().unwrap();
+        assert_eq!((range.start_line, range.end_line), (5, 5));
+        assert_eq!(range.text, "let x = 1;");
+
+        // A line inside the function reformats all of it
+        let range = format_range(source, &config, 2..=2).unwrap().unwrap();
+        assert_eq!((range.start_line, range.end_line), (1, 3));
+        assert_eq!(
+            range.text,
+            "fn add(a: i32, b: i32) -> i32 {\n    return a + b;\n}"
+        );
+
+        assert!(format_range(source, &config, 4..=4).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_format_range_leaves_unsafe_regions() {
+        let config = FormatterConfig::default();
+        // Already formatted
+        assert!(format_range("let y = 2;\n", &config, 1..=1)
+            .unwrap()
+            .is_none());
+        // A comment inside an expression has nowhere to go
+        assert!(format_range("let   y = /* two */ 2;\n", &config, 1..=1)
+            .unwrap()
+            .is_none());
+        assert!(format_range("let y = ;\n", &config, 1..=1).is_err());
+    }
+
+    #[test]
+    fn test_format_source_keeps_comments() {
+        let config = FormatterConfig::default();
+        let source = "let   x = 1;\n\n// Keep this spacing\nlet   y = 2;  // two\nlet   z = 3;\n";
+        assert_eq!(
+            format_source(source, &config).unwrap(),
+            "let x = 1;\n\n// Keep this spacing\nlet y = 2; // two\nlet z = 3;\n"
+        );
+        assert!(format_source("let y = ;\n", &config).is_err());
+    }
+
+    #[test]
+    fn test_comments_inside_declarations() {
+        let config = FormatterConfig::default();
+        let source = "fn main() {\n  // setup\n  let x = 1;   // one\n\n  // done\n  x+1\n}\n";
+        assert_eq!(
+            format_source(source, &config).unwrap(),
+            "fn main() {\n    // setup\n    let x = 1; // one\n\n    // done\n    x + 1\n}\n"
+        );
+
+        let source =
+            "struct Point {\n  x: i32, // across\n  /* down */\n  y: i32,\n  // more later\n}\n";
+        assert_eq!(
+            format_source(source, &config).unwrap(),
+            "struct Point {\n    x: i32, // across\n    /* down */\n    y: i32,\n    // more later\n}\n"
+        );
+
+        // Comments travel with the parsed program too
+        let (tokens, comments, _) = Lexer::new("// only a comment\nfn f() {\n}\n")
+            .unwrap()
+            .scan_tokens_with_comments();
+        let program = Parser::new(tokens).with_comments(comments).parse().unwrap();
+        assert_eq!(program.comments[0].text, "// only a comment");
+        assert_eq!(format_program(&program), "// only a comment\nfn f() {}\n");
+    }
+
+    #[test]
+    fn test_brace_style_and_trailing_commas() {
+        let config = FormatterConfig {
+            brace_style: BraceStyle::NextLine,
+            trailing_comma: TrailingComma::Never,
+            ..FormatterConfig::default()
+        };
+        let program =
+            parse("struct Point { x: i32, y: i32 }\nfn main() {\n    print(\"hi\");\n}\n");
+
+        let formatted = format_program_with_config(&program, config);
+        assert_eq!(
+            formatted,
+            "struct Point\n{\n    x: i32,\n    y: i32\n}\n\nfn main()\n{\n    print(\"hi\");\n}\n"
+        );
+        // The formatted program parses back to the same declarations
+        assert_eq!(parse(&formatted).statements.len(), 2);
+    }
+}
@@ -0,0 +1,269 @@
+use super::{FileIndex, IndexedSymbolKind, ReferenceKind, SymbolIndex};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Pseudo-node for calls made from module-level statements
+pub const TOP_LEVEL: &str = "<top-level>";
+
+/// A function or method in the call graph
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CallGraphNode {
+    pub name: String,
+    pub file: PathBuf,
+    pub line: usize,
+    pub is_entry: bool,
+    pub reachable: bool,
+}
+
+/// Project-wide call graph built from the symbol index
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CallGraph {
+    /// Nodes keyed by qualified name
+    pub nodes: BTreeMap<String, CallGraphNode>,
+    /// Caller -> set of callees, both by qualified name
+    pub edges: BTreeMap<String, BTreeSet<String>>,
+}
+
+/// Options controlling which functions count as entry points
+#[derive(Debug, Clone)]
+pub struct CallGraphOptions {
+    /// Function names treated as program entry points
+    pub entry_names: Vec<String>,
+    /// Treat `@test` functions as entry points
+    pub include_tests: bool,
+    /// Treat exported functions as entry points (for libraries)
+    pub include_exports: bool,
+}
+
+impl Default for CallGraphOptions {
+    fn default() -> Self {
+        Self {
+            entry_names: vec!["main".to_string()],
+            include_tests: true,
+            include_exports: false,
+        }
+    }
+}
+
+impl CallGraph {
+    /// Build the call graph for every function and method in the index
+    pub fn build(index: &SymbolIndex, options: &CallGraphOptions) -> Self {
+        let mut graph = CallGraph::default();
+        let mut by_name: HashMap<&str, Vec<&str>> = HashMap::new();
+
+        for (path, symbol) in index.symbols() {
+            if !matches!(
+                symbol.kind,
+                IndexedSymbolKind::Function | IndexedSymbolKind::Method
+            ) {
+                continue;
+            }
+
+            let is_entry = (symbol.kind == IndexedSymbolKind::Function
+                && options.entry_names.iter().any(|n| n == &symbol.name))
+                || (options.include_tests && symbol.attributes.iter().any(|a| a == "test"))
+                || (options.include_exports && symbol.exported);
+
+            graph.nodes.insert(
+                symbol.qualified_name.clone(),
+                CallGraphNode {
+                    name: symbol.qualified_name.clone(),
+                    file: path.to_path_buf(),
+                    line: symbol.span.start_line,
+                    is_entry,
+                    reachable: false,
+                },
+            );
+            by_name
+                .entry(symbol.name.as_str())
+                .or_default()
+                .push(symbol.qualified_name.as_str());
+        }
+
+        for (_, file) in index.files() {
+            for reference in &file.references {
+                if reference.kind != ReferenceKind::Call {
+                    continue;
+                }
+                let Some(candidates) = by_name.get(reference.name.as_str()) else {
+                    continue;
+                };
+
+                let caller = reference
+                    .container
+                    .clone()
+                    .unwrap_or_else(|| TOP_LEVEL.to_string());
+                for callee in resolve_callees(file, candidates) {
+                    graph
+                        .edges
+                        .entry(caller.clone())
+                        .or_default()
+                        .insert(callee.to_string());
+                }
+            }
+        }
+
+        graph.mark_reachable();
+        graph
+    }
+
+    /// Flood-fill reachability from entry points and module-level code
+    fn mark_reachable(&mut self) {
+        let mut queue: VecDeque<String> = self
+            .nodes
+            .values()
+            .filter(|n| n.is_entry)
+            .map(|n| n.name.clone())
+            .collect();
+        queue.push_back(TOP_LEVEL.to_string());
+
+        let mut visited = BTreeSet::new();
+        while let Some(name) = queue.pop_front() {
+            if !visited.insert(name.clone()) {
+                continue;
+            }
+            if let Some(node) = self.nodes.get_mut(&name) {
+                node.reachable = true;
+            }
+            if let Some(callees) = self.edges.get(&name) {
+                queue.extend(callees.iter().cloned());
+            }
+        }
+    }
+
+    /// Functions that cannot be reached from any entry point
+    pub fn unreachable(&self) -> Vec<&CallGraphNode> {
+        self.nodes.values().filter(|n| !n.reachable).collect()
+    }
+
+    /// Render the graph in Graphviz DOT format
+    ///
+    /// Entry points are drawn with a double border and unreachable
+    /// functions are greyed out.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph callgraph {\n    rankdir=LR;\n    node [shape=box];\n");
+
+        for node in self.nodes.values() {
+            let style = if node.is_entry {
+                " peripheries=2"
+            } else if !node.reachable {
+                " style=dashed color=gray fontcolor=gray"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                out,
+                "    \"{}\" [label=\"{}\"{}];",
+                node.name, node.name, style
+            );
+        }
+
+        for (caller, callees) in &self.edges {
+            for callee in callees {
+                let _ = writeln!(out, "    \"{}\" -> \"{}\";", caller, callee);
+            }
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render the graph as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+/// Narrow callee candidates using the caller's module and its imports
+///
+/// A call resolves to a definition in the same module when one exists,
+/// then to definitions in imported modules. If neither matches (e.g. a
+/// method call on a value of unknown type) every candidate is kept so the
+/// dead-code report errs on the side of keeping code alive.
+pub(crate) fn resolve_callees<'a>(file: &FileIndex, candidates: &[&'a str]) -> Vec<&'a str> {
+    let local_prefix = format!("{}::", file.module);
+    let local: Vec<&str> = candidates
+        .iter()
+        .copied()
+        .filter(|c| c.starts_with(&local_prefix))
+        .collect();
+    if !local.is_empty() {
+        return local;
+    }
+
+    let imported: Vec<&str> = candidates
+        .iter()
+        .copied()
+        .filter(|c| imports_definition(file, c))
+        .collect();
+    if !imported.is_empty() {
+        return imported;
+    }
+
+    candidates.to_vec()
+}
+
+/// Whether `file` imports the module that defines `qualified`
+pub(crate) fn imports_definition(file: &FileIndex, qualified: &str) -> bool {
+    file.imports.iter().any(|import| {
+        let module = import
+            .trim_start_matches("./")
+            .trim_end_matches(".script")
+            .replace('/', "::");
+        qualified.starts_with(&format!("{}::", module))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn build(source: &str) -> CallGraph {
+        let mut index = SymbolIndex::new("/project");
+        index
+            .update_file(Path::new("/project/game.script"), source)
+            .unwrap();
+        CallGraph::build(&index, &CallGraphOptions::default())
+    }
+
+    #[test]
+    fn test_unreachable_functions() {
+        let graph = build(
+            r#"
+fn helper() -> i32 { 1 }
+fn unused() -> i32 { helper() }
+fn main() { helper(); }
+"#,
+        );
+
+        let unreachable: Vec<_> = graph.unreachable().iter().map(|n| n.name.clone()).collect();
+        assert_eq!(unreachable, vec!["game::unused"]);
+        assert!(graph.edges["game::main"].contains("game::helper"));
+    }
+
+    #[test]
+    fn test_tests_and_top_level_are_roots() {
+        let graph = build(
+            r#"
+fn setup() {}
+fn checked() {}
+@test
+fn test_checked() { checked(); }
+setup();
+"#,
+        );
+
+        assert!(graph.unreachable().is_empty());
+    }
+
+    #[test]
+    fn test_dot_output() {
+        let graph = build("fn a() {}\nfn main() { a(); }");
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph callgraph {"));
+        assert!(dot.contains("\"game::main\" -> \"game::a\";"));
+    }
+}
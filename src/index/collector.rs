@@ -0,0 +1,654 @@
+use super::{
+    FileIndex, IndexSpan, IndexedSymbol, IndexedSymbolKind, ReferenceKind, SymbolReference,
+    TraitImpl,
+};
+use crate::parser::{
+    Block, EnumConstructorArgs, EnumVariantFields, ExportKind, Expr, ExprKind, ImportSpecifier,
+    Method, Param, Pattern, PatternKind, Program, Stmt, StmtKind, TypeAnn, TypeKind,
+};
+
+/// Collect all definitions and references from a parsed program
+pub fn collect_file_symbols(program: &Program, module: &str) -> FileIndex {
+    let mut collector = SymbolCollector::new(module);
+    for stmt in &program.statements {
+        collector.visit_top_level(stmt, false);
+    }
+
+    FileIndex {
+        module: module.to_string(),
+        hash: String::new(),
+        symbols: collector.symbols,
+        references: collector.references,
+        imports: collector.imports,
+        impls: collector.impls,
+    }
+}
+
+/// Walks an AST and records the symbols it defines and the names it uses
+struct SymbolCollector {
+    module: String,
+    symbols: Vec<IndexedSymbol>,
+    references: Vec<SymbolReference>,
+    imports: Vec<String>,
+    impls: Vec<TraitImpl>,
+    /// Qualified name of the function or method currently being visited
+    container: Option<String>,
+}
+
+impl SymbolCollector {
+    fn new(module: &str) -> Self {
+        Self {
+            module: module.to_string(),
+            symbols: Vec::new(),
+            references: Vec::new(),
+            imports: Vec::new(),
+            impls: Vec::new(),
+            container: None,
+        }
+    }
+
+    fn qualify(&self, name: &str) -> String {
+        if self.module.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}::{}", self.module, name)
+        }
+    }
+
+    fn define(
+        &mut self,
+        name: &str,
+        qualified_name: String,
+        kind: IndexedSymbolKind,
+        stmt: &Stmt,
+        signature: String,
+        exported: bool,
+    ) {
+        self.symbols.push(IndexedSymbol {
+            name: name.to_string(),
+            qualified_name,
+            kind,
+            span: IndexSpan::from(stmt.span),
+            signature,
+            container: self.container.clone(),
+            attributes: stmt.attributes.iter().map(|a| a.name.clone()).collect(),
+            exported,
+        });
+    }
+
+    fn reference(&mut self, name: &str, kind: ReferenceKind, expr_span: crate::source::Span) {
+        self.references.push(SymbolReference {
+            name: name.to_string(),
+            kind,
+            span: IndexSpan::from(expr_span),
+            container: self.container.clone(),
+        });
+    }
+
+    /// Visit a statement that appears at module level
+    fn visit_top_level(&mut self, stmt: &Stmt, exported: bool) {
+        match &stmt.kind {
+            StmtKind::Function {
+                name,
+                params,
+                ret_type,
+                body,
+                is_async,
+                ..
+            } => {
+                let qualified = self.qualify(name);
+                let signature = function_signature(name, params, ret_type.as_ref(), *is_async);
+                self.define(
+                    name,
+                    qualified.clone(),
+                    IndexedSymbolKind::Function,
+                    stmt,
+                    signature,
+                    exported,
+                );
+                self.visit_function_body(qualified, params, ret_type.as_ref(), body);
+            }
+            StmtKind::Let {
+                name,
+                type_ann,
+                init,
+            } => {
+                let signature = match type_ann {
+                    Some(ann) => format!("let {}: {}", name, ann),
+                    None => format!("let {}", name),
+                };
+                let qualified = self.qualify(name);
+                self.define(
+                    name,
+                    qualified,
+                    IndexedSymbolKind::Variable,
+                    stmt,
+                    signature,
+                    exported,
+                );
+                if let Some(ann) = type_ann {
+                    self.visit_type(ann);
+                }
+                if let Some(init) = init {
+                    self.visit_expr(init);
+                }
+            }
+            StmtKind::Struct { name, fields, .. } => {
+                let qualified = self.qualify(name);
+                self.define(
+                    name,
+                    qualified.clone(),
+                    IndexedSymbolKind::Struct,
+                    stmt,
+                    format!("struct {}", name),
+                    exported,
+                );
+                for field in fields {
+                    self.symbols.push(IndexedSymbol {
+                        name: field.name.clone(),
+                        qualified_name: format!("{}::{}", qualified, field.name),
+                        kind: IndexedSymbolKind::Field,
+                        span: IndexSpan::from(field.span),
+                        signature: field.to_string(),
+                        container: Some(qualified.clone()),
+                        attributes: Vec::new(),
+                        exported,
+                    });
+                    self.visit_type(&field.type_ann);
+                }
+            }
+            StmtKind::Enum { name, variants, .. } => {
+                let qualified = self.qualify(name);
+                self.define(
+                    name,
+                    qualified.clone(),
+                    IndexedSymbolKind::Enum,
+                    stmt,
+                    format!("enum {}", name),
+                    exported,
+                );
+                for variant in variants {
+                    self.symbols.push(IndexedSymbol {
+                        name: variant.name.clone(),
+                        qualified_name: format!("{}::{}", qualified, variant.name),
+                        kind: IndexedSymbolKind::Variant,
+                        span: IndexSpan::from(variant.span),
+                        signature: format!("{}::{}", name, variant.name),
+                        container: Some(qualified.clone()),
+                        attributes: Vec::new(),
+                        exported,
+                    });
+                    match &variant.fields {
+                        EnumVariantFields::Unit => {}
+                        EnumVariantFields::Tuple(types) => {
+                            for ty in types {
+                                self.visit_type(ty);
+                            }
+                        }
+                        EnumVariantFields::Struct(fields) => {
+                            for field in fields {
+                                self.visit_type(&field.type_ann);
+                            }
+                        }
+                    }
+                }
+            }
+            StmtKind::Impl(impl_block) => {
+                self.reference(&impl_block.type_name, ReferenceKind::Type, impl_block.span);
+                if let Some(trait_name) = &impl_block.trait_name {
+                    self.reference(trait_name, ReferenceKind::Type, impl_block.span);
+                    self.impls.push(TraitImpl {
+                        type_name: impl_block.type_name.clone(),
+                        trait_name: trait_name.clone(),
+                        span: IndexSpan::from(impl_block.span),
+                    });
+                }
+                let type_qualified = self.qualify(&impl_block.type_name);
+                for method in &impl_block.methods {
+                    self.visit_method(&type_qualified, method, exported);
+                }
+            }
+            StmtKind::Trait(trait_decl) => {
+                let qualified = self.qualify(&trait_decl.name);
+                self.define(
+                    &trait_decl.name,
+                    qualified.clone(),
+                    IndexedSymbolKind::Trait,
+                    stmt,
+                    format!("trait {}", trait_decl.name),
+                    exported,
+                );
+                for method in &trait_decl.methods {
+                    let method_qualified = format!("{}::{}", qualified, method.name);
+                    self.symbols.push(IndexedSymbol {
+                        name: method.name.clone(),
+                        qualified_name: method_qualified.clone(),
+                        kind: IndexedSymbolKind::Method,
+                        span: IndexSpan::from(method.span),
+                        signature: function_signature(
+                            &method.name,
+                            &method.params,
+                            method.ret_type.as_ref(),
+                            false,
+                        ),
+                        container: Some(qualified.clone()),
+                        attributes: Vec::new(),
+                        exported,
+                    });
+                    if let Some(body) = &method.default_body {
+                        self.visit_function_body(
+                            method_qualified,
+                            &method.params,
+                            method.ret_type.as_ref(),
+                            body,
+                        );
+                    } else {
+                        for param in &method.params {
+                            self.visit_type(&param.type_ann);
+                        }
+                        if let Some(ret) = &method.ret_type {
+                            self.visit_type(ret);
+                        }
+                    }
+                }
+            }
+            StmtKind::Import { imports, module } => {
+                self.imports.push(module.clone());
+                for spec in imports {
+                    if let ImportSpecifier::Named { name, .. } = spec {
+                        self.reference(name, ReferenceKind::Import, stmt.span);
+                    }
+                }
+            }
+            StmtKind::Export { export } => match export {
+                ExportKind::Declaration(inner) => self.visit_top_level(inner, true),
+                ExportKind::Function {
+                    name,
+                    params,
+                    ret_type,
+                    body,
+                    is_async,
+                } => {
+                    let qualified = self.qualify(name);
+                    let signature = function_signature(name, params, ret_type.as_ref(), *is_async);
+                    self.define(
+                        name,
+                        qualified.clone(),
+                        IndexedSymbolKind::Function,
+                        stmt,
+                        signature,
+                        true,
+                    );
+                    self.visit_function_body(qualified, params, ret_type.as_ref(), body);
+                }
+                ExportKind::Variable {
+                    name,
+                    type_ann,
+                    init,
+                } => {
+                    let qualified = self.qualify(name);
+                    self.define(
+                        name,
+                        qualified,
+                        IndexedSymbolKind::Variable,
+                        stmt,
+                        format!("let {}", name),
+                        true,
+                    );
+                    if let Some(ann) = type_ann {
+                        self.visit_type(ann);
+                    }
+                    if let Some(init) = init {
+                        self.visit_expr(init);
+                    }
+                }
+                ExportKind::Named { specifiers } => {
+                    for spec in specifiers {
+                        self.reference(&spec.name, ReferenceKind::Read, stmt.span);
+                    }
+                }
+                ExportKind::Default { expr } => self.visit_expr(expr),
+            },
+            _ => self.visit_stmt(stmt),
+        }
+    }
+
+    fn visit_method(&mut self, type_qualified: &str, method: &Method, exported: bool) {
+        let qualified = format!("{}::{}", type_qualified, method.name);
+        let signature = function_signature(
+            &method.name,
+            &method.params,
+            method.ret_type.as_ref(),
+            method.is_async,
+        );
+        self.symbols.push(IndexedSymbol {
+            name: method.name.clone(),
+            qualified_name: qualified.clone(),
+            kind: IndexedSymbolKind::Method,
+            span: IndexSpan::from(method.span),
+            signature,
+            container: Some(type_qualified.to_string()),
+            attributes: Vec::new(),
+            exported,
+        });
+        self.visit_function_body(
+            qualified,
+            &method.params,
+            method.ret_type.as_ref(),
+            &method.body,
+        );
+    }
+
+    fn visit_function_body(
+        &mut self,
+        qualified: String,
+        params: &[Param],
+        ret_type: Option<&TypeAnn>,
+        body: &Block,
+    ) {
+        for param in params {
+            self.visit_type(&param.type_ann);
+        }
+        if let Some(ret) = ret_type {
+            self.visit_type(ret);
+        }
+
+        let previous = self.container.replace(qualified);
+        self.visit_block(body);
+        self.container = previous;
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        for stmt in &block.statements {
+            self.visit_stmt(stmt);
+        }
+        if let Some(expr) = &block.final_expr {
+            self.visit_expr(expr);
+        }
+    }
+
+    /// Visit a statement nested inside a function body
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Let { type_ann, init, .. } => {
+                if let Some(ann) = type_ann {
+                    self.visit_type(ann);
+                }
+                if let Some(init) = init {
+                    self.visit_expr(init);
+                }
+            }
+            StmtKind::Return(expr) => {
+                if let Some(expr) = expr {
+                    self.visit_expr(expr);
+                }
+            }
+            StmtKind::Expression(expr) => self.visit_expr(expr),
+            StmtKind::While { condition, body } => {
+                self.visit_expr(condition);
+                self.visit_block(body);
+            }
+            StmtKind::For { iterable, body, .. } => {
+                self.visit_expr(iterable);
+                self.visit_block(body);
+            }
+            StmtKind::Break | StmtKind::Continue => {}
+            StmtKind::Function { .. }
+            | StmtKind::Struct { .. }
+            | StmtKind::Enum { .. }
+            | StmtKind::Impl(_)
+            | StmtKind::Trait(_)
+            | StmtKind::Import { .. }
+            | StmtKind::Export { .. } => self.visit_top_level(stmt, false),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Literal(_) => {}
+            ExprKind::Identifier(name) => self.reference(name, ReferenceKind::Read, expr.span),
+            ExprKind::Binary { left, right, .. } => {
+                self.visit_expr(left);
+                self.visit_expr(right);
+            }
+            ExprKind::Unary { expr: inner, .. } => self.visit_expr(inner),
+            ExprKind::Call { callee, args } => {
+                match &callee.kind {
+                    ExprKind::Identifier(name) => {
+                        self.reference(name, ReferenceKind::Call, callee.span)
+                    }
+                    ExprKind::Member { object, property } => {
+                        self.visit_expr(object);
+                        self.reference(property, ReferenceKind::Call, callee.span);
+                    }
+                    _ => self.visit_expr(callee),
+                }
+                for arg in args {
+                    self.visit_expr(arg);
+                }
+            }
+            ExprKind::Index { object, index } => {
+                self.visit_expr(object);
+                self.visit_expr(index);
+            }
+            ExprKind::Member { object, property } => {
+                self.visit_expr(object);
+                self.reference(property, ReferenceKind::Read, expr.span);
+            }
+            ExprKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.visit_expr(condition);
+                self.visit_expr(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.visit_expr(else_branch);
+                }
+            }
+            ExprKind::Block(block) => self.visit_block(block),
+            ExprKind::Array(elements) => {
+                for element in elements {
+                    self.visit_expr(element);
+                }
+            }
+            ExprKind::Assign { target, value } => {
+                match &target.kind {
+                    ExprKind::Identifier(name) => {
+                        self.reference(name, ReferenceKind::Write, target.span)
+                    }
+                    _ => self.visit_expr(target),
+                }
+                self.visit_expr(value);
+            }
+            ExprKind::Match {
+                expr: scrutinee,
+                arms,
+            } => {
+                self.visit_expr(scrutinee);
+                for arm in arms {
+                    self.visit_pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.visit_expr(guard);
+                    }
+                    self.visit_expr(&arm.body);
+                }
+            }
+            ExprKind::Await { expr: inner } | ExprKind::ErrorPropagation { expr: inner } => {
+                self.visit_expr(inner)
+            }
+            ExprKind::ListComprehension {
+                element,
+                iterable,
+                condition,
+                ..
+            } => {
+                self.visit_expr(element);
+                self.visit_expr(iterable);
+                if let Some(condition) = condition {
+                    self.visit_expr(condition);
+                }
+            }
+            ExprKind::GenericConstructor { name, type_args } => {
+                self.reference(name, ReferenceKind::Type, expr.span);
+                for arg in type_args {
+                    self.visit_type(arg);
+                }
+            }
+            ExprKind::StructConstructor { name, fields } => {
+                self.reference(name, ReferenceKind::Type, expr.span);
+                for (_, value) in fields {
+                    self.visit_expr(value);
+                }
+            }
+            ExprKind::EnumConstructor {
+                enum_name,
+                variant,
+                args,
+            } => {
+                if let Some(enum_name) = enum_name {
+                    self.reference(enum_name, ReferenceKind::Type, expr.span);
+                }
+                self.reference(variant, ReferenceKind::Call, expr.span);
+                match args {
+                    EnumConstructorArgs::Unit => {}
+                    EnumConstructorArgs::Tuple(values) => {
+                        for value in values {
+                            self.visit_expr(value);
+                        }
+                    }
+                    EnumConstructorArgs::Struct(fields) => {
+                        for (_, value) in fields {
+                            self.visit_expr(value);
+                        }
+                    }
+                }
+            }
+            ExprKind::TryCatch {
+                try_expr,
+                catch_clauses,
+                finally_block,
+            } => {
+                self.visit_expr(try_expr);
+                for clause in catch_clauses {
+                    if let Some(ty) = &clause.error_type {
+                        self.visit_type(ty);
+                    }
+                    if let Some(condition) = &clause.condition {
+                        self.visit_expr(condition);
+                    }
+                    self.visit_block(&clause.handler);
+                }
+                if let Some(finally_block) = finally_block {
+                    self.visit_block(finally_block);
+                }
+            }
+            ExprKind::Closure { parameters, body } => {
+                for param in parameters {
+                    if let Some(ty) = &param.type_ann {
+                        self.visit_type(ty);
+                    }
+                }
+                self.visit_expr(body);
+            }
+            ExprKind::Cast { expr, target_type } => {
+                self.visit_type(target_type);
+                self.visit_expr(expr);
+            }
+        }
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Identifier(_) | PatternKind::Literal(_) => {}
+            PatternKind::Array(patterns)
+            | PatternKind::Tuple(patterns)
+            | PatternKind::Or(patterns) => {
+                for pattern in patterns {
+                    self.visit_pattern(pattern);
+                }
+            }
+            PatternKind::Binding { pattern, .. } => self.visit_pattern(pattern),
+            PatternKind::Object(fields) => {
+                for (_, pattern) in fields {
+                    if let Some(pattern) = pattern {
+                        self.visit_pattern(pattern);
+                    }
+                }
+            }
+            PatternKind::EnumConstructor {
+                enum_name,
+                variant,
+                args,
+            } => {
+                if let Some(enum_name) = enum_name {
+                    self.reference(enum_name, ReferenceKind::Type, pattern.span);
+                }
+                self.reference(variant, ReferenceKind::Read, pattern.span);
+                if let Some(args) = args {
+                    for arg in args {
+                        self.visit_pattern(arg);
+                    }
+                }
+            }
+        }
+    }
+
+    fn visit_type(&mut self, ty: &TypeAnn) {
+        match &ty.kind {
+            TypeKind::Named(name) => {
+                if !is_builtin_type(name) {
+                    self.reference(name, ReferenceKind::Type, ty.span);
+                }
+            }
+            TypeKind::Array(inner) => self.visit_type(inner),
+            TypeKind::Function { params, ret } => {
+                for param in params {
+                    self.visit_type(param);
+                }
+                self.visit_type(ret);
+            }
+            TypeKind::Generic { name, args } => {
+                if !is_builtin_type(name) {
+                    self.reference(name, ReferenceKind::Type, ty.span);
+                }
+                for arg in args {
+                    self.visit_type(arg);
+                }
+            }
+            TypeKind::TypeParam(_) => {}
+            TypeKind::Tuple(types) => {
+                for ty in types {
+                    self.visit_type(ty);
+                }
+            }
+            TypeKind::Reference { inner, .. } => self.visit_type(inner),
+        }
+    }
+}
+
+/// Render a compact, single-line signature for a function or method
+fn function_signature(
+    name: &str,
+    params: &[Param],
+    ret_type: Option<&TypeAnn>,
+    is_async: bool,
+) -> String {
+    let params = params
+        .iter()
+        .map(|p| format!("{}: {}", p.name, p.type_ann))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let prefix = if is_async { "async fn" } else { "fn" };
+    match ret_type {
+        Some(ret) => format!("{} {}({}) -> {}", prefix, name, params, ret),
+        None => format!("{} {}({})", prefix, name, params),
+    }
+}
+
+fn is_builtin_type(name: &str) -> bool {
+    matches!(
+        name,
+        "i32" | "f32" | "bool" | "string" | "unknown" | "Never" | "Option" | "Result" | "Vec"
+    )
+}
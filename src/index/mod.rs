@@ -0,0 +1,615 @@
+//! Persistent project-wide symbol index
+//!
+//! The index records every definition (functions, methods, types, variants,
+//! fields and module-level bindings) and every reference to a name across a
+//! project, grouped by file. Each file entry stores a hash of the source it
+//! was built from so the index can be refreshed incrementally: only files
+//! whose contents changed are re-parsed.
+//!
+//! The index is stored as JSON in `.script/index.json` under the project
+//! root. It backs workspace symbol search and find-references in the LSP,
+//! the documentation search index, and `script grep-def <name>`.
+
+pub mod callgraph;
+mod collector;
+
+pub use callgraph::{CallGraph, CallGraphNode, CallGraphOptions};
+pub use collector::collect_file_symbols;
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::lexer::Lexer;
+use crate::parser::{Parser, Program};
+use crate::source::Span;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory (relative to the project root) holding compiler caches
+pub const INDEX_DIR: &str = ".script";
+/// File name of the persisted symbol index
+pub const INDEX_FILE: &str = "index.json";
+/// Bumped whenever the on-disk layout changes; older indexes are rebuilt
+pub const INDEX_FORMAT_VERSION: u32 = 2;
+
+/// Kind of an indexed definition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IndexedSymbolKind {
+    Function,
+    Method,
+    Struct,
+    Enum,
+    Trait,
+    Variant,
+    Field,
+    Variable,
+}
+
+impl fmt::Display for IndexedSymbolKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            IndexedSymbolKind::Function => "function",
+            IndexedSymbolKind::Method => "method",
+            IndexedSymbolKind::Struct => "struct",
+            IndexedSymbolKind::Enum => "enum",
+            IndexedSymbolKind::Trait => "trait",
+            IndexedSymbolKind::Variant => "variant",
+            IndexedSymbolKind::Field => "field",
+            IndexedSymbolKind::Variable => "variable",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How a name is used at a reference site
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ReferenceKind {
+    /// The name is read as a value
+    Read,
+    /// The name is assigned to
+    Write,
+    /// The name is called as a function, method or variant constructor
+    Call,
+    /// The name is used as a type
+    Type,
+    /// The name is imported from another module
+    Import,
+}
+
+/// A serializable source range (1-based lines and columns)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IndexSpan {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl From<Span> for IndexSpan {
+    fn from(span: Span) -> Self {
+        Self {
+            start_line: span.start.line,
+            start_column: span.start.column,
+            end_line: span.end.line,
+            end_column: span.end.column,
+        }
+    }
+}
+
+impl fmt::Display for IndexSpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.start_line, self.start_column)
+    }
+}
+
+/// A definition recorded in the index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedSymbol {
+    /// Simple name (e.g. `update`)
+    pub name: String,
+    /// Module-qualified name (e.g. `game::player::Player::update`)
+    pub qualified_name: String,
+    pub kind: IndexedSymbolKind,
+    pub span: IndexSpan,
+    /// Single-line rendering of the declaration
+    pub signature: String,
+    /// Qualified name of the enclosing item, if any
+    pub container: Option<String>,
+    /// Attribute names attached to the declaration (e.g. `test`)
+    pub attributes: Vec<String>,
+    /// Whether the definition is exported from its module
+    pub exported: bool,
+}
+
+/// A use of a name recorded in the index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolReference {
+    pub name: String,
+    pub kind: ReferenceKind,
+    pub span: IndexSpan,
+    /// Qualified name of the function or method containing the reference
+    pub container: Option<String>,
+}
+
+/// A trait implemented for a type by an `impl Trait for Type` block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraitImpl {
+    /// Simple name of the implementing type
+    pub type_name: String,
+    /// Simple name of the implemented trait
+    pub trait_name: String,
+    pub span: IndexSpan,
+}
+
+/// Index entry for a single source file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileIndex {
+    /// Module path derived from the file location (e.g. `game::player`)
+    pub module: String,
+    /// SHA-256 of the source the entry was built from
+    pub hash: String,
+    pub symbols: Vec<IndexedSymbol>,
+    pub references: Vec<SymbolReference>,
+    /// Modules imported by this file
+    pub imports: Vec<String>,
+    /// Trait implementations declared in this file
+    pub impls: Vec<TraitImpl>,
+}
+
+/// Result of refreshing an index against the files on disk
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexRefresh {
+    pub updated: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub unchanged: usize,
+    /// Files that could not be read or parsed, with the reason
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Persistent project-wide symbol database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolIndex {
+    version: u32,
+    /// Entries keyed by path relative to the project root
+    files: BTreeMap<PathBuf, FileIndex>,
+    #[serde(skip)]
+    root: PathBuf,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl SymbolIndex {
+    /// Create an empty index for the project rooted at `root`
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            version: INDEX_FORMAT_VERSION,
+            files: BTreeMap::new(),
+            root: root.into(),
+            dirty: false,
+        }
+    }
+
+    /// Location of the persisted index for a project root
+    pub fn index_path(root: &Path) -> PathBuf {
+        root.join(INDEX_DIR).join(INDEX_FILE)
+    }
+
+    /// Load the index for a project, or start a fresh one if none exists
+    ///
+    /// An index written by an incompatible format version is discarded.
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = Self::index_path(root);
+        if !path.exists() {
+            return Ok(Self::new(root));
+        }
+
+        let content = fs::read_to_string(&path).map_err(|e| {
+            Error::new(
+                ErrorKind::FileError,
+                format!("Failed to read symbol index '{}': {}", path.display(), e),
+            )
+        })?;
+
+        match serde_json::from_str::<SymbolIndex>(&content) {
+            Ok(mut index) if index.version == INDEX_FORMAT_VERSION => {
+                index.root = root.to_path_buf();
+                Ok(index)
+            }
+            // Stale or corrupt indexes are rebuilt rather than reported
+            _ => {
+                let mut index = Self::new(root);
+                index.dirty = true;
+                Ok(index)
+            }
+        }
+    }
+
+    /// Write the index to disk if it changed since it was loaded
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let path = Self::index_path(&self.root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| {
+                Error::new(
+                    ErrorKind::FileError,
+                    format!("Failed to create '{}': {}", parent.display(), e),
+                )
+            })?;
+        }
+
+        let content = serde_json::to_string_pretty(self).map_err(|e| {
+            Error::new(
+                ErrorKind::InternalError,
+                format!("Failed to serialize symbol index: {}", e),
+            )
+        })?;
+
+        fs::write(&path, content).map_err(|e| {
+            Error::new(
+                ErrorKind::FileError,
+                format!("Failed to write symbol index '{}': {}", path.display(), e),
+            )
+        })?;
+
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Project root this index describes
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the index has unsaved changes
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Re-index a file from its source text
+    ///
+    /// Returns `false` without re-parsing when the source is unchanged since
+    /// the file was last indexed.
+    pub fn update_file(&mut self, path: &Path, source: &str) -> Result<bool> {
+        let key = self.relative_key(path);
+        let hash = hash_source(source);
+        if self
+            .files
+            .get(&key)
+            .map(|f| f.hash == hash)
+            .unwrap_or(false)
+        {
+            return Ok(false);
+        }
+
+        let lexer = Lexer::new(source)?;
+        let (tokens, errors) = lexer.scan_tokens();
+        if let Some(error) = errors.into_iter().next() {
+            return Err(error.with_file_name(path.to_string_lossy().to_string()));
+        }
+        let mut parser = Parser::new(tokens);
+        let program = parser
+            .parse()
+            .map_err(|e| e.with_file_name(path.to_string_lossy().to_string()))?;
+
+        self.insert(key, hash, &program);
+        Ok(true)
+    }
+
+    /// Re-index a file that has already been parsed
+    ///
+    /// Used by the compilation pipeline so that indexing does not parse
+    /// every file a second time.
+    pub fn update_program(&mut self, path: &Path, source: &str, program: &Program) -> bool {
+        let key = self.relative_key(path);
+        let hash = hash_source(source);
+        if self
+            .files
+            .get(&key)
+            .map(|f| f.hash == hash)
+            .unwrap_or(false)
+        {
+            return false;
+        }
+
+        self.insert(key, hash, program);
+        true
+    }
+
+    fn insert(&mut self, key: PathBuf, hash: String, program: &Program) {
+        let module = module_name_for(&key);
+        let mut entry = collect_file_symbols(program, &module);
+        entry.hash = hash;
+        self.files.insert(key, entry);
+        self.dirty = true;
+    }
+
+    /// Drop a file from the index
+    pub fn remove_file(&mut self, path: &Path) -> bool {
+        let key = self.relative_key(path);
+        let removed = self.files.remove(&key).is_some();
+        self.dirty |= removed;
+        removed
+    }
+
+    /// Bring the index up to date with every `.script` file under the root
+    pub fn refresh(&mut self) -> Result<IndexRefresh> {
+        let mut report = IndexRefresh::default();
+        let mut seen = Vec::new();
+
+        for entry in walkdir::WalkDir::new(&self.root)
+            .into_iter()
+            .filter_entry(|e| !is_ignored_dir(e.path()))
+        {
+            let entry = entry.map_err(|e| {
+                Error::new(
+                    ErrorKind::FileError,
+                    format!("Failed to walk project directory: {}", e),
+                )
+            })?;
+            let path = entry.path();
+            if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some("script") {
+                continue;
+            }
+
+            seen.push(self.relative_key(path));
+            let source = match fs::read_to_string(path) {
+                Ok(source) => source,
+                Err(e) => {
+                    report.failed.push((path.to_path_buf(), e.to_string()));
+                    continue;
+                }
+            };
+
+            match self.update_file(path, &source) {
+                Ok(true) => report.updated.push(path.to_path_buf()),
+                Ok(false) => report.unchanged += 1,
+                Err(e) => report.failed.push((path.to_path_buf(), e.message)),
+            }
+        }
+
+        let stale: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|key| !seen.contains(key))
+            .cloned()
+            .collect();
+        for key in stale {
+            self.files.remove(&key);
+            self.dirty = true;
+            report.removed.push(self.root.join(key));
+        }
+
+        Ok(report)
+    }
+
+    /// Iterate over indexed files as `(relative path, entry)`
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &FileIndex)> {
+        self.files
+            .iter()
+            .map(|(path, entry)| (path.as_path(), entry))
+    }
+
+    /// Look up the entry for a single file
+    pub fn file(&self, path: &Path) -> Option<&FileIndex> {
+        self.files.get(&self.relative_key(path))
+    }
+
+    /// Iterate over every definition as `(relative path, symbol)`
+    pub fn symbols(&self) -> impl Iterator<Item = (&Path, &IndexedSymbol)> {
+        self.files
+            .iter()
+            .flat_map(|(path, entry)| entry.symbols.iter().map(move |s| (path.as_path(), s)))
+    }
+
+    /// Iterate over every reference as `(relative path, reference)`
+    pub fn references(&self) -> impl Iterator<Item = (&Path, &SymbolReference)> {
+        self.files
+            .iter()
+            .flat_map(|(path, entry)| entry.references.iter().map(move |r| (path.as_path(), r)))
+    }
+
+    /// Iterate over every trait implementation as `(relative path, impl)`
+    pub fn trait_impls(&self) -> impl Iterator<Item = (&Path, &TraitImpl)> {
+        self.files
+            .iter()
+            .flat_map(|(path, entry)| entry.impls.iter().map(move |i| (path.as_path(), i)))
+    }
+
+    /// Find definitions by simple or qualified name
+    pub fn find_definitions(&self, name: &str) -> Vec<(&Path, &IndexedSymbol)> {
+        self.symbols()
+            .filter(|(_, s)| s.name == name || s.qualified_name == name)
+            .collect()
+    }
+
+    /// Find every reference to a simple name
+    pub fn find_references(&self, name: &str) -> Vec<(&Path, &SymbolReference)> {
+        self.references().filter(|(_, r)| r.name == name).collect()
+    }
+
+    /// Case-insensitive symbol search ranked by exact, prefix, then substring match
+    pub fn search(&self, query: &str) -> Vec<(&Path, &IndexedSymbol)> {
+        let query = query.to_lowercase();
+        let mut results: Vec<(u8, &Path, &IndexedSymbol)> = self
+            .symbols()
+            .filter_map(|(path, symbol)| {
+                let name = symbol.name.to_lowercase();
+                let rank = if name == query {
+                    0
+                } else if name.starts_with(&query) {
+                    1
+                } else if name.contains(&query)
+                    || symbol.qualified_name.to_lowercase().contains(&query)
+                {
+                    2
+                } else {
+                    return None;
+                };
+                Some((rank, path, symbol))
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then_with(|| a.2.name.len().cmp(&b.2.name.len()))
+                .then_with(|| a.2.qualified_name.cmp(&b.2.qualified_name))
+        });
+
+        results
+            .into_iter()
+            .map(|(_, path, symbol)| (path, symbol))
+            .collect()
+    }
+
+    fn relative_key(&self, path: &Path) -> PathBuf {
+        path.strip_prefix(&self.root)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+}
+
+/// Derive a `::`-separated module path from a relative file path
+pub fn module_name_for(relative: &Path) -> String {
+    let without_ext = relative.with_extension("");
+    without_ext
+        .components()
+        .filter_map(|c| c.as_os_str().to_str())
+        .filter(|c| *c != "src" && *c != ".")
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn hash_source(source: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(source.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+pub(crate) fn is_ignored_dir(path: &Path) -> bool {
+    path.is_dir()
+        && matches!(
+            path.file_name().and_then(|n| n.to_str()),
+            Some(INDEX_DIR) | Some("target") | Some("node_modules") | Some(".git")
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SOURCE: &str = r#"
+struct Player {
+    health: i32
+}
+
+impl Player {
+    fn heal(amount: i32) -> i32 {
+        clamp(amount)
+    }
+}
+
+fn clamp(value: i32) -> i32 {
+    value
+}
+
+fn main() {
+    let p = Player { health: 10 };
+    clamp(5);
+}
+"#;
+
+    #[test]
+    fn test_collects_definitions_and_references() {
+        let mut index = SymbolIndex::new("/project");
+        let changed = index
+            .update_file(Path::new("/project/src/game.script"), SOURCE)
+            .unwrap();
+        assert!(changed);
+
+        let defs = index.find_definitions("clamp");
+        assert_eq!(defs.len(), 1);
+        assert_eq!(defs[0].1.qualified_name, "game::clamp");
+        assert_eq!(defs[0].1.kind, IndexedSymbolKind::Function);
+
+        let method = index.find_definitions("game::Player::heal");
+        assert_eq!(method.len(), 1);
+        assert_eq!(method[0].1.kind, IndexedSymbolKind::Method);
+
+        let calls: Vec<_> = index
+            .find_references("clamp")
+            .into_iter()
+            .filter(|(_, r)| r.kind == ReferenceKind::Call)
+            .collect();
+        assert_eq!(calls.len(), 2);
+        assert!(calls
+            .iter()
+            .any(|(_, r)| r.container.as_deref() == Some("game::Player::heal")));
+    }
+
+    #[test]
+    fn test_unchanged_source_is_not_reindexed() {
+        let mut index = SymbolIndex::new("/project");
+        let path = Path::new("/project/game.script");
+        assert!(index.update_file(path, SOURCE).unwrap());
+        assert!(!index.update_file(path, SOURCE).unwrap());
+        assert!(index.update_file(path, "fn other() {}").unwrap());
+        assert!(index.find_definitions("clamp").is_empty());
+    }
+
+    #[test]
+    fn test_search_ranks_exact_matches_first() {
+        let mut index = SymbolIndex::new("/project");
+        index
+            .update_file(
+                Path::new("/project/a.script"),
+                "fn update_all() {}\nfn update() {}\nfn pre_update() {}",
+            )
+            .unwrap();
+
+        let names: Vec<_> = index
+            .search("update")
+            .into_iter()
+            .map(|(_, s)| s.name.clone())
+            .collect();
+        assert_eq!(names, vec!["update", "update_all", "pre_update"]);
+    }
+
+    #[test]
+    fn test_persist_and_refresh() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("main.script");
+        fs::write(&file, "fn main() {}").unwrap();
+
+        let mut index = SymbolIndex::load(dir.path()).unwrap();
+        let report = index.refresh().unwrap();
+        assert_eq!(report.updated.len(), 1);
+        index.save().unwrap();
+        assert!(SymbolIndex::index_path(dir.path()).exists());
+
+        let mut reloaded = SymbolIndex::load(dir.path()).unwrap();
+        assert_eq!(reloaded.find_definitions("main").len(), 1);
+        let report = reloaded.refresh().unwrap();
+        assert!(report.updated.is_empty());
+        assert_eq!(report.unchanged, 1);
+
+        fs::remove_file(&file).unwrap();
+        let report = reloaded.refresh().unwrap();
+        assert_eq!(report.removed.len(), 1);
+        assert!(reloaded.find_definitions("main").is_empty());
+    }
+
+    #[test]
+    fn test_module_name_for() {
+        assert_eq!(
+            module_name_for(Path::new("src/game/player.script")),
+            "game::player"
+        );
+        assert_eq!(module_name_for(Path::new("main.script")), "main");
+    }
+}
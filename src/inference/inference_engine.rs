@@ -3,9 +3,10 @@ use crate::compilation::resource_limits::{ResourceLimits, ResourceMonitor};
 use crate::error::{Error, ErrorKind};
 use crate::parser::{
     BinaryOp, Block, Expr, ExprKind, Literal, Pattern, PatternKind, Program, Stmt, StmtKind,
-    UnaryOp,
+    TypeAnn, TypeKind, UnaryOp,
 };
 use crate::source::Span;
+use crate::types::conversion::{cast_result_type, suffix_type};
 use crate::types::Type;
 use std::collections::HashMap;
 
@@ -31,6 +32,8 @@ pub struct InferenceEngine {
     resource_monitor: ResourceMonitor,
     /// Recursion depth tracking for stack overflow protection
     recursion_depth: usize,
+    /// Give names that are not in scope the unknown type instead of failing
+    allow_undefined: bool,
 }
 
 impl InferenceEngine {
@@ -47,6 +50,7 @@ impl InferenceEngine {
             stmt_types: HashMap::new(),
             resource_monitor: ResourceMonitor::new(limits),
             recursion_depth: 0,
+            allow_undefined: false,
         }
     }
 
@@ -60,6 +64,18 @@ impl InferenceEngine {
         Self::with_resource_limits(ResourceLimits::testing())
     }
 
+    /// Create a new inference engine for editor tooling
+    ///
+    /// Documents being edited often use names this engine cannot see, such
+    /// as imports, so undefined names get the unknown type rather than
+    /// failing the whole program.
+    pub fn for_editor() -> Self {
+        InferenceEngine {
+            allow_undefined: true,
+            ..Self::for_development()
+        }
+    }
+
     /// Infer types for a program
     pub fn infer_program(&mut self, program: &Program) -> Result<InferenceResult, Error> {
         // Start resource monitoring for type inference phase
@@ -104,6 +120,18 @@ impl InferenceEngine {
 
     /// Initialize built-in functions and types
     fn initialize_builtins(&mut self) {
+        // Standard library functions. Parameters are left open since several
+        // builtins accept more than their signature spells out; return types
+        // are kept so calls to them have useful types
+        let stdlib = crate::stdlib::StdLib::new();
+        for name in stdlib.function_names() {
+            if let Some(function) = stdlib.get_function(name) {
+                self.context
+                    .type_env_mut()
+                    .define(name.to_string(), builtin_type(&function.signature));
+            }
+        }
+
         // Add print function: (unknown) -> ()
         let print_type = Type::Function {
             params: vec![Type::Unknown],
@@ -112,6 +140,13 @@ impl InferenceEngine {
         self.context
             .type_env_mut()
             .define("print".to_string(), print_type);
+
+        // Standard library constants such as PI and MAX_I32
+        for name in crate::stdlib::constants::CONSTANT_NAMES {
+            if let Some(ty) = crate::stdlib::constants::constant_type(name) {
+                self.context.type_env_mut().define(name.to_string(), ty);
+            }
+        }
     }
 
     /// Infer type for a statement
@@ -284,6 +319,11 @@ impl InferenceEngine {
                 Type::Unknown // Unit type would be better
             }
 
+            StmtKind::Break | StmtKind::Continue => {
+                // Loop control doesn't produce values
+                Type::Unknown
+            }
+
             StmtKind::Import { .. } => {
                 // Import statements don't produce values
                 Type::Unknown
@@ -306,10 +346,19 @@ impl InferenceEngine {
                 Type::Named(name.clone())
             }
 
-            StmtKind::Impl(_) => {
+            StmtKind::Impl(impl_block) => {
                 // TODO: Implement impl block type inference
+                if let Some(trait_name) = &impl_block.trait_name {
+                    let target = type_ann_to_type(&TypeAnn {
+                        kind: TypeKind::Named(impl_block.type_name.clone()),
+                        span: impl_block.span,
+                    });
+                    self.context.register_trait_impl(target, trait_name);
+                }
                 Type::Unknown
             }
+
+            StmtKind::Trait(_) => Type::Unknown,
         };
 
         self.stmt_types.insert(stmt.span, ty.clone());
@@ -348,6 +397,7 @@ impl InferenceEngine {
                     // TODO: Add constraint that it must be numeric
                     ty
                 }
+                Literal::TypedNumber(_, suffix) => suffix_type(*suffix),
                 Literal::String(_) => Type::String,
                 Literal::Boolean(_) => Type::Bool,
                 Literal::Null => Type::Option(Box::new(Type::Unknown)),
@@ -356,6 +406,8 @@ impl InferenceEngine {
             ExprKind::Identifier(name) => {
                 if let Some(ty) = self.context.type_env().lookup(name) {
                     ty.clone()
+                } else if self.allow_undefined {
+                    Type::Unknown
                 } else {
                     return Err(Error::new(
                         ErrorKind::TypeError,
@@ -710,6 +762,14 @@ impl InferenceEngine {
                     ret: Box::new(return_type),
                 }
             }
+
+            ExprKind::Cast { expr, target_type } => {
+                let source = self.infer_expr(expr)?;
+                let target = type_ann_to_type(target_type);
+                let source = self.context.apply_substitution(&source);
+                cast_result_type(&source, &target)
+                    .map_err(|message| Error::new(ErrorKind::TypeError, message))?
+            }
         };
 
         self.expr_types.insert(expr.span, ty.clone());
@@ -753,6 +813,7 @@ impl InferenceEngine {
             PatternKind::Literal(literal) => {
                 let literal_type = match literal {
                     Literal::Number(_) => Type::F32, // TODO: Could be i32 too
+                    Literal::TypedNumber(_, suffix) => suffix_type(*suffix),
                     Literal::String(_) => Type::String,
                     Literal::Boolean(_) => Type::Bool,
                     Literal::Null => Type::Option(Box::new(Type::Unknown)),
@@ -799,6 +860,39 @@ impl InferenceEngine {
                 // TODO: Implement proper object pattern matching
                 Ok(())
             }
+            PatternKind::Tuple(patterns) => {
+                // Expected type should be a tuple with one type per element
+                let element_types: Vec<Type> = match expected_type {
+                    Type::Tuple(types) if types.len() == patterns.len() => types.clone(),
+                    _ => {
+                        let types: Vec<Type> = patterns
+                            .iter()
+                            .map(|_| self.context.fresh_type_var())
+                            .collect();
+                        self.context.add_constraint(Constraint::equality(
+                            expected_type.clone(),
+                            Type::Tuple(types.clone()),
+                            pattern.span,
+                        ));
+                        types
+                    }
+                };
+
+                for (sub_pattern, element_type) in patterns.iter().zip(element_types.iter()) {
+                    self.check_pattern_compatibility(sub_pattern, element_type)?;
+                }
+                Ok(())
+            }
+            PatternKind::Binding {
+                name,
+                pattern: sub_pattern,
+            } => {
+                // The binding has the type of the whole matched value
+                self.context
+                    .type_env_mut()
+                    .define(name.clone(), expected_type.clone());
+                self.check_pattern_compatibility(sub_pattern, expected_type)
+            }
             PatternKind::Or(patterns) => {
                 // All patterns in an or-pattern must be compatible with the same type
                 for sub_pattern in patterns {
@@ -861,6 +955,42 @@ impl InferenceEngine {
     }
 }
 
+/// Type of a standard library function as the engine sees it: parameters
+/// accept anything and generic parameters in the result are unknown
+fn builtin_type(signature: &Type) -> Type {
+    match signature {
+        Type::Function { params, ret } => Type::Function {
+            params: vec![Type::Unknown; params.len()],
+            ret: Box::new(erase_type_params(ret)),
+        },
+        other => erase_type_params(other),
+    }
+}
+
+fn erase_type_params(ty: &Type) -> Type {
+    let erase = |inner: &Type| Box::new(erase_type_params(inner));
+    match ty {
+        Type::TypeParam(_) => Type::Unknown,
+        Type::Array(elem) => Type::Array(erase(elem)),
+        Type::Option(inner) => Type::Option(erase(inner)),
+        Type::Future(inner) => Type::Future(erase(inner)),
+        Type::Result { ok, err } => Type::Result {
+            ok: erase(ok),
+            err: erase(err),
+        },
+        Type::Function { params, ret } => Type::Function {
+            params: params.iter().map(erase_type_params).collect(),
+            ret: erase(ret),
+        },
+        Type::Generic { name, args } => Type::Generic {
+            name: name.clone(),
+            args: args.iter().map(erase_type_params).collect(),
+        },
+        Type::Tuple(items) => Type::Tuple(items.iter().map(erase_type_params).collect()),
+        other => other.clone(),
+    }
+}
+
 impl Default for InferenceEngine {
     fn default() -> Self {
         Self::new()
@@ -913,6 +1043,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_infer_stdlib_constant() {
+        let result = infer_program_str("let limit = MAX_U32; limit;").unwrap();
+
+        assert!(result.expr_types.values().any(|t| t == &Type::U32));
+    }
+
     #[test]
     fn test_infer_function() {
         let code = r#"
@@ -954,4 +1091,24 @@ mod tests {
         let result = infer_program_str("fn f(x: i32) { x } f(1, 2);");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_editor_mode_tolerates_undefined_names() {
+        let source = "let shout = to_uppercase(missing); let size = string_len(shout);";
+        let (tokens, _errors) = Lexer::new(source).unwrap().scan_tokens();
+        let program = Parser::new(tokens).parse().unwrap();
+
+        assert!(InferenceEngine::new().infer_program(&program).is_err());
+
+        // Standard library return types survive the unknown argument
+        let result = InferenceEngine::for_editor()
+            .infer_program(&program)
+            .unwrap();
+        let let_types: Vec<_> = program
+            .statements
+            .iter()
+            .map(|stmt| result.stmt_types.get(&stmt.span))
+            .collect();
+        assert_eq!(let_types, vec![Some(&Type::String), Some(&Type::I32)]);
+    }
 }
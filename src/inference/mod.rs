@@ -156,6 +156,11 @@ impl InferenceContext {
             .define(name.to_string(), Type::TypeParam(name.to_string()));
     }
 
+    /// Record that a user-defined `impl Trait for Type` block exists
+    pub fn register_trait_impl(&mut self, type_: Type, trait_name: &str) {
+        self.trait_checker.register_impl(type_, trait_name);
+    }
+
     /// Get a reference to the trait checker
     pub fn trait_checker(&self) -> &TraitChecker {
         &self.trait_checker
@@ -222,7 +227,11 @@ pub fn type_ann_to_type(type_ann: &TypeAnn) -> Type {
             // Map common type names to built-in types
             match name.as_str() {
                 "i32" => Type::I32,
+                "i64" => Type::I64,
+                "u32" => Type::U32,
+                "u64" => Type::U64,
                 "f32" => Type::F32,
+                "f64" => Type::F64,
                 "bool" => Type::Bool,
                 "string" => Type::String,
                 "unknown" => Type::Unknown,
@@ -266,7 +266,11 @@ impl OptimizedSubstitution {
 
             // Primitive types and types without type variables
             Type::I32
+            | Type::I64
+            | Type::U32
+            | Type::U64
             | Type::F32
+            | Type::F64
             | Type::Bool
             | Type::String
             | Type::Unknown
@@ -290,9 +294,16 @@ impl OptimizedSubstitution {
             Type::Tuple(types) => types.len() > 2,
             Type::Array(_) | Type::Option(_) | Type::Future(_) | Type::Result { .. } => true,
             // Don't cache simple types
-            Type::I32 | Type::F32 | Type::Bool | Type::String | Type::Unknown | Type::Never => {
-                false
-            }
+            Type::I32
+            | Type::I64
+            | Type::U32
+            | Type::U64
+            | Type::F32
+            | Type::F64
+            | Type::Bool
+            | Type::String
+            | Type::Unknown
+            | Type::Never => false,
             Type::TypeVar(_) | Type::Named(_) | Type::TypeParam(_) => false,
             Type::Reference { .. } => true,
             Type::Struct { fields, .. } => fields.len() > 2, // Cache structs with multiple fields
@@ -396,6 +407,10 @@ impl OptimizedSubstitution {
                     self.hash_type(field_type, hasher);
                 }
             }
+            Type::I64 => 19u8.hash(hasher),
+            Type::U32 => 20u8.hash(hasher),
+            Type::U64 => 21u8.hash(hasher),
+            Type::F64 => 22u8.hash(hasher),
         }
     }
 
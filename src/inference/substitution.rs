@@ -95,7 +95,11 @@ pub fn apply_substitution(subst: &Substitution, ty: &Type) -> Type {
         Type::Option(inner_ty) => Type::Option(Box::new(apply_substitution(subst, inner_ty))),
         // Basic types and named types are not affected by substitution
         Type::I32
+        | Type::I64
+        | Type::U32
+        | Type::U64
         | Type::F32
+        | Type::F64
         | Type::Bool
         | Type::String
         | Type::Unknown
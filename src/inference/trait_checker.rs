@@ -2,13 +2,15 @@ use crate::types::{
     generics::{BuiltinTrait, MissingConstraint, TraitBound},
     Type,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// Trait checker for validating trait implementations and constraints
 #[derive(Debug, Clone)]
 pub struct TraitChecker {
     /// Built-in trait implementations for primitive types
     builtin_impls: HashMap<(Type, BuiltinTrait), bool>,
+    /// Implementations declared with `impl Trait for Type`
+    user_impls: HashSet<(Type, String)>,
     /// Cache for trait satisfaction checks
     trait_cache: HashMap<(Type, String), bool>,
     /// Trait dependency graph
@@ -20,6 +22,7 @@ impl TraitChecker {
     pub fn new() -> Self {
         let mut checker = TraitChecker {
             builtin_impls: HashMap::new(),
+            user_impls: HashSet::new(),
             trait_cache: HashMap::new(),
             trait_dependencies: HashMap::new(),
         };
@@ -31,7 +34,16 @@ impl TraitChecker {
 
     /// Initialize built-in trait implementations for primitive types
     fn init_builtin_impls(&mut self) {
-        let primitive_types = vec![Type::I32, Type::F32, Type::Bool, Type::String];
+        let primitive_types = vec![
+            Type::I32,
+            Type::I64,
+            Type::U32,
+            Type::U64,
+            Type::F32,
+            Type::F64,
+            Type::Bool,
+            Type::String,
+        ];
 
         for type_ in primitive_types {
             // All primitives implement basic traits
@@ -49,13 +61,13 @@ impl TraitChecker {
                 .insert((type_.clone(), BuiltinTrait::Hash), true);
 
             // Numeric types implement Ord
-            if matches!(type_, Type::I32 | Type::F32) {
+            if type_.is_numeric() {
                 self.builtin_impls
                     .insert((type_.clone(), BuiltinTrait::Ord), true);
             }
 
             // Simple types implement Copy
-            if matches!(type_, Type::I32 | Type::F32 | Type::Bool) {
+            if type_.is_numeric() || type_ == Type::Bool {
                 self.builtin_impls
                     .insert((type_.clone(), BuiltinTrait::Copy), true);
             }
@@ -72,6 +84,13 @@ impl TraitChecker {
             .insert("Copy".to_string(), vec!["Clone".to_string()]);
     }
 
+    /// Record a user-defined implementation of a trait for a type
+    pub fn register_impl(&mut self, type_: Type, trait_name: &str) {
+        self.trait_cache
+            .remove(&(type_.clone(), trait_name.to_string()));
+        self.user_impls.insert((type_, trait_name.to_string()));
+    }
+
     /// Check if a type implements a trait
     pub fn implements_trait(&mut self, type_: &Type, trait_name: &str) -> bool {
         // Check cache first
@@ -89,6 +108,13 @@ impl TraitChecker {
 
     /// Internal trait implementation checking
     fn check_trait_implementation(&mut self, type_: &Type, trait_name: &str) -> bool {
+        if self
+            .user_impls
+            .contains(&(type_.clone(), trait_name.to_string()))
+        {
+            return true;
+        }
+
         // Check built-in trait
         if let Some(builtin_trait) = BuiltinTrait::from_name(trait_name) {
             return self.check_builtin_trait(type_, &builtin_trait);
@@ -216,6 +242,16 @@ impl TraitChecker {
             }
         }
 
+        // And the traits it implements with impl blocks
+        let mut user_traits: Vec<String> = self
+            .user_impls
+            .iter()
+            .filter(|(impl_type, _)| impl_type == type_)
+            .map(|(_, trait_name)| trait_name.clone())
+            .collect();
+        user_traits.sort();
+        traits.extend(user_traits);
+
         traits
     }
 
@@ -354,4 +390,19 @@ mod tests {
         checker.clear_cache();
         assert!(checker.implements_trait(&Type::I32, "Eq"));
     }
+
+    #[test]
+    fn test_user_trait_impls() {
+        let mut checker = TraitChecker::new();
+        let point = Type::Named("Point".to_string());
+
+        assert!(!checker.implements_trait(&point, "Shape"));
+
+        // Registering an impl replaces the cached negative result
+        checker.register_impl(point.clone(), "Shape");
+        assert!(checker.implements_trait(&point, "Shape"));
+        assert!(checker.implements_trait(&Type::Array(Box::new(point.clone())), "Shape"));
+        assert!(!checker.implements_trait(&Type::I32, "Shape"));
+        assert_eq!(checker.get_implemented_traits(&point), vec!["Shape"]);
+    }
 }
@@ -41,7 +41,11 @@ pub fn unify(t1: &Type, t2: &Type, span: Span) -> Result<Substitution, Error> {
 
         // Basic types must match exactly
         (Type::I32, Type::I32)
+        | (Type::I64, Type::I64)
+        | (Type::U32, Type::U32)
+        | (Type::U64, Type::U64)
         | (Type::F32, Type::F32)
+        | (Type::F64, Type::F64)
         | (Type::Bool, Type::Bool)
         | (Type::String, Type::String) => Ok(Substitution::new()),
 
@@ -179,6 +183,9 @@ pub fn unify(t1: &Type, t2: &Type, span: Span) -> Result<Substitution, Error> {
         // Option types - unify inner types
         (Type::Option(inner1), Type::Option(inner2)) => unify(inner1, inner2, span),
 
+        // Future types - unify the types they resolve to
+        (Type::Future(inner1), Type::Future(inner2)) => unify(inner1, inner2, span),
+
         // Type mismatch
         _ => Err(Error::new(
             ErrorKind::TypeError,
@@ -444,6 +451,33 @@ mod tests {
         let result = unify(&nested2, &nested1, span).unwrap();
         assert_eq!(result.get(0), Some(&Type::I32));
     }
+
+    #[test]
+    fn test_unify_optimized_wrapper_types() {
+        let span = test_span();
+
+        let future = Type::Future(Box::new(Type::TypeVar(0)));
+        let result = unify_optimized(&future, &Type::Future(Box::new(Type::String)), span).unwrap();
+        assert_eq!(result.get(0), Some(&Type::String));
+
+        let named = Type::Named("Point".to_string());
+        assert!(unify_optimized(&named, &named, span).is_ok());
+        assert!(unify_optimized(&named, &Type::Named("Size".to_string()), span).is_err());
+
+        let result = unify_optimized(
+            &Type::Result {
+                ok: Box::new(Type::I32),
+                err: Box::new(Type::TypeVar(1)),
+            },
+            &Type::Result {
+                ok: Box::new(Type::I32),
+                err: Box::new(Type::String),
+            },
+            span,
+        )
+        .unwrap();
+        assert_eq!(result.get(1), Some(&Type::String));
+    }
 }
 
 /// Optimized unify function that returns OptimizedSubstitution for better performance
@@ -483,7 +517,11 @@ pub fn unify_optimized(t1: &Type, t2: &Type, span: Span) -> Result<OptimizedSubs
 
         // Basic types must match exactly
         (Type::I32, Type::I32)
+        | (Type::I64, Type::I64)
+        | (Type::U32, Type::U32)
+        | (Type::U64, Type::U64)
         | (Type::F32, Type::F32)
+        | (Type::F64, Type::F64)
         | (Type::Bool, Type::Bool)
         | (Type::String, Type::String) => Ok(OptimizedSubstitution::new()),
 
@@ -528,6 +566,29 @@ pub fn unify_optimized(t1: &Type, t2: &Type, span: Span) -> Result<OptimizedSubs
             Ok(subst)
         }
 
+        // Wrapper types - unify what they wrap
+        (Type::Option(inner1), Type::Option(inner2))
+        | (Type::Future(inner1), Type::Future(inner2)) => unify_optimized(inner1, inner2, span),
+
+        (Type::Result { ok: o1, err: e1 }, Type::Result { ok: o2, err: e2 }) => {
+            let mut subst = unify_optimized(o1, o2, span)?;
+            subst.compose(unify_optimized(e1, e2, span)?);
+            Ok(subst)
+        }
+
+        // Named types must have the same name
+        (Type::Named(n1), Type::Named(n2)) if n1 == n2 => Ok(OptimizedSubstitution::new()),
+
+        // Generic types and tuples - unify argument by argument
+        (Type::Generic { name: n1, args: a1 }, Type::Generic { name: n2, args: a2 })
+            if n1 == n2 && a1.len() == a2.len() =>
+        {
+            unify_pairwise_optimized(a1, a2, span)
+        }
+        (Type::Tuple(elems1), Type::Tuple(elems2)) if elems1.len() == elems2.len() => {
+            unify_pairwise_optimized(elems1, elems2, span)
+        }
+
         // All other cases are unification failures
         _ => Err(Error::new(
             ErrorKind::TypeError,
@@ -536,3 +597,16 @@ pub fn unify_optimized(t1: &Type, t2: &Type, span: Span) -> Result<OptimizedSubs
         .with_location(span.start)),
     }
 }
+
+/// Unify two lists of types of the same length element by element
+fn unify_pairwise_optimized(
+    types1: &[Type],
+    types2: &[Type],
+    span: Span,
+) -> Result<OptimizedSubstitution, Error> {
+    let mut subst = OptimizedSubstitution::new();
+    for (ty1, ty2) in types1.iter().zip(types2) {
+        subst.compose(unify_optimized(ty1, ty2, span)?);
+    }
+    Ok(subst)
+}
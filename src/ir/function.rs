@@ -1,4 +1,5 @@
-use super::{BasicBlock, BlockId};
+use super::{BasicBlock, BlockId, Probe, ValueId};
+use crate::source::Span;
 use crate::types::Type;
 use std::collections::HashMap;
 use std::fmt;
@@ -14,6 +15,45 @@ pub struct Parameter {
     pub ty: Type,
 }
 
+/// Source-level variable of a function, for debug information
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugVariable {
+    /// Variable name
+    pub name: String,
+    /// Allocation holding the variable's value
+    pub ptr: ValueId,
+    /// Variable type
+    pub ty: Type,
+    /// Whether the variable is one of the function's parameters
+    pub is_param: bool,
+    /// Where the variable is declared
+    pub span: Option<Span>,
+}
+
+/// How the instantiations of a generic function are compiled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GenericDispatch {
+    /// Let the monomorphizer decide for each instantiation
+    #[default]
+    Auto,
+    /// Always generate a specialized copy (`@monomorphize(always)`)
+    Specialize,
+    /// Share one version operating on boxed values (`@monomorphize(never)`)
+    Boxed,
+}
+
+/// Trait method called on a value whose type is a type parameter
+///
+/// Functions carrying one are bodiless stubs standing in for the
+/// implementation. Monomorphization redirects calls to them to
+/// `Type::method` of the concrete type substituted for `type_param`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraitMethodRef {
+    pub type_param: String,
+    pub trait_name: String,
+    pub method: String,
+}
+
 /// Function in the IR
 #[derive(Debug, Clone)]
 pub struct Function {
@@ -33,6 +73,19 @@ pub struct Function {
     next_block_id: u32,
     /// Whether this is an async function
     pub is_async: bool,
+    /// How instantiations are compiled if the function is generic
+    pub generic_dispatch: GenericDispatch,
+    /// Set if this is a trait method stub rather than a real function
+    pub trait_method: Option<TraitMethodRef>,
+    /// Source span of the function's declaration
+    pub span: Option<Span>,
+    /// Source-level variables, in declaration order
+    pub debug_variables: Vec<DebugVariable>,
+    /// Probes to run before every return from the function
+    ///
+    /// Kept apart from the blocks because codegen creates returns of its
+    /// own, such as the early return of the `?` operator.
+    pub exit_probes: Vec<Probe>,
 }
 
 impl Function {
@@ -47,6 +100,11 @@ impl Function {
             entry_block: None,
             next_block_id: 0,
             is_async: false,
+            generic_dispatch: GenericDispatch::Auto,
+            trait_method: None,
+            span: None,
+            debug_variables: Vec::new(),
+            exit_probes: Vec::new(),
         }
     }
 
@@ -81,6 +139,17 @@ impl Function {
         &self.blocks
     }
 
+    /// Remove a basic block from this function
+    ///
+    /// The entry block cannot be removed. Branches into the removed block
+    /// are left for the caller to fix up.
+    pub fn remove_block(&mut self, id: BlockId) -> Option<BasicBlock> {
+        if self.entry_block == Some(id) {
+            return None;
+        }
+        self.blocks.remove(&id)
+    }
+
     /// Get all blocks in execution order (entry block first)
     pub fn blocks_in_order(&self) -> Vec<&BasicBlock> {
         let mut result = Vec::new();
@@ -1,4 +1,4 @@
-use super::{BlockId, FunctionId, ValueId};
+use super::{BlockId, FunctionId, Intrinsic, ValueId};
 use crate::source::Span;
 use crate::types::Type;
 use std::fmt;
@@ -262,9 +262,15 @@ pub enum Instruction {
     },
 
     /// Create a closure with captured variables
+    ///
+    /// The closure's environment holds one 8-byte slot per captured variable,
+    /// in the order of `captured_vars`. Variables captured by reference store
+    /// their address in the slot.
     CreateClosure {
         /// Unique identifier for the closure function
         function_id: String,
+        /// The lowered closure function, taking the environment as its first parameter
+        function: FunctionId,
         /// Parameter names for the closure
         parameters: Vec<String>,
         /// Captured variables (name -> ValueId)
@@ -273,6 +279,16 @@ pub enum Instruction {
         captures_by_ref: bool,
     },
 
+    /// Load a captured variable from a closure's environment
+    LoadCapture {
+        /// The environment pointer passed to the closure function
+        environment: ValueId,
+        /// Slot of the captured variable
+        index: usize,
+        /// Type of the captured value
+        ty: Type,
+    },
+
     /// Invoke a closure with arguments
     InvokeClosure {
         /// The closure to invoke
@@ -282,13 +298,51 @@ pub enum Instruction {
         /// Return type of the closure
         return_type: Type,
     },
+
+    /// Call a runtime hook for instrumentation, such as a coverage counter
+    ///
+    /// Inserted by the instrumentation pass in `ir::instrument`.
+    Probe(Probe),
+
+    /// A machine operation such as an atomic or SIMD instruction
+    ///
+    /// Emitted by `@intrinsic` functions and `@ir` blocks; the module
+    /// verifier checks `args` and `ty` against the intrinsic's signature.
+    Intrinsic {
+        intrinsic: Intrinsic,
+        args: Vec<ValueId>,
+        /// Result type, or `None` for intrinsics without a result
+        ty: Option<Type>,
+    },
+}
+
+/// A call to a runtime hook that takes only an id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Probe {
+    /// Name of the runtime function to call, such as `script_coverage_hit`
+    pub hook: String,
+    /// What the hook is told, such as the number of a coverage counter
+    pub id: u32,
+}
+
+impl Probe {
+    pub fn new(hook: impl Into<String>, id: u32) -> Self {
+        Probe {
+            hook: hook.into(),
+            id,
+        }
+    }
 }
 
 /// Constant values
 #[derive(Debug, Clone, PartialEq)]
 pub enum Constant {
     I32(i32),
+    I64(i64),
+    U32(u32),
+    U64(u64),
     F32(f32),
+    F64(f64),
     Bool(bool),
     String(String),
     Null,
@@ -382,7 +436,10 @@ impl Instruction {
             Instruction::ValidateFieldAccess { .. } => Some(Type::Bool), // Returns true if field access is valid
             Instruction::ErrorPropagation { success_type, .. } => Some(success_type.clone()),
             Instruction::CreateClosure { .. } => Some(Type::Named("Closure".to_string())),
+            Instruction::LoadCapture { ty, .. } => Some(ty.clone()),
             Instruction::InvokeClosure { return_type, .. } => Some(return_type.clone()),
+            Instruction::Probe(_) => None,
+            Instruction::Intrinsic { ty, .. } => ty.clone(),
         }
     }
 
@@ -396,6 +453,142 @@ impl Instruction {
                 | Instruction::Suspend { .. }
         )
     }
+
+    /// Get the values this instruction reads
+    pub fn operands(&self) -> Vec<ValueId> {
+        match self {
+            Instruction::Const(_)
+            | Instruction::Alloc { .. }
+            | Instruction::AllocStruct { .. }
+            | Instruction::AllocEnum { .. }
+            | Instruction::Return(None)
+            | Instruction::Branch(_)
+            | Instruction::CreateAsyncState { .. } => Vec::new(),
+            Instruction::Binary { lhs, rhs, .. } | Instruction::Compare { lhs, rhs, .. } => {
+                vec![*lhs, *rhs]
+            }
+            Instruction::Unary { operand, .. } => vec![*operand],
+            Instruction::Cast { value, .. } => vec![*value],
+            Instruction::Call { args, .. } => args.clone(),
+            Instruction::Load { ptr, .. } => vec![*ptr],
+            Instruction::Store { ptr, value } => vec![*ptr, *value],
+            Instruction::GetElementPtr { ptr, index, .. } => vec![*ptr, *index],
+            Instruction::GetFieldPtr { object, .. }
+            | Instruction::LoadField { object, .. }
+            | Instruction::ValidateFieldAccess { object, .. } => vec![*object],
+            Instruction::StoreField { object, value, .. } => vec![*object, *value],
+            Instruction::ConstructStruct { fields, .. } => {
+                fields.iter().map(|(_, value)| *value).collect()
+            }
+            Instruction::ConstructEnum { args, .. } => args.clone(),
+            Instruction::GetEnumTag { enum_value }
+            | Instruction::ExtractEnumData { enum_value, .. } => vec![*enum_value],
+            Instruction::SetEnumTag { enum_ptr, .. } => vec![*enum_ptr],
+            Instruction::Phi { incoming, .. } => incoming.iter().map(|(value, _)| *value).collect(),
+            Instruction::Return(Some(value)) => vec![*value],
+            Instruction::CondBranch { condition, .. } => vec![*condition],
+            Instruction::Suspend { state, .. } => vec![*state],
+            Instruction::PollFuture { future, .. } => vec![*future],
+            Instruction::StoreAsyncState {
+                state_ptr, value, ..
+            } => vec![*state_ptr, *value],
+            Instruction::LoadAsyncState { state_ptr, .. }
+            | Instruction::GetAsyncState { state_ptr }
+            | Instruction::SetAsyncState { state_ptr, .. } => vec![*state_ptr],
+            Instruction::BoundsCheck {
+                array,
+                index,
+                length,
+                ..
+            } => {
+                let mut values = vec![*array, *index];
+                values.extend(length);
+                values
+            }
+            Instruction::ErrorPropagation { value, .. } => vec![*value],
+            Instruction::CreateClosure { captured_vars, .. } => {
+                captured_vars.iter().map(|(_, value)| *value).collect()
+            }
+            Instruction::LoadCapture { environment, .. } => vec![*environment],
+            Instruction::InvokeClosure { closure, args, .. } => {
+                let mut values = vec![*closure];
+                values.extend(args);
+                values
+            }
+            Instruction::Probe(_) => vec![],
+            Instruction::Intrinsic { args, .. } => args.clone(),
+        }
+    }
+
+    /// Get mutable references to the values this instruction reads
+    ///
+    /// Yields the same values in the same order as [`Instruction::operands`].
+    pub fn operands_mut(&mut self) -> Vec<&mut ValueId> {
+        match self {
+            Instruction::Const(_)
+            | Instruction::Alloc { .. }
+            | Instruction::AllocStruct { .. }
+            | Instruction::AllocEnum { .. }
+            | Instruction::Return(None)
+            | Instruction::Branch(_)
+            | Instruction::CreateAsyncState { .. } => Vec::new(),
+            Instruction::Binary { lhs, rhs, .. } | Instruction::Compare { lhs, rhs, .. } => {
+                vec![lhs, rhs]
+            }
+            Instruction::Unary { operand, .. } => vec![operand],
+            Instruction::Cast { value, .. } => vec![value],
+            Instruction::Call { args, .. } => args.iter_mut().collect(),
+            Instruction::Load { ptr, .. } => vec![ptr],
+            Instruction::Store { ptr, value } => vec![ptr, value],
+            Instruction::GetElementPtr { ptr, index, .. } => vec![ptr, index],
+            Instruction::GetFieldPtr { object, .. }
+            | Instruction::LoadField { object, .. }
+            | Instruction::ValidateFieldAccess { object, .. } => vec![object],
+            Instruction::StoreField { object, value, .. } => vec![object, value],
+            Instruction::ConstructStruct { fields, .. } => {
+                fields.iter_mut().map(|(_, value)| value).collect()
+            }
+            Instruction::ConstructEnum { args, .. } => args.iter_mut().collect(),
+            Instruction::GetEnumTag { enum_value }
+            | Instruction::ExtractEnumData { enum_value, .. } => vec![enum_value],
+            Instruction::SetEnumTag { enum_ptr, .. } => vec![enum_ptr],
+            Instruction::Phi { incoming, .. } => {
+                incoming.iter_mut().map(|(value, _)| value).collect()
+            }
+            Instruction::Return(Some(value)) => vec![value],
+            Instruction::CondBranch { condition, .. } => vec![condition],
+            Instruction::Suspend { state, .. } => vec![state],
+            Instruction::PollFuture { future, .. } => vec![future],
+            Instruction::StoreAsyncState {
+                state_ptr, value, ..
+            } => vec![state_ptr, value],
+            Instruction::LoadAsyncState { state_ptr, .. }
+            | Instruction::GetAsyncState { state_ptr }
+            | Instruction::SetAsyncState { state_ptr, .. } => vec![state_ptr],
+            Instruction::BoundsCheck {
+                array,
+                index,
+                length,
+                ..
+            } => {
+                let mut values = vec![array, index];
+                values.extend(length.as_mut());
+                values
+            }
+            Instruction::ErrorPropagation { value, .. } => vec![value],
+            Instruction::CreateClosure { captured_vars, .. } => {
+                captured_vars.iter_mut().map(|(_, value)| value).collect()
+            }
+            Instruction::LoadCapture { environment, .. } => vec![environment],
+            Instruction::InvokeClosure { closure, args, .. } => {
+                let mut values = vec![closure];
+                values.extend(args.iter_mut());
+                values
+            }
+            Instruction::Probe(_) => vec![],
+            Instruction::Intrinsic { args, .. } => args.iter_mut().collect(),
+        }
+    }
 }
 
 impl Constant {
@@ -403,7 +596,11 @@ impl Constant {
     pub fn get_type(&self) -> Type {
         match self {
             Constant::I32(_) => Type::I32,
+            Constant::I64(_) => Type::I64,
+            Constant::U32(_) => Type::U32,
+            Constant::U64(_) => Type::U64,
             Constant::F32(_) => Type::F32,
+            Constant::F64(_) => Type::F64,
             Constant::Bool(_) => Type::Bool,
             Constant::String(_) => Type::String,
             Constant::Null => Type::Unknown,
@@ -652,6 +849,7 @@ impl fmt::Display for Instruction {
                 parameters,
                 captured_vars,
                 captures_by_ref,
+                ..
             } => {
                 write!(
                     f,
@@ -662,6 +860,13 @@ impl fmt::Display for Instruction {
                     captures_by_ref
                 )
             }
+            Instruction::LoadCapture {
+                environment,
+                index,
+                ty,
+            } => {
+                write!(f, "load_capture {}[{}] : {}", environment, index, ty)
+            }
             Instruction::InvokeClosure {
                 closure,
                 args,
@@ -676,6 +881,19 @@ impl fmt::Display for Instruction {
                 }
                 write!(f, ") : {}", return_type)
             }
+            Instruction::Probe(probe) => write!(f, "probe {} {}", probe.hook, probe.id),
+            Instruction::Intrinsic {
+                intrinsic,
+                args,
+                ty,
+            } => {
+                let args: Vec<String> = args.iter().map(ValueId::to_string).collect();
+                write!(f, "intrinsic {}({})", intrinsic, args.join(", "))?;
+                match ty {
+                    Some(ty) => write!(f, " : {}", ty),
+                    None => Ok(()),
+                }
+            }
         }
     }
 }
@@ -684,7 +902,11 @@ impl fmt::Display for Constant {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Constant::I32(n) => write!(f, "{}i32", n),
+            Constant::I64(n) => write!(f, "{}i64", n),
+            Constant::U32(n) => write!(f, "{}u32", n),
+            Constant::U64(n) => write!(f, "{}u64", n),
             Constant::F32(n) => write!(f, "{}f32", n),
+            Constant::F64(n) => write!(f, "{}f64", n),
             Constant::Bool(b) => write!(f, "{}", b),
             Constant::String(s) => write!(f, "\"{}\"", s),
             Constant::Null => write!(f, "null"),
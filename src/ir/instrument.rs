@@ -0,0 +1,380 @@
+//! Instrumentation of IR modules
+//!
+//! Coverage, the function profiler and other tools that count or trace
+//! execution all want runtime calls at the same kinds of places. `instrument`
+//! walks every function, offers each place to a [`ProbePolicy`] as a
+//! [`ProbeSite`], and inserts the [`Probe`] the policy returns:
+//! - `FunctionEntry` and `Block` probes go at the start of their block,
+//!   after any phi nodes.
+//! - `Branch` and `LoopBackEdge` probes go on the edge, so they only run
+//!   when control takes it. An edge that ends an unconditional branch gets
+//!   the probe just before the branch; any other edge is split with a block
+//!   of its own.
+//! - `FunctionExit` probes are kept on the function and emitted by codegen
+//!   before every return.
+//!
+//! Sites are offered in a fixed order, function by function and block by
+//! block in id order, so numbering probes as they are handed out gives the
+//! same numbers on every build.
+
+use super::optimizer::analysis::DominanceAnalysis;
+use super::optimizer::first_free_value_id;
+use super::{
+    BlockId, Function, FunctionId, Instruction, InstructionWithLocation, Module, Probe, ValueId,
+};
+use crate::source::Span;
+use std::collections::BTreeMap;
+
+/// A place in a function where a probe can run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeSite {
+    /// Start of the function
+    FunctionEntry,
+    /// Start of a basic block
+    Block(BlockId),
+    /// Edge from a conditional branch to one of its targets, `taken` being
+    /// whether it is the edge for a true condition
+    Branch {
+        from: BlockId,
+        to: BlockId,
+        taken: bool,
+    },
+    /// Edge from the end of a loop body back to the loop header
+    LoopBackEdge { latch: BlockId, header: BlockId },
+    /// Every return from the function
+    FunctionExit,
+}
+
+/// Decides which probe, if any, runs at each site
+pub trait ProbePolicy {
+    /// The probe to run at `site` in `function`
+    fn probe(&mut self, function: &Function, site: ProbeSite) -> Option<Probe>;
+}
+
+/// Insert the probes `policy` asks for into every function of `module`
+pub fn instrument(module: &mut Module, policy: &mut dyn ProbePolicy) {
+    let mut next_value_id = first_free_value_id(module);
+    let mut functions: Vec<FunctionId> = module.functions().keys().copied().collect();
+    functions.sort_by_key(|id| id.0);
+
+    for func_id in functions {
+        if let Some(function) = module.get_function_mut(func_id) {
+            instrument_function(function, policy, &mut next_value_id);
+        }
+    }
+}
+
+fn instrument_function(
+    function: &mut Function,
+    policy: &mut dyn ProbePolicy,
+    next_value_id: &mut u32,
+) {
+    let Some(entry) = function.entry_block else {
+        return;
+    };
+    function.update_predecessors();
+    let dominance = DominanceAnalysis::without_post_dominance().analyze(function);
+
+    let mut blocks: Vec<BlockId> = function.blocks().keys().copied().collect();
+    blocks.sort_by_key(|id| id.0);
+
+    // Ask for every probe before changing the function
+    let mut starts: Vec<(BlockId, Probe)> = Vec::new();
+    let mut edges: BTreeMap<(BlockId, BlockId), Vec<Probe>> = BTreeMap::new();
+    if let Some(probe) = policy.probe(function, ProbeSite::FunctionEntry) {
+        starts.push((entry, probe));
+    }
+    for &block_id in &blocks {
+        if let Some(probe) = policy.probe(function, ProbeSite::Block(block_id)) {
+            starts.push((block_id, probe));
+        }
+
+        let Some(block) = function.get_block(block_id) else {
+            continue;
+        };
+        if let Some(Instruction::CondBranch {
+            then_block,
+            else_block,
+            ..
+        }) = block.terminator()
+        {
+            // Both edges would be the same edge, so neither tells which
+            // way the branch went
+            if then_block != else_block {
+                for (to, taken) in [(*then_block, true), (*else_block, false)] {
+                    let site = ProbeSite::Branch {
+                        from: block_id,
+                        to,
+                        taken,
+                    };
+                    if let Some(probe) = policy.probe(function, site) {
+                        edges.entry((block_id, to)).or_default().push(probe);
+                    }
+                }
+            }
+        }
+
+        for &header in &block.successors {
+            if dominance.dominates(header, block_id) {
+                let site = ProbeSite::LoopBackEdge {
+                    latch: block_id,
+                    header,
+                };
+                if let Some(probe) = policy.probe(function, site) {
+                    edges.entry((block_id, header)).or_default().push(probe);
+                }
+            }
+        }
+    }
+    if let Some(probe) = policy.probe(function, ProbeSite::FunctionExit) {
+        function.exit_probes.push(probe);
+    }
+
+    let mut fresh = || {
+        let id = ValueId(*next_value_id);
+        *next_value_id += 1;
+        id
+    };
+
+    // Later probes for a block go after earlier ones
+    let mut inserted: BTreeMap<BlockId, usize> = BTreeMap::new();
+    for (block_id, probe) in starts {
+        let Some(block) = function.get_block_mut(block_id) else {
+            continue;
+        };
+        let location = block
+            .instructions
+            .iter()
+            .find_map(|(_, inst)| inst.source_location);
+        let phis = block
+            .instructions
+            .iter()
+            .take_while(|(_, inst)| matches!(inst.instruction, Instruction::Phi { .. }))
+            .count();
+        let count = inserted.entry(block_id).or_default();
+        block
+            .instructions
+            .insert(phis + *count, (fresh(), probe_instruction(probe, location)));
+        *count += 1;
+    }
+
+    for ((from, to), probes) in edges {
+        probe_edge(function, from, to, probes, &mut fresh);
+    }
+    function.update_predecessors();
+}
+
+/// Run `probes` when control goes from `from` to `to`
+fn probe_edge(
+    function: &mut Function,
+    from: BlockId,
+    to: BlockId,
+    probes: Vec<Probe>,
+    fresh: &mut impl FnMut() -> ValueId,
+) {
+    let Some(block) = function.get_block(from) else {
+        return;
+    };
+    let Some((_, terminator)) = block.instructions.last() else {
+        return;
+    };
+    let location = terminator.source_location;
+
+    if terminator.instruction == Instruction::Branch(to) {
+        if let Some(block) = function.get_block_mut(from) {
+            let position = block.instructions.len() - 1;
+            for (offset, probe) in probes.into_iter().enumerate() {
+                block.instructions.insert(
+                    position + offset,
+                    (fresh(), probe_instruction(probe, location)),
+                );
+            }
+        }
+        return;
+    }
+
+    // Give the edge a block of its own and send the branch through it
+    let edge = function.create_block(format!("probe_{}_{}", from, to));
+    if let Some(block) = function.get_block_mut(edge) {
+        for probe in probes {
+            block.add_instruction_with_location(fresh(), probe_instruction(probe, location));
+        }
+        let branch = InstructionWithLocation {
+            instruction: Instruction::Branch(to),
+            source_location: location,
+        };
+        block.add_instruction_with_location(fresh(), branch);
+    }
+    if let Some(block) = function.get_block_mut(from) {
+        if let Some((
+            _,
+            InstructionWithLocation {
+                instruction:
+                    Instruction::CondBranch {
+                        then_block,
+                        else_block,
+                        ..
+                    },
+                ..
+            },
+        )) = block.instructions.last_mut()
+        {
+            for target in [then_block, else_block] {
+                if *target == to {
+                    *target = edge;
+                }
+            }
+        }
+        for successor in &mut block.successors {
+            if *successor == to {
+                *successor = edge;
+            }
+        }
+    }
+    if let Some(block) = function.get_block_mut(to) {
+        for (_, inst) in &mut block.instructions {
+            if let Instruction::Phi { incoming, .. } = &mut inst.instruction {
+                for (_, predecessor) in incoming.iter_mut() {
+                    if *predecessor == from {
+                        *predecessor = edge;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn probe_instruction(probe: Probe, location: Option<Span>) -> InstructionWithLocation {
+    InstructionWithLocation {
+        instruction: Instruction::Probe(probe),
+        source_location: location,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{ComparisonOp, Constant, IrBuilder};
+    use crate::types::Type;
+
+    /// Numbers every site it is offered, recording them in order
+    #[derive(Default)]
+    struct EverySite {
+        sites: Vec<ProbeSite>,
+    }
+
+    impl ProbePolicy for EverySite {
+        fn probe(&mut self, _function: &Function, site: ProbeSite) -> Option<Probe> {
+            self.sites.push(site);
+            Some(Probe::new("hook", self.sites.len() as u32 - 1))
+        }
+    }
+
+    fn probes(block: &crate::ir::BasicBlock) -> Vec<u32> {
+        block
+            .instructions
+            .iter()
+            .filter_map(|(_, inst)| match &inst.instruction {
+                Instruction::Probe(probe) => Some(probe.id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_probes_loop_edges_and_exits() {
+        // fn count(n) { let i = 0; while i < n { i = i + 1 } return i }
+        let mut builder = IrBuilder::new();
+        let func = builder.create_function("count".to_string(), vec![], Type::I32);
+        let entry = builder.get_current_block().unwrap();
+        let header = builder.create_block("header".to_string()).unwrap();
+        let body = builder.create_block("body".to_string()).unwrap();
+        let exit = builder.create_block("exit".to_string()).unwrap();
+
+        let zero = builder.const_value(Constant::I32(0));
+        let limit = builder.const_value(Constant::I32(10));
+        builder.build_branch(header);
+
+        builder.set_current_block(header);
+        let i = builder
+            .add_instruction(Instruction::Phi {
+                incoming: vec![(zero, entry)],
+                ty: Type::I32,
+            })
+            .unwrap();
+        let more = builder.build_compare(ComparisonOp::Lt, i, limit).unwrap();
+        builder.build_cond_branch(more, body, exit);
+
+        builder.set_current_block(body);
+        builder.build_branch(header);
+
+        builder.set_current_block(exit);
+        builder.build_return(Some(i));
+
+        let mut module = builder.build();
+        let mut policy = EverySite::default();
+        instrument(&mut module, &mut policy);
+
+        assert_eq!(
+            policy.sites,
+            vec![
+                ProbeSite::FunctionEntry,
+                ProbeSite::Block(entry),
+                ProbeSite::Block(header),
+                ProbeSite::Branch {
+                    from: header,
+                    to: body,
+                    taken: true
+                },
+                ProbeSite::Branch {
+                    from: header,
+                    to: exit,
+                    taken: false
+                },
+                ProbeSite::Block(body),
+                ProbeSite::LoopBackEdge {
+                    latch: body,
+                    header
+                },
+                ProbeSite::Block(exit),
+                ProbeSite::FunctionExit,
+            ]
+        );
+
+        let function = module.get_function(func).unwrap();
+        assert_eq!(probes(function.get_block(entry).unwrap()), vec![0, 1]);
+        // The header's probe goes after its phi
+        let header_block = function.get_block(header).unwrap();
+        assert!(matches!(
+            header_block.instructions[0].1.instruction,
+            Instruction::Phi { .. }
+        ));
+        assert_eq!(probes(header_block), vec![2]);
+        // The back edge ends an unconditional branch, so its probe goes
+        // before the branch
+        let body_block = function.get_block(body).unwrap();
+        assert_eq!(probes(body_block), vec![5, 6]);
+        assert!(body_block.has_terminator());
+        assert_eq!(function.exit_probes, vec![Probe::new("hook", 8)]);
+
+        // The conditional branch goes through a new block for each edge
+        let Some(Instruction::CondBranch {
+            then_block,
+            else_block,
+            ..
+        }) = header_block.terminator()
+        else {
+            panic!("header should end in a conditional branch");
+        };
+        let taken = function.get_block(*then_block).unwrap();
+        assert_eq!(probes(taken), vec![3]);
+        assert_eq!(taken.terminator(), Some(&Instruction::Branch(body)));
+        let not_taken = function.get_block(*else_block).unwrap();
+        assert_eq!(probes(not_taken), vec![4]);
+        assert_eq!(not_taken.predecessors, vec![header]);
+        assert_eq!(
+            function.get_block(exit).unwrap().predecessors,
+            vec![*else_block]
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! Intrinsics
+//!
+//! Intrinsics are IR instructions for machine operations Script has no
+//! syntax for: atomics, bit counting, fused arithmetic and 4-lane SIMD.
+//! Expert code reaches them through `@intrinsic` functions and `@ir`
+//! blocks (see `lowering::inline_ir`), and the module verifier checks every
+//! use against the signatures here before code generation.
+
+use super::{Constant, Function, Instruction, ValueId};
+use crate::types::Type;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Name of the 4 x `f32` vector type
+pub const F32X4: &str = "f32x4";
+/// Name of the 4 x `i32` vector type
+pub const I32X4: &str = "i32x4";
+
+/// A machine operation exposed to expert code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Intrinsic {
+    /// `atomic_load(addr) -> int`
+    AtomicLoad,
+    /// `atomic_store(addr, value)`
+    AtomicStore,
+    /// `atomic_add(addr, value) -> old`
+    AtomicAdd,
+    /// `atomic_sub(addr, value) -> old`
+    AtomicSub,
+    /// `atomic_and(addr, value) -> old`
+    AtomicAnd,
+    /// `atomic_or(addr, value) -> old`
+    AtomicOr,
+    /// `atomic_xor(addr, value) -> old`
+    AtomicXor,
+    /// `atomic_xchg(addr, value) -> old`
+    AtomicXchg,
+    /// `atomic_cas(addr, expected, replacement) -> old`
+    AtomicCas,
+    /// `fence()`: a sequentially consistent memory fence
+    Fence,
+    /// `popcnt(int) -> int`
+    Popcnt,
+    /// `clz(int) -> int`: count leading zeros
+    Clz,
+    /// `ctz(int) -> int`: count trailing zeros
+    Ctz,
+    /// `sqrt(float) -> float`
+    Sqrt,
+    /// `fma(a, b, c) -> a * b + c` with a single rounding
+    Fma,
+    /// `simd_splat(lane) -> vector` with every lane set to `lane`
+    SimdSplat,
+    /// `simd_add(vector, vector) -> vector`
+    SimdAdd,
+    /// `simd_sub(vector, vector) -> vector`
+    SimdSub,
+    /// `simd_mul(vector, vector) -> vector`
+    SimdMul,
+    /// `simd_extract(vector, lane) -> lane value`; `lane` is a constant
+    SimdExtract,
+    /// `simd_sum(vector) -> lane value`: the sum of all lanes
+    SimdSum,
+}
+
+impl Intrinsic {
+    pub const ALL: &'static [Intrinsic] = &[
+        Intrinsic::AtomicLoad,
+        Intrinsic::AtomicStore,
+        Intrinsic::AtomicAdd,
+        Intrinsic::AtomicSub,
+        Intrinsic::AtomicAnd,
+        Intrinsic::AtomicOr,
+        Intrinsic::AtomicXor,
+        Intrinsic::AtomicXchg,
+        Intrinsic::AtomicCas,
+        Intrinsic::Fence,
+        Intrinsic::Popcnt,
+        Intrinsic::Clz,
+        Intrinsic::Ctz,
+        Intrinsic::Sqrt,
+        Intrinsic::Fma,
+        Intrinsic::SimdSplat,
+        Intrinsic::SimdAdd,
+        Intrinsic::SimdSub,
+        Intrinsic::SimdMul,
+        Intrinsic::SimdExtract,
+        Intrinsic::SimdSum,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Intrinsic::AtomicLoad => "atomic_load",
+            Intrinsic::AtomicStore => "atomic_store",
+            Intrinsic::AtomicAdd => "atomic_add",
+            Intrinsic::AtomicSub => "atomic_sub",
+            Intrinsic::AtomicAnd => "atomic_and",
+            Intrinsic::AtomicOr => "atomic_or",
+            Intrinsic::AtomicXor => "atomic_xor",
+            Intrinsic::AtomicXchg => "atomic_xchg",
+            Intrinsic::AtomicCas => "atomic_cas",
+            Intrinsic::Fence => "fence",
+            Intrinsic::Popcnt => "popcnt",
+            Intrinsic::Clz => "clz",
+            Intrinsic::Ctz => "ctz",
+            Intrinsic::Sqrt => "sqrt",
+            Intrinsic::Fma => "fma",
+            Intrinsic::SimdSplat => "simd_splat",
+            Intrinsic::SimdAdd => "simd_add",
+            Intrinsic::SimdSub => "simd_sub",
+            Intrinsic::SimdMul => "simd_mul",
+            Intrinsic::SimdExtract => "simd_extract",
+            Intrinsic::SimdSum => "simd_sum",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|i| i.name() == name)
+    }
+
+    /// Number of operands the intrinsic takes
+    pub fn arity(self) -> usize {
+        match self {
+            Intrinsic::Fence => 0,
+            Intrinsic::AtomicLoad
+            | Intrinsic::Popcnt
+            | Intrinsic::Clz
+            | Intrinsic::Ctz
+            | Intrinsic::Sqrt
+            | Intrinsic::SimdSplat
+            | Intrinsic::SimdSum => 1,
+            Intrinsic::AtomicCas | Intrinsic::Fma => 3,
+            _ => 2,
+        }
+    }
+
+    /// Whether the intrinsic produces a value
+    pub fn has_result(self) -> bool {
+        !matches!(self, Intrinsic::AtomicStore | Intrinsic::Fence)
+    }
+
+    /// Whether the intrinsic reads or writes shared memory, so it may not
+    /// be removed or moved
+    pub fn has_side_effects(self) -> bool {
+        matches!(
+            self,
+            Intrinsic::AtomicLoad
+                | Intrinsic::AtomicStore
+                | Intrinsic::AtomicAdd
+                | Intrinsic::AtomicSub
+                | Intrinsic::AtomicAnd
+                | Intrinsic::AtomicOr
+                | Intrinsic::AtomicXor
+                | Intrinsic::AtomicXchg
+                | Intrinsic::AtomicCas
+                | Intrinsic::Fence
+        )
+    }
+
+    /// The result type implied by operands of type `args`, for uses that
+    /// do not state one; `atomic_load` defaults to `i64`
+    pub fn result_type(self, args: &[Type]) -> Option<Type> {
+        if !self.has_result() {
+            return None;
+        }
+        let arg = |i: usize| args.get(i).cloned().unwrap_or(Type::Unknown);
+        Some(match self {
+            Intrinsic::AtomicLoad => Type::I64,
+            Intrinsic::AtomicAdd
+            | Intrinsic::AtomicSub
+            | Intrinsic::AtomicAnd
+            | Intrinsic::AtomicOr
+            | Intrinsic::AtomicXor
+            | Intrinsic::AtomicXchg
+            | Intrinsic::AtomicCas => arg(1),
+            Intrinsic::SimdSplat => match arg(0) {
+                Type::F32 => Type::Named(F32X4.to_string()),
+                Type::I32 => Type::Named(I32X4.to_string()),
+                _ => Type::Unknown,
+            },
+            Intrinsic::SimdExtract | Intrinsic::SimdSum => {
+                lane_type(&arg(0)).unwrap_or(Type::Unknown)
+            }
+            _ => arg(0),
+        })
+    }
+
+    /// Check a use of the intrinsic with operands of type `args` and result
+    /// type `result`; `Unknown` operand types are not checked
+    pub fn check(self, args: &[Type], result: Option<&Type>) -> Result<(), String> {
+        if args.len() != self.arity() {
+            return Err(format!(
+                "{} takes {} operand{}, not {}",
+                self,
+                self.arity(),
+                if self.arity() == 1 { "" } else { "s" },
+                args.len()
+            ));
+        }
+        match (self.has_result(), result) {
+            (true, None) => return Err(format!("{} produces a value", self)),
+            (false, Some(_)) => return Err(format!("{} does not produce a value", self)),
+            _ => {}
+        }
+
+        let result = result.cloned().unwrap_or(Type::Unknown);
+        let expect = |ty: &Type, ok: bool, what: &str| {
+            if ok || *ty == Type::Unknown {
+                Ok(())
+            } else {
+                Err(format!("{} expects {}, not {}", self, what, ty))
+            }
+        };
+        let same = |ty: &Type| {
+            expect(
+                ty,
+                *ty == result || result == Type::Unknown,
+                "its result type",
+            )
+        };
+        match self {
+            Intrinsic::Fence => Ok(()),
+            Intrinsic::AtomicLoad => {
+                expect(&args[0], is_address(&args[0]), "an i64 address")?;
+                expect(&result, is_integer(&result), "an integer result")
+            }
+            Intrinsic::AtomicStore => {
+                expect(&args[0], is_address(&args[0]), "an i64 address")?;
+                expect(&args[1], is_integer(&args[1]), "an integer value")
+            }
+            Intrinsic::AtomicAdd
+            | Intrinsic::AtomicSub
+            | Intrinsic::AtomicAnd
+            | Intrinsic::AtomicOr
+            | Intrinsic::AtomicXor
+            | Intrinsic::AtomicXchg
+            | Intrinsic::AtomicCas => {
+                expect(&args[0], is_address(&args[0]), "an i64 address")?;
+                expect(&result, is_integer(&result), "an integer result")?;
+                args[1..].iter().try_for_each(same)
+            }
+            Intrinsic::Popcnt | Intrinsic::Clz | Intrinsic::Ctz => {
+                expect(&result, is_integer(&result), "an integer")?;
+                same(&args[0])
+            }
+            Intrinsic::Sqrt | Intrinsic::Fma => {
+                expect(&result, is_float(&result), "a float")?;
+                args.iter().try_for_each(same)
+            }
+            Intrinsic::SimdSplat => {
+                let lane = lane_type(&result);
+                expect(&result, lane.is_some(), "a vector result")?;
+                let lane = lane.unwrap_or(Type::Unknown);
+                expect(&args[0], args[0] == lane, &format!("a {} lane", lane))
+            }
+            Intrinsic::SimdAdd | Intrinsic::SimdSub | Intrinsic::SimdMul => {
+                expect(&result, lane_type(&result).is_some(), "a vector result")?;
+                args.iter().try_for_each(same)
+            }
+            Intrinsic::SimdExtract | Intrinsic::SimdSum => {
+                let lane = lane_type(&args[0]);
+                expect(&args[0], lane.is_some(), "a vector")?;
+                if let Some(lane) = lane {
+                    expect(&result, result == lane, &format!("a {} result", lane))?;
+                }
+                match args.get(1) {
+                    Some(index) => expect(index, *index == Type::I32, "an i32 lane number"),
+                    None => Ok(()),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Intrinsic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Check every intrinsic in `function` against its signature
+///
+/// Operand types come from the instructions that define them and from the
+/// function's parameters; the lane of `simd_extract` must be a constant.
+pub fn verify(function: &Function) -> Result<(), String> {
+    let mut types: HashMap<ValueId, Type> = function
+        .params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| (ValueId(i as u32 + 1000), param.ty.clone()))
+        .collect();
+    let mut constants = HashMap::new();
+    for block in function.blocks().values() {
+        for (value, inst) in &block.instructions {
+            if let Some(ty) = inst.instruction.result_type() {
+                types.insert(*value, ty);
+            }
+            if let Instruction::Const(Constant::I32(n)) = inst.instruction {
+                constants.insert(*value, n);
+            }
+        }
+    }
+
+    for block in function.blocks_in_order() {
+        for (_, inst) in &block.instructions {
+            let Instruction::Intrinsic {
+                intrinsic,
+                args,
+                ty,
+            } = &inst.instruction
+            else {
+                continue;
+            };
+            let arg_types: Vec<Type> = args
+                .iter()
+                .map(|arg| types.get(arg).cloned().unwrap_or(Type::Unknown))
+                .collect();
+            let mut result = intrinsic.check(&arg_types, ty.as_ref());
+            if *intrinsic == Intrinsic::SimdExtract && result.is_ok() {
+                let lane = args.get(1).and_then(|lane| constants.get(lane));
+                if !lane.is_some_and(|lane| (0..4).contains(lane)) {
+                    result = Err(format!("{} needs a constant lane from 0 to 3", intrinsic));
+                }
+            }
+            result.map_err(|message| {
+                format!(
+                    "Block {} in function {}: {}",
+                    block.name, function.name, message
+                )
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Type of each lane of a vector type
+pub fn lane_type(ty: &Type) -> Option<Type> {
+    match ty {
+        Type::Named(name) if name == F32X4 => Some(Type::F32),
+        Type::Named(name) if name == I32X4 => Some(Type::I32),
+        _ => None,
+    }
+}
+
+fn is_address(ty: &Type) -> bool {
+    matches!(ty, Type::I64 | Type::U64)
+}
+
+fn is_integer(ty: &Type) -> bool {
+    matches!(ty, Type::I32 | Type::I64 | Type::U32 | Type::U64)
+}
+
+fn is_float(ty: &Type) -> bool {
+    matches!(ty, Type::F32 | Type::F64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_signatures() {
+        let f32x4 = Type::Named(F32X4.to_string());
+        assert!(Intrinsic::AtomicAdd
+            .check(&[Type::I64, Type::I32], Some(&Type::I32))
+            .is_ok());
+        assert!(Intrinsic::AtomicAdd
+            .check(&[Type::I32, Type::I32], Some(&Type::I32))
+            .is_err());
+        assert!(Intrinsic::AtomicStore
+            .check(&[Type::I64, Type::I64], Some(&Type::I64))
+            .is_err());
+        assert!(Intrinsic::Sqrt
+            .check(&[Type::I32], Some(&Type::I32))
+            .is_err());
+        assert!(Intrinsic::Fma
+            .check(&[Type::F64, Type::Unknown, Type::F64], Some(&Type::F64))
+            .is_ok());
+        assert!(Intrinsic::SimdSplat
+            .check(&[Type::F32], Some(&f32x4))
+            .is_ok());
+        assert!(Intrinsic::SimdExtract
+            .check(&[f32x4.clone(), Type::I32], Some(&Type::I32))
+            .is_err());
+        assert_eq!(
+            Intrinsic::Popcnt.check(&[], Some(&Type::I32)).unwrap_err(),
+            "popcnt takes 1 operand, not 0"
+        );
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        for intrinsic in Intrinsic::ALL {
+            assert_eq!(Intrinsic::from_name(intrinsic.name()), Some(*intrinsic));
+        }
+        assert_eq!(Intrinsic::from_name("atomic_mul"), None);
+    }
+}
@@ -36,6 +36,16 @@ pub struct StructLayout {
     pub alignment: u32,
 }
 
+impl StructLayout {
+    /// Look up a field by name
+    pub fn field(&self, name: &str) -> Option<&FieldLayout> {
+        self.fields
+            .iter()
+            .find(|(field_name, _)| field_name == name)
+            .map(|(_, field)| field)
+    }
+}
+
 /// Enum variant layout
 #[derive(Debug, Clone)]
 pub struct VariantLayout {
@@ -45,6 +55,8 @@ pub struct VariantLayout {
     pub tag: u32,
     /// Layout of variant data (if any)
     pub data_layout: VariantDataLayout,
+    /// Types of the variant's fields in declaration order
+    pub field_types: Vec<Type>,
 }
 
 /// Layout of variant data
@@ -72,6 +84,30 @@ pub struct EnumLayout {
     pub alignment: u32,
 }
 
+impl EnumLayout {
+    /// Offset of the variant data, after the tag
+    pub fn data_offset(&self) -> u32 {
+        align_up(self.tag_size, self.alignment)
+    }
+
+    /// Look up a variant by name
+    pub fn variant(&self, name: &str) -> Option<&VariantLayout> {
+        self.variants.iter().find(|v| v.name == name)
+    }
+}
+
+impl VariantLayout {
+    /// Position of a named field in a struct variant
+    pub fn field_index(&self, field_name: &str) -> Option<usize> {
+        match &self.data_layout {
+            VariantDataLayout::Struct(fields) => {
+                fields.iter().position(|(name, _)| name == field_name)
+            }
+            _ => None,
+        }
+    }
+}
+
 /// Memory layout calculator
 pub struct LayoutCalculator {
     /// Cache of calculated layouts
@@ -93,6 +129,14 @@ impl LayoutCalculator {
             crate::parser::TypeKind::Named(name) => {
                 // Check if it's a known type like Option or Result
                 match name.as_str() {
+                    "i32" => Type::I32,
+                    "i64" => Type::I64,
+                    "u32" => Type::U32,
+                    "u64" => Type::U64,
+                    "f32" => Type::F32,
+                    "f64" => Type::F64,
+                    "bool" => Type::Bool,
+                    "string" => Type::String,
                     "Option" => Type::Unknown, // Generic Option without args
                     "Result" => Type::Unknown, // Generic Result without args
                     _ => Type::Named(name.clone()),
@@ -200,9 +244,11 @@ impl LayoutCalculator {
                     name: variant.name.clone(),
                     tag: tag as u32,
                     data_layout: VariantDataLayout::Unit,
+                    field_types: Vec::new(),
                 },
                 EnumVariantFields::Tuple(types) => {
                     let mut tuple_layouts = Vec::new();
+                    let mut field_types = Vec::new();
                     let mut variant_size = 0u32;
                     let mut variant_alignment = 1u32;
 
@@ -213,6 +259,7 @@ impl LayoutCalculator {
                         variant_size += type_layout.size;
                         variant_alignment = variant_alignment.max(type_layout.alignment);
                         tuple_layouts.push(type_layout);
+                        field_types.push(ty);
                     }
 
                     max_variant_size = max_variant_size.max(variant_size);
@@ -222,6 +269,7 @@ impl LayoutCalculator {
                         name: variant.name.clone(),
                         tag: tag as u32,
                         data_layout: VariantDataLayout::Tuple(tuple_layouts),
+                        field_types,
                     }
                 }
                 EnumVariantFields::Struct(fields) => {
@@ -253,6 +301,7 @@ impl LayoutCalculator {
                     VariantLayout {
                         name: variant.name.clone(),
                         tag: tag as u32,
+                        field_types: struct_fields.iter().map(|(_, f)| f.ty.clone()).collect(),
                         data_layout: VariantDataLayout::Struct(struct_fields),
                     }
                 }
@@ -286,10 +335,14 @@ impl LayoutCalculator {
                 size: 4,
                 alignment: 4,
             },
-            Type::F32 => TypeLayout {
+            Type::U32 | Type::F32 => TypeLayout {
                 size: 4,
                 alignment: 4,
             },
+            Type::I64 | Type::U64 | Type::F64 => TypeLayout {
+                size: 8,
+                alignment: 8,
+            },
             Type::Bool => TypeLayout {
                 size: 1,
                 alignment: 1,
@@ -412,6 +465,11 @@ impl LayoutCalculator {
         })
     }
 
+    /// Get the struct layout
+    pub fn get_struct_layout(&self, struct_name: &str) -> Option<&StructLayout> {
+        self.struct_layouts.get(struct_name)
+    }
+
     /// Get the tag value for an enum variant
     pub fn get_variant_tag(&self, enum_name: &str, variant_name: &str) -> Option<u32> {
         self.enum_layouts.get(enum_name).and_then(|layout| {
@@ -428,6 +486,18 @@ impl LayoutCalculator {
         self.enum_layouts.get(enum_name)
     }
 
+    /// Find the only enum declaring a variant, for unqualified variant names
+    pub fn find_variant_enum(&self, variant_name: &str) -> Option<&EnumLayout> {
+        let mut candidates = self
+            .enum_layouts
+            .values()
+            .filter(|layout| layout.variant(variant_name).is_some());
+        match (candidates.next(), candidates.next()) {
+            (Some(layout), None) => Some(layout),
+            _ => None,
+        }
+    }
+
     /// Get variant layout information
     pub fn get_variant_layout(
         &self,
@@ -448,7 +518,7 @@ fn align_up(value: u32, alignment: u32) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::parser::{StructField, TypeAnn};
+    use crate::parser::{EnumVariant, StructField, TypeAnn};
     use crate::source::{SourceLocation, Span};
 
     fn dummy_span() -> Span {
@@ -507,6 +577,47 @@ mod tests {
         assert_eq!(layout.fields[1].1.offset, 4);
     }
 
+    #[test]
+    fn test_enum_layout() {
+        let mut calc = LayoutCalculator::new();
+        let named = |name: &str| TypeAnn {
+            kind: crate::parser::TypeKind::Named(name.to_string()),
+            span: dummy_span(),
+        };
+
+        let def = EnumDefinition {
+            name: "Token".to_string(),
+            generic_params: None,
+            variants: vec![
+                EnumVariant {
+                    name: "Eof".to_string(),
+                    fields: EnumVariantFields::Unit,
+                    span: dummy_span(),
+                },
+                EnumVariant {
+                    name: "Pair".to_string(),
+                    fields: EnumVariantFields::Tuple(vec![named("bool"), named("i32")]),
+                    span: dummy_span(),
+                },
+            ],
+            where_clause: None,
+            span: dummy_span(),
+            is_monomorphized: false,
+            original_type: None,
+        };
+
+        let layout = calc.calculate_enum_layout(&def);
+        assert_eq!(layout.data_offset(), 4);
+        assert_eq!(layout.max_variant_size, 8); // bool, padding, i32
+        assert_eq!(layout.total_size, 12);
+
+        let pair = layout.variant("Pair").unwrap();
+        assert_eq!(pair.tag, 1);
+        assert_eq!(pair.field_types, vec![Type::Bool, Type::I32]);
+        assert_eq!(calc.find_variant_enum("Eof").unwrap().name, "Token");
+        assert!(calc.find_variant_enum("Missing").is_none());
+    }
+
     #[test]
     fn test_align_up() {
         assert_eq!(align_up(0, 4), 0);
@@ -0,0 +1,283 @@
+//! Linking of separately lowered modules
+//!
+//! `link_modules` merges modules lowered on their own into one, so passes
+//! run afterwards see the whole program: the inliner can inline across
+//! module boundaries and dead function elimination can drop what no module
+//! calls. A function without blocks that is not a trait method stub is a
+//! declaration of a function another module defines; calls to it are bound
+//! to that definition by name. Calls to ids no module defines go to the
+//! runtime and are left as they are.
+//!
+//! The first module is the program. The top-level code of every other
+//! module becomes a function named `<module>::__script_main__`, called at
+//! the start of the program's, in the order the modules are given.
+
+use super::optimizer::first_free_value_id;
+use super::{FunctionId, Instruction, InstructionWithLocation, Module};
+use crate::error::{Error, ErrorKind, Result};
+use std::collections::HashMap;
+
+/// Function holding a module's top-level code
+const TOPLEVEL_MAIN: &str = "__script_main__";
+
+/// Merge `modules` into one, binding declarations to definitions
+///
+/// Fails if two modules define a function with the same name or a
+/// declaration has no definition.
+pub fn link_modules(modules: Vec<Module>) -> Result<Module> {
+    let mut modules = modules.into_iter();
+    let Some(mut linked) = modules.next() else {
+        return Err(Error::new(ErrorKind::ModuleError, "No modules to link"));
+    };
+    let others: Vec<Module> = modules.collect();
+    if others.is_empty() {
+        return Ok(linked);
+    }
+
+    // Ids of the program's functions stay as they are, and every other
+    // function gets one above any id a module uses, runtime calls included
+    let mut next_id = others
+        .iter()
+        .map(|module| module.next_function_id().0)
+        .fold(linked.next_function_id().0, u32::max);
+    let mut id_maps: Vec<HashMap<FunctionId, FunctionId>> = vec![HashMap::new()];
+    for module in &others {
+        let mut map = HashMap::new();
+        for id in module.function_ids() {
+            map.insert(id, FunctionId(next_id));
+            next_id += 1;
+        }
+        id_maps.push(map);
+    }
+
+    // Find the definition for every name
+    let mut definitions: HashMap<String, (FunctionId, String)> = HashMap::new();
+    let mut inits = Vec::new();
+    let modules_with_ids = std::iter::once(&linked).chain(&others).zip(&id_maps);
+    for (index, (module, map)) in modules_with_ids.enumerate() {
+        for (id, function) in module.functions() {
+            if is_declaration(function) {
+                continue;
+            }
+            let id = map.get(id).copied().unwrap_or(*id);
+            let mut name = function.name.clone();
+            if index > 0 && name == TOPLEVEL_MAIN {
+                name = format!("{}::{}", module.name, TOPLEVEL_MAIN);
+                inits.push(id);
+            }
+            if let Some((_, other)) = definitions.get(&name) {
+                return Err(Error::new(
+                    ErrorKind::ModuleError,
+                    format!(
+                        "Function '{}' is defined in both module '{}' and module '{}'",
+                        name, other, module.name
+                    ),
+                ));
+            }
+            definitions.insert(name, (id, module.name.clone()));
+        }
+    }
+
+    // Point declarations at their definitions
+    let modules_with_ids = std::iter::once(&linked).chain(&others).zip(&mut id_maps);
+    for (module, map) in modules_with_ids {
+        for (id, function) in module.functions() {
+            if !is_declaration(function) {
+                continue;
+            }
+            let (definition, _) = definitions.get(&function.name).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::ModuleError,
+                    format!(
+                        "Function '{}' is declared in module '{}' but no module defines it",
+                        function.name, module.name
+                    ),
+                )
+            })?;
+            map.insert(*id, *definition);
+        }
+    }
+
+    // Bind the program's own calls, then move every other module's
+    // functions over
+    for id in linked.function_ids() {
+        if linked.get_function(id).is_some_and(is_declaration) {
+            linked.remove_function(id)?;
+        } else if let Some(function) = linked.get_function_mut(id) {
+            remap_calls(function, &id_maps[0]);
+        }
+    }
+    for (module, map) in others.into_iter().zip(&id_maps[1..]) {
+        for name in module.external_function_names() {
+            if let Some(ty) = module.get_external_function_type(&name) {
+                if !linked.is_external_function(&name) {
+                    linked.declare_external_function(name.clone(), ty.clone());
+                }
+            }
+        }
+        for name in module.exported_function_names() {
+            linked.mark_exported(name);
+        }
+        for layout in module.structs().values() {
+            if linked.get_struct(&layout.name).is_none() {
+                linked.add_struct(layout.clone());
+            }
+        }
+        for layout in module.enums().values() {
+            if linked.get_enum(&layout.name).is_none() {
+                linked.add_enum(layout.clone());
+            }
+        }
+
+        let module_name = module.name.clone();
+        for mut function in module.functions().values().cloned() {
+            if is_declaration(&function) {
+                continue;
+            }
+            function.id = map[&function.id];
+            if function.name == TOPLEVEL_MAIN {
+                function.name = format!("{}::{}", module_name, TOPLEVEL_MAIN);
+            }
+            remap_calls(&mut function, map);
+            linked.add_function(function)?;
+        }
+    }
+    while linked.next_function_id().0 < next_id {
+        linked.reserve_function_id();
+    }
+
+    call_inits(&mut linked, &inits);
+    Ok(linked)
+}
+
+/// Whether `function` only declares a function another module defines
+fn is_declaration(function: &super::Function) -> bool {
+    function.entry_block.is_none() && function.trait_method.is_none()
+}
+
+/// Rewrite the callees of `function` through `map`
+fn remap_calls(function: &mut super::Function, map: &HashMap<FunctionId, FunctionId>) {
+    let blocks: Vec<_> = function.blocks().keys().copied().collect();
+    for block_id in blocks {
+        let Some(block) = function.get_block_mut(block_id) else {
+            continue;
+        };
+        for (_, inst) in &mut block.instructions {
+            let callee = match &mut inst.instruction {
+                Instruction::Call { func, .. } => func,
+                Instruction::CreateClosure { function, .. } => function,
+                _ => continue,
+            };
+            if let Some(id) = map.get(callee) {
+                *callee = *id;
+            }
+        }
+    }
+}
+
+/// Call `inits` at the start of the program's top-level code
+fn call_inits(module: &mut Module, inits: &[FunctionId]) {
+    let Some(main) = module.get_function_id(TOPLEVEL_MAIN) else {
+        return;
+    };
+    let calls: Vec<Instruction> = inits
+        .iter()
+        .filter_map(|id| module.get_function(*id))
+        .map(|init| Instruction::Call {
+            func: init.id,
+            args: Vec::new(),
+            ty: init.return_type.clone(),
+        })
+        .collect();
+    let mut next_value_id = first_free_value_id(module);
+
+    let Some(function) = module.get_function_mut(main) else {
+        return;
+    };
+    let Some(entry) = function.entry_block else {
+        return;
+    };
+    if let Some(block) = function.get_block_mut(entry) {
+        for (position, instruction) in calls.into_iter().enumerate() {
+            let value = super::ValueId(next_value_id);
+            next_value_id += 1;
+            let call = InstructionWithLocation {
+                instruction,
+                source_location: None,
+            };
+            block.instructions.insert(position, (value, call));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Constant, IrBuilder};
+    use crate::types::Type;
+
+    /// A module defining `name`, returning a constant
+    fn library(name: &str) -> Module {
+        let mut builder = IrBuilder::new();
+        builder.create_function(name.to_string(), vec![], Type::I32);
+        let value = builder.const_value(Constant::I32(7));
+        builder.build_return(Some(value));
+        let mut module = builder.build();
+        module.name = "lib".to_string();
+        module
+    }
+
+    #[test]
+    fn test_binds_declarations_to_definitions() {
+        // The program calls `helper`, which only the library defines
+        let mut builder = IrBuilder::new();
+        let declared =
+            builder
+                .module_mut()
+                .create_function("helper".to_string(), vec![], Type::I32);
+        builder.create_function(TOPLEVEL_MAIN.to_string(), vec![], Type::I32);
+        let result = builder.build_call(declared, vec![], Type::I32);
+        builder.build_return(result);
+        let program = builder.build();
+
+        let mut lib = library("helper");
+        lib.create_function("missing".to_string(), vec![], Type::I32);
+        let error = link_modules(vec![program.clone(), lib]).unwrap_err();
+        assert!(error.message.contains("'missing'"));
+
+        let linked = link_modules(vec![program, library("helper")]).unwrap();
+        let helper = linked.get_function_id("helper").unwrap();
+        assert!(linked.get_function(helper).unwrap().entry_block.is_some());
+        let main = linked.get_function_by_name(TOPLEVEL_MAIN).unwrap();
+        let calls: Vec<FunctionId> = main
+            .blocks()
+            .values()
+            .flat_map(|block| &block.instructions)
+            .filter_map(|(_, inst)| match inst.instruction {
+                Instruction::Call { func, .. } => Some(func),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls, vec![helper]);
+        assert_eq!(linked.functions().len(), 2);
+    }
+
+    #[test]
+    fn test_runs_module_top_level_code_first() {
+        let program = library(TOPLEVEL_MAIN);
+        let mut lib = library(TOPLEVEL_MAIN);
+        lib.name = "config".to_string();
+
+        let linked = link_modules(vec![program, lib]).unwrap();
+        let init = linked.get_function_id("config::__script_main__").unwrap();
+        let main = linked.get_function_by_name(TOPLEVEL_MAIN).unwrap();
+        let entry = main.get_block(main.entry_block.unwrap()).unwrap();
+        assert!(matches!(
+            entry.instructions[0].1.instruction,
+            Instruction::Call { func, .. } if func == init
+        ));
+
+        let error = link_modules(vec![library("f"), library("f")]).unwrap_err();
+        assert!(error.message.contains("defined in both module"));
+    }
+}
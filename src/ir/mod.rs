@@ -7,26 +7,35 @@
 //! - SSA-based (Static Single Assignment) for easier optimization
 //! - Backend-agnostic (can target Cranelift, LLVM, or other backends)
 
+use crate::source::Span;
 use crate::types::Type;
 use std::fmt;
 
 pub mod block;
 pub mod function;
 pub mod instruction;
+pub mod instrument;
+pub mod intrinsic;
 pub mod layout;
+pub mod link;
 pub mod module;
 pub mod optimizer;
 pub mod value;
 
 pub use block::{BasicBlock, BlockId};
-pub use function::{Function, FunctionId, Parameter};
+pub use function::{
+    DebugVariable, Function, FunctionId, GenericDispatch, Parameter, TraitMethodRef,
+};
 pub use instruction::{
-    BinaryOp, ComparisonOp, Constant, Instruction, InstructionWithLocation, UnaryOp,
+    BinaryOp, ComparisonOp, Constant, Instruction, InstructionWithLocation, Probe, UnaryOp,
 };
+pub use instrument::{instrument, ProbePolicy, ProbeSite};
+pub use intrinsic::Intrinsic;
 pub use layout::{
     EnumLayout, FieldLayout, LayoutCalculator, StructLayout, TypeLayout, VariantDataLayout,
     VariantLayout,
 };
+pub use link::link_modules;
 pub use module::Module;
 pub use value::{Value, ValueId};
 
@@ -41,6 +50,8 @@ pub struct IrBuilder {
     current_block: Option<BlockId>,
     /// Next value ID to allocate
     next_value_id: u32,
+    /// Source span attached to the instructions being built
+    current_location: Option<Span>,
 }
 
 impl IrBuilder {
@@ -51,6 +62,7 @@ impl IrBuilder {
             current_function: None,
             current_block: None,
             next_value_id: 0,
+            current_location: None,
         }
     }
 
@@ -102,6 +114,11 @@ impl IrBuilder {
         self.current_function
     }
 
+    /// Get the module being built
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
     /// Get a mutable reference to the module
     pub fn module_mut(&mut self) -> &mut Module {
         &mut self.module
@@ -133,6 +150,33 @@ impl IrBuilder {
         self.current_block
     }
 
+    /// Set the source span attached to subsequently built instructions,
+    /// returning the previous one
+    pub fn set_location(&mut self, location: Option<Span>) -> Option<Span> {
+        std::mem::replace(&mut self.current_location, location)
+    }
+
+    /// Get the source span attached to instructions being built
+    pub fn current_location(&self) -> Option<Span> {
+        self.current_location
+    }
+
+    /// Record a source-level variable of the current function
+    pub fn declare_variable(&mut self, name: String, ptr: ValueId, ty: Type, is_param: bool) {
+        let span = self.current_location;
+        if let Some(func_id) = self.current_function {
+            if let Some(func) = self.module.get_function_mut(func_id) {
+                func.debug_variables.push(DebugVariable {
+                    name,
+                    ptr,
+                    ty,
+                    is_param,
+                    span,
+                });
+            }
+        }
+    }
+
     /// Generate a new value ID
     pub fn next_value_id(&mut self) -> ValueId {
         let id = ValueId(self.next_value_id);
@@ -146,7 +190,13 @@ impl IrBuilder {
             let value_id = self.next_value_id();
             if let Some(func) = self.module.get_function_mut(func_id) {
                 if let Some(block) = func.get_block_mut(block_id) {
-                    block.add_instruction(value_id, inst);
+                    block.add_instruction_with_location(
+                        value_id,
+                        InstructionWithLocation {
+                            instruction: inst,
+                            source_location: self.current_location,
+                        },
+                    );
                     return Some(value_id);
                 }
             }
@@ -177,6 +227,15 @@ impl IrBuilder {
         self.add_instruction(Instruction::Unary { op, operand, ty })
     }
 
+    /// Build a conversion between two types
+    pub fn build_cast(&mut self, value: ValueId, from_ty: Type, to_ty: Type) -> Option<ValueId> {
+        self.add_instruction(Instruction::Cast {
+            value,
+            from_ty,
+            to_ty,
+        })
+    }
+
     /// Build a comparison
     pub fn build_compare(
         &mut self,
@@ -462,18 +521,34 @@ impl IrBuilder {
     pub fn build_create_closure(
         &mut self,
         function_id: String,
+        function: FunctionId,
         parameters: Vec<String>,
         captured_vars: Vec<(String, ValueId)>,
         captures_by_ref: bool,
     ) -> Option<ValueId> {
         self.add_instruction(Instruction::CreateClosure {
             function_id,
+            function,
             parameters,
             captured_vars,
             captures_by_ref,
         })
     }
 
+    /// Build a load of a captured variable from a closure environment
+    pub fn build_load_capture(
+        &mut self,
+        environment: ValueId,
+        index: usize,
+        ty: Type,
+    ) -> Option<ValueId> {
+        self.add_instruction(Instruction::LoadCapture {
+            environment,
+            index,
+            ty,
+        })
+    }
+
     /// Build a closure invocation instruction
     pub fn build_invoke_closure(
         &mut self,
@@ -1,7 +1,7 @@
-use super::{Function, FunctionId, Parameter};
+use super::{EnumLayout, Function, FunctionId, Parameter, StructLayout, VariantDataLayout};
 use crate::error::{Error, ErrorKind};
 use crate::types::Type;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
 /// IR Module containing functions and global data
@@ -9,6 +9,8 @@ use std::fmt;
 pub struct Module {
     /// Module name
     pub name: String,
+    /// Path of the source file the module was compiled from
+    pub source_file: Option<String>,
     /// Functions in this module
     functions: HashMap<FunctionId, Function>,
     /// Function name to ID mapping
@@ -17,6 +19,12 @@ pub struct Module {
     next_function_id: u32,
     /// External function declarations
     external_functions: HashMap<String, Type>,
+    /// Names of the functions the module exports
+    exported_functions: HashSet<String>,
+    /// Field layouts of the structs declared in this module
+    structs: HashMap<String, StructLayout>,
+    /// Tagged-union layouts of the enums declared in this module
+    enums: HashMap<String, EnumLayout>,
 }
 
 impl Module {
@@ -24,10 +32,14 @@ impl Module {
     pub fn new() -> Self {
         let mut module = Module {
             name: "main".to_string(),
+            source_file: None,
             functions: HashMap::new(),
             function_names: HashMap::new(),
             next_function_id: 0,
             external_functions: HashMap::new(),
+            exported_functions: HashSet::new(),
+            structs: HashMap::new(),
+            enums: HashMap::new(),
         };
 
         // Register built-in functions
@@ -126,6 +138,61 @@ impl Module {
         self.external_functions.get(name)
     }
 
+    /// Get the names of all external functions
+    pub fn external_function_names(&self) -> Vec<String> {
+        self.external_functions.keys().cloned().collect()
+    }
+
+    /// Remove an external function declaration
+    pub fn remove_external_function(&mut self, name: &str) -> Option<Type> {
+        self.external_functions.remove(name)
+    }
+
+    /// Mark a function as exported from the module
+    pub fn mark_exported(&mut self, name: String) {
+        self.exported_functions.insert(name);
+    }
+
+    /// Check if a function is exported from the module
+    pub fn is_exported(&self, name: &str) -> bool {
+        self.exported_functions.contains(name)
+    }
+
+    /// Get the names of all exported functions
+    pub fn exported_function_names(&self) -> Vec<String> {
+        self.exported_functions.iter().cloned().collect()
+    }
+
+    /// Register the layout of a struct declared in this module
+    pub fn add_struct(&mut self, layout: StructLayout) {
+        self.structs.insert(layout.name.clone(), layout);
+    }
+
+    /// Get the layout of a struct by name
+    pub fn get_struct(&self, name: &str) -> Option<&StructLayout> {
+        self.structs.get(name)
+    }
+
+    /// Get all struct layouts
+    pub fn structs(&self) -> &HashMap<String, StructLayout> {
+        &self.structs
+    }
+
+    /// Register the layout of an enum declared in this module
+    pub fn add_enum(&mut self, layout: EnumLayout) {
+        self.enums.insert(layout.name.clone(), layout);
+    }
+
+    /// Get the layout of an enum by name
+    pub fn get_enum(&self, name: &str) -> Option<&EnumLayout> {
+        self.enums.get(name)
+    }
+
+    /// Get all enum layouts
+    pub fn enums(&self) -> &HashMap<String, EnumLayout> {
+        &self.enums
+    }
+
     /// Add a function to the module
     pub fn add_function(&mut self, function: Function) -> Result<FunctionId, Error> {
         let func_id = function.id;
@@ -198,8 +265,11 @@ impl Module {
             )
         })?;
 
-        // Remove from name mapping
-        self.function_names.remove(&function.name);
+        // Remove from name mapping, unless the name has been given to
+        // another function since
+        if self.function_names.get(&function.name) == Some(&func_id) {
+            self.function_names.remove(&function.name);
+        }
 
         Ok(function)
     }
@@ -386,6 +456,9 @@ impl Module {
                     func.name
                 ));
             }
+
+            // Check intrinsics against their signatures
+            super::intrinsic::verify(func)?;
         }
 
         Ok(())
@@ -412,6 +485,65 @@ impl fmt::Display for Module {
             writeln!(f)?;
         }
 
+        // Struct layouts
+        let mut structs: Vec<&StructLayout> = self.structs.values().collect();
+        structs.sort_by(|a, b| a.name.cmp(&b.name));
+        for layout in structs {
+            let fields: Vec<String> = layout
+                .fields
+                .iter()
+                .map(|(name, field)| format!("{}: {} @{}", name, field.ty, field.offset))
+                .collect();
+            writeln!(
+                f,
+                "struct {} [size={}, align={}] {{ {} }}",
+                layout.name,
+                layout.total_size,
+                layout.alignment,
+                fields.join(", ")
+            )?;
+        }
+        if !self.structs.is_empty() {
+            writeln!(f)?;
+        }
+
+        // Enum layouts: a u32 tag followed by the largest variant's data
+        let mut enums: Vec<&EnumLayout> = self.enums.values().collect();
+        enums.sort_by(|a, b| a.name.cmp(&b.name));
+        for layout in enums {
+            writeln!(
+                f,
+                "enum {} [size={}, align={}, data@{}] {{",
+                layout.name,
+                layout.total_size,
+                layout.alignment,
+                layout.data_offset()
+            )?;
+            for variant in &layout.variants {
+                write!(f, "    {} = {}", variant.name, variant.tag)?;
+                match &variant.data_layout {
+                    VariantDataLayout::Unit => {}
+                    VariantDataLayout::Tuple(_) => {
+                        let types: Vec<String> =
+                            variant.field_types.iter().map(Type::to_string).collect();
+                        write!(f, "({})", types.join(", "))?;
+                    }
+                    VariantDataLayout::Struct(fields) => {
+                        let fields: Vec<String> = fields
+                            .iter()
+                            .map(|(name, field)| {
+                                format!("{}: {} @{}", name, field.ty, field.offset)
+                            })
+                            .collect();
+                        write!(f, " {{ {} }}", fields.join(", "))?;
+                    }
+                }
+                writeln!(f)?;
+            }
+            writeln!(f, "}}")?;
+            writeln!(f)?;
+        }
+
         // Functions
         for func in self.functions.values() {
             write!(f, "{}", func)?;
@@ -0,0 +1,345 @@
+//! Escape Analysis
+//!
+//! This module determines which allocations in a function may outlive it.
+//! An allocation escapes when a pointer to it is returned, passed to a call
+//! or closure, stored somewhere outside the function's own allocations, or
+//! saved across a suspension point. Allocations that do not escape can live
+//! in the function's stack frame instead of on the heap.
+
+use crate::ir::{Function, Instruction, ValueId};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Escape information for a function
+#[derive(Debug, Clone, Default)]
+pub struct EscapeInfo {
+    /// Values produced by allocating instructions
+    pub allocations: HashSet<ValueId>,
+    /// Allocations that may outlive the function
+    pub escaping: HashSet<ValueId>,
+}
+
+impl EscapeInfo {
+    /// Check if an allocation may outlive the function
+    ///
+    /// Values that are not allocations are reported as escaping.
+    pub fn escapes(&self, allocation: ValueId) -> bool {
+        !self.allocations.contains(&allocation) || self.escaping.contains(&allocation)
+    }
+
+    /// Get the allocations that can be placed on the stack
+    pub fn stack_allocations(&self) -> Vec<ValueId> {
+        let mut values: Vec<ValueId> = self
+            .allocations
+            .difference(&self.escaping)
+            .copied()
+            .collect();
+        values.sort_by_key(|value| value.0);
+        values
+    }
+}
+
+impl fmt::Display for EscapeInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Escape Analysis:")?;
+        let mut allocations: Vec<_> = self.allocations.iter().collect();
+        allocations.sort_by_key(|value| value.0);
+        for allocation in allocations {
+            let place = if self.escaping.contains(allocation) {
+                "heap"
+            } else {
+                "stack"
+            };
+            writeln!(f, "  {}: {}", allocation, place)?;
+        }
+        Ok(())
+    }
+}
+
+/// Escape analysis pass
+///
+/// Tracks which allocations each value may point to and which allocations
+/// each allocation may contain a pointer to, so that storing a pointer in a
+/// local variable's slot does not by itself make it escape.
+pub struct EscapeAnalysis {
+    /// Allocations each value may point to
+    points_to: HashMap<ValueId, HashSet<ValueId>>,
+    /// Allocations whose pointers may be stored in each allocation
+    contents: HashMap<ValueId, HashSet<ValueId>>,
+}
+
+impl EscapeAnalysis {
+    /// Create a new escape analysis
+    pub fn new() -> Self {
+        EscapeAnalysis {
+            points_to: HashMap::new(),
+            contents: HashMap::new(),
+        }
+    }
+
+    /// Run escape analysis on a function
+    pub fn analyze(&mut self, func: &Function) -> EscapeInfo {
+        self.points_to.clear();
+        self.contents.clear();
+
+        let mut info = EscapeInfo::default();
+        let instructions: Vec<(ValueId, &Instruction)> = func
+            .blocks_in_order()
+            .into_iter()
+            .flat_map(|block| {
+                block
+                    .instructions
+                    .iter()
+                    .map(|(value, inst)| (*value, &inst.instruction))
+            })
+            .collect();
+
+        for (value, instruction) in &instructions {
+            if is_allocation(instruction) {
+                info.allocations.insert(*value);
+                self.points_to.entry(*value).or_default().insert(*value);
+            }
+        }
+
+        // State machines keep their locals alive across suspensions
+        if func.is_async {
+            info.escaping = info.allocations.clone();
+            return info;
+        }
+
+        self.propagate(&instructions);
+
+        let mut roots = HashSet::new();
+        for (_, instruction) in &instructions {
+            for value in self.escaping_operands(instruction) {
+                roots.extend(self.targets(value));
+            }
+        }
+
+        // Anything reachable from an escaping allocation escapes too
+        let mut worklist: Vec<ValueId> = roots.into_iter().collect();
+        while let Some(allocation) = worklist.pop() {
+            if !info.escaping.insert(allocation) {
+                continue;
+            }
+            if let Some(contained) = self.contents.get(&allocation) {
+                worklist.extend(contained.iter().copied());
+            }
+        }
+
+        info
+    }
+
+    /// Propagate points-to and contents sets until they stop changing
+    fn propagate(&mut self, instructions: &[(ValueId, &Instruction)]) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (value, instruction) in instructions {
+                match instruction {
+                    // Derived pointers and copies alias their source
+                    Instruction::GetElementPtr { ptr: source, .. }
+                    | Instruction::GetFieldPtr { object: source, .. }
+                    | Instruction::Cast { value: source, .. } => {
+                        let targets = self.targets(*source);
+                        changed |= add_all(self.points_to.entry(*value).or_default(), targets);
+                    }
+                    Instruction::Phi { incoming, .. } => {
+                        let targets: HashSet<ValueId> = incoming
+                            .iter()
+                            .flat_map(|(incoming, _)| self.targets(*incoming))
+                            .collect();
+                        changed |= add_all(self.points_to.entry(*value).or_default(), targets);
+                    }
+                    // Loads yield whatever was stored in the allocation
+                    Instruction::Load { ptr: source, .. }
+                    | Instruction::LoadField { object: source, .. }
+                    | Instruction::ExtractEnumData {
+                        enum_value: source, ..
+                    } => {
+                        let targets: HashSet<ValueId> = self
+                            .targets(*source)
+                            .into_iter()
+                            .flat_map(|allocation| self.stored_in(allocation))
+                            .collect();
+                        changed |= add_all(self.points_to.entry(*value).or_default(), targets);
+                    }
+                    Instruction::Store { ptr: target, value }
+                    | Instruction::StoreField {
+                        object: target,
+                        value,
+                        ..
+                    } => {
+                        let stored = self.targets(*value);
+                        for allocation in self.targets(*target) {
+                            changed |= add_all(
+                                self.contents.entry(allocation).or_default(),
+                                stored.clone(),
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Operands whose pointees may outlive the function
+    fn escaping_operands(&self, instruction: &Instruction) -> Vec<ValueId> {
+        match instruction {
+            Instruction::Return(Some(value)) => vec![*value],
+            Instruction::Call { args, .. } => args.clone(),
+            Instruction::InvokeClosure { closure, args, .. } => {
+                let mut values = args.clone();
+                values.push(*closure);
+                values
+            }
+            Instruction::CreateClosure { captured_vars, .. } => {
+                captured_vars.iter().map(|(_, value)| *value).collect()
+            }
+            Instruction::ConstructStruct { fields, .. } => {
+                fields.iter().map(|(_, value)| *value).collect()
+            }
+            Instruction::ConstructEnum { args, .. } => args.clone(),
+            Instruction::StoreAsyncState { value, .. } => vec![*value],
+            Instruction::Suspend { state, .. } => vec![*state],
+            Instruction::PollFuture { future, .. } => vec![*future],
+            Instruction::ErrorPropagation { value, .. } => vec![*value],
+            // Stores through pointers of unknown origin may write anywhere
+            Instruction::Store { ptr: target, value }
+            | Instruction::StoreField {
+                object: target,
+                value,
+                ..
+            } if self.targets(*target).is_empty() => vec![*value],
+            // Atomics work on raw addresses, which may be any allocation
+            Instruction::Intrinsic {
+                intrinsic, args, ..
+            } if intrinsic.has_side_effects() => args.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Allocations a value may point to
+    fn targets(&self, value: ValueId) -> HashSet<ValueId> {
+        self.points_to.get(&value).cloned().unwrap_or_default()
+    }
+
+    /// Allocations whose pointers may be stored in an allocation
+    fn stored_in(&self, allocation: ValueId) -> HashSet<ValueId> {
+        self.contents.get(&allocation).cloned().unwrap_or_default()
+    }
+}
+
+impl Default for EscapeAnalysis {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether an instruction allocates memory for its result
+fn is_allocation(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Alloc { .. }
+            | Instruction::AllocStruct { .. }
+            | Instruction::AllocEnum { .. }
+            | Instruction::ConstructStruct { .. }
+    )
+}
+
+/// Add `values` to `set`, returning whether it grew
+fn add_all(set: &mut HashSet<ValueId>, values: HashSet<ValueId>) -> bool {
+    let before = set.len();
+    set.extend(values);
+    set.len() != before
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Constant, IrBuilder};
+    use crate::types::Type;
+
+    fn point() -> Type {
+        Type::Named("Point".to_string())
+    }
+
+    #[test]
+    fn test_local_struct_does_not_escape() {
+        let mut builder = IrBuilder::new();
+        let func_id = builder.create_function("length".to_string(), vec![], Type::I32);
+
+        let point = builder
+            .build_alloc_struct("Point".to_string(), point())
+            .unwrap();
+        let x = builder.const_value(Constant::I32(3));
+        let field = builder
+            .add_instruction(Instruction::GetFieldPtr {
+                object: point,
+                field_name: "x".to_string(),
+                field_ty: Type::I32,
+            })
+            .unwrap();
+        builder.build_store(field, x);
+        // Binding the struct to a variable stores its pointer in the slot
+        let slot = builder.build_alloc(point()).unwrap();
+        builder.build_store(slot, point);
+        let loaded = builder.build_load(slot, point()).unwrap();
+        let result = builder
+            .add_instruction(Instruction::LoadField {
+                object: loaded,
+                field_name: "x".to_string(),
+                field_ty: Type::I32,
+            })
+            .unwrap();
+        builder.build_return(Some(result));
+
+        let module = builder.build();
+        let info = EscapeAnalysis::new().analyze(module.get_function(func_id).unwrap());
+
+        assert!(!info.escapes(point));
+        assert!(!info.escapes(slot));
+        assert_eq!(info.stack_allocations().len(), 2);
+    }
+
+    #[test]
+    fn test_returned_struct_escapes_through_slot() {
+        let mut builder = IrBuilder::new();
+        let func_id = builder.create_function("make".to_string(), vec![], point());
+
+        let point = builder
+            .build_alloc_struct("Point".to_string(), point())
+            .unwrap();
+        let slot = builder.build_alloc(point()).unwrap();
+        builder.build_store(slot, point);
+        let loaded = builder.build_load(slot, point()).unwrap();
+        builder.build_return(Some(loaded));
+
+        let module = builder.build();
+        let info = EscapeAnalysis::new().analyze(module.get_function(func_id).unwrap());
+
+        assert!(info.escapes(point));
+        assert!(!info.escapes(slot));
+    }
+
+    #[test]
+    fn test_call_argument_escapes() {
+        let mut builder = IrBuilder::new();
+        let callee = builder.create_function("consume".to_string(), vec![], Type::Unknown);
+        builder.build_return(None);
+        let func_id = builder.create_function("caller".to_string(), vec![], Type::Unknown);
+
+        let point = builder
+            .build_alloc_struct("Point".to_string(), point())
+            .unwrap();
+        builder.build_call(callee, vec![point], Type::Unknown);
+        builder.build_return(None);
+
+        let module = builder.build();
+        let info = EscapeAnalysis::new().analyze(module.get_function(func_id).unwrap());
+
+        assert!(info.escapes(point));
+        assert!(info.stack_allocations().is_empty());
+    }
+}
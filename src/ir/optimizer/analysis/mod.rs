@@ -6,12 +6,14 @@
 pub mod control_flow;
 pub mod data_flow;
 pub mod dominance;
+pub mod escape;
 pub mod liveness;
 pub mod use_def;
 
 pub use control_flow::{CfgEdge, CfgNode, ControlFlowGraph};
 pub use data_flow::{DataFlowAnalysis, DataFlowDirection, DataFlowProblem, DataFlowSolver};
 pub use dominance::{DominanceAnalysis, DominanceInfo};
+pub use escape::{EscapeAnalysis, EscapeInfo};
 pub use liveness::{LivenessAnalysis, LivenessInfo};
 pub use use_def::{DefUseChains, DefUseInfo, UseDefChains};
 
@@ -38,28 +38,22 @@ impl ConstantFolding {
     fn eval_binary_op(op: BinaryOp, lhs: &Constant, rhs: &Constant) -> Option<Constant> {
         match (op, lhs, rhs) {
             // Integer arithmetic
+            // Overflow and division by zero are left for the runtime to
+            // trap or wrap according to the overflow mode
             (BinaryOp::Add, Constant::I32(a), Constant::I32(b)) => {
-                Some(Constant::I32(a.wrapping_add(*b)))
+                a.checked_add(*b).map(Constant::I32)
             }
             (BinaryOp::Sub, Constant::I32(a), Constant::I32(b)) => {
-                Some(Constant::I32(a.wrapping_sub(*b)))
+                a.checked_sub(*b).map(Constant::I32)
             }
             (BinaryOp::Mul, Constant::I32(a), Constant::I32(b)) => {
-                Some(Constant::I32(a.wrapping_mul(*b)))
+                a.checked_mul(*b).map(Constant::I32)
             }
             (BinaryOp::Div, Constant::I32(a), Constant::I32(b)) => {
-                if *b != 0 {
-                    Some(Constant::I32(a / b))
-                } else {
-                    None // Division by zero - don't fold
-                }
+                a.checked_div(*b).map(Constant::I32)
             }
             (BinaryOp::Mod, Constant::I32(a), Constant::I32(b)) => {
-                if *b != 0 {
-                    Some(Constant::I32(a % b))
-                } else {
-                    None // Modulo by zero - don't fold
-                }
+                a.checked_rem(*b).map(Constant::I32)
             }
 
             // Float arithmetic
@@ -92,7 +86,7 @@ impl ConstantFolding {
     /// Evaluate a unary operation on a constant
     fn eval_unary_op(op: UnaryOp, operand: &Constant) -> Option<Constant> {
         match (op, operand) {
-            (UnaryOp::Neg, Constant::I32(n)) => Some(Constant::I32(-n)),
+            (UnaryOp::Neg, Constant::I32(n)) => n.checked_neg().map(Constant::I32),
             (UnaryOp::Neg, Constant::F32(f)) => Some(Constant::F32(-f)),
             (UnaryOp::Not, Constant::Bool(b)) => Some(Constant::Bool(!b)),
             _ => None, // Type mismatch
@@ -0,0 +1,277 @@
+//! Constant Propagation Optimization Pass
+//!
+//! This pass forwards constants through memory and control flow so that
+//! constant folding can see them. Local variables are lowered to stack slots,
+//! so a slot that is written exactly once with a constant has every load from
+//! it replaced by that constant. Phi nodes whose incoming values are all the
+//! same constant become that constant.
+
+use super::OptimizationPass;
+use crate::ir::{Constant, Function, Instruction, Module as IrModule, ValueId};
+use std::collections::{HashMap, HashSet};
+
+/// Constant propagation optimization pass
+#[derive(Debug)]
+pub struct ConstantPropagation {
+    /// Number of instructions replaced by constants
+    propagated_count: usize,
+}
+
+impl ConstantPropagation {
+    /// Create a new constant propagation pass
+    pub fn new() -> Self {
+        ConstantPropagation {
+            propagated_count: 0,
+        }
+    }
+
+    /// Get the number of instructions replaced by constants
+    pub fn propagated_count(&self) -> usize {
+        self.propagated_count
+    }
+
+    /// Optimize a single function
+    fn optimize_function(&mut self, function: &mut Function) -> bool {
+        let mut changed = false;
+
+        loop {
+            let replacements = Self::find_replacements(function);
+            if replacements.is_empty() {
+                break;
+            }
+
+            let block_ids: Vec<_> = function.blocks().keys().copied().collect();
+            for block_id in block_ids {
+                if let Some(block) = function.get_block_mut(block_id) {
+                    for (value_id, inst_with_loc) in &mut block.instructions {
+                        if let Some(constant) = replacements.get(value_id) {
+                            inst_with_loc.instruction = Instruction::Const(constant.clone());
+                        }
+                    }
+                }
+            }
+
+            self.propagated_count += replacements.len();
+            changed = true;
+        }
+
+        changed
+    }
+
+    /// Find loads and phis that always produce a known constant
+    fn find_replacements(function: &Function) -> HashMap<ValueId, Constant> {
+        let mut constants = HashMap::new();
+        let mut slots = HashSet::new();
+        for block in function.blocks().values() {
+            for (value_id, inst_with_loc) in &block.instructions {
+                match &inst_with_loc.instruction {
+                    Instruction::Const(constant) => {
+                        constants.insert(*value_id, constant.clone());
+                    }
+                    Instruction::Alloc { .. } => {
+                        slots.insert(*value_id);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let slot_values = Self::single_store_slots(function, &slots);
+        let mut replacements = HashMap::new();
+        for block in function.blocks().values() {
+            for (value_id, inst_with_loc) in &block.instructions {
+                let constant = match &inst_with_loc.instruction {
+                    Instruction::Load { ptr, .. } => slot_values
+                        .get(ptr)
+                        .and_then(|stored| constants.get(stored)),
+                    Instruction::Phi { incoming, .. } => {
+                        let mut values = incoming.iter().map(|(value, _)| constants.get(value));
+                        match values.next() {
+                            Some(Some(first)) if values.all(|value| value == Some(first)) => {
+                                Some(first)
+                            }
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                if let Some(constant) = constant {
+                    replacements.insert(*value_id, constant.clone());
+                }
+            }
+        }
+
+        replacements
+    }
+
+    /// Map stack slots that are stored to exactly once to the stored value
+    ///
+    /// Slots whose address is used by anything other than a direct load or
+    /// store may be written through an alias and are left out.
+    fn single_store_slots(
+        function: &Function,
+        slots: &HashSet<ValueId>,
+    ) -> HashMap<ValueId, ValueId> {
+        let mut stores: HashMap<ValueId, Vec<ValueId>> = HashMap::new();
+        let mut aliased = HashSet::new();
+
+        for block in function.blocks().values() {
+            for (_, inst_with_loc) in &block.instructions {
+                match &inst_with_loc.instruction {
+                    Instruction::Load { .. } => {}
+                    Instruction::Store { ptr, value } => {
+                        if slots.contains(ptr) {
+                            stores.entry(*ptr).or_default().push(*value);
+                        }
+                        if slots.contains(value) {
+                            aliased.insert(*value);
+                        }
+                    }
+                    instruction => {
+                        aliased.extend(
+                            instruction
+                                .operands()
+                                .into_iter()
+                                .filter(|value| slots.contains(value)),
+                        );
+                    }
+                }
+            }
+        }
+
+        stores
+            .into_iter()
+            .filter(|(slot, values)| values.len() == 1 && !aliased.contains(slot))
+            .map(|(slot, values)| (slot, values[0]))
+            .collect()
+    }
+}
+
+impl OptimizationPass for ConstantPropagation {
+    fn optimize(&mut self, module: &mut IrModule) -> bool {
+        let mut changed = false;
+        self.propagated_count = 0;
+
+        let func_ids: Vec<_> = module.functions().keys().copied().collect();
+        for func_id in func_ids {
+            if let Some(function) = module.get_function_mut(func_id) {
+                changed |= self.optimize_function(function);
+            }
+        }
+
+        changed
+    }
+
+    fn name(&self) -> &'static str {
+        "constant-propagation"
+    }
+}
+
+impl Default for ConstantPropagation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BinaryOp, IrBuilder};
+    use crate::types::Type;
+
+    fn instructions(module: &IrModule, func_id: crate::ir::FunctionId) -> Vec<Instruction> {
+        let func = module.get_function(func_id).unwrap();
+        func.blocks_in_order()
+            .into_iter()
+            .flat_map(|block| block.instructions.iter())
+            .map(|(_, inst)| inst.instruction.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_propagates_single_store_slot() {
+        let mut builder = IrBuilder::new();
+        let func_id = builder.create_function("test".to_string(), vec![], Type::I32);
+
+        // let x = 2; return x + x
+        let slot = builder.build_alloc(Type::I32).unwrap();
+        let two = builder.const_value(Constant::I32(2));
+        builder.build_store(slot, two);
+        let x = builder.build_load(slot, Type::I32).unwrap();
+        let sum = builder
+            .build_binary(BinaryOp::Add, x, x, Type::I32)
+            .unwrap();
+        builder.build_return(Some(sum));
+
+        let mut module = builder.build();
+        let mut pass = ConstantPropagation::new();
+        assert!(pass.optimize(&mut module));
+        assert_eq!(pass.propagated_count(), 1);
+
+        let has_load = instructions(&module, func_id)
+            .iter()
+            .any(|inst| matches!(inst, Instruction::Load { .. }));
+        assert!(!has_load);
+    }
+
+    #[test]
+    fn test_reassigned_slot_is_not_propagated() {
+        let mut builder = IrBuilder::new();
+        let func_id = builder.create_function("test".to_string(), vec![], Type::I32);
+
+        // let mut x = 1; x = 2; return x
+        let slot = builder.build_alloc(Type::I32).unwrap();
+        let one = builder.const_value(Constant::I32(1));
+        builder.build_store(slot, one);
+        let two = builder.const_value(Constant::I32(2));
+        builder.build_store(slot, two);
+        let x = builder.build_load(slot, Type::I32).unwrap();
+        builder.build_return(Some(x));
+
+        let mut module = builder.build();
+        assert!(!ConstantPropagation::new().optimize(&mut module));
+
+        let has_load = instructions(&module, func_id)
+            .iter()
+            .any(|inst| matches!(inst, Instruction::Load { .. }));
+        assert!(has_load);
+    }
+
+    #[test]
+    fn test_propagates_phi_of_equal_constants() {
+        let mut builder = IrBuilder::new();
+        let func_id = builder.create_function("test".to_string(), vec![], Type::I32);
+
+        let then_block = builder.create_block("then".to_string()).unwrap();
+        let else_block = builder.create_block("else".to_string()).unwrap();
+        let merge = builder.create_block("merge".to_string()).unwrap();
+
+        let cond = builder.const_value(Constant::Bool(true));
+        builder.build_cond_branch(cond, then_block, else_block);
+
+        builder.set_current_block(then_block);
+        let a = builder.const_value(Constant::I32(7));
+        builder.build_branch(merge);
+
+        builder.set_current_block(else_block);
+        let b = builder.const_value(Constant::I32(7));
+        builder.build_branch(merge);
+
+        builder.set_current_block(merge);
+        let phi = builder
+            .add_instruction(Instruction::Phi {
+                incoming: vec![(a, then_block), (b, else_block)],
+                ty: Type::I32,
+            })
+            .unwrap();
+        builder.build_return(Some(phi));
+
+        let mut module = builder.build();
+        assert!(ConstantPropagation::new().optimize(&mut module));
+
+        let has_phi = instructions(&module, func_id)
+            .iter()
+            .any(|inst| matches!(inst, Instruction::Phi { .. }));
+        assert!(!has_phi);
+    }
+}
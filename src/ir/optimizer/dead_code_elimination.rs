@@ -29,6 +29,21 @@ impl DeadCodeElimination {
         }
     }
 
+    /// Number of blocks removed by the last run
+    pub fn blocks_removed(&self) -> usize {
+        self.blocks_removed
+    }
+
+    /// Number of instructions removed by the last run
+    pub fn instructions_removed(&self) -> usize {
+        self.instructions_removed
+    }
+
+    /// Number of conditional branches made unconditional by the last run
+    pub fn branches_simplified(&self) -> usize {
+        self.branches_simplified
+    }
+
     /// Find all reachable blocks in a function
     fn find_reachable_blocks(function: &Function) -> HashSet<BlockId> {
         let mut reachable = HashSet::new();
@@ -56,80 +71,12 @@ impl DeadCodeElimination {
 
     /// Find all used values in a function
     fn find_used_values(function: &Function) -> HashSet<ValueId> {
-        let mut used = HashSet::new();
-
-        for block in function.blocks().values() {
-            for (_, inst_with_loc) in &block.instructions {
-                let inst = &inst_with_loc.instruction;
-                match inst {
-                    Instruction::Binary { lhs, rhs, .. } => {
-                        used.insert(*lhs);
-                        used.insert(*rhs);
-                    }
-                    Instruction::Unary { operand, .. } => {
-                        used.insert(*operand);
-                    }
-                    Instruction::Compare { lhs, rhs, .. } => {
-                        used.insert(*lhs);
-                        used.insert(*rhs);
-                    }
-                    Instruction::Cast { value, .. } => {
-                        used.insert(*value);
-                    }
-                    Instruction::Call { args, .. } => {
-                        for arg in args {
-                            used.insert(*arg);
-                        }
-                    }
-                    Instruction::Load { ptr, .. } => {
-                        used.insert(*ptr);
-                    }
-                    Instruction::Store { ptr, value } => {
-                        used.insert(*ptr);
-                        used.insert(*value);
-                    }
-                    Instruction::GetElementPtr { ptr, index, .. } => {
-                        used.insert(*ptr);
-                        used.insert(*index);
-                    }
-                    Instruction::GetFieldPtr { object, .. } => {
-                        used.insert(*object);
-                    }
-                    Instruction::LoadField { object, .. } => {
-                        used.insert(*object);
-                    }
-                    Instruction::StoreField { object, value, .. } => {
-                        used.insert(*object);
-                        used.insert(*value);
-                    }
-                    Instruction::Phi { incoming, .. } => {
-                        for (value, _) in incoming {
-                            used.insert(*value);
-                        }
-                    }
-                    Instruction::Return(Some(value)) => {
-                        used.insert(*value);
-                    }
-                    Instruction::CondBranch { condition, .. } => {
-                        used.insert(*condition);
-                    }
-                    Instruction::CreateClosure { captured_vars, .. } => {
-                        for (_, value) in captured_vars {
-                            used.insert(*value);
-                        }
-                    }
-                    Instruction::InvokeClosure { closure, args, .. } => {
-                        used.insert(*closure);
-                        for arg in args {
-                            used.insert(*arg);
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-
-        used
+        function
+            .blocks()
+            .values()
+            .flat_map(|block| &block.instructions)
+            .flat_map(|(_, inst_with_loc)| inst_with_loc.instruction.operands())
+            .collect()
     }
 
     /// Check if an instruction has side effects
@@ -177,7 +124,7 @@ impl DeadCodeElimination {
             Instruction::SetAsyncState { .. } => true, // Setting state has side effects
 
             // Security operations
-            Instruction::BoundsCheck { .. } => false, // Bounds checking has no side effects (just returns bool)
+            Instruction::BoundsCheck { .. } => true, // Bounds checking traps on out-of-range indices
             Instruction::ValidateFieldAccess { .. } => false, // Field validation has no side effects
 
             // Error handling
@@ -186,15 +133,54 @@ impl DeadCodeElimination {
             // Closure operations
             Instruction::CreateClosure { .. } => false, // Creating a closure has no side effects
             Instruction::InvokeClosure { .. } => true, // Invoking a closure has side effects (function call)
+            Instruction::LoadCapture { .. } => false, // Reading the environment has no side effects
+
+            // Instrumentation
+            Instruction::Probe(_) => true, // Probes call into the runtime
+
+            // Atomics and fences touch shared memory
+            Instruction::Intrinsic { intrinsic, .. } => intrinsic.has_side_effects(),
         }
     }
 
     /// Remove unreachable blocks from a function
-    fn remove_unreachable_blocks(&mut self, _function: &mut Function) -> bool {
-        // For now, we can't actually remove blocks because the blocks field is private
-        // We'll mark this as a TODO and just return false
-        // TODO: Add a public API to remove blocks from Function
-        false
+    fn remove_unreachable_blocks(&mut self, function: &mut Function) -> bool {
+        let reachable = Self::find_reachable_blocks(function);
+        let unreachable: Vec<BlockId> = function
+            .blocks()
+            .keys()
+            .filter(|block_id| !reachable.contains(block_id))
+            .copied()
+            .collect();
+
+        for block_id in &unreachable {
+            if function.remove_block(*block_id).is_some() {
+                self.blocks_removed += 1;
+            }
+        }
+
+        if unreachable.is_empty() {
+            return false;
+        }
+
+        function.update_predecessors();
+        Self::prune_phi_incoming(function);
+        true
+    }
+
+    /// Drop phi incoming values from blocks that are no longer predecessors
+    fn prune_phi_incoming(function: &mut Function) {
+        let block_ids: Vec<BlockId> = function.blocks().keys().copied().collect();
+        for block_id in block_ids {
+            if let Some(block) = function.get_block_mut(block_id) {
+                let predecessors = block.predecessors.clone();
+                for (_, inst_with_loc) in &mut block.instructions {
+                    if let Instruction::Phi { incoming, .. } = &mut inst_with_loc.instruction {
+                        incoming.retain(|(_, pred)| predecessors.contains(pred));
+                    }
+                }
+            }
+        }
     }
 
     /// Remove dead instructions (whose results are never used)
@@ -277,6 +263,11 @@ impl DeadCodeElimination {
             }
         }
 
+        if changed {
+            function.update_predecessors();
+            Self::prune_phi_incoming(function);
+        }
+
         changed
     }
 
@@ -298,6 +289,16 @@ impl DeadCodeElimination {
         None
     }
 
+    /// Check if a block starts with phi nodes
+    fn has_phis(function: &Function, block_id: BlockId) -> bool {
+        function.get_block(block_id).map_or(false, |block| {
+            block
+                .instructions
+                .iter()
+                .any(|(_, inst)| matches!(inst.instruction, Instruction::Phi { .. }))
+        })
+    }
+
     /// Remove empty blocks that just jump to another block
     fn remove_empty_blocks(&mut self, function: &mut Function) -> bool {
         let mut changed = false;
@@ -317,8 +318,9 @@ impl DeadCodeElimination {
                 if block.instructions.len() == 1 {
                     if let Some((_, inst_with_loc)) = block.instructions.first() {
                         if let Instruction::Branch(target) = &inst_with_loc.instruction {
-                            if block_id != *target {
-                                // Avoid self-loops
+                            // Avoid self-loops, and keep the block when the
+                            // target's phis tell its predecessors apart
+                            if block_id != *target && !Self::has_phis(function, *target) {
                                 block_redirects.insert(block_id, *target);
                             }
                         }
@@ -329,7 +331,6 @@ impl DeadCodeElimination {
 
         // Apply redirects
         if !block_redirects.is_empty() {
-            changed = true;
             let block_ids: Vec<BlockId> = function.blocks().keys().cloned().collect();
 
             // Update all branches to point to the final destination
@@ -343,6 +344,7 @@ impl DeadCodeElimination {
                                     *target = new_target;
                                     block.successors.clear();
                                     block.successors.push(new_target);
+                                    changed = true;
                                 }
                             }
                             Instruction::CondBranch {
@@ -366,36 +368,20 @@ impl DeadCodeElimination {
                                     block.successors.clear();
                                     block.successors.push(*then_block);
                                     block.successors.push(*else_block);
+                                    changed = true;
                                 }
                             }
                             _ => {}
                         }
                     }
-
-                    // Update phi nodes
-                    let mut new_instructions = Vec::new();
-                    for (value_id, inst_with_loc) in &block.instructions {
-                        let mut inst_with_loc = inst_with_loc.clone();
-
-                        if let Instruction::Phi { incoming, .. } = &mut inst_with_loc.instruction {
-                            for (_, pred_block) in incoming.iter_mut() {
-                                if let Some(&new_pred) = block_redirects.get(pred_block) {
-                                    *pred_block = new_pred;
-                                }
-                            }
-                        }
-
-                        new_instructions.push((*value_id, inst_with_loc));
-                    }
-                    block.instructions = new_instructions;
                 }
             }
 
-            // Since we can't remove blocks, just count them
-            self.blocks_removed += block_redirects.len();
-
-            // Update predecessors
-            function.update_predecessors();
+            // The bypassed blocks are now unreachable and get removed on the
+            // next iteration
+            if changed {
+                function.update_predecessors();
+            }
         }
 
         changed
@@ -449,13 +435,6 @@ impl OptimizationPass for DeadCodeElimination {
             }
         }
 
-        if changed {
-            eprintln!(
-                "Dead code elimination: removed {} blocks, {} instructions, simplified {} branches",
-                self.blocks_removed, self.instructions_removed, self.branches_simplified
-            );
-        }
-
         changed
     }
 
@@ -481,7 +460,7 @@ mod tests {
         let mut builder = IrBuilder::new();
 
         // Create a function with unreachable blocks
-        let _func_id = builder.create_function("test".to_string(), vec![], Type::I32);
+        let func_id = builder.create_function("test".to_string(), vec![], Type::I32);
 
         let entry = builder.get_current_block().unwrap();
         let reachable = builder.create_block("reachable".to_string()).unwrap();
@@ -502,10 +481,11 @@ mod tests {
         let mut module = builder.build();
 
         let mut pass = DeadCodeElimination::new();
-        // Since we can't remove blocks yet, this test just verifies the pass runs
-        pass.optimize(&mut module);
+        assert!(pass.optimize(&mut module));
 
-        // TODO: Once block removal is implemented, verify unreachable blocks are removed
+        let func = module.get_function(func_id).unwrap();
+        assert!(func.get_block(unreachable).is_none());
+        assert!(func.get_block(entry).is_some());
     }
 
     #[test]
@@ -574,7 +554,7 @@ mod tests {
             }
         }
 
-        // TODO: Once block removal is implemented, verify else_block is removed
+        assert!(func.get_block(else_block).is_none());
     }
 
     #[test]
@@ -0,0 +1,439 @@
+//! Function Inlining Optimization Pass
+//!
+//! Replaces calls to small functions with a copy of the callee's body, so
+//! helpers written in Script do not pay for a call on every use. A function is
+//! inlined when its instruction count is within the threshold and the call
+//! graph built by [`DependencyAnalyzer`] shows it cannot reach itself.
+//!
+//! The block containing the call is split at the call: the copied blocks run
+//! in between, each `return` branches to the continuation block, and a phi at
+//! the top of the continuation takes over the call's value ID.
+
+use super::{first_free_value_id, OptimizationPass};
+use crate::compilation::{DependencyAnalyzer, DependencyGraph};
+use crate::ir::{BlockId, Function, FunctionId, Instruction, Module as IrModule, ValueId};
+use std::collections::HashMap;
+
+/// Default maximum number of instructions in an inlined function
+pub const DEFAULT_INLINE_THRESHOLD: usize = 30;
+
+/// Parameters are referenced as `ValueId(PARAM_VALUE_BASE + index)`, the
+/// numbering the lowerer gives them
+const PARAM_VALUE_BASE: u32 = 1000;
+
+/// Function inlining optimization pass
+#[derive(Debug)]
+pub struct FunctionInlining {
+    /// Maximum number of instructions in an inlined function
+    threshold: usize,
+    /// Number of call sites inlined
+    inlined_count: usize,
+    /// Next free value ID in the module being optimized
+    next_value_id: u32,
+}
+
+impl FunctionInlining {
+    /// Create an inlining pass with the default size threshold
+    pub fn new() -> Self {
+        Self::with_threshold(DEFAULT_INLINE_THRESHOLD)
+    }
+
+    /// Create an inlining pass that inlines functions of at most `threshold` instructions
+    pub fn with_threshold(threshold: usize) -> Self {
+        FunctionInlining {
+            threshold,
+            inlined_count: 0,
+            next_value_id: 0,
+        }
+    }
+
+    /// Get the maximum number of instructions in an inlined function
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Get the number of call sites inlined
+    pub fn inlined_count(&self) -> usize {
+        self.inlined_count
+    }
+
+    /// Allocate a value ID not used anywhere in the module
+    fn fresh_value(&mut self) -> ValueId {
+        let id = ValueId(self.next_value_id);
+        self.next_value_id += 1;
+        id
+    }
+
+    /// Collect copies of the functions that may be inlined
+    fn find_candidates(&self, module: &IrModule) -> HashMap<FunctionId, Function> {
+        let call_graph = DependencyAnalyzer::build_call_graph(module);
+        module
+            .functions()
+            .iter()
+            .filter(|(_, function)| self.is_inlinable(function, &call_graph))
+            .map(|(id, function)| (*id, function.clone()))
+            .collect()
+    }
+
+    /// Check whether a function is small, non-recursive and has a plain body
+    fn is_inlinable(&self, function: &Function, call_graph: &DependencyGraph) -> bool {
+        if function.entry_block.is_none() || function.is_async || function.trait_method.is_some() {
+            return false;
+        }
+
+        let mut size = 0;
+        for block in function.blocks().values() {
+            for (_, inst_with_loc) in &block.instructions {
+                size += 1;
+                if matches!(
+                    inst_with_loc.instruction,
+                    Instruction::Suspend { .. }
+                        | Instruction::PollFuture { .. }
+                        | Instruction::CreateAsyncState { .. }
+                        | Instruction::StoreAsyncState { .. }
+                        | Instruction::LoadAsyncState { .. }
+                        | Instruction::GetAsyncState { .. }
+                        | Instruction::SetAsyncState { .. }
+                ) {
+                    return false;
+                }
+            }
+        }
+
+        size <= self.threshold && !call_graph.is_in_cycle(&function.name)
+    }
+
+    /// Inline every call to a candidate in `caller`
+    fn inline_calls(
+        &mut self,
+        caller: &mut Function,
+        candidates: &HashMap<FunctionId, Function>,
+    ) -> usize {
+        let mut count = 0;
+        while let Some((block_id, index, callee)) = find_call_site(caller, candidates) {
+            self.inline_call(caller, block_id, index, &candidates[&callee]);
+            count += 1;
+        }
+        if count > 0 {
+            caller.update_predecessors();
+        }
+        count
+    }
+
+    /// Replace the call at `index` in `block_id` with a copy of `callee`
+    fn inline_call(
+        &mut self,
+        caller: &mut Function,
+        block_id: BlockId,
+        index: usize,
+        callee: &Function,
+    ) {
+        let Some(block) = caller.get_block_mut(block_id) else {
+            return;
+        };
+        let mut tail = block.instructions.split_off(index);
+        let (call_id, call) = tail.remove(0);
+        let (args, ty) = match call.instruction {
+            Instruction::Call { args, ty, .. } => (args, ty),
+            _ => return,
+        };
+        let old_successors = std::mem::take(&mut block.successors);
+
+        // Copy the callee's blocks, giving every block and value a new ID
+        let continuation = caller.create_block(format!("{}.return", callee.name));
+        let mut block_ids: Vec<_> = callee.blocks().keys().copied().collect();
+        block_ids.sort_by_key(|id| id.0);
+        let block_map: HashMap<BlockId, BlockId> = block_ids
+            .iter()
+            .map(|&id| {
+                let name = format!("{}.{}", callee.name, callee.blocks()[&id].name);
+                (id, caller.create_block(name))
+            })
+            .collect();
+
+        let mut value_map: HashMap<ValueId, ValueId> = args
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| (ValueId(PARAM_VALUE_BASE + i as u32), *arg))
+            .collect();
+        for block in callee.blocks().values() {
+            for (value_id, _) in &block.instructions {
+                let fresh = self.fresh_value();
+                value_map.insert(*value_id, fresh);
+            }
+        }
+
+        let mut returned = Vec::new();
+        for id in &block_ids {
+            let new_block = block_map[id];
+            for (value_id, inst_with_loc) in &callee.blocks()[id].instructions {
+                let mut inst_with_loc = inst_with_loc.clone();
+                let instruction = &mut inst_with_loc.instruction;
+                if let Instruction::Return(value) = instruction {
+                    if let Some(value) = value {
+                        returned.push((value_map.get(value).copied().unwrap_or(*value), new_block));
+                    }
+                    *instruction = Instruction::Branch(continuation);
+                } else {
+                    for operand in instruction.operands_mut() {
+                        if let Some(mapped) = value_map.get(operand) {
+                            *operand = *mapped;
+                        }
+                    }
+                    remap_blocks(instruction, &block_map);
+                }
+
+                if let Some(block) = caller.get_block_mut(new_block) {
+                    block.add_instruction_with_location(value_map[value_id], inst_with_loc);
+                }
+            }
+        }
+
+        // Jump from the call site into the copied entry block
+        let branch_id = self.fresh_value();
+        let callee_entry = callee.entry_block.map(|entry| block_map[&entry]);
+        if let (Some(block), Some(entry)) = (caller.get_block_mut(block_id), callee_entry) {
+            block.add_instruction(branch_id, Instruction::Branch(entry));
+        }
+
+        // The continuation receives the returned value and the rest of the block
+        if let Some(block) = caller.get_block_mut(continuation) {
+            if !returned.is_empty() {
+                block.add_instruction(
+                    call_id,
+                    Instruction::Phi {
+                        incoming: returned,
+                        ty,
+                    },
+                );
+            }
+            for (value_id, inst_with_loc) in tail {
+                block.add_instruction_with_location(value_id, inst_with_loc);
+            }
+            if !block.has_terminator() {
+                block.successors = old_successors.clone();
+            }
+        }
+
+        // Successors are now entered from the continuation block
+        for successor in old_successors {
+            if let Some(block) = caller.get_block_mut(successor) {
+                for (_, inst_with_loc) in &mut block.instructions {
+                    if let Instruction::Phi { incoming, .. } = &mut inst_with_loc.instruction {
+                        for (_, pred) in incoming.iter_mut() {
+                            if *pred == block_id {
+                                *pred = continuation;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Find the first call to a candidate, as (block, instruction index, callee)
+fn find_call_site(
+    function: &Function,
+    candidates: &HashMap<FunctionId, Function>,
+) -> Option<(BlockId, usize, FunctionId)> {
+    let mut block_ids: Vec<_> = function.blocks().keys().copied().collect();
+    block_ids.sort_by_key(|id| id.0);
+
+    for block_id in block_ids {
+        let block = &function.blocks()[&block_id];
+        for (index, (_, inst_with_loc)) in block.instructions.iter().enumerate() {
+            if let Instruction::Call { func, .. } = &inst_with_loc.instruction {
+                if candidates.contains_key(func) && *func != function.id {
+                    return Some((block_id, index, *func));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Point the block operands of a copied instruction at the copied blocks
+fn remap_blocks(instruction: &mut Instruction, block_map: &HashMap<BlockId, BlockId>) {
+    let remap = |block: &mut BlockId| {
+        if let Some(mapped) = block_map.get(block) {
+            *block = *mapped;
+        }
+    };
+
+    match instruction {
+        Instruction::Branch(target) => remap(target),
+        Instruction::CondBranch {
+            then_block,
+            else_block,
+            ..
+        } => {
+            remap(then_block);
+            remap(else_block);
+        }
+        Instruction::Phi { incoming, .. } => {
+            for (_, block) in incoming.iter_mut() {
+                remap(block);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl OptimizationPass for FunctionInlining {
+    fn optimize(&mut self, module: &mut IrModule) -> bool {
+        self.inlined_count = 0;
+        self.next_value_id = first_free_value_id(module);
+
+        let candidates = self.find_candidates(module);
+        if candidates.is_empty() {
+            return false;
+        }
+
+        let func_ids: Vec<_> = module.functions().keys().copied().collect();
+        for func_id in func_ids {
+            if let Some(function) = module.get_function_mut(func_id) {
+                self.inlined_count += self.inline_calls(function, &candidates);
+            }
+        }
+
+        self.inlined_count > 0
+    }
+
+    fn name(&self) -> &'static str {
+        "inlining"
+    }
+}
+
+impl Default for FunctionInlining {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BinaryOp, Constant, IrBuilder, Parameter};
+    use crate::types::Type;
+
+    fn calls(module: &IrModule, func_id: FunctionId) -> usize {
+        module
+            .get_function(func_id)
+            .unwrap()
+            .blocks()
+            .values()
+            .flat_map(|block| block.instructions.iter())
+            .filter(|(_, inst)| matches!(inst.instruction, Instruction::Call { .. }))
+            .count()
+    }
+
+    fn param(name: &str) -> Parameter {
+        Parameter {
+            name: name.to_string(),
+            ty: Type::I32,
+        }
+    }
+
+    #[test]
+    fn test_inlines_small_function() {
+        let mut builder = IrBuilder::new();
+
+        // fn double(x: i32) -> i32 { return x + x }
+        let double = builder.create_function("double".to_string(), vec![param("x")], Type::I32);
+        let x = ValueId(PARAM_VALUE_BASE);
+        let sum = builder
+            .build_binary(BinaryOp::Add, x, x, Type::I32)
+            .unwrap();
+        builder.build_return(Some(sum));
+
+        // fn main() -> i32 { return double(21) + 1 }
+        let main = builder.create_function("main".to_string(), vec![], Type::I32);
+        let arg = builder.const_value(Constant::I32(21));
+        let result = builder.build_call(double, vec![arg], Type::I32).unwrap();
+        let one = builder.const_value(Constant::I32(1));
+        let total = builder
+            .build_binary(BinaryOp::Add, result, one, Type::I32)
+            .unwrap();
+        builder.build_return(Some(total));
+
+        let mut module = builder.build();
+        let mut pass = FunctionInlining::new();
+        assert!(pass.optimize(&mut module));
+        assert_eq!(pass.inlined_count(), 1);
+        assert_eq!(calls(&module, main), 0);
+
+        let func = module.get_function(main).unwrap();
+        let insts: Vec<_> = func
+            .blocks()
+            .values()
+            .flat_map(|block| block.instructions.iter())
+            .map(|(id, inst)| (*id, inst.instruction.clone()))
+            .collect();
+
+        // The copied body reads the argument, and the call's value is now a phi
+        assert!(insts.iter().any(|(_, inst)| matches!(
+            inst,
+            Instruction::Binary { lhs, rhs, .. } if *lhs == arg && *rhs == arg
+        )));
+        assert!(insts
+            .iter()
+            .any(|(id, inst)| *id == result && matches!(inst, Instruction::Phi { .. })));
+        assert!(insts.contains(&(
+            total,
+            Instruction::Binary {
+                op: BinaryOp::Add,
+                lhs: result,
+                rhs: one,
+                ty: Type::I32,
+            }
+        )));
+    }
+
+    #[test]
+    fn test_recursive_function_is_not_inlined() {
+        let mut builder = IrBuilder::new();
+
+        let countdown =
+            builder.create_function("countdown".to_string(), vec![param("n")], Type::I32);
+        let result = builder
+            .build_call(countdown, vec![ValueId(PARAM_VALUE_BASE)], Type::I32)
+            .unwrap();
+        builder.build_return(Some(result));
+
+        let main = builder.create_function("main".to_string(), vec![], Type::I32);
+        let arg = builder.const_value(Constant::I32(3));
+        let result = builder.build_call(countdown, vec![arg], Type::I32).unwrap();
+        builder.build_return(Some(result));
+
+        let mut module = builder.build();
+        assert!(!FunctionInlining::new().optimize(&mut module));
+        assert_eq!(calls(&module, main), 1);
+    }
+
+    #[test]
+    fn test_threshold_limits_inlining() {
+        let mut builder = IrBuilder::new();
+
+        let helper = builder.create_function("helper".to_string(), vec![], Type::I32);
+        let mut value = builder.const_value(Constant::I32(1));
+        for _ in 0..5 {
+            value = builder
+                .build_binary(BinaryOp::Mul, value, value, Type::I32)
+                .unwrap();
+        }
+        builder.build_return(Some(value));
+
+        let main = builder.create_function("main".to_string(), vec![], Type::I32);
+        let result = builder.build_call(helper, vec![], Type::I32).unwrap();
+        builder.build_return(Some(result));
+
+        let mut module = builder.build();
+        assert!(!FunctionInlining::with_threshold(4).optimize(&mut module));
+        assert_eq!(calls(&module, main), 1);
+
+        assert!(FunctionInlining::with_threshold(7).optimize(&mut module));
+        assert_eq!(calls(&module, main), 0);
+    }
+}
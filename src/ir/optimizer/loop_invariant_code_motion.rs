@@ -281,6 +281,20 @@ impl LoopInvariantCodeMotion {
             // Closure operations
             Instruction::CreateClosure { .. } => false, // Closure creation has side effects
             Instruction::InvokeClosure { .. } => false, // Closure invocation has side effects
+            Instruction::LoadCapture { environment, .. } => {
+                self.is_value_loop_invariant(*environment, defined_in_loop, current_invariants)
+            }
+
+            // Probes run on every iteration
+            Instruction::Probe(_) => false,
+
+            // Atomics see other threads' writes on every iteration
+            Instruction::Intrinsic { intrinsic, args, .. } => {
+                !intrinsic.has_side_effects()
+                    && args.iter().all(|arg| {
+                        self.is_value_loop_invariant(*arg, defined_in_loop, current_invariants)
+                    })
+            }
         }
     }
 
@@ -0,0 +1,624 @@
+//! Memory to Register Promotion (mem2reg)
+//!
+//! The lowerer gives every local variable and parameter its own stack slot
+//! and reads and writes it through loads and stores. This pass rewrites those
+//! slots into SSA values: phi nodes are placed on the iterated dominance
+//! frontier of the blocks that store to a slot, and a walk over the dominator
+//! tree replaces each load with the value that reaches it.
+//!
+//! Only slots of scalar type whose address is used exclusively by loads and
+//! stores are promoted. Anything else may be aliased and stays in memory.
+
+use super::analysis::dominance::{DominanceAnalysis, DominanceInfo};
+use super::{first_free_value_id, OptimizationPass};
+use crate::ir::{
+    BlockId, Constant, Function, Instruction, InstructionWithLocation, Module as IrModule, ValueId,
+};
+use crate::types::Type;
+use std::collections::{HashMap, HashSet};
+
+/// Memory to register promotion pass
+#[derive(Debug)]
+pub struct Mem2Reg {
+    /// Number of stack slots promoted to registers
+    promoted_count: usize,
+    /// Number of phi nodes inserted
+    phis_inserted: usize,
+    /// Next free value ID in the module being optimized
+    next_value_id: u32,
+}
+
+/// A stack slot selected for promotion
+struct PromotedSlot {
+    /// Type of the value held in the slot
+    ty: Type,
+    /// Blocks that store to the slot
+    def_blocks: HashSet<BlockId>,
+}
+
+/// State for renaming loads and stores during the dominator tree walk
+struct Renamer<'a> {
+    slots: &'a HashMap<ValueId, PromotedSlot>,
+    /// Phi nodes placed for each slot, keyed by phi value
+    phi_slots: &'a HashMap<ValueId, ValueId>,
+    /// Value currently held by each slot
+    stacks: HashMap<ValueId, Vec<ValueId>>,
+    /// Initial value of each slot on paths that never store to it
+    undefined: &'a HashMap<ValueId, ValueId>,
+    /// Loads replaced by the value they would have read
+    replacements: HashMap<ValueId, ValueId>,
+    /// Incoming edges collected for each phi
+    incoming: HashMap<ValueId, Vec<(ValueId, BlockId)>>,
+}
+
+impl Mem2Reg {
+    /// Create a new mem2reg pass
+    pub fn new() -> Self {
+        Mem2Reg {
+            promoted_count: 0,
+            phis_inserted: 0,
+            next_value_id: 0,
+        }
+    }
+
+    /// Get the number of stack slots promoted to registers
+    pub fn promoted_count(&self) -> usize {
+        self.promoted_count
+    }
+
+    /// Get the number of phi nodes inserted
+    pub fn phis_inserted(&self) -> usize {
+        self.phis_inserted
+    }
+
+    /// Allocate a value ID not used anywhere in the module
+    fn fresh_value(&mut self) -> ValueId {
+        let id = ValueId(self.next_value_id);
+        self.next_value_id += 1;
+        id
+    }
+
+    /// Optimize a single function
+    fn optimize_function(&mut self, function: &mut Function) -> bool {
+        let entry = match function.entry_block {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        function.update_predecessors();
+        let reachable = Self::reachable_blocks(function, entry);
+        let slots = Self::find_promotable_slots(function, &reachable);
+        if slots.is_empty() {
+            return false;
+        }
+
+        let dominance = DominanceAnalysis::without_post_dominance().analyze(function);
+
+        // Place phi nodes on the iterated dominance frontier of each slot's stores
+        let mut phi_slots = HashMap::new();
+        let mut block_phis: HashMap<BlockId, Vec<ValueId>> = HashMap::new();
+        for (&slot, info) in &slots {
+            for block in Self::phi_blocks(&dominance, &info.def_blocks, &reachable) {
+                let phi = self.fresh_value();
+                phi_slots.insert(phi, slot);
+                block_phis.entry(block).or_default().push(phi);
+            }
+        }
+
+        // Paths that load before any store read the type's zero value
+        let mut undefined = HashMap::new();
+        let mut zero_values: HashMap<Type, ValueId> = HashMap::new();
+        let mut entry_constants = Vec::new();
+        for (&slot, info) in &slots {
+            let value = match zero_values.get(&info.ty) {
+                Some(&value) => value,
+                None => {
+                    let value = self.fresh_value();
+                    zero_values.insert(info.ty.clone(), value);
+                    entry_constants.push((value, zero_constant(&info.ty)));
+                    value
+                }
+            };
+            undefined.insert(slot, value);
+        }
+
+        let mut renamer = Renamer {
+            slots: &slots,
+            phi_slots: &phi_slots,
+            stacks: HashMap::new(),
+            undefined: &undefined,
+            replacements: HashMap::new(),
+            incoming: HashMap::new(),
+        };
+        renamer.rename_block(function, &dominance, &block_phis, entry);
+        let Renamer {
+            replacements,
+            mut incoming,
+            ..
+        } = renamer;
+
+        // Drop the promoted slots and their loads and stores, then point
+        // every remaining use of a load at the value it was replaced with
+        let block_ids: Vec<_> = function.blocks().keys().copied().collect();
+        for block_id in block_ids {
+            let Some(block) = function.get_block_mut(block_id) else {
+                continue;
+            };
+
+            block.instructions.retain(|(value_id, inst_with_loc)| {
+                match &inst_with_loc.instruction {
+                    Instruction::Alloc { .. } => !slots.contains_key(value_id),
+                    Instruction::Load { ptr, .. } | Instruction::Store { ptr, .. } => {
+                        !slots.contains_key(ptr)
+                    }
+                    _ => true,
+                }
+            });
+
+            for (_, inst_with_loc) in &mut block.instructions {
+                for operand in inst_with_loc.instruction.operands_mut() {
+                    *operand = resolve(&replacements, *operand);
+                }
+            }
+
+            if let Some(phis) = block_phis.get(&block_id) {
+                let mut nodes = Vec::with_capacity(phis.len());
+                for &phi in phis {
+                    let slot = phi_slots[&phi];
+                    let mut incoming: Vec<_> = incoming
+                        .remove(&phi)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|(value, pred)| (resolve(&replacements, value), pred))
+                        .collect();
+                    // Predecessors the walk never reached still jump here
+                    for &pred in &block.predecessors {
+                        if !incoming.iter().any(|(_, block)| *block == pred) {
+                            incoming.push((undefined[&slot], pred));
+                        }
+                    }
+                    let ty = slots[&slot].ty.clone();
+                    nodes.push((
+                        phi,
+                        InstructionWithLocation::new(Instruction::Phi { incoming, ty }),
+                    ));
+                }
+                block.instructions.splice(0..0, nodes);
+            }
+        }
+
+        if let Some(block) = function.get_block_mut(entry) {
+            let constants = entry_constants.into_iter().map(|(value, constant)| {
+                (
+                    value,
+                    InstructionWithLocation::new(Instruction::Const(constant)),
+                )
+            });
+            block.instructions.splice(0..0, constants);
+        }
+
+        self.promoted_count += slots.len();
+        self.phis_inserted += phi_slots.len();
+        true
+    }
+
+    /// Find the blocks reachable from the entry block
+    fn reachable_blocks(function: &Function, entry: BlockId) -> HashSet<BlockId> {
+        let mut reachable = HashSet::new();
+        let mut worklist = vec![entry];
+        while let Some(block_id) = worklist.pop() {
+            if !reachable.insert(block_id) {
+                continue;
+            }
+            if let Some(block) = function.get_block(block_id) {
+                worklist.extend(block.successors.iter().copied());
+            }
+        }
+        reachable
+    }
+
+    /// Find stack slots that are only ever loaded from and stored to
+    fn find_promotable_slots(
+        function: &Function,
+        reachable: &HashSet<BlockId>,
+    ) -> HashMap<ValueId, PromotedSlot> {
+        let mut slots = HashMap::new();
+        for block in function.blocks().values() {
+            for (value_id, inst_with_loc) in &block.instructions {
+                if let Instruction::Alloc { ty } = &inst_with_loc.instruction {
+                    if is_promotable_type(ty) {
+                        slots.insert(
+                            *value_id,
+                            PromotedSlot {
+                                ty: ty.clone(),
+                                def_blocks: HashSet::new(),
+                            },
+                        );
+                    }
+                }
+            }
+        }
+
+        let mut rejected = HashSet::new();
+        for block in function.blocks().values() {
+            let is_reachable = reachable.contains(&block.id);
+            for (_, inst_with_loc) in &block.instructions {
+                match &inst_with_loc.instruction {
+                    Instruction::Load { ptr, ty } => {
+                        if let Some(slot) = slots.get(ptr) {
+                            if !is_reachable || *ty != slot.ty {
+                                rejected.insert(*ptr);
+                            }
+                        }
+                    }
+                    Instruction::Store { ptr, value } => {
+                        if slots.contains_key(value) {
+                            rejected.insert(*value);
+                        }
+                        if let Some(slot) = slots.get_mut(ptr) {
+                            if is_reachable {
+                                slot.def_blocks.insert(block.id);
+                            } else {
+                                rejected.insert(*ptr);
+                            }
+                        }
+                    }
+                    instruction => {
+                        rejected.extend(
+                            instruction
+                                .operands()
+                                .into_iter()
+                                .filter(|value| slots.contains_key(value)),
+                        );
+                    }
+                }
+            }
+        }
+
+        slots.retain(|slot, _| !rejected.contains(slot));
+        slots
+    }
+
+    /// Compute the blocks that need a phi node for a slot stored in `def_blocks`
+    fn phi_blocks(
+        dominance: &DominanceInfo,
+        def_blocks: &HashSet<BlockId>,
+        reachable: &HashSet<BlockId>,
+    ) -> Vec<BlockId> {
+        let mut placed = HashSet::new();
+        let mut result = Vec::new();
+        let mut worklist: Vec<_> = def_blocks.iter().copied().collect();
+
+        while let Some(block) = worklist.pop() {
+            for frontier in dominance.dominance_frontier(block) {
+                if reachable.contains(&frontier) && placed.insert(frontier) {
+                    result.push(frontier);
+                    if !def_blocks.contains(&frontier) {
+                        worklist.push(frontier);
+                    }
+                }
+            }
+        }
+
+        result.sort_by_key(|block| block.0);
+        result
+    }
+}
+
+impl Renamer<'_> {
+    /// Current value of a slot at this point in the walk
+    fn current_value(&self, slot: ValueId) -> ValueId {
+        self.stacks
+            .get(&slot)
+            .and_then(|stack| stack.last())
+            .copied()
+            .unwrap_or(self.undefined[&slot])
+    }
+
+    /// Rename loads and stores in a block and its dominator tree children
+    fn rename_block(
+        &mut self,
+        function: &Function,
+        dominance: &DominanceInfo,
+        block_phis: &HashMap<BlockId, Vec<ValueId>>,
+        block_id: BlockId,
+    ) {
+        let Some(block) = function.get_block(block_id) else {
+            return;
+        };
+
+        let mut pushed = Vec::new();
+        for &phi in block_phis.get(&block_id).into_iter().flatten() {
+            let slot = self.phi_slots[&phi];
+            self.stacks.entry(slot).or_default().push(phi);
+            pushed.push(slot);
+        }
+
+        for (value_id, inst_with_loc) in &block.instructions {
+            match &inst_with_loc.instruction {
+                Instruction::Load { ptr, .. } if self.slots.contains_key(ptr) => {
+                    let value = self.current_value(*ptr);
+                    self.replacements.insert(*value_id, value);
+                }
+                Instruction::Store { ptr, value } if self.slots.contains_key(ptr) => {
+                    let value = resolve(&self.replacements, *value);
+                    self.stacks.entry(*ptr).or_default().push(value);
+                    pushed.push(*ptr);
+                }
+                _ => {}
+            }
+        }
+
+        for &successor in &block.successors {
+            for &phi in block_phis.get(&successor).into_iter().flatten() {
+                let value = self.current_value(self.phi_slots[&phi]);
+                let incoming = self.incoming.entry(phi).or_default();
+                if !incoming.iter().any(|(_, pred)| *pred == block_id) {
+                    incoming.push((value, block_id));
+                }
+            }
+        }
+
+        let mut children = dominance.dominator_tree_children(block_id);
+        children.sort_by_key(|child| child.0);
+        for child in children {
+            self.rename_block(function, dominance, block_phis, child);
+        }
+
+        for slot in pushed {
+            if let Some(stack) = self.stacks.get_mut(&slot) {
+                stack.pop();
+            }
+        }
+    }
+}
+
+/// Follow a chain of replaced loads to the value they stand for
+fn resolve(replacements: &HashMap<ValueId, ValueId>, mut value: ValueId) -> ValueId {
+    while let Some(&replacement) = replacements.get(&value) {
+        value = replacement;
+    }
+    value
+}
+
+/// Check whether values of a type can live in a register
+fn is_promotable_type(ty: &Type) -> bool {
+    matches!(
+        ty,
+        Type::I32 | Type::I64 | Type::U32 | Type::U64 | Type::F32 | Type::F64 | Type::Bool
+    )
+}
+
+/// Get the zero value of a promotable type
+fn zero_constant(ty: &Type) -> Constant {
+    match ty {
+        Type::I64 => Constant::I64(0),
+        Type::U32 => Constant::U32(0),
+        Type::U64 => Constant::U64(0),
+        Type::F32 => Constant::F32(0.0),
+        Type::F64 => Constant::F64(0.0),
+        Type::Bool => Constant::Bool(false),
+        _ => Constant::I32(0),
+    }
+}
+
+impl OptimizationPass for Mem2Reg {
+    fn optimize(&mut self, module: &mut IrModule) -> bool {
+        let mut changed = false;
+        self.promoted_count = 0;
+        self.phis_inserted = 0;
+        self.next_value_id = first_free_value_id(module);
+
+        let func_ids: Vec<_> = module.functions().keys().copied().collect();
+        for func_id in func_ids {
+            if let Some(function) = module.get_function_mut(func_id) {
+                changed |= self.optimize_function(function);
+            }
+        }
+
+        changed
+    }
+
+    fn name(&self) -> &'static str {
+        "mem2reg"
+    }
+}
+
+impl Default for Mem2Reg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BinaryOp, ComparisonOp, FunctionId, IrBuilder};
+
+    fn instructions(module: &IrModule, func_id: FunctionId) -> Vec<Instruction> {
+        let func = module.get_function(func_id).unwrap();
+        func.blocks_in_order()
+            .into_iter()
+            .flat_map(|block| block.instructions.iter())
+            .map(|(_, inst)| inst.instruction.clone())
+            .collect()
+    }
+
+    fn has_memory_ops(instructions: &[Instruction]) -> bool {
+        instructions.iter().any(|inst| {
+            matches!(
+                inst,
+                Instruction::Alloc { .. } | Instruction::Load { .. } | Instruction::Store { .. }
+            )
+        })
+    }
+
+    #[test]
+    fn test_promotes_straight_line_slot() {
+        let mut builder = IrBuilder::new();
+        let func_id = builder.create_function("test".to_string(), vec![], Type::I32);
+
+        // let mut x = 1; x = x + 2; return x
+        let slot = builder.build_alloc(Type::I32).unwrap();
+        let one = builder.const_value(Constant::I32(1));
+        builder.build_store(slot, one);
+        let x = builder.build_load(slot, Type::I32).unwrap();
+        let two = builder.const_value(Constant::I32(2));
+        let sum = builder
+            .build_binary(BinaryOp::Add, x, two, Type::I32)
+            .unwrap();
+        builder.build_store(slot, sum);
+        let result = builder.build_load(slot, Type::I32).unwrap();
+        builder.build_return(Some(result));
+
+        let mut module = builder.build();
+        let mut pass = Mem2Reg::new();
+        assert!(pass.optimize(&mut module));
+        assert_eq!(pass.promoted_count(), 1);
+        assert_eq!(pass.phis_inserted(), 0);
+
+        let insts = instructions(&module, func_id);
+        assert!(!has_memory_ops(&insts));
+        assert!(insts.contains(&Instruction::Binary {
+            op: BinaryOp::Add,
+            lhs: one,
+            rhs: two,
+            ty: Type::I32,
+        }));
+        assert!(insts.contains(&Instruction::Return(Some(sum))));
+    }
+
+    #[test]
+    fn test_inserts_phi_at_merge() {
+        let mut builder = IrBuilder::new();
+        let func_id = builder.create_function("test".to_string(), vec![], Type::I32);
+
+        let then_block = builder.create_block("then".to_string()).unwrap();
+        let else_block = builder.create_block("else".to_string()).unwrap();
+        let merge = builder.create_block("merge".to_string()).unwrap();
+
+        let slot = builder.build_alloc(Type::I32).unwrap();
+        let cond = builder.const_value(Constant::Bool(true));
+        builder.build_cond_branch(cond, then_block, else_block);
+
+        builder.set_current_block(then_block);
+        let a = builder.const_value(Constant::I32(1));
+        builder.build_store(slot, a);
+        builder.build_branch(merge);
+
+        builder.set_current_block(else_block);
+        let b = builder.const_value(Constant::I32(2));
+        builder.build_store(slot, b);
+        builder.build_branch(merge);
+
+        builder.set_current_block(merge);
+        let x = builder.build_load(slot, Type::I32).unwrap();
+        builder.build_return(Some(x));
+
+        let mut module = builder.build();
+        let mut pass = Mem2Reg::new();
+        assert!(pass.optimize(&mut module));
+        assert_eq!(pass.phis_inserted(), 1);
+
+        let func = module.get_function(func_id).unwrap();
+        let merge_block = func.get_block(merge).unwrap();
+        let (phi_id, phi) = &merge_block.instructions[0];
+        match &phi.instruction {
+            Instruction::Phi { incoming, ty } => {
+                assert_eq!(*ty, Type::I32);
+                assert_eq!(incoming.len(), 2);
+                assert!(incoming.contains(&(a, then_block)));
+                assert!(incoming.contains(&(b, else_block)));
+            }
+            other => panic!("expected a phi, got {:?}", other),
+        }
+        assert_eq!(
+            merge_block.terminator(),
+            Some(&Instruction::Return(Some(*phi_id)))
+        );
+        assert!(!has_memory_ops(&instructions(&module, func_id)));
+    }
+
+    #[test]
+    fn test_loop_counter_becomes_phi() {
+        let mut builder = IrBuilder::new();
+        let func_id = builder.create_function("test".to_string(), vec![], Type::I32);
+
+        let header = builder.create_block("header".to_string()).unwrap();
+        let body = builder.create_block("body".to_string()).unwrap();
+        let exit = builder.create_block("exit".to_string()).unwrap();
+
+        // let mut i = 0; while i < 10 { i = i + 1 }; return i
+        let slot = builder.build_alloc(Type::I32).unwrap();
+        let zero = builder.const_value(Constant::I32(0));
+        builder.build_store(slot, zero);
+        builder.build_branch(header);
+
+        builder.set_current_block(header);
+        let i = builder.build_load(slot, Type::I32).unwrap();
+        let ten = builder.const_value(Constant::I32(10));
+        let cond = builder.build_compare(ComparisonOp::Lt, i, ten).unwrap();
+        builder.build_cond_branch(cond, body, exit);
+
+        builder.set_current_block(body);
+        let current = builder.build_load(slot, Type::I32).unwrap();
+        let one = builder.const_value(Constant::I32(1));
+        let next = builder
+            .build_binary(BinaryOp::Add, current, one, Type::I32)
+            .unwrap();
+        builder.build_store(slot, next);
+        builder.build_branch(header);
+
+        builder.set_current_block(exit);
+        let result = builder.build_load(slot, Type::I32).unwrap();
+        builder.build_return(Some(result));
+
+        let mut module = builder.build();
+        assert!(Mem2Reg::new().optimize(&mut module));
+
+        let func = module.get_function(func_id).unwrap();
+        let header_block = func.get_block(header).unwrap();
+        let (phi_id, phi) = &header_block.instructions[0];
+        let entry = func.entry_block.unwrap();
+        match &phi.instruction {
+            Instruction::Phi { incoming, .. } => {
+                assert!(incoming.contains(&(zero, entry)));
+                assert!(incoming.contains(&(next, body)));
+            }
+            other => panic!("expected a phi, got {:?}", other),
+        }
+
+        let insts = instructions(&module, func_id);
+        assert!(!has_memory_ops(&insts));
+        assert!(insts.contains(&Instruction::Binary {
+            op: BinaryOp::Add,
+            lhs: *phi_id,
+            rhs: one,
+            ty: Type::I32,
+        }));
+        assert!(insts.contains(&Instruction::Return(Some(*phi_id))));
+    }
+
+    #[test]
+    fn test_aliased_slot_is_not_promoted() {
+        let mut builder = IrBuilder::new();
+        let func_id = builder.create_function("test".to_string(), vec![], Type::I32);
+
+        let slot = builder.build_alloc(Type::I32).unwrap();
+        let one = builder.const_value(Constant::I32(1));
+        builder.build_store(slot, one);
+        let holder = builder
+            .build_alloc(Type::Reference {
+                mutable: true,
+                inner: Box::new(Type::I32),
+            })
+            .unwrap();
+        builder.build_store(holder, slot);
+        let x = builder.build_load(slot, Type::I32).unwrap();
+        builder.build_return(Some(x));
+
+        let mut module = builder.build();
+        assert!(!Mem2Reg::new().optimize(&mut module));
+        assert!(has_memory_ops(&instructions(&module, func_id)));
+    }
+}
@@ -8,18 +8,24 @@ use crate::ir::Module as IrModule;
 pub mod analysis;
 pub mod common_subexpression_elimination;
 pub mod constant_folding;
+pub mod constant_propagation;
 pub mod dead_code_elimination;
+pub mod inlining;
 pub mod loop_analysis;
 pub mod loop_invariant_code_motion;
 pub mod loop_unrolling;
+pub mod mem2reg;
 
 pub use analysis::AnalysisManager;
 pub use common_subexpression_elimination::CommonSubexpressionElimination;
 pub use constant_folding::ConstantFolding;
+pub use constant_propagation::ConstantPropagation;
 pub use dead_code_elimination::DeadCodeElimination;
+pub use inlining::FunctionInlining;
 pub use loop_analysis::LoopAnalyzer;
 pub use loop_invariant_code_motion::LoopInvariantCodeMotion;
 pub use loop_unrolling::{LoopUnrolling, PartialLoopUnrolling};
+pub use mem2reg::Mem2Reg;
 
 /// Trait for optimization passes
 pub trait OptimizationPass {
@@ -31,8 +37,27 @@ pub trait OptimizationPass {
     fn name(&self) -> &'static str;
 }
 
-/// Optimizer that manages and runs optimization passes
-pub struct Optimizer {
+/// Find one past the largest value ID used in the module
+///
+/// Passes that create instructions allocate their value IDs from here, as
+/// the builder that numbered the module is gone by the time they run.
+pub(crate) fn first_free_value_id(module: &IrModule) -> u32 {
+    let mut max = None;
+    for function in module.functions().values() {
+        for block in function.blocks().values() {
+            for (value_id, inst_with_loc) in &block.instructions {
+                max = max.max(Some(value_id.0));
+                for operand in inst_with_loc.instruction.operands() {
+                    max = max.max(Some(operand.0));
+                }
+            }
+        }
+    }
+    max.map_or(0, |max| max + 1)
+}
+
+/// Pass manager that registers and runs optimization passes
+pub struct PassManager {
     /// List of optimization passes to run
     passes: Vec<Box<dyn OptimizationPass>>,
     /// Maximum number of iterations to run passes
@@ -41,32 +66,40 @@ pub struct Optimizer {
     debug: bool,
 }
 
-impl Optimizer {
-    /// Create a new optimizer with default settings
+impl PassManager {
+    /// Create a new pass manager with no passes registered
     pub fn new() -> Self {
-        Optimizer {
+        PassManager {
             passes: Vec::new(),
             max_iterations: 10,
             debug: false,
         }
     }
 
-    /// Create an optimizer with all standard optimization passes
+    /// Create a pass manager with all standard optimization passes
     pub fn with_standard_passes() -> Self {
-        let mut optimizer = Self::new();
-        optimizer.add_pass(Box::new(ConstantFolding::new()));
-        optimizer.add_pass(Box::new(CommonSubexpressionElimination::new()));
-        optimizer.add_pass(Box::new(LoopInvariantCodeMotion::new()));
-        optimizer.add_pass(Box::new(LoopUnrolling::new()));
-        optimizer.add_pass(Box::new(DeadCodeElimination::new())); // Run DCE last to clean up
-        optimizer
+        let mut manager = Self::new();
+        manager.register_pass(Box::new(FunctionInlining::new()));
+        manager.register_pass(Box::new(Mem2Reg::new())); // Promote locals, including inlined ones
+        manager.register_pass(Box::new(ConstantPropagation::new()));
+        manager.register_pass(Box::new(ConstantFolding::new()));
+        manager.register_pass(Box::new(CommonSubexpressionElimination::new()));
+        manager.register_pass(Box::new(LoopInvariantCodeMotion::new()));
+        manager.register_pass(Box::new(LoopUnrolling::new()));
+        manager.register_pass(Box::new(DeadCodeElimination::new())); // Run DCE last to clean up
+        manager
     }
 
-    /// Add an optimization pass
-    pub fn add_pass(&mut self, pass: Box<dyn OptimizationPass>) {
+    /// Register an optimization pass, to run after those already registered
+    pub fn register_pass(&mut self, pass: Box<dyn OptimizationPass>) {
         self.passes.push(pass);
     }
 
+    /// Get the names of the registered passes in run order
+    pub fn pass_names(&self) -> Vec<&'static str> {
+        self.passes.iter().map(|pass| pass.name()).collect()
+    }
+
     /// Set the maximum number of iterations
     pub fn set_max_iterations(&mut self, max: usize) {
         self.max_iterations = max;
@@ -121,16 +154,17 @@ impl Optimizer {
     }
 }
 
-impl Default for Optimizer {
+impl Default for PassManager {
     fn default() -> Self {
         Self::new()
     }
 }
 
 /// Optimization level configuration
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum OptimizationLevel {
     /// No optimizations
+    #[default]
     None,
     /// Basic optimizations (fast compilation)
     O1,
@@ -141,25 +175,60 @@ pub enum OptimizationLevel {
 }
 
 impl OptimizationLevel {
-    /// Create an optimizer configured for this optimization level
-    pub fn create_optimizer(self) -> Optimizer {
+    /// Parse a level as given on the command line (`-O0` through `-O3`)
+    pub fn parse(flag: &str) -> Option<Self> {
+        match flag {
+            "-O0" => Some(OptimizationLevel::None),
+            "-O1" => Some(OptimizationLevel::O1),
+            "-O2" => Some(OptimizationLevel::O2),
+            "-O3" => Some(OptimizationLevel::O3),
+            _ => None,
+        }
+    }
+
+    /// The level for a number, as in `opt-level = 2`
+    pub fn from_number(level: u8) -> Option<Self> {
+        match level {
+            0 => Some(OptimizationLevel::None),
+            1 => Some(OptimizationLevel::O1),
+            2 => Some(OptimizationLevel::O2),
+            3 => Some(OptimizationLevel::O3),
+            _ => None,
+        }
+    }
+
+    /// The level's number, 0 for no optimizations
+    pub fn number(self) -> u8 {
+        match self {
+            OptimizationLevel::None => 0,
+            OptimizationLevel::O1 => 1,
+            OptimizationLevel::O2 => 2,
+            OptimizationLevel::O3 => 3,
+        }
+    }
+
+    /// Create a pass manager configured for this optimization level
+    pub fn create_pass_manager(self) -> PassManager {
         match self {
-            OptimizationLevel::None => Optimizer::new(),
+            OptimizationLevel::None => PassManager::new(),
             OptimizationLevel::O1 => {
-                let mut opt = Optimizer::new();
-                opt.add_pass(Box::new(ConstantFolding::new()));
+                let mut opt = PassManager::new();
+                opt.register_pass(Box::new(Mem2Reg::new()));
+                opt.register_pass(Box::new(ConstantPropagation::new()));
+                opt.register_pass(Box::new(ConstantFolding::new()));
+                opt.register_pass(Box::new(DeadCodeElimination::new()));
                 opt.set_max_iterations(3);
                 opt
             }
             OptimizationLevel::O2 => {
-                let mut opt = Optimizer::with_standard_passes();
+                let mut opt = PassManager::with_standard_passes();
                 opt.set_max_iterations(5);
                 opt
             }
             OptimizationLevel::O3 => {
-                let mut opt = Optimizer::with_standard_passes();
+                let mut opt = PassManager::with_standard_passes();
                 // Add aggressive loop optimizations for O3
-                opt.add_pass(Box::new(PartialLoopUnrolling::new()));
+                opt.register_pass(Box::new(PartialLoopUnrolling::new()));
                 opt.set_max_iterations(10);
                 opt
             }
@@ -172,30 +241,90 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_optimizer_creation() {
-        let opt = Optimizer::new();
+    fn test_pass_manager_creation() {
+        let opt = PassManager::new();
         assert_eq!(opt.passes.len(), 0);
         assert_eq!(opt.max_iterations, 10);
 
-        let opt = Optimizer::with_standard_passes();
-        assert_eq!(opt.passes.len(), 5); // CF, CSE, LICM, unrolling, DCE
+        let opt = PassManager::with_standard_passes();
+        assert_eq!(opt.passes.len(), 8); // inlining, mem2reg, CP, CF, CSE, LICM, unrolling, DCE
     }
 
     #[test]
     fn test_optimization_levels() {
-        let opt = OptimizationLevel::None.create_optimizer();
+        let opt = OptimizationLevel::None.create_pass_manager();
         assert_eq!(opt.passes.len(), 0);
 
-        let opt = OptimizationLevel::O1.create_optimizer();
-        assert_eq!(opt.passes.len(), 1);
+        let opt = OptimizationLevel::O1.create_pass_manager();
+        assert_eq!(
+            opt.pass_names(),
+            vec![
+                "mem2reg",
+                "constant-propagation",
+                "constant-folding",
+                "DeadCodeElimination"
+            ]
+        );
         assert_eq!(opt.max_iterations, 3);
 
-        let opt = OptimizationLevel::O2.create_optimizer();
-        assert_eq!(opt.passes.len(), 5);
+        let opt = OptimizationLevel::O2.create_pass_manager();
+        assert_eq!(opt.passes.len(), 8);
         assert_eq!(opt.max_iterations, 5);
 
-        let opt = OptimizationLevel::O3.create_optimizer();
-        assert_eq!(opt.passes.len(), 6); // Standard passes + partial unrolling
+        let opt = OptimizationLevel::O3.create_pass_manager();
+        assert_eq!(opt.passes.len(), 9); // Standard passes + partial unrolling
         assert_eq!(opt.max_iterations, 10);
     }
+
+    #[test]
+    fn test_optimization_level_parse() {
+        assert_eq!(OptimizationLevel::parse("-O1"), Some(OptimizationLevel::O1));
+        assert_eq!(OptimizationLevel::parse("-O2"), Some(OptimizationLevel::O2));
+        assert_eq!(OptimizationLevel::parse("-O9"), None);
+    }
+
+    #[test]
+    fn test_o1_folds_locals_and_removes_dead_branch() {
+        use crate::ir::{BinaryOp, ComparisonOp, Constant, Instruction, IrBuilder};
+        use crate::types::Type;
+
+        let mut builder = IrBuilder::new();
+        let func_id = builder.create_function("main".to_string(), vec![], Type::I32);
+        let then_block = builder.create_block("then".to_string()).unwrap();
+        let else_block = builder.create_block("else".to_string()).unwrap();
+
+        // let x = 2 + 3; if x > 4 { return 1 } else { return 0 }
+        let slot = builder.build_alloc(Type::I32).unwrap();
+        let two = builder.const_value(Constant::I32(2));
+        let three = builder.const_value(Constant::I32(3));
+        let sum = builder
+            .build_binary(BinaryOp::Add, two, three, Type::I32)
+            .unwrap();
+        builder.build_store(slot, sum);
+        let x = builder.build_load(slot, Type::I32).unwrap();
+        let four = builder.const_value(Constant::I32(4));
+        let cond = builder.build_compare(ComparisonOp::Gt, x, four).unwrap();
+        builder.build_cond_branch(cond, then_block, else_block);
+
+        builder.set_current_block(then_block);
+        let one = builder.const_value(Constant::I32(1));
+        builder.build_return(Some(one));
+
+        builder.set_current_block(else_block);
+        let zero = builder.const_value(Constant::I32(0));
+        builder.build_return(Some(zero));
+
+        let mut module = builder.build();
+        OptimizationLevel::O1
+            .create_pass_manager()
+            .optimize(&mut module);
+
+        let func = module.get_function(func_id).unwrap();
+        assert!(func.get_block(else_block).is_none());
+        let entry = func.get_block(func.entry_block.unwrap()).unwrap();
+        assert!(matches!(
+            entry.terminator(),
+            Some(Instruction::Branch(target)) if *target == then_block
+        ));
+    }
 }
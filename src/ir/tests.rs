@@ -307,3 +307,36 @@ fn test_type_cast_operations() {
     assert!(output.contains("cast"));
     assert!(output.contains("i32 to f32"));
 }
+
+#[test]
+fn test_instruction_source_locations() {
+    use crate::source::{SourceLocation, Span};
+
+    let mut builder = IrBuilder::new();
+    let func_id = builder.create_function("located".to_string(), vec![], Type::I32);
+
+    let span = Span::new(
+        SourceLocation::new(3, 5, 20),
+        SourceLocation::new(3, 12, 27),
+    );
+    assert_eq!(builder.set_location(Some(span)), None);
+    let value = builder.const_value(Constant::I32(1));
+    let ptr = builder.build_alloc(Type::I32).unwrap();
+    builder.declare_variable("x".to_string(), ptr, Type::I32, false);
+    assert_eq!(builder.set_location(None), Some(span));
+    builder.build_return(Some(value));
+
+    let module = builder.build();
+    let func = module.get_function(func_id).unwrap();
+    let instructions = &func
+        .get_block(func.entry_block.unwrap())
+        .unwrap()
+        .instructions;
+    assert_eq!(instructions[0].1.source_location, Some(span));
+    assert_eq!(instructions[2].1.source_location, None);
+
+    assert_eq!(func.debug_variables.len(), 1);
+    assert_eq!(func.debug_variables[0].name, "x");
+    assert_eq!(func.debug_variables[0].ptr, ptr);
+    assert_eq!(func.debug_variables[0].span, Some(span));
+}
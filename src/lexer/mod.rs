@@ -6,7 +6,7 @@ mod token;
 pub mod fuzz;
 
 pub use scanner::{Lexer, UnicodeSecurityConfig, UnicodeSecurityLevel};
-pub use token::{Token, TokenKind};
+pub use token::{Comment, NumberSuffix, Token, TokenKind};
 
 #[cfg(test)]
 mod tests;
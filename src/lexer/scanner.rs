@@ -1,6 +1,7 @@
 use super::lru_cache::LruCache;
-use super::{Token, TokenKind};
+use super::{Comment, NumberSuffix, Token, TokenKind};
 use crate::{
+    edition::Edition,
     error::{Error, Result},
     source::{SourceLocation, Span},
 };
@@ -132,10 +133,12 @@ pub struct Lexer {
     start_index: usize,      // Byte offset
     start_char_index: usize, // Character offset
     tokens: Vec<Token>,
+    comments: Vec<Comment>,
     errors: Vec<Error>,
     string_interner: StringInterner,
     unicode_config: UnicodeSecurityConfig,
     unicode_cache: UnicodeCache,
+    edition: Edition,
 }
 
 impl Lexer {
@@ -167,10 +170,12 @@ impl Lexer {
             start_index: 0,
             start_char_index: 0,
             tokens: Vec::new(),
+            comments: Vec::new(),
             errors: Vec::new(),
             string_interner: StringInterner::new(),
             unicode_config,
             unicode_cache: UnicodeCache::new(),
+            edition: Edition::default(),
         })
     }
 
@@ -184,7 +189,26 @@ impl Lexer {
         self.unicode_config = config;
     }
 
-    pub fn scan_tokens(mut self) -> (Vec<Token>, Vec<Error>) {
+    /// Get the language edition used to decide which words are keywords
+    pub fn edition(&self) -> Edition {
+        self.edition
+    }
+
+    /// Set the language edition used to decide which words are keywords
+    pub fn set_edition(&mut self, edition: Edition) {
+        self.edition = edition;
+    }
+
+    pub fn scan_tokens(self) -> (Vec<Token>, Vec<Error>) {
+        let (tokens, _, errors) = self.scan_tokens_with_comments();
+        (tokens, errors)
+    }
+
+    /// Scan all tokens, also returning the comments between them
+    ///
+    /// Comments are not tokens, so the parser never sees them; tools that
+    /// re-print source, such as the formatter, put them back from here.
+    pub fn scan_tokens_with_comments(mut self) -> (Vec<Token>, Vec<Comment>, Vec<Error>) {
         while !self.is_at_end() {
             self.start_location = self.location;
             self.start_index = self.current;
@@ -193,7 +217,7 @@ impl Lexer {
         }
 
         self.add_token(TokenKind::Eof);
-        (self.tokens, self.errors)
+        (self.tokens, self.comments, self.errors)
     }
 
     /// Get the next token for LSP and iterative parsing
@@ -324,6 +348,7 @@ impl Lexer {
                         while self.peek() != '\n' && !self.is_at_end() {
                             self.advance();
                         }
+                        self.add_comment();
                     }
                 } else if self.match_char('*') {
                     // Check if it's a doc comment (/**)
@@ -332,6 +357,7 @@ impl Lexer {
                     } else {
                         // Regular multi-line comment
                         self.scan_multiline_comment();
+                        self.add_comment();
                     }
                 } else {
                     self.add_token(TokenKind::Slash);
@@ -471,10 +497,43 @@ impl Lexer {
             String::new() // Safe fallback for invalid indices
         };
 
-        match value.parse::<f64>() {
-            Ok(num) => self.add_token(TokenKind::Number(num)),
-            Err(_) => self.error("Invalid number format"),
+        let num = match value.parse::<f64>() {
+            Ok(num) => num,
+            Err(_) => {
+                self.error("Invalid number format");
+                return;
+            }
+        };
+
+        match self.scan_number_suffix() {
+            Some(suffix) if !suffix.is_float() && value.contains('.') => self.error(&format!(
+                "Integer suffix '{}' on a floating point literal",
+                suffix
+            )),
+            Some(suffix) if !suffix.fits(num) => {
+                self.error(&format!("Literal out of range for {}", suffix))
+            }
+            Some(suffix) => self.add_token(TokenKind::TypedNumber(num, suffix)),
+            None => self.add_token(TokenKind::Number(num)),
+        }
+    }
+
+    /// Consume a type suffix directly following a number, e.g. `i64`
+    fn scan_number_suffix(&mut self) -> Option<NumberSuffix> {
+        let rest = self.input.get(self.current..)?;
+        let suffix = NumberSuffix::parse(rest.get(..3)?)?;
+        // `10i64x` is not a suffixed literal
+        if rest[3..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        {
+            return None;
+        }
+        for _ in 0..3 {
+            self.advance();
         }
+        Some(suffix)
     }
 
     /// Normalize an identifier using Unicode NFKC normalization
@@ -665,12 +724,29 @@ impl Lexer {
     }
 
     fn scan_identifier(&mut self) {
+        // Raw identifier: `r#name` is always a name, even if `name` is a keyword
+        let raw = self.current == self.start_index + 1
+            && self.input_bytes[self.start_index] == b'r'
+            && self.peek() == '#'
+            && (self.peek_next().is_alphabetic() || self.peek_next() == '_');
+        if raw {
+            self.advance();
+        }
+
         while self.peek().is_alphanumeric() || self.peek() == '_' {
             self.advance();
         }
 
         // First extract the identifier into owned string
-        let original_string = self.extract_current_lexeme();
+        let lexeme = self.extract_current_lexeme();
+        if raw {
+            let name = lexeme[2..].to_string();
+            if let Some(identifier) = self.process_identifier(name) {
+                self.add_token(TokenKind::Identifier(identifier));
+            }
+            return;
+        }
+        let original_string = lexeme;
 
         // Special case: standalone underscore is a wildcard token
         if original_string == "_" {
@@ -683,6 +759,15 @@ impl Lexer {
 
         if let Some(identifier) = final_identifier {
             // Use normalized value for keyword lookup and token creation
+            if self.edition.is_reserved(&identifier) {
+                self.errors.push(
+                    Error::lexer(format!(
+                        "'{}' is a reserved keyword in edition {}; write 'r#{}' to use it as a name",
+                        identifier, self.edition, identifier
+                    ))
+                    .with_location(self.start_location),
+                );
+            }
             let token_kind = TokenKind::from_keyword(&identifier).unwrap_or_else(|| {
                 // Intern the identifier for memory efficiency
                 let _interned_index = self.string_interner.intern(identifier.clone());
@@ -839,6 +924,18 @@ impl Lexer {
         self.tokens.push(Token::new(kind, span, lexeme));
     }
 
+    /// Record the text scanned since the start of the current lexeme as a
+    /// comment
+    fn add_comment(&mut self) {
+        let text = self.input[self.start_index..self.current]
+            .trim_end_matches('\r')
+            .to_string();
+        self.comments.push(Comment {
+            text,
+            span: Span::new(self.start_location, self.location),
+        });
+    }
+
     fn scan_doc_comment(&mut self) {
         let mut content = String::new();
 
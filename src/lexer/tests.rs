@@ -114,6 +114,40 @@ fn test_keywords() {
     );
 }
 
+#[test]
+fn test_loop_control_keywords() {
+    let tokens = scan("while true { break continue }");
+
+    assert_eq!(
+        tokens,
+        vec![
+            TokenKind::While,
+            TokenKind::True,
+            TokenKind::LeftBrace,
+            TokenKind::Break,
+            TokenKind::Continue,
+            TokenKind::RightBrace,
+        ]
+    );
+}
+
+#[test]
+fn test_trait_keywords() {
+    let tokens = scan("trait Describe impl Describe for i32");
+
+    assert_eq!(
+        tokens,
+        vec![
+            TokenKind::Trait,
+            TokenKind::Identifier("Describe".to_string()),
+            TokenKind::Impl,
+            TokenKind::Identifier("Describe".to_string()),
+            TokenKind::For,
+            TokenKind::Identifier("i32".to_string()),
+        ]
+    );
+}
+
 #[test]
 fn test_module_keywords() {
     let input = "import export from as in";
@@ -166,6 +200,30 @@ fn test_numbers() {
     );
 }
 
+#[test]
+fn test_number_suffixes() {
+    let input = "10i64 7u32 1.5f64 2f32 3i64x";
+    let tokens = scan(input);
+
+    assert_eq!(
+        tokens,
+        vec![
+            TokenKind::TypedNumber(10.0, NumberSuffix::I64),
+            TokenKind::TypedNumber(7.0, NumberSuffix::U32),
+            TokenKind::TypedNumber(1.5, NumberSuffix::F64),
+            TokenKind::TypedNumber(2.0, NumberSuffix::F32),
+            TokenKind::Number(3.0),
+            TokenKind::Identifier("i64x".to_string()),
+        ]
+    );
+
+    let (_, errors) = scan_with_errors("1.5i32");
+    assert_eq!(errors.len(), 1);
+
+    let (_, errors) = scan_with_errors("5000000000u32");
+    assert_eq!(errors.len(), 1);
+}
+
 #[test]
 fn test_strings() {
     let input = r#""hello" "world" "Script lang" "with\nnewline" "with\ttab" "quote\"inside""#;
@@ -762,3 +820,35 @@ fn test_colon_colon_operator() {
         ]
     );
 }
+
+#[test]
+fn test_raw_identifiers() {
+    let tokens = scan("r#fn r#yield r x");
+
+    assert_eq!(
+        tokens,
+        vec![
+            TokenKind::Identifier("fn".to_string()),
+            TokenKind::Identifier("yield".to_string()),
+            TokenKind::Identifier("r".to_string()),
+            TokenKind::Identifier("x".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_edition_reserved_keywords() {
+    // Words reserved by later editions are plain names in the default edition
+    assert_eq!(
+        scan("yield"),
+        vec![TokenKind::Identifier("yield".to_string())]
+    );
+
+    let mut lexer = Lexer::new("let yield = r#yield").unwrap();
+    lexer.set_edition(crate::edition::Edition::E2026);
+    let (_, errors) = lexer.scan_tokens();
+
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].message.contains("r#yield"));
+    assert_eq!(errors[0].location.unwrap().column, 5);
+}
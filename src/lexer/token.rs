@@ -20,10 +20,81 @@ impl Token {
     }
 }
 
+/// A `//` or `/* */` comment, kept beside the tokens as trivia
+///
+/// Doc comments are tokens of their own and never show up here.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// The comment as written, including its delimiters
+    pub text: String,
+    pub span: Span,
+}
+
+/// Type suffix of a numeric literal, e.g. the `i64` in `10i64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NumberSuffix {
+    I32,
+    I64,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+impl NumberSuffix {
+    /// Parse a suffix as written after the digits
+    pub fn parse(suffix: &str) -> Option<Self> {
+        match suffix {
+            "i32" => Some(NumberSuffix::I32),
+            "i64" => Some(NumberSuffix::I64),
+            "u32" => Some(NumberSuffix::U32),
+            "u64" => Some(NumberSuffix::U64),
+            "f32" => Some(NumberSuffix::F32),
+            "f64" => Some(NumberSuffix::F64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NumberSuffix::I32 => "i32",
+            NumberSuffix::I64 => "i64",
+            NumberSuffix::U32 => "u32",
+            NumberSuffix::U64 => "u64",
+            NumberSuffix::F32 => "f32",
+            NumberSuffix::F64 => "f64",
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, NumberSuffix::F32 | NumberSuffix::F64)
+    }
+
+    /// Whether a literal value is representable in the suffix's type
+    pub fn fits(&self, value: f64) -> bool {
+        match self {
+            NumberSuffix::I32 => value <= i32::MAX as f64,
+            NumberSuffix::I64 => value <= i64::MAX as f64,
+            NumberSuffix::U32 => value <= u32::MAX as f64,
+            NumberSuffix::U64 => value <= u64::MAX as f64,
+            NumberSuffix::F32 => value <= f32::MAX as f64,
+            NumberSuffix::F64 => value.is_finite(),
+        }
+    }
+}
+
+impl fmt::Display for NumberSuffix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenKind {
     // Literals
     Number(f64),
+    /// Number with an explicit type suffix
+    TypedNumber(f64, NumberSuffix),
     String(String),
     Identifier(String),
 
@@ -35,6 +106,8 @@ pub enum TokenKind {
     Else,
     While,
     For,
+    Break,
+    Continue,
     Return,
     True,
     False,
@@ -45,6 +118,7 @@ pub enum TokenKind {
     Struct,
     Enum,
     Impl,
+    Trait,
     Where,
     Try,
     Catch,
@@ -122,6 +196,8 @@ impl TokenKind {
             map.insert("else", TokenKind::Else);
             map.insert("while", TokenKind::While);
             map.insert("for", TokenKind::For);
+            map.insert("break", TokenKind::Break);
+            map.insert("continue", TokenKind::Continue);
             map.insert("return", TokenKind::Return);
             map.insert("true", TokenKind::True);
             map.insert("false", TokenKind::False);
@@ -131,6 +207,7 @@ impl TokenKind {
             map.insert("struct", TokenKind::Struct);
             map.insert("enum", TokenKind::Enum);
             map.insert("impl", TokenKind::Impl);
+            map.insert("trait", TokenKind::Trait);
             map.insert("where", TokenKind::Where);
             map.insert("try", TokenKind::Try);
             map.insert("catch", TokenKind::Catch);
@@ -155,6 +232,7 @@ impl fmt::Display for TokenKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             TokenKind::Number(n) => write!(f, "Number({})", n),
+            TokenKind::TypedNumber(n, suffix) => write!(f, "Number({}{})", n, suffix),
             TokenKind::String(s) => write!(f, "String(\"{}\")", s),
             TokenKind::Identifier(id) => write!(f, "Identifier({})", id),
 
@@ -165,6 +243,8 @@ impl fmt::Display for TokenKind {
             TokenKind::Else => write!(f, "else"),
             TokenKind::While => write!(f, "while"),
             TokenKind::For => write!(f, "for"),
+            TokenKind::Break => write!(f, "break"),
+            TokenKind::Continue => write!(f, "continue"),
             TokenKind::Return => write!(f, "return"),
             TokenKind::True => write!(f, "true"),
             TokenKind::False => write!(f, "false"),
@@ -175,6 +255,7 @@ impl fmt::Display for TokenKind {
             TokenKind::Struct => write!(f, "struct"),
             TokenKind::Enum => write!(f, "enum"),
             TokenKind::Impl => write!(f, "impl"),
+            TokenKind::Trait => write!(f, "trait"),
             TokenKind::Where => write!(f, "where"),
             TokenKind::Try => write!(f, "try"),
             TokenKind::Catch => write!(f, "catch"),
@@ -1,18 +1,23 @@
 pub mod codegen;
 pub mod compilation;
+pub mod completions;
 pub mod debugger;
 pub mod doc;
+pub mod edition;
 pub mod error;
 pub mod formatter;
+pub mod index;
 pub mod inference;
 pub mod ir;
 pub mod lexer;
+pub mod lint;
 pub mod lowering;
 pub mod lsp;
 pub mod manuscript;
 #[cfg(feature = "mcp")]
 pub mod mcp;
 pub mod metaprogramming;
+pub mod metrics;
 pub mod module;
 pub mod package;
 pub mod parser;
@@ -35,11 +40,14 @@ mod tests {
 pub use codegen::{CodeGenerator, ExecutableModule};
 pub use compilation::{DependencyAnalyzer, DependencyGraph};
 pub use debugger::{
-    get_debugger, initialize_debugger, is_debugger_initialized, shutdown_debugger, Breakpoint,
-    BreakpointCondition, BreakpointId, BreakpointManager, BreakpointType, DebugEvent, DebugHook,
-    DebugSession, Debugger, DebuggerState, ExecutionContext, RuntimeDebugInterface,
+    get_debugger, initialize_debugger, initialize_debugger_with, is_debugger_initialized,
+    shutdown_debugger, Breakpoint, BreakpointCondition, BreakpointId, BreakpointManager,
+    BreakpointType, DebugEvent, DebugHook, DebugSession, Debugger, DebuggerState,
+    ExecutionContext, RuntimeDebugInterface,
 };
+pub use edition::Edition;
 pub use error::{Error, Result};
+pub use index::SymbolIndex;
 pub use inference::{InferenceEngine, InferenceResult};
 pub use ir::{IrBuilder, Module as IrModule};
 pub use lexer::{Lexer, Token, TokenKind};
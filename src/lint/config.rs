@@ -0,0 +1,189 @@
+//! Lint levels and the `[lints]` table of `script.toml`
+//!
+//! Every lint has a level: `allow` turns it off, `warn` reports it and
+//! `deny` reports it as an error that fails `script lint`. A project sets
+//! levels by lint name in its manifest; lints it leaves out keep their
+//! default level:
+//!
+//! ```toml
+//! [lints]
+//! shadowing = "warn"
+//! float_equality = "deny"
+//! unused_variables = "allow"
+//! ```
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::package::PackageManifest;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// How a lint is reported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    /// Reported as an error
+    Deny,
+}
+
+impl fmt::Display for LintLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LintLevel::Allow => "allow",
+            LintLevel::Warn => "warning",
+            LintLevel::Deny => "error",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The checks the linter runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// A local variable or parameter that is never read
+    UnusedVariables,
+    /// A local variable with the name of another in the same function
+    Shadowing,
+    /// Statements after a `return`, `break` or `continue`
+    UnreachableCode,
+    /// `==` or `!=` between floating point values
+    FloatEquality,
+    /// A call to an async function whose future is dropped without `await`
+    UnawaitedFutures,
+}
+
+impl Lint {
+    /// Every lint, in the order they are documented
+    pub const ALL: [Lint; 5] = [
+        Lint::UnusedVariables,
+        Lint::Shadowing,
+        Lint::UnreachableCode,
+        Lint::FloatEquality,
+        Lint::UnawaitedFutures,
+    ];
+
+    /// Name used in `[lints]` and in diagnostics
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariables => "unused_variables",
+            Lint::Shadowing => "shadowing",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::FloatEquality => "float_equality",
+            Lint::UnawaitedFutures => "unawaited_futures",
+        }
+    }
+
+    /// The lint called `name`
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+
+    /// Level of the lint when the project does not set one
+    ///
+    /// Shadowing is often deliberate, as in `let line = line.trim()`, so it
+    /// is only reported when asked for.
+    pub fn default_level(self) -> LintLevel {
+        match self {
+            Lint::Shadowing => LintLevel::Allow,
+            _ => LintLevel::Warn,
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Level of every lint for a project
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LintConfig {
+    /// Levels the project set; other lints use their default
+    levels: HashMap<Lint, LintLevel>,
+}
+
+impl LintConfig {
+    /// The configuration for a `[lints]` table
+    pub fn from_levels(levels: &HashMap<String, LintLevel>) -> Result<Self> {
+        let mut config = Self::default();
+        for (name, level) in levels {
+            let lint = Lint::from_name(name).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::Configuration,
+                    format!("Unknown lint '{}' in [lints]", name),
+                )
+            })?;
+            config.set(lint, *level);
+        }
+        Ok(config)
+    }
+
+    /// Load the configuration applying to files in `dir`
+    ///
+    /// Uses the `[lints]` table of the nearest `script.toml` in `dir` or its
+    /// ancestors, and the default levels outside a project.
+    pub fn discover(dir: &Path) -> Result<Self> {
+        let Some(manifest) = dir
+            .ancestors()
+            .map(|dir| dir.join("script.toml"))
+            .find(|path| path.is_file())
+        else {
+            return Ok(Self::default());
+        };
+        let lints = PackageManifest::from_file(&manifest)?.lints;
+        Self::from_levels(&lints).map_err(|e| e.with_file_name(manifest.display().to_string()))
+    }
+
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        self.levels
+            .get(&lint)
+            .copied()
+            .unwrap_or_else(|| lint.default_level())
+    }
+
+    pub fn set(&mut self, lint: Lint, level: LintLevel) {
+        self.levels.insert(lint, level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_discover_reads_manifest_lints() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("src");
+        fs::create_dir(&nested).unwrap();
+        assert_eq!(
+            LintConfig::discover(&nested)
+                .unwrap()
+                .level(Lint::Shadowing),
+            LintLevel::Allow
+        );
+
+        fs::write(
+            dir.path().join("script.toml"),
+            "[package]\nname = \"game\"\nversion = \"0.1.0\"\n\n[lints]\nshadowing = \"warn\"\nfloat_equality = \"deny\"\n",
+        )
+        .unwrap();
+        let config = LintConfig::discover(&nested).unwrap();
+        assert_eq!(config.level(Lint::Shadowing), LintLevel::Warn);
+        assert_eq!(config.level(Lint::FloatEquality), LintLevel::Deny);
+        assert_eq!(config.level(Lint::UnusedVariables), LintLevel::Warn);
+
+        fs::write(
+            dir.path().join("script.toml"),
+            "[package]\nname = \"game\"\nversion = \"0.1.0\"\n\n[lints]\nunused = \"allow\"\n",
+        )
+        .unwrap();
+        let error = LintConfig::discover(&nested).unwrap_err();
+        assert!(error.message.contains("Unknown lint 'unused'"));
+    }
+}
@@ -0,0 +1,755 @@
+//! Linter for Script source
+//!
+//! `script lint` and the language server run these checks over a file once
+//! it parses. Each check is a [`Lint`] whose level the project sets in the
+//! `[lints]` table of `script.toml`:
+//! - `unused_variables`: a local variable or parameter that is never read.
+//!   Names starting with an underscore are exempt, and the fix adds one.
+//! - `shadowing`: a local variable named like another in scope in the same
+//!   function.
+//! - `unreachable_code`: statements after a `return`, `break` or
+//!   `continue`, which the fix removes.
+//! - `float_equality`: `==` or `!=` on floating point values, which rounding
+//!   makes unreliable.
+//! - `unawaited_futures`: a statement calling an async function without
+//!   `await`, so the call never runs. Inside an async function the fix
+//!   awaits it.
+//!
+//! The float and future checks use the types inference finds for the file
+//! alone; values whose types come from other modules are not checked.
+
+mod config;
+
+pub use config::{Lint, LintConfig, LintLevel};
+
+use crate::error::{Fix, JsonDiagnostic, Result, Severity};
+use crate::inference::InferenceEngine;
+use crate::lexer::{Lexer, NumberSuffix, Token, TokenKind};
+use crate::parser::visitor::{walk_expr, walk_stmt};
+use crate::parser::{
+    BinaryOp, Block, ExportKind, Expr, ExprKind, ImplBlock, Literal, Method, Param, Parser,
+    Pattern, PatternKind, Program, Stmt, StmtKind, Visitor,
+};
+use crate::source::{SourceLocation, Span};
+use crate::types::Type;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A lint reported for a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintDiagnostic {
+    pub lint: Lint,
+    /// `Warn` or `Deny`, as configured
+    pub level: LintLevel,
+    pub message: String,
+    pub span: Span,
+    /// Edits that resolve the lint
+    pub fixes: Vec<Fix>,
+}
+
+impl LintDiagnostic {
+    /// The diagnostic in the JSON error format, coded with the lint's name
+    pub fn to_json(&self, file: Option<&str>) -> JsonDiagnostic {
+        let severity = match self.level {
+            LintLevel::Deny => Severity::Error,
+            _ => Severity::Warning,
+        };
+        JsonDiagnostic::new(
+            self.lint.name(),
+            severity,
+            self.message.clone(),
+            file,
+            Some(self.span),
+        )
+        .with_fixes(&self.fixes)
+    }
+}
+
+impl fmt::Display for LintDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.level, self.lint, self.message)
+    }
+}
+
+/// Lint a file, returning its diagnostics in source order
+///
+/// Fails with the first lexer or parser error if the file does not parse.
+pub fn lint_source(source: &str, config: &LintConfig) -> Result<Vec<LintDiagnostic>> {
+    let (tokens, errors) = Lexer::new(source)?.scan_tokens();
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+    let program = Parser::new(tokens.clone()).parse()?;
+
+    let mut linter = Linter {
+        config,
+        tokens: &tokens,
+        types: InferenceEngine::for_editor()
+            .infer_program(&program)
+            .map(|result| result.expr_types)
+            .unwrap_or_default(),
+        async_functions: async_functions(&program),
+        in_async: false,
+        scopes: vec![Scope::default()],
+        diagnostics: Vec::new(),
+    };
+    linter.visit_program(&program);
+
+    let mut diagnostics = linter.diagnostics;
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.start.byte_offset);
+    Ok(diagnostics)
+}
+
+/// Apply the first fix of each diagnostic to `source`
+///
+/// A fix overlapping one applied before it is left out; linting the result
+/// again reports it once more.
+pub fn apply_fixes(source: &str, diagnostics: &[LintDiagnostic]) -> String {
+    let mut edits: Vec<(usize, usize, &str)> = diagnostics
+        .iter()
+        .filter_map(|diagnostic| match diagnostic.fixes.first()? {
+            Fix::Edit { span, text, .. } => {
+                Some((span.start.byte_offset, span.end.byte_offset, text.as_str()))
+            }
+            Fix::Import { .. } => None,
+        })
+        .collect();
+    edits.sort_by_key(|&(start, end, _)| (start, end));
+
+    let mut output = String::with_capacity(source.len());
+    let mut position = 0;
+    for (start, end, text) in edits {
+        if start < position {
+            continue;
+        }
+        output.push_str(&source[position..start]);
+        output.push_str(text);
+        position = end;
+    }
+    output.push_str(&source[position..]);
+    output
+}
+
+/// Names of the file's top-level async functions
+fn async_functions(program: &Program) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for stmt in &program.statements {
+        match &stmt.kind {
+            StmtKind::Function {
+                name,
+                is_async: true,
+                ..
+            }
+            | StmtKind::Export {
+                export:
+                    ExportKind::Function {
+                        name,
+                        is_async: true,
+                        ..
+                    },
+            } => {
+                names.insert(name.clone());
+            }
+            StmtKind::Export {
+                export: ExportKind::Declaration(decl),
+            } => {
+                if let StmtKind::Function {
+                    name,
+                    is_async: true,
+                    ..
+                } = &decl.kind
+                {
+                    names.insert(name.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+#[derive(Default)]
+struct Scope {
+    bindings: Vec<Binding>,
+    /// Whether the scope holds a function's or closure's parameters, where
+    /// looking for shadowed names stops
+    function: bool,
+}
+
+struct Binding {
+    name: String,
+    /// Where the name is written, or the statement declaring it when the
+    /// name could not be found
+    span: Span,
+    exact: bool,
+    used: bool,
+}
+
+struct Linter<'a> {
+    config: &'a LintConfig,
+    /// Tokens of the file, which locate names the syntax tree has no span for
+    tokens: &'a [Token],
+    /// Inferred type of each expression, by span
+    types: HashMap<Span, Type>,
+    async_functions: HashSet<String>,
+    /// Whether the innermost function is async
+    in_async: bool,
+    /// Local scopes, the file's top level first
+    scopes: Vec<Scope>,
+    diagnostics: Vec<LintDiagnostic>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, lint: Lint, span: Span, message: String, fixes: Vec<Fix>) {
+        let level = self.config.level(lint);
+        if level == LintLevel::Allow {
+            return;
+        }
+        self.diagnostics.push(LintDiagnostic {
+            lint,
+            level,
+            message,
+            span,
+            fixes,
+        });
+    }
+
+    /// Span of the first identifier `name` at or after byte `from`
+    fn name_span(&self, name: &str, from: usize) -> Option<Span> {
+        self.tokens
+            .iter()
+            .find(|token| {
+                token.span.start.byte_offset >= from
+                    && matches!(&token.kind, TokenKind::Identifier(found) if found == name)
+            })
+            .map(|token| token.span)
+    }
+
+    /// `end`, or the end of the semicolon that follows it
+    fn after_semicolon(&self, end: SourceLocation) -> SourceLocation {
+        self.tokens
+            .iter()
+            .find(|token| {
+                token.span.start.byte_offset >= end.byte_offset
+                    && !matches!(token.kind, TokenKind::Newline)
+            })
+            .filter(|token| matches!(token.kind, TokenKind::Semicolon))
+            .map_or(end, |token| token.span.end)
+    }
+
+    fn push_scope(&mut self, function: bool) {
+        self.scopes.push(Scope {
+            bindings: Vec::new(),
+            function,
+        });
+    }
+
+    /// Leave the innermost scope, reporting the bindings never read
+    fn pop_scope(&mut self) {
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+        for binding in scope.bindings {
+            if binding.used || binding.name.starts_with('_') || binding.name == "self" {
+                continue;
+            }
+            let fixes = if binding.exact {
+                vec![Fix::Edit {
+                    title: format!("Prefix '{}' with an underscore", binding.name),
+                    span: Span::single(binding.span.start),
+                    text: "_".to_string(),
+                }]
+            } else {
+                Vec::new()
+            };
+            self.report(
+                Lint::UnusedVariables,
+                binding.span,
+                format!("unused variable '{}'", binding.name),
+                fixes,
+            );
+        }
+    }
+
+    /// Bind `name`, found at or after byte `from` of `stmt_span`
+    fn declare(&mut self, name: &str, from: usize, stmt_span: Span) -> Option<usize> {
+        let found = self.name_span(name, from);
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.bindings.push(Binding {
+                name: name.to_string(),
+                span: found.unwrap_or(stmt_span),
+                exact: found.is_some(),
+                used: false,
+            });
+        }
+        found.map(|span| span.end.byte_offset)
+    }
+
+    /// Bind a variable, reporting it if it shadows another in the function
+    fn declare_variable(&mut self, name: &str, from: usize, stmt_span: Span) {
+        if !name.starts_with('_') {
+            let mut shadowed = None;
+            for scope in self.scopes.iter().rev() {
+                if let Some(binding) = scope.bindings.iter().rev().find(|b| b.name == name) {
+                    shadowed = Some(binding.span.start.line);
+                    break;
+                }
+                if scope.function {
+                    break;
+                }
+            }
+            if let Some(line) = shadowed {
+                let span = self.name_span(name, from).unwrap_or(stmt_span);
+                self.report(
+                    Lint::Shadowing,
+                    span,
+                    format!("'{}' shadows the variable declared on line {}", name, line),
+                    Vec::new(),
+                );
+            }
+        }
+        self.declare(name, from, stmt_span);
+    }
+
+    /// Enter a function or closure, binding its parameters in order after
+    /// byte `from`
+    fn enter_function<'p>(
+        &mut self,
+        params: impl IntoIterator<Item = &'p str>,
+        mut from: usize,
+        span: Span,
+    ) {
+        self.push_scope(true);
+        for name in params {
+            if let Some(end) = self.declare(name, from, span) {
+                from = end;
+            }
+        }
+    }
+
+    /// Mark the innermost binding of `name` as read
+    fn use_name(&mut self, name: &str) {
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(binding) = scope.bindings.iter_mut().rev().find(|b| b.name == name) {
+                binding.used = true;
+                return;
+            }
+        }
+    }
+
+    /// Bind the names `pattern` introduces
+    fn declare_pattern(&mut self, pattern: &Pattern) {
+        match &pattern.kind {
+            // Capitalized names are enum variants or constants, not bindings
+            PatternKind::Identifier(name) if !name.starts_with(char::is_uppercase) => {
+                self.declare_variable(name, pattern.span.start.byte_offset, pattern.span);
+            }
+            PatternKind::Binding { name, pattern: sub } => {
+                self.declare_variable(name, pattern.span.start.byte_offset, pattern.span);
+                self.declare_pattern(sub);
+            }
+            PatternKind::Array(items) | PatternKind::Tuple(items) => {
+                for item in items {
+                    self.declare_pattern(item);
+                }
+            }
+            // Every alternative binds the same names
+            PatternKind::Or(alternatives) => {
+                if let Some(first) = alternatives.first() {
+                    self.declare_pattern(first);
+                }
+            }
+            PatternKind::Object(fields) => {
+                for (field, sub) in fields {
+                    match sub {
+                        Some(sub) => self.declare_pattern(sub),
+                        None => self.declare_variable(
+                            field,
+                            pattern.span.start.byte_offset,
+                            pattern.span,
+                        ),
+                    }
+                }
+            }
+            PatternKind::EnumConstructor {
+                args: Some(args), ..
+            } => {
+                for arg in args {
+                    self.declare_pattern(arg);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn is_float(&self, expr: &Expr) -> bool {
+        match &expr.kind {
+            ExprKind::Literal(Literal::Number(_)) => self.tokens.iter().any(|token| {
+                token.span.start == expr.span.start
+                    && matches!(token.kind, TokenKind::Number(_))
+                    && token.lexeme.contains('.')
+            }),
+            ExprKind::Literal(Literal::TypedNumber(_, suffix)) => {
+                matches!(suffix, NumberSuffix::F32 | NumberSuffix::F64)
+            }
+            _ => self.types.get(&expr.span).is_some_and(Type::is_float),
+        }
+    }
+
+    /// Report a statement that drops the future of an async call
+    fn check_unawaited(&mut self, expr: &Expr) {
+        let calls_async = match &expr.kind {
+            ExprKind::Call { callee, .. } => match &callee.kind {
+                ExprKind::Identifier(name) => self.async_functions.contains(name),
+                _ => false,
+            },
+            _ => false,
+        };
+        let is_future = matches!(self.types.get(&expr.span), Some(Type::Future(_)));
+        if !calls_async && !is_future {
+            return;
+        }
+
+        let fixes = if self.in_async {
+            vec![Fix::Edit {
+                title: "Await the future".to_string(),
+                span: Span::single(expr.span.start),
+                text: "await ".to_string(),
+            }]
+        } else {
+            Vec::new()
+        };
+        self.report(
+            Lint::UnawaitedFutures,
+            expr.span,
+            "this future is never awaited, so it never runs".to_string(),
+            fixes,
+        );
+    }
+
+    /// Report the statements of `block` after one that never falls through
+    fn check_unreachable(&mut self, block: &Block) {
+        let Some(exit) = block.statements.iter().position(|stmt| {
+            matches!(
+                stmt.kind,
+                StmtKind::Return(_) | StmtKind::Break | StmtKind::Continue
+            )
+        }) else {
+            return;
+        };
+        let mut rest: Vec<Span> = block.statements[exit + 1..]
+            .iter()
+            .map(|stmt| stmt.span)
+            .collect();
+        rest.extend(block.final_expr.iter().map(|expr| expr.span));
+        let (Some(first), Some(last)) = (rest.first(), rest.last()) else {
+            return;
+        };
+
+        let removed = Span::new(
+            self.after_semicolon(block.statements[exit].span.end),
+            self.after_semicolon(last.end),
+        );
+        self.report(
+            Lint::UnreachableCode,
+            Span::new(first.start, last.end),
+            "unreachable code".to_string(),
+            vec![Fix::Edit {
+                title: "Remove unreachable code".to_string(),
+                span: removed,
+                text: String::new(),
+            }],
+        );
+    }
+}
+
+impl Visitor for Linter<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Let { name, init, .. } => {
+                if let Some(init) = init {
+                    self.visit_expr(init);
+                }
+                self.declare_variable(name, stmt.span.start.byte_offset, stmt.span);
+            }
+            StmtKind::For {
+                variable,
+                iterable,
+                body,
+            } => {
+                self.visit_expr(iterable);
+                self.push_scope(false);
+                self.declare_variable(variable, stmt.span.start.byte_offset, stmt.span);
+                self.visit_block(body);
+                self.pop_scope();
+            }
+            StmtKind::Expression(expr) => {
+                self.check_unawaited(expr);
+                self.visit_expr(expr);
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        self.check_unreachable(block);
+        self.push_scope(false);
+        for stmt in &block.statements {
+            self.visit_stmt(stmt);
+        }
+        if let Some(expr) = &block.final_expr {
+            self.visit_expr(expr);
+        }
+        self.pop_scope();
+    }
+
+    fn visit_function(&mut self, name: &str, params: &[Param], body: &Block, stmt: &Stmt) {
+        let is_async = match &stmt.kind {
+            StmtKind::Function { is_async, .. } => *is_async,
+            StmtKind::Export {
+                export: ExportKind::Function { is_async, .. },
+            } => *is_async,
+            _ => false,
+        };
+        let from = self
+            .name_span(name, stmt.span.start.byte_offset)
+            .map_or(stmt.span.start.byte_offset, |span| span.end.byte_offset);
+
+        let outer = std::mem::replace(&mut self.in_async, is_async);
+        self.enter_function(params.iter().map(|p| p.name.as_str()), from, stmt.span);
+        self.visit_block(body);
+        self.pop_scope();
+        self.in_async = outer;
+    }
+
+    fn visit_method(&mut self, _impl_block: &ImplBlock, method: &Method) {
+        let from = self
+            .name_span(&method.name, method.span.start.byte_offset)
+            .map_or(method.span.start.byte_offset, |span| span.end.byte_offset);
+
+        let outer = std::mem::replace(&mut self.in_async, method.is_async);
+        self.enter_function(
+            method.params.iter().map(|p| p.name.as_str()),
+            from,
+            method.span,
+        );
+        self.visit_block(&method.body);
+        self.pop_scope();
+        self.in_async = outer;
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Identifier(name) => self.use_name(name),
+            ExprKind::Binary {
+                left,
+                op: BinaryOp::Equal | BinaryOp::NotEqual,
+                right,
+            } => {
+                if self.is_float(left) || self.is_float(right) {
+                    self.report(
+                        Lint::FloatEquality,
+                        expr.span,
+                        "floating point values are compared exactly; compare their difference against a tolerance instead".to_string(),
+                        Vec::new(),
+                    );
+                }
+                walk_expr(self, expr);
+            }
+            // Assigning to a variable is not reading it
+            ExprKind::Assign { target, value } => {
+                if !matches!(target.kind, ExprKind::Identifier(_)) {
+                    self.visit_expr(target);
+                }
+                self.visit_expr(value);
+            }
+            ExprKind::Closure { parameters, body } => {
+                // Closures run wherever they are called, so they are never
+                // in an async function
+                let outer = std::mem::replace(&mut self.in_async, false);
+                self.enter_function(
+                    parameters.iter().map(|p| p.name.as_str()),
+                    expr.span.start.byte_offset,
+                    expr.span,
+                );
+                self.visit_expr(body);
+                self.pop_scope();
+                self.in_async = outer;
+            }
+            ExprKind::Match {
+                expr: scrutinee,
+                arms,
+            } => {
+                self.visit_expr(scrutinee);
+                for arm in arms {
+                    self.push_scope(false);
+                    self.declare_pattern(&arm.pattern);
+                    if let Some(guard) = &arm.guard {
+                        self.visit_expr(guard);
+                    }
+                    self.visit_expr(&arm.body);
+                    self.pop_scope();
+                }
+            }
+            ExprKind::ListComprehension {
+                element,
+                variable,
+                iterable,
+                condition,
+            } => {
+                self.visit_expr(iterable);
+                self.push_scope(false);
+                // The variable is written after the element
+                self.declare_variable(variable, element.span.end.byte_offset, expr.span);
+                if let Some(condition) = condition {
+                    self.visit_expr(condition);
+                }
+                self.visit_expr(element);
+                self.pop_scope();
+            }
+            ExprKind::TryCatch {
+                try_expr,
+                catch_clauses,
+                finally_block,
+            } => {
+                self.visit_expr(try_expr);
+                for clause in catch_clauses {
+                    self.push_scope(false);
+                    if let Some(var) = &clause.var {
+                        self.declare_variable(var, clause.span.start.byte_offset, clause.span);
+                    }
+                    if let Some(condition) = &clause.condition {
+                        self.visit_expr(condition);
+                    }
+                    self.visit_block(&clause.handler);
+                    self.pop_scope();
+                }
+                if let Some(finally_block) = finally_block {
+                    self.visit_block(finally_block);
+                }
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(source: &str) -> Vec<LintDiagnostic> {
+        let mut config = LintConfig::default();
+        config.set(Lint::Shadowing, LintLevel::Warn);
+        lint_source(source, &config).unwrap()
+    }
+
+    fn lints(diagnostics: &[LintDiagnostic]) -> Vec<(Lint, usize)> {
+        diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.lint, diagnostic.span.start.line))
+            .collect()
+    }
+
+    #[test]
+    fn test_unused_and_shadowed_variables() {
+        let source = "fn area(width: f32, height: f32) -> f32 {
+    let scale = 2.0;
+    let _debug = 1;
+    let width = width * 2.0;
+    width
+}
+";
+        let diagnostics = lint(source);
+        assert_eq!(
+            lints(&diagnostics),
+            vec![
+                (Lint::UnusedVariables, 1),
+                (Lint::UnusedVariables, 2),
+                (Lint::Shadowing, 4),
+            ]
+        );
+        assert_eq!(diagnostics[0].message, "unused variable 'height'");
+        assert_eq!(
+            diagnostics[2].message,
+            "'width' shadows the variable declared on line 1"
+        );
+
+        let fixed = apply_fixes(source, &diagnostics);
+        assert!(fixed.starts_with("fn area(width: f32, _height: f32) -> f32 {\n    let _scale"));
+    }
+
+    #[test]
+    fn test_unreachable_code_is_removed() {
+        let source = "fn first(items: [i32]) -> i32 {
+    for item in items {
+        return item;
+        print(item);
+        print(0);
+    }
+    0
+}
+";
+        let diagnostics = lint(source);
+        assert_eq!(lints(&diagnostics), vec![(Lint::UnreachableCode, 4)]);
+        assert_eq!(
+            apply_fixes(source, &diagnostics),
+            "fn first(items: [i32]) -> i32 {
+    for item in items {
+        return item;
+    }
+    0
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_float_equality_and_unawaited_futures() {
+        let source = "async fn save() -> bool { true }
+async fn run(ratio: f64) {
+    if ratio == 0.5 { print(1) }
+    if 3 == 3 { print(2) }
+    save();
+}
+fn main() {
+    save();
+}
+";
+        let diagnostics = lint(source);
+        assert_eq!(
+            lints(&diagnostics),
+            vec![
+                (Lint::FloatEquality, 3),
+                (Lint::UnawaitedFutures, 5),
+                (Lint::UnawaitedFutures, 8),
+            ]
+        );
+        // Only an async function can await the future
+        assert_eq!(diagnostics[1].fixes.len(), 1);
+        assert!(diagnostics[2].fixes.is_empty());
+        assert!(apply_fixes(source, &diagnostics).contains("    await save();\n}\nfn main"));
+    }
+
+    #[test]
+    fn test_levels_from_config() {
+        let source =
+            "fn main() {\n    let unused = 1;\n    let x = 1;\n    let x = 2;\n    print(x);\n}\n";
+        // Shadowing is allowed unless configured
+        let diagnostics = lint_source(source, &LintConfig::default()).unwrap();
+        assert_eq!(
+            lints(&diagnostics),
+            vec![(Lint::UnusedVariables, 2), (Lint::UnusedVariables, 3)]
+        );
+
+        let mut config = LintConfig::default();
+        config.set(Lint::UnusedVariables, LintLevel::Deny);
+        let diagnostics = lint_source(source, &config).unwrap();
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.level == LintLevel::Deny));
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "error[unused_variables]: unused variable 'unused'"
+        );
+    }
+}
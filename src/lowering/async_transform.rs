@@ -200,8 +200,10 @@ fn calculate_state_size(
 /// Estimate the size of a type in bytes
 fn estimate_type_size(ty: &Type) -> u32 {
     match ty {
-        Type::I32 => 4,
+        Type::I32 | Type::U32 => 4,
+        Type::I64 | Type::U64 => 8,
         Type::F32 => 4,
+        Type::F64 => 8,
         Type::Bool => 1,
         Type::String => 8,          // String is a pointer to heap data
         Type::Unknown => 8,         // Default pointer size
@@ -647,7 +649,8 @@ fn create_async_wrapper(
         let param_value = ValueId(i as u32);
         // SECURITY: Properly allocate space for each parameter with bounds checking
         let param_size = match &param.ty {
-            Type::I32 | Type::F32 => 4,
+            Type::I32 | Type::U32 | Type::F32 => 4,
+            Type::I64 | Type::U64 | Type::F64 => 8,
             Type::Bool => 1,
             Type::String => 16, // Pointer + length
             _ => 8,             // Default pointer size
@@ -430,7 +430,8 @@ fn calculate_state_size(
 /// Calculate the size of a type with validation
 pub fn calculate_type_size(ty: &Type) -> AsyncTransformResult<u32> {
     match ty {
-        Type::I32 | Type::F32 | Type::Bool => Ok(4),
+        Type::I32 | Type::U32 | Type::F32 | Type::Bool => Ok(4),
+        Type::I64 | Type::U64 | Type::F64 => Ok(8),
         Type::String => Ok(16), // Pointer + length
         Type::Array(element_ty) => {
             let element_size = calculate_type_size(element_ty)?;
@@ -0,0 +1,367 @@
+//! Closure conversion
+//!
+//! Closures are lowered to ordinary IR functions that take the closure's
+//! environment as an extra first parameter:
+//!
+//! - **Capture analysis**: the variables a closure uses from enclosing
+//!   scopes, taken from semantic analysis when available and otherwise found
+//!   by walking the closure body
+//! - **Environment construction**: `CreateClosure` copies each captured value
+//!   into an environment slot; variables the closure assigns to are captured
+//!   by reference, so their slot holds the variable's address
+//! - **Closure functions**: the body reads its captures back with
+//!   `LoadCapture` into locals before running
+//!
+//! Named functions used as values are wrapped in a closure function that
+//! ignores the environment and forwards its arguments.
+
+use super::expr::{lower_expression, runtime_error, type_error};
+use super::{AstLowerer, LoweringResult};
+use crate::ir::{FunctionId, Parameter, ValueId};
+use crate::parser::visitor::walk_expr;
+use crate::parser::{ClosureParam, Expr, ExprKind, Visitor};
+use crate::types::Type;
+use std::collections::HashSet;
+
+/// Value id of a function's first parameter (see `AstLowerer::lower_function`)
+const FIRST_PARAM: u32 = 1000;
+
+/// Name of the IR function a closure expression is lowered to
+pub(super) fn closure_function_name(expr_id: usize) -> String {
+    format!("closure_{}", expr_id)
+}
+
+/// Name of the closure function wrapping a named function used as a value
+fn function_wrapper_name(function: &str) -> String {
+    format!("closure_{}", function)
+}
+
+/// A variable captured from an enclosing scope
+#[derive(Debug, Clone)]
+struct Capture {
+    name: String,
+    ty: Type,
+    by_ref: bool,
+}
+
+/// Collects the names a closure body reads and assigns to
+#[derive(Default)]
+struct CaptureCollector {
+    used: Vec<String>,
+    assigned: HashSet<String>,
+}
+
+impl Visitor for CaptureCollector {
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Identifier(name) if !self.used.contains(name) => {
+                self.used.push(name.clone());
+            }
+            ExprKind::Assign { target, .. } => {
+                if let ExprKind::Identifier(name) = &target.kind {
+                    self.assigned.insert(name.clone());
+                }
+            }
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// Find the variables a closure captures, in capture order
+fn closure_captures(
+    lowerer: &AstLowerer,
+    parameters: &[ClosureParam],
+    body: &Expr,
+    expr: &Expr,
+) -> LoweringResult<Vec<Capture>> {
+    if let Some(captures) = lowerer.closure_captures.get(&expr.id) {
+        return captures
+            .iter()
+            .map(|(name, _, is_mutable)| {
+                let variable = lowerer.context.lookup_variable(name).ok_or_else(|| {
+                    runtime_error(
+                        format!("Captured variable '{}' not found in scope", name),
+                        expr,
+                        "closure capture",
+                    )
+                })?;
+                Ok(Capture {
+                    name: name.clone(),
+                    ty: variable.ty.clone(),
+                    by_ref: *is_mutable,
+                })
+            })
+            .collect();
+    }
+
+    let mut collector = CaptureCollector::default();
+    collector.visit_expr(body);
+
+    Ok(collector
+        .used
+        .into_iter()
+        .filter(|name| parameters.iter().all(|param| &param.name != name))
+        .filter_map(|name| {
+            let variable = lowerer.context.lookup_variable(&name)?;
+            Some(Capture {
+                ty: variable.ty.clone(),
+                by_ref: collector.assigned.contains(&name),
+                name,
+            })
+        })
+        .collect())
+}
+
+/// Lower a closure expression to a closure function and a `CreateClosure`
+pub(super) fn lower_closure(
+    lowerer: &mut AstLowerer,
+    parameters: &[ClosureParam],
+    body: &Expr,
+    expr: &Expr,
+) -> LoweringResult<ValueId> {
+    let captures = closure_captures(lowerer, parameters, body, expr)?;
+
+    // Capture values at the creation site, before entering the closure function
+    let mut captured_vars = Vec::with_capacity(captures.len());
+    for capture in &captures {
+        let variable = lowerer
+            .context
+            .lookup_variable(&capture.name)
+            .cloned()
+            .ok_or_else(|| {
+                runtime_error(
+                    format!("Captured variable '{}' not found in scope", capture.name),
+                    expr,
+                    "closure capture",
+                )
+            })?;
+        let value = if capture.by_ref {
+            variable.ptr
+        } else {
+            lowerer
+                .builder
+                .build_load(variable.ptr, variable.ty)
+                .ok_or_else(|| {
+                    runtime_error(
+                        format!("Failed to load captured variable '{}'", capture.name),
+                        expr,
+                        "closure capture",
+                    )
+                })?
+        };
+        captured_vars.push((capture.name.clone(), value));
+    }
+
+    // Prefer annotations, then the types inferred by semantic analysis
+    let (inferred_params, inferred_ret) = match lowerer.get_expression_type_by_id(expr.id) {
+        Some(Type::Function { params, ret }) if params.len() == parameters.len() => {
+            (params.clone(), Some((**ret).clone()))
+        }
+        _ => (vec![Type::Unknown; parameters.len()], None),
+    };
+    let params: Vec<Parameter> = parameters
+        .iter()
+        .zip(inferred_params)
+        .map(|(param, inferred)| Parameter {
+            name: param.name.clone(),
+            ty: param
+                .type_ann
+                .as_ref()
+                .map(crate::types::conversion::type_from_ast)
+                .unwrap_or(inferred),
+        })
+        .collect();
+
+    let name = closure_function_name(expr.id);
+    let function = lower_closure_function(lowerer, &name, params, &captures, |lowerer| {
+        let return_type = match inferred_ret {
+            Some(ty) if ty != Type::Unknown => ty,
+            _ => lowerer.get_expression_type(body)?,
+        };
+        lowerer.set_function_return_type(return_type.clone());
+        let value = lower_expression(lowerer, body)?;
+        Ok((value, return_type))
+    })?;
+
+    let param_names = parameters.iter().map(|p| p.name.clone()).collect();
+    let captures_by_ref = captures.iter().any(|capture| capture.by_ref);
+    lowerer
+        .builder
+        .build_create_closure(name, function, param_names, captured_vars, captures_by_ref)
+        .ok_or_else(|| runtime_error("Failed to create closure instruction", expr, "closure"))
+}
+
+/// Lower a named function used as a value to a closure without captures
+pub(super) fn lower_function_value(
+    lowerer: &mut AstLowerer,
+    target: FunctionId,
+    expr: &Expr,
+) -> LoweringResult<ValueId> {
+    let (target_name, params, return_type) = {
+        let function = lowerer
+            .builder
+            .module()
+            .get_function(target)
+            .ok_or_else(|| type_error("Function is not defined", expr, "function value"))?;
+        (
+            function.name.clone(),
+            function.params.clone(),
+            function.return_type.clone(),
+        )
+    };
+
+    let name = function_wrapper_name(&target_name);
+    let function = match lowerer.builder.module().get_function_by_name(&name) {
+        Some(wrapper) => wrapper.id,
+        None => lower_closure_function(lowerer, &name, params.clone(), &[], |lowerer| {
+            lowerer.set_function_return_type(return_type.clone());
+            let args = (0..params.len())
+                .map(|i| ValueId(FIRST_PARAM + 1 + i as u32))
+                .collect();
+            let value = lowerer
+                .builder
+                .build_call(target, args, return_type.clone())
+                .ok_or_else(|| {
+                    runtime_error(
+                        format!("Failed to call function '{}'", target_name),
+                        expr,
+                        "function value",
+                    )
+                })?;
+            Ok((value, return_type.clone()))
+        })?,
+    };
+
+    let param_names = params.iter().map(|p| p.name.clone()).collect();
+    lowerer
+        .builder
+        .build_create_closure(name, function, param_names, Vec::new(), false)
+        .ok_or_else(|| runtime_error("Failed to create closure instruction", expr, "closure"))
+}
+
+/// Lower a call through a closure value
+pub(super) fn lower_closure_call(
+    lowerer: &mut AstLowerer,
+    callee: &Expr,
+    args: &[Expr],
+) -> LoweringResult<ValueId> {
+    let return_type = match lowerer.get_expression_type(callee)? {
+        Type::Function { ret, .. } => *ret,
+        _ => Type::Unknown,
+    };
+    let closure = lower_expression(lowerer, callee)?;
+    let arg_values = args
+        .iter()
+        .map(|arg| lower_expression(lowerer, arg))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    lowerer
+        .builder
+        .build_invoke_closure(closure, arg_values, return_type)
+        .ok_or_else(|| runtime_error("Failed to invoke closure", callee, "function call"))
+}
+
+/// Create a closure function, lowering its body with `lower_body`
+///
+/// The function takes the environment followed by `params`. Captures are
+/// loaded from the environment and parameters are spilled to locals before
+/// the body runs; the enclosing function's lowering state is restored
+/// afterwards, even when lowering the body fails.
+fn lower_closure_function(
+    lowerer: &mut AstLowerer,
+    name: &str,
+    params: Vec<Parameter>,
+    captures: &[Capture],
+    lower_body: impl FnOnce(&mut AstLowerer) -> LoweringResult<(ValueId, Type)>,
+) -> LoweringResult<FunctionId> {
+    let enclosing_function = lowerer.builder.current_function();
+    let enclosing_block = lowerer.builder.get_current_block();
+
+    let mut ir_params = vec![Parameter {
+        name: "env".to_string(),
+        ty: Type::Unknown,
+    }];
+    ir_params.extend(params.iter().cloned());
+    let func_id = lowerer
+        .builder
+        .create_function(name.to_string(), ir_params, Type::Unknown);
+    let enclosing = lowerer.context.enter_nested_function(func_id);
+
+    let result = lower_closure_prologue(lowerer, &params, captures).and_then(|()| {
+        let (value, return_type) = lower_body(lowerer)?;
+        if !lowerer.builder.current_block_has_terminator() {
+            if return_type == Type::Unknown {
+                lowerer.builder.build_return(None);
+            } else {
+                lowerer.builder.build_return(Some(value));
+            }
+        }
+        Ok(func_id)
+    });
+
+    lowerer.context.exit_nested_function(enclosing);
+    if let Some(function) = enclosing_function {
+        lowerer.builder.set_current_function(function);
+    }
+    if let Some(block) = enclosing_block {
+        lowerer.builder.set_current_block(block);
+    }
+    result
+}
+
+/// Bind captures and parameters to locals at the start of a closure function
+fn lower_closure_prologue(
+    lowerer: &mut AstLowerer,
+    params: &[Parameter],
+    captures: &[Capture],
+) -> LoweringResult<()> {
+    let alloc_error = |name: &str| {
+        crate::error::Error::new(
+            crate::error::ErrorKind::RuntimeError,
+            format!("Failed to allocate closure variable '{}'", name),
+        )
+    };
+    let environment = ValueId(FIRST_PARAM);
+
+    for (index, capture) in captures.iter().enumerate() {
+        if capture.by_ref {
+            // The slot holds the address of the enclosing function's variable
+            let ptr = lowerer
+                .builder
+                .build_load_capture(environment, index, Type::Unknown)
+                .ok_or_else(|| alloc_error(&capture.name))?;
+            lowerer
+                .context
+                .define_variable(capture.name.clone(), ptr, capture.ty.clone());
+        } else {
+            let value = lowerer
+                .builder
+                .build_load_capture(environment, index, capture.ty.clone())
+                .ok_or_else(|| alloc_error(&capture.name))?;
+            let ptr = lowerer
+                .builder
+                .build_alloc(capture.ty.clone())
+                .ok_or_else(|| alloc_error(&capture.name))?;
+            lowerer.builder.build_store(ptr, value);
+            lowerer
+                .context
+                .define_variable(capture.name.clone(), ptr, capture.ty.clone());
+        }
+    }
+
+    for (i, param) in params.iter().enumerate() {
+        let ptr = lowerer
+            .builder
+            .build_alloc(param.ty.clone())
+            .ok_or_else(|| alloc_error(&param.name))?;
+        lowerer
+            .builder
+            .build_store(ptr, ValueId(FIRST_PARAM + 1 + i as u32));
+        lowerer
+            .context
+            .define_variable(param.name.clone(), ptr, param.ty.clone());
+    }
+
+    Ok(())
+}
@@ -24,6 +24,15 @@ pub struct LoweringContext {
     loop_stack: Vec<LoopContext>,
 }
 
+/// Scopes and loops of a function whose lowering was suspended to lower a
+/// nested function such as a closure body
+#[derive(Debug)]
+pub struct SuspendedFunction {
+    scopes: Vec<HashMap<String, Variable>>,
+    current_function: Option<FunctionId>,
+    loop_stack: Vec<LoopContext>,
+}
+
 /// Context for loop constructs
 #[derive(Debug, Clone)]
 pub struct LoopContext {
@@ -97,6 +106,29 @@ impl LoweringContext {
         self.pop_scope();
     }
 
+    /// Enter a function nested inside the current one
+    ///
+    /// The nested function starts with only the global scope visible; the
+    /// enclosing function's variables and loops are restored by
+    /// `exit_nested_function`.
+    pub fn enter_nested_function(&mut self, func_id: FunctionId) -> SuspendedFunction {
+        let globals = self.scopes.first().cloned().unwrap_or_default();
+        let suspended = SuspendedFunction {
+            scopes: std::mem::replace(&mut self.scopes, vec![globals]),
+            current_function: self.current_function,
+            loop_stack: std::mem::take(&mut self.loop_stack),
+        };
+        self.enter_function(func_id);
+        suspended
+    }
+
+    /// Return to the function suspended by `enter_nested_function`
+    pub fn exit_nested_function(&mut self, suspended: SuspendedFunction) {
+        self.scopes = suspended.scopes;
+        self.current_function = suspended.current_function;
+        self.loop_stack = suspended.loop_stack;
+    }
+
     /// Get the current function
     pub fn current_function(&self) -> Option<FunctionId> {
         self.current_function
@@ -0,0 +1,41 @@
+//! Coverage instrumentation
+//!
+//! With coverage enabled, every basic block that has instructions from the
+//! source gets a probe calling `script_coverage_hit` at its start (after any
+//! phi nodes). The counter's lines in the `CoverageMap` are the lines those
+//! instructions start on.
+
+use crate::ir::{Function, Probe, ProbePolicy, ProbeSite};
+use crate::runtime::coverage::CoverageMap;
+
+/// Runtime hook that counts a block execution
+pub(super) const COVERAGE_HOOK: &str = "script_coverage_hit";
+
+/// Probes every block with source lines, recording the lines in `map`
+pub(super) struct CoveragePolicy<'a> {
+    pub(super) map: &'a mut CoverageMap,
+}
+
+impl ProbePolicy for CoveragePolicy<'_> {
+    fn probe(&mut self, function: &Function, site: ProbeSite) -> Option<Probe> {
+        let ProbeSite::Block(block_id) = site else {
+            return None;
+        };
+        let block = function.get_block(block_id)?;
+
+        let mut lines: Vec<u32> = block
+            .instructions
+            .iter()
+            .filter_map(|(_, inst)| inst.source_location)
+            .map(|span| span.start.line as u32)
+            .filter(|line| *line > 0)
+            .collect();
+        if lines.is_empty() {
+            return None;
+        }
+        lines.sort_unstable();
+        lines.dedup();
+
+        Some(Probe::new(COVERAGE_HOOK, self.map.add_counter(lines)))
+    }
+}
@@ -2,12 +2,15 @@ use super::{AstLowerer, LoweringResult};
 use crate::error::{Error, ErrorKind};
 use crate::ir::{
     BinaryOp as IrBinaryOp, ComparisonOp, Constant, Instruction, UnaryOp as IrUnaryOp, ValueId,
+    VariantLayout,
 };
+use crate::lexer::NumberSuffix;
 use crate::parser::{
-    BinaryOp as AstBinaryOp, ClosureParam, Expr, ExprKind, Literal, MatchArm, Pattern, PatternKind,
+    BinaryOp as AstBinaryOp, Expr, ExprKind, Literal, MatchArm, Pattern, PatternKind, TypeAnn,
     UnaryOp as AstUnaryOp,
 };
 use crate::source::Span;
+use crate::stdlib::ScriptValue;
 use crate::types::Type;
 
 /// Create an error with span information and context
@@ -26,7 +29,7 @@ fn lowering_error(
 }
 
 /// Create a runtime error with expression context
-fn runtime_error(message: impl Into<String>, expr: &Expr, operation: &str) -> Error {
+pub(super) fn runtime_error(message: impl Into<String>, expr: &Expr, operation: &str) -> Error {
     lowering_error(
         ErrorKind::RuntimeError,
         message,
@@ -36,7 +39,7 @@ fn runtime_error(message: impl Into<String>, expr: &Expr, operation: &str) -> Er
 }
 
 /// Create a type error with expression context
-fn type_error(message: impl Into<String>, expr: &Expr, operation: &str) -> Error {
+pub(super) fn type_error(message: impl Into<String>, expr: &Expr, operation: &str) -> Error {
     lowering_error(
         ErrorKind::TypeError,
         message,
@@ -59,7 +62,17 @@ fn security_error(message: impl Into<String>, expr: &Expr, operation: &str) -> E
 }
 
 /// Lower an expression to IR
+///
+/// Instructions are attributed to the span of the innermost expression
+/// they were emitted for.
 pub fn lower_expression(lowerer: &mut AstLowerer, expr: &Expr) -> LoweringResult<ValueId> {
+    let previous_location = lowerer.builder.set_location(Some(expr.span));
+    let result = lower_expression_kind(lowerer, expr);
+    lowerer.builder.set_location(previous_location);
+    result
+}
+
+fn lower_expression_kind(lowerer: &mut AstLowerer, expr: &Expr) -> LoweringResult<ValueId> {
     match &expr.kind {
         ExprKind::Literal(lit) => lower_literal(lowerer, lit),
         ExprKind::Identifier(name) => lower_identifier(lowerer, name, expr),
@@ -74,9 +87,11 @@ pub fn lower_expression(lowerer: &mut AstLowerer, expr: &Expr) -> LoweringResult
             then_branch,
             else_branch,
         } => lower_if(lowerer, condition, then_branch, else_branch.as_deref()),
-        ExprKind::Block(block) => lowerer
-            .lower_block(block)?
-            .ok_or_else(|| type_error("Block expression must produce a value", expr, "block")),
+        ExprKind::Block(block) => match lowerer.lower_block(block)? {
+            Some(value) => Ok(value),
+            // Statement blocks such as `if done { break }` produce unit
+            None => Ok(lowerer.builder.const_value(Constant::Null)),
+        },
         ExprKind::Array(elements) => lower_array(lowerer, elements),
         ExprKind::Index { object, index } => lower_index(lowerer, object, index, expr),
         ExprKind::Member { object, property } => lower_member(lowerer, object, property, expr),
@@ -116,7 +131,25 @@ pub fn lower_expression(lowerer: &mut AstLowerer, expr: &Expr) -> LoweringResult
             // TODO: Implement proper try-catch lowering with exception handling
             lower_expression(lowerer, try_expr)
         }
-        ExprKind::Closure { parameters, body } => lower_closure(lowerer, parameters, body, expr),
+        ExprKind::Closure { parameters, body } => {
+            super::closure::lower_closure(lowerer, parameters, body, expr)
+        }
+        ExprKind::Cast {
+            expr: inner_expr,
+            target_type,
+        } => lower_cast(lowerer, inner_expr, target_type, expr),
+    }
+}
+
+/// Constant of the type named by a numeric literal's suffix
+fn suffixed_constant(n: f64, suffix: NumberSuffix) -> Constant {
+    match suffix {
+        NumberSuffix::I32 => Constant::I32(n as i32),
+        NumberSuffix::I64 => Constant::I64(n as i64),
+        NumberSuffix::U32 => Constant::U32(n as u32),
+        NumberSuffix::U64 => Constant::U64(n as u64),
+        NumberSuffix::F32 => Constant::F32(n as f32),
+        NumberSuffix::F64 => Constant::F64(n),
     }
 }
 
@@ -131,6 +164,7 @@ fn lower_literal(lowerer: &mut AstLowerer, literal: &Literal) -> LoweringResult<
                 Constant::F32(*n as f32)
             }
         }
+        Literal::TypedNumber(n, suffix) => suffixed_constant(*n, *suffix),
         Literal::String(s) => Constant::String(s.clone()),
         Literal::Boolean(b) => Constant::Bool(*b),
         Literal::Null => Constant::Null,
@@ -154,6 +188,12 @@ fn lower_identifier(lowerer: &mut AstLowerer, name: &str, expr: &Expr) -> Loweri
                     "identifier",
                 )
             })
+    } else if let Some(func_id) = lowerer.context.get_function(name) {
+        // A named function used as a value becomes a closure
+        super::closure::lower_function_value(lowerer, func_id, expr)
+    } else if let Some(constant) = stdlib_constant(name) {
+        // Standard library constants are inlined at each use
+        Ok(lowerer.builder.const_value(constant))
     } else {
         Err(type_error(
             format!("Undefined variable: {}", name),
@@ -163,6 +203,20 @@ fn lower_identifier(lowerer: &mut AstLowerer, name: &str, expr: &Expr) -> Loweri
     }
 }
 
+/// IR constant for a standard library constant such as `PI`
+fn stdlib_constant(name: &str) -> Option<Constant> {
+    match crate::stdlib::constants::constant_value(name)? {
+        ScriptValue::I32(n) => Some(Constant::I32(n)),
+        ScriptValue::I64(n) => Some(Constant::I64(n)),
+        ScriptValue::U32(n) => Some(Constant::U32(n)),
+        ScriptValue::U64(n) => Some(Constant::U64(n)),
+        ScriptValue::F32(n) => Some(Constant::F32(n)),
+        ScriptValue::F64(n) => Some(Constant::F64(n)),
+        ScriptValue::Bool(b) => Some(Constant::Bool(b)),
+        _ => None,
+    }
+}
+
 /// Lower a binary operation
 fn lower_binary(
     lowerer: &mut AstLowerer,
@@ -333,109 +387,233 @@ fn lower_unary(
     }
 }
 
-/// Lower a function call
-fn lower_call(lowerer: &mut AstLowerer, callee: &Expr, args: &[Expr]) -> LoweringResult<ValueId> {
-    // For now, only support direct function calls
-    if let ExprKind::Identifier(func_name) = &callee.kind {
-        // Lower arguments
-        let arg_values: Vec<ValueId> = args
-            .iter()
-            .map(|arg| lower_expression(lowerer, arg))
-            .collect::<Result<Vec<_>, _>>()?;
-
-        // Check if it's a built-in runtime function
-        if func_name == "print" {
-            // Special handling for print - generate a call to the runtime print function
-            if args.len() != 1 {
-                return Err(type_error(
-                    format!("print expects exactly 1 argument, got {}", args.len()),
-                    callee,
-                    "function call",
-                ));
-            }
-
-            // Get or register the script_print runtime function
-            let print_func_id = if let Some(id) = lowerer.context.get_function("script_print") {
-                id
-            } else {
-                // Register the runtime print function
-                // script_print is an external runtime function, not defined in Script code
-                // We just need a FunctionId to reference it
-                let func_id = lowerer.builder.module_mut().reserve_function_id();
-                lowerer
-                    .context
-                    .register_function("script_print".to_string(), func_id);
-                func_id
-            };
+/// Lower an explicit `cast<T>(value)` conversion
+///
+/// The conversion semantics (wrapping, truncation, saturation and rounding)
+/// are those of the IR `Cast` instruction.
+fn lower_cast(
+    lowerer: &mut AstLowerer,
+    inner_expr: &Expr,
+    target_type: &TypeAnn,
+    expr: &Expr,
+) -> LoweringResult<ValueId> {
+    let value = lower_expression(lowerer, inner_expr)?;
+    let from_ty = lowerer.get_expression_type(inner_expr)?;
+    let to_ty = lowerer.convert_type_annotation(target_type);
+    if from_ty == to_ty {
+        return Ok(value);
+    }
 
-            // The argument is the string value from the Script code
-            let string_value = arg_values[0];
+    lowerer
+        .builder
+        .build_cast(value, from_ty, to_ty)
+        .ok_or_else(|| runtime_error("Failed to build cast instruction", expr, "cast"))
+}
 
-            // Call script_print(string_ptr, len)
-            // We'll handle the Pascal-style string format in the code generation phase
-            // For now, just pass the string value and a dummy length
+/// Lower a function call
+fn lower_call(lowerer: &mut AstLowerer, callee: &Expr, args: &[Expr]) -> LoweringResult<ValueId> {
+    if let ExprKind::Member { object, property } = &callee.kind {
+        return lower_method_call(lowerer, object, property, args, callee);
+    }
 
-            // We need to determine the length based on the expression type
-            if let Some(string_expr) = &args.get(0) {
-                if let ExprKind::Literal(crate::parser::Literal::String(s)) = &string_expr.kind {
-                    // For string literals, we know the length at compile time
-                    let len = lowerer
-                        .builder
-                        .const_value(crate::ir::Constant::I32(s.len() as i32));
+    // Direct calls name a function, including associated functions named
+    // `Type::function`; anything else evaluates to a closure
+    let func_name = match &callee.kind {
+        ExprKind::Identifier(name) if lowerer.context.lookup_variable(name).is_none() => name,
+        _ => return super::closure::lower_closure_call(lowerer, callee, args),
+    };
 
-                    return lowerer
-                        .builder
-                        .build_call(print_func_id, vec![string_value, len], Type::Unknown)
-                        .ok_or_else(|| {
-                            runtime_error(
-                                "Failed to generate call to runtime print function",
-                                callee,
-                                "function call",
-                            )
-                        });
-                }
-            }
+    // Lower arguments
+    let arg_values: Vec<ValueId> = args
+        .iter()
+        .map(|arg| lower_expression(lowerer, arg))
+        .collect::<Result<Vec<_>, _>>()?;
 
-            // For non-string-literal arguments, we'll need to handle them differently
-            // For now, return an error
-            return Err(runtime_error(
-                "print function currently only supports string literals",
+    // Check if it's a built-in runtime function
+    if func_name == "print" {
+        // Special handling for print - generate a call to the runtime print function
+        if args.len() != 1 {
+            return Err(type_error(
+                format!("print expects exactly 1 argument, got {}", args.len()),
                 callee,
                 "function call",
             ));
         }
 
-        // Look up the function
-        if let Some(func_id) = lowerer.context.get_function(func_name) {
-            // Get the function's return type
-            let return_type = Type::Unknown; // TODO: Get actual return type
-
-            lowerer
-                .builder
-                .build_call(func_id, arg_values, return_type)
-                .ok_or_else(|| {
-                    runtime_error(
-                        format!("Failed to call function '{}'", func_name),
-                        callee,
-                        "function call",
-                    )
-                })
+        // Get or register the script_print runtime function
+        let print_func_id = if let Some(id) = lowerer.context.get_function("script_print") {
+            id
         } else {
-            Err(type_error(
-                format!("Function '{}' is not defined", func_name),
-                callee,
-                "function call",
-            ))
+            // Register the runtime print function
+            // script_print is an external runtime function, not defined in Script code
+            // We just need a FunctionId to reference it
+            let func_id = lowerer.builder.module_mut().reserve_function_id();
+            lowerer
+                .context
+                .register_function("script_print".to_string(), func_id);
+            func_id
+        };
+
+        // The argument is the string value from the Script code
+        let string_value = arg_values[0];
+
+        // Call script_print(string_ptr, len)
+        // We'll handle the Pascal-style string format in the code generation phase
+        // For now, just pass the string value and a dummy length
+
+        // We need to determine the length based on the expression type
+        if let Some(string_expr) = &args.get(0) {
+            if let ExprKind::Literal(crate::parser::Literal::String(s)) = &string_expr.kind {
+                // For string literals, we know the length at compile time
+                let len = lowerer
+                    .builder
+                    .const_value(crate::ir::Constant::I32(s.len() as i32));
+
+                return lowerer
+                    .builder
+                    .build_call(print_func_id, vec![string_value, len], Type::Unknown)
+                    .ok_or_else(|| {
+                        runtime_error(
+                            "Failed to generate call to runtime print function",
+                            callee,
+                            "function call",
+                        )
+                    });
+            }
         }
+
+        // For non-string-literal arguments, we'll need to handle them differently
+        // For now, return an error
+        return Err(runtime_error(
+            "print function currently only supports string literals",
+            callee,
+            "function call",
+        ));
+    }
+
+    // Look up the function
+    if let Some(func_id) = lowerer.context.get_function(func_name) {
+        let return_type = lowerer.function_return_type(func_id);
+
+        lowerer
+            .builder
+            .build_call(func_id, arg_values, return_type)
+            .ok_or_else(|| {
+                runtime_error(
+                    format!("Failed to call function '{}'", func_name),
+                    callee,
+                    "function call",
+                )
+            })
     } else {
         Err(type_error(
-            "Indirect function calls are not yet supported - use direct function names only",
+            format!("Function '{}' is not defined", func_name),
             callee,
             "function call",
         ))
     }
 }
 
+/// Lower `receiver.method(args)` to a call of the `Type::method` function
+/// lowered from the receiver type's impl block, passing the receiver as `self`
+fn lower_method_call(
+    lowerer: &mut AstLowerer,
+    object: &Expr,
+    method: &str,
+    args: &[Expr],
+    callee: &Expr,
+) -> LoweringResult<ValueId> {
+    let mut receiver_type = lowerer.get_expression_type(object)?;
+    // In a trait default method lowered for an impl, `Self` is the impl's type
+    if let (Type::TypeParam(param), Some(self_type)) = (&receiver_type, &lowerer.self_type) {
+        if param == "Self" {
+            receiver_type = Type::Named(self_type.clone());
+        }
+    }
+
+    // Methods called through a trait bound go through a stub that
+    // monomorphization resolves to the concrete impl
+    if let Type::TypeParam(param) = &receiver_type {
+        let func_id = lowerer.trait_method_stub(param, method).ok_or_else(|| {
+            type_error(
+                format!(
+                    "No trait bound on type parameter {} declares method '{}'",
+                    param, method
+                ),
+                callee,
+                "method call",
+            )
+        })?;
+        let mut arg_values = vec![lower_expression(lowerer, object)?];
+        for arg in args {
+            arg_values.push(lower_expression(lowerer, arg)?);
+        }
+        let return_type = lowerer.function_return_type(func_id);
+        return lowerer
+            .builder
+            .build_call(func_id, arg_values, return_type)
+            .ok_or_else(|| {
+                runtime_error(
+                    format!("Failed to call method '{}::{}'", param, method),
+                    callee,
+                    "method call",
+                )
+            });
+    }
+
+    let type_name = super::receiver_type_name(&receiver_type).ok_or_else(|| {
+        type_error(
+            format!(
+                "Cannot call method '{}' on a value of type {}",
+                method, receiver_type
+            ),
+            callee,
+            "method call",
+        )
+    })?;
+    let func_id = lowerer.method_function(type_name, method).ok_or_else(|| {
+        type_error(
+            format!("Type {} has no method '{}'", type_name, method),
+            callee,
+            "method call",
+        )
+    })?;
+
+    let takes_self = lowerer
+        .builder
+        .module()
+        .get_function(func_id)
+        .and_then(|func| func.params.first())
+        .is_some_and(|param| param.name == "self");
+    if !takes_self {
+        return Err(type_error(
+            format!(
+                "'{}::{}' is an associated function; call it as {}::{}()",
+                type_name, method, type_name, method
+            ),
+            callee,
+            "method call",
+        ));
+    }
+
+    let mut arg_values = vec![lower_expression(lowerer, object)?];
+    for arg in args {
+        arg_values.push(lower_expression(lowerer, arg)?);
+    }
+
+    let return_type = lowerer.function_return_type(func_id);
+    lowerer
+        .builder
+        .build_call(func_id, arg_values, return_type)
+        .ok_or_else(|| {
+            runtime_error(
+                format!("Failed to call method '{}::{}'", type_name, method),
+                callee,
+                "method call",
+            )
+        })
+}
+
 /// Lower an if expression
 fn lower_if(
     lowerer: &mut AstLowerer,
@@ -761,48 +939,27 @@ fn lower_member(
     let object_value = lower_expression(lowerer, object)?;
     let object_type = lowerer.get_expression_type(object)?;
 
-    // Implement member access similar to member assignment but for reading
-    match object_type {
-        Type::Named(type_name) => {
-            // For named types, generate field access using GetElementPtr
-            let field_offset = calculate_field_offset(&type_name, property)?;
-            let field_index = lowerer.builder.const_value(Constant::I32(field_offset));
-
-            let field_ptr = lowerer
-                .builder
-                .add_instruction(Instruction::GetElementPtr {
-                    ptr: object_value,
-                    index: field_index,
-                    elem_ty: Type::Unknown, // Will be resolved when object types are fully implemented
-                })
-                .ok_or_else(|| {
-                    runtime_error(
-                        format!(
-                            "Failed to access field '{}' on type '{}'",
-                            property, type_name
-                        ),
-                        expr,
-                        "member access",
-                    )
-                })?;
-
-            // Load the value from the field location
-            let field_value = lowerer
-                .builder
-                .add_instruction(Instruction::Load {
-                    ptr: field_ptr,
-                    ty: Type::Unknown, // Will be resolved when object types are fully implemented
-                })
-                .ok_or_else(|| {
-                    runtime_error(
-                        format!("Failed to load field '{}'", property),
-                        expr,
-                        "member access",
-                    )
-                })?;
+    if let Some((field_ptr, field_ty)) = struct_field_ptr(
+        lowerer,
+        object_value,
+        &object_type,
+        property,
+        expr,
+        "member access",
+    )? {
+        return lowerer
+            .builder
+            .build_load(field_ptr, field_ty)
+            .ok_or_else(|| {
+                runtime_error(
+                    format!("Failed to load field '{}'", property),
+                    expr,
+                    "member access",
+                )
+            });
+    }
 
-            Ok(field_value)
-        }
+    match object_type {
         Type::Unknown => {
             // SECURITY FIX: Replace vulnerable hash-based field access with secure validation
             // The previous implementation used hash-based offsets which allowed type confusion attacks
@@ -917,36 +1074,14 @@ fn lower_assign(lowerer: &mut AstLowerer, target: &Expr, value: &Expr) -> Loweri
             let object_value = lower_expression(lowerer, object)?;
             let object_type = lowerer.get_expression_type(object)?;
 
-            // For now, we'll implement member assignment for Named types and prepare for future object types
-            match object_type {
-                Type::Named(type_name) => {
-                    // For named types, we'll generate a field access using GetElementPtr
-                    // This assumes the object layout will be defined later
-                    // For now, we use a placeholder field offset calculation
-                    let field_offset = calculate_field_offset(&type_name, property)?;
-
-                    // Generate field pointer using GetElementPtr with field offset
-                    let field_index = lowerer.builder.const_value(Constant::I32(field_offset));
-                    let field_ptr = lowerer.builder.add_instruction(Instruction::GetElementPtr {
-                        ptr: object_value,
-                        index: field_index,
-                        elem_ty: Type::Unknown, // Will be resolved when object types are fully implemented
-                    }).ok_or_else(|| {
-                        runtime_error(
-                            format!("Failed to access field '{}' for assignment on type '{}'", property, type_name),
-                            target,
-                            "assignment"
-                        )
-                    })?;
-
-                    // Store the value to the field location
-                    lowerer.builder.add_instruction(Instruction::Store {
-                        ptr: field_ptr,
-                        value: value_id,
-                    });
+            if let Some((field_ptr, _)) =
+                struct_field_ptr(lowerer, object_value, &object_type, property, target, "assignment")?
+            {
+                lowerer.builder.build_store(field_ptr, value_id);
+                return Ok(value_id);
+            }
 
-                    Ok(value_id)
-                }
+            match object_type {
                 Type::Unknown => {
                     // For gradual typing, allow member assignment and defer validation to runtime
                     let field_hash = calculate_field_offset("unknown", property)? as i32;
@@ -1081,6 +1216,29 @@ fn lower_match(
 ) -> LoweringResult<ValueId> {
     // Evaluate the expression being matched
     let match_value = lower_expression(lowerer, expr)?;
+    let match_type = lowerer.get_expression_type(expr)?;
+
+    // Arms over variants of a declared enum switch on its tag, which is
+    // read once up front
+    let arm_variants: Vec<Option<VariantLayout>> = arms
+        .iter()
+        .map(|arm| match &binding_target(&arm.pattern).kind {
+            PatternKind::EnumConstructor {
+                enum_name, variant, ..
+            } => pattern_variant(lowerer, enum_name.as_deref(), variant, &match_type),
+            _ => None,
+        })
+        .collect();
+    let match_tag = if arm_variants.iter().any(Option::is_some) {
+        Some(
+            lowerer
+                .builder
+                .build_get_enum_tag(match_value)
+                .ok_or_else(|| runtime_error("Failed to read enum tag", expr, "match"))?,
+        )
+    } else {
+        None
+    };
 
     // Create blocks for the match arms and the final merge block
     let merge_block = lowerer
@@ -1129,37 +1287,77 @@ fn lower_match(
         // Set current block for pattern testing
         lowerer.builder.set_current_block(current_test_block);
 
-        // Test the pattern and generate conditional branch
-        let pattern_matches = lower_pattern_test(lowerer, &arm.pattern, match_value)?;
-
-        // Test guard if present
-        let final_condition = if let Some(guard) = &arm.guard {
-            let guard_value = lower_expression(lowerer, guard)?;
-            // AND the pattern match with the guard
-            lowerer
-                .builder
-                .build_binary(IrBinaryOp::And, pattern_matches, guard_value, Type::Bool)
-                .ok_or_else(|| Error::new(ErrorKind::RuntimeError, "Failed to build guard AND"))?
-        } else {
-            pattern_matches
+        // Test the pattern and generate conditional branch. For enum variants
+        // the tag is checked first so the payload is only read when it
+        // belongs to this variant.
+        let pattern_matches = match (
+            match_tag,
+            &arm_variants[i],
+            &binding_target(&arm.pattern).kind,
+        ) {
+            (Some(tag), Some(variant), PatternKind::EnumConstructor { args, .. }) => {
+                if let Some(next_block) = next_test_block {
+                    let is_variant = lower_tag_test(lowerer, tag, variant)?;
+                    let payload_block = lowerer
+                        .builder
+                        .create_block(format!("match.arm{}.payload", i))
+                        .ok_or_else(|| {
+                            Error::new(ErrorKind::RuntimeError, "Failed to create payload block")
+                        })?;
+                    lowerer
+                        .builder
+                        .build_cond_branch(is_variant, payload_block, next_block);
+                    lowerer.builder.set_current_block(payload_block);
+                }
+                lower_payload_test(lowerer, variant, args, match_value)?
+            }
+            _ => lower_pattern_test(lowerer, &arm.pattern, match_value)?,
         };
 
-        // Branch based on the final condition
-        if let Some(next_block) = next_test_block {
-            lowerer
-                .builder
-                .build_cond_branch(final_condition, arm_body_block, next_block);
-        } else {
-            // Last arm - if it doesn't match, we have a non-exhaustive match error
-            // For now, just branch to the body (assuming exhaustive matching)
-            lowerer.builder.build_branch(arm_body_block);
-        }
+        // Branch to the arm body, testing the guard if present. Pattern
+        // variables are bound before the guard so it can refer to them, and
+        // a failing guard falls through to the next arm.
+        let bound_in_guard = match (&arm.guard, next_test_block) {
+            (Some(guard), Some(next_block)) => {
+                let guard_block = lowerer
+                    .builder
+                    .create_block(format!("match.arm{}.guard", i))
+                    .ok_or_else(|| {
+                        Error::new(ErrorKind::RuntimeError, "Failed to create guard block")
+                    })?;
+                lowerer
+                    .builder
+                    .build_cond_branch(pattern_matches, guard_block, next_block);
+
+                lowerer.builder.set_current_block(guard_block);
+                bind_pattern_variables(lowerer, &arm.pattern, match_value)?;
+                let guard_value = lower_expression(lowerer, guard)?;
+                lowerer
+                    .builder
+                    .build_cond_branch(guard_value, arm_body_block, next_block);
+                true
+            }
+            (None, Some(next_block)) => {
+                lowerer
+                    .builder
+                    .build_cond_branch(pattern_matches, arm_body_block, next_block);
+                false
+            }
+            (_, None) => {
+                // Last arm - if it doesn't match, we have a non-exhaustive match error
+                // For now, just branch to the body (assuming exhaustive matching)
+                lowerer.builder.build_branch(arm_body_block);
+                false
+            }
+        };
 
         // Generate the arm body
         lowerer.builder.set_current_block(arm_body_block);
 
         // Bind pattern variables to the current scope
-        bind_pattern_variables(lowerer, &arm.pattern, match_value)?;
+        if !bound_in_guard {
+            bind_pattern_variables(lowerer, &arm.pattern, match_value)?;
+        }
 
         // Lower the arm body expression
         let arm_result = lower_expression(lowerer, &arm.body)?;
@@ -1217,6 +1415,9 @@ fn lower_pattern_test(
                         lowerer.builder.const_value(Constant::F32(*n as f32))
                     }
                 }
+                Literal::TypedNumber(n, suffix) => {
+                    lowerer.builder.const_value(suffixed_constant(*n, *suffix))
+                }
                 Literal::String(s) => lowerer.builder.const_value(Constant::String(s.clone())),
                 Literal::Boolean(b) => lowerer.builder.const_value(Constant::Bool(*b)),
                 Literal::Null => lowerer.builder.const_value(Constant::Null),
@@ -1302,6 +1503,31 @@ fn lower_pattern_test(
 
             Ok(result)
         }
+        PatternKind::Tuple(patterns) => {
+            // Test each element that can fail to match
+            let mut result = lowerer.builder.const_value(Constant::Bool(true));
+
+            for (i, sub_pattern) in patterns.iter().enumerate() {
+                if is_irrefutable(sub_pattern) {
+                    continue;
+                }
+
+                let element = extract_tuple_element(lowerer, value, i)?;
+                let sub_test = lower_pattern_test(lowerer, sub_pattern, element)?;
+                result = lowerer
+                    .builder
+                    .build_binary(IrBinaryOp::And, result, sub_test, Type::Bool)
+                    .ok_or_else(|| {
+                        Error::new(ErrorKind::RuntimeError, "Failed to build tuple pattern AND")
+                    })?;
+            }
+
+            Ok(result)
+        }
+        PatternKind::Binding { pattern, .. } => {
+            // The binding itself always matches; its sub-pattern decides
+            lower_pattern_test(lowerer, pattern, value)
+        }
         PatternKind::Or(patterns) => {
             // OR pattern - any sub-pattern can match
             let mut result = lowerer.builder.const_value(Constant::Bool(false));
@@ -1319,22 +1545,199 @@ fn lower_pattern_test(
             Ok(result)
         }
         PatternKind::EnumConstructor {
-            enum_name: _enum_name,
-            variant: _variant,
-            args: _args,
+            enum_name,
+            variant,
+            args,
         } => {
-            // For enum constructor patterns, we need to check if the value
-            // matches the specific enum variant
+            let Some(variant_layout) =
+                pattern_variant(lowerer, enum_name.as_deref(), variant, &Type::Unknown)
+            else {
+                // Enums not declared in this program, such as the stdlib
+                // Option and Result, are not checked yet
+                return Ok(lowerer.builder.const_value(Constant::Bool(true)));
+            };
 
-            // TODO: Implement proper enum variant matching
-            // For now, return a placeholder true value
-            Ok(lowerer.builder.const_value(Constant::Bool(true)))
+            // Compare the tag, then test the payload
+            let tag = lowerer
+                .builder
+                .build_get_enum_tag(value)
+                .ok_or_else(|| Error::new(ErrorKind::RuntimeError, "Failed to read enum tag"))?;
+            let is_variant = lower_tag_test(lowerer, tag, &variant_layout)?;
+            let payload_matches = lower_payload_test(lowerer, &variant_layout, args, value)?;
+            lowerer
+                .builder
+                .build_binary(IrBinaryOp::And, is_variant, payload_matches, Type::Bool)
+                .ok_or_else(|| {
+                    Error::new(ErrorKind::RuntimeError, "Failed to build enum pattern AND")
+                })
         }
     }
 }
 
-/// Calculate field offset for named types
-/// This is a placeholder implementation that will be enhanced when struct types are added
+/// Resolve the variant named by an enum constructor pattern
+fn pattern_variant(
+    lowerer: &mut AstLowerer,
+    enum_name: Option<&str>,
+    variant: &str,
+    ty: &Type,
+) -> Option<VariantLayout> {
+    if let Some(name) = enum_name {
+        lowerer.declare_builtin_enum(name);
+    }
+    lowerer
+        .resolve_enum(enum_name, variant, ty)
+        .and_then(|layout| layout.variant(variant))
+        .cloned()
+}
+
+/// Compare an enum tag against a variant's tag
+fn lower_tag_test(
+    lowerer: &mut AstLowerer,
+    tag: ValueId,
+    variant: &VariantLayout,
+) -> LoweringResult<ValueId> {
+    let expected = lowerer
+        .builder
+        .const_value(Constant::I32(variant.tag as i32));
+    lowerer
+        .builder
+        .build_compare(ComparisonOp::Eq, tag, expected)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::RuntimeError,
+                "Failed to build enum tag comparison",
+            )
+        })
+}
+
+/// Test the payload of a value already known to be `variant`
+///
+/// Bindings and wildcards always match, so their fields are not loaded here.
+fn lower_payload_test(
+    lowerer: &mut AstLowerer,
+    variant: &VariantLayout,
+    args: &Option<Vec<Pattern>>,
+    value: ValueId,
+) -> LoweringResult<ValueId> {
+    let mut result = lowerer.builder.const_value(Constant::Bool(true));
+
+    for (i, sub_pattern) in args.iter().flatten().enumerate() {
+        if is_irrefutable(sub_pattern) {
+            continue;
+        }
+
+        let field = extract_variant_field(lowerer, variant, value, i)?;
+        let sub_test = lower_pattern_test(lowerer, sub_pattern, field)?;
+        result = lowerer
+            .builder
+            .build_binary(IrBinaryOp::And, result, sub_test, Type::Bool)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::RuntimeError,
+                    "Failed to build payload pattern AND",
+                )
+            })?;
+    }
+
+    Ok(result)
+}
+
+/// The pattern a `name @ pattern` binding matches, looking through nested bindings
+fn binding_target(pattern: &Pattern) -> &Pattern {
+    match &pattern.kind {
+        PatternKind::Binding { pattern, .. } => binding_target(pattern),
+        _ => pattern,
+    }
+}
+
+/// Check if a pattern matches every value without testing it
+fn is_irrefutable(pattern: &Pattern) -> bool {
+    match &pattern.kind {
+        PatternKind::Wildcard | PatternKind::Identifier(_) => true,
+        PatternKind::Tuple(patterns) => patterns.iter().all(is_irrefutable),
+        PatternKind::Binding { pattern, .. } => is_irrefutable(pattern),
+        _ => false,
+    }
+}
+
+/// Load element `index` of a tuple value
+fn extract_tuple_element(
+    lowerer: &mut AstLowerer,
+    value: ValueId,
+    index: usize,
+) -> LoweringResult<ValueId> {
+    lowerer
+        .builder
+        .build_load_field(value, index.to_string(), Type::Unknown)
+        .ok_or_else(|| Error::new(ErrorKind::RuntimeError, "Failed to load tuple element"))
+}
+
+/// Load field `index` of an enum value's payload
+fn extract_variant_field(
+    lowerer: &mut AstLowerer,
+    variant: &VariantLayout,
+    value: ValueId,
+    index: usize,
+) -> LoweringResult<ValueId> {
+    let ty = variant.field_types.get(index).cloned().ok_or_else(|| {
+        Error::new(
+            ErrorKind::TypeError,
+            format!(
+                "Variant '{}' has {} field(s), pattern expects more",
+                variant.name,
+                variant.field_types.len()
+            ),
+        )
+    })?;
+    lowerer
+        .builder
+        .build_extract_enum_data(value, index as u32, ty)
+        .ok_or_else(|| Error::new(ErrorKind::RuntimeError, "Failed to extract enum payload"))
+}
+
+/// Pointer to a field of a struct value, with the field's type
+///
+/// Returns `None` if `object_type` is not a declared struct.
+fn struct_field_ptr(
+    lowerer: &mut AstLowerer,
+    object: ValueId,
+    object_type: &Type,
+    property: &str,
+    expr: &Expr,
+    operation: &str,
+) -> LoweringResult<Option<(ValueId, Type)>> {
+    let Some(layout) = lowerer.struct_layout(object_type) else {
+        return Ok(None);
+    };
+    let struct_name = layout.name.clone();
+    let field_ty = match layout.field(property) {
+        Some(field) => field.ty.clone(),
+        None => {
+            return Err(type_error(
+                format!("Struct '{}' has no field '{}'", struct_name, property),
+                expr,
+                operation,
+            ))
+        }
+    };
+
+    let field_ptr = lowerer
+        .builder
+        .build_get_field_ptr(object, property.to_string(), field_ty.clone())
+        .ok_or_else(|| {
+            runtime_error(
+                format!(
+                    "Failed to access field '{}' on struct '{}'",
+                    property, struct_name
+                ),
+                expr,
+                operation,
+            )
+        })?;
+    Ok(Some((field_ptr, field_ty)))
+}
+
+/// Calculate a placeholder field offset for values of unknown type
 fn calculate_field_offset(type_name: &str, field_name: &str) -> LoweringResult<i32> {
     // For now, use a simple hash-based offset calculation
     // This will be replaced with proper struct layout analysis
@@ -1370,27 +1773,8 @@ fn bind_pattern_variables(
             Ok(())
         }
         PatternKind::Identifier(name) => {
-            // Create a variable binding
-            let var_type = Type::Unknown; // TODO: Get actual type from pattern analysis
-            let var_ptr = lowerer
-                .builder
-                .build_alloc(var_type.clone())
-                .ok_or_else(|| {
-                    Error::new(
-                        ErrorKind::RuntimeError,
-                        "Failed to allocate pattern variable",
-                    )
-                })?;
-
-            // Store the matched value
-            lowerer.builder.build_store(var_ptr, value);
-
-            // Add to the lowering context
-            lowerer
-                .context
-                .define_variable(name.clone(), var_ptr, var_type);
-
-            Ok(())
+            // TODO: Get actual type from pattern analysis
+            bind_variable(lowerer, name, value, Type::Unknown)
         }
         PatternKind::Array(patterns) => {
             // Bind each element of the array pattern
@@ -1440,6 +1824,12 @@ fn bind_pattern_variables(
                     lowerer.builder.build_store(var_ptr, field_value);
 
                     // Add to the lowering context
+                    lowerer.builder.declare_variable(
+                        field_name.clone(),
+                        var_ptr,
+                        var_type.clone(),
+                        false,
+                    );
                     lowerer
                         .context
                         .define_variable(field_name.clone(), var_ptr, var_type);
@@ -1447,6 +1837,25 @@ fn bind_pattern_variables(
             }
             Ok(())
         }
+        PatternKind::Tuple(patterns) => {
+            // Destructure the tuple element by element
+            for (i, sub_pattern) in patterns.iter().enumerate() {
+                if matches!(
+                    sub_pattern.kind,
+                    PatternKind::Wildcard | PatternKind::Literal(_)
+                ) {
+                    continue;
+                }
+                let element = extract_tuple_element(lowerer, value, i)?;
+                bind_pattern_variables(lowerer, sub_pattern, element)?;
+            }
+            Ok(())
+        }
+        PatternKind::Binding { name, pattern } => {
+            // Bind the whole value, then whatever the sub-pattern binds
+            bind_variable(lowerer, name, value, Type::Unknown)?;
+            bind_pattern_variables(lowerer, pattern, value)
+        }
         PatternKind::Or(patterns) => {
             // For OR patterns, we would need to determine which pattern actually matched
             // This is complex and requires runtime support
@@ -1457,51 +1866,145 @@ fn bind_pattern_variables(
             Ok(())
         }
         PatternKind::EnumConstructor {
-            enum_name: _enum_name,
-            variant: _variant,
+            enum_name,
+            variant,
             args,
         } => {
-            // For enum constructor patterns, bind variables from the arguments
-            if let Some(pattern_args) = args {
-                for (_i, arg_pattern) in pattern_args.iter().enumerate() {
-                    // TODO: Extract the actual field values from the enum variant
-                    // For now, just use the original value (placeholder)
+            let Some(variant_layout) =
+                pattern_variant(lowerer, enum_name.as_deref(), variant, &Type::Unknown)
+            else {
+                // Enums not declared in this program have no known layout
+                for arg_pattern in args.iter().flatten() {
                     bind_pattern_variables(lowerer, arg_pattern, value)?;
                 }
+                return Ok(());
+            };
+
+            // Destructure the payload field by field
+            for (i, arg_pattern) in args.iter().flatten().enumerate() {
+                match &arg_pattern.kind {
+                    PatternKind::Wildcard | PatternKind::Literal(_) => {}
+                    PatternKind::Identifier(name) => {
+                        let field = extract_variant_field(lowerer, &variant_layout, value, i)?;
+                        let ty = variant_layout.field_types[i].clone();
+                        bind_variable(lowerer, name, field, ty)?;
+                    }
+                    _ => {
+                        let field = extract_variant_field(lowerer, &variant_layout, value, i)?;
+                        bind_pattern_variables(lowerer, arg_pattern, field)?;
+                    }
+                }
             }
             Ok(())
         }
     }
 }
 
+/// Bind a pattern variable to a matched value
+fn bind_variable(
+    lowerer: &mut AstLowerer,
+    name: &str,
+    value: ValueId,
+    var_type: Type,
+) -> LoweringResult<()> {
+    let var_ptr = lowerer
+        .builder
+        .build_alloc(var_type.clone())
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::RuntimeError,
+                "Failed to allocate pattern variable",
+            )
+        })?;
+
+    // Store the matched value
+    lowerer.builder.build_store(var_ptr, value);
+
+    // Add to the lowering context
+    lowerer
+        .builder
+        .declare_variable(name.to_string(), var_ptr, var_type.clone(), false);
+    lowerer
+        .context
+        .define_variable(name.to_string(), var_ptr, var_type);
+
+    Ok(())
+}
+
 /// Lower a struct constructor expression
+///
+/// The struct is allocated and each field is stored through its field
+/// pointer. Field initializers run in source order.
 fn lower_struct_constructor(
     lowerer: &mut AstLowerer,
     name: &str,
     fields: &[(String, Expr)],
     expr: &Expr,
 ) -> LoweringResult<ValueId> {
-    // Get the struct type from expression type info
-    let struct_type = lowerer.get_expression_type(expr)?;
+    let Some(layout) = lowerer.layouts.get_struct_layout(name) else {
+        return Err(type_error(
+            format!("Unknown struct '{}'", name),
+            expr,
+            "struct constructor",
+        ));
+    };
+    for (field_name, _) in fields {
+        if layout.field(field_name).is_none() {
+            return Err(type_error(
+                format!("Struct '{}' has no field '{}'", name, field_name),
+                expr,
+                "struct constructor",
+            ));
+        }
+    }
+    if let Some((missing, _)) = layout
+        .fields
+        .iter()
+        .find(|(field_name, _)| !fields.iter().any(|(given, _)| given == field_name))
+    {
+        return Err(type_error(
+            format!("Missing field '{}' in '{}' literal", missing, name),
+            expr,
+            "struct constructor",
+        ));
+    }
 
-    // Lower all field expressions
+    let struct_type = lowerer.get_expression_type(expr)?;
     let mut field_values = Vec::new();
     for (field_name, field_expr) in fields {
-        let field_value = lower_expression(lowerer, field_expr)?;
-        field_values.push((field_name.clone(), field_value));
+        field_values.push((field_name, lower_expression(lowerer, field_expr)?));
     }
 
-    // Build the struct construction instruction
-    lowerer
+    let struct_ptr = lowerer
         .builder
-        .build_construct_struct(name.to_string(), field_values, struct_type)
+        .build_alloc_struct(name.to_string(), struct_type.clone())
         .ok_or_else(|| {
             runtime_error(
-                format!("Failed to construct struct '{}'", name),
+                format!("Failed to allocate struct '{}'", name),
                 expr,
                 "struct constructor",
             )
-        })
+        })?;
+    for (field_name, value) in field_values {
+        let (field_ptr, _) = struct_field_ptr(
+            lowerer,
+            struct_ptr,
+            &struct_type,
+            field_name,
+            expr,
+            "struct constructor",
+        )?
+        .ok_or_else(|| {
+            runtime_error(
+                format!("'{}' is not a struct", name),
+                expr,
+                "struct constructor",
+            )
+        })?;
+        lowerer.builder.build_store(field_ptr, value);
+    }
+
+    Ok(struct_ptr)
 }
 
 /// Lower an enum constructor expression
@@ -1519,23 +2022,30 @@ fn lower_enum_constructor(
     let actual_enum_name = match enum_name {
         Some(name) => name.clone(),
         None => {
-            // Try to infer enum name from the type
+            // Try to infer enum name from the type, then from the declared enums
             match &enum_type {
                 Type::Named(name) => name.clone(),
                 Type::Generic { name, .. } => name.clone(),
-                _ => {
-                    return Err(type_error(
-                        "Cannot determine enum name for unqualified variant",
-                        expr,
-                        "enum constructor",
-                    ))
-                }
+                _ => match lowerer.layouts.find_variant_enum(variant) {
+                    Some(layout) => layout.name.clone(),
+                    None => {
+                        return Err(type_error(
+                            "Cannot determine enum name for unqualified variant",
+                            expr,
+                            "enum constructor",
+                        ))
+                    }
+                },
             }
         }
     };
 
+    lowerer.declare_builtin_enum(&actual_enum_name);
+
     // Special handling for Result and Option types - use stdlib constructors
+    // unless the program declares its own
     if (actual_enum_name == "Result" || actual_enum_name == "Option")
+        && lowerer.layouts.get_enum_layout(&actual_enum_name).is_none()
         && (variant == "Ok" || variant == "Err" || variant == "Some" || variant == "None")
     {
         // Lower argument expressions
@@ -1587,8 +2097,27 @@ fn lower_enum_constructor(
                 )
             })
     } else {
-        // For other enum types, use the standard enum construction
-        // Lower all argument expressions based on the variant type
+        let variant_layout = lowerer
+            .resolve_enum(Some(&actual_enum_name), variant, &enum_type)
+            .ok_or_else(|| {
+                type_error(
+                    format!("Unknown enum '{}'", actual_enum_name),
+                    expr,
+                    "enum constructor",
+                )
+            })?
+            .variant(variant)
+            .cloned()
+            .ok_or_else(|| {
+                type_error(
+                    format!("Enum '{}' has no variant '{}'", actual_enum_name, variant),
+                    expr,
+                    "enum constructor",
+                )
+            })?;
+
+        // The payload is stored in declaration order, whatever order the
+        // fields of a struct variant are written in
         let arg_values = match args {
             crate::parser::EnumConstructorArgs::Unit => Vec::new(),
             crate::parser::EnumConstructorArgs::Tuple(exprs) => {
@@ -1600,28 +2129,57 @@ fn lower_enum_constructor(
                 values
             }
             crate::parser::EnumConstructorArgs::Struct(fields) => {
-                // For struct variants, we need to lower fields in the correct order
-                // For now, we'll just lower them as a sequence of values
-                let mut values = Vec::new();
-                for (_, field_expr) in fields {
-                    let field_value = lower_expression(lowerer, field_expr)?;
-                    values.push(field_value);
+                let mut slots = vec![None; variant_layout.field_types.len()];
+                for (field_name, field_expr) in fields {
+                    let index = variant_layout.field_index(field_name).ok_or_else(|| {
+                        type_error(
+                            format!(
+                                "Variant '{}::{}' has no field '{}'",
+                                actual_enum_name, variant, field_name
+                            ),
+                            field_expr,
+                            "enum constructor",
+                        )
+                    })?;
+                    slots[index] = Some(lower_expression(lowerer, field_expr)?);
                 }
-                values
+                slots
+                    .into_iter()
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| {
+                        type_error(
+                            format!(
+                                "Missing fields in variant '{}::{}'",
+                                actual_enum_name, variant
+                            ),
+                            expr,
+                            "enum constructor",
+                        )
+                    })?
             }
         };
 
-        // Get the variant tag - for now, use a simple hash-based approach
-        // In a production implementation, this would look up the actual tag from the enum definition
-        let tag = calculate_variant_tag(&actual_enum_name, variant);
+        if arg_values.len() != variant_layout.field_types.len() {
+            return Err(type_error(
+                format!(
+                    "Variant '{}::{}' expects {} value(s), got {}",
+                    actual_enum_name,
+                    variant,
+                    variant_layout.field_types.len(),
+                    arg_values.len()
+                ),
+                expr,
+                "enum constructor",
+            ));
+        }
 
-        // Build the enum construction instruction
+        // Build the enum construction instruction: the tag plus the payload
         lowerer
             .builder
             .build_construct_enum(
                 actual_enum_name,
                 variant.to_string(),
-                tag,
+                variant_layout.tag,
                 arg_values,
                 enum_type,
             )
@@ -1635,19 +2193,6 @@ fn lower_enum_constructor(
     }
 }
 
-/// Calculate a tag value for an enum variant
-/// This is a placeholder implementation that will be replaced when proper enum definitions are available
-fn calculate_variant_tag(enum_name: &str, variant_name: &str) -> u32 {
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    use std::hash::Hasher;
-    hasher.write(enum_name.as_bytes());
-    hasher.write(variant_name.as_bytes());
-    let hash = hasher.finish();
-
-    // Use a limited range for tags
-    (hash % 256) as u32
-}
-
 /// Lower an error propagation expression (? operator)
 fn lower_error_propagation(
     lowerer: &mut AstLowerer,
@@ -1696,64 +2241,3 @@ fn lower_error_propagation(
             )
         })
 }
-
-/// Lower a closure expression to IR
-fn lower_closure(
-    lowerer: &mut AstLowerer,
-    parameters: &[ClosureParam],
-    body: &Expr,
-    expr: &Expr,
-) -> LoweringResult<ValueId> {
-    // Generate unique function ID for this closure
-    let function_id = format!("closure_{}", expr.id);
-
-    // Extract parameter names and types
-    let param_names: Vec<String> = parameters.iter().map(|p| p.name.clone()).collect();
-
-    let _param_types: Vec<Type> = parameters
-        .iter()
-        .map(|p| {
-            if let Some(ref type_ann) = p.type_ann {
-                crate::types::conversion::type_from_ast(type_ann)
-            } else {
-                Type::Unknown // Will be inferred
-            }
-        })
-        .collect();
-
-    // Lower the closure body
-    let _body_value = lower_expression(lowerer, body)?;
-
-    // Get captured variables from the closure captures map
-    let captures = lowerer
-        .closure_captures
-        .get(&expr.id)
-        .cloned()
-        .unwrap_or_default();
-
-    // Determine if any captures are by reference
-    let captures_by_ref = captures.iter().any(|(_, _, is_mutable)| *is_mutable);
-
-    // Convert captures to the format expected by the IR builder
-    // We need to look up the ValueId for each captured variable
-    let mut captured_vars: Vec<(String, crate::ir::ValueId)> = Vec::new();
-    for (name, _ty, _is_mutable) in captures {
-        // Look up the variable in the current context
-        if let Some(variable) = lowerer.context.lookup_variable(&name) {
-            captured_vars.push((name, variable.ptr));
-        } else {
-            // Variable not found in context - this shouldn't happen if semantic analysis passed
-            return Err(runtime_error(
-                format!("Captured variable '{}' not found in scope", name),
-                expr,
-                "closure capture",
-            ));
-        }
-    }
-
-    // Create the closure instruction
-    lowerer
-        .builder
-        .build_create_closure(function_id, param_names, captured_vars, captures_by_ref)
-        .ok_or_else(|| runtime_error("Failed to create closure instruction", expr, "closure"))
-}
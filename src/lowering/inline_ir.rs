@@ -0,0 +1,419 @@
+//! Inline IR
+//!
+//! Two escape hatches let expert code, such as performance-critical parts
+//! of the standard library written in Script, emit IR directly:
+//!
+//! ```text
+//! @intrinsic(popcnt)
+//! fn count_ones(x: i32) -> i32 {}
+//!
+//! @ir
+//! fn dot2(ax: f32, ay: f32, bx: f32, by: f32) -> f32 {
+//!     "%x = mul ax, bx"
+//!     "%r = fma ay, by, %x"
+//!     "ret %r"
+//! }
+//! ```
+//!
+//! An `@intrinsic(name)` function has an empty body and applies the
+//! intrinsic to its parameters. The body of an `@ir` function is a list of
+//! strings holding one instruction each, or several separated by `;`:
+//!
+//! ```text
+//! [%name =] op operand, ... [: type]
+//! ret [operand]
+//! ```
+//!
+//! `op` is a binary operation (`add`, `sub`, `mul`, `div`, `mod`, `and`,
+//! `or`), a comparison (`eq`, `ne`, `lt`, `le`, `gt`, `ge`), `neg`, `not`
+//! or an intrinsic from `ir::intrinsic`. Operands are `%name`s defined
+//! earlier, parameter names, or numeric literals, which are `i32` or `f64`
+//! unless suffixed like `1i64` or `0.5f32`. The result type follows from
+//! the operands unless stated. Both forms are checked by the IR verifier,
+//! so a mistake is a compile error rather than a miscompilation.
+
+use super::{AstLowerer, LoweringResult};
+use crate::error::{Error, ErrorKind};
+use crate::ir::{
+    intrinsic, BinaryOp, ComparisonOp, Constant, FunctionId, Instruction, Intrinsic, UnaryOp,
+    ValueId,
+};
+use crate::parser::{Attribute, Block, ExprKind, Literal, Stmt, StmtKind};
+use crate::types::Type;
+use std::collections::HashMap;
+
+/// Whether a function with these attributes is lowered from inline IR
+pub(super) fn is_inline_ir(attributes: &[Attribute]) -> bool {
+    attributes
+        .iter()
+        .any(|attr| attr.name == "intrinsic" || attr.name == "ir")
+}
+
+impl AstLowerer {
+    /// Lower the body of an `@intrinsic` or `@ir` function and verify it
+    pub(super) fn lower_inline_ir_function(
+        &mut self,
+        name: &str,
+        stmt: &Stmt,
+    ) -> LoweringResult<()> {
+        let StmtKind::Function { body, .. } = &stmt.kind else {
+            return Ok(());
+        };
+        let error = |message: String| {
+            Error::new(ErrorKind::CompilationError, message).with_location(stmt.span.start)
+        };
+        let func_id = self
+            .context
+            .get_function(name)
+            .ok_or_else(|| error(format!("Function '{}' not found", name)))?;
+        let entry = self
+            .builder
+            .module()
+            .get_function(func_id)
+            .and_then(|function| function.entry_block)
+            .ok_or_else(|| error(format!("Function '{}' has no entry block", name)))?;
+        self.builder.set_current_function(func_id);
+        self.builder.set_current_block(entry);
+        let previous_location = self.builder.set_location(Some(stmt.span));
+
+        let result = match stmt.attributes.iter().find(|attr| attr.name == "intrinsic") {
+            Some(attr) => self.lower_intrinsic_body(func_id, attr, body),
+            None => ir_lines(body).and_then(|lines| self.lower_ir_body(func_id, &lines)),
+        };
+        self.builder.set_location(previous_location);
+        result.map_err(|message| error(format!("In '{}': {}", name, message)))?;
+
+        let function = self.builder.module().get_function(func_id);
+        if let Some(function) = function {
+            intrinsic::verify(function).map_err(error)?;
+        }
+        Ok(())
+    }
+
+    /// The body of `@intrinsic(name)`: the intrinsic of the parameters
+    fn lower_intrinsic_body(
+        &mut self,
+        func_id: FunctionId,
+        attr: &Attribute,
+        body: &Block,
+    ) -> Result<(), String> {
+        if !body.statements.is_empty() || body.final_expr.is_some() {
+            return Err("an @intrinsic function must have an empty body".to_string());
+        }
+        let name = attr.args.first().map(|arg| arg.trim()).unwrap_or("");
+        let intrinsic =
+            Intrinsic::from_name(name).ok_or_else(|| format!("'{}' is not an intrinsic", name))?;
+
+        let (params, return_type) = self.signature(func_id);
+        let args = (0..params.len()).map(param_value).collect();
+        let ty = intrinsic
+            .has_result()
+            .then_some(return_type)
+            .filter(|ty| *ty != Type::Unknown);
+        let result = self.builder.add_instruction(Instruction::Intrinsic {
+            intrinsic,
+            args,
+            ty: ty.clone(),
+        });
+        self.builder.build_return(ty.and(result));
+        Ok(())
+    }
+
+    /// The body of an `@ir` function
+    fn lower_ir_body(&mut self, func_id: FunctionId, lines: &[String]) -> Result<(), String> {
+        let (params, return_type) = self.signature(func_id);
+        let mut values: HashMap<String, (ValueId, Type)> = params
+            .iter()
+            .enumerate()
+            .map(|(i, (name, ty))| (name.clone(), (param_value(i), ty.clone())))
+            .collect();
+
+        let mut returned = false;
+        for (number, line) in lines.iter().enumerate() {
+            let line_error = |message: String| format!("IR line {}: {}", number + 1, message);
+            if returned {
+                return Err(line_error("instructions after 'ret'".to_string()));
+            }
+            let (line, stated) = match line.split_once(':') {
+                Some((line, ty)) => (line, Some(parse_type(ty.trim()).map_err(line_error)?)),
+                None => (line.as_str(), None),
+            };
+            let (target, rest) = match line.split_once('=') {
+                Some((target, rest)) => {
+                    let target = target.trim();
+                    let name = target
+                        .strip_prefix('%')
+                        .filter(|name| is_name(name))
+                        .ok_or_else(|| line_error(format!("'{}' is not a %name", target)))?;
+                    (Some(name.to_string()), rest.trim())
+                }
+                None => (None, line.trim()),
+            };
+            let (op, operands) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let mut args = Vec::new();
+            for operand in operands.split(',').map(str::trim).filter(|o| !o.is_empty()) {
+                args.push(self.ir_operand(operand, &values).map_err(line_error)?);
+            }
+
+            if op == "ret" {
+                if target.is_some() || args.len() > 1 {
+                    return Err(line_error("'ret' takes at most one operand".to_string()));
+                }
+                if let Some((_, ty)) = args.first() {
+                    if return_type != Type::Unknown && *ty != Type::Unknown && *ty != return_type {
+                        return Err(line_error(format!(
+                            "returns {}, but the function returns {}",
+                            ty, return_type
+                        )));
+                    }
+                }
+                self.builder
+                    .build_return(args.first().map(|(value, _)| *value));
+                returned = true;
+                continue;
+            }
+
+            let (instruction, ty) = ir_instruction(op, &args, stated).map_err(line_error)?;
+            let value = self.builder.add_instruction(instruction);
+            match (target, value, ty) {
+                (Some(name), Some(value), Some(ty)) => {
+                    values.insert(name, (value, ty));
+                }
+                (Some(_), _, None) => {
+                    return Err(line_error(format!("'{}' does not produce a value", op)));
+                }
+                _ => {}
+            }
+        }
+
+        if !returned {
+            return Err("inline IR must end with 'ret'".to_string());
+        }
+        Ok(())
+    }
+
+    /// A `%name`, parameter name or literal operand with its type
+    fn ir_operand(
+        &mut self,
+        operand: &str,
+        values: &HashMap<String, (ValueId, Type)>,
+    ) -> Result<(ValueId, Type), String> {
+        let name = operand.strip_prefix('%').unwrap_or(operand);
+        if let Some(value) = values.get(name) {
+            return Ok(value.clone());
+        }
+        if operand.starts_with('%') || is_name(operand) {
+            return Err(format!("'{}' is not defined", operand));
+        }
+        let constant = parse_literal(operand)?;
+        let ty = constant_type(&constant);
+        Ok((self.builder.const_value(constant), ty))
+    }
+
+    /// Parameter names and types and the return type of a function
+    fn signature(&self, func_id: FunctionId) -> (Vec<(String, Type)>, Type) {
+        self.builder
+            .module()
+            .get_function(func_id)
+            .map(|function| {
+                let params = function
+                    .params
+                    .iter()
+                    .map(|param| (param.name.clone(), param.ty.clone()))
+                    .collect();
+                (params, function.return_type.clone())
+            })
+            .unwrap_or((Vec::new(), Type::Unknown))
+    }
+}
+
+/// The instruction of `op` applied to `args`, with its result type
+fn ir_instruction(
+    op: &str,
+    args: &[(ValueId, Type)],
+    stated: Option<Type>,
+) -> Result<(Instruction, Option<Type>), String> {
+    let operand_count = |count: usize| {
+        if args.len() == count {
+            Ok(())
+        } else {
+            Err(format!(
+                "'{}' takes {} operands, not {}",
+                op,
+                count,
+                args.len()
+            ))
+        }
+    };
+    let first_type = || {
+        args.iter()
+            .map(|(_, ty)| ty.clone())
+            .find(|ty| *ty != Type::Unknown)
+            .unwrap_or(Type::Unknown)
+    };
+
+    if let Some(op) = binary_op(op) {
+        operand_count(2)?;
+        let ty = stated.unwrap_or_else(first_type);
+        let instruction = Instruction::Binary {
+            op,
+            lhs: args[0].0,
+            rhs: args[1].0,
+            ty: ty.clone(),
+        };
+        return Ok((instruction, Some(ty)));
+    }
+    if let Some(op) = comparison_op(op) {
+        operand_count(2)?;
+        let instruction = Instruction::Compare {
+            op,
+            lhs: args[0].0,
+            rhs: args[1].0,
+        };
+        return Ok((instruction, Some(Type::Bool)));
+    }
+    if let Some(op) = unary_op(op) {
+        operand_count(1)?;
+        let ty = stated.unwrap_or_else(first_type);
+        let instruction = Instruction::Unary {
+            op,
+            operand: args[0].0,
+            ty: ty.clone(),
+        };
+        return Ok((instruction, Some(ty)));
+    }
+
+    let intrinsic =
+        Intrinsic::from_name(op).ok_or_else(|| format!("unknown operation '{}'", op))?;
+    let arg_types: Vec<Type> = args.iter().map(|(_, ty)| ty.clone()).collect();
+    let ty = match stated {
+        Some(ty) if intrinsic.has_result() => Some(ty),
+        _ => intrinsic.result_type(&arg_types),
+    };
+    let instruction = Instruction::Intrinsic {
+        intrinsic,
+        args: args.iter().map(|(value, _)| *value).collect(),
+        ty: ty.clone(),
+    };
+    Ok((instruction, ty))
+}
+
+/// The instructions of an `@ir` body, which may only hold strings
+fn ir_lines(body: &Block) -> Result<Vec<String>, String> {
+    let exprs = body
+        .statements
+        .iter()
+        .map(|stmt| match &stmt.kind {
+            StmtKind::Expression(expr) => Some(expr),
+            _ => None,
+        })
+        .chain(body.final_expr.as_deref().map(Some));
+
+    let mut lines = Vec::new();
+    for expr in exprs {
+        let Some(ExprKind::Literal(Literal::String(text))) = expr.map(|expr| &expr.kind) else {
+            return Err("the body of an @ir function may only contain IR strings".to_string());
+        };
+        lines.extend(
+            text.split(';')
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from),
+        );
+    }
+    Ok(lines)
+}
+
+/// Parameters are referred to by `ValueId(1000 + index)`, as in
+/// `lower_function`
+fn param_value(index: usize) -> ValueId {
+    ValueId(index as u32 + 1000)
+}
+
+fn is_name(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn binary_op(op: &str) -> Option<BinaryOp> {
+    Some(match op {
+        "add" => BinaryOp::Add,
+        "sub" => BinaryOp::Sub,
+        "mul" => BinaryOp::Mul,
+        "div" => BinaryOp::Div,
+        "mod" => BinaryOp::Mod,
+        "and" => BinaryOp::And,
+        "or" => BinaryOp::Or,
+        _ => return None,
+    })
+}
+
+fn comparison_op(op: &str) -> Option<ComparisonOp> {
+    Some(match op {
+        "eq" => ComparisonOp::Eq,
+        "ne" => ComparisonOp::Ne,
+        "lt" => ComparisonOp::Lt,
+        "le" => ComparisonOp::Le,
+        "gt" => ComparisonOp::Gt,
+        "ge" => ComparisonOp::Ge,
+        _ => return None,
+    })
+}
+
+fn unary_op(op: &str) -> Option<UnaryOp> {
+    match op {
+        "neg" => Some(UnaryOp::Neg),
+        "not" => Some(UnaryOp::Not),
+        _ => None,
+    }
+}
+
+fn parse_type(name: &str) -> Result<Type, String> {
+    Ok(match name {
+        "i32" => Type::I32,
+        "i64" => Type::I64,
+        "u32" => Type::U32,
+        "u64" => Type::U64,
+        "f32" => Type::F32,
+        "f64" => Type::F64,
+        "bool" => Type::Bool,
+        intrinsic::F32X4 | intrinsic::I32X4 => Type::Named(name.to_string()),
+        _ => return Err(format!("'{}' is not an IR type", name)),
+    })
+}
+
+fn parse_literal(text: &str) -> Result<Constant, String> {
+    let invalid = || format!("'{}' is not a number", text);
+    for suffix in ["i32", "i64", "u32", "u64", "f32", "f64"] {
+        let Some(number) = text.strip_suffix(suffix) else {
+            continue;
+        };
+        return match suffix {
+            "i32" => number.parse().map(Constant::I32).map_err(|_| invalid()),
+            "i64" => number.parse().map(Constant::I64).map_err(|_| invalid()),
+            "u32" => number.parse().map(Constant::U32).map_err(|_| invalid()),
+            "u64" => number.parse().map(Constant::U64).map_err(|_| invalid()),
+            "f32" => number.parse().map(Constant::F32).map_err(|_| invalid()),
+            _ => number.parse().map(Constant::F64).map_err(|_| invalid()),
+        };
+    }
+    match text.parse::<i32>() {
+        Ok(n) => Ok(Constant::I32(n)),
+        Err(_) => text.parse().map(Constant::F64).map_err(|_| invalid()),
+    }
+}
+
+fn constant_type(constant: &Constant) -> Type {
+    match constant {
+        Constant::I32(_) => Type::I32,
+        Constant::I64(_) => Type::I64,
+        Constant::U32(_) => Type::U32,
+        Constant::U64(_) => Type::U64,
+        Constant::F32(_) => Type::F32,
+        Constant::F64(_) => Type::F64,
+        _ => Type::Unknown,
+    }
+}
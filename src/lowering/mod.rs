@@ -5,17 +5,29 @@
 //! into simpler IR instructions.
 
 use crate::error::{Error, ErrorKind};
-use crate::ir::{Constant, Instruction, IrBuilder, Module as IrModule, Parameter, ValueId};
-use crate::parser::{Block, Expr, Program, Stmt, StmtKind};
+use crate::ir::{
+    Constant, EnumLayout, FunctionId, GenericDispatch, Instruction, IrBuilder, LayoutCalculator,
+    Module as IrModule, Parameter, StructLayout, TraitMethodRef, ValueId,
+};
+use crate::parser::{
+    Attribute, Block, ExportKind, Expr, GenericParams, ImplBlock, Method, Param, Program, Stmt,
+    StmtKind, TraitDecl, TypeAnn, TypeKind,
+};
+use crate::runtime::coverage::CoverageMap;
 use crate::semantic::{analyzer::GenericInstantiation, SymbolTable};
+use crate::types::definitions::{EnumDefinition, StructDefinition};
 use crate::types::Type;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::mem;
 
 pub mod async_transform;
 pub mod async_transform_secure;
+pub mod closure;
 pub mod context;
+mod coverage;
 pub mod expr;
+mod inline_ir;
 pub mod stmt;
 
 pub use async_transform::{transform_async_function, AsyncTransformInfo};
@@ -38,6 +50,25 @@ pub struct AstLowerer {
     generic_instantiations: Vec<GenericInstantiation>,
     /// Capture information for closures (maps closure expression ID to captures)
     closure_captures: HashMap<usize, Vec<(String, Type, bool)>>, // (name, type, is_mutable)
+    /// Layouts of the structs and enums declared in the program
+    layouts: LayoutCalculator,
+    /// Trait declarations, for the default methods impls inherit
+    traits: HashMap<String, TraitDecl>,
+    /// Type of the impl whose method is being lowered, which `Self` refers to
+    self_type: Option<String>,
+    /// Trait bounds of the type parameters of the function being lowered
+    type_param_bounds: HashMap<String, Vec<String>>,
+    /// Lines covered by each coverage counter, when inserting counters
+    coverage: Option<CoverageMap>,
+}
+
+/// Where a for-loop gets its items from when iterating with `next()`
+#[derive(Debug, Clone, Copy)]
+enum IteratorSource {
+    /// A user-defined `next` method returning `Option<T>`
+    Method(FunctionId),
+    /// A stdlib `Iterator` value
+    Stdlib,
 }
 
 impl AstLowerer {
@@ -55,9 +86,25 @@ impl AstLowerer {
             type_info,
             generic_instantiations,
             closure_captures,
+            layouts: LayoutCalculator::new(),
+            traits: HashMap::new(),
+            self_type: None,
+            type_param_bounds: HashMap::new(),
+            coverage: None,
         }
     }
 
+    /// Insert a coverage probe at the start of every basic block
+    pub fn enable_coverage(&mut self) {
+        self.coverage = Some(CoverageMap::new());
+    }
+
+    /// Source lines of the coverage counters in the lowered program, if
+    /// coverage is enabled
+    pub fn coverage_map(&self) -> Option<&CoverageMap> {
+        self.coverage.as_ref()
+    }
+
     /// Get the generic instantiations for monomorphization
     pub fn generic_instantiations(&self) -> &[GenericInstantiation] {
         &self.generic_instantiations
@@ -73,10 +120,62 @@ impl AstLowerer {
         self.get_expression_type(expr)
     }
 
+    /// Declare the functions `program` defines, so calls to them lower to
+    /// declarations that [`link_modules`](crate::ir::link_modules) binds to
+    /// the definitions
+    ///
+    /// Call before [`lower_program`](Self::lower_program) when `program` is
+    /// another module lowered on its own.
+    pub fn declare_functions(&mut self, program: &Program) {
+        let (statements, _) = unwrap_exports(&program.statements);
+        for stmt in &statements {
+            if let StmtKind::Function {
+                name,
+                params,
+                ret_type,
+                is_async,
+                ..
+            } = &stmt.kind
+            {
+                let ir_params: Vec<Parameter> = params
+                    .iter()
+                    .map(|p| Parameter {
+                        name: p.name.clone(),
+                        ty: self.convert_type_annotation(&p.type_ann),
+                    })
+                    .collect();
+                let mut return_type = ret_type
+                    .as_ref()
+                    .map(|t| self.convert_type_annotation(t))
+                    .unwrap_or(Type::Unknown);
+                if *is_async {
+                    return_type = Type::Future(Box::new(return_type));
+                }
+
+                let func_id =
+                    self.builder
+                        .module_mut()
+                        .create_function(name.clone(), ir_params, return_type);
+                self.context.register_function(name.clone(), func_id);
+            }
+        }
+    }
+
     /// Lower a program to IR
     pub fn lower_program(&mut self, program: &Program) -> LoweringResult<IrModule> {
+        // Exported declarations are lowered like their unexported forms
+        let (statements, exported) = unwrap_exports(&program.statements);
+
+        // Traits first, so impls declared before their trait inherit its defaults
+        for stmt in &statements {
+            if let StmtKind::Trait(trait_decl) = &stmt.kind {
+                self.traits
+                    .insert(trait_decl.name.clone(), trait_decl.clone());
+            }
+        }
+
         // First pass: collect all function declarations
-        for stmt in &program.statements {
+        for stmt in &statements {
             if let StmtKind::Function {
                 name,
                 params,
@@ -112,13 +211,22 @@ impl AstLowerer {
                     self.builder
                         .create_function(name.clone(), ir_params, return_type)
                 };
+                if let Some(function) = self.builder.module_mut().get_function_mut(func_id) {
+                    function.generic_dispatch = generic_dispatch(&stmt.attributes);
+                    function.span = Some(stmt.span);
+                }
                 self.context.register_function(name.clone(), func_id);
+            } else if let StmtKind::Impl(impl_block) = &stmt.kind {
+                self.declare_impl_methods(impl_block);
+            } else if let StmtKind::Struct { .. } = &stmt.kind {
+                self.declare_struct(stmt);
+            } else if let StmtKind::Enum { .. } = &stmt.kind {
+                self.declare_enum(stmt);
             }
         }
 
         // Transform async functions into state machines
-        let async_functions: Vec<_> = program
-            .statements
+        let async_functions: Vec<_> = statements
             .iter()
             .filter_map(|stmt| match &stmt.kind {
                 StmtKind::Function { name, is_async, .. } if *is_async => {
@@ -141,12 +249,30 @@ impl AstLowerer {
         }
 
         // Second pass: lower function bodies and global statements
-        for stmt in &program.statements {
+        for stmt in &statements {
             match &stmt.kind {
+                StmtKind::Function { name, .. } if inline_ir::is_inline_ir(&stmt.attributes) => {
+                    self.lower_inline_ir_function(name, stmt)?;
+                }
                 StmtKind::Function {
-                    name, params, body, ..
+                    name,
+                    params,
+                    body,
+                    generic_params,
+                    ..
                 } => {
+                    self.type_param_bounds = type_param_bounds(generic_params.as_ref());
                     self.lower_function(name, params, body)?;
+                    self.type_param_bounds.clear();
+                }
+                StmtKind::Impl(impl_block) => {
+                    self.self_type = Some(impl_block.type_name.clone());
+                    for method in self.impl_methods(impl_block) {
+                        let params = method_params(impl_block, &method);
+                        let name = format!("{}::{}", impl_block.type_name, method.name);
+                        self.lower_function(&name, &params, &method.body)?;
+                    }
+                    self.self_type = None;
                 }
                 _ => {
                     // Global statements go into a special main function
@@ -159,7 +285,251 @@ impl AstLowerer {
         // Finalize main function if it has statements
         self.finalize_main_function();
 
-        Ok(mem::replace(&mut self.builder, IrBuilder::new()).build())
+        for name in exported {
+            if self.context.get_function(&name).is_some() {
+                self.builder.module_mut().mark_exported(name);
+            }
+        }
+
+        let mut module = mem::replace(&mut self.builder, IrBuilder::new()).build();
+        if let Some(map) = &mut self.coverage {
+            crate::ir::instrument(&mut module, &mut coverage::CoveragePolicy { map });
+        }
+
+        Ok(module)
+    }
+
+    /// Declare the methods of an impl block as `Type::method` functions
+    fn declare_impl_methods(&mut self, impl_block: &ImplBlock) {
+        for method in &self.impl_methods(impl_block) {
+            let ir_params: Vec<Parameter> = method_params(impl_block, method)
+                .iter()
+                .map(|p| Parameter {
+                    name: p.name.clone(),
+                    ty: self.convert_type_annotation(&p.type_ann),
+                })
+                .collect();
+            let return_type = method
+                .ret_type
+                .as_ref()
+                .map(|t| self.convert_type_annotation(&self_to_type(t, &impl_block.type_name)))
+                .unwrap_or(Type::Unknown);
+
+            let name = format!("{}::{}", impl_block.type_name, method.name);
+            let func_id = self
+                .builder
+                .create_function(name.clone(), ir_params, return_type);
+            if let Some(function) = self.builder.module_mut().get_function_mut(func_id) {
+                function.span = Some(method.span);
+            }
+            self.context.register_function(name, func_id);
+        }
+    }
+
+    /// Methods of an impl block, plus the trait defaults it doesn't override
+    fn impl_methods(&self, impl_block: &ImplBlock) -> Vec<Method> {
+        let mut methods = impl_block.methods.clone();
+        let trait_decl = impl_block
+            .trait_name
+            .as_ref()
+            .and_then(|name| self.traits.get(name));
+        if let Some(trait_decl) = trait_decl {
+            for trait_method in &trait_decl.methods {
+                if methods.iter().any(|m| m.name == trait_method.name) {
+                    continue;
+                }
+                if let Some(method) = trait_method.default_method() {
+                    methods.push(method);
+                }
+            }
+        }
+        methods
+    }
+
+    /// Find or create the stub standing in for a trait method called on a
+    /// value of generic type `type_param`
+    ///
+    /// The stub is named `<T as Trait>::method` and has no body;
+    /// monomorphization redirects calls to it to the concrete impl.
+    fn trait_method_stub(&mut self, type_param: &str, method: &str) -> Option<FunctionId> {
+        let bounds = self.type_param_bounds.get(type_param)?;
+        let (trait_name, trait_method) = bounds.iter().find_map(|bound| {
+            let trait_decl = self.traits.get(bound)?;
+            let trait_method = trait_decl.methods.iter().find(|m| m.name == method)?;
+            Some((bound.clone(), trait_method.clone()))
+        })?;
+
+        let name = format!("<{} as {}>::{}", type_param, trait_name, method);
+        if let Some(func_id) = self.context.get_function(&name) {
+            return Some(func_id);
+        }
+
+        let param_kind = TypeKind::TypeParam(type_param.to_string());
+        let params: Vec<Parameter> = trait_method
+            .params
+            .iter()
+            .map(|p| Parameter {
+                name: p.name.clone(),
+                ty: self.convert_type_annotation(&replace_self(&p.type_ann, &param_kind)),
+            })
+            .collect();
+        let return_type = trait_method
+            .ret_type
+            .as_ref()
+            .map(|t| self.convert_type_annotation(&replace_self(t, &param_kind)))
+            .unwrap_or(Type::Unknown);
+
+        let module = self.builder.module_mut();
+        let func_id = module.create_function(name.clone(), params, return_type);
+        if let Some(function) = module.get_function_mut(func_id) {
+            function.trait_method = Some(TraitMethodRef {
+                type_param: type_param.to_string(),
+                trait_name,
+                method: method.to_string(),
+            });
+        }
+        self.context.register_function(name, func_id);
+        Some(func_id)
+    }
+
+    /// Compute the field layout of a struct and record it in the module
+    fn declare_struct(&mut self, stmt: &Stmt) {
+        if let StmtKind::Struct {
+            name,
+            generic_params,
+            fields,
+            where_clause,
+        } = &stmt.kind
+        {
+            let definition = StructDefinition {
+                name: name.clone(),
+                generic_params: generic_params
+                    .as_ref()
+                    .map(crate::types::generics::GenericParams::from_ast),
+                fields: fields.clone(),
+                where_clause: where_clause.clone(),
+                span: stmt.span,
+                is_monomorphized: false,
+                original_type: None,
+            };
+            let layout = self.layouts.calculate_struct_layout(&definition);
+            self.builder.module_mut().add_struct(layout);
+        }
+    }
+
+    /// Layout of the struct a value of type `ty` points to
+    fn struct_layout(&self, ty: &Type) -> Option<&StructLayout> {
+        match ty {
+            Type::Named(name) | Type::Struct { name, .. } => self.layouts.get_struct_layout(name),
+            Type::Reference { inner, .. } => self.struct_layout(inner),
+            _ => None,
+        }
+    }
+
+    /// Compute the tagged-union layout of an enum and record it in the module
+    fn declare_enum(&mut self, stmt: &Stmt) {
+        if let StmtKind::Enum {
+            name,
+            generic_params,
+            variants,
+            where_clause,
+        } = &stmt.kind
+        {
+            let definition = EnumDefinition {
+                name: name.clone(),
+                generic_params: generic_params
+                    .as_ref()
+                    .map(crate::types::generics::GenericParams::from_ast),
+                variants: variants.clone(),
+                where_clause: where_clause.clone(),
+                span: stmt.span,
+                is_monomorphized: false,
+                original_type: None,
+            };
+            let layout = self.layouts.calculate_enum_layout(&definition);
+            self.builder.module_mut().add_enum(layout);
+        }
+    }
+
+    /// Declare the layout of a built-in enum, such as the stdlib `ErrorKind`,
+    /// the first time the program names it
+    ///
+    /// Declaring built-in enums on demand keeps their variants from making
+    /// unqualified variants of the program's own enums ambiguous.
+    fn declare_builtin_enum(&mut self, name: &str) {
+        if name != crate::stdlib::error::ErrorKind::TYPE_NAME
+            || self.layouts.get_enum_layout(name).is_some()
+        {
+            return;
+        }
+
+        let span = crate::source::Span::single(crate::source::SourceLocation::initial());
+        let definition = EnumDefinition {
+            name: name.to_string(),
+            generic_params: None,
+            variants: crate::stdlib::error::ErrorKind::ALL
+                .iter()
+                .map(|kind| crate::parser::EnumVariant {
+                    name: kind.name().to_string(),
+                    fields: crate::parser::EnumVariantFields::Unit,
+                    span,
+                })
+                .collect(),
+            where_clause: None,
+            span,
+            is_monomorphized: false,
+            original_type: None,
+        };
+        let layout = self.layouts.calculate_enum_layout(&definition);
+        self.builder.module_mut().add_enum(layout);
+    }
+
+    /// Resolve the enum a variant belongs to
+    ///
+    /// Unqualified variants are looked up through the type of the value or,
+    /// failing that, the only enum declaring a variant of that name.
+    fn resolve_enum(
+        &self,
+        enum_name: Option<&str>,
+        variant: &str,
+        ty: &Type,
+    ) -> Option<&EnumLayout> {
+        match enum_name {
+            Some(name) => self.layouts.get_enum_layout(name),
+            None => match ty {
+                Type::Named(name) | Type::Generic { name, .. } => {
+                    self.layouts.get_enum_layout(name)
+                }
+                _ => None,
+            }
+            .or_else(|| self.layouts.find_variant_enum(variant)),
+        }
+    }
+
+    /// Find the function lowered for `Type::method`
+    fn method_function(&self, type_name: &str, method: &str) -> Option<FunctionId> {
+        self.context
+            .get_function(&format!("{}::{}", type_name, method))
+    }
+
+    /// Declared return type of a lowered function
+    fn function_return_type(&self, func_id: FunctionId) -> Type {
+        self.builder
+            .module()
+            .get_function(func_id)
+            .map(|func| func.return_type.clone())
+            .unwrap_or(Type::Unknown)
+    }
+
+    /// Set the return type of the function being lowered
+    fn set_function_return_type(&mut self, return_type: Type) {
+        if let Some(func) = self
+            .builder
+            .current_function()
+            .and_then(|id| self.builder.module_mut().get_function_mut(id))
+        {
+            func.return_type = return_type;
+        }
     }
 
     /// Lower a function body
@@ -179,6 +549,14 @@ impl AstLowerer {
         self.builder.set_current_function(func_id);
         self.context.enter_function(func_id);
 
+        // The prologue is attributed to the function's declaration
+        let span = self
+            .builder
+            .module()
+            .get_function(func_id)
+            .and_then(|function| function.span);
+        let previous_location = self.builder.set_location(span);
+
         // Register function parameters as variables in the current scope
         for (i, param) in params.iter().enumerate() {
             // Convert the parameter type annotation to our Type system
@@ -203,6 +581,8 @@ impl AstLowerer {
             self.builder.build_store(param_ptr, param_value_id);
 
             // Register the parameter as a variable in the current scope
+            self.builder
+                .declare_variable(param.name.clone(), param_ptr, param_type.clone(), true);
             self.context
                 .define_variable(param.name.clone(), param_ptr, param_type);
         }
@@ -213,6 +593,7 @@ impl AstLowerer {
         // Ensure the function has a return
         self.ensure_return();
 
+        self.builder.set_location(previous_location);
         self.context.exit_function();
         Ok(())
     }
@@ -237,8 +618,15 @@ impl AstLowerer {
         Ok(result)
     }
 
-    /// Lower a statement
+    /// Lower a statement, attributing its instructions to its span
     fn lower_statement(&mut self, stmt: &Stmt) -> LoweringResult<()> {
+        let previous_location = self.builder.set_location(Some(stmt.span));
+        let result = self.lower_statement_kind(stmt);
+        self.builder.set_location(previous_location);
+        result
+    }
+
+    fn lower_statement_kind(&mut self, stmt: &Stmt) -> LoweringResult<()> {
         match &stmt.kind {
             StmtKind::Let { name, init, .. } => {
                 if let Some(init_expr) = init {
@@ -254,6 +642,8 @@ impl AstLowerer {
                     self.builder.build_store(ptr, value);
 
                     // Register the variable
+                    self.builder
+                        .declare_variable(name.clone(), ptr, ty.clone(), false);
                     self.context.define_variable(name.clone(), ptr, ty);
                 }
             }
@@ -282,6 +672,10 @@ impl AstLowerer {
                 self.lower_for(variable, iterable, body)?;
             }
 
+            StmtKind::Break => self.lower_loop_exit(true)?,
+
+            StmtKind::Continue => self.lower_loop_exit(false)?,
+
             StmtKind::Function { .. } => {
                 // Functions are handled in the first pass
             }
@@ -297,18 +691,16 @@ impl AstLowerer {
             }
 
             StmtKind::Struct { .. } => {
-                // TODO: Implement struct definition lowering
-                // Structs are handled during semantic analysis
+                // Struct layouts are registered in the first pass
             }
 
             StmtKind::Enum { .. } => {
-                // TODO: Implement enum definition lowering
-                // Enums are handled during semantic analysis
+                // Enum layouts are registered in the first pass
             }
 
-            StmtKind::Impl(_) => {
-                // TODO: Implement impl block lowering
-                // Impl blocks are handled during semantic analysis
+            StmtKind::Impl(_) | StmtKind::Trait(_) => {
+                // Impl methods and trait defaults are lowered as functions in
+                // the program passes
             }
         }
 
@@ -349,7 +741,9 @@ impl AstLowerer {
 
         // Body block
         self.builder.set_current_block(body_block);
+        self.context.push_loop(cond_block, after_block);
         self.lower_block(body)?;
+        self.context.pop_loop();
         self.builder.build_branch(cond_block);
 
         // After block
@@ -358,6 +752,32 @@ impl AstLowerer {
         Ok(())
     }
 
+    /// Lower `break` (or `continue`) to a jump out of (or back around) the
+    /// innermost loop
+    fn lower_loop_exit(&mut self, is_break: bool) -> LoweringResult<()> {
+        let keyword = if is_break { "break" } else { "continue" };
+        let target = match self.context.current_loop() {
+            Some(loop_ctx) if is_break => loop_ctx.break_block,
+            Some(loop_ctx) => loop_ctx.continue_block,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::TypeError,
+                    format!("'{}' outside of a loop", keyword),
+                ))
+            }
+        };
+        self.builder.build_branch(target);
+
+        // Anything after the jump is unreachable but still needs a block
+        let unreachable = self
+            .builder
+            .create_block(format!("{}.after", keyword))
+            .ok_or_else(|| Error::new(ErrorKind::RuntimeError, "Failed to create block"))?;
+        self.builder.set_current_block(unreachable);
+
+        Ok(())
+    }
+
     /// Lower a for loop
     fn lower_for(&mut self, variable: &str, iterable: &Expr, body: &Block) -> LoweringResult<()> {
         // Lower the iterable expression
@@ -373,13 +793,32 @@ impl AstLowerer {
                 // Treat as range iteration (0..n)
                 self.lower_range_for_loop(variable, iter_value, body)
             }
-            _ => {
-                // For other types, we'll implement a generic iterator protocol later
-                Err(Error::new(
-                    ErrorKind::TypeError,
-                    format!("Cannot iterate over type: {:?}", iter_type),
-                ))
+            Type::Named(ref name) if name == "Iterator" => {
+                self.lower_iterator_for_loop(variable, iter_value, IteratorSource::Stdlib, body)
+            }
+            Type::Generic { ref name, .. } if name == "Iterator" => {
+                self.lower_iterator_for_loop(variable, iter_value, IteratorSource::Stdlib, body)
             }
+            Type::Named(ref name)
+            | Type::Struct { ref name, .. }
+            | Type::Generic { ref name, .. } => {
+                let next_fn = self.method_function(name, "next").ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::TypeError,
+                        format!("Cannot iterate over type {}: it has no `next` method", name),
+                    )
+                })?;
+                self.lower_iterator_for_loop(
+                    variable,
+                    iter_value,
+                    IteratorSource::Method(next_fn),
+                    body,
+                )
+            }
+            _ => Err(Error::new(
+                ErrorKind::TypeError,
+                format!("Cannot iterate over type: {:?}", iter_type),
+            )),
         }
     }
 
@@ -526,6 +965,8 @@ impl AstLowerer {
                 Error::new(ErrorKind::RuntimeError, "Failed to allocate loop variable")
             })?;
         self.builder.build_store(var_ptr, element_value);
+        self.builder
+            .declare_variable(variable.to_string(), var_ptr, element_type.clone(), false);
         self.context
             .define_variable(variable.to_string(), var_ptr, element_type);
 
@@ -662,6 +1103,8 @@ impl AstLowerer {
             )
         })?;
         self.builder.build_store(var_ptr, current_counter);
+        self.builder
+            .declare_variable(variable.to_string(), var_ptr, Type::I32, false);
         self.context
             .define_variable(variable.to_string(), var_ptr, Type::I32);
 
@@ -698,6 +1141,162 @@ impl AstLowerer {
         Ok(())
     }
 
+    /// Lower a for-loop over an iterator
+    ///
+    /// The loop desugars to repeated `next()` calls: each iteration asks the
+    /// iterator for an `Option`, exits on `None` and binds the loop variable to
+    /// the unwrapped `Some` value. Stdlib iterators are immutable values, so
+    /// the loop keeps the current iterator in a slot, peeks at its next item
+    /// and replaces it with the advanced iterator.
+    fn lower_iterator_for_loop(
+        &mut self,
+        variable: &str,
+        iter_value: ValueId,
+        source: IteratorSource,
+        body: &Block,
+    ) -> LoweringResult<()> {
+        let item_type = match source {
+            IteratorSource::Method(next_fn) => {
+                let return_type = self
+                    .builder
+                    .module_mut()
+                    .get_function(next_fn)
+                    .map(|func| func.return_type.clone());
+                match return_type {
+                    Some(Type::Option(item_type)) => *item_type,
+                    Some(Type::Generic { name, mut args })
+                        if name == "Option" && args.len() == 1 =>
+                    {
+                        args.remove(0)
+                    }
+                    _ => Type::Unknown,
+                }
+            }
+            IteratorSource::Stdlib => Type::Unknown,
+        };
+        let option_type = Type::Option(Box::new(item_type.clone()));
+        let iterator_type = Type::Named("Iterator".to_string());
+
+        let cond_block = self
+            .builder
+            .create_block("iter.next".to_string())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::RuntimeError,
+                    "Failed to create iterator next block",
+                )
+            })?;
+        let body_block = self
+            .builder
+            .create_block("iter.body".to_string())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::RuntimeError,
+                    "Failed to create iterator body block",
+                )
+            })?;
+        let after_block = self
+            .builder
+            .create_block("iter.after".to_string())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::RuntimeError,
+                    "Failed to create iterator after block",
+                )
+            })?;
+
+        // Stdlib iterators are threaded through a slot holding the iterator
+        // that has not been consumed yet
+        let state_ptr = match source {
+            IteratorSource::Stdlib => {
+                let ptr = self
+                    .builder
+                    .build_alloc(iterator_type.clone())
+                    .ok_or_else(|| {
+                        Error::new(ErrorKind::RuntimeError, "Failed to allocate iterator state")
+                    })?;
+                self.builder.build_store(ptr, iter_value);
+                Some(ptr)
+            }
+            IteratorSource::Method(_) => None,
+        };
+
+        // Push loop context for break/continue support
+        self.context.push_loop(cond_block, after_block);
+        self.builder.build_branch(cond_block);
+
+        // Next block: fetch the next item and stop on None
+        self.builder.set_current_block(cond_block);
+        let current = state_ptr
+            .map(|ptr| {
+                self.builder
+                    .build_load(ptr, iterator_type.clone())
+                    .ok_or_else(|| {
+                        Error::new(ErrorKind::RuntimeError, "Failed to load iterator state")
+                    })
+            })
+            .transpose()?;
+        let next_item = match source {
+            IteratorSource::Method(next_fn) => {
+                self.builder
+                    .build_call(next_fn, vec![iter_value], option_type.clone())
+            }
+            IteratorSource::Stdlib => current.and_then(|current| {
+                self.builder.build_stdlib_call(
+                    "iter_peek".to_string(),
+                    vec![current],
+                    option_type.clone(),
+                )
+            }),
+        };
+        let next_item = next_item
+            .ok_or_else(|| Error::new(ErrorKind::RuntimeError, "Failed to call iterator next"))?;
+        let has_item = self
+            .builder
+            .build_stdlib_call("is_some".to_string(), vec![next_item], Type::Bool)
+            .ok_or_else(|| Error::new(ErrorKind::RuntimeError, "Failed to check iterator item"))?;
+        self.builder
+            .build_cond_branch(has_item, body_block, after_block);
+
+        // Body block: advance the iterator, bind the item and run the body
+        self.builder.set_current_block(body_block);
+        if let (Some(ptr), Some(current)) = (state_ptr, current) {
+            let advanced = self
+                .builder
+                .build_stdlib_call("iter_advance".to_string(), vec![current], iterator_type)
+                .ok_or_else(|| Error::new(ErrorKind::RuntimeError, "Failed to advance iterator"))?;
+            self.builder.build_store(ptr, advanced);
+        }
+
+        self.context.push_scope();
+        let item = self
+            .builder
+            .build_stdlib_call(
+                "option_unwrap".to_string(),
+                vec![next_item],
+                item_type.clone(),
+            )
+            .ok_or_else(|| Error::new(ErrorKind::RuntimeError, "Failed to unwrap iterator item"))?;
+        let var_ptr = self.builder.build_alloc(item_type.clone()).ok_or_else(|| {
+            Error::new(ErrorKind::RuntimeError, "Failed to allocate loop variable")
+        })?;
+        self.builder.build_store(var_ptr, item);
+        self.builder
+            .declare_variable(variable.to_string(), var_ptr, item_type.clone(), false);
+        self.context
+            .define_variable(variable.to_string(), var_ptr, item_type);
+
+        self.lower_block(body)?;
+        self.context.pop_scope();
+        self.builder.build_branch(cond_block);
+
+        // After block
+        self.builder.set_current_block(after_block);
+        self.context.pop_loop();
+
+        Ok(())
+    }
+
     /// Convert a type annotation to a Type
     fn convert_type_annotation(&self, type_ann: &crate::parser::TypeAnn) -> Type {
         use crate::parser::TypeKind;
@@ -705,7 +1304,11 @@ impl AstLowerer {
         match &type_ann.kind {
             TypeKind::Named(name) => match name.as_str() {
                 "i32" => Type::I32,
+                "i64" => Type::I64,
+                "u32" => Type::U32,
+                "u64" => Type::U64,
                 "f32" => Type::F32,
+                "f64" => Type::F64,
                 "bool" => Type::Bool,
                 "string" => Type::String,
                 "String" => Type::String,
@@ -772,6 +1375,9 @@ impl AstLowerer {
                             Type::F32
                         }
                     }
+                    Literal::TypedNumber(_, suffix) => {
+                        crate::types::conversion::suffix_type(*suffix)
+                    }
                     Literal::String(_) => Type::String,
                     Literal::Boolean(_) => Type::Bool,
                     Literal::Null => Type::Option(Box::new(Type::Unknown)),
@@ -782,16 +1388,14 @@ impl AstLowerer {
                 if let Some(var) = self.context.lookup_variable(name) {
                     Ok(var.ty.clone())
                 } else {
-                    // Check if it's a function
-                    if self.context.get_function(name).is_some() {
-                        // For now, return function type - TODO: get actual signature
-                        Ok(Type::Function {
-                            params: vec![],
-                            ret: Box::new(Type::Unknown),
-                        })
-                    } else {
-                        Ok(Type::Unknown)
-                    }
+                    // Check if it's a function, then a stdlib constant
+                    Ok(self
+                        .context
+                        .get_function(name)
+                        .and_then(|id| self.builder.module().get_function(id))
+                        .map(|func| func.signature_type())
+                        .or_else(|| crate::stdlib::constants::constant_type(name))
+                        .unwrap_or(Type::Unknown))
                 }
             }
             ExprKind::Binary { left, op, right: _ } => {
@@ -832,21 +1436,29 @@ impl AstLowerer {
                 }
             }
             ExprKind::Call { callee, args: _ } => {
-                // For function calls, we'd need to look up the function signature
-                // For now, try to get the type from the callee
-                if let ExprKind::Identifier(func_name) = &callee.kind {
-                    // Handle built-in functions
-                    match func_name.as_str() {
-                        "print" => Ok(Type::Unknown), // print returns unit/void
-                        _ => {
-                            // For user-defined functions, we'd need to look up their return type
-                            // TODO: Get actual return type from function registry
-                            Ok(Type::Unknown)
-                        }
+                // Use the declared return type of the function or method called
+                let func_id = match &callee.kind {
+                    ExprKind::Identifier(func_name)
+                        if self.context.lookup_variable(func_name).is_none() =>
+                    {
+                        self.context.get_function(func_name)
                     }
-                } else {
-                    Ok(Type::Unknown)
-                }
+                    ExprKind::Member { object, property } => {
+                        let receiver_type = self.get_expression_type(object)?;
+                        receiver_type_name(&receiver_type)
+                            .and_then(|name| self.method_function(name, property))
+                    }
+                    // Calls through a closure return the closure's return type
+                    _ => {
+                        return Ok(match self.get_expression_type(callee)? {
+                            Type::Function { ret, .. } => *ret,
+                            _ => Type::Unknown,
+                        })
+                    }
+                };
+                Ok(func_id
+                    .map(|id| self.function_return_type(id))
+                    .unwrap_or(Type::Unknown))
             }
             ExprKind::If {
                 then_branch,
@@ -890,13 +1502,12 @@ impl AstLowerer {
                     _ => Ok(Type::Unknown),
                 }
             }
-            ExprKind::Member {
-                object: _,
-                property: _,
-            } => {
-                // Member access type depends on object type and property
-                // For now, return unknown
-                Ok(Type::Unknown)
+            ExprKind::Member { object, property } => {
+                let object_type = self.get_expression_type(object)?;
+                Ok(self
+                    .struct_layout(&object_type)
+                    .and_then(|layout| layout.field(property))
+                    .map_or(Type::Unknown, |field| field.ty.clone()))
             }
             ExprKind::Assign { value, target: _ } => {
                 // Assignment expression returns the type of the assigned value
@@ -924,20 +1535,16 @@ impl AstLowerer {
                 // NOTE: With monomorphization complete, this may be the correct approach
                 Ok(Type::Named(name.clone()))
             }
-            ExprKind::StructConstructor { name, fields: _ } => {
-                // TODO: Implement struct constructor type inference
-                // For now, return the struct type
-                Ok(Type::Named(name.clone()))
-            }
+            ExprKind::StructConstructor { name, fields: _ } => Ok(Type::Named(name.clone())),
             ExprKind::EnumConstructor {
                 enum_name,
-                variant: _,
+                variant,
                 args: _,
             } => {
-                // TODO: Implement enum constructor type inference
-                // For now, return the enum type if known
                 if let Some(enum_name) = enum_name {
                     Ok(Type::Named(enum_name.clone()))
+                } else if let Some(layout) = self.layouts.find_variant_enum(variant) {
+                    Ok(Type::Named(layout.name.clone()))
                 } else {
                     Ok(Type::Unknown)
                 }
@@ -956,6 +1563,15 @@ impl AstLowerer {
                 self.infer_type_from_expr(try_expr)
             }
             ExprKind::Closure { parameters, body } => {
+                // Once lowered, the closure function has the resolved signature
+                let name = closure::closure_function_name(expr.id);
+                if let Some(func) = self.builder.module().get_function_by_name(&name) {
+                    return Ok(Type::Function {
+                        params: func.params.iter().skip(1).map(|p| p.ty.clone()).collect(),
+                        ret: Box::new(func.return_type.clone()),
+                    });
+                }
+
                 // Infer parameter types
                 let param_types: Vec<Type> = parameters
                     .iter()
@@ -976,6 +1592,7 @@ impl AstLowerer {
                     ret: Box::new(return_type),
                 })
             }
+            ExprKind::Cast { target_type, .. } => Ok(self.convert_type_annotation(target_type)),
         }
     }
 
@@ -1023,6 +1640,147 @@ impl AstLowerer {
     }
 }
 
+/// Top-level statements with exported declarations replaced by the
+/// declarations themselves, and the names the program exports
+fn unwrap_exports(statements: &[Stmt]) -> (Vec<Cow<'_, Stmt>>, Vec<String>) {
+    let mut unwrapped = Vec::with_capacity(statements.len());
+    let mut exported = Vec::new();
+
+    for stmt in statements {
+        let StmtKind::Export { export } = &stmt.kind else {
+            unwrapped.push(Cow::Borrowed(stmt));
+            continue;
+        };
+        match export {
+            ExportKind::Declaration(inner) => {
+                if let StmtKind::Function { name, .. } = &inner.kind {
+                    exported.push(name.clone());
+                }
+                unwrapped.push(Cow::Borrowed(inner.as_ref()));
+            }
+            ExportKind::Function {
+                name,
+                params,
+                ret_type,
+                body,
+                is_async,
+            } => {
+                exported.push(name.clone());
+                unwrapped.push(Cow::Owned(Stmt {
+                    kind: StmtKind::Function {
+                        name: name.clone(),
+                        generic_params: None,
+                        params: params.clone(),
+                        ret_type: ret_type.clone(),
+                        where_clause: None,
+                        body: body.clone(),
+                        is_async: *is_async,
+                    },
+                    span: stmt.span,
+                    attributes: stmt.attributes.clone(),
+                }));
+            }
+            ExportKind::Named { specifiers } => {
+                exported.extend(specifiers.iter().map(|spec| spec.name.clone()));
+                unwrapped.push(Cow::Borrowed(stmt));
+            }
+            ExportKind::Variable { .. } | ExportKind::Default { .. } => {
+                unwrapped.push(Cow::Borrowed(stmt));
+            }
+        }
+    }
+
+    (unwrapped, exported)
+}
+
+/// Dispatch strategy requested by a `@monomorphize(always|never)` attribute
+fn generic_dispatch(attributes: &[Attribute]) -> GenericDispatch {
+    let Some(attr) = attributes.iter().find(|attr| attr.name == "monomorphize") else {
+        return GenericDispatch::Auto;
+    };
+    match attr.args.first().map(|arg| arg.trim()) {
+        Some("always") => GenericDispatch::Specialize,
+        Some("never") => GenericDispatch::Boxed,
+        _ => GenericDispatch::Auto,
+    }
+}
+
+/// Parameters of a method with `Self` resolved to the impl's type
+fn method_params(impl_block: &ImplBlock, method: &Method) -> Vec<Param> {
+    method
+        .params
+        .iter()
+        .map(|param| Param {
+            name: param.name.clone(),
+            type_ann: self_to_type(&param.type_ann, &impl_block.type_name),
+        })
+        .collect()
+}
+
+/// Trait bounds of each type parameter of a generic function
+fn type_param_bounds(generic_params: Option<&GenericParams>) -> HashMap<String, Vec<String>> {
+    generic_params
+        .map(|generics| {
+            generics
+                .params
+                .iter()
+                .map(|param| {
+                    let bounds = param
+                        .bounds
+                        .iter()
+                        .map(|bound| bound.trait_name.clone())
+                        .collect();
+                    (param.name.clone(), bounds)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Name of the type whose impl methods a value of type `ty` can call
+fn receiver_type_name(ty: &Type) -> Option<&str> {
+    match ty {
+        Type::Named(name) | Type::Struct { name, .. } | Type::Generic { name, .. } => {
+            Some(name.as_str())
+        }
+        Type::Reference { inner, .. } => receiver_type_name(inner),
+        Type::I32 => Some("i32"),
+        Type::I64 => Some("i64"),
+        Type::U32 => Some("u32"),
+        Type::U64 => Some("u64"),
+        Type::F32 => Some("f32"),
+        Type::F64 => Some("f64"),
+        Type::Bool => Some("bool"),
+        Type::String => Some("string"),
+        _ => None,
+    }
+}
+
+/// Replace `Self` in a type annotation with the named type
+fn self_to_type(type_ann: &TypeAnn, type_name: &str) -> TypeAnn {
+    replace_self(type_ann, &TypeKind::Named(type_name.to_string()))
+}
+
+/// Replace `Self` in a type annotation with another type
+fn replace_self(type_ann: &TypeAnn, replacement: &TypeKind) -> TypeAnn {
+    let kind = match &type_ann.kind {
+        TypeKind::Named(name) | TypeKind::TypeParam(name) if name == "Self" => replacement.clone(),
+        TypeKind::Array(element) => TypeKind::Array(Box::new(replace_self(element, replacement))),
+        TypeKind::Generic { name, args } => TypeKind::Generic {
+            name: name.clone(),
+            args: args
+                .iter()
+                .map(|arg| replace_self(arg, replacement))
+                .collect(),
+        },
+        other => other.clone(),
+    };
+    TypeAnn {
+        kind,
+        span: type_ann.span,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1071,4 +1829,563 @@ mod tests {
         assert_eq!(func.name, "add");
         assert_eq!(func.params.len(), 2);
     }
+
+    #[test]
+    fn test_lower_records_debug_info() {
+        let source = "fn add(x: i32, y: i32) -> i32 {\n    let sum = x + y;\n    return sum\n}";
+
+        let module = lower_source(source).unwrap();
+        let func = module.get_function_by_name("add").unwrap();
+        assert_eq!(func.span.map(|span| span.start.line), Some(1));
+
+        let names: Vec<_> = func
+            .debug_variables
+            .iter()
+            .map(|variable| (variable.name.as_str(), variable.is_param))
+            .collect();
+        assert_eq!(names, vec![("x", true), ("y", true), ("sum", false)]);
+
+        let locations: Vec<_> = func
+            .blocks()
+            .values()
+            .flat_map(|block| block.instructions.iter())
+            .filter_map(|(_, inst)| inst.source_location.map(|span| span.start))
+            .map(|start| (start.line, start.column))
+            .collect();
+        assert!(locations.iter().any(|&(line, _)| line == 3));
+        // The addition is attributed to the `x + y` expression rather than
+        // the whole `let` statement
+        assert!(locations.contains(&(2, 15)));
+    }
+
+    #[test]
+    fn test_lower_with_coverage_counters() {
+        let source =
+            "fn pick(x: i32) -> i32 {\n    if x > 0 {\n        return 1\n    }\n    return 2\n}";
+        let lexer = Lexer::new(source).unwrap();
+        let (tokens, _) = lexer.scan_tokens();
+        let program = Parser::new(tokens).parse().unwrap();
+        let mut lowerer = AstLowerer::new(
+            SymbolTable::new(),
+            HashMap::new(),
+            Vec::new(),
+            HashMap::new(),
+        );
+        lowerer.enable_coverage();
+        let module = lowerer.lower_program(&program).unwrap();
+        let map = lowerer.coverage_map().unwrap();
+
+        let func = module.get_function_by_name("pick").unwrap();
+        let mut counters = Vec::new();
+        for block in func.blocks().values() {
+            let counted: Vec<_> = block
+                .instructions
+                .iter()
+                .filter_map(|(_, inst)| match &inst.instruction {
+                    Instruction::Probe(probe) if probe.hook == coverage::COVERAGE_HOOK => {
+                        Some(probe.id)
+                    }
+                    _ => None,
+                })
+                .collect();
+            assert!(counted.len() <= 1);
+            counters.extend(counted);
+        }
+        counters.sort_unstable();
+        assert_eq!(counters, (0..map.len() as u32).collect::<Vec<_>>());
+
+        let lines: Vec<u32> = counters
+            .iter()
+            .flat_map(|counter| map.lines(*counter).to_vec())
+            .collect();
+        assert!(lines.contains(&3) && lines.contains(&5));
+    }
+
+    #[test]
+    fn test_lower_exported_functions() {
+        let source = "export fn area(w: i32, h: i32) -> i32 {\n    return w * h\n}\n\
+                      fn helper() -> i32 {\n    return 1\n}\n\
+                      export { helper }";
+
+        let module = lower_source(source).unwrap();
+        assert!(module.has_function_by_name("area"));
+        assert!(module.is_exported("area"));
+        assert!(module.is_exported("helper"));
+        assert_eq!(module.exported_function_names().len(), 2);
+    }
+
+    #[test]
+    fn test_lower_for_loop_over_user_iterator() {
+        let source = r#"
+            struct Countdown { remaining: i32 }
+
+            impl Countdown {
+                fn next(self) -> Option<i32> {
+                }
+            }
+
+            fn count(countdown: Countdown) {
+                for n in countdown {
+                    n;
+                }
+            }
+        "#;
+
+        let module = lower_source(source).unwrap();
+        let next = module.get_function_by_name("Countdown::next").unwrap();
+        assert_eq!(next.params[0].ty, Type::Named("Countdown".to_string()));
+
+        // Each iteration calls `next` and checks the returned Option
+        let count = module.get_function_by_name("count").unwrap();
+        let calls_next = count.blocks().values().any(|block| {
+            block.instructions.iter().any(|(_, inst)| {
+                matches!(inst.instruction, Instruction::Call { func, .. } if func == next.id)
+            })
+        });
+        assert!(calls_next);
+    }
+
+    #[test]
+    fn test_lower_struct_fields() {
+        let source = r#"
+            struct Point { x: i32, y: f32 }
+
+            fn make() -> f32 {
+                let p = Point { y: 2.0, x: 1 };
+                p.x = 3;
+                p.y
+            }
+        "#;
+
+        let module = lower_source(source).unwrap();
+        let layout = module.get_struct("Point").unwrap();
+        assert_eq!(layout.field("x").unwrap().offset, 0);
+        assert_eq!(layout.field("y").unwrap().offset, 4);
+        assert_eq!(layout.total_size, 8);
+
+        let instructions: Vec<Instruction> = module
+            .get_function_by_name("make")
+            .unwrap()
+            .blocks()
+            .values()
+            .flat_map(|block| block.instructions.iter())
+            .map(|(_, inst)| inst.instruction.clone())
+            .collect();
+        assert!(instructions.iter().any(|inst| matches!(
+            inst,
+            Instruction::AllocStruct { struct_name, .. } if struct_name == "Point"
+        )));
+
+        // Two stores from the literal, one from the assignment and one load
+        let mut fields: Vec<(String, Type)> = instructions
+            .iter()
+            .filter_map(|inst| match inst {
+                Instruction::GetFieldPtr {
+                    field_name,
+                    field_ty,
+                    ..
+                } => Some((field_name.clone(), field_ty.clone())),
+                _ => None,
+            })
+            .collect();
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        let x = ("x".to_string(), Type::I32);
+        let y = ("y".to_string(), Type::F32);
+        assert_eq!(fields, vec![x.clone(), x, y.clone(), y]);
+        assert!(instructions
+            .iter()
+            .any(|inst| matches!(inst, Instruction::Load { ty: Type::F32, .. })));
+    }
+
+    #[test]
+    fn test_lower_generic_struct() {
+        let source = r#"
+            struct Pair<T: Clone> { first: T, second: T }
+
+            fn main() -> i32 {
+                0
+            }
+        "#;
+
+        let module = lower_source(source).unwrap();
+        let layout = module.get_struct("Pair").unwrap();
+        let first = layout.field("first").unwrap();
+        let second = layout.field("second").unwrap();
+        assert_eq!(first.offset, 0);
+        assert_eq!(second.offset, first.size);
+    }
+
+    #[test]
+    fn test_lower_method_and_associated_calls() {
+        let source = r#"
+            struct Counter { count: i32 }
+
+            impl Counter {
+                fn new() -> Counter {
+                }
+
+                fn add(self, amount: i32) -> i32 {
+                    amount
+                }
+            }
+
+            fn run() -> i32 {
+                let counter = Counter::new();
+                counter.add(2)
+            }
+        "#;
+
+        let module = lower_source(source).unwrap();
+        let new = module.get_function_by_name("Counter::new").unwrap().id;
+        let add = module.get_function_by_name("Counter::add").unwrap().id;
+
+        let calls: Vec<(FunctionId, usize, Type)> = module
+            .get_function_by_name("run")
+            .unwrap()
+            .blocks()
+            .values()
+            .flat_map(|block| block.instructions.iter())
+            .filter_map(|(_, inst)| match &inst.instruction {
+                Instruction::Call { func, args, ty } => Some((*func, args.len(), ty.clone())),
+                _ => None,
+            })
+            .collect();
+
+        // The receiver is passed as `self` ahead of the call's arguments
+        assert!(calls.contains(&(new, 0, Type::Named("Counter".to_string()))));
+        assert!(calls.contains(&(add, 2, Type::I32)));
+    }
+
+    #[test]
+    fn test_lower_break_and_continue() {
+        let source = r#"
+            fn spin(n: i32) -> i32 {
+                while true {
+                    if n == 2 { continue }
+                    if n > 5 { break }
+                }
+                n
+            }
+        "#;
+
+        let module = lower_source(source).unwrap();
+        let func = module.get_function_by_name("spin").unwrap();
+        let blocks = func.blocks();
+        let jumps: Vec<(&str, &str)> = blocks
+            .values()
+            .flat_map(|block| {
+                block
+                    .instructions
+                    .iter()
+                    .filter_map(move |(_, inst)| match &inst.instruction {
+                        Instruction::Branch(target) => {
+                            Some((block.name.as_str(), blocks[target].name.as_str()))
+                        }
+                        _ => None,
+                    })
+            })
+            .collect();
+
+        assert!(jumps.contains(&("if.then", "while.cond")));
+        assert!(jumps.contains(&("if.then", "while.after")));
+    }
+
+    #[test]
+    fn test_lower_closure_captures() {
+        let source = r#"
+            fn double(x: i32) -> i32 {
+                x * 2
+            }
+
+            fn run(offset: i32) -> i32 {
+                let add = |x: i32| x + offset;
+                let twice = double;
+                add(twice(20))
+            }
+        "#;
+
+        let module = lower_source(source).unwrap();
+        let instructions = |func: &crate::ir::Function| -> Vec<Instruction> {
+            func.blocks()
+                .values()
+                .flat_map(|block| block.instructions.iter())
+                .map(|(_, inst)| inst.instruction.clone())
+                .collect()
+        };
+
+        // The closure body takes its environment first and reads `offset` from it
+        let closure = module
+            .functions()
+            .values()
+            .find(|func| func.name.starts_with("closure_") && func.name != "closure_double")
+            .unwrap();
+        let params: Vec<&str> = closure.params.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(params, vec!["env", "x"]);
+        assert_eq!(closure.return_type, Type::I32);
+        assert!(instructions(closure).iter().any(|inst| matches!(
+            inst,
+            Instruction::LoadCapture {
+                index: 0,
+                ty: Type::I32,
+                ..
+            }
+        )));
+
+        // `double` used as a value is wrapped in a closure forwarding to it
+        let double = module.get_function_by_name("double").unwrap().id;
+        let wrapper = module.get_function_by_name("closure_double").unwrap();
+        assert_eq!(wrapper.params.len(), 2);
+        assert!(instructions(wrapper)
+            .iter()
+            .any(|inst| matches!(inst, Instruction::Call { func, .. } if *func == double)));
+
+        let run = instructions(module.get_function_by_name("run").unwrap());
+        let captures: Vec<usize> = run
+            .iter()
+            .filter_map(|inst| match inst {
+                Instruction::CreateClosure { captured_vars, .. } => Some(captured_vars.len()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(captures, vec![1, 0]);
+        let invoke_types: Vec<Type> = run
+            .iter()
+            .filter_map(|inst| match inst {
+                Instruction::InvokeClosure { return_type, .. } => Some(return_type.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(invoke_types, vec![Type::I32, Type::I32]);
+    }
+
+    #[test]
+    fn test_lower_stdlib_constant_and_error_kind() {
+        let source = r#"
+            fn half_turn() -> f32 {
+                PI
+            }
+
+            fn missing() -> ErrorKind {
+                ErrorKind::NotFound
+            }
+        "#;
+
+        let module = lower_source(source).unwrap();
+        let half_turn = module.get_function_by_name("half_turn").unwrap();
+        assert!(half_turn
+            .blocks()
+            .values()
+            .flat_map(|block| block.instructions.iter())
+            .any(|(_, inst)| matches!(
+                inst.instruction,
+                Instruction::Const(Constant::F32(value)) if value == std::f32::consts::PI
+            )));
+
+        let layout = module.get_enum("ErrorKind").unwrap();
+        assert!(layout.variant("NotFound").is_some());
+        assert!(layout.variant("Parse").is_some());
+    }
+
+    #[test]
+    fn test_lower_generic_enum() {
+        let source = r#"
+            enum Choice<T> {
+                One(T),
+                Neither
+            }
+
+            fn main() -> i32 {
+                0
+            }
+        "#;
+
+        let module = lower_source(source).unwrap();
+        let layout = module.get_enum("Choice").unwrap();
+        let tags: Vec<u32> = layout.variants.iter().map(|v| v.tag).collect();
+        assert_eq!(tags, vec![0, 1]);
+        // An unresolved type parameter is laid out like a pointer
+        assert_eq!(layout.data_offset(), 8);
+        assert!(layout.variant("One").is_some());
+    }
+
+    #[test]
+    fn test_lower_enum_match() {
+        let source = r#"
+            enum Shape {
+                Circle(f32),
+                Rect { w: f32, h: f32 },
+                Empty
+            }
+
+            fn area(shape: Shape) -> f32 {
+                match shape {
+                    Shape::Circle(r) => r,
+                    Shape::Rect(w, h) => w,
+                    Empty => 0.0
+                }
+            }
+        "#;
+
+        let module = lower_source(source).unwrap();
+        let layout = module.get_enum("Shape").unwrap();
+        let tags: Vec<u32> = layout.variants.iter().map(|v| v.tag).collect();
+        assert_eq!(tags, vec![0, 1, 2]);
+        assert_eq!(layout.data_offset(), 4);
+
+        let instructions = |name: &str| -> Vec<Instruction> {
+            module
+                .get_function_by_name(name)
+                .unwrap()
+                .blocks()
+                .values()
+                .flat_map(|block| block.instructions.iter())
+                .map(|(_, inst)| inst.instruction.clone())
+                .collect()
+        };
+
+        // The tag is read once and the payload is destructured per arm
+        let area = instructions("area");
+        let tag_reads = area
+            .iter()
+            .filter(|inst| matches!(inst, Instruction::GetEnumTag { .. }))
+            .count();
+        assert_eq!(tag_reads, 1);
+        let mut extracted: Vec<(u32, Type)> = area
+            .iter()
+            .filter_map(|inst| match inst {
+                Instruction::ExtractEnumData {
+                    variant_index, ty, ..
+                } => Some((*variant_index, ty.clone())),
+                _ => None,
+            })
+            .collect();
+        extracted.sort_by_key(|(index, _)| *index);
+        assert_eq!(
+            extracted,
+            vec![(0, Type::F32), (0, Type::F32), (1, Type::F32)]
+        );
+    }
+
+    #[test]
+    fn test_lower_match_guard_and_binding() {
+        let source = r#"
+            enum Reading {
+                Value(i32),
+                Missing
+            }
+
+            fn describe(reading: Reading) -> i32 {
+                match reading {
+                    whole @ Reading::Value(n) if n > 0 => n,
+                    Reading::Value(_) => 0,
+                    Missing => -1
+                }
+            }
+        "#;
+
+        let module = lower_source(source).unwrap();
+        let function = module.get_function_by_name("describe").unwrap();
+
+        // The guard runs in its own block after the bindings are stored, and
+        // falls through to the next arm when it fails
+        let guard = function
+            .blocks()
+            .values()
+            .find(|block| block.name == "match.arm0.guard")
+            .expect("guarded arm should have a guard block");
+        let stores = guard
+            .instructions
+            .iter()
+            .filter(|(_, inst)| matches!(inst.instruction, Instruction::Store { .. }))
+            .count();
+        assert_eq!(stores, 2);
+        assert!(matches!(
+            guard.terminator(),
+            Some(Instruction::CondBranch { .. })
+        ));
+
+        // The body block does not bind the variables a second time
+        let body = function
+            .blocks()
+            .values()
+            .find(|block| block.name == "match.arm0.body")
+            .unwrap();
+        assert!(!body
+            .instructions
+            .iter()
+            .any(|(_, inst)| matches!(inst.instruction, Instruction::Store { .. })));
+    }
+
+    #[test]
+    fn test_lower_inline_ir_functions() {
+        let source = r#"
+            @intrinsic(popcnt)
+            fn count_ones(x: i32) -> i32 {}
+
+            @ir
+            fn mul_add(a: f64, b: f64, c: f64) -> f64 {
+                "%p = mul a, b"
+                "%r = add %p, c; ret %r"
+            }
+        "#;
+        let module = lower_source(source).unwrap();
+        assert!(module.validate().is_ok());
+
+        let count_ones = module.get_function_by_name("count_ones").unwrap();
+        let entry = count_ones
+            .get_block(count_ones.entry_block.unwrap())
+            .unwrap();
+        assert!(matches!(
+            &entry.instructions[0].1.instruction,
+            Instruction::Intrinsic {
+                intrinsic: crate::ir::Intrinsic::Popcnt,
+                ty: Some(Type::I32),
+                ..
+            }
+        ));
+        assert!(matches!(
+            entry.terminator(),
+            Some(Instruction::Return(Some(_)))
+        ));
+
+        let mul_add = module.get_function_by_name("mul_add").unwrap();
+        let entry = mul_add.get_block(mul_add.entry_block.unwrap()).unwrap();
+        assert_eq!(entry.instructions.len(), 3);
+        assert!(matches!(
+            &entry.instructions[1].1.instruction,
+            Instruction::Binary { ty: Type::F64, .. }
+        ));
+    }
+
+    #[test]
+    fn test_inline_ir_is_verified() {
+        let wrong_type = r#"
+            @intrinsic(sqrt)
+            fn root(x: i32) -> i32 {}
+        "#;
+        let error = lower_source(wrong_type).unwrap_err();
+        assert!(error.message.contains("sqrt expects a float"));
+
+        let unknown = r#"
+            @ir
+            fn f(x: i32) -> i32 {
+                "%y = rotate x, 1"
+                "ret %y"
+            }
+        "#;
+        let error = lower_source(unknown).unwrap_err();
+        assert!(error
+            .message
+            .contains("IR line 1: unknown operation 'rotate'"));
+
+        let lane = r#"
+            @ir
+            fn first(v: f32x4, i: i32) -> f32 {
+                "%x = simd_extract v, i"
+                "ret %x"
+            }
+        "#;
+        let error = lower_source(lane).unwrap_err();
+        assert!(error.message.contains("constant lane"));
+    }
 }
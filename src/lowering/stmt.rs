@@ -18,6 +18,8 @@ pub fn lower_statement(lowerer: &mut AstLowerer, stmt: &Stmt) -> LoweringResult<
             iterable,
             body,
         } => lowerer.lower_for(variable, iterable, body),
+        StmtKind::Break => lowerer.lower_loop_exit(true),
+        StmtKind::Continue => lowerer.lower_loop_exit(false),
         StmtKind::Function { .. } => {
             // Functions are handled in the first pass
             Ok(())
@@ -30,16 +32,14 @@ pub fn lower_statement(lowerer: &mut AstLowerer, stmt: &Stmt) -> LoweringResult<
             // Exports are handled during semantic analysis
             Ok(())
         }
-        StmtKind::Struct { name, .. } => {
-            // TODO: Implement struct definition lowering
-            // Structs are handled during semantic analysis
-            let _ = name; // suppress warning
+        StmtKind::Struct { .. } => {
+            // Struct layouts are registered when the program's declarations
+            // are collected
             Ok(())
         }
-        StmtKind::Enum { name, .. } => {
-            // TODO: Implement enum definition lowering
-            // Enums are handled during semantic analysis
-            let _ = name; // suppress warning
+        StmtKind::Enum { .. } => {
+            // Enum layouts are registered when the program's declarations
+            // are collected
             Ok(())
         }
         StmtKind::Impl(_) => {
@@ -47,6 +47,10 @@ pub fn lower_statement(lowerer: &mut AstLowerer, stmt: &Stmt) -> LoweringResult<
             // Impl blocks are handled during semantic analysis
             Ok(())
         }
+        StmtKind::Trait(_) => {
+            // Traits only declare methods; defaults are lowered per impl
+            Ok(())
+        }
     }
 }
 
@@ -70,6 +74,9 @@ fn lower_let(
         lowerer.builder.build_store(ptr, value);
 
         // Register the variable
+        lowerer
+            .builder
+            .declare_variable(name.to_string(), ptr, ty.clone(), false);
         lowerer.context.define_variable(name.to_string(), ptr, ty);
     } else {
         // Uninitialized variable - allocate with default value
@@ -83,6 +90,9 @@ fn lower_let(
         let default_value = lowerer.builder.const_value(Constant::Null);
         lowerer.builder.build_store(ptr, default_value);
 
+        lowerer
+            .builder
+            .declare_variable(name.to_string(), ptr, ty.clone(), false);
         lowerer.context.define_variable(name.to_string(), ptr, ty);
     }
 
@@ -0,0 +1,168 @@
+use crate::inference::{InferenceEngine, InferenceResult};
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::parser::visitor::{walk_expr, walk_stmt};
+use crate::parser::{
+    Block, ExportKind, Expr, ExprKind, ImplBlock, Method, Param, Parser, Program, Stmt, StmtKind,
+    Visitor,
+};
+use crate::source::{SourceLocation, Span};
+use crate::types::Type;
+use std::collections::{HashMap, HashSet};
+
+/// A document's tokens, syntax tree and inferred types
+///
+/// Semantic tokens and inlay hints both report what inference concluded
+/// about a document. Inference runs in the engine's editor mode, so names
+/// it cannot resolve, such as imports, are unknown rather than fatal. When
+/// the document does not parse or does not type check, the tree or the
+/// types are missing and callers fall back to what the tokens show.
+pub(crate) struct DocumentAnalysis {
+    /// Tokens other than newlines
+    pub tokens: Vec<Token>,
+    pub program: Option<Program>,
+    pub types: Option<InferenceResult>,
+    /// Functions declared in the document, by name
+    pub functions: HashMap<String, FunctionInfo>,
+    pub structs: HashSet<String>,
+    pub enums: HashSet<String>,
+    /// Extent of each function, method and closure with its parameter names
+    param_scopes: Vec<(Span, Vec<String>)>,
+}
+
+pub(crate) struct FunctionInfo {
+    pub params: Vec<String>,
+    pub is_async: bool,
+}
+
+impl DocumentAnalysis {
+    pub fn new(content: &str) -> Self {
+        let mut analysis = DocumentAnalysis {
+            tokens: Vec::new(),
+            program: None,
+            types: None,
+            functions: HashMap::new(),
+            structs: HashSet::new(),
+            enums: HashSet::new(),
+            param_scopes: Vec::new(),
+        };
+
+        let Ok(lexer) = Lexer::new(content) else {
+            return analysis;
+        };
+        let (tokens, errors) = lexer.scan_tokens();
+        analysis.tokens = tokens
+            .iter()
+            .filter(|token| !matches!(token.kind, TokenKind::Newline | TokenKind::Eof))
+            .cloned()
+            .collect();
+        if !errors.is_empty() {
+            return analysis;
+        }
+
+        if let Ok(program) = Parser::new(tokens).parse() {
+            analysis.visit_program(&program);
+            analysis.types = InferenceEngine::for_editor().infer_program(&program).ok();
+            analysis.program = Some(program);
+        }
+        analysis
+    }
+
+    /// Inferred type of the expression at `span`
+    pub fn expr_type(&self, span: &Span) -> Option<&Type> {
+        self.types
+            .as_ref()
+            .and_then(|types| types.expr_types.get(span))
+    }
+
+    /// Inferred type of the statement at `span`
+    pub fn stmt_type(&self, span: &Span) -> Option<&Type> {
+        self.types
+            .as_ref()
+            .and_then(|types| types.stmt_types.get(span))
+    }
+
+    /// Whether `name` at `location` refers to a parameter of an enclosing
+    /// function, method or closure
+    pub fn is_parameter(&self, name: &str, location: SourceLocation) -> bool {
+        self.param_scopes.iter().any(|(span, params)| {
+            contains(span, location) && params.iter().any(|param| param == name)
+        })
+    }
+
+    /// Index of the identifier token `name` that starts at or after `location`
+    pub fn identifier_after(&self, name: &str, location: SourceLocation) -> Option<usize> {
+        self.tokens.iter().position(|token| {
+            token.span.start.byte_offset >= location.byte_offset
+                && matches!(&token.kind, TokenKind::Identifier(found) if found == name)
+        })
+    }
+}
+
+/// Whether inference pinned `ty` down completely
+///
+/// Unconstrained numeric literals and names inference could not see leave
+/// type variables and unknowns behind, which are not worth showing.
+pub(crate) fn is_resolved(ty: &Type) -> bool {
+    match ty {
+        Type::TypeVar(_) | Type::Unknown | Type::TypeParam(_) => false,
+        Type::Array(inner) | Type::Option(inner) | Type::Future(inner) => is_resolved(inner),
+        Type::Result { ok, err } => is_resolved(ok) && is_resolved(err),
+        Type::Function { params, ret } => params.iter().all(is_resolved) && is_resolved(ret),
+        Type::Generic { args, .. } => args.iter().all(is_resolved),
+        Type::Tuple(items) => items.iter().all(is_resolved),
+        Type::Reference { inner, .. } => is_resolved(inner),
+        _ => true,
+    }
+}
+
+fn contains(span: &Span, location: SourceLocation) -> bool {
+    span.start.byte_offset <= location.byte_offset && location.byte_offset < span.end.byte_offset
+}
+
+impl Visitor for DocumentAnalysis {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Struct { name, .. } => {
+                self.structs.insert(name.clone());
+            }
+            StmtKind::Enum { name, .. } => {
+                self.enums.insert(name.clone());
+            }
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_function(&mut self, name: &str, params: &[Param], body: &Block, stmt: &Stmt) {
+        let is_async = match &stmt.kind {
+            StmtKind::Function { is_async, .. } => *is_async,
+            StmtKind::Export {
+                export: ExportKind::Function { is_async, .. },
+            } => *is_async,
+            _ => false,
+        };
+        let params: Vec<String> = params.iter().map(|param| param.name.clone()).collect();
+        self.param_scopes.push((stmt.span, params.clone()));
+        self.functions
+            .insert(name.to_string(), FunctionInfo { params, is_async });
+        self.visit_block(body);
+    }
+
+    fn visit_method(&mut self, _impl_block: &ImplBlock, method: &Method) {
+        let params = method
+            .params
+            .iter()
+            .map(|param| param.name.clone())
+            .collect();
+        self.param_scopes.push((method.span, params));
+        self.visit_block(&method.body);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        if let ExprKind::Closure { parameters, .. } = &expr.kind {
+            let params = parameters.iter().map(|param| param.name.clone()).collect();
+            self.param_scopes.push((expr.span, params));
+        }
+        walk_expr(self, expr);
+    }
+}
@@ -1,8 +1,10 @@
 use tower_lsp::lsp_types::{
-    CompletionOptions, SemanticTokenModifier, SemanticTokenType, SemanticTokensFullOptions,
-    SemanticTokensLegend, SemanticTokensOptions, SemanticTokensServerCapabilities,
-    ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, TextDocumentSyncOptions,
-    WorkDoneProgressOptions,
+    CallHierarchyServerCapability, CodeActionKind, CodeActionOptions, CodeActionProviderCapability,
+    CompletionOptions, DocumentOnTypeFormattingOptions, FoldingRangeProviderCapability,
+    RenameOptions, SelectionRangeProviderCapability, SemanticTokenModifier, SemanticTokenType,
+    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
+    SemanticTokensServerCapabilities, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextDocumentSyncOptions, WorkDoneProgressOptions,
 };
 
 /// Define the semantic token types we support for Script language
@@ -80,6 +82,40 @@ pub fn get_server_capabilities() -> ServerCapabilities {
         // Definition provider for go-to definition
         definition_provider: Some(tower_lsp::lsp_types::OneOf::Left(true)),
 
+        // Project-wide queries backed by the persistent symbol index
+        references_provider: Some(tower_lsp::lsp_types::OneOf::Left(true)),
+        workspace_symbol_provider: Some(tower_lsp::lsp_types::OneOf::Left(true)),
+        call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+        rename_provider: Some(tower_lsp::lsp_types::OneOf::Right(RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: WorkDoneProgressOptions::default(),
+        })),
+
+        // Inferred types, parameter names and async markers shown inline
+        inlay_hint_provider: Some(tower_lsp::lsp_types::OneOf::Left(true)),
+
+        // Structural folding and expand-selection
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+
+        // Formatting honours the nearest script-fmt.toml
+        document_formatting_provider: Some(tower_lsp::lsp_types::OneOf::Left(true)),
+        document_range_formatting_provider: Some(tower_lsp::lsp_types::OneOf::Left(true)),
+        document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+            first_trigger_character: "}".to_string(),
+            more_trigger_character: Some(vec![";".to_string()]),
+        }),
+
+        // Quick fixes for diagnostics and organize imports
+        code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+            code_action_kinds: Some(vec![
+                CodeActionKind::QUICKFIX,
+                CodeActionKind::SOURCE_ORGANIZE_IMPORTS,
+            ]),
+            work_done_progress_options: WorkDoneProgressOptions::default(),
+            resolve_provider: None,
+        })),
+
         // Additional capabilities can be added here
         ..Default::default()
     }
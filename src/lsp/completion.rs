@@ -74,6 +74,15 @@ pub fn generate_completions(
     CompletionResponse::Array(items)
 }
 
+/// Prefix of the identifier being completed, if the cursor is in an
+/// expression position
+pub fn expression_prefix(content: &str, position: Position) -> Option<String> {
+    match get_completion_context(content, position) {
+        CompletionContext::Expression { prefix } => Some(prefix),
+        _ => None,
+    }
+}
+
 /// Context for completion
 #[derive(Debug)]
 enum CompletionContext {
@@ -457,7 +466,11 @@ fn format_function_signature(name: &str, ty: &Type) -> String {
 fn format_type(ty: &Type) -> String {
     match ty {
         Type::I32 => "i32".to_string(),
+        Type::I64 => "i64".to_string(),
+        Type::U32 => "u32".to_string(),
+        Type::U64 => "u64".to_string(),
         Type::F32 => "f32".to_string(),
+        Type::F64 => "f64".to_string(),
         Type::Bool => "bool".to_string(),
         Type::String => "string".to_string(),
         Type::Array(inner) => format!("Array<{}>", format_type(inner)),
@@ -167,6 +167,7 @@ fn find_identifier_in_stmt(stmt: &Stmt, target: &SourceLocation) -> Option<Ident
         StmtKind::Export { .. } => {
             // Handle exports if needed
         }
+        StmtKind::Break | StmtKind::Continue => {}
         StmtKind::Struct { name, .. } => {
             // Check if target is on the struct name
             if stmt.span.contains_location(target) {
@@ -195,6 +196,14 @@ fn find_identifier_in_stmt(stmt: &Stmt, target: &SourceLocation) -> Option<Ident
             }
             // TODO: Check methods within impl block
         }
+        StmtKind::Trait(trait_decl) => {
+            if stmt.span.contains_location(target) {
+                return Some(IdentifierInfo {
+                    name: trait_decl.name.clone(),
+                    span: stmt.span,
+                });
+            }
+        }
     }
 
     None
@@ -360,7 +369,9 @@ fn find_identifier_in_expr(expr: &Expr, target: &SourceLocation) -> Option<Ident
                 }
             }
         }
-        ExprKind::ErrorPropagation { expr } => find_identifier_in_expr(expr, target),
+        ExprKind::ErrorPropagation { expr } | ExprKind::Cast { expr, .. } => {
+            find_identifier_in_expr(expr, target)
+        }
         ExprKind::TryCatch {
             try_expr,
             catch_clauses: _,
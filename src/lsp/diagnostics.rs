@@ -0,0 +1,193 @@
+use crate::error::Error;
+use crate::lexer::Lexer;
+use crate::lint::{lint_source, LintConfig, LintDiagnostic, LintLevel};
+use crate::parser::Parser;
+use crate::semantic::{SemanticAnalyzer, SemanticError};
+use crate::source::Span;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range};
+
+/// Where a document lives, so its imports resolve the way the compiler
+/// would resolve them
+#[derive(Debug, Clone, Default)]
+pub struct DocumentContext {
+    /// Path of the document, for imports relative to it
+    pub file: Option<PathBuf>,
+    /// Workspace root, searched for imported modules
+    pub root: Option<PathBuf>,
+    /// Contents of open documents by canonical path, read instead of the
+    /// files on disk when they are imported
+    pub sources: Arc<HashMap<PathBuf, String>>,
+}
+
+/// The errors the compiler reports for a document
+///
+/// Lexing and parsing stop at their first error, so a document with a
+/// syntax error only reports that; otherwise every semantic error is
+/// reported, each with the fixes the analyzer attached. An imported module
+/// that fails to compile is reported at the import.
+pub fn document_errors(content: &str, context: &DocumentContext) -> Vec<Error> {
+    let lexer = match Lexer::new(content) {
+        Ok(lexer) => lexer,
+        Err(error) => return vec![error],
+    };
+    let (tokens, errors) = lexer.scan_tokens();
+    if !errors.is_empty() {
+        return errors;
+    }
+
+    let program = match Parser::new(tokens).parse() {
+        Ok(program) => program,
+        Err(error) => return vec![error],
+    };
+
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.set_current_file(context.file.clone());
+    if let Some(root) = &context.root {
+        analyzer.add_module_search_path(root.clone());
+    }
+    analyzer.set_source_overrides(context.sources.clone());
+    if let Err(error) = analyzer.analyze_program(&program) {
+        return vec![error];
+    }
+    analyzer
+        .errors()
+        .iter()
+        .cloned()
+        .map(SemanticError::into_error)
+        .collect()
+}
+
+/// The lints reported for a document
+///
+/// Levels come from the `[lints]` of the project the document is in. A
+/// document that does not parse has no lints; its syntax error is reported
+/// instead.
+pub fn document_lints(content: &str, context: &DocumentContext) -> Vec<LintDiagnostic> {
+    let config = context
+        .file
+        .as_deref()
+        .and_then(Path::parent)
+        .and_then(|dir| LintConfig::discover(dir).ok())
+        .unwrap_or_default();
+    lint_source(content, &config).unwrap_or_default()
+}
+
+/// Diagnostics for every error and lint in a document
+pub fn document_diagnostics(content: &str, context: &DocumentContext) -> Vec<Diagnostic> {
+    let errors = document_errors(content, context);
+    let lints = document_lints(content, context);
+    errors
+        .iter()
+        .map(|error| to_diagnostic(error, content))
+        .chain(lints.iter().map(lint_to_diagnostic))
+        .collect()
+}
+
+/// The diagnostic for `error`
+///
+/// Errors only record where they start, so the range covers the identifier
+/// at that position, or a single character if there is none. The diagnostic
+/// is coded with the error's code, which `script explain` describes.
+pub fn to_diagnostic(error: &Error, content: &str) -> Diagnostic {
+    let start = error.location.map_or(Position::new(0, 0), |location| {
+        Position::new(
+            location.line.saturating_sub(1) as u32,
+            location.column.saturating_sub(1) as u32,
+        )
+    });
+    let width = content.lines().nth(start.line as usize).map_or(0, |line| {
+        let rest: Vec<char> = line.chars().skip(start.character as usize).collect();
+        let word = rest
+            .iter()
+            .take_while(|c| c.is_alphanumeric() || **c == '_')
+            .count();
+        word.max(rest.len().min(1))
+    });
+
+    Diagnostic {
+        range: Range::new(
+            start,
+            Position::new(start.line, start.character + width as u32),
+        ),
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: Some(NumberOrString::String(error.code.to_string())),
+        source: Some("script".to_string()),
+        message: error
+            .notes
+            .iter()
+            .fold(error.message.clone(), |message, note| {
+                format!("{}\nnote: {}", message, note)
+            }),
+        ..Default::default()
+    }
+}
+
+/// The diagnostic for `lint`, coded with the lint's name
+pub fn lint_to_diagnostic(lint: &LintDiagnostic) -> Diagnostic {
+    let severity = match lint.level {
+        LintLevel::Deny => DiagnosticSeverity::ERROR,
+        _ => DiagnosticSeverity::WARNING,
+    };
+    Diagnostic {
+        range: span_to_range(&lint.span),
+        severity: Some(severity),
+        code: Some(NumberOrString::String(lint.lint.name().to_string())),
+        source: Some("script".to_string()),
+        message: lint.message.clone(),
+        ..Default::default()
+    }
+}
+
+/// The lexer counts lines and columns from 1, LSP from 0
+pub(crate) fn span_to_range(span: &Span) -> Range {
+    let position = |line: usize, column: usize| {
+        Position::new(
+            line.saturating_sub(1) as u32,
+            column.saturating_sub(1) as u32,
+        )
+    };
+    Range::new(
+        position(span.start.line, span.start.column),
+        position(span.end.line, span.end.column),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostic_covers_identifier() {
+        let context = DocumentContext::default();
+        let diagnostics = document_diagnostics("fn main() {\n    totl + 1;\n}\n", &context);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].range,
+            Range::new(Position::new(1, 4), Position::new(1, 8))
+        );
+        assert!(diagnostics[0]
+            .message
+            .starts_with("cannot find variable 'totl'"));
+
+        assert!(document_diagnostics("fn main() {\n    1 + 1;\n}\n", &context).is_empty());
+    }
+
+    #[test]
+    fn test_lints_are_warnings() {
+        let context = DocumentContext::default();
+        let diagnostics = document_diagnostics("fn main() {\n    let count = 1;\n}\n", &context);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("unused_variables".to_string()))
+        );
+        assert_eq!(
+            diagnostics[0].range,
+            Range::new(Position::new(1, 8), Position::new(1, 13))
+        );
+    }
+}
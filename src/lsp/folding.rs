@@ -0,0 +1,263 @@
+use crate::lexer::Lexer;
+use crate::parser::visitor::{walk_expr, walk_stmt};
+use crate::parser::{Expr, ExprKind, ImplBlock, Method, Parser, Stmt, StmtKind, Visitor};
+use crate::source::Span;
+use tower_lsp::lsp_types::{FoldingRange, FoldingRangeKind};
+
+/// Compute the folding ranges of a document
+///
+/// Declarations, blocks and match arms fold from their first line; their
+/// closing line stays visible when it holds a closing brace. Multi-line
+/// block comments and runs of line comments fold as comments. Structural
+/// ranges are only produced when the document parses.
+pub fn folding_ranges(content: &str) -> Vec<FoldingRange> {
+    let mut collector = FoldingCollector::default();
+
+    if let Ok(lexer) = Lexer::new(content) {
+        let (tokens, errors) = lexer.scan_tokens();
+        if errors.is_empty() {
+            if let Ok(program) = Parser::new(tokens).parse() {
+                collector.visit_program(&program);
+            }
+        }
+    }
+
+    let mut ranges = collector.ranges;
+    ranges.extend(comment_ranges(content));
+
+    // Several nodes can start on the same line (`if` and its block, say);
+    // keep the widest range for each line
+    ranges.sort_by(|a, b| {
+        a.start_line
+            .cmp(&b.start_line)
+            .then(b.end_line.cmp(&a.end_line))
+    });
+    ranges.dedup_by_key(|range| range.start_line);
+    ranges
+}
+
+#[derive(Default)]
+struct FoldingCollector {
+    ranges: Vec<FoldingRange>,
+}
+
+impl FoldingCollector {
+    /// Fold a node ending in a closing brace, keeping that line visible
+    fn fold_braced(&mut self, span: Span) {
+        if span.end.line > span.start.line + 1 {
+            self.push(span.start.line - 1, span.end.line - 2);
+        }
+    }
+
+    /// Fold a node through its last line
+    fn fold_through(&mut self, span: Span) {
+        if span.end.line > span.start.line {
+            self.push(span.start.line - 1, span.end.line - 1);
+        }
+    }
+
+    fn push(&mut self, start_line: usize, end_line: usize) {
+        self.ranges.push(FoldingRange {
+            start_line: start_line as u32,
+            start_character: None,
+            end_line: end_line as u32,
+            end_character: None,
+            kind: Some(FoldingRangeKind::Region),
+            collapsed_text: None,
+        });
+    }
+}
+
+impl Visitor for FoldingCollector {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::Function { .. }
+            | StmtKind::While { .. }
+            | StmtKind::For { .. }
+            | StmtKind::Struct { .. }
+            | StmtKind::Enum { .. }
+            | StmtKind::Impl(_)
+            | StmtKind::Export { .. } => self.fold_braced(stmt.span),
+            StmtKind::Trait(trait_decl) => {
+                self.fold_braced(stmt.span);
+                for method in &trait_decl.methods {
+                    if method.default_body.is_some() {
+                        self.fold_braced(method.span);
+                    }
+                }
+            }
+            _ => {}
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_method(&mut self, _impl_block: &ImplBlock, method: &Method) {
+        self.fold_braced(method.span);
+        self.visit_block(&method.body);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Block(_)
+            | ExprKind::If { .. }
+            | ExprKind::Array(_)
+            | ExprKind::Call { .. }
+            | ExprKind::StructConstructor { .. }
+            | ExprKind::TryCatch { .. } => self.fold_braced(expr.span),
+            ExprKind::Match { arms, .. } => {
+                self.fold_braced(expr.span);
+                for arm in arms {
+                    let span = Span::new(arm.pattern.span.start, arm.body.span.end);
+                    match arm.body.kind {
+                        ExprKind::Block(_) => self.fold_braced(span),
+                        _ => self.fold_through(span),
+                    }
+                }
+            }
+            ExprKind::Closure { body, .. } => match body.kind {
+                ExprKind::Block(_) => {}
+                _ => self.fold_through(expr.span),
+            },
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// Folding ranges for multi-line block comments and for runs of two or
+/// more lines that hold only a line comment
+///
+/// Comments are not part of the token stream, so the source is scanned
+/// directly, skipping string literals.
+fn comment_ranges(content: &str) -> Vec<FoldingRange> {
+    let mut ranges = Vec::new();
+    let mut chars = content.chars().peekable();
+    let mut line = 0u32;
+    // Whether the current line has had anything but whitespace before `//`
+    let mut line_has_code = false;
+    // First and last line of the current run of comment-only lines
+    let mut comment_run: Option<(u32, u32)> = None;
+
+    let comment = |start_line, end_line| FoldingRange {
+        start_line,
+        start_character: None,
+        end_line,
+        end_character: None,
+        kind: Some(FoldingRangeKind::Comment),
+        collapsed_text: None,
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => {
+                line += 1;
+                line_has_code = false;
+            }
+            '"' => {
+                line_has_code = true;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            if chars.next() == Some('\n') {
+                                line += 1;
+                            }
+                        }
+                        '\n' => line += 1,
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                if !line_has_code {
+                    comment_run = match comment_run {
+                        Some((start, end)) if end + 1 == line => Some((start, line)),
+                        run => {
+                            if let Some((start, end)) = run.filter(|(start, end)| end > start) {
+                                ranges.push(comment(start, end));
+                            }
+                            Some((line, line))
+                        }
+                    };
+                }
+                // Skip the rest of the line, leaving the newline to the outer loop
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let start = line;
+                let mut depth = 1;
+                while depth > 0 {
+                    match chars.next() {
+                        Some('\n') => line += 1,
+                        Some('/') if chars.peek() == Some(&'*') => {
+                            chars.next();
+                            depth += 1;
+                        }
+                        Some('*') if chars.peek() == Some(&'/') => {
+                            chars.next();
+                            depth -= 1;
+                        }
+                        Some(_) => {}
+                        None => break,
+                    }
+                }
+                if line > start {
+                    ranges.push(comment(start, line));
+                }
+                line_has_code = true;
+            }
+            c if c.is_whitespace() => {}
+            _ => line_has_code = true,
+        }
+    }
+
+    if let Some((start, end)) = comment_run.filter(|(start, end)| end > start) {
+        ranges.push(comment(start, end));
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(ranges: &[FoldingRange]) -> Vec<(u32, u32)> {
+        ranges
+            .iter()
+            .map(|range| (range.start_line, range.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_structural_folding() {
+        let content = "fn classify(n: i32) -> string {\n\
+                       \x20   match n {\n\
+                       \x20       0 => {\n\
+                       \x20           \"zero\"\n\
+                       \x20       },\n\
+                       \x20       _ => \"other\"\n\
+                       \x20   }\n\
+                       }\n";
+
+        let ranges = folding_ranges(content);
+        assert_eq!(lines(&ranges), vec![(0, 6), (1, 5), (2, 3)]);
+        assert!(ranges
+            .iter()
+            .all(|range| range.kind == Some(FoldingRangeKind::Region)));
+    }
+
+    #[test]
+    fn test_comment_folding() {
+        let content = "// first\n// second\nlet x = 1; // trailing\n/* a\n   b */\nlet s = \"/* not a comment\";\n";
+
+        let ranges = folding_ranges(content);
+        assert_eq!(lines(&ranges), vec![(0, 1), (3, 4)]);
+        assert!(ranges
+            .iter()
+            .all(|range| range.kind == Some(FoldingRangeKind::Comment)));
+    }
+}
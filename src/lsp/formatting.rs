@@ -0,0 +1,119 @@
+use crate::error::Result;
+use crate::formatter::{format_range, FormatterConfig};
+use std::path::Path;
+use tower_lsp::lsp_types::{FormattingOptions, Position, Range, TextEdit};
+
+/// Formatter configuration for a document
+///
+/// The nearest `script-fmt.toml` wins; without one the editor's indentation
+/// settings apply.
+pub fn formatter_config(
+    path: Option<&Path>,
+    options: &FormattingOptions,
+) -> Result<FormatterConfig> {
+    if let Some(dir) = path.and_then(Path::parent) {
+        if let Some(config) = FormatterConfig::discover(dir)? {
+            return Ok(config);
+        }
+    }
+
+    Ok(FormatterConfig {
+        indent_size: options.tab_size as usize,
+        use_spaces: options.insert_spaces,
+        ..FormatterConfig::default()
+    })
+}
+
+/// Edits reformatting the top-level declarations overlapping `range`
+///
+/// Documents that do not parse are left untouched, since the edited region
+/// is usually incomplete while typing.
+pub fn range_formatting_edits(
+    content: &str,
+    config: &FormatterConfig,
+    range: Range,
+) -> Vec<TextEdit> {
+    let lines = range.start.line as usize + 1..=range.end.line as usize + 1;
+    let Ok(Some(formatted)) = format_range(content, config, lines) else {
+        return Vec::new();
+    };
+
+    let last_line = content
+        .lines()
+        .nth(formatted.end_line - 1)
+        .unwrap_or_default();
+    vec![TextEdit {
+        range: Range {
+            start: Position::new(formatted.start_line as u32 - 1, 0),
+            end: Position::new(
+                formatted.end_line as u32 - 1,
+                last_line.encode_utf16().count() as u32,
+            ),
+        },
+        new_text: formatted.text,
+    }]
+}
+
+/// Edits reformatting the whole document
+pub fn document_formatting_edits(content: &str, config: &FormatterConfig) -> Vec<TextEdit> {
+    let last_line = content.lines().count().max(1) as u32 - 1;
+    range_formatting_edits(
+        content,
+        config,
+        Range::new(Position::new(0, 0), Position::new(last_line, 0)),
+    )
+}
+
+/// Edits reformatting the declaration just completed by typing `ch`
+pub fn on_type_formatting_edits(
+    content: &str,
+    config: &FormatterConfig,
+    position: Position,
+    ch: &str,
+) -> Vec<TextEdit> {
+    if ch != "}" && ch != ";" {
+        return Vec::new();
+    }
+    range_formatting_edits(content, config, Range::new(position, position))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_formatting_edits() {
+        let content = "let   a = 1;\nlet b =2;\n";
+        let config = FormatterConfig::default();
+
+        let edits = range_formatting_edits(
+            content,
+            &config,
+            Range::new(Position::new(1, 0), Position::new(1, 3)),
+        );
+        assert_eq!(edits.len(), 1);
+        assert_eq!(
+            edits[0].range,
+            Range::new(Position::new(1, 0), Position::new(1, 9))
+        );
+        assert_eq!(edits[0].new_text, "let b = 2;");
+
+        // An unfinished document produces no edits
+        let edits = on_type_formatting_edits("fn main() {\n", &config, Position::new(0, 11), "}");
+        assert!(edits.is_empty());
+    }
+
+    #[test]
+    fn test_editor_options_without_config_file() {
+        let options = FormattingOptions {
+            tab_size: 2,
+            insert_spaces: true,
+            ..FormattingOptions::default()
+        };
+        let config = formatter_config(None, &options).unwrap();
+        assert_eq!(config.indent_size, 2);
+
+        let edits = document_formatting_edits("fn main() {\nlet x = 1;\n}\n", &config);
+        assert_eq!(edits[0].new_text, "fn main() {\n  let x = 1;\n}");
+    }
+}
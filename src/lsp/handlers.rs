@@ -1,12 +1,36 @@
-use crate::lsp::completion::generate_completions;
+use crate::lsp::completion::{expression_prefix, generate_completions};
 use crate::lsp::definition::goto_definition;
+use crate::lsp::folding::folding_ranges;
+use crate::lsp::formatting::{
+    document_formatting_edits, formatter_config, on_type_formatting_edits, range_formatting_edits,
+};
+use crate::lsp::hierarchy::{
+    incoming_calls, outgoing_calls, prepare_call_hierarchy, prepare_type_hierarchy, subtypes,
+    supertypes,
+};
+use crate::lsp::imports::{auto_import_completions, import_edits, organize_imports};
+use crate::lsp::inlay_hints::inlay_hints;
+use crate::lsp::quick_fixes::quick_fixes;
+use crate::lsp::references::{find_references, identifier_at_position, workspace_symbols};
+use crate::lsp::rename::{prepare_rename, rename_symbol};
+use crate::lsp::selection_range::selection_ranges;
 use crate::lsp::semantic_tokens::generate_semantic_tokens;
 use crate::lsp::state::ServerState;
+use std::collections::HashMap;
+use std::path::Path;
 use tower_lsp::jsonrpc::{Error, Result};
 use tower_lsp::lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyIncomingCallsParams, CallHierarchyItem,
+    CallHierarchyOutgoingCall, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionResponse,
     CompletionParams, CompletionResponse, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
-    DidOpenTextDocumentParams, GotoDefinitionParams, GotoDefinitionResponse, SemanticTokens,
-    SemanticTokensParams, SemanticTokensResult,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentOnTypeFormattingParams,
+    DocumentRangeFormattingParams, FoldingRange, FoldingRangeParams, GotoDefinitionParams,
+    GotoDefinitionResponse, InlayHint, InlayHintParams, Location, PrepareRenameResponse,
+    ReferenceParams, RenameParams, SelectionRange, SelectionRangeParams, SemanticTokens,
+    SemanticTokensParams, SemanticTokensResult, SymbolInformation, TextDocumentPositionParams,
+    TextEdit, TypeHierarchyItem, TypeHierarchyPrepareParams, TypeHierarchySubtypesParams,
+    TypeHierarchySupertypesParams, Url, WorkspaceEdit, WorkspaceSymbolParams,
 };
 
 /// Handle textDocument/didOpen notification
@@ -71,6 +95,18 @@ pub async fn handle_semantic_tokens_full(
     Ok(Some(SemanticTokensResult::Tokens(result)))
 }
 
+/// Handle textDocument/inlayHint request
+pub async fn handle_inlay_hint(
+    state: &ServerState,
+    params: InlayHintParams,
+) -> Result<Option<Vec<InlayHint>>> {
+    let document = state
+        .get_document(&params.text_document.uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+
+    Ok(Some(inlay_hints(&document.content, params.range)))
+}
+
 /// Handle textDocument/completion request
 pub async fn handle_completion(
     state: &ServerState,
@@ -92,7 +128,22 @@ pub async fn handle_completion(
         .unwrap_or(tower_lsp::lsp_types::CompletionTriggerKind::INVOKED);
 
     // Generate completions
-    let completions = generate_completions(&document.content, position, trigger_kind);
+    let mut completions = generate_completions(&document.content, position, trigger_kind);
+
+    // Offer exported project symbols together with the import they need
+    if let (Some(prefix), Ok(path)) = (
+        expression_prefix(&document.content, position),
+        uri.to_file_path(),
+    ) {
+        let imports = state
+            .with_symbol_index(|index| {
+                auto_import_completions(index, &document.content, &path, &prefix)
+            })
+            .unwrap_or_default();
+        if let CompletionResponse::Array(items) = &mut completions {
+            items.extend(imports);
+        }
+    }
 
     Ok(Some(completions))
 }
@@ -117,6 +168,319 @@ pub async fn handle_goto_definition(
     Ok(location.map(GotoDefinitionResponse::Scalar))
 }
 
+/// Handle textDocument/foldingRange request
+pub async fn handle_folding_range(
+    state: &ServerState,
+    params: FoldingRangeParams,
+) -> Result<Option<Vec<FoldingRange>>> {
+    let document = state
+        .get_document(&params.text_document.uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+
+    Ok(Some(folding_ranges(&document.content)))
+}
+
+/// Handle textDocument/formatting request
+pub async fn handle_formatting(
+    state: &ServerState,
+    params: DocumentFormattingParams,
+) -> Result<Option<Vec<TextEdit>>> {
+    let uri = params.text_document.uri;
+    let document = state
+        .get_document(&uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+
+    let path = uri.to_file_path().ok();
+    let config = formatter_config(path.as_deref(), &params.options)
+        .map_err(|e| Error::invalid_params(e.to_string()))?;
+    Ok(Some(document_formatting_edits(&document.content, &config)))
+}
+
+/// Handle textDocument/rangeFormatting request
+pub async fn handle_range_formatting(
+    state: &ServerState,
+    params: DocumentRangeFormattingParams,
+) -> Result<Option<Vec<TextEdit>>> {
+    let uri = params.text_document.uri;
+    let document = state
+        .get_document(&uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+
+    let path = uri.to_file_path().ok();
+    let config = formatter_config(path.as_deref(), &params.options)
+        .map_err(|e| Error::invalid_params(e.to_string()))?;
+    Ok(Some(range_formatting_edits(
+        &document.content,
+        &config,
+        params.range,
+    )))
+}
+
+/// Handle textDocument/onTypeFormatting request
+pub async fn handle_on_type_formatting(
+    state: &ServerState,
+    params: DocumentOnTypeFormattingParams,
+) -> Result<Option<Vec<TextEdit>>> {
+    let uri = params.text_document_position.text_document.uri;
+    let position = params.text_document_position.position;
+    let document = state
+        .get_document(&uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+
+    let path = uri.to_file_path().ok();
+    let config = formatter_config(path.as_deref(), &params.options)
+        .map_err(|e| Error::invalid_params(e.to_string()))?;
+    Ok(Some(on_type_formatting_edits(
+        &document.content,
+        &config,
+        position,
+        &params.ch,
+    )))
+}
+
+/// Handle textDocument/selectionRange request
+pub async fn handle_selection_range(
+    state: &ServerState,
+    params: SelectionRangeParams,
+) -> Result<Option<Vec<SelectionRange>>> {
+    let document = state
+        .get_document(&params.text_document.uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+
+    Ok(Some(selection_ranges(&document.content, &params.positions)))
+}
+
+/// Handle textDocument/codeAction request
+pub async fn handle_code_action(
+    state: &ServerState,
+    params: CodeActionParams,
+) -> Result<Option<CodeActionResponse>> {
+    let uri = params.text_document.uri;
+
+    // Skip the work the client did not ask for
+    let only = params.context.only.as_deref();
+    let quick_fix = requested(only, &CodeActionKind::QUICKFIX);
+    let organize = requested(only, &CodeActionKind::SOURCE_ORGANIZE_IMPORTS);
+    if !quick_fix && !organize {
+        return Ok(None);
+    }
+
+    let document = state
+        .get_document(&uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+
+    let mut actions = Vec::new();
+
+    if quick_fix {
+        let path = uri.to_file_path().ok();
+        let context = state.document_context(&uri);
+        let fixes = quick_fixes(&uri, &document.content, &context, params.range, |name| {
+            path.as_ref()
+                .and_then(|path| {
+                    state.with_symbol_index(|index| {
+                        import_edits(index, &document.content, path, name)
+                    })
+                })
+                .unwrap_or_default()
+        });
+        actions.extend(fixes.into_iter().map(CodeActionOrCommand::CodeAction));
+    }
+
+    if organize {
+        let edits = organize_imports(&document.content);
+        if !edits.is_empty() {
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: "Organize imports".to_string(),
+                kind: Some(CodeActionKind::SOURCE_ORGANIZE_IMPORTS),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(uri, edits)])),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }));
+        }
+    }
+
+    if actions.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(actions))
+}
+
+/// Whether a code action of `kind` falls under the kinds the client asked
+/// for, where no list means every kind
+fn requested(only: Option<&[CodeActionKind]>, kind: &CodeActionKind) -> bool {
+    only.map_or(true, |only| {
+        only.iter().any(|requested| {
+            kind.as_str() == requested.as_str()
+                || kind
+                    .as_str()
+                    .starts_with(&format!("{}.", requested.as_str()))
+        })
+    })
+}
+
+/// Handle textDocument/references request
+pub async fn handle_references(
+    state: &ServerState,
+    params: ReferenceParams,
+) -> Result<Option<Vec<Location>>> {
+    let uri = params.text_document_position.text_document.uri;
+    let position = params.text_document_position.position;
+
+    let document = state
+        .get_document(&uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+
+    let name = match identifier_at_position(&document.content, position) {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+
+    let include_declaration = params.context.include_declaration;
+    Ok(state.with_symbol_index(|index| find_references(index, &name, include_declaration)))
+}
+
+/// Handle workspace/symbol request
+pub async fn handle_workspace_symbol(
+    state: &ServerState,
+    params: WorkspaceSymbolParams,
+) -> Result<Option<Vec<SymbolInformation>>> {
+    Ok(state.with_symbol_index(|index| workspace_symbols(index, &params.query)))
+}
+
+/// Handle textDocument/prepareRename request
+pub async fn handle_prepare_rename(
+    state: &ServerState,
+    params: TextDocumentPositionParams,
+) -> Result<Option<PrepareRenameResponse>> {
+    let uri = params.text_document.uri;
+    let document = state
+        .get_document(&uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+    let path = uri
+        .to_file_path()
+        .map_err(|_| Error::invalid_params("Rename needs a file on disk"))?;
+
+    state
+        .with_symbol_index(|index| prepare_rename(index, &path, &document.content, params.position))
+        .unwrap_or(Ok(None))
+        .map_err(Error::invalid_params)
+}
+
+/// Handle textDocument/rename request
+pub async fn handle_rename(
+    state: &ServerState,
+    params: RenameParams,
+) -> Result<Option<WorkspaceEdit>> {
+    let uri = params.text_document_position.text_document.uri;
+    let position = params.text_document_position.position;
+    let document = state
+        .get_document(&uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+    let path = uri
+        .to_file_path()
+        .map_err(|_| Error::invalid_params("Rename needs a file on disk"))?;
+
+    // Unsaved editor contents take precedence over the files on disk
+    let read_source = |file: &Path| {
+        Url::from_file_path(file)
+            .ok()
+            .and_then(|uri| state.get_document(&uri))
+            .map(|document| document.content)
+            .or_else(|| std::fs::read_to_string(file).ok())
+    };
+    state
+        .with_symbol_index(|index| {
+            rename_symbol(
+                index,
+                &path,
+                &document.content,
+                position,
+                &params.new_name,
+                read_source,
+            )
+        })
+        .unwrap_or(Ok(None))
+        .map_err(Error::invalid_params)
+}
+
+/// Handle textDocument/prepareCallHierarchy request
+pub async fn handle_prepare_call_hierarchy(
+    state: &ServerState,
+    params: CallHierarchyPrepareParams,
+) -> Result<Option<Vec<CallHierarchyItem>>> {
+    let uri = params.text_document_position_params.text_document.uri;
+    let position = params.text_document_position_params.position;
+
+    let document = state
+        .get_document(&uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+
+    let name = match identifier_at_position(&document.content, position) {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+
+    Ok(state
+        .with_symbol_index(|index| prepare_call_hierarchy(index, &name))
+        .filter(|items| !items.is_empty()))
+}
+
+/// Handle callHierarchy/incomingCalls request
+pub async fn handle_incoming_calls(
+    state: &ServerState,
+    params: CallHierarchyIncomingCallsParams,
+) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+    Ok(state.with_symbol_index(|index| incoming_calls(index, &params.item)))
+}
+
+/// Handle callHierarchy/outgoingCalls request
+pub async fn handle_outgoing_calls(
+    state: &ServerState,
+    params: CallHierarchyOutgoingCallsParams,
+) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+    Ok(state.with_symbol_index(|index| outgoing_calls(index, &params.item)))
+}
+
+/// Handle textDocument/prepareTypeHierarchy request
+pub async fn handle_prepare_type_hierarchy(
+    state: &ServerState,
+    params: TypeHierarchyPrepareParams,
+) -> Result<Option<Vec<TypeHierarchyItem>>> {
+    let uri = params.text_document_position_params.text_document.uri;
+    let position = params.text_document_position_params.position;
+
+    let document = state
+        .get_document(&uri)
+        .ok_or_else(|| Error::invalid_params("Document not found"))?;
+
+    let name = match identifier_at_position(&document.content, position) {
+        Some(name) => name,
+        None => return Ok(None),
+    };
+
+    Ok(state
+        .with_symbol_index(|index| prepare_type_hierarchy(index, &name))
+        .filter(|items| !items.is_empty()))
+}
+
+/// Handle typeHierarchy/supertypes request
+pub async fn handle_supertypes(
+    state: &ServerState,
+    params: TypeHierarchySupertypesParams,
+) -> Result<Option<Vec<TypeHierarchyItem>>> {
+    Ok(state.with_symbol_index(|index| supertypes(index, &params.item)))
+}
+
+/// Handle typeHierarchy/subtypes request
+pub async fn handle_subtypes(
+    state: &ServerState,
+    params: TypeHierarchySubtypesParams,
+) -> Result<Option<Vec<TypeHierarchyItem>>> {
+    Ok(state.with_symbol_index(|index| subtypes(index, &params.item)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
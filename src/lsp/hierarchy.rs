@@ -0,0 +1,327 @@
+use crate::index::callgraph::resolve_callees;
+use crate::index::{IndexedSymbol, IndexedSymbolKind, ReferenceKind, SymbolIndex};
+use crate::lsp::references::{symbol_kind, to_location};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tower_lsp::lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, Location, Range,
+    SymbolKind, TypeHierarchyItem, Url,
+};
+
+/// Call hierarchy items for the functions and methods named `name`
+pub fn prepare_call_hierarchy(index: &SymbolIndex, name: &str) -> Vec<CallHierarchyItem> {
+    index
+        .find_definitions(name)
+        .into_iter()
+        .filter(|(_, symbol)| is_callable(symbol))
+        .filter_map(|(path, symbol)| call_item(index, path, symbol))
+        .collect()
+}
+
+/// Functions and methods that call `item`, with the ranges of each call
+///
+/// Calls made from module-level statements are reported with a module
+/// item for the calling file.
+pub fn incoming_calls(
+    index: &SymbolIndex,
+    item: &CallHierarchyItem,
+) -> Vec<CallHierarchyIncomingCall> {
+    let target = qualified_name(&item.data, &item.name);
+    let Some((_, symbol)) = find_symbol(index, &target) else {
+        return Vec::new();
+    };
+    let candidates = callables_named(index, &symbol.name);
+
+    // Caller qualified name (or calling file for module-level code) -> call sites
+    let mut callers: BTreeMap<(Option<String>, &Path), Vec<Range>> = BTreeMap::new();
+    for (path, file) in index.files() {
+        for reference in &file.references {
+            if reference.kind != ReferenceKind::Call
+                || reference.name != symbol.name
+                || !resolve_callees(file, &candidates).contains(&symbol.qualified_name.as_str())
+            {
+                continue;
+            }
+            if let Some(location) = to_location(index.root(), path, &reference.span) {
+                callers
+                    .entry((reference.container.clone(), path))
+                    .or_default()
+                    .push(location.range);
+            }
+        }
+    }
+
+    callers
+        .into_iter()
+        .filter_map(|((container, path), from_ranges)| {
+            let from = match container {
+                Some(caller) => {
+                    let (path, symbol) = find_symbol(index, &caller)?;
+                    call_item(index, path, symbol)?
+                }
+                None => module_item(index, path, from_ranges[0])?,
+            };
+            Some(CallHierarchyIncomingCall { from, from_ranges })
+        })
+        .collect()
+}
+
+/// Functions and methods called by `item`, with the ranges of each call
+pub fn outgoing_calls(
+    index: &SymbolIndex,
+    item: &CallHierarchyItem,
+) -> Vec<CallHierarchyOutgoingCall> {
+    let caller = qualified_name(&item.data, &item.name);
+    let Some((path, _)) = find_symbol(index, &caller) else {
+        return Vec::new();
+    };
+    let Some(file) = index.file(&index.root().join(path)) else {
+        return Vec::new();
+    };
+
+    // Callee qualified name -> call sites
+    let mut callees: BTreeMap<&str, Vec<Range>> = BTreeMap::new();
+    for reference in &file.references {
+        if reference.kind != ReferenceKind::Call
+            || reference.container.as_deref() != Some(caller.as_str())
+        {
+            continue;
+        }
+        let candidates = callables_named(index, &reference.name);
+        let Some(location) = to_location(index.root(), path, &reference.span) else {
+            continue;
+        };
+        for callee in resolve_callees(file, &candidates) {
+            callees.entry(callee).or_default().push(location.range);
+        }
+    }
+
+    callees
+        .into_iter()
+        .filter_map(|(callee, from_ranges)| {
+            let (path, symbol) = find_symbol(index, callee)?;
+            Some(CallHierarchyOutgoingCall {
+                to: call_item(index, path, symbol)?,
+                from_ranges,
+            })
+        })
+        .collect()
+}
+
+/// Type hierarchy items for the structs, enums and traits named `name`
+pub fn prepare_type_hierarchy(index: &SymbolIndex, name: &str) -> Vec<TypeHierarchyItem> {
+    index
+        .find_definitions(name)
+        .into_iter()
+        .filter(|(_, symbol)| is_type(symbol))
+        .filter_map(|(path, symbol)| type_item(index, path, symbol))
+        .collect()
+}
+
+/// Traits implemented by the struct or enum `item`
+pub fn supertypes(index: &SymbolIndex, item: &TypeHierarchyItem) -> Vec<TypeHierarchyItem> {
+    let target = qualified_name(&item.data, &item.name);
+    let Some((_, symbol)) = find_symbol(index, &target) else {
+        return Vec::new();
+    };
+    if symbol.kind == IndexedSymbolKind::Trait {
+        return Vec::new();
+    }
+
+    let mut traits: Vec<&str> = index
+        .trait_impls()
+        .filter(|(_, implementation)| implementation.type_name == symbol.name)
+        .map(|(_, implementation)| implementation.trait_name.as_str())
+        .collect();
+    traits.sort_unstable();
+    traits.dedup();
+
+    traits
+        .into_iter()
+        .flat_map(|name| index.find_definitions(name))
+        .filter(|(_, symbol)| symbol.kind == IndexedSymbolKind::Trait)
+        .filter_map(|(path, symbol)| type_item(index, path, symbol))
+        .collect()
+}
+
+/// Structs and enums implementing the trait `item`
+pub fn subtypes(index: &SymbolIndex, item: &TypeHierarchyItem) -> Vec<TypeHierarchyItem> {
+    let target = qualified_name(&item.data, &item.name);
+    let Some((_, symbol)) = find_symbol(index, &target) else {
+        return Vec::new();
+    };
+    if symbol.kind != IndexedSymbolKind::Trait {
+        return Vec::new();
+    }
+
+    let mut types: Vec<&str> = index
+        .trait_impls()
+        .filter(|(_, implementation)| implementation.trait_name == symbol.name)
+        .map(|(_, implementation)| implementation.type_name.as_str())
+        .collect();
+    types.sort_unstable();
+    types.dedup();
+
+    types
+        .into_iter()
+        .flat_map(|name| index.find_definitions(name))
+        .filter(|(_, symbol)| {
+            matches!(
+                symbol.kind,
+                IndexedSymbolKind::Struct | IndexedSymbolKind::Enum
+            )
+        })
+        .filter_map(|(path, symbol)| type_item(index, path, symbol))
+        .collect()
+}
+
+fn is_callable(symbol: &IndexedSymbol) -> bool {
+    matches!(
+        symbol.kind,
+        IndexedSymbolKind::Function | IndexedSymbolKind::Method
+    )
+}
+
+fn is_type(symbol: &IndexedSymbol) -> bool {
+    matches!(
+        symbol.kind,
+        IndexedSymbolKind::Struct | IndexedSymbolKind::Enum | IndexedSymbolKind::Trait
+    )
+}
+
+/// Qualified names of every function and method named `name`
+fn callables_named<'a>(index: &'a SymbolIndex, name: &str) -> Vec<&'a str> {
+    index
+        .symbols()
+        .filter(|(_, symbol)| symbol.name == name && is_callable(symbol))
+        .map(|(_, symbol)| symbol.qualified_name.as_str())
+        .collect()
+}
+
+fn find_symbol<'a>(
+    index: &'a SymbolIndex,
+    qualified: &str,
+) -> Option<(&'a Path, &'a IndexedSymbol)> {
+    index
+        .symbols()
+        .find(|(_, symbol)| symbol.qualified_name == qualified)
+}
+
+/// Items carry the symbol's qualified name in `data`; fall back to the
+/// display name for items built by other servers or older clients
+fn qualified_name(data: &Option<serde_json::Value>, name: &str) -> String {
+    data.as_ref()
+        .and_then(|data| data.as_str())
+        .unwrap_or(name)
+        .to_string()
+}
+
+fn call_item(
+    index: &SymbolIndex,
+    path: &Path,
+    symbol: &IndexedSymbol,
+) -> Option<CallHierarchyItem> {
+    let Location { uri, range } = to_location(index.root(), path, &symbol.span)?;
+    Some(CallHierarchyItem {
+        name: symbol.name.clone(),
+        kind: symbol_kind(symbol.kind),
+        tags: None,
+        detail: Some(symbol.signature.clone()),
+        uri,
+        range,
+        selection_range: range,
+        data: Some(serde_json::Value::String(symbol.qualified_name.clone())),
+    })
+}
+
+/// Item standing for the module-level code of a file
+fn module_item(index: &SymbolIndex, path: &Path, range: Range) -> Option<CallHierarchyItem> {
+    let file = index.file(&index.root().join(path))?;
+    let uri = Url::from_file_path(index.root().join(path)).ok()?;
+    Some(CallHierarchyItem {
+        name: file.module.clone(),
+        kind: SymbolKind::MODULE,
+        tags: None,
+        detail: None,
+        uri,
+        range,
+        selection_range: range,
+        data: None,
+    })
+}
+
+fn type_item(
+    index: &SymbolIndex,
+    path: &Path,
+    symbol: &IndexedSymbol,
+) -> Option<TypeHierarchyItem> {
+    let Location { uri, range } = to_location(index.root(), path, &symbol.span)?;
+    Some(TypeHierarchyItem {
+        name: symbol.name.clone(),
+        kind: symbol_kind(symbol.kind),
+        tags: None,
+        detail: Some(symbol.signature.clone()),
+        uri,
+        range,
+        selection_range: range,
+        data: Some(serde_json::Value::String(symbol.qualified_name.clone())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "trait Shape {\n    fn area(self) -> f32;\n}\n\n\
+                          struct Square {\n    side: f32\n}\n\n\
+                          impl Shape for Square {\n    fn area(self) -> f32 {\n        scale(self.side)\n    }\n}\n\n\
+                          fn scale(x: f32) -> f32 {\n    x * x\n}\n\n\
+                          fn main() {\n    scale(1.0);\n    scale(2.0);\n}\n\n\
+                          scale(3.0);\n";
+
+    fn test_index() -> SymbolIndex {
+        let mut index = SymbolIndex::new("/project");
+        index
+            .update_file(Path::new("/project/shapes.script"), SOURCE)
+            .unwrap();
+        index
+    }
+
+    #[test]
+    fn test_call_hierarchy() {
+        let index = test_index();
+        let items = prepare_call_hierarchy(&index, "scale");
+        assert_eq!(items.len(), 1);
+
+        let incoming = incoming_calls(&index, &items[0]);
+        let callers: Vec<(&str, usize)> = incoming
+            .iter()
+            .map(|call| (call.from.name.as_str(), call.from_ranges.len()))
+            .collect();
+        assert_eq!(callers, vec![("shapes", 1), ("area", 1), ("main", 2)]);
+        assert_eq!(incoming[0].from.kind, SymbolKind::MODULE);
+
+        let main = prepare_call_hierarchy(&index, "main");
+        let outgoing = outgoing_calls(&index, &main[0]);
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].to.name, "scale");
+        assert_eq!(outgoing[0].from_ranges.len(), 2);
+    }
+
+    #[test]
+    fn test_type_hierarchy() {
+        let index = test_index();
+
+        let square = prepare_type_hierarchy(&index, "Square");
+        assert_eq!(square.len(), 1);
+        let traits = supertypes(&index, &square[0]);
+        assert_eq!(traits.len(), 1);
+        assert_eq!(traits[0].name, "Shape");
+        assert_eq!(traits[0].kind, SymbolKind::INTERFACE);
+
+        let types = subtypes(&index, &traits[0]);
+        assert_eq!(types.len(), 1);
+        assert_eq!(types[0].name, "Square");
+        assert!(subtypes(&index, &square[0]).is_empty());
+    }
+}
@@ -0,0 +1,549 @@
+//! Import maintenance for the language server
+//!
+//! Organizing imports merges the `import` statements of a document by
+//! module, sorts and deduplicates them, and drops specifiers whose names are
+//! never used. Auto-import offers exported project symbols as completions,
+//! each carrying the edit that imports it from the module the resolver maps
+//! to the defining file, and the same edits back the quick fix for a name
+//! that is not defined.
+//!
+//! Import statements are recognized one line at a time, so an import that
+//! spans several lines is never rewritten.
+
+use crate::compilation::ModuleLoader;
+use crate::index::{IndexedSymbol, IndexedSymbolKind, SymbolIndex};
+use crate::lexer::{Lexer, TokenKind};
+use crate::parser::{ImportSpecifier, Parser, StmtKind};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::Path;
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, Documentation, MarkupContent, MarkupKind, Position, Range,
+    TextEdit,
+};
+
+/// An import statement occupying a single line
+#[derive(Debug, Clone)]
+struct ImportLine {
+    /// Zero-based line number
+    line: u32,
+    module: String,
+    specifiers: Vec<ImportSpecifier>,
+    /// Whether the statement was terminated with a semicolon
+    semicolon: bool,
+}
+
+/// Imports of one module, merged across statements
+#[derive(Debug, Default)]
+struct ModuleImports {
+    namespaces: BTreeSet<String>,
+    defaults: BTreeSet<String>,
+    named: BTreeSet<(String, Option<String>)>,
+}
+
+impl ModuleImports {
+    fn add(&mut self, specifier: &ImportSpecifier) {
+        match specifier {
+            ImportSpecifier::Namespace { alias } => {
+                self.namespaces.insert(alias.clone());
+            }
+            ImportSpecifier::Default { name } => {
+                self.defaults.insert(name.clone());
+            }
+            ImportSpecifier::Named { name, alias } => {
+                self.named.insert((name.clone(), alias.clone()));
+            }
+        }
+    }
+
+    /// Drop specifiers whose local name is not in `used`
+    fn retain_used(&mut self, used: &HashSet<String>) {
+        self.namespaces.retain(|alias| used.contains(alias));
+        self.defaults.retain(|name| used.contains(name));
+        self.named
+            .retain(|(name, alias)| used.contains(alias.as_ref().unwrap_or(name)));
+    }
+
+    /// Render as import statements: namespaces, then defaults, then one
+    /// statement holding every named import
+    fn statements(&self, module: &str, semicolon: bool) -> Vec<String> {
+        let end = if semicolon { ";" } else { "" };
+        let mut statements = Vec::new();
+
+        for alias in &self.namespaces {
+            statements.push(format!("import * as {} from \"{}\"{}", alias, module, end));
+        }
+        for name in &self.defaults {
+            statements.push(format!("import {} from \"{}\"{}", name, module, end));
+        }
+        if !self.named.is_empty() {
+            let names = self
+                .named
+                .iter()
+                .map(|(name, alias)| match alias {
+                    Some(alias) => format!("{} as {}", name, alias),
+                    None => name.clone(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            statements.push(format!("import {{ {} }} from \"{}\"{}", names, module, end));
+        }
+
+        statements
+    }
+}
+
+/// Edits that sort, merge and deduplicate the imports of a document and
+/// remove unused ones
+///
+/// Returns no edits if the imports are already organized, or if the
+/// document cannot be tokenized or has an import that does not fit on one
+/// line, since unused names cannot be determined reliably then.
+pub fn organize_imports(content: &str) -> Vec<TextEdit> {
+    let Ok(imports) = import_lines(content)
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        return Vec::new();
+    };
+    let Some(first) = imports.first() else {
+        return Vec::new();
+    };
+
+    let import_line_numbers: HashSet<u32> = imports.iter().map(|import| import.line).collect();
+    let Some(used) = used_names(content, &import_line_numbers) else {
+        return Vec::new();
+    };
+
+    let mut modules: BTreeMap<&str, ModuleImports> = BTreeMap::new();
+    for import in &imports {
+        let entry = modules.entry(import.module.as_str()).or_default();
+        for specifier in &import.specifiers {
+            entry.add(specifier);
+        }
+    }
+
+    let semicolon = imports.iter().any(|import| import.semicolon);
+    let organized: Vec<String> = modules
+        .iter_mut()
+        .flat_map(|(module, imports)| {
+            imports.retain_used(&used);
+            imports.statements(module, semicolon)
+        })
+        .collect();
+
+    let lines: Vec<&str> = content.lines().collect();
+    let current: Vec<&str> = imports
+        .iter()
+        .map(|import| lines[import.line as usize].trim())
+        .collect();
+    let contiguous = imports
+        .windows(2)
+        .all(|pair| pair[1].line == pair[0].line + 1);
+    if contiguous && current == organized {
+        return Vec::new();
+    }
+
+    // The organized block replaces the first import; the others are deleted
+    let mut new_text = organized.join("\n");
+    if !new_text.is_empty() {
+        new_text.push('\n');
+    }
+    let mut edits = vec![TextEdit {
+        range: line_range(first.line),
+        new_text,
+    }];
+    edits.extend(imports[1..].iter().map(|import| TextEdit {
+        range: line_range(import.line),
+        new_text: String::new(),
+    }));
+    edits
+}
+
+/// Edit that imports `name` from `module`
+///
+/// A named import is added to an existing `import { .. }` statement for the
+/// module if there is one; otherwise a new statement is inserted after the
+/// last import. Returns `None` if the name is already imported.
+pub fn add_import_edit(content: &str, module: &str, name: &str) -> Option<TextEdit> {
+    let imports: Vec<ImportLine> = import_lines(content)
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+
+    let from_module: Vec<&ImportLine> = imports
+        .iter()
+        .filter(|import| import.module == module)
+        .collect();
+    let already_imported = from_module.iter().any(|import| {
+        import.specifiers.iter().any(|specifier| match specifier {
+            ImportSpecifier::Named { name: imported, .. } => imported == name,
+            ImportSpecifier::Default { name: imported } => imported == name,
+            ImportSpecifier::Namespace { .. } => false,
+        })
+    });
+    if already_imported {
+        return None;
+    }
+
+    let specifier = ImportSpecifier::Named {
+        name: name.to_string(),
+        alias: None,
+    };
+
+    let named_import = from_module.into_iter().find(|import| {
+        import
+            .specifiers
+            .iter()
+            .all(|specifier| matches!(specifier, ImportSpecifier::Named { .. }))
+    });
+    if let Some(import) = named_import {
+        let mut merged = ModuleImports::default();
+        for existing in &import.specifiers {
+            merged.add(existing);
+        }
+        merged.add(&specifier);
+        let mut new_text = merged.statements(module, import.semicolon).join("\n");
+        new_text.push('\n');
+        return Some(TextEdit {
+            range: line_range(import.line),
+            new_text,
+        });
+    }
+
+    let mut single = ModuleImports::default();
+    single.add(&specifier);
+    let semicolon = imports.iter().any(|import| import.semicolon);
+    let mut new_text = single.statements(module, semicolon).join("\n");
+    new_text.push('\n');
+
+    let line = imports.last().map_or(0, |import| import.line + 1);
+    let position = Position { line, character: 0 };
+    Some(TextEdit {
+        range: Range {
+            start: position,
+            end: position,
+        },
+        new_text,
+    })
+}
+
+/// Completions for exported project symbols that are not yet in scope
+///
+/// Each item carries an additional edit importing the symbol. Symbols whose
+/// file the module resolver cannot reach from `file` are skipped.
+pub fn auto_import_completions(
+    index: &SymbolIndex,
+    content: &str,
+    file: &Path,
+    prefix: &str,
+) -> Vec<CompletionItem> {
+    if prefix.is_empty() {
+        return Vec::new();
+    }
+
+    let mut in_scope: HashSet<String> = import_lines(content)
+        .into_iter()
+        .filter_map(Result::ok)
+        .flat_map(|import| import.specifiers)
+        .map(|specifier| match specifier {
+            ImportSpecifier::Named { name, alias } => alias.unwrap_or(name),
+            ImportSpecifier::Default { name } => name,
+            ImportSpecifier::Namespace { alias } => alias,
+        })
+        .collect();
+    if let Some(entry) = index.file(file) {
+        in_scope.extend(entry.symbols.iter().map(|symbol| symbol.name.clone()));
+    }
+
+    let mut items = Vec::new();
+
+    let candidates = importable_symbols(index, file, |symbol| {
+        symbol.name.starts_with(prefix) && !in_scope.contains(&symbol.name)
+    });
+    for (module, symbol, kind) in candidates {
+        let Some(edit) = add_import_edit(content, &module, &symbol.name) else {
+            continue;
+        };
+
+        items.push(CompletionItem {
+            label: symbol.name.clone(),
+            kind: Some(kind),
+            detail: Some(symbol.signature.clone()),
+            documentation: Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("Imports `{}` from `\"{}\"`", symbol.name, module),
+            })),
+            additional_text_edits: Some(vec![edit]),
+            ..Default::default()
+        });
+    }
+
+    items
+}
+
+/// Edits importing `name` from each project module that exports it, with
+/// the module's path
+///
+/// Modules the resolver cannot reach from `file`, and modules `name` is
+/// already imported from, are left out.
+pub fn import_edits(
+    index: &SymbolIndex,
+    content: &str,
+    file: &Path,
+    name: &str,
+) -> Vec<(String, TextEdit)> {
+    importable_symbols(index, file, |symbol| symbol.name == name)
+        .into_iter()
+        .filter_map(|(module, symbol, _)| {
+            let edit = add_import_edit(content, &module, &symbol.name)?;
+            Some((module, edit))
+        })
+        .collect()
+}
+
+/// Exported top-level symbols of other files that `accept` lets through,
+/// each with the module that imports it from `file` and its completion kind
+///
+/// A symbol is listed once per module, in index order.
+fn importable_symbols<'a>(
+    index: &'a SymbolIndex,
+    file: &Path,
+    accept: impl Fn(&IndexedSymbol) -> bool,
+) -> Vec<(String, &'a IndexedSymbol, CompletionItemKind)> {
+    let mut symbols = Vec::new();
+    let mut offered = HashSet::new();
+
+    for (path, symbol) in index.symbols() {
+        if !symbol.exported || symbol.container.is_some() || !accept(symbol) {
+            continue;
+        }
+        let Some(kind) = importable_kind(symbol.kind) else {
+            continue;
+        };
+
+        let target = index.root().join(path);
+        if target == file {
+            continue;
+        }
+        let Some(module) = import_specifier(index.root(), file, &target) else {
+            continue;
+        };
+        if offered.insert((symbol.name.clone(), module.clone())) {
+            symbols.push((module, symbol, kind));
+        }
+    }
+
+    symbols
+}
+
+/// Module path that imports `target` from `from_file`
+///
+/// Paths relative to the importing file's directory are preferred over
+/// paths from the project root. A candidate is only returned if the module
+/// loader resolves it back to `target`.
+pub fn import_specifier(root: &Path, from_file: &Path, target: &Path) -> Option<String> {
+    let mut loader = ModuleLoader::new();
+    loader.add_search_path(root.to_path_buf());
+
+    [from_file.parent(), Some(root)]
+        .into_iter()
+        .flatten()
+        .find_map(|base| {
+            let relative = target.strip_prefix(base).ok()?.with_extension("");
+            let module = relative
+                .components()
+                .map(|component| component.as_os_str().to_str())
+                .collect::<Option<Vec<_>>>()?
+                .join(".");
+
+            let resolved = loader.resolve_module(&module, Some(from_file)).ok()?;
+            same_file(&resolved, target).then_some(module)
+        })
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn importable_kind(kind: IndexedSymbolKind) -> Option<CompletionItemKind> {
+    match kind {
+        IndexedSymbolKind::Function => Some(CompletionItemKind::FUNCTION),
+        IndexedSymbolKind::Struct => Some(CompletionItemKind::STRUCT),
+        IndexedSymbolKind::Enum => Some(CompletionItemKind::ENUM),
+        IndexedSymbolKind::Trait => Some(CompletionItemKind::INTERFACE),
+        IndexedSymbolKind::Variable => Some(CompletionItemKind::VARIABLE),
+        IndexedSymbolKind::Method | IndexedSymbolKind::Variant | IndexedSymbolKind::Field => None,
+    }
+}
+
+/// Every line that starts with `import`, parsed on its own; lines that do
+/// not parse as a single import statement are returned as `Err(line)`
+fn import_lines(content: &str) -> Vec<Result<ImportLine, u32>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, text)| starts_with_import(text.trim_start()))
+        .map(|(line, text)| {
+            let line = line as u32;
+            parse_import_line(line, text.trim()).ok_or(line)
+        })
+        .collect()
+}
+
+fn starts_with_import(text: &str) -> bool {
+    text.strip_prefix("import")
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|next| !next.is_alphanumeric() && next != '_')
+}
+
+fn parse_import_line(line: u32, text: &str) -> Option<ImportLine> {
+    let lexer = Lexer::new(text).ok()?;
+    let (tokens, errors) = lexer.scan_tokens();
+    if !errors.is_empty() {
+        return None;
+    }
+
+    let program = Parser::new(tokens).parse().ok()?;
+    match program.statements.as_slice() {
+        [stmt] => match &stmt.kind {
+            StmtKind::Import { imports, module } => Some(ImportLine {
+                line,
+                module: module.clone(),
+                specifiers: imports.clone(),
+                semicolon: text.ends_with(';'),
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Identifiers used outside the given (zero-based) lines
+fn used_names(content: &str, skip_lines: &HashSet<u32>) -> Option<HashSet<String>> {
+    let lexer = Lexer::new(content).ok()?;
+    let (tokens, errors) = lexer.scan_tokens();
+    if !errors.is_empty() {
+        return None;
+    }
+
+    Some(
+        tokens
+            .into_iter()
+            .filter(|token| !skip_lines.contains(&(token.span.start.line as u32 - 1)))
+            .filter_map(|token| match token.kind {
+                TokenKind::Identifier(name) => Some(name),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// Range covering a whole line, including its line break
+fn line_range(line: u32) -> Range {
+    Range {
+        start: Position { line, character: 0 },
+        end: Position {
+            line: line + 1,
+            character: 0,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn apply(content: &str, edits: &[TextEdit]) -> String {
+        let mut lines: Vec<String> = content.lines().map(|line| format!("{}\n", line)).collect();
+        let mut edits = edits.to_vec();
+        edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start.line));
+        for edit in edits {
+            let start = edit.range.start.line as usize;
+            let end = (edit.range.end.line as usize).min(lines.len());
+            lines.splice(start..end, [edit.new_text]);
+        }
+        lines.concat()
+    }
+
+    #[test]
+    fn test_organize_imports() {
+        let content = "import { sub, add } from \"math\"\n\
+                       import * as io from \"std.io\"\n\
+                       import { add, unused } from \"math\"\n\
+                       \n\
+                       fn main() {\n    io.print(add(1, sub(3, 2)))\n}\n";
+
+        let organized = apply(content, &organize_imports(content));
+        assert_eq!(
+            organized,
+            "import { add, sub } from \"math\"\n\
+             import * as io from \"std.io\"\n\
+             \n\
+             fn main() {\n    io.print(add(1, sub(3, 2)))\n}\n"
+        );
+
+        // Organizing is idempotent
+        assert!(organize_imports(&organized).is_empty());
+    }
+
+    #[test]
+    fn test_organize_imports_skips_multiline_imports() {
+        let content = "import {\n    add\n} from \"math\"\nimport { sub } from \"math\"\n";
+        assert!(organize_imports(content).is_empty());
+    }
+
+    #[test]
+    fn test_add_import_edit() {
+        let content =
+            "import { add } from \"math\";\nimport * as io from \"std.io\";\n\nlet x = 1;\n";
+
+        let extended = add_import_edit(content, "math", "sub").unwrap();
+        assert_eq!(extended.range, line_range(0));
+        assert_eq!(extended.new_text, "import { add, sub } from \"math\";\n");
+
+        let inserted = add_import_edit(content, "util", "clamp").unwrap();
+        assert_eq!(inserted.range.start, Position::new(2, 0));
+        assert_eq!(inserted.new_text, "import { clamp } from \"util\";\n");
+
+        assert!(add_import_edit(content, "math", "add").is_none());
+    }
+
+    #[test]
+    fn test_auto_import_completions() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let library = root.join("util").join("math.script");
+        let main = root.join("main.script");
+        fs::create_dir_all(library.parent().unwrap()).unwrap();
+        fs::write(
+            &library,
+            "export fn clamp(x: i32) -> i32 { x }\nfn helper() {}\n",
+        )
+        .unwrap();
+
+        let mut index = SymbolIndex::new(root);
+        index
+            .update_file(&library, &fs::read_to_string(&library).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            import_specifier(root, &main, &library).as_deref(),
+            Some("util.math")
+        );
+
+        let content = "let y = cl";
+        let items = auto_import_completions(&index, content, &main, "cl");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "clamp");
+        let edits = items[0].additional_text_edits.as_ref().unwrap();
+        assert_eq!(edits[0].new_text, "import { clamp } from \"util.math\"\n");
+
+        // Unexported symbols are not offered
+        assert!(auto_import_completions(&index, content, &main, "hel").is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+use crate::lsp::analysis::{is_resolved, DocumentAnalysis};
+use crate::parser::visitor::{walk_expr, walk_stmt};
+use crate::parser::{Expr, ExprKind, Stmt, StmtKind, Visitor};
+use crate::source::{SourceLocation, Span};
+use crate::types::Type;
+use std::collections::HashSet;
+use tower_lsp::lsp_types::{InlayHint, InlayHintKind, InlayHintLabel, Position, Range};
+
+/// Compute the inlay hints for the part of a document in `range`
+///
+/// Three kinds of hint come from type inference:
+/// - the inferred type after a `let` name that has no annotation
+/// - parameter names before the arguments of calls to functions declared in
+///   the document, unless the argument is a variable of the same name
+/// - `async` before calls that produce a future without awaiting it
+///
+/// Hints are only given where inference settled on a concrete type, so a
+/// document that does not type check shows fewer of them.
+pub fn inlay_hints(content: &str, range: Range) -> Vec<InlayHint> {
+    let analysis = DocumentAnalysis::new(content);
+    let Some(program) = &analysis.program else {
+        return Vec::new();
+    };
+
+    let mut collector = HintCollector {
+        analysis: &analysis,
+        awaited: HashSet::new(),
+        hints: Vec::new(),
+    };
+    collector.visit_program(program);
+
+    let mut hints: Vec<InlayHint> = collector
+        .hints
+        .into_iter()
+        .filter(|hint| in_range(hint.position, range))
+        .collect();
+    hints.sort_by_key(|hint| (hint.position.line, hint.position.character));
+    hints
+}
+
+struct HintCollector<'a> {
+    analysis: &'a DocumentAnalysis,
+    /// Expressions that are the operand of an `await`
+    awaited: HashSet<Span>,
+    hints: Vec<InlayHint>,
+}
+
+impl HintCollector<'_> {
+    /// Add a hint at `location`; hints shown before code are padded on the
+    /// right to keep them apart from it
+    fn push(
+        &mut self,
+        location: SourceLocation,
+        label: String,
+        kind: Option<InlayHintKind>,
+        before_code: bool,
+    ) {
+        self.hints.push(InlayHint {
+            position: to_position(location),
+            label: InlayHintLabel::String(label),
+            kind,
+            text_edits: None,
+            tooltip: None,
+            padding_left: None,
+            padding_right: Some(before_code),
+            data: None,
+        });
+    }
+
+    fn let_type_hint(&mut self, stmt: &Stmt, name: &str, init: &Expr) {
+        // The type of a literal is plain to see
+        if matches!(init.kind, ExprKind::Literal(_)) {
+            return;
+        }
+        let Some(ty) = self
+            .analysis
+            .stmt_type(&stmt.span)
+            .filter(|ty| is_resolved(ty))
+        else {
+            return;
+        };
+        let Some(index) = self.analysis.identifier_after(name, stmt.span.start) else {
+            return;
+        };
+        let end = self.analysis.tokens[index].span.end;
+        self.push(end, format!(": {}", ty), Some(InlayHintKind::TYPE), false);
+    }
+
+    fn call_hints(&mut self, call: &Expr, callee: &Expr, args: &[Expr]) {
+        let is_future = self
+            .analysis
+            .expr_type(&call.span)
+            .is_some_and(|ty| matches!(ty, Type::Future(_)));
+        if is_future && !self.awaited.contains(&call.span) {
+            self.push(callee.span.start, "async".to_string(), None, true);
+        }
+
+        let ExprKind::Identifier(name) = &callee.kind else {
+            return;
+        };
+        let Some(function) = self.analysis.functions.get(name) else {
+            return;
+        };
+        let params = function.params.clone();
+        for (param, arg) in params.iter().zip(args) {
+            let obvious = matches!(&arg.kind, ExprKind::Identifier(arg_name) if arg_name == param);
+            if !obvious {
+                self.push(
+                    arg.span.start,
+                    format!("{}:", param),
+                    Some(InlayHintKind::PARAMETER),
+                    true,
+                );
+            }
+        }
+    }
+}
+
+impl Visitor for HintCollector<'_> {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        if let StmtKind::Let {
+            name,
+            type_ann: None,
+            init: Some(init),
+        } = &stmt.kind
+        {
+            self.let_type_hint(stmt, name, init);
+        }
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Await { expr: awaited } => {
+                self.awaited.insert(awaited.span);
+            }
+            ExprKind::Call { callee, args } => self.call_hints(expr, callee, args),
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+/// The lexer counts lines and columns from 1, LSP from 0
+fn to_position(location: SourceLocation) -> Position {
+    Position::new(
+        location.line.saturating_sub(1) as u32,
+        location.column.saturating_sub(1) as u32,
+    )
+}
+
+fn in_range(position: Position, range: Range) -> bool {
+    let key = |p: Position| (p.line, p.character);
+    key(range.start) <= key(position) && key(position) <= key(range.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(content: &str) -> Vec<(u32, u32, String)> {
+        let whole = Range::new(Position::new(0, 0), Position::new(u32::MAX, 0));
+        inlay_hints(content, whole)
+            .into_iter()
+            .map(|hint| match hint.label {
+                InlayHintLabel::String(label) => {
+                    (hint.position.line, hint.position.character, label)
+                }
+                InlayHintLabel::LabelParts(_) => panic!("expected a plain label"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_let_types_and_parameter_names() {
+        let source = "fn area(width: i32, height: i32) -> i32 {\n    width * height\n}\n\n\
+                      fn main() {\n    let height = 4;\n    let size = area(3, height);\n    let name = \"box\";\n}\n";
+        assert_eq!(
+            labels(source),
+            vec![(6, 12, ": i32".to_string()), (6, 20, "width:".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_async_marker_only_without_await() {
+        let source = "async fn load() -> string {\n    \"data\"\n}\n\n\
+                      async fn main() {\n    let pending = load();\n    let data = await load();\n}\n";
+        assert_eq!(
+            labels(source),
+            vec![
+                (5, 15, ": Future<string>".to_string()),
+                (5, 18, "async".to_string()),
+                (6, 12, ": string".to_string()),
+            ]
+        );
+    }
+}
@@ -1,10 +1,22 @@
+pub mod analysis;
 pub mod capabilities;
 pub mod completion;
 pub mod definition;
+pub mod diagnostics;
+pub mod folding;
+pub mod formatting;
 pub mod handlers;
+pub mod hierarchy;
+pub mod imports;
+pub mod inlay_hints;
+pub mod quick_fixes;
+pub mod references;
+pub mod rename;
+pub mod selection_range;
 pub mod semantic_tokens;
 pub mod server;
 pub mod state;
+pub mod workspace;
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,174 @@
+use crate::error::Fix;
+use crate::lsp::diagnostics::{
+    document_errors, document_lints, lint_to_diagnostic, span_to_range, to_diagnostic,
+    DocumentContext,
+};
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, Diagnostic, Position, Range, TextEdit, Url, WorkspaceEdit,
+};
+
+/// Quick fixes for the errors and lints in a document that overlap `range`
+///
+/// Each fix the compiler attached to an error, or the linter to a lint,
+/// becomes a code action that resolves its diagnostic. An import fix turns
+/// into one action per module `import_edits` offers for the name, given as
+/// the module path with the edit that imports the name from it.
+pub fn quick_fixes(
+    uri: &Url,
+    content: &str,
+    context: &DocumentContext,
+    range: Range,
+    import_edits: impl Fn(&str) -> Vec<(String, TextEdit)>,
+) -> Vec<CodeAction> {
+    let mut actions = Vec::new();
+
+    for error in document_errors(content, context) {
+        let diagnostic = to_diagnostic(&error, content);
+        if !overlaps(diagnostic.range, range) {
+            continue;
+        }
+
+        for fix in &error.fixes {
+            match fix {
+                Fix::Edit { title, span, text } => {
+                    let edit = TextEdit {
+                        range: span_to_range(span),
+                        new_text: text.clone(),
+                    };
+                    actions.push(quick_fix(title.clone(), uri, edit, &diagnostic));
+                }
+                Fix::Import { name } => {
+                    for (module, edit) in import_edits(name) {
+                        let title = format!("Import '{}' from \"{}\"", name, module);
+                        actions.push(quick_fix(title, uri, edit, &diagnostic));
+                    }
+                }
+            }
+        }
+    }
+
+    for lint in document_lints(content, context) {
+        let diagnostic = lint_to_diagnostic(&lint);
+        if !overlaps(diagnostic.range, range) {
+            continue;
+        }
+        for fix in &lint.fixes {
+            if let Fix::Edit { title, span, text } = fix {
+                let edit = TextEdit {
+                    range: span_to_range(span),
+                    new_text: text.clone(),
+                };
+                actions.push(quick_fix(title.clone(), uri, edit, &diagnostic));
+            }
+        }
+    }
+
+    actions
+}
+
+fn quick_fix(title: String, uri: &Url, edit: TextEdit, diagnostic: &Diagnostic) -> CodeAction {
+    CodeAction {
+        title,
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diagnostic.clone()]),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn overlaps(a: Range, b: Range) -> bool {
+    let key = |p: Position| (p.line, p.character);
+    key(a.start) <= key(b.end) && key(b.start) <= key(a.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri() -> Url {
+        Url::parse("file:///project/main.script").unwrap()
+    }
+
+    fn line(line: u32) -> Range {
+        Range::new(Position::new(line, 0), Position::new(line, 100))
+    }
+
+    fn edits(action: &CodeAction) -> Vec<TextEdit> {
+        action.edit.as_ref().unwrap().changes.as_ref().unwrap()[&uri()].clone()
+    }
+
+    #[test]
+    fn test_did_you_mean_and_import() {
+        let content = "fn main() {\n    let total = 1;\n    totl + total;\n}\n";
+        let actions = quick_fixes(&uri(), content, &Default::default(), line(2), |name| {
+            let edit = TextEdit {
+                range: Range::new(Position::new(0, 0), Position::new(0, 0)),
+                new_text: format!("import {{ {} }} from \"util\"\n", name),
+            };
+            vec![("util".to_string(), edit)]
+        });
+
+        let titles: Vec<&str> = actions.iter().map(|action| action.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["Change to 'total'", "Import 'totl' from \"util\""]
+        );
+        assert_eq!(
+            edits(&actions[0]),
+            vec![TextEdit {
+                range: Range::new(Position::new(2, 4), Position::new(2, 8)),
+                new_text: "total".to_string(),
+            }]
+        );
+        assert_eq!(actions[0].kind, Some(CodeActionKind::QUICKFIX));
+        assert_eq!(actions[0].diagnostics.as_ref().unwrap().len(), 1);
+
+        // Nothing to fix on other lines
+        assert!(
+            quick_fixes(&uri(), content, &Default::default(), line(1), |_| Vec::new(
+            ))
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_add_missing_match_arms() {
+        let content =
+            "fn pick(flag: bool) -> i32 {\n    match flag {\n        true => 1,\n    }\n}\n";
+        let actions = quick_fixes(&uri(), content, &Default::default(), line(1), |_| {
+            Vec::new()
+        });
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].title, "Add missing match arms");
+        assert_eq!(
+            edits(&actions[0]),
+            vec![TextEdit {
+                range: Range::new(Position::new(3, 4), Position::new(3, 4)),
+                new_text: "    false => {},\n    ".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_lint_fixes() {
+        let content = "fn main() {\n    let count = 1;\n}\n";
+        let actions = quick_fixes(&uri(), content, &Default::default(), line(1), |_| {
+            Vec::new()
+        });
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].title, "Prefix 'count' with an underscore");
+        assert_eq!(
+            edits(&actions[0]),
+            vec![TextEdit {
+                range: Range::new(Position::new(1, 8), Position::new(1, 8)),
+                new_text: "_".to_string(),
+            }]
+        );
+        let diagnostic = &actions[0].diagnostics.as_ref().unwrap()[0];
+        assert_eq!(diagnostic.message, "unused variable 'count'");
+    }
+}
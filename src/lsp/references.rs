@@ -0,0 +1,163 @@
+use crate::index::{IndexSpan, IndexedSymbolKind, SymbolIndex};
+use crate::lexer::{Lexer, TokenKind};
+use std::path::Path;
+use tower_lsp::lsp_types::{Location, Position, Range, SymbolInformation, SymbolKind, Url};
+
+/// Find the identifier under the cursor using the token stream
+pub fn identifier_at_position(content: &str, position: Position) -> Option<String> {
+    identifier_range_at_position(content, position).map(|(name, _)| name)
+}
+
+/// Find the identifier under the cursor and the range it covers
+pub fn identifier_range_at_position(content: &str, position: Position) -> Option<(String, Range)> {
+    let lexer = Lexer::new(content).ok()?;
+    let (tokens, _errors) = lexer.scan_tokens();
+
+    let line = position.line as usize + 1;
+    let column = position.character as usize + 1;
+
+    tokens.into_iter().find_map(|token| match token.kind {
+        TokenKind::Identifier(name)
+            if token.span.start.line == line
+                && token.span.start.column <= column
+                && column <= token.span.start.column + name.chars().count() =>
+        {
+            let range = identifier_range(token.span.start.line, token.span.start.column, &name);
+            Some((name, range))
+        }
+        _ => None,
+    })
+}
+
+/// The range of an identifier starting at a 1-based line and column
+pub(crate) fn identifier_range(line: usize, column: usize, name: &str) -> Range {
+    let line = line.saturating_sub(1) as u32;
+    let character = column.saturating_sub(1) as u32;
+    Range {
+        start: Position { line, character },
+        end: Position {
+            line,
+            character: character + name.chars().count() as u32,
+        },
+    }
+}
+
+/// Locations of every definition and reference of `name` in the project
+pub fn find_references(
+    index: &SymbolIndex,
+    name: &str,
+    include_declaration: bool,
+) -> Vec<Location> {
+    let mut locations = Vec::new();
+
+    if include_declaration {
+        for (path, symbol) in index.find_definitions(name) {
+            if let Some(location) = to_location(index.root(), path, &symbol.span) {
+                locations.push(location);
+            }
+        }
+    }
+
+    for (path, reference) in index.find_references(name) {
+        if let Some(location) = to_location(index.root(), path, &reference.span) {
+            locations.push(location);
+        }
+    }
+
+    locations
+}
+
+/// Answer a workspace/symbol query from the index
+#[allow(deprecated)] // SymbolInformation::deprecated is required by the struct
+pub fn workspace_symbols(index: &SymbolIndex, query: &str) -> Vec<SymbolInformation> {
+    index
+        .search(query)
+        .into_iter()
+        .filter_map(|(path, symbol)| {
+            let location = to_location(index.root(), path, &symbol.span)?;
+            Some(SymbolInformation {
+                name: symbol.name.clone(),
+                kind: symbol_kind(symbol.kind),
+                tags: None,
+                deprecated: None,
+                location,
+                container_name: symbol.container.clone(),
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn symbol_kind(kind: IndexedSymbolKind) -> SymbolKind {
+    match kind {
+        IndexedSymbolKind::Function => SymbolKind::FUNCTION,
+        IndexedSymbolKind::Method => SymbolKind::METHOD,
+        IndexedSymbolKind::Struct => SymbolKind::STRUCT,
+        IndexedSymbolKind::Enum => SymbolKind::ENUM,
+        IndexedSymbolKind::Trait => SymbolKind::INTERFACE,
+        IndexedSymbolKind::Variant => SymbolKind::ENUM_MEMBER,
+        IndexedSymbolKind::Field => SymbolKind::FIELD,
+        IndexedSymbolKind::Variable => SymbolKind::VARIABLE,
+    }
+}
+
+pub(crate) fn to_location(root: &Path, relative: &Path, span: &IndexSpan) -> Option<Location> {
+    let uri = Url::from_file_path(root.join(relative)).ok()?;
+    Some(Location {
+        uri,
+        range: Range {
+            start: Position {
+                line: span.start_line.saturating_sub(1) as u32,
+                character: span.start_column.saturating_sub(1) as u32,
+            },
+            end: Position {
+                line: span.end_line.saturating_sub(1) as u32,
+                character: span.end_column.saturating_sub(1) as u32,
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_index() -> SymbolIndex {
+        let mut index = SymbolIndex::new("/project");
+        index
+            .update_file(
+                Path::new("/project/main.script"),
+                "fn spawn() {}\nfn main() {\n    spawn();\n    spawn();\n}",
+            )
+            .unwrap();
+        index
+    }
+
+    #[test]
+    fn test_identifier_at_position() {
+        let content = "let speed = 10;\nlet x = speed + 1;";
+        let name = identifier_at_position(
+            content,
+            Position {
+                line: 1,
+                character: 9,
+            },
+        );
+        assert_eq!(name.as_deref(), Some("speed"));
+    }
+
+    #[test]
+    fn test_find_references() {
+        let index = test_index();
+        assert_eq!(find_references(&index, "spawn", false).len(), 2);
+        assert_eq!(find_references(&index, "spawn", true).len(), 3);
+    }
+
+    #[test]
+    fn test_workspace_symbols() {
+        let index = test_index();
+        let symbols = workspace_symbols(&index, "spa");
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "spawn");
+        assert_eq!(symbols[0].kind, SymbolKind::FUNCTION);
+    }
+}
@@ -0,0 +1,583 @@
+//! Project-wide rename backed by the symbol index
+//!
+//! The name under the cursor is resolved the same way call hierarchy
+//! resolves calls: a definition in the current module wins, then
+//! definitions in imported modules. `Type::name` and `module::name` paths
+//! are matched on their qualifier, and `.name` on methods and fields.
+//! Every definition, import, export and use in the workspace that resolves
+//! to the same symbol is rewritten.
+//!
+//! A rename is refused when the new name is not an identifier, when the
+//! symbol cannot be told apart from another definition of the same name,
+//! or when the new name would collide with an existing definition or
+//! import, or be shadowed by a name already used next to a renamed use.
+
+use crate::index::callgraph::{imports_definition, resolve_callees};
+use crate::index::{
+    FileIndex, IndexSpan, IndexedSymbol, IndexedSymbolKind, ReferenceKind, SymbolIndex,
+};
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::lsp::references::identifier_range;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use tower_lsp::lsp_types::{Position, PrepareRenameResponse, TextEdit, Url, WorkspaceEdit};
+
+/// The range and current name of the symbol under the cursor, or an
+/// explanation of why it cannot be renamed
+pub fn prepare_rename(
+    index: &SymbolIndex,
+    path: &Path,
+    content: &str,
+    position: Position,
+) -> Result<Option<PrepareRenameResponse>, String> {
+    let tokens = scan(content);
+    let Some(cursor) = cursor_at(&tokens, position) else {
+        return Ok(None);
+    };
+    resolve_target(index, path, &tokens, &cursor)?;
+    Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
+        range: identifier_range(cursor.line, cursor.column, &cursor.name),
+        placeholder: cursor.name,
+    }))
+}
+
+/// Rename the symbol under the cursor everywhere it is used
+///
+/// `read_source` supplies the text of other files in the workspace,
+/// preferring unsaved editor contents over the file on disk.
+pub fn rename_symbol(
+    index: &SymbolIndex,
+    path: &Path,
+    content: &str,
+    position: Position,
+    new_name: &str,
+    read_source: impl Fn(&Path) -> Option<String>,
+) -> Result<Option<WorkspaceEdit>, String> {
+    let tokens = scan(content);
+    let Some(cursor) = cursor_at(&tokens, position) else {
+        return Ok(None);
+    };
+    validate_name(new_name)?;
+    if new_name == cursor.name {
+        return Ok(None);
+    }
+
+    let (target_path, target) = resolve_target(index, path, &tokens, &cursor)?;
+    check_collisions(index, target, new_name)?;
+
+    let mut changes = HashMap::new();
+    for (relative, file) in index.files() {
+        let sites = rename_sites(file, target);
+        let defines = relative == target_path;
+        if sites.is_empty() && !defines {
+            continue;
+        }
+
+        let file_path = index.root().join(relative);
+        let source = if file_path == path {
+            content.to_string()
+        } else {
+            read_source(&file_path).ok_or_else(|| format!("Cannot read {}", file_path.display()))?
+        };
+        let file_tokens = scan(&source);
+
+        let mut positions = BTreeSet::new();
+        if defines {
+            positions.extend(find_token(&file_tokens, &target.name, &target.span, |_| {
+                true
+            }));
+        }
+        let mut containers = BTreeSet::new();
+        for site in &sites {
+            if let Some(found) = find_token(&file_tokens, &target.name, &site.span, |previous| {
+                site.accepts(previous)
+            }) {
+                positions.insert(found);
+                containers.insert(site.container);
+            }
+        }
+        if !is_member(target) {
+            check_shadowing(file, relative, &containers, &target.name, new_name)?;
+        }
+
+        if positions.is_empty() {
+            continue;
+        }
+        let uri = Url::from_file_path(&file_path)
+            .map_err(|_| format!("Cannot build a URI for {}", file_path.display()))?;
+        let edits: Vec<TextEdit> = positions
+            .into_iter()
+            .map(|(line, column)| TextEdit {
+                range: identifier_range(line, column, &target.name),
+                new_text: new_name.to_string(),
+            })
+            .collect();
+        changes.insert(uri, edits);
+    }
+
+    Ok(Some(WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    }))
+}
+
+/// The identifier under the cursor and the tokens just before it
+struct Cursor {
+    name: String,
+    line: usize,
+    column: usize,
+    previous: Option<TokenKind>,
+    qualifier: Option<String>,
+}
+
+/// A reference that refers to the symbol being renamed
+struct Site<'a> {
+    span: IndexSpan,
+    container: Option<&'a str>,
+    /// The token before the name must be `::` (`Type::name`) or `.`
+    /// (`value.name`); plain uses must not follow a `.`
+    after: Option<TokenKind>,
+}
+
+impl Site<'_> {
+    fn accepts(&self, previous: Option<&TokenKind>) -> bool {
+        match &self.after {
+            Some(kind) => previous == Some(kind),
+            None => previous != Some(&TokenKind::Dot),
+        }
+    }
+}
+
+fn scan(source: &str) -> Vec<Token> {
+    match Lexer::new(source) {
+        Ok(lexer) => lexer.scan_tokens().0,
+        Err(_) => Vec::new(),
+    }
+}
+
+fn cursor_at(tokens: &[Token], position: Position) -> Option<Cursor> {
+    let line = position.line as usize + 1;
+    let column = position.character as usize + 1;
+
+    tokens
+        .iter()
+        .enumerate()
+        .find_map(|(i, token)| match &token.kind {
+            TokenKind::Identifier(name)
+                if token.span.start.line == line
+                    && token.span.start.column <= column
+                    && column <= token.span.start.column + name.chars().count() =>
+            {
+                let previous = i.checked_sub(1).map(|p| tokens[p].kind.clone());
+                let qualifier = match (previous.as_ref(), i.checked_sub(2).map(|q| &tokens[q].kind))
+                {
+                    (Some(TokenKind::ColonColon), Some(TokenKind::Identifier(qualifier))) => {
+                        Some(qualifier.clone())
+                    }
+                    _ => None,
+                };
+                Some(Cursor {
+                    name: name.clone(),
+                    line: token.span.start.line,
+                    column: token.span.start.column,
+                    previous,
+                    qualifier,
+                })
+            }
+            _ => None,
+        })
+}
+
+/// Position of the first identifier `name` inside `span` whose preceding
+/// token is accepted
+fn find_token(
+    tokens: &[Token],
+    name: &str,
+    span: &IndexSpan,
+    accept: impl Fn(Option<&TokenKind>) -> bool,
+) -> Option<(usize, usize)> {
+    let start = (span.start_line, span.start_column);
+    let end = (span.end_line, span.end_column);
+    tokens.iter().enumerate().find_map(|(i, token)| {
+        let at = (token.span.start.line, token.span.start.column);
+        let previous = i.checked_sub(1).map(|p| &tokens[p].kind);
+        match &token.kind {
+            TokenKind::Identifier(found)
+                if found == name && start <= at && at <= end && accept(previous) =>
+            {
+                Some(at)
+            }
+            _ => None,
+        }
+    })
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && TokenKind::from_keyword(name).is_none();
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("`{}` is not a valid identifier", name))
+    }
+}
+
+fn is_member(symbol: &IndexedSymbol) -> bool {
+    matches!(
+        symbol.kind,
+        IndexedSymbolKind::Method | IndexedSymbolKind::Field
+    )
+}
+
+/// Find the single definition the cursor refers to
+fn resolve_target<'a>(
+    index: &'a SymbolIndex,
+    path: &Path,
+    tokens: &[Token],
+    cursor: &Cursor,
+) -> Result<(&'a Path, &'a IndexedSymbol), String> {
+    let candidates: Vec<(&Path, &IndexedSymbol)> = index
+        .symbols()
+        .filter(|(_, symbol)| symbol.name == cursor.name)
+        .collect();
+    if candidates.is_empty() {
+        return Err(format!("`{}` is not defined in the workspace", cursor.name));
+    }
+
+    // The cursor is on the definition itself
+    for &(relative, symbol) in &candidates {
+        if index.root().join(relative) == path
+            && find_token(tokens, &cursor.name, &symbol.span, |_| true)
+                == Some((cursor.line, cursor.column))
+        {
+            return unique_member(&candidates, relative, symbol);
+        }
+    }
+
+    let matches: Vec<(&Path, &IndexedSymbol)> = match (&cursor.previous, &cursor.qualifier) {
+        (Some(TokenKind::Dot), _) => candidates
+            .iter()
+            .copied()
+            .filter(|(_, symbol)| is_member(symbol))
+            .collect(),
+        (Some(TokenKind::ColonColon), Some(qualifier)) => {
+            let path = format!("{}::{}", qualifier, cursor.name);
+            candidates
+                .iter()
+                .copied()
+                .filter(|(_, symbol)| {
+                    symbol.qualified_name == path
+                        || symbol.qualified_name.ends_with(&format!("::{}", path))
+                })
+                .collect()
+        }
+        _ => {
+            let names: Vec<&str> = candidates
+                .iter()
+                .filter(|(_, symbol)| !is_member(symbol))
+                .map(|(_, symbol)| symbol.qualified_name.as_str())
+                .collect();
+            let resolved = match index.file(path) {
+                Some(file) => resolve_callees(file, &names),
+                None => names,
+            };
+            candidates
+                .iter()
+                .copied()
+                .filter(|(_, symbol)| resolved.contains(&symbol.qualified_name.as_str()))
+                .collect()
+        }
+    };
+
+    match matches.as_slice() {
+        [] => Err(format!("`{}` is not defined in the workspace", cursor.name)),
+        [(relative, symbol)] => unique_member(&candidates, relative, symbol),
+        _ => Err(ambiguous(&cursor.name, &matches)),
+    }
+}
+
+/// Uses of `.name` cannot be told apart, so a method or field can only be
+/// renamed while it is the only member with that name
+fn unique_member<'a>(
+    candidates: &[(&'a Path, &'a IndexedSymbol)],
+    relative: &'a Path,
+    symbol: &'a IndexedSymbol,
+) -> Result<(&'a Path, &'a IndexedSymbol), String> {
+    if is_member(symbol) {
+        let members: Vec<(&Path, &IndexedSymbol)> = candidates
+            .iter()
+            .copied()
+            .filter(|(_, candidate)| is_member(candidate))
+            .collect();
+        if members.len() > 1 {
+            return Err(ambiguous(&symbol.name, &members));
+        }
+    }
+    Ok((relative, symbol))
+}
+
+fn ambiguous(name: &str, matches: &[(&Path, &IndexedSymbol)]) -> String {
+    let names: BTreeSet<&str> = matches
+        .iter()
+        .map(|(_, symbol)| symbol.qualified_name.as_str())
+        .collect();
+    format!(
+        "`{}` is ambiguous: it could be any of {}",
+        name,
+        names.into_iter().collect::<Vec<_>>().join(", ")
+    )
+}
+
+/// References in `file` that resolve to `target`
+fn rename_sites<'a>(file: &'a FileIndex, target: &IndexedSymbol) -> Vec<Site<'a>> {
+    let qualified = target.qualified_name.as_str();
+    let visible =
+        qualified.starts_with(&format!("{}::", file.module)) || imports_definition(file, qualified);
+
+    file.references
+        .iter()
+        .filter_map(|reference| {
+            let after = if reference.name == target.name {
+                if is_member(target) {
+                    Some(TokenKind::Dot)
+                } else if visible {
+                    None
+                } else {
+                    return None;
+                }
+            } else if reference.name.ends_with(&format!("::{}", target.name))
+                && (qualified == reference.name
+                    || qualified.ends_with(&format!("::{}", reference.name))
+                    || visible)
+            {
+                Some(TokenKind::ColonColon)
+            } else {
+                return None;
+            };
+            Some(Site {
+                span: reference.span,
+                container: reference.container.as_deref(),
+                after,
+            })
+        })
+        .filter(|site| {
+            // A local definition with the same name hides the target
+            site.after.is_some()
+                || !file.symbols.iter().any(|symbol| {
+                    symbol.name == target.name
+                        && symbol.qualified_name != target.qualified_name
+                        && !is_member(symbol)
+                })
+        })
+        .collect()
+}
+
+/// Refuse a new name that is already defined next to the target, or that
+/// files using the target already define or import
+fn check_collisions(
+    index: &SymbolIndex,
+    target: &IndexedSymbol,
+    new_name: &str,
+) -> Result<(), String> {
+    let prefix = &target.qualified_name[..target.qualified_name.len() - target.name.len()];
+    let sibling = format!("{}{}", prefix, new_name);
+    if let Some((path, _)) = index
+        .symbols()
+        .find(|(_, symbol)| symbol.qualified_name == sibling)
+    {
+        return Err(format!(
+            "`{}` is already defined in {}",
+            new_name,
+            path.display()
+        ));
+    }
+    if is_member(target) {
+        return Ok(());
+    }
+
+    for (path, file) in index.files() {
+        if rename_sites(file, target).is_empty() {
+            continue;
+        }
+        if file
+            .symbols
+            .iter()
+            .any(|symbol| symbol.name == new_name && symbol.container.is_none())
+        {
+            return Err(format!(
+                "`{}` is already defined in {}",
+                new_name,
+                path.display()
+            ));
+        }
+        if file
+            .references
+            .iter()
+            .any(|reference| reference.name == new_name && reference.kind == ReferenceKind::Import)
+        {
+            return Err(format!(
+                "`{}` is already imported in {}",
+                new_name,
+                path.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Refuse a new name that is already used in a function (or at module
+/// level) where the target is renamed, since the renamed uses would then
+/// refer to whichever binding is closer
+fn check_shadowing(
+    file: &FileIndex,
+    path: &Path,
+    containers: &BTreeSet<Option<&str>>,
+    name: &str,
+    new_name: &str,
+) -> Result<(), String> {
+    let Some(used) = file.references.iter().find(|reference| {
+        reference.name == new_name && containers.contains(&reference.container.as_deref())
+    }) else {
+        return Ok(());
+    };
+    let place = match &used.container {
+        Some(container) => container.clone(),
+        None => path.display().to_string(),
+    };
+    Err(format!(
+        "Renamed uses of `{}` would be shadowed by the `{}` already used in {}",
+        name, new_name, place
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    const MATH: &str = "export fn add(a: i32, b: i32) -> i32 {\n    a + b\n}\n\n\
+                        fn twice(x: i32) -> i32 {\n    add(x, x)\n}\n";
+    const MAIN: &str = "import { add } from \"./math.script\"\n\n\
+                        fn main() {\n    let total = add(1, 2);\n    print(total);\n}\n";
+
+    fn test_index() -> SymbolIndex {
+        let mut index = SymbolIndex::new("/project");
+        index
+            .update_file(Path::new("/project/math.script"), MATH)
+            .unwrap();
+        index
+            .update_file(Path::new("/project/main.script"), MAIN)
+            .unwrap();
+        index
+    }
+
+    fn read_source(path: &Path) -> Option<String> {
+        match path.file_name()?.to_str()? {
+            "math.script" => Some(MATH.to_string()),
+            "main.script" => Some(MAIN.to_string()),
+            _ => None,
+        }
+    }
+
+    fn edits_by_file(edit: WorkspaceEdit) -> BTreeMap<PathBuf, Vec<(u32, u32)>> {
+        edit.changes
+            .unwrap()
+            .into_iter()
+            .map(|(uri, edits)| {
+                let mut positions: Vec<(u32, u32)> = edits
+                    .iter()
+                    .map(|edit| (edit.range.start.line, edit.range.start.character))
+                    .collect();
+                positions.sort();
+                (uri.to_file_path().unwrap(), positions)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rename_across_modules() {
+        let index = test_index();
+        let position = Position {
+            line: 3,
+            character: 17,
+        };
+        let edit = rename_symbol(
+            &index,
+            Path::new("/project/main.script"),
+            MAIN,
+            position,
+            "sum",
+            read_source,
+        )
+        .unwrap()
+        .unwrap();
+
+        let edits = edits_by_file(edit);
+        assert_eq!(
+            edits[Path::new("/project/math.script")],
+            vec![(0, 10), (5, 4)]
+        );
+        assert_eq!(
+            edits[Path::new("/project/main.script")],
+            vec![(0, 9), (3, 16)]
+        );
+    }
+
+    #[test]
+    fn test_rename_refuses_collisions() {
+        let index = test_index();
+        let on_add = Position {
+            line: 0,
+            character: 11,
+        };
+        let rename = |new_name: &str| {
+            rename_symbol(
+                &index,
+                Path::new("/project/math.script"),
+                MATH,
+                on_add,
+                new_name,
+                read_source,
+            )
+        };
+
+        assert!(rename("twice").unwrap_err().contains("already defined"));
+        assert!(rename("total").unwrap_err().contains("shadowed"));
+        assert!(rename("fn").unwrap_err().contains("not a valid identifier"));
+        assert!(rename("plus").is_ok());
+    }
+
+    #[test]
+    fn test_prepare_rename() {
+        let index = test_index();
+        let response = prepare_rename(
+            &index,
+            Path::new("/project/main.script"),
+            MAIN,
+            Position {
+                line: 3,
+                character: 17,
+            },
+        )
+        .unwrap();
+        assert!(matches!(
+            response,
+            Some(PrepareRenameResponse::RangeWithPlaceholder { placeholder, .. })
+                if placeholder == "add"
+        ));
+
+        let local = prepare_rename(
+            &index,
+            Path::new("/project/main.script"),
+            MAIN,
+            Position {
+                line: 4,
+                character: 11,
+            },
+        );
+        assert!(local.is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::lexer::{Lexer, TokenKind};
+use crate::parser::visitor::{walk_expr, walk_stmt};
+use crate::parser::{Expr, ExprKind, ImplBlock, Method, Parser, Pattern, Stmt, Visitor};
+use crate::source::Span;
+use tower_lsp::lsp_types::{Position, Range, SelectionRange};
+
+/// Compute the selection range hierarchy for each position
+///
+/// Each result starts at the innermost token under the position and grows
+/// through the enclosing expressions, statements and declarations. A
+/// position outside any node gets an empty range at that position, since
+/// the protocol requires one result per position.
+pub fn selection_ranges(content: &str, positions: &[Position]) -> Vec<SelectionRange> {
+    let mut collector = RangeCollector::default();
+
+    if let Ok(lexer) = Lexer::new(content) {
+        let (tokens, errors) = lexer.scan_tokens();
+        collector.ranges.extend(
+            tokens
+                .iter()
+                .filter(|token| !matches!(token.kind, TokenKind::Newline | TokenKind::Eof))
+                .map(|token| span_to_range(&token.span)),
+        );
+
+        if errors.is_empty() {
+            if let Ok(program) = Parser::new(tokens).parse() {
+                collector.visit_program(&program);
+            }
+        }
+    }
+
+    positions
+        .iter()
+        .map(|&position| selection_range_at(&collector.ranges, position))
+        .collect()
+}
+
+/// Nest the ranges containing `position` from the innermost outwards
+fn selection_range_at(ranges: &[Range], position: Position) -> SelectionRange {
+    let mut containing: Vec<Range> = ranges
+        .iter()
+        .filter(|range| contains(range, position))
+        .copied()
+        .collect();
+
+    // Enclosing nodes start no later and end no earlier than the nodes they
+    // contain
+    containing.sort_by(|a, b| {
+        position_key(a.start)
+            .cmp(&position_key(b.start))
+            .then(position_key(b.end).cmp(&position_key(a.end)))
+    });
+    containing.dedup();
+
+    // Working outwards from the innermost range, skip neighbours that merely
+    // touch the position without enclosing the ranges inside them
+    let mut nested: Vec<Range> = Vec::new();
+    for range in containing.into_iter().rev() {
+        if let Some(inner) = nested.last() {
+            if !(contains(&range, inner.start) && contains(&range, inner.end)) {
+                continue;
+            }
+        }
+        nested.push(range);
+    }
+
+    let mut selection: Option<SelectionRange> = None;
+    for range in nested.into_iter().rev() {
+        selection = Some(SelectionRange {
+            range,
+            parent: selection.map(Box::new),
+        });
+    }
+
+    selection.unwrap_or(SelectionRange {
+        range: Range {
+            start: position,
+            end: position,
+        },
+        parent: None,
+    })
+}
+
+#[derive(Default)]
+struct RangeCollector {
+    ranges: Vec<Range>,
+}
+
+impl RangeCollector {
+    fn push(&mut self, span: &Span) {
+        self.ranges.push(span_to_range(span));
+    }
+}
+
+impl Visitor for RangeCollector {
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        self.push(&stmt.span);
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_method(&mut self, _impl_block: &ImplBlock, method: &Method) {
+        self.push(&method.span);
+        self.visit_block(&method.body);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        self.push(&expr.span);
+        if let ExprKind::Match { arms, .. } = &expr.kind {
+            for arm in arms {
+                self.push(&Span::new(arm.pattern.span.start, arm.body.span.end));
+            }
+        }
+        walk_expr(self, expr);
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        self.push(&pattern.span);
+    }
+}
+
+fn contains(range: &Range, position: Position) -> bool {
+    position_key(range.start) <= position_key(position)
+        && position_key(position) <= position_key(range.end)
+}
+
+fn position_key(position: Position) -> (u32, u32) {
+    (position.line, position.character)
+}
+
+/// Convert a span to an LSP range
+fn span_to_range(span: &Span) -> Range {
+    Range {
+        start: Position {
+            line: span.start.line.saturating_sub(1) as u32,
+            character: span.start.column.saturating_sub(1) as u32,
+        },
+        end: Position {
+            line: span.end.line.saturating_sub(1) as u32,
+            character: span.end.column.saturating_sub(1) as u32,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(selection: &SelectionRange) -> Vec<Range> {
+        let mut ranges = vec![selection.range];
+        let mut parent = &selection.parent;
+        while let Some(selection) = parent {
+            ranges.push(selection.range);
+            parent = &selection.parent;
+        }
+        ranges
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+    }
+
+    #[test]
+    fn test_selection_expands_structurally() {
+        let content = "fn main() {\n    let total = width * (height + 1);\n}\n";
+
+        // Cursor on `height`
+        let selections = selection_ranges(content, &[Position::new(1, 27)]);
+        assert_eq!(selections.len(), 1);
+
+        let ranges = chain(&selections[0]);
+        assert_eq!(ranges[0], range((1, 25), (1, 31)));
+        assert!(ranges.contains(&range((1, 25), (1, 35))));
+        assert!(ranges.contains(&range((1, 16), (1, 36))));
+        assert_eq!(*ranges.last().unwrap(), range((0, 0), (2, 1)));
+
+        // Each range contains the one before it
+        for pair in ranges.windows(2) {
+            assert!(contains(&pair[1], pair[0].start) && contains(&pair[1], pair[0].end));
+        }
+    }
+
+    #[test]
+    fn test_selection_outside_nodes() {
+        let selections = selection_ranges("let x = 1;\n\n", &[Position::new(1, 0)]);
+        assert_eq!(selections[0].range, range((1, 0), (1, 0)));
+        assert!(selections[0].parent.is_none());
+    }
+}
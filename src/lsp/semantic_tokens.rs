@@ -1,8 +1,12 @@
-use crate::lexer::{Lexer, TokenKind};
-use crate::lsp::capabilities::SUPPORTED_TOKEN_TYPES;
-use tower_lsp::lsp_types::{SemanticToken, SemanticTokenType};
+use crate::lexer::TokenKind;
+use crate::lsp::analysis::DocumentAnalysis;
+use crate::lsp::capabilities::{SUPPORTED_TOKEN_MODIFIERS, SUPPORTED_TOKEN_TYPES};
+use crate::types::Type;
+use tower_lsp::lsp_types::{SemanticToken, SemanticTokenModifier, SemanticTokenType};
 
 /// Convert a Script token to an LSP semantic token type index
+///
+/// Identifiers depend on context and are classified by `classify_identifier`.
 fn token_kind_to_semantic_type(kind: &TokenKind) -> Option<u32> {
     let token_type = match kind {
         // Keywords
@@ -13,6 +17,8 @@ fn token_kind_to_semantic_type(kind: &TokenKind) -> Option<u32> {
         | TokenKind::Else
         | TokenKind::While
         | TokenKind::For
+        | TokenKind::Break
+        | TokenKind::Continue
         | TokenKind::In
         | TokenKind::True
         | TokenKind::False
@@ -23,13 +29,19 @@ fn token_kind_to_semantic_type(kind: &TokenKind) -> Option<u32> {
         | TokenKind::Async
         | TokenKind::Await
         | TokenKind::Match
-        | TokenKind::Print => SemanticTokenType::KEYWORD,
-
-        // Identifiers (could be variables, functions, types, etc.)
-        TokenKind::Identifier(_) => SemanticTokenType::VARIABLE,
+        | TokenKind::Print
+        | TokenKind::Mut
+        | TokenKind::Struct
+        | TokenKind::Enum
+        | TokenKind::Impl
+        | TokenKind::Trait
+        | TokenKind::Where
+        | TokenKind::Try
+        | TokenKind::Catch
+        | TokenKind::Finally => SemanticTokenType::KEYWORD,
 
         // Literals
-        TokenKind::Number(_) => SemanticTokenType::NUMBER,
+        TokenKind::Number(_) | TokenKind::TypedNumber(..) => SemanticTokenType::NUMBER,
         TokenKind::String(_) => SemanticTokenType::STRING,
 
         // Operators
@@ -64,48 +76,157 @@ fn token_kind_to_semantic_type(kind: &TokenKind) -> Option<u32> {
         .map(|pos| pos as u32)
 }
 
+/// Index of a token type in the legend
+fn type_index(token_type: &SemanticTokenType) -> u32 {
+    SUPPORTED_TOKEN_TYPES
+        .iter()
+        .position(|t| t == token_type)
+        .map(|pos| pos as u32)
+        .unwrap_or(0)
+}
+
+/// Bit of a token modifier in the legend
+fn modifier_bit(modifier: &SemanticTokenModifier) -> u32 {
+    SUPPORTED_TOKEN_MODIFIERS
+        .iter()
+        .position(|m| m == modifier)
+        .map(|pos| 1 << pos)
+        .unwrap_or(0)
+}
+
+/// Whether `ty` is a function returning a future
+fn is_async_function(ty: &Type) -> bool {
+    matches!(ty, Type::Function { ret, .. } if ret.is_future())
+}
+
+/// Classify the identifier at `index` by the tokens around it and the type
+/// inference gave it
+fn classify_identifier(
+    analysis: &DocumentAnalysis,
+    index: usize,
+    name: &str,
+) -> (SemanticTokenType, u32) {
+    let tokens = &analysis.tokens;
+    let token = &tokens[index];
+    let previous = index.checked_sub(1).map(|i| &tokens[i].kind);
+    let next = tokens.get(index + 1).map(|t| &t.kind);
+    let declaration = modifier_bit(&SemanticTokenModifier::DECLARATION);
+    let async_bit = modifier_bit(&SemanticTokenModifier::ASYNC);
+
+    // Declarations
+    match previous {
+        Some(TokenKind::Fn) => {
+            let is_async = index >= 2 && matches!(tokens[index - 2].kind, TokenKind::Async);
+            let modifiers = if is_async {
+                declaration | async_bit
+            } else {
+                declaration
+            };
+            return (SemanticTokenType::FUNCTION, modifiers);
+        }
+        Some(TokenKind::Struct) => return (SemanticTokenType::STRUCT, declaration),
+        Some(TokenKind::Enum) => return (SemanticTokenType::ENUM, declaration),
+        Some(TokenKind::Trait) => return (SemanticTokenType::TYPE, declaration),
+        Some(TokenKind::Let | TokenKind::Mut | TokenKind::For) => {
+            return (SemanticTokenType::VARIABLE, declaration)
+        }
+        Some(TokenKind::Dot) if next == Some(&TokenKind::LeftParen) => {
+            return (SemanticTokenType::METHOD, 0)
+        }
+        Some(TokenKind::Dot) => return (SemanticTokenType::PROPERTY, 0),
+        _ => {}
+    }
+
+    // Paths such as `Color::Red` and `math::sqrt`
+    if next == Some(&TokenKind::ColonColon) {
+        if analysis.enums.contains(name) {
+            return (SemanticTokenType::ENUM, 0);
+        }
+        return (SemanticTokenType::NAMESPACE, 0);
+    }
+    if previous == Some(&TokenKind::ColonColon) {
+        let qualifier = &tokens[index - 2].kind;
+        if matches!(qualifier, TokenKind::Identifier(q) if analysis.enums.contains(q)) {
+            return (SemanticTokenType::ENUM_MEMBER, 0);
+        }
+        if next == Some(&TokenKind::LeftParen) {
+            return (SemanticTokenType::FUNCTION, 0);
+        }
+    }
+
+    if analysis.is_parameter(name, token.span.start) {
+        return (SemanticTokenType::PARAMETER, 0);
+    }
+
+    // Uses, by what inference found
+    if let Some(ty) = analysis.expr_type(&token.span) {
+        if matches!(ty, Type::Function { .. }) {
+            let modifiers = if is_async_function(ty) { async_bit } else { 0 };
+            return (SemanticTokenType::FUNCTION, modifiers);
+        }
+        return (SemanticTokenType::VARIABLE, 0);
+    }
+    if let Some(function) = analysis.functions.get(name) {
+        let modifiers = if function.is_async { async_bit } else { 0 };
+        return (SemanticTokenType::FUNCTION, modifiers);
+    }
+    if analysis.structs.contains(name) {
+        return (SemanticTokenType::STRUCT, 0);
+    }
+    if analysis.enums.contains(name) {
+        return (SemanticTokenType::ENUM, 0);
+    }
+    if matches!(previous, Some(TokenKind::Colon | TokenKind::Arrow)) {
+        return (SemanticTokenType::TYPE, 0);
+    }
+    (SemanticTokenType::VARIABLE, 0)
+}
+
 /// Generate semantic tokens for a Script source file
+///
+/// Keywords, literals and operators are classified by their token.
+/// Identifiers are classified by where they appear and, when the document
+/// type checks, by their inferred type, so a variable holding a closure
+/// highlights as a function and calls to async functions carry the `async`
+/// modifier.
 pub fn generate_semantic_tokens(source: &str) -> Vec<SemanticToken> {
-    let mut lexer = match Lexer::new(source) {
-        Ok(lexer) => lexer,
-        Err(_) => return Vec::new(), // Return empty tokens on lexer initialization error
-    };
+    let analysis = DocumentAnalysis::new(source);
     let mut tokens = Vec::new();
     let mut prev_line = 0;
     let mut prev_col = 0;
 
-    loop {
-        let (token_opt, _errors) = lexer.next_token();
-        let token = match token_opt {
-            Some(t) => t,
-            None => break,
+    for (index, token) in analysis.tokens.iter().enumerate() {
+        let (token_type, token_modifiers_bitset) = match &token.kind {
+            TokenKind::Identifier(name) => {
+                let (token_type, modifiers) = classify_identifier(&analysis, index, name);
+                (type_index(&token_type), modifiers)
+            }
+            // Skip tokens we don't want to highlight
+            kind => match token_kind_to_semantic_type(kind) {
+                Some(token_type) => (token_type, 0),
+                None => continue,
+            },
         };
 
-        if matches!(token.kind, TokenKind::Eof) {
-            break;
-        }
+        // The lexer counts lines and columns from 1, LSP from 0
+        let line = (token.span.start.line - 1) as u32;
+        let col = (token.span.start.column - 1) as u32;
+        let length = (token.span.end.byte_offset - token.span.start.byte_offset) as u32;
 
-        // Skip tokens we don't want to highlight
-        if let Some(token_type) = token_kind_to_semantic_type(&token.kind) {
-            let line = token.span.start.line as u32;
-            let col = token.span.start.column as u32;
-            let length = (token.span.end.byte_offset - token.span.start.byte_offset) as u32;
-
-            // LSP semantic tokens use delta encoding
-            let delta_line = line - prev_line;
-            let delta_start = if delta_line == 0 { col - prev_col } else { col };
-
-            tokens.push(SemanticToken {
-                delta_line,
-                delta_start,
-                length,
-                token_type,
-                token_modifiers_bitset: 0, // We'll add modifiers later
-            });
-
-            prev_line = line;
-            prev_col = col;
-        }
+        // LSP semantic tokens use delta encoding
+        let delta_line = line - prev_line;
+        let delta_start = if delta_line == 0 { col - prev_col } else { col };
+
+        tokens.push(SemanticToken {
+            delta_line,
+            delta_start,
+            length,
+            token_type,
+            token_modifiers_bitset,
+        });
+
+        prev_line = line;
+        prev_col = col;
     }
 
     tokens
@@ -155,4 +276,51 @@ let greeting = "Hello, World!";
             "Expected some semantic tokens but got none"
         );
     }
+
+    /// Absolute (line, column, type, modifiers) of each token
+    fn decode(tokens: &[SemanticToken]) -> Vec<(u32, u32, SemanticTokenType, u32)> {
+        let mut line = 0;
+        let mut column = 0;
+        tokens
+            .iter()
+            .map(|token| {
+                if token.delta_line > 0 {
+                    column = 0;
+                }
+                line += token.delta_line;
+                column += token.delta_start;
+                let token_type = SUPPORTED_TOKEN_TYPES[token.token_type as usize].clone();
+                (line, column, token_type, token.token_modifiers_bitset)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_identifiers_classified_by_inference() {
+        let source = "async fn fetch(id: i32) -> i32 {\n    id\n}\n\n\
+                      fn main() {\n    let double = |x: i32| x * 2;\n    let task = fetch(double(21));\n    task.len()\n}\n";
+        let tokens = decode(&generate_semantic_tokens(source));
+        let at = |line, column| {
+            tokens
+                .iter()
+                .find(|token| token.0 == line && token.1 == column)
+                .map(|token| (token.2.clone(), token.3))
+                .unwrap()
+        };
+        let declaration = modifier_bit(&SemanticTokenModifier::DECLARATION);
+        let async_bit = modifier_bit(&SemanticTokenModifier::ASYNC);
+
+        assert_eq!(at(0, 0), (SemanticTokenType::KEYWORD, 0));
+        assert_eq!(
+            at(0, 9),
+            (SemanticTokenType::FUNCTION, declaration | async_bit)
+        );
+        assert_eq!(at(0, 15), (SemanticTokenType::PARAMETER, 0));
+        assert_eq!(at(1, 4), (SemanticTokenType::PARAMETER, 0));
+        assert_eq!(at(5, 8), (SemanticTokenType::VARIABLE, declaration));
+        // Called through a variable, but inference knows it is a closure
+        assert_eq!(at(6, 21), (SemanticTokenType::FUNCTION, 0));
+        assert_eq!(at(6, 15), (SemanticTokenType::FUNCTION, async_bit));
+        assert_eq!(at(7, 9), (SemanticTokenType::METHOD, 0));
+    }
 }
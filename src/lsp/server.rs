@@ -1,28 +1,47 @@
+use crate::lsp::diagnostics::document_diagnostics;
+use crate::lsp::workspace::{file_diagnostics, WorkspaceDiagnostics, DEBOUNCE};
 use crate::lsp::{capabilities::get_server_capabilities, handlers::*, state::ServerState};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tower_lsp::jsonrpc::Result;
-use tower_lsp::{lsp_types::*, LanguageServer, LspService, Server};
+use tower_lsp::{lsp_types::*, Client, LanguageServer, LspService, Server};
 
 /// The main Script Language Server implementation
 #[derive(Debug)]
 pub struct ScriptLanguageServer {
     state: ServerState,
+    /// Connection to the editor, absent when the server is driven directly
+    client: Option<Client>,
+    /// Module graph and pending re-checks for workspace-wide diagnostics
+    workspace: Arc<WorkspaceDiagnostics>,
+    /// Whether the editor accepts dynamic registration of type hierarchy
+    /// requests, which `ServerCapabilities` has no static field for
+    register_type_hierarchy: AtomicBool,
 }
 
 impl ScriptLanguageServer {
     pub fn new() -> Self {
         Self {
             state: ServerState::new(),
+            client: None,
+            workspace: Arc::new(WorkspaceDiagnostics::new()),
+            register_type_hierarchy: AtomicBool::new(false),
+        }
+    }
+
+    /// Create a server that can send requests to the editor
+    pub fn with_client(client: Client) -> Self {
+        Self {
+            client: Some(client),
+            ..Self::new()
         }
     }
 
     /// Create a new LSP service for this server
     pub fn create_service() -> (LspService<Self>, tower_lsp::ClientSocket) {
-        LspService::new(|client| {
-            // We can use the client for sending notifications/requests to the editor
-            let _client = client;
-            ScriptLanguageServer::new()
-        })
+        LspService::new(ScriptLanguageServer::with_client)
     }
 
     /// Run the server over stdio
@@ -60,11 +79,79 @@ impl ScriptLanguageServer {
     {
         Server::new(read, write, socket).serve(service).await;
     }
+
+    /// Send the editor the compiler's diagnostics for an open document
+    async fn publish_diagnostics(&self, uri: Url) {
+        let Some(client) = &self.client else {
+            return;
+        };
+        let Some(document) = self.state.get_document(&uri) else {
+            return;
+        };
+        let context = self.state.document_context(&uri);
+        let diagnostics = document_diagnostics(&document.content, &context);
+        client
+            .publish_diagnostics(uri, diagnostics, Some(document.version))
+            .await;
+    }
+
+    /// Check `file` and the workspace files that depend on it again once
+    /// edits have paused for `DEBOUNCE`
+    fn schedule_check(&self, file: PathBuf) {
+        let Some(client) = self.client.clone() else {
+            return;
+        };
+        let generation = self.workspace.file_changed(&self.state, &file);
+        let workspace = self.workspace.clone();
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(DEBOUNCE).await;
+            let files = workspace.take_affected(generation);
+            publish_files(client, state, files).await;
+        });
+    }
+}
+
+/// Check `files` off the async threads and send the editor the results
+async fn publish_files(client: Client, state: ServerState, files: Vec<PathBuf>) {
+    if files.is_empty() {
+        return;
+    }
+    let checked = tokio::task::spawn_blocking(move || {
+        let sources = Arc::new(state.open_sources());
+        files
+            .iter()
+            .filter_map(|file| file_diagnostics(&state, &sources, file))
+            .collect::<Vec<_>>()
+    })
+    .await
+    .unwrap_or_default();
+
+    for (uri, diagnostics, version) in checked {
+        client.publish_diagnostics(uri, diagnostics, version).await;
+    }
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for ScriptLanguageServer {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        if let Some(root) = params.root_uri.and_then(|uri| uri.to_file_path().ok()) {
+            if let Err(e) = self.state.load_symbol_index(&root) {
+                eprintln!("Failed to load symbol index: {}", e);
+            }
+            self.state
+                .with_symbol_index(|index| self.workspace.rebuild(index));
+        }
+
+        let type_hierarchy = params
+            .capabilities
+            .text_document
+            .as_ref()
+            .and_then(|text_document| text_document.type_hierarchy.as_ref())
+            .and_then(|type_hierarchy| type_hierarchy.dynamic_registration);
+        self.register_type_hierarchy
+            .store(type_hierarchy == Some(true), Ordering::Relaxed);
+
         Ok(InitializeResult {
             capabilities: get_server_capabilities(),
             ..Default::default()
@@ -72,7 +159,31 @@ impl LanguageServer for ScriptLanguageServer {
     }
 
     async fn initialized(&self, _: InitializedParams) {
-        // Called after successful initialization
+        let Some(client) = &self.client else {
+            return;
+        };
+
+        // Report on the whole workspace, not just the documents opened later
+        tokio::spawn(publish_files(
+            client.clone(),
+            self.state.clone(),
+            self.workspace.files(),
+        ));
+
+        if !self.register_type_hierarchy.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let registration = Registration {
+            id: "script-type-hierarchy".to_string(),
+            method: "textDocument/prepareTypeHierarchy".to_string(),
+            register_options: Some(serde_json::json!({
+                "documentSelector": [{ "language": "script" }]
+            })),
+        };
+        if let Err(e) = client.register_capability(vec![registration]).await {
+            eprintln!("Failed to register type hierarchy: {:?}", e);
+        }
     }
 
     async fn shutdown(&self) -> Result<()> {
@@ -80,21 +191,38 @@ impl LanguageServer for ScriptLanguageServer {
     }
 
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let uri = params.text_document.uri.clone();
         if let Err(e) = handle_did_open(&self.state, params).await {
             eprintln!("Error in did_open: {:?}", e);
         }
+        self.publish_diagnostics(uri).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        let uri = params.text_document.uri.clone();
         if let Err(e) = handle_did_change(&self.state, params).await {
             eprintln!("Error in did_change: {:?}", e);
         }
+        if let Ok(file) = uri.to_file_path() {
+            self.schedule_check(file);
+        }
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri.clone();
         if let Err(e) = handle_did_close(&self.state, params).await {
             eprintln!("Error in did_close: {:?}", e);
         }
+        // A workspace file reverts to its contents on disk, for itself and
+        // the files importing it; diagnostics of any other file are stale
+        match uri.to_file_path() {
+            Ok(file) if self.workspace.contains(&file) => self.schedule_check(file),
+            _ => {
+                if let Some(client) = &self.client {
+                    client.publish_diagnostics(uri, Vec::new(), None).await;
+                }
+            }
+        }
     }
 
     async fn semantic_tokens_full(
@@ -104,6 +232,10 @@ impl LanguageServer for ScriptLanguageServer {
         handle_semantic_tokens_full(&self.state, params).await
     }
 
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        handle_inlay_hint(&self.state, params).await
+    }
+
     async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
         handle_completion(&self.state, params).await
     }
@@ -114,6 +246,103 @@ impl LanguageServer for ScriptLanguageServer {
     ) -> Result<Option<GotoDefinitionResponse>> {
         handle_goto_definition(&self.state, params).await
     }
+
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        handle_folding_range(&self.state, params).await
+    }
+
+    async fn selection_range(
+        &self,
+        params: SelectionRangeParams,
+    ) -> Result<Option<Vec<SelectionRange>>> {
+        handle_selection_range(&self.state, params).await
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        handle_formatting(&self.state, params).await
+    }
+
+    async fn range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        handle_range_formatting(&self.state, params).await
+    }
+
+    async fn on_type_formatting(
+        &self,
+        params: DocumentOnTypeFormattingParams,
+    ) -> Result<Option<Vec<TextEdit>>> {
+        handle_on_type_formatting(&self.state, params).await
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        handle_code_action(&self.state, params).await
+    }
+
+    async fn prepare_call_hierarchy(
+        &self,
+        params: CallHierarchyPrepareParams,
+    ) -> Result<Option<Vec<CallHierarchyItem>>> {
+        handle_prepare_call_hierarchy(&self.state, params).await
+    }
+
+    async fn incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyIncomingCall>>> {
+        handle_incoming_calls(&self.state, params).await
+    }
+
+    async fn outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+        handle_outgoing_calls(&self.state, params).await
+    }
+
+    async fn prepare_type_hierarchy(
+        &self,
+        params: TypeHierarchyPrepareParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        handle_prepare_type_hierarchy(&self.state, params).await
+    }
+
+    async fn supertypes(
+        &self,
+        params: TypeHierarchySupertypesParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        handle_supertypes(&self.state, params).await
+    }
+
+    async fn subtypes(
+        &self,
+        params: TypeHierarchySubtypesParams,
+    ) -> Result<Option<Vec<TypeHierarchyItem>>> {
+        handle_subtypes(&self.state, params).await
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        handle_references(&self.state, params).await
+    }
+
+    async fn symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> Result<Option<Vec<SymbolInformation>>> {
+        handle_workspace_symbol(&self.state, params).await
+    }
+
+    async fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        handle_prepare_rename(&self.state, params).await
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        handle_rename(&self.state, params).await
+    }
 }
 
 #[cfg(test)]
@@ -1,5 +1,9 @@
+use crate::index::SymbolIndex;
+use crate::lsp::diagnostics::DocumentContext;
 use dashmap::DashMap;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 use tower_lsp::lsp_types::TextDocumentItem;
 use url::Url;
 
@@ -33,23 +37,79 @@ impl Document {
 pub struct ServerState {
     /// Currently open documents indexed by their URI
     pub documents: Arc<DashMap<Url, Document>>,
+    /// Project-wide symbol index, loaded once the workspace root is known
+    pub symbol_index: Arc<RwLock<Option<SymbolIndex>>>,
 }
 
 impl ServerState {
     pub fn new() -> Self {
         Self {
             documents: Arc::new(DashMap::new()),
+            symbol_index: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Load (or build) the symbol index for a workspace root
+    pub fn load_symbol_index(&self, root: &Path) -> crate::Result<()> {
+        let mut index = SymbolIndex::load(root)?;
+        index.refresh()?;
+        index.save()?;
+        if let Ok(mut slot) = self.symbol_index.write() {
+            *slot = Some(index);
+        }
+        Ok(())
+    }
+
+    /// Run a query against the symbol index, if one is loaded
+    pub fn with_symbol_index<T>(&self, f: impl FnOnce(&SymbolIndex) -> T) -> Option<T> {
+        let guard = self.symbol_index.read().ok()?;
+        guard.as_ref().map(f)
+    }
+
+    /// Contents of the open documents by canonical path
+    pub fn open_sources(&self) -> HashMap<PathBuf, String> {
+        self.documents
+            .iter()
+            .filter_map(|doc| {
+                let path = doc.uri.to_file_path().ok()?;
+                let path = path.canonicalize().unwrap_or(path);
+                Some((path, doc.content.clone()))
+            })
+            .collect()
+    }
+
+    /// Context for analyzing the document at `uri` against the workspace
+    /// and the other open documents
+    pub fn document_context(&self, uri: &Url) -> DocumentContext {
+        DocumentContext {
+            file: uri.to_file_path().ok(),
+            root: self.with_symbol_index(|index| index.root().to_path_buf()),
+            sources: Arc::new(self.open_sources()),
+        }
+    }
+
+    /// Re-index an open document; unparseable intermediate edits are ignored
+    fn reindex_document(&self, uri: &Url, text: &str) {
+        let Ok(path) = uri.to_file_path() else {
+            return;
+        };
+        if let Ok(mut slot) = self.symbol_index.write() {
+            if let Some(index) = slot.as_mut() {
+                let _ = index.update_file(&path, text);
+            }
         }
     }
 
     /// Open a new document
     pub fn open_document(&self, item: TextDocumentItem) {
         let doc = Document::new(item);
+        self.reindex_document(&doc.uri, &doc.content);
         self.documents.insert(doc.uri.clone(), doc);
     }
 
     /// Update an existing document
     pub fn update_document(&self, uri: Url, version: i32, text: String) -> Option<()> {
+        self.reindex_document(&uri, &text);
         self.documents.get_mut(&uri)?.update(version, text);
         Some(())
     }
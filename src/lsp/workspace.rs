@@ -0,0 +1,301 @@
+use crate::compilation::ModuleLoader;
+use crate::index::SymbolIndex;
+use crate::lsp::diagnostics::{document_diagnostics, DocumentContext};
+use crate::lsp::state::ServerState;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tower_lsp::lsp_types::{Diagnostic, Url};
+
+/// How long edits must pause before the affected files are checked again
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Which workspace files import which, from the imports in the symbol index
+///
+/// Files are keyed by their path under the workspace root, the way the
+/// editor names them.
+#[derive(Debug, Default)]
+pub struct ModuleGraph {
+    /// Workspace files each file imports
+    imports: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    /// Workspace files that import each file
+    importers: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+    /// Canonical path of each file, to match resolved imports against
+    canonical: HashMap<PathBuf, PathBuf>,
+}
+
+impl ModuleGraph {
+    /// Build the graph of every file in `index`
+    pub fn build(index: &SymbolIndex) -> Self {
+        let mut graph = ModuleGraph::default();
+        let files: Vec<PathBuf> = index
+            .files()
+            .map(|(path, _)| index.root().join(path))
+            .collect();
+        for file in &files {
+            graph.add_file(file);
+        }
+        for file in &files {
+            graph.update_file(index, file);
+        }
+        graph
+    }
+
+    /// Re-read the imports of `file` from `index`
+    pub fn update_file(&mut self, index: &SymbolIndex, file: &Path) {
+        self.add_file(file);
+        self.clear_imports(file);
+
+        let mut loader = ModuleLoader::new();
+        loader.add_search_path(index.root().to_path_buf());
+        let imports: BTreeSet<PathBuf> = index
+            .file(file)
+            .map(|entry| entry.imports.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|module| loader.resolve_module(module, Some(file)).ok())
+            .filter_map(|resolved| self.workspace_file(&resolved))
+            .filter(|imported| imported != file)
+            .collect();
+
+        for imported in &imports {
+            self.importers
+                .entry(imported.clone())
+                .or_default()
+                .insert(file.to_path_buf());
+        }
+        self.imports.insert(file.to_path_buf(), imports);
+    }
+
+    /// Whether `file` is part of the workspace
+    pub fn contains(&self, file: &Path) -> bool {
+        self.canonical.contains_key(file)
+    }
+
+    /// Every file in the workspace
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self.canonical.keys().cloned().collect();
+        files.sort();
+        files
+    }
+
+    /// Files that import `file`, directly or through other files
+    pub fn dependents(&self, file: &Path) -> BTreeSet<PathBuf> {
+        let mut found = BTreeSet::new();
+        let mut queue = vec![file.to_path_buf()];
+        while let Some(next) = queue.pop() {
+            for importer in self.importers.get(&next).into_iter().flatten() {
+                if importer != file && found.insert(importer.clone()) {
+                    queue.push(importer.clone());
+                }
+            }
+        }
+        found
+    }
+
+    fn add_file(&mut self, file: &Path) {
+        if !self.canonical.contains_key(file) {
+            let canonical = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+            self.canonical.insert(file.to_path_buf(), canonical);
+        }
+    }
+
+    fn clear_imports(&mut self, file: &Path) {
+        for imported in self.imports.remove(file).unwrap_or_default() {
+            if let Some(importers) = self.importers.get_mut(&imported) {
+                importers.remove(file);
+            }
+        }
+    }
+
+    /// The workspace file a resolved import points at
+    fn workspace_file(&self, resolved: &Path) -> Option<PathBuf> {
+        let canonical = resolved
+            .canonicalize()
+            .unwrap_or_else(|_| resolved.to_path_buf());
+        self.canonical
+            .iter()
+            .find(|(_, known)| **known == canonical)
+            .map(|(file, _)| file.clone())
+    }
+}
+
+/// Diagnostics for every file in the workspace, kept current as files change
+///
+/// An error in a module shows up at the imports of every file that depends
+/// on it, so a change re-checks the changed file and its reverse
+/// dependencies. Changes that arrive in quick succession are checked once,
+/// after `DEBOUNCE` passes without another one.
+#[derive(Debug, Default)]
+pub struct WorkspaceDiagnostics {
+    graph: Mutex<ModuleGraph>,
+    /// Files changed since the last check
+    pending: Mutex<BTreeSet<PathBuf>>,
+    /// Counts changes, so only the last of a burst runs the check
+    generation: AtomicU64,
+}
+
+impl WorkspaceDiagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn graph(&self) -> MutexGuard<'_, ModuleGraph> {
+        self.graph
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Build the module graph from the symbol index
+    pub fn rebuild(&self, index: &SymbolIndex) {
+        *self.graph() = ModuleGraph::build(index);
+    }
+
+    /// Every file in the workspace
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.graph().files()
+    }
+
+    /// Whether `file` is part of the workspace
+    pub fn contains(&self, file: &Path) -> bool {
+        self.graph().contains(file)
+    }
+
+    /// Record that `file` changed, after the index has been updated
+    ///
+    /// Returns the generation of the change, to pass to `take_affected`
+    /// once the debounce delay has passed.
+    pub fn file_changed(&self, state: &ServerState, file: &Path) -> u64 {
+        state.with_symbol_index(|index| self.graph().update_file(index, file));
+        self.pending
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(file.to_path_buf());
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// The changed files and the files that depend on them
+    ///
+    /// Returns nothing when a later change has come in since `generation`,
+    /// since the check for that change covers these files too.
+    pub fn take_affected(&self, generation: u64) -> Vec<PathBuf> {
+        if self.generation.load(Ordering::SeqCst) != generation {
+            return Vec::new();
+        }
+        let changed = std::mem::take(
+            &mut *self
+                .pending
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        );
+
+        let graph = self.graph();
+        let mut affected = changed.clone();
+        for file in &changed {
+            affected.extend(graph.dependents(file));
+        }
+        affected.into_iter().collect()
+    }
+}
+
+/// Diagnostics for a workspace file, with the version of the open document
+/// they apply to
+///
+/// Open documents are checked as the editor has them, closed files as they
+/// are on disk. Modules they import are read the same way.
+pub fn file_diagnostics(
+    state: &ServerState,
+    sources: &Arc<HashMap<PathBuf, String>>,
+    file: &Path,
+) -> Option<(Url, Vec<Diagnostic>, Option<i32>)> {
+    let uri = Url::from_file_path(file).ok()?;
+    let (content, version) = match state.get_document(&uri) {
+        Some(document) => (document.content, Some(document.version)),
+        None => (fs::read_to_string(file).ok()?, None),
+    };
+
+    let context = DocumentContext {
+        file: Some(file.to_path_buf()),
+        root: state.with_symbol_index(|index| index.root().to_path_buf()),
+        sources: sources.clone(),
+    };
+    let diagnostics = document_diagnostics(&content, &context);
+    Some((uri, diagnostics, version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, name: &str, content: &str) -> PathBuf {
+        let path = root.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_dependents_are_transitive() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let base = write(root, "base.script", "export fn one() -> i32 { 1 }\n");
+        let middle = write(
+            root,
+            "middle.script",
+            "import { one } from \"base\"\nexport fn two() -> i32 { one() + 1 }\n",
+        );
+        let top = write(
+            root,
+            "top.script",
+            "import { two } from \"middle\"\nfn main() { two(); }\n",
+        );
+        let other = write(root, "other.script", "fn main() {}\n");
+
+        let mut index = SymbolIndex::new(root);
+        for file in [&base, &middle, &top, &other] {
+            index
+                .update_file(file, &fs::read_to_string(file).unwrap())
+                .unwrap();
+        }
+
+        let mut graph = ModuleGraph::build(&index);
+        assert_eq!(
+            graph.dependents(&base),
+            BTreeSet::from([middle.clone(), top.clone()])
+        );
+        assert_eq!(graph.dependents(&middle), BTreeSet::from([top.clone()]));
+        assert!(graph.dependents(&other).is_empty());
+
+        // Dropping the import cuts the chain
+        index
+            .update_file(&middle, "export fn two() -> i32 { 2 }\n")
+            .unwrap();
+        graph.update_file(&index, &middle);
+        assert!(graph.dependents(&base).is_empty());
+    }
+
+    #[test]
+    fn test_change_rechecks_dependents_once() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        let base = write(root, "base.script", "export fn one() -> i32 { 1 }\n");
+        let main = write(
+            root,
+            "main.script",
+            "import { one } from \"base\"\nfn main() { one(); }\n",
+        );
+
+        let state = ServerState::new();
+        state.load_symbol_index(root).unwrap();
+        let workspace = WorkspaceDiagnostics::new();
+        state.with_symbol_index(|index| workspace.rebuild(index));
+
+        let first = workspace.file_changed(&state, &base);
+        let second = workspace.file_changed(&state, &base);
+        assert!(workspace.take_affected(first).is_empty());
+        assert_eq!(workspace.take_affected(second), vec![base, main]);
+    }
+}
@@ -1,173 +1,773 @@
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
 use colored::*;
-use script::compilation::CompilationContext;
-use script::debugger::{get_debugger, initialize_debugger, shutdown_debugger, Debugger};
+use script::codegen::{eliminate_dead_functions, OverflowMode};
+use script::compilation::time_passes::{self, Phase};
+use script::compilation::{
+    hermetic, remote_cache, ArtifactKey, BuildProfile, CompilationContext, DeclaredInputs,
+    RemoteCache, RemoteCacheConfig,
+};
+use script::completions::{self, Shell};
+use script::debugger::{
+    get_debugger, initialize_debugger, initialize_debugger_with, shutdown_debugger,
+};
 use script::doc::{generator::DocGenerator, html::HtmlGenerator};
-use script::repl::EnhancedRepl;
-use script::testing::TestingFramework;
-use script::{error::ErrorReporter, Lexer, Parser, SemanticAnalyzer, Token, TokenKind};
-use script::{AstLowerer, CodeGenerator};
+use script::edition::{migrate_package, Edition};
+use script::formatter::{format_source, FormatterConfig};
+use script::index::{CallGraph, CallGraphOptions};
+use script::ir::optimizer::OptimizationLevel;
+use script::lint::{apply_fixes, lint_source, LintConfig, LintLevel};
+use script::manuscript::templates::{scaffold_project, ProjectTemplate};
+use script::metrics::{MetricThresholds, MetricsReport};
+use script::repl::{EnhancedRepl, ReplEvaluator};
+use script::runtime::{
+    coverage, deterministic, function_profiler, gc, heap_profiler, leak_check, shutdown, GcMode,
+};
+use script::runtime::{CoverageMap, CoverageReport};
+use script::stdlib::diff::{diff_lines, unified_diff, DEFAULT_CONTEXT};
+use script::stdlib::inspect::inspect_value;
+use script::testing::{apply_expect_updates, ExpectMismatch, TestRunOptions, TestingFramework};
+use script::{
+    error::{
+        error_format, set_error_format, ErrorFormat, ErrorReporter, Fix, JsonDiagnostic, Severity,
+    },
+    Lexer, Parser as ScriptParser, SemanticAnalyzer, Token, TokenKind,
+};
+use script::{AstLowerer, CodeGenerator, IrModule};
+use script::{PackageManifest, SymbolIndex};
 use std::{
     env, fs,
     io::{self, Write},
-    path::Path,
+    path::{Path, PathBuf},
     process,
 };
 
+/// Input modes of the basic REPL
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum Mode {
     Tokens,
     Parse,
     Run,
-    Test,
-    Doc,
     Debug,
 }
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
+#[derive(Parser)]
+#[command(
+    name = "script",
+    version = env!("CARGO_PKG_VERSION"),
+    about = "The Script programming language",
+    long_about = None,
+    after_help = "`script <file>` is shorthand for `script run <file>`, and `script` alone starts the REPL."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// When to color output
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Report what each compilation stage did
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Target to compile for
+    #[arg(long, global = true, value_enum, default_value_t = Target::Native)]
+    target: Target,
+
+    /// How errors and warnings are printed: human, or json for one object
+    /// per diagnostic on stderr
+    #[arg(
+        long,
+        global = true,
+        value_name = "FORMAT",
+        value_parser = parse_error_format,
+        default_value = "human"
+    )]
+    error_format: ErrorFormat,
+
+    /// Report the time and memory each compilation phase takes, per module
+    #[arg(long, global = true)]
+    time_passes: bool,
+}
 
-    // Check for version flag
-    if args.len() >= 2 && (args[1] == "--version" || args[1] == "-V") {
-        println!(
-            "Script Language v{} - Production Ready ✅",
-            env!("CARGO_PKG_VERSION")
-        );
-        println!("🚀 Enterprise-grade security with comprehensive validation");
-        println!("🔒 Memory-safe • Type-safe • Performance-optimized");
-        println!("📖 Documentation: https://github.com/moikapy/script");
-        return;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ColorChoice {
+    /// Color output written to a terminal, unless NO_COLOR is set
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Target {
+    /// Machine code for this machine, run by the JIT
+    Native,
+    /// A WebAssembly module
+    Wasm32,
+}
+
+/// Intermediate form printed by `--emit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Emit {
+    Tokens,
+    Ast,
+    Ir,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compile and run a script file, or a project directory as a whole
+    Run(RunArgs),
+
+    /// Compile a script file for the target given by --target
+    Build {
+        /// Script file to compile
+        #[arg(add = completions::script_files())]
+        file: PathBuf,
+
+        /// Output file, the input with the target's extension by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Use the release profile instead of dev
+        #[arg(long)]
+        release: bool,
+
+        /// Override the profile's opt-level
+        #[command(flatten)]
+        optimize: OptimizeArgs,
+
+        /// Fail if the build reads anything but its package's declared inputs
+        #[arg(long)]
+        hermetic: bool,
+
+        /// Share compiled output through the remote build cache at this URL
+        #[arg(long, env = "SCRIPT_REMOTE_CACHE", value_name = "URL")]
+        remote_cache: Option<String>,
+
+        /// Download from the remote cache without uploading new builds
+        #[arg(long, requires = "remote_cache")]
+        remote_cache_read_only: bool,
+    },
+
+    /// Report errors in a script file or project without running it
+    Check {
+        /// Script file or project directory
+        #[arg(add = completions::script_files())]
+        path: PathBuf,
+
+        /// Print the tokens, syntax tree or IR of the program
+        #[arg(long, value_enum)]
+        emit: Option<Emit>,
+    },
+
+    /// Run the tests in a script file
+    Test {
+        /// Script file holding the tests
+        #[arg(add = completions::script_files())]
+        file: PathBuf,
+
+        /// Rewrite failing expect() snapshots with the actual values
+        #[arg(long)]
+        update_expect: bool,
+    },
+
+    /// Generate HTML documentation for a source directory
+    Doc {
+        /// Directory of script files to document
+        source: PathBuf,
+
+        /// Directory to write the documentation to
+        #[arg(default_value = "./docs")]
+        output: PathBuf,
+    },
+
+    /// Debug a script file, or manage breakpoints (`script debug help`)
+    Debug {
+        /// Start without the breakpoints saved in .script-debug.toml
+        #[arg(long)]
+        no_restore: bool,
+
+        /// A script file, or a breakpoint command and its arguments
+        #[arg(
+            value_name = "COMMAND",
+            trailing_var_arg = true,
+            allow_hyphen_values = true
+        )]
+        args: Vec<String>,
+    },
+
+    /// Format script files in place
+    Fmt {
+        /// Files and directories to format, the current directory by default
+        #[arg(add = completions::script_files())]
+        paths: Vec<PathBuf>,
+
+        /// List the files that would change instead of changing them
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Check script files for likely mistakes, configured by `[lints]`
+    Lint {
+        /// Files and directories to lint, the current directory by default
+        #[arg(add = completions::script_files())]
+        paths: Vec<PathBuf>,
+
+        /// Apply the fixes for the problems that have one
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Start the interactive REPL
+    Repl,
+
+    /// Run project-wide static analyses
+    Analyze(AnalyzeArgs),
+
+    /// Find where a symbol is defined
+    GrepDef {
+        /// Name of the symbol
+        name: String,
+
+        /// Project directory, the current directory by default
+        dir: Option<PathBuf>,
+    },
+
+    /// Explain an error code with examples
+    Explain {
+        /// Code from a diagnostic, such as E0425
+        code: String,
+    },
+
+    /// Migrate a package to a newer edition
+    Fix {
+        /// Edition to migrate to, the latest when no value is given
+        #[arg(
+            long,
+            required = true,
+            value_name = "EDITION",
+            num_args = 0..=1,
+            default_missing_value = "latest",
+            value_parser = parse_edition
+        )]
+        edition: Option<Edition>,
+
+        /// Project directory, the current directory by default
+        dir: Option<PathBuf>,
+    },
+
+    /// Work with the line counts written by `script run --coverage`
+    Coverage {
+        #[command(subcommand)]
+        command: CoverageCommand,
+    },
+
+    /// Create a minimal project
+    Init {
+        /// Create a library
+        #[arg(long, conflicts_with_all = ["bin", "game"])]
+        lib: bool,
+
+        /// Create an application (the default)
+        #[arg(long, conflicts_with = "game")]
+        bin: bool,
+
+        /// Create an application with an update and render loop
+        #[arg(long)]
+        game: bool,
+
+        /// Project directory, the current directory by default
+        dir: Option<PathBuf>,
+    },
+
+    /// Update the script toolchain
+    Update(UpdateArgs),
+
+    /// Print a shell completion script
+    Completions {
+        /// Shell to complete for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+#[derive(Args)]
+struct RunArgs {
+    /// Script file, or project directory to compile as a whole
+    #[arg(add = completions::script_files())]
+    path: PathBuf,
+
+    #[command(flatten)]
+    optimize: OptimizeArgs,
+
+    /// Print the tokens, syntax tree or IR before running
+    #[arg(long, value_enum)]
+    emit: Option<Emit>,
+
+    /// Report the functions removed by dead function elimination
+    #[arg(long)]
+    print_dce_stats: bool,
+
+    /// Overflow behavior of integer arithmetic: debug, trap or wrap
+    #[arg(long, value_name = "MODE", value_parser = parse_overflow_mode)]
+    overflow: Option<OverflowMode>,
+
+    /// How the runtime reclaims reference cycles: rc, cycle or generational
+    #[arg(long, value_name = "MODE", value_parser = parse_gc_mode)]
+    gc: Option<GcMode>,
+
+    /// Make randomness and time reproducible, seeded with SEED (0 by default)
+    #[arg(
+        long,
+        value_name = "SEED",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0"
+    )]
+    deterministic: Option<u64>,
+
+    /// Profile function calls and write a flamegraph stack file
+    #[arg(long)]
+    profile: bool,
+
+    /// Track allocations and print the top SITES allocation sites at exit
+    #[arg(
+        long,
+        value_name = "SITES",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "20"
+    )]
+    heap_profile: Option<usize>,
+
+    /// Report files, sockets and tasks still open when the program exits
+    #[arg(long)]
+    check_leaks: bool,
+
+    /// Count executed lines and write them to a `.coverage` file
+    #[arg(long)]
+    coverage: bool,
+}
+
+#[derive(Args)]
+struct OptimizeArgs {
+    /// IR optimization level, from 0 (none) to 3
+    #[arg(
+        short = 'O',
+        long = "opt-level",
+        value_name = "LEVEL",
+        value_parser = parse_opt_level
+    )]
+    opt_level: Option<OptimizationLevel>,
+}
+
+#[derive(Args)]
+struct AnalyzeArgs {
+    /// Print the call graph of the project
+    #[arg(long, required_unless_present = "metrics", conflicts_with = "metrics")]
+    callgraph: bool,
+
+    /// Report complexity metrics for each function
+    #[arg(long)]
+    metrics: bool,
+
+    /// Output format: dot or json for --callgraph, text or json for --metrics
+    #[arg(long)]
+    format: Option<String>,
+
+    /// Write the call graph to a file
+    #[arg(short, long, requires = "callgraph")]
+    output: Option<String>,
+
+    /// Treat a function as an entry point of the call graph
+    #[arg(long = "entry", value_name = "NAME", requires = "callgraph")]
+    entries: Vec<String>,
+
+    /// Treat exported functions as entry points
+    #[arg(long, requires = "callgraph")]
+    include_exports: bool,
+
+    /// Leave test functions out of the call graph
+    #[arg(long, requires = "callgraph")]
+    no_tests: bool,
+
+    /// Flag functions with a higher cyclomatic complexity
+    #[arg(long, value_name = "N", requires = "metrics")]
+    max_complexity: Option<usize>,
+
+    /// Flag functions with more lines
+    #[arg(long, value_name = "N", requires = "metrics")]
+    max_length: Option<usize>,
+
+    /// Flag functions nested deeper
+    #[arg(long, value_name = "N", requires = "metrics")]
+    max_nesting: Option<usize>,
+
+    /// Flag functions with more parameters
+    #[arg(long, value_name = "N", requires = "metrics")]
+    max_params: Option<usize>,
+
+    /// Project directory, the current directory by default
+    dir: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum CoverageCommand {
+    /// Summarize .coverage files, all of those in the current directory by
+    /// default
+    Report {
+        /// Coverage files to merge
+        files: Vec<PathBuf>,
+
+        /// Also write an LCOV tracefile
+        #[arg(long, value_name = "FILE")]
+        lcov: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+#[group(multiple = false)]
+struct UpdateArgs {
+    /// Only check whether an update is available
+    #[arg(long)]
+    check: bool,
+
+    /// Update without asking for confirmation
+    #[arg(long)]
+    force: bool,
+
+    /// List the versions available
+    #[arg(long)]
+    list: bool,
+
+    /// Install a specific version
+    #[arg(long = "version", value_name = "VERSION")]
+    to_version: Option<String>,
+
+    /// Go back to the version installed before the last update
+    #[arg(long)]
+    rollback: bool,
+}
+
+fn parse_opt_level(level: &str) -> Result<OptimizationLevel, String> {
+    OptimizationLevel::parse(&format!("-O{}", level))
+        .ok_or_else(|| format!("expected 0, 1, 2 or 3, got '{}'", level))
+}
+
+fn parse_overflow_mode(mode: &str) -> Result<OverflowMode, String> {
+    OverflowMode::parse(mode).ok_or_else(|| "expected debug, trap or wrap".to_string())
+}
+
+fn parse_error_format(format: &str) -> Result<ErrorFormat, String> {
+    ErrorFormat::parse(format).ok_or_else(|| "expected human or json".to_string())
+}
+
+fn parse_gc_mode(mode: &str) -> Result<GcMode, String> {
+    GcMode::parse(mode).ok_or_else(|| "expected rc, cycle or generational".to_string())
+}
+
+fn parse_edition(edition: &str) -> Result<Edition, String> {
+    if edition == "latest" {
+        return Ok(Edition::LATEST);
     }
+    Edition::parse(edition).map_err(|error| error.message)
+}
 
-    // Check for update command
-    if args.len() >= 2 && args[1] == "update" {
-        run_update_command(&args);
-        return;
+/// Compiler options for running a program
+#[derive(Debug, Clone, Copy, Default)]
+struct RunOptions {
+    /// Report the functions removed by dead function elimination
+    print_dce_stats: bool,
+    /// Report what each compilation stage did
+    verbose: bool,
+    /// Print the optimized IR before generating code
+    emit_ir: bool,
+    /// Overflow behavior of integer arithmetic
+    overflow_mode: OverflowMode,
+    /// How the runtime reclaims reference cycles
+    gc_mode: GcMode,
+    /// IR optimization passes to run before code generation
+    opt_level: OptimizationLevel,
+    /// Seed for deterministic execution, if enabled
+    deterministic_seed: Option<u64>,
+    /// Profile function calls and write a flamegraph stack file
+    profile: bool,
+    /// Count executed lines and write them to a `.coverage` file
+    coverage: bool,
+    /// Track allocations and print this many top allocation sites at exit
+    heap_profile: Option<usize>,
+    /// Report resources still open at exit
+    check_leaks: bool,
+}
+
+impl RunArgs {
+    fn options(&self, verbose: bool) -> RunOptions {
+        RunOptions {
+            print_dce_stats: self.print_dce_stats,
+            verbose,
+            emit_ir: self.emit == Some(Emit::Ir),
+            overflow_mode: self.overflow.unwrap_or_default(),
+            gc_mode: self.gc.unwrap_or_default(),
+            opt_level: self.optimize.opt_level.unwrap_or_default(),
+            deterministic_seed: self.deterministic,
+            profile: self.profile,
+            coverage: self.coverage,
+            heap_profile: self.heap_profile,
+            check_leaks: self.check_leaks,
+        }
     }
+}
 
-    // Check for doc command
-    if args.len() >= 2 && args[1] == "doc" {
-        run_doc_command(&args);
-        return;
+fn main() {
+    completions::complete_from_env(Cli::command);
+    let cli = Cli::parse_from(expand_shorthand(env::args().collect()));
+
+    match cli.color {
+        ColorChoice::Auto => {}
+        ColorChoice::Always => colored::control::set_override(true),
+        ColorChoice::Never => colored::control::set_override(false),
+    }
+    set_error_format(cli.error_format);
+    if cli.time_passes {
+        time_passes::start();
+    }
+    let verbose = cli.verbose > 0;
+
+    match cli.command {
+        None | Some(Command::Repl) => run_repl(),
+        Some(Command::Run(args)) => run_path(&args, cli.target, verbose),
+        Some(Command::Build {
+            file,
+            output,
+            release,
+            optimize,
+            hermetic,
+            remote_cache,
+            remote_cache_read_only,
+        }) => run_build_command(
+            &file,
+            output.as_deref(),
+            release,
+            optimize.opt_level,
+            hermetic,
+            remote_cache.map(|url| {
+                RemoteCacheConfig::new(url)
+                    .with_token(env::var(remote_cache::TOKEN_ENV).ok())
+                    .read_only(remote_cache_read_only)
+            }),
+            cli.target,
+        ),
+        Some(Command::Check { path, emit }) => check_path(&path, emit, cli.target),
+        Some(Command::Test {
+            file,
+            update_expect,
+        }) => {
+            let source = read_script_file(&file);
+            println!("{} Testing {}", "Script:".cyan().bold(), file.display());
+            run_tests(&source, Some(&file.to_string_lossy()), update_expect);
+        }
+        Some(Command::Doc { source, output }) => run_doc_command(&source, &output),
+        Some(Command::Debug { no_restore, args }) => match args.as_slice() {
+            [file] if file.ends_with(".script") => {
+                let path = Path::new(file);
+                let source = read_script_file(path);
+                println!("{} Debugging {}", "Script:".cyan().bold(), path.display());
+                run_debug_session(&source, Some(file), !no_restore);
+            }
+            _ => run_debug_command(&args, !no_restore),
+        },
+        Some(Command::Fmt { paths, check }) => run_fmt_command(&paths, check),
+        Some(Command::Lint { paths, fix }) => run_lint_command(&paths, fix),
+        Some(Command::Analyze(args)) => run_analyze_command(&args),
+        Some(Command::GrepDef { name, dir }) => run_grep_def_command(&name, dir),
+        Some(Command::Explain { code }) => run_explain_command(&code),
+        Some(Command::Fix { edition, dir }) => {
+            run_fix_command(edition.unwrap_or(Edition::LATEST), dir)
+        }
+        Some(Command::Coverage {
+            command: CoverageCommand::Report { files, lcov },
+        }) => run_coverage_report(files, lcov.as_deref()),
+        Some(Command::Init {
+            lib,
+            bin: _,
+            game,
+            dir,
+        }) => {
+            let template = if lib {
+                ProjectTemplate::Library
+            } else if game {
+                ProjectTemplate::Game
+            } else {
+                ProjectTemplate::Binary
+            };
+            run_init_command(template, dir);
+        }
+        Some(Command::Update(args)) => run_update_command(&args),
+        Some(Command::Completions { shell }) => {
+            if let Err(e) = completions::write_registration(shell, "script", &mut io::stdout()) {
+                eprintln!("{}: {}", "Error".red().bold(), e);
+                process::exit(1);
+            }
+        }
     }
+    print_time_passes();
+}
 
-    // Check for debug command
-    if args.len() >= 2 && args[1] == "debug" {
-        run_debug_command(&args);
+/// Print the phases timed for `--time-passes` to stderr, as one line of
+/// JSON with `--error-format=json`
+fn print_time_passes() {
+    if !time_passes::is_enabled() {
         return;
     }
+    let report = time_passes::stop();
+    if error_format() == ErrorFormat::Json {
+        eprintln!("{}", report.to_json_line());
+    } else {
+        eprint!("{}", report);
+    }
+}
+
+/// Rewrite the older `script <file> [--mode] [flags]` form into subcommands
+///
+/// A file on its own, or with `--run`, runs it. `--test` and
+/// `--update-expect` test it, `--debug` debugs it, and `--tokens` and
+/// `--parse` check it, printing the tokens or syntax tree.
+fn expand_shorthand(mut args: Vec<String>) -> Vec<String> {
+    let Some(first) = args.get(1) else {
+        return args;
+    };
+    if first.starts_with('-') || Cli::command().find_subcommand(first).is_some() {
+        return args;
+    }
+
+    let modes = [
+        "--run",
+        "--test",
+        "--update-expect",
+        "--debug",
+        "--tokens",
+        "--parse",
+    ];
+    let mode = args
+        .iter()
+        .skip(2)
+        .position(|arg| modes.contains(&arg.as_str()))
+        .map(|i| args.remove(i + 2));
+    let (command, extra): (&str, &[&str]) = match mode.as_deref() {
+        Some("--test") => ("test", &[]),
+        Some("--update-expect") => ("test", &["--update-expect"]),
+        Some("--debug") => ("debug", &[]),
+        Some("--tokens") => ("check", &["--emit", "tokens"]),
+        Some("--parse") => ("check", &["--emit", "ast"]),
+        _ => ("run", &[]),
+    };
+    args.insert(1, command.to_string());
+    args.extend(extra.iter().map(|arg| arg.to_string()));
+    args
+}
 
-    if args.len() > 3 {
+/// Read a `.script` file, exiting with an error if it cannot be read
+fn read_script_file(path: &Path) -> String {
+    if path.extension().and_then(|s| s.to_str()) != Some("script") {
+        eprintln!("{}: File must have .script extension", "Error".red().bold());
+        process::exit(1);
+    }
+    fs::read_to_string(path).unwrap_or_else(|e| {
         eprintln!(
-            "Usage: {} [script file] [--tokens|--run|--test|--debug]",
-            args[0]
+            "{}: Could not read file '{}': {}",
+            "Error".red().bold(),
+            path.display(),
+            e
         );
-        eprintln!("   or: {} doc [source dir] [output dir]", args[0]);
-        eprintln!("   or: {} debug [commands...]", args[0]);
+        process::exit(1);
+    })
+}
+
+/// Run a script file, or compile and run a project directory
+fn run_path(args: &RunArgs, target: Target, verbose: bool) {
+    if target != Target::Native {
         eprintln!(
-            "   or: {} update [--check|--force|--version <version>]",
-            args[0]
+            "{}: Only native code can be run; use `script build --target wasm32` for WebAssembly",
+            "Error".red().bold()
         );
-        eprintln!("   or: {} --version", args[0]);
         process::exit(1);
     }
+    let options = args.options(verbose);
+    let path = args.path.as_path();
 
-    if args.len() >= 2 {
-        run_file(&args[1], &args);
-    } else {
-        run_repl();
-    }
-}
-
-fn run_file(path: &str, args: &[String]) {
-    let path = Path::new(path);
-
-    // Check if it's a directory - if so, compile as a project
     if path.is_dir() {
-        let mode = if args.len() > 2 && args[2] == "--run" {
-            Mode::Run
-        } else {
-            Mode::Parse
-        };
-
-        if mode == Mode::Run {
-            println!(
-                "{} Compiling project in {}",
-                "Script:".cyan().bold(),
-                path.display()
-            );
-            compile_and_run_project(path);
-        } else {
-            println!(
-                "{} Project compilation without --run is not yet supported",
+        if matches!(args.emit, Some(Emit::Tokens | Emit::Ast)) {
+            eprintln!(
+                "{}: --emit tokens and --emit ast need a single file",
                 "Error".red().bold()
             );
-            println!("Use: {} {} --run", args[0], path.display());
             process::exit(1);
         }
+        println!(
+            "{} Compiling project in {}",
+            "Script:".cyan().bold(),
+            path.display()
+        );
+        compile_and_run_project(path, options);
         return;
     }
 
-    // Single file compilation
-    if path.extension().and_then(|s| s.to_str()) != Some("script") {
-        eprintln!("{}: File must have .script extension", "Error".red().bold());
-        process::exit(1);
+    let source = read_script_file(path);
+    let file_name = path.to_string_lossy();
+    match args.emit {
+        Some(Emit::Tokens) => tokenize_and_display(&source, Some(&file_name)),
+        Some(Emit::Ast) => parse_and_display(&source, Some(&file_name)),
+        _ => {}
     }
+    println!("{} Running {}", "Script:".cyan().bold(), path.display());
+    run_program(&source, Some(&file_name), options);
+}
 
-    match fs::read_to_string(path) {
-        Ok(source) => {
-            let mode = if args.len() > 2 {
-                match args[2].as_str() {
-                    "--tokens" => Mode::Tokens,
-                    "--parse" => Mode::Parse,
-                    "--test" => Mode::Test,
-                    "--debug" => Mode::Debug,
-                    _ => Mode::Run,
-                }
-            } else {
-                Mode::Run
-            };
-
-            match mode {
-                Mode::Tokens => {
-                    println!("{} Tokenizing {}", "Script:".cyan().bold(), path.display());
-                    tokenize_and_display(&source, Some(path.to_string_lossy().as_ref()));
-                }
-                Mode::Parse => {
-                    println!("{} Parsing {}", "Script:".cyan().bold(), path.display());
-                    parse_and_display(&source, Some(path.to_string_lossy().as_ref()));
-                }
-                Mode::Run => {
-                    println!("{} Running {}", "Script:".cyan().bold(), path.display());
-                    run_program(&source, Some(path.to_string_lossy().as_ref()));
-                }
-                Mode::Test => {
-                    println!("{} Testing {}", "Script:".cyan().bold(), path.display());
-                    run_tests(&source, Some(path.to_string_lossy().as_ref()));
-                }
-                Mode::Debug => {
-                    println!("{} Debugging {}", "Script:".cyan().bold(), path.display());
-                    run_debug_session(&source, Some(path.to_string_lossy().as_ref()));
-                }
-                Mode::Doc => {
-                    println!(
-                        "{} Mode::Doc is not supported for single files",
-                        "Error".red().bold()
-                    );
-                    println!("Use: {} doc <source dir> [output dir]", args[0]);
-                    process::exit(1);
-                }
-            }
-        }
-        Err(e) => {
+/// Compile a script file or project without running it, printing the
+/// requested intermediate form
+///
+/// For `--target wasm32` the WebAssembly module is generated too, so
+/// constructs that backend cannot compile are reported.
+fn check_path(path: &Path, emit: Option<Emit>, target: Target) {
+    let ir_module = if path.is_dir() {
+        if matches!(emit, Some(Emit::Tokens | Emit::Ast)) {
             eprintln!(
-                "{}: Could not read file '{}': {}",
-                "Error".red().bold(),
-                path.display(),
-                e
+                "{}: --emit tokens and --emit ast need a single file",
+                "Error".red().bold()
             );
             process::exit(1);
         }
+        compile_project(path)
+    } else {
+        let source = read_script_file(path);
+        let file_name = path.to_string_lossy();
+        match emit {
+            Some(Emit::Tokens) => tokenize_and_display(&source, Some(&file_name)),
+            Some(Emit::Ast) => parse_and_display(&source, Some(&file_name)),
+            _ => {}
+        }
+        match compile_to_ir(&source, Some(&file_name), None) {
+            Some(module) => module,
+            None => process::exit(1),
+        }
+    };
+
+    if emit == Some(Emit::Ir) {
+        println!("{}", ir_module);
+    }
+    if target == Target::Wasm32 {
+        let generated = time_passes::time(None, Phase::Codegen, || {
+            CodeGenerator::new().generate_wasm(&ir_module)
+        });
+        if let Err(error) = generated {
+            let mut reporter = ErrorReporter::new();
+            reporter.report(error);
+            reporter.print_all();
+            process::exit(1);
+        }
     }
+    println!("{} {}: no errors", "Checked".green().bold(), path.display());
 }
 
 fn run_repl() {
@@ -197,15 +797,14 @@ fn run_basic_repl() {
     println!("Type 'exit' to quit\n");
 
     let mut mode = Mode::Parse;
+    let mut evaluator = ReplEvaluator::new();
 
     loop {
         let prompt = match mode {
             Mode::Tokens => "tokens>",
             Mode::Parse => "script>",
             Mode::Run => "script>",
-            Mode::Test => "test>",
             Mode::Debug => "debug>",
-            Mode::Doc => "doc>",
         };
         print!("{} ", prompt.cyan().bold());
         io::stdout().flush().unwrap();
@@ -231,6 +830,12 @@ fn run_basic_repl() {
             continue;
         }
 
+        if line == ":run" {
+            mode = Mode::Run;
+            println!("Switched to run mode");
+            continue;
+        }
+
         if line == ":debug" {
             mode = Mode::Debug;
             println!("Switched to debug mode");
@@ -241,28 +846,14 @@ fn run_basic_repl() {
             match mode {
                 Mode::Tokens => tokenize_and_display(line, None),
                 Mode::Parse => parse_and_display(line, None),
-                Mode::Run => {
-                    println!(
-                        "{} Run mode is not supported in basic REPL",
-                        "Note:".yellow()
-                    );
-                    println!("Use enhanced REPL for full functionality");
-                }
-                Mode::Test => {
-                    println!(
-                        "{} Test mode is not supported in basic REPL",
-                        "Note:".yellow()
-                    );
-                }
+                Mode::Run => match evaluator.eval(line) {
+                    Ok(Some(value)) => println!("=> {}", inspect_value(&value)),
+                    Ok(None) => {}
+                    Err(error) => println!("{}: {}", "Error".red().bold(), error),
+                },
                 Mode::Debug => {
                     handle_debug_command(line);
                 }
-                Mode::Doc => {
-                    println!(
-                        "{} Doc mode is not supported in basic REPL",
-                        "Note:".yellow()
-                    );
-                }
             }
         }
     }
@@ -365,7 +956,7 @@ fn parse_and_display(source: &str, file_name: Option<&str>) {
         return;
     }
 
-    let mut parser = Parser::new(tokens);
+    let mut parser = ScriptParser::new(tokens);
     match parser.parse() {
         Ok(program) => {
             println!("\n{}", "AST:".green().bold());
@@ -395,10 +986,37 @@ fn parse_and_display(source: &str, file_name: Option<&str>) {
     }
 }
 
-fn run_program(source: &str, file_name: Option<&str>) {
-    // Lexing
-    let lexer = match Lexer::new(source) {
-        Ok(lexer) => lexer,
+fn run_program(source: &str, file_name: Option<&str>, options: RunOptions) {
+    let mut coverage_map = options.coverage.then(CoverageMap::new);
+    let Some(mut ir_module) = compile_to_ir(source, file_name, coverage_map.as_mut()) else {
+        return;
+    };
+
+    // Optimize, and drop functions the program can never call before
+    // generating code
+    let dce_stats = time_passes::time(None, Phase::Opt, || {
+        options
+            .opt_level
+            .create_pass_manager()
+            .optimize(&mut ir_module);
+        eliminate_dead_functions(&mut ir_module)
+    });
+    if options.print_dce_stats || options.verbose {
+        print!("{}", dce_stats);
+    }
+    if options.emit_ir {
+        println!("{}", ir_module);
+    }
+
+    // Generate code
+    let mut codegen = CodeGenerator::with_runtime_options(
+        options.overflow_mode,
+        options.gc_mode,
+        options.profile,
+    );
+    let generated = time_passes::time(None, Phase::Codegen, || codegen.generate(&ir_module));
+    let executable = match generated {
+        Ok(exec) => exec,
         Err(error) => {
             let mut reporter = ErrorReporter::new();
             reporter.report(error);
@@ -406,7 +1024,88 @@ fn run_program(source: &str, file_name: Option<&str>) {
             return;
         }
     };
-    let (tokens, lex_errors) = lexer.scan_tokens();
+
+    print_time_passes();
+
+    // Execute
+    if let Err(error) = gc::initialize_with_mode(options.gc_mode) {
+        eprintln!("{}: {}", "Warning".yellow().bold(), error);
+    }
+    if let Err(error) = shutdown::install_signal_handlers() {
+        eprintln!("{}: {}", "Warning".yellow().bold(), error);
+    }
+    if let Some(seed) = options.deterministic_seed {
+        deterministic::enable(seed);
+    }
+    if options.profile {
+        function_profiler::start();
+    }
+    if options.heap_profile.is_some() {
+        heap_profiler::start();
+    }
+    if options.check_leaks {
+        leak_check::start();
+    }
+    if let Some(map) = &coverage_map {
+        coverage::start(map);
+    }
+    let result = executable.execute();
+    shutdown::run_cleanups();
+    if options.check_leaks {
+        print_leak_report();
+    }
+    if options.profile {
+        write_profile(file_name.unwrap_or("script"));
+    }
+    if let Some(sites) = options.heap_profile {
+        print_heap_profile(sites);
+    }
+    if let Some(map) = &coverage_map {
+        write_coverage(file_name.unwrap_or("script"), map);
+    }
+    match result {
+        Ok(exit_code) => {
+            if exit_code != 0 {
+                process::exit(exit_code);
+            }
+        }
+        Err(error) => {
+            let mut reporter = ErrorReporter::new();
+            reporter.report(error);
+            reporter.print_all();
+            process::exit(1);
+        }
+    }
+}
+
+/// Run the front end and lower `source` to monomorphized IR, reporting any
+/// errors
+///
+/// With a coverage map, every block gets a coverage counter and the map
+/// receives the lines each counter covers.
+fn compile_to_ir(
+    source: &str,
+    file_name: Option<&str>,
+    coverage: Option<&mut CoverageMap>,
+) -> Option<IrModule> {
+    let module = file_name
+        .and_then(|name| Path::new(name).file_stem())
+        .map_or("<input>".into(), |stem| stem.to_string_lossy());
+    let module = Some(module.as_ref());
+
+    // Lexing
+    let lexed = time_passes::time(module, Phase::Lex, || {
+        Lexer::new(source).map(|lexer| lexer.scan_tokens())
+    });
+    let (tokens, lex_errors) = match lexed {
+        Ok(lexed) => lexed,
+        Err(error) => {
+            let mut reporter = ErrorReporter::new();
+            reporter.report(error);
+            reporter.print_all();
+            return None;
+        }
+    };
 
     if !lex_errors.is_empty() {
         let mut reporter = ErrorReporter::new();
@@ -414,28 +1113,31 @@ fn run_program(source: &str, file_name: Option<&str>) {
             reporter.report(error);
         }
         reporter.print_all();
-        return;
+        return None;
     }
 
     // Parsing
-    let mut parser = Parser::new(tokens);
-    let program = match parser.parse() {
+    let mut parser = ScriptParser::new(tokens);
+    let program = match time_passes::time(module, Phase::Parse, || parser.parse()) {
         Ok(prog) => prog,
         Err(error) => {
             let mut reporter = ErrorReporter::new();
             reporter.report(error);
             reporter.print_all();
-            return;
+            return None;
         }
     };
 
     // Perform semantic analysis
     let mut analyzer = SemanticAnalyzer::new();
-    if let Err(error) = analyzer.analyze_program(&program) {
+    let analyzed = time_passes::time(module, Phase::Semantic, || {
+        analyzer.analyze_program(&program)
+    });
+    if let Err(error) = analyzed {
         let mut reporter = ErrorReporter::new();
         reporter.report(error);
         reporter.print_all();
-        return;
+        return None;
     }
 
     // Check for semantic errors
@@ -463,13 +1165,22 @@ fn run_program(source: &str, file_name: Option<&str>) {
             reporter.report(err);
         }
         reporter.print_all();
-        return;
+        return None;
+    }
+
+    for warning in analyzer.warnings() {
+        print_semantic_warning(file_name.unwrap_or("<input>"), warning);
     }
 
     // Extract type information, generic instantiations, closure captures, and symbol table
-    let type_info = analyzer.extract_type_info();
-    let generic_instantiations = analyzer.generic_instantiations().to_vec();
-    let closure_captures = analyzer.extract_closure_captures();
+    let (type_info, generic_instantiations, closure_captures) =
+        time_passes::time(module, Phase::Inference, || {
+            (
+                analyzer.extract_type_info(),
+                analyzer.generic_instantiations().to_vec(),
+                analyzer.extract_closure_captures(),
+            )
+        });
     let symbol_table = analyzer.into_symbol_table();
 
     // Lower to IR
@@ -479,15 +1190,22 @@ fn run_program(source: &str, file_name: Option<&str>) {
         generic_instantiations.clone(),
         closure_captures,
     );
-    let mut ir_module = match lowerer.lower_program(&program) {
+    if coverage.is_some() {
+        lowerer.enable_coverage();
+    }
+    let lowered = time_passes::time(module, Phase::Lowering, || lowerer.lower_program(&program));
+    let mut ir_module = match lowered {
         Ok(module) => module,
         Err(error) => {
             let mut reporter = ErrorReporter::new();
             reporter.report(error);
             reporter.print_all();
-            return;
+            return None;
         }
     };
+    if let (Some(coverage), Some(map)) = (coverage, lowerer.coverage_map()) {
+        *coverage = map.clone();
+    }
 
     // Monomorphize generic functions if any exist
     if !generic_instantiations.is_empty() {
@@ -496,11 +1214,14 @@ fn run_program(source: &str, file_name: Option<&str>) {
         let mut mono_context = MonomorphizationContext::new();
         mono_context.initialize_from_semantic_analysis(&generic_instantiations, &type_info);
 
-        if let Err(error) = mono_context.monomorphize(&mut ir_module) {
+        let monomorphized = time_passes::time(module, Phase::Monomorphization, || {
+            mono_context.monomorphize(&mut ir_module)
+        });
+        if let Err(error) = monomorphized {
             let mut reporter = ErrorReporter::new();
             reporter.report(error);
             reporter.print_all();
-            return;
+            return None;
         }
 
         // Print monomorphization statistics if there were any generic functions
@@ -516,36 +1237,24 @@ fn run_program(source: &str, file_name: Option<&str>) {
         }
     }
 
-    // Generate code
-    let mut codegen = CodeGenerator::new();
-    let executable = match codegen.generate(&ir_module) {
-        Ok(exec) => exec,
-        Err(error) => {
-            let mut reporter = ErrorReporter::new();
-            reporter.report(error);
-            reporter.print_all();
-            return;
-        }
-    };
+    Some(ir_module)
+}
 
-    // Execute
-    match executable.execute() {
-        Ok(exit_code) => {
-            if exit_code != 0 {
-                process::exit(exit_code);
-            }
-        }
-        Err(error) => {
+/// Compile a project directory with the settings in its `script.toml`,
+/// reporting any errors
+fn compile_project(dir: &Path) -> IrModule {
+    let mut context = CompilationContext::new();
+    if let Some(root) = script::manuscript::find_package_root(Some(dir)) {
+        let applied = PackageManifest::from_file(root.join("script.toml"))
+            .map_err(script::Error::from)
+            .and_then(|manifest| context.apply_manifest(&manifest));
+        if let Err(error) = applied {
             let mut reporter = ErrorReporter::new();
             reporter.report(error);
             reporter.print_all();
             process::exit(1);
         }
     }
-}
-
-fn compile_and_run_project(dir: &Path) {
-    let mut context = CompilationContext::new();
 
     let ir_module = match context.compile_directory(dir) {
         Ok(module) => module,
@@ -557,9 +1266,47 @@ fn compile_and_run_project(dir: &Path) {
         }
     };
 
+    for (path, warning) in context.warnings() {
+        print_semantic_warning(&path.display().to_string(), warning);
+    }
+
+    ir_module
+}
+
+fn compile_and_run_project(dir: &Path, options: RunOptions) {
+    if options.coverage {
+        eprintln!(
+            "{}: --coverage is only supported when running a single file",
+            "Warning".yellow().bold()
+        );
+    }
+
+    let mut ir_module = compile_project(dir);
+
+    // Optimize, and drop functions the program can never call before
+    // generating code
+    let dce_stats = time_passes::time(None, Phase::Opt, || {
+        options
+            .opt_level
+            .create_pass_manager()
+            .optimize(&mut ir_module);
+        eliminate_dead_functions(&mut ir_module)
+    });
+    if options.print_dce_stats || options.verbose {
+        print!("{}", dce_stats);
+    }
+    if options.emit_ir {
+        println!("{}", ir_module);
+    }
+
     // Generate code
-    let mut codegen = CodeGenerator::new();
-    let executable = match codegen.generate(&ir_module) {
+    let mut codegen = CodeGenerator::with_runtime_options(
+        options.overflow_mode,
+        options.gc_mode,
+        options.profile,
+    );
+    let generated = time_passes::time(None, Phase::Codegen, || codegen.generate(&ir_module));
+    let executable = match generated {
         Ok(exec) => exec,
         Err(error) => {
             let mut reporter = ErrorReporter::new();
@@ -569,8 +1316,43 @@ fn compile_and_run_project(dir: &Path) {
         }
     };
 
+    print_time_passes();
+
     // Execute
-    match executable.execute() {
+    if let Err(error) = gc::initialize_with_mode(options.gc_mode) {
+        eprintln!("{}: {}", "Warning".yellow().bold(), error);
+    }
+    if let Err(error) = shutdown::install_signal_handlers() {
+        eprintln!("{}: {}", "Warning".yellow().bold(), error);
+    }
+    if let Some(seed) = options.deterministic_seed {
+        deterministic::enable(seed);
+    }
+    if options.profile {
+        function_profiler::start();
+    }
+    if options.heap_profile.is_some() {
+        heap_profiler::start();
+    }
+    if options.check_leaks {
+        leak_check::start();
+    }
+    let result = executable.execute();
+    shutdown::run_cleanups();
+    if options.check_leaks {
+        print_leak_report();
+    }
+    if options.profile {
+        let name = dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("script");
+        write_profile(name);
+    }
+    if let Some(sites) = options.heap_profile {
+        print_heap_profile(sites);
+    }
+    match result {
         Ok(exit_code) => {
             if exit_code != 0 {
                 process::exit(exit_code);
@@ -585,7 +1367,86 @@ fn compile_and_run_project(dir: &Path) {
     }
 }
 
-fn run_tests(source: &str, file_name: Option<&str>) {
+/// Print the function profile and write its call stacks to
+/// `<program>.folded` in the working directory, for flamegraph tools
+fn write_profile(program: &str) {
+    let report = function_profiler::stop();
+    println!("\n{}", report);
+
+    let stem = Path::new(program)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("script");
+    let path = format!("{}.folded", stem);
+    let written = fs::File::create(&path).and_then(|mut file| report.write_collapsed(&mut file));
+    match written {
+        Ok(()) => println!("{} Wrote call stacks to {}", "Profile:".cyan().bold(), path),
+        Err(e) => eprintln!("{}: Could not write {}: {}", "Error".red().bold(), path, e),
+    }
+}
+
+/// Print the `sites` allocation sites that allocated the most bytes
+fn print_heap_profile(sites: usize) {
+    let report = heap_profiler::stop();
+    print!("\n{}", report.top(sites));
+    if report.sites.len() > sites {
+        println!("... {} more sites", report.sites.len() - sites);
+    }
+}
+
+/// Print the files, sockets and tasks the program left open, with where
+/// each was created
+fn print_leak_report() {
+    let report = leak_check::stop();
+    if report.is_clean() {
+        eprint!("\n{}", report);
+    } else {
+        eprint!("\n{}: {}", "Warning".yellow().bold(), report);
+    }
+}
+
+/// Add the program's line counts to `<program>.coverage` in the working
+/// directory
+fn write_coverage(program: &str, map: &CoverageMap) {
+    let mut report = CoverageReport::new();
+    report.add_file(program, &coverage::stop(map));
+
+    let stem = Path::new(program)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("script");
+    let path = format!("{}.coverage", stem);
+    match report.save(Path::new(&path)) {
+        Ok(()) => println!(
+            "{} Wrote line counts to {}",
+            "Coverage:".cyan().bold(),
+            path
+        ),
+        Err(e) => eprintln!("{}: Could not write {}: {}", "Error".red().bold(), path, e),
+    }
+}
+
+fn print_semantic_warning(file_name: &str, warning: &script::semantic::SemanticWarning) {
+    if error_format() == ErrorFormat::Json {
+        let diagnostic = JsonDiagnostic::new(
+            warning.kind.code(),
+            script::error::Severity::Warning,
+            warning.kind.to_string(),
+            Some(file_name),
+            Some(warning.span),
+        );
+        eprintln!("{}", diagnostic.to_json_line());
+        return;
+    }
+    eprintln!(
+        "{}: {}:{}",
+        format!("Warning[{}]", warning.kind.code()).yellow(),
+        file_name,
+        warning
+    );
+}
+
+fn run_tests(source: &str, file_name: Option<&str>, update_expect: bool) {
     // Lexing
     let lexer = match Lexer::new(source) {
         Ok(lexer) => lexer,
@@ -622,7 +1483,7 @@ fn run_tests(source: &str, file_name: Option<&str>) {
     }
 
     // Parsing
-    let mut parser = Parser::new(tokens);
+    let mut parser = ScriptParser::new(tokens);
     let program = match parser.parse() {
         Ok(prog) => prog,
         Err(mut error) => {
@@ -648,227 +1509,1076 @@ fn run_tests(source: &str, file_name: Option<&str>) {
     };
 
     // Run tests using the testing framework
-    let mut framework = TestingFramework::new();
+    let options = TestRunOptions {
+        update_expect,
+        ..TestRunOptions::default()
+    };
+    let mut framework = TestingFramework::new().with_run_options(options);
 
     match framework.run_tests(&program) {
         Ok(summary) => {
             println!("\n{summary}");
 
-            if !summary.all_passed() {
+            if update_expect {
+                update_expectations(source, file_name, framework.expect_updates());
+            }
+
+            if !summary.all_passed() {
+                process::exit(1);
+            }
+        }
+        Err(error) => {
+            let mut reporter = ErrorReporter::new();
+            reporter.report(error);
+            reporter.print_all();
+            process::exit(1);
+        }
+    }
+}
+
+/// Rewrite the `expect` literals that didn't match in the tested file
+fn update_expectations(source: &str, file_name: Option<&str>, updates: &[ExpectMismatch]) {
+    if updates.is_empty() {
+        println!(
+            "{} All expectations are up to date",
+            "Expect:".cyan().bold()
+        );
+        return;
+    }
+    let Some(file_name) = file_name else {
+        return;
+    };
+
+    let result = apply_expect_updates(source, updates).and_then(|updated| {
+        let hunks = diff_lines(source, &updated, DEFAULT_CONTEXT);
+        print!("{}", unified_diff(&hunks, file_name, file_name, true));
+        fs::write(file_name, updated).map_err(Into::into)
+    });
+    match result {
+        Ok(()) => println!(
+            "{} Updated {} expectation(s) in {}",
+            "Expect:".cyan().bold(),
+            updates
+                .iter()
+                .map(|update| update.span)
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            file_name
+        ),
+        Err(error) => {
+            let mut reporter = ErrorReporter::new();
+            reporter.report(error);
+            reporter.print_all();
+            process::exit(1);
+        }
+    }
+}
+
+fn run_debug_session(source: &str, file_name: Option<&str>, restore: bool) {
+    // Lexing
+    let lexer = match Lexer::new(source) {
+        Ok(lexer) => lexer,
+        Err(error) => {
+            let mut reporter = ErrorReporter::new();
+            reporter.report(error);
+            reporter.print_all();
+            return;
+        }
+    };
+    let (tokens, lex_errors) = lexer.scan_tokens();
+
+    if !lex_errors.is_empty() {
+        let mut reporter = ErrorReporter::new();
+        for mut error in lex_errors {
+            if let Some(name) = file_name {
+                error = error.with_file_name(name);
+            }
+
+            // Add source line context
+            if let Some(loc) = error.location {
+                if loc.line > 0 {
+                    let lines: Vec<&str> = source.lines().collect();
+                    if loc.line <= lines.len() {
+                        error = error.with_source_line(lines[loc.line - 1]);
+                    }
+                }
+            }
+
+            reporter.report(error);
+        }
+        reporter.print_all();
+        return;
+    }
+
+    // Parsing
+    let mut parser = ScriptParser::new(tokens);
+    let program = match parser.parse() {
+        Ok(prog) => prog,
+        Err(mut error) => {
+            if let Some(name) = file_name {
+                error = error.with_file_name(name);
+            }
+
+            // Add source line context
+            if let Some(loc) = error.location {
+                if loc.line > 0 {
+                    let lines: Vec<&str> = source.lines().collect();
+                    if loc.line <= lines.len() {
+                        error = error.with_source_line(lines[loc.line - 1]);
+                    }
+                }
+            }
+
+            let mut reporter = ErrorReporter::new();
+            reporter.report(error);
+            reporter.print_all();
+            return;
+        }
+    };
+
+    // Initialize debugger
+    if let Err(e) = initialize_debugger_with(restore) {
+        eprintln!(
+            "{}: Failed to initialize debugger: {}",
+            "Error".red().bold(),
+            e
+        );
+        process::exit(1);
+    }
+    let debugger = match get_debugger() {
+        Ok(debugger) => debugger,
+        Err(e) => {
+            eprintln!("{}: {}", "Debug Error".red().bold(), e);
+            process::exit(1);
+        }
+    };
+
+    // Start debugger session
+    debugger.load_program(
+        program,
+        source.to_string(),
+        file_name.map(|s| s.to_string()),
+    );
+
+    match debugger.start_session() {
+        Ok(()) => {
+            println!("{}", "Debug session ended.".green());
+        }
+        Err(error) => {
+            eprintln!("{}: {}", "Debug Error".red().bold(), error);
+            process::exit(1);
+        }
+    }
+
+    // Shutdown debugger
+    if let Err(e) = shutdown_debugger() {
+        eprintln!("{}: Failed to shutdown debugger: {}", "Warning".yellow(), e);
+    }
+}
+
+fn run_doc_command(source_dir: &Path, output_dir: &Path) {
+    if !source_dir.exists() {
+        eprintln!(
+            "{}: Source directory '{}' does not exist",
+            "Error".red().bold(),
+            source_dir.display()
+        );
+        process::exit(1);
+    }
+
+    println!("{} Generating documentation", "Script:".cyan().bold());
+    println!("  Source: {}", source_dir.display());
+    println!("  Output: {}", output_dir.display());
+
+    // Create documentation generator
+    let mut doc_generator = DocGenerator::new();
+
+    // Process all .script files in the directory
+    if let Err(e) = process_directory(&mut doc_generator, source_dir, "") {
+        eprintln!("{}: {}", "Error".red().bold(), e);
+        process::exit(1);
+    }
+
+    // Generate HTML documentation
+    let html_generator = HtmlGenerator::new(output_dir);
+    match html_generator.generate(doc_generator.database()) {
+        Ok(_) => {
+            println!(
+                "\n{} Documentation generated successfully!",
+                "Success:".green().bold()
+            );
+            println!(
+                "Open {}/index.html to view the documentation",
+                output_dir.display()
+            );
+        }
+        Err(e) => {
+            eprintln!("{}: Failed to generate HTML: {}", "Error".red().bold(), e);
+            process::exit(1);
+        }
+    }
+}
+
+fn process_directory(
+    doc_generator: &mut DocGenerator,
+    dir: &Path,
+    module_prefix: &str,
+) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        if path.is_dir() {
+            // Recursively process subdirectories
+            let dir_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+            let new_prefix = if module_prefix.is_empty() {
+                dir_name.to_string()
+            } else {
+                format!("{}::{}", module_prefix, dir_name)
+            };
+
+            process_directory(doc_generator, &path, &new_prefix)?;
+        } else if path.extension().and_then(|s| s.to_str()) == Some("script") {
+            // Process .script file
+            let file_name = path.file_stem().and_then(|n| n.to_str()).unwrap_or("");
+
+            let module_name = if module_prefix.is_empty() {
+                file_name.to_string()
+            } else {
+                format!("{}::{}", module_prefix, file_name)
+            };
+
+            println!("  Processing: {module_name}");
+
+            match fs::read_to_string(&path) {
+                Ok(source) => {
+                    if let Err(e) = doc_generator.generate_from_source(&source, &module_name) {
+                        eprintln!("    {}: {}", "Warning".yellow(), e);
+                    }
+                }
+                Err(e) => {
+                    eprintln!(
+                        "    {}: Could not read {}: {}",
+                        "Warning".yellow(),
+                        path.display(),
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Load the symbol index for a project and bring it up to date
+fn load_project_index(root: &Path) -> SymbolIndex {
+    let mut index = match SymbolIndex::load(root) {
+        Ok(index) => index,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red().bold(), e);
+            process::exit(1);
+        }
+    };
+
+    // Only files changed since the last run are re-parsed
+    match index.refresh() {
+        Ok(report) => {
+            for (path, reason) in &report.failed {
+                eprintln!(
+                    "{}: Skipped {}: {}",
+                    "Warning".yellow(),
+                    path.display(),
+                    reason
+                );
+            }
+        }
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red().bold(), e);
+            process::exit(1);
+        }
+    }
+
+    if let Err(e) = index.save() {
+        eprintln!("{}: Could not save symbol index: {}", "Warning".yellow(), e);
+    }
+
+    index
+}
+
+/// Format script files in place, or with `check` list those that would
+/// change and fail if there are any
+///
+/// Directories are searched for `.script` files, skipping hidden
+/// directories and `target`. Each file uses the nearest `script-fmt.toml`.
+fn run_fmt_command(paths: &[PathBuf], check: bool) {
+    let files = script_files(paths);
+
+    let mut failed = false;
+    let mut unformatted = 0;
+    for file in &files {
+        let source = match fs::read_to_string(file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!(
+                    "{}: Could not read {}: {}",
+                    "Error".red().bold(),
+                    file.display(),
+                    e
+                );
+                failed = true;
+                continue;
+            }
+        };
+        let config = file
+            .parent()
+            .map(FormatterConfig::discover)
+            .transpose()
+            .map(|config| config.flatten().unwrap_or_default());
+        let formatted = config.and_then(|config| format_source(&source, &config));
+
+        match formatted {
+            Ok(formatted) if formatted == source => {}
+            Ok(_) if check => {
+                println!("{} {}", "Unformatted".yellow().bold(), file.display());
+                unformatted += 1;
+            }
+            Ok(formatted) => match fs::write(file, formatted) {
+                Ok(()) => println!("{} {}", "Formatted".green().bold(), file.display()),
+                Err(e) => {
+                    eprintln!(
+                        "{}: Could not write {}: {}",
+                        "Error".red().bold(),
+                        file.display(),
+                        e
+                    );
+                    failed = true;
+                }
+            },
+            Err(error) => {
+                let mut reporter = ErrorReporter::new();
+                reporter.report(error.with_file_name(file.display().to_string()));
+                reporter.print_all();
+                failed = true;
+            }
+        }
+    }
+
+    if unformatted > 0 {
+        println!(
+            "{} file(s) need formatting; run `script fmt` to fix them",
+            unformatted
+        );
+    }
+    if failed || unformatted > 0 {
+        process::exit(1);
+    }
+}
+
+/// The files named in `paths` and the script files in the directories it
+/// names, skipping hidden and `target` directories; the current directory
+/// when `paths` is empty
+fn script_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let paths = if paths.is_empty() {
+        vec![env::current_dir().unwrap_or_else(|_| Path::new(".").to_path_buf())]
+    } else {
+        paths.to_vec()
+    };
+
+    let mut files = Vec::new();
+    for path in &paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let entries = walkdir::WalkDir::new(path)
+            .into_iter()
+            .filter_entry(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                entry.depth() == 0 || !(name.starts_with('.') || name == "target")
+            })
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry.path().extension().and_then(|ext| ext.to_str()) == Some("script")
+            });
+        files.extend(entries.map(|entry| entry.into_path()));
+    }
+    files.sort();
+    files
+}
+
+/// Lint script files, optionally applying the fixes
+fn run_lint_command(paths: &[PathBuf], fix: bool) {
+    let mut failed = false;
+    let mut warnings = 0;
+    let mut errors = 0;
+    for file in script_files(paths) {
+        let source = match fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!(
+                    "{}: Could not read {}: {}",
+                    "Error".red().bold(),
+                    file.display(),
+                    e
+                );
+                failed = true;
+                continue;
+            }
+        };
+        let linted = LintConfig::discover(file.parent().unwrap_or(Path::new(".")))
+            .and_then(|config| Ok((lint_source(&source, &config)?, config)));
+        let (mut diagnostics, config) = match linted {
+            Ok(linted) => linted,
+            Err(error) => {
+                let mut reporter = ErrorReporter::new();
+                reporter.report(error.with_file_name(file.display().to_string()));
+                reporter.print_all();
+                failed = true;
+                continue;
+            }
+        };
+
+        if fix && diagnostics.iter().any(|d| !d.fixes.is_empty()) {
+            let fixed = apply_fixes(&source, &diagnostics);
+            if let Err(e) = fs::write(&file, &fixed) {
+                eprintln!(
+                    "{}: Could not write {}: {}",
+                    "Error".red().bold(),
+                    file.display(),
+                    e
+                );
+                failed = true;
+                continue;
+            }
+            println!("{} {}", "Fixed".green().bold(), file.display());
+            // Every fix leaves the file parsing
+            diagnostics = lint_source(&fixed, &config).unwrap_or_default();
+        }
+
+        for diagnostic in diagnostics {
+            if error_format() == ErrorFormat::Json {
+                if diagnostic.level == LintLevel::Deny {
+                    errors += 1;
+                }
+                let file = file.display().to_string();
+                eprintln!("{}", diagnostic.to_json(Some(&file)).to_json_line());
+                continue;
+            }
+            let label = format!("{}[{}]", diagnostic.level, diagnostic.lint);
+            let label = if diagnostic.level == LintLevel::Deny {
+                errors += 1;
+                label.red().bold()
+            } else {
+                warnings += 1;
+                label.yellow().bold()
+            };
+            println!(
+                "{}: {} at {}:{}",
+                label,
+                diagnostic.message,
+                file.display(),
+                diagnostic.span.start
+            );
+            if let Some(Fix::Edit { title, .. }) = diagnostic.fixes.first() {
+                println!("  {} {}", "help:".cyan().bold(), title);
+            }
+        }
+    }
+
+    if warnings + errors > 0 && error_format() == ErrorFormat::Human {
+        println!(
+            "{} warning(s) and {} error(s); `script lint --fix` applies the suggested fixes",
+            warnings, errors
+        );
+    }
+    if failed || errors > 0 {
+        process::exit(1);
+    }
+}
+
+/// Run project-wide static analyses
+fn run_analyze_command(args: &AnalyzeArgs) {
+    let mut options = CallGraphOptions::default();
+    options.entry_names.extend(args.entries.iter().cloned());
+    options.include_exports = args.include_exports;
+    options.include_tests = !args.no_tests;
+
+    let mut thresholds = MetricThresholds::default();
+    for (limit, threshold) in [
+        (args.max_complexity, &mut thresholds.max_complexity),
+        (args.max_length, &mut thresholds.max_length),
+        (args.max_nesting, &mut thresholds.max_nesting),
+        (args.max_params, &mut thresholds.max_params),
+    ] {
+        if let Some(limit) = limit {
+            *threshold = limit;
+        }
+    }
+
+    let root = args
+        .dir
+        .clone()
+        .unwrap_or_else(|| env::current_dir().unwrap_or_else(|_| Path::new(".").to_path_buf()));
+    let format = args.format.as_deref();
+
+    if args.metrics {
+        run_metrics_analysis(&root, format.unwrap_or("text"), &thresholds);
+    } else {
+        run_callgraph_analysis(
+            &root,
+            format.unwrap_or("dot"),
+            args.output.as_deref(),
+            &options,
+        );
+    }
+}
+
+fn run_callgraph_analysis(
+    root: &Path,
+    format: &str,
+    output: Option<&str>,
+    options: &CallGraphOptions,
+) {
+    let index = load_project_index(root);
+    let graph = CallGraph::build(&index, options);
+
+    let rendered = match format {
+        "dot" => graph.to_dot(),
+        "json" => graph.to_json(),
+        other => {
+            eprintln!(
+                "{}: Unknown call graph format '{}' (expected dot or json)",
+                "Error".red().bold(),
+                other
+            );
+            process::exit(1);
+        }
+    };
+
+    match output {
+        Some(file) => {
+            if let Err(e) = fs::write(file, rendered) {
+                eprintln!(
+                    "{}: Could not write '{}': {}",
+                    "Error".red().bold(),
+                    file,
+                    e
+                );
+                process::exit(1);
+            }
+            println!("{} Call graph written to {}", "Script:".cyan().bold(), file);
+        }
+        None => print!("{}", rendered),
+    }
+
+    // The report goes to stderr when the graph itself is on stdout so the
+    // output can be piped straight into graphviz
+    let unreachable = graph.unreachable();
+    let report = |line: String| {
+        if output.is_some() {
+            println!("{}", line);
+        } else {
+            eprintln!("{}", line);
+        }
+    };
+    if unreachable.is_empty() {
+        report(format!(
+            "{} All {} functions are reachable",
+            "Analysis:".green().bold(),
+            graph.nodes.len()
+        ));
+    } else {
+        report(format!(
+            "{} {} of {} functions are unreachable from entry points:",
+            "Analysis:".yellow().bold(),
+            unreachable.len(),
+            graph.nodes.len()
+        ));
+        for node in unreachable {
+            report(format!(
+                "  {}:{} {}",
+                node.file.display(),
+                node.line,
+                node.name
+            ));
+        }
+    }
+}
+
+/// Report per-function metrics, exiting with an error on threshold violations
+fn run_metrics_analysis(root: &Path, format: &str, thresholds: &MetricThresholds) {
+    let report = match MetricsReport::collect(root) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red().bold(), e);
+            process::exit(1);
+        }
+    };
+    for (path, reason) in &report.failed {
+        eprintln!(
+            "{}: Skipped {}: {}",
+            "Warning".yellow(),
+            path.display(),
+            reason
+        );
+    }
+
+    match format {
+        "json" => println!("{}", report.to_json()),
+        "text" => {
+            println!(
+                "{:<40} {:>10} {:>6} {:>7} {:>6}",
+                "Function", "Complexity", "Length", "Nesting", "Params"
+            );
+            for function in &report.functions {
+                println!(
+                    "{:<40} {:>10} {:>6} {:>7} {:>6}",
+                    format!(
+                        "{}:{} {}",
+                        function.file.display(),
+                        function.line,
+                        function.name
+                    ),
+                    function.complexity,
+                    function.length,
+                    function.nesting,
+                    function.params
+                );
+            }
+        }
+        other => {
+            eprintln!(
+                "{}: Unknown metrics format '{}' (expected text or json)",
+                "Error".red().bold(),
+                other
+            );
+            process::exit(1);
+        }
+    }
+
+    let violations = report.violations(thresholds);
+    if violations.is_empty() {
+        eprintln!(
+            "{} All {} functions are within thresholds",
+            "Analysis:".green().bold(),
+            report.functions.len()
+        );
+        return;
+    }
+
+    eprintln!(
+        "{} {} threshold violations:",
+        "Analysis:".red().bold(),
+        violations.len()
+    );
+    for violation in &violations {
+        eprintln!("  {}", violation);
+    }
+    process::exit(1);
+}
+
+/// Look up definitions by name using the persistent symbol index
+/// Compile a script ahead of time for another target
+/// Compile a script file to the target's output format, with the profile
+/// its project's `script.toml` sets for release or dev builds
+fn run_build_command(
+    path: &Path,
+    output: Option<&Path>,
+    release: bool,
+    opt_level: Option<OptimizationLevel>,
+    hermetic: bool,
+    remote_cache: Option<RemoteCacheConfig>,
+    target: Target,
+) {
+    // The JIT is the only native backend, so wasm32 is currently the only target
+    if target != Target::Wasm32 {
+        eprintln!(
+            "{}: Native executables are not supported yet; build with --target wasm32",
+            "Error".red().bold()
+        );
+        process::exit(1);
+    }
+
+    let source = read_script_file(path);
+    let file = path.to_string_lossy();
+    let mut profile = build_profile(path, release);
+    if let Some(opt_level) = opt_level {
+        profile.opt_level = opt_level;
+    }
+    let output = output
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| path.with_extension("wasm"));
+
+    // A hit in the remote cache skips compiling; cache failures only warn
+    let cache = remote_cache.and_then(|config| match RemoteCache::new(config) {
+        Ok(cache) => Some((cache, build_cache_key(path, &profile, target))),
+        Err(error) => {
+            eprintln!(
+                "{}: Remote cache disabled: {}",
+                "Warning".yellow().bold(),
+                error
+            );
+            None
+        }
+    });
+    if let Some((cache, key)) = &cache {
+        match cache.get(key) {
+            Ok(Some(bytes)) => {
+                write_build_output(&output, &bytes);
+                println!(
+                    "{} {} with profile {} from the remote cache ({} bytes)",
+                    "Built".green().bold(),
+                    output.display(),
+                    profile,
+                    bytes.len()
+                );
+                return;
+            }
+            Ok(None) => {}
+            Err(error) => eprintln!(
+                "{}: Remote cache lookup failed: {}",
+                "Warning".yellow().bold(),
+                error
+            ),
+        }
+    }
+
+    if hermetic {
+        match DeclaredInputs::discover(path) {
+            Ok(inputs) => hermetic::start(inputs),
+            Err(error) => {
+                let mut reporter = ErrorReporter::new();
+                reporter.report(error);
+                reporter.print_all();
                 process::exit(1);
             }
         }
-        Err(error) => {
+    }
+    let compiled = compile_to_ir(&source, Some(&file), None);
+    if hermetic {
+        if let Err(error) = hermetic::stop().into_result() {
             let mut reporter = ErrorReporter::new();
             reporter.report(error);
             reporter.print_all();
             process::exit(1);
         }
     }
-}
+    let Some(mut ir_module) = compiled else {
+        process::exit(1);
+    };
+    time_passes::time(None, Phase::Opt, || profile.optimize(&mut ir_module));
 
-fn run_debug_session(source: &str, file_name: Option<&str>) {
-    // Lexing
-    let lexer = match Lexer::new(source) {
-        Ok(lexer) => lexer,
+    let mut codegen = CodeGenerator::with_debug(profile.debug_flags());
+    let generated = time_passes::time(None, Phase::Codegen, || codegen.generate_wasm(&ir_module));
+    let bytes = match generated {
+        Ok(bytes) => bytes,
         Err(error) => {
             let mut reporter = ErrorReporter::new();
             reporter.report(error);
             reporter.print_all();
-            return;
+            process::exit(1);
         }
     };
-    let (tokens, lex_errors) = lexer.scan_tokens();
-
-    if !lex_errors.is_empty() {
-        let mut reporter = ErrorReporter::new();
-        for mut error in lex_errors {
-            if let Some(name) = file_name {
-                error = error.with_file_name(name);
-            }
-
-            // Add source line context
-            if let Some(loc) = error.location {
-                if loc.line > 0 {
-                    let lines: Vec<&str> = source.lines().collect();
-                    if loc.line <= lines.len() {
-                        error = error.with_source_line(lines[loc.line - 1]);
-                    }
-                }
-            }
 
-            reporter.report(error);
+    write_build_output(&output, &bytes);
+    if let Some((cache, key)) = &cache {
+        if let Err(error) = cache.put(key, &bytes) {
+            eprintln!(
+                "{}: Remote cache upload failed: {}",
+                "Warning".yellow().bold(),
+                error
+            );
         }
-        reporter.print_all();
-        return;
     }
 
-    // Parsing
-    let mut parser = Parser::new(tokens);
-    let program = match parser.parse() {
-        Ok(prog) => prog,
-        Err(mut error) => {
-            if let Some(name) = file_name {
-                error = error.with_file_name(name);
-            }
+    println!(
+        "{} {} with profile {} ({} functions, {} bytes)",
+        "Built".green().bold(),
+        output.display(),
+        profile,
+        codegen.stats().functions_generated,
+        bytes.len()
+    );
+}
 
-            // Add source line context
-            if let Some(loc) = error.location {
-                if loc.line > 0 {
-                    let lines: Vec<&str> = source.lines().collect();
-                    if loc.line <= lines.len() {
-                        error = error.with_source_line(lines[loc.line - 1]);
-                    }
-                }
-            }
+fn write_build_output(output: &Path, bytes: &[u8]) {
+    if let Err(error) = fs::write(output, bytes) {
+        eprintln!(
+            "{}: Failed to write {}: {}",
+            "Error".red().bold(),
+            output.display(),
+            error
+        );
+        process::exit(1);
+    }
+}
 
-            let mut reporter = ErrorReporter::new();
-            reporter.report(error);
-            reporter.print_all();
-            return;
-        }
+/// Remote cache key of building `path`: the sources of its package, or the
+/// scripts next to it outside a package, and every flag of the build
+fn build_cache_key(path: &Path, profile: &BuildProfile, target: Target) -> ArtifactKey {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+    let root = script::manuscript::find_package_root(Some(&dir));
+
+    let mut files: Vec<PathBuf> = match &root {
+        Some(root) => walkdir::WalkDir::new(root.join("src"))
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.into_path())
+            .filter(|file| file.extension().and_then(|e| e.to_str()) == Some("script"))
+            .chain([root.join("script.toml"), root.join("script.lock")])
+            .collect(),
+        None => fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|file| file.extension().and_then(|e| e.to_str()) == Some("script"))
+            .collect(),
     };
-
-    // Start debugger session
-    let mut debugger = Debugger::new();
-    debugger.load_program(
-        program,
-        source.to_string(),
-        file_name.map(|s| s.to_string()),
-    );
-
-    match debugger.start_session() {
-        Ok(()) => {
-            println!("{}", "Debug session ended.".green());
+    files.push(path.clone());
+    files.sort();
+    files.dedup();
+
+    // Names are relative, so checkouts in different places share artifacts
+    let base = root.unwrap_or(dir);
+    let relative = |file: &Path| {
+        file.strip_prefix(&base)
+            .unwrap_or(file)
+            .display()
+            .to_string()
+    };
+    let mut key = ArtifactKey::builder();
+    for file in &files {
+        if let Ok(contents) = fs::read(file) {
+            key = key.source(&relative(file), &contents);
         }
+    }
+    key.flag("entry", &relative(&path))
+        .flag("target", &format!("{:?}", target))
+        .flag("profile", &format!("{:?}", profile))
+        .finish()
+}
+
+/// The release or dev profile of the project `path` belongs to, or the
+/// built-in one outside a project
+fn build_profile(path: &Path, release: bool) -> BuildProfile {
+    let dir = fs::canonicalize(path)
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf));
+    let Some(root) = dir.and_then(|dir| script::manuscript::find_package_root(Some(&dir))) else {
+        return BuildProfile::for_mode(release);
+    };
+    match PackageManifest::from_file(root.join("script.toml")) {
+        Ok(manifest) => BuildProfile::from_manifest(&manifest, release),
         Err(error) => {
-            eprintln!("{}: {}", "Debug Error".red().bold(), error);
+            let mut reporter = ErrorReporter::new();
+            reporter.report(script::Error::from(error));
+            reporter.print_all();
             process::exit(1);
         }
     }
 }
 
-fn run_doc_command(args: &[String]) {
-    if args.len() < 3 {
-        eprintln!(
-            "{}: doc command requires source directory",
-            "Error".red().bold()
-        );
-        eprintln!("Usage: {} doc <source dir> [output dir]", args[0]);
+fn run_grep_def_command(name: &str, dir: Option<PathBuf>) {
+    let root =
+        dir.unwrap_or_else(|| env::current_dir().unwrap_or_else(|_| Path::new(".").to_path_buf()));
+
+    let index = load_project_index(&root);
+
+    let definitions = index.find_definitions(name);
+    if definitions.is_empty() {
+        eprintln!("No definitions found for '{}'", name);
         process::exit(1);
     }
 
-    let source_dir = Path::new(&args[2]);
-    let output_dir = if args.len() >= 4 {
-        Path::new(&args[3])
-    } else {
-        Path::new("./docs")
-    };
+    for (path, symbol) in definitions {
+        println!(
+            "{}:{} {:8} {}",
+            path.display().to_string().cyan(),
+            symbol.span,
+            symbol.kind.to_string().yellow(),
+            symbol.signature
+        );
+    }
+}
 
-    if !source_dir.exists() {
+/// Print the explanation of an error code
+fn run_explain_command(code: &str) {
+    let Some(entry) = script::error::explain(code) else {
         eprintln!(
-            "{}: Source directory '{}' does not exist",
+            "{}: '{}' is not an error code; codes look like E0425",
             "Error".red().bold(),
-            source_dir.display()
+            code
         );
         process::exit(1);
-    }
-
-    println!("{} Generating documentation", "Script:".cyan().bold());
-    println!("  Source: {}", source_dir.display());
-    println!("  Output: {}", output_dir.display());
+    };
 
-    // Create documentation generator
-    let mut doc_generator = DocGenerator::new();
+    println!("{}: {}\n", entry.code.bold(), entry.title);
+    print!("{}", entry.explanation);
+}
 
-    // Process all .script files in the directory
-    if let Err(e) = process_directory(&mut doc_generator, source_dir, "") {
-        eprintln!("{}: {}", "Error".red().bold(), e);
+/// Create a minimal project in the current directory or the one given
+fn run_init_command(template: ProjectTemplate, dir: Option<PathBuf>) {
+    let dir =
+        dir.unwrap_or_else(|| env::current_dir().unwrap_or_else(|_| Path::new(".").to_path_buf()));
+    // Named after the directory, resolving `.` to its real name
+    let name = fs::canonicalize(&dir)
+        .unwrap_or_else(|_| dir.clone())
+        .file_name()
+        .and_then(|name| name.to_str())
+        .map(String::from);
+    let Some(name) = name else {
+        eprintln!(
+            "{}: Cannot name a project after '{}'",
+            "Error".red().bold(),
+            dir.display()
+        );
         process::exit(1);
-    }
+    };
 
-    // Generate HTML documentation
-    let html_generator = HtmlGenerator::new(output_dir);
-    match html_generator.generate(doc_generator.database()) {
-        Ok(_) => {
-            println!(
-                "\n{} Documentation generated successfully!",
-                "Success:".green().bold()
-            );
+    match scaffold_project(&dir, &name, template) {
+        Ok(created) => {
+            for path in &created {
+                println!("{} {}", "Created".green().bold(), path.display());
+            }
             println!(
-                "Open {}/index.html to view the documentation",
-                output_dir.display()
+                "Initialized {} project '{}' in {}",
+                template.name(),
+                name,
+                dir.display()
             );
         }
-        Err(e) => {
-            eprintln!("{}: Failed to generate HTML: {}", "Error".red().bold(), e);
+        Err(error) => {
+            eprintln!("{}: {}", "Error".red().bold(), error);
             process::exit(1);
         }
     }
 }
 
-fn process_directory(
-    doc_generator: &mut DocGenerator,
-    dir: &Path,
-    module_prefix: &str,
-) -> std::io::Result<()> {
-    for entry in fs::read_dir(dir)? {
-        let entry = entry?;
-        let path = entry.path();
+/// Migrate a package to a newer edition
+fn run_fix_command(edition: Edition, dir: Option<PathBuf>) {
+    let start =
+        dir.unwrap_or_else(|| env::current_dir().unwrap_or_else(|_| Path::new(".").to_path_buf()));
 
-        if path.is_dir() {
-            // Recursively process subdirectories
-            let dir_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    let Some(root) = script::manuscript::find_package_root(Some(&start)) else {
+        eprintln!(
+            "{}: No script.toml found in '{}' or its parents",
+            "Error".red().bold(),
+            start.display()
+        );
+        process::exit(1);
+    };
 
-            let new_prefix = if module_prefix.is_empty() {
-                dir_name.to_string()
-            } else {
-                format!("{}::{}", module_prefix, dir_name)
-            };
+    match migrate_package(&root, edition) {
+        Ok(migration) => {
+            for (path, renamed) in &migration.changed_files {
+                println!(
+                    "{} {} ({})",
+                    "Fixed".green().bold(),
+                    path.strip_prefix(&root).unwrap_or(path).display(),
+                    renamed
+                        .iter()
+                        .map(|name| format!("r#{}", name))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            println!(
+                "Migrated {} from edition {} to {} ({} files changed)",
+                root.display(),
+                migration.from,
+                migration.to,
+                migration.changed_files.len()
+            );
+        }
+        Err(error) => {
+            let mut reporter = ErrorReporter::new();
+            reporter.report(error);
+            reporter.print_all();
+            process::exit(1);
+        }
+    }
+}
 
-            process_directory(doc_generator, &path, &new_prefix)?;
-        } else if path.extension().and_then(|s| s.to_str()) == Some("script") {
-            // Process .script file
-            let file_name = path.file_stem().and_then(|n| n.to_str()).unwrap_or("");
+/// Render the line counts in `.coverage` files as a per-file summary and,
+/// with `--lcov`, an LCOV tracefile
+fn run_coverage_report(mut files: Vec<PathBuf>, lcov: Option<&Path>) {
+    // Without arguments, report every .coverage file in the working directory
+    if files.is_empty() {
+        files = fs::read_dir(".")
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| {
+                        path.extension().and_then(|ext| ext.to_str()) == Some("coverage")
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        files.sort();
+    }
+    if files.is_empty() {
+        eprintln!(
+            "{}: No .coverage files found; run a program with --coverage first",
+            "Error".red().bold()
+        );
+        process::exit(1);
+    }
 
-            let module_name = if module_prefix.is_empty() {
-                file_name.to_string()
-            } else {
-                format!("{}::{}", module_prefix, file_name)
-            };
+    let mut report = CoverageReport::new();
+    for file in &files {
+        match CoverageReport::load(file) {
+            Ok(counts) => report.merge(&counts),
+            Err(e) => {
+                eprintln!(
+                    "{}: Could not read {}: {}",
+                    "Error".red().bold(),
+                    file.display(),
+                    e
+                );
+                process::exit(1);
+            }
+        }
+    }
 
-            println!("  Processing: {module_name}");
+    print!("{}", report);
 
-            match fs::read_to_string(&path) {
-                Ok(source) => {
-                    if let Err(e) = doc_generator.generate_from_source(&source, &module_name) {
-                        eprintln!("    {}: {}", "Warning".yellow(), e);
-                    }
-                }
-                Err(e) => {
-                    eprintln!(
-                        "    {}: Could not read {}: {}",
-                        "Warning".yellow(),
-                        path.display(),
-                        e
-                    );
-                }
+    if let Some(path) = lcov {
+        let written = fs::File::create(path).and_then(|mut file| report.write_lcov(&mut file));
+        match written {
+            Ok(()) => println!(
+                "{} Wrote LCOV report to {}",
+                "Coverage:".cyan().bold(),
+                path.display()
+            ),
+            Err(e) => {
+                eprintln!(
+                    "{}: Could not write {}: {}",
+                    "Error".red().bold(),
+                    path.display(),
+                    e
+                );
+                process::exit(1);
             }
         }
     }
-
-    Ok(())
 }
 
 /// Run the debug command interface
-fn run_debug_command(args: &[String]) {
-    if args.len() < 3 {
+///
+/// Without `restore` the debugger starts from no breakpoints and leaves the
+/// saved ones alone.
+fn run_debug_command(args: &[String], restore: bool) {
+    let Some(command) = args.first() else {
         print_debug_help();
         return;
-    }
+    };
 
     // Initialize debugger
-    if let Err(e) = initialize_debugger() {
+    if let Err(e) = initialize_debugger_with(restore) {
         eprintln!(
             "{}: Failed to initialize debugger: {}",
             "Error".red().bold(),
@@ -877,15 +2587,14 @@ fn run_debug_command(args: &[String]) {
         process::exit(1);
     }
 
-    let command = &args[2];
     match command.as_str() {
         "help" | "-h" | "--help" => print_debug_help(),
-        "break" | "b" => handle_breakpoint_command(&args[3..]),
+        "break" | "b" => handle_breakpoint_command(&args[1..]),
         "list" | "l" => list_breakpoints(),
-        "remove" | "rm" => remove_breakpoint_command(&args[3..]),
+        "remove" | "rm" => remove_breakpoint_command(&args[1..]),
         "clear" => clear_all_breakpoints(),
-        "enable" => enable_breakpoint_command(&args[3..]),
-        "disable" => disable_breakpoint_command(&args[3..]),
+        "enable" => enable_breakpoint_command(&args[1..]),
+        "disable" => disable_breakpoint_command(&args[1..]),
         "stats" => show_breakpoint_stats(),
         _ => {
             eprintln!(
@@ -955,6 +2664,14 @@ fn print_debug_help() {
     println!("  script debug break test.script 15    # Break at line 15 in test.script");
     println!("  script debug list                    # List all breakpoints");
     println!("  script debug remove 1                # Remove breakpoint 1");
+    println!("  script debug program.script          # Debug a script file");
+    println!();
+    println!(
+        "Breakpoints are saved to {} in the project root and restored the next",
+        script::debugger::BREAKPOINTS_FILE
+    );
+    println!("time the debugger starts. Pass --no-restore before the command to start");
+    println!("without them.");
 }
 
 /// Handle breakpoint command
@@ -1310,78 +3027,24 @@ fn handle_debug_command(input: &str) {
     }
 }
 
-fn run_update_command(args: &[String]) {
+fn run_update_command(args: &UpdateArgs) {
     use script::update;
 
-    if args.len() >= 3 {
-        match args[2].as_str() {
-            "--check" => match update::check_update() {
-                Ok(_) => {}
-                Err(e) => {
-                    eprintln!("{}: {}", "Error".red().bold(), e);
-                    process::exit(1);
-                }
-            },
-            "--list" => match update::list_versions() {
-                Ok(_) => {}
-                Err(e) => {
-                    eprintln!("{}: {}", "Error".red().bold(), e);
-                    process::exit(1);
-                }
-            },
-            "--force" => match update::update(true) {
-                Ok(_) => {}
-                Err(e) => {
-                    eprintln!("{}: {}", "Error".red().bold(), e);
-                    process::exit(1);
-                }
-            },
-            "--version" => {
-                if args.len() >= 4 {
-                    match update::update_to_version(&args[3]) {
-                        Ok(_) => {}
-                        Err(e) => {
-                            eprintln!("{}: {}", "Error".red().bold(), e);
-                            process::exit(1);
-                        }
-                    }
-                } else {
-                    eprintln!(
-                        "{}: --version requires a version number",
-                        "Error".red().bold()
-                    );
-                    eprintln!("Usage: {} update --version <version>", args[0]);
-                    process::exit(1);
-                }
-            }
-            "--rollback" => match update::rollback() {
-                Ok(_) => {}
-                Err(e) => {
-                    eprintln!("{}: {}", "Error".red().bold(), e);
-                    process::exit(1);
-                }
-            },
-            _ => {
-                eprintln!(
-                    "{}: Unknown update option '{}'",
-                    "Error".red().bold(),
-                    args[2]
-                );
-                eprintln!(
-                    "Usage: {} update [--check|--force|--list|--version <version>|--rollback]",
-                    args[0]
-                );
-                process::exit(1);
-            }
-        }
+    let result = if args.check {
+        update::check_update().map(|_| ())
+    } else if args.list {
+        update::list_versions()
+    } else if let Some(version) = &args.to_version {
+        update::update_to_version(version)
+    } else if args.rollback {
+        update::rollback()
     } else {
-        // Default: update with prompt
-        match update::update(false) {
-            Ok(_) => {}
-            Err(e) => {
-                eprintln!("{}: {}", "Error".red().bold(), e);
-                process::exit(1);
-            }
-        }
+        // Asks before installing unless forced
+        update::update(args.force)
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}: {}", "Error".red().bold(), e);
+        process::exit(1);
     }
 }
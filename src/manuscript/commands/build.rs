@@ -1,9 +1,12 @@
 /// Build a Script package
-use super::{print_error, print_info, print_progress, print_success};
-use crate::compilation::CompilationContext;
+use super::{print_error, print_info, print_progress, print_success, print_warning};
+use crate::compilation::{hermetic, BuildProfile, CompilationContext, DeclaredInputs};
+use crate::ir::Module as IrModule;
 use crate::manuscript;
-use crate::package::{Package, PackageError, PackageResult};
-use crate::{parser::Parser, AstLowerer, CodeGenerator, Lexer};
+use crate::package::{Package, PackageError, PackageResult, Workspace};
+use crate::parser::{Parser, Program};
+use crate::semantic::{SemanticAnalyzer, SemanticWarning, SemanticWarningKind};
+use crate::{AstLowerer, CodeGenerator, Lexer};
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::collections::HashMap;
@@ -15,6 +18,9 @@ pub async fn execute(
     targets: Vec<String>,
     all: bool,
     clean: bool,
+    hermetic: bool,
+    workspace: bool,
+    packages: Vec<String>,
 ) -> PackageResult<()> {
     // Find package root
     let package_root = manuscript::find_package_root(None).ok_or_else(|| {
@@ -24,30 +30,135 @@ pub async fn execute(
         )
     })?;
 
-    let manifest_path = package_root.join("script.toml");
-    let package = Package::from_manifest_file(&manifest_path)?;
+    let found = Workspace::find(&package_root)?;
+    let selected = select_packages(&package_root, found.as_ref(), workspace, &packages)?;
+
+    // A target qualified with a package name must name a package being built
+    for target in &targets {
+        if let Some((name, _)) = target.split_once('/') {
+            if !selected
+                .iter()
+                .any(|package| package.manifest.package.name == name)
+            {
+                return Err(PackageError::ManifestParse(format!(
+                    "Target '{}' belongs to package '{}', which is not being built",
+                    target, name
+                )));
+            }
+        }
+    }
+
+    // The packages of a workspace share the target directory at its root
+    let target_dir = found
+        .as_ref()
+        .map_or(package_root.as_path(), |workspace| workspace.root_path())
+        .join("target");
 
     // Clean build directory if requested
-    if clean {
-        let build_dir = package_root.join("target");
-        if build_dir.exists() {
-            print_progress("Cleaning", "target directory");
-            fs::remove_dir_all(&build_dir)?;
-        }
+    if clean && target_dir.exists() {
+        print_progress("Cleaning", "target directory");
+        fs::remove_dir_all(&target_dir)?;
     }
 
     // Determine build mode
     let mode = if release { "release" } else { "debug" };
-    let build_dir = package_root.join("target").join(mode);
-    fs::create_dir_all(&build_dir)?;
 
-    print_info(&format!(
-        "Building {} package in {} mode",
-        package.manifest.package.name.cyan(),
-        mode.yellow()
-    ));
+    let mut built_count = 0;
+    let mut stability_warnings = Vec::new();
+    for package in &selected {
+        let name = &package.manifest.package.name;
+        let package_targets = targets_of(name, &targets);
+        if !targets.is_empty() && package_targets.is_empty() {
+            continue;
+        }
+
+        // A workspace build skips packages with nothing to build
+        let build_targets = collect_targets(package, &package_targets, all);
+        if build_targets.is_empty() {
+            continue;
+        }
 
-    // Collect build targets
+        // Members build into a directory of their own, so their outputs
+        // don't collide
+        let build_dir = match &found {
+            Some(workspace) if workspace.root_path() != package.root_path => {
+                target_dir.join(mode).join(name)
+            }
+            _ => target_dir.join(mode),
+        };
+        let (built, warnings) =
+            build_package(package, &build_targets, &build_dir, release, hermetic).await?;
+        built_count += built;
+        stability_warnings.extend(warnings);
+    }
+
+    if built_count == 0 {
+        return Err(PackageError::ManifestParse(
+            "No build targets found. Add [lib] or [[bin]] sections to script.toml".to_string(),
+        ));
+    }
+
+    print_success(&format!("Successfully built {} targets", built_count));
+    print_stability_summary(&stability_warnings);
+
+    Ok(())
+}
+
+/// The packages `manuscript build` run in `package_root` builds
+///
+/// In a workspace that is every package with `--workspace`, the packages
+/// named with `--package`, or else the package in `package_root`, which
+/// for the root means its default members. A package outside of a
+/// workspace is a workspace of its own.
+fn select_packages(
+    package_root: &Path,
+    workspace: Option<&Workspace>,
+    all_packages: bool,
+    names: &[String],
+) -> PackageResult<Vec<Package>> {
+    let Some(workspace) = workspace else {
+        let package = Package::from_manifest_file(package_root.join("script.toml"))?;
+        if let Some(name) = names
+            .iter()
+            .find(|name| **name != package.manifest.package.name)
+        {
+            return Err(PackageError::ManifestParse(format!(
+                "Package '{}' not found: {} is not in a workspace",
+                name,
+                package_root.display()
+            )));
+        }
+        return Ok(vec![package]);
+    };
+
+    let packages = if all_packages {
+        workspace.packages().collect()
+    } else if !names.is_empty() {
+        workspace.select(names)?
+    } else if workspace.root_path() == package_root {
+        workspace.default_packages()?
+    } else {
+        workspace.package_at(package_root).into_iter().collect()
+    };
+    Ok(packages.into_iter().cloned().collect())
+}
+
+/// The targets `--target` asks of the package `name`
+///
+/// A plain target name applies to every package being built, and
+/// `<package>/<target>` only to that package.
+fn targets_of(name: &str, targets: &[String]) -> Vec<String> {
+    targets
+        .iter()
+        .filter_map(|target| match target.split_once('/') {
+            Some((package, target)) => (package == name).then(|| target.to_string()),
+            None => Some(target.clone()),
+        })
+        .collect()
+}
+
+/// The library and binaries of `package` that `targets` and `all` select
+fn collect_targets(package: &Package, targets: &[String], all: bool) -> Vec<BuildTarget> {
     let mut build_targets = Vec::new();
 
     if all || (!targets.is_empty() && targets.contains(&"lib".to_string())) {
@@ -78,11 +189,43 @@ pub async fn execute(
         }
     }
 
-    if build_targets.is_empty() {
-        return Err(PackageError::ManifestParse(
-            "No build targets found. Add [lib] or [[bin]] sections to script.toml".to_string(),
-        ));
-    }
+    build_targets
+}
+
+/// Build `build_targets` of one package into `build_dir`, returning how
+/// many were built and the stability warnings they raised
+async fn build_package(
+    package: &Package,
+    build_targets: &[BuildTarget],
+    build_dir: &Path,
+    release: bool,
+    hermetic: bool,
+) -> PackageResult<(usize, Vec<SemanticWarning>)> {
+    fs::create_dir_all(build_dir)?;
+
+    let profile = BuildProfile::from_manifest(&package.manifest, release);
+    print_info(&format!(
+        "Building {} package with profile {}",
+        package.manifest.package.name.cyan(),
+        profile.to_string().yellow()
+    ));
+
+    // A hermetic build may only read the package's declared inputs
+    let hermetic = hermetic
+        || package
+            .manifest
+            .build
+            .as_ref()
+            .map_or(false, |build| build.hermetic);
+    let declared_inputs = if hermetic {
+        print_info("Hermetic build: only declared inputs may be read");
+        Some(
+            DeclaredInputs::for_package(&package.root_path, &package.manifest)
+                .map_err(|e| PackageError::ManifestParse(e.to_string()))?,
+        )
+    } else {
+        None
+    };
 
     // Create progress bar
     let pb = ProgressBar::new(build_targets.len() as u64);
@@ -96,12 +239,22 @@ pub async fn execute(
     // Build each target
     let mut built_count = 0;
     let mut errors = Vec::new();
+    let mut stability_warnings = Vec::new();
 
-    for target in &build_targets {
+    for target in build_targets {
         pb.set_message(format!("Building {} ({})", target.name.cyan(), target.kind));
 
-        match build_target(&package, &target, &build_dir, release).await {
-            Ok(output_path) => {
+        match build_target(
+            package,
+            target,
+            build_dir,
+            release,
+            declared_inputs.as_ref(),
+        )
+        .await
+        {
+            Ok((output_path, warnings)) => {
+                stability_warnings.extend(warnings);
                 built_count += 1;
                 pb.inc(1);
                 print_success(&format!(
@@ -131,9 +284,128 @@ pub async fn execute(
         )));
     }
 
-    print_success(&format!("Successfully built {} targets", built_count));
+    Ok((built_count, stability_warnings))
+}
 
-    Ok(())
+/// Build every binary target of a package into `build_dir`, returning the
+/// name and output path of each
+pub async fn build_binaries(
+    package: &Package,
+    build_dir: &Path,
+    release: bool,
+) -> PackageResult<Vec<(String, PathBuf)>> {
+    fs::create_dir_all(build_dir)?;
+
+    let mut outputs = Vec::new();
+    for bin in &package.manifest.bin {
+        let target = BuildTarget {
+            name: bin.name.clone(),
+            path: package.root_path.join(&bin.path),
+            kind: TargetKind::Binary,
+        };
+        let (output_path, _) = build_target(package, &target, build_dir, release, None)
+            .await
+            .map_err(|e| {
+                PackageError::ManifestParse(format!("Failed to build {}: {}", bin.name, e))
+            })?;
+        outputs.push((bin.name.clone(), output_path));
+    }
+    Ok(outputs)
+}
+
+/// Lex and parse the source file at `path`
+pub(crate) fn parse_file(path: &Path, context: &CompilationContext) -> PackageResult<Program> {
+    hermetic::check_read(path).map_err(|e| PackageError::ManifestParse(e.to_string()))?;
+    let source = fs::read_to_string(path)?;
+    let mut lexer = Lexer::new(&source).map_err(|e| PackageError::ManifestParse(e.to_string()))?;
+    lexer.set_edition(context.edition());
+    let (tokens, lex_errors) = lexer.scan_tokens();
+
+    if !lex_errors.is_empty() {
+        return Err(PackageError::ManifestParse(format!(
+            "Lexical errors in {}: {} errors",
+            path.display(),
+            lex_errors.len()
+        )));
+    }
+
+    let mut parser = Parser::new(tokens);
+    parser.parse().map_err(|e| {
+        PackageError::ManifestParse(format!("Parse error in {}: {}", path.display(), e))
+    })
+}
+
+/// Lower `ast` to a module named `name`, declaring the functions of
+/// `library` so calls to them can be linked
+pub(crate) fn lower_module(
+    ast: &Program,
+    name: &str,
+    path: &Path,
+    library: Option<&Program>,
+) -> PackageResult<IrModule> {
+    let symbol_table = crate::semantic::SymbolTable::new();
+    let type_info = HashMap::new();
+    let closure_captures = HashMap::new();
+    let mut lowerer = AstLowerer::new(symbol_table, type_info, Vec::new(), closure_captures);
+    if let Some(library) = library {
+        lowerer.declare_functions(library);
+    }
+    let mut module = lowerer.lower_program(ast).map_err(|e| {
+        PackageError::ManifestParse(format!("Lowering error in {}: {}", path.display(), e))
+    })?;
+    module.name = name.to_string();
+    Ok(module)
+}
+
+/// Summarize the deprecated and unstable APIs the package uses
+///
+/// Each API is listed once with the number of places it is used.
+fn print_stability_summary(warnings: &[SemanticWarning]) {
+    let mut deprecated: Vec<(String, usize)> = Vec::new();
+    let mut unstable: Vec<(String, usize)> = Vec::new();
+
+    for warning in warnings {
+        let (list, description) = match &warning.kind {
+            SemanticWarningKind::DeprecatedUsage { name, since, note } => {
+                let mut description = name.clone();
+                if let Some(since) = since {
+                    description.push_str(&format!(" (since {})", since));
+                }
+                if let Some(note) = note {
+                    description.push_str(&format!(": {}", note));
+                }
+                (&mut deprecated, description)
+            }
+            SemanticWarningKind::UnstableUsage { name, .. } => (&mut unstable, name.clone()),
+            // Not about API stability
+            SemanticWarningKind::UnreachablePattern { .. }
+            | SemanticWarningKind::FutureKeyword { .. } => continue,
+        };
+        match list.iter_mut().find(|(d, _)| *d == description) {
+            Some((_, count)) => *count += 1,
+            None => list.push((description, 1)),
+        }
+    }
+
+    for (title, list) in [("deprecated", &deprecated), ("unstable", &unstable)] {
+        if list.is_empty() {
+            continue;
+        }
+        print_warning(&format!(
+            "Package uses {} {} API{}",
+            list.len(),
+            title,
+            if list.len() == 1 { "" } else { "s" }
+        ));
+        for (description, count) in list {
+            eprintln!(
+                "    {} ({} use{})",
+                description.yellow(),
+                count,
+                if *count == 1 { "" } else { "s" }
+            );
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -163,55 +435,27 @@ async fn build_target(
     target: &BuildTarget,
     build_dir: &Path,
     release: bool,
-) -> PackageResult<PathBuf> {
-    // Read source file
-    let source = fs::read_to_string(&target.path)?;
-
+    declared_inputs: Option<&DeclaredInputs>,
+) -> PackageResult<(PathBuf, Vec<SemanticWarning>)> {
     // Create compilation context
     let mut context = CompilationContext::new();
     context.set_package_root(package.root_path.clone());
     context.set_release_mode(release);
+    context
+        .apply_manifest(&package.manifest)
+        .map_err(|e| PackageError::ManifestParse(e.to_string()))?;
 
-    // Tokenize
-    let lexer = Lexer::new(&source).map_err(|e| PackageError::ManifestParse(e.to_string()))?;
-    let (tokens, lex_errors) = lexer.scan_tokens();
-
-    if !lex_errors.is_empty() {
-        return Err(PackageError::ManifestParse(format!(
-            "Lexical errors in {}: {} errors",
-            target.path.display(),
-            lex_errors.len()
-        )));
+    if let Some(inputs) = declared_inputs {
+        hermetic::start(inputs.clone());
     }
-
-    // Parse
-    let mut parser = Parser::new(tokens);
-    let ast = parser.parse().map_err(|e| {
-        PackageError::ManifestParse(format!("Parse error in {}: {}", target.path.display(), e))
-    })?;
-
-    // Lower to IR
-    let symbol_table = crate::semantic::SymbolTable::new();
-    let type_info = std::collections::HashMap::new();
-    let closure_captures = HashMap::new();
-    let mut lowerer = AstLowerer::new(symbol_table, type_info, Vec::new(), closure_captures);
-    let ir_module = lowerer.lower_program(&ast).map_err(|e| {
-        PackageError::ManifestParse(format!(
-            "Lowering error in {}: {}",
-            target.path.display(),
-            e
-        ))
-    })?;
-
-    // Generate code
-    let mut generator = CodeGenerator::new();
-    let _executable_module = generator.generate(&ir_module).map_err(|e| {
-        PackageError::ManifestParse(format!(
-            "Code generation error in {}: {}",
-            target.path.display(),
-            e
-        ))
-    })?;
+    let compiled = compile_target(package, target, &context);
+    let report = hermetic::stop();
+    if declared_inputs.is_some() {
+        report
+            .into_result()
+            .map_err(|e| PackageError::ManifestParse(e.to_string()))?;
+    }
+    let warnings = compiled?;
 
     // Determine output path
     let output_name = match target.kind {
@@ -234,5 +478,62 @@ async fn build_target(
         }
     }
 
-    Ok(output_path)
+    Ok((output_path, warnings))
+}
+
+/// Parse, analyze, lower and generate code for `target`
+fn compile_target(
+    package: &Package,
+    target: &BuildTarget,
+    context: &CompilationContext,
+) -> PackageResult<Vec<SemanticWarning>> {
+    let ast = parse_file(&target.path, context)?;
+
+    // Collect uses of deprecated and unstable APIs, including those of
+    // dependencies. Semantic errors are not fatal to `manuscript build` yet.
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.set_edition(context.edition());
+    analyzer.set_current_file(Some(target.path.clone()));
+    analyzer.add_module_search_path(package.root_path.clone());
+    if let Some(parent) = target.path.parent() {
+        analyzer.add_module_search_path(parent.to_path_buf());
+    }
+    let warnings = match analyzer.analyze_program(&ast) {
+        Ok(()) => analyzer.warnings().to_vec(),
+        Err(_) => Vec::new(),
+    };
+
+    // Lower to IR. A binary is linked with the package's library, which is
+    // lowered on its own.
+    let library = match (target.kind, package.lib_entry_point()) {
+        (TargetKind::Binary, Some(lib_path)) if lib_path.exists() && lib_path != target.path => {
+            Some((parse_file(&lib_path, context)?, lib_path))
+        }
+        _ => None,
+    };
+    let mut modules = vec![lower_module(
+        &ast,
+        &target.name,
+        &target.path,
+        library.as_ref().map(|(lib_ast, _)| lib_ast),
+    )?];
+    if let Some((lib_ast, lib_path)) = &library {
+        modules.push(lower_module(lib_ast, "lib", lib_path, None)?);
+    }
+
+    // Optimize and generate code as the package's profile asks
+    let profile = context.build_profile();
+    let (ir_module, _) = profile.link_and_optimize(modules).map_err(|e| {
+        PackageError::ManifestParse(format!("Link error in {}: {}", target.path.display(), e))
+    })?;
+    let mut generator = CodeGenerator::with_debug(profile.debug_flags());
+    let _executable_module = generator.generate(&ir_module).map_err(|e| {
+        PackageError::ManifestParse(format!(
+            "Code generation error in {}: {}",
+            target.path.display(),
+            e
+        ))
+    })?;
+
+    Ok(warnings)
 }
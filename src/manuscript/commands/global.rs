@@ -0,0 +1,197 @@
+/// Install, switch and remove globally installed package binaries
+use super::{build, print_info, print_progress, print_success, print_warning};
+use crate::manuscript::GlobalInstalls;
+use crate::package::{Package, PackageError, PackageResult, Version};
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Install packages globally and put shims for their binaries on PATH
+///
+/// Each package is given as the path to its directory. A `name@version`
+/// that is already installed switches to that version instead.
+pub async fn install(packages: Vec<String>, force: bool) -> PackageResult<()> {
+    if packages.is_empty() {
+        return Err(PackageError::ManifestParse(
+            "No packages specified for global installation".to_string(),
+        ));
+    }
+
+    let installs = GlobalInstalls::open()?;
+    for spec in packages {
+        let path = Path::new(&spec);
+        if path.join("script.toml").is_file() {
+            install_from_dir(&installs, path, force).await?;
+        } else {
+            match parse_spec(&spec)? {
+                (name, Some(version)) if installs.versions(name)?.contains(&version) => {
+                    activate(&installs, name, &version, force)?
+                }
+                _ => {
+                    return Err(PackageError::Registry(format!(
+                        "{} is not installed globally and installing from the registry is not \
+                         supported yet. Pass the path to the package directory instead.",
+                        spec
+                    )))
+                }
+            }
+        }
+    }
+
+    print_path_hint(&installs);
+    Ok(())
+}
+
+async fn install_from_dir(installs: &GlobalInstalls, dir: &Path, force: bool) -> PackageResult<()> {
+    let package = Package::from_manifest_file(dir.join("script.toml"))?;
+    let name = package.manifest.package.name.clone();
+    let version = Version::parse(&package.manifest.package.version)?;
+    if package.manifest.bin.is_empty() {
+        return Err(PackageError::ManifestParse(format!(
+            "{} has no [[bin]] targets to install",
+            name
+        )));
+    }
+
+    print_progress("Installing", &format!("{} {} (global)", name, version));
+    let version_dir = installs.version_dir(&name, &version);
+    if installs.install(dir, &package.manifest, force)? {
+        // Build from the installed copy so the shims never depend on the
+        // source directory
+        let installed = Package::from_manifest_file(version_dir.join("script.toml"))?;
+        let build_dir = version_dir.join("target").join("release");
+        if let Err(e) = build::build_binaries(&installed, &build_dir, true).await {
+            fs::remove_dir_all(&version_dir)?;
+            return Err(e);
+        }
+    } else {
+        print_info(&format!(
+            "{} {} is already installed. Use --force to reinstall.",
+            name, version
+        ));
+    }
+
+    activate(installs, &name, &version, force)
+}
+
+/// Switch a globally installed package to another installed version
+pub async fn use_version(spec: String) -> PackageResult<()> {
+    let installs = GlobalInstalls::open()?;
+    let (name, version) = parse_spec(&spec)?;
+    let version = version.ok_or_else(|| {
+        PackageError::ManifestParse(format!("Specify a version to use, e.g. {}@1.0.0", name))
+    })?;
+
+    let versions = installs.versions(name)?;
+    if !versions.contains(&version) {
+        let installed: Vec<String> = versions.iter().map(Version::to_string).collect();
+        return Err(PackageError::PackageNotFound {
+            name: if installed.is_empty() {
+                name.to_string()
+            } else {
+                format!("{}@{} (installed: {})", name, version, installed.join(", "))
+            },
+        });
+    }
+
+    activate(&installs, name, &version, false)
+}
+
+/// Remove globally installed packages, or single versions of them
+pub async fn uninstall(packages: Vec<String>) -> PackageResult<()> {
+    if packages.is_empty() {
+        return Err(PackageError::ManifestParse(
+            "No packages specified to uninstall".to_string(),
+        ));
+    }
+
+    let installs = GlobalInstalls::open()?;
+    for spec in packages {
+        let (name, version) = parse_spec(&spec)?;
+        let now_active = installs.uninstall(name, version.as_ref(), &interpreter())?;
+        print_success(&format!("Uninstalled {}", spec.cyan()));
+        if let Some(active) = now_active {
+            print_info(&format!("{} now uses version {}", name, active));
+        }
+    }
+    Ok(())
+}
+
+/// List globally installed packages with their versions and binaries
+pub async fn list() -> PackageResult<()> {
+    let installs = GlobalInstalls::open()?;
+    let packages = installs.packages()?;
+    if packages.is_empty() {
+        print_info("No packages are installed globally");
+        return Ok(());
+    }
+
+    for name in packages {
+        let active = installs.active_version(&name)?;
+        let versions: Vec<String> = installs
+            .versions(&name)?
+            .iter()
+            .map(|version| {
+                if Some(version) == active.as_ref() {
+                    format!("{}*", version).green().bold().to_string()
+                } else {
+                    version.to_string()
+                }
+            })
+            .collect();
+        println!("{} {}", name.cyan().bold(), versions.join(", "));
+
+        let shims = installs.shims(&name)?;
+        if !shims.is_empty() {
+            println!("    {}", shims.join(", ").dimmed());
+        }
+    }
+    Ok(())
+}
+
+fn activate(
+    installs: &GlobalInstalls,
+    name: &str,
+    version: &Version,
+    force: bool,
+) -> PackageResult<()> {
+    let shims = installs.activate(name, version, &interpreter(), force)?;
+    print_success(&format!(
+        "Using {} {} ({})",
+        name.cyan(),
+        version,
+        shims.join(", ")
+    ));
+    Ok(())
+}
+
+/// Split `name@version`; the version is optional
+fn parse_spec(spec: &str) -> PackageResult<(&str, Option<Version>)> {
+    match spec.split_once('@') {
+        Some((name, version)) => Ok((name, Some(Version::parse(version)?))),
+        None => Ok((spec, None)),
+    }
+}
+
+/// The `script` interpreter the shims run, preferring the one installed
+/// alongside manuscript
+fn interpreter() -> PathBuf {
+    let exe = format!("script{}", std::env::consts::EXE_SUFFIX);
+    std::env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(|dir| dir.join(&exe)))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(exe))
+}
+
+fn print_path_hint(installs: &GlobalInstalls) {
+    let on_path = std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir == installs.bin_dir()))
+        .unwrap_or(false);
+    if !on_path {
+        print_warning(&format!(
+            "{} is not on your PATH. Add it to run installed binaries.",
+            installs.bin_dir().display()
+        ));
+    }
+}
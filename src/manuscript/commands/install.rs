@@ -1,13 +1,14 @@
 /// Install dependencies for a Script package
-use super::{print_info, print_progress, print_success, print_warning};
+use super::{global, print_info, print_progress, print_success, print_warning};
 use crate::manuscript;
 use crate::package::{
-    DependencySpec, Package, PackageError, PackageManager, PackageManifest, PackageResult,
+    Dependency, DependencyKind, DependencySpec, LockFile, Package, PackageError, PackageManager,
+    PackageManifest, PackageResult, Workspace,
 };
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub async fn execute(
     packages: Vec<String>,
@@ -15,9 +16,11 @@ pub async fn execute(
     save: bool,
     force: bool,
     global: bool,
+    locked: bool,
+    frozen: bool,
 ) -> PackageResult<()> {
     if global {
-        return install_global(packages, force).await;
+        return global::install(packages, force).await;
     }
 
     // Find package root
@@ -29,27 +32,75 @@ pub async fn execute(
     })?;
 
     let manifest_path = package_root.join("script.toml");
+    let config = manuscript::ManuscriptConfig::load()?;
+    let mut manager = PackageManager::with_config(config.package_manager_config())?;
+    let installed_dir = manuscript::cache_dir()?.join("packages");
 
     if packages.is_empty() {
-        // Install from manifest
-        install_from_manifest(&manifest_path, force).await
+        // Install from manifest. A workspace installs the dependencies of
+        // all its packages into the script.lock at its root.
+        let package = match Workspace::find(&package_root)? {
+            Some(workspace) => workspace.unified_package()?,
+            None => Package::from_manifest_file(&manifest_path)?,
+        };
+        install_from_manifest(
+            package,
+            &mut manager,
+            &installed_dir,
+            force,
+            locked || frozen,
+            frozen,
+        )
+    } else if locked || frozen {
+        Err(PackageError::DependencyResolution(
+            "Cannot add packages with --locked or --frozen, which keep script.lock as it is"
+                .to_string(),
+        ))
     } else {
         // Install specific packages
-        install_packages(&manifest_path, packages, dev, save, force).await
+        install_packages(
+            &manifest_path,
+            packages,
+            &mut manager,
+            &installed_dir,
+            dev,
+            save,
+            force,
+        )
     }
 }
 
-async fn install_from_manifest(manifest_path: &PathBuf, force: bool) -> PackageResult<()> {
+/// Install the manifest's dependencies and bring script.lock up to date
+///
+/// With `locked`, script.lock must exist and already be up to date. With
+/// `frozen`, every dependency must also be installed, so nothing is
+/// downloaded.
+fn install_from_manifest(
+    mut package: Package,
+    manager: &mut PackageManager,
+    installed_dir: &Path,
+    force: bool,
+    locked: bool,
+    frozen: bool,
+) -> PackageResult<()> {
     print_info("Installing dependencies from script.toml");
 
-    let mut package = Package::from_manifest_file(manifest_path)?;
-    let mut manager = PackageManager::new()?;
+    package.manifest.check_host()?;
 
     // Check if lock file exists
-    let lock_path = manifest_path.parent().unwrap().join("script.lock");
-    if lock_path.exists() && !force {
+    let lock_path = package.root_path.join("script.lock");
+    if lock_path.exists() && (!force || locked) {
         print_info("Using script.lock for consistent dependencies");
-        package.lock_file = Some(crate::package::LockFile::from_file(&lock_path)?);
+        package.lock_file = Some(LockFile::from_file(&lock_path)?);
+    }
+    if locked {
+        let lock_file = package.lock_file.as_ref().ok_or_else(|| {
+            PackageError::DependencyResolution(
+                "script.lock is missing; run `manuscript install` without --locked or --frozen to create it"
+                    .to_string(),
+            )
+        })?;
+        lock_file.check_manifest(&package.manifest)?;
     }
 
     // Count total dependencies
@@ -72,21 +123,33 @@ async fn install_from_manifest(manifest_path: &PathBuf, force: bool) -> PackageR
     print_progress("Resolving", "dependency graph");
     let graph = manager.resolve_dependencies(&package)?;
 
-    let build_order = graph.build_order();
-    pb.set_length(build_order.len() as u64);
+    let dependencies: Vec<Dependency> = graph.packages().into_iter().cloned().collect();
+    pb.set_length(dependencies.len() as u64);
 
-    for package_name in build_order {
-        pb.set_message(format!("Installing {}", package_name));
+    let package_dir =
+        |dependency: &Dependency| package_dir(&package.root_path, installed_dir, dependency);
+
+    for dependency in &dependencies {
+        pb.set_message(format!("Installing {}", dependency.name));
 
         // Install package
         // In a real implementation, this would download and extract the package
-        let package_dir = manuscript::cache_dir()?.join("packages").join(package_name);
-        if !package_dir.exists() || force {
-            fs::create_dir_all(&package_dir)?;
+        let dir = package_dir(dependency);
+        if matches!(dependency.kind, DependencyKind::Path { .. }) {
+            pb.inc(1);
+        } else if !dir.exists() || force {
+            if frozen {
+                pb.abandon();
+                return Err(PackageError::DependencyResolution(format!(
+                    "'{}' is not installed, and --frozen does not download packages",
+                    dependency.name
+                )));
+            }
+            fs::create_dir_all(&dir)?;
             // Simulate package installation
             pb.inc(1);
         } else {
-            pb.set_message(format!("Using cached {}", package_name));
+            pb.set_message(format!("Using cached {}", dependency.name));
             pb.inc(1);
         }
     }
@@ -94,20 +157,21 @@ async fn install_from_manifest(manifest_path: &PathBuf, force: bool) -> PackageR
     pb.finish_with_message("Installation complete");
 
     // Update lock file
-    if package.lock_file.is_none() {
-        print_progress("Creating", "script.lock");
-        let lock_file = create_lock_file(&graph)?;
-        lock_file.save_to_file(&lock_path)?;
-    }
+    let lock_file = LockFile::from_graph(&graph, package_dir)?;
+    update_lock_file(&lock_path, package.lock_file.as_ref(), &lock_file, locked)?;
 
-    print_success(&format!("Installed {} dependencies", build_order.len()));
+    print_success(&format!("Installed {} dependencies", dependencies.len()));
 
     Ok(())
 }
 
-async fn install_packages(
-    manifest_path: &PathBuf,
+/// Install `packages`, adding them to script.toml and script.lock with
+/// `save`
+fn install_packages(
+    manifest_path: &Path,
     packages: Vec<String>,
+    manager: &mut PackageManager,
+    installed_dir: &Path,
     dev: bool,
     save: bool,
     force: bool,
@@ -117,6 +181,7 @@ async fn install_packages(
     }
 
     let mut manifest = PackageManifest::from_file(manifest_path)?;
+    manifest.check_host()?;
     let mut added = Vec::new();
 
     for package_spec in packages {
@@ -137,7 +202,7 @@ async fn install_packages(
 
         // Install the package
         // In a real implementation, this would download and install
-        let package_dir = manuscript::cache_dir()?.join("packages").join(&name);
+        let package_dir = installed_dir.join(&name);
         if !package_dir.exists() || force {
             fs::create_dir_all(&package_dir)?;
         }
@@ -158,45 +223,48 @@ async fn install_packages(
             "dependencies"
         };
         print_success(&format!("Added {} packages to {}", added.len(), dep_type));
+
+        // script.lock follows script.toml, so a later --locked install passes
+        let root = manifest_path.parent().unwrap_or(Path::new("."));
+        relock(root, manager, installed_dir)?;
     }
 
     Ok(())
 }
 
-async fn install_global(packages: Vec<String>, force: bool) -> PackageResult<()> {
-    if packages.is_empty() {
-        return Err(PackageError::ManifestParse(
-            "No packages specified for global installation".to_string(),
-        ));
+/// Resolve the dependencies of the package in `package_root`, or of its
+/// whole workspace, and bring script.lock up to date with them
+///
+/// Versions the lock file pins that still satisfy script.toml are kept.
+fn relock(
+    package_root: &Path,
+    manager: &mut PackageManager,
+    installed_dir: &Path,
+) -> PackageResult<()> {
+    let mut package = match Workspace::find(package_root)? {
+        Some(workspace) => workspace.unified_package()?,
+        None => Package::from_manifest_file(package_root.join("script.toml"))?,
+    };
+    let lock_path = package.root_path.join("script.lock");
+    if lock_path.exists() {
+        package.lock_file = Some(LockFile::from_file(&lock_path)?);
     }
 
-    manuscript::ensure_manuscript_dirs()?;
-    let global_dir = manuscript::global_packages_dir()?;
-
-    for package_spec in packages {
-        let (name, version) = parse_package_spec(&package_spec)?;
-
-        print_progress("Installing", &format!("{} {} (global)", name, version));
+    let graph = manager.resolve_dependencies(&package)?;
+    let lock_file = LockFile::from_graph(&graph, |dependency| {
+        package_dir(&package.root_path, installed_dir, dependency)
+    })?;
+    update_lock_file(&lock_path, package.lock_file.as_ref(), &lock_file, false)
+}
 
-        let package_dir = global_dir.join(&name);
-        if package_dir.exists() && !force {
-            print_warning(&format!(
-                "{} is already installed globally. Use --force to reinstall.",
-                name
-            ));
-            continue;
+/// Where `dependency` of the package in `package_root` is installed
+fn package_dir(package_root: &Path, installed_dir: &Path, dependency: &Dependency) -> PathBuf {
+    match &dependency.kind {
+        DependencyKind::Path { path } => package_root.join(path),
+        DependencyKind::Registry | DependencyKind::Git { .. } => {
+            installed_dir.join(&dependency.name)
         }
-
-        // Install globally
-        fs::create_dir_all(&package_dir)?;
-
-        // Create symlinks for binaries
-        // In a real implementation, this would link executables to PATH
-
-        print_success(&format!("Installed {} {} globally", name.cyan(), version));
     }
-
-    Ok(())
 }
 
 fn parse_package_spec(spec: &str) -> PackageResult<(String, String)> {
@@ -210,26 +278,101 @@ fn parse_package_spec(spec: &str) -> PackageResult<(String, String)> {
     }
 }
 
-fn create_lock_file(
-    graph: &crate::package::DependencyGraph,
-) -> PackageResult<crate::package::LockFile> {
-    let mut lock_file = crate::package::LockFile::new();
-
-    for package_name in graph.build_order() {
-        if let Some(version) = graph.get_resolved_version(package_name) {
-            let entry = crate::package::LockEntry {
-                name: package_name.to_string(),
-                version: version.to_string(),
-                source: "registry".to_string(),
-                checksum: None,
-                dependencies: graph
-                    .get_dependencies(package_name)
-                    .map(|deps| deps.iter().map(|d| d.name.clone()).collect())
-                    .unwrap_or_default(),
-            };
-            lock_file.packages.push(entry);
+/// Write `lock_file` to `lock_path` unless `existing` already matches it
+///
+/// With `locked`, a lock file that would change is an error instead.
+fn update_lock_file(
+    lock_path: &Path,
+    existing: Option<&LockFile>,
+    lock_file: &LockFile,
+    locked: bool,
+) -> PackageResult<()> {
+    match existing {
+        Some(existing) if existing.packages == lock_file.packages => Ok(()),
+        Some(existing) if locked => {
+            let changed = lock_file
+                .packages
+                .iter()
+                .find(|entry| !existing.packages.contains(entry))
+                .map(|entry| entry.name.as_str())
+                .unwrap_or("a dependency");
+            Err(PackageError::DependencyResolution(format!(
+                "script.lock is out of date: '{}' no longer matches its locked version or checksum",
+                changed
+            )))
+        }
+        Some(_) => {
+            print_progress("Updating", "script.lock");
+            lock_file.save_to_file(lock_path)
+        }
+        None => {
+            print_progress("Creating", "script.lock");
+            lock_file.save_to_file(lock_path)
         }
     }
+}
 
-    Ok(lock_file)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::{
+        CacheConfig, PackageManagerConfig, PackageMetadata, RegistryServer, RegistryStore,
+        RetryPolicy, Version,
+    };
+    use tempfile::TempDir;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_saved_packages_are_locked() {
+        let dir = TempDir::new().unwrap();
+        let store = RegistryStore::open(dir.path().join("registry")).unwrap();
+        for version in ["1.2.0", "1.3.0"] {
+            let metadata = PackageMetadata::new("json", Version::parse(version).unwrap());
+            store.publish(&metadata, b"archive", None).unwrap();
+        }
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        runtime.spawn(RegistryServer::new(store, Vec::new()).serve(listener));
+
+        let mut manager = PackageManager::with_config(PackageManagerConfig {
+            cache_config: CacheConfig {
+                cache_dir: dir.path().join("cache"),
+                ..CacheConfig::default()
+            },
+            registry_url: url,
+            retry_policy: RetryPolicy::none(),
+            ..PackageManagerConfig::default()
+        })
+        .unwrap();
+        let installed_dir = dir.path().join("installed");
+        let root = dir.path().join("app");
+        fs::create_dir(&root).unwrap();
+        let manifest_path = root.join("script.toml");
+        PackageManifest::new("app").to_file(&manifest_path).unwrap();
+
+        // manuscript install json@^1.2.0 --save
+        install_packages(
+            &manifest_path,
+            vec!["json@^1.2.0".to_string()],
+            &mut manager,
+            &installed_dir,
+            false,
+            true,
+            false,
+        )
+        .unwrap();
+        let lock_file = LockFile::from_file(root.join("script.lock")).unwrap();
+        assert_eq!(lock_file.packages.len(), 1);
+        assert_eq!(lock_file.packages[0].name, "json");
+        assert_eq!(lock_file.packages[0].version, "1.3.0");
+
+        // manuscript install --locked
+        let package = Package::from_manifest_file(&manifest_path).unwrap();
+        install_from_manifest(package, &mut manager, &installed_dir, false, true, false).unwrap();
+        assert_eq!(
+            LockFile::from_file(root.join("script.lock")).unwrap(),
+            lock_file
+        );
+    }
 }
@@ -1,13 +1,17 @@
 pub mod build;
 pub mod cache;
+pub mod global;
 pub mod info;
 /// Command implementations for manuscript CLI
 pub mod init;
 pub mod install;
 pub mod new;
 pub mod publish;
+pub mod registry;
 pub mod run;
 pub mod search;
+pub mod semver_check;
+pub mod test;
 pub mod update;
 
 use colored::*;
@@ -1,28 +1,51 @@
 /// Create a new Script package from a template
-use super::{print_info, print_success};
-use crate::package::PackageResult;
+use super::{print_info, print_progress, print_success};
+use crate::manuscript::templates::{self, ProjectTemplate};
+use crate::package::{PackageError, PackageResult};
+use colored::*;
 use std::path::PathBuf;
 
 pub async fn execute(
     path: PathBuf,
-    _template: Option<String>,
+    template: Option<String>,
     list_templates: bool,
 ) -> PackageResult<()> {
     if list_templates {
         print_info("Available templates:");
-        println!("  - default: Basic Script package");
-        println!("  - lib: Library package");
-        println!("  - cli: Command-line application");
-        println!("  - web: Web application (coming soon)");
-        println!("  - game: Game development (coming soon)");
-    } else {
-        print_info(&format!(
-            "Creating new package at {:?} is not yet fully implemented",
-            path
-        ));
-        // For now, just create the directory
-        std::fs::create_dir_all(&path)?;
-        print_success(&format!("Created directory at {:?}", path));
+        for template in ProjectTemplate::ALL {
+            println!("  - {}: {}", template.name(), template.description());
+        }
+        return Ok(());
     }
+
+    let template = match template {
+        Some(name) => ProjectTemplate::parse(&name).ok_or_else(|| {
+            PackageError::ManifestParse(format!(
+                "Unknown template '{}' (see --list-templates)",
+                name
+            ))
+        })?,
+        None => ProjectTemplate::Binary,
+    };
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| {
+            PackageError::ManifestParse(format!("Cannot name a package after {:?}", path))
+        })?
+        .to_string();
+
+    print_progress(
+        "Creating",
+        &format!("{} package '{}'", template.name(), name),
+    );
+    for file in templates::scaffold_project(&path, &name, template)? {
+        print_success(&format!("Created {}", file.display()));
+    }
+
+    println!();
+    print_info("Next steps:");
+    println!("  • cd {}", path.display().to_string().cyan());
+    println!("  • Edit {} to add dependencies", "script.toml".cyan());
     Ok(())
 }
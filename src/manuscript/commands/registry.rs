@@ -0,0 +1,61 @@
+/// Host a package registry backed by a local directory
+use super::{print_info, print_success, print_warning};
+use crate::package::{PackageError, PackageResult, RegistryServer, RegistryStore};
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::net::TcpListener;
+
+/// Serve the registry stored in `root` until interrupted
+///
+/// Publish tokens come from `--token` and from a `tokens` file in the
+/// registry root with one token per line.
+pub async fn serve(root: Option<PathBuf>, addr: String, tokens: Vec<String>) -> PackageResult<()> {
+    let root = match root {
+        Some(root) => root,
+        None => crate::manuscript::manuscript_home()?.join("registry"),
+    };
+    let store = RegistryStore::open(&root)?;
+
+    let mut tokens = tokens;
+    tokens.extend(read_tokens_file(&root.join("tokens"))?);
+    if tokens.is_empty() {
+        print_warning("No publish tokens configured; the registry is read-only");
+    }
+
+    let listener = TcpListener::bind(&addr)
+        .await
+        .map_err(|e| PackageError::Registry(format!("Failed to listen on {}: {}", addr, e)))?;
+    let url = format!("http://{}", listener.local_addr()?);
+
+    print_success(&format!(
+        "Serving registry {} at {}",
+        root.display(),
+        url.cyan()
+    ));
+    print_info(&format!(
+        "Point clients at it with default_registry = \"{}\" in the manuscript config",
+        url
+    ));
+
+    let server = RegistryServer::new(store, tokens);
+    tokio::select! {
+        result = server.serve(listener) => result,
+        _ = tokio::signal::ctrl_c() => {
+            print_info("Registry stopped");
+            Ok(())
+        }
+    }
+}
+
+fn read_tokens_file(path: &Path) -> PackageResult<Vec<String>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
@@ -1,12 +1,181 @@
-/// Run a Script package or command
-use super::print_info;
-use crate::package::PackageResult;
+/// Run a script from the `[scripts]` section of script.toml
+use super::{build, print_error, print_info};
+use crate::manuscript;
+use crate::package::{Package, PackageError, PackageResult, ScriptSpec};
+use colored::*;
+use std::path::Path;
+use std::process::{self, ExitStatus};
+use tokio::process::{Child, Command};
 
-pub async fn execute(_script: Option<String>, _args: Vec<String>, list: bool) -> PackageResult<()> {
-    if list {
-        print_info("Listing scripts is not yet implemented");
-    } else {
-        print_info("Running scripts is not yet implemented");
+pub async fn execute(script: Option<String>, args: Vec<String>, list: bool) -> PackageResult<()> {
+    let package_root = manuscript::find_package_root(None).ok_or_else(|| {
+        PackageError::ManifestParse(
+            "Not in a Script package directory. Run 'manuscript init' to create a package."
+                .to_string(),
+        )
+    })?;
+    let package = Package::from_manifest_file(package_root.join("script.toml"))?;
+
+    let name = match script {
+        Some(name) if !list => name,
+        _ => {
+            list_scripts(&package);
+            return Ok(());
+        }
+    };
+
+    let spec = package.manifest.scripts.get(&name).ok_or_else(|| {
+        let mut names: Vec<&str> = package
+            .manifest
+            .scripts
+            .keys()
+            .map(String::as_str)
+            .collect();
+        names.sort_unstable();
+        PackageError::ManifestParse(if names.is_empty() {
+            format!(
+                "No script named '{}': script.toml has no [scripts] section",
+                name
+            )
+        } else {
+            format!(
+                "No script named '{}' (available: {})",
+                name,
+                names.join(", ")
+            )
+        })
+    })?;
+
+    if spec.requires_build() {
+        build::execute(false, Vec::new(), false, false, false, false, Vec::new()).await?;
+    }
+
+    print_info(&format!("Running {}: {}", name.cyan(), spec.command()));
+    let mut child = spawn(spec, &args, &package, &package_root)?;
+    let status = wait_forwarding_signals(&mut child).await?;
+
+    if !status.success() {
+        let code = exit_code(status);
+        print_error(&format!("Script {} exited with status {}", name, code));
+        process::exit(code);
     }
     Ok(())
 }
+
+fn list_scripts(package: &Package) {
+    let scripts = &package.manifest.scripts;
+    if scripts.is_empty() {
+        print_info("No scripts defined. Add a [scripts] section to script.toml");
+        return;
+    }
+
+    let mut names: Vec<&String> = scripts.keys().collect();
+    names.sort();
+    let width = names.iter().map(|name| name.len()).max().unwrap_or(0);
+
+    println!("Scripts in {}:", package.manifest.package.name.cyan());
+    for name in names {
+        let spec = &scripts[name];
+        let summary = spec.description().unwrap_or(spec.command());
+        let build_note = if spec.requires_build() {
+            " (builds first)".dimmed().to_string()
+        } else {
+            String::new()
+        };
+        let padded = format!("{:width$}", name, width = width);
+        println!("  {}  {}{}", padded.green().bold(), summary, build_note);
+    }
+}
+
+/// Start the script's command in a shell at the package root
+///
+/// Extra arguments are passed as positional parameters rather than spliced
+/// into the command line, so they reach the command without being
+/// re-interpreted by the shell.
+fn spawn(
+    spec: &ScriptSpec,
+    args: &[String],
+    package: &Package,
+    package_root: &Path,
+) -> PackageResult<Child> {
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("cmd");
+        command.arg("/C").arg(spec.command()).args(args);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command
+            .arg("-c")
+            .arg(format!("{} \"$@\"", spec.command()))
+            .arg("sh")
+            .args(args);
+        command
+    };
+
+    command
+        .current_dir(package_root)
+        .env("MANUSCRIPT_PACKAGE_NAME", &package.manifest.package.name)
+        .env(
+            "MANUSCRIPT_PACKAGE_VERSION",
+            &package.manifest.package.version,
+        )
+        .env("MANUSCRIPT_PACKAGE_ROOT", package_root);
+    if let Some(env) = spec.env() {
+        command.envs(env);
+    }
+
+    Ok(command.spawn()?)
+}
+
+/// Wait for the script, passing termination signals on to it
+///
+/// Ctrl-C reaches the script directly because it shares the terminal's
+/// process group, so manuscript only has to outlive it. SIGTERM and SIGHUP
+/// sent to manuscript alone are forwarded.
+#[cfg(unix)]
+async fn wait_forwarding_signals(child: &mut Child) -> PackageResult<ExitStatus> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut hangup = signal(SignalKind::hangup())?;
+
+    loop {
+        let forwarded = tokio::select! {
+            status = child.wait() => return Ok(status?),
+            _ = interrupt.recv() => None,
+            _ = terminate.recv() => Some("TERM"),
+            _ = hangup.recv() => Some("HUP"),
+        };
+        if let (Some(signal), Some(pid)) = (forwarded, child.id()) {
+            // Best effort: the child may already be exiting
+            let _ = process::Command::new("kill")
+                .args(["-s", signal, &pid.to_string()])
+                .status();
+        }
+    }
+}
+
+/// Wait for the script; Ctrl-C reaches it through the shared console
+#[cfg(not(unix))]
+async fn wait_forwarding_signals(child: &mut Child) -> PackageResult<ExitStatus> {
+    loop {
+        tokio::select! {
+            status = child.wait() => return Ok(status?),
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    }
+}
+
+/// Exit code to report for the script, using the shell convention of
+/// 128 + signal number for scripts killed by a signal
+fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
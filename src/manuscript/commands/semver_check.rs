@@ -0,0 +1,168 @@
+/// Check the package's public API against the last published version
+use super::{print_error, print_info, print_success, print_warning};
+use crate::index::SymbolIndex;
+use crate::manuscript;
+use crate::package::{
+    ApiChangeKind, ApiDiff, Package, PackageError, PackageResult, PublicApi, Version,
+};
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub async fn execute(baseline: Option<PathBuf>, baseline_rev: Option<String>) -> PackageResult<()> {
+    let package_root = manuscript::find_package_root(None).ok_or_else(|| {
+        PackageError::ManifestParse(
+            "Not in a Script package directory. Run 'manuscript init' to create a package."
+                .to_string(),
+        )
+    })?;
+    let package = Package::from_manifest_file(package_root.join("script.toml"))?;
+    let current_version = Version::parse(&package.manifest.package.version)?;
+
+    // The published sources are either given directly or checked out from git
+    // into a temporary directory that lives until the comparison is done
+    let checkout;
+    let baseline_root = match baseline {
+        Some(dir) => dir,
+        None => {
+            let rev = match baseline_rev {
+                Some(rev) => rev,
+                None => latest_release_tag(&package_root)?.ok_or_else(|| {
+                    PackageError::Registry(
+                        "No published version found: there is no release tag (e.g. v1.2.0). \
+                         Use --baseline or --baseline-rev to choose one."
+                            .to_string(),
+                    )
+                })?,
+            };
+            print_info(&format!("Comparing against {}", rev.cyan()));
+            checkout = tempfile::tempdir()?;
+            checkout_revision(&package_root, &rev, checkout.path())?;
+            checkout.path().to_path_buf()
+        }
+    };
+
+    let published = Package::from_manifest_file(baseline_root.join("script.toml"))?;
+    let published_version = Version::parse(&published.manifest.package.version)?;
+
+    let old_api = public_api(&baseline_root)?;
+    let new_api = public_api(&package_root)?;
+    let diff = ApiDiff::compare(&old_api, &new_api);
+
+    if diff.changes.is_empty() {
+        print_info(&format!(
+            "Public API unchanged since {} ({} items)",
+            published_version,
+            new_api.len()
+        ));
+    }
+    for change in &diff.changes {
+        let line = change.to_string();
+        match (&change.kind, change.breaking) {
+            (_, true) => println!("  {} {}", "breaking".red().bold(), line),
+            (ApiChangeKind::Added, false) => println!("  {} {}", "+".green(), line),
+            _ => println!("  {} {}", "~".yellow(), line),
+        }
+    }
+
+    let bump = diff.required_bump();
+    let suggested = diff.suggested_version(&published_version);
+    println!();
+    print_info(&format!(
+        "Published version {}; the changes need a {} bump: {} -> {}",
+        published_version,
+        bump.to_string().bold(),
+        published_version,
+        suggested.to_string().green().bold()
+    ));
+
+    if current_version < suggested {
+        if diff.is_breaking() {
+            print_error(&format!(
+                "script.toml version {} is not a valid release after {}: {} breaking change{}",
+                current_version,
+                published_version,
+                diff.breaking_changes().count(),
+                if diff.breaking_changes().count() == 1 {
+                    ""
+                } else {
+                    "s"
+                }
+            ));
+            return Err(PackageError::ManifestParse(format!(
+                "Version must be at least {}",
+                suggested
+            )));
+        }
+        print_warning(&format!(
+            "Set the version in script.toml to {} before publishing",
+            suggested
+        ));
+    } else {
+        print_success(&format!("Version {} is semver-compatible", current_version));
+    }
+
+    Ok(())
+}
+
+/// Public API of the package rooted at `root`, read from its `src` directory
+fn public_api(root: &Path) -> PackageResult<PublicApi> {
+    let src = root.join("src");
+    let mut index = SymbolIndex::new(if src.is_dir() {
+        src
+    } else {
+        root.to_path_buf()
+    });
+    let refresh = index
+        .refresh()
+        .map_err(|e| PackageError::Io(std::io::Error::other(e.to_string())))?;
+    for (path, reason) in &refresh.failed {
+        print_warning(&format!("Skipped {}: {}", path.display(), reason));
+    }
+    Ok(PublicApi::from_index(&index))
+}
+
+/// Highest `vX.Y.Z` (or `X.Y.Z`) tag in the repository, if any
+fn latest_release_tag(root: &Path) -> PackageResult<Option<String>> {
+    let tags = git(root, &["tag", "--list"])?;
+    let tags = String::from_utf8_lossy(&tags);
+    Ok(tags
+        .lines()
+        .filter_map(|tag| {
+            let version = Version::parse(tag.trim().trim_start_matches('v')).ok()?;
+            Some((version, tag.trim().to_string()))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, tag)| tag))
+}
+
+/// Write the manifest and sources of the package at `rev` into `dest`
+fn checkout_revision(root: &Path, rev: &str, dest: &Path) -> PackageResult<()> {
+    // Run from the package root, git lists paths relative to it
+    let files = git(root, &["ls-tree", "-r", "--name-only", rev])?;
+    for file in String::from_utf8_lossy(&files).lines() {
+        if !(file.ends_with(".script") || file == "script.toml") {
+            continue;
+        }
+        let contents = git(root, &["show", &format!("{}:./{}", rev, file)])?;
+        let path = dest.join(file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+fn git(dir: &Path, args: &[&str]) -> PackageResult<Vec<u8>> {
+    let output = Command::new("git").current_dir(dir).args(args).output()?;
+    if !output.status.success() {
+        return Err(PackageError::Io(std::io::Error::other(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(output.stdout)
+}
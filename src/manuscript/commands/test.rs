@@ -0,0 +1,190 @@
+/// Run the tests of a Script package and its workspace members
+use super::build::{lower_module, parse_file};
+use super::{print_error, print_info, print_progress, print_success};
+use crate::compilation::CompilationContext;
+use crate::manuscript;
+use crate::package::{Package, PackageError, PackageResult, Workspace};
+use crate::parser::Program;
+use crate::semantic::SemanticAnalyzer;
+use crate::testing::{
+    junit_report, TestDiscovery, TestResult, TestRunOptions, TestStatus, TestSummary,
+    TestingFramework,
+};
+use colored::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub async fn execute(
+    filter: Option<String>,
+    release: bool,
+    junit: Option<PathBuf>,
+) -> PackageResult<()> {
+    // Find package root
+    let package_root = manuscript::find_package_root(None).ok_or_else(|| {
+        PackageError::ManifestParse(
+            "Not in a Script package directory. Run 'manuscript init' to create a package."
+                .to_string(),
+        )
+    })?;
+    let root = Package::from_manifest_file(package_root.join("script.toml"))?;
+
+    // A workspace is tested along with every member
+    let packages: Vec<Package> = if root.is_workspace() {
+        Workspace::load(&package_root)?
+            .packages()
+            .cloned()
+            .collect()
+    } else {
+        vec![root]
+    };
+
+    let mode = if release { "release" } else { "dev" };
+    let mut suites: Vec<(String, Vec<TestResult>)> = Vec::new();
+    let mut compile_errors = 0;
+
+    for package in &packages {
+        let files = test_files(package)?;
+        if files.is_empty() {
+            continue;
+        }
+        print_progress(
+            "Testing",
+            &format!(
+                "{} v{} ({} profile)",
+                package.manifest.package.name, package.manifest.package.version, mode
+            ),
+        );
+
+        let mut context = CompilationContext::new();
+        context.set_package_root(package.root_path.clone());
+        context.set_release_mode(release);
+        context
+            .apply_manifest(&package.manifest)
+            .map_err(|e| PackageError::ManifestParse(e.to_string()))?;
+
+        for file in files {
+            let relative = file.strip_prefix(&package.root_path).unwrap_or(&file);
+            let suite = format!("{}/{}", package.manifest.package.name, relative.display());
+
+            let program = match compile_tests(package, &file, &context) {
+                Ok(program) => program,
+                Err(error) => {
+                    print_error(&format!("Failed to compile {}: {}", suite.red(), error));
+                    compile_errors += 1;
+                    continue;
+                }
+            };
+
+            let mut discovery = TestDiscovery::new();
+            if let Some(filter) = &filter {
+                discovery = discovery.with_name_filter(filter.clone());
+            }
+            let mut framework = TestingFramework::new()
+                .with_discovery(discovery)
+                .with_run_options(TestRunOptions::default());
+            framework
+                .run_tests(&program)
+                .map_err(|e| PackageError::ManifestParse(format!("{}: {}", suite, e)))?;
+            if !framework.results().is_empty() {
+                suites.push((suite, framework.results().to_vec()));
+            }
+        }
+    }
+
+    if let Some(path) = &junit {
+        let suites: Vec<(&str, &[TestResult])> = suites
+            .iter()
+            .map(|(name, results)| (name.as_str(), results.as_slice()))
+            .collect();
+        fs::write(path, junit_report(&suites))?;
+        print_info(&format!("Wrote JUnit report to {}", path.display()));
+    }
+
+    let results: Vec<TestResult> = suites
+        .into_iter()
+        .flat_map(|(_, results)| results)
+        .collect();
+    let summary = TestSummary::from_results(&results);
+    println!("\n{}", summary);
+
+    let failed = results
+        .iter()
+        .filter(|r| !matches!(r.status, TestStatus::Passed | TestStatus::Skipped(_)))
+        .count();
+    if failed > 0 || compile_errors > 0 {
+        return Err(PackageError::ManifestParse(format!(
+            "{} test(s) failed and {} test file(s) did not compile",
+            failed, compile_errors
+        )));
+    }
+    if results.is_empty() {
+        print_info("No tests found");
+    } else {
+        print_success(&format!("All {} tests passed", summary.passed));
+    }
+    Ok(())
+}
+
+/// The package's files with tests: everything in `tests/` and the sources
+/// that declare `@test` functions
+fn test_files(package: &Package) -> PackageResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let tests_dir = package.root_path.join("tests");
+    if tests_dir.is_dir() {
+        for entry in walkdir::WalkDir::new(&tests_dir) {
+            let entry = entry.map_err(|e| {
+                PackageError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+            })?;
+            if entry.path().extension().and_then(|s| s.to_str()) == Some("script") {
+                files.push(entry.path().to_path_buf());
+            }
+        }
+    }
+
+    for file in &package.source_files {
+        if !files.contains(file) && fs::read_to_string(file)?.contains("@test") {
+            files.push(file.clone());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Parse, analyze and lower the test file at `path` with the context's
+/// profile, so tests only run when they compile
+///
+/// `pkg:` imports of the package's dev-dependencies resolve here like
+/// those of its dependencies.
+fn compile_tests(
+    package: &Package,
+    path: &Path,
+    context: &CompilationContext,
+) -> PackageResult<Program> {
+    let program = parse_file(path, context)?;
+
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.set_edition(context.edition());
+    analyzer.set_current_file(Some(path.to_path_buf()));
+    analyzer.add_module_search_path(package.root_path.clone());
+    analyzer.add_module_search_path(package.root_path.join("src"));
+    if let Some(parent) = path.parent() {
+        analyzer.add_module_search_path(parent.to_path_buf());
+    }
+    analyzer.analyze_program(&program).map_err(|e| {
+        PackageError::ManifestParse(format!("Semantic error in {}: {}", path.display(), e))
+    })?;
+
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("tests");
+    let module = lower_module(&program, name, path, None)?;
+    context
+        .build_profile()
+        .link_and_optimize(vec![module])
+        .map_err(|e| {
+            PackageError::ManifestParse(format!("Link error in {}: {}", path.display(), e))
+        })?;
+
+    Ok(program)
+}
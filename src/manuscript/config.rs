@@ -1,5 +1,5 @@
 /// Configuration management for manuscript
-use crate::package::{PackageError, PackageResult};
+use crate::package::{PackageError, PackageManagerConfig, PackageResult, RetryPolicy};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -39,6 +39,9 @@ pub struct NetworkConfig {
     pub timeout: u64,
     pub retries: u32,
     pub proxy: Option<String>,
+    /// Registry mirrors tried, in order, when a download fails
+    #[serde(default)]
+    pub mirrors: Vec<String>,
 }
 
 impl Default for ManuscriptConfig {
@@ -59,6 +62,7 @@ impl Default for ManuscriptConfig {
                 timeout: 30,
                 retries: 3,
                 proxy: None,
+                mirrors: Vec::new(),
             },
         }
     }
@@ -103,4 +107,14 @@ impl ManuscriptConfig {
     pub fn set_token(&mut self, registry: String, token: String) {
         self.tokens.insert(registry, token);
     }
+
+    /// Package manager settings for the default registry and network options
+    pub fn package_manager_config(&self) -> PackageManagerConfig {
+        PackageManagerConfig {
+            registry_url: self.default_registry.clone(),
+            registry_mirrors: self.network.mirrors.clone(),
+            retry_policy: RetryPolicy::with_retries(self.network.retries),
+            ..PackageManagerConfig::default()
+        }
+    }
 }
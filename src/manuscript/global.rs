@@ -0,0 +1,388 @@
+/// Globally installed packages and their shims
+///
+/// Every installed version of a package lives in `packages/<name>/<version>`
+/// under the manuscript home, and one version per package is active. Each
+/// binary of the active version gets a shim in `bin` that runs its entry
+/// point with the `script` interpreter, so switching versions only rewrites
+/// the shims.
+use crate::package::{PackageError, PackageManifest, PackageResult, Version};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File in a package's directory naming its active version
+const ACTIVE_FILE: &str = "active";
+/// Text in every shim that identifies the package owning it
+const SHIM_MARKER: &str = "manuscript shim for";
+
+/// The global installation directories
+#[derive(Debug, Clone)]
+pub struct GlobalInstalls {
+    packages_dir: PathBuf,
+    bin_dir: PathBuf,
+}
+
+impl GlobalInstalls {
+    /// Open the global installs of the manuscript home, creating its
+    /// directories if needed
+    pub fn open() -> PackageResult<Self> {
+        super::ensure_manuscript_dirs()?;
+        Ok(Self::at(&super::manuscript_home()?))
+    }
+
+    /// Global installs rooted at `home`
+    pub fn at(home: &Path) -> Self {
+        Self {
+            packages_dir: home.join("packages"),
+            bin_dir: home.join("bin"),
+        }
+    }
+
+    /// Directory holding the shims, which users add to their PATH
+    pub fn bin_dir(&self) -> &Path {
+        &self.bin_dir
+    }
+
+    pub fn version_dir(&self, name: &str, version: &Version) -> PathBuf {
+        self.packages_dir.join(name).join(version.to_string())
+    }
+
+    /// Names of all globally installed packages, sorted
+    pub fn packages(&self) -> PackageResult<Vec<String>> {
+        let mut names = Vec::new();
+        if self.packages_dir.is_dir() {
+            for entry in fs::read_dir(&self.packages_dir)? {
+                let entry = entry?;
+                if entry.path().is_dir() && !self.versions(&entry_name(&entry))?.is_empty() {
+                    names.push(entry_name(&entry));
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Installed versions of a package, oldest first
+    pub fn versions(&self, name: &str) -> PackageResult<Vec<Version>> {
+        let dir = self.packages_dir.join(name);
+        let mut versions = Vec::new();
+        if dir.is_dir() {
+            for entry in fs::read_dir(&dir)? {
+                let entry = entry?;
+                if entry.path().is_dir() {
+                    if let Ok(version) = Version::parse(&entry_name(&entry)) {
+                        versions.push(version);
+                    }
+                }
+            }
+        }
+        versions.sort();
+        Ok(versions)
+    }
+
+    pub fn active_version(&self, name: &str) -> PackageResult<Option<Version>> {
+        let path = self.packages_dir.join(name).join(ACTIVE_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Version::parse(fs::read_to_string(path)?.trim()).ok())
+    }
+
+    /// Copy the package at `source` into the store without activating it
+    ///
+    /// Build output and hidden directories are left out. An existing copy of
+    /// the same version is only replaced when `force` is set; returns whether
+    /// the package was copied.
+    pub fn install(
+        &self,
+        source: &Path,
+        manifest: &PackageManifest,
+        force: bool,
+    ) -> PackageResult<bool> {
+        let version = Version::parse(&manifest.package.version)?;
+        let dest = self.version_dir(&manifest.package.name, &version);
+        if dest.exists() {
+            if !force {
+                return Ok(false);
+            }
+            fs::remove_dir_all(&dest)?;
+        }
+
+        let entries = walkdir::WalkDir::new(source)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                !(entry.depth() == 1 && name == "target") && !name.starts_with('.')
+            });
+        for entry in entries {
+            let entry = entry.map_err(|e| PackageError::Io(e.into()))?;
+            let relative = entry.path().strip_prefix(source).unwrap_or(entry.path());
+            let target = dest.join(relative);
+            if entry.file_type().is_dir() {
+                fs::create_dir_all(&target)?;
+            } else {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(entry.path(), &target)?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Make `version` the active version of a package and point its shims at
+    /// it, returning the names of the shims
+    ///
+    /// Fails if a binary name is already taken by another package's shim,
+    /// unless `force` is set.
+    pub fn activate(
+        &self,
+        name: &str,
+        version: &Version,
+        interpreter: &Path,
+        force: bool,
+    ) -> PackageResult<Vec<String>> {
+        let dir = self.version_dir(name, version);
+        let manifest = PackageManifest::from_file(dir.join("script.toml"))?;
+
+        if !force {
+            for bin in &manifest.bin {
+                if let Some(owner) = self.shim_owner(&bin.name)? {
+                    if owner != name {
+                        return Err(PackageError::ManifestParse(format!(
+                            "Binary {} is already installed by {}. Use --force to replace it.",
+                            bin.name, owner
+                        )));
+                    }
+                }
+            }
+        }
+
+        self.remove_shims(name)?;
+        fs::create_dir_all(&self.bin_dir)?;
+        let mut shims = Vec::new();
+        for bin in &manifest.bin {
+            let path = self.shim_path(&bin.name);
+            fs::write(
+                &path,
+                shim_contents(name, version, interpreter, &dir.join(&bin.path)),
+            )?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+            }
+            shims.push(bin.name.clone());
+        }
+
+        fs::write(
+            self.packages_dir.join(name).join(ACTIVE_FILE),
+            version.to_string(),
+        )?;
+        Ok(shims)
+    }
+
+    /// Remove one version of a package, or all of them when `version` is
+    /// `None`
+    ///
+    /// If the active version is removed, the newest remaining version becomes
+    /// active and is returned.
+    pub fn uninstall(
+        &self,
+        name: &str,
+        version: Option<&Version>,
+        interpreter: &Path,
+    ) -> PackageResult<Option<Version>> {
+        let installed = self.versions(name)?;
+        if installed.is_empty() {
+            return Err(PackageError::PackageNotFound {
+                name: name.to_string(),
+            });
+        }
+
+        let version = match version {
+            Some(version) if !installed.contains(version) => {
+                return Err(PackageError::PackageNotFound {
+                    name: format!("{}@{}", name, version),
+                })
+            }
+            Some(version) => version,
+            None => {
+                self.remove_shims(name)?;
+                fs::remove_dir_all(self.packages_dir.join(name))?;
+                return Ok(None);
+            }
+        };
+
+        let was_active = self.active_version(name)?.as_ref() == Some(version);
+        fs::remove_dir_all(self.version_dir(name, version))?;
+
+        let remaining = self.versions(name)?;
+        match remaining.last() {
+            None => {
+                self.remove_shims(name)?;
+                fs::remove_dir_all(self.packages_dir.join(name))?;
+                Ok(None)
+            }
+            Some(newest) if was_active => {
+                self.activate(name, newest, interpreter, true)?;
+                Ok(Some(newest.clone()))
+            }
+            Some(_) => Ok(None),
+        }
+    }
+
+    /// Names of the shims that belong to a package, sorted
+    pub fn shims(&self, name: &str) -> PackageResult<Vec<String>> {
+        let mut shims = Vec::new();
+        if self.bin_dir.is_dir() {
+            for entry in fs::read_dir(&self.bin_dir)? {
+                let entry = entry?;
+                let shim = entry_name(&entry);
+                let shim = shim.strip_suffix(".cmd").unwrap_or(&shim).to_string();
+                if self.shim_owner(&shim)?.as_deref() == Some(name) {
+                    shims.push(shim);
+                }
+            }
+        }
+        shims.sort();
+        Ok(shims)
+    }
+
+    fn remove_shims(&self, name: &str) -> PackageResult<()> {
+        for shim in self.shims(name)? {
+            fs::remove_file(self.shim_path(&shim))?;
+        }
+        Ok(())
+    }
+
+    fn shim_path(&self, bin: &str) -> PathBuf {
+        if cfg!(windows) {
+            self.bin_dir.join(format!("{}.cmd", bin))
+        } else {
+            self.bin_dir.join(bin)
+        }
+    }
+
+    /// Package owning the shim for `bin`, if there is a manuscript shim
+    fn shim_owner(&self, bin: &str) -> PackageResult<Option<String>> {
+        let path = self.shim_path(bin);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path).unwrap_or_default();
+        Ok(contents.lines().find_map(|line| {
+            let (_, rest) = line.split_once(SHIM_MARKER)?;
+            rest.split_whitespace().next().map(str::to_string)
+        }))
+    }
+}
+
+fn entry_name(entry: &fs::DirEntry) -> String {
+    entry.file_name().to_string_lossy().into_owned()
+}
+
+fn shim_contents(name: &str, version: &Version, interpreter: &Path, entry: &Path) -> String {
+    if cfg!(windows) {
+        format!(
+            "@echo off\r\nrem {} {} {}\r\n\"{}\" \"{}\" %*\r\n",
+            SHIM_MARKER,
+            name,
+            version,
+            interpreter.display(),
+            entry.display()
+        )
+    } else {
+        format!(
+            "#!/bin/sh\n# {} {} {}\nexec \"{}\" \"{}\" \"$@\"\n",
+            SHIM_MARKER,
+            name,
+            version,
+            interpreter.display(),
+            entry.display()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_package(dir: &Path, name: &str, version: &str, bin: &str) -> PackageManifest {
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(dir.join("target").join("stale"), "").unwrap();
+        let manifest = format!(
+            "[package]\nname = \"{}\"\nversion = \"{}\"\n\n\
+             [[bin]]\nname = \"{}\"\npath = \"src/main.script\"\n",
+            name, version, bin
+        );
+        fs::write(dir.join("script.toml"), &manifest).unwrap();
+        fs::write(dir.join("src").join("main.script"), "print(\"hi\")\n").unwrap();
+        PackageManifest::from_str(&manifest).unwrap()
+    }
+
+    #[test]
+    fn test_install_and_switch_versions() {
+        let home = TempDir::new().unwrap();
+        let source = TempDir::new().unwrap();
+        let installs = GlobalInstalls::at(home.path());
+        let interpreter = Path::new("script");
+
+        for version in ["1.0.0", "1.1.0"] {
+            let manifest = write_package(source.path(), "fmt", version, "script-fmt");
+            assert!(installs.install(source.path(), &manifest, false).unwrap());
+        }
+        let v1 = Version::parse("1.0.0").unwrap();
+        let v2 = Version::parse("1.1.0").unwrap();
+        assert_eq!(
+            installs.versions("fmt").unwrap(),
+            vec![v1.clone(), v2.clone()]
+        );
+        assert!(!installs.version_dir("fmt", &v1).join("target").exists());
+
+        let shims = installs.activate("fmt", &v1, interpreter, false).unwrap();
+        assert_eq!(shims, vec!["script-fmt".to_string()]);
+        assert_eq!(installs.active_version("fmt").unwrap(), Some(v1.clone()));
+        assert_eq!(installs.shims("fmt").unwrap(), shims);
+
+        installs.activate("fmt", &v2, interpreter, false).unwrap();
+        let shim = fs::read_to_string(installs.shim_path("script-fmt")).unwrap();
+        assert!(shim.contains("1.1.0"));
+
+        // Removing the active version falls back to the newest remaining one
+        let active = installs.uninstall("fmt", Some(&v2), interpreter).unwrap();
+        assert_eq!(active, Some(v1));
+
+        installs.uninstall("fmt", None, interpreter).unwrap();
+        assert!(installs.packages().unwrap().is_empty());
+        assert!(installs.shims("fmt").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_shim_conflict_requires_force() {
+        let home = TempDir::new().unwrap();
+        let installs = GlobalInstalls::at(home.path());
+        let interpreter = Path::new("script");
+        let version = Version::parse("0.1.0").unwrap();
+
+        for name in ["first", "second"] {
+            let source = TempDir::new().unwrap();
+            let manifest = write_package(source.path(), name, "0.1.0", "tool");
+            installs.install(source.path(), &manifest, false).unwrap();
+        }
+
+        installs
+            .activate("first", &version, interpreter, false)
+            .unwrap();
+        assert!(installs
+            .activate("second", &version, interpreter, false)
+            .is_err());
+        installs
+            .activate("second", &version, interpreter, true)
+            .unwrap();
+        assert!(installs.shims("first").unwrap().is_empty());
+        assert_eq!(installs.shims("second").unwrap(), vec!["tool".to_string()]);
+    }
+}
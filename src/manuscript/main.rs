@@ -4,12 +4,17 @@
 /// It provides commands for managing Script packages including:
 /// - init: Initialize a new Script package
 /// - install: Install dependencies
+/// - uninstall, global: Manage globally installed binaries
 /// - build: Build the package
+/// - test: Run the tests of the package and its workspace members
 /// - publish: Publish to registry
 /// - search: Search for packages
-/// - run: Run a script command
-use clap::{Parser, Subcommand};
+/// - run: Run a script from script.toml
+/// - semver-check: Check the public API against the last published version
+/// - completions: Print a shell completion script
+use clap::{CommandFactory, Parser, Subcommand};
 use colored::*;
+use script::completions::{self, Shell};
 use std::path::PathBuf;
 use std::process;
 
@@ -71,9 +76,29 @@ enum Commands {
         #[arg(short, long)]
         force: bool,
 
-        /// Install packages globally
+        /// Install package directories globally, with shims in ~/.manuscript/bin
         #[arg(short, long)]
         global: bool,
+
+        /// Fail if script.lock is missing or out of date instead of updating it
+        #[arg(long)]
+        locked: bool,
+
+        /// Like --locked, and also fail instead of downloading any package
+        #[arg(long)]
+        frozen: bool,
+    },
+
+    /// Remove globally installed packages (`name` or `name@version`)
+    Uninstall {
+        /// Packages to remove
+        packages: Vec<String>,
+    },
+
+    /// Manage globally installed packages
+    Global {
+        #[command(subcommand)]
+        command: GlobalCommands,
     },
 
     /// Build the current package
@@ -82,7 +107,8 @@ enum Commands {
         #[arg(long)]
         release: bool,
 
-        /// Build specific targets
+        /// Build specific targets; `<package>/<target>` picks a target of
+        /// one workspace package
         #[arg(long)]
         target: Vec<String>,
 
@@ -93,6 +119,33 @@ enum Commands {
         /// Clean before building
         #[arg(long)]
         clean: bool,
+
+        /// Fail if the build reads anything but its declared inputs
+        #[arg(long)]
+        hermetic: bool,
+
+        /// Build every package of the workspace
+        #[arg(long)]
+        workspace: bool,
+
+        /// Build only this workspace package (can be repeated)
+        #[arg(short = 'p', long = "package", value_name = "NAME")]
+        package: Vec<String>,
+    },
+
+    /// Run the tests of the package and its workspace members
+    Test {
+        /// Only run tests whose name contains this string
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Compile the tests with the release profile
+        #[arg(long)]
+        release: bool,
+
+        /// Write a JUnit XML report to this file
+        #[arg(long, value_name = "FILE")]
+        junit: Option<PathBuf>,
     },
 
     /// Publish package to registry
@@ -128,12 +181,14 @@ enum Commands {
         full: bool,
     },
 
-    /// Run a script or command
+    /// Run a script from the [scripts] section of script.toml
     Run {
-        /// Script or command to run
+        /// Name of the script to run; lists the scripts when omitted
+        #[arg(add = clap_complete::ArgValueCompleter::new(completions::manifest_scripts))]
         script: Option<String>,
 
         /// Arguments to pass to the script
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
         args: Vec<String>,
 
         /// List available scripts
@@ -167,6 +222,12 @@ enum Commands {
         dry_run: bool,
     },
 
+    /// Host a package registry
+    Registry {
+        #[command(subcommand)]
+        command: RegistryCommands,
+    },
+
     /// Manage package cache
     Cache {
         #[command(subcommand)]
@@ -191,6 +252,17 @@ enum Commands {
         versions: bool,
     },
 
+    /// Compare the public API with the last published version
+    SemverCheck {
+        /// Directory containing the published version's sources
+        #[arg(long, conflicts_with = "baseline_rev")]
+        baseline: Option<PathBuf>,
+
+        /// Git revision of the published version (defaults to the latest release tag)
+        #[arg(long)]
+        baseline_rev: Option<String>,
+    },
+
     /// Create a new Script package from a template
     New {
         /// Package path
@@ -204,6 +276,13 @@ enum Commands {
         #[arg(long)]
         list_templates: bool,
     },
+
+    /// Print a shell completion script
+    Completions {
+        /// Shell to complete for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
 }
 
 #[derive(Subcommand)]
@@ -230,12 +309,44 @@ enum CacheCommands {
     Verify,
 }
 
+#[derive(Subcommand)]
+enum RegistryCommands {
+    /// Serve a registry from a local directory
+    Serve {
+        /// Directory holding the registry (defaults to ~/.manuscript/registry)
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+
+        /// Token that may publish packages (repeatable)
+        #[arg(long = "token")]
+        tokens: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum GlobalCommands {
+    /// List globally installed packages and their versions
+    List,
+
+    /// Switch to another installed version (`name@version`)
+    Use {
+        /// Package and version to use
+        package: String,
+    },
+}
+
 use script::manuscript::commands::{
-    build, cache, info, init, install, new, publish, run, search, update,
+    build, cache, global, info, init, install, new, publish, registry, run, search, semver_check,
+    test, update,
 };
 
 #[tokio::main]
 async fn main() {
+    completions::complete_from_env(Cli::command);
     let cli = Cli::parse();
 
     // Set up colored output
@@ -265,13 +376,28 @@ async fn main() {
             save,
             force,
             global,
-        }) => install::execute(packages, dev, save, force, global).await,
+            locked,
+            frozen,
+        }) => install::execute(packages, dev, save, force, global, locked, frozen).await,
+        Some(Commands::Uninstall { packages }) => global::uninstall(packages).await,
+        Some(Commands::Global { command }) => match command {
+            GlobalCommands::List => global::list().await,
+            GlobalCommands::Use { package } => global::use_version(package).await,
+        },
         Some(Commands::Build {
             release,
             target,
             all,
             clean,
-        }) => build::execute(release, target, all, clean).await,
+            hermetic,
+            workspace,
+            package,
+        }) => build::execute(release, target, all, clean, hermetic, workspace, package).await,
+        Some(Commands::Test {
+            filter,
+            release,
+            junit,
+        }) => test::execute(filter, release, junit).await,
         Some(Commands::Publish {
             registry,
             token,
@@ -288,6 +414,11 @@ async fn main() {
             major,
             dry_run,
         }) => update::execute(packages, all, patch, minor, major, dry_run).await,
+        Some(Commands::Registry { command }) => match command {
+            RegistryCommands::Serve { root, addr, tokens } => {
+                registry::serve(root, addr, tokens).await
+            }
+        },
         Some(Commands::Cache { command }) => match command {
             CacheCommands::Clean { all, older_than } => cache::clean(all, older_than).await,
             CacheCommands::List { size } => cache::list(size).await,
@@ -299,14 +430,21 @@ async fn main() {
             deps,
             versions,
         }) => info::execute(package, version, deps, versions).await,
+        Some(Commands::SemverCheck {
+            baseline,
+            baseline_rev,
+        }) => semver_check::execute(baseline, baseline_rev).await,
         Some(Commands::New {
             path,
             template,
             list_templates,
         }) => new::execute(path, template, list_templates).await,
+        Some(Commands::Completions { shell }) => {
+            completions::write_registration(shell, "manuscript", &mut std::io::stdout())
+                .map_err(Into::into)
+        }
         None => {
             // Show help if no command provided
-            use clap::CommandFactory;
             let _ = Cli::command().print_help();
             println!();
             Ok(())
@@ -9,10 +9,12 @@
 /// - Local package caching
 pub mod commands;
 pub mod config;
+pub mod global;
 pub mod templates;
 pub mod utils;
 
 pub use config::ManuscriptConfig;
+pub use global::GlobalInstalls;
 
 use crate::package::{PackageError, PackageResult};
 use std::path::{Path, PathBuf};
@@ -38,6 +40,11 @@ pub fn global_packages_dir() -> PackageResult<PathBuf> {
     Ok(manuscript_home()?.join("packages"))
 }
 
+/// Get the directory holding shims for globally installed binaries
+pub fn global_bin_dir() -> PackageResult<PathBuf> {
+    Ok(manuscript_home()?.join("bin"))
+}
+
 /// Get the cache directory
 pub fn cache_dir() -> PackageResult<PathBuf> {
     Ok(manuscript_home()?.join("cache"))
@@ -53,6 +60,7 @@ pub fn ensure_manuscript_dirs() -> PackageResult<()> {
     let home = manuscript_home()?;
     std::fs::create_dir_all(&home)?;
     std::fs::create_dir_all(global_packages_dir()?)?;
+    std::fs::create_dir_all(global_bin_dir()?)?;
     std::fs::create_dir_all(cache_dir()?)?;
     Ok(())
 }
@@ -1,4 +1,9 @@
-/// Templates for package initialization
+//! Templates for package initialization
+
+use crate::edition::Edition;
+use crate::package::{PackageError, PackageManifest, PackageResult};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 pub const GITIGNORE_TEMPLATE: &str = r#"# Build artifacts
 /target/
@@ -172,3 +177,242 @@ test = "manuscript test"
 build = "manuscript build --release"
 fmt = "manuscript fmt"
 "#;
+
+/// Kind of project created by `script init` and `manuscript new`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectTemplate {
+    /// A library with `src/lib.script`
+    Library,
+    /// An application with `src/main.script`
+    Binary,
+    /// An application with a frame loop in `src/main.script`
+    Game,
+}
+
+impl ProjectTemplate {
+    pub const ALL: [ProjectTemplate; 3] = [
+        ProjectTemplate::Binary,
+        ProjectTemplate::Library,
+        ProjectTemplate::Game,
+    ];
+
+    /// Look up a template by name, accepting `manuscript new`'s older names
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "bin" | "binary" | "default" | "cli" => Some(ProjectTemplate::Binary),
+            "lib" | "library" => Some(ProjectTemplate::Library),
+            "game" => Some(ProjectTemplate::Game),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProjectTemplate::Library => "lib",
+            ProjectTemplate::Binary => "bin",
+            ProjectTemplate::Game => "game",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ProjectTemplate::Library => "Library package",
+            ProjectTemplate::Binary => "Command-line application",
+            ProjectTemplate::Game => "Game with an update and render loop",
+        }
+    }
+
+    fn source_path(&self) -> &'static str {
+        match self {
+            ProjectTemplate::Library => "src/lib.script",
+            ProjectTemplate::Binary | ProjectTemplate::Game => "src/main.script",
+        }
+    }
+}
+
+pub fn generate_manifest(name: &str, template: ProjectTemplate) -> String {
+    let target = match template {
+        ProjectTemplate::Library => "[lib]\npath = \"src/lib.script\"\n".to_string(),
+        ProjectTemplate::Binary | ProjectTemplate::Game => {
+            format!("[[bin]]\nname = \"{}\"\npath = \"src/main.script\"\n", name)
+        }
+    };
+    format!(
+        r#"[package]
+name = "{}"
+version = "0.1.0"
+edition = "{}"
+
+[dependencies]
+
+{}"#,
+        name,
+        Edition::LATEST,
+        target
+    )
+}
+
+pub fn generate_source(name: &str, template: ProjectTemplate) -> String {
+    match template {
+        ProjectTemplate::Library => format!(
+            r#"// {} - a Script library
+
+/// Greet someone by name
+fn greet(name: string) -> string {{
+    "Hello, " + name + "!"
+}}
+
+/// Add two numbers together
+fn add(a: i32, b: i32) -> i32 {{
+    a + b
+}}
+"#,
+            name
+        ),
+        ProjectTemplate::Binary => format!(
+            r#"// {} - a Script application
+
+fn main() {{
+    print("Hello from {}!")
+}}
+"#,
+            name, name
+        ),
+        ProjectTemplate::Game => format!(
+            r#"// {} - a Script game
+//
+// `update` advances the game by one frame and `render` draws it.
+
+fn update(x: f32, dt: f32) -> f32 {{
+    x + 60.0 * dt
+}}
+
+fn render(frame: i32, x: f32) {{
+    print("frame " + frame + ": player at " + x)
+}}
+
+fn main() {{
+    let dt: f32 = 1.0 / 60.0
+    let mut x: f32 = 0.0
+    for frame in 0..10 {{
+        x = update(x, dt)
+        render(frame, x)
+    }}
+}}
+"#,
+            name
+        ),
+    }
+}
+
+pub fn generate_test(name: &str) -> String {
+    format!(
+        r#"// Tests for {}
+//
+// Run with `script tests/{}_test.script --test`.
+
+@test
+fn test_addition() {{
+    assert_eq(2 + 2, 4)
+}}
+"#,
+        name, name
+    )
+}
+
+/// Create a minimal project named `name` in `dir`
+///
+/// Writes `script.toml`, the source file for the template, a test under
+/// `tests/` and a `.gitignore`, creating `dir` if needed. Files that
+/// already exist are left alone, except that an existing `script.toml` is
+/// an error. Returns the files created, relative to `dir`.
+pub fn scaffold_project(
+    dir: &Path,
+    name: &str,
+    template: ProjectTemplate,
+) -> PackageResult<Vec<PathBuf>> {
+    if dir.join("script.toml").exists() {
+        return Err(PackageError::ManifestParse(format!(
+            "A script.toml file already exists in {}",
+            dir.display()
+        )));
+    }
+
+    // Checks the name before anything is written
+    let manifest = generate_manifest(name, template);
+    PackageManifest::from_str(&manifest)?;
+
+    let test_name = match template {
+        ProjectTemplate::Library => "lib",
+        ProjectTemplate::Binary | ProjectTemplate::Game => "main",
+    };
+    let files = [
+        (PathBuf::from("script.toml"), manifest),
+        (
+            PathBuf::from(template.source_path()),
+            generate_source(name, template),
+        ),
+        (
+            PathBuf::from(format!("tests/{}_test.script", test_name)),
+            generate_test(test_name),
+        ),
+        (PathBuf::from(".gitignore"), GITIGNORE_TEMPLATE.to_string()),
+    ];
+
+    let mut created = Vec::new();
+    for (path, content) in files {
+        let full_path = dir.join(&path);
+        if full_path.exists() {
+            continue;
+        }
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&full_path, content)?;
+        created.push(path);
+    }
+    Ok(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scaffold_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join("space-game");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(project.join("src/main.script"), "fn main() {}\n").unwrap();
+
+        let created = scaffold_project(&project, "space-game", ProjectTemplate::Game).unwrap();
+        assert_eq!(
+            created,
+            vec![
+                PathBuf::from("script.toml"),
+                PathBuf::from("tests/main_test.script"),
+                PathBuf::from(".gitignore"),
+            ]
+        );
+        // Existing sources are kept
+        assert_eq!(
+            fs::read_to_string(project.join("src/main.script")).unwrap(),
+            "fn main() {}\n"
+        );
+        let manifest = PackageManifest::from_file(project.join("script.toml")).unwrap();
+        assert_eq!(manifest.package.name, "space-game");
+        assert_eq!(manifest.bin[0].path, PathBuf::from("src/main.script"));
+
+        assert!(scaffold_project(&project, "space-game", ProjectTemplate::Game).is_err());
+
+        let library = dir.path().join("lib");
+        scaffold_project(&library, "utils", ProjectTemplate::Library).unwrap();
+        let manifest = PackageManifest::from_file(library.join("script.toml")).unwrap();
+        assert!(manifest.lib.is_some() && manifest.bin.is_empty());
+        assert!(library.join("src/lib.script").is_file());
+
+        let invalid = dir.path().join("invalid");
+        assert!(scaffold_project(&invalid, "2fast", ProjectTemplate::Binary).is_err());
+        assert!(!invalid.exists());
+    }
+}
@@ -1,22 +1,162 @@
 //! MCP (Model Context Protocol) Server for Script Language
 //!
-//! This binary provides MCP server functionality for AI model integration.
+//! This binary speaks MCP over stdio: newline-delimited JSON-RPC 2.0
+//! messages on stdin, responses on stdout, logging on stderr. It exposes
+//! the `MCPServer` tools, including `script_analyzer`, `script_run`,
+//! `script_formatter` and `script_diagnostics`. Code passed to
+//! `script_run` is executed under a strict `SecurityPolicy`.
 
-use std::io::{self, Write};
+use script::mcp::protocol::{error_codes, MCP_VERSION};
+use script::mcp::{MCPConfig, MCPServer, SecurityContext};
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
 
 fn main() {
-    println!("Script Language MCP Server v0.5.0-alpha");
-    println!("Model Context Protocol implementation for secure AI integration");
-    println!();
+    eprintln!(
+        "Script Language MCP Server v{} (stdio)",
+        env!("CARGO_PKG_VERSION")
+    );
 
-    // TODO: Implement MCP server functionality
-    // - Protocol handling
-    // - Security sandboxing
-    // - Resource limits
-    // - API endpoints
+    let server = MCPServer::new(MCPConfig::default());
+    let session = match server.open_session(Some("stdio".to_string())) {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("Failed to start MCP session: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    eprintln!("MCP server implementation is pending. This is a placeholder binary.");
-    eprintln!("See src/mcp/ for the planned implementation.");
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                eprintln!("Failed to read from stdin: {}", e);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
 
-    std::process::exit(1);
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&server, &session, &message),
+            Err(e) => Some(error_response(
+                Value::Null,
+                error_codes::PARSE_ERROR,
+                format!("Parse error: {}", e),
+            )),
+        };
+
+        if let Some(response) = response {
+            if writeln!(stdout, "{}", response)
+                .and_then(|_| stdout.flush())
+                .is_err()
+            {
+                break;
+            }
+        }
+    }
+}
+
+/// Handle one JSON-RPC message, returning the response if it needs one
+///
+/// Messages without an `id` are notifications and never get a response.
+fn handle_message(server: &MCPServer, session: &SecurityContext, message: &Value) -> Option<Value> {
+    let id = message.get("id").cloned();
+    let method = message.get("method").and_then(Value::as_str);
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let Some(method) = method else {
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            error_codes::INVALID_REQUEST,
+            "Missing method".to_string(),
+        ));
+    };
+    let Some(id) = id else {
+        // `notifications/initialized`, `notifications/cancelled`, ...
+        return None;
+    };
+
+    let result = match method {
+        "initialize" => Ok(json!({
+            "protocolVersion": params
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .unwrap_or(MCP_VERSION),
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": {
+                "name": "script-mcp",
+                "version": env!("CARGO_PKG_VERSION")
+            }
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => {
+            let tools: Vec<Value> = server
+                .list_tools()
+                .into_iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "inputSchema": tool.input_schema
+                    })
+                })
+                .collect();
+            Ok(json!({ "tools": tools }))
+        }
+        "tools/call" => call_tool(server, session, &params),
+        other => Err((
+            error_codes::METHOD_NOT_FOUND,
+            format!("Method not found: {}", other),
+        )),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, message),
+    })
+}
+
+fn call_tool(
+    server: &MCPServer,
+    session: &SecurityContext,
+    params: &Value,
+) -> Result<Value, (i32, String)> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or((error_codes::INVALID_PARAMS, "Missing tool name".to_string()))?;
+    let empty = serde_json::Map::new();
+    let arguments = params
+        .get("arguments")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    if !server.list_tools().iter().any(|tool| tool.name == name) {
+        return Err((
+            error_codes::INVALID_PARAMS,
+            format!("Unknown tool: {}", name),
+        ));
+    }
+
+    // Failures inside a tool (bad input, security violations) are reported
+    // as tool results so the model can see and react to them
+    Ok(match server.call_tool(name, arguments, session) {
+        Ok(result) => json!({ "content": result.content, "isError": result.is_error }),
+        Err(e) => json!({
+            "content": [{ "type": "text", "text": e.to_string() }],
+            "isError": true
+        }),
+    })
+}
+
+fn error_response(id: Value, code: i32, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message }
+    })
 }
@@ -0,0 +1,172 @@
+//! Sandboxed execution for the `script_run` tool
+//!
+//! Code is executed by the statement-level `DebugInterpreter` rather than
+//! the JIT. The interpreter only provides pure builtins (`print`,
+//! `println`, `len`), so programs have no way to reach the file system,
+//! network, processes or FFI. The remaining `SecurityPolicy` limits are
+//! enforced here: imports are rejected unless file system access is
+//! allowed, execution is aborted after `max_cpu_time`, and captured output
+//! is capped at `max_memory` bytes.
+
+use crate::debugger::{DebugInterpreter, Debugger, PauseState, StepAction, StepController};
+use crate::error::{Error, ErrorKind, Result};
+use crate::lexer::Lexer;
+use crate::parser::{Parser, StmtKind};
+use crate::security::SecurityPolicy;
+use crate::semantic::SemanticAnalyzer;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Result of a sandboxed run
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunOutput {
+    /// Everything the program printed
+    pub output: String,
+    /// Whether output was cut off at the policy's memory limit
+    pub truncated: bool,
+    /// Final value of the program, if it produced one
+    pub value: Option<String>,
+    pub elapsed: Duration,
+}
+
+/// Runs untrusted code under a `SecurityPolicy`
+#[derive(Debug, Clone)]
+pub struct SandboxedRunner {
+    policy: SecurityPolicy,
+}
+
+impl SandboxedRunner {
+    pub fn new(policy: SecurityPolicy) -> Self {
+        Self { policy }
+    }
+
+    pub fn policy(&self) -> &SecurityPolicy {
+        &self.policy
+    }
+
+    /// Check and execute `code`
+    ///
+    /// Lexer, parser and semantic errors are returned as errors, as are
+    /// policy violations and runtime failures.
+    pub fn run(&self, code: &str) -> Result<RunOutput> {
+        let lexer = Lexer::new(code)?;
+        let (tokens, lex_errors) = lexer.scan_tokens();
+        if let Some(error) = lex_errors.into_iter().next() {
+            return Err(error);
+        }
+        let program = Parser::new(tokens).parse()?;
+
+        if !self.policy.allow_file_system
+            && program
+                .statements
+                .iter()
+                .any(|stmt| matches!(stmt.kind, StmtKind::Import { .. }))
+        {
+            return Err(Error::new(
+                ErrorKind::SecurityViolation,
+                "Imports are not allowed: the sandbox policy denies file system access",
+            ));
+        }
+
+        let mut analyzer = SemanticAnalyzer::new();
+        analyzer.analyze_program(&program)?;
+        if let Some(error) = analyzer.errors().first() {
+            return Err(error.clone().into_error());
+        }
+
+        let mut capture = CapturedOutput {
+            output: String::new(),
+            limit: self.policy.max_memory,
+            truncated: false,
+        };
+        let start = Instant::now();
+        // A private debugger keeps sandboxed runs away from any breakpoints
+        // set on the global one
+        let debugger = Arc::new(Debugger::new());
+        let mut interpreter = DebugInterpreter::new(debugger, "<sandbox>", &mut capture);
+        interpreter.set_time_limit(Duration::from_millis(self.policy.max_cpu_time));
+        let value = interpreter.run(&program, false)?;
+
+        Ok(RunOutput {
+            output: capture.output,
+            truncated: capture.truncated,
+            value: value.map(|v| v.to_string()),
+            elapsed: start.elapsed(),
+        })
+    }
+}
+
+/// Collects program output, never pausing
+struct CapturedOutput {
+    output: String,
+    limit: usize,
+    truncated: bool,
+}
+
+impl StepController for CapturedOutput {
+    fn on_pause(&mut self, _state: &PauseState<'_>) -> StepAction {
+        StepAction::Continue
+    }
+
+    fn on_output(&mut self, text: &str) {
+        let remaining = self.limit.saturating_sub(self.output.len());
+        if text.len() <= remaining {
+            self.output.push_str(text);
+            return;
+        }
+
+        let mut end = remaining;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.output.push_str(&text[..end]);
+        self.truncated = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captures_output() {
+        let runner = SandboxedRunner::new(SecurityPolicy::strict());
+        let result = runner.run("let x = 20 + 22;\nprintln(x);\n").unwrap();
+        assert_eq!(result.output, "42\n");
+        assert!(!result.truncated);
+    }
+
+    #[test]
+    fn test_imports_denied_without_file_system() {
+        let runner = SandboxedRunner::new(SecurityPolicy::strict());
+        let error = runner
+            .run("import * as secrets from \"./secrets\"\nprintln(1);\n")
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::SecurityViolation);
+    }
+
+    #[test]
+    fn test_cpu_time_limit() {
+        let policy = SecurityPolicy {
+            max_cpu_time: 20,
+            ..SecurityPolicy::strict()
+        };
+        let error = SandboxedRunner::new(policy)
+            .run("while true {}\n")
+            .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::SecurityViolation);
+    }
+
+    #[test]
+    fn test_output_capped_at_memory_limit() {
+        let policy = SecurityPolicy {
+            max_memory: 4,
+            ..SecurityPolicy::strict()
+        };
+        let result = SandboxedRunner::new(policy)
+            .run("println(123456);\n")
+            .unwrap();
+        assert_eq!(result.output, "1234");
+        assert!(result.truncated);
+    }
+}
@@ -10,11 +10,13 @@ use super::protocol::{
     MCPMethod, MCPNotification, MCPParams, MCPResult, Request, Response, ServerCapabilities, Tool,
     ToolResult,
 };
+use super::runner::SandboxedRunner;
 use super::sandbox::{AnalysisResult, SandboxConfig, SandboxedAnalyzer};
 use super::security::{SecurityContext, SecurityError, SecurityManager, ValidationResult};
 use super::MCPConfig;
 use crate::error::{Error as ScriptError, Result as ScriptResult};
-use crate::{Lexer, Parser};
+use crate::security::SecurityPolicy;
+use crate::{Lexer, Parser, SemanticAnalyzer};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -29,6 +31,8 @@ pub struct MCPServer {
     security_manager: Arc<SecurityManager>,
     /// Sandboxed analyzer for code analysis
     analyzer: Arc<SandboxedAnalyzer>,
+    /// Sandboxed interpreter for the run tool
+    runner: SandboxedRunner,
     /// Server capabilities
     capabilities: ServerCapabilities,
     /// Session tracking
@@ -76,6 +80,14 @@ impl MCPServer {
 
         let analyzer = Arc::new(SandboxedAnalyzer::new(sandbox_config));
 
+        // Evaluated code never gets file system, network, process or FFI
+        // access; only the resource limits come from the configuration
+        let runner = SandboxedRunner::new(SecurityPolicy {
+            max_memory: config.resource_limits.max_memory,
+            max_cpu_time: config.resource_limits.max_cpu_time_ms,
+            ..SecurityPolicy::strict()
+        });
+
         let capabilities = Self::create_server_capabilities();
         let tools = Arc::new(RwLock::new(Self::create_tool_registry()));
 
@@ -83,6 +95,7 @@ impl MCPServer {
             config,
             security_manager,
             analyzer,
+            runner,
             capabilities,
             sessions: Arc::new(RwLock::new(HashMap::new())),
             tools,
@@ -230,6 +243,40 @@ impl MCPServer {
         })
     }
 
+    /// All registered tools, sorted by name
+    pub fn list_tools(&self) -> Vec<Tool> {
+        let mut tools: Vec<Tool> = self.tools.read().unwrap().values().cloned().collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+
+    /// Open a security session for a long-lived client connection
+    pub fn open_session(&self, client_id: Option<String>) -> ScriptResult<SecurityContext> {
+        self.security_manager
+            .create_session(client_id)
+            .map_err(|e| ScriptError::runtime(format!("Failed to create session: {}", e)))
+    }
+
+    /// Invoke a tool outside of the request/response envelope
+    ///
+    /// Used by transports that handle JSON-RPC framing themselves and keep
+    /// one session (from `open_session`) per client.
+    pub fn call_tool(
+        &self,
+        tool_name: &str,
+        arguments: &serde_json::Map<String, Value>,
+        session: &SecurityContext,
+    ) -> ScriptResult<ToolResult> {
+        if !self.tools.read().unwrap().contains_key(tool_name) {
+            return Err(ScriptError::runtime(format!("Unknown tool: {}", tool_name)));
+        }
+
+        self.stats
+            .analysis_requests
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.execute_tool(tool_name, arguments, session)
+    }
+
     /// Handle resource listing
     fn handle_list_resources(&self, request: &Request) -> ScriptResult<Response> {
         self.validate_session(&request.id)?;
@@ -375,6 +422,8 @@ impl MCPServer {
             "script_semantic" => self.execute_script_semantic(code, arguments),
             "script_quality" => self.execute_script_quality(code, arguments),
             "script_dependencies" => self.execute_script_dependencies(code, arguments),
+            "script_run" => self.execute_script_run(code, arguments),
+            "script_diagnostics" => self.execute_script_diagnostics(code, arguments),
             _ => Err(ScriptError::runtime(format!("Unknown tool: {}", tool_name))),
         }
     }
@@ -478,6 +527,94 @@ impl MCPServer {
         })
     }
 
+    /// Execute code in the sandboxed interpreter
+    fn execute_script_run(
+        &self,
+        code: &str,
+        _arguments: &serde_json::Map<String, Value>,
+    ) -> ScriptResult<ToolResult> {
+        match self.runner.run(code) {
+            Ok(run) => {
+                let mut text = format!(
+                    "# Script Output\n\n```\n{}\n```\n\n**Completed in:** {:?}",
+                    run.output.trim_end(),
+                    run.elapsed
+                );
+                if let Some(value) = run.value.filter(|v| v != "null") {
+                    text.push_str(&format!("\n**Result:** {}", value));
+                }
+                if run.truncated {
+                    text.push_str("\n**Note:** output was truncated at the memory limit");
+                }
+                Ok(ToolResult {
+                    content: vec![json!({ "type": "text", "text": text })],
+                    is_error: false,
+                })
+            }
+            Err(e) => {
+                if e.kind == crate::error::ErrorKind::SecurityViolation {
+                    self.stats
+                        .security_violations
+                        .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
+                Ok(ToolResult {
+                    content: vec![json!({
+                        "type": "text",
+                        "text": format!("# Execution Failed\n\n{}", e)
+                    })],
+                    is_error: true,
+                })
+            }
+        }
+    }
+
+    /// Report lexer, parser and semantic diagnostics as structured JSON
+    fn execute_script_diagnostics(
+        &self,
+        code: &str,
+        _arguments: &serde_json::Map<String, Value>,
+    ) -> ScriptResult<ToolResult> {
+        let diagnostic = |error: &ScriptError| {
+            json!({
+                "severity": "error",
+                "kind": format!("{:?}", error.kind),
+                "message": error.message,
+                "notes": error.notes,
+                "line": error.location.map(|l| l.line),
+                "column": error.location.map(|l| l.column),
+            })
+        };
+
+        let mut diagnostics = Vec::new();
+        let lexer = Lexer::new(code)?;
+        let (tokens, lex_errors) = lexer.scan_tokens();
+        diagnostics.extend(lex_errors.iter().map(diagnostic));
+
+        if lex_errors.is_empty() {
+            match Parser::new(tokens).parse() {
+                Ok(program) => {
+                    let mut analyzer = SemanticAnalyzer::new();
+                    if let Err(error) = analyzer.analyze_program(&program) {
+                        diagnostics.push(diagnostic(&error));
+                    }
+                    for error in analyzer.errors() {
+                        diagnostics.push(diagnostic(&error.clone().into_error()));
+                    }
+                }
+                Err(error) => diagnostics.push(diagnostic(&error)),
+            }
+        }
+
+        let report = json!({ "diagnostics": diagnostics });
+        Ok(ToolResult {
+            content: vec![json!({
+                "type": "text",
+                "text": serde_json::to_string_pretty(&report).unwrap_or_default()
+            })],
+            is_error: !diagnostics.is_empty(),
+        })
+    }
+
     /// Execute lexical analysis
     fn execute_script_lexer(
         &self,
@@ -853,6 +990,44 @@ impl MCPServer {
             },
         );
 
+        tools.insert(
+            "script_run".to_string(),
+            Tool {
+                name: "script_run".to_string(),
+                description: "Run Script language code in a sandbox without file system, network or process access and return its output".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "code": {
+                            "type": "string",
+                            "description": "Script language code to run"
+                        }
+                    },
+                    "required": ["code"]
+                }),
+            },
+        );
+
+        tools.insert(
+            "script_diagnostics".to_string(),
+            Tool {
+                name: "script_diagnostics".to_string(),
+                description:
+                    "Report lexer, parser and semantic errors in Script language code as JSON"
+                        .to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "code": {
+                            "type": "string",
+                            "description": "Script language code to check"
+                        }
+                    },
+                    "required": ["code"]
+                }),
+            },
+        );
+
         tools
     }
 
@@ -87,7 +87,7 @@ impl ConstEvaluator {
 
     fn evaluate_literal(&self, lit: &Literal) -> Result<ConstValue> {
         match lit {
-            Literal::Number(n) => Ok(ConstValue::Number(*n)),
+            Literal::Number(n) | Literal::TypedNumber(n, _) => Ok(ConstValue::Number(*n)),
             Literal::String(s) => Ok(ConstValue::String(s.clone())),
             Literal::Boolean(b) => Ok(ConstValue::Boolean(*b)),
             Literal::Null => Err(Error::new(
@@ -0,0 +1,457 @@
+//! Per-function complexity and maintainability metrics
+//!
+//! Metrics are computed from the AST with `parser::Visitor`:
+//!
+//! - **Cyclomatic complexity**: 1 plus one for every branch point (`if`,
+//!   `while`, `for`, each extra `match` arm or guard, `&&`, `||`, each
+//!   `catch` clause and list comprehension filters)
+//! - **Length**: number of source lines spanned by the function
+//! - **Nesting depth**: deepest level of nested control flow in the body
+//! - **Parameter count**
+//!
+//! `script analyze --metrics` reports these for every function in a project
+//! and exits with an error when any function exceeds the configured
+//! `MetricThresholds`, so it can be used as a CI gate.
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::lexer::Lexer;
+use crate::parser::visitor::{walk_expr, walk_stmt};
+use crate::parser::{
+    BinaryOp, Block, Expr, ExprKind, ImplBlock, Method, Param, Parser, Program, Stmt, StmtKind,
+    Visitor,
+};
+use crate::source::Span;
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Metrics for a single function or method
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FunctionMetrics {
+    /// Function name, `Type::method` for methods
+    pub name: String,
+    /// File the function is defined in, relative to the project root
+    pub file: PathBuf,
+    pub line: usize,
+    pub complexity: usize,
+    pub length: usize,
+    pub nesting: usize,
+    pub params: usize,
+}
+
+/// A measured metric
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Metric {
+    Complexity,
+    Length,
+    Nesting,
+    Params,
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Metric::Complexity => write!(f, "cyclomatic complexity"),
+            Metric::Length => write!(f, "length"),
+            Metric::Nesting => write!(f, "nesting depth"),
+            Metric::Params => write!(f, "parameter count"),
+        }
+    }
+}
+
+/// Upper limits for each metric; a function above any limit is a violation
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricThresholds {
+    pub max_complexity: usize,
+    pub max_length: usize,
+    pub max_nesting: usize,
+    pub max_params: usize,
+}
+
+impl Default for MetricThresholds {
+    fn default() -> Self {
+        Self {
+            max_complexity: 10,
+            max_length: 50,
+            max_nesting: 4,
+            max_params: 5,
+        }
+    }
+}
+
+/// A function exceeding one of the thresholds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThresholdViolation {
+    pub function: String,
+    pub file: PathBuf,
+    pub line: usize,
+    pub metric: Metric,
+    pub value: usize,
+    pub limit: usize,
+}
+
+impl fmt::Display for ThresholdViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{} {} has {} {} (limit {})",
+            self.file.display(),
+            self.line,
+            self.function,
+            self.metric,
+            self.value,
+            self.limit
+        )
+    }
+}
+
+impl FunctionMetrics {
+    /// Every threshold this function exceeds
+    pub fn violations(&self, thresholds: &MetricThresholds) -> Vec<ThresholdViolation> {
+        [
+            (
+                Metric::Complexity,
+                self.complexity,
+                thresholds.max_complexity,
+            ),
+            (Metric::Length, self.length, thresholds.max_length),
+            (Metric::Nesting, self.nesting, thresholds.max_nesting),
+            (Metric::Params, self.params, thresholds.max_params),
+        ]
+        .into_iter()
+        .filter(|(_, value, limit)| value > limit)
+        .map(|(metric, value, limit)| ThresholdViolation {
+            function: self.name.clone(),
+            file: self.file.clone(),
+            line: self.line,
+            metric,
+            value,
+            limit,
+        })
+        .collect()
+    }
+}
+
+/// Metrics for every function in a project
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetricsReport {
+    pub functions: Vec<FunctionMetrics>,
+    /// Files that could not be parsed, with the reason
+    #[serde(skip)]
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl MetricsReport {
+    /// Measure every `.script` file under `root`
+    pub fn collect(root: &Path) -> Result<Self> {
+        let mut report = MetricsReport::default();
+
+        for entry in walkdir::WalkDir::new(root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| !crate::index::is_ignored_dir(e.path()))
+        {
+            let entry = entry.map_err(|e| {
+                Error::new(
+                    ErrorKind::FileError,
+                    format!("Failed to walk project directory: {}", e),
+                )
+            })?;
+            let path = entry.path();
+            if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some("script") {
+                continue;
+            }
+
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            let result = fs::read_to_string(path)
+                .map_err(|e| e.to_string())
+                .and_then(|source| measure_source(&source, relative).map_err(|e| e.message));
+            match result {
+                Ok(functions) => report.functions.extend(functions),
+                Err(reason) => report.failed.push((path.to_path_buf(), reason)),
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// All threshold violations, in file order
+    pub fn violations(&self, thresholds: &MetricThresholds) -> Vec<ThresholdViolation> {
+        self.functions
+            .iter()
+            .flat_map(|f| f.violations(thresholds))
+            .collect()
+    }
+
+    /// Render the report as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+/// Parse a source file and measure its functions
+pub fn measure_source(source: &str, file: &Path) -> Result<Vec<FunctionMetrics>> {
+    let lexer = Lexer::new(source)?;
+    let (tokens, errors) = lexer.scan_tokens();
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error.with_file_name(file.to_string_lossy().to_string()));
+    }
+    let program = Parser::new(tokens)
+        .parse()
+        .map_err(|e| e.with_file_name(file.to_string_lossy().to_string()))?;
+    Ok(measure_program(&program, file))
+}
+
+/// Measure every function and method in an already parsed program
+pub fn measure_program(program: &Program, file: &Path) -> Vec<FunctionMetrics> {
+    let mut collector = MetricsCollector {
+        file: file.to_path_buf(),
+        stack: Vec::new(),
+        results: Vec::new(),
+    };
+    collector.visit_program(program);
+    collector.results
+}
+
+/// Function currently being measured
+struct Measuring {
+    metrics: FunctionMetrics,
+    depth: usize,
+}
+
+struct MetricsCollector {
+    file: PathBuf,
+    /// Enclosing functions; nested functions are measured separately
+    stack: Vec<Measuring>,
+    results: Vec<FunctionMetrics>,
+}
+
+impl MetricsCollector {
+    fn measure(&mut self, name: String, params: &[Param], span: Span, body: &Block) {
+        self.stack.push(Measuring {
+            metrics: FunctionMetrics {
+                name,
+                file: self.file.clone(),
+                line: span.start.line,
+                complexity: 1,
+                length: span.end.line.saturating_sub(span.start.line) + 1,
+                nesting: 0,
+                params: params.len(),
+            },
+            depth: 0,
+        });
+        self.visit_block(body);
+        if let Some(done) = self.stack.pop() {
+            self.results.push(done.metrics);
+        }
+    }
+
+    fn add_branches(&mut self, count: usize) {
+        if let Some(current) = self.stack.last_mut() {
+            current.metrics.complexity += count;
+        }
+    }
+
+    /// Visit `f` one nesting level deeper
+    fn nested(&mut self, f: impl FnOnce(&mut Self)) {
+        if let Some(current) = self.stack.last_mut() {
+            current.depth += 1;
+            current.metrics.nesting = current.metrics.nesting.max(current.depth);
+        }
+        f(self);
+        if let Some(current) = self.stack.last_mut() {
+            current.depth -= 1;
+        }
+    }
+}
+
+impl Visitor for MetricsCollector {
+    fn visit_function(&mut self, name: &str, params: &[Param], body: &Block, stmt: &Stmt) {
+        self.measure(name.to_string(), params, stmt.span, body);
+    }
+
+    fn visit_method(&mut self, impl_block: &ImplBlock, method: &Method) {
+        let name = format!("{}::{}", impl_block.type_name, method.name);
+        self.measure(name, &method.params, method.span, &method.body);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        match &stmt.kind {
+            StmtKind::While { condition, body } => {
+                self.add_branches(1);
+                self.visit_expr(condition);
+                self.nested(|v| v.visit_block(body));
+            }
+            StmtKind::For { iterable, body, .. } => {
+                self.add_branches(1);
+                self.visit_expr(iterable);
+                self.nested(|v| v.visit_block(body));
+            }
+            _ => walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.add_branches(1);
+                self.visit_expr(condition);
+                self.nested(|v| v.visit_expr(then_branch));
+                match else_branch.as_deref() {
+                    // `else if` chains stay at the same level
+                    Some(
+                        else_if @ Expr {
+                            kind: ExprKind::If { .. },
+                            ..
+                        },
+                    ) => self.visit_expr(else_if),
+                    Some(other) => self.nested(|v| v.visit_expr(other)),
+                    None => {}
+                }
+            }
+            ExprKind::Match {
+                expr: scrutinee,
+                arms,
+            } => {
+                let guards = arms.iter().filter(|arm| arm.guard.is_some()).count();
+                self.add_branches(arms.len().saturating_sub(1) + guards);
+                self.visit_expr(scrutinee);
+                self.nested(|v| {
+                    for arm in arms {
+                        if let Some(guard) = &arm.guard {
+                            v.visit_expr(guard);
+                        }
+                        v.visit_expr(&arm.body);
+                    }
+                });
+            }
+            ExprKind::TryCatch {
+                try_expr,
+                catch_clauses,
+                finally_block,
+            } => {
+                self.add_branches(catch_clauses.len());
+                self.nested(|v| {
+                    v.visit_expr(try_expr);
+                    for clause in catch_clauses {
+                        if let Some(condition) = &clause.condition {
+                            v.visit_expr(condition);
+                        }
+                        v.visit_block(&clause.handler);
+                    }
+                    if let Some(finally_block) = finally_block {
+                        v.visit_block(finally_block);
+                    }
+                });
+            }
+            ExprKind::Binary {
+                op: BinaryOp::And | BinaryOp::Or,
+                ..
+            } => {
+                self.add_branches(1);
+                walk_expr(self, expr);
+            }
+            ExprKind::ListComprehension {
+                condition: Some(_), ..
+            } => {
+                self.add_branches(1);
+                walk_expr(self, expr);
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measure(source: &str) -> Vec<FunctionMetrics> {
+        measure_source(source, Path::new("game.script")).unwrap()
+    }
+
+    #[test]
+    fn test_straight_line_function() {
+        let metrics = measure("fn add(a: i32, b: i32) -> i32 {\n    a + b\n}");
+        assert_eq!(metrics.len(), 1);
+        let add = &metrics[0];
+        assert_eq!(add.name, "add");
+        assert_eq!(add.complexity, 1);
+        assert_eq!(add.params, 2);
+        assert_eq!(add.nesting, 0);
+        assert_eq!(add.length, 3);
+    }
+
+    #[test]
+    fn test_branches_and_nesting() {
+        let metrics = measure(
+            r#"
+fn classify(xs: [i32]) -> i32 {
+    let total = 0;
+    for x in xs {
+        if x > 0 && x < 10 {
+            while total < 100 {
+                total = total + x;
+            }
+        } else if x < 0 {
+            total = total - 1;
+        } else {
+            total = 0;
+        }
+    }
+    total
+}
+"#,
+        );
+        let classify = &metrics[0];
+        // for + if + && + else-if + while
+        assert_eq!(classify.complexity, 6);
+        // for > if > while
+        assert_eq!(classify.nesting, 3);
+    }
+
+    #[test]
+    fn test_methods_and_match_arms() {
+        let metrics = measure(
+            r#"
+impl Shape {
+    fn sides(self, kind: i32) -> i32 {
+        match kind {
+            0 => 3,
+            1 => 4,
+            _ => 0,
+        }
+    }
+}
+"#,
+        );
+        assert_eq!(metrics[0].name, "Shape::sides");
+        assert_eq!(metrics[0].complexity, 3);
+        assert_eq!(metrics[0].params, 2);
+    }
+
+    #[test]
+    fn test_threshold_violations() {
+        let metrics = measure("fn wide(a: i32, b: i32, c: i32) {}");
+        let thresholds = MetricThresholds {
+            max_params: 2,
+            ..MetricThresholds::default()
+        };
+        let violations = metrics[0].violations(&thresholds);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].metric, Metric::Params);
+        assert_eq!(
+            violations[0].to_string(),
+            "game.script:1 wide has parameter count 3 (limit 2)"
+        );
+        assert!(metrics[0]
+            .violations(&MetricThresholds::default())
+            .is_empty());
+    }
+}
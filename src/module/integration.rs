@@ -1546,7 +1546,11 @@ impl ModuleCompilationPipeline {
         let result = match &type_ann.kind {
             TypeKind::Named(name) => match name.as_str() {
                 "i32" => crate::types::Type::I32,
+                "i64" => crate::types::Type::I64,
+                "u32" => crate::types::Type::U32,
+                "u64" => crate::types::Type::U64,
                 "f32" => crate::types::Type::F32,
+                "f64" => crate::types::Type::F64,
                 "bool" => crate::types::Type::Bool,
                 "string" => crate::types::Type::String,
                 _ => crate::types::Type::Named(name.clone()),
@@ -1745,7 +1749,11 @@ impl ModuleCompilationPipeline {
     fn type_to_type_ann_kind(&self, ty: &crate::types::Type) -> crate::parser::TypeKind {
         match ty {
             crate::types::Type::I32 => crate::parser::TypeKind::Named("i32".to_string()),
+            crate::types::Type::I64 => crate::parser::TypeKind::Named("i64".to_string()),
+            crate::types::Type::U32 => crate::parser::TypeKind::Named("u32".to_string()),
+            crate::types::Type::U64 => crate::parser::TypeKind::Named("u64".to_string()),
             crate::types::Type::F32 => crate::parser::TypeKind::Named("f32".to_string()),
+            crate::types::Type::F64 => crate::parser::TypeKind::Named("f64".to_string()),
             crate::types::Type::Bool => crate::parser::TypeKind::Named("bool".to_string()),
             crate::types::Type::String => crate::parser::TypeKind::Named("string".to_string()),
             crate::types::Type::Unknown => crate::parser::TypeKind::Named("unknown".to_string()),
@@ -4,6 +4,7 @@ mod context;
 mod error;
 mod integration;
 mod integrity;
+mod packages;
 mod path;
 mod path_security;
 mod permissions;
@@ -37,6 +38,7 @@ pub use integrity::{
     TrustLevel as IntegrityTrustLevel, TrustedModuleEntry, TrustedModuleRegistry,
     VerificationRequirements, VerificationResult,
 };
+pub use packages::{installed_packages_dir, PackageImport, PackageLocator, PACKAGE_SCHEME};
 pub use path::{ImportPath, ModulePath, RelativePath};
 pub use path_security::{ModulePathSanitizer, PathSecurityValidator};
 pub use permissions::{
@@ -0,0 +1,379 @@
+//! Imports of third-party packages
+//!
+//! `import "pkg:json"` imports the library of the project's `json`
+//! dependency, and `import "pkg:json/stream"` the `stream` module next to
+//! it. Naming a registry, as in `pkg:@internal/json`, also requires
+//! `script.toml` to take the dependency from that registry, so an import
+//! cannot silently pick up a package of the same name from another one.
+//!
+//! Packages are found through the importing project's `script.toml` rather
+//! than the directory layout: a `path` dependency is read where it lives and
+//! any other dependency where `manuscript install` put it. Code outside a
+//! project can import installed packages, but not name a registry.
+
+use crate::module::path::is_valid_identifier;
+use crate::module::{ModuleError, ModuleErrorKind, ModulePath, ModuleResult};
+use crate::package::{DependencySpec, PackageManifest};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Prefix of import paths naming a package
+pub const PACKAGE_SCHEME: &str = "pkg:";
+
+/// A parsed `pkg:` import path
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct PackageImport {
+    /// Registry the dependency has to come from
+    pub registry: Option<String>,
+    /// Name of the dependency in `script.toml`
+    pub package: String,
+    /// Module inside the package, empty for the package's library
+    pub module: Vec<String>,
+}
+
+impl PackageImport {
+    /// Whether `path` names a package rather than a file
+    pub fn is_package_import(path: &str) -> bool {
+        path.starts_with(PACKAGE_SCHEME)
+    }
+
+    /// Parse `pkg:[@registry/]package[/module...]`
+    pub fn parse(path: &str) -> ModuleResult<Self> {
+        let Some(rest) = path.strip_prefix(PACKAGE_SCHEME) else {
+            return Err(ModuleError::invalid_path(path, "expected 'pkg:'"));
+        };
+
+        let (registry, rest) = match rest.strip_prefix('@') {
+            Some(qualified) => match qualified.split_once('/') {
+                Some((registry, rest)) if is_valid_name(registry) => {
+                    (Some(registry.to_string()), rest)
+                }
+                _ => {
+                    return Err(ModuleError::invalid_path(
+                        path,
+                        "expected a registry name and a package, as in 'pkg:@registry/package'",
+                    ))
+                }
+            },
+            None => (None, rest),
+        };
+
+        let mut parts = rest.split('/');
+        let package = parts.next().unwrap_or_default();
+        if !is_valid_name(package) {
+            return Err(ModuleError::invalid_path(
+                path,
+                format!("invalid package name '{}'", package),
+            ));
+        }
+        let module: Vec<String> = parts.map(str::to_string).collect();
+        if let Some(segment) = module.iter().find(|segment| !is_valid_identifier(segment)) {
+            return Err(ModuleError::invalid_path(
+                path,
+                format!("invalid module name '{}'", segment),
+            ));
+        }
+
+        Ok(Self {
+            registry,
+            package: package.to_string(),
+            module,
+        })
+    }
+
+    /// The module path of the import
+    ///
+    /// Dashes in the package name become underscores, since module path
+    /// segments are identifiers.
+    pub fn module_path(&self) -> ModuleResult<ModulePath> {
+        let mut segments = vec![self.package.replace('-', "_")];
+        segments.extend(self.module.iter().cloned());
+        ModulePath::new(segments, true)
+    }
+}
+
+impl fmt::Display for PackageImport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", PACKAGE_SCHEME)?;
+        if let Some(registry) = &self.registry {
+            write!(f, "@{}/", registry)?;
+        }
+        write!(f, "{}", self.package)?;
+        for segment in &self.module {
+            write!(f, "/{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+/// Package and registry names may also contain dashes
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Finds the files of `pkg:` imports for a project
+#[derive(Debug, Clone, Default)]
+pub struct PackageLocator {
+    /// Directory holding the project's `script.toml`
+    project_root: Option<PathBuf>,
+    manifest: Option<PackageManifest>,
+    /// Where `manuscript install` puts packages
+    installed_dir: Option<PathBuf>,
+}
+
+impl PackageLocator {
+    /// A locator for the project at `project_root`, or for code outside a
+    /// project, looking for installed packages in `installed_dir`
+    pub fn new(
+        project_root: Option<PathBuf>,
+        installed_dir: Option<PathBuf>,
+    ) -> ModuleResult<Self> {
+        let manifest = match &project_root {
+            Some(root) if root.join("script.toml").is_file() => Some(
+                PackageManifest::from_file(root.join("script.toml"))
+                    .map_err(|e| ModuleError::config_error(e.to_string()))?,
+            ),
+            _ => None,
+        };
+        Ok(Self {
+            project_root,
+            manifest,
+            installed_dir,
+        })
+    }
+
+    /// A locator for the project `dir` is in, found by looking for
+    /// `script.toml` in it and its parents
+    pub fn discover(dir: &Path) -> ModuleResult<Self> {
+        let root = dir
+            .ancestors()
+            .find(|dir| dir.join("script.toml").is_file())
+            .map(Path::to_path_buf);
+        Self::new(root, installed_packages_dir())
+    }
+
+    /// The source file `import` refers to
+    pub fn locate(&self, import: &PackageImport) -> ModuleResult<PathBuf> {
+        let package_dir = self.package_dir(import)?;
+        if !package_dir.is_dir() {
+            return Err(ModuleError::new(
+                ModuleErrorKind::NotFound,
+                format!(
+                    "Package '{}' is not installed; run `manuscript install`",
+                    import.package
+                ),
+            )
+            .with_module_path(import.to_string()));
+        }
+
+        let library = library_path(&package_dir);
+        if import.module.is_empty() {
+            return if library.is_file() {
+                Ok(library)
+            } else {
+                Err(ModuleError::not_found(import.to_string()).with_file_path(library))
+            };
+        }
+
+        let module_dir = library.parent().unwrap_or(&package_dir);
+        ModulePath::new(import.module.clone(), true)?
+            .possible_file_paths(module_dir)
+            .into_iter()
+            .find(|path| path.is_file())
+            .ok_or_else(|| ModuleError::not_found(import.to_string()))
+    }
+
+    /// Directory of the package `import` names
+    fn package_dir(&self, import: &PackageImport) -> ModuleResult<PathBuf> {
+        let Some(manifest) = &self.manifest else {
+            if import.registry.is_some() {
+                return Err(ModuleError::config_error(format!(
+                    "'{}' names a registry, which only works in a project whose script.toml lists the dependency",
+                    import
+                )));
+            }
+            return self.installed(&import.package);
+        };
+
+        let spec = manifest
+            .dependencies
+            .get(&import.package)
+            .or_else(|| manifest.dev_dependencies.get(&import.package))
+            .ok_or_else(|| {
+                ModuleError::new(
+                    ModuleErrorKind::NotFound,
+                    format!(
+                        "Package '{}' is not a dependency; add it to [dependencies] in script.toml",
+                        import.package
+                    ),
+                )
+                .with_module_path(import.to_string())
+            })?;
+
+        if let Some(expected) = &import.registry {
+            let registry = match spec {
+                DependencySpec::Simple(_) => None,
+                DependencySpec::Detailed { registry, .. } => registry.as_deref(),
+            };
+            if registry != Some(expected.as_str()) {
+                return Err(ModuleError::config_error(format!(
+                    "Dependency '{}' does not come from registry '{}'; set `registry = \"{}\"` for it in script.toml",
+                    import.package, expected, expected
+                )));
+            }
+        }
+
+        self.dependency_dir(&import.package, spec)
+    }
+
+    /// Directories of every dependency of the project, whether installed
+    /// or not
+    pub fn dependency_dirs(&self) -> ModuleResult<Vec<PathBuf>> {
+        let Some(manifest) = &self.manifest else {
+            return Ok(Vec::new());
+        };
+        manifest
+            .dependencies
+            .iter()
+            .chain(&manifest.dev_dependencies)
+            .map(|(name, spec)| self.dependency_dir(name, spec))
+            .collect()
+    }
+
+    /// Where the dependency `name` is read from
+    fn dependency_dir(&self, name: &str, spec: &DependencySpec) -> ModuleResult<PathBuf> {
+        let (path, package) = match spec {
+            DependencySpec::Simple(_) => (None, None),
+            DependencySpec::Detailed { path, package, .. } => (path.as_ref(), package.as_deref()),
+        };
+        match (path, &self.project_root) {
+            (Some(path), Some(root)) => Ok(root.join(path)),
+            _ => self.installed(package.unwrap_or(name)),
+        }
+    }
+
+    fn installed(&self, package: &str) -> ModuleResult<PathBuf> {
+        self.installed_dir
+            .as_ref()
+            .map(|dir| dir.join(package))
+            .ok_or_else(|| {
+                ModuleError::config_error("Cannot find where manuscript installs packages")
+            })
+    }
+}
+
+/// Where `manuscript install` puts packages
+pub fn installed_packages_dir() -> Option<PathBuf> {
+    crate::manuscript::cache_dir()
+        .ok()
+        .map(|dir| dir.join("packages"))
+}
+
+/// Entry file of the package in `package_dir`, from its `[lib]` table
+fn library_path(package_dir: &Path) -> PathBuf {
+    let relative = PackageManifest::from_file(package_dir.join("script.toml"))
+        .ok()
+        .and_then(|manifest| manifest.lib)
+        .map(|lib| lib.path)
+        .unwrap_or_else(|| PathBuf::from("src/lib.script"));
+    package_dir.join(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_package_imports() {
+        let import = PackageImport::parse("pkg:json").unwrap();
+        assert_eq!(import.package, "json");
+        assert!(import.registry.is_none() && import.module.is_empty());
+
+        let import = PackageImport::parse("pkg:@internal/json-utils/stream/reader").unwrap();
+        assert_eq!(import.registry.as_deref(), Some("internal"));
+        assert_eq!(import.module, vec!["stream", "reader"]);
+        assert_eq!(import.to_string(), "pkg:@internal/json-utils/stream/reader");
+        assert_eq!(
+            import.module_path().unwrap().to_string(),
+            "json_utils.stream.reader"
+        );
+
+        for bad in [
+            "json",
+            "pkg:",
+            "pkg:@internal",
+            "pkg:@/json",
+            "pkg:json/",
+            "pkg:a b",
+        ] {
+            assert!(PackageImport::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_locate_through_manifest() {
+        let project = TempDir::new().unwrap();
+        let installed = TempDir::new().unwrap();
+        fs::write(
+            project.path().join("script.toml"),
+            r#"
+            [package]
+            name = "game"
+            version = "0.1.0"
+
+            [dependencies]
+            json = "1.0"
+            math = { path = "vendor/math" }
+            secret = { version = "2.0", registry = "internal" }
+            "#,
+        )
+        .unwrap();
+        let json = installed.path().join("json/src");
+        fs::create_dir_all(json.join("stream")).unwrap();
+        fs::write(json.join("lib.script"), "").unwrap();
+        fs::write(json.join("stream/mod.script"), "").unwrap();
+        let math = project.path().join("vendor/math");
+        fs::create_dir_all(&math).unwrap();
+        fs::write(
+            math.join("script.toml"),
+            "[package]\nname = \"math\"\nversion = \"0.1.0\"\n\n[lib]\npath = \"math.script\"\n",
+        )
+        .unwrap();
+        fs::write(math.join("math.script"), "").unwrap();
+        fs::create_dir_all(installed.path().join("secret/src")).unwrap();
+        fs::write(installed.path().join("secret/src/lib.script"), "").unwrap();
+
+        let locator = PackageLocator::new(
+            Some(project.path().to_path_buf()),
+            Some(installed.path().to_path_buf()),
+        )
+        .unwrap();
+        let locate = |path: &str| locator.locate(&PackageImport::parse(path).unwrap());
+
+        assert_eq!(locate("pkg:json").unwrap(), json.join("lib.script"));
+        assert_eq!(
+            locate("pkg:json/stream").unwrap(),
+            json.join("stream/mod.script")
+        );
+        assert_eq!(locate("pkg:math").unwrap(), math.join("math.script"));
+        assert!(locate("pkg:@internal/secret").is_ok());
+        assert!(locate("pkg:@internal/json").is_err());
+        assert!(locate("pkg:json/missing").is_err());
+        let error = locate("pkg:yaml").unwrap_err();
+        assert!(error.message.contains("not a dependency"));
+
+        let outside = PackageLocator::new(None, Some(installed.path().to_path_buf())).unwrap();
+        assert!(outside
+            .locate(&PackageImport::parse("pkg:json").unwrap())
+            .is_ok());
+        assert!(outside
+            .locate(&PackageImport::parse("pkg:@internal/secret").unwrap())
+            .is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::module::{ModuleError, ModuleResult};
+use crate::module::{ModuleError, ModuleResult, PackageImport};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::path::{Path, PathBuf};
@@ -183,6 +183,7 @@ pub enum ImportKind {
     Super,    // super.foo
     Crate,    // crate.foo
     Self_,    // self.foo
+    Package,  // pkg:json or pkg:@registry/json
 }
 
 impl ImportPath {
@@ -193,7 +194,10 @@ impl ImportPath {
             return Err(ModuleError::invalid_path(&path_str, "empty import path"));
         }
 
-        let kind = if path_str.starts_with("./") {
+        let kind = if PackageImport::is_package_import(&path_str) {
+            PackageImport::parse(&path_str)?;
+            ImportKind::Package
+        } else if path_str.starts_with("./") {
             ImportKind::Relative
         } else if path_str.starts_with("../") {
             ImportKind::Relative
@@ -221,6 +225,15 @@ impl ImportPath {
             ImportKind::Super => self.resolve_super(current_module),
             ImportKind::Crate => self.resolve_crate(),
             ImportKind::Self_ => self.resolve_self(current_module),
+            ImportKind::Package => PackageImport::parse(&self.path)?.module_path(),
+        }
+    }
+
+    /// The package this path imports, if it is a `pkg:` import
+    pub fn package(&self) -> Option<PackageImport> {
+        match self.kind {
+            ImportKind::Package => PackageImport::parse(&self.path).ok(),
+            _ => None,
         }
     }
 
@@ -337,7 +350,7 @@ impl RelativePath {
 }
 
 /// Check if a string is a valid identifier for module segments
-fn is_valid_identifier(s: &str) -> bool {
+pub(crate) fn is_valid_identifier(s: &str) -> bool {
     if s.is_empty() {
         return false;
     }
@@ -428,6 +441,23 @@ mod tests {
         assert_eq!(resolved.segments(), ["grandparent", "parent", "sibling"]);
     }
 
+    #[test]
+    fn test_import_path_package() {
+        let import = ImportPath::new("pkg:@internal/json/stream").unwrap();
+        assert_eq!(import.kind, ImportKind::Package);
+        assert_eq!(
+            import.package().unwrap().registry.as_deref(),
+            Some("internal")
+        );
+
+        let current = ModulePath::from_string("app.main").unwrap();
+        let resolved = import.resolve(&current).unwrap();
+        assert_eq!(resolved.segments(), ["json", "stream"]);
+
+        assert!(ImportPath::new("pkg:").is_err());
+        assert!(ImportPath::new("./sibling").unwrap().package().is_none());
+    }
+
     #[test]
     fn test_file_path_conversion() {
         let path = ModulePath::from_string("foo.bar").unwrap();
@@ -1,6 +1,6 @@
 use crate::module::{
-    ImportPath, ModuleError, ModuleLoadContext, ModuleMetadata, ModulePath, ModuleResult,
-    ResolvedModule,
+    installed_packages_dir, ImportPath, ModuleError, ModuleLoadContext, ModuleMetadata, ModulePath,
+    ModuleResult, PackageLocator, ResolvedModule,
 };
 use std::collections::HashSet;
 use std::path::{Path, PathBuf};
@@ -106,6 +106,29 @@ impl FileSystemResolver {
         Err(ModuleError::not_found(module_path.to_string()))
     }
 
+    /// Find the file `import_path`, which resolved to `module_path`, refers to
+    ///
+    /// Package imports are found through the project's `script.toml`, and
+    /// everything else through the search paths.
+    pub fn find_import_file(
+        &self,
+        import_path: &ImportPath,
+        module_path: &ModulePath,
+        context: &ModuleLoadContext,
+    ) -> ModuleResult<PathBuf> {
+        let Some(package) = import_path.package() else {
+            return self.find_module_file(module_path, context);
+        };
+        if !self.config.search_external {
+            return Err(ModuleError::config_error(format!(
+                "Cannot import '{}': package imports are disabled",
+                import_path
+            )));
+        }
+        PackageLocator::new(Some(context.package_root.clone()), installed_packages_dir())?
+            .locate(&package)
+    }
+
     fn find_stdlib_module(&self, module_path: &ModulePath) -> ModuleResult<PathBuf> {
         // Remove 'std' prefix for file system lookup
         let segments = &module_path.segments()[1..];
@@ -175,7 +198,7 @@ impl ModuleResolver for FileSystemResolver {
         let module_path = self.resolve_import_path(import_path, context)?;
 
         // Find the file for this module
-        let file_path = self.find_module_file(&module_path, context)?;
+        let file_path = self.find_import_file(import_path, &module_path, context)?;
 
         // Check for circular imports
         if self.visited_paths.contains(&file_path) {
@@ -212,7 +235,8 @@ impl ModuleResolver for FileSystemResolver {
 
     fn module_exists(&self, import_path: &ImportPath, context: &ModuleLoadContext) -> bool {
         if let Ok(module_path) = self.resolve_import_path(import_path, context) {
-            self.find_module_file(&module_path, context).is_ok()
+            self.find_import_file(import_path, &module_path, context)
+                .is_ok()
         } else {
             false
         }
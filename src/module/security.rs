@@ -113,6 +113,21 @@ pub enum ModuleCapability {
     ResourceAllocation { cpu_time: u64, memory: usize },
 }
 
+impl ModuleCapability {
+    /// Whether holding this capability grants `requested`
+    ///
+    /// Access to a directory covers every path inside it.
+    pub fn covers(&self, requested: &ModuleCapability) -> bool {
+        match (self, requested) {
+            (ModuleCapability::FileRead(granted), ModuleCapability::FileRead(path))
+            | (ModuleCapability::FileWrite(granted), ModuleCapability::FileWrite(path)) => {
+                path.starts_with(granted)
+            }
+            _ => self == requested,
+        }
+    }
+}
+
 /// Resource limits for a module
 #[derive(Debug, Clone)]
 pub struct ResourceLimits {
@@ -178,7 +193,11 @@ impl ModuleSecurityContext {
         }
 
         // Then check explicit capabilities
-        if self.capabilities.contains(capability) {
+        if self
+            .capabilities
+            .iter()
+            .any(|granted| granted.covers(capability))
+        {
             Ok(())
         } else {
             Err(ModuleError::security_violation(format!(
@@ -399,6 +418,19 @@ mod tests {
         assert!(ctx
             .check_capability(&ModuleCapability::ProcessSpawn)
             .is_err());
+
+        // Access to a directory covers the files in it
+        let mut ctx = ModuleSecurityContext::new(module_path, TrustLevel::Untrusted);
+        ctx.grant_capability(ModuleCapability::FileRead(PathBuf::from("/pkg/src")))
+            .unwrap();
+        assert!(ctx
+            .check_capability(&ModuleCapability::FileRead(PathBuf::from(
+                "/pkg/src/lib.script"
+            )))
+            .is_ok());
+        assert!(ctx
+            .check_capability(&ModuleCapability::FileRead(PathBuf::from("/pkg/secrets")))
+            .is_err());
     }
 
     #[test]
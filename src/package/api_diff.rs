@@ -0,0 +1,334 @@
+//! Public API comparison for semver checks
+//!
+//! The public API of a package is read from the symbol index: every exported
+//! definition, the fields and variants of exported types, and the methods
+//! implemented on them. Two snapshots are compared item by item, keyed by
+//! qualified name, to find the smallest version bump that is still correct.
+
+use super::Version;
+use crate::index::{IndexedSymbolKind, SymbolIndex};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// A single public item
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiItem {
+    pub kind: IndexedSymbolKind,
+    pub signature: String,
+    /// Qualified name of the type the item belongs to, for fields, variants
+    /// and methods
+    pub container: Option<String>,
+}
+
+/// The public API of a package, keyed by qualified name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PublicApi {
+    items: BTreeMap<String, ApiItem>,
+}
+
+impl PublicApi {
+    /// Extract the public API from a symbol index
+    pub fn from_index(index: &SymbolIndex) -> Self {
+        let exported_types: BTreeSet<&str> = index
+            .symbols()
+            .filter(|(_, s)| {
+                s.exported
+                    && matches!(
+                        s.kind,
+                        IndexedSymbolKind::Struct
+                            | IndexedSymbolKind::Enum
+                            | IndexedSymbolKind::Trait
+                    )
+            })
+            .map(|(_, s)| s.qualified_name.as_str())
+            .collect();
+
+        let mut items = BTreeMap::new();
+        for (_, symbol) in index.symbols() {
+            // Methods are never exported themselves; they are public when the
+            // type they are implemented on is
+            let public = symbol.exported
+                || (symbol.kind == IndexedSymbolKind::Method
+                    && symbol
+                        .container
+                        .as_deref()
+                        .is_some_and(|c| exported_types.contains(c)));
+            if public {
+                items.insert(
+                    symbol.qualified_name.clone(),
+                    ApiItem {
+                        kind: symbol.kind,
+                        signature: symbol.signature.clone(),
+                        container: symbol.container.clone(),
+                    },
+                );
+            }
+        }
+
+        PublicApi { items }
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = (&str, &ApiItem)> {
+        self.items.iter().map(|(name, item)| (name.as_str(), item))
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl FromIterator<(String, ApiItem)> for PublicApi {
+    fn from_iter<I: IntoIterator<Item = (String, ApiItem)>>(iter: I) -> Self {
+        PublicApi {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// How an item changed between two versions
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiChangeKind {
+    Added,
+    Removed,
+    /// The item's signature changed
+    Changed {
+        old: String,
+        new: String,
+    },
+}
+
+/// A change to one public item
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiChange {
+    pub qualified_name: String,
+    pub item_kind: IndexedSymbolKind,
+    pub kind: ApiChangeKind,
+    /// Whether code written against the old API can stop compiling
+    pub breaking: bool,
+}
+
+impl fmt::Display for ApiChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ApiChangeKind::Added => write!(f, "added {} {}", self.item_kind, self.qualified_name),
+            ApiChangeKind::Removed => {
+                write!(f, "removed {} {}", self.item_kind, self.qualified_name)
+            }
+            ApiChangeKind::Changed { old, new } => write!(
+                f,
+                "changed {} {}: `{}` -> `{}`",
+                self.item_kind, self.qualified_name, old, new
+            ),
+        }
+    }
+}
+
+/// Kind of version bump a set of changes requires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VersionBump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl fmt::Display for VersionBump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            VersionBump::Patch => "patch",
+            VersionBump::Minor => "minor",
+            VersionBump::Major => "major",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Differences between two snapshots of a public API
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiDiff {
+    pub changes: Vec<ApiChange>,
+}
+
+impl ApiDiff {
+    /// Compare the API of a published version against a newer one
+    pub fn compare(old: &PublicApi, new: &PublicApi) -> Self {
+        let mut changes = Vec::new();
+
+        for (name, old_item) in &old.items {
+            match new.items.get(name) {
+                None => changes.push(ApiChange {
+                    qualified_name: name.clone(),
+                    item_kind: old_item.kind,
+                    kind: ApiChangeKind::Removed,
+                    breaking: true,
+                }),
+                Some(new_item) if new_item.signature != old_item.signature => {
+                    changes.push(ApiChange {
+                        qualified_name: name.clone(),
+                        item_kind: new_item.kind,
+                        kind: ApiChangeKind::Changed {
+                            old: old_item.signature.clone(),
+                            new: new_item.signature.clone(),
+                        },
+                        breaking: true,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        for (name, new_item) in &new.items {
+            if old.items.contains_key(name) {
+                continue;
+            }
+            // Struct literals must list every field and matches over an enum
+            // must cover every variant, so growing an existing type breaks
+            // downstream code. New methods and new top-level items do not.
+            let breaking = matches!(
+                new_item.kind,
+                IndexedSymbolKind::Field | IndexedSymbolKind::Variant
+            ) && new_item
+                .container
+                .as_ref()
+                .is_some_and(|c| old.items.contains_key(c));
+            changes.push(ApiChange {
+                qualified_name: name.clone(),
+                item_kind: new_item.kind,
+                kind: ApiChangeKind::Added,
+                breaking,
+            });
+        }
+
+        ApiDiff { changes }
+    }
+
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &ApiChange> {
+        self.changes.iter().filter(|c| c.breaking)
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|c| c.breaking)
+    }
+
+    /// Kind of bump the changes require under semver
+    pub fn required_bump(&self) -> VersionBump {
+        if self.is_breaking() {
+            VersionBump::Major
+        } else if self.changes.is_empty() {
+            VersionBump::Patch
+        } else {
+            VersionBump::Minor
+        }
+    }
+
+    /// Smallest version after `published` that is compatible with the changes
+    ///
+    /// Before 1.0.0 every version shifts one place: breaking changes bump the
+    /// minor version and additions bump the patch version.
+    pub fn suggested_version(&self, published: &Version) -> Version {
+        match (self.required_bump(), published.major) {
+            (VersionBump::Major, 0) => published.next_minor(),
+            (VersionBump::Major, _) => published.next_major(),
+            (VersionBump::Minor, 0) | (VersionBump::Patch, _) => published.next_patch(),
+            (VersionBump::Minor, _) => published.next_minor(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(kind: IndexedSymbolKind, signature: &str, container: Option<&str>) -> ApiItem {
+        ApiItem {
+            kind,
+            signature: signature.to_string(),
+            container: container.map(str::to_string),
+        }
+    }
+
+    fn api(items: &[(&str, ApiItem)]) -> PublicApi {
+        items
+            .iter()
+            .map(|(name, item)| (name.to_string(), item.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_additions_need_minor_bump() {
+        let old = api(&[(
+            "lib::add",
+            item(IndexedSymbolKind::Function, "fn add(a: i32) -> i32", None),
+        )]);
+        let mut new = old.clone();
+        new.items.insert(
+            "lib::sub".to_string(),
+            item(IndexedSymbolKind::Function, "fn sub(a: i32) -> i32", None),
+        );
+
+        let diff = ApiDiff::compare(&old, &new);
+        assert!(!diff.is_breaking());
+        assert_eq!(diff.required_bump(), VersionBump::Minor);
+        assert_eq!(
+            diff.suggested_version(&Version::new(1, 2, 3)),
+            Version::new(1, 3, 0)
+        );
+    }
+
+    #[test]
+    fn test_signature_change_is_breaking() {
+        let old = api(&[(
+            "lib::add",
+            item(IndexedSymbolKind::Function, "fn add(a: i32) -> i32", None),
+        )]);
+        let new = api(&[(
+            "lib::add",
+            item(IndexedSymbolKind::Function, "fn add(a: f32) -> f32", None),
+        )]);
+
+        let diff = ApiDiff::compare(&old, &new);
+        assert_eq!(diff.required_bump(), VersionBump::Major);
+        assert_eq!(
+            diff.suggested_version(&Version::new(1, 2, 3)),
+            Version::new(2, 0, 0)
+        );
+        assert_eq!(
+            diff.suggested_version(&Version::new(0, 4, 1)),
+            Version::new(0, 5, 0)
+        );
+    }
+
+    #[test]
+    fn test_new_variant_on_existing_enum_is_breaking() {
+        let old = api(&[(
+            "lib::Color",
+            item(IndexedSymbolKind::Enum, "enum Color", None),
+        )]);
+        let mut new = old.clone();
+        new.items.insert(
+            "lib::Color::Red".to_string(),
+            item(IndexedSymbolKind::Variant, "Color::Red", Some("lib::Color")),
+        );
+
+        let diff = ApiDiff::compare(&old, &new);
+        assert!(diff.is_breaking());
+        assert_eq!(diff.breaking_changes().count(), 1);
+    }
+
+    #[test]
+    fn test_unchanged_api_needs_patch_bump() {
+        let old = api(&[(
+            "lib::Point",
+            item(IndexedSymbolKind::Struct, "struct Point", None),
+        )]);
+        let diff = ApiDiff::compare(&old, &old.clone());
+        assert!(diff.changes.is_empty());
+        assert_eq!(
+            diff.suggested_version(&Version::new(0, 1, 0)),
+            Version::new(0, 1, 1)
+        );
+    }
+}
@@ -57,6 +57,11 @@ pub enum DependencySpec {
 
         /// Target platform specification
         target: Option<String>,
+
+        /// Take the rest of the specification from the workspace's
+        /// `[workspace.dependencies]`
+        #[serde(default)]
+        workspace: bool,
     },
 }
 
@@ -93,7 +98,14 @@ impl DependencySpec {
                 optional,
                 default_features,
                 target,
+                workspace,
             } => {
+                if *workspace {
+                    return Err(PackageError::ManifestParse(format!(
+                        "Dependency '{}' is inherited from a workspace, but the package is not in one",
+                        name
+                    )));
+                }
                 let actual_name = package.as_ref().unwrap_or(&name.to_string()).clone();
 
                 let kind = if let Some(git_url) = git {
@@ -243,6 +255,14 @@ impl Dependency {
             }
         }
     }
+
+    /// Where the dependency comes from, as `script.lock` records it
+    pub fn source(&self) -> String {
+        match &self.kind {
+            DependencyKind::Registry => "registry".to_string(),
+            DependencyKind::Git { .. } | DependencyKind::Path { .. } => self.id(),
+        }
+    }
 }
 
 /// Types of dependency sources
@@ -426,6 +446,22 @@ impl DependencyGraph {
         &self.build_order
     }
 
+    /// Every package in the build order with the dependency that brings it
+    /// in, preferring the one the root declares
+    pub fn packages(&self) -> Vec<&Dependency> {
+        self.build_order
+            .iter()
+            .filter_map(|name| {
+                self.dependencies
+                    .get("root")
+                    .into_iter()
+                    .chain(self.dependencies.values())
+                    .flatten()
+                    .find(|dependency| &dependency.name == name)
+            })
+            .collect()
+    }
+
     /// Check if the graph is empty
     pub fn is_empty(&self) -> bool {
         self.dependencies.is_empty()
@@ -626,6 +662,7 @@ mod tests {
             optional: false,
             default_features: true,
             target: None,
+            workspace: false,
         };
 
         assert!(!spec.is_registry());
@@ -652,6 +689,7 @@ mod tests {
             optional: false,
             default_features: true,
             target: None,
+            workspace: false,
         };
 
         assert!(!spec.is_registry());
@@ -1,11 +1,108 @@
 /// HTTP client implementation for registry operations
 use super::{PackageError, PackageResult};
-use reqwest::blocking::{Client, ClientBuilder, Response};
+use reqwest::blocking::{Client, ClientBuilder, RequestBuilder, Response};
+use reqwest::header::RANGE;
+use reqwest::StatusCode;
 use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::thread;
 use std::time::Duration;
 
+/// How requests that fail with transient errors are retried
+///
+/// Connection failures, timeouts, interrupted transfers and 408, 429 and
+/// 5xx responses are transient. The delay doubles after every retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Number of retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between retries
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// The default backoff with a different number of retries
+    pub fn with_retries(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `retry`, counting from 1
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// A failed request attempt and whether trying again might succeed
+struct Failure {
+    error: PackageError,
+    transient: bool,
+}
+
+impl Failure {
+    fn fatal(error: PackageError) -> Self {
+        Self {
+            error,
+            transient: false,
+        }
+    }
+
+    fn request(context: &str, error: reqwest::Error) -> Self {
+        Self {
+            transient: error.is_timeout() || error.is_connect() || error.is_body(),
+            error: PackageError::Registry(format!("{}: {}", context, error)),
+        }
+    }
+
+    fn status(response: Response) -> Self {
+        let status = response.status();
+        let error_text = response
+            .text()
+            .unwrap_or_else(|_| "Unknown error".to_string());
+
+        Self {
+            transient: status.is_server_error()
+                || status == StatusCode::TOO_MANY_REQUESTS
+                || status == StatusCode::REQUEST_TIMEOUT,
+            error: PackageError::Registry(format!(
+                "Request failed with status {}: {}",
+                status, error_text
+            )),
+        }
+    }
+}
+
+/// Blocking HTTP client for registries and remote build caches
 pub struct HttpClient {
     client: Client,
+    retry: RetryPolicy,
 }
 
 impl HttpClient {
@@ -20,16 +117,30 @@ impl HttpClient {
             .build()
             .map_err(|e| PackageError::Registry(format!("Failed to build HTTP client: {}", e)))?;
 
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            retry: RetryPolicy::default(),
+        })
     }
 
-    pub fn get(&self, url: &str) -> PackageResult<Vec<u8>> {
-        let response =
-            self.client.get(url).send().map_err(|e| {
-                PackageError::Registry(format!("Failed to send GET request: {}", e))
-            })?;
+    /// Set how transient failures of idempotent requests are retried
+    pub fn set_retry_policy(&mut self, retry: RetryPolicy) {
+        self.retry = retry;
+    }
 
-        self.handle_response(response)
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry
+    }
+
+    pub fn get(&self, url: &str) -> PackageResult<Vec<u8>> {
+        self.retrying(|| {
+            let response = self
+                .client
+                .get(url)
+                .send()
+                .map_err(|e| Failure::request("Failed to send GET request", e))?;
+            Self::read_response(response)
+        })
     }
 
     pub fn get_with_headers(
@@ -37,17 +148,46 @@ impl HttpClient {
         url: &str,
         headers: HashMap<String, String>,
     ) -> PackageResult<Vec<u8>> {
-        let mut request = self.client.get(url);
-
-        for (key, value) in headers {
-            request = request.header(key, value);
-        }
+        self.retrying(|| {
+            let response = with_headers(self.client.get(url), &headers)
+                .send()
+                .map_err(|e| Failure::request("Failed to send GET request with headers", e))?;
+            Self::read_response(response)
+        })
+    }
 
-        let response = request.send().map_err(|e| {
-            PackageError::Registry(format!("Failed to send GET request with headers: {}", e))
-        })?;
+    /// GET `url`, or `None` when the server does not have it
+    pub fn get_if_found(
+        &self,
+        url: &str,
+        headers: &HashMap<String, String>,
+    ) -> PackageResult<Option<Vec<u8>>> {
+        self.retrying(|| {
+            let response = with_headers(self.client.get(url), headers)
+                .send()
+                .map_err(|e| Failure::request("Failed to send GET request", e))?;
+            if response.status() == StatusCode::NOT_FOUND {
+                return Ok(None);
+            }
+            Self::read_response(response).map(Some)
+        })
+    }
 
-        self.handle_response(response)
+    /// PUT `body` at `url`; PUT is idempotent, so transient failures are
+    /// retried
+    pub fn put_with_headers(
+        &self,
+        url: &str,
+        body: &[u8],
+        headers: &HashMap<String, String>,
+    ) -> PackageResult<()> {
+        self.retrying(|| {
+            let response = with_headers(self.client.put(url), headers)
+                .body(body.to_vec())
+                .send()
+                .map_err(|e| Failure::request("Failed to send PUT request", e))?;
+            Self::read_response(response).map(|_| ())
+        })
     }
 
     pub fn post(&self, url: &str, body: Vec<u8>) -> PackageResult<Vec<u8>> {
@@ -83,12 +223,17 @@ impl HttpClient {
     }
 
     pub fn head(&self, url: &str) -> PackageResult<bool> {
-        let response =
-            self.client.head(url).send().map_err(|e| {
-                PackageError::Registry(format!("Failed to send HEAD request: {}", e))
-            })?;
-
-        Ok(response.status().is_success())
+        self.retrying(|| {
+            let response = self
+                .client
+                .head(url)
+                .send()
+                .map_err(|e| Failure::request("Failed to send HEAD request", e))?;
+            if response.status().is_server_error() {
+                return Err(Failure::status(response));
+            }
+            Ok(response.status().is_success())
+        })
     }
 
     pub fn download_with_progress<F>(
@@ -114,7 +259,6 @@ impl HttpClient {
         let mut buffer = Vec::new();
         let mut downloaded = 0u64;
 
-        use std::io::Read;
         let mut chunk = [0u8; 8192];
 
         loop {
@@ -137,6 +281,130 @@ impl HttpClient {
         Ok(buffer)
     }
 
+    /// Download `url` into the file at `partial`, resuming from any bytes it
+    /// already holds
+    ///
+    /// Interrupted transfers are retried with a `Range` request for the
+    /// remaining bytes, so a partial file left behind by an earlier run is
+    /// picked up too. Servers that ignore the range restart the download.
+    /// The partial file is removed once the whole body has been read.
+    pub fn download_resumable<F>(
+        &self,
+        url: &str,
+        headers: &HashMap<String, String>,
+        partial: &Path,
+        mut progress_callback: F,
+    ) -> PackageResult<Vec<u8>>
+    where
+        F: FnMut(u64, u64),
+    {
+        if let Some(parent) = partial.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        self.retrying(|| self.download_range(url, headers, partial, &mut progress_callback))?;
+
+        let data = fs::read(partial)?;
+        fs::remove_file(partial)?;
+        Ok(data)
+    }
+
+    /// One attempt at fetching the bytes of `url` missing from `partial`
+    fn download_range(
+        &self,
+        url: &str,
+        headers: &HashMap<String, String>,
+        partial: &Path,
+        progress_callback: &mut dyn FnMut(u64, u64),
+    ) -> Result<(), Failure> {
+        let offset = fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+
+        let mut request = with_headers(self.client.get(url), headers);
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request
+            .send()
+            .map_err(|e| Failure::request("Failed to send download request", e))?;
+
+        let status = response.status();
+        let (file, mut downloaded) = match status {
+            StatusCode::PARTIAL_CONTENT => (OpenOptions::new().append(true).open(partial), offset),
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                // The partial file no longer matches what the server has, so
+                // start over from the first byte
+                fs::remove_file(partial).map_err(|e| Failure::fatal(e.into()))?;
+                return Err(Failure {
+                    error: PackageError::Registry(format!(
+                        "Server rejected resuming the download of {}",
+                        url
+                    )),
+                    transient: true,
+                });
+            }
+            status if status.is_success() => (File::create(partial), 0),
+            _ => return Err(Failure::status(response)),
+        };
+        let mut file = file.map_err(|e| Failure::fatal(e.into()))?;
+        let total_size = response
+            .content_length()
+            .map(|len| len + downloaded)
+            .unwrap_or(0);
+
+        let mut chunk = [0u8; 8192];
+        loop {
+            let n = response.read(&mut chunk).map_err(|e| Failure {
+                error: PackageError::Registry(format!("Download read error: {}", e)),
+                transient: true,
+            })?;
+            if n == 0 {
+                break;
+            }
+            file.write_all(&chunk[..n])
+                .map_err(|e| Failure::fatal(e.into()))?;
+            downloaded += n as u64;
+            progress_callback(downloaded, total_size);
+        }
+
+        if total_size > 0 && downloaded < total_size {
+            return Err(Failure {
+                error: PackageError::Registry(format!(
+                    "Download ended after {} of {} bytes",
+                    downloaded, total_size
+                )),
+                transient: true,
+            });
+        }
+        Ok(())
+    }
+
+    /// Run `attempt` until it succeeds, fails permanently or the retry
+    /// policy gives up
+    fn retrying<T>(&self, mut attempt: impl FnMut() -> Result<T, Failure>) -> PackageResult<T> {
+        let mut retry = 0;
+        loop {
+            match attempt() {
+                Ok(value) => return Ok(value),
+                Err(failure) if failure.transient && retry < self.retry.max_retries => {
+                    retry += 1;
+                    thread::sleep(self.retry.backoff(retry));
+                }
+                Err(failure) => return Err(failure.error),
+            }
+        }
+    }
+
+    fn read_response(response: Response) -> Result<Vec<u8>, Failure> {
+        if response.status().is_success() {
+            response
+                .bytes()
+                .map(|b| b.to_vec())
+                .map_err(|e| Failure::request("Failed to read response body", e))
+        } else {
+            Err(Failure::status(response))
+        }
+    }
+
     fn handle_response(&self, response: Response) -> PackageResult<Vec<u8>> {
         if response.status().is_success() {
             response
@@ -157,6 +425,13 @@ impl HttpClient {
     }
 }
 
+fn with_headers(mut request: RequestBuilder, headers: &HashMap<String, String>) -> RequestBuilder {
+    for (key, value) in headers {
+        request = request.header(key, value);
+    }
+    request
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,4 +444,20 @@ mod tests {
         let client_with_timeout = HttpClient::with_timeout(Duration::from_secs(60));
         assert!(client_with_timeout.is_ok());
     }
+
+    #[test]
+    fn test_retry_backoff_doubles_up_to_cap() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_secs(1));
+        assert_eq!(policy.backoff(40), Duration::from_secs(1));
+        assert_eq!(RetryPolicy::none().max_retries, 0);
+    }
 }
@@ -3,10 +3,20 @@
 /// This module handles the parsing of Script package manifests, which define
 /// package metadata, dependencies, build configuration, and project structure.
 use super::{DependencySpec, PackageError, PackageMetadata, PackageResult, Version};
+use crate::edition::{self, Edition};
+use crate::lint::LintLevel;
+use crate::stdlib::walk::{glob, Glob};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+/// Platform names accepted in `platforms`; `unix` covers every platform but
+/// Windows and WebAssembly
+pub const PLATFORMS: &[&str] = &["linux", "macos", "windows", "unix", "wasm"];
+
+/// Capability names accepted in `capabilities`
+pub const CAPABILITIES: &[&str] = &["network", "fs"];
+
 /// Complete package manifest structure representing script.toml
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PackageManifest {
@@ -38,6 +48,13 @@ pub struct PackageManifest {
 
     #[serde(default)]
     pub profile: HashMap<String, ProfileConfig>,
+
+    #[serde(default)]
+    pub scripts: HashMap<String, ScriptSpec>,
+
+    /// Levels for `script lint`, by lint name
+    #[serde(default)]
+    pub lints: HashMap<String, LintLevel>,
 }
 
 impl PackageManifest {
@@ -50,6 +67,10 @@ impl PackageManifest {
                 authors: Vec::new(),
                 description: None,
                 edition: default_edition(),
+                language_version: None,
+                min_script_version: None,
+                platforms: Vec::new(),
+                capabilities: Vec::new(),
                 license: None,
                 license_file: None,
                 repository: None,
@@ -57,6 +78,7 @@ impl PackageManifest {
                 documentation: None,
                 keywords: Vec::new(),
                 categories: Vec::new(),
+                badges: HashMap::new(),
                 readme: None,
                 include: Vec::new(),
                 exclude: Vec::new(),
@@ -73,6 +95,8 @@ impl PackageManifest {
             workspace: None,
             target: HashMap::new(),
             profile: HashMap::new(),
+            scripts: HashMap::new(),
+            lints: HashMap::new(),
         }
     }
 
@@ -127,6 +151,15 @@ impl PackageManifest {
             .parse::<semver::Version>()
             .map_err(|e| PackageError::ManifestParse(format!("Invalid version: {}", e)))?;
 
+        Edition::parse(&self.package.edition)
+            .map_err(|e| PackageError::ManifestParse(e.message))?;
+        if let Some(ref requirement) = self.package.language_version {
+            requirement.parse::<semver::VersionReq>().map_err(|e| {
+                PackageError::ManifestParse(format!("Invalid language_version: {}", e))
+            })?;
+        }
+        self.validate_requirements()?;
+
         // Validate library path if present
         if let Some(ref lib) = self.lib {
             if lib.path.as_os_str().is_empty() {
@@ -174,9 +207,153 @@ impl PackageManifest {
             }
         }
 
+        // Validate profiles
+        for (name, profile) in &self.profile {
+            if !PROFILES.contains(&name.as_str()) {
+                return Err(PackageError::ManifestParse(format!(
+                    "Unknown profile '{}', expected one of: {}",
+                    name,
+                    PROFILES.join(", ")
+                )));
+            }
+            if let Some(level) = profile.opt_level {
+                if level > 3 {
+                    return Err(PackageError::ManifestParse(format!(
+                        "Profile {} opt-level must be between 0 and 3, found {}",
+                        name, level
+                    )));
+                }
+            }
+        }
+
+        // Validate scripts
+        for (name, script) in &self.scripts {
+            if name.is_empty() || name.chars().any(char::is_whitespace) {
+                return Err(PackageError::ManifestParse(format!(
+                    "Invalid script name: '{}'",
+                    name
+                )));
+            }
+            if script.command().trim().is_empty() {
+                return Err(PackageError::ManifestParse(format!(
+                    "Script {} command cannot be empty",
+                    name
+                )));
+            }
+        }
+
         Ok(())
     }
 
+    /// Validate `min_script_version`, `platforms`, `capabilities` and
+    /// `badges`, and that they can be satisfied together
+    fn validate_requirements(&self) -> PackageResult<()> {
+        let package = &self.package;
+        if let Some(ref minimum) = package.min_script_version {
+            let minimum = minimum.parse::<semver::Version>().map_err(|e| {
+                PackageError::ManifestParse(format!("Invalid min_script_version: {}", e))
+            })?;
+            if let Some(ref requirement) = package.language_version {
+                let parsed = requirement.parse::<semver::VersionReq>()?;
+                if !allows_version_from(&parsed, &minimum) {
+                    return Err(PackageError::ManifestParse(format!(
+                        "language_version {} excludes every release from min_script_version {}",
+                        requirement, minimum
+                    )));
+                }
+            }
+        }
+
+        check_names("platform", &package.platforms, PLATFORMS)?;
+        check_names("capability", &package.capabilities, CAPABILITIES)?;
+        if !package.platforms.is_empty() && package.platforms.iter().all(|p| p == "wasm") {
+            if let Some(capability) = package.capabilities.first() {
+                return Err(PackageError::ManifestParse(format!(
+                    "Packages that only support wasm cannot require the '{}' capability",
+                    capability
+                )));
+            }
+        }
+
+        for (name, url) in &package.badges {
+            if name.is_empty() || url.trim().is_empty() {
+                return Err(PackageError::ManifestParse(format!(
+                    "Badge '{}' needs a name and an image URL",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the package runs on `platform`; no `platforms` means any
+    pub fn supports_platform(&self, platform: &str) -> bool {
+        let platforms = &self.package.platforms;
+        platforms.is_empty()
+            || platforms.iter().any(|declared| {
+                declared == platform
+                    || (declared == "unix" && platform != "windows" && platform != "wasm")
+            })
+    }
+
+    /// Check that the package can be built on this machine: the compiler is
+    /// at least `min_script_version` and the host is one of `platforms`
+    pub fn check_host(&self) -> PackageResult<()> {
+        if let Some(ref minimum) = self.package.min_script_version {
+            edition::check_min_script_version(minimum)
+                .map_err(|e| PackageError::Incompatible(e.message))?;
+        }
+
+        let host = host_platform();
+        if !self.supports_platform(host) {
+            return Err(PackageError::Incompatible(format!(
+                "{} supports {}, but this machine runs {}",
+                self.package.name,
+                self.package.platforms.join(", "),
+                host
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check that this package can depend on `dependency`: the dependency
+    /// supports every platform this package lists and needs no capability
+    /// this package does not declare
+    pub fn check_dependency(&self, dependency: &PackageManifest) -> PackageResult<()> {
+        let name = &dependency.package.name;
+        if let Some(capability) = dependency
+            .package
+            .capabilities
+            .iter()
+            .find(|capability| !self.package.capabilities.contains(capability))
+        {
+            return Err(PackageError::Incompatible(format!(
+                "{} requires the '{}' capability, which {} does not declare in `capabilities`",
+                name, capability, self.package.name
+            )));
+        }
+
+        if let Some(platform) = self
+            .package
+            .platforms
+            .iter()
+            .find(|platform| !dependency.supports_platform(platform))
+        {
+            return Err(PackageError::Incompatible(format!(
+                "{} does not support {}, which {} lists in `platforms`",
+                name, platform, self.package.name
+            )));
+        }
+        Ok(())
+    }
+
+    /// Language edition the package is written for
+    ///
+    /// Falls back to the default edition for names `validate` would reject.
+    pub fn edition(&self) -> Edition {
+        Edition::parse(&self.package.edition).unwrap_or_default()
+    }
+
     /// Get package metadata
     pub fn metadata(&self) -> PackageMetadata {
         let version = Version::parse(&self.package.version).unwrap_or_default();
@@ -241,6 +418,20 @@ pub struct PackageConfig {
     #[serde(default = "default_edition")]
     pub edition: String,
 
+    /// Semver requirement on the compiler, e.g. `">=0.5"`
+    pub language_version: Option<String>,
+
+    /// Oldest compiler release the package builds with, e.g. `"0.5.0"`
+    pub min_script_version: Option<String>,
+
+    /// Platforms the package runs on, from `PLATFORMS`; empty means any
+    #[serde(default)]
+    pub platforms: Vec<String>,
+
+    /// Capabilities the package needs at run time, from `CAPABILITIES`
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+
     pub license: Option<String>,
 
     #[serde(rename = "license-file")]
@@ -256,6 +447,10 @@ pub struct PackageConfig {
     #[serde(default)]
     pub categories: Vec<String>,
 
+    /// Badges shown with the package, as name to image URL
+    #[serde(default)]
+    pub badges: HashMap<String, String>,
+
     #[serde(default)]
     pub readme: Option<PathBuf>,
 
@@ -272,6 +467,61 @@ pub struct PackageConfig {
     pub metadata: HashMap<String, toml::Value>,
 }
 
+/// Platform name of the machine the compiler runs on, as used in
+/// `platforms`
+pub fn host_platform() -> &'static str {
+    if cfg!(target_family = "wasm") {
+        "wasm"
+    } else {
+        std::env::consts::OS
+    }
+}
+
+fn check_names(kind: &str, names: &[String], known: &[&str]) -> PackageResult<()> {
+    for (i, name) in names.iter().enumerate() {
+        if !known.contains(&name.as_str()) {
+            return Err(PackageError::ManifestParse(format!(
+                "Unknown {} '{}', expected one of: {}",
+                kind,
+                name,
+                known.join(", ")
+            )));
+        }
+        if names[..i].contains(name) {
+            return Err(PackageError::ManifestParse(format!(
+                "{} '{}' is listed twice",
+                kind, name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether some release from `minimum` on satisfies `requirement`
+///
+/// Only the versions a comparator names, and the next patch, minor and
+/// major release after each, can be the first to match, so those are the
+/// only candidates tried besides `minimum` itself.
+fn allows_version_from(requirement: &semver::VersionReq, minimum: &semver::Version) -> bool {
+    let mut candidates = vec![minimum.clone()];
+    for comparator in &requirement.comparators {
+        let (major, minor, patch) = (
+            comparator.major,
+            comparator.minor.unwrap_or(0),
+            comparator.patch.unwrap_or(0),
+        );
+        candidates.extend([
+            semver::Version::new(major, minor, patch),
+            semver::Version::new(major, minor, patch + 1),
+            semver::Version::new(major, minor + 1, 0),
+            semver::Version::new(major + 1, 0, 0),
+        ]);
+    }
+    candidates
+        .iter()
+        .any(|version| version >= minimum && requirement.matches(version))
+}
+
 fn default_edition() -> String {
     "2024".to_string()
 }
@@ -382,6 +632,57 @@ impl Default for BinaryConfig {
     }
 }
 
+/// Named command from the `[scripts]` section, run with `manuscript run`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ScriptSpec {
+    /// Shell command only (`test = "script tests/all.script"`)
+    Simple(String),
+
+    /// Command with a description, environment and build requirement
+    Detailed {
+        command: String,
+
+        /// Shown by `manuscript run --list`
+        description: Option<String>,
+
+        /// Extra environment variables for the command
+        #[serde(default)]
+        env: HashMap<String, String>,
+
+        /// Build the package before running the command
+        #[serde(default)]
+        build: bool,
+    },
+}
+
+impl ScriptSpec {
+    pub fn command(&self) -> &str {
+        match self {
+            ScriptSpec::Simple(command) => command,
+            ScriptSpec::Detailed { command, .. } => command,
+        }
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        match self {
+            ScriptSpec::Simple(_) => None,
+            ScriptSpec::Detailed { description, .. } => description.as_deref(),
+        }
+    }
+
+    pub fn env(&self) -> Option<&HashMap<String, String>> {
+        match self {
+            ScriptSpec::Simple(_) => None,
+            ScriptSpec::Detailed { env, .. } => Some(env),
+        }
+    }
+
+    pub fn requires_build(&self) -> bool {
+        matches!(self, ScriptSpec::Detailed { build: true, .. })
+    }
+}
+
 /// Build configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildConfig {
@@ -414,6 +715,15 @@ pub struct BuildConfig {
 
     #[serde(default)]
     pub strip: Option<String>,
+
+    /// Fail the build if it reads anything but its declared inputs
+    #[serde(default)]
+    pub hermetic: bool,
+
+    /// Files and directories a hermetic build may read besides its sources
+    /// and dependencies, relative to the package root
+    #[serde(default)]
+    pub inputs: Vec<PathBuf>,
 }
 
 impl Default for BuildConfig {
@@ -429,6 +739,8 @@ impl Default for BuildConfig {
             incremental: true,
             split_debuginfo: None,
             strip: None,
+            hermetic: false,
+            inputs: Vec::new(),
         }
     }
 }
@@ -474,7 +786,7 @@ pub struct WorkspaceConfig {
     #[serde(default)]
     pub resolver: String,
 
-    #[serde(default)]
+    #[serde(default, alias = "default-members")]
     pub default_members: Vec<String>,
 
     #[serde(default)]
@@ -484,6 +796,75 @@ pub struct WorkspaceConfig {
     pub metadata: HashMap<String, toml::Value>,
 }
 
+impl WorkspaceConfig {
+    /// The directories of the workspace's member packages below `root`,
+    /// sorted and without the excluded ones
+    ///
+    /// Members and exclusions are glob patterns relative to `root`, such as
+    /// `crates/*` or `libs/**/game-*`. A pattern with wildcards stands for
+    /// every matching directory holding a script.toml; a plain path is
+    /// always a member.
+    pub fn member_dirs(&self, root: &Path) -> PackageResult<Vec<PathBuf>> {
+        let root_pattern = escape_glob(
+            &root
+                .to_string_lossy()
+                .replace(std::path::MAIN_SEPARATOR, "/"),
+        );
+        let mut dirs = Vec::new();
+        for member in &self.members {
+            if !member.contains(['*', '?', '[']) {
+                dirs.push(root.join(member));
+                continue;
+            }
+            let pattern = format!("{}/{}", root_pattern.trim_end_matches('/'), member);
+            let matches = glob(&pattern).map_err(|e| {
+                PackageError::ManifestParse(format!("Invalid workspace member '{}': {}", member, e))
+            })?;
+            dirs.extend(
+                matches
+                    .into_iter()
+                    .map(PathBuf::from)
+                    .filter(|dir| dir.join("script.toml").is_file()),
+            );
+        }
+
+        let excluded = self
+            .exclude
+            .iter()
+            .map(|pattern| {
+                Glob::new(pattern).map_err(|e| {
+                    PackageError::ManifestParse(format!(
+                        "Invalid workspace exclusion '{}': {}",
+                        pattern, e
+                    ))
+                })
+            })
+            .collect::<PackageResult<Vec<_>>>()?;
+        dirs.retain(|dir| {
+            let relative = dir.strip_prefix(root).unwrap_or(dir);
+            let relative = relative
+                .to_string_lossy()
+                .replace(std::path::MAIN_SEPARATOR, "/");
+            !excluded.iter().any(|glob| glob.matches(&relative))
+        });
+        dirs.sort();
+        dirs.dedup();
+        Ok(dirs)
+    }
+}
+
+/// Escape the glob wildcards in a literal path
+fn escape_glob(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
 /// Target-specific configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TargetConfig {
@@ -497,35 +878,33 @@ pub struct TargetConfig {
     pub build_dependencies: HashMap<String, DependencySpec>,
 }
 
-/// Profile configuration for build optimization
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProfileConfig {
-    #[serde(default)]
-    pub opt_level: OptimizationLevel,
+/// Names of the profiles `[profile.<name>]` can configure
+pub const PROFILES: &[&str] = &["dev", "release"];
 
-    #[serde(default)]
-    pub debug: bool,
-
-    #[serde(default)]
-    pub debug_assertions: bool,
-
-    #[serde(default)]
-    pub overflow_checks: bool,
-
-    #[serde(default)]
-    pub lto: bool,
-
-    #[serde(default)]
-    pub panic: PanicStrategy,
-
-    #[serde(default)]
-    pub incremental: bool,
-
-    #[serde(default)]
-    pub codegen_units: Option<u32>,
-
-    #[serde(default)]
-    pub rpath: bool,
+/// Optimization settings of a `[profile.<name>]` table
+///
+/// Settings left out keep the value of the built-in profile, see
+/// [`BuildProfile`](crate::compilation::BuildProfile).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProfileConfig {
+    /// IR optimization level, 0 to 3
+    #[serde(default, alias = "opt_level", skip_serializing_if = "Option::is_none")]
+    pub opt_level: Option<u8>,
+
+    /// Optimize the program as a whole, dropping exported functions that it
+    /// never calls
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lto: Option<bool>,
+
+    /// Keep the line and variable information debuggers use
+    #[serde(
+        default,
+        alias = "debug_info",
+        alias = "debug",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub debug_info: Option<bool>,
 }
 
 /// Validate package name according to Script language conventions
@@ -554,6 +933,10 @@ pub fn create_default_manifest(name: &str, is_lib: bool) -> PackageManifest {
             authors: Vec::new(),
             description: None,
             edition: default_edition(),
+            language_version: None,
+            min_script_version: None,
+            platforms: Vec::new(),
+            capabilities: Vec::new(),
             license: None,
             license_file: None,
             repository: None,
@@ -561,6 +944,7 @@ pub fn create_default_manifest(name: &str, is_lib: bool) -> PackageManifest {
             documentation: None,
             keywords: Vec::new(),
             categories: Vec::new(),
+            badges: HashMap::new(),
             readme: None,
             include: Vec::new(),
             exclude: Vec::new(),
@@ -577,6 +961,8 @@ pub fn create_default_manifest(name: &str, is_lib: bool) -> PackageManifest {
         workspace: None,
         target: HashMap::new(),
         profile: HashMap::new(),
+        scripts: HashMap::new(),
+        lints: HashMap::new(),
     };
 
     if is_lib {
@@ -693,4 +1079,162 @@ mod tests {
         manifest.package.version = "invalid-version".to_string();
         assert!(manifest.validate().is_err());
     }
+
+    #[test]
+    fn test_scripts_section() {
+        let manifest = PackageManifest::from_str(
+            r#"
+            [package]
+            name = "app"
+            version = "0.1.0"
+
+            [scripts]
+            test = "script tests/all.script"
+
+            [scripts.serve]
+            command = "script src/server.script"
+            description = "Start the server"
+            env = { PORT = "8080" }
+            build = true
+            "#,
+        )
+        .unwrap();
+
+        let test = &manifest.scripts["test"];
+        assert_eq!(test.command(), "script tests/all.script");
+        assert!(!test.requires_build());
+
+        let serve = &manifest.scripts["serve"];
+        assert_eq!(serve.description(), Some("Start the server"));
+        assert_eq!(serve.env().unwrap()["PORT"], "8080");
+        assert!(serve.requires_build());
+
+        let mut manifest = manifest;
+        manifest
+            .scripts
+            .insert("lint".to_string(), ScriptSpec::Simple("  ".to_string()));
+        assert!(manifest.validate().is_err());
+    }
+
+    #[test]
+    fn test_edition_and_language_version() {
+        let manifest = PackageManifest::from_str(
+            r#"
+            [package]
+            name = "app"
+            version = "0.1.0"
+            edition = "2026"
+            language_version = ">=0.5"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(manifest.edition(), Edition::E2026);
+        assert_eq!(manifest.package.language_version.as_deref(), Some(">=0.5"));
+
+        let mut manifest = manifest;
+        manifest.package.edition = "2025".to_string();
+        assert!(manifest.validate().is_err());
+
+        manifest.package.edition = "2024".to_string();
+        manifest.package.language_version = Some("soon".to_string());
+        assert!(manifest.validate().is_err());
+    }
+
+    #[test]
+    fn test_platform_and_capability_requirements() {
+        let manifest = PackageManifest::from_str(
+            r#"
+            [package]
+            name = "app"
+            version = "0.1.0"
+            language_version = ">=0.1"
+            min_script_version = "0.1.0"
+            platforms = ["unix", "windows"]
+            capabilities = ["fs"]
+
+            [package.badges]
+            ci = "https://ci.example.com/app.svg"
+            "#,
+        )
+        .unwrap();
+        assert!(manifest.supports_platform("linux"));
+        assert!(!manifest.supports_platform("wasm"));
+        assert_eq!(manifest.package.badges.len(), 1);
+
+        let mut dependency = create_default_manifest("http", true);
+        assert!(manifest.check_dependency(&dependency).is_ok());
+        dependency.package.capabilities = vec!["network".to_string()];
+        let error = manifest.check_dependency(&dependency).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("requires the 'network' capability"));
+        dependency.package.capabilities.clear();
+        dependency.package.platforms = vec!["linux".to_string()];
+        assert!(manifest.check_dependency(&dependency).is_err());
+
+        let invalid = |edit: &dyn Fn(&mut PackageManifest)| {
+            let mut manifest = manifest.clone();
+            edit(&mut manifest);
+            manifest.validate().is_err()
+        };
+        assert!(invalid(&|m| m.package.platforms.push("amiga".to_string())));
+        assert!(invalid(&|m| m.package.capabilities.push("fs".to_string())));
+        assert!(invalid(&|m| m.package.platforms = vec!["wasm".to_string()]));
+        assert!(invalid(
+            &|m| m.package.min_script_version = Some("0.1".to_string())
+        ));
+        assert!(invalid(&|m| {
+            m.package.language_version = Some("<0.4".to_string());
+            m.package.min_script_version = Some("0.5.0".to_string());
+        }));
+        assert!(!invalid(&|m| {
+            m.package.language_version = Some(">0.6, <0.8".to_string());
+            m.package.min_script_version = Some("0.5.0".to_string());
+        }));
+    }
+
+    #[test]
+    fn test_workspace_member_dirs() {
+        let root = tempfile::TempDir::new().unwrap();
+        for dir in [
+            "crates/core",
+            "crates/net",
+            "crates/old",
+            "crates/docs",
+            "libs/game/game-audio",
+            "libs/game/engine",
+            "tools",
+        ] {
+            std::fs::create_dir_all(root.path().join(dir)).unwrap();
+        }
+        for dir in [
+            "crates/core",
+            "crates/net",
+            "crates/old",
+            "libs/game/game-audio",
+            "libs/game/engine",
+        ] {
+            std::fs::write(root.path().join(dir).join("script.toml"), "").unwrap();
+        }
+
+        let workspace: WorkspaceConfig = toml::from_str(
+            r#"
+            members = ["crates/*", "libs/**/game-*", "tools"]
+            exclude = ["crates/old"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            workspace.member_dirs(root.path()).unwrap(),
+            vec![
+                root.path().join("crates/core"),
+                root.path().join("crates/net"),
+                root.path().join("libs/game/game-audio"),
+                root.path().join("tools"),
+            ]
+        );
+
+        let invalid: WorkspaceConfig = toml::from_str(r#"members = ["crates/[a"]"#).unwrap();
+        assert!(invalid.member_dirs(root.path()).is_err());
+    }
 }
@@ -7,26 +7,38 @@
 /// - Local package caching and management
 use serde::{Deserialize, Serialize};
 
+mod api_diff;
 mod cache;
 mod dependency;
 mod http_client;
 mod manifest;
 mod registry;
+mod registry_server;
 mod resolver;
 mod version;
+mod workspace;
 
+pub use api_diff::{ApiChange, ApiChangeKind, ApiDiff, ApiItem, PublicApi, VersionBump};
 pub use cache::{CacheConfig, CacheEntry, CacheManager, PackageCache};
 pub use dependency::{
     Dependency, DependencyGraph, DependencyKind, DependencyResolver, DependencySpec,
     ResolutionResult,
 };
-pub use manifest::{BinaryConfig, BuildConfig, LibraryConfig, PackageConfig, PackageManifest};
-pub use registry::{PackageInfo, PackageRegistry, PublishResult, RegistryClient};
+pub use http_client::{HttpClient, RetryPolicy};
+pub use manifest::{
+    BinaryConfig, BuildConfig, LibraryConfig, PackageConfig, PackageManifest, ProfileConfig,
+    ScriptSpec, WorkspaceConfig,
+};
+pub use registry::{
+    PackageInfo, PackageRegistry, PublishResult, PublishablePackage, RegistryClient, RegistryConfig,
+};
+pub use registry_server::{RegistryServer, RegistryStore};
 pub use resolver::{PackageResolver, PackageSource, ResolverConfig};
 pub use version::{Version, VersionConstraint, VersionSpec};
+pub use workspace::Workspace;
 
 use crate::error::Error;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
 
 /// Result type for package operations
@@ -56,6 +68,9 @@ pub enum PackageError {
     #[error("Cache error: {0}")]
     Cache(String),
 
+    #[error("Incompatible package: {0}")]
+    Incompatible(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -196,7 +211,7 @@ impl Package {
 }
 
 /// Lock file structure for dependency version locking
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct LockFile {
     pub version: String,
     pub packages: Vec<LockEntry>,
@@ -204,7 +219,7 @@ pub struct LockFile {
 }
 
 /// Individual entry in the lock file
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct LockEntry {
     pub name: String,
     pub version: String,
@@ -239,6 +254,100 @@ impl LockFile {
         std::fs::write(path, content)?;
         Ok(())
     }
+
+    /// Lock the versions `graph` resolved for every package in it, the
+    /// dependencies of dependencies included, with a checksum of each
+    /// one's directory as `package_dir` finds it
+    pub fn from_graph(
+        graph: &DependencyGraph,
+        package_dir: impl Fn(&Dependency) -> PathBuf,
+    ) -> PackageResult<Self> {
+        let mut lock_file = Self::new();
+        for dependency in graph.packages() {
+            let Some(version) = graph.get_resolved_version(&dependency.name) else {
+                continue;
+            };
+            let dir = package_dir(dependency);
+            let checksum = if dir.is_dir() {
+                Some(resolver::directory_checksum(&dir)?)
+            } else {
+                None
+            };
+            let mut dependencies: Vec<String> = graph
+                .get_dependencies(&dependency.name)
+                .map(|deps| deps.iter().map(|d| d.name.clone()).collect())
+                .unwrap_or_default();
+            dependencies.sort();
+
+            lock_file.packages.push(LockEntry {
+                name: dependency.name.clone(),
+                version: version.to_string(),
+                source: dependency.source(),
+                checksum,
+                dependencies,
+            });
+        }
+        lock_file.packages.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(lock_file)
+    }
+
+    /// The version locked for `dependency`, unless its entry no longer
+    /// matches the dependency's source and version constraint
+    pub fn locked_version(&self, dependency: &Dependency) -> Option<Version> {
+        let entry = self
+            .packages
+            .iter()
+            .find(|entry| entry.name == dependency.name)?;
+        let version = Version::parse(&entry.version).ok()?;
+        (entry.source == dependency.source() && dependency.is_satisfied_by(&version))
+            .then_some(version)
+    }
+
+    /// Check that the lock file pins every dependency of `manifest` as it
+    /// asks, and nothing the dependencies don't need
+    pub fn check_manifest(&self, manifest: &PackageManifest) -> PackageResult<()> {
+        let mut names = Vec::new();
+        for (name, spec) in manifest
+            .dependencies
+            .iter()
+            .chain(&manifest.dev_dependencies)
+        {
+            let dependency = spec.resolve(name)?;
+            if self.locked_version(&dependency).is_none() {
+                return Err(PackageError::DependencyResolution(format!(
+                    "script.lock is out of date: it does not lock '{}' as script.toml asks",
+                    dependency.name
+                )));
+            }
+            names.push(dependency.name);
+        }
+
+        // The packages locked dependencies depend on are needed as well
+        let mut next = 0;
+        while let Some(name) = names.get(next).cloned() {
+            next += 1;
+            let Some(entry) = self.packages.iter().find(|entry| entry.name == name) else {
+                continue;
+            };
+            for dependency in &entry.dependencies {
+                if !names.contains(dependency) {
+                    names.push(dependency.clone());
+                }
+            }
+        }
+
+        if let Some(stale) = self
+            .packages
+            .iter()
+            .find(|entry| !names.contains(&entry.name))
+        {
+            return Err(PackageError::DependencyResolution(format!(
+                "script.lock is out of date: '{}' is no longer a dependency",
+                stale.name
+            )));
+        }
+        Ok(())
+    }
 }
 
 /// Package manager for handling all package operations
@@ -266,7 +375,11 @@ impl PackageManager {
     pub fn with_config(config: PackageManagerConfig) -> PackageResult<Self> {
         let cache = PackageCache::with_config(config.cache_config)?;
         let resolver = PackageResolver::new(config.resolver_config);
-        let registry = Box::new(RegistryClient::new(&config.registry_url)?);
+        let registry = Box::new(
+            RegistryClient::new(&config.registry_url)?
+                .with_mirrors(config.registry_mirrors)
+                .with_retry_policy(config.retry_policy),
+        );
 
         Ok(Self {
             cache,
@@ -276,14 +389,46 @@ impl PackageManager {
     }
 
     /// Resolve dependencies for a package
+    ///
+    /// A dependency keeps the version the package's lock file pins while
+    /// that still satisfies script.toml, so resolving it again needs no
+    /// registry.
     pub fn resolve_dependencies(&mut self, package: &Package) -> PackageResult<DependencyGraph> {
         // For now, create a simple dependency graph without full resolution
         // In a complete implementation, this would use the actual resolver
         let mut graph = DependencyGraph::new();
 
-        for (name, spec) in &package.manifest.dependencies {
-            let dependency = spec.resolve(name)?;
-            graph.add_dependency("root".to_string(), dependency);
+        let mut queue = VecDeque::new();
+        for (name, spec) in package
+            .manifest
+            .dependencies
+            .iter()
+            .chain(&package.manifest.dev_dependencies)
+        {
+            queue.push_back(("root".to_string(), spec.resolve(name)?));
+        }
+
+        while let Some((parent, dependency)) = queue.pop_front() {
+            if graph.get_resolved_version(&dependency.name).is_none() {
+                let version = self.resolve_version(package, &dependency)?;
+                graph.set_resolved_version(dependency.name.clone(), version);
+
+                // The dependencies of a local package are known from its
+                // manifest, with paths relative to it
+                if let DependencyKind::Path { path } = &dependency.kind {
+                    let manifest = PackageManifest::from_file(
+                        package.root_path.join(path).join("script.toml"),
+                    )?;
+                    for (name, spec) in &manifest.dependencies {
+                        let mut transitive = spec.resolve(name)?;
+                        if let DependencyKind::Path { path: nested } = &mut transitive.kind {
+                            *nested = workspace::normalize(&path.join(&*nested));
+                        }
+                        queue.push_back((dependency.name.clone(), transitive));
+                    }
+                }
+            }
+            graph.add_dependency(parent, dependency);
         }
 
         graph.validate()?;
@@ -291,6 +436,42 @@ impl PackageManager {
         Ok(graph)
     }
 
+    /// The version of `dependency` to install for `package`
+    fn resolve_version(
+        &self,
+        package: &Package,
+        dependency: &Dependency,
+    ) -> PackageResult<Version> {
+        let locked = package
+            .lock_file
+            .as_ref()
+            .and_then(|lock_file| lock_file.locked_version(dependency));
+        if let Some(version) = locked {
+            return Ok(version);
+        }
+
+        match &dependency.kind {
+            DependencyKind::Path { path } => {
+                let manifest =
+                    PackageManifest::from_file(package.root_path.join(path).join("script.toml"))?;
+                Ok(Version::parse(&manifest.package.version)?)
+            }
+            // The source pins the revision; the version is only known once
+            // the repository is cloned
+            DependencyKind::Git { .. } => Ok(Version::new(0, 0, 0)),
+            DependencyKind::Registry => self
+                .registry
+                .get_package_versions(&dependency.name)?
+                .into_iter()
+                .filter(|version| dependency.is_satisfied_by(version))
+                .max()
+                .ok_or_else(|| PackageError::VersionConflict {
+                    name: dependency.name.clone(),
+                    constraint: dependency.version_constraint.to_string(),
+                }),
+        }
+    }
+
     /// Install dependencies for a package
     pub fn install_dependencies(&mut self, package: &mut Package) -> PackageResult<()> {
         let graph = self.resolve_dependencies(package)?;
@@ -298,7 +479,7 @@ impl PackageManager {
         for (name, deps) in graph.dependencies() {
             if let Some(version) = graph.get_resolved_version(name) {
                 for dep in deps {
-                    self.install_dependency(name, dep, version)?;
+                    self.install_dependency(&package.manifest, name, dep, version)?;
                 }
             }
         }
@@ -307,9 +488,10 @@ impl PackageManager {
         Ok(())
     }
 
-    /// Install a single dependency
+    /// Install a single dependency of the package with manifest `root`
     fn install_dependency(
         &mut self,
+        root: &PackageManifest,
         name: &str,
         dependency: &Dependency,
         resolved_version: &Version,
@@ -331,6 +513,7 @@ impl PackageManager {
             } => {
                 // Git dependency installation logic
                 self.install_git_dependency(
+                    root,
                     name,
                     dependency,
                     resolved_version,
@@ -342,7 +525,7 @@ impl PackageManager {
             }
             DependencyKind::Path { path } => {
                 // Path dependency installation logic
-                self.install_path_dependency(name, dependency, resolved_version, &path)?;
+                self.install_path_dependency(root, name, dependency, resolved_version, &path)?;
             }
         }
 
@@ -352,6 +535,7 @@ impl PackageManager {
     /// Install a Git dependency
     fn install_git_dependency(
         &mut self,
+        root: &PackageManifest,
         name: &str,
         _dependency: &Dependency,
         resolved_version: &Version,
@@ -425,6 +609,8 @@ impl PackageManager {
                 name, package.manifest.package.name
             )));
         }
+        root.check_dependency(&package.manifest)?;
+        package.manifest.check_host()?;
 
         // Copy the package sources to the cache
         let mut package_data = Vec::new();
@@ -448,6 +634,7 @@ impl PackageManager {
     /// Install a Path dependency
     fn install_path_dependency(
         &mut self,
+        root: &PackageManifest,
         name: &str,
         _dependency: &Dependency,
         resolved_version: &Version,
@@ -486,6 +673,8 @@ impl PackageManager {
                 name, package.manifest.package.name
             )));
         }
+        root.check_dependency(&package.manifest)?;
+        package.manifest.check_host()?;
 
         // For path dependencies, we don't need to copy files to cache
         // Instead, we'll create a symlink or reference to the local path
@@ -504,6 +693,9 @@ pub struct PackageManagerConfig {
     pub cache_config: CacheConfig,
     pub resolver_config: ResolverConfig,
     pub registry_url: String,
+    /// Mirrors tried, in order, when a download from the registry fails
+    pub registry_mirrors: Vec<String>,
+    pub retry_policy: RetryPolicy,
 }
 
 impl Default for PackageManagerConfig {
@@ -512,6 +704,8 @@ impl Default for PackageManagerConfig {
             cache_config: CacheConfig::default(),
             resolver_config: ResolverConfig::default(),
             registry_url: "https://packages.script.org".to_string(),
+            registry_mirrors: Vec::new(),
+            retry_policy: RetryPolicy::default(),
         }
     }
 }
@@ -551,6 +745,72 @@ mod tests {
         assert_eq!(lock_file.packages[0].name, deserialized.packages[0].name);
     }
 
+    #[test]
+    fn test_lock_file_from_graph() {
+        let temp_dir = TempDir::new().unwrap();
+        let dep_dir = temp_dir.path().join("math");
+        fs::create_dir(&dep_dir).unwrap();
+        fs::write(dep_dir.join("lib.script"), "fn add(a, b) { a + b }").unwrap();
+
+        let manifest: PackageManifest = toml::from_str(
+            r#"
+            [package]
+            name = "game"
+            version = "0.1.0"
+
+            [dependencies]
+            math = { path = "math" }
+            "#,
+        )
+        .unwrap();
+        let dependency = manifest.dependencies["math"].resolve("math").unwrap();
+        let mut graph = DependencyGraph::new();
+        graph.set_resolved_version("math".to_string(), Version::new(1, 2, 0));
+        graph.add_dependency("root".to_string(), dependency.clone());
+        // Packages the dependencies need are locked too
+        graph.set_resolved_version("simd".to_string(), Version::new(0, 3, 1));
+        graph.add_dependency(
+            "math".to_string(),
+            Dependency::registry("simd", VersionConstraint::parse("^0.3.0").unwrap()),
+        );
+        graph.compute_build_order().unwrap();
+
+        let package_dir = |_: &Dependency| dep_dir.clone();
+        let lock_file = LockFile::from_graph(&graph, package_dir).unwrap();
+        assert_eq!(lock_file.packages.len(), 2);
+        assert_eq!(lock_file.packages[0].source, "path+math");
+        assert!(lock_file.packages[0].checksum.is_some());
+        assert_eq!(lock_file.packages[0].dependencies, vec!["simd"]);
+        assert_eq!(lock_file.packages[1].name, "simd");
+        assert_eq!(lock_file.packages[1].version, "0.3.1");
+        assert_eq!(
+            lock_file.locked_version(&dependency),
+            Some(Version::new(1, 2, 0))
+        );
+        assert!(lock_file.check_manifest(&manifest).is_ok());
+
+        // Changing a locked package changes its checksum
+        fs::write(dep_dir.join("lib.script"), "fn add(a, b) { b + a }").unwrap();
+        assert_ne!(
+            LockFile::from_graph(&graph, package_dir).unwrap(),
+            lock_file
+        );
+
+        // A dependency script.toml no longer lists, or lists differently,
+        // makes the lock out of date
+        let mut changed = manifest.clone();
+        changed.dependencies.clear();
+        assert!(lock_file.check_manifest(&changed).is_err());
+        let mut unreachable = lock_file.clone();
+        unreachable.packages[0].dependencies.clear();
+        assert!(unreachable.check_manifest(&manifest).is_err());
+        changed.dependencies.insert(
+            "math".to_string(),
+            DependencySpec::Simple("^1.0".to_string()),
+        );
+        assert!(lock_file.check_manifest(&changed).is_err());
+    }
+
     #[test]
     fn test_path_dependency_resolution() {
         // Create a temporary directory structure for testing
@@ -586,6 +846,7 @@ authors = ["Test Author"]
             optional: false,
             default_features: true,
             target: None,
+            workspace: false,
         };
 
         // Resolve the dependency
@@ -614,6 +875,7 @@ authors = ["Test Author"]
             optional: false,
             default_features: true,
             target: None,
+            workspace: false,
         };
 
         let dependency = spec.resolve("git-dep").unwrap();
@@ -643,6 +905,7 @@ authors = ["Test Author"]
             optional: true,
             default_features: false,
             target: Some("x86_64-pc-windows-msvc".to_string()),
+            workspace: false,
         };
 
         let dependency = spec.resolve("feature-dep").unwrap();
@@ -1,4 +1,4 @@
-use super::http_client::HttpClient;
+use super::http_client::{HttpClient, RetryPolicy};
 /// Package registry client and API for Script language packages
 ///
 /// This module provides:
@@ -9,6 +9,8 @@ use super::http_client::HttpClient;
 use super::{PackageError, PackageMetadata, PackageResult, Version};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Package registry trait for different registry implementations
 pub trait PackageRegistry: Send + Sync {
@@ -42,10 +44,16 @@ pub trait PackageRegistry: Send + Sync {
 }
 
 /// Registry client implementation
+///
+/// Package downloads fall back to the configured mirrors, in order, when the
+/// registry itself cannot serve them. Partially downloaded archives are kept
+/// in the download directory so that a retry resumes where it stopped.
 pub struct RegistryClient {
     base_url: String,
+    mirrors: Vec<String>,
     client: HttpClient,
     auth_token: Option<String>,
+    download_dir: PathBuf,
 }
 
 impl RegistryClient {
@@ -53,8 +61,10 @@ impl RegistryClient {
     pub fn new(base_url: impl Into<String>) -> PackageResult<Self> {
         Ok(Self {
             base_url: base_url.into(),
+            mirrors: Vec::new(),
             client: HttpClient::new()?,
             auth_token: None,
+            download_dir: default_download_dir(),
         })
     }
 
@@ -63,13 +73,43 @@ impl RegistryClient {
         base_url: impl Into<String>,
         auth_token: impl Into<String>,
     ) -> PackageResult<Self> {
+        let mut client = Self::new(base_url)?;
+        client.auth_token = Some(auth_token.into());
+        Ok(client)
+    }
+
+    /// Create a registry client from a registry configuration
+    pub fn from_config(config: &RegistryConfig) -> PackageResult<Self> {
+        let mut client = HttpClient::with_timeout(Duration::from_secs(config.timeout_seconds))?;
+        client.set_retry_policy(RetryPolicy::with_retries(config.retries));
+
         Ok(Self {
-            base_url: base_url.into(),
-            client: HttpClient::new()?,
-            auth_token: Some(auth_token.into()),
+            base_url: config.url.clone(),
+            mirrors: config.mirrors.clone(),
+            client,
+            auth_token: config.auth_token.clone(),
+            download_dir: default_download_dir(),
         })
     }
 
+    /// Set the mirrors tried, in order, when a download from the registry fails
+    pub fn with_mirrors(mut self, mirrors: Vec<String>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
+    /// Set how transient request failures are retried
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.client.set_retry_policy(retry);
+        self
+    }
+
+    /// Set the directory partially downloaded archives are kept in
+    pub fn with_download_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.download_dir = dir.into();
+        self
+    }
+
     /// Set authentication token
     pub fn set_auth_token(&mut self, token: impl Into<String>) {
         self.auth_token = Some(token.into());
@@ -81,11 +121,15 @@ impl RegistryClient {
     }
 
     fn build_url(&self, path: &str) -> String {
-        if self.base_url.ends_with('/') {
-            format!("{}{}", self.base_url, path.trim_start_matches('/'))
-        } else {
-            format!("{}/{}", self.base_url, path.trim_start_matches('/'))
+        join_url(&self.base_url, path)
+    }
+
+    fn auth_headers(&self) -> HashMap<String, String> {
+        let mut headers = HashMap::new();
+        if let Some(ref token) = self.auth_token {
+            headers.insert("Authorization".to_string(), format!("Bearer {}", token));
         }
+        headers
     }
 
     fn make_request(&self, method: &str, url: &str, body: Option<&[u8]>) -> PackageResult<Vec<u8>> {
@@ -175,8 +219,30 @@ impl PackageRegistry for RegistryClient {
     }
 
     fn download_package(&self, name: &str, version: &str) -> PackageResult<Vec<u8>> {
-        let url = self.build_url(&format!("/api/v1/packages/{}/{}/download", name, version));
-        self.make_request("GET", &url, None)
+        let path = format!("/api/v1/packages/{}/{}/download", name, version);
+        let partial =
+            self.download_dir
+                .join(format!("{}-{}.part", name.replace('/', "_"), version));
+        let headers = self.auth_headers();
+
+        let mut failures = Vec::new();
+        for source in std::iter::once(&self.base_url).chain(&self.mirrors) {
+            let url = join_url(source, &path);
+            match self
+                .client
+                .download_resumable(&url, &headers, &partial, |_, _| {})
+            {
+                Ok(data) => return Ok(data),
+                Err(e) => failures.push(format!("{}: {}", source, e)),
+            }
+        }
+
+        Err(PackageError::Registry(format!(
+            "Failed to download {} {} from any source:\n  {}",
+            name,
+            version,
+            failures.join("\n  ")
+        )))
     }
 
     fn publish_package(
@@ -198,11 +264,7 @@ impl PackageRegistry for RegistryClient {
 
         // Temporarily set auth token for this request
         let _old_token = self.auth_token.clone();
-        let client = RegistryClient {
-            base_url: self.base_url.clone(),
-            client: HttpClient::new()?,
-            auth_token: Some(auth_token.to_string()),
-        };
+        let client = RegistryClient::with_auth(self.base_url.clone(), auth_token)?;
 
         let response_data = client.make_request("POST", &url, Some(&request_body))?;
 
@@ -312,21 +374,34 @@ pub struct PublishResult {
 // Internal API types
 
 #[derive(Serialize, Deserialize)]
-struct SearchResponse {
-    packages: Vec<PackageSearchResult>,
-    total_count: usize,
+pub(crate) struct SearchResponse {
+    pub(crate) packages: Vec<PackageSearchResult>,
+    pub(crate) total_count: usize,
 }
 
 #[derive(Serialize, Deserialize)]
-struct VersionsResponse {
-    versions: Vec<String>,
+pub(crate) struct VersionsResponse {
+    pub(crate) versions: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize)]
-struct PublishRequest {
-    metadata: PackageMetadata,
-    package_data: Vec<u8>,
-    signature: Option<String>,
+pub(crate) struct PublishRequest {
+    pub(crate) metadata: PackageMetadata,
+    pub(crate) package_data: Vec<u8>,
+    pub(crate) signature: Option<String>,
+}
+
+fn join_url(base: &str, path: &str) -> String {
+    if base.ends_with('/') {
+        format!("{}{}", base, path.trim_start_matches('/'))
+    } else {
+        format!("{}/{}", base, path.trim_start_matches('/'))
+    }
+}
+
+/// Where partially downloaded package archives are kept between attempts
+fn default_download_dir() -> PathBuf {
+    std::env::temp_dir().join("manuscript").join("downloads")
 }
 
 // Helper to avoid adding urlencoding dependency for this example
@@ -348,6 +423,16 @@ pub struct RegistryConfig {
     pub auth_token: Option<String>,
     pub trusted: bool,
     pub timeout_seconds: u64,
+    /// Mirrors tried, in order, when a download from `url` fails
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    /// Retries for requests that fail with transient errors
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+fn default_retries() -> u32 {
+    RetryPolicy::default().max_retries
 }
 
 impl RegistryConfig {
@@ -358,6 +443,8 @@ impl RegistryConfig {
             auth_token: None,
             trusted: false,
             timeout_seconds: 30,
+            mirrors: Vec::new(),
+            retries: default_retries(),
         }
     }
 
@@ -370,6 +457,11 @@ impl RegistryConfig {
         self.trusted = true;
         self
     }
+
+    pub fn with_mirrors(mut self, mirrors: Vec<String>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
 }
 
 /// Registry manager for handling multiple registries
@@ -482,6 +574,35 @@ mod tests {
     //     assert!(!results.is_empty());
     // }
 
+    #[test]
+    fn test_registry_client_from_config() {
+        let config = RegistryConfig::new("private", "https://registry.example.com")
+            .with_auth("token")
+            .with_mirrors(vec!["https://mirror.example.com".to_string()]);
+
+        let client = RegistryClient::from_config(&config).unwrap();
+        assert_eq!(client.base_url, "https://registry.example.com");
+        assert_eq!(client.mirrors, vec!["https://mirror.example.com"]);
+        assert_eq!(client.auth_token.as_deref(), Some("token"));
+        assert_eq!(client.client.retry_policy().max_retries, config.retries);
+    }
+
+    #[test]
+    fn test_download_falls_back_to_mirrors() {
+        let dir = tempfile::tempdir().unwrap();
+        // Nothing listens on these ports, so every source fails immediately
+        let client = RegistryClient::new("http://127.0.0.1:9")
+            .unwrap()
+            .with_mirrors(vec!["http://127.0.0.1:1/".to_string()])
+            .with_retry_policy(RetryPolicy::none())
+            .with_download_dir(dir.path());
+
+        let error = client.download_package("pkg", "1.0.0").unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("http://127.0.0.1:9:"));
+        assert!(message.contains("http://127.0.0.1:1/:"));
+    }
+
     #[test]
     fn test_publishable_package() {
         let metadata = PackageMetadata::new("test-package", Version::new(1, 0, 0));
@@ -0,0 +1,1022 @@
+/// Filesystem-backed package registry server
+///
+/// Serves the same HTTP API that `RegistryClient` talks to, so a team can host
+/// a private registry from a plain directory:
+///
+/// ```text
+/// <root>/packages/<name>/<version>/record.json   metadata, checksum, publish time
+/// <root>/packages/<name>/<version>/archive       the published package archive
+/// <root>/packages/<name>/downloads.json          download counters
+/// <root>/cache/ac/<key>                          digest of the artifact built for a key
+/// <root>/cache/cas/<digest>                      build artifact, named by its SHA-256
+/// ```
+///
+/// The `cache` directory backs the remote build cache that
+/// `compilation::RemoteCache` talks to.
+use super::registry::{
+    DownloadStats, PackageInfo, PackageSearchResult, PublishRequest, PublishResult, SearchResponse,
+    VersionsResponse,
+};
+use super::{PackageError, PackageMetadata, PackageResult, Version};
+use chrono::{Duration as ChronoDuration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Largest request body accepted, which bounds the size of published packages
+const MAX_BODY_SIZE: usize = 256 * 1024 * 1024;
+
+/// Largest request head (request line and headers) accepted
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+/// A published version as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VersionRecord {
+    metadata: PackageMetadata,
+    published_at: String,
+    checksum: String,
+    signature: Option<String>,
+}
+
+/// Download counters for one package
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DownloadCounts {
+    versions: HashMap<String, u64>,
+    /// Downloads per day, keyed by ISO date
+    days: HashMap<String, u64>,
+}
+
+/// Package storage for the registry server
+pub struct RegistryStore {
+    root: PathBuf,
+    downloads_lock: Mutex<()>,
+}
+
+impl RegistryStore {
+    /// Open the registry stored under `root`, creating it if needed
+    pub fn open(root: impl Into<PathBuf>) -> PackageResult<Self> {
+        let root = root.into();
+        fs::create_dir_all(root.join("packages"))?;
+        Ok(Self {
+            root,
+            downloads_lock: Mutex::new(()),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Names of all packages with at least one published version
+    pub fn packages(&self) -> PackageResult<Vec<String>> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(self.root.join("packages"))? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                if let Some(name) = entry.file_name().to_str() {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Published versions of a package, oldest first
+    pub fn versions(&self, name: &str) -> PackageResult<Vec<Version>> {
+        let dir = self.package_dir(name)?;
+        if !dir.is_dir() {
+            return Err(PackageError::PackageNotFound {
+                name: name.to_string(),
+            });
+        }
+
+        let mut versions = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            if !path.join("record.json").is_file() {
+                continue;
+            }
+            if let Some(Ok(version)) = entry.file_name().to_str().map(Version::parse) {
+                versions.push(version);
+            }
+        }
+        versions.sort();
+        Ok(versions)
+    }
+
+    pub fn exists(&self, name: &str, version: &Version) -> bool {
+        self.version_dir(name, version)
+            .map(|dir| dir.join("record.json").is_file())
+            .unwrap_or(false)
+    }
+
+    pub fn metadata(&self, name: &str, version: &Version) -> PackageResult<PackageMetadata> {
+        Ok(self.record(name, version)?.metadata)
+    }
+
+    /// Path of the stored archive for a published version
+    pub fn archive_path(&self, name: &str, version: &Version) -> PackageResult<PathBuf> {
+        if !self.exists(name, version) {
+            return Err(PackageError::PackageNotFound {
+                name: format!("{}@{}", name, version),
+            });
+        }
+        Ok(self.version_dir(name, version)?.join("archive"))
+    }
+
+    /// Store a new version; published versions are immutable
+    pub fn publish(
+        &self,
+        metadata: &PackageMetadata,
+        archive: &[u8],
+        signature: Option<&str>,
+    ) -> PackageResult<PublishResult> {
+        let name = &metadata.name;
+        let version = &metadata.version;
+        if self.exists(name, version) {
+            return Err(PackageError::Registry(format!(
+                "{} {} is already published",
+                name, version
+            )));
+        }
+
+        let mut warnings = Vec::new();
+        if metadata.description.is_none() {
+            warnings.push("package has no description".to_string());
+        }
+        if metadata.license.is_none() {
+            warnings.push("package has no license".to_string());
+        }
+
+        let record = VersionRecord {
+            metadata: metadata.clone(),
+            published_at: Utc::now().to_rfc3339(),
+            checksum: format!("{:x}", Sha256::digest(archive)),
+            signature: signature.map(str::to_string),
+        };
+
+        // Write into a staging directory and rename it into place, so readers
+        // never see a version without its archive
+        let dir = self.version_dir(name, version)?;
+        let staging = dir.with_file_name(format!(".{}.publishing", version));
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+        fs::write(staging.join("archive"), archive)?;
+        fs::write(staging.join("record.json"), to_json(&record)?)?;
+        fs::rename(&staging, &dir)?;
+
+        Ok(PublishResult {
+            success: true,
+            message: format!("Published {} {}", name, version),
+            package_url: Some(format!("/api/v1/packages/{}/{}", name, version)),
+            version: version.to_string(),
+            warnings,
+        })
+    }
+
+    /// Registry-wide information about a package, taken from its latest version
+    pub fn package_info(&self, name: &str) -> PackageResult<PackageInfo> {
+        let versions = self.versions(name)?;
+        let latest = versions
+            .last()
+            .ok_or_else(|| PackageError::PackageNotFound {
+                name: name.to_string(),
+            })?;
+
+        let mut published: Vec<String> = Vec::new();
+        for version in &versions {
+            published.push(self.record(name, version)?.published_at);
+        }
+        published.sort();
+        let latest_record = self.record(name, latest)?;
+        let metadata = latest_record.metadata;
+
+        Ok(PackageInfo {
+            name: name.to_string(),
+            description: metadata.description,
+            latest_version: latest.to_string(),
+            versions: versions.iter().map(Version::to_string).collect(),
+            authors: metadata.authors,
+            license: metadata.license,
+            repository: metadata.repository,
+            homepage: metadata.homepage,
+            documentation: metadata.documentation,
+            keywords: metadata.keywords,
+            categories: metadata.categories,
+            created_at: published.first().cloned().unwrap_or_default(),
+            updated_at: published.last().cloned().unwrap_or_default(),
+            download_count: self.download_counts(name)?.versions.values().sum(),
+        })
+    }
+
+    /// Packages whose name, keywords or description match `query`, best first
+    pub fn search(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> PackageResult<Vec<PackageSearchResult>> {
+        let query = query.to_lowercase();
+        let mut results = Vec::new();
+
+        for name in self.packages()? {
+            let info = match self.package_info(&name) {
+                Ok(info) => info,
+                Err(PackageError::PackageNotFound { .. }) => continue,
+                Err(e) => return Err(e),
+            };
+
+            let lower_name = name.to_lowercase();
+            let relevance_score = if query.is_empty() || lower_name == query {
+                1.0
+            } else if lower_name.starts_with(&query) {
+                0.8
+            } else if lower_name.contains(&query) {
+                0.6
+            } else if info.keywords.iter().any(|k| k.to_lowercase() == query) {
+                0.4
+            } else if info
+                .description
+                .as_ref()
+                .is_some_and(|d| d.to_lowercase().contains(&query))
+            {
+                0.2
+            } else {
+                continue;
+            };
+
+            results.push(PackageSearchResult {
+                name: info.name,
+                description: info.description,
+                latest_version: info.latest_version,
+                authors: info.authors,
+                keywords: info.keywords,
+                download_count: info.download_count,
+                updated_at: info.updated_at,
+                relevance_score,
+            });
+        }
+
+        results.sort_by(|a, b| {
+            b.relevance_score
+                .partial_cmp(&a.relevance_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        if let Some(limit) = limit {
+            results.truncate(limit);
+        }
+        Ok(results)
+    }
+
+    /// Count a download of a version
+    pub fn record_download(&self, name: &str, version: &Version) -> PackageResult<()> {
+        let _guard = self
+            .downloads_lock
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let mut counts = self.download_counts(name)?;
+        *counts.versions.entry(version.to_string()).or_insert(0) += 1;
+        *counts
+            .days
+            .entry(Utc::now().date_naive().to_string())
+            .or_insert(0) += 1;
+
+        fs::write(
+            self.package_dir(name)?.join("downloads.json"),
+            to_json(&counts)?,
+        )?;
+        Ok(())
+    }
+
+    pub fn stats(&self, name: &str) -> PackageResult<DownloadStats> {
+        self.versions(name)?;
+        let counts = self.download_counts(name)?;
+        let today = Utc::now().date_naive();
+        let since = |days: i64| -> u64 {
+            counts
+                .days
+                .iter()
+                .filter_map(|(day, count)| {
+                    let day = NaiveDate::parse_from_str(day, "%Y-%m-%d").ok()?;
+                    (today - day < ChronoDuration::days(days)).then_some(*count)
+                })
+                .sum()
+        };
+
+        Ok(DownloadStats {
+            total_downloads: counts.versions.values().sum(),
+            downloads_last_day: since(1),
+            downloads_last_week: since(7),
+            downloads_last_month: since(30),
+            version_downloads: counts.versions.clone(),
+        })
+    }
+
+    fn record(&self, name: &str, version: &Version) -> PackageResult<VersionRecord> {
+        let path = self.version_dir(name, version)?.join("record.json");
+        if !path.is_file() {
+            return Err(PackageError::PackageNotFound {
+                name: format!("{}@{}", name, version),
+            });
+        }
+        serde_json::from_slice(&fs::read(&path)?).map_err(|e| {
+            PackageError::Registry(format!("Corrupt record {}: {}", path.display(), e))
+        })
+    }
+
+    fn download_counts(&self, name: &str) -> PackageResult<DownloadCounts> {
+        let path = self.package_dir(name)?.join("downloads.json");
+        if !path.is_file() {
+            return Ok(DownloadCounts::default());
+        }
+        serde_json::from_slice(&fs::read(&path)?).map_err(|e| {
+            PackageError::Registry(format!("Corrupt download counts {}: {}", path.display(), e))
+        })
+    }
+
+    fn package_dir(&self, name: &str) -> PackageResult<PathBuf> {
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(PackageError::Registry(format!(
+                "Invalid package name: {}",
+                name
+            )));
+        }
+        Ok(self.root.join("packages").join(name))
+    }
+
+    fn version_dir(&self, name: &str, version: &Version) -> PackageResult<PathBuf> {
+        Ok(self.package_dir(name)?.join(version.to_string()))
+    }
+
+    /// Digest of the build artifact cached under `key`
+    pub fn cached_digest(&self, key: &str) -> PackageResult<Option<String>> {
+        let path = self.cache_path("ac", key)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read_to_string(path)?.trim().to_string()))
+    }
+
+    /// Record that the artifact built for `key` has SHA-256 `digest`
+    pub fn cache_digest(&self, key: &str, digest: &str) -> PackageResult<()> {
+        self.cache_path("cas", digest)?;
+        self.write_atomically(&self.cache_path("ac", key)?, digest.as_bytes())
+    }
+
+    /// The cached build artifact with SHA-256 `digest`
+    pub fn cached_artifact(&self, digest: &str) -> PackageResult<Option<Vec<u8>>> {
+        let path = self.cache_path("cas", digest)?;
+        if !path.is_file() {
+            return Ok(None);
+        }
+        Ok(Some(fs::read(path)?))
+    }
+
+    /// Cache a build artifact, which must have SHA-256 `digest`
+    pub fn cache_artifact(&self, digest: &str, artifact: &[u8]) -> PackageResult<()> {
+        let path = self.cache_path("cas", digest)?;
+        let actual = format!("{:x}", Sha256::digest(artifact));
+        if actual != digest {
+            return Err(PackageError::Registry(format!(
+                "Artifact does not match its digest {}",
+                digest
+            )));
+        }
+        self.write_atomically(&path, artifact)
+    }
+
+    /// Path of a cache entry; keys and digests are hex SHA-256 hashes
+    fn cache_path(&self, kind: &str, id: &str) -> PackageResult<PathBuf> {
+        let valid = id.len() == 64
+            && id
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c));
+        if !valid {
+            return Err(PackageError::Registry(format!("Invalid cache key: {}", id)));
+        }
+        Ok(self.root.join("cache").join(kind).join(id))
+    }
+
+    /// Write `contents` to `path` so concurrent readers never see part of it
+    fn write_atomically(&self, path: &Path, contents: &[u8]) -> PackageResult<()> {
+        let dir = path.parent().unwrap_or(&self.root);
+        fs::create_dir_all(dir)?;
+        let mut file = tempfile::NamedTempFile::new_in(dir)?;
+        std::io::Write::write_all(&mut file, contents)?;
+        file.persist(path).map_err(|e| PackageError::Io(e.error))?;
+        Ok(())
+    }
+}
+
+/// HTTP server for a `RegistryStore`
+///
+/// Publishing and writing to the build cache require one of the configured
+/// bearer tokens; every other endpoint is public. With no tokens configured
+/// the registry is read-only.
+pub struct RegistryServer {
+    store: RegistryStore,
+    tokens: HashSet<String>,
+}
+
+impl RegistryServer {
+    pub fn new(store: RegistryStore, tokens: impl IntoIterator<Item = String>) -> Self {
+        Self {
+            store,
+            tokens: tokens.into_iter().collect(),
+        }
+    }
+
+    pub fn store(&self) -> &RegistryStore {
+        &self.store
+    }
+
+    /// Accept connections on `listener` until it fails
+    pub async fn serve(self, listener: TcpListener) -> PackageResult<()> {
+        let server = Arc::new(self);
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let server = Arc::clone(&server);
+            tokio::spawn(async move {
+                if let Err(e) = server.handle_connection(stream).await {
+                    log::debug!("registry connection from {} failed: {}", peer, e);
+                }
+            });
+        }
+    }
+
+    async fn handle_connection(self: Arc<Self>, mut stream: TcpStream) -> std::io::Result<()> {
+        let response = match read_request(&mut stream).await? {
+            Ok(request) => {
+                let server = Arc::clone(&self);
+                let (method, path) = (request.method.clone(), request.path.clone());
+                let response = tokio::task::spawn_blocking(move || server.handle(&request))
+                    .await
+                    .unwrap_or_else(|e| Response::error(500, &e.to_string()));
+                log::info!("{} {} {}", method, path, response.status);
+                response
+            }
+            Err(response) => response,
+        };
+        stream.write_all(&response.to_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    /// Route a request to the registry API
+    fn handle(&self, request: &Request) -> Response {
+        let segments: Vec<&str> = request
+            .path
+            .trim_matches('/')
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect();
+        let route = match segments.as_slice() {
+            ["api", "v1", "packages", rest @ ..] => rest,
+            ["cache", "v1", kind, id] => return respond(request, self.cache(request, kind, id)),
+            _ => return Response::error(404, "Not found"),
+        };
+
+        let result = match (request.method.as_str(), route) {
+            ("POST", ["publish"]) => self.publish(request),
+            ("GET", ["search"]) => self.search(request),
+            ("GET", [name]) => self
+                .store
+                .package_info(name)
+                .and_then(|i| Response::json(&i)),
+            ("GET", [name, "versions"]) => self.store.versions(name).and_then(|versions| {
+                Response::json(&VersionsResponse {
+                    versions: versions.iter().map(Version::to_string).collect(),
+                })
+            }),
+            ("GET", [name, "stats"]) => self.store.stats(name).and_then(|s| Response::json(&s)),
+            ("GET", [name, version, "metadata"]) => parse_version(version)
+                .and_then(|v| self.store.metadata(name, &v))
+                .and_then(|m| Response::json(&m)),
+            ("GET", [name, version, "download"]) => self.download(request, name, version),
+            ("HEAD", [name, version, "exists"]) => parse_version(version).map(|v| {
+                if self.store.exists(name, &v) {
+                    Response::new(200, Vec::new())
+                } else {
+                    Response::error(404, "Not found")
+                }
+            }),
+            _ => Ok(Response::error(404, "Not found")),
+        };
+        respond(request, result)
+    }
+
+    fn search(&self, request: &Request) -> PackageResult<Response> {
+        let query = request.query.get("q").map(String::as_str).unwrap_or("");
+        let limit = request.query.get("limit").and_then(|l| l.parse().ok());
+        let packages = self.store.search(query, limit)?;
+        Response::json(&SearchResponse {
+            total_count: packages.len(),
+            packages,
+        })
+    }
+
+    /// The error response for a request without a valid write token
+    fn check_token(&self, request: &Request, action: &str) -> Option<Response> {
+        if self.tokens.is_empty() {
+            return Some(Response::error(
+                403,
+                &format!("{} is disabled: this registry has no tokens", action),
+            ));
+        }
+        let token = request
+            .headers
+            .get("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if !token.is_some_and(|token| self.tokens.contains(token)) {
+            return Some(Response::error(401, "Missing or invalid token"));
+        }
+        None
+    }
+
+    /// Read or write an entry of the build cache
+    fn cache(&self, request: &Request, kind: &str, id: &str) -> PackageResult<Response> {
+        let found = |entry: Option<Vec<u8>>| match entry {
+            Some(body) => Response::new(200, body),
+            None => Response::error(404, "Not cached"),
+        };
+        match (request.method.as_str(), kind) {
+            ("GET" | "HEAD", "ac") => {
+                Ok(found(self.store.cached_digest(id)?.map(String::into_bytes)))
+            }
+            ("GET" | "HEAD", "cas") => Ok(found(self.store.cached_artifact(id)?)),
+            ("PUT", "ac" | "cas") => {
+                if let Some(response) = self.check_token(request, "Writing to the build cache") {
+                    return Ok(response);
+                }
+                if kind == "ac" {
+                    let digest = String::from_utf8_lossy(&request.body);
+                    self.store.cache_digest(id, digest.trim())?;
+                } else {
+                    self.store.cache_artifact(id, &request.body)?;
+                }
+                Ok(Response::new(200, Vec::new()))
+            }
+            _ => Ok(Response::error(404, "Not found")),
+        }
+    }
+
+    fn publish(&self, request: &Request) -> PackageResult<Response> {
+        if let Some(response) = self.check_token(request, "Publishing") {
+            return Ok(response);
+        }
+
+        let publish: PublishRequest = serde_json::from_slice(&request.body)
+            .map_err(|e| PackageError::Registry(format!("Invalid publish request: {}", e)))?;
+        let result = self.store.publish(
+            &publish.metadata,
+            &publish.package_data,
+            publish.signature.as_deref(),
+        )?;
+        Response::json(&result)
+    }
+
+    /// Serve an archive, honouring `Range` requests so downloads can resume
+    fn download(&self, request: &Request, name: &str, version: &str) -> PackageResult<Response> {
+        let version = parse_version(version)?;
+        let archive = fs::read(self.store.archive_path(name, &version)?)?;
+        let len = archive.len();
+
+        let range = request
+            .headers
+            .get("range")
+            .and_then(|r| parse_range(r, len));
+        let response = match range {
+            None => Response::new(200, archive),
+            Some(Err(())) => {
+                let mut response = Response::error(416, "Range not satisfiable");
+                response
+                    .headers
+                    .push(("Content-Range".to_string(), format!("bytes */{}", len)));
+                return Ok(response);
+            }
+            Some(Ok((start, end))) => {
+                let mut response = Response::new(206, archive[start..=end].to_vec());
+                response.headers.push((
+                    "Content-Range".to_string(),
+                    format!("bytes {}-{}/{}", start, end, len),
+                ));
+                response
+            }
+        };
+
+        // Resumed transfers continue a download that was already counted
+        if !matches!(range, Some(Ok((start, _))) if start > 0) {
+            self.store.record_download(name, &version)?;
+        }
+        Ok(response)
+    }
+}
+
+/// A parsed HTTP request
+struct Request {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    /// Header names are lowercased
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+/// The response to `request`, or the error it failed with
+fn respond(request: &Request, result: PackageResult<Response>) -> Response {
+    let mut response = result.unwrap_or_else(|e| match e {
+        PackageError::PackageNotFound { .. } => Response::error(404, &e.to_string()),
+        PackageError::Io(_) => Response::error(500, &e.to_string()),
+        _ => Response::error(400, &e.to_string()),
+    });
+    response.head_only = request.method == "HEAD";
+    response
+}
+
+/// An HTTP response; connections are closed after every response
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    /// Omit the body but keep its length, as for HEAD requests
+    head_only: bool,
+}
+
+impl Response {
+    fn new(status: u16, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            headers: vec![(
+                "Content-Type".to_string(),
+                "application/octet-stream".to_string(),
+            )],
+            body,
+            head_only: false,
+        }
+    }
+
+    fn json<T: Serialize>(value: &T) -> PackageResult<Self> {
+        let mut response = Self::new(200, to_json(value)?);
+        response.headers[0].1 = "application/json".to_string();
+        Ok(response)
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        let mut response = Self::new(status, message.as_bytes().to_vec());
+        response.headers[0].1 = "text/plain; charset=utf-8".to_string();
+        response
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason(self.status));
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str(&format!("Content-Length: {}\r\n", self.body.len()));
+        head.push_str("Accept-Ranges: bytes\r\nConnection: close\r\n\r\n");
+
+        let mut bytes = head.into_bytes();
+        if !self.head_only {
+            bytes.extend_from_slice(&self.body);
+        }
+        bytes
+    }
+}
+
+/// Read one request, or the error response to send instead
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Result<Request, Response>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let head_end = loop {
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos;
+        }
+        if buffer.len() > MAX_HEAD_SIZE {
+            return Ok(Err(Response::error(431, "Request head too large")));
+        }
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..head_end]).into_owned();
+    let mut request = match parse_head(&head) {
+        Some(request) => request,
+        None => return Ok(Err(Response::error(400, "Malformed request"))),
+    };
+
+    let content_length = match request.headers.get("content-length") {
+        Some(value) => match value.parse::<usize>() {
+            Ok(len) => len,
+            Err(_) => return Ok(Err(Response::error(400, "Invalid Content-Length"))),
+        },
+        None => 0,
+    };
+    if content_length > MAX_BODY_SIZE {
+        return Ok(Err(Response::error(413, "Request body too large")));
+    }
+
+    let mut body = buffer.split_off(head_end + 4);
+    while body.len() < content_length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    body.truncate(content_length);
+    request.body = body;
+    Ok(Ok(request))
+}
+
+fn parse_head(head: &str) -> Option<Request> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (target, ""),
+    };
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect();
+
+    let mut headers = HashMap::new();
+    for line in lines {
+        let (name, value) = line.split_once(':')?;
+        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+
+    Some(Request {
+        method,
+        path: percent_decode(path),
+        query,
+        headers,
+        body: Vec::new(),
+    })
+}
+
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes[i] {
+            b'%' if i + 2 < bytes.len() => std::str::from_utf8(&bytes[i + 1..i + 3])
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match (escaped, bytes[i]) {
+            (Some(byte), _) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (None, b'+') => decoded.push(b' '),
+            (None, byte) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Parse a single `bytes=start-end` range against a body of `len` bytes
+///
+/// Returns `None` for ranges this server ignores (serving the whole body)
+/// and `Some(Err(()))` for ranges that cannot be satisfied.
+fn parse_range(header: &str, len: usize) -> Option<Result<(usize, usize), ()>> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+    let (start, end) = spec.split_once('-')?;
+
+    let range = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: usize = suffix.parse().ok()?;
+            if suffix == 0 {
+                return Some(Err(()));
+            }
+            (len.saturating_sub(suffix), len.checked_sub(1)?)
+        }
+        (start, "") => (start.parse().ok()?, len.saturating_sub(1)),
+        (start, end) => {
+            let end: usize = end.parse().ok()?;
+            (start.parse().ok()?, end.min(len.saturating_sub(1)))
+        }
+    };
+
+    if range.0 >= len || range.0 > range.1 {
+        Some(Err(()))
+    } else {
+        Some(Ok(range))
+    }
+}
+
+fn parse_version(version: &str) -> PackageResult<Version> {
+    Ok(Version::parse(version)?)
+}
+
+fn to_json<T: Serialize>(value: &T) -> PackageResult<Vec<u8>> {
+    serde_json::to_vec_pretty(value)
+        .map_err(|e| PackageError::Registry(format!("Failed to serialize response: {}", e)))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        206 => "Partial Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        416 => "Range Not Satisfiable",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::{PackageRegistry, PublishablePackage, RegistryClient, RetryPolicy};
+    use tempfile::TempDir;
+
+    fn metadata(name: &str, version: &str) -> PackageMetadata {
+        let mut metadata = PackageMetadata::new(name, Version::parse(version).unwrap());
+        metadata.description = Some(format!("The {} package", name));
+        metadata
+    }
+
+    fn request(method: &str, path: &str, headers: &[(&str, &str)]) -> Request {
+        Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            query: HashMap::new(),
+            headers: headers
+                .iter()
+                .map(|(k, v)| (k.to_lowercase(), v.to_string()))
+                .collect(),
+            body: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_store_publish_and_read_back() {
+        let dir = TempDir::new().unwrap();
+        let store = RegistryStore::open(dir.path()).unwrap();
+
+        store
+            .publish(&metadata("json-utils", "1.0.0"), b"one", None)
+            .unwrap();
+        store
+            .publish(&metadata("json-utils", "1.2.0"), b"two", None)
+            .unwrap();
+        assert!(store
+            .publish(&metadata("json-utils", "1.2.0"), b"again", None)
+            .is_err());
+
+        let versions = store.versions("json-utils").unwrap();
+        assert_eq!(versions.len(), 2);
+        let info = store.package_info("json-utils").unwrap();
+        assert_eq!(info.latest_version, "1.2.0");
+        assert_eq!(store.search("json", None).unwrap()[0].name, "json-utils");
+        assert!(store.versions("../escape").is_err());
+    }
+
+    #[test]
+    fn test_download_honours_ranges() {
+        let dir = TempDir::new().unwrap();
+        let server = RegistryServer::new(RegistryStore::open(dir.path()).unwrap(), Vec::new());
+        server
+            .store()
+            .publish(&metadata("pkg", "0.1.0"), b"0123456789", None)
+            .unwrap();
+        let path = "/api/v1/packages/pkg/0.1.0/download";
+
+        let full = server.handle(&request("GET", path, &[]));
+        assert_eq!(
+            (full.status, full.body.as_slice()),
+            (200, &b"0123456789"[..])
+        );
+
+        let rest = server.handle(&request("GET", path, &[("Range", "bytes=4-")]));
+        assert_eq!((rest.status, rest.body.as_slice()), (206, &b"456789"[..]));
+
+        let past_end = server.handle(&request("GET", path, &[("Range", "bytes=10-")]));
+        assert_eq!(past_end.status, 416);
+
+        // Only the full download is counted; the resumed one continues it
+        let stats = server.store().stats("pkg").unwrap();
+        assert_eq!(stats.total_downloads, 1);
+    }
+
+    #[test]
+    fn test_publish_requires_token() {
+        let dir = TempDir::new().unwrap();
+        let server = RegistryServer::new(
+            RegistryStore::open(dir.path()).unwrap(),
+            vec!["secret".to_string()],
+        );
+        let path = "/api/v1/packages/publish";
+
+        let anonymous = server.handle(&request("POST", path, &[]));
+        assert_eq!(anonymous.status, 401);
+
+        let mut publish = request("POST", path, &[("Authorization", "Bearer secret")]);
+        publish.body = serde_json::to_vec(&PublishRequest {
+            metadata: metadata("pkg", "1.0.0"),
+            package_data: b"archive".to_vec(),
+            signature: None,
+        })
+        .unwrap();
+        assert_eq!(server.handle(&publish).status, 200);
+        assert!(server
+            .store()
+            .exists("pkg", &Version::parse("1.0.0").unwrap()));
+    }
+
+    #[test]
+    fn test_registry_client_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = RegistryServer::new(
+            RegistryStore::open(dir.path()).unwrap(),
+            vec!["secret".to_string()],
+        );
+        runtime.spawn(server.serve(listener));
+
+        let client = RegistryClient::new(&url)
+            .unwrap()
+            .with_retry_policy(RetryPolicy::none())
+            .with_download_dir(dir.path().join("downloads"));
+        let package = PublishablePackage::new(metadata("pkg", "1.0.0"), b"archive".to_vec());
+        assert!(client.publish_package(&package, "secret").unwrap().success);
+
+        assert_eq!(client.get_package_versions("pkg").unwrap().len(), 1);
+        assert_eq!(
+            client.download_package("pkg", "1.0.0").unwrap(),
+            b"archive".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_remote_cache_round_trip() {
+        use crate::compilation::{ArtifactKey, RemoteCache, RemoteCacheConfig};
+
+        let dir = TempDir::new().unwrap();
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = RegistryServer::new(
+            RegistryStore::open(dir.path()).unwrap(),
+            vec!["secret".to_string()],
+        );
+        runtime.spawn(server.serve(listener));
+
+        let key = ArtifactKey::builder()
+            .source("main.script", b"fn main() {}")
+            .finish();
+        let cache =
+            RemoteCache::new(RemoteCacheConfig::new(&url).with_token(Some("secret".to_string())))
+                .unwrap();
+        assert_eq!(cache.get(&key).unwrap(), None);
+        cache.put(&key, b"artifact").unwrap();
+        assert_eq!(cache.get(&key).unwrap(), Some(b"artifact".to_vec()));
+
+        // Writes need a token
+        let anonymous = RemoteCache::new(RemoteCacheConfig::new(&url)).unwrap();
+        let other = ArtifactKey::builder().finish();
+        assert!(anonymous.put(&other, b"artifact").is_err());
+        assert_eq!(anonymous.get(&other).unwrap(), None);
+        assert!(RegistryStore::open(dir.path())
+            .unwrap()
+            .cache_artifact(key.as_str(), b"artifact")
+            .is_err());
+    }
+}
@@ -196,40 +196,41 @@ version = "{}"
     ) -> PackageResult<bool> {
         // Verify package integrity using checksums
         if let Some(expected_checksum) = &resolved.checksum {
-            let actual_checksum = self.compute_package_checksum(package_dir)?;
+            let actual_checksum = directory_checksum(package_dir)?;
             Ok(actual_checksum == *expected_checksum)
         } else {
             // If no checksum is available, just check if the manifest exists
             Ok(package_dir.join("script.toml").exists())
         }
     }
+}
 
-    fn compute_package_checksum(&self, package_dir: &Path) -> PackageResult<String> {
-        let mut hasher = Sha256::new();
-        self.hash_directory(&mut hasher, package_dir)?;
-        Ok(format!("{:x}", hasher.finalize()))
-    }
+/// SHA-256 of the names and contents of everything in `dir`
+pub(crate) fn directory_checksum(dir: &Path) -> PackageResult<String> {
+    let mut hasher = Sha256::new();
+    hash_directory(&mut hasher, dir)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
 
-    fn hash_directory(&self, hasher: &mut Sha256, dir: &Path) -> PackageResult<()> {
-        let mut entries: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
-        entries.sort_by_key(|e| e.file_name());
+fn hash_directory(hasher: &mut Sha256, dir: &Path) -> PackageResult<()> {
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|e| e.file_name());
 
-        for entry in entries {
-            let path = entry.path();
-            let file_name = entry.file_name();
+    for entry in entries {
+        let path = entry.path();
+        let file_name = entry.file_name();
 
-            hasher.update(file_name.to_string_lossy().as_bytes());
+        hasher.update(file_name.to_string_lossy().as_bytes());
 
-            if path.is_file() {
-                let content = std::fs::read(&path)?;
-                hasher.update(&content);
-            } else if path.is_dir() {
-                self.hash_directory(hasher, &path)?;
-            }
+        if path.is_file() {
+            let content = std::fs::read(&path)?;
+            hasher.update(&content);
+        } else if path.is_dir() {
+            hash_directory(hasher, &path)?;
         }
-
-        Ok(())
     }
+
+    Ok(())
 }
 
 /// Configuration for the package resolver
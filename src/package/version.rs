@@ -6,11 +6,15 @@
 /// - Version comparison and ordering
 use super::{PackageError, PackageResult};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::fmt;
 use std::str::FromStr;
 
 /// Semantic version representation
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+///
+/// Versions are ordered by semver precedence: a prerelease comes before its
+/// release, so `1.0.0-alpha < 1.0.0`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Version {
     pub major: u64,
     pub minor: u64,
@@ -130,6 +134,18 @@ impl Default for Version {
     }
 }
 
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_semver().cmp(&other.to_semver())
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl fmt::Display for Version {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
@@ -576,6 +592,26 @@ mod tests {
         assert!(!v1.is_compatible_with(&v3)); // Different major version
     }
 
+    #[test]
+    fn test_prerelease_ordering() {
+        let ordered: Vec<Version> = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-beta",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "1.0.1",
+        ]
+        .iter()
+        .map(|v| Version::parse(v).unwrap())
+        .collect();
+        assert!(ordered.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let constraint = VersionConstraint::parse(">=1.0.0").unwrap();
+        assert!(!constraint.matches(&ordered[3]));
+        assert!(constraint.matches(&ordered[4]));
+    }
+
     #[test]
     fn test_constraint_parsing() {
         let constraint = VersionConstraint::parse("^1.2.3").unwrap();
@@ -0,0 +1,598 @@
+//! Workspaces of several packages
+//!
+//! A package whose script.toml has a `[workspace]` table is the root of a
+//! workspace. Its `members` are glob patterns for the directories of the
+//! other packages, which share the root's script.lock and `target`
+//! directory:
+//!
+//! ```toml
+//! [workspace]
+//! members = ["crates/*", "tools/cli"]
+//! exclude = ["crates/experimental"]
+//! default_members = ["tools/cli"]
+//!
+//! [workspace.dependencies]
+//! json = "^1.2.0"
+//! ```
+//!
+//! A member takes a dependency declared in `[workspace.dependencies]` with
+//! `json = { workspace = true }`, optionally adding `features` or making it
+//! `optional`. The dependencies of all packages are unified, so the
+//! workspace builds against one version of each.
+
+use super::{
+    DependencySpec, Package, PackageError, PackageManifest, PackageResult, WorkspaceConfig,
+};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+/// A workspace root and its member packages
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    root: Package,
+    members: Vec<Package>,
+}
+
+impl Workspace {
+    /// Load the workspace whose root package is in `root_dir`
+    pub fn load(root_dir: &Path) -> PackageResult<Self> {
+        let mut root = Package::from_manifest_file(root_dir.join("script.toml"))?;
+        let config = root.manifest.workspace.clone().ok_or_else(|| {
+            PackageError::ManifestParse(format!(
+                "{} is not a workspace root: its script.toml has no [workspace] table",
+                root_dir.display()
+            ))
+        })?;
+        inherit_dependencies(&config, root_dir, &mut root.manifest)?;
+
+        let mut members = Vec::new();
+        for dir in config.member_dirs(&root.root_path)? {
+            if dir == root.root_path {
+                continue;
+            }
+            let manifest_path = dir.join("script.toml");
+            if !manifest_path.is_file() {
+                return Err(PackageError::ManifestParse(format!(
+                    "Workspace member {} has no script.toml",
+                    dir.display()
+                )));
+            }
+            let mut member = Package::from_manifest_file(manifest_path)?;
+            if member.manifest.workspace.is_some() {
+                return Err(PackageError::ManifestParse(format!(
+                    "Workspace member {} is itself a workspace root; workspaces cannot be nested",
+                    dir.display()
+                )));
+            }
+            inherit_dependencies(&config, &root.root_path, &mut member.manifest)?;
+            members.push(member);
+        }
+
+        let mut names = HashSet::new();
+        for package in std::iter::once(&root).chain(&members) {
+            if !names.insert(package.manifest.package.name.as_str()) {
+                return Err(PackageError::ManifestParse(format!(
+                    "The workspace has two packages named '{}'",
+                    package.manifest.package.name
+                )));
+            }
+        }
+
+        Ok(Self { root, members })
+    }
+
+    /// The workspace `package_dir` is the root or a member of, looking in
+    /// the directory and its parents
+    pub fn find(package_dir: &Path) -> PackageResult<Option<Self>> {
+        for dir in package_dir.ancestors() {
+            // Manifests of unrelated parent directories don't matter here
+            let Ok(manifest) = PackageManifest::from_file(dir.join("script.toml")) else {
+                continue;
+            };
+            let Some(config) = &manifest.workspace else {
+                continue;
+            };
+            if dir == package_dir
+                || config
+                    .member_dirs(dir)?
+                    .iter()
+                    .any(|member| member == package_dir)
+            {
+                return Self::load(dir).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// The root package, which holds the `[workspace]` table
+    pub fn root(&self) -> &Package {
+        &self.root
+    }
+
+    pub fn root_path(&self) -> &Path {
+        &self.root.root_path
+    }
+
+    pub fn config(&self) -> &WorkspaceConfig {
+        self.root
+            .manifest
+            .workspace
+            .as_ref()
+            .expect("a workspace root has a [workspace] table")
+    }
+
+    /// The member packages, sorted by directory
+    pub fn members(&self) -> &[Package] {
+        &self.members
+    }
+
+    /// The root package followed by the members
+    pub fn packages(&self) -> impl Iterator<Item = &Package> {
+        std::iter::once(&self.root).chain(&self.members)
+    }
+
+    /// The package named `name`
+    pub fn package(&self, name: &str) -> Option<&Package> {
+        self.packages()
+            .find(|package| package.manifest.package.name == name)
+    }
+
+    /// The package in the directory `dir`
+    pub fn package_at(&self, dir: &Path) -> Option<&Package> {
+        let dir = normalize(dir);
+        self.packages()
+            .find(|package| normalize(&package.root_path) == dir)
+    }
+
+    /// The packages named `names`, in workspace order
+    pub fn select(&self, names: &[String]) -> PackageResult<Vec<&Package>> {
+        if let Some(unknown) = names.iter().find(|name| self.package(name).is_none()) {
+            let known: Vec<&str> = self
+                .packages()
+                .map(|package| package.manifest.package.name.as_str())
+                .collect();
+            return Err(PackageError::ManifestParse(format!(
+                "Package '{}' is not in the workspace; its packages are {}",
+                unknown,
+                known.join(", ")
+            )));
+        }
+        Ok(self
+            .packages()
+            .filter(|package| names.contains(&package.manifest.package.name))
+            .collect())
+    }
+
+    /// The packages a command run in the root directory uses when none
+    /// are named: the `default_members`, or else the root package
+    pub fn default_packages(&self) -> PackageResult<Vec<&Package>> {
+        let default_members = &self.config().default_members;
+        if default_members.is_empty() {
+            return Ok(vec![&self.root]);
+        }
+        default_members
+            .iter()
+            .map(|member| {
+                self.package_at(&self.root_path().join(member))
+                    .ok_or_else(|| {
+                        PackageError::ManifestParse(format!(
+                            "Default member '{}' is not a member of the workspace",
+                            member
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    /// The root package with the dependencies of every package in the
+    /// workspace, for resolving them together into the shared script.lock
+    ///
+    /// A dependency several packages use must come from the same source in
+    /// each. Its version constraints are combined, so one version has to
+    /// satisfy them all, and its features are merged. Path dependencies
+    /// are made relative to the workspace root.
+    pub fn unified_package(&self) -> PackageResult<Package> {
+        let mut unified: BTreeMap<String, UnifiedDependency> = BTreeMap::new();
+        for package in self.packages() {
+            for (dependencies, dev) in [
+                (&package.manifest.dependencies, false),
+                (&package.manifest.dev_dependencies, true),
+            ] {
+                let mut dependencies: Vec<_> = dependencies.iter().collect();
+                dependencies.sort_by(|a, b| a.0.cmp(b.0));
+                for (name, spec) in dependencies {
+                    let spec = rebase(spec, &package.root_path, self.root_path());
+                    let source = spec.resolve(name)?.id();
+                    match unified.entry(name.clone()) {
+                        Entry::Vacant(entry) => {
+                            entry.insert(UnifiedDependency {
+                                spec: detailed(&spec),
+                                source,
+                                dev_only: dev,
+                            });
+                        }
+                        Entry::Occupied(mut entry) => {
+                            let existing = entry.get_mut();
+                            if existing.source != source {
+                                return Err(PackageError::DependencyResolution(format!(
+                                    "Workspace packages depend on '{}' from different sources: {} and {}",
+                                    name, existing.source, source
+                                )));
+                            }
+                            merge(&mut existing.spec, &spec);
+                            existing.dev_only &= dev;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut package = self.root.clone();
+        package.manifest.dependencies = HashMap::new();
+        package.manifest.dev_dependencies = HashMap::new();
+        for (name, dependency) in unified {
+            if dependency.dev_only {
+                package
+                    .manifest
+                    .dev_dependencies
+                    .insert(name, dependency.spec);
+            } else {
+                package.manifest.dependencies.insert(name, dependency.spec);
+            }
+        }
+        Ok(package)
+    }
+}
+
+/// A dependency of the workspace as a whole
+struct UnifiedDependency {
+    spec: DependencySpec,
+    source: String,
+    /// No package needs it outside of its tests
+    dev_only: bool,
+}
+
+/// Replace the dependencies of `manifest` marked `workspace = true` with
+/// the ones `[workspace.dependencies]` declares
+fn inherit_dependencies(
+    config: &WorkspaceConfig,
+    root: &Path,
+    manifest: &mut PackageManifest,
+) -> PackageResult<()> {
+    let package_name = manifest.package.name.clone();
+    let tables = [
+        &mut manifest.dependencies,
+        &mut manifest.dev_dependencies,
+        &mut manifest.build_dependencies,
+    ]
+    .into_iter()
+    .chain(manifest.target.values_mut().flat_map(|target| {
+        [
+            &mut target.dependencies,
+            &mut target.dev_dependencies,
+            &mut target.build_dependencies,
+        ]
+    }));
+
+    for table in tables {
+        for (name, spec) in table.iter_mut() {
+            let DependencySpec::Detailed {
+                features,
+                optional,
+                workspace: true,
+                ..
+            } = spec
+            else {
+                continue;
+            };
+            let declared = config.dependencies.get(name).ok_or_else(|| {
+                PackageError::ManifestParse(format!(
+                    "'{}' inherits the dependency '{}' from the workspace, but [workspace.dependencies] does not declare it",
+                    package_name, name
+                ))
+            })?;
+
+            let mut inherited = detailed(declared);
+            if let DependencySpec::Detailed {
+                path,
+                features: inherited_features,
+                optional: inherited_optional,
+                ..
+            } = &mut inherited
+            {
+                // Paths in the root manifest are relative to the root
+                if let Some(path) = path {
+                    *path = root.join(&*path);
+                }
+                for feature in features.iter() {
+                    if !inherited_features.contains(feature) {
+                        inherited_features.push(feature.clone());
+                    }
+                }
+                *inherited_optional = *optional;
+            }
+            *spec = inherited;
+        }
+    }
+    Ok(())
+}
+
+/// `spec` in its detailed form
+fn detailed(spec: &DependencySpec) -> DependencySpec {
+    match spec {
+        DependencySpec::Simple(version) => DependencySpec::Detailed {
+            version: Some(version.clone()),
+            git: None,
+            branch: None,
+            tag: None,
+            rev: None,
+            path: None,
+            registry: None,
+            package: None,
+            features: Vec::new(),
+            optional: false,
+            default_features: true,
+            target: None,
+            workspace: false,
+        },
+        DependencySpec::Detailed { .. } => spec.clone(),
+    }
+}
+
+/// Add the requirements of `spec` to those of `into`
+fn merge(into: &mut DependencySpec, spec: &DependencySpec) {
+    let DependencySpec::Detailed {
+        version: into_version,
+        features: into_features,
+        optional: into_optional,
+        default_features: into_default_features,
+        ..
+    } = into
+    else {
+        unreachable!("unified dependencies are detailed");
+    };
+    let no_features = Vec::new();
+    let (version, features, optional, default_features) = match spec {
+        DependencySpec::Simple(version) => (Some(version), &no_features, false, true),
+        DependencySpec::Detailed {
+            version,
+            features,
+            optional,
+            default_features,
+            ..
+        } => (version.as_ref(), features, *optional, *default_features),
+    };
+
+    // A list of constraints is parsed as all of them at once
+    if let Some(version) = version {
+        *into_version = match into_version.take() {
+            Some(existing) if existing.split(',').any(|c| c.trim() == version.trim()) => {
+                Some(existing)
+            }
+            Some(existing) => Some(format!("{}, {}", existing, version)),
+            None => Some(version.clone()),
+        };
+    }
+    for feature in features {
+        if !into_features.contains(feature) {
+            into_features.push(feature.clone());
+        }
+    }
+    *into_optional &= optional;
+    *into_default_features |= default_features;
+}
+
+/// `spec` with its path, relative to `package_dir`, made relative to the
+/// workspace root where possible
+fn rebase(spec: &DependencySpec, package_dir: &Path, root: &Path) -> DependencySpec {
+    let mut spec = spec.clone();
+    if let DependencySpec::Detailed {
+        path: Some(path), ..
+    } = &mut spec
+    {
+        let absolute = normalize(&package_dir.join(&*path));
+        *path = absolute
+            .strip_prefix(normalize(root))
+            .map(Path::to_path_buf)
+            .unwrap_or(absolute);
+    }
+    spec
+}
+
+/// `path` without `.` components and with `..` applied to the components
+/// before it
+pub(super) fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(normal.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                normal.pop();
+            }
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::DependencyKind;
+    use tempfile::TempDir;
+
+    fn write(root: &Path, path: &str, contents: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    fn workspace() -> TempDir {
+        let dir = TempDir::new().unwrap();
+        write(
+            dir.path(),
+            "script.toml",
+            r#"
+            [package]
+            name = "app"
+            version = "0.1.0"
+
+            [dependencies]
+            json = "^1.2.0"
+
+            [workspace]
+            members = ["crates/*"]
+            default_members = ["crates/net"]
+
+            [workspace.dependencies]
+            json = { version = "^1.2.0", features = ["std"] }
+            util = { path = "vendor/util" }
+            "#,
+        );
+        write(
+            dir.path(),
+            "crates/core/script.toml",
+            r#"
+            [package]
+            name = "core"
+            version = "0.1.0"
+
+            [dependencies]
+            json = { workspace = true, features = ["serde"] }
+            util = { workspace = true }
+
+            [dev-dependencies]
+            bench = "^0.3"
+            "#,
+        );
+        write(
+            dir.path(),
+            "crates/net/script.toml",
+            r#"
+            [package]
+            name = "net"
+            version = "0.1.0"
+
+            [dependencies]
+            json = ">=1.3.0"
+            core = { path = "../core" }
+            "#,
+        );
+        dir
+    }
+
+    #[test]
+    fn test_load_and_select() {
+        let dir = workspace();
+        let workspace = Workspace::load(dir.path()).unwrap();
+        let names: Vec<&str> = workspace
+            .packages()
+            .map(|package| package.manifest.package.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["app", "core", "net"]);
+
+        let selected = workspace
+            .select(&["net".to_string(), "app".to_string()])
+            .unwrap();
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected[0].manifest.package.name, "app");
+        assert!(workspace.select(&["missing".to_string()]).is_err());
+
+        let defaults = workspace.default_packages().unwrap();
+        assert_eq!(defaults.len(), 1);
+        assert_eq!(defaults[0].manifest.package.name, "net");
+
+        let member = Workspace::find(&dir.path().join("crates/core"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(member.root_path(), dir.path());
+        assert!(Workspace::find(&dir.path().join("crates"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_inherited_dependencies() {
+        let dir = workspace();
+        let workspace = Workspace::load(dir.path()).unwrap();
+        let core = workspace.package("core").unwrap();
+
+        let json = core.manifest.dependencies["json"].resolve("json").unwrap();
+        assert_eq!(json.version_constraint.to_string(), "^1.2.0");
+        assert_eq!(json.features, vec!["std", "serde"]);
+
+        let util = core.manifest.dependencies["util"].resolve("util").unwrap();
+        assert_eq!(
+            util.kind,
+            DependencyKind::Path {
+                path: dir.path().join("vendor/util")
+            }
+        );
+
+        write(
+            dir.path(),
+            "crates/bad/script.toml",
+            r#"
+            [package]
+            name = "bad"
+            version = "0.1.0"
+
+            [dependencies]
+            missing = { workspace = true }
+            "#,
+        );
+        assert!(Workspace::load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_unified_dependencies() {
+        let dir = workspace();
+        let workspace = Workspace::load(dir.path()).unwrap();
+        let package = workspace.unified_package().unwrap();
+        let manifest = &package.manifest;
+
+        let json = manifest.dependencies["json"].resolve("json").unwrap();
+        assert_eq!(json.version_constraint.to_string(), "^1.2.0, >=1.3.0");
+        assert_eq!(json.features, vec!["std", "serde"]);
+
+        // Paths are relative to the workspace root
+        for (name, path) in [("core", "crates/core"), ("util", "vendor/util")] {
+            let dependency = manifest.dependencies[name].resolve(name).unwrap();
+            assert_eq!(
+                dependency.kind,
+                DependencyKind::Path {
+                    path: PathBuf::from(path)
+                }
+            );
+        }
+        assert!(manifest.dev_dependencies.contains_key("bench"));
+
+        // The same dependency from two sources can't be unified
+        write(
+            dir.path(),
+            "crates/other/script.toml",
+            r#"
+            [package]
+            name = "other"
+            version = "0.1.0"
+
+            [dependencies]
+            json = { git = "https://example.com/json.git" }
+            "#,
+        );
+        let workspace = Workspace::load(dir.path()).unwrap();
+        assert!(workspace.unified_package().is_err());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(Path::new("/ws/crates/net/../core/./src")),
+            PathBuf::from("/ws/crates/core/src")
+        );
+        assert_eq!(normalize(Path::new("../a/..")), PathBuf::from(".."));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::lexer::TokenKind;
+use crate::lexer::{Comment, NumberSuffix, TokenKind};
 use crate::source::Span;
 use std::fmt;
 
@@ -40,9 +40,13 @@ pub struct WherePredicate {
 }
 
 /// Implementation block for methods on a type
+///
+/// `impl Trait for Type { .. }` sets `trait_name`; its methods implement the
+/// trait and defaults it doesn't override are inherited.
 #[derive(Debug, Clone, PartialEq)]
 pub struct ImplBlock {
     pub type_name: String,
+    pub trait_name: Option<String>,
     pub generic_params: Option<GenericParams>,
     pub methods: Vec<Method>,
     pub where_clause: Option<WhereClause>,
@@ -62,6 +66,24 @@ pub struct Method {
     pub span: Span,
 }
 
+/// Trait declaration: a named set of method signatures
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitDecl {
+    pub name: String,
+    pub methods: Vec<TraitMethod>,
+    pub span: Span,
+}
+
+/// Method signature within a trait, with an optional default body
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitMethod {
+    pub name: String,
+    pub params: Vec<Param>,
+    pub ret_type: Option<TypeAnn>,
+    pub default_body: Option<Block>,
+    pub span: Span,
+}
+
 /// Field in a struct declaration
 #[derive(Debug, Clone, PartialEq)]
 pub struct StructField {
@@ -92,6 +114,8 @@ pub enum EnumVariantFields {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
     pub statements: Vec<Stmt>,
+    /// Comments in source order, when the parser was given them
+    pub comments: Vec<Comment>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -135,6 +159,10 @@ pub enum StmtKind {
         iterable: Expr,
         body: Block,
     },
+    /// Exit the innermost loop
+    Break,
+    /// Skip to the next iteration of the innermost loop
+    Continue,
     Import {
         imports: ImportSpec,
         module: String,
@@ -155,6 +183,7 @@ pub enum StmtKind {
         where_clause: Option<WhereClause>,
     },
     Impl(ImplBlock),
+    Trait(TraitDecl),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -244,6 +273,11 @@ pub enum ExprKind {
         parameters: Vec<ClosureParam>,
         body: Box<Expr>,
     },
+    /// Explicit numeric conversion (e.g., cast<i32>(x))
+    Cast {
+        expr: Box<Expr>,
+        target_type: TypeAnn,
+    },
 }
 
 /// Closure parameter with optional type annotation
@@ -307,7 +341,14 @@ pub enum PatternKind {
     Literal(Literal),
     Array(Vec<Pattern>),
     Object(Vec<(String, Option<Pattern>)>),
+    /// Tuple pattern (e.g., (x, _), (0, y))
+    Tuple(Vec<Pattern>),
     Or(Vec<Pattern>),
+    /// Binding pattern: binds the whole value while matching a sub-pattern (e.g., n @ Some(_))
+    Binding {
+        name: String,
+        pattern: Box<Pattern>,
+    },
     /// Enum constructor pattern (e.g., Some(x), None, Result::Ok(value))
     EnumConstructor {
         /// Enum name (None for inferred, Some for qualified like Option::Some)
@@ -403,6 +444,8 @@ pub enum TypeKind {
 #[derive(Debug, Clone, PartialEq)]
 pub enum Literal {
     Number(f64),
+    /// Number with a type suffix, e.g. `10i64`
+    TypedNumber(f64, NumberSuffix),
     String(String),
     Boolean(bool),
     Null,
@@ -538,6 +581,8 @@ impl fmt::Display for Stmt {
             StmtKind::While { condition, body } => {
                 write!(f, "while {} {}", condition, body)
             }
+            StmtKind::Break => write!(f, "break"),
+            StmtKind::Continue => write!(f, "continue"),
             StmtKind::For {
                 variable,
                 iterable,
@@ -704,6 +749,9 @@ impl fmt::Display for Stmt {
             StmtKind::Impl(impl_block) => {
                 write!(f, "{}", impl_block)
             }
+            StmtKind::Trait(trait_decl) => {
+                write!(f, "{}", trait_decl)
+            }
         }
     }
 }
@@ -874,6 +922,9 @@ impl fmt::Display for Expr {
                 }
                 write!(f, "| {}", body)
             }
+            ExprKind::Cast { expr, target_type } => {
+                write!(f, "cast<{}>({})", target_type, expr)
+            }
         }
     }
 }
@@ -920,6 +971,20 @@ impl fmt::Display for Pattern {
                 }
                 write!(f, "}}")
             }
+            PatternKind::Tuple(patterns) => {
+                write!(f, "(")?;
+                for (i, p) in patterns.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", p)?;
+                }
+                if patterns.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            PatternKind::Binding { name, pattern } => write!(f, "{} @ {}", name, pattern),
             PatternKind::Or(patterns) => {
                 for (i, p) in patterns.iter().enumerate() {
                     if i > 0 {
@@ -1012,6 +1077,7 @@ impl fmt::Display for Literal {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Literal::Number(n) => write!(f, "{}", n),
+            Literal::TypedNumber(n, suffix) => write!(f, "{}{}", n, suffix),
             Literal::String(s) => write!(f, "\"{}\"", s),
             Literal::Boolean(b) => write!(f, "{}", b),
             Literal::Null => write!(f, "null"),
@@ -1289,6 +1355,10 @@ impl fmt::Display for ImplBlock {
             write!(f, "{}", generics)?;
         }
 
+        if let Some(trait_name) = &self.trait_name {
+            write!(f, " {} for", trait_name)?;
+        }
+
         write!(f, " {}", self.type_name)?;
 
         // Display where clause
@@ -1337,3 +1407,51 @@ impl fmt::Display for Method {
         write!(f, " {}", self.body)
     }
 }
+
+impl TraitMethod {
+    /// The default body as a method of a type that doesn't override it
+    pub fn default_method(&self) -> Option<Method> {
+        self.default_body.as_ref().map(|body| Method {
+            name: self.name.clone(),
+            generic_params: None,
+            params: self.params.clone(),
+            ret_type: self.ret_type.clone(),
+            where_clause: None,
+            body: body.clone(),
+            is_async: false,
+            span: self.span,
+        })
+    }
+}
+
+impl fmt::Display for TraitDecl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "trait {} {{", self.name)?;
+        for method in &self.methods {
+            writeln!(f, "    {method}")?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl fmt::Display for TraitMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fn {}(", self.name)?;
+        for (i, param) in self.params.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", param.name, param.type_ann)?;
+        }
+        write!(f, ")")?;
+
+        if let Some(ret_type) = &self.ret_type {
+            write!(f, " -> {}", ret_type)?;
+        }
+
+        match &self.default_body {
+            Some(body) => write!(f, " {}", body),
+            None => write!(f, ";"),
+        }
+    }
+}
@@ -1,8 +1,10 @@
 mod ast;
 mod parser;
+pub mod visitor;
 
 pub use ast::*;
 pub use parser::Parser;
+pub use visitor::Visitor;
 
 #[cfg(test)]
 mod tests;
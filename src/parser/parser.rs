@@ -1,14 +1,15 @@
 use super::ast::*;
 use crate::{
     error::{Error, ErrorKind, Result},
-    lexer::{Token, TokenKind},
+    lexer::{Comment, Token, TokenKind},
     source::{SourceLocation, Span},
 };
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
-    next_expr_id: usize, // Counter for generating unique expression IDs
+    next_expr_id: usize,    // Counter for generating unique expression IDs
+    comments: Vec<Comment>, // Trivia handed on to the program
 }
 
 impl Parser {
@@ -17,9 +18,17 @@ impl Parser {
             tokens,
             current: 0,
             next_expr_id: 0,
+            comments: Vec::new(),
         }
     }
 
+    /// Keep the comments scanned alongside the tokens, so the parsed
+    /// program can be printed back with them
+    pub fn with_comments(mut self, comments: Vec<Comment>) -> Self {
+        self.comments = comments;
+        self
+    }
+
     /// Generate a unique expression ID
     fn next_expr_id(&mut self) -> usize {
         let id = self.next_expr_id;
@@ -48,7 +57,10 @@ impl Parser {
             statements.push(self.parse_statement()?);
         }
 
-        Ok(Program { statements })
+        Ok(Program {
+            statements,
+            comments: std::mem::take(&mut self.comments),
+        })
     }
 
     fn parse_statement(&mut self) -> Result<Stmt> {
@@ -80,6 +92,10 @@ impl Parser {
             self.parse_while_statement()?
         } else if self.match_token(&TokenKind::For) {
             self.parse_for_statement()?
+        } else if self.match_token(&TokenKind::Break) {
+            StmtKind::Break
+        } else if self.match_token(&TokenKind::Continue) {
+            StmtKind::Continue
         } else if self.match_token(&TokenKind::Import) {
             self.parse_import_statement()?
         } else if self.match_token(&TokenKind::Export) {
@@ -90,6 +106,8 @@ impl Parser {
             self.parse_enum_declaration()?
         } else if self.match_token(&TokenKind::Impl) {
             self.parse_impl_block()?
+        } else if self.match_token(&TokenKind::Trait) {
+            self.parse_trait_declaration()?
         } else {
             StmtKind::Expression(self.parse_expression()?)
         };
@@ -182,6 +200,7 @@ impl Parser {
             None
         };
 
+        self.skip_newlines_before_brace();
         self.consume(&TokenKind::LeftBrace, "Expected '{' before function body")?;
         let body = self.parse_block()?;
 
@@ -382,6 +401,7 @@ impl Parser {
                 None
             };
 
+            self.skip_newlines_before_brace();
             self.consume(&TokenKind::LeftBrace, "Expected '{' before function body")?;
             let body = self.parse_block()?;
 
@@ -424,6 +444,7 @@ impl Parser {
                 None
             };
 
+            self.skip_newlines_before_brace();
             self.consume(&TokenKind::LeftBrace, "Expected '{' before function body")?;
             let body = self.parse_block()?;
 
@@ -506,6 +527,7 @@ impl Parser {
             None
         };
 
+        self.skip_newlines_before_brace();
         self.consume(&TokenKind::LeftBrace, "Expected '{' after struct name")?;
 
         let mut fields = Vec::new();
@@ -557,8 +579,18 @@ impl Parser {
             None
         };
 
-        // Parse the type name (potentially with generic arguments)
-        let type_name = self.consume_identifier("Expected type name after 'impl'")?;
+        // Parse the type name (potentially with generic arguments), or the
+        // trait name of `impl Trait for Type`
+        let mut type_name = self.consume_identifier("Expected type name after 'impl'")?;
+        let trait_name = if self.match_token(&TokenKind::For) {
+            let trait_name = std::mem::replace(
+                &mut type_name,
+                self.consume_identifier("Expected type name after 'for'")?,
+            );
+            Some(trait_name)
+        } else {
+            None
+        };
 
         // NOTE: Generic parsing for impl blocks is not yet implemented
         // Currently only simple type names are supported
@@ -570,6 +602,7 @@ impl Parser {
             None
         };
 
+        self.skip_newlines_before_brace();
         self.consume(&TokenKind::LeftBrace, "Expected '{' after impl type")?;
 
         let mut methods = Vec::new();
@@ -603,6 +636,7 @@ impl Parser {
             .unwrap_or_else(|| SourceLocation::initial());
         let impl_block = ImplBlock {
             type_name,
+            trait_name,
             generic_params,
             methods,
             where_clause,
@@ -612,6 +646,61 @@ impl Parser {
         Ok(StmtKind::Impl(impl_block))
     }
 
+    fn parse_trait_declaration(&mut self) -> Result<StmtKind> {
+        let start = self.previous_location();
+        let name = self.consume_identifier("Expected trait name")?;
+
+        self.skip_newlines_before_brace();
+        self.consume(&TokenKind::LeftBrace, "Expected '{' after trait name")?;
+
+        let mut methods = Vec::new();
+        while !self.check(&TokenKind::RightBrace) && !self.is_at_end() {
+            // Skip newlines and separators between signatures
+            if self.match_token(&TokenKind::Newline) || self.match_token(&TokenKind::Semicolon) {
+                continue;
+            }
+            methods.push(self.parse_trait_method()?);
+        }
+
+        self.consume(&TokenKind::RightBrace, "Expected '}' after trait methods")?;
+
+        Ok(StmtKind::Trait(TraitDecl {
+            name,
+            methods,
+            span: Span::new(start, self.previous_location()),
+        }))
+    }
+
+    /// Parse a trait method: a signature, optionally followed by a default body
+    fn parse_trait_method(&mut self) -> Result<TraitMethod> {
+        let start = self.current_location();
+        self.consume(&TokenKind::Fn, "Expected 'fn' for trait method")?;
+
+        let name = self.consume_identifier("Expected method name")?;
+        let params = self.parse_method_params()?;
+
+        let ret_type = if self.match_token(&TokenKind::Arrow) {
+            Some(self.parse_type_annotation()?)
+        } else {
+            None
+        };
+
+        self.skip_newlines_before_brace();
+        let default_body = if self.match_token(&TokenKind::LeftBrace) {
+            Some(self.parse_block()?)
+        } else {
+            None
+        };
+
+        Ok(TraitMethod {
+            name,
+            params,
+            ret_type,
+            default_body,
+            span: Span::new(start, self.previous_location()),
+        })
+    }
+
     fn parse_enum_declaration(&mut self) -> Result<StmtKind> {
         let name = self.consume_identifier("Expected enum name")?;
 
@@ -629,6 +718,7 @@ impl Parser {
             None
         };
 
+        self.skip_newlines_before_brace();
         self.consume(&TokenKind::LeftBrace, "Expected '{' after enum name")?;
 
         let mut variants = Vec::new();
@@ -754,6 +844,40 @@ impl Parser {
             None
         };
 
+        let params = self.parse_method_params()?;
+
+        let ret_type = if self.match_token(&TokenKind::Arrow) {
+            Some(self.parse_type_annotation()?)
+        } else {
+            None
+        };
+
+        // Parse where clause if present
+        let where_clause = if self.check(&TokenKind::Where) {
+            Some(self.parse_where_clause()?)
+        } else {
+            None
+        };
+
+        self.skip_newlines_before_brace();
+        self.consume(&TokenKind::LeftBrace, "Expected '{' before method body")?;
+        let body = self.parse_block()?;
+
+        let span = Span::new(start, self.previous_location());
+        Ok(Method {
+            name,
+            generic_params,
+            params,
+            ret_type,
+            where_clause,
+            body,
+            is_async,
+            span,
+        })
+    }
+
+    /// Parse a method's parameter list, including a leading `self`
+    fn parse_method_params(&mut self) -> Result<Vec<Param>> {
         self.consume(&TokenKind::LeftParen, "Expected '(' after method name")?;
 
         let mut params = Vec::new();
@@ -805,33 +929,7 @@ impl Parser {
 
         self.consume(&TokenKind::RightParen, "Expected ')' after parameters")?;
 
-        let ret_type = if self.match_token(&TokenKind::Arrow) {
-            Some(self.parse_type_annotation()?)
-        } else {
-            None
-        };
-
-        // Parse where clause if present
-        let where_clause = if self.check(&TokenKind::Where) {
-            Some(self.parse_where_clause()?)
-        } else {
-            None
-        };
-
-        self.consume(&TokenKind::LeftBrace, "Expected '{' before method body")?;
-        let body = self.parse_block()?;
-
-        let span = Span::new(start, self.previous_location());
-        Ok(Method {
-            name,
-            generic_params,
-            params,
-            ret_type,
-            where_clause,
-            body,
-            is_async,
-            span,
-        })
+        Ok(params)
     }
 
     fn parse_block(&mut self) -> Result<Block> {
@@ -1117,6 +1215,52 @@ impl Parser {
         ))
     }
 
+    /// Parse the rest of `Name::member` after the `::`
+    ///
+    /// As in patterns, a capitalized member is an enum variant, constructed
+    /// here with its arguments. Anything else names an associated function
+    /// and becomes the identifier `Name::member`, which calls like any other
+    /// function.
+    fn parse_path_expression(&mut self, name: String, start: SourceLocation) -> Result<Expr> {
+        let member = self.consume_identifier("Expected name after '::'")?;
+
+        if !member.chars().next().is_some_and(char::is_uppercase) {
+            let span = Span::new(start, self.previous_location());
+            return Ok(
+                self.create_expr(ExprKind::Identifier(format!("{}::{}", name, member)), span)
+            );
+        }
+
+        let args = if self.match_token(&TokenKind::LeftParen) {
+            let mut args = Vec::new();
+            if !self.check(&TokenKind::RightParen) {
+                loop {
+                    args.push(self.parse_expression()?);
+                    if !self.match_token(&TokenKind::Comma) {
+                        break;
+                    }
+                }
+            }
+            self.consume(
+                &TokenKind::RightParen,
+                "Expected ')' after enum constructor arguments",
+            )?;
+            EnumConstructorArgs::Tuple(args)
+        } else {
+            EnumConstructorArgs::Unit
+        };
+
+        let span = Span::new(start, self.previous_location());
+        Ok(self.create_expr(
+            ExprKind::EnumConstructor {
+                enum_name: Some(name),
+                variant: member,
+                args,
+            },
+            span,
+        ))
+    }
+
     fn parse_primary(&mut self) -> Result<Expr> {
         let start = self.current_location();
 
@@ -1127,6 +1271,14 @@ impl Parser {
                 return Ok(self.create_expr(ExprKind::Literal(Literal::Number(n)), span));
             }
         }
+        if let Some(token) = self.match_token_if(|t| matches!(t, TokenKind::TypedNumber(..))) {
+            if let TokenKind::TypedNumber(n, suffix) = token.kind {
+                let span = Span::new(start, self.previous_location());
+                return Ok(
+                    self.create_expr(ExprKind::Literal(Literal::TypedNumber(n, suffix)), span)
+                );
+            }
+        }
 
         // Strings
         if let Some(token) = self.match_token_if(|t| matches!(t, TokenKind::String(_))) {
@@ -1151,13 +1303,19 @@ impl Parser {
         if let Some(token) = self.match_token_if(|t| matches!(t, TokenKind::Identifier(_))) {
             if let TokenKind::Identifier(name) = token.kind {
                 // Check for generic type arguments
-                if self.check(&TokenKind::Less) {
+                if name == "cast" && self.check(&TokenKind::Less) {
+                    return self.parse_cast_expression(start);
+                } else if self.check(&TokenKind::Less) {
                     // This is a generic constructor like Vec<i32>
                     let type_args = self.parse_generic_args()?;
                     let span = Span::new(start, self.previous_location());
                     return Ok(
                         self.create_expr(ExprKind::GenericConstructor { name, type_args }, span)
                     );
+                } else if self.is_struct_literal_start(&name) {
+                    return self.parse_struct_literal(name, start);
+                } else if self.match_token(&TokenKind::ColonColon) {
+                    return self.parse_path_expression(name, start);
                 } else {
                     // Regular identifier
                     let span = Span::new(start, self.previous_location());
@@ -1178,6 +1336,19 @@ impl Parser {
             return self.parse_closure_expression(start);
         }
 
+        // Closures without parameters: `||` is scanned as a single token
+        if self.match_token(&TokenKind::Or) {
+            let body = Box::new(self.parse_expression()?);
+            let span = Span::new(start, self.previous_location());
+            return Ok(self.create_expr(
+                ExprKind::Closure {
+                    parameters: Vec::new(),
+                    body,
+                },
+                span,
+            ));
+        }
+
         // If expressions
         if self.match_token(&TokenKind::If) {
             return self.parse_if_expression();
@@ -1503,6 +1674,15 @@ impl Parser {
                 });
             }
         }
+        if let Some(token) = self.match_token_if(|t| matches!(t, TokenKind::TypedNumber(..))) {
+            if let TokenKind::TypedNumber(n, suffix) = token.kind {
+                let span = Span::new(start, self.previous_location());
+                return Ok(Pattern {
+                    kind: PatternKind::Literal(Literal::TypedNumber(n, suffix)),
+                    span,
+                });
+            }
+        }
 
         if let Some(token) = self.match_token_if(|t| matches!(t, TokenKind::String(_))) {
             if let TokenKind::String(s) = token.kind {
@@ -1552,6 +1732,33 @@ impl Parser {
             });
         }
 
+        // Tuple destructuring, or a parenthesized pattern
+        if self.match_token(&TokenKind::LeftParen) {
+            let mut patterns = Vec::new();
+            let mut trailing_comma = false;
+
+            while !self.check(&TokenKind::RightParen) {
+                patterns.push(self.parse_pattern()?);
+                trailing_comma = self.match_token(&TokenKind::Comma);
+                if !trailing_comma {
+                    break;
+                }
+            }
+
+            self.consume(&TokenKind::RightParen, "Expected ')' after tuple pattern")?;
+
+            // `(p)` only groups; `(p,)` is a one-element tuple
+            if patterns.len() == 1 && !trailing_comma {
+                return Ok(patterns.into_iter().next().unwrap());
+            }
+
+            let span = Span::new(start, self.previous_location());
+            return Ok(Pattern {
+                kind: PatternKind::Tuple(patterns),
+                span,
+            });
+        }
+
         // Object destructuring
         if self.match_token(&TokenKind::LeftBrace) {
             let mut fields = Vec::new();
@@ -1592,6 +1799,19 @@ impl Parser {
         // Identifier pattern or enum constructor
         if let Some(token) = self.match_token_if(|t| matches!(t, TokenKind::Identifier(_))) {
             if let TokenKind::Identifier(name) = token.kind {
+                // Binding pattern: name @ pattern
+                if self.match_token(&TokenKind::At) {
+                    let pattern = self.parse_primary_pattern()?;
+                    let span = Span::new(start, self.previous_location());
+                    return Ok(Pattern {
+                        kind: PatternKind::Binding {
+                            name,
+                            pattern: Box::new(pattern),
+                        },
+                        span,
+                    });
+                }
+
                 // Check if this is an enum constructor pattern
                 // Could be: Some(x), Option::Some(x), or just Some/None
 
@@ -1766,6 +1986,28 @@ impl Parser {
     }
 
     /// Parse generic type arguments: <T>, <K, V>, <Option<T>, Result<E>>
+    /// Parse the rest of `cast<T>(value)` after the `cast` identifier
+    fn parse_cast_expression(&mut self, start: SourceLocation) -> Result<Expr> {
+        let mut type_args = self.parse_generic_args()?;
+        if type_args.len() != 1 {
+            return Err(self.error("Expected exactly one target type in cast<T>(value)"));
+        }
+        let target_type = type_args.remove(0);
+
+        self.consume(&TokenKind::LeftParen, "Expected '(' after cast<T>")?;
+        let expr = self.parse_expression()?;
+        self.consume(&TokenKind::RightParen, "Expected ')' after cast value")?;
+
+        let span = Span::new(start, self.previous_location());
+        Ok(self.create_expr(
+            ExprKind::Cast {
+                expr: Box::new(expr),
+                target_type,
+            },
+            span,
+        ))
+    }
+
     fn parse_generic_args(&mut self) -> Result<Vec<TypeAnn>> {
         self.consume(&TokenKind::Less, "Expected '<' to start generic arguments")?;
 
@@ -1998,7 +2240,8 @@ impl Parser {
         self.previous()
     }
 
-    fn is_at_end(&self) -> bool {
+    /// Whether all tokens have been consumed
+    pub fn is_at_end(&self) -> bool {
         matches!(self.peek().kind, TokenKind::Eof)
     }
 
@@ -2018,6 +2261,24 @@ impl Parser {
         self.tokens[self.current - 1].clone()
     }
 
+    /// Skip line breaks between a declaration header and its opening brace,
+    /// so `fn main()` may be followed by `{` on the next line
+    fn skip_newlines_before_brace(&mut self) {
+        let mut lookahead = self.current;
+        while matches!(
+            self.tokens.get(lookahead).map(|token| &token.kind),
+            Some(TokenKind::Newline)
+        ) {
+            lookahead += 1;
+        }
+        if matches!(
+            self.tokens.get(lookahead).map(|token| &token.kind),
+            Some(TokenKind::LeftBrace)
+        ) {
+            self.current = lookahead;
+        }
+    }
+
     fn consume(&mut self, kind: &TokenKind, message: &str) -> Result<Token> {
         if self.check(kind) {
             Ok(self.advance())
@@ -2072,6 +2333,51 @@ impl Parser {
     }
 
     /// Parse a closure expression: |param1, param2| body
+    /// Whether `Name {` starts a struct literal rather than a block, such as
+    /// the body of `if Ready { ... }`
+    fn is_struct_literal_start(&self, name: &str) -> bool {
+        if !name.starts_with(|c: char| c.is_ascii_uppercase()) || !self.check(&TokenKind::LeftBrace)
+        {
+            return false;
+        }
+        let mut ahead = self.tokens[self.current + 1..]
+            .iter()
+            .filter(|token| !matches!(token.kind, TokenKind::Newline));
+        match ahead.next().map(|token| &token.kind) {
+            Some(TokenKind::RightBrace) => true,
+            Some(TokenKind::Identifier(_)) => {
+                matches!(
+                    ahead.next().map(|token| &token.kind),
+                    Some(TokenKind::Colon)
+                )
+            }
+            _ => false,
+        }
+    }
+
+    /// Parse `Name { field: value, ... }` after the struct name
+    fn parse_struct_literal(&mut self, name: String, start: SourceLocation) -> Result<Expr> {
+        self.consume(&TokenKind::LeftBrace, "Expected '{' after struct name")?;
+        let mut fields = Vec::new();
+        loop {
+            while self.match_token(&TokenKind::Newline) {}
+            if self.check(&TokenKind::RightBrace) {
+                break;
+            }
+            let field = self.consume_identifier("Expected field name")?;
+            self.consume(&TokenKind::Colon, "Expected ':' after field name")?;
+            fields.push((field, self.parse_expression()?));
+            while self.match_token(&TokenKind::Newline) {}
+            if !self.match_token(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.consume(&TokenKind::RightBrace, "Expected '}' after struct fields")?;
+
+        let span = Span::new(start, self.previous_location());
+        Ok(self.create_expr(ExprKind::StructConstructor { name, fields }, span))
+    }
+
     fn parse_closure_expression(&mut self, start: SourceLocation) -> Result<Expr> {
         let mut parameters = Vec::new();
 
@@ -2129,6 +2435,8 @@ impl Parser {
                 | TokenKind::For
                 | TokenKind::If
                 | TokenKind::While
+                | TokenKind::Break
+                | TokenKind::Continue
                 | TokenKind::Return => return,
                 _ => {}
             }
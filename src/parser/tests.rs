@@ -56,6 +56,51 @@ fn test_parse_impl_blocks() {
     }
 }
 
+#[test]
+fn test_parse_traits() {
+    let program = parse(
+        r#"
+        trait Describe {
+            fn name(self) -> string;
+            fn describe(self) -> string {
+                "a " + self.name()
+            }
+        }
+
+        impl Describe for i32 {
+            fn name(self) -> string {
+                "number"
+            }
+        }
+    "#,
+    )
+    .unwrap();
+
+    assert_eq!(program.statements.len(), 2);
+
+    match &program.statements[0].kind {
+        StmtKind::Trait(trait_decl) => {
+            assert_eq!(trait_decl.name, "Describe");
+            assert_eq!(trait_decl.methods.len(), 2);
+            assert_eq!(trait_decl.methods[0].name, "name");
+            assert!(trait_decl.methods[0].default_body.is_none());
+            assert!(trait_decl.methods[0].ret_type.is_some());
+            assert_eq!(trait_decl.methods[1].name, "describe");
+            assert!(trait_decl.methods[1].default_body.is_some());
+        }
+        _ => panic!("Expected trait declaration"),
+    }
+
+    match &program.statements[1].kind {
+        StmtKind::Impl(impl_block) => {
+            assert_eq!(impl_block.trait_name.as_deref(), Some("Describe"));
+            assert_eq!(impl_block.type_name, "i32");
+            assert_eq!(impl_block.methods.len(), 1);
+        }
+        _ => panic!("Expected impl block"),
+    }
+}
+
 #[test]
 fn test_parse_generic_impl_block() {
     let program = parse(
@@ -141,6 +186,34 @@ fn test_parse_async_methods() {
     }
 }
 
+#[test]
+fn test_parse_paths() {
+    // Capitalized members construct enum variants
+    let expr = parse_expr("Color::Red").unwrap();
+    assert!(matches!(
+        expr.kind,
+        ExprKind::EnumConstructor { ref enum_name, ref variant, args: EnumConstructorArgs::Unit }
+            if enum_name.as_deref() == Some("Color") && variant == "Red"
+    ));
+
+    let expr = parse_expr("Shape::Circle(1.5)").unwrap();
+    assert!(matches!(
+        expr.kind,
+        ExprKind::EnumConstructor { args: EnumConstructorArgs::Tuple(ref args), .. }
+            if args.len() == 1
+    ));
+
+    // Other members are associated functions called like any function
+    let expr = parse_expr("Point::new(1, 2)").unwrap();
+    match expr.kind {
+        ExprKind::Call { callee, args } => {
+            assert!(matches!(callee.kind, ExprKind::Identifier(ref name) if name == "Point::new"));
+            assert_eq!(args.len(), 2);
+        }
+        _ => panic!("Expected call"),
+    }
+}
+
 #[test]
 fn test_parse_literals() {
     // Numbers
@@ -271,6 +344,23 @@ fn test_parse_grouped_expressions() {
     }
 }
 
+#[test]
+fn test_parse_closure_without_parameters() {
+    for source in ["|| 42", "| | 42"] {
+        let expr = parse_expr(source).unwrap();
+        match &expr.kind {
+            ExprKind::Closure { parameters, body } => {
+                assert!(parameters.is_empty());
+                assert!(matches!(
+                    body.kind,
+                    ExprKind::Literal(Literal::Number(42.0))
+                ));
+            }
+            _ => panic!("Expected closure expression"),
+        }
+    }
+}
+
 #[test]
 fn test_parse_call_expressions() {
     let expr = parse_expr("foo()").unwrap();
@@ -515,6 +605,20 @@ fn test_parse_while_statement() {
     }
 }
 
+#[test]
+fn test_parse_break_and_continue() {
+    let program = parse("while true { continue; break }").unwrap();
+
+    match &program.statements[0].kind {
+        StmtKind::While { body, .. } => {
+            assert!(matches!(body.statements[0].kind, StmtKind::Continue));
+            assert!(matches!(body.statements[1].kind, StmtKind::Break));
+            assert!(body.final_expr.is_none());
+        }
+        _ => panic!("Expected while statement"),
+    }
+}
+
 #[test]
 fn test_parse_return_statement() {
     let program = parse("return 42").unwrap();
@@ -1626,6 +1730,54 @@ fn test_parse_generic_constructors() {
     }
 }
 
+#[test]
+fn test_parse_cast_expression() {
+    let expr = parse_expr("cast<i64>(x + 1)").unwrap();
+    match &expr.kind {
+        ExprKind::Cast { expr, target_type } => {
+            assert!(matches!(&target_type.kind, TypeKind::Named(n) if n == "i64"));
+            assert!(matches!(&expr.kind, ExprKind::Binary { .. }));
+        }
+        _ => panic!("Expected cast expression, got {:?}", expr.kind),
+    }
+    assert_eq!(expr.to_string(), "cast<i64>((x + 1))");
+
+    assert!(parse_expr("cast<i32, f32>(x)").is_err());
+    assert!(parse_expr("cast<i32> x").is_err());
+
+    // `cast` is an ordinary identifier elsewhere
+    assert!(matches!(
+        parse_expr("cast").unwrap().kind,
+        ExprKind::Identifier(ref name) if name == "cast"
+    ));
+}
+
+#[test]
+fn test_parse_struct_literals() {
+    let expr = parse_expr("Point { x: 1, y: a + 2 }").unwrap();
+    match &expr.kind {
+        ExprKind::StructConstructor { name, fields } => {
+            assert_eq!(name, "Point");
+            let names: Vec<&str> = fields.iter().map(|(name, _)| name.as_str()).collect();
+            assert_eq!(names, vec!["x", "y"]);
+            assert!(matches!(fields[1].1.kind, ExprKind::Binary { .. }));
+        }
+        _ => panic!("Expected struct literal, got {:?}", expr.kind),
+    }
+
+    // A capitalized condition followed by a block is not a struct literal
+    let program = parse("if Ready { go() }").unwrap();
+    match &program.statements[0].kind {
+        StmtKind::Expression(expr) => match &expr.kind {
+            ExprKind::If { condition, .. } => {
+                assert!(matches!(&condition.kind, ExprKind::Identifier(n) if n == "Ready"));
+            }
+            _ => panic!("Expected if expression"),
+        },
+        _ => panic!("Expected expression statement"),
+    }
+}
+
 #[test]
 fn test_parse_type_parameter_patterns() {
     // Test various type parameter patterns
@@ -2189,3 +2341,25 @@ fn test_parse_enum_with_where_clause() {
         _ => panic!("Expected enum statement"),
     }
 }
+
+#[test]
+fn test_parse_declaration_brace_on_next_line() {
+    let program = parse(
+        "struct Point\n{\n    x: i32\n}\n\nimpl Point\n{\n    fn get(self) -> i32\n    {\n        self.x\n    }\n}\n\nfn main()\n{\n    let p = 1;\n}\n",
+    )
+    .unwrap();
+
+    assert_eq!(program.statements.len(), 3);
+    assert!(matches!(
+        program.statements[0].kind,
+        StmtKind::Struct { .. }
+    ));
+    match &program.statements[1].kind {
+        StmtKind::Impl(impl_block) => assert_eq!(impl_block.methods.len(), 1),
+        _ => panic!("Expected impl block"),
+    }
+    match &program.statements[2].kind {
+        StmtKind::Function { body, .. } => assert_eq!(body.statements.len(), 1),
+        _ => panic!("Expected function"),
+    }
+}
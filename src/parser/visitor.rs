@@ -0,0 +1,273 @@
+//! Read-only AST traversal
+//!
+//! `Visitor` has one hook per node category. Every hook defaults to the
+//! matching `walk_*` function, which visits the node's children, so an
+//! implementation only overrides the nodes it cares about and calls the
+//! `walk_*` function itself to keep descending.
+
+use super::ast::*;
+
+pub trait Visitor {
+    fn visit_program(&mut self, program: &Program) {
+        walk_program(self, program);
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt) {
+        walk_stmt(self, stmt);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        walk_block(self, block);
+    }
+
+    /// Called for free functions (including exported ones) before their body
+    fn visit_function(&mut self, name: &str, params: &[Param], body: &Block, stmt: &Stmt) {
+        let _ = (name, params, stmt);
+        self.visit_block(body);
+    }
+
+    /// Called for each method of an impl block
+    fn visit_method(&mut self, impl_block: &ImplBlock, method: &Method) {
+        let _ = impl_block;
+        self.visit_block(&method.body);
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern) {
+        let _ = pattern;
+    }
+}
+
+pub fn walk_program<V: Visitor + ?Sized>(visitor: &mut V, program: &Program) {
+    for stmt in &program.statements {
+        visitor.visit_stmt(stmt);
+    }
+}
+
+pub fn walk_block<V: Visitor + ?Sized>(visitor: &mut V, block: &Block) {
+    for stmt in &block.statements {
+        visitor.visit_stmt(stmt);
+    }
+    if let Some(expr) = &block.final_expr {
+        visitor.visit_expr(expr);
+    }
+}
+
+pub fn walk_stmt<V: Visitor + ?Sized>(visitor: &mut V, stmt: &Stmt) {
+    match &stmt.kind {
+        StmtKind::Let { init, .. } => {
+            if let Some(init) = init {
+                visitor.visit_expr(init);
+            }
+        }
+        StmtKind::Function {
+            name, params, body, ..
+        } => visitor.visit_function(name, params, body, stmt),
+        StmtKind::Return(expr) => {
+            if let Some(expr) = expr {
+                visitor.visit_expr(expr);
+            }
+        }
+        StmtKind::Expression(expr) => visitor.visit_expr(expr),
+        StmtKind::While { condition, body } => {
+            visitor.visit_expr(condition);
+            visitor.visit_block(body);
+        }
+        StmtKind::For { iterable, body, .. } => {
+            visitor.visit_expr(iterable);
+            visitor.visit_block(body);
+        }
+        StmtKind::Export { export } => match export {
+            ExportKind::Function {
+                name, params, body, ..
+            } => visitor.visit_function(name, params, body, stmt),
+            ExportKind::Variable {
+                init: Some(init), ..
+            } => visitor.visit_expr(init),
+            ExportKind::Default { expr } => visitor.visit_expr(expr),
+            ExportKind::Declaration(decl) => visitor.visit_stmt(decl),
+            ExportKind::Named { .. } | ExportKind::Variable { init: None, .. } => {}
+        },
+        StmtKind::Impl(impl_block) => {
+            for method in &impl_block.methods {
+                visitor.visit_method(impl_block, method);
+            }
+        }
+        StmtKind::Trait(trait_decl) => {
+            for body in trait_decl
+                .methods
+                .iter()
+                .filter_map(|m| m.default_body.as_ref())
+            {
+                visitor.visit_block(body);
+            }
+        }
+        StmtKind::Import { .. }
+        | StmtKind::Struct { .. }
+        | StmtKind::Enum { .. }
+        | StmtKind::Break
+        | StmtKind::Continue => {}
+    }
+}
+
+pub fn walk_expr<V: Visitor + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match &expr.kind {
+        ExprKind::Literal(_) | ExprKind::Identifier(_) | ExprKind::GenericConstructor { .. } => {}
+        ExprKind::Binary { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        ExprKind::Unary { expr, .. }
+        | ExprKind::Await { expr }
+        | ExprKind::ErrorPropagation { expr }
+        | ExprKind::Cast { expr, .. } => visitor.visit_expr(expr),
+        ExprKind::Call { callee, args } => {
+            visitor.visit_expr(callee);
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        ExprKind::Index { object, index } => {
+            visitor.visit_expr(object);
+            visitor.visit_expr(index);
+        }
+        ExprKind::Member { object, .. } => visitor.visit_expr(object),
+        ExprKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            visitor.visit_expr(condition);
+            visitor.visit_expr(then_branch);
+            if let Some(else_branch) = else_branch {
+                visitor.visit_expr(else_branch);
+            }
+        }
+        ExprKind::Block(block) => visitor.visit_block(block),
+        ExprKind::Array(elements) => {
+            for element in elements {
+                visitor.visit_expr(element);
+            }
+        }
+        ExprKind::Assign { target, value } => {
+            visitor.visit_expr(target);
+            visitor.visit_expr(value);
+        }
+        ExprKind::Match { expr, arms } => {
+            visitor.visit_expr(expr);
+            for arm in arms {
+                visitor.visit_pattern(&arm.pattern);
+                if let Some(guard) = &arm.guard {
+                    visitor.visit_expr(guard);
+                }
+                visitor.visit_expr(&arm.body);
+            }
+        }
+        ExprKind::ListComprehension {
+            element,
+            iterable,
+            condition,
+            ..
+        } => {
+            visitor.visit_expr(iterable);
+            if let Some(condition) = condition {
+                visitor.visit_expr(condition);
+            }
+            visitor.visit_expr(element);
+        }
+        ExprKind::StructConstructor { fields, .. } => {
+            for (_, value) in fields {
+                visitor.visit_expr(value);
+            }
+        }
+        ExprKind::EnumConstructor { args, .. } => match args {
+            EnumConstructorArgs::Unit => {}
+            EnumConstructorArgs::Tuple(values) => {
+                for value in values {
+                    visitor.visit_expr(value);
+                }
+            }
+            EnumConstructorArgs::Struct(fields) => {
+                for (_, value) in fields {
+                    visitor.visit_expr(value);
+                }
+            }
+        },
+        ExprKind::TryCatch {
+            try_expr,
+            catch_clauses,
+            finally_block,
+        } => {
+            visitor.visit_expr(try_expr);
+            for clause in catch_clauses {
+                if let Some(condition) = &clause.condition {
+                    visitor.visit_expr(condition);
+                }
+                visitor.visit_block(&clause.handler);
+            }
+            if let Some(finally_block) = finally_block {
+                visitor.visit_block(finally_block);
+            }
+        }
+        ExprKind::Closure { body, .. } => visitor.visit_expr(body),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    #[derive(Default)]
+    struct Counter {
+        functions: Vec<String>,
+        calls: usize,
+    }
+
+    impl Visitor for Counter {
+        fn visit_function(&mut self, name: &str, _: &[Param], body: &Block, _: &Stmt) {
+            self.functions.push(name.to_string());
+            self.visit_block(body);
+        }
+
+        fn visit_method(&mut self, impl_block: &ImplBlock, method: &Method) {
+            self.functions
+                .push(format!("{}::{}", impl_block.type_name, method.name));
+            self.visit_block(&method.body);
+        }
+
+        fn visit_expr(&mut self, expr: &Expr) {
+            if matches!(expr.kind, ExprKind::Call { .. }) {
+                self.calls += 1;
+            }
+            walk_expr(self, expr);
+        }
+    }
+
+    #[test]
+    fn test_visits_nested_nodes() {
+        let source = r#"
+struct Point { x: i32 }
+impl Point {
+    fn len(self) -> i32 { abs(self.x) }
+}
+fn main() {
+    let p = 1;
+    if true { print(p.len()); } else { print(0); }
+    for i in range(0, 3) { print(i); }
+}
+"#;
+        let (tokens, _) = Lexer::new(source).unwrap().scan_tokens();
+        let program = Parser::new(tokens).parse().unwrap();
+
+        let mut counter = Counter::default();
+        counter.visit_program(&program);
+
+        assert_eq!(counter.functions, vec!["Point::len", "main"]);
+        assert_eq!(counter.calls, 6);
+    }
+}
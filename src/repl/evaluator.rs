@@ -0,0 +1,222 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::debugger::{
+    DebugInterpreter, Debugger, InterpreterState, PauseState, StepAction, StepController,
+};
+use crate::parser::{Program, StmtKind};
+use crate::runtime::Value;
+use crate::semantic::{FunctionSignature, SemanticAnalyzer};
+use crate::source::{SourceLocation, Span};
+use crate::types::conversion::type_from_ast;
+use crate::types::Type;
+use crate::{Lexer, Parser};
+
+/// Executes REPL input, keeping variables and functions between lines
+///
+/// JIT-compiled code cannot hand its locals back to the REPL, so lines are
+/// executed by the statement interpreter. Each line is still checked by the
+/// semantic analyzer, seeded with everything defined on earlier lines.
+pub struct ReplEvaluator {
+    debugger: Arc<Debugger>,
+    state: InterpreterState,
+    /// Signatures of functions defined so far, for semantic analysis
+    signatures: HashMap<String, FunctionSignature>,
+}
+
+impl ReplEvaluator {
+    pub fn new() -> Self {
+        ReplEvaluator {
+            debugger: Arc::new(Debugger::new()),
+            state: InterpreterState::default(),
+            signatures: HashMap::new(),
+        }
+    }
+
+    /// Check and run one piece of input
+    ///
+    /// Returns the value of a trailing expression, if it is not `null`.
+    pub fn eval(&mut self, source: &str) -> Result<Option<Value>, String> {
+        let lexer = Lexer::new(source).map_err(|e| format!("Lexer error: {}", e))?;
+        let (tokens, lex_errors) = lexer.scan_tokens();
+        if !lex_errors.is_empty() {
+            return Err(lex_errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+
+        let mut parser = Parser::new(tokens);
+        let program = parser.parse().map_err(|e| format!("Parse error: {}", e))?;
+
+        self.analyze(&program)?;
+        self.record_signatures(&program);
+
+        let mut controller = ReplController;
+        let state = std::mem::take(&mut self.state);
+        let mut interpreter =
+            DebugInterpreter::new(self.debugger.clone(), "<repl>", &mut controller)
+                .with_state(state);
+        let result = interpreter.run_incremental(&program);
+        self.state = interpreter.into_state();
+
+        match result {
+            Ok(Some(Value::Null)) | Ok(None) => Ok(None),
+            Ok(value) => Ok(value),
+            Err(e) => Err(format!("Runtime error: {}", e)),
+        }
+    }
+
+    /// Variables defined so far
+    pub fn variables(&self) -> &HashMap<String, Value> {
+        self.state.globals()
+    }
+
+    /// Forget all variables and functions
+    pub fn clear(&mut self) {
+        self.state = InterpreterState::default();
+        self.signatures.clear();
+    }
+
+    fn analyze(&self, program: &Program) -> Result<(), String> {
+        let mut analyzer = SemanticAnalyzer::new();
+
+        // Names defined again by this input replace the session's versions
+        let redefined: HashSet<&str> = program
+            .statements
+            .iter()
+            .filter_map(|stmt| match &stmt.kind {
+                StmtKind::Let { name, .. } | StmtKind::Function { name, .. } => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        let span = Span::single(SourceLocation::initial());
+        let symbols = analyzer.symbol_table_mut();
+        for (name, value) in self.state.globals() {
+            if !redefined.contains(name.as_str()) {
+                let _ = symbols.define_variable(name.clone(), value_type(value), span, true);
+            }
+        }
+        for (name, signature) in &self.signatures {
+            if !redefined.contains(name.as_str()) {
+                let _ = symbols.define_function(name.clone(), signature.clone(), span);
+            }
+        }
+
+        analyzer
+            .analyze_program(program)
+            .map_err(|e| format!("Semantic error: {}", e))?;
+        if !analyzer.errors().is_empty() {
+            return Err(analyzer
+                .errors()
+                .iter()
+                .map(|e| format!("Semantic error: {}", e.clone().into_error()))
+                .collect::<Vec<_>>()
+                .join("\n"));
+        }
+        Ok(())
+    }
+
+    fn record_signatures(&mut self, program: &Program) {
+        for stmt in &program.statements {
+            if let StmtKind::Function {
+                name,
+                params,
+                ret_type,
+                is_async,
+                ..
+            } = &stmt.kind
+            {
+                let signature = FunctionSignature {
+                    generic_params: Vec::new(),
+                    params: params
+                        .iter()
+                        .map(|p| (p.name.clone(), type_from_ast(&p.type_ann)))
+                        .collect(),
+                    return_type: ret_type
+                        .as_ref()
+                        .map(type_from_ast)
+                        .unwrap_or(Type::Unknown),
+                    is_const: false,
+                    is_async: *is_async,
+                };
+                self.signatures.insert(name.clone(), signature);
+            }
+        }
+    }
+}
+
+impl Default for ReplEvaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Runs to completion, printing program output directly
+struct ReplController;
+
+impl StepController for ReplController {
+    fn on_pause(&mut self, _state: &PauseState<'_>) -> StepAction {
+        StepAction::Continue
+    }
+}
+
+fn value_type(value: &Value) -> Type {
+    match value {
+        Value::I32(_) => Type::I32,
+        Value::I64(_) => Type::I64,
+        Value::F32(_) => Type::F32,
+        Value::F64(_) => Type::F64,
+        Value::Bool(_) => Type::Bool,
+        Value::String(_) => Type::String,
+        Value::Array(_) => Type::Array(Box::new(Type::Unknown)),
+        _ => Type::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variables_persist_between_lines() {
+        let mut repl = ReplEvaluator::new();
+        assert_eq!(repl.eval("let x = 40;").unwrap(), None);
+        assert_eq!(repl.eval("let y = x + 2;").unwrap(), None);
+        assert_eq!(
+            repl.eval("y").unwrap().map(|v| v.to_string()),
+            Some("42".to_string())
+        );
+        assert!(repl.variables().contains_key("x"));
+    }
+
+    #[test]
+    fn test_functions_persist_between_lines() {
+        let mut repl = ReplEvaluator::new();
+        repl.eval("fn square(n: i32) -> i32 { n * n }").unwrap();
+        assert_eq!(
+            repl.eval("square(7)").unwrap().map(|v| v.to_string()),
+            Some("49".to_string())
+        );
+    }
+
+    #[test]
+    fn test_errors_leave_session_intact() {
+        let mut repl = ReplEvaluator::new();
+        repl.eval("let x = 1;").unwrap();
+        assert!(repl.eval("let y = undefined_name;").is_err());
+        assert!(repl.eval("x").is_ok());
+
+        repl.clear();
+        assert!(repl.variables().is_empty());
+    }
+
+    #[test]
+    fn test_value_type_keeps_width() {
+        assert_eq!(value_type(&Value::I64(i64::MAX)), Type::I64);
+        assert_eq!(value_type(&Value::F32(1.5)), Type::F32);
+        assert_eq!(value_type(&Value::F64(1.5)), Type::F64);
+    }
+}
@@ -2,14 +2,18 @@ use colored::*;
 use std::io::{self, Write};
 
 use crate::error::ErrorReporter;
+use crate::lexer::NumberSuffix;
 use crate::runtime::{Runtime, Value};
 use crate::semantic::SemanticAnalyzer;
+use crate::stdlib::inspect::inspect_value;
 use crate::{Lexer, Parser, Token, TokenKind};
 
+mod evaluator;
 mod history;
 mod module_loader;
 mod session;
 
+pub use evaluator::ReplEvaluator;
 pub use history::History;
 pub use module_loader::{ModuleExports, ModuleInfo, ModuleLoader};
 pub use session::Session;
@@ -19,6 +23,8 @@ pub use session::Session;
 pub enum ReplMode {
     /// Interactive development mode (default)
     Interactive,
+    /// Execute each line, keeping variables and functions between lines
+    Run,
     /// Token analysis mode
     Tokens,
     /// Parse tree mode
@@ -39,6 +45,8 @@ pub struct EnhancedRepl {
     runtime: Runtime,
     /// Module loader for handling imports
     module_loader: ModuleLoader,
+    /// Interpreter state for run mode
+    evaluator: ReplEvaluator,
     /// Current multiline input buffer
     multiline_buffer: String,
     /// Whether we're in multiline input mode
@@ -59,6 +67,7 @@ impl EnhancedRepl {
             history,
             runtime,
             module_loader: ModuleLoader::new(),
+            evaluator: ReplEvaluator::new(),
             multiline_buffer: String::new(),
             in_multiline: false,
             prompt_counter: 1,
@@ -129,6 +138,10 @@ impl EnhancedRepl {
             "  {}  - Switch to interactive development mode (default)",
             ":interactive".cyan()
         );
+        println!(
+            "  {}          - Switch to run mode (execute each line)",
+            ":run".cyan()
+        );
         println!(
             "  {}      - Switch to token analysis mode",
             ":tokens".cyan()
@@ -155,6 +168,7 @@ impl EnhancedRepl {
         } else {
             match self.mode {
                 ReplMode::Interactive => format!("[{}]> ", self.prompt_counter.to_string().green()),
+                ReplMode::Run => format!("run[{}]> ", self.prompt_counter.to_string().green()),
                 ReplMode::Tokens => "tokens> ".cyan().to_string(),
                 ReplMode::Parse => "parse> ".yellow().to_string(),
                 ReplMode::Debug => "debug> ".red().to_string(),
@@ -186,6 +200,10 @@ impl EnhancedRepl {
                 self.mode = ReplMode::Interactive;
                 println!("Switched to {} mode", "interactive".green());
             }
+            ":run" => {
+                self.mode = ReplMode::Run;
+                println!("Switched to {} mode", "run".green());
+            }
             ":tokens" => {
                 self.mode = ReplMode::Tokens;
                 println!("Switched to {} mode", "tokens".cyan());
@@ -201,6 +219,7 @@ impl EnhancedRepl {
             ":history" => self.show_history(),
             ":clear" => {
                 self.session.clear();
+                self.evaluator.clear();
                 println!("{} Session state cleared", "✓".green());
             }
             ":vars" => self.show_variables(),
@@ -346,7 +365,11 @@ impl EnhancedRepl {
                 let complete_input = self.multiline_buffer.clone();
                 self.multiline_buffer.clear();
                 self.in_multiline = false;
-                self.execute_input(complete_input);
+                if self.mode == ReplMode::Run {
+                    self.run_input(complete_input);
+                } else {
+                    self.execute_input(complete_input);
+                }
                 self.prompt_counter += 1;
             }
             return;
@@ -355,6 +378,7 @@ impl EnhancedRepl {
         // Process single line input
         match self.mode {
             ReplMode::Interactive => self.execute_input(input),
+            ReplMode::Run => self.run_input(input),
             ReplMode::Tokens => self.analyze_tokens(input),
             ReplMode::Parse => self.analyze_parse(input),
             ReplMode::Debug => self.debug_input(input),
@@ -380,7 +404,7 @@ impl EnhancedRepl {
         match self.compile_and_run(&input) {
             Ok(result) => {
                 if let Some(value) = result {
-                    println!("=> {}", format!("{:?}", value).green());
+                    println!("=> {}", inspect_value(&value).green());
                 }
             }
             Err(error) => {
@@ -389,6 +413,27 @@ impl EnhancedRepl {
         }
     }
 
+    /// Execute input in run mode
+    fn run_input(&mut self, input: String) {
+        match self.evaluator.eval(&input) {
+            Ok(Some(value)) => println!("=> {}", inspect_value(&value).green()),
+            Ok(None) => {}
+            Err(error) => println!("{}", error.red()),
+        }
+
+        // Keep `:vars` in sync with the interpreter
+        let variables: Vec<(String, Value)> = self
+            .evaluator
+            .variables()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        for (name, value) in variables {
+            let var_type = self.infer_value_type(&value);
+            self.session.define_variable(name, value, var_type);
+        }
+    }
+
     /// Compile and run input, updating session state
     fn compile_and_run(&mut self, source: &str) -> Result<Option<Value>, String> {
         // Tokenize
@@ -585,6 +630,12 @@ impl EnhancedRepl {
     fn literal_to_value(&self, literal: &crate::parser::Literal) -> Value {
         match literal {
             crate::parser::Literal::Number(n) => Value::F32(*n as f32),
+            crate::parser::Literal::TypedNumber(n, suffix) => match suffix {
+                NumberSuffix::I32 => Value::I32(*n as i32),
+                NumberSuffix::I64 | NumberSuffix::U32 | NumberSuffix::U64 => Value::I64(*n as i64),
+                NumberSuffix::F32 => Value::F32(*n as f32),
+                NumberSuffix::F64 => Value::F64(*n),
+            },
             crate::parser::Literal::String(s) => Value::String(s.clone()),
             crate::parser::Literal::Boolean(b) => Value::Bool(*b),
             crate::parser::Literal::Null => Value::Null,
@@ -597,9 +648,9 @@ impl EnhancedRepl {
             Value::Null => crate::types::Type::Unknown,
             Value::Bool(_) => crate::types::Type::Bool,
             Value::I32(_) => crate::types::Type::I32,
-            Value::I64(_) => crate::types::Type::I32, // Map I64 to I32 since Type doesn't have I64
+            Value::I64(_) => crate::types::Type::I64,
             Value::F32(_) => crate::types::Type::F32,
-            Value::F64(_) => crate::types::Type::F32, // Map F64 to F32 since Type doesn't have F64
+            Value::F64(_) => crate::types::Type::F64,
             Value::String(_) => crate::types::Type::String,
             Value::Array(_) => crate::types::Type::Array(Box::new(crate::types::Type::Unknown)),
             Value::Object(_) => crate::types::Type::Unknown,
@@ -708,7 +759,9 @@ impl EnhancedRepl {
         match var_type {
             crate::types::Type::Bool => Value::Bool(false),
             crate::types::Type::I32 => Value::I32(0),
+            crate::types::Type::I64 => Value::I64(0),
             crate::types::Type::F32 => Value::F32(0.0),
+            crate::types::Type::F64 => Value::F64(0.0),
             crate::types::Type::String => Value::String(String::new()),
             _ => Value::Null,
         }
@@ -0,0 +1,193 @@
+//! Fixed-size arena for Script allocations
+//!
+//! The embedded profile serves every allocation the memory manager makes
+//! from one block reserved when the runtime starts, so a program can never
+//! grow past it and never asks the system allocator for more. Free space is
+//! kept as a list of blocks ordered by offset. Allocation takes the first
+//! block that fits, and freed blocks merge with their neighbours.
+
+use std::alloc::{self, Layout};
+use std::collections::BTreeMap;
+use std::ptr::NonNull;
+use std::sync::{Mutex, MutexGuard};
+
+/// Every block starts and ends on this boundary
+const BLOCK_ALIGN: usize = 16;
+
+/// A fixed block of memory handed out in pieces
+pub struct Arena {
+    base: NonNull<u8>,
+    capacity: usize,
+    /// Free blocks as offset to length, with no two adjacent
+    free: Mutex<BTreeMap<usize, usize>>,
+}
+
+// The memory behind `base` is only touched through the free list lock or by
+// the owner of an allocation
+unsafe impl Send for Arena {}
+unsafe impl Sync for Arena {}
+
+impl Arena {
+    /// Reserve an arena of at least `capacity` bytes
+    ///
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "arena capacity must be non-zero");
+        let capacity = round_up(capacity, BLOCK_ALIGN);
+        let layout = Layout::from_size_align(capacity, BLOCK_ALIGN)
+            .expect("arena capacity overflows a layout");
+        let base = NonNull::new(unsafe { alloc::alloc(layout) })
+            .unwrap_or_else(|| alloc::handle_alloc_error(layout));
+
+        Arena {
+            base,
+            capacity,
+            free: Mutex::new(BTreeMap::from([(0, capacity)])),
+        }
+    }
+
+    fn free(&self) -> MutexGuard<'_, BTreeMap<usize, usize>> {
+        self.free
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Total size of the arena in bytes
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Bytes not handed out, though not necessarily in one block
+    pub fn available(&self) -> usize {
+        self.free().values().sum()
+    }
+
+    /// Whether `ptr` points into the arena
+    pub fn contains(&self, ptr: *const u8) -> bool {
+        let start = self.base.as_ptr() as usize;
+        (start..start + self.capacity).contains(&(ptr as usize))
+    }
+
+    /// Allocate a block for `layout`, or `None` when no free block fits
+    pub fn allocate(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let size = block_size(layout);
+        let align = layout.align().max(BLOCK_ALIGN);
+        let base = self.base.as_ptr() as usize;
+
+        let mut free = self.free();
+        let (offset, len, start) = free.iter().find_map(|(&offset, &len)| {
+            let start = round_up(base + offset, align) - base;
+            (start - offset + size <= len).then_some((offset, len, start))
+        })?;
+
+        free.remove(&offset);
+        if start > offset {
+            free.insert(offset, start - offset);
+        }
+        let end = start + size;
+        if end < offset + len {
+            free.insert(end, offset + len - end);
+        }
+
+        // SAFETY: `start + size` lies within the arena
+        Some(unsafe { NonNull::new_unchecked(self.base.as_ptr().add(start)) })
+    }
+
+    /// Return a block to the arena
+    ///
+    /// # Safety
+    /// `ptr` must have come from `allocate` on this arena with the same
+    /// `layout`, and must not be used or freed again afterwards.
+    pub unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let mut offset = ptr.as_ptr() as usize - self.base.as_ptr() as usize;
+        let mut len = block_size(layout);
+
+        let mut free = self.free();
+        if let Some(next) = free.remove(&(offset + len)) {
+            len += next;
+        }
+        let previous = free
+            .range(..offset)
+            .next_back()
+            .map(|(&start, &size)| (start, size));
+        if let Some((start, size)) = previous {
+            if start + size == offset {
+                free.remove(&start);
+                offset = start;
+                len += size;
+            }
+        }
+        free.insert(offset, len);
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        let layout = Layout::from_size_align(self.capacity, BLOCK_ALIGN)
+            .expect("arena layout was valid when allocated");
+        unsafe { alloc::dealloc(self.base.as_ptr(), layout) };
+    }
+}
+
+impl std::fmt::Debug for Arena {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Arena")
+            .field("capacity", &self.capacity)
+            .field("available", &self.available())
+            .finish()
+    }
+}
+
+/// Bytes taken from the arena for `layout`
+fn block_size(layout: Layout) -> usize {
+    round_up(layout.size().max(1), BLOCK_ALIGN)
+}
+
+fn round_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocations_stay_within_capacity() {
+        let arena = Arena::new(100);
+        assert_eq!(arena.capacity(), 112);
+
+        let small = Layout::from_size_align(40, 8).unwrap();
+        let first = arena.allocate(small).unwrap();
+        let second = arena.allocate(small).unwrap();
+        assert!(arena.contains(first.as_ptr()) && arena.contains(second.as_ptr()));
+        assert_eq!(arena.available(), 16);
+        assert!(arena.allocate(small).is_none());
+
+        // Freeing both merges them back into one block big enough for more
+        unsafe {
+            arena.deallocate(first, small);
+            arena.deallocate(second, small);
+        }
+        assert_eq!(arena.available(), 112);
+        let large = Layout::from_size_align(100, 8).unwrap();
+        assert!(arena.allocate(large).is_some());
+        let outside = 0u8;
+        assert!(!arena.contains(&outside));
+    }
+
+    #[test]
+    fn test_alignment_is_respected() {
+        let arena = Arena::new(1024);
+        let byte = Layout::from_size_align(1, 1).unwrap();
+        let wide = Layout::from_size_align(64, 64).unwrap();
+
+        let _byte = arena.allocate(byte).unwrap();
+        let aligned = arena.allocate(wide).unwrap();
+        assert_eq!(aligned.as_ptr() as usize % 64, 0);
+
+        // The padding skipped to align it is still free
+        unsafe { arena.deallocate(aligned, wide) };
+        assert_eq!(arena.available(), 1024 - 16);
+    }
+}
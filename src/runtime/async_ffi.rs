@@ -31,6 +31,13 @@ fn get_global_executor() -> Result<Arc<Mutex<Executor>>, SecurityError> {
     Ok(executor)
 }
 
+/// Stop the global executor if it was ever started, cancelling its tasks
+pub(crate) fn shutdown_global_executor() {
+    if let Some(executor) = GLOBAL_EXECUTOR.get() {
+        let _ = Executor::shutdown(executor.clone());
+    }
+}
+
 /// Get or initialize the global security manager
 fn get_security_manager() -> Result<Arc<Mutex<AsyncSecurityManager>>, SecurityError> {
     GLOBAL_SECURITY_MANAGER.get_or_init(|| {
@@ -8,6 +8,9 @@ use std::thread;
 use std::time::{Duration, Instant};
 
 use crate::error::{Error, Result};
+use crate::runtime::deterministic::{self, VirtualClock};
+use crate::runtime::leak_check::{ResourceKind, TrackedResource};
+use crate::runtime::profile;
 
 /// Configuration for the async runtime with security limits
 #[derive(Debug, Clone)]
@@ -397,6 +400,8 @@ pub struct Task {
     is_running: AtomicBool,
     /// Atomic flag to mark as completed
     is_completed: AtomicBool,
+    /// Open until the task finishes or is dropped
+    _resource: TrackedResource,
 }
 
 /// Unique identifier for tasks
@@ -550,7 +555,9 @@ impl Executor {
     }
 
     /// Spawn a new task with security checks and atomic operations
+    #[track_caller]
     pub fn spawn(executor: Arc<Mutex<Self>>, future: BoxedFuture<()>) -> Result<TaskId> {
+        profile::require_full("The async executor")?;
         let (task_id, shared) = {
             let mut exec = executor
                 .lock()
@@ -584,6 +591,9 @@ impl Executor {
                 waker,
                 is_running: AtomicBool::new(false),
                 is_completed: AtomicBool::new(false),
+                _resource: TrackedResource::new(ResourceKind::Task, || {
+                    format!("async task {}", task_id.0)
+                }),
             });
 
             // Ensure tasks vector is large enough but bounded
@@ -614,6 +624,9 @@ impl Executor {
             .map_err(|_| Error::lock_poisoned("Failed to acquire lock on executor"))?
             .shared
             .clone();
+        // Timers on a virtual clock only fire when the clock moves, so an
+        // idle executor moves it to the next timer
+        let clock = deterministic::virtual_clock();
 
         loop {
             // Check for shutdown
@@ -657,6 +670,18 @@ impl Executor {
                         )));
                     }
 
+                    if let Some(clock) = &clock {
+                        // Waking a timer queues its task, which needs the lock
+                        drop(queue);
+                        let advanced = clock.advance_to_next_timer();
+                        queue = shared.ready_queue.lock().map_err(|_| {
+                            Error::lock_poisoned("Failed to acquire lock on ready queue")
+                        })?;
+                        if advanced {
+                            continue;
+                        }
+                    }
+
                     // Wait for wake signal with timeout
                     let wait_timeout = Duration::from_millis(100); // Short timeout to check global timeout regularly
                     let (new_queue, _timeout_result) = shared
@@ -895,16 +920,33 @@ impl TimerThread {
     }
 }
 
+/// When a timer fires
+enum TimerDeadline {
+    Real(std::time::Instant),
+    /// A reading of the virtual clock the timer was created under
+    Virtual(Arc<VirtualClock>, Duration),
+}
+
 /// A simple async timer future
+///
+/// A timer created on a thread with a virtual clock (see
+/// `crate::runtime::deterministic`) waits on that clock instead of real time.
 pub struct Timer {
-    deadline: std::time::Instant,
+    deadline: TimerDeadline,
     registered: bool,
 }
 
 impl Timer {
     pub fn new(duration: Duration) -> Self {
+        let deadline = match deterministic::virtual_clock() {
+            Some(clock) => {
+                let deadline = clock.now().saturating_add(duration);
+                TimerDeadline::Virtual(clock, deadline)
+            }
+            None => TimerDeadline::Real(std::time::Instant::now() + duration),
+        };
         Timer {
-            deadline: std::time::Instant::now() + duration,
+            deadline,
             registered: false,
         }
     }
@@ -914,12 +956,23 @@ impl ScriptFuture for Timer {
     type Output = ();
 
     fn poll(&mut self, waker: &Waker) -> Poll<Self::Output> {
-        if std::time::Instant::now() >= self.deadline {
+        let expired = match &self.deadline {
+            TimerDeadline::Real(deadline) => std::time::Instant::now() >= *deadline,
+            TimerDeadline::Virtual(clock, deadline) => clock.now() >= *deadline,
+        };
+        if expired {
             Poll::Ready(())
         } else {
             if !self.registered {
-                // Register with the global timer thread
-                TimerThread::get().register(self.deadline, waker.clone());
+                match &self.deadline {
+                    // Register with the global timer thread
+                    TimerDeadline::Real(deadline) => {
+                        TimerThread::get().register(*deadline, waker.clone())
+                    }
+                    TimerDeadline::Virtual(clock, deadline) => {
+                        clock.register(*deadline, waker.clone())
+                    }
+                }
                 self.registered = true;
             }
             Poll::Pending
@@ -1080,6 +1133,7 @@ impl BlockingExecutor {
                 waker,
                 is_running: AtomicBool::new(false),
                 is_completed: AtomicBool::new(false),
+                _resource: TrackedResource::default(),
             });
 
             // Ensure tasks vector is large enough
@@ -1094,7 +1148,9 @@ impl BlockingExecutor {
 
         // Run the executor in a separate thread to avoid blocking the current thread completely
         let exec_clone = executor.clone();
+        let clock = deterministic::virtual_clock();
         let handle = thread::spawn(move || {
+            deterministic::install_clock(clock);
             Self::run_until_complete(exec_clone);
         });
 
@@ -1123,6 +1179,8 @@ impl BlockingExecutor {
     where
         T: Send + 'static,
     {
+        profile::require_full("The async executor")?;
+
         // Validate timeout
         if timeout.is_zero() {
             return Err(Error::security_error("Timeout cannot be zero"));
@@ -1189,6 +1247,7 @@ impl BlockingExecutor {
                 waker,
                 is_running: AtomicBool::new(false),
                 is_completed: AtomicBool::new(false),
+                _resource: TrackedResource::default(),
             });
 
             // Ensure tasks vector is large enough
@@ -1202,7 +1261,9 @@ impl BlockingExecutor {
 
         // Run the executor in a separate thread
         let exec_clone = executor.clone();
+        let clock = deterministic::virtual_clock();
         let handle = thread::spawn(move || {
+            deterministic::install_clock(clock);
             Self::run_until_complete(exec_clone);
         });
 
@@ -1232,6 +1293,7 @@ impl BlockingExecutor {
             Ok(exec) => exec.shared.clone(),
             Err(_) => return, // Exit on lock failure
         };
+        let clock = deterministic::virtual_clock();
 
         loop {
             // Check for shutdown
@@ -1260,6 +1322,19 @@ impl BlockingExecutor {
                         return; // All tasks completed
                     }
 
+                    if let Some(clock) = &clock {
+                        // Waking a timer queues its task, which needs the lock
+                        drop(queue);
+                        let advanced = clock.advance_to_next_timer();
+                        queue = match shared.ready_queue.lock() {
+                            Ok(q) => q,
+                            Err(_) => return, // Exit on lock failure
+                        };
+                        if advanced {
+                            continue;
+                        }
+                    }
+
                     // Wait for wake signal
                     queue = match shared.wake_signal.wait(queue) {
                         Ok(q) => q,
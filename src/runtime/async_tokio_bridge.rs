@@ -13,6 +13,7 @@ use tokio::task::JoinHandle;
 
 use crate::error::{Error, ErrorKind, Result};
 use crate::runtime::async_runtime::AsyncRuntimeConfig;
+use crate::runtime::leak_check::{ResourceKind, TrackedResource};
 use crate::runtime::Value;
 
 /// Tokio runtime configuration for Script
@@ -97,6 +98,7 @@ impl TokioBridge {
     }
 
     /// Spawn a future on the Tokio runtime with security checks
+    #[track_caller]
     pub fn spawn<F>(&self, future: F) -> Result<JoinHandle<F::Output>>
     where
         F: Future + Send + 'static,
@@ -119,7 +121,9 @@ impl TokioBridge {
         let active_tasks = self.active_tasks.clone();
 
         // Wrap the future to decrement counter on completion
+        let resource = TrackedResource::new(ResourceKind::Task, || "async task".to_string());
         let wrapped_future = async move {
+            let _resource = resource;
             let result = future.await;
             active_tasks.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
             result
@@ -138,6 +142,7 @@ impl TokioBridge {
     }
 
     /// Spawn a blocking task on the Tokio runtime
+    #[track_caller]
     pub fn spawn_blocking<F, R>(&self, f: F) -> Result<JoinHandle<R>>
     where
         F: FnOnce() -> R + Send + 'static,
@@ -160,7 +165,9 @@ impl TokioBridge {
         let active_tasks = self.active_tasks.clone();
 
         // Wrap the function to decrement counter on completion
+        let resource = TrackedResource::new(ResourceKind::Task, || "blocking task".to_string());
         let wrapped_fn = move || {
+            let _resource = resource;
             let result = f();
             active_tasks.fetch_sub(1, std::sync::atomic::Ordering::Relaxed);
             result
@@ -251,12 +258,14 @@ pub fn init_global_runtime() -> Result<()> {
 
 /// Get the global Tokio runtime
 pub fn global_runtime() -> Result<&'static TokioBridge> {
+    crate::runtime::profile::require_full("The Tokio runtime")?;
     GLOBAL_RUNTIME
         .get()
         .ok_or_else(|| Error::new(ErrorKind::RuntimeError, "Tokio runtime not initialized"))
 }
 
 /// Spawn a future on the global runtime
+#[track_caller]
 pub fn spawn<F>(future: F) -> Result<JoinHandle<F::Output>>
 where
     F: Future + Send + 'static,
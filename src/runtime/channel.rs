@@ -0,0 +1,520 @@
+//! Bounded channels for message passing
+//!
+//! A `Channel<T>` is a fixed-capacity queue shared by any number of senders
+//! and receivers, on any threads. Values are received in the order they
+//! were sent. When the buffer is full, senders wait for room (backpressure)
+//! instead of growing the queue:
+//! - Threads block in `send` and `recv`
+//! - Async tasks await `send_async` and `recv_async`, which park the task
+//!   until the channel is ready
+//!
+//! Closing a channel rejects further sends; values already buffered can
+//! still be received, after which receives return `None`. `select` waits on
+//! several channels at once and takes from whichever is ready first.
+
+use crate::runtime::ScriptFuture;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::task::{Poll, Waker};
+
+/// Why a value could not be sent; the value is handed back
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrySendError<T> {
+    /// The buffer is full
+    Full(T),
+    /// The channel has been closed
+    Closed(T),
+}
+
+/// Why no value could be received without waiting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The buffer is empty but more values may arrive
+    Empty,
+    /// The buffer is empty and the channel has been closed
+    Closed,
+}
+
+impl fmt::Display for TryRecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TryRecvError::Empty => write!(f, "channel is empty"),
+            TryRecvError::Closed => write!(f, "channel is closed"),
+        }
+    }
+}
+
+/// Wakes a thread blocked in `select`
+#[derive(Default)]
+struct Signal {
+    ready: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl Signal {
+    fn notify(&self) {
+        *self.ready.lock().unwrap_or_else(|e| e.into_inner()) = true;
+        self.condvar.notify_one();
+    }
+
+    fn wait(&self) {
+        let mut ready = self.ready.lock().unwrap_or_else(|e| e.into_inner());
+        while !*ready {
+            ready = self.condvar.wait(ready).unwrap_or_else(|e| e.into_inner());
+        }
+        *ready = false;
+    }
+}
+
+struct State<T> {
+    queue: VecDeque<T>,
+    closed: bool,
+    /// Async receivers waiting for a value
+    recv_wakers: Vec<Waker>,
+    /// Async senders waiting for room
+    send_wakers: Vec<Waker>,
+    /// Threads blocked in `select` on this channel
+    selectors: Vec<Arc<Signal>>,
+}
+
+struct Shared<T> {
+    capacity: usize,
+    state: Mutex<State<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+/// A bounded multi-producer, multi-consumer channel
+///
+/// Clones refer to the same channel.
+pub struct Channel<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Clone for Channel<T> {
+    fn clone(&self) -> Self {
+        Channel {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Channel<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("Channel")
+            .field("capacity", &self.shared.capacity)
+            .field("len", &state.queue.len())
+            .field("closed", &state.closed)
+            .finish()
+    }
+}
+
+impl<T> Channel<T> {
+    /// Create a channel buffering at most `capacity` values
+    ///
+    /// A capacity of zero is treated as one.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Channel {
+            shared: Arc::new(Shared {
+                capacity,
+                state: Mutex::new(State {
+                    queue: VecDeque::with_capacity(capacity),
+                    closed: false,
+                    recv_wakers: Vec::new(),
+                    send_wakers: Vec::new(),
+                    selectors: Vec::new(),
+                }),
+                not_empty: Condvar::new(),
+                not_full: Condvar::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<T>> {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Maximum number of buffered values
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity
+    }
+
+    /// Number of values waiting to be received
+    pub fn len(&self) -> usize {
+        self.lock().queue.len()
+    }
+
+    /// Whether no values are waiting to be received
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the channel has been closed
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    /// Whether `other` refers to the same channel
+    pub fn same_channel(&self, other: &Channel<T>) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+
+    /// Close the channel, waking everything waiting on it
+    pub fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        Self::wake_receivers(&mut state);
+        for waker in state.send_wakers.drain(..) {
+            waker.wake();
+        }
+        drop(state);
+        self.shared.not_empty.notify_all();
+        self.shared.not_full.notify_all();
+    }
+
+    /// Send a value if there is room, without waiting
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let mut state = self.lock();
+        if state.closed {
+            return Err(TrySendError::Closed(value));
+        }
+        if state.queue.len() >= self.shared.capacity {
+            return Err(TrySendError::Full(value));
+        }
+        self.push(&mut state, value);
+        Ok(())
+    }
+
+    /// Send a value, blocking while the buffer is full
+    ///
+    /// Returns the value back if the channel is closed.
+    pub fn send(&self, value: T) -> Result<(), T> {
+        let mut state = self.lock();
+        loop {
+            if state.closed {
+                return Err(value);
+            }
+            if state.queue.len() < self.shared.capacity {
+                self.push(&mut state, value);
+                return Ok(());
+            }
+            state = self
+                .shared
+                .not_full
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Send a value from an async task, waiting while the buffer is full
+    pub fn send_async(&self, value: T) -> SendFuture<T> {
+        SendFuture {
+            channel: self.clone(),
+            value: Some(value),
+        }
+    }
+
+    /// Take the next value if one is buffered, without waiting
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        let mut state = self.lock();
+        match self.pop(&mut state) {
+            Some(value) => Ok(value),
+            None if state.closed => Err(TryRecvError::Closed),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    /// Take the next value, blocking until one arrives
+    ///
+    /// Returns `None` once the channel is closed and drained.
+    pub fn recv(&self) -> Option<T> {
+        let mut state = self.lock();
+        loop {
+            if let Some(value) = self.pop(&mut state) {
+                return Some(value);
+            }
+            if state.closed {
+                return None;
+            }
+            state = self
+                .shared
+                .not_empty
+                .wait(state)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    /// Take the next value from an async task
+    pub fn recv_async(&self) -> RecvFuture<T> {
+        RecvFuture {
+            channel: self.clone(),
+        }
+    }
+
+    fn push(&self, state: &mut State<T>, value: T) {
+        state.queue.push_back(value);
+        Self::wake_receivers(state);
+        self.shared.not_empty.notify_one();
+    }
+
+    fn pop(&self, state: &mut State<T>) -> Option<T> {
+        let value = state.queue.pop_front()?;
+        if let Some(waker) = state.send_wakers.pop() {
+            waker.wake();
+        }
+        self.shared.not_full.notify_one();
+        Some(value)
+    }
+
+    fn wake_receivers(state: &mut State<T>) {
+        for waker in state.recv_wakers.drain(..) {
+            waker.wake();
+        }
+        for selector in &state.selectors {
+            selector.notify();
+        }
+    }
+}
+
+/// Future returned by `Channel::send_async`
+pub struct SendFuture<T> {
+    channel: Channel<T>,
+    value: Option<T>,
+}
+
+impl<T> ScriptFuture for SendFuture<T> {
+    /// `Err` hands the value back if the channel was closed
+    type Output = Result<(), T>;
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Output> {
+        let Some(value) = self.value.take() else {
+            return Poll::Ready(Ok(()));
+        };
+        let mut state = self.channel.lock();
+        if state.closed {
+            return Poll::Ready(Err(value));
+        }
+        if state.queue.len() < self.channel.shared.capacity {
+            self.channel.push(&mut state, value);
+            return Poll::Ready(Ok(()));
+        }
+        state.send_wakers.push(waker.clone());
+        self.value = Some(value);
+        Poll::Pending
+    }
+}
+
+/// Future returned by `Channel::recv_async`
+pub struct RecvFuture<T> {
+    channel: Channel<T>,
+}
+
+impl<T> ScriptFuture for RecvFuture<T> {
+    /// `None` once the channel is closed and drained
+    type Output = Option<T>;
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Output> {
+        let mut state = self.channel.lock();
+        if let Some(value) = self.channel.pop(&mut state) {
+            return Poll::Ready(Some(value));
+        }
+        if state.closed {
+            return Poll::Ready(None);
+        }
+        state.recv_wakers.push(waker.clone());
+        Poll::Pending
+    }
+}
+
+/// Take a value from whichever channel has one first, blocking until one
+/// does
+///
+/// Returns the index of the channel and the value, or `None` once every
+/// channel is closed and drained. Channels earlier in the list win ties.
+pub fn select<T>(channels: &[Channel<T>]) -> Option<(usize, T)> {
+    if channels.is_empty() {
+        return None;
+    }
+
+    // Register before checking, so a send between the check and the wait
+    // still wakes this thread
+    let signal = Arc::new(Signal::default());
+    for channel in channels {
+        channel.lock().selectors.push(Arc::clone(&signal));
+    }
+
+    let selected = loop {
+        match try_select(channels) {
+            Ok(selected) => break Some(selected),
+            Err(TryRecvError::Closed) => break None,
+            Err(TryRecvError::Empty) => signal.wait(),
+        }
+    };
+
+    for channel in channels {
+        channel
+            .lock()
+            .selectors
+            .retain(|selector| !Arc::ptr_eq(selector, &signal));
+    }
+    selected
+}
+
+/// Take a value from the first channel that has one, without waiting
+///
+/// Fails with `Closed` only when every channel is closed and drained.
+pub fn try_select<T>(channels: &[Channel<T>]) -> Result<(usize, T), TryRecvError> {
+    let mut all_closed = true;
+    for (index, channel) in channels.iter().enumerate() {
+        match channel.try_recv() {
+            Ok(value) => return Ok((index, value)),
+            Err(TryRecvError::Empty) => all_closed = false,
+            Err(TryRecvError::Closed) => {}
+        }
+    }
+    if all_closed {
+        Err(TryRecvError::Closed)
+    } else {
+        Err(TryRecvError::Empty)
+    }
+}
+
+/// Take a value from whichever channel has one first, from an async task
+pub fn select_async<T>(channels: Vec<Channel<T>>) -> SelectFuture<T> {
+    SelectFuture { channels }
+}
+
+/// Future returned by `select_async`
+pub struct SelectFuture<T> {
+    channels: Vec<Channel<T>>,
+}
+
+impl<T> ScriptFuture for SelectFuture<T> {
+    /// The channel index and value, or `None` once every channel is closed
+    /// and drained
+    type Output = Option<(usize, T)>;
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Output> {
+        for channel in &self.channels {
+            channel.lock().recv_wakers.push(waker.clone());
+        }
+        match try_select(&self.channels) {
+            Ok(selected) => Poll::Ready(Some(selected)),
+            Err(TryRecvError::Closed) => Poll::Ready(None),
+            Err(TryRecvError::Empty) => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+    use std::thread;
+    use std::time::Duration;
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_send_recv_and_close() {
+        let channel = Channel::new(2);
+        assert_eq!(channel.try_send(1), Ok(()));
+        assert_eq!(channel.try_send(2), Ok(()));
+        assert_eq!(channel.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(channel.len(), 2);
+
+        assert_eq!(channel.recv(), Some(1));
+        channel.close();
+        assert_eq!(channel.send(4), Err(4));
+        assert_eq!(channel.try_recv(), Ok(2));
+        assert_eq!(channel.try_recv(), Err(TryRecvError::Closed));
+        assert_eq!(channel.recv(), None);
+    }
+
+    #[test]
+    fn test_backpressure_blocks_sender_across_threads() {
+        let channel = Channel::new(1);
+        let sent = Arc::new(AtomicUsize::new(0));
+
+        let producer = {
+            let channel = channel.clone();
+            let sent = Arc::clone(&sent);
+            thread::spawn(move || {
+                for i in 0..5 {
+                    channel.send(i).unwrap();
+                    sent.fetch_add(1, Ordering::SeqCst);
+                }
+                channel.close();
+            })
+        };
+
+        // The producer can only get one value ahead of the consumer
+        thread::sleep(Duration::from_millis(20));
+        assert!(sent.load(Ordering::SeqCst) <= 1);
+
+        let received: Vec<i32> = std::iter::from_fn(|| channel.recv()).collect();
+        producer.join().unwrap();
+        assert_eq!(received, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_async_send_waits_for_room() {
+        let channel = Channel::new(1);
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(Arc::clone(&counter));
+
+        let mut first = channel.send_async("a");
+        assert_eq!(first.poll(&waker), Poll::Ready(Ok(())));
+        let mut second = channel.send_async("b");
+        assert_eq!(second.poll(&waker), Poll::Pending);
+
+        let mut recv = channel.recv_async();
+        assert_eq!(recv.poll(&waker), Poll::Ready(Some("a")));
+        assert_eq!(counter.0.load(Ordering::SeqCst), 1);
+        assert_eq!(second.poll(&waker), Poll::Ready(Ok(())));
+
+        let mut recv = channel.recv_async();
+        assert_eq!(recv.poll(&waker), Poll::Ready(Some("b")));
+    }
+
+    #[test]
+    fn test_select() {
+        let fast = Channel::new(4);
+        let slow = Channel::new(4);
+
+        let sender = {
+            let slow = slow.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                slow.send("slow").unwrap();
+            })
+        };
+        assert_eq!(select(&[fast.clone(), slow.clone()]), Some((1, "slow")));
+        sender.join().unwrap();
+
+        fast.send("fast").unwrap();
+        slow.send("slow").unwrap();
+        assert_eq!(try_select(&[fast.clone(), slow.clone()]), Ok((0, "fast")));
+
+        let waker = Waker::from(Arc::new(CountingWaker(AtomicUsize::new(0))));
+        let mut future = select_async(vec![fast.clone(), slow.clone()]);
+        assert_eq!(future.poll(&waker), Poll::Ready(Some((1, "slow"))));
+
+        fast.close();
+        slow.close();
+        assert_eq!(select(&[fast, slow]), None);
+    }
+}
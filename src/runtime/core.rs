@@ -14,8 +14,10 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
 
 use crate::error::Error;
-use crate::runtime::gc::CollectionStats;
+use crate::runtime::arena::Arena;
+use crate::runtime::gc::{CollectionStats, GcMode};
 use crate::runtime::panic::PanicInfo;
+use crate::runtime::profile::{RuntimeProfile, EMBEDDED_ARENA_SIZE};
 use crate::runtime::{Result, RuntimeError};
 
 /// Global runtime instance
@@ -41,24 +43,82 @@ pub struct RuntimeConfig {
     /// Enable memory profiling
     pub enable_profiling: bool,
     /// Enable cycle detection
+    ///
+    /// Deprecated in favor of `gc_mode`; `false` runs the runtime as
+    /// `GcMode::RefCountOnly` whatever `gc_mode` says.
     pub enable_gc: bool,
+    /// How reference cycles are reclaimed
+    pub gc_mode: GcMode,
     /// GC collection threshold (allocations between collections)
     pub gc_threshold: usize,
     /// Enable panic handler
     pub enable_panic_handler: bool,
     /// Stack size for Script threads
     pub stack_size: usize,
+    /// Seed all RNGs, run time functions on a virtual clock and iterate
+    /// hash maps in key order, so runs can be replayed exactly
+    pub deterministic: bool,
+    /// Seed used in deterministic mode
+    pub seed: u64,
+    /// Count allocations by site for `memory_report`
+    pub track_allocations: bool,
+    /// Record open files, sockets and tasks so leaks can be reported at exit
+    pub check_leaks: bool,
+    /// Which parts of the runtime and stdlib are available
+    pub profile: RuntimeProfile,
+    /// Serve allocations from one fixed block of this many bytes instead of
+    /// the system allocator (0 = system allocator)
+    pub arena_size: usize,
 }
 
 impl Default for RuntimeConfig {
+    /// The configuration for the profile the crate was built for
     fn default() -> Self {
+        match RuntimeProfile::default() {
+            RuntimeProfile::Full => RuntimeConfig::full(),
+            RuntimeProfile::Embedded => RuntimeConfig::embedded(),
+        }
+    }
+}
+
+impl RuntimeConfig {
+    /// Configuration for hosts with an operating system
+    pub fn full() -> Self {
         RuntimeConfig {
             max_heap_size: 0, // Unlimited
             enable_profiling: cfg!(debug_assertions),
             enable_gc: true,
+            gc_mode: GcMode::default(),
             gc_threshold: 1000,
             enable_panic_handler: true,
             stack_size: 2 * 1024 * 1024, // 2MB
+            deterministic: false,
+            seed: 0,
+            track_allocations: false,
+            check_leaks: false,
+            profile: RuntimeProfile::Full,
+            arena_size: 0,
+        }
+    }
+
+    /// Configuration for constrained targets, with a heap bounded by a
+    /// fixed-size arena of `EMBEDDED_ARENA_SIZE` bytes
+    pub fn embedded() -> Self {
+        RuntimeConfig {
+            enable_profiling: false,
+            profile: RuntimeProfile::Embedded,
+            arena_size: EMBEDDED_ARENA_SIZE,
+            ..RuntimeConfig::full()
+        }
+    }
+
+    /// The mode the collector runs in, taking the deprecated `enable_gc`
+    /// switch into account
+    pub fn effective_gc_mode(&self) -> GcMode {
+        if self.enable_gc {
+            self.gc_mode
+        } else {
+            GcMode::RefCountOnly
         }
     }
 }
@@ -85,6 +145,8 @@ pub struct MemoryManager {
     total_allocations: AtomicUsize,
     /// Total deallocations
     total_deallocations: AtomicUsize,
+    /// Fixed block allocations come from, if `arena_size` is set
+    arena: Option<Arena>,
     /// Configuration
     config: RuntimeConfig,
 }
@@ -153,20 +215,39 @@ impl Runtime {
 
         let runtime = Arc::new(Runtime::new(config.clone()));
         *runtime_lock = Some(runtime.clone());
+        crate::runtime::profile::set(config.profile);
 
         // Initialize subsystems based on configuration
         if config.enable_profiling {
             crate::runtime::profiler::initialize();
         }
 
-        if config.enable_gc {
-            crate::runtime::gc::initialize();
-        }
+        crate::runtime::gc::initialize_with_mode(config.effective_gc_mode()).map_err(|e| {
+            RuntimeError::InvalidOperation(format!("GC initialization failed: {}", e))
+        })?;
 
         if config.enable_panic_handler {
             runtime.install_panic_handler();
         }
 
+        if config.deterministic {
+            crate::runtime::deterministic::enable(config.seed);
+        }
+
+        if config.track_allocations {
+            crate::runtime::heap_profiler::start();
+        }
+
+        if config.check_leaks {
+            crate::runtime::leak_check::start();
+        }
+
+        let pool = crate::runtime::ThreadPool::global();
+        pool.configure(crate::runtime::ThreadPoolConfig {
+            stack_size: config.stack_size,
+            ..pool.config()
+        });
+
         Ok(())
     }
 
@@ -293,6 +374,7 @@ impl MemoryManager {
             heap_peak: AtomicUsize::new(0),
             total_allocations: AtomicUsize::new(0),
             total_deallocations: AtomicUsize::new(0),
+            arena: (config.arena_size > 0).then(|| Arena::new(config.arena_size)),
             config,
         }
     }
@@ -334,6 +416,18 @@ impl MemoryManager {
         self.total_allocations.fetch_add(1, Ordering::Relaxed);
 
         // Allocate memory
+        if let Some(arena) = &self.arena {
+            return match arena.allocate(layout) {
+                Some(ptr) => Ok(ptr.as_ptr()),
+                None => {
+                    self.heap_used.fetch_sub(size, Ordering::Relaxed);
+                    Err(RuntimeError::AllocationFailed(format!(
+                        "Arena of {} bytes is full",
+                        arena.capacity()
+                    )))
+                }
+            };
+        }
         unsafe {
             let ptr = std::alloc::alloc(layout);
             if ptr.is_null() {
@@ -347,7 +441,10 @@ impl MemoryManager {
 
     /// Deallocate memory
     pub unsafe fn deallocate(&self, ptr: *mut u8, layout: Layout) {
-        std::alloc::dealloc(ptr, layout);
+        match (&self.arena, std::ptr::NonNull::new(ptr)) {
+            (Some(arena), Some(block)) if arena.contains(ptr) => arena.deallocate(block, layout),
+            _ => std::alloc::dealloc(ptr, layout),
+        }
 
         self.heap_used.fetch_sub(layout.size(), Ordering::Relaxed);
         self.total_deallocations.fetch_add(1, Ordering::Relaxed);
@@ -515,6 +612,38 @@ mod tests {
         crate::runtime::shutdown().unwrap();
     }
 
+    #[test]
+    fn test_enable_gc_overrides_gc_mode() {
+        let mut config = RuntimeConfig::full();
+        config.gc_mode = GcMode::Generational;
+        assert_eq!(config.effective_gc_mode(), GcMode::Generational);
+
+        config.enable_gc = false;
+        assert_eq!(config.effective_gc_mode(), GcMode::RefCountOnly);
+    }
+
+    #[test]
+    fn test_arena_bounds_heap() {
+        let memory = MemoryManager::new(RuntimeConfig {
+            arena_size: 256,
+            ..RuntimeConfig::full()
+        });
+        let layout = Layout::from_size_align(200, 8).unwrap();
+
+        let ptr = memory.allocate(layout).unwrap();
+        assert!(memory.allocate(layout).is_err());
+        assert_eq!(memory.stats().heap_used, 200);
+
+        unsafe {
+            memory.deallocate(ptr, layout);
+        }
+        assert_eq!(memory.stats().heap_used, 0);
+        let ptr = memory.allocate(layout).unwrap();
+        unsafe {
+            memory.deallocate(ptr, layout);
+        }
+    }
+
     #[test]
     fn test_protected_execution() {
         let _ = crate::runtime::shutdown();
@@ -0,0 +1,361 @@
+//! Code coverage for Script programs
+//!
+//! Lowering with coverage enabled puts a counter at the start of every basic
+//! block and records in a `CoverageMap` which source lines each block covers.
+//! Between `start` and `stop`, the generated code calls `hit` each time a
+//! block runs. A line's hit count is that of the most-executed block on it.
+//!
+//! Line counts are saved in `.coverage` files, one `file <path>` header
+//! followed by `<line> <hits>` pairs per source file. Saving adds to any
+//! counts already in the file, so several runs accumulate. A
+//! `CoverageReport` renders the counts as a per-file summary or as LCOV for
+//! CI services.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+static COUNTERS: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+
+/// Source lines covered by each coverage counter
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoverageMap {
+    counters: Vec<Vec<u32>>,
+}
+
+impl CoverageMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a counter for a block covering `lines`, returning its index
+    pub fn add_counter(&mut self, lines: Vec<u32>) -> u32 {
+        self.counters.push(lines);
+        (self.counters.len() - 1) as u32
+    }
+
+    /// Lines covered by a counter
+    pub fn lines(&self, counter: u32) -> &[u32] {
+        self.counters
+            .get(counter as usize)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn len(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+}
+
+/// Reset the counters and start counting block executions
+pub fn start(map: &CoverageMap) {
+    let mut counters = COUNTERS.lock().unwrap_or_else(|e| e.into_inner());
+    counters.clear();
+    counters.resize(map.len(), 0);
+}
+
+/// Count one execution of a block
+pub fn hit(counter: u32) {
+    let mut counters = COUNTERS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(count) = counters.get_mut(counter as usize) {
+        *count += 1;
+    }
+}
+
+/// Stop counting and return the line counts of the file `map` was built for
+pub fn stop(map: &CoverageMap) -> FileCoverage {
+    let counts = std::mem::take(&mut *COUNTERS.lock().unwrap_or_else(|e| e.into_inner()));
+    FileCoverage::from_counts(map, &counts)
+}
+
+/// Hit counts of the instrumented lines of one source file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileCoverage {
+    pub lines: BTreeMap<u32, u64>,
+}
+
+impl FileCoverage {
+    /// Line counts from the counts of the counters in `map`
+    pub fn from_counts(map: &CoverageMap, counts: &[u64]) -> Self {
+        let mut lines = BTreeMap::new();
+        for (counter, block_lines) in map.counters.iter().enumerate() {
+            let count = counts.get(counter).copied().unwrap_or(0);
+            for line in block_lines {
+                let hits = lines.entry(*line).or_insert(0);
+                *hits = (*hits).max(count);
+            }
+        }
+        FileCoverage { lines }
+    }
+
+    /// Number of instrumented lines
+    pub fn lines_found(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Number of lines that ran at least once
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|hits| **hits > 0).count()
+    }
+
+    /// Ranges of lines that never ran, such as `3-5` or `9`
+    pub fn missed_ranges(&self) -> Vec<String> {
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+        for (&line, _) in self.lines.iter().filter(|(_, hits)| **hits == 0) {
+            match ranges.last_mut() {
+                // Lines in between that were not instrumented join the range
+                Some((_, end)) if !self.lines.range(*end + 1..line).any(|(_, h)| *h > 0) => {
+                    *end = line
+                }
+                _ => ranges.push((line, line)),
+            }
+        }
+        ranges
+            .into_iter()
+            .map(|(start, end)| {
+                if start == end {
+                    start.to_string()
+                } else {
+                    format!("{}-{}", start, end)
+                }
+            })
+            .collect()
+    }
+
+    /// Add the counts of another run of the same file
+    pub fn merge(&mut self, other: &FileCoverage) {
+        for (line, hits) in &other.lines {
+            *self.lines.entry(*line).or_insert(0) += hits;
+        }
+    }
+}
+
+/// Line coverage of a set of source files
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CoverageReport {
+    pub files: BTreeMap<String, FileCoverage>,
+}
+
+impl CoverageReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file's counts, merging them with counts already reported
+    pub fn add_file(&mut self, path: &str, coverage: &FileCoverage) {
+        self.files
+            .entry(path.to_string())
+            .or_default()
+            .merge(coverage);
+    }
+
+    /// Merge another report into this one
+    pub fn merge(&mut self, other: &CoverageReport) {
+        for (path, coverage) in &other.files {
+            self.add_file(path, coverage);
+        }
+    }
+
+    /// Parse the contents of a `.coverage` file
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut report = CoverageReport::new();
+        let mut current: Option<String> = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(path) = line.strip_prefix("file ") {
+                report.files.entry(path.to_string()).or_default();
+                current = Some(path.to_string());
+                continue;
+            }
+
+            let invalid = || {
+                format!(
+                    "line {}: Expected '<line> <hits>', found '{}'",
+                    index + 1,
+                    line
+                )
+            };
+            let path = current.as_ref().ok_or_else(|| {
+                format!("line {}: Line counts before any 'file' header", index + 1)
+            })?;
+            let (number, hits) = line.split_once(' ').ok_or_else(invalid)?;
+            let number: u32 = number.parse().map_err(|_| invalid())?;
+            let hits: u64 = hits.trim().parse().map_err(|_| invalid())?;
+            *report
+                .files
+                .entry(path.clone())
+                .or_default()
+                .lines
+                .entry(number)
+                .or_insert(0) += hits;
+        }
+
+        Ok(report)
+    }
+
+    /// Read a `.coverage` file
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// Add this report's counts to the `.coverage` file at `path`, creating
+    /// it if needed
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut merged = if path.exists() {
+            Self::load(path)?
+        } else {
+            CoverageReport::new()
+        };
+        merged.merge(self);
+        let mut file = fs::File::create(path)?;
+        merged.write(&mut file)
+    }
+
+    /// Write the counts in `.coverage` format
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "# Script coverage")?;
+        for (path, coverage) in &self.files {
+            writeln!(out, "file {}", path)?;
+            for (line, hits) in &coverage.lines {
+                writeln!(out, "{} {}", line, hits)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the counts as an LCOV tracefile
+    pub fn write_lcov<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "TN:")?;
+        for (path, coverage) in &self.files {
+            writeln!(out, "SF:{}", path)?;
+            for (line, hits) in &coverage.lines {
+                writeln!(out, "DA:{},{}", line, hits)?;
+            }
+            writeln!(out, "LF:{}", coverage.lines_found())?;
+            writeln!(out, "LH:{}", coverage.lines_hit())?;
+            writeln!(out, "end_of_record")?;
+        }
+        Ok(())
+    }
+
+    /// Number of instrumented lines across all files
+    pub fn lines_found(&self) -> usize {
+        self.files.values().map(FileCoverage::lines_found).sum()
+    }
+
+    /// Number of lines that ran at least once across all files
+    pub fn lines_hit(&self) -> usize {
+        self.files.values().map(FileCoverage::lines_hit).sum()
+    }
+}
+
+fn percent(hit: usize, found: usize) -> f64 {
+    if found == 0 {
+        100.0
+    } else {
+        100.0 * hit as f64 / found as f64
+    }
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>7} {:>7} {:>7}  {:<30} Missed lines",
+            "Lines", "Hit", "Cover", "File"
+        )?;
+        for (path, coverage) in &self.files {
+            writeln!(
+                f,
+                "{:>7} {:>7} {:>6.1}%  {:<30} {}",
+                coverage.lines_found(),
+                coverage.lines_hit(),
+                percent(coverage.lines_hit(), coverage.lines_found()),
+                path,
+                coverage.missed_ranges().join(", ")
+            )?;
+        }
+        writeln!(
+            f,
+            "{:>7} {:>7} {:>6.1}%  Total",
+            self.lines_found(),
+            self.lines_hit(),
+            percent(self.lines_hit(), self.lines_found())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_counts_from_blocks() {
+        let mut map = CoverageMap::new();
+        let entry = map.add_counter(vec![1, 2]);
+        let loop_body = map.add_counter(vec![2, 3]);
+        let never = map.add_counter(vec![5, 7]);
+        assert_eq!((entry, loop_body, never), (0, 1, 2));
+
+        start(&map);
+        hit(entry);
+        for _ in 0..3 {
+            hit(loop_body);
+        }
+        hit(99);
+        let coverage = stop(&map);
+
+        let lines: Vec<_> = coverage.lines.into_iter().collect();
+        assert_eq!(lines, vec![(1, 1), (2, 3), (3, 3), (5, 0), (7, 0)]);
+    }
+
+    #[test]
+    fn test_report_round_trip_and_lcov() {
+        let coverage = FileCoverage {
+            lines: [(1, 2), (2, 0), (3, 0), (5, 1), (6, 0)]
+                .into_iter()
+                .collect(),
+        };
+        assert_eq!(coverage.missed_ranges(), vec!["2-3", "6"]);
+
+        let mut report = CoverageReport::new();
+        report.add_file("main.script", &coverage);
+        let mut text = Vec::new();
+        report.write(&mut text).unwrap();
+        let mut parsed = CoverageReport::parse(&String::from_utf8(text).unwrap()).unwrap();
+        assert_eq!(parsed, report);
+
+        // A second run adds to the counts
+        parsed.merge(&report);
+        assert_eq!(parsed.files["main.script"].lines[&1], 4);
+        assert_eq!((parsed.lines_found(), parsed.lines_hit()), (5, 2));
+
+        let mut lcov = Vec::new();
+        report.write_lcov(&mut lcov).unwrap();
+        assert_eq!(
+            String::from_utf8(lcov).unwrap(),
+            "TN:\nSF:main.script\nDA:1,2\nDA:2,0\nDA:3,0\nDA:5,1\nDA:6,0\nLF:5\nLH:2\nend_of_record\n"
+        );
+
+        assert!(CoverageReport::parse("1 2")
+            .unwrap_err()
+            .starts_with("line 1"));
+        assert!(CoverageReport::parse("file a\nx y").is_err());
+    }
+}
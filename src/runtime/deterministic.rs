@@ -0,0 +1,345 @@
+//! Deterministic execution for replays and testing
+//!
+//! With `RuntimeConfig::deterministic` set (or `script --deterministic`),
+//! a run depends only on its inputs and a seed:
+//! - Every RNG is seeded from the configured seed. Each thread's RNG gets its
+//!   own stream, numbered in the order threads first draw a random number.
+//! - Time functions read a virtual clock that starts at zero and only moves
+//!   when `sleep` is called or the host calls `advance_clock`. Wall-clock
+//!   time starts at `VIRTUAL_EPOCH_SECS`.
+//! - Hash maps and sets iterate in key order instead of hash order.
+//!
+//! The mode is process-wide. Stdlib code gets randomness from `with_rng` and
+//! time from `monotonic_now`, `system_now` and `sleep`, which fall back to
+//! entropy and the real clocks when the mode is off.
+//!
+//! A `VirtualClock` can also be installed on a single thread with
+//! `install_clock`, without the rest of deterministic mode. The test runner
+//! does this for `@test(virtual_time)` so that tests of code using `sleep`,
+//! intervals and timeouts finish without real waiting while other tests keep
+//! the real clock. Async timers created under a virtual clock wait on that
+//! clock, and executors move it forward to the next timer when every task is
+//! waiting.
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::task::Waker;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Unix time at which the virtual clock starts (2024-01-01T00:00:00Z)
+pub const VIRTUAL_EPOCH_SECS: u64 = 1_704_067_200;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static SEED: AtomicU64 = AtomicU64::new(0);
+/// Bumped on every mode change so threads reseed their RNGs
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+/// Next RNG stream handed to a thread
+static NEXT_STREAM: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// This thread's RNG and the generation it was seeded in
+    static RNG: RefCell<Option<(u64, StdRng)>> = const { RefCell::new(None) };
+    /// Clock installed on this thread, overriding the process-wide one
+    static CLOCK: RefCell<Option<Arc<VirtualClock>>> = const { RefCell::new(None) };
+}
+
+struct ClockState {
+    now: Duration,
+    /// Pending timers as (deadline, waker), in no particular order
+    timers: Vec<(Duration, Waker)>,
+}
+
+/// A clock that only moves when told to, with the timers waiting on it
+pub struct VirtualClock {
+    state: Mutex<ClockState>,
+}
+
+impl VirtualClock {
+    /// A clock reading zero with no timers
+    pub fn new() -> Self {
+        VirtualClock {
+            state: Mutex::new(ClockState {
+                now: Duration::ZERO,
+                timers: Vec::new(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ClockState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The current reading
+    pub fn now(&self) -> Duration {
+        self.lock().now
+    }
+
+    /// Move the clock forward, waking every timer that comes due
+    ///
+    /// Returns how many timers were woken.
+    pub fn advance(&self, by: Duration) -> usize {
+        let mut state = self.lock();
+        state.now = state.now.saturating_add(by);
+        let now = state.now;
+        let (due, pending) = std::mem::take(&mut state.timers)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(deadline, _)| *deadline <= now);
+        state.timers = pending;
+        // Wake outside the lock; a woken task may register a new timer
+        drop(state);
+
+        let woken = due.len();
+        for (_, waker) in due {
+            waker.wake();
+        }
+        woken
+    }
+
+    /// Jump to the earliest pending timer and wake it
+    ///
+    /// Returns false if no timer is pending.
+    pub fn advance_to_next_timer(&self) -> bool {
+        let next = {
+            let state = self.lock();
+            state
+                .timers
+                .iter()
+                .map(|(deadline, _)| *deadline)
+                .min()
+                .map(|deadline| deadline.saturating_sub(state.now))
+        };
+        match next {
+            Some(by) => {
+                self.advance(by);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Wake `waker` once the clock reaches `deadline`
+    pub fn register(&self, deadline: Duration, waker: Waker) {
+        let mut state = self.lock();
+        if deadline <= state.now {
+            drop(state);
+            waker.wake();
+        } else {
+            state.timers.push((deadline, waker));
+        }
+    }
+
+    /// Timers that have not come due yet
+    pub fn pending_timers(&self) -> usize {
+        self.lock().timers.len()
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The clock read in deterministic mode
+fn global_clock() -> &'static Mutex<Arc<VirtualClock>> {
+    static GLOBAL: OnceLock<Mutex<Arc<VirtualClock>>> = OnceLock::new();
+    GLOBAL.get_or_init(|| Mutex::new(Arc::new(VirtualClock::new())))
+}
+
+/// Install a virtual clock on this thread, or remove it with `None`
+///
+/// Returns the clock installed before, so callers can restore it.
+pub fn install_clock(clock: Option<Arc<VirtualClock>>) -> Option<Arc<VirtualClock>> {
+    CLOCK.with(|cell| std::mem::replace(&mut *cell.borrow_mut(), clock))
+}
+
+/// The virtual clock this thread reads, if any
+///
+/// A clock installed on the thread wins over the deterministic-mode clock.
+pub fn virtual_clock() -> Option<Arc<VirtualClock>> {
+    CLOCK.with(|cell| cell.borrow().clone()).or_else(|| {
+        is_enabled().then(|| {
+            global_clock()
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .clone()
+        })
+    })
+}
+
+/// Turn on deterministic execution with the given seed
+///
+/// Resets the virtual clock and the RNG streams, so enabling again with the
+/// same seed replays the same run.
+pub fn enable(seed: u64) {
+    SEED.store(seed, Ordering::SeqCst);
+    NEXT_STREAM.store(0, Ordering::SeqCst);
+    *global_clock()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(VirtualClock::new());
+    ENABLED.store(true, Ordering::SeqCst);
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Turn deterministic execution off
+pub fn disable() {
+    ENABLED.store(false, Ordering::SeqCst);
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Whether deterministic execution is on
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// The seed in use, if deterministic execution is on
+pub fn seed() -> Option<u64> {
+    is_enabled().then(|| SEED.load(Ordering::SeqCst))
+}
+
+/// The RNG for a stream of a seed
+///
+/// Streams are spread with a 64-bit odd constant so that nearby stream
+/// numbers do not give related seeds.
+pub fn seeded_rng(seed: u64, stream: u64) -> StdRng {
+    StdRng::seed_from_u64(seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// A new RNG: the next stream of the seed in deterministic mode, seeded
+/// from entropy otherwise
+pub fn new_rng() -> StdRng {
+    if is_enabled() {
+        seeded_rng(
+            SEED.load(Ordering::SeqCst),
+            NEXT_STREAM.fetch_add(1, Ordering::SeqCst),
+        )
+    } else {
+        StdRng::from_entropy()
+    }
+}
+
+/// Run `f` with this thread's RNG
+///
+/// The RNG is reseeded whenever deterministic execution is turned on or off.
+pub fn with_rng<R>(f: impl FnOnce(&mut StdRng) -> R) -> R {
+    RNG.with(|cell| {
+        let mut slot = cell.borrow_mut();
+        let generation = GENERATION.load(Ordering::SeqCst);
+        if !matches!(&*slot, Some((seeded_in, _)) if *seeded_in == generation) {
+            *slot = Some((generation, new_rng()));
+        }
+        let (_, rng) = slot.as_mut().expect("RNG was just initialized");
+        f(rng)
+    })
+}
+
+/// Reseed this thread's RNG, as `random_seed` does
+pub fn reseed(seed: u64) {
+    RNG.with(|cell| {
+        *cell.borrow_mut() = Some((
+            GENERATION.load(Ordering::SeqCst),
+            StdRng::seed_from_u64(seed),
+        ));
+    });
+}
+
+/// Time since the runtime clock started
+///
+/// Under a virtual clock this is its reading; otherwise it is real time
+/// since the clock was first read.
+pub fn monotonic_now() -> Duration {
+    static START: OnceLock<Instant> = OnceLock::new();
+    match virtual_clock() {
+        Some(clock) => clock.now(),
+        None => START.get_or_init(Instant::now).elapsed(),
+    }
+}
+
+/// Time since the Unix epoch
+pub fn system_now() -> Duration {
+    if let Some(clock) = virtual_clock() {
+        Duration::from_secs(VIRTUAL_EPOCH_SECS) + clock.now()
+    } else {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+/// Move the virtual clock forward, firing the timers that come due
+///
+/// Returns false, without effect, if this thread has no virtual clock.
+pub fn advance_clock(by: Duration) -> bool {
+    match virtual_clock() {
+        Some(clock) => {
+            clock.advance(by);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Wait for `duration`
+///
+/// Under a virtual clock this advances the clock and returns at once.
+pub fn sleep(duration: Duration) {
+    if !advance_clock(duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Sort hash map entries by key when iteration order must be reproducible
+pub fn order_entries<K: Ord, V>(entries: &mut [(K, V)]) {
+    if is_enabled() {
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_seeded_streams() {
+        let draw = |seed, stream| seeded_rng(seed, stream).gen::<u64>();
+
+        assert_eq!(draw(42, 0), draw(42, 0));
+        assert_ne!(draw(42, 0), draw(42, 1));
+        assert_ne!(draw(42, 0), draw(43, 0));
+    }
+
+    #[test]
+    fn test_virtual_clock_timers() {
+        let clock = VirtualClock::new();
+        let waker = futures::task::noop_waker();
+        clock.register(Duration::from_millis(100), waker.clone());
+        clock.register(Duration::from_millis(300), waker.clone());
+
+        assert_eq!(clock.advance(Duration::from_millis(50)), 0);
+        assert_eq!(clock.advance(Duration::from_millis(50)), 1);
+        assert!(clock.advance_to_next_timer());
+        assert_eq!(clock.now(), Duration::from_millis(300));
+        assert!(!clock.advance_to_next_timer());
+        assert_eq!(clock.pending_timers(), 0);
+    }
+
+    #[test]
+    fn test_installed_clock_is_per_thread() {
+        let clock = Arc::new(VirtualClock::new());
+        let previous = install_clock(Some(Arc::clone(&clock)));
+
+        sleep(Duration::from_secs(3600));
+        assert_eq!(monotonic_now(), Duration::from_secs(3600));
+        let other = thread::spawn(monotonic_now).join().unwrap();
+        assert!(other < Duration::from_secs(3600));
+
+        install_clock(previous);
+    }
+}
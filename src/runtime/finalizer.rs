@@ -0,0 +1,92 @@
+//! Finalizers for reference-counted values
+//!
+//! A finalizer is attached to a `ScriptRc` allocation and runs once, right
+//! after the last strong reference is released and the value is dropped. It
+//! is not given the value, so it cannot bring it back to life, and weak
+//! references to the value no longer upgrade by the time it runs.
+//!
+//! Finalizers are kept per thread: a finalizer runs only when its value is
+//! released on the thread that registered it. A finalizer that captures a
+//! strong reference to its own value keeps that value alive forever.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+type Finalizer = Box<dyn FnOnce()>;
+
+thread_local! {
+    /// Pending finalizers, keyed by the address of the allocation they watch
+    static FINALIZERS: RefCell<HashMap<usize, Vec<Finalizer>>> = RefCell::new(HashMap::new());
+}
+
+/// Run `finalizer` once the value at `address` is deallocated
+///
+/// `address` is the `ScriptRc::as_raw` of the value to watch. Finalizers
+/// registered on the same value run in registration order.
+pub fn register_finalizer(address: *const (), finalizer: impl FnOnce() + 'static) {
+    FINALIZERS.with(|finalizers| {
+        finalizers
+            .borrow_mut()
+            .entry(address as usize)
+            .or_default()
+            .push(Box::new(finalizer));
+    });
+}
+
+/// Number of values on this thread with finalizers still to run
+pub fn pending_finalizers() -> usize {
+    FINALIZERS.with(|finalizers| finalizers.borrow().len())
+}
+
+/// Run the finalizers of a value that was just dropped
+pub(crate) fn run_finalizers(address: *const ()) {
+    // The table is released before running anything, since a finalizer may
+    // drop other values or register new finalizers
+    let pending = FINALIZERS
+        .try_with(|finalizers| {
+            let mut finalizers = finalizers.try_borrow_mut().ok()?;
+            if finalizers.is_empty() {
+                None
+            } else {
+                finalizers.remove(&(address as usize))
+            }
+        })
+        .ok()
+        .flatten();
+
+    for finalizer in pending.into_iter().flatten() {
+        finalizer();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::ScriptRc;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_finalizer_runs_on_last_release() {
+        let ran = Rc::new(Cell::new(0));
+        let value = ScriptRc::new(42);
+        let weak = value.downgrade();
+        let copy = value.clone();
+
+        let counter = ran.clone();
+        register_finalizer(value.as_raw(), move || counter.set(counter.get() + 1));
+        let counter = ran.clone();
+        register_finalizer(value.as_raw(), move || {
+            // The value is gone by the time finalizers run
+            assert!(weak.upgrade().is_none());
+            counter.set(counter.get() + 1);
+        });
+        assert_eq!(pending_finalizers(), 1);
+
+        drop(value);
+        assert_eq!(ran.get(), 0);
+        drop(copy);
+        assert_eq!(ran.get(), 2);
+        assert_eq!(pending_finalizers(), 0);
+    }
+}
@@ -0,0 +1,355 @@
+//! Function profiler for Script programs
+//!
+//! With profiling enabled in code generation, every compiled Script function
+//! calls `enter` on entry and `exit` before each return. The profiler keeps
+//! a call stack per thread and, between `start` and `stop`, aggregates for
+//! each function:
+//! - how many times it was called
+//! - its total time, including callees (recursive calls are counted once)
+//! - its self time, excluding callees
+//!
+//! Self time is also recorded per call stack, which `ProfileReport` writes in
+//! the collapsed-stack format read by flamegraph tools
+//! (`main;update;physics 1200`, weights in nanoseconds).
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Bumped by `start` so threads drop call stacks from earlier sessions
+static SESSION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Default)]
+struct Names {
+    names: Vec<String>,
+    ids: HashMap<String, u32>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Counters {
+    calls: u64,
+    total: Duration,
+    self_time: Duration,
+}
+
+#[derive(Default)]
+struct ProfileData {
+    started: Option<Instant>,
+    functions: HashMap<u32, Counters>,
+    /// Self time of each call stack, outermost function first
+    stacks: HashMap<Vec<u32>, Duration>,
+}
+
+struct Frame {
+    id: u32,
+    start: Instant,
+    /// Time spent in callees that have returned
+    children: Duration,
+}
+
+thread_local! {
+    /// This thread's call stack and the session it belongs to
+    static STACK: RefCell<(u64, Vec<Frame>)> = const { RefCell::new((0, Vec::new())) };
+}
+
+fn lock<T>(mutex: &'static OnceLock<Mutex<T>>) -> MutexGuard<'static, T>
+where
+    T: Default,
+{
+    mutex
+        .get_or_init(|| Mutex::new(T::default()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn names() -> MutexGuard<'static, Names> {
+    static NAMES: OnceLock<Mutex<Names>> = OnceLock::new();
+    lock(&NAMES)
+}
+
+fn data() -> MutexGuard<'static, ProfileData> {
+    static DATA: OnceLock<Mutex<ProfileData>> = OnceLock::new();
+    lock(&DATA)
+}
+
+/// The id passed to `enter` and `exit` for a function, assigned on first use
+pub fn function_id(name: &str) -> u32 {
+    let mut names = names();
+    if let Some(&id) = names.ids.get(name) {
+        return id;
+    }
+    let id = names.names.len() as u32;
+    names.names.push(name.to_string());
+    names.ids.insert(name.to_string(), id);
+    id
+}
+
+/// Clear earlier results and start recording calls
+pub fn start() {
+    *data() = ProfileData {
+        started: Some(Instant::now()),
+        ..ProfileData::default()
+    };
+    SESSION.fetch_add(1, Ordering::SeqCst);
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Stop recording and return what was recorded since `start`
+///
+/// Calls still running are left out.
+pub fn stop() -> ProfileReport {
+    ENABLED.store(false, Ordering::SeqCst);
+    let data = std::mem::take(&mut *data());
+    let names = names();
+    let name = |id: u32| {
+        names
+            .names
+            .get(id as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("<function {}>", id))
+    };
+
+    let mut functions: Vec<FunctionProfile> = data
+        .functions
+        .into_iter()
+        .map(|(id, counters)| FunctionProfile {
+            name: name(id),
+            calls: counters.calls,
+            total_time: counters.total,
+            self_time: counters.self_time,
+        })
+        .collect();
+    functions.sort_by(|a, b| b.self_time.cmp(&a.self_time).then(a.name.cmp(&b.name)));
+
+    let mut stacks: Vec<(Vec<String>, Duration)> = data
+        .stacks
+        .into_iter()
+        .map(|(path, time)| (path.into_iter().map(name).collect(), time))
+        .collect();
+    stacks.sort();
+
+    ProfileReport {
+        duration: data
+            .started
+            .map(|start| start.elapsed())
+            .unwrap_or_default(),
+        functions,
+        stacks,
+    }
+}
+
+/// Whether calls are being recorded
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Record entry into a function
+pub fn enter(id: u32) {
+    if !is_enabled() {
+        return;
+    }
+    STACK.with(|cell| {
+        let mut stack = cell.borrow_mut();
+        let session = SESSION.load(Ordering::SeqCst);
+        if stack.0 != session {
+            *stack = (session, Vec::new());
+        }
+        stack.1.push(Frame {
+            id,
+            start: Instant::now(),
+            children: Duration::ZERO,
+        });
+    });
+}
+
+/// Record a return from a function
+///
+/// Frames above the function's own are closed with it, so a callee that
+/// left without reporting its exit is charged up to this point.
+pub fn exit(id: u32) {
+    if !is_enabled() {
+        return;
+    }
+    let now = Instant::now();
+    STACK.with(|cell| {
+        let mut stack = cell.borrow_mut();
+        if stack.0 != SESSION.load(Ordering::SeqCst) {
+            return;
+        }
+        let frames = &mut stack.1;
+        let Some(position) = frames.iter().rposition(|frame| frame.id == id) else {
+            return;
+        };
+
+        let mut data = data();
+        while frames.len() > position {
+            let path: Vec<u32> = frames.iter().map(|frame| frame.id).collect();
+            let frame = frames.pop().expect("stack is longer than position");
+            let elapsed = now.saturating_duration_since(frame.start);
+            let self_time = elapsed.saturating_sub(frame.children);
+            let recursive = frames.iter().any(|outer| outer.id == frame.id);
+
+            let counters = data.functions.entry(frame.id).or_default();
+            counters.calls += 1;
+            counters.self_time += self_time;
+            if !recursive {
+                counters.total += elapsed;
+            }
+            *data.stacks.entry(path).or_default() += self_time;
+
+            if let Some(parent) = frames.last_mut() {
+                parent.children += elapsed;
+            }
+        }
+    });
+}
+
+/// Time spent in one function
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionProfile {
+    pub name: String,
+    pub calls: u64,
+    /// Time from entry to return, including callees
+    pub total_time: Duration,
+    /// Time excluding callees
+    pub self_time: Duration,
+}
+
+/// Results of a profiling session
+#[derive(Debug, Clone, Default)]
+pub struct ProfileReport {
+    /// Wall-clock time between `start` and `stop`
+    pub duration: Duration,
+    /// Functions by self time, highest first
+    pub functions: Vec<FunctionProfile>,
+    /// Self time of each call stack, outermost function first
+    pub stacks: Vec<(Vec<String>, Duration)>,
+}
+
+impl ProfileReport {
+    /// Write the call stacks in collapsed-stack format, one stack per line
+    /// weighted by its self time in nanoseconds
+    pub fn write_collapsed<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        for (path, time) in &self.stacks {
+            let nanos = time.as_nanos();
+            if nanos > 0 {
+                writeln!(out, "{} {}", path.join(";"), nanos)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |time: Duration| {
+            if self.duration.is_zero() {
+                0.0
+            } else {
+                100.0 * time.as_secs_f64() / self.duration.as_secs_f64()
+            }
+        };
+
+        writeln!(
+            f,
+            "Profile: {:.3}ms, {} functions",
+            self.duration.as_secs_f64() * 1000.0,
+            self.functions.len()
+        )?;
+        writeln!(
+            f,
+            "{:>12} {:>7} {:>12} {:>7} {:>10}  Function",
+            "Self ms", "Self%", "Total ms", "Total%", "Calls"
+        )?;
+        for function in &self.functions {
+            writeln!(
+                f,
+                "{:>12.3} {:>6.1}% {:>12.3} {:>6.1}% {:>10}  {}",
+                function.self_time.as_secs_f64() * 1000.0,
+                percent(function.self_time),
+                function.total_time.as_secs_f64() * 1000.0,
+                percent(function.total_time),
+                function.calls,
+                function.name
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_and_total_time() {
+        let outer = function_id("profiler_test_outer");
+        let inner = function_id("profiler_test_inner");
+        assert_eq!(function_id("profiler_test_outer"), outer);
+
+        start();
+        enter(outer);
+        for _ in 0..2 {
+            enter(inner);
+            std::thread::sleep(Duration::from_millis(5));
+            exit(inner);
+        }
+        // A recursive call and a callee that never reports its exit
+        enter(outer);
+        enter(inner);
+        exit(outer);
+        exit(outer);
+        let report = stop();
+
+        let profile = |name: &str| {
+            report
+                .functions
+                .iter()
+                .find(|function| function.name == name)
+                .unwrap()
+                .clone()
+        };
+        let (outer_profile, inner_profile) = (
+            profile("profiler_test_outer"),
+            profile("profiler_test_inner"),
+        );
+        assert_eq!(outer_profile.calls, 2);
+        assert_eq!(inner_profile.calls, 3);
+        assert!(inner_profile.self_time >= Duration::from_millis(10));
+        assert!(outer_profile.total_time >= inner_profile.total_time);
+        assert!(outer_profile.self_time < inner_profile.self_time);
+
+        let path = vec![
+            "profiler_test_outer".to_string(),
+            "profiler_test_inner".to_string(),
+        ];
+        assert!(report.stacks.iter().any(|(stack, _)| *stack == path));
+    }
+
+    #[test]
+    fn test_collapsed_output() {
+        let report = ProfileReport {
+            duration: Duration::from_millis(3),
+            functions: Vec::new(),
+            stacks: vec![
+                (vec!["main".to_string()], Duration::from_micros(1)),
+                (
+                    vec!["main".to_string(), "update".to_string()],
+                    Duration::from_micros(2),
+                ),
+                (vec!["idle".to_string()], Duration::ZERO),
+            ],
+        };
+        let mut out = Vec::new();
+        report.write_collapsed(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "main 1000\nmain;update 2000\n"
+        );
+    }
+}
@@ -8,6 +8,14 @@
 //! 1. Tracking potential cycle roots when reference counts decrease
 //! 2. Periodically running trial deletion to identify actual cycles
 //! 3. Collecting objects that are only reachable from cycles
+//!
+//! In `GcMode::Generational` the buffered roots are split into two
+//! generations. Minor collections only scan roots buffered since the last
+//! collection; roots that survive one are promoted to the old generation,
+//! which is scanned by every `MAJOR_COLLECTION_INTERVAL`th collection. A
+//! reference stored into an old object can close a cycle the minor
+//! collections would miss, so generated code calls `write_barrier` after
+//! such stores to have the object scanned with the young roots.
 
 use std::collections::{HashMap, HashSet};
 use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
@@ -23,12 +31,66 @@ use crate::runtime::type_registry;
 /// Global cycle collector instance
 static CYCLE_COLLECTOR: RwLock<Option<Arc<CycleCollector>>> = RwLock::new(None);
 
+/// Number of collections between two scans of the old generation
+const MAJOR_COLLECTION_INTERVAL: usize = 8;
+
+/// How the runtime reclaims reference cycles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GcMode {
+    /// Reference counting alone; cycles are never reclaimed
+    RefCountOnly,
+    /// Trial deletion over every buffered root on each collection
+    #[default]
+    CycleCollector,
+    /// Trial deletion over young roots, with periodic scans of old ones
+    Generational,
+}
+
+impl GcMode {
+    /// Parse a mode from its command line name
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "rc" => Some(GcMode::RefCountOnly),
+            "cycle" => Some(GcMode::CycleCollector),
+            "generational" => Some(GcMode::Generational),
+            _ => None,
+        }
+    }
+
+    /// Command line name of the mode
+    pub fn name(self) -> &'static str {
+        match self {
+            GcMode::RefCountOnly => "rc",
+            GcMode::CycleCollector => "cycle",
+            GcMode::Generational => "generational",
+        }
+    }
+
+    /// Whether generated code must report pointer stores to the collector
+    pub fn needs_write_barriers(self) -> bool {
+        self == GcMode::Generational
+    }
+}
+
 /// Initialize the garbage collector
 pub fn initialize() -> Result<(), &'static str> {
+    initialize_with_mode(GcMode::default())
+}
+
+/// Initialize the garbage collector in the given mode
+///
+/// `GcMode::RefCountOnly` leaves the runtime without a collector.
+pub fn initialize_with_mode(mode: GcMode) -> Result<(), &'static str> {
     let mut collector = CYCLE_COLLECTOR
         .write()
         .map_err(|_| "Failed to acquire collector lock")?;
-    *collector = Some(Arc::new(CycleCollector::new()));
+    if mode == GcMode::RefCountOnly {
+        if let Some(c) = collector.take() {
+            c.shutdown();
+        }
+        return Ok(());
+    }
+    *collector = Some(Arc::new(CycleCollector::new(mode)));
 
     // Start the background GC thread
     let collector_ref = collector
@@ -80,6 +142,16 @@ pub fn collect_cycles() {
     }
 }
 
+/// Force a cycle collection over every buffered root, including the old
+/// generation
+pub fn collect_cycles_full() {
+    if let Ok(collector) = CYCLE_COLLECTOR.read() {
+        if let Some(c) = collector.as_ref() {
+            let _ = c.collect_full();
+        }
+    }
+}
+
 /// Perform incremental cycle collection
 /// Returns true if collection is complete, false if more work remains
 pub fn collect_cycles_incremental(max_work: usize) -> bool {
@@ -101,6 +173,19 @@ pub fn possible_cycle<T: ?Sized>(rc: &ScriptRc<T>) {
     }
 }
 
+/// Record a reference stored into the object at `address`
+///
+/// Generated code calls this after pointer stores in generational mode.
+/// An old object written to is moved to the remembered set and scanned
+/// with the young roots by the next collection.
+pub fn write_barrier(address: usize) {
+    if let Ok(collector) = CYCLE_COLLECTOR.read() {
+        if let Some(c) = collector.as_ref() {
+            c.record_write(address);
+        }
+    }
+}
+
 /// Represents a node in the object graph
 #[derive(Debug, Clone)]
 struct GraphNode {
@@ -230,6 +315,8 @@ struct RcBox<T: ?Sized> {
 struct IncrementalState {
     /// Roots being processed
     roots: Vec<usize>,
+    /// Whether the roots include the old generation
+    major: bool,
     /// Objects to scan
     to_scan: Vec<usize>,
     /// Current phase
@@ -253,8 +340,21 @@ enum CollectionPhase {
     CollectWhite,
 }
 
+/// Old generation bookkeeping of `GcMode::Generational`
+#[derive(Debug, Default)]
+struct Generations {
+    /// Roots that survived a collection
+    old: HashSet<usize>,
+    /// Old objects written to since the last collection
+    remembered: HashSet<usize>,
+    /// Collections since the old generation was last scanned
+    minor_since_major: usize,
+}
+
 /// The cycle collector
 pub struct CycleCollector {
+    /// Collection strategy
+    mode: GcMode,
     /// Map of all registered ScriptRc addresses to their type info
     registered: Mutex<HashMap<usize, RegisteredRc>>,
     /// Nodes that might be cycle roots (ref count decreased but not to zero)
@@ -269,6 +369,8 @@ pub struct CycleCollector {
     stats: Mutex<CollectionStats>,
     /// Incremental collection state
     incremental_state: Mutex<Option<IncrementalState>>,
+    /// Old generation, empty unless the mode is generational
+    generations: Mutex<Generations>,
 }
 
 /// Statistics about cycle collection
@@ -284,12 +386,38 @@ pub struct CollectionStats {
     pub total_time: Duration,
     /// Last collection time
     pub last_collection: Option<Instant>,
+    /// Collections that scanned only young and remembered roots
+    pub minor_collections: usize,
+    /// Collections that scanned every buffered root
+    pub major_collections: usize,
+    /// Roots promoted to the old generation
+    pub objects_promoted: usize,
+    /// Stores into old objects recorded by the write barrier
+    pub remembered_writes: usize,
+}
+
+/// Snapshot of the objects tracked by the collector
+#[derive(Debug, Clone)]
+pub struct HeapStats {
+    /// Mode of the running collector; `RefCountOnly` when there is none
+    pub mode: GcMode,
+    /// Live objects registered with the collector
+    pub tracked_objects: usize,
+    /// Roots buffered since the last collection
+    pub young_roots: usize,
+    /// Roots that survived a collection
+    pub old_roots: usize,
+    /// Old objects written to since the last collection
+    pub remembered: usize,
+    /// Collection totals
+    pub collections: CollectionStats,
 }
 
 impl CycleCollector {
     /// Create a new cycle collector
-    fn new() -> Self {
+    fn new(mode: GcMode) -> Self {
         CycleCollector {
+            mode,
             registered: Mutex::new(HashMap::new()),
             possible_roots: Mutex::new(HashSet::new()),
             allocation_count: AtomicUsize::new(0),
@@ -297,6 +425,7 @@ impl CycleCollector {
             shutdown: AtomicBool::new(false),
             stats: Mutex::new(CollectionStats::default()),
             incremental_state: Mutex::new(None),
+            generations: Mutex::new(Generations::default()),
         }
     }
 
@@ -333,11 +462,29 @@ impl CycleCollector {
             return Err("Failed to acquire possible_roots lock for unregister");
         }
 
+        if let Ok(mut generations) = self.generations.lock() {
+            generations.old.remove(&address);
+            generations.remembered.remove(&address);
+        } else {
+            return Err("Failed to acquire generations lock for unregister");
+        }
+
         Ok(())
     }
 
     /// Add a possible cycle root
     fn add_possible_root(&self, address: usize) -> Result<(), &'static str> {
+        // Old roots wait for the next major collection unless written to
+        if self.mode == GcMode::Generational {
+            let generations = self
+                .generations
+                .lock()
+                .map_err(|_| "Failed to acquire generations lock")?;
+            if generations.old.contains(&address) {
+                return Ok(());
+            }
+        }
+
         let mut possible_roots = self
             .possible_roots
             .lock()
@@ -348,18 +495,19 @@ impl CycleCollector {
 
     /// Perform cycle collection using Bacon-Rajan algorithm
     fn collect(&self) -> Result<(), &'static str> {
+        self.collect_roots(false)
+    }
+
+    /// Perform cycle collection over every buffered root
+    fn collect_full(&self) -> Result<(), &'static str> {
+        self.collect_roots(true)
+    }
+
+    fn collect_roots(&self, force_major: bool) -> Result<(), &'static str> {
         let start = Instant::now();
 
         // Take a snapshot of possible roots
-        let roots: Vec<usize> = {
-            let mut possible_roots = self
-                .possible_roots
-                .lock()
-                .map_err(|_| "Failed to acquire possible_roots lock during collection")?;
-            let roots: Vec<_> = possible_roots.iter().cloned().collect();
-            possible_roots.clear(); // Clear for next collection
-            roots
-        };
+        let (roots, major) = self.take_roots(force_major)?;
 
         if roots.is_empty() {
             return Ok(());
@@ -384,21 +532,133 @@ impl CycleCollector {
         }
 
         // Phase 4: Collect white objects (garbage)
-        let collected = self.collect_white(&roots);
+        let collected = self.finish_collection(&roots, major);
+
+        if let Ok(mut stats) = self.stats.lock() {
+            stats.total_time += start.elapsed();
+        }
+
+        // Log collection results
+        profiler::record_gc_collection(collected, start.elapsed());
+
+        Ok(())
+    }
+
+    /// Take the roots of the next collection and whether they include the
+    /// old generation
+    ///
+    /// Outside generational mode every collection is a major one over the
+    /// buffered roots.
+    fn take_roots(&self, force_major: bool) -> Result<(Vec<usize>, bool), &'static str> {
+        let mut roots: HashSet<usize> = {
+            let mut possible_roots = self
+                .possible_roots
+                .lock()
+                .map_err(|_| "Failed to acquire possible_roots lock during collection")?;
+            std::mem::take(&mut *possible_roots) // Clear for next collection
+        };
+
+        if self.mode != GcMode::Generational {
+            return Ok((roots.into_iter().collect(), true));
+        }
+
+        let mut generations = self
+            .generations
+            .lock()
+            .map_err(|_| "Failed to acquire generations lock during collection")?;
+        roots.extend(generations.remembered.drain());
+        let major = force_major || generations.minor_since_major + 1 >= MAJOR_COLLECTION_INTERVAL;
+        if major {
+            roots.extend(generations.old.drain());
+            generations.minor_since_major = 0;
+        } else {
+            generations.minor_since_major += 1;
+        }
+        Ok((roots.into_iter().collect(), major))
+    }
+
+    /// Free the garbage among `roots`, promote the survivors and record the
+    /// collection; returns the number of objects collected
+    fn finish_collection(&self, roots: &[usize], major: bool) -> usize {
+        let collected = self.collect_white(roots);
+        let promoted = if self.mode == GcMode::Generational {
+            self.promote(roots)
+        } else {
+            0
+        };
 
         // Update statistics
         if let Ok(mut stats) = self.stats.lock() {
             stats.collections += 1;
+            if major {
+                stats.major_collections += 1;
+            } else {
+                stats.minor_collections += 1;
+            }
             stats.objects_collected += collected;
+            stats.objects_promoted += promoted;
             stats.cycles_detected += if collected > 0 { 1 } else { 0 };
-            stats.total_time += start.elapsed();
             stats.last_collection = Some(Instant::now());
         }
 
-        // Log collection results
-        profiler::record_gc_collection(collected, start.elapsed());
+        collected
+    }
 
-        Ok(())
+    /// Move the roots still alive after a collection to the old generation
+    fn promote(&self, roots: &[usize]) -> usize {
+        let Ok(registered) = self.registered.lock() else {
+            return 0;
+        };
+        let Ok(mut generations) = self.generations.lock() else {
+            return 0;
+        };
+
+        let mut promoted = 0;
+        for &addr in roots {
+            if registered.contains_key(&addr) && generations.old.insert(addr) {
+                promoted += 1;
+            }
+        }
+        promoted
+    }
+
+    /// Remember an old object a reference was stored into
+    fn record_write(&self, address: usize) {
+        if self.mode != GcMode::Generational {
+            return;
+        }
+
+        let remembered = match self.generations.lock() {
+            Ok(mut generations) => {
+                generations.old.remove(&address) && generations.remembered.insert(address)
+            }
+            Err(_) => false,
+        };
+        if remembered {
+            if let Ok(mut stats) = self.stats.lock() {
+                stats.remembered_writes += 1;
+            }
+        }
+    }
+
+    /// Snapshot of the tracked objects and collection totals
+    fn heap_stats(&self) -> HeapStats {
+        let tracked_objects = self.registered.lock().map(|r| r.len()).unwrap_or(0);
+        let young_roots = self.possible_roots.lock().map(|r| r.len()).unwrap_or(0);
+        let (old_roots, remembered) = self
+            .generations
+            .lock()
+            .map(|g| (g.old.len(), g.remembered.len()))
+            .unwrap_or((0, 0));
+
+        HeapStats {
+            mode: self.mode,
+            tracked_objects,
+            young_roots,
+            old_roots,
+            remembered,
+            collections: self.stats(),
+        }
     }
 
     /// Mark all buffered objects white
@@ -544,14 +804,9 @@ impl CycleCollector {
         // If no collection in progress, start a new one
         if state_guard.is_none() {
             // Take a snapshot of possible roots
-            let roots: Vec<usize> = {
-                let mut possible_roots = match self.possible_roots.lock() {
-                    Ok(guard) => guard,
-                    Err(_) => return true, // Lock failed, pretend collection is complete
-                };
-                let roots: Vec<_> = possible_roots.iter().cloned().collect();
-                possible_roots.clear();
-                roots
+            let (roots, major) = match self.take_roots(false) {
+                Ok(roots) => roots,
+                Err(_) => return true, // Lock failed, pretend collection is complete
             };
 
             if roots.is_empty() {
@@ -560,6 +815,7 @@ impl CycleCollector {
 
             *state_guard = Some(IncrementalState {
                 roots,
+                major,
                 to_scan: Vec::new(),
                 phase: CollectionPhase::MarkWhite,
                 processed: 0,
@@ -630,15 +886,7 @@ impl CycleCollector {
 
                 CollectionPhase::CollectWhite => {
                     // Collect white objects
-                    let collected = self.collect_white(&state.roots);
-
-                    // Update statistics
-                    if let Ok(mut stats) = self.stats.lock() {
-                        stats.collections += 1;
-                        stats.objects_collected += collected;
-                        stats.cycles_detected += if collected > 0 { 1 } else { 0 };
-                        stats.last_collection = Some(Instant::now());
-                    }
+                    self.finish_collection(&state.roots, state.major);
 
                     // Clear state - collection complete
                     *state_guard = None;
@@ -670,6 +918,23 @@ pub fn get_stats() -> Option<CollectionStats> {
     None
 }
 
+/// Get a snapshot of the heap tracked by the collector
+pub fn heap_stats() -> HeapStats {
+    if let Ok(collector) = CYCLE_COLLECTOR.read() {
+        if let Some(c) = collector.as_ref() {
+            return c.heap_stats();
+        }
+    }
+    HeapStats {
+        mode: GcMode::RefCountOnly,
+        tracked_objects: 0,
+        young_roots: 0,
+        old_roots: 0,
+        remembered: 0,
+        collections: CollectionStats::default(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -694,6 +959,56 @@ mod tests {
         shutdown();
     }
 
+    #[test]
+    fn test_gc_mode() {
+        assert_eq!(GcMode::parse("generational"), Some(GcMode::Generational));
+        assert_eq!(GcMode::parse("rc"), Some(GcMode::RefCountOnly));
+        assert_eq!(GcMode::parse("tracing"), None);
+        assert_eq!(GcMode::default().name(), "cycle");
+        assert!(GcMode::Generational.needs_write_barriers());
+        assert!(!GcMode::CycleCollector.needs_write_barriers());
+    }
+
+    #[test]
+    fn test_generational_roots() {
+        let collector = CycleCollector::new(GcMode::Generational);
+        let type_id = ScriptRc::new(0u8).type_id();
+        for address in [0x10, 0x20] {
+            collector
+                .registered
+                .lock()
+                .unwrap()
+                .insert(address, RegisteredRc { address, type_id });
+            collector.add_possible_root(address).unwrap();
+        }
+
+        // Both roots survive a minor collection and are promoted
+        let (roots, major) = collector.take_roots(false).unwrap();
+        assert_eq!(roots.len(), 2);
+        assert!(!major);
+        assert_eq!(collector.promote(&roots), 2);
+
+        // Old roots are not buffered again until written to
+        collector.add_possible_root(0x10).unwrap();
+        collector.record_write(0x20);
+        let stats = collector.heap_stats();
+        assert_eq!(stats.tracked_objects, 2);
+        assert_eq!(
+            (stats.young_roots, stats.old_roots, stats.remembered),
+            (0, 1, 1)
+        );
+        assert_eq!(stats.collections.remembered_writes, 1);
+
+        let (roots, major) = collector.take_roots(false).unwrap();
+        assert_eq!(roots, vec![0x20]);
+        assert!(!major);
+
+        // A major collection scans the old generation too
+        let (roots, major) = collector.take_roots(true).unwrap();
+        assert_eq!(roots, vec![0x10]);
+        assert!(major);
+    }
+
     #[test]
     fn test_collection_threshold() {
         initialize();
@@ -0,0 +1,209 @@
+//! Allocation-site tracking for Script programs
+//!
+//! While tracking is on, every `ScriptRc` allocation is counted against its
+//! site: the source location that created the value and the type allocated.
+//! Tracking starts with `start`, or at runtime initialization when
+//! `RuntimeConfig::track_allocations` is set. `report` returns the counts
+//! so far and `stop` ends tracking and returns the final counts.
+//!
+//! Counts are cumulative: a site's bytes are everything it allocated since
+//! tracking started, including values that have since been released.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Where an allocation came from and what it allocated
+type SiteKey = (&'static str, u32, &'static str);
+
+/// Allocation count and bytes of each site
+type Sites = HashMap<SiteKey, (u64, u64)>;
+
+static SITES: Mutex<Option<Sites>> = Mutex::new(None);
+
+fn sites() -> MutexGuard<'static, Option<Sites>> {
+    SITES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Clear earlier counts and start tracking allocations
+pub fn start() {
+    *sites() = Some(HashMap::new());
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Stop tracking and return the counts recorded since `start`
+pub fn stop() -> HeapReport {
+    ENABLED.store(false, Ordering::SeqCst);
+    let sites = sites().take().unwrap_or_default();
+    HeapReport::from_sites(sites)
+}
+
+/// The counts recorded so far, without stopping
+pub fn report() -> HeapReport {
+    let sites = sites().clone().unwrap_or_default();
+    HeapReport::from_sites(sites)
+}
+
+/// Whether allocations are being tracked
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Count an allocation of `size` bytes of `type_name` made at `location`
+pub fn record_allocation(
+    location: &'static Location<'static>,
+    type_name: &'static str,
+    size: usize,
+) {
+    if !is_enabled() {
+        return;
+    }
+    if let Some(sites) = sites().as_mut() {
+        let counts = sites
+            .entry((location.file(), location.line(), type_name))
+            .or_default();
+        counts.0 += 1;
+        counts.1 += size as u64;
+    }
+}
+
+/// Allocations made at one site
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllocationSite {
+    /// Source location as `file:line`
+    pub location: String,
+    pub type_name: String,
+    pub count: u64,
+    pub bytes: u64,
+}
+
+/// Allocation counts of a tracking session
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeapReport {
+    /// Sites by bytes allocated, highest first
+    pub sites: Vec<AllocationSite>,
+    /// Allocations across all sites
+    pub total_count: u64,
+    /// Bytes allocated across all sites
+    pub total_bytes: u64,
+}
+
+impl HeapReport {
+    fn from_sites(sites: Sites) -> Self {
+        let mut sites: Vec<AllocationSite> = sites
+            .into_iter()
+            .map(|((file, line, type_name), (count, bytes))| AllocationSite {
+                location: format!("{}:{}", file, line),
+                type_name: type_name.to_string(),
+                count,
+                bytes,
+            })
+            .collect();
+        sites.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then(b.count.cmp(&a.count))
+                .then_with(|| a.location.cmp(&b.location))
+                .then_with(|| a.type_name.cmp(&b.type_name))
+        });
+
+        HeapReport {
+            total_count: sites.iter().map(|site| site.count).sum(),
+            total_bytes: sites.iter().map(|site| site.bytes).sum(),
+            sites,
+        }
+    }
+
+    /// The report with only the `n` sites that allocated the most bytes
+    ///
+    /// Totals still cover every site.
+    pub fn top(&self, n: usize) -> HeapReport {
+        HeapReport {
+            sites: self.sites.iter().take(n).cloned().collect(),
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for HeapReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Heap profile: {} allocations, {} bytes",
+            self.total_count, self.total_bytes
+        )?;
+        writeln!(
+            f,
+            "{:>12} {:>7} {:>10}  {:<40} Type",
+            "Bytes", "Bytes%", "Count", "Site"
+        )?;
+        for site in &self.sites {
+            let percent = if self.total_bytes == 0 {
+                0.0
+            } else {
+                100.0 * site.bytes as f64 / self.total_bytes as f64
+            };
+            writeln!(
+                f,
+                "{:>12} {:>6.1}% {:>10}  {:<40} {}",
+                site.bytes, percent, site.count, site.location, site.type_name
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn allocate(type_name: &'static str, size: usize) {
+        record_allocation(Location::caller(), type_name, size);
+    }
+
+    #[test]
+    fn test_sites_by_bytes() {
+        start();
+        for _ in 0..3 {
+            allocate("String", 16);
+        }
+        let big = Location::caller();
+        record_allocation(big, "Vec", 400);
+        record_allocation(big, "Vec", 100);
+        let report = stop();
+
+        allocate("ignored", 1);
+        assert!(!is_enabled());
+
+        // Other tests may allocate while tracking is on
+        let vec_site = report
+            .sites
+            .iter()
+            .find(|site| site.type_name == "Vec")
+            .unwrap();
+        assert_eq!((vec_site.count, vec_site.bytes), (2, 500));
+        assert_eq!(vec_site.location, format!("{}:{}", big.file(), big.line()));
+        let string_site = report
+            .sites
+            .iter()
+            .find(|site| site.type_name == "String")
+            .unwrap();
+        assert_eq!((string_site.count, string_site.bytes), (3, 48));
+        assert!(report.sites.iter().all(|site| site.type_name != "ignored"));
+        assert!(report.total_bytes >= 548);
+
+        let top = report.top(1);
+        assert_eq!(top.sites.len(), 1);
+        assert_eq!(top.total_bytes, report.total_bytes);
+        let table = top.to_string();
+        assert!(table.starts_with("Heap profile:"));
+        assert_eq!(table.lines().count(), 3);
+    }
+}
@@ -0,0 +1,230 @@
+//! Resource leak checking for Script programs
+//!
+//! While checking is on, every file handle, socket and spawned task that
+//! embeds a [`TrackedResource`] is recorded together with the call site
+//! that created it, and forgotten again when the guard is dropped. Whatever
+//! is still recorded when the program exits was never closed or finished,
+//! and `stop` returns it as a [`LeakReport`].
+//!
+//! Checking starts with `start`, or at runtime initialization when
+//! `RuntimeConfig::check_leaks` is set. The stdlib tracks its sockets, the
+//! threads started by `thread_spawn` and tasks spawned on the async
+//! executors. Its file functions close each file before they return, so
+//! file handles only show up when native code keeps one open behind a
+//! guard of its own.
+
+use crate::runtime::stack_trace::get_stack_tracker;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Open resources by id, which is also the order they were opened in
+static OPEN: Mutex<Option<BTreeMap<u64, LeakedResource>>> = Mutex::new(None);
+
+fn open() -> MutexGuard<'static, Option<BTreeMap<u64, LeakedResource>>> {
+    OPEN.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Forget earlier resources and start tracking new ones
+pub fn start() {
+    *open() = Some(BTreeMap::new());
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Stop tracking and return the resources that are still open
+pub fn stop() -> LeakReport {
+    ENABLED.store(false, Ordering::SeqCst);
+    let open = open().take().unwrap_or_default();
+    LeakReport::from_open(open)
+}
+
+/// The resources open right now, without stopping
+pub fn report() -> LeakReport {
+    let open = open().clone().unwrap_or_default();
+    LeakReport::from_open(open)
+}
+
+/// Whether resources are being tracked
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// What kind of resource was leaked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    File,
+    Socket,
+    Task,
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ResourceKind::File => "file",
+            ResourceKind::Socket => "socket",
+            ResourceKind::Task => "task",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Records a resource as open for as long as the guard lives
+///
+/// Embed one in the value that owns the resource, or move it into the
+/// task that runs, so dropping the owner closes the record too. The default
+/// guard tracks nothing.
+#[derive(Debug, Default)]
+pub struct TrackedResource {
+    id: Option<u64>,
+}
+
+impl TrackedResource {
+    /// Record a resource of `kind` opened by the caller
+    ///
+    /// `describe` is only called while checking is on.
+    #[track_caller]
+    pub fn new(kind: ResourceKind, describe: impl FnOnce() -> String) -> Self {
+        if !is_enabled() {
+            return TrackedResource::default();
+        }
+
+        let location = Location::caller();
+        let script_location = get_stack_tracker()
+            .and_then(|tracker| tracker.capture_trace().top_frame().cloned())
+            .map(|frame| frame.to_string());
+        let resource = LeakedResource {
+            kind,
+            description: describe(),
+            location: format!("{}:{}", location.file(), location.line()),
+            script_location,
+        };
+
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        match open().as_mut() {
+            Some(open) => {
+                open.insert(id, resource);
+                TrackedResource { id: Some(id) }
+            }
+            None => TrackedResource::default(),
+        }
+    }
+}
+
+impl Drop for TrackedResource {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            if let Some(open) = open().as_mut() {
+                open.remove(&id);
+            }
+        }
+    }
+}
+
+/// A resource that was still open at exit
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeakedResource {
+    pub kind: ResourceKind,
+    /// What the resource is, such as the address of a socket
+    pub description: String,
+    /// Location in the runtime or host that opened it, as `file:line`
+    pub location: String,
+    /// Innermost Script frame at the time, if frames were being recorded
+    pub script_location: Option<String>,
+}
+
+/// Resources still open at the end of a checking session
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LeakReport {
+    /// Leaked resources in the order they were opened
+    pub leaks: Vec<LeakedResource>,
+}
+
+impl LeakReport {
+    fn from_open(open: BTreeMap<u64, LeakedResource>) -> Self {
+        LeakReport {
+            leaks: open.into_values().collect(),
+        }
+    }
+
+    /// Whether every tracked resource was released
+    pub fn is_clean(&self) -> bool {
+        self.leaks.is_empty()
+    }
+
+    /// Number of leaked resources of `kind`
+    pub fn count(&self, kind: ResourceKind) -> usize {
+        self.leaks.iter().filter(|leak| leak.kind == kind).count()
+    }
+}
+
+impl fmt::Display for LeakReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_clean() {
+            return writeln!(f, "Leak check: no resources left open");
+        }
+
+        writeln!(
+            f,
+            "Leak check: {} resources left open ({} files, {} sockets, {} tasks)",
+            self.leaks.len(),
+            self.count(ResourceKind::File),
+            self.count(ResourceKind::Socket),
+            self.count(ResourceKind::Task)
+        )?;
+        for leak in &self.leaks {
+            writeln!(
+                f,
+                "  {:<6} {}\n         opened at {}",
+                leak.kind, leak.description, leak.location
+            )?;
+            if let Some(script_location) = &leak.script_location {
+                writeln!(f, "         in {}", script_location)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_resources_still_open() {
+        let before = TrackedResource::new(ResourceKind::File, || "ignored".to_string());
+
+        start();
+        let socket = TrackedResource::new(ResourceKind::Socket, || "TCP stream".to_string());
+        let line = line!() - 1;
+        let task = TrackedResource::new(ResourceKind::Task, || "async task".to_string());
+        drop(task);
+        drop(before);
+        let leaked = stop();
+        drop(socket);
+
+        // Other tests may open resources while checking is on
+        let ours: Vec<&LeakedResource> = leaked
+            .leaks
+            .iter()
+            .filter(|leak| leak.location.starts_with(file!()))
+            .collect();
+        assert_eq!(ours.len(), 1);
+        assert_eq!(ours[0].kind, ResourceKind::Socket);
+        assert_eq!(ours[0].description, "TCP stream");
+        assert_eq!(ours[0].location, format!("{}:{}", file!(), line));
+
+        assert!(!is_enabled());
+        let text = leaked.to_string();
+        assert!(text.starts_with("Leak check: "));
+        assert!(text.contains("socket TCP stream\n         opened at "));
+        assert!(LeakReport::default()
+            .to_string()
+            .contains("no resources left open"));
+    }
+}
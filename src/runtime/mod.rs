@@ -5,10 +5,15 @@
 //! - Runtime initialization and shutdown
 //! - Panic handling with stack traces
 //! - Memory profiling and leak detection
+//! - Function profiling with flamegraph output
+//! - Allocation tracking by site
+//! - Line coverage with LCOV output
+//! - An embedded profile with a fixed-size heap for constrained targets
 //!
 //! The runtime is designed to be thread-safe for future actor model support
 //! and integrates with the code generated by the IR system.
 
+pub mod arena;
 pub mod async_ffi;
 pub mod async_ffi_secure;
 pub mod async_generators;
@@ -17,13 +22,21 @@ pub mod async_runtime;
 pub mod async_runtime_secure;
 // pub mod async_security_tests; // Temporarily disabled for compilation
 pub mod async_tokio_bridge;
+pub mod channel;
 pub mod closure;
 pub mod core;
+pub mod coverage;
+pub mod deterministic;
 pub mod distributed;
+pub mod finalizer;
+pub mod function_profiler;
 pub mod gc;
+pub mod heap_profiler;
+pub mod leak_check;
 pub mod method_dispatch;
 pub mod optimized_value;
 pub mod panic;
+pub mod profile;
 pub mod profiler;
 pub mod rc;
 pub mod recovery;
@@ -32,7 +45,10 @@ pub mod safe_gc;
 pub mod sandbox;
 pub mod scheduler;
 pub mod security;
+pub mod shutdown;
 pub mod stack_trace;
+pub mod sync;
+pub mod thread_pool;
 pub mod traceable;
 pub mod type_registry;
 pub mod value;
@@ -44,6 +60,7 @@ pub use async_tokio_bridge::{
     block_on as tokio_block_on, global_runtime, init_global_runtime, spawn as tokio_spawn,
     TokioBridge, TokioConfig,
 };
+pub use channel::{Channel, TryRecvError, TrySendError};
 pub use closure::{
     create_closure_optimal, create_optimized_closure_heap, create_simple_closure,
     get_performance_config, get_performance_stats, reset_performance_stats, set_performance_config,
@@ -51,10 +68,16 @@ pub use closure::{
     OptimizedClosureRuntime,
 };
 pub use core::{Runtime, RuntimeConfig};
+pub use coverage::{CoverageMap, CoverageReport, FileCoverage};
 pub use distributed::{DistributedNode, DistributedScheduler, LoadBalancingStrategy};
-pub use gc::CycleCollector;
+pub use finalizer::register_finalizer;
+pub use function_profiler::{FunctionProfile, ProfileReport};
+pub use gc::{CycleCollector, GcMode, HeapStats};
+pub use heap_profiler::{AllocationSite, HeapReport};
+pub use leak_check::{LeakReport, LeakedResource, ResourceKind, TrackedResource};
 pub use method_dispatch::{get_method_dispatcher, MethodDispatcher};
 pub use panic::{PanicHandler, RecoveryContext, RecoveryPolicy, RecoveryResult};
+pub use profile::RuntimeProfile;
 pub use profiler::{AllocationStats, MemoryProfiler};
 pub use rc::{ScriptRc, ScriptWeak};
 pub use recovery::{RecoveryMetrics, RuntimeState, StateRecoveryManager, ValidationResult};
@@ -63,10 +86,13 @@ pub use safe_gc::{GcSecurityConfig, SecureCycleCollector, SecurityError as GcSec
 pub use sandbox::{Capability, Sandbox, SandboxConfig, SandboxManager, SecurityViolation};
 pub use scheduler::{Scheduler, Task};
 pub use security::{SecurityConfig, SecurityEvent, SecurityEventType, SecurityMonitor};
+pub use shutdown::{install_signal_handlers, on_shutdown, run_cleanups};
 pub use stack_trace::{
     capture_current_trace, get_stack_tracker, initialize_stack_tracker, RuntimeStackTracker,
     StackFrame, StackGuard, StackTrace, StackTraceBuilder, StackTraceConfig,
 };
+pub use sync::{LockGuard, ScriptMutex, ScriptRwLock, SyncError};
+pub use thread_pool::{JoinHandle, ThreadPool, ThreadPoolConfig};
 pub use traceable::Traceable;
 pub use type_registry::{RegisterableType, TypeId, TypeInfo};
 pub use value::Value;
@@ -106,9 +132,11 @@ pub fn initialize() -> Result<()> {
     recovery::initialize_state_recovery();
 
     // Initialize Tokio runtime bridge
-    async_tokio_bridge::init_global_runtime().map_err(|e| {
-        RuntimeError::InvalidOperation(format!("Tokio runtime initialization failed: {}", e))
-    })?;
+    if profile::current() == RuntimeProfile::Full {
+        async_tokio_bridge::init_global_runtime().map_err(|e| {
+            RuntimeError::InvalidOperation(format!("Tokio runtime initialization failed: {}", e))
+        })?;
+    }
 
     // Initialize stack trace tracking
     stack_trace::initialize_stack_tracker().map_err(|e| {
@@ -0,0 +1,80 @@
+//! Runtime profiles
+//!
+//! The full profile is the default. The embedded profile is for running
+//! Script as a scripting layer on constrained targets, and is selected with
+//! `RuntimeConfig::embedded` or by building with the `embedded` feature:
+//! - Script allocations come out of one fixed-size [`Arena`] instead of the
+//!   system allocator, so the heap has a hard bound decided up front.
+//! - The async executors refuse to spawn or block on tasks, and the Tokio
+//!   bridge is never started.
+//! - The stdlib only has the core type, math, string, conversion and
+//!   collection functions. File I/O, networking, processes, threads and the
+//!   rest of what needs an operating system are left out.
+//!
+//! The profile is process-wide and set when the runtime is initialized.
+//!
+//! [`Arena`]: crate::runtime::arena::Arena
+
+use crate::error::{Error, Result};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Arena size used by the embedded profile unless configured otherwise
+pub const EMBEDDED_ARENA_SIZE: usize = 4 * 1024 * 1024;
+
+static EMBEDDED: AtomicBool = AtomicBool::new(cfg!(feature = "embedded"));
+
+/// Which parts of the runtime and stdlib are available
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuntimeProfile {
+    /// Everything, for hosts with an operating system
+    Full,
+    /// Core, math, string and collections on a fixed-size heap
+    Embedded,
+}
+
+impl Default for RuntimeProfile {
+    /// The embedded profile when built with the `embedded` feature
+    fn default() -> Self {
+        if cfg!(feature = "embedded") {
+            RuntimeProfile::Embedded
+        } else {
+            RuntimeProfile::Full
+        }
+    }
+}
+
+impl fmt::Display for RuntimeProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RuntimeProfile::Full => "full",
+            RuntimeProfile::Embedded => "embedded",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Switch the process to `profile`
+pub fn set(profile: RuntimeProfile) {
+    EMBEDDED.store(profile == RuntimeProfile::Embedded, Ordering::SeqCst);
+}
+
+/// The active profile
+pub fn current() -> RuntimeProfile {
+    if EMBEDDED.load(Ordering::Relaxed) {
+        RuntimeProfile::Embedded
+    } else {
+        RuntimeProfile::Full
+    }
+}
+
+/// Fail unless the full profile is active, naming `what` was refused
+pub fn require_full(what: &str) -> Result<()> {
+    match current() {
+        RuntimeProfile::Full => Ok(()),
+        RuntimeProfile::Embedded => Err(Error::runtime(format!(
+            "{} is not available in the embedded runtime profile",
+            what
+        ))),
+    }
+}
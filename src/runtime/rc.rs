@@ -14,7 +14,9 @@ use std::ops::Deref;
 use std::ptr::NonNull;
 use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
 
+use crate::runtime::finalizer;
 use crate::runtime::gc;
+use crate::runtime::heap_profiler;
 use crate::runtime::profiler;
 use crate::runtime::type_registry::{self, TypeId};
 
@@ -70,6 +72,7 @@ pub struct ScriptWeak<T> {
 
 impl<T> ScriptRc<T> {
     /// Create a new reference-counted value
+    #[track_caller]
     pub fn new(value: T) -> Self
     where
         T: 'static,
@@ -91,6 +94,11 @@ impl<T> ScriptRc<T> {
 
         // Notify profiler of allocation
         profiler::record_allocation(layout.size(), std::any::type_name::<T>());
+        heap_profiler::record_allocation(
+            std::panic::Location::caller(),
+            std::any::type_name::<T>(),
+            layout.size(),
+        );
 
         // Allocate memory
         unsafe {
@@ -298,6 +306,7 @@ impl<T: ?Sized> Drop for ScriptRc<T> {
 
                 // Drop the value
                 std::ptr::drop_in_place(&mut (*self.ptr.as_ptr()).value);
+                finalizer::run_finalizers(self.as_raw());
 
                 // Atomically decrement weak count with compare-and-swap to prevent races
                 loop {
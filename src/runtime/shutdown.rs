@@ -0,0 +1,203 @@
+//! Graceful shutdown on SIGINT and SIGTERM
+//!
+//! Once [`install_signal_handlers`] has run, the first SIGINT or SIGTERM
+//! (Ctrl-C on Windows) shuts the program down in this order:
+//! 1. tasks on the global async executor are cancelled
+//! 2. cleanups registered with [`on_shutdown`] run, most recent first
+//! 3. stdout and stderr are flushed
+//! 4. the process exits with `128 + signal`, 130 for SIGINT and 143 for
+//!    SIGTERM, as a shell would report it
+//!
+//! A second signal while cleanups are still running exits at once with the
+//! code for that signal. A program that finishes normally should call
+//! [`run_cleanups`] itself before it exits so the same cleanups run.
+
+use super::{async_ffi, Result, RuntimeError};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// Signal number of SIGINT
+pub const SIGINT: i32 = 2;
+
+/// Signal number of SIGTERM
+pub const SIGTERM: i32 = 15;
+
+type Cleanup = Box<dyn FnOnce() + Send>;
+
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Signal that started the shutdown, or 0 if none has
+static SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Cleanups waiting to run, in registration order
+fn cleanups() -> MutexGuard<'static, Vec<Cleanup>> {
+    static CLEANUPS: OnceLock<Mutex<Vec<Cleanup>>> = OnceLock::new();
+    CLEANUPS
+        .get_or_init(|| Mutex::new(Vec::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Run `cleanup` when the program shuts down, by signal or normally
+pub fn on_shutdown(cleanup: impl FnOnce() + Send + 'static) {
+    cleanups().push(Box::new(cleanup));
+}
+
+/// Run every registered cleanup once, most recently registered first
+///
+/// The registry is not locked while a cleanup runs, so a cleanup may
+/// register another one; it runs before those registered earlier.
+pub fn run_cleanups() {
+    loop {
+        let next = cleanups().pop();
+        match next {
+            Some(cleanup) => cleanup(),
+            None => break,
+        }
+    }
+}
+
+/// Exit code for a process stopped by `signal`
+pub fn exit_code_for_signal(signal: i32) -> i32 {
+    128 + signal
+}
+
+/// The signal that started a shutdown, if one has been received
+pub fn shutdown_signal() -> Option<i32> {
+    match SIGNAL.load(Ordering::SeqCst) {
+        0 => None,
+        signal => Some(signal),
+    }
+}
+
+/// Whether a shutdown has started; long-running loops can poll this
+pub fn shutdown_requested() -> bool {
+    shutdown_signal().is_some()
+}
+
+/// Shut down after `signal` and return the code to exit with
+///
+/// Does everything but exit, so callers that own the process can decide
+/// when to leave.
+pub fn shutdown(signal: i32) -> i32 {
+    SIGNAL.store(signal, Ordering::SeqCst);
+    async_ffi::shutdown_global_executor();
+    run_cleanups();
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+    exit_code_for_signal(signal)
+}
+
+/// Start watching for SIGINT and SIGTERM
+///
+/// The signals are watched from a dedicated thread, so this works whether
+/// or not the async runtime has been initialized. Calling it again does
+/// nothing.
+pub fn install_signal_handlers() -> Result<()> {
+    if INSTALLED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| RuntimeError::InvalidOperation(format!("Cannot watch signals: {}", e)))?;
+    // Listen before returning so a signal that arrives straight away is not
+    // handled by the default action
+    let mut signals = runtime
+        .block_on(async { Signals::listen() })
+        .map_err(|e| RuntimeError::InvalidOperation(format!("Cannot watch signals: {}", e)))?;
+
+    std::thread::Builder::new()
+        .name("script-signals".to_string())
+        .spawn(move || {
+            runtime.block_on(async move {
+                let signal = signals.next().await;
+                let cleanup = tokio::task::spawn_blocking(move || shutdown(signal));
+                let code = tokio::select! {
+                    code = cleanup => code.unwrap_or_else(|_| exit_code_for_signal(signal)),
+                    second = signals.next() => exit_code_for_signal(second),
+                };
+                std::process::exit(code);
+            })
+        })
+        .map_err(|e| RuntimeError::InvalidOperation(format!("Cannot watch signals: {}", e)))?;
+    Ok(())
+}
+
+#[cfg(unix)]
+struct Signals {
+    interrupt: tokio::signal::unix::Signal,
+    terminate: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+    fn listen() -> std::io::Result<Self> {
+        use tokio::signal::unix::{signal, SignalKind};
+        Ok(Signals {
+            interrupt: signal(SignalKind::interrupt())?,
+            terminate: signal(SignalKind::terminate())?,
+        })
+    }
+
+    async fn next(&mut self) -> i32 {
+        tokio::select! {
+            _ = self.interrupt.recv() => SIGINT,
+            _ = self.terminate.recv() => SIGTERM,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct Signals;
+
+#[cfg(not(unix))]
+impl Signals {
+    fn listen() -> std::io::Result<Self> {
+        Ok(Signals)
+    }
+
+    async fn next(&mut self) -> i32 {
+        match tokio::signal::ctrl_c().await {
+            Ok(()) => SIGINT,
+            // Without a Ctrl-C handler there is nothing to wait for
+            Err(_) => std::future::pending().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_exit_codes_follow_shell_convention() {
+        assert_eq!(exit_code_for_signal(SIGINT), 130);
+        assert_eq!(exit_code_for_signal(SIGTERM), 143);
+    }
+
+    #[test]
+    fn test_cleanups_run_once_in_reverse_order() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        for name in ["flush", "close"] {
+            let order = order.clone();
+            on_shutdown(move || order.lock().unwrap().push(name));
+        }
+        let nested = order.clone();
+        on_shutdown(move || {
+            let order = nested.clone();
+            on_shutdown(move || order.lock().unwrap().push("nested"));
+            nested.lock().unwrap().push("outer");
+        });
+
+        run_cleanups();
+        run_cleanups();
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec!["outer", "nested", "close", "flush"]
+        );
+    }
+}
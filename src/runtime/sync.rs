@@ -0,0 +1,564 @@
+//! Locks with deadlock detection
+//!
+//! `ScriptMutex<T>` and `ScriptRwLock<T>` hold a value that threads read
+//! and replace through a `LockGuard`. Guards are ordinary values that Script
+//! code can pass around, so a lock stays held until its guard is unlocked
+//! or the last copy of it is dropped.
+//!
+//! Every lock is tracked in a process-wide lock graph recording which
+//! threads hold it and which lock each blocked thread is waiting for.
+//! Before a thread blocks, the graph is searched for threads waiting on each
+//! other in a cycle that leads back to it. If one is found the lock call
+//! fails with `SyncError::Deadlock` instead of hanging, and every hook
+//! registered with `on_deadlock` is called; `report_deadlocks_to` counts
+//! them in a `SecurityMetrics` as `async_race_conditions_detected`.
+
+use crate::security::SecurityMetrics;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock, RwLock};
+use std::thread::{self, ThreadId};
+
+/// Identifies a lock in the lock graph
+pub type LockId = u64;
+
+/// A cycle of threads waiting on each other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deadlock {
+    /// The lock the detecting thread tried to take
+    pub lock: LockId,
+    /// The threads in the cycle, starting with the detecting thread
+    pub threads: Vec<ThreadId>,
+}
+
+impl fmt::Display for Deadlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.threads.len() == 1 {
+            write!(f, "lock #{} is already held by this thread", self.lock)
+        } else {
+            write!(
+                f,
+                "lock #{} would deadlock: {} threads are waiting on each other",
+                self.lock,
+                self.threads.len()
+            )
+        }
+    }
+}
+
+/// Why a lock or guard operation failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncError {
+    /// Waiting for the lock would never end
+    Deadlock(Deadlock),
+    /// The guard has already been unlocked
+    Released,
+    /// The guard only allows reading
+    ReadOnly,
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::Deadlock(deadlock) => write!(f, "Deadlock detected: {}", deadlock),
+            SyncError::Released => write!(f, "lock guard has already been unlocked"),
+            SyncError::ReadOnly => write!(f, "read guards cannot change the locked value"),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+type DeadlockHook = Box<dyn Fn(&Deadlock) + Send + Sync>;
+
+fn hooks() -> &'static RwLock<Vec<DeadlockHook>> {
+    static HOOKS: OnceLock<RwLock<Vec<DeadlockHook>>> = OnceLock::new();
+    HOOKS.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+/// Call `hook` whenever a deadlock is detected
+pub fn on_deadlock(hook: impl Fn(&Deadlock) + Send + Sync + 'static) {
+    hooks()
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(Box::new(hook));
+}
+
+/// Count detected deadlocks as race conditions in `metrics`
+pub fn report_deadlocks_to(metrics: Arc<SecurityMetrics>) {
+    on_deadlock(move |_| metrics.record_async_race_condition());
+}
+
+fn run_hooks(deadlock: &Deadlock) {
+    for hook in hooks()
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .iter()
+    {
+        hook(deadlock);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Default)]
+struct Holders {
+    writer: Option<ThreadId>,
+    /// One entry per read guard, so a thread may appear more than once
+    readers: Vec<ThreadId>,
+}
+
+impl Holders {
+    fn admits(&self, access: Access) -> bool {
+        match access {
+            Access::Read => self.writer.is_none(),
+            Access::Write => self.writer.is_none() && self.readers.is_empty(),
+        }
+    }
+
+    /// Threads that must release the lock before `access` is granted
+    fn blockers(&self, access: Access) -> Vec<ThreadId> {
+        let mut blockers: Vec<ThreadId> = self.writer.into_iter().collect();
+        if access == Access::Write {
+            blockers.extend(&self.readers);
+        }
+        blockers
+    }
+}
+
+#[derive(Default)]
+struct LockGraph {
+    held: HashMap<LockId, Holders>,
+    waiting: HashMap<ThreadId, (LockId, Access)>,
+}
+
+impl LockGraph {
+    /// Find a chain of waiting threads from the holders of `lock` back to
+    /// `current`
+    fn find_cycle(&self, lock: LockId, access: Access, current: ThreadId) -> Option<Deadlock> {
+        let start = self.held.get(&lock)?.blockers(access);
+        let mut stack: Vec<(ThreadId, Vec<ThreadId>)> = start
+            .into_iter()
+            .map(|thread| (thread, vec![current]))
+            .collect();
+        let mut visited = HashSet::new();
+
+        while let Some((thread, path)) = stack.pop() {
+            if thread == current {
+                return Some(Deadlock {
+                    lock,
+                    threads: path,
+                });
+            }
+            if !visited.insert(thread) {
+                continue;
+            }
+            if let Some((waited, waited_access)) = self.waiting.get(&thread) {
+                if let Some(holders) = self.held.get(waited) {
+                    for next in holders.blockers(*waited_access) {
+                        let mut path = path.clone();
+                        path.push(thread);
+                        stack.push((next, path));
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+fn graph() -> MutexGuard<'static, LockGraph> {
+    static GRAPH: OnceLock<Mutex<LockGraph>> = OnceLock::new();
+    GRAPH
+        .get_or_init(|| Mutex::new(LockGraph::default()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+struct LockShared<T> {
+    id: LockId,
+    /// Signalled when the lock is released; waits on the graph mutex
+    released: Condvar,
+    value: Mutex<T>,
+}
+
+impl<T> LockShared<T> {
+    fn new(value: T) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+        LockShared {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            released: Condvar::new(),
+            value: Mutex::new(value),
+        }
+    }
+
+    fn value(&self) -> MutexGuard<'_, T> {
+        self.value
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn acquire<T>(lock: &Arc<LockShared<T>>, access: Access) -> Result<LockGuard<T>, SyncError> {
+    let current = thread::current().id();
+    let mut graph = graph();
+    loop {
+        let holders = graph.held.entry(lock.id).or_default();
+        if holders.admits(access) {
+            match access {
+                Access::Read => holders.readers.push(current),
+                Access::Write => holders.writer = Some(current),
+            }
+            graph.waiting.remove(&current);
+            return Ok(LockGuard::new(lock, access, current));
+        }
+
+        if let Some(deadlock) = graph.find_cycle(lock.id, access, current) {
+            graph.waiting.remove(&current);
+            drop(graph);
+            run_hooks(&deadlock);
+            return Err(SyncError::Deadlock(deadlock));
+        }
+
+        graph.waiting.insert(current, (lock.id, access));
+        graph = lock
+            .released
+            .wait(graph)
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+}
+
+fn try_acquire<T>(lock: &Arc<LockShared<T>>, access: Access) -> Option<LockGuard<T>> {
+    let current = thread::current().id();
+    let mut graph = graph();
+    let holders = graph.held.entry(lock.id).or_default();
+    if !holders.admits(access) {
+        return None;
+    }
+    match access {
+        Access::Read => holders.readers.push(current),
+        Access::Write => holders.writer = Some(current),
+    }
+    Some(LockGuard::new(lock, access, current))
+}
+
+/// A mutual exclusion lock around a value
+///
+/// Clones refer to the same lock.
+pub struct ScriptMutex<T> {
+    shared: Arc<LockShared<T>>,
+}
+
+impl<T> ScriptMutex<T> {
+    /// Create an unlocked mutex
+    pub fn new(value: T) -> Self {
+        ScriptMutex {
+            shared: Arc::new(LockShared::new(value)),
+        }
+    }
+
+    /// The lock's id in the lock graph
+    pub fn id(&self) -> LockId {
+        self.shared.id
+    }
+
+    /// Wait for the lock
+    ///
+    /// Fails instead of waiting if that would deadlock, including when this
+    /// thread already holds the lock.
+    pub fn lock(&self) -> Result<LockGuard<T>, SyncError> {
+        acquire(&self.shared, Access::Write)
+    }
+
+    /// Take the lock if it is free
+    pub fn try_lock(&self) -> Option<LockGuard<T>> {
+        try_acquire(&self.shared, Access::Write)
+    }
+
+    /// Whether two handles refer to the same lock
+    pub fn same_lock(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+impl<T> Clone for ScriptMutex<T> {
+    fn clone(&self) -> Self {
+        ScriptMutex {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ScriptMutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptMutex")
+            .field("id", &self.shared.id)
+            .finish()
+    }
+}
+
+/// A reader-writer lock around a value
+///
+/// Any number of read guards or a single write guard may be held at once.
+/// Clones refer to the same lock.
+pub struct ScriptRwLock<T> {
+    shared: Arc<LockShared<T>>,
+}
+
+impl<T> ScriptRwLock<T> {
+    /// Create an unlocked reader-writer lock
+    pub fn new(value: T) -> Self {
+        ScriptRwLock {
+            shared: Arc::new(LockShared::new(value)),
+        }
+    }
+
+    /// The lock's id in the lock graph
+    pub fn id(&self) -> LockId {
+        self.shared.id
+    }
+
+    /// Wait for shared read access
+    pub fn read(&self) -> Result<LockGuard<T>, SyncError> {
+        acquire(&self.shared, Access::Read)
+    }
+
+    /// Wait for exclusive write access
+    ///
+    /// Fails if this thread holds a read guard on the same lock, since the
+    /// write could never be granted.
+    pub fn write(&self) -> Result<LockGuard<T>, SyncError> {
+        acquire(&self.shared, Access::Write)
+    }
+
+    /// Whether two handles refer to the same lock
+    pub fn same_lock(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+impl<T> Clone for ScriptRwLock<T> {
+    fn clone(&self) -> Self {
+        ScriptRwLock {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<T> fmt::Debug for ScriptRwLock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptRwLock")
+            .field("id", &self.shared.id)
+            .finish()
+    }
+}
+
+struct GuardInner<T> {
+    lock: Arc<LockShared<T>>,
+    access: Access,
+    owner: ThreadId,
+    released: AtomicBool,
+}
+
+impl<T> GuardInner<T> {
+    fn release(&self) {
+        if self.released.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let mut graph = graph();
+        if let Some(holders) = graph.held.get_mut(&self.lock.id) {
+            match self.access {
+                Access::Read => {
+                    if let Some(i) = holders.readers.iter().position(|t| *t == self.owner) {
+                        holders.readers.swap_remove(i);
+                    }
+                }
+                Access::Write => holders.writer = None,
+            }
+            if holders.writer.is_none() && holders.readers.is_empty() {
+                graph.held.remove(&self.lock.id);
+            }
+        }
+        self.lock.released.notify_all();
+    }
+}
+
+impl<T> Drop for GuardInner<T> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Access to a locked value
+///
+/// Clones share the same hold on the lock, which is released by `unlock`
+/// or when the last clone is dropped.
+pub struct LockGuard<T> {
+    inner: Arc<GuardInner<T>>,
+}
+
+impl<T> LockGuard<T> {
+    fn new(lock: &Arc<LockShared<T>>, access: Access, owner: ThreadId) -> Self {
+        LockGuard {
+            inner: Arc::new(GuardInner {
+                lock: Arc::clone(lock),
+                access,
+                owner,
+                released: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    fn check_held(&self) -> Result<(), SyncError> {
+        if self.inner.released.load(Ordering::SeqCst) {
+            Err(SyncError::Released)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The locked value
+    pub fn get(&self) -> Result<T, SyncError>
+    where
+        T: Clone,
+    {
+        self.check_held()?;
+        Ok(self.inner.lock.value().clone())
+    }
+
+    /// Replace the locked value
+    pub fn set(&self, value: T) -> Result<(), SyncError> {
+        self.check_held()?;
+        if self.inner.access == Access::Read {
+            return Err(SyncError::ReadOnly);
+        }
+        *self.inner.lock.value() = value;
+        Ok(())
+    }
+
+    /// Release the lock
+    pub fn unlock(&self) -> Result<(), SyncError> {
+        self.check_held()?;
+        self.inner.release();
+        Ok(())
+    }
+
+    /// Whether the guard still holds its lock
+    pub fn is_held(&self) -> bool {
+        !self.inner.released.load(Ordering::SeqCst)
+    }
+
+    /// Whether this is a read guard
+    pub fn is_read_only(&self) -> bool {
+        self.inner.access == Access::Read
+    }
+
+    /// Whether two guards share the same hold on a lock
+    pub fn same_guard(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl<T> Clone for LockGuard<T> {
+    fn clone(&self) -> Self {
+        LockGuard {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> fmt::Debug for LockGuard<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LockGuard")
+            .field("lock", &self.inner.lock.id)
+            .field("read_only", &self.is_read_only())
+            .field("held", &self.is_held())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Channel;
+
+    #[test]
+    fn test_mutex_guards() {
+        let mutex = ScriptMutex::new(1);
+        let guard = mutex.lock().unwrap();
+        assert!(mutex.try_lock().is_none());
+        guard.set(guard.get().unwrap() + 1).unwrap();
+        guard.unlock().unwrap();
+        assert_eq!(guard.get(), Err(SyncError::Released));
+
+        // Dropping the last clone of a guard releases the lock
+        let guard = mutex.lock().unwrap();
+        let copy = guard.clone();
+        drop(guard);
+        assert!(mutex.try_lock().is_none());
+        drop(copy);
+        assert_eq!(mutex.lock().unwrap().get().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_rwlock_readers_and_writers() {
+        let lock = ScriptRwLock::new("a".to_string());
+        let first = lock.read().unwrap();
+        let second = lock.read().unwrap();
+        assert_eq!(second.set("b".to_string()), Err(SyncError::ReadOnly));
+
+        // Upgrading while reading can never succeed
+        assert!(matches!(lock.write(), Err(SyncError::Deadlock(_))));
+        first.unlock().unwrap();
+        second.unlock().unwrap();
+
+        let writer = lock.write().unwrap();
+        writer.set("b".to_string()).unwrap();
+        writer.unlock().unwrap();
+        assert_eq!(lock.read().unwrap().get().unwrap(), "b");
+    }
+
+    #[test]
+    fn test_deadlock_is_detected() {
+        let a = ScriptMutex::new(());
+        let b = ScriptMutex::new(());
+        let metrics = Arc::new(SecurityMetrics::new());
+        report_deadlocks_to(Arc::clone(&metrics));
+
+        let relock = a.lock().unwrap();
+        assert!(matches!(a.lock(), Err(SyncError::Deadlock(d)) if d.threads.len() == 1));
+        drop(relock);
+
+        // This thread holds `a` and the other holds `b`; whichever asks for
+        // the second lock last closes the cycle and gets the error
+        let holding_a = a.lock().unwrap();
+        let ready = Channel::new(1);
+        let other = {
+            let (a, b, ready) = (a.clone(), b.clone(), ready.clone());
+            thread::spawn(move || {
+                let holding_b = b.lock().unwrap();
+                ready.send(()).unwrap();
+                let result = a.lock().map(|_| ());
+                drop(holding_b);
+                result
+            })
+        };
+        ready.recv();
+        let mine = b.lock().map(|_| ());
+        drop(holding_a);
+        let theirs = other.join().unwrap();
+
+        assert!(mine.is_err() != theirs.is_err());
+        assert!(
+            metrics
+                .async_race_conditions_detected
+                .load(Ordering::Relaxed)
+                >= 2
+        );
+    }
+}
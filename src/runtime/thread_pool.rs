@@ -0,0 +1,323 @@
+//! Thread pool backing Script threads
+//!
+//! `thread_spawn` and scoped threads run their closures on pooled OS
+//! threads. The pool starts empty and grows on demand, so every task that
+//! is accepted gets a thread of its own and tasks that wait on each other
+//! (for example through a channel) cannot deadlock. Idle workers are reused
+//! and exit after `keep_alive`.
+//!
+//! The number of tasks running or queued at once is capped by
+//! `ThreadPoolConfig::max_threads`, which comes from the `allow_threads` and
+//! `max_threads` fields of a `SecurityPolicy`. Spawning past the cap, or
+//! under a policy that denies threads, fails instead of waiting.
+
+use crate::runtime::channel::Channel;
+use crate::runtime::{Result, RuntimeError};
+use crate::security::SecurityPolicy;
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::thread;
+use std::time::Duration;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Limits and settings for a thread pool
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThreadPoolConfig {
+    /// Maximum number of tasks running or queued at once; 0 denies threads
+    pub max_threads: usize,
+    /// Stack size of each worker thread in bytes
+    pub stack_size: usize,
+    /// How long an idle worker waits for work before exiting
+    pub keep_alive: Duration,
+}
+
+impl ThreadPoolConfig {
+    /// The thread limits of a security policy
+    pub fn from_policy(policy: &SecurityPolicy) -> Self {
+        ThreadPoolConfig {
+            max_threads: if policy.allow_threads {
+                policy.max_threads
+            } else {
+                0
+            },
+            stack_size: 2 * 1024 * 1024, // 2MB
+            keep_alive: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Default for ThreadPoolConfig {
+    fn default() -> Self {
+        Self::from_policy(&SecurityPolicy::default())
+    }
+}
+
+struct PoolState {
+    config: ThreadPoolConfig,
+    queue: VecDeque<Job>,
+    /// Tasks accepted and not yet finished
+    active: usize,
+    /// Worker threads alive
+    workers: usize,
+    /// Workers waiting for a job
+    idle: usize,
+}
+
+struct PoolShared {
+    state: Mutex<PoolState>,
+    work: Condvar,
+}
+
+impl PoolShared {
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A growable pool of OS threads
+#[derive(Clone)]
+pub struct ThreadPool {
+    shared: Arc<PoolShared>,
+}
+
+impl ThreadPool {
+    /// Create an empty pool
+    pub fn new(config: ThreadPoolConfig) -> Self {
+        ThreadPool {
+            shared: Arc::new(PoolShared {
+                state: Mutex::new(PoolState {
+                    config,
+                    queue: VecDeque::new(),
+                    active: 0,
+                    workers: 0,
+                    idle: 0,
+                }),
+                work: Condvar::new(),
+            }),
+        }
+    }
+
+    /// The pool shared by Script threads
+    pub fn global() -> &'static ThreadPool {
+        static POOL: OnceLock<ThreadPool> = OnceLock::new();
+        POOL.get_or_init(|| ThreadPool::new(ThreadPoolConfig::default()))
+    }
+
+    /// Replace the pool's limits
+    ///
+    /// Tasks already accepted keep running; new limits apply to later spawns.
+    pub fn configure(&self, config: ThreadPoolConfig) {
+        self.shared.lock().config = config;
+    }
+
+    /// Apply the thread limits of a security policy
+    pub fn apply_policy(&self, policy: &SecurityPolicy) {
+        let mut state = self.shared.lock();
+        state.config.max_threads = ThreadPoolConfig::from_policy(policy).max_threads;
+    }
+
+    /// Current limits
+    pub fn config(&self) -> ThreadPoolConfig {
+        self.shared.lock().config.clone()
+    }
+
+    /// Tasks running or queued
+    pub fn active_tasks(&self) -> usize {
+        self.shared.lock().active
+    }
+
+    /// Worker threads alive, busy or idle
+    pub fn worker_count(&self) -> usize {
+        self.shared.lock().workers
+    }
+
+    /// Run `task` on a pool thread
+    ///
+    /// Fails if threads are denied or `max_threads` tasks are already
+    /// running or queued.
+    pub fn spawn<T, F>(&self, task: F) -> Result<JoinHandle<T>>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let result = Channel::new(1);
+        let sender = result.clone();
+        let shared = Arc::clone(&self.shared);
+        let job: Job = Box::new(move || {
+            let outcome = panic::catch_unwind(AssertUnwindSafe(task));
+            // The buffer holds exactly this one result, so this never waits
+            let _ = sender.send(outcome);
+            shared.lock().active -= 1;
+        });
+
+        let mut state = self.shared.lock();
+        if state.config.max_threads == 0 {
+            return Err(RuntimeError::InvalidOperation(
+                "Thread spawning is not allowed by the security policy".to_string(),
+            ));
+        }
+        if state.active >= state.config.max_threads {
+            return Err(RuntimeError::InvalidOperation(format!(
+                "Thread limit reached: {} threads are already running",
+                state.config.max_threads
+            )));
+        }
+
+        state.active += 1;
+        state.queue.push_back(job);
+        // Every queued job needs a worker, so start one unless enough are idle
+        if state.queue.len() > state.idle {
+            if let Err(error) = self.start_worker(&mut state) {
+                state.queue.pop_back();
+                state.active -= 1;
+                return Err(error);
+            }
+        } else {
+            self.shared.work.notify_one();
+        }
+
+        Ok(JoinHandle { result })
+    }
+
+    fn start_worker(&self, state: &mut PoolState) -> Result<()> {
+        let shared = Arc::clone(&self.shared);
+        thread::Builder::new()
+            .name(format!("script-worker-{}", state.workers))
+            .stack_size(state.config.stack_size)
+            .spawn(move || worker_loop(shared))
+            .map_err(|e| {
+                RuntimeError::InvalidOperation(format!("Failed to start thread: {}", e))
+            })?;
+        state.workers += 1;
+        Ok(())
+    }
+}
+
+fn worker_loop(shared: Arc<PoolShared>) {
+    let mut state = shared.lock();
+    loop {
+        if let Some(job) = state.queue.pop_front() {
+            drop(state);
+            job();
+            state = shared.lock();
+            continue;
+        }
+
+        state.idle += 1;
+        let keep_alive = state.config.keep_alive;
+        let (guard, timeout) = shared
+            .work
+            .wait_timeout(state, keep_alive)
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        state = guard;
+        state.idle -= 1;
+
+        if timeout.timed_out() && state.queue.is_empty() {
+            state.workers -= 1;
+            return;
+        }
+    }
+}
+
+/// Handle to a task running on a `ThreadPool`
+pub struct JoinHandle<T> {
+    result: Channel<thread::Result<T>>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Whether the task has finished
+    pub fn is_finished(&self) -> bool {
+        !self.result.is_empty()
+    }
+
+    /// Wait for the task to finish and return its result
+    ///
+    /// A panic in the task is returned as `RuntimeError::Panic`.
+    pub fn join(self) -> Result<T> {
+        match self.result.recv() {
+            Some(Ok(value)) => Ok(value),
+            Some(Err(payload)) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .map(|s| s.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned())
+                    .unwrap_or_else(|| "thread panicked".to_string());
+                Err(RuntimeError::Panic(message))
+            }
+            None => Err(RuntimeError::InvalidOperation(
+                "Thread finished without a result".to_string(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(max_threads: usize) -> ThreadPool {
+        ThreadPool::new(ThreadPoolConfig {
+            max_threads,
+            ..ThreadPoolConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_spawn_and_join() {
+        let pool = pool(4);
+        let handles: Vec<_> = (0..4)
+            .map(|i| pool.spawn(move || i * 10).unwrap())
+            .collect();
+        let results: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(results, vec![0, 10, 20, 30]);
+
+        let panicked = pool.spawn(|| -> i32 { panic!("boom") }).unwrap();
+        assert!(matches!(panicked.join(), Err(RuntimeError::Panic(message)) if message == "boom"));
+        assert_eq!(pool.active_tasks(), 0);
+    }
+
+    #[test]
+    fn test_thread_limits() {
+        let denied = ThreadPool::new(ThreadPoolConfig::from_policy(&SecurityPolicy::strict()));
+        assert!(denied.spawn(|| ()).is_err());
+
+        let pool = pool(1);
+        let gate = Channel::new(1);
+        let waiting = {
+            let gate = gate.clone();
+            pool.spawn(move || gate.recv()).unwrap()
+        };
+        assert!(pool.spawn(|| ()).is_err());
+
+        gate.send(()).unwrap();
+        assert_eq!(waiting.join().unwrap(), Some(()));
+        assert!(pool.spawn(|| ()).unwrap().join().is_ok());
+    }
+
+    #[test]
+    fn test_dependent_tasks_do_not_deadlock() {
+        let pool = pool(8);
+        let channel = Channel::new(1);
+        let consumer = {
+            let channel = channel.clone();
+            pool.spawn(move || std::iter::from_fn(|| channel.recv()).sum::<i32>())
+                .unwrap()
+        };
+        let producer = pool
+            .spawn(move || {
+                for i in 1..=10 {
+                    channel.send(i).unwrap();
+                }
+                channel.close();
+            })
+            .unwrap();
+
+        producer.join().unwrap();
+        assert_eq!(consumer.join().unwrap(), 55);
+        assert!(pool.worker_count() >= 2);
+    }
+}
@@ -6,7 +6,7 @@
 use crate::error::{Error, ErrorKind};
 use crate::runtime::{ScriptRc, Value};
 use crate::stdlib::{
-    ScriptHashMap, ScriptOption, ScriptResult, ScriptString, ScriptValue, ScriptVec,
+    ScriptEnum, ScriptHashMap, ScriptOption, ScriptResult, ScriptString, ScriptValue, ScriptVec,
 };
 
 /// Convert a runtime Value to a stdlib ScriptValue
@@ -18,17 +18,13 @@ pub fn value_to_script_value(value: &Value) -> Result<ScriptValue, Error> {
 
         Value::I32(i) => Ok(ScriptValue::I32(*i)),
 
-        Value::I64(_) => {
-            // ScriptValue doesn't have I64, convert to I32 with bounds check
-            return Err(Error::new(
-                ErrorKind::TypeError,
-                "Cannot convert i64 to ScriptValue (no i64 support)",
-            ));
-        }
+        Value::I64(i) => Ok(ScriptValue::I64(*i)),
 
         Value::F32(f) => Ok(ScriptValue::F32(*f)),
 
-        Value::F64(f) | Value::Number(f) => {
+        Value::F64(f) => Ok(ScriptValue::F64(*f)),
+
+        Value::Number(f) => {
             // Convert to F32 with potential precision loss
             Ok(ScriptValue::F32(*f as f32))
         }
@@ -123,11 +119,16 @@ pub fn value_to_script_value(value: &Value) -> Result<ScriptValue, Error> {
                 }
 
                 _ => {
-                    // Other enum types not supported in ScriptValue
-                    Err(Error::new(
-                        ErrorKind::TypeError,
-                        format!("Cannot convert enum {} to ScriptValue", type_name),
-                    ))
+                    // Other built-in enums, such as ErrorKind
+                    let data = match data {
+                        Some(inner) => Some(value_to_script_value(inner)?),
+                        None => None,
+                    };
+                    Ok(ScriptValue::Enum(ScriptRc::new(ScriptEnum {
+                        type_name: type_name.clone(),
+                        variant: variant.clone(),
+                        data,
+                    })))
                 }
             }
         }
@@ -159,8 +160,21 @@ pub fn script_value_to_value(script_value: &ScriptValue) -> Value {
 
         ScriptValue::I32(i) => Value::I32(*i),
 
+        ScriptValue::I64(i) => Value::I64(*i),
+
+        ScriptValue::U32(u) => Value::I64(*u as i64),
+
+        // The runtime has no unsigned 64-bit value; keep the magnitude as f64
+        // when it does not fit in an i64
+        ScriptValue::U64(u) => match i64::try_from(*u) {
+            Ok(i) => Value::I64(i),
+            Err(_) => Value::F64(*u as f64),
+        },
+
         ScriptValue::F32(f) => Value::F32(*f),
 
+        ScriptValue::F64(f) => Value::F64(*f),
+
         ScriptValue::String(s) => Value::String(s.as_str().to_string()),
 
         ScriptValue::Array(vec) => {
@@ -207,6 +221,15 @@ pub fn script_value_to_value(script_value: &ScriptValue) -> Value {
             },
         },
 
+        ScriptValue::Enum(value) => Value::Enum {
+            type_name: value.type_name.clone(),
+            variant: value.variant.clone(),
+            data: value
+                .data
+                .as_ref()
+                .map(|inner| ScriptRc::new(script_value_to_value(inner))),
+        },
+
         ScriptValue::Object(obj) => {
             // Convert object fields to Value::Object
             let mut map = std::collections::HashMap::new();
@@ -233,6 +256,27 @@ pub fn script_value_to_value(script_value: &ScriptValue) -> Value {
             // Convert to null for now
             Value::Null
         }
+
+        ScriptValue::Weak(_) => {
+            // Weak references aren't directly convertible to runtime Value
+            // Convert to null for now
+            Value::Null
+        }
+
+        ScriptValue::Channel(_) => {
+            // Channels aren't directly convertible to runtime Value
+            // Convert to null for now
+            Value::Null
+        }
+
+        ScriptValue::Mutex(_)
+        | ScriptValue::RwLock(_)
+        | ScriptValue::LockGuard(_)
+        | ScriptValue::AtomicI32(_) => {
+            // Locks and atomics aren't directly convertible to runtime Value
+            // Convert to null for now
+            Value::Null
+        }
     }
 }
 
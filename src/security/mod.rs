@@ -101,6 +101,10 @@ pub struct SecurityPolicy {
     pub allow_network: bool,
     /// Allow process spawning
     pub allow_process_spawn: bool,
+    /// Allow spawning OS threads
+    pub allow_threads: bool,
+    /// Maximum number of threads running or queued at once
+    pub max_threads: usize,
     /// Allow FFI calls
     pub allow_ffi: bool,
     /// Maximum memory allocation
@@ -116,6 +120,8 @@ impl SecurityPolicy {
             allow_file_system: true,
             allow_network: true,
             allow_process_spawn: true,
+            allow_threads: true,
+            max_threads: usize::MAX,
             allow_ffi: true,
             max_memory: usize::MAX,
             max_cpu_time: u64::MAX,
@@ -128,6 +134,8 @@ impl SecurityPolicy {
             allow_file_system: false,
             allow_network: false,
             allow_process_spawn: false,
+            allow_threads: false,
+            max_threads: 0,
             allow_ffi: false,
             max_memory: 10_000_000, // 10MB
             max_cpu_time: 5_000,    // 5 seconds
@@ -140,6 +148,8 @@ impl SecurityPolicy {
             allow_file_system: false,
             allow_network: false,
             allow_process_spawn: false,
+            allow_threads: false,
+            max_threads: 0,
             allow_ffi: false,
             max_memory: 1_000_000, // 1MB
             max_cpu_time: 1_000,   // 1 second
@@ -153,6 +163,8 @@ impl Default for SecurityPolicy {
             allow_file_system: true,
             allow_network: true,
             allow_process_spawn: false,
+            allow_threads: true,
+            max_threads: 64,
             allow_ffi: false,
             max_memory: 100_000_000, // 100MB
             max_cpu_time: 30_000,    // 30 seconds
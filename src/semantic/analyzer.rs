@@ -1,29 +1,111 @@
-use crate::error::ErrorKind;
+use crate::edition::{keyword_since, Edition};
+use crate::error::{ErrorKind, Fix};
 use crate::inference::{type_ann_to_type, InferenceContext};
 use crate::parser::{
 <<<<<<< HEAD
-    BinaryOp, Block, ExportKind, Expr, ExprKind, GenericParams, ImportSpecifier, Literal, Param,
-    Program, Stmt, StmtKind, TraitBound, TypeAnn, UnaryOp,
+    BinaryOp, Block, ExportKind, Expr, ExprKind, GenericParam, GenericParams, ImportSpecifier,
+    Literal, Param, Program, Stmt, StmtKind, TraitBound, TraitDecl, TraitMethod, TypeAnn, UnaryOp,
 =======
     BinaryOp, Block, ExportKind, Expr, ExprKind, ImportSpecifier, Literal, Param, Program, Stmt,
-    StmtKind, TypeAnn, TypeKind, UnaryOp, ImplBlock, Method, GenericParams,
+    StmtKind, TypeAnn, TypeKind, UnaryOp, ImplBlock, Method, GenericParam, GenericParams,
+    TraitBound, TraitDecl, TraitMethod,
 >>>>>>> 289b5f6 (feat: Complete generic system implementation with full compilation pipeline)
 };
-use crate::source::Span;
+use crate::source::{SourceLocation, Span};
+use crate::types::conversion::{cast_result_type, suffix_type};
+use crate::types::generics::BuiltinTrait;
 use crate::types::Type;
 use crate::Result;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
 use super::capture_analysis::{CaptureAnalyzer, CaptureInfo};
-use super::error::{SemanticError, SemanticErrorKind};
+use super::error::{SemanticError, SemanticErrorKind, SemanticWarning, SemanticWarningKind};
 use super::memory_safety::{MemorySafetyContext, MemorySafetyViolation};
 use super::module_loader_integration::ModuleLoaderIntegration;
 use super::symbol::{
-    EnumInfo, EnumVariantInfo, EnumVariantType, FunctionSignature, Symbol, SymbolKind,
+    EnumInfo, EnumVariantInfo, EnumVariantType, FunctionSignature, Stability, Symbol, SymbolKind,
 };
 use super::symbol_table::SymbolTable;
 
+/// The type an impl block for `type_name` applies to
+fn named_type(type_name: &str) -> Type {
+    type_ann_to_type(&TypeAnn {
+        kind: TypeKind::Named(type_name.to_string()),
+        span: Span::dummy(),
+    })
+}
+
+/// Replace `Self` in a method signature type with the receiver's type
+fn replace_self(ty: &Type, self_type: &Type) -> Type {
+    match ty {
+        Type::Named(name) | Type::TypeParam(name) if name == "Self" => self_type.clone(),
+        Type::Array(elem) => Type::Array(Box::new(replace_self(elem, self_type))),
+        Type::Option(inner) => Type::Option(Box::new(replace_self(inner, self_type))),
+        Type::Generic { name, args } => Type::Generic {
+            name: name.clone(),
+            args: args.iter().map(|arg| replace_self(arg, self_type)).collect(),
+        },
+        other => other.clone(),
+    }
+}
+
+/// Add the type parameters `ty` mentions to `names`, in order of appearance
+fn collect_type_params(ty: &Type, names: &mut Vec<String>) {
+    match ty {
+        Type::TypeParam(name) => {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        Type::Array(inner) | Type::Option(inner) | Type::Future(inner) => {
+            collect_type_params(inner, names)
+        }
+        Type::Generic { args, .. } | Type::Tuple(args) => {
+            for arg in args {
+                collect_type_params(arg, names);
+            }
+        }
+        Type::Function { params, ret } => {
+            for param in params {
+                collect_type_params(param, names);
+            }
+            collect_type_params(ret, names);
+        }
+        _ => {}
+    }
+}
+
+/// Fix inserting an arm with an empty body for each missing pattern just
+/// before the closing brace of the match at `span`
+///
+/// In a match spread over several lines the arms go on lines of their own,
+/// indented one level past the brace, which is assumed to start its line.
+fn missing_arms_fix(span: Span, missing_patterns: &[String]) -> Fix {
+    let brace = SourceLocation::new(
+        span.end.line,
+        span.end.column.saturating_sub(1),
+        span.end.byte_offset.saturating_sub(1),
+    );
+    let text = if span.start.line == span.end.line {
+        missing_patterns
+            .iter()
+            .map(|pattern| format!("{} => {{}}, ", pattern))
+            .collect::<String>()
+    } else {
+        let brace_indent = " ".repeat(brace.column.saturating_sub(1));
+        missing_patterns
+            .iter()
+            .map(|pattern| format!("    {} => {{}},\n{}", pattern, brace_indent))
+            .collect::<String>()
+    };
+    Fix::Edit {
+        title: "Add missing match arms".to_string(),
+        span: Span::single(brace),
+        text,
+    }
+}
+
 /// Convert a Type to TypeAnn for interface compatibility
 fn type_to_type_ann(ty: &Type) -> TypeAnn {
     // Create a dummy span for the conversion
@@ -34,7 +116,11 @@ fn type_to_type_ann(ty: &Type) -> TypeAnn {
 
     let kind = match ty {
         Type::I32 => TypeKind::Named("i32".to_string()),
+        Type::I64 => TypeKind::Named("i64".to_string()),
+        Type::U32 => TypeKind::Named("u32".to_string()),
+        Type::U64 => TypeKind::Named("u64".to_string()),
         Type::F32 => TypeKind::Named("f32".to_string()),
+        Type::F64 => TypeKind::Named("f64".to_string()),
         Type::Bool => TypeKind::Named("bool".to_string()),
         Type::String => TypeKind::Named("string".to_string()),
         Type::Unknown => TypeKind::Named("unknown".to_string()),
@@ -131,12 +217,16 @@ pub struct SemanticAnalyzer {
     context_stack: Vec<AnalysisContext>,
     /// Collected errors
     errors: Vec<SemanticError>,
+    /// Collected warnings, such as uses of deprecated items
+    warnings: Vec<SemanticWarning>,
     /// Whether memory safety analysis is enabled
     memory_safety_enabled: bool,
     /// Impl blocks for method resolution
     impl_blocks: Vec<ImplBlock>,
     /// Method resolution cache
     method_cache: HashMap<(String, String), Vec<Method>>, // (type_name, method_name) -> methods
+    /// Trait declarations by name
+    traits: HashMap<String, TraitDecl>,
     /// Generic instantiations for monomorphization
     generic_instantiations: Vec<GenericInstantiation>,
     /// Type information for expressions (maps expression ID to type)
@@ -145,6 +235,8 @@ pub struct SemanticAnalyzer {
     module_loader: ModuleLoaderIntegration,
     /// Capture information for closures (maps closure expression ID to captures)
     closure_captures: HashMap<usize, Vec<CaptureInfo>>,
+    /// Language edition the program is written for
+    edition: Edition,
 }
 
 impl SemanticAnalyzer {
@@ -156,13 +248,16 @@ impl SemanticAnalyzer {
             memory_safety_ctx: MemorySafetyContext::new(),
             context_stack: vec![AnalysisContext::new()],
             errors: Vec::new(),
+            warnings: Vec::new(),
             memory_safety_enabled: true,
             impl_blocks: Vec::new(),
             method_cache: HashMap::new(),
+            traits: HashMap::new(),
             generic_instantiations: Vec::new(),
             type_info: HashMap::new(),
             module_loader: ModuleLoaderIntegration::new(),
             closure_captures: HashMap::new(),
+            edition: Edition::default(),
         }
     }
 
@@ -174,13 +269,16 @@ impl SemanticAnalyzer {
             memory_safety_ctx: MemorySafetyContext::new(),
             context_stack: vec![AnalysisContext::new()],
             errors: Vec::new(),
+            warnings: Vec::new(),
             memory_safety_enabled: true,
             impl_blocks: Vec::new(),
             method_cache: HashMap::new(),
+            traits: HashMap::new(),
             generic_instantiations: Vec::new(),
             type_info: HashMap::new(),
             module_loader: ModuleLoaderIntegration::new(),
             closure_captures: HashMap::new(),
+            edition: Edition::default(),
         }
     }
 
@@ -201,6 +299,18 @@ impl SemanticAnalyzer {
         self.module_loader.add_search_path(path);
     }
 
+    /// Read imported modules from `sources`, keyed by canonical path, where
+    /// they have an entry instead of from disk
+    pub fn set_source_overrides(&mut self, sources: std::sync::Arc<HashMap<PathBuf, String>>) {
+        self.module_loader.set_source_overrides(sources);
+    }
+
+    /// Set the language edition, used to warn about names that later
+    /// editions reserve as keywords
+    pub fn set_edition(&mut self, edition: Edition) {
+        self.edition = edition;
+    }
+
     /// Enable or disable memory safety analysis
     pub fn set_memory_safety_enabled(&mut self, enabled: bool) {
         self.memory_safety_enabled = enabled;
@@ -329,8 +439,11 @@ impl SemanticAnalyzer {
                         }
                     }
                 }
-                super::symbol::SymbolKind::Parameter | super::symbol::SymbolKind::BuiltIn => {
-                    // Skip parameters and built-ins as they shouldn't be imported
+                super::symbol::SymbolKind::Parameter
+                | super::symbol::SymbolKind::BuiltIn
+                | super::symbol::SymbolKind::Constant => {
+                    // Skip parameters, built-ins and stdlib constants as they
+                    // shouldn't be imported
                     continue;
                 }
                 _ => {
@@ -397,7 +510,10 @@ impl SemanticAnalyzer {
         let filter = |symbol: &Symbol| {
             // Consider symbols "public" if they're not parameters or built-ins
             // In a real implementation, this would check actual visibility modifiers
-            !matches!(symbol.kind, SymbolKind::Parameter | SymbolKind::BuiltIn)
+            !matches!(
+                symbol.kind,
+                SymbolKind::Parameter | SymbolKind::BuiltIn | SymbolKind::Constant
+            )
         };
         self.import_symbols_from_with_filter(source_table, Some(&filter), module_name)
     }
@@ -455,6 +571,23 @@ impl SemanticAnalyzer {
         self.errors.push(error);
     }
 
+    /// Add an error for `name` at `span` not being defined, with fixes that
+    /// correct it to a similar name in scope or import it
+    fn add_undefined_name_error(&mut self, error: SemanticError, name: &str, span: Span) {
+        let similar = self.symbol_table.similar_names(name);
+        let mut error = error.with_did_you_mean(&similar);
+        for name in similar {
+            error = error.with_fix(Fix::Edit {
+                title: format!("Change to '{}'", name),
+                span,
+                text: name,
+            });
+        }
+        self.add_error(error.with_fix(Fix::Import {
+            name: name.to_string(),
+        }));
+    }
+
     /// Check if a type is the boolean type
     fn is_bool_type(&self, ty: &Type) -> bool {
         matches!(ty, Type::Bool)
@@ -504,6 +637,10 @@ impl SemanticAnalyzer {
         // Add built-in functions to the global scope
         self.add_builtins()?;
 
+        // Traits and their impls may be declared after the generic code
+        // whose bounds refer to them
+        self.collect_traits(program);
+
         // Analyze all statements
         for stmt in &program.statements {
             self.analyze_stmt(stmt)?;
@@ -533,6 +670,27 @@ impl SemanticAnalyzer {
         Ok(())
     }
 
+    /// Register the program's trait declarations and `impl Trait for Type`
+    /// blocks ahead of analysis
+    fn collect_traits(&mut self, program: &Program) {
+        for stmt in &program.statements {
+            match &stmt.kind {
+                StmtKind::Trait(trait_decl) => {
+                    self.traits
+                        .entry(trait_decl.name.clone())
+                        .or_insert_with(|| trait_decl.clone());
+                }
+                StmtKind::Impl(impl_block) => {
+                    if let Some(trait_name) = &impl_block.trait_name {
+                        self.inference_ctx
+                            .register_trait_impl(named_type(&impl_block.type_name), trait_name);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
     /// Finalize memory safety analysis and collect any remaining violations
     fn finalize_memory_safety_analysis(&mut self) {
         // Collect all remaining memory safety violations
@@ -625,6 +783,25 @@ impl SemanticAnalyzer {
                 .into_error()
             })?;
 
+        // Standard library constants such as PI and MAX_I32
+        for name in crate::stdlib::constants::CONSTANT_NAMES {
+            if let Some(ty) = crate::stdlib::constants::constant_type(name) {
+                self.symbol_table
+                    .define_constant(
+                        name.to_string(),
+                        ty,
+                        crate::source::Span::single(crate::source::SourceLocation::initial()),
+                    )
+                    .map_err(|e| {
+                        SemanticError::new(
+                            SemanticErrorKind::DuplicateVariable(e),
+                            crate::source::Span::single(crate::source::SourceLocation::initial()),
+                        )
+                        .into_error()
+                    })?;
+            }
+        }
+
         // Add built-in Option<T> enum
         let option_variants = vec![
             EnumVariantInfo {
@@ -714,12 +891,100 @@ impl SemanticAnalyzer {
                 .into_error()
             })?;
 
+        // Add built-in ErrorKind enum naming the category of a stdlib error
+        let error_kind_info = EnumInfo {
+            generic_params: None,
+            variants: crate::stdlib::error::ErrorKind::ALL
+                .iter()
+                .map(|kind| EnumVariantInfo {
+                    name: kind.name().to_string(),
+                    variant_type: EnumVariantType::Unit,
+                })
+                .collect(),
+            where_clause: None,
+        };
+
+        self.symbol_table
+            .define_enum(
+                crate::stdlib::error::ErrorKind::TYPE_NAME.to_string(),
+                error_kind_info,
+                crate::source::Span::single(crate::source::SourceLocation::initial()),
+            )
+            .map_err(|e| {
+                SemanticError::new(
+                    SemanticErrorKind::DuplicateType(e),
+                    crate::source::Span::single(crate::source::SourceLocation::initial()),
+                )
+                .into_error()
+            })?;
+
+        // Add built-in Error struct returned by io and network functions
+        let error_kind_type = Type::Named(crate::stdlib::error::ErrorKind::TYPE_NAME.to_string());
+        let error_info = crate::semantic::symbol::StructInfo {
+            generic_params: None,
+            fields: vec![
+                ("kind".to_string(), error_kind_type),
+                ("message".to_string(), Type::String),
+                ("code".to_string(), Type::Option(Box::new(Type::I32))),
+                (
+                    "source".to_string(),
+                    Type::Option(Box::new(Type::Named("Error".to_string()))),
+                ),
+            ],
+            where_clause: None,
+        };
+
+        self.symbol_table
+            .define_struct(
+                "Error".to_string(),
+                error_info,
+                crate::source::Span::single(crate::source::SourceLocation::initial()),
+            )
+            .map_err(|e| {
+                SemanticError::new(
+                    SemanticErrorKind::DuplicateType(e),
+                    crate::source::Span::single(crate::source::SourceLocation::initial()),
+                )
+                .into_error()
+            })?;
+
         Ok(())
     }
 
     /// Convert a stdlib type signature to a semantic analyzer function signature
-    fn convert_stdlib_signature(&self, _stdlib_type: &Type) -> FunctionSignature {
-        // For now, create a simple signature for all stdlib functions
+    fn convert_stdlib_signature(&self, stdlib_type: &Type) -> FunctionSignature {
+        // Functions registered with type parameters, such as
+        // `weak_ref<T>(T) -> Weak<T>`, are checked as generic functions
+        if let Type::Function { params, ret } = stdlib_type {
+            let mut type_params = Vec::new();
+            collect_type_params(stdlib_type, &mut type_params);
+            if !type_params.is_empty() {
+                let span = Span::single(crate::source::SourceLocation::initial());
+                return FunctionSignature {
+                    generic_params: Some(GenericParams {
+                        params: type_params
+                            .into_iter()
+                            .map(|name| GenericParam {
+                                name,
+                                bounds: vec![],
+                                span,
+                            })
+                            .collect(),
+                        span,
+                    }),
+                    params: params
+                        .iter()
+                        .enumerate()
+                        .map(|(i, ty)| (format!("arg{}", i), ty.clone()))
+                        .collect(),
+                    return_type: (**ret).clone(),
+                    is_const: false,
+                    is_async: false,
+                };
+            }
+        }
+
+        // For now, create a simple signature for the other stdlib functions
         // In a complete implementation, this would parse the actual Type to extract
         // function parameters and return type
         FunctionSignature {
@@ -1117,6 +1382,22 @@ impl SemanticAnalyzer {
             } => {
                 self.analyze_for(variable, iterable, body)?;
             }
+            StmtKind::Break => {
+                if !self.current_context().in_loop {
+                    self.add_error(SemanticError::new(
+                        SemanticErrorKind::BreakOutsideLoop,
+                        stmt.span,
+                    ));
+                }
+            }
+            StmtKind::Continue => {
+                if !self.current_context().in_loop {
+                    self.add_error(SemanticError::new(
+                        SemanticErrorKind::ContinueOutsideLoop,
+                        stmt.span,
+                    ));
+                }
+            }
             StmtKind::Struct {
                 name,
                 generic_params,
@@ -1150,10 +1431,94 @@ impl SemanticAnalyzer {
             StmtKind::Impl(impl_block) => {
                 self.analyze_impl_block(impl_block)?;
             }
+            StmtKind::Trait(trait_decl) => {
+                self.analyze_trait(trait_decl)?;
+            }
         }
+
+        self.check_future_keyword(stmt);
+        self.record_stability(stmt);
         Ok(())
     }
 
+    /// Warn if a statement defines a name that a later edition reserves
+    ///
+    /// The lexer rejects such names once the edition is raised, so the
+    /// warning points at `script fix --edition` before that happens.
+    fn check_future_keyword(&mut self, stmt: &Stmt) {
+        let names: Vec<&String> = match &stmt.kind {
+            StmtKind::Let { name, .. }
+            | StmtKind::Struct { name, .. }
+            | StmtKind::Enum { name, .. } => vec![name],
+            StmtKind::Function { name, params, .. } => std::iter::once(name)
+                .chain(params.iter().map(|param| &param.name))
+                .collect(),
+            _ => return,
+        };
+        for name in names {
+            if let Some(edition) = keyword_since(name).filter(|since| *since > self.edition) {
+                self.warnings.push(SemanticWarning::new(
+                    SemanticWarningKind::FutureKeyword {
+                        name: name.clone(),
+                        edition,
+                    },
+                    stmt.span,
+                ));
+            }
+        }
+    }
+
+    /// Attach `@deprecated` / `@unstable` to the symbol a statement defines
+    ///
+    /// The stability is stored on the symbol, so it travels with it when the
+    /// module is imported and uses in downstream modules are reported too.
+    fn record_stability(&mut self, stmt: &Stmt) {
+        let Some(stability) = Stability::from_attributes(&stmt.attributes) else {
+            return;
+        };
+        let name = match &stmt.kind {
+            StmtKind::Function { name, .. }
+            | StmtKind::Let { name, .. }
+            | StmtKind::Struct { name, .. }
+            | StmtKind::Enum { name, .. } => name,
+            StmtKind::Export { export } => match export {
+                ExportKind::Function { name, .. } | ExportKind::Variable { name, .. } => name,
+                ExportKind::Declaration(decl) => match &decl.kind {
+                    StmtKind::Function { name, .. }
+                    | StmtKind::Let { name, .. }
+                    | StmtKind::Struct { name, .. }
+                    | StmtKind::Enum { name, .. } => name,
+                    _ => return,
+                },
+                _ => return,
+            },
+            _ => return,
+        };
+
+        if let Some(id) = self.symbol_table.lookup(name).map(|symbol| symbol.id) {
+            if let Some(symbol) = self.symbol_table.get_symbol_mut(id) {
+                symbol.stability = Some(stability);
+            }
+        }
+    }
+
+    /// Warn if a used symbol is deprecated or unstable
+    fn check_stability(&mut self, name: &str, stability: Option<Stability>, span: Span) {
+        let kind = match stability {
+            Some(Stability::Deprecated { since, note }) => SemanticWarningKind::DeprecatedUsage {
+                name: name.to_string(),
+                since,
+                note,
+            },
+            Some(Stability::Unstable { note }) => SemanticWarningKind::UnstableUsage {
+                name: name.to_string(),
+                note,
+            },
+            None => return,
+        };
+        self.warnings.push(SemanticWarning::new(kind, span));
+    }
+
     /// Analyze a let statement
     fn analyze_let(
         &mut self,
@@ -1368,6 +1733,9 @@ impl SemanticAnalyzer {
                 self.inference_ctx.define_type_param(&generic_param.name);
 
                 // Add trait bounds to inference context
+                for bound in &generic_param.bounds {
+                    self.check_trait_defined(&bound.trait_name, bound.span);
+                }
                 if !generic_param.bounds.is_empty() {
                     let bound_names: Vec<String> = generic_param
                         .bounds
@@ -1581,6 +1949,9 @@ impl SemanticAnalyzer {
                 self.inference_ctx.define_type_param(&generic_param.name);
 
                 // Add trait bounds to inference context
+                for bound in &generic_param.bounds {
+                    self.check_trait_defined(&bound.trait_name, bound.span);
+                }
                 if !generic_param.bounds.is_empty() {
                     let bound_names: Vec<String> = generic_param
                         .bounds
@@ -1744,8 +2115,8 @@ impl SemanticAnalyzer {
         // Analyze condition
         self.analyze_expr(condition)?;
 
-        // Enter loop context
-        self.current_context_mut().in_loop = true;
+        // Enter loop context, restoring the enclosing loop's afterwards
+        let was_in_loop = std::mem::replace(&mut self.current_context_mut().in_loop, true);
 
         // Analyze body in new scope
         self.symbol_table.enter_scope();
@@ -1755,7 +2126,7 @@ impl SemanticAnalyzer {
         self.inference_ctx.pop_scope();
 
         // Exit loop context
-        self.current_context_mut().in_loop = false;
+        self.current_context_mut().in_loop = was_in_loop;
 
         Ok(())
     }
@@ -1763,15 +2134,14 @@ impl SemanticAnalyzer {
     /// Analyze a for loop
     fn analyze_for(&mut self, variable: &str, iterable: &Expr, body: &Block) -> Result<()> {
         // Analyze iterable
-        self.analyze_expr(iterable)?;
+        let iterable_type = self.analyze_expr(iterable)?;
+        let loop_var_type = self.iteration_item_type(&iterable_type, iterable.span);
 
         // Enter loop scope
         self.symbol_table.enter_scope();
         self.inference_ctx.push_scope();
 
         // Define loop variable
-        // TODO: Infer type from iterable
-        let loop_var_type = Type::Unknown;
         match self.symbol_table.define_variable(
             variable.to_string(),
             loop_var_type,
@@ -1788,14 +2158,14 @@ impl SemanticAnalyzer {
             }
         }
 
-        // Enter loop context
-        self.current_context_mut().in_loop = true;
+        // Enter loop context, restoring the enclosing loop's afterwards
+        let was_in_loop = std::mem::replace(&mut self.current_context_mut().in_loop, true);
 
         // Analyze body
         self.analyze_block(body)?;
 
         // Exit loop context
-        self.current_context_mut().in_loop = false;
+        self.current_context_mut().in_loop = was_in_loop;
 
         // Exit loop scope
         self.symbol_table.exit_scope();
@@ -1804,6 +2174,75 @@ impl SemanticAnalyzer {
         Ok(())
     }
 
+    /// Determine the type a for-loop binds for each item of an iterable
+    ///
+    /// Arrays yield their elements and `i32` counts from zero. Any other type
+    /// is iterable through the iterator protocol: stdlib `Iterator` values, or
+    /// a type whose impl block defines `fn next(self) -> Option<T>`.
+    fn iteration_item_type(&mut self, iterable_type: &Type, span: Span) -> Type {
+        let type_name = match iterable_type {
+            Type::Array(element_type) => return element_type.as_ref().clone(),
+            Type::I32 => return Type::I32,
+            Type::Unknown | Type::TypeVar(_) | Type::TypeParam(_) => return Type::Unknown,
+            Type::Generic { name, args } if name == "Iterator" => {
+                return args.first().cloned().unwrap_or(Type::Unknown)
+            }
+            Type::Named(name) if name == "Iterator" => return Type::Unknown,
+            Type::Named(name) | Type::Struct { name, .. } | Type::Generic { name, .. } => {
+                name.clone()
+            }
+            _ => {
+                self.add_error(
+                    SemanticError::invalid_operation("for-in", iterable_type.clone(), span)
+                        .with_note(format!("type {} is not iterable", iterable_type)),
+                );
+                return Type::Unknown;
+            }
+        };
+
+        let next_method = self
+            .impl_blocks
+            .iter()
+            .filter(|impl_block| impl_block.type_name == type_name)
+            .flat_map(|impl_block| impl_block.methods.iter())
+            .find(|method| method.name == "next")
+            .cloned();
+        let Some(next_method) = next_method else {
+            self.add_error(
+                SemanticError::invalid_operation("for-in", iterable_type.clone(), span)
+                    .with_note(format!("type {} is not iterable", iterable_type))
+                    .with_help(format!(
+                        "implement `fn next(self) -> Option<T>` for {} to iterate over it",
+                        type_name
+                    )),
+            );
+            return Type::Unknown;
+        };
+
+        let ret_type = next_method.ret_type.as_ref().map(type_ann_to_type);
+        match ret_type {
+            Some(Type::Option(item_type)) => *item_type,
+            Some(Type::Generic { name, args }) if name == "Option" => {
+                args.into_iter().next().unwrap_or(Type::Unknown)
+            }
+            other => {
+                let found = other.unwrap_or(Type::Unknown);
+                self.add_error(
+                    SemanticError::type_mismatch(
+                        Type::Option(Box::new(Type::Unknown)),
+                        found,
+                        next_method.span,
+                    )
+                    .with_note(format!(
+                        "{}::next must return an Option to be used in a for loop",
+                        type_name
+                    )),
+                );
+                Type::Unknown
+            }
+        }
+    }
+
     /// Analyze an import statement
     fn analyze_import_stmt(
         &mut self,
@@ -1841,7 +2280,11 @@ impl SemanticAnalyzer {
                     if err.contains("Symbol") {
                         // Extract symbol name from error message for better error reporting
                         let symbol_name = err.split('\'').nth(1).unwrap_or("unknown");
+                        let similar = self
+                            .symbol_table
+                            .similar_module_members(source, symbol_name);
                         SemanticError::undefined_import(symbol_name, source, span)
+                            .with_did_you_mean(&similar)
                     } else {
                         SemanticError::module_not_found(source, span)
                     }
@@ -2016,6 +2459,10 @@ impl SemanticAnalyzer {
             ExprKind::Closure { parameters, body } => {
                 self.analyze_closure(parameters, body, expr.id, expr.span)
             }
+            ExprKind::Cast {
+                expr: inner,
+                target_type,
+            } => self.analyze_cast(inner, target_type, expr.span),
         };
 
         // Record the type information for this expression
@@ -2037,6 +2484,7 @@ impl SemanticAnalyzer {
                     Type::F32
                 }
             }
+            Literal::TypedNumber(_, suffix) => suffix_type(*suffix),
             Literal::String(_) => Type::String,
             Literal::Boolean(_) => Type::Bool,
             Literal::Null => Type::Option(Box::new(Type::Unknown)),
@@ -2056,9 +2504,11 @@ impl SemanticAnalyzer {
         if let Some(symbol) = self.symbol_table.lookup_with_modules(name) {
             let symbol_id = symbol.id;
             let ty = symbol.ty.clone();
+            let stability = symbol.stability.clone();
 
             // Mark as used
             self.symbol_table.mark_used(symbol_id);
+            self.check_stability(name, stability, span);
 
             // Memory safety analysis
             if self.memory_safety_enabled {
@@ -2074,7 +2524,8 @@ impl SemanticAnalyzer {
 
             Ok(ty)
         } else {
-            self.add_error(SemanticError::undefined_variable(name, span));
+            let error = SemanticError::undefined_variable(name, span);
+            self.add_undefined_name_error(error, name, span);
             Ok(Type::Unknown)
         }
     }
@@ -2105,7 +2556,7 @@ impl SemanticAnalyzer {
                         right_type.clone(),
                         span,
                     ).with_note(format!(
-                        "arithmetic operations require numeric types (i32 or f32), but found {} and {}",
+                        "arithmetic operations require numeric types (i32, i64, u32, u64, f32 or f64), but found {} and {}",
                         left_type, right_type
                     )));
                     return Ok(Type::Unknown);
@@ -2124,7 +2575,7 @@ impl SemanticAnalyzer {
                     // Both are the same numeric type
                     left_type
                 } else {
-                    // Mixed numeric types (i32 and f32)
+                    // Mixed numeric types (e.g. i32 and i64)
                     // For now, we don't allow implicit conversions
                     self.add_error(
                         SemanticError::type_mismatch(left_type.clone(), right_type.clone(), span)
@@ -2150,7 +2601,7 @@ impl SemanticAnalyzer {
                         right_type.clone(),
                         span,
                     ).with_note(format!(
-                        "comparison operations require numeric types (i32 or f32), but found {} and {}",
+                        "comparison operations require numeric types (i32, i64, u32, u64, f32 or f64), but found {} and {}",
                         left_type, right_type
                     )));
                     return Ok(Type::Bool);
@@ -2269,18 +2720,23 @@ impl SemanticAnalyzer {
                 .collect::<Result<Vec<_>>>()?;
 
             // Check if function exists at all (including imports)
-            let symbol_info = self
-                .symbol_table
-                .lookup_with_modules(name)
-                .map(|s| (s.id, s.ty.clone(), s.function_signature().cloned()));
+            let symbol_info = self.symbol_table.lookup_with_modules(name).map(|s| {
+                (
+                    s.id,
+                    s.ty.clone(),
+                    s.function_signature().cloned(),
+                    s.stability.clone(),
+                )
+            });
 
-            if let Some((func_id, symbol_type, maybe_signature)) = symbol_info {
+            if let Some((func_id, symbol_type, maybe_signature, stability)) = symbol_info {
+                self.check_stability(name, stability, callee.span);
                 if let Some(signature) = maybe_signature {
                     // Handle generic functions
                     let instantiated_signature = if signature.generic_params.is_some() {
                         // Create instantiation and track it for monomorphization
                         let instantiated =
-                            self.instantiate_generic_function(&signature, &arg_types)?;
+                            self.instantiate_generic_function(&signature, &arg_types, span)?;
 
                         // Track this generic instantiation
                         let instantiation = GenericInstantiation {
@@ -2370,11 +2826,30 @@ impl SemanticAnalyzer {
                 }
             } else {
                 // Function doesn't exist
-                self.add_error(SemanticError::undefined_function(name, span));
+                let error = SemanticError::undefined_function(name, span);
+                self.add_undefined_name_error(error, name, callee.span);
                 return Ok(Type::Unknown);
             }
         }
 
+        // Method calls resolve through impl blocks, or through the trait
+        // bounds of a type parameter receiver
+        if let ExprKind::Member { object, property } = &callee.kind {
+            let receiver_type = self.analyze_expr(object)?;
+            return match &receiver_type {
+                Type::TypeParam(type_param) => {
+                    self.resolve_trait_method_call(type_param, property, args, span)
+                }
+                Type::Unknown | Type::TypeVar(_) => {
+                    for arg in args {
+                        self.analyze_expr(arg)?;
+                    }
+                    Ok(Type::Unknown)
+                }
+                _ => self.resolve_method_call(&receiver_type, property, args, span),
+            };
+        }
+
         // General case: callee is an expression
         let callee_type = self.analyze_expr(callee)?;
 
@@ -2701,7 +3176,8 @@ impl SemanticAnalyzer {
                         ));
                     }
                 } else {
-                    self.add_error(SemanticError::undefined_variable(name, target.span));
+                    let error = SemanticError::undefined_variable(name, target.span);
+                    self.add_undefined_name_error(error, name, target.span);
                 }
             }
             ExprKind::Index { object, index } => {
@@ -2770,15 +3246,21 @@ impl SemanticAnalyzer {
 
         if !exhaustiveness_result.is_exhaustive {
             let missing_patterns = exhaustiveness_result.missing_patterns.join(", ");
+            let arms_help = exhaustiveness_result
+                .missing_patterns
+                .iter()
+                .map(|pattern| format!("{} => ...", pattern))
+                .collect::<Vec<_>>()
+                .join(", ");
             let mut error = SemanticError::new(SemanticErrorKind::NonExhaustivePatterns, span)
                 .with_note(format!(
                     "Pattern matching is not exhaustive. Missing patterns: {}",
                     missing_patterns
                 ))
-                .with_help(
-                    "Consider adding a wildcard pattern `_` to handle all remaining cases"
-                        .to_string(),
-                );
+                .with_help(format!(
+                    "Add an arm for each missing pattern ({}) or a wildcard pattern `_`",
+                    arms_help
+                ));
 
             // Add a note if guards are present
             if exhaustiveness_result.has_guards {
@@ -2787,16 +3269,18 @@ impl SemanticAnalyzer {
                 );
             }
 
-            self.add_error(error);
+            self.add_error(error.with_fix(missing_arms_fix(
+                span,
+                &exhaustiveness_result.missing_patterns,
+            )));
         }
 
-        // Report redundant patterns
+        // Arms that can never run are dead code, not errors
         for (index, pattern_span) in exhaustiveness_result.redundant_patterns {
-            self.add_error(
-                SemanticError::new(SemanticErrorKind::RedundantPattern, pattern_span)
-                    .with_note(format!("Pattern {} is unreachable", index + 1))
-                    .with_help("Remove this pattern or reorder the match arms".to_string()),
-            );
+            self.warnings.push(SemanticWarning::new(
+                SemanticWarningKind::UnreachablePattern { arm: index + 1 },
+                pattern_span,
+            ));
         }
 
         // Analyze each arm
@@ -2843,6 +3327,23 @@ impl SemanticAnalyzer {
         Ok(result_type.unwrap_or(Type::Unknown))
     }
 
+    /// Analyze an explicit `cast<T>(value)` conversion
+    fn analyze_cast(&mut self, expr: &Expr, target_type: &TypeAnn, span: Span) -> Result<Type> {
+        let source = self.analyze_expr(expr)?;
+        let target = type_ann_to_type(target_type);
+
+        match cast_result_type(&source, &target) {
+            Ok(ty) => Ok(ty),
+            Err(message) => {
+                self.add_error(
+                    SemanticError::invalid_operation(&format!("cast<{}>", target), source, span)
+                        .with_note(message),
+                );
+                Ok(target)
+            }
+        }
+    }
+
     /// Analyze an error propagation expression (?)
     fn analyze_error_propagation(&mut self, expr: &Expr, span: Span) -> Result<Type> {
         // Analyze the inner expression
@@ -3160,6 +3661,61 @@ impl SemanticAnalyzer {
                 // Object destructuring - TODO: implement properly
                 Ok(())
             }
+            PatternKind::Tuple(patterns) => {
+                // Tuple destructuring - each element has its own type
+                let element_types = match expected_type {
+                    Type::Tuple(types) if types.len() == patterns.len() => types.clone(),
+                    Type::Tuple(types) => {
+                        self.add_error(
+                            SemanticError::new(
+                                SemanticErrorKind::InvalidOperation {
+                                    op: "pattern match".to_string(),
+                                    ty: expected_type.clone(),
+                                },
+                                pattern.span,
+                            )
+                            .with_note(format!(
+                                "Tuple pattern has {} elements, but the value has {}",
+                                patterns.len(),
+                                types.len()
+                            )),
+                        );
+                        vec![Type::Unknown; patterns.len()]
+                    }
+                    Type::Unknown | Type::TypeVar(_) => vec![Type::Unknown; patterns.len()],
+                    _ => {
+                        self.add_error(SemanticError::type_mismatch(
+                            expected_type.clone(),
+                            Type::Tuple(vec![Type::Unknown; patterns.len()]),
+                            pattern.span,
+                        ));
+                        vec![Type::Unknown; patterns.len()]
+                    }
+                };
+
+                // Still bind the elements on a mismatch to avoid follow-on errors
+                for (sub_pattern, element_type) in patterns.iter().zip(element_types.iter()) {
+                    self.analyze_pattern(sub_pattern, element_type)?;
+                }
+                Ok(())
+            }
+            PatternKind::Binding {
+                name,
+                pattern: sub_pattern,
+            } => {
+                // Bind the whole value, then the sub-pattern's variables
+                if let Err(err) = self.symbol_table.define_variable(
+                    name.clone(),
+                    expected_type.clone(),
+                    pattern.span,
+                    false,
+                ) {
+                    self.add_error(
+                        SemanticError::duplicate_variable(name, pattern.span).with_note(err),
+                    );
+                }
+                self.analyze_pattern(sub_pattern, expected_type)
+            }
             PatternKind::Or(patterns) => {
                 // Or pattern - all alternatives should bind the same variables
                 for sub_pattern in patterns {
@@ -3311,6 +3867,11 @@ impl SemanticAnalyzer {
         &self.errors
     }
 
+    /// Get collected warnings
+    pub fn warnings(&self) -> &[SemanticWarning] {
+        &self.warnings
+    }
+
     /// Get the collected generic instantiations
     pub fn generic_instantiations(&self) -> &[GenericInstantiation] {
         &self.generic_instantiations
@@ -3463,6 +4024,9 @@ impl SemanticAnalyzer {
                 self.validate_const_expression(iterable)?;
                 self.validate_const_block(body)?;
             }
+            StmtKind::Break | StmtKind::Continue => {
+                // Loop control is allowed
+            }
             StmtKind::Function { .. } => {
                 // Nested functions are not allowed in const functions
                 self.add_error(SemanticError::const_function_violation(
@@ -3477,10 +4041,13 @@ impl SemanticAnalyzer {
                     stmt.span,
                 ));
             }
-            StmtKind::Struct { .. } | StmtKind::Enum { .. } | StmtKind::Impl(_) => {
-                // Struct/enum/impl definitions not allowed in function bodies
+            StmtKind::Struct { .. }
+            | StmtKind::Enum { .. }
+            | StmtKind::Impl(_)
+            | StmtKind::Trait(_) => {
+                // Struct/enum/impl/trait definitions not allowed in function bodies
                 self.add_error(SemanticError::const_function_violation(
-                    "struct/enum/impl definitions not allowed in function bodies",
+                    "struct/enum/impl/trait definitions not allowed in function bodies",
                     stmt.span,
                 ));
             }
@@ -3658,6 +4225,8 @@ impl SemanticAnalyzer {
                 }
             }
 
+            ExprKind::Cast { expr, .. } => self.validate_const_expression(expr),
+
             // Error propagation is not allowed in const functions
             ExprKind::ErrorPropagation { .. } => {
                 self.add_error(SemanticError::const_function_violation(
@@ -3690,8 +4259,9 @@ impl SemanticAnalyzer {
         &mut self,
         signature: &FunctionSignature,
         arg_types: &[Type],
+        span: Span,
     ) -> Result<FunctionSignature> {
-        let _generic_params = signature.generic_params.as_ref().unwrap();
+        let generic_params = signature.generic_params.as_ref().unwrap();
 
         // Create a type substitution map
         let mut type_substitutions = HashMap::new();
@@ -3725,6 +4295,32 @@ impl SemanticAnalyzer {
 
         let instantiated_return = self.substitute_type(&signature.return_type, &type_substitutions);
 
+        // Every inferred type argument must implement its parameter's bounds
+        for param in &generic_params.params {
+            let Some(type_arg) = type_substitutions.get(&param.name) else {
+                continue;
+            };
+            for bound in &param.bounds {
+                if !self.satisfies_trait_bound(type_arg, &bound.trait_name) {
+                    self.add_error(
+                        SemanticError::trait_bound_not_satisfied(
+                            type_arg.clone(),
+                            &bound.trait_name,
+                            span,
+                        )
+                        .with_note(format!(
+                            "required by the bound `{}: {}`",
+                            param.name, bound.trait_name
+                        ))
+                        .with_help(format!(
+                            "implement it with `impl {} for {} {{ .. }}`",
+                            bound.trait_name, type_arg
+                        )),
+                    );
+                }
+            }
+        }
+
         // Track this generic instantiation for monomorphization
         if !type_substitutions.is_empty() {
             // We need the function name, but it's not passed to this method
@@ -3831,8 +4427,16 @@ impl SemanticAnalyzer {
 
     /// Analyze an impl block
     fn analyze_impl_block(&mut self, impl_block: &ImplBlock) -> Result<()> {
+        // Check a trait impl against its trait; the trait's default methods
+        // it doesn't override become methods of the type
+        let mut resolved_block = impl_block.clone();
+        if let Some(trait_name) = &impl_block.trait_name {
+            let inherited = self.check_trait_impl(impl_block, trait_name);
+            resolved_block.methods.extend(inherited);
+        }
+
         // Store the impl block for method resolution
-        self.impl_blocks.push(impl_block.clone());
+        self.impl_blocks.push(resolved_block);
 
         // Clear method cache for the target type
         let target_type_name = impl_block.type_name.clone();
@@ -3873,6 +4477,301 @@ impl SemanticAnalyzer {
         Ok(())
     }
 
+    /// Check that an `impl Trait for Type` block implements exactly the
+    /// trait's methods, returning the default methods it inherits
+    fn check_trait_impl(&mut self, impl_block: &ImplBlock, trait_name: &str) -> Vec<Method> {
+        self.inference_ctx
+            .register_trait_impl(named_type(&impl_block.type_name), trait_name);
+
+        let Some(trait_decl) = self.traits.get(trait_name).cloned() else {
+            // Builtin traits don't declare methods to check against
+            if BuiltinTrait::from_name(trait_name).is_none() {
+                self.add_error(SemanticError::undefined_trait(trait_name, impl_block.span));
+            }
+            return Vec::new();
+        };
+
+        for method in &impl_block.methods {
+            match trait_decl.methods.iter().find(|m| m.name == method.name) {
+                Some(declared) if declared.params.len() != method.params.len() => {
+                    self.add_error(
+                        SemanticError::argument_count_mismatch(
+                            declared.params.len(),
+                            method.params.len(),
+                            method.span,
+                        )
+                        .with_note(format!("trait '{}' declares `{}`", trait_name, declared)),
+                    );
+                }
+                Some(_) => {}
+                None => {
+                    self.add_error(SemanticError::new(
+                        SemanticErrorKind::MethodNotInTrait {
+                            trait_name: trait_name.to_string(),
+                            method_name: method.name.clone(),
+                        },
+                        method.span,
+                    ));
+                }
+            }
+        }
+
+        let mut inherited = Vec::new();
+        for declared in &trait_decl.methods {
+            if impl_block.methods.iter().any(|m| m.name == declared.name) {
+                continue;
+            }
+            match declared.default_method() {
+                Some(method) => inherited.push(method),
+                None => {
+                    self.add_error(
+                        SemanticError::new(
+                            SemanticErrorKind::MissingTraitMethod {
+                                trait_name: trait_name.to_string(),
+                                type_name: impl_block.type_name.clone(),
+                                method_name: declared.name.clone(),
+                            },
+                            impl_block.span,
+                        )
+                        .with_help(format!("add `{}` to the impl block", declared)),
+                    );
+                }
+            }
+        }
+        inherited
+    }
+
+    /// Analyze a trait declaration
+    ///
+    /// Default method bodies are checked once, generically: `Self` is a type
+    /// parameter bounded by the trait, so they may only call the trait's own
+    /// methods on `self`.
+    fn analyze_trait(&mut self, trait_decl: &TraitDecl) -> Result<()> {
+        match self.traits.get(&trait_decl.name) {
+            Some(existing) if existing.span != trait_decl.span => {
+                self.add_error(SemanticError::new(
+                    SemanticErrorKind::DuplicateType(trait_decl.name.clone()),
+                    trait_decl.span,
+                ));
+                return Ok(());
+            }
+            Some(_) => {}
+            None => {
+                self.traits
+                    .insert(trait_decl.name.clone(), trait_decl.clone());
+            }
+        }
+
+        for method in &trait_decl.methods {
+            if let Some(body) = &method.default_body {
+                self.analyze_default_method(trait_decl, method, body)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Analyze the default body of a trait method
+    fn analyze_default_method(
+        &mut self,
+        trait_decl: &TraitDecl,
+        method: &TraitMethod,
+        body: &Block,
+    ) -> Result<()> {
+        let self_type = Type::TypeParam("Self".to_string());
+        let self_param = GenericParam {
+            name: "Self".to_string(),
+            bounds: vec![TraitBound {
+                trait_name: trait_decl.name.clone(),
+                span: trait_decl.span,
+            }],
+            span: trait_decl.span,
+        };
+
+        self.symbol_table.enter_scope();
+        self.inference_ctx.push_scope();
+        if self.memory_safety_enabled {
+            self.memory_safety_ctx.enter_scope();
+        }
+
+        self.push_context(AnalysisContext {
+            current_function_return: Some(
+                method
+                    .ret_type
+                    .as_ref()
+                    .map(|ret| replace_self(&type_ann_to_type(ret), &self_type))
+                    .unwrap_or(Type::Unknown),
+            ),
+            in_loop: false,
+            _in_const_function: false,
+            in_async_function: false,
+            generic_params: Some(GenericParams {
+                params: vec![self_param],
+                span: trait_decl.span,
+            }),
+            generic_param_names: vec!["Self".to_string()],
+        });
+        self.inference_ctx.define_type_param("Self");
+
+        for param in &method.params {
+            let param_type = replace_self(&type_ann_to_type(&param.type_ann), &self_type);
+            match self.symbol_table.define_parameter(
+                param.name.clone(),
+                param_type.clone(),
+                param.type_ann.span,
+            ) {
+                Ok(symbol_id) => {
+                    self.symbol_table.mark_used(symbol_id);
+
+                    // Parameters are initialized by the caller
+                    if self.memory_safety_enabled {
+                        let _ = self.memory_safety_ctx.define_variable(
+                            param.name.clone(),
+                            param_type,
+                            false,
+                            param.type_ann.span,
+                        );
+                        let _ = self
+                            .memory_safety_ctx
+                            .initialize_variable(&param.name, param.type_ann.span);
+                    }
+                }
+                Err(err) => {
+                    self.add_error(
+                        SemanticError::duplicate_variable(&param.name, param.type_ann.span)
+                            .with_note(err),
+                    );
+                }
+            }
+        }
+
+        self.analyze_block(body)?;
+
+        self.pop_context();
+        self.symbol_table.exit_scope();
+        self.inference_ctx.pop_scope();
+        if self.memory_safety_enabled {
+            self.memory_safety_ctx.exit_scope(method.span);
+        }
+
+        Ok(())
+    }
+
+    /// Report a trait bound naming neither a builtin nor a declared trait
+    fn check_trait_defined(&mut self, trait_name: &str, span: Span) {
+        if BuiltinTrait::from_name(trait_name).is_none() && !self.traits.contains_key(trait_name) {
+            self.add_error(SemanticError::undefined_trait(trait_name, span));
+        }
+    }
+
+    /// Trait bounds of a type parameter in the enclosing generic scopes
+    fn type_param_bounds(&self, type_param: &str) -> Vec<String> {
+        self.context_stack
+            .iter()
+            .rev()
+            .filter_map(|ctx| ctx.generic_params.as_ref())
+            .flat_map(|generics| generics.params.iter())
+            .find(|param| param.name == type_param)
+            .map(|param| {
+                param
+                    .bounds
+                    .iter()
+                    .map(|bound| bound.trait_name.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether a type argument satisfies a trait bound
+    ///
+    /// A type parameter satisfies the bounds it is declared with, so bounded
+    /// generic code can pass its parameters on to other generic functions.
+    fn satisfies_trait_bound(&mut self, ty: &Type, trait_name: &str) -> bool {
+        match ty {
+            Type::Unknown | Type::TypeVar(_) => true,
+            Type::TypeParam(name) => self
+                .type_param_bounds(name)
+                .iter()
+                .any(|bound| bound == trait_name),
+            _ => self.inference_ctx.check_trait_implementation(ty, trait_name),
+        }
+    }
+
+    /// Resolve a method call on a value whose type is a type parameter
+    ///
+    /// Only the methods of the parameter's bounds are callable; which
+    /// implementation runs is decided when the function is monomorphized.
+    fn resolve_trait_method_call(
+        &mut self,
+        type_param: &str,
+        method_name: &str,
+        args: &[Expr],
+        span: Span,
+    ) -> Result<Type> {
+        let arg_types: Vec<Type> = args
+            .iter()
+            .map(|arg| self.analyze_expr(arg))
+            .collect::<Result<Vec<_>>>()?;
+
+        let declared = self
+            .type_param_bounds(type_param)
+            .iter()
+            .filter_map(|bound| self.traits.get(bound))
+            .find_map(|trait_decl| {
+                trait_decl
+                    .methods
+                    .iter()
+                    .find(|method| method.name == method_name)
+                    .cloned()
+            });
+
+        let Some(declared) = declared else {
+            let mut error = SemanticError::method_not_found(type_param, method_name, span);
+            let candidate = self
+                .traits
+                .values()
+                .find(|trait_decl| trait_decl.methods.iter().any(|m| m.name == method_name));
+            if let Some(trait_decl) = candidate {
+                error = error.with_help(format!(
+                    "add a bound to call trait methods: `{}: {}`",
+                    type_param, trait_decl.name
+                ));
+            }
+            self.add_error(error);
+            return Ok(Type::Unknown);
+        };
+
+        let receiver_type = Type::TypeParam(type_param.to_string());
+        let params: Vec<&Param> = declared
+            .params
+            .iter()
+            .filter(|p| p.name != "self")
+            .collect();
+        if params.len() != args.len() {
+            self.add_error(SemanticError::argument_count_mismatch(
+                params.len(),
+                args.len(),
+                span,
+            ));
+        } else {
+            for (param, (arg, arg_type)) in params.iter().zip(args.iter().zip(arg_types.iter())) {
+                let param_type = replace_self(&type_ann_to_type(&param.type_ann), &receiver_type);
+                if !arg_type.is_assignable_to(&param_type) {
+                    self.add_error(SemanticError::type_mismatch(
+                        param_type,
+                        arg_type.clone(),
+                        arg.span,
+                    ));
+                }
+            }
+        }
+
+        Ok(declared
+            .ret_type
+            .as_ref()
+            .map(|ret| replace_self(&type_ann_to_type(ret), &receiver_type))
+            .unwrap_or(Type::Unknown))
+    }
+
     /// Analyze a method within an impl block
     fn analyze_method(&mut self, method: &Method, target_type: &TypeAnn) -> Result<()> {
         // Create a unique method name that includes the type
@@ -3886,7 +4785,7 @@ impl SemanticAnalyzer {
         param_types.push(("self".to_string(), self_type));
 
         // Add other parameters
-        for param in &method.params {
+        for param in method.params.iter().filter(|p| p.name != "self") {
             let param_type = type_ann_to_type(&param.type_ann);
             param_types.push((param.name.clone(), param_type));
         }
@@ -3995,7 +4894,7 @@ impl SemanticAnalyzer {
         }
 
         // Define method parameters
-        for param in &method.params {
+        for param in method.params.iter().filter(|p| p.name != "self") {
             let param_type = type_ann_to_type(&param.type_ann);
             match self.symbol_table.define_parameter(
                 param.name.clone(),
@@ -4061,7 +4960,7 @@ impl SemanticAnalyzer {
         // Find matching impl blocks
         let mut matching_methods = Vec::new();
         for impl_block in &self.impl_blocks.clone() {
-            let impl_target_type = Type::Named(impl_block.type_name.clone());
+            let impl_target_type = named_type(&impl_block.type_name);
 
             // Check if receiver type matches impl target type
             if self.types_match(receiver_type, &impl_target_type) {
@@ -4079,6 +4978,9 @@ impl SemanticAnalyzer {
             .insert(cache_key, matching_methods.clone());
 
         if matching_methods.is_empty() {
+            for arg in args {
+                self.analyze_expr(arg)?;
+            }
             self.add_error(SemanticError::new(
                 SemanticErrorKind::MethodNotFound {
                     type_name: receiver_type.to_string(),
@@ -4117,26 +5019,26 @@ impl SemanticAnalyzer {
                 .collect::<Result<Vec<_>>>()?;
 
             // Check argument count (excluding self)
-            if args.len() != method.params.len() {
+            let params: Vec<&Param> = method.params.iter().filter(|p| p.name != "self").collect();
+            if args.len() != params.len() {
                 self.add_error(
-                    SemanticError::argument_count_mismatch(method.params.len(), args.len(), span)
+                    SemanticError::argument_count_mismatch(params.len(), args.len(), span)
                         .with_note(format!(
                             "method '{}' on type {} expects {} arguments, but {} were provided",
                             method_name,
                             receiver_type,
-                            method.params.len(),
+                            params.len(),
                             args.len()
                         )),
                 );
             } else {
                 // Check each argument type
-                for (i, (param, (arg, arg_type))) in method
-                    .params
+                for (i, (param, (arg, arg_type))) in params
                     .iter()
                     .zip(args.iter().zip(arg_types.iter()))
                     .enumerate()
                 {
-                    let param_type = type_ann_to_type(&param.type_ann);
+                    let param_type = replace_self(&type_ann_to_type(&param.type_ann), receiver_type);
 
                     if !arg_type.is_assignable_to(&param_type) {
                         self.add_error(
@@ -4163,7 +5065,7 @@ impl SemanticAnalyzer {
             Ok(method
                 .ret_type
                 .as_ref()
-                .map(type_ann_to_type)
+                .map(|ret| replace_self(&type_ann_to_type(ret), receiver_type))
                 .unwrap_or(Type::Unknown))
         } else {
             self.add_error(SemanticError::new(
@@ -4204,6 +5106,28 @@ impl SemanticAnalyzer {
         property: &str,
         span: crate::source::Span,
     ) -> Result<Type> {
+        // `alias.member` where `alias` names a namespace import and no
+        // variable shadows it
+        if let ExprKind::Identifier(alias) = &object.kind {
+            let module = self
+                .symbol_table
+                .namespace_module(alias)
+                .filter(|_| self.symbol_table.lookup(alias).is_none())
+                .map(str::to_string);
+            if let Some(module) = module {
+                let qualified = format!("{}.{}", alias, property);
+                if let Some(symbol) = self.symbol_table.lookup_with_modules(&qualified) {
+                    return Ok(symbol.ty.clone());
+                }
+                let similar = self.symbol_table.similar_module_members(&module, property);
+                self.add_error(
+                    SemanticError::undefined_import(property, &module, span)
+                        .with_did_you_mean(&similar),
+                );
+                return Ok(Type::Unknown);
+            }
+        }
+
         let object_type = self.analyze_expr(object)?;
 
         // Try method resolution first
@@ -4232,10 +5156,11 @@ impl SemanticAnalyzer {
         span: crate::source::Span,
     ) -> Result<Type> {
         // Look up the struct definition and clone the info we need
-        let struct_info_opt = self
-            .symbol_table
-            .lookup(name)
-            .and_then(|symbol| symbol.struct_info().cloned());
+        let (struct_info_opt, stability) = match self.symbol_table.lookup(name) {
+            Some(symbol) => (symbol.struct_info().cloned(), symbol.stability.clone()),
+            None => (None, None),
+        };
+        self.check_stability(name, stability, span);
 
         if let Some(struct_info) = struct_info_opt {
             // Check if struct has generic parameters
@@ -4863,6 +5788,69 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_undefined_name_fixes() {
+        let analyzer =
+            analyze_program("fn main() {\n    let total = 1;\n    totl + 1;\n}\n").unwrap();
+        let error = &analyzer.errors()[0];
+        assert_eq!(
+            error.fixes,
+            vec![
+                Fix::Edit {
+                    title: "Change to 'total'".to_string(),
+                    span: error.span,
+                    text: "total".to_string(),
+                },
+                Fix::Import {
+                    name: "totl".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undefined_name_suggests_every_close_name() {
+        let analyzer = analyze_program(
+            "fn main() {\n    let total = 1;\n    let totals = 2;\n    totas + 1;\n}\n",
+        )
+        .unwrap();
+        let error = &analyzer.errors()[0];
+        assert!(error
+            .notes
+            .contains(&"help: did you mean 'total' or 'totals'?".to_string()));
+        let titles: Vec<&str> = error
+            .fixes
+            .iter()
+            .filter_map(|fix| match fix {
+                Fix::Edit { title, .. } => Some(title.as_str()),
+                Fix::Import { .. } => None,
+            })
+            .collect();
+        assert_eq!(titles, vec!["Change to 'total'", "Change to 'totals'"]);
+    }
+
+    #[test]
+    fn test_missing_arms_fix() {
+        let analyzer = analyze_program(
+            "fn pick(flag: bool) -> i32 {\n    match flag {\n        true => 1,\n    }\n}\n",
+        )
+        .unwrap();
+        let error = analyzer
+            .errors()
+            .iter()
+            .find(|error| error.kind == SemanticErrorKind::NonExhaustivePatterns)
+            .unwrap();
+        let brace = SourceLocation::new(4, 5, 69);
+        assert_eq!(
+            error.fixes,
+            vec![Fix::Edit {
+                title: "Add missing match arms".to_string(),
+                span: Span::single(brace),
+                text: "    false => {},\n    ".to_string(),
+            }]
+        );
+    }
+
     #[test]
     fn test_function_definition() {
         let analyzer = analyze_program("fn add(x: i32, y: i32) -> i32 { x + y }").unwrap();
@@ -5016,4 +6004,91 @@ mod tests {
         assert!(analyzer.is_assignable_to(&array_i32, &Type::Unknown));
         assert!(analyzer.is_assignable_to(&Type::Unknown, &array_i32));
     }
+
+    #[test]
+    fn test_deprecated_function_call_warns() {
+        let analyzer = analyze_program(
+            r#"
+@deprecated(since = "1.2", note = "use add instead")
+fn old_add(a: i32, b: i32) -> i32 { a + b }
+
+@unstable
+fn preview() -> i32 { 1 }
+
+fn add(a: i32, b: i32) -> i32 { a + b }
+
+let x = old_add(1, 2);
+let y = preview();
+let z = add(x, y);
+"#,
+        )
+        .unwrap();
+
+        assert!(analyzer.errors().is_empty());
+        let warnings = analyzer.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0].kind,
+            SemanticWarningKind::DeprecatedUsage {
+                name: "old_add".to_string(),
+                since: Some("1.2".to_string()),
+                note: Some("use add instead".to_string()),
+            }
+        );
+        assert_eq!(
+            warnings[0].kind.to_string(),
+            "use of deprecated item 'old_add' (deprecated since 1.2): use add instead"
+        );
+        assert_eq!(warnings[1].item_name(), Some("preview"));
+    }
+
+    #[test]
+    fn test_future_keyword_names_warn() {
+        let analyzer = analyze_program(
+            r#"
+fn defer(yield: i32) -> i32 { yield }
+let total = defer(1);
+"#,
+        )
+        .unwrap();
+
+        assert!(analyzer.errors().is_empty());
+        let warnings = analyzer.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0].kind,
+            SemanticWarningKind::FutureKeyword {
+                name: "defer".to_string(),
+                edition: Edition::E2026,
+            }
+        );
+        assert!(warnings[1]
+            .kind
+            .to_string()
+            .contains("script fix --edition 2026"));
+    }
+
+    #[test]
+    fn test_deprecation_propagates_through_imports() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join("mathlib.script"),
+            "@deprecated(since = \"0.3\")\nexport fn legacy(a: i32) -> i32 { a }\n",
+        )
+        .unwrap();
+
+        let source = "import { legacy } from \"mathlib\"\nlet v = legacy(1);\n";
+        let (tokens, _) = Lexer::new(source).unwrap().scan_tokens();
+        let program = Parser::new(tokens).parse().unwrap();
+
+        let mut analyzer = SemanticAnalyzer::new();
+        analyzer.add_module_search_path(temp_dir.path().to_path_buf());
+        analyzer.analyze_program(&program).unwrap();
+
+        assert_eq!(analyzer.warnings().len(), 1);
+        assert!(analyzer.warnings()[0].kind.to_string().contains("since 0.3"));
+    }
 }
@@ -1,5 +1,5 @@
 use crate::parser::{Block, ClosureParam, Expr, ExprKind};
-use crate::semantic::symbol::{Symbol, SymbolId};
+use crate::semantic::symbol::{Symbol, SymbolId, SymbolKind};
 use crate::semantic::symbol_table::{ScopeId, SymbolTable};
 use crate::types::Type;
 use std::collections::{HashMap, HashSet};
@@ -98,9 +98,12 @@ impl<'a> CaptureAnalyzer<'a> {
                 // Check if it's a local variable or needs to be captured
                 if !context.closure_locals.contains(name) {
                     // Try to find the variable in outer scopes
+                    // Constants are inlined where they are used, so they are
+                    // never captured
                     if let Some(symbol) = self
                         .symbol_table
                         .lookup_in_scope(name, context.current_scope)
+                        .filter(|symbol| !matches!(symbol.kind, SymbolKind::Constant))
                     {
                         context.free_variables.insert(name.clone(), symbol.id);
                     }
@@ -212,7 +215,7 @@ impl<'a> CaptureAnalyzer<'a> {
                     self.find_free_variables_in_block(finally, context);
                 }
             }
-            ExprKind::ErrorPropagation { expr } => {
+            ExprKind::ErrorPropagation { expr } | ExprKind::Cast { expr, .. } => {
                 self.find_free_variables(expr, context);
             }
             ExprKind::Await { expr } => {
@@ -1,4 +1,5 @@
-use crate::error::{Error, ErrorKind};
+use crate::edition::Edition;
+use crate::error::{Error, ErrorKind, Fix};
 use crate::source::Span;
 use crate::types::Type;
 use std::fmt;
@@ -118,6 +119,21 @@ pub enum SemanticErrorKind {
     InvalidErrorPropagation { actual_type: Type },
     /// Duplicate type definition
     DuplicateType(String),
+    /// Trait not defined
+    UndefinedTrait(String),
+    /// Impl block doesn't define a required trait method
+    MissingTraitMethod {
+        trait_name: String,
+        type_name: String,
+        method_name: String,
+    },
+    /// Impl block defines a method its trait doesn't declare
+    MethodNotInTrait {
+        trait_name: String,
+        method_name: String,
+    },
+    /// Type argument doesn't implement a bound of its type parameter
+    TraitBoundNotSatisfied { ty: Type, trait_name: String },
 }
 
 /// Semantic error with location information
@@ -126,6 +142,8 @@ pub struct SemanticError {
     pub kind: SemanticErrorKind,
     pub span: Span,
     pub notes: Vec<String>,
+    /// Fixes an editor can apply
+    pub fixes: Vec<Fix>,
 }
 
 impl SemanticError {
@@ -135,6 +153,7 @@ impl SemanticError {
             kind,
             span,
             notes: Vec::new(),
+            fixes: Vec::new(),
         }
     }
 
@@ -144,11 +163,29 @@ impl SemanticError {
         self
     }
 
+    /// Attach a fix to this error
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fixes.push(fix);
+        self
+    }
+
     /// Add a help message to this error (convenience method)
     pub fn with_help(self, help: String) -> Self {
         self.with_note(format!("help: {}", help))
     }
 
+    /// Suggest the names the user may have meant, if there are any
+    pub fn with_did_you_mean(self, names: &[String]) -> Self {
+        let quoted: Vec<String> = names.iter().map(|name| format!("'{}'", name)).collect();
+        match quoted.as_slice() {
+            [] => self,
+            [only] => self.with_help(format!("did you mean {}?", only)),
+            [rest @ .., last] => {
+                self.with_help(format!("did you mean {} or {}?", rest.join(", "), last))
+            }
+        }
+    }
+
     /// Generate helpful suggestions for common error patterns
     pub fn with_suggestions(mut self) -> Self {
         match &self.kind.clone() {
@@ -413,22 +450,72 @@ impl SemanticError {
             error = error.with_source_line(line);
         }
 
-        // Add notes as part of the message
-        if !self.notes.is_empty() {
-            let notes_str = self.notes.join("\n    note: ");
-            error = Error::new(
-                ErrorKind::SemanticError,
-                format!("{}\n    note: {}", error.message, notes_str),
-            )
-            .with_location(self.span.start);
+        error.notes = self.notes;
+        error.fixes = self.fixes;
+        error.with_code(self.kind.code())
+    }
+}
 
-            // Re-add source line if we had it
-            if let Some(line) = source_line {
-                error = error.with_source_line(line);
-            }
+impl SemanticErrorKind {
+    /// Stable code of the error, explained by `script explain`
+    pub fn code(&self) -> &'static str {
+        match self {
+            SemanticErrorKind::TypeMismatch { .. } => "E0301",
+            SemanticErrorKind::ArgumentCountMismatch { .. } => "E0302",
+            SemanticErrorKind::ReturnTypeMismatch { .. } => "E0303",
+            SemanticErrorKind::MissingReturn { .. } => "E0304",
+            SemanticErrorKind::InvalidOperation { .. } => "E0305",
+            SemanticErrorKind::InvalidBinaryOperation { .. } => "E0306",
+            SemanticErrorKind::FunctionAsValue(_) => "E0307",
+            SemanticErrorKind::NotCallable(_) => "E0308",
+            SemanticErrorKind::NotIndexable(_) => "E0309",
+            SemanticErrorKind::InvalidIndexType(_) => "E0310",
+            SemanticErrorKind::ErrorPropagationInNonResult => "E0311",
+            SemanticErrorKind::InvalidErrorPropagation { .. } => "E0312",
+            SemanticErrorKind::TraitBoundNotSatisfied { .. } => "E0313",
+            SemanticErrorKind::DuplicateVariable(_) => "E0401",
+            SemanticErrorKind::DuplicateFunction(_) => "E0402",
+            SemanticErrorKind::DuplicateType(_) => "E0403",
+            SemanticErrorKind::UndefinedVariable(_) => "E0425",
+            SemanticErrorKind::UndefinedFunction(_) => "E0426",
+            SemanticErrorKind::UndefinedType(_) => "E0427",
+            SemanticErrorKind::UndefinedTypeParameter(_) => "E0428",
+            SemanticErrorKind::UndefinedTrait(_) => "E0429",
+            SemanticErrorKind::AssignmentToImmutable(_) => "E0501",
+            SemanticErrorKind::InvalidAssignmentTarget => "E0502",
+            SemanticErrorKind::BreakOutsideLoop => "E0503",
+            SemanticErrorKind::ContinueOutsideLoop => "E0504",
+            SemanticErrorKind::ReturnOutsideFunction => "E0505",
+            SemanticErrorKind::MemorySafetyViolation(_) => "E0506",
+            SemanticErrorKind::InvalidMemberAccess(_) => "E0601",
+            SemanticErrorKind::UnknownMember { .. } => "E0602",
+            SemanticErrorKind::MethodNotFound { .. } => "E0603",
+            SemanticErrorKind::DuplicateField(_) => "E0604",
+            SemanticErrorKind::MissingField(_) => "E0605",
+            SemanticErrorKind::UnknownField(_) => "E0606",
+            SemanticErrorKind::NotAStruct(_) => "E0607",
+            SemanticErrorKind::NotAnEnum(_) => "E0608",
+            SemanticErrorKind::UnknownVariant { .. } => "E0609",
+            SemanticErrorKind::UnqualifiedEnumVariant(_) => "E0610",
+            SemanticErrorKind::VariantFormMismatch { .. } => "E0611",
+            SemanticErrorKind::MissingTraitMethod { .. } => "E0701",
+            SemanticErrorKind::MethodNotInTrait { .. } => "E0702",
+            SemanticErrorKind::NonExhaustivePatterns => "E0801",
+            SemanticErrorKind::RedundantPattern => "E0802",
+            SemanticErrorKind::ConstFunctionViolation(_) => "E0901",
+            SemanticErrorKind::NonConstFunctionCall { .. } => "E0902",
+            SemanticErrorKind::IoInConstFunction(_) => "E0903",
+            SemanticErrorKind::MutableOperationInConstFunction(_) => "E0904",
+            SemanticErrorKind::ModuleError(_) => "E1001",
+            SemanticErrorKind::UndefinedImport { .. } => "E1002",
+            SemanticErrorKind::ConflictingImport(_) => "E1003",
+            SemanticErrorKind::UndefinedExport(_) => "E1004",
+            SemanticErrorKind::ConflictingExport(_) => "E1005",
+            SemanticErrorKind::ModuleNotFound(_) => "E1006",
+            SemanticErrorKind::CircularImport { .. } => "E1007",
+            SemanticErrorKind::PrivateSymbolAccess { .. } => "E1008",
+            SemanticErrorKind::ActorError(_) => "E1101",
         }
-
-        error
     }
 }
 
@@ -656,6 +743,37 @@ impl fmt::Display for SemanticErrorKind {
             SemanticErrorKind::DuplicateType(name) => {
                 write!(f, "type '{}' is already defined", name)
             }
+            SemanticErrorKind::UndefinedTrait(name) => {
+                write!(f, "undefined trait '{}'", name)
+            }
+            SemanticErrorKind::MissingTraitMethod {
+                trait_name,
+                type_name,
+                method_name,
+            } => {
+                write!(
+                    f,
+                    "missing method '{}' in impl of trait '{}' for type '{}'",
+                    method_name, trait_name, type_name
+                )
+            }
+            SemanticErrorKind::MethodNotInTrait {
+                trait_name,
+                method_name,
+            } => {
+                write!(
+                    f,
+                    "method '{}' is not a member of trait '{}'",
+                    method_name, trait_name
+                )
+            }
+            SemanticErrorKind::TraitBoundNotSatisfied { ty, trait_name } => {
+                write!(
+                    f,
+                    "the trait bound '{}: {}' is not satisfied",
+                    ty, trait_name
+                )
+            }
         }
     }
 }
@@ -849,6 +967,122 @@ impl SemanticError {
     pub fn undefined_type(name: &str, span: Span) -> Self {
         SemanticError::new(SemanticErrorKind::UndefinedType(name.to_string()), span)
     }
+
+    pub fn undefined_trait(name: &str, span: Span) -> Self {
+        SemanticError::new(SemanticErrorKind::UndefinedTrait(name.to_string()), span)
+    }
+
+    pub fn trait_bound_not_satisfied(ty: Type, trait_name: &str, span: Span) -> Self {
+        SemanticError::new(
+            SemanticErrorKind::TraitBoundNotSatisfied {
+                ty,
+                trait_name: trait_name.to_string(),
+            },
+            span,
+        )
+    }
+}
+
+/// Kinds of semantic warnings
+#[derive(Debug, Clone, PartialEq)]
+pub enum SemanticWarningKind {
+    /// Use of an item marked `@deprecated`
+    DeprecatedUsage {
+        name: String,
+        since: Option<String>,
+        note: Option<String>,
+    },
+    /// Use of an item marked `@unstable`
+    UnstableUsage { name: String, note: Option<String> },
+    /// Match arm that earlier arms leave no values for
+    UnreachablePattern { arm: usize },
+    /// Name that is a keyword in a later edition
+    FutureKeyword { name: String, edition: Edition },
+}
+
+impl SemanticWarningKind {
+    /// Stable code of the warning, explained by `script explain`
+    pub fn code(&self) -> &'static str {
+        match self {
+            SemanticWarningKind::DeprecatedUsage { .. } => "W0001",
+            SemanticWarningKind::UnstableUsage { .. } => "W0002",
+            SemanticWarningKind::UnreachablePattern { .. } => "W0003",
+            SemanticWarningKind::FutureKeyword { .. } => "W0004",
+        }
+    }
+}
+
+impl fmt::Display for SemanticWarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SemanticWarningKind::DeprecatedUsage { name, since, note } => {
+                write!(f, "use of deprecated item '{}'", name)?;
+                if let Some(since) = since {
+                    write!(f, " (deprecated since {})", since)?;
+                }
+                if let Some(note) = note {
+                    write!(f, ": {}", note)?;
+                }
+                Ok(())
+            }
+            SemanticWarningKind::UnstableUsage { name, note } => {
+                write!(f, "use of unstable item '{}'", name)?;
+                if let Some(note) = note {
+                    write!(f, ": {}", note)?;
+                }
+                Ok(())
+            }
+            SemanticWarningKind::UnreachablePattern { arm } => {
+                write!(
+                    f,
+                    "unreachable pattern: match arm {} is covered by earlier arms",
+                    arm
+                )
+            }
+            SemanticWarningKind::FutureKeyword { name, edition } => {
+                write!(
+                    f,
+                    "'{}' is a keyword in edition {}; run `script fix --edition {}` to rename it to 'r#{}'",
+                    name, edition, edition, name
+                )
+            }
+        }
+    }
+}
+
+/// Semantic warning with location information
+///
+/// Warnings never stop compilation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticWarning {
+    pub kind: SemanticWarningKind,
+    pub span: Span,
+}
+
+impl SemanticWarning {
+    pub fn new(kind: SemanticWarningKind, span: Span) -> Self {
+        SemanticWarning { kind, span }
+    }
+
+    /// Name of the deprecated or unstable item that was used
+    pub fn item_name(&self) -> Option<&str> {
+        match &self.kind {
+            SemanticWarningKind::DeprecatedUsage { name, .. }
+            | SemanticWarningKind::UnstableUsage { name, .. } => Some(name),
+            SemanticWarningKind::UnreachablePattern { .. }
+            | SemanticWarningKind::FutureKeyword { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for SemanticWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.span.start.line, self.span.start.column, self.kind
+        )
+    }
 }
 
 #[cfg(test)]
@@ -878,6 +1112,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_did_you_mean() {
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        let notes = |err: SemanticError| err.notes;
+
+        let err = SemanticError::undefined_variable("x", make_span());
+        assert!(notes(err.clone().with_did_you_mean(&[])).is_empty());
+        assert_eq!(
+            notes(err.clone().with_did_you_mean(&names(&["y"]))),
+            vec!["help: did you mean 'y'?"]
+        );
+        assert_eq!(
+            notes(err.with_did_you_mean(&names(&["a", "b", "c"]))),
+            vec!["help: did you mean 'a', 'b' or 'c'?"]
+        );
+    }
+
     #[test]
     fn test_error_with_notes() {
         let err = SemanticError::undefined_variable("x", make_span())
@@ -886,9 +1142,15 @@ mod tests {
 
         let general_err = err.into_error();
         assert!(general_err.message.contains("undefined variable 'x'"));
-        assert!(general_err.message.contains("did you mean 'y'?"));
+        assert_eq!(
+            general_err.notes,
+            vec![
+                "did you mean 'y'?".to_string(),
+                "variables must be declared before use".to_string()
+            ]
+        );
         assert!(general_err
-            .message
+            .to_string()
             .contains("variables must be declared before use"));
     }
 
@@ -906,8 +1168,6 @@ mod tests {
         assert!(general_err
             .message
             .contains("return type mismatch: expected unknown, found i32"));
-        assert!(general_err
-            .message
-            .contains("function has no return type annotation"));
+        assert!(general_err.notes[0].contains("function has no return type annotation"));
     }
 }
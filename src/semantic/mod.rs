@@ -8,10 +8,11 @@ mod symbol;
 mod symbol_table;
 
 pub use analyzer::{GenericInstantiation, SemanticAnalyzer};
-pub use error::{SemanticError, SemanticErrorKind};
+pub use error::{SemanticError, SemanticErrorKind, SemanticWarning, SemanticWarningKind};
 pub use memory_safety::{MemorySafetyContext, MemorySafetyViolation};
 pub use symbol::{
-    EnumInfo, EnumVariantInfo, EnumVariantType, FunctionSignature, StructInfo, Symbol, SymbolKind,
+    EnumInfo, EnumVariantInfo, EnumVariantType, FunctionSignature, Stability, StructInfo, Symbol,
+    SymbolKind,
 };
 pub use symbol_table::{ScopeId, SymbolTable};
 
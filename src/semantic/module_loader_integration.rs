@@ -1,3 +1,4 @@
+use crate::compilation::hermetic;
 use crate::compilation::module_loader::ModuleLoader;
 use crate::error::{Error, ErrorKind, Result};
 use crate::lexer::Lexer;
@@ -18,6 +19,9 @@ pub struct ModuleLoaderIntegration {
     loaded_modules: Arc<Mutex<HashMap<String, LoadedModule>>>,
     /// Current file path for resolving relative imports
     current_file: Option<PathBuf>,
+    /// Sources to use instead of the files on disk, such as unsaved editor
+    /// buffers, keyed by canonical path
+    source_overrides: Arc<HashMap<PathBuf, String>>,
 }
 
 /// Represents a loaded and parsed module
@@ -40,6 +44,7 @@ impl ModuleLoaderIntegration {
             module_loader: ModuleLoader::new(),
             loaded_modules: Arc::new(Mutex::new(HashMap::new())),
             current_file: None,
+            source_overrides: Arc::new(HashMap::new()),
         }
     }
 
@@ -48,6 +53,23 @@ impl ModuleLoaderIntegration {
         self.current_file = file;
     }
 
+    /// Read modules from `sources` rather than from disk where a path
+    /// matches, including modules those modules import
+    pub fn set_source_overrides(&mut self, sources: Arc<HashMap<PathBuf, String>>) {
+        self.source_overrides = sources;
+    }
+
+    /// Source of the module at `file_path`, from the overrides or from disk
+    fn read_source(&self, file_path: &Path) -> std::io::Result<String> {
+        let canonical = file_path
+            .canonicalize()
+            .unwrap_or_else(|_| file_path.to_path_buf());
+        match self.source_overrides.get(&canonical) {
+            Some(source) => Ok(source.clone()),
+            None => fs::read_to_string(file_path),
+        }
+    }
+
     /// Load and parse a module, returning its symbol table
     pub fn load_module(&mut self, module_path: &str, span: Span) -> Result<SymbolTable> {
         // Check if module is already loaded
@@ -90,8 +112,9 @@ impl ModuleLoaderIntegration {
         module_name: &str,
         span: Span,
     ) -> Result<LoadedModule> {
-        // Read the source file
-        let source = fs::read_to_string(file_path).map_err(|e| {
+        // Read the source file, unless a hermetic build did not declare it
+        hermetic::check_read(file_path).map_err(|e| e.with_location(span.start))?;
+        let source = self.read_source(file_path).map_err(|e| {
             Error::new(
                 ErrorKind::FileError,
                 format!("Failed to read module '{}': {}", module_name, e),
@@ -123,6 +146,7 @@ impl ModuleLoaderIntegration {
         // Set up module context
         let old_file = file_path.to_path_buf();
         analyzer.set_current_file(Some(old_file.clone()));
+        analyzer.set_source_overrides(self.source_overrides.clone());
 
         // Analyze the module
         analyzer.analyze_program(&ast).map_err(|e| {
@@ -1,14 +1,29 @@
 //! Pattern exhaustiveness checking for match expressions
 //!
-//! This module implements an algorithm to verify that match expressions
-//! cover all possible cases, ensuring no runtime panics from missing patterns.
+//! This module implements the usefulness algorithm from Maranget's
+//! "Warnings for pattern matching". A pattern is *useful* with respect to a
+//! list of earlier patterns if some value matches it but none of them:
+//!
+//! - **Reachability**: an arm whose pattern is not useful against the arms
+//!   before it can never run
+//! - **Exhaustiveness**: a match is exhaustive when a wildcard after its last
+//!   arm would not be useful; the values such a wildcard would catch are
+//!   reported as missing patterns
+//!
+//! Patterns are reduced to constructors (enum variants, booleans, tuples,
+//! fixed-length arrays and literals) applied to sub-patterns, and checked
+//! column by column against a pattern matrix. Arms with guards can fail at
+//! runtime, so they never make later arms unreachable and don't count
+//! towards exhaustiveness.
 
 use crate::parser::{Literal, MatchArm, Pattern, PatternKind};
-use crate::semantic::symbol::EnumInfo;
+use crate::semantic::symbol::EnumVariantType;
 use crate::semantic::{SymbolKind, SymbolTable};
 use crate::source::Span;
 use crate::types::Type;
-use std::collections::HashSet;
+
+/// Maximum number of missing patterns reported for one match
+const MAX_MISSING_PATTERNS: usize = 10;
 
 /// Result of exhaustiveness checking
 #[derive(Debug)]
@@ -23,23 +38,33 @@ pub struct ExhaustivenessResult {
     pub has_guards: bool,
 }
 
-/// Pattern matrix for exhaustiveness checking
-#[derive(Debug)]
-struct PatternMatrix {
-    /// Each row is a pattern arm
-    rows: Vec<PatternRow>,
+/// A way of building a value that patterns can test for
+#[derive(Debug, Clone, PartialEq)]
+enum Constructor {
+    Bool(bool),
+    /// Enum variant, by name
+    Variant(String),
+    Tuple,
+    /// Array of a fixed length
+    Array(usize),
+    /// Number, string or null literal, by its source form
+    Literal(String),
+    /// A pattern the checker can't see into, such as an object pattern.
+    /// It only matches itself, so it never covers other patterns.
+    Opaque(usize),
 }
 
-#[derive(Debug)]
-struct PatternRow {
-    /// The patterns in this row
-    patterns: Vec<Pattern>,
-    /// The original arm index (for error reporting)
-    arm_index: usize,
-    /// The span of the original pattern
-    span: Span,
+/// A pattern reduced to constructors and wildcards
+#[derive(Debug, Clone)]
+enum Pat {
+    Wild,
+    Ctor(Constructor, Vec<Pat>),
+    Or(Vec<Pat>),
 }
 
+/// One row of the pattern matrix, with a pattern per column
+type Row = Vec<Pat>;
+
 /// Check if a set of match arms is exhaustive for a given type
 pub fn check_exhaustiveness(
     arms: &[MatchArm],
@@ -47,539 +72,478 @@ pub fn check_exhaustiveness(
     _scrutinee_span: Span,
     symbol_table: &SymbolTable,
 ) -> ExhaustivenessResult {
-    // Build the pattern matrix
-    let mut matrix = PatternMatrix::new();
+    let mut checker = Checker::new(symbol_table);
+    let types = [scrutinee_type.clone()];
 
+    // Walk the arms in order, keeping the unguarded ones as the matrix
+    // later arms are checked against
+    let mut matrix: Vec<Row> = Vec::new();
+    let mut redundant_patterns = Vec::new();
     for (index, arm) in arms.iter().enumerate() {
-        matrix.add_row(vec![arm.pattern.clone()], index, arm.pattern.span);
+        let row = vec![checker.lower(&arm.pattern)];
+        if !checker.is_useful(&matrix, &row, &types) {
+            redundant_patterns.push((index, arm.pattern.span));
+        }
+        if arm.guard.is_none() {
+            matrix.push(row);
+        }
     }
 
-    // Check for wildcard or catch-all pattern WITHOUT guards
-    // A pattern with a guard doesn't guarantee exhaustiveness
-    let has_wildcard = arms.iter().any(|arm| {
-        arm.guard.is_none()
-            && (matches!(arm.pattern.kind, PatternKind::Wildcard)
-                || matches!(arm.pattern.kind, PatternKind::Identifier(_)))
-    });
-
-    // Simple exhaustiveness check for now
-    // TODO: Implement full pattern exhaustiveness algorithm
-    let is_exhaustive =
-        has_wildcard || check_simple_exhaustiveness(arms, scrutinee_type, symbol_table);
-
-    // Find redundant patterns
-    let redundant_patterns = find_redundant_patterns(arms);
-
-    // Generate missing patterns
-    let missing_patterns = if !is_exhaustive {
-        generate_missing_patterns(arms, scrutinee_type, symbol_table)
-    } else {
-        vec![]
-    };
-
-    // Check if any patterns have guards
-    let has_guards = arms.iter().any(|arm| arm.guard.is_some());
+    let missing_patterns: Vec<String> = checker
+        .missing(&matrix, &types)
+        .into_iter()
+        .filter_map(|witness| witness.first().map(ToString::to_string))
+        .collect();
 
     ExhaustivenessResult {
-        is_exhaustive,
+        is_exhaustive: missing_patterns.is_empty(),
         missing_patterns,
         redundant_patterns,
-        has_guards,
+        has_guards: arms.iter().any(|arm| arm.guard.is_some()),
     }
 }
 
-/// Simple exhaustiveness check for basic types
-fn check_simple_exhaustiveness(
-    arms: &[MatchArm],
-    scrutinee_type: &Type,
-    symbol_table: &SymbolTable,
-) -> bool {
-    match scrutinee_type {
-        Type::Bool => {
-            // Check if both true and false are covered
-            let mut has_true = false;
-            let mut has_false = false;
-
-            for arm in arms {
-                // Only count patterns without guards for exhaustiveness
-                if arm.guard.is_none() {
-                    if let PatternKind::Literal(Literal::Boolean(b)) = &arm.pattern.kind {
-                        if *b {
-                            has_true = true;
-                        } else {
-                            has_false = true;
-                        }
-                    }
-                }
-            }
-
-            has_true && has_false
-        }
-        Type::I32 | Type::F32 | Type::String => {
-            // For numeric and string types, we can't enumerate all values
-            // So we require a wildcard or identifier pattern
-            false
-        }
-        Type::Array(_) => {
-            // Arrays require more complex pattern matching
-            // For now, require a wildcard
-            false
-        }
-        Type::Result { .. } => {
-            // For Result<T, E>, we need to check Ok and Err variants
-            check_result_exhaustiveness(arms)
-        }
-        Type::Option(_) => {
-            // For Option<T>, we need to check Some and None variants
-            check_option_exhaustiveness(arms)
-        }
-        Type::Named(name) => {
-            // Check if this is an enum type
-            if let Some(symbol) = symbol_table.lookup(name) {
-                if let SymbolKind::Enum(enum_info) = &symbol.kind {
-                    return check_enum_exhaustiveness(arms, name, enum_info);
-                }
-            }
-            // For non-enum named types, require a wildcard
-            false
-        }
-        _ => false,
-    }
+/// Usefulness checking against the enums in a symbol table
+struct Checker<'a> {
+    symbol_table: &'a SymbolTable,
+    /// Number of opaque patterns seen, used to tell them apart
+    opaque_count: usize,
 }
 
-/// Check if enum patterns are exhaustive
-fn check_enum_exhaustiveness(arms: &[MatchArm], _enum_name: &str, enum_info: &EnumInfo) -> bool {
-    use std::collections::HashSet;
-
-    // Collect all variant names
-    let all_variants: HashSet<&str> = enum_info.variants.iter().map(|v| v.name.as_str()).collect();
-
-    // Track which variants are covered
-    let mut covered_variants = HashSet::new();
-
-    for arm in arms {
-        // Skip patterns with guards as they don't guarantee coverage
-        if arm.guard.is_some() {
-            continue;
+impl<'a> Checker<'a> {
+    fn new(symbol_table: &'a SymbolTable) -> Self {
+        Checker {
+            symbol_table,
+            opaque_count: 0,
         }
+    }
 
-        match &arm.pattern.kind {
-            PatternKind::EnumConstructor { variant, .. } => {
-                covered_variants.insert(variant.as_str());
-            }
-            PatternKind::Wildcard | PatternKind::Identifier(_) => {
-                // Wildcard or identifier patterns cover all variants
-                return true;
+    /// Reduce a parsed pattern to constructors and wildcards
+    fn lower(&mut self, pattern: &Pattern) -> Pat {
+        match &pattern.kind {
+            PatternKind::Wildcard | PatternKind::Identifier(_) => Pat::Wild,
+            PatternKind::Binding { pattern, .. } => self.lower(pattern),
+            PatternKind::Literal(Literal::Boolean(b)) => Pat::Ctor(Constructor::Bool(*b), vec![]),
+            PatternKind::Literal(literal) => {
+                Pat::Ctor(Constructor::Literal(literal.to_string()), vec![])
             }
-            PatternKind::Or(patterns) => {
-                // Check each alternative in the or-pattern
-                for pattern in patterns {
-                    if let PatternKind::EnumConstructor { variant, .. } = &pattern.kind {
-                        covered_variants.insert(variant.as_str());
-                    }
-                }
+            PatternKind::Tuple(patterns) => Pat::Ctor(Constructor::Tuple, self.lower_all(patterns)),
+            PatternKind::Array(patterns) => {
+                Pat::Ctor(Constructor::Array(patterns.len()), self.lower_all(patterns))
             }
-            _ => {
-                // Other pattern kinds don't match enum constructors
+            PatternKind::Or(patterns) => Pat::Or(self.lower_all(patterns)),
+            PatternKind::EnumConstructor { variant, args, .. } => Pat::Ctor(
+                Constructor::Variant(variant.clone()),
+                args.as_deref()
+                    .map_or_else(Vec::new, |args| self.lower_all(args)),
+            ),
+            PatternKind::Object(_) => {
+                self.opaque_count += 1;
+                Pat::Ctor(Constructor::Opaque(self.opaque_count), vec![])
             }
         }
     }
 
-    // Check if all variants are covered
-    all_variants == covered_variants
-}
-
-/// Check if Result<T, E> patterns are exhaustive
-fn check_result_exhaustiveness(arms: &[MatchArm]) -> bool {
-    let mut has_ok = false;
-    let mut has_err = false;
+    fn lower_all(&mut self, patterns: &[Pattern]) -> Vec<Pat> {
+        patterns.iter().map(|pattern| self.lower(pattern)).collect()
+    }
 
-    for arm in arms {
-        // Skip patterns with guards as they don't guarantee coverage
-        if arm.guard.is_some() {
-            continue;
-        }
+    /// Check if some value matches `row` but no row of `matrix`
+    fn is_useful(&self, matrix: &[Row], row: &[Pat], types: &[Type]) -> bool {
+        let Some((head, rest)) = row.split_first() else {
+            return matrix.is_empty();
+        };
 
-        match &arm.pattern.kind {
-            PatternKind::EnumConstructor {
-                enum_name, variant, ..
-            } => {
-                // Check if this is a Result variant
-                if enum_name.as_deref() == Some("Result") || variant == "Ok" || variant == "Err" {
-                    match variant.as_str() {
-                        "Ok" => has_ok = true,
-                        "Err" => has_err = true,
-                        _ => {}
-                    }
-                }
-            }
-            PatternKind::Wildcard | PatternKind::Identifier(_) => {
-                // Wildcard or identifier patterns cover all variants
-                return true;
+        match head {
+            Pat::Or(alternatives) => alternatives
+                .iter()
+                .any(|alternative| self.is_useful(matrix, &with_head(alternative, rest), types)),
+            Pat::Ctor(ctor, fields) => {
+                let ty = self.column_type(&types[0], matrix, Some(head));
+                let arity = fields.len();
+                let mut row = fields.clone();
+                row.extend_from_slice(rest);
+                self.is_useful(
+                    &specialize(matrix, ctor, arity),
+                    &row,
+                    &self.specialized_types(ctor, &ty, arity, &types[1..]),
+                )
             }
-            PatternKind::Or(patterns) => {
-                // Check each alternative in the or-pattern
-                for pattern in patterns {
-                    if let PatternKind::EnumConstructor { variant, .. } = &pattern.kind {
-                        match variant.as_str() {
-                            "Ok" => has_ok = true,
-                            "Err" => has_err = true,
-                            _ => {}
-                        }
-                    }
+            Pat::Wild => {
+                let ty = self.column_type(&types[0], matrix, None);
+                match self.complete_signature(matrix, &ty) {
+                    // Every constructor appears in this column, so the
+                    // wildcard is useful if it is useful for one of them
+                    Some(ctors) => ctors.iter().any(|ctor| {
+                        let arity = self.arity(ctor, &ty, matrix);
+                        let mut row = vec![Pat::Wild; arity];
+                        row.extend_from_slice(rest);
+                        self.is_useful(
+                            &specialize(matrix, ctor, arity),
+                            &row,
+                            &self.specialized_types(ctor, &ty, arity, &types[1..]),
+                        )
+                    }),
+                    // Values built with a missing constructor only match
+                    // the rows starting with a wildcard
+                    None => self.is_useful(&default_matrix(matrix), rest, &types[1..]),
                 }
             }
-            _ => {
-                // Other pattern kinds don't match Result
-            }
         }
     }
 
-    has_ok && has_err
-}
-
-/// Check if Option<T> patterns are exhaustive
-fn check_option_exhaustiveness(arms: &[MatchArm]) -> bool {
-    let mut has_some = false;
-    let mut has_none = false;
-
-    for arm in arms {
-        // Skip patterns with guards as they don't guarantee coverage
-        if arm.guard.is_some() {
-            continue;
-        }
+    /// Values that no row of `matrix` matches, as one pattern per column
+    ///
+    /// At most `MAX_MISSING_PATTERNS` are returned.
+    fn missing(&self, matrix: &[Row], types: &[Type]) -> Vec<Row> {
+        let Some((ty, rest_types)) = types.split_first() else {
+            return if matrix.is_empty() {
+                vec![Vec::new()]
+            } else {
+                Vec::new()
+            };
+        };
 
-        match &arm.pattern.kind {
-            PatternKind::EnumConstructor {
-                enum_name, variant, ..
-            } => {
-                // Check if this is an Option variant
-                if enum_name.as_deref() == Some("Option") || variant == "Some" || variant == "None"
-                {
-                    match variant.as_str() {
-                        "Some" => has_some = true,
-                        "None" => has_none = true,
-                        _ => {}
+        let ty = self.column_type(ty, matrix, None);
+        let mut witnesses = Vec::new();
+
+        if let Some(ctors) = self.complete_signature(matrix, &ty) {
+            for ctor in ctors {
+                let arity = self.arity(&ctor, &ty, matrix);
+                let types = self.specialized_types(&ctor, &ty, arity, rest_types);
+                for mut fields in self.missing(&specialize(matrix, &ctor, arity), &types) {
+                    let rest = fields.split_off(arity);
+                    witnesses.push(with_head(&Pat::Ctor(ctor.clone(), fields), &rest));
+                    if witnesses.len() >= MAX_MISSING_PATTERNS {
+                        return witnesses;
                     }
                 }
             }
-            PatternKind::Wildcard | PatternKind::Identifier(_) => {
-                // Wildcard or identifier patterns cover all variants
-                return true;
-            }
-            PatternKind::Or(patterns) => {
-                // Check each alternative in the or-pattern
-                for pattern in patterns {
-                    if let PatternKind::EnumConstructor { variant, .. } = &pattern.kind {
-                        match variant.as_str() {
-                            "Some" => has_some = true,
-                            "None" => has_none = true,
-                            _ => {}
-                        }
-                    }
-                }
-            }
-            _ => {
-                // Other pattern kinds don't match Option
-            }
+            return witnesses;
         }
-    }
 
-    has_some && has_none
-}
+        let rest_witnesses = self.missing(&default_matrix(matrix), rest_types);
+        if rest_witnesses.is_empty() {
+            return witnesses;
+        }
 
-/// Find patterns that are redundant (unreachable)
-fn find_redundant_patterns(arms: &[MatchArm]) -> Vec<(usize, Span)> {
-    let mut redundant = Vec::new();
-    let mut covered_patterns = Vec::new();
+        // Name the missing constructors when the type has finitely many
+        let heads = head_constructors(matrix);
+        let missing_heads: Vec<Pat> = match self.all_constructors(&ty) {
+            Some(ctors) => ctors
+                .into_iter()
+                .filter(|ctor| !heads.contains(ctor))
+                .map(|ctor| {
+                    let arity = self.arity(&ctor, &ty, matrix);
+                    Pat::Ctor(ctor, vec![Pat::Wild; arity])
+                })
+                .collect(),
+            None => vec![Pat::Wild],
+        };
 
-    for (index, arm) in arms.iter().enumerate() {
-        // Check if this pattern is subsumed by previous patterns
-        if is_pattern_redundant(&arm.pattern, &covered_patterns, arm.guard.is_some()) {
-            redundant.push((index, arm.pattern.span));
+        for head in &missing_heads {
+            for rest in &rest_witnesses {
+                witnesses.push(with_head(head, rest));
+                if witnesses.len() >= MAX_MISSING_PATTERNS {
+                    return witnesses;
+                }
+            }
         }
+        witnesses
+    }
 
-        // Only add to covered patterns if there's no guard
-        // Patterns with guards don't fully cover their pattern space
-        if arm.guard.is_none() {
-            covered_patterns.push(&arm.pattern);
+    /// The constructors of `ty`, if every one of them heads a row of `matrix`
+    fn complete_signature(&self, matrix: &[Row], ty: &Type) -> Option<Vec<Constructor>> {
+        let ctors = self.all_constructors(ty)?;
+        let heads = head_constructors(matrix);
+        if ctors.iter().all(|ctor| heads.contains(ctor)) {
+            Some(ctors)
+        } else {
+            None
         }
     }
 
-    redundant
-}
-
-/// Check if a pattern is redundant given previously seen patterns
-fn is_pattern_redundant(pattern: &Pattern, previous: &[&Pattern], has_guard: bool) -> bool {
-    // A pattern with a guard is never redundant because the guard might fail
-    if has_guard {
-        return false;
-    }
+    /// The constructors of `ty`, if it has finitely many
+    fn all_constructors(&self, ty: &Type) -> Option<Vec<Constructor>> {
+        let variants = |names: &[&str]| -> Option<Vec<Constructor>> {
+            Some(
+                names
+                    .iter()
+                    .map(|name| Constructor::Variant(name.to_string()))
+                    .collect(),
+            )
+        };
 
-    // A pattern is redundant if it's subsumed by any previous pattern
-    for prev in previous {
-        if pattern_subsumes(prev, pattern) {
-            return true;
+        match ty {
+            Type::Bool => Some(vec![Constructor::Bool(true), Constructor::Bool(false)]),
+            Type::Option(_) => variants(&["Some", "None"]),
+            Type::Result { .. } => variants(&["Ok", "Err"]),
+            Type::Tuple(_) => Some(vec![Constructor::Tuple]),
+            Type::Named(name) | Type::Generic { name, .. } => match self.enum_variants(name) {
+                Some(declared) => Some(
+                    declared
+                        .iter()
+                        .map(|(name, _)| Constructor::Variant(name.clone()))
+                        .collect(),
+                ),
+                None if name == "Option" => variants(&["Some", "None"]),
+                None if name == "Result" => variants(&["Ok", "Err"]),
+                None => None,
+            },
+            _ => None,
         }
     }
 
-    false
-}
-
-/// Check if pattern1 subsumes pattern2 (i.e., pattern1 matches everything pattern2 does)
-fn pattern_subsumes(pattern1: &Pattern, pattern2: &Pattern) -> bool {
-    match (&pattern1.kind, &pattern2.kind) {
-        // Wildcard and identifier patterns subsume everything
-        (PatternKind::Wildcard, _) | (PatternKind::Identifier(_), _) => true,
-
-        // Same literals
-        (PatternKind::Literal(lit1), PatternKind::Literal(lit2)) => lit1 == lit2,
-
-        // Array patterns
-        (PatternKind::Array(pats1), PatternKind::Array(pats2)) => {
-            pats1.len() == pats2.len()
-                && pats1
-                    .iter()
-                    .zip(pats2.iter())
-                    .all(|(p1, p2)| pattern_subsumes(p1, p2))
-        }
+    /// Variants of a declared enum, with their field types
+    fn enum_variants(&self, name: &str) -> Option<Vec<(String, Vec<Type>)>> {
+        let symbol = self.symbol_table.lookup(name)?;
+        let SymbolKind::Enum(enum_info) = &symbol.kind else {
+            return None;
+        };
+        Some(
+            enum_info
+                .variants
+                .iter()
+                .map(|variant| {
+                    let fields = match &variant.variant_type {
+                        EnumVariantType::Unit => Vec::new(),
+                        EnumVariantType::Tuple(types) => types.clone(),
+                        EnumVariantType::Struct(fields) => {
+                            fields.iter().map(|(_, ty)| ty.clone()).collect()
+                        }
+                    };
+                    (variant.name.clone(), fields)
+                })
+                .collect(),
+        )
+    }
 
-        // Enum constructor patterns
-        (
-            PatternKind::EnumConstructor {
-                enum_name: en1,
-                variant: v1,
-                args: args1,
+    /// Types of the fields `ctor` has in a value of type `ty`, if known
+    fn field_types(&self, ctor: &Constructor, ty: &Type) -> Option<Vec<Type>> {
+        match (ctor, ty) {
+            (Constructor::Variant(variant), Type::Option(inner)) => match variant.as_str() {
+                "Some" => Some(vec![(**inner).clone()]),
+                _ => Some(Vec::new()),
             },
-            PatternKind::EnumConstructor {
-                enum_name: en2,
-                variant: v2,
-                args: args2,
+            (Constructor::Variant(variant), Type::Result { ok, err }) => match variant.as_str() {
+                "Ok" => Some(vec![(**ok).clone()]),
+                _ => Some(vec![(**err).clone()]),
             },
-        ) => {
-            // Must be the same variant
-            v1 == v2 && en1 == en2 &&
-            // And arguments must match
-            match (args1, args2) {
-                (None, None) => true,
-                (Some(a1), Some(a2)) => {
-                    a1.len() == a2.len() &&
-                    a1.iter().zip(a2.iter()).all(|(p1, p2)| pattern_subsumes(p1, p2))
-                }
-                _ => false,
+            (Constructor::Variant(variant), Type::Named(name) | Type::Generic { name, .. }) => self
+                .enum_variants(name)?
+                .into_iter()
+                .find(|(variant_name, _)| variant_name == variant)
+                .map(|(_, fields)| fields),
+            (Constructor::Tuple, Type::Tuple(types)) => Some(types.clone()),
+            (Constructor::Array(len), Type::Array(element)) => {
+                Some(vec![(**element).clone(); *len])
             }
+            (Constructor::Array(len), _) => Some(vec![Type::Unknown; *len]),
+            (Constructor::Bool(_), _)
+            | (Constructor::Literal(_), _)
+            | (Constructor::Opaque(_), _) => Some(Vec::new()),
+            _ => None,
         }
+    }
 
-        // Or patterns
-        (PatternKind::Or(pats), _) => {
-            // An or-pattern subsumes if any of its alternatives subsume
-            pats.iter().any(|p| pattern_subsumes(p, pattern2))
-        }
-        (_, PatternKind::Or(pats)) => {
-            // pattern1 subsumes an or-pattern if it subsumes all alternatives
-            pats.iter().all(|p| pattern_subsumes(pattern1, p))
-        }
-
-        _ => false,
+    /// Column types after specializing the first column by `ctor`
+    fn specialized_types(
+        &self,
+        ctor: &Constructor,
+        ty: &Type,
+        arity: usize,
+        rest: &[Type],
+    ) -> Vec<Type> {
+        let mut types = self.field_types(ctor, ty).unwrap_or_default();
+        types.resize(arity, Type::Unknown);
+        types.extend_from_slice(rest);
+        types
     }
-}
 
-/// Generate descriptions of missing patterns
-fn generate_missing_patterns(
-    arms: &[MatchArm],
-    scrutinee_type: &Type,
-    symbol_table: &SymbolTable,
-) -> Vec<String> {
-    let mut missing = Vec::new();
-
-    match scrutinee_type {
-        Type::Bool => {
-            let mut has_true = false;
-            let mut has_false = false;
-
-            for arm in arms {
-                if let PatternKind::Literal(Literal::Boolean(b)) = &arm.pattern.kind {
-                    if *b {
-                        has_true = true;
-                    } else {
-                        has_false = true;
-                    }
-                }
-            }
+    /// Number of fields `ctor` has, preferring the patterns in `matrix`
+    fn arity(&self, ctor: &Constructor, ty: &Type, matrix: &[Row]) -> usize {
+        matrix
+            .iter()
+            .flat_map(|row| expand_head(row))
+            .find_map(|row| match row.first() {
+                Some(Pat::Ctor(head, fields)) if head == ctor => Some(fields.len()),
+                _ => None,
+            })
+            .or_else(|| self.field_types(ctor, ty).map(|types| types.len()))
+            .unwrap_or(0)
+    }
 
-            if !has_true {
-                missing.push("true".to_string());
-            }
-            if !has_false {
-                missing.push("false".to_string());
-            }
-        }
-        Type::I32 => {
-            missing.push("_ (or any integer pattern)".to_string());
-        }
-        Type::F32 => {
-            missing.push("_ (or any float pattern)".to_string());
-        }
-        Type::String => {
-            missing.push("_ (or any string pattern)".to_string());
+    /// The type of the first column, inferred from its patterns when the
+    /// scrutinee's type is not known
+    fn column_type(&self, ty: &Type, matrix: &[Row], head: Option<&Pat>) -> Type {
+        if !matches!(ty, Type::Unknown | Type::TypeVar(_) | Type::TypeParam(_)) {
+            return ty.clone();
         }
-        Type::Array(_) => {
-            missing.push("_ (or any array pattern)".to_string());
-        }
-        Type::Result { .. } => {
-            let mut has_ok = false;
-            let mut has_err = false;
-
-            for arm in arms {
-                if arm.guard.is_none() {
-                    match &arm.pattern.kind {
-                        PatternKind::EnumConstructor { variant, .. } => match variant.as_str() {
-                            "Ok" => has_ok = true,
-                            "Err" => has_err = true,
-                            _ => {}
-                        },
-                        PatternKind::Or(patterns) => {
-                            for pattern in patterns {
-                                if let PatternKind::EnumConstructor { variant, .. } = &pattern.kind
-                                {
-                                    match variant.as_str() {
-                                        "Ok" => has_ok = true,
-                                        "Err" => has_err = true,
-                                        _ => {}
-                                    }
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
 
-            if !has_ok {
-                missing.push("Ok(_)".to_string());
-            }
-            if !has_err {
-                missing.push("Err(_)".to_string());
-            }
+        let mut ctors = head_constructors(matrix);
+        if let Some(Pat::Ctor(ctor, _)) = head {
+            ctors.push(ctor.clone());
         }
-        Type::Option(_) => {
-            let mut has_some = false;
-            let mut has_none = false;
-
-            for arm in arms {
-                if arm.guard.is_none() {
-                    match &arm.pattern.kind {
-                        PatternKind::EnumConstructor { variant, .. } => match variant.as_str() {
-                            "Some" => has_some = true,
-                            "None" => has_none = true,
-                            _ => {}
-                        },
-                        PatternKind::Or(patterns) => {
-                            for pattern in patterns {
-                                if let PatternKind::EnumConstructor { variant, .. } = &pattern.kind
-                                {
-                                    match variant.as_str() {
-                                        "Some" => has_some = true,
-                                        "None" => has_none = true,
-                                        _ => {}
-                                    }
-                                }
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
 
-            if !has_some {
-                missing.push("Some(_)".to_string());
-            }
-            if !has_none {
-                missing.push("None".to_string());
-            }
-        }
-        Type::Named(name) => {
-            // Check if this is an enum type
-            if let Some(symbol) = symbol_table.lookup(name) {
-                if let SymbolKind::Enum(enum_info) = &symbol.kind {
-                    // Find missing enum variants
-                    let mut covered_variants = HashSet::new();
-
-                    for arm in arms {
-                        if arm.guard.is_none() {
-                            match &arm.pattern.kind {
-                                PatternKind::EnumConstructor { variant, .. } => {
-                                    covered_variants.insert(variant.as_str());
-                                }
-                                PatternKind::Or(patterns) => {
-                                    for pattern in patterns {
-                                        if let PatternKind::EnumConstructor { variant, .. } =
-                                            &pattern.kind
-                                        {
-                                            covered_variants.insert(variant.as_str());
-                                        }
-                                    }
-                                }
-                                _ => {}
-                            }
+        for ctor in ctors {
+            match &ctor {
+                Constructor::Bool(_) => return Type::Bool,
+                Constructor::Tuple => {
+                    let arity = self.arity(&ctor, ty, matrix);
+                    return Type::Tuple(vec![Type::Unknown; arity]);
+                }
+                Constructor::Variant(variant) => match variant.as_str() {
+                    "Some" | "None" => return Type::Option(Box::new(Type::Unknown)),
+                    "Ok" | "Err" => {
+                        return Type::Result {
+                            ok: Box::new(Type::Unknown),
+                            err: Box::new(Type::Unknown),
                         }
                     }
-
-                    // Generate missing patterns for uncovered variants
-                    for variant_info in &enum_info.variants {
-                        if !covered_variants.contains(variant_info.name.as_str()) {
-                            use crate::semantic::symbol::EnumVariantType;
-                            let pattern = match &variant_info.variant_type {
-                                EnumVariantType::Unit => variant_info.name.clone(),
-                                EnumVariantType::Tuple(types) => {
-                                    let wildcards = vec!["_"; types.len()].join(", ");
-                                    format!("{}({})", variant_info.name, wildcards)
-                                }
-                                EnumVariantType::Struct(_fields) => {
-                                    format!("{} {{ .. }}", variant_info.name)
-                                }
-                            };
-                            missing.push(pattern);
+                    _ => {
+                        if let Some(name) = self.enum_with_variant(variant) {
+                            return Type::Named(name);
                         }
                     }
+                },
+                _ => {}
+            }
+        }
+        ty.clone()
+    }
 
-                    // If no specific variants are missing, suggest wildcard
-                    if missing.is_empty() {
-                        missing.push("_ (wildcard pattern)".to_string());
-                    }
-                } else {
-                    missing.push("_ (wildcard pattern)".to_string());
+    /// Name of a declared enum with a variant called `variant`
+    fn enum_with_variant(&self, variant: &str) -> Option<String> {
+        self.symbol_table
+            .all_symbols()
+            .find_map(|(name, symbol)| match &symbol.kind {
+                SymbolKind::Enum(enum_info)
+                    if enum_info.variants.iter().any(|v| v.name == variant) =>
+                {
+                    Some(name.clone())
                 }
-            } else {
-                missing.push("_ (wildcard pattern)".to_string());
+                _ => None,
+            })
+    }
+}
+
+/// A row starting with `head`, followed by `rest`
+fn with_head(head: &Pat, rest: &[Pat]) -> Row {
+    let mut row = Vec::with_capacity(rest.len() + 1);
+    row.push(head.clone());
+    row.extend_from_slice(rest);
+    row
+}
+
+/// Split a row whose first pattern is an or-pattern into one row per alternative
+fn expand_head(row: &[Pat]) -> Vec<Row> {
+    match row.split_first() {
+        Some((Pat::Or(alternatives), rest)) => alternatives
+            .iter()
+            .flat_map(|alternative| expand_head(&with_head(alternative, rest)))
+            .collect(),
+        _ => vec![row.to_vec()],
+    }
+}
+
+/// The distinct constructors heading the rows of `matrix`
+fn head_constructors(matrix: &[Row]) -> Vec<Constructor> {
+    let mut ctors = Vec::new();
+    for row in matrix.iter().flat_map(|row| expand_head(row)) {
+        if let Some(Pat::Ctor(ctor, _)) = row.first() {
+            if !ctors.contains(ctor) {
+                ctors.push(ctor.clone());
             }
         }
-        _ => {
-            missing.push("_ (wildcard pattern)".to_string());
-        }
     }
+    ctors
+}
 
-    missing
+/// The rows matching a value built with `ctor`, with its fields in place of
+/// the first column
+fn specialize(matrix: &[Row], ctor: &Constructor, arity: usize) -> Vec<Row> {
+    let mut specialized = Vec::new();
+    for row in matrix.iter().flat_map(|row| expand_head(row)) {
+        let Some((head, rest)) = row.split_first() else {
+            continue;
+        };
+        let mut fields = match head {
+            Pat::Ctor(head, fields) if head == ctor => fields.clone(),
+            Pat::Wild => Vec::new(),
+            _ => continue,
+        };
+        // A wrong number of fields is reported elsewhere; pad or truncate
+        // to keep the matrix rectangular
+        fields.resize(arity, Pat::Wild);
+        fields.extend_from_slice(rest);
+        specialized.push(fields);
+    }
+    specialized
+}
+
+/// The rows matching values whose constructor heads no row, without their
+/// first column
+fn default_matrix(matrix: &[Row]) -> Vec<Row> {
+    matrix
+        .iter()
+        .flat_map(|row| expand_head(row))
+        .filter(|row| matches!(row.first(), Some(Pat::Wild)))
+        .map(|row| row[1..].to_vec())
+        .collect()
 }
 
-impl PatternMatrix {
-    fn new() -> Self {
-        PatternMatrix { rows: Vec::new() }
+impl std::fmt::Display for Pat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pat::Wild | Pat::Ctor(Constructor::Opaque(_), _) => write!(f, "_"),
+            Pat::Ctor(Constructor::Bool(b), _) => write!(f, "{}", b),
+            Pat::Ctor(Constructor::Literal(literal), _) => write!(f, "{}", literal),
+            Pat::Ctor(Constructor::Variant(variant), fields) => {
+                write!(f, "{}", variant)?;
+                if fields.is_empty() {
+                    return Ok(());
+                }
+                write!(f, "(")?;
+                write_list(f, fields, ", ")?;
+                write!(f, ")")
+            }
+            Pat::Ctor(Constructor::Tuple, fields) => {
+                write!(f, "(")?;
+                write_list(f, fields, ", ")?;
+                if fields.len() == 1 {
+                    write!(f, ",")?;
+                }
+                write!(f, ")")
+            }
+            Pat::Ctor(Constructor::Array(_), fields) => {
+                write!(f, "[")?;
+                write_list(f, fields, ", ")?;
+                write!(f, "]")
+            }
+            Pat::Or(alternatives) => write_list(f, alternatives, " | "),
+        }
     }
+}
 
-    fn add_row(&mut self, patterns: Vec<Pattern>, arm_index: usize, span: Span) {
-        self.rows.push(PatternRow {
-            patterns,
-            arm_index,
-            span,
-        });
+fn write_list(
+    f: &mut std::fmt::Formatter<'_>,
+    patterns: &[Pat],
+    separator: &str,
+) -> std::fmt::Result {
+    for (i, pattern) in patterns.iter().enumerate() {
+        if i > 0 {
+            write!(f, "{}", separator)?;
+        }
+        write!(f, "{}", pattern)?;
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::parser::{Expr, ExprKind, MatchArm, Pattern, PatternKind};
+    use crate::semantic::symbol::EnumInfo;
     use crate::source::Span;
 
     fn dummy_span() -> Span {
@@ -895,4 +859,136 @@ mod tests {
         );
         assert!(result.is_exhaustive);
     }
+
+    /// Parse the arms of `match value { <arms> }`
+    fn parse_arms(arms: &str) -> Vec<MatchArm> {
+        use crate::lexer::Lexer;
+        use crate::parser::{Parser, StmtKind};
+
+        let source = format!("match value {{ {} }}", arms);
+        let (tokens, errors) = Lexer::new(&source).unwrap().scan_tokens();
+        assert!(errors.is_empty());
+        let program = Parser::new(tokens).parse().unwrap();
+        match &program.statements[0].kind {
+            StmtKind::Expression(Expr {
+                kind: ExprKind::Match { arms, .. },
+                ..
+            }) => arms.clone(),
+            other => panic!("Expected a match expression, got {:?}", other),
+        }
+    }
+
+    fn option_of_bool() -> Type {
+        Type::Option(Box::new(Type::Bool))
+    }
+
+    #[test]
+    fn test_nested_patterns_exhaustive() {
+        let arms = parse_arms("Some(true) => 1, Some(false) => 2, None => 3");
+        let result =
+            check_exhaustiveness(&arms, &option_of_bool(), dummy_span(), &SymbolTable::new());
+        assert!(result.is_exhaustive);
+        assert!(result.redundant_patterns.is_empty());
+    }
+
+    #[test]
+    fn test_nested_patterns_missing() {
+        let arms = parse_arms("Some(true) => 1, None => 3");
+        let result =
+            check_exhaustiveness(&arms, &option_of_bool(), dummy_span(), &SymbolTable::new());
+        assert!(!result.is_exhaustive);
+        assert_eq!(result.missing_patterns, vec!["Some(false)"]);
+    }
+
+    #[test]
+    fn test_nested_result_of_option_inferred() {
+        // The payload types are unknown, so they are inferred from the patterns
+        let ty = Type::Result {
+            ok: Box::new(Type::Unknown),
+            err: Box::new(Type::Unknown),
+        };
+        let arms = parse_arms("Ok(Some(n)) => n, Err(_) => 0");
+        let result = check_exhaustiveness(&arms, &ty, dummy_span(), &SymbolTable::new());
+        assert_eq!(result.missing_patterns, vec!["Ok(None)"]);
+
+        let arms = parse_arms("Ok(Some(n)) => n, Ok(None) => 0, Err(_) => 0");
+        let result = check_exhaustiveness(&arms, &ty, dummy_span(), &SymbolTable::new());
+        assert!(result.is_exhaustive);
+    }
+
+    #[test]
+    fn test_tuple_patterns() {
+        let ty = Type::Tuple(vec![Type::Bool, Type::Bool]);
+        let arms = parse_arms("(true, _) => 1, (_, true) => 2");
+        let result = check_exhaustiveness(&arms, &ty, dummy_span(), &SymbolTable::new());
+        assert_eq!(result.missing_patterns, vec!["(false, false)"]);
+
+        let arms =
+            parse_arms("(true, _) => 1, (_, true) => 2, (false, false) => 3, (true, true) => 4");
+        let result = check_exhaustiveness(&arms, &ty, dummy_span(), &SymbolTable::new());
+        assert!(result.is_exhaustive);
+        assert_eq!(result.redundant_patterns.len(), 1);
+        assert_eq!(result.redundant_patterns[0].0, 3);
+    }
+
+    #[test]
+    fn test_guarded_arms() {
+        // A guarded arm neither covers its pattern nor hides later arms
+        let arms = parse_arms("Some(x) if x => 1, Some(_) => 2, None => 3");
+        let result =
+            check_exhaustiveness(&arms, &option_of_bool(), dummy_span(), &SymbolTable::new());
+        assert!(result.is_exhaustive);
+        assert!(result.redundant_patterns.is_empty());
+        assert!(result.has_guards);
+
+        let arms = parse_arms("Some(x) if x => 1, None => 3");
+        let result =
+            check_exhaustiveness(&arms, &option_of_bool(), dummy_span(), &SymbolTable::new());
+        assert_eq!(result.missing_patterns, vec!["Some(_)"]);
+    }
+
+    #[test]
+    fn test_unreachable_after_covering_variants() {
+        let arms = parse_arms("Some(_) => 1, None => 2, Some(true) => 3, x @ None => 4");
+        let result =
+            check_exhaustiveness(&arms, &option_of_bool(), dummy_span(), &SymbolTable::new());
+        let unreachable: Vec<usize> = result
+            .redundant_patterns
+            .iter()
+            .map(|(index, _)| *index)
+            .collect();
+        assert_eq!(unreachable, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_missing_enum_variants_listed() {
+        use crate::semantic::symbol::{EnumVariantInfo, EnumVariantType};
+
+        let mut symbol_table = SymbolTable::new();
+        let variant = |name: &str, fields: Vec<Type>| EnumVariantInfo {
+            name: name.to_string(),
+            variant_type: if fields.is_empty() {
+                EnumVariantType::Unit
+            } else {
+                EnumVariantType::Tuple(fields)
+            },
+        };
+        let enum_info = EnumInfo {
+            generic_params: None,
+            variants: vec![
+                variant("Move", vec![Type::I32, Type::I32]),
+                variant("Jump", vec![]),
+                variant("Quit", vec![]),
+            ],
+            where_clause: None,
+        };
+        symbol_table
+            .define_enum("Command".to_string(), enum_info, dummy_span())
+            .unwrap();
+
+        // The scrutinee type is inferred from the variant names
+        let arms = parse_arms("Jump => 1");
+        let result = check_exhaustiveness(&arms, &Type::Unknown, dummy_span(), &symbol_table);
+        assert_eq!(result.missing_patterns, vec!["Move(_, _)", "Quit"]);
+    }
 }
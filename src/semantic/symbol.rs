@@ -25,6 +25,62 @@ pub struct Symbol {
     pub is_used: bool,
     /// Scope where this symbol was defined
     pub scope_id: super::ScopeId,
+    /// Stability declared by `@deprecated` or `@unstable`, if any
+    pub stability: Option<Stability>,
+}
+
+/// Stability of an API, declared with an attribute on its definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stability {
+    /// `@deprecated(since = "1.2", note = "use `bar` instead")`
+    Deprecated {
+        since: Option<String>,
+        note: Option<String>,
+    },
+    /// `@unstable` or `@unstable(note = "...")`
+    Unstable { note: Option<String> },
+}
+
+impl Stability {
+    /// Read the stability declared by a definition's attributes
+    ///
+    /// `@deprecated` takes precedence if both attributes are present.
+    pub fn from_attributes(attributes: &[crate::parser::Attribute]) -> Option<Self> {
+        if let Some(attr) = attributes.iter().find(|a| a.name == "deprecated") {
+            return Some(Stability::Deprecated {
+                since: attribute_arg(&attr.args, "since"),
+                note: attribute_arg(&attr.args, "note"),
+            });
+        }
+        attributes
+            .iter()
+            .find(|a| a.name == "unstable")
+            .map(|attr| Stability::Unstable {
+                note: attribute_arg(&attr.args, "note"),
+            })
+    }
+
+    pub fn is_deprecated(&self) -> bool {
+        matches!(self, Stability::Deprecated { .. })
+    }
+}
+
+/// Find the value of a `key = value` attribute argument, without quotes
+fn attribute_arg(args: &[String], key: &str) -> Option<String> {
+    args.iter().find_map(|arg| {
+        let (name, value) = arg.split_once('=')?;
+        if name.trim() != key {
+            return None;
+        }
+        let value = value.trim();
+        Some(
+            value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value)
+                .to_string(),
+        )
+    })
 }
 
 /// Different kinds of symbols
@@ -40,7 +96,7 @@ pub enum SymbolKind {
     BuiltIn,
     /// Actor (for future use)
     Actor,
-    /// Constant (for future use)
+    /// Constant
     Constant,
     /// Struct type definition
     Struct(StructInfo),
@@ -126,6 +182,7 @@ impl Symbol {
             is_mutable,
             is_used: false,
             scope_id,
+            stability: None,
         }
     }
 
@@ -151,6 +208,7 @@ impl Symbol {
             is_mutable: false,
             is_used: false,
             scope_id,
+            stability: None,
         }
     }
 
@@ -171,6 +229,28 @@ impl Symbol {
             is_mutable: false,
             is_used: false,
             scope_id,
+            stability: None,
+        }
+    }
+
+    /// Create a new constant symbol
+    pub fn constant(
+        id: SymbolId,
+        name: String,
+        ty: Type,
+        def_span: Span,
+        scope_id: super::ScopeId,
+    ) -> Self {
+        Symbol {
+            id,
+            name,
+            kind: SymbolKind::Constant,
+            ty,
+            def_span,
+            is_mutable: false,
+            is_used: false,
+            scope_id,
+            stability: None,
         }
     }
 
@@ -192,6 +272,7 @@ impl Symbol {
             is_mutable: false,
             is_used: false,
             scope_id,
+            stability: None,
         }
     }
 
@@ -213,6 +294,7 @@ impl Symbol {
             is_mutable: false,
             is_used: false,
             scope_id,
+            stability: None,
         }
     }
 
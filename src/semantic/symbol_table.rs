@@ -179,10 +179,18 @@ impl SymbolTable {
         def_span: Span,
         is_mutable: bool,
     ) -> Result<SymbolId, String> {
-        // Check if already defined in current scope (no shadowing in same scope)
-        if let Some(scope) = self.scopes.get(&self.current_scope) {
+        // Check if already defined in current scope (no shadowing in same scope),
+        // except that a program may replace a stdlib constant such as PI
+        if let Some(scope) = self.scopes.get_mut(&self.current_scope) {
             if let Some(existing_ids) = scope.symbols.get(&name) {
-                if !existing_ids.is_empty() {
+                let only_constants = existing_ids.iter().all(|id| {
+                    self.symbols
+                        .get(id)
+                        .is_some_and(|symbol| matches!(symbol.kind, SymbolKind::Constant))
+                });
+                if only_constants {
+                    scope.symbols.remove(&name);
+                } else {
                     return Err(format!("Variable '{}' already defined in this scope", name));
                 }
             }
@@ -308,6 +316,39 @@ impl SymbolTable {
         Ok(symbol_id)
     }
 
+    /// Define an immutable constant in the current scope
+    pub fn define_constant(
+        &mut self,
+        name: String,
+        ty: Type,
+        def_span: Span,
+    ) -> Result<SymbolId, String> {
+        if let Some(scope) = self.scopes.get(&self.current_scope) {
+            if let Some(existing_ids) = scope.symbols.get(&name) {
+                if !existing_ids.is_empty() {
+                    return Err(format!("Constant '{}' already defined in this scope", name));
+                }
+            }
+        }
+
+        let symbol_id = SymbolId(self.next_symbol_id);
+        self.next_symbol_id += 1;
+
+        let symbol = Symbol::constant(symbol_id, name.clone(), ty, def_span, self.current_scope);
+
+        self.symbols.insert(symbol_id, symbol);
+
+        if let Some(scope) = self.scopes.get_mut(&self.current_scope) {
+            scope
+                .symbols
+                .entry(name)
+                .or_insert_with(Vec::new)
+                .push(symbol_id);
+        }
+
+        Ok(symbol_id)
+    }
+
     /// Look up a symbol by name, searching from current scope to global
     pub fn lookup(&self, name: &str) -> Option<&Symbol> {
         self.lookup_all(name).into_iter().next()
@@ -339,6 +380,68 @@ impl SymbolTable {
         result
     }
 
+    /// The visible name closest in spelling to `name`, for "did you mean"
+    /// suggestions
+    pub fn similar_name(&self, name: &str) -> Option<String> {
+        self.similar_names(name).into_iter().next()
+    }
+
+    /// Visible names close in spelling to `name`, closest first
+    ///
+    /// Candidates are the names in scope, which include the standard library
+    /// and named imports, and the aliases of namespace imports. Ties go to
+    /// the innermost scope, then alphabetically.
+    pub fn similar_names(&self, name: &str) -> Vec<String> {
+        let mut candidates: Vec<&str> = Vec::new();
+        let mut current = Some(self.current_scope);
+        while let Some(scope) = current.and_then(|id| self.scopes.get(&id)) {
+            let mut names: Vec<&str> = scope.symbols.keys().map(String::as_str).collect();
+            names.sort();
+            candidates.extend(names);
+            current = scope.parent;
+        }
+        if let Some(module) = self.modules.get(&self.current_module) {
+            let mut aliases: Vec<&str> = module
+                .namespace_imports
+                .keys()
+                .map(String::as_str)
+                .collect();
+            aliases.sort();
+            candidates.extend(aliases);
+        }
+
+        closest_names(name, candidates)
+    }
+
+    /// Names defined by the module imported as `module_path` that are close
+    /// in spelling to `name`, closest first
+    pub fn similar_module_members(&self, module_path: &str, name: &str) -> Vec<String> {
+        let Some(scope) = self
+            .find_module(module_path)
+            .and_then(|id| self.modules.get(&id))
+            .and_then(|module| self.scopes.get(&module.root_scope))
+        else {
+            return Vec::new();
+        };
+        let mut names: Vec<&str> = scope.symbols.keys().map(String::as_str).collect();
+        names.sort();
+
+        closest_names(name, names)
+    }
+
+    /// The name of the module the current module imports as `alias` with
+    /// `import * as alias`
+    pub fn namespace_module(&self, alias: &str) -> Option<&str> {
+        let module_id = self
+            .modules
+            .get(&self.current_module)?
+            .namespace_imports
+            .get(alias)?;
+        self.modules
+            .get(module_id)
+            .map(|module| module.name.as_str())
+    }
+
     /// Look up a function by name and argument types
     pub fn lookup_function(&self, name: &str, arg_types: &[Type]) -> Option<&Symbol> {
         let candidates = self.lookup_all(name);
@@ -565,6 +668,7 @@ impl SymbolTable {
                                     is_mutable: source_symbol.is_mutable,
                                     is_used: false,
                                     scope_id: self.current_scope,
+                                    stability: source_symbol.stability.clone(),
                                 };
 
                                 // Add to our symbols and scope
@@ -662,6 +766,7 @@ impl SymbolTable {
                     is_mutable: false,
                     is_used: false,
                     scope_id: self.current_scope,
+                    stability: None,
                 };
 
                 self.symbols.insert(symbol_id, symbol);
@@ -791,6 +896,7 @@ impl SymbolTable {
                             is_mutable: source_symbol.is_mutable,
                             is_used: false,
                             scope_id: self.current_scope,
+                            stability: source_symbol.stability.clone(),
                         };
 
                         self.symbols.insert(new_symbol_id, new_symbol);
@@ -965,6 +1071,54 @@ impl SymbolTable {
     }
 }
 
+/// Most suggestions offered for one misspelled name
+const MAX_SUGGESTIONS: usize = 3;
+
+/// The `candidates` close enough in spelling to `name` to suggest, closest
+/// first and otherwise in the order given
+///
+/// Names shorter than three characters get no suggestions, and at most one
+/// edit is allowed for names up to five characters, two beyond that.
+fn closest_names<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let max_distance = match name.chars().count() {
+        0..=2 => return Vec::new(),
+        3..=5 => 1,
+        _ => 2,
+    };
+
+    let mut close: Vec<(usize, &str)> = Vec::new();
+    for candidate in candidates {
+        if candidate == name || close.iter().any(|(_, seen)| *seen == candidate) {
+            continue;
+        }
+        let distance = edit_distance(name, candidate);
+        if distance <= max_distance {
+            close.push((distance, candidate));
+        }
+    }
+    close.sort_by_key(|(distance, _)| *distance);
+    close
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+/// Levenshtein distance between two names, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut row = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            row[j + 1] = substitution.min(previous[j + 1] + 1).min(row[j] + 1);
+        }
+        previous = row;
+    }
+    previous[b.len()]
+}
+
 impl Default for SymbolTable {
     fn default() -> Self {
         Self::new()
@@ -1004,6 +1158,61 @@ mod tests {
         assert!(table.lookup("y").is_none());
     }
 
+    #[test]
+    fn test_similar_name_prefers_closest_visible_name() {
+        let mut table = SymbolTable::new();
+        table
+            .define_variable("counter".to_string(), Type::I32, make_span(), true)
+            .unwrap();
+        table.enter_scope();
+        table
+            .define_variable("count".to_string(), Type::I32, make_span(), true)
+            .unwrap();
+
+        assert_eq!(table.similar_name("cont"), Some("count".to_string()));
+        assert_eq!(table.similar_name("countr"), Some("count".to_string()));
+        assert_eq!(table.similar_name("conter"), Some("counter".to_string()));
+        assert_eq!(table.similar_name("total"), None);
+        assert_eq!(table.similar_name("cu"), None);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_similar_names_include_module_members() {
+        let mut table = SymbolTable::new();
+        for name in ["counter", "count", "amount"] {
+            table
+                .define_variable(name.to_string(), Type::I32, make_span(), true)
+                .unwrap();
+        }
+        assert_eq!(table.similar_names("countr"), vec!["count", "counter"]);
+        assert_eq!(table.similar_names("cont"), vec!["count"]);
+
+        let mut math = SymbolTable::new();
+        for name in ["sqrt", "sqrt2", "sin"] {
+            math.define_variable(name.to_string(), Type::F32, make_span(), false)
+                .unwrap();
+        }
+        table.register_module("math", &math);
+        table
+            .process_import(
+                &[ImportSpecifier::Namespace {
+                    alias: "math".to_string(),
+                }],
+                "math",
+                make_span(),
+            )
+            .unwrap();
+        assert_eq!(table.namespace_module("math"), Some("math"));
+        assert_eq!(table.similar_names("maths"), vec!["math"]);
+        assert_eq!(table.similar_module_members("math", "sqr"), vec!["sqrt"]);
+        assert_eq!(
+            table.similar_module_members("math", "sqrtt"),
+            vec!["sqrt", "sqrt2"]
+        );
+        assert!(table.similar_module_members("physics", "sqrt").is_empty());
+    }
+
     #[test]
     fn test_scope_management() {
         let mut table = SymbolTable::new();
@@ -1104,6 +1313,28 @@ mod tests {
         assert!(result.unwrap_err().contains("already defined"));
     }
 
+    #[test]
+    fn test_variable_replaces_constant() {
+        let mut table = SymbolTable::new();
+
+        table
+            .define_constant("PI".to_string(), Type::F32, make_span())
+            .unwrap();
+        let pi = table.lookup("PI").unwrap();
+        assert!(matches!(pi.kind, SymbolKind::Constant));
+        assert!(!pi.is_mutable);
+        assert!(table
+            .define_constant("PI".to_string(), Type::F32, make_span())
+            .is_err());
+
+        table
+            .define_variable("PI".to_string(), Type::I32, make_span(), false)
+            .unwrap();
+        let pi = table.lookup("PI").unwrap();
+        assert!(matches!(pi.kind, SymbolKind::Variable));
+        assert_eq!(pi.ty, Type::I32);
+    }
+
     #[test]
     fn test_conflicting_function_signatures() {
         let mut table = SymbolTable::new();
@@ -43,6 +43,24 @@ fn test_basic_variable_declaration() {
     assert!(symbol.is_mutable);
 }
 
+#[test]
+fn test_suffixed_literal_types() {
+    let analyzer = parse_and_analyze("let big = 10i64 * 3i64; let half: f64 = 0.5f64;").unwrap();
+    assert_eq!(analyzer.symbol_table().lookup("big").unwrap().ty, Type::I64);
+    assert_eq!(
+        analyzer.symbol_table().lookup("half").unwrap().ty,
+        Type::F64
+    );
+
+    expect_semantic_error(
+        "let x = 1i64 + 1u32;",
+        SemanticErrorKind::TypeMismatch {
+            expected: Type::I64,
+            found: Type::U32,
+        },
+    );
+}
+
 #[test]
 fn test_variable_without_initializer() {
     // Variable without initializer and with type annotation should work
@@ -96,6 +114,150 @@ fn test_variable_shadowing() {
     assert!(analyzer.errors().is_empty());
 }
 
+#[test]
+fn test_stdlib_constants() {
+    let analyzer = parse_and_analyze(
+        r#"
+        let angle: f32 = PI / 2.0;
+        let limit: i64 = MAX_I64;
+        angle;
+        limit;
+    "#,
+    )
+    .unwrap();
+
+    assert!(analyzer.errors().is_empty());
+    let pi = analyzer.symbol_table().lookup("PI").unwrap();
+    assert_eq!(pi.ty, Type::F32);
+    assert!(!pi.is_mutable);
+}
+
+#[test]
+fn test_stdlib_constant_redefined() {
+    let analyzer = parse_and_analyze(
+        r#"
+        let E: i32 = 3;
+        E + 1;
+    "#,
+    )
+    .unwrap();
+
+    assert!(analyzer.errors().is_empty());
+    assert_eq!(analyzer.symbol_table().lookup("E").unwrap().ty, Type::I32);
+}
+
+#[test]
+fn test_error_kind_enum() {
+    let analyzer = parse_and_analyze(
+        r#"
+        let kind = ErrorKind::NotFound;
+        kind;
+    "#,
+    )
+    .unwrap();
+
+    assert!(analyzer.errors().is_empty());
+    let error_kind = analyzer.symbol_table().lookup("ErrorKind").unwrap();
+    let variants = &error_kind.enum_info().unwrap().variants;
+    assert!(variants.iter().any(|variant| variant.name == "Parse"));
+
+    let error = analyzer.symbol_table().lookup("Error").unwrap();
+    let fields = &error.struct_info().unwrap().fields;
+    assert_eq!(
+        fields[0],
+        ("kind".to_string(), Type::Named("ErrorKind".to_string()))
+    );
+    assert!(fields.iter().any(|(name, _)| name == "source"));
+}
+
+#[test]
+fn test_weak_ref_types() {
+    let analyzer = parse_and_analyze(
+        r#"
+        let name: string = "player";
+        let handle: Weak<string> = weak_ref(name);
+        let current: Option<string> = weak_upgrade(handle);
+        current;
+    "#,
+    )
+    .unwrap();
+    assert!(analyzer.errors().is_empty());
+
+    expect_semantic_error(
+        r#"
+        let name: string = "player";
+        let count: Option<i32> = weak_upgrade(weak_ref(name));
+        count;
+    "#,
+        SemanticErrorKind::TypeMismatch {
+            expected: Type::Generic {
+                name: "Option".to_string(),
+                args: vec![Type::I32],
+            },
+            found: Type::Generic {
+                name: "Option".to_string(),
+                args: vec![Type::String],
+            },
+        },
+    );
+}
+
+#[test]
+fn test_channel_types() {
+    let analyzer = parse_and_analyze(
+        r#"
+        let ch = channel(4);
+        send(ch, 1);
+        let sent: bool = try_send(ch, 2);
+        let next = recv(ch);
+        let ready = select([ch]);
+        close_channel(ch);
+        sent;
+        next;
+        ready;
+    "#,
+    )
+    .unwrap();
+    assert!(analyzer.errors().is_empty());
+}
+
+#[test]
+fn test_thread_types() {
+    let analyzer = parse_and_analyze(
+        r#"
+        let worker = thread_spawn(|| 40 + 2);
+        let answer = thread_join(worker);
+        let total = thread_scope(|scope| {
+            let part = scope_spawn(scope, || 1);
+            thread_join(part)
+        });
+        answer;
+        total;
+    "#,
+    )
+    .unwrap();
+    assert!(analyzer.errors().is_empty());
+}
+
+#[test]
+fn test_sync_types() {
+    let analyzer = parse_and_analyze(
+        r#"
+        let lock = mutex_new(0);
+        let guard = mutex_lock(lock);
+        guard_set(guard, guard_get(guard) + 1);
+        unlock(guard);
+        let counter = atomic_i32(0);
+        let previous: i32 = atomic_add(counter, 1);
+        let swapped: bool = atomic_compare_exchange(counter, 1, 2);
+        previous;
+        swapped;
+    "#,
+    )
+    .unwrap();
+    assert!(analyzer.errors().is_empty());
+}
+
 #[test]
 fn test_function_declaration() {
     let analyzer = parse_and_analyze(
@@ -251,6 +413,86 @@ fn test_for_loop_variable_scope() {
     );
 }
 
+/// Analyze a program that is expected to produce errors, returning the analyzer
+fn analyze_with_errors(source: &str) -> SemanticAnalyzer {
+    let lexer = Lexer::new(source).unwrap();
+    let (tokens, _) = lexer.scan_tokens();
+    let mut parser = Parser::new(tokens);
+    let program = parser.parse().unwrap();
+
+    let mut analyzer = SemanticAnalyzer::new();
+    let _ = analyzer.analyze_program(&program);
+    analyzer
+}
+
+#[test]
+fn test_for_loop_infers_array_element_type() {
+    expect_semantic_error(
+        r#"
+        let arr: [i32] = [1, 2, 3];
+        for x in arr {
+            let s: string = x;
+        }
+    "#,
+        SemanticErrorKind::TypeMismatch {
+            expected: Type::String,
+            found: Type::I32,
+        },
+    );
+}
+
+#[test]
+fn test_for_loop_iterator_protocol() {
+    let analyzer = analyze_with_errors(
+        r#"
+        struct Countdown {
+            remaining: i32
+        }
+
+        impl Countdown {
+            fn next(self) -> Option<i32> {
+                return None;
+            }
+        }
+
+        fn count(countdown: Countdown) {
+            for n in countdown {
+                let s: string = n;
+            }
+        }
+    "#,
+    );
+
+    // The loop variable takes the item type of `next`
+    assert!(analyzer.errors().iter().any(|error| error.kind
+        == SemanticErrorKind::TypeMismatch {
+            expected: Type::String,
+            found: Type::I32,
+        }));
+}
+
+#[test]
+fn test_for_loop_over_non_iterable() {
+    let analyzer = analyze_with_errors(
+        r#"
+        struct Point {
+            x: i32
+        }
+
+        fn walk(point: Point) {
+            for p in point {
+                p;
+            }
+        }
+    "#,
+    );
+
+    assert!(analyzer.errors().iter().any(|error| matches!(
+        &error.kind,
+        SemanticErrorKind::InvalidOperation { op, .. } if op == "for-in"
+    )));
+}
+
 #[test]
 fn test_array_literal() {
     let analyzer = parse_and_analyze(
@@ -570,6 +812,28 @@ fn test_return_outside_function() {
     expect_semantic_error("return 42;", SemanticErrorKind::ReturnOutsideFunction);
 }
 
+#[test]
+fn test_loop_control_outside_loop() {
+    expect_semantic_error("break;", SemanticErrorKind::BreakOutsideLoop);
+    expect_semantic_error(
+        "fn f() { continue; }",
+        SemanticErrorKind::ContinueOutsideLoop,
+    );
+
+    // Leaving a nested loop must not end the enclosing one
+    assert!(parse_and_analyze(
+        r#"
+        while true {
+            for i in [1, 2] {
+                continue;
+            }
+            break;
+        }
+    "#
+    )
+    .is_ok());
+}
+
 #[test]
 fn test_binary_operations() {
     let analyzer = parse_and_analyze(
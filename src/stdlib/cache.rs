@@ -0,0 +1,708 @@
+//! Caching collections for Script
+//!
+//! This module provides caches for memoizing expensive computations:
+//! - `LruCache`, an in-memory cache that evicts the least recently used entry
+//!   once it reaches its capacity
+//! - `DiskCache`, a directory of JSON files that survives between runs, for
+//!   tooling scripts that repeat slow work
+//!
+//! Both keep `CacheStats` with hit and miss counts. Cache keys are strings;
+//! numbers and booleans are converted to their string form.
+
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::functional::execute_script_closure;
+use crate::stdlib::{
+    expect_args, handle_id, string_value, ScriptOption, ScriptResult, ScriptValue, ScriptVec,
+};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// LRU caches created by scripts, keyed by the id stored in their handle
+fn lru_caches() -> MutexGuard<'static, HashMap<i32, LruCache>> {
+    static LRU_CACHES: OnceLock<Mutex<HashMap<i32, LruCache>>> = OnceLock::new();
+    LRU_CACHES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Disk caches created by scripts, keyed by the id stored in their handle
+fn disk_caches() -> MutexGuard<'static, HashMap<i32, DiskCache>> {
+    static DISK_CACHES: OnceLock<Mutex<HashMap<i32, DiskCache>>> = OnceLock::new();
+    DISK_CACHES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Object key holding an LRU cache's id
+const LRU_ID_KEY: &str = "_lru_cache_id";
+/// Object key holding a disk cache's id
+const DISK_ID_KEY: &str = "_disk_cache_id";
+
+/// Hit and miss counters for a cache
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// Fraction of lookups that were hits, 0.0 before any lookup
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+
+    fn record(&mut self, hit: bool) {
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct LruEntry {
+    value: ScriptValue,
+    last_used: u64,
+}
+
+/// In-memory cache evicting the least recently used entry when full
+#[derive(Debug, Clone)]
+pub struct LruCache {
+    capacity: usize,
+    entries: HashMap<String, LruEntry>,
+    /// Keys ordered by last use
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+impl LruCache {
+    /// Create a cache holding at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        LruCache {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            tick: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = self.tick;
+            self.recency.insert(self.tick, key.to_string());
+        }
+    }
+
+    /// Look up `key`, marking it as recently used
+    pub fn get(&mut self, key: &str) -> Option<ScriptValue> {
+        let hit = self.entries.contains_key(key);
+        self.stats.record(hit);
+        if !hit {
+            return None;
+        }
+        self.touch(key);
+        self.entries.get(key).map(|entry| entry.value.clone())
+    }
+
+    /// Whether `key` is cached, without affecting recency or stats
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Insert or replace `key`, evicting the least recently used entry if full
+    pub fn put(&mut self, key: String, value: ScriptValue) {
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.value = value;
+            self.touch(&key);
+            return;
+        }
+
+        if self.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = self.recency.pop_first() {
+                self.entries.remove(&oldest);
+                self.stats.evictions += 1;
+            }
+        }
+
+        self.tick += 1;
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            LruEntry {
+                value,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    /// Remove `key`, returning its value
+    pub fn remove(&mut self, key: &str) -> Option<ScriptValue> {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.last_used);
+        Some(entry.value)
+    }
+
+    /// Remove all entries, keeping the stats
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn stats(&self) -> &CacheStats {
+        &self.stats
+    }
+}
+
+/// Cache persisted as one JSON file per entry in a directory
+///
+/// Only values that can be represented as JSON (numbers, booleans, strings,
+/// unit, arrays and objects) can be stored. `i32` and `f32` numbers are
+/// stored as plain JSON numbers; other numbers and objects are wrapped in a
+/// single-key object naming their type, so they read back unchanged.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    stats: CacheStats,
+}
+
+impl DiskCache {
+    /// Open a cache in `dir`, creating the directory if needed
+    pub fn open(dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(DiskCache {
+            dir: dir.as_ref().to_path_buf(),
+            stats: CacheStats::default(),
+        })
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:x}.json", Sha256::digest(key.as_bytes())))
+    }
+
+    /// Look up `key`; unreadable or corrupt entries count as misses
+    pub fn get(&mut self, key: &str) -> Option<ScriptValue> {
+        let value = fs::read_to_string(self.entry_path(key))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .and_then(|json| from_json(&json));
+        self.stats.record(value.is_some());
+        value
+    }
+
+    /// Whether `key` has an entry on disk
+    pub fn contains(&self, key: &str) -> bool {
+        self.entry_path(key).is_file()
+    }
+
+    /// Store `value` under `key`
+    pub fn put(&mut self, key: &str, value: &ScriptValue) -> Result<(), String> {
+        let json = to_json(value)?;
+        let contents = serde_json::to_string(&json).map_err(|e| e.to_string())?;
+
+        // Write to a temporary file first so readers never see partial entries
+        let path = self.entry_path(key);
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, contents).map_err(|e| e.to_string())?;
+        fs::rename(&temp, &path).map_err(|e| e.to_string())
+    }
+
+    /// Remove `key`, returning whether it was cached
+    pub fn remove(&mut self, key: &str) -> bool {
+        fs::remove_file(self.entry_path(key)).is_ok()
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn stats(&self) -> &CacheStats {
+        &self.stats
+    }
+}
+
+/// Wrap `value` in a single-key object tagging it with `tag`
+fn tagged(tag: &str, value: serde_json::Value) -> serde_json::Value {
+    let mut object = serde_json::Map::new();
+    object.insert(tag.to_string(), value);
+    serde_json::Value::Object(object)
+}
+
+fn float_json(n: f64) -> Result<serde_json::Value, String> {
+    serde_json::Number::from_f64(n)
+        .map(serde_json::Value::Number)
+        .ok_or_else(|| format!("Cannot cache non-finite number {}", n))
+}
+
+/// Convert a value to JSON for the disk cache
+fn to_json(value: &ScriptValue) -> Result<serde_json::Value, String> {
+    use serde_json::Value;
+
+    Ok(match value {
+        ScriptValue::I32(n) => Value::from(*n),
+        ScriptValue::I64(n) => tagged("i64", Value::from(*n)),
+        ScriptValue::U32(n) => tagged("u32", Value::from(*n)),
+        ScriptValue::U64(n) => tagged("u64", Value::from(*n)),
+        ScriptValue::F32(n) => float_json(*n as f64)?,
+        ScriptValue::F64(n) => tagged("f64", float_json(*n)?),
+        ScriptValue::Bool(b) => Value::Bool(*b),
+        ScriptValue::String(s) => Value::String(s.as_str().to_string()),
+        ScriptValue::Unit => Value::Null,
+        ScriptValue::Array(items) => Value::Array(
+            items
+                .to_vec()
+                .map_err(|e| e.to_string())?
+                .iter()
+                .map(to_json)
+                .collect::<Result<_, _>>()?,
+        ),
+        ScriptValue::Object(fields) => tagged(
+            "object",
+            Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), to_json(value)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+        ),
+        other => {
+            return Err(format!(
+                "Cannot store {} values in a disk cache",
+                other.get_type()
+            ))
+        }
+    })
+}
+
+/// Convert JSON read from the disk cache back to a value, or `None` if it
+/// was not written by `to_json`
+fn from_json(json: &serde_json::Value) -> Option<ScriptValue> {
+    use serde_json::Value;
+
+    Some(match json {
+        Value::Null => ScriptValue::Unit,
+        Value::Bool(b) => ScriptValue::Bool(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(n) => ScriptValue::I32(i32::try_from(n).ok()?),
+            None => ScriptValue::F32(n.as_f64()? as f32),
+        },
+        Value::String(s) => string_value(s),
+        Value::Array(items) => ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(
+            items.iter().map(from_json).collect::<Option<_>>()?,
+        ))),
+        Value::Object(object) if object.len() == 1 => {
+            let (tag, value) = object.iter().next()?;
+            match (tag.as_str(), value) {
+                ("i64", Value::Number(n)) => ScriptValue::I64(n.as_i64()?),
+                ("u32", Value::Number(n)) => ScriptValue::U32(u32::try_from(n.as_u64()?).ok()?),
+                ("u64", Value::Number(n)) => ScriptValue::U64(n.as_u64()?),
+                ("f64", Value::Number(n)) => ScriptValue::F64(n.as_f64()?),
+                ("object", Value::Object(fields)) => ScriptValue::Object(ScriptRc::new(
+                    fields
+                        .iter()
+                        .map(|(key, value)| Some((key.clone(), from_json(value)?)))
+                        .collect::<Option<_>>()?,
+                )),
+                _ => return None,
+            }
+        }
+        Value::Object(_) => return None,
+    })
+}
+
+fn option_value(value: Option<ScriptValue>) -> ScriptValue {
+    ScriptValue::Option(ScriptRc::new(match value {
+        Some(value) => ScriptOption::some(value),
+        None => ScriptOption::none(),
+    }))
+}
+
+/// Read a cache key; numbers and booleans use their string form
+fn cache_key(value: &ScriptValue) -> RuntimeResult<String> {
+    match value {
+        ScriptValue::String(s) => Ok(s.as_str().to_string()),
+        ScriptValue::I32(n) => Ok(n.to_string()),
+        ScriptValue::F32(n) => Ok(n.to_string()),
+        ScriptValue::Bool(b) => Ok(b.to_string()),
+        _ => Err(RuntimeError::InvalidOperation(
+            "Cache keys must be strings, numbers or booleans".to_string(),
+        )),
+    }
+}
+
+fn new_handle(key: &str, id: i32) -> ScriptValue {
+    let mut fields = HashMap::new();
+    fields.insert(key.to_string(), ScriptValue::I32(id));
+    ScriptValue::Object(ScriptRc::new(fields))
+}
+
+/// A cache handle passed in from a script
+enum CacheHandle {
+    Lru(i32),
+    Disk(i32),
+}
+
+impl CacheHandle {
+    fn from_value(value: &ScriptValue) -> RuntimeResult<Self> {
+        let expected = "a cache created by lru_new or disk_cache_new";
+        match handle_id(value, LRU_ID_KEY, expected) {
+            Ok(id) => Ok(CacheHandle::Lru(id)),
+            Err(_) => handle_id(value, DISK_ID_KEY, expected).map(CacheHandle::Disk),
+        }
+    }
+
+    fn with_lru<T>(id: i32, f: impl FnOnce(&mut LruCache) -> T) -> RuntimeResult<T> {
+        lru_caches()
+            .get_mut(&id)
+            .map(f)
+            .ok_or_else(|| RuntimeError::InvalidOperation("Unknown LRU cache".to_string()))
+    }
+
+    fn with_disk<T>(id: i32, f: impl FnOnce(&mut DiskCache) -> T) -> RuntimeResult<T> {
+        disk_caches()
+            .get_mut(&id)
+            .map(f)
+            .ok_or_else(|| RuntimeError::InvalidOperation("Unknown disk cache".to_string()))
+    }
+
+    fn get(&self, key: &str) -> RuntimeResult<Option<ScriptValue>> {
+        match self {
+            CacheHandle::Lru(id) => Self::with_lru(*id, |cache| cache.get(key)),
+            CacheHandle::Disk(id) => Self::with_disk(*id, |cache| cache.get(key)),
+        }
+    }
+
+    fn contains(&self, key: &str) -> RuntimeResult<bool> {
+        match self {
+            CacheHandle::Lru(id) => Self::with_lru(*id, |cache| cache.contains(key)),
+            CacheHandle::Disk(id) => Self::with_disk(*id, |cache| cache.contains(key)),
+        }
+    }
+
+    fn put(&self, key: String, value: ScriptValue) -> RuntimeResult<()> {
+        match self {
+            CacheHandle::Lru(id) => Self::with_lru(*id, |cache| cache.put(key, value)),
+            CacheHandle::Disk(id) => Self::with_disk(*id, |cache| cache.put(&key, &value))?
+                .map_err(RuntimeError::InvalidOperation),
+        }
+    }
+
+    fn stats(&self) -> RuntimeResult<(CacheStats, usize)> {
+        match self {
+            CacheHandle::Lru(id) => {
+                Self::with_lru(*id, |cache| (cache.stats().clone(), cache.len()))
+            }
+            CacheHandle::Disk(id) => Self::with_disk(*id, |cache| {
+                let entries = fs::read_dir(cache.dir())
+                    .map(|entries| entries.filter_map(|entry| entry.ok()).count())
+                    .unwrap_or(0);
+                (cache.stats().clone(), entries)
+            }),
+        }
+    }
+}
+
+/// Create an LRU cache with the given capacity
+pub fn lru_new_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "lru_new")?;
+
+    let capacity = args[0].to_i32()?;
+    if capacity < 1 {
+        return Err(RuntimeError::InvalidOperation(
+            "LRU cache capacity must be at least 1".to_string(),
+        ));
+    }
+
+    let mut caches = lru_caches();
+    let id = caches.len() as i32;
+    caches.insert(id, LruCache::new(capacity as usize));
+    Ok(new_handle(LRU_ID_KEY, id))
+}
+
+/// Open a disk cache in a directory
+pub fn disk_cache_new_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "disk_cache_new")?;
+
+    let dir = match &args[0] {
+        ScriptValue::String(dir) => dir.as_str().to_string(),
+        _ => {
+            return Err(RuntimeError::InvalidOperation(
+                "disk_cache_new expects a directory path".to_string(),
+            ))
+        }
+    };
+
+    match DiskCache::open(&dir) {
+        Ok(cache) => {
+            let mut caches = disk_caches();
+            let id = caches.len() as i32;
+            caches.insert(id, cache);
+            Ok(ScriptValue::Result(ScriptRc::new(ScriptResult::ok(
+                new_handle(DISK_ID_KEY, id),
+            ))))
+        }
+        Err(e) => Ok(ScriptValue::Result(ScriptRc::new(ScriptResult::err(
+            string_value(&format!("Failed to open cache directory '{}': {}", dir, e)),
+        )))),
+    }
+}
+
+/// Look up a key, returning an Option
+pub fn cache_get_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "cache_get")?;
+
+    let cache = CacheHandle::from_value(&args[0])?;
+    Ok(option_value(cache.get(&cache_key(&args[1])?)?))
+}
+
+/// Store a value under a key
+pub fn cache_put_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 3, "cache_put")?;
+
+    let cache = CacheHandle::from_value(&args[0])?;
+    cache.put(cache_key(&args[1])?, args[2].clone())?;
+    Ok(ScriptValue::Unit)
+}
+
+/// Check whether a key is cached
+pub fn cache_contains_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "cache_contains")?;
+
+    let cache = CacheHandle::from_value(&args[0])?;
+    Ok(ScriptValue::Bool(cache.contains(&cache_key(&args[1])?)?))
+}
+
+/// Return the cached value for a key, computing and storing it on a miss
+pub fn cached_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 3, "cached")?;
+
+    let cache = CacheHandle::from_value(&args[0])?;
+    let key = cache_key(&args[1])?;
+    if let Some(value) = cache.get(&key)? {
+        return Ok(value);
+    }
+
+    if !matches!(args[2], ScriptValue::Closure(_)) {
+        return Err(RuntimeError::InvalidOperation(
+            "cached expects a closure to compute missing values".to_string(),
+        ));
+    }
+    let value = execute_script_closure(&args[2], &[])
+        .map_err(|e| RuntimeError::InvalidOperation(e.to_string()))?;
+    cache.put(key, value.clone())?;
+    Ok(value)
+}
+
+/// Get cache statistics as an object
+pub fn cache_stats_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "cache_stats")?;
+
+    let (stats, size) = CacheHandle::from_value(&args[0])?.stats()?;
+    let mut fields = HashMap::new();
+    fields.insert("hits".to_string(), ScriptValue::I32(stats.hits as i32));
+    fields.insert("misses".to_string(), ScriptValue::I32(stats.misses as i32));
+    fields.insert(
+        "evictions".to_string(),
+        ScriptValue::I32(stats.evictions as i32),
+    );
+    fields.insert("size".to_string(), ScriptValue::I32(size as i32));
+    fields.insert(
+        "hit_rate".to_string(),
+        ScriptValue::F32(stats.hit_rate() as f32),
+    );
+    Ok(ScriptValue::Object(ScriptRc::new(fields)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::ThreadPool;
+
+    fn key(s: &str) -> ScriptValue {
+        string_value(s)
+    }
+
+    #[test]
+    fn test_lru_evicts_least_recently_used() {
+        let mut cache = LruCache::new(2);
+        cache.put("a".to_string(), ScriptValue::I32(1));
+        cache.put("b".to_string(), ScriptValue::I32(2));
+
+        // Using "a" makes "b" the eviction candidate
+        assert!(cache.get("a").is_some());
+        cache.put("c".to_string(), ScriptValue::I32(3));
+
+        assert!(cache.contains("a"));
+        assert!(!cache.contains("b"));
+        assert!(cache.contains("c"));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats().evictions, 1);
+
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 1);
+        assert_eq!(cache.stats().hit_rate(), 0.5);
+    }
+
+    #[test]
+    fn test_lru_replace_keeps_size() {
+        let mut cache = LruCache::new(2);
+        cache.put("a".to_string(), ScriptValue::I32(1));
+        cache.put("a".to_string(), ScriptValue::I32(2));
+        assert_eq!(cache.len(), 1);
+        assert!(matches!(cache.get("a"), Some(ScriptValue::I32(2))));
+
+        assert!(cache.remove("a").is_some());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_disk_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = DiskCache::open(dir.path()).unwrap();
+
+        let mut fields = HashMap::new();
+        fields.insert("name".to_string(), key("script"));
+        fields.insert(
+            "sizes".to_string(),
+            ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(vec![
+                ScriptValue::I32(1),
+                ScriptValue::F32(2.5),
+            ]))),
+        );
+        let value = ScriptValue::Object(ScriptRc::new(fields));
+
+        assert!(cache.get("build").is_none());
+        cache.put("build", &value).unwrap();
+        assert!(cache.contains("build"));
+
+        // A fresh cache on the same directory sees the entry
+        let mut reopened = DiskCache::open(dir.path()).unwrap();
+        match reopened.get("build") {
+            Some(ScriptValue::Object(fields)) => {
+                assert_eq!(
+                    fields.get("name").unwrap().as_string().unwrap().as_str(),
+                    "script"
+                );
+                let sizes = match fields.get("sizes") {
+                    Some(ScriptValue::Array(sizes)) => sizes.to_vec().unwrap(),
+                    _ => panic!("expected array"),
+                };
+                assert!(matches!(sizes[0], ScriptValue::I32(1)));
+                assert!(matches!(sizes[1], ScriptValue::F32(n) if n == 2.5));
+            }
+            other => panic!("expected cached object, got {:?}", other),
+        }
+        assert_eq!(reopened.stats().hits, 1);
+
+        let closure_like = ScriptValue::Result(ScriptRc::new(ScriptResult::ok(ScriptValue::Unit)));
+        assert!(cache.put("bad", &closure_like).is_err());
+    }
+
+    #[test]
+    fn test_disk_cache_keeps_number_widths() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = DiskCache::open(dir.path()).unwrap();
+
+        let precise = 0.1_f64 + 0.2;
+        let value = ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(vec![
+            ScriptValue::I64(i64::MAX),
+            ScriptValue::F64(precise),
+            ScriptValue::U32(u32::MAX),
+            ScriptValue::U64(u64::MAX),
+            ScriptValue::I32(-7),
+        ])));
+        cache.put("widths", &value).unwrap();
+
+        let items = match cache.get("widths") {
+            Some(ScriptValue::Array(items)) => items.to_vec().unwrap(),
+            other => panic!("expected cached array, got {:?}", other),
+        };
+        assert!(matches!(items[0], ScriptValue::I64(i64::MAX)));
+        assert!(matches!(items[1], ScriptValue::F64(n) if n == precise));
+        assert!(matches!(items[2], ScriptValue::U32(u32::MAX)));
+        assert!(matches!(items[3], ScriptValue::U64(u64::MAX)));
+        assert!(matches!(items[4], ScriptValue::I32(-7)));
+
+        // Entries this module did not write are misses rather than guesses
+        let path = cache.entry_path("foreign");
+        fs::write(&path, r#"{"name": "script"}"#).unwrap();
+        assert!(cache.get("foreign").is_none());
+    }
+
+    #[test]
+    fn test_cache_script_functions() {
+        let cache = lru_new_impl(&[ScriptValue::I32(4)]).unwrap();
+        cache_put_impl(&[cache.clone(), key("answer"), ScriptValue::I32(42)]).unwrap();
+        cache_put_impl(&[cache.clone(), ScriptValue::I32(7), ScriptValue::Bool(true)]).unwrap();
+
+        let contains = cache_contains_impl(&[cache.clone(), key("7")]).unwrap();
+        assert_eq!(contains.as_bool(), Some(true));
+
+        let found = cache_get_impl(&[cache.clone(), key("answer")]).unwrap();
+        let missing = cache_get_impl(&[cache.clone(), key("question")]).unwrap();
+        match (found, missing) {
+            (ScriptValue::Option(found), ScriptValue::Option(missing)) => {
+                assert!(matches!(&*found, ScriptOption::Some(ScriptValue::I32(42))));
+                assert!(matches!(&*missing, ScriptOption::None));
+            }
+            _ => panic!("expected options"),
+        }
+
+        match cache_stats_impl(&[cache]).unwrap() {
+            ScriptValue::Object(stats) => {
+                assert!(matches!(stats.get("hits"), Some(ScriptValue::I32(1))));
+                assert!(matches!(stats.get("size"), Some(ScriptValue::I32(2))));
+            }
+            _ => panic!("expected stats object"),
+        }
+
+        assert!(lru_new_impl(&[ScriptValue::I32(0)]).is_err());
+        assert!(cache_get_impl(&[ScriptValue::Unit, key("a")]).is_err());
+    }
+
+    #[test]
+    fn test_cache_shared_with_threads() {
+        let cache = lru_new_impl(&[ScriptValue::I32(4)]).unwrap();
+        let handle = cache.clone();
+        let put = ThreadPool::global()
+            .spawn(move || cache_put_impl(&[handle, key("answer"), ScriptValue::I32(42)]))
+            .unwrap();
+        put.join().unwrap().unwrap();
+
+        match cache_get_impl(&[cache, key("answer")]).unwrap() {
+            ScriptValue::Option(found) => {
+                assert!(matches!(&*found, ScriptOption::Some(ScriptValue::I32(42))));
+            }
+            _ => panic!("expected an option"),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Channels for Script
+//!
+//! `channel(capacity)` creates a bounded `Channel<T>` that tasks and threads
+//! can use to pass values instead of sharing mutable state:
+//! - `send(ch, value)` waits while the buffer is full, so fast producers are
+//!   slowed to the pace of their consumers
+//! - `recv(ch)` waits for the next value and returns `None` once the channel
+//!   is closed and drained
+//! - `try_send` and `try_recv` never wait
+//! - `close_channel(ch)` stops further sends
+//! - `select(channels)` receives from whichever channel is ready first
+//!
+//! Async tasks written in Rust use `Channel::send_async`, `recv_async` and
+//! `select_async` from `crate::runtime::channel` on the same channels.
+
+use crate::runtime::channel::{self, TrySendError};
+use crate::runtime::{Channel, Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::{expect_args, ScriptOption, ScriptValue};
+use std::collections::HashMap;
+
+/// Create a channel buffering at most `capacity` values
+pub fn channel_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "channel")?;
+
+    let capacity = args[0].to_i32()?;
+    if capacity < 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "channel capacity must be at least 1, got {}",
+            capacity
+        )));
+    }
+    Ok(ScriptValue::Channel(Channel::new(capacity as usize)))
+}
+
+/// Send a value, waiting while the channel is full
+pub fn send_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "send")?;
+
+    channel_arg(&args[0], "send")?
+        .send(args[1].clone())
+        .map_err(|_| closed_error("send"))?;
+    Ok(ScriptValue::Unit)
+}
+
+/// Send a value if there is room, returning whether it was sent
+pub fn try_send_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "try_send")?;
+
+    match channel_arg(&args[0], "try_send")?.try_send(args[1].clone()) {
+        Ok(()) => Ok(ScriptValue::Bool(true)),
+        Err(TrySendError::Full(_)) => Ok(ScriptValue::Bool(false)),
+        Err(TrySendError::Closed(_)) => Err(closed_error("try_send")),
+    }
+}
+
+/// Receive the next value, waiting until one arrives
+pub fn recv_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "recv")?;
+
+    Ok(option(channel_arg(&args[0], "recv")?.recv()))
+}
+
+/// Receive the next value if one is buffered
+pub fn try_recv_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "try_recv")?;
+
+    Ok(option(channel_arg(&args[0], "try_recv")?.try_recv().ok()))
+}
+
+/// Close a channel to further sends
+pub fn close_channel_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "close_channel")?;
+
+    channel_arg(&args[0], "close_channel")?.close();
+    Ok(ScriptValue::Unit)
+}
+
+/// Receive from whichever channel in an array has a value first
+///
+/// Returns `Some({ index, value })`, or `None` once every channel is closed
+/// and drained.
+pub fn select_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "select")?;
+
+    let channels = match &args[0] {
+        ScriptValue::Array(values) => values
+            .to_vec()
+            .map_err(|e| RuntimeError::InvalidOperation(e.to_string()))?
+            .iter()
+            .map(|value| channel_arg(value, "select").cloned())
+            .collect::<RuntimeResult<Vec<_>>>()?,
+        other => {
+            return Err(RuntimeError::InvalidOperation(format!(
+                "select expects an array of channels, got {:?}",
+                other.get_type()
+            )))
+        }
+    };
+    if channels.is_empty() {
+        return Err(RuntimeError::InvalidOperation(
+            "select needs at least one channel".to_string(),
+        ));
+    }
+
+    Ok(option(channel::select(&channels).map(|(index, value)| {
+        let mut fields = HashMap::new();
+        fields.insert("index".to_string(), ScriptValue::I32(index as i32));
+        fields.insert("value".to_string(), value);
+        ScriptValue::Object(ScriptRc::new(fields))
+    })))
+}
+
+fn channel_arg<'a>(
+    value: &'a ScriptValue,
+    function: &str,
+) -> RuntimeResult<&'a Channel<ScriptValue>> {
+    match value {
+        ScriptValue::Channel(channel) => Ok(channel),
+        other => Err(RuntimeError::InvalidOperation(format!(
+            "{} expects a channel, got {:?}",
+            function,
+            other.get_type()
+        ))),
+    }
+}
+
+fn option(value: Option<ScriptValue>) -> ScriptValue {
+    let option = match value {
+        Some(value) => ScriptOption::some(value),
+        None => ScriptOption::none(),
+    };
+    ScriptValue::Option(ScriptRc::new(option))
+}
+
+fn closed_error(function: &str) -> RuntimeError {
+    RuntimeError::InvalidOperation(format!("{} on a closed channel", function))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::ScriptVec;
+    use std::thread;
+
+    fn unwrap_option(value: ScriptValue) -> Option<ScriptValue> {
+        match value {
+            ScriptValue::Option(option) => match &*option {
+                ScriptOption::Some(value) => Some(value.clone()),
+                ScriptOption::None => None,
+            },
+            other => panic!("expected an option, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_channel_script_functions() {
+        let ch = channel_impl(&[ScriptValue::I32(1)]).unwrap();
+        assert!(channel_impl(&[ScriptValue::I32(0)]).is_err());
+
+        assert_eq!(
+            try_send_impl(&[ch.clone(), ScriptValue::I32(7)]).unwrap(),
+            ScriptValue::Bool(true)
+        );
+        // Full: the second value is refused rather than buffered
+        assert_eq!(
+            try_send_impl(&[ch.clone(), ScriptValue::I32(8)]).unwrap(),
+            ScriptValue::Bool(false)
+        );
+        assert_eq!(
+            unwrap_option(recv_impl(&[ch.clone()]).unwrap()),
+            Some(ScriptValue::I32(7))
+        );
+        assert_eq!(unwrap_option(try_recv_impl(&[ch.clone()]).unwrap()), None);
+
+        close_channel_impl(&[ch.clone()]).unwrap();
+        assert!(send_impl(&[ch.clone(), ScriptValue::I32(9)]).is_err());
+        assert_eq!(unwrap_option(recv_impl(&[ch.clone()]).unwrap()), None);
+        assert!(recv_impl(&[ScriptValue::I32(1)]).is_err());
+    }
+
+    #[test]
+    fn test_select_across_threads() {
+        let first = channel_impl(&[ScriptValue::I32(2)]).unwrap();
+        let second = channel_impl(&[ScriptValue::I32(2)]).unwrap();
+
+        let producer = {
+            let second = second.clone();
+            thread::spawn(move || send_impl(&[second, ScriptValue::Bool(true)]).unwrap())
+        };
+        let channels = ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(vec![
+            first.clone(),
+            second.clone(),
+        ])));
+        let selected = unwrap_option(select_impl(&[channels.clone()]).unwrap()).unwrap();
+        producer.join().unwrap();
+
+        match selected {
+            ScriptValue::Object(fields) => {
+                assert_eq!(fields.get("index"), Some(&ScriptValue::I32(1)));
+                assert_eq!(fields.get("value"), Some(&ScriptValue::Bool(true)));
+            }
+            other => panic!("expected an object, got {:?}", other),
+        }
+
+        close_channel_impl(&[first]).unwrap();
+        close_channel_impl(&[second]).unwrap();
+        assert_eq!(unwrap_option(select_impl(&[channels]).unwrap()), None);
+    }
+}
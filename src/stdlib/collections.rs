@@ -4,7 +4,7 @@
 //! that can be used from Script code, with functional programming capabilities.
 
 use crate::error::{Error, ErrorKind, Result};
-use crate::runtime::{RuntimeError, ScriptRc, Value};
+use crate::runtime::{deterministic, RuntimeError, ScriptRc, Value};
 use crate::stdlib::{ScriptOption, ScriptString, ScriptValue};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -586,13 +586,9 @@ impl ScriptHashMap {
     /// Get all keys as a vector
     pub fn keys(&self) -> Result<ScriptVec> {
         let vec = ScriptVec::new();
-        let data = self
-            .data
-            .read()
-            .map_err(|_| Error::lock_poisoned("Failed to acquire read lock on hash map data"))?;
-        for key in data.keys() {
+        for (key, _) in self.iter()? {
             vec.push(ScriptValue::String(ScriptRc::new(ScriptString::from_str(
-                key,
+                &key,
             ))))?;
         }
         Ok(vec)
@@ -601,23 +597,24 @@ impl ScriptHashMap {
     /// Get all values as a vector
     pub fn values(&self) -> Result<ScriptVec> {
         let vec = ScriptVec::new();
-        let data = self
-            .data
-            .read()
-            .map_err(|_| Error::lock_poisoned("Failed to acquire read lock on hash map data"))?;
-        for value in data.values() {
-            vec.push(value.clone())?;
+        for (_, value) in self.iter()? {
+            vec.push(value)?;
         }
         Ok(vec)
     }
 
     /// Create an iterator over the hash map entries
+    ///
+    /// Entries come in key order in deterministic mode and in hash order
+    /// otherwise.
     pub fn iter(&self) -> Result<Vec<(String, ScriptValue)>> {
         let data = self
             .data
             .read()
             .map_err(|_| Error::lock_poisoned("Failed to acquire read lock on hash map data"))?;
-        Ok(data.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        let mut entries: Vec<_> = data.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        deterministic::order_entries(&mut entries);
+        Ok(entries)
     }
 }
 
@@ -980,12 +977,16 @@ impl ScriptHashSet {
     fn value_to_key(&self, value: &ScriptValue) -> crate::error::Result<String> {
         match value {
             ScriptValue::I32(i) => Ok(format!("i32:{}", i)),
+            ScriptValue::I64(i) => Ok(format!("i64:{}", i)),
+            ScriptValue::U32(u) => Ok(format!("u32:{}", u)),
+            ScriptValue::U64(u) => Ok(format!("u64:{}", u)),
             ScriptValue::F32(f) => Ok(format!("f32:{}", f)),
+            ScriptValue::F64(f) => Ok(format!("f64:{}", f)),
             ScriptValue::Bool(b) => Ok(format!("bool:{}", b)),
             ScriptValue::String(s) => Ok(format!("string:{}", s.as_str())),
             ScriptValue::Unit => Ok("unit".to_string()),
             _ => Err(crate::error::Error::type_error(format!(
-                "HashSet can only contain hashable types (i32, f32, bool, string, unit), got {:?}",
+                "HashSet can only contain hashable types (numbers, bool, string, unit), got {:?}",
                 value.get_type()
             ))),
         }
@@ -1011,6 +1012,34 @@ impl ScriptHashSet {
             return Ok(ScriptValue::F32(f));
         }
 
+        if let Some(stripped) = key.strip_prefix("i64:") {
+            let n = stripped
+                .parse::<i64>()
+                .map_err(|_| crate::error::Error::type_error("Invalid i64 in HashSet key"))?;
+            return Ok(ScriptValue::I64(n));
+        }
+
+        if let Some(stripped) = key.strip_prefix("u32:") {
+            let n = stripped
+                .parse::<u32>()
+                .map_err(|_| crate::error::Error::type_error("Invalid u32 in HashSet key"))?;
+            return Ok(ScriptValue::U32(n));
+        }
+
+        if let Some(stripped) = key.strip_prefix("u64:") {
+            let n = stripped
+                .parse::<u64>()
+                .map_err(|_| crate::error::Error::type_error("Invalid u64 in HashSet key"))?;
+            return Ok(ScriptValue::U64(n));
+        }
+
+        if let Some(stripped) = key.strip_prefix("f64:") {
+            let n = stripped
+                .parse::<f64>()
+                .map_err(|_| crate::error::Error::type_error("Invalid f64 in HashSet key"))?;
+            return Ok(ScriptValue::F64(n));
+        }
+
         if let Some(stripped) = key.strip_prefix("bool:") {
             let b = stripped
                 .parse::<bool>()
@@ -0,0 +1,582 @@
+//! Application configuration for Script
+//!
+//! `config_load(path, schema)` reads a TOML file and checks it against a
+//! schema, an object mapping each key to either a type name or a spec:
+//!
+//! ```text
+//! {
+//!     name: { type: "string", required: true },
+//!     port: { type: "i32", default: 8080 },
+//!     debug: "bool",
+//!     tags: { type: "array", items: "string", default: [] },
+//!     server: { type: "table", fields: { host: { type: "string", default: "localhost" } } },
+//! }
+//! ```
+//!
+//! Types are `string`, `i32`, `f32`, `bool`, `array`, `table` and `any`.
+//! Arrays may give the spec of their `items` and tables the schema of their
+//! `fields`; tables without `fields` and `any` values are taken as they are.
+//! Missing keys take their `default`, and a missing table with `fields` is
+//! checked as an empty table so its own defaults apply. Keys the schema does
+//! not list are reported as unknown, catching misspelled settings.
+//!
+//! Validation collects every problem before failing. The `InvalidData` error
+//! it returns has a `problems` array of `{ path, message }` objects, where
+//! `path` is the offending key path such as `server.port` or `tags[2]`.
+
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::error::{self, ErrorKind, ScriptError};
+use crate::stdlib::{expect_args, string_arg, string_value, ScriptResult, ScriptValue, ScriptVec};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+
+/// Expected type of a configuration value
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    String,
+    I32,
+    F32,
+    Bool,
+    Array(Option<Box<Spec>>),
+    Table(Option<Schema>),
+    Any,
+}
+
+impl Kind {
+    fn name(&self) -> &'static str {
+        match self {
+            Kind::String => "string",
+            Kind::I32 => "i32",
+            Kind::F32 => "f32",
+            Kind::Bool => "bool",
+            Kind::Array(_) => "array",
+            Kind::Table(_) => "table",
+            Kind::Any => "any",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Spec {
+    kind: Kind,
+    required: bool,
+    default: Option<ScriptValue>,
+}
+
+/// Specs by key, sorted so problems are reported in a stable order
+type Schema = BTreeMap<String, Spec>;
+
+/// A value that does not match the schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigProblem {
+    /// Key path of the value, such as `server.port` or `tags[2]`
+    pub path: String,
+    pub message: String,
+}
+
+/// Entries of an object or string-keyed hash map
+fn entries(value: &ScriptValue) -> Option<Vec<(String, ScriptValue)>> {
+    match value {
+        ScriptValue::Object(fields) => Some(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+        ),
+        ScriptValue::HashMap(map) => map.iter().ok(),
+        _ => None,
+    }
+}
+
+fn parse_schema(value: &ScriptValue, path: &str) -> Result<Schema, String> {
+    let entries = entries(value).ok_or_else(|| {
+        format!(
+            "Schema{} must be an object, got {:?}",
+            at(path),
+            value.get_type()
+        )
+    })?;
+    entries
+        .into_iter()
+        .map(|(key, spec)| {
+            let spec = parse_spec(&spec, &join(path, &key))?;
+            Ok((key, spec))
+        })
+        .collect()
+}
+
+fn parse_spec(value: &ScriptValue, path: &str) -> Result<Spec, String> {
+    if let Some(name) = value.as_string() {
+        return Ok(Spec {
+            kind: parse_kind(name.as_str(), None, None, path)?,
+            required: false,
+            default: None,
+        });
+    }
+
+    let fields: HashMap<String, ScriptValue> = entries(value)
+        .ok_or_else(|| format!("Spec{} must be a type name or an object", at(path)))?
+        .into_iter()
+        .collect();
+    if let Some(key) = fields.keys().find(|key| {
+        !matches!(
+            key.as_str(),
+            "type" | "required" | "default" | "items" | "fields"
+        )
+    }) {
+        return Err(format!("Unknown spec key '{}'{}", key, at(path)));
+    }
+
+    let name = fields
+        .get("type")
+        .and_then(ScriptValue::as_string)
+        .ok_or_else(|| format!("Spec{} needs a 'type'", at(path)))?;
+    let required = match fields.get("required") {
+        None => false,
+        Some(ScriptValue::Bool(required)) => *required,
+        Some(_) => return Err(format!("'required'{} must be a bool", at(path))),
+    };
+    Ok(Spec {
+        kind: parse_kind(
+            name.as_str(),
+            fields.get("items"),
+            fields.get("fields"),
+            path,
+        )?,
+        required,
+        default: fields.get("default").cloned(),
+    })
+}
+
+fn parse_kind(
+    name: &str,
+    items: Option<&ScriptValue>,
+    fields: Option<&ScriptValue>,
+    path: &str,
+) -> Result<Kind, String> {
+    if items.is_some() && name != "array" {
+        return Err(format!("Only arrays have 'items'{}", at(path)));
+    }
+    if fields.is_some() && name != "table" {
+        return Err(format!("Only tables have 'fields'{}", at(path)));
+    }
+
+    Ok(match name {
+        "string" => Kind::String,
+        "i32" => Kind::I32,
+        "f32" => Kind::F32,
+        "bool" => Kind::Bool,
+        "array" => Kind::Array(
+            items
+                .map(|items| parse_spec(items, &format!("{}[]", path)).map(Box::new))
+                .transpose()?,
+        ),
+        "table" => Kind::Table(
+            fields
+                .map(|fields| parse_schema(fields, path))
+                .transpose()?,
+        ),
+        "any" => Kind::Any,
+        other => return Err(format!("Unknown type '{}'{}", other, at(path))),
+    })
+}
+
+/// ` for 'path'`, or nothing at the top level
+fn at(path: &str) -> String {
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!(" for '{}'", path)
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn toml_type_name(value: &toml::Value) -> &'static str {
+    match value {
+        toml::Value::String(_) => "string",
+        toml::Value::Integer(_) => "integer",
+        toml::Value::Float(_) => "float",
+        toml::Value::Boolean(_) => "bool",
+        toml::Value::Datetime(_) => "datetime",
+        toml::Value::Array(_) => "array",
+        toml::Value::Table(_) => "table",
+    }
+}
+
+fn object(fields: HashMap<String, ScriptValue>) -> ScriptValue {
+    ScriptValue::Object(ScriptRc::new(fields))
+}
+
+/// Convert a TOML value without a schema
+///
+/// Integers that do not fit an `i32` become `i64`, and datetimes strings.
+fn convert(value: &toml::Value) -> ScriptValue {
+    match value {
+        toml::Value::String(s) => string_value(s),
+        toml::Value::Integer(n) => match i32::try_from(*n) {
+            Ok(n) => ScriptValue::I32(n),
+            Err(_) => ScriptValue::I64(*n),
+        },
+        toml::Value::Float(f) => ScriptValue::F32(*f as f32),
+        toml::Value::Boolean(b) => ScriptValue::Bool(*b),
+        toml::Value::Datetime(datetime) => string_value(&datetime.to_string()),
+        toml::Value::Array(items) => ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(
+            items.iter().map(convert).collect(),
+        ))),
+        toml::Value::Table(table) => object(
+            table
+                .iter()
+                .map(|(key, value)| (key.clone(), convert(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn validate_table(
+    table: &toml::Table,
+    schema: &Schema,
+    path: &str,
+    problems: &mut Vec<ConfigProblem>,
+) -> HashMap<String, ScriptValue> {
+    let mut unknown: Vec<_> = table
+        .keys()
+        .filter(|key| !schema.contains_key(*key))
+        .collect();
+    unknown.sort();
+    for key in unknown {
+        report(problems, &join(path, key), "unknown key".to_string());
+    }
+
+    let mut values = HashMap::new();
+    for (key, spec) in schema {
+        let key_path = join(path, key);
+        let value = match (table.get(key), &spec.kind) {
+            (Some(value), _) => validate_value(value, &spec.kind, &key_path, problems),
+            (None, _) if spec.required => {
+                report(problems, &key_path, "missing required key".to_string())
+            }
+            (None, _) if spec.default.is_some() => spec.default.clone(),
+            (None, Kind::Table(Some(fields))) => Some(object(validate_table(
+                &toml::Table::new(),
+                fields,
+                &key_path,
+                problems,
+            ))),
+            (None, _) => None,
+        };
+        if let Some(value) = value {
+            values.insert(key.clone(), value);
+        }
+    }
+    values
+}
+
+fn validate_value(
+    value: &toml::Value,
+    kind: &Kind,
+    path: &str,
+    problems: &mut Vec<ConfigProblem>,
+) -> Option<ScriptValue> {
+    match (kind, value) {
+        (Kind::Any, value) => Some(convert(value)),
+        (Kind::String, toml::Value::String(s)) => Some(string_value(s)),
+        (Kind::I32, toml::Value::Integer(n)) => match i32::try_from(*n) {
+            Ok(n) => Some(ScriptValue::I32(n)),
+            Err(_) => report(problems, path, format!("{} is out of range for i32", n)),
+        },
+        (Kind::F32, toml::Value::Float(f)) => Some(ScriptValue::F32(*f as f32)),
+        (Kind::F32, toml::Value::Integer(n)) => Some(ScriptValue::F32(*n as f32)),
+        (Kind::Bool, toml::Value::Boolean(b)) => Some(ScriptValue::Bool(*b)),
+        (Kind::Array(None), toml::Value::Array(_)) => Some(convert(value)),
+        (Kind::Array(Some(items)), toml::Value::Array(values)) => {
+            let mut converted = Vec::with_capacity(values.len());
+            let mut valid = true;
+            for (index, item) in values.iter().enumerate() {
+                let item_path = format!("{}[{}]", path, index);
+                match validate_value(item, &items.kind, &item_path, problems) {
+                    Some(item) => converted.push(item),
+                    None => valid = false,
+                }
+            }
+            valid.then(|| ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(converted))))
+        }
+        (Kind::Table(None), toml::Value::Table(_)) => Some(convert(value)),
+        (Kind::Table(Some(fields)), toml::Value::Table(table)) => {
+            Some(object(validate_table(table, fields, path, problems)))
+        }
+        (kind, value) => report(
+            problems,
+            path,
+            format!("expected {}, found {}", kind.name(), toml_type_name(value)),
+        ),
+    }
+}
+
+fn report(problems: &mut Vec<ConfigProblem>, path: &str, message: String) -> Option<ScriptValue> {
+    problems.push(ConfigProblem {
+        path: path.to_string(),
+        message,
+    });
+    None
+}
+
+/// Parse TOML source and check it against a schema, returning the
+/// configuration as an object or every problem found
+///
+/// Fails with a `RuntimeError` if the schema itself is malformed.
+pub fn load_config_str(
+    source: &str,
+    schema: &ScriptValue,
+) -> RuntimeResult<Result<ScriptValue, ConfigError>> {
+    let schema = parse_schema(schema, "").map_err(RuntimeError::InvalidOperation)?;
+
+    let table = match toml::from_str::<toml::Table>(source) {
+        Ok(table) => table,
+        Err(e) => {
+            let line = e.span().map(|span| {
+                source[..span.start.min(source.len())]
+                    .lines()
+                    .count()
+                    .max(1)
+            });
+            let message = match line {
+                Some(line) => format!("line {}: {}", line, e.message()),
+                None => e.message().to_string(),
+            };
+            return Ok(Err(ConfigError::Parse(message)));
+        }
+    };
+
+    let mut problems = Vec::new();
+    let config = validate_table(&table, &schema, "", &mut problems);
+    if problems.is_empty() {
+        Ok(Ok(object(config)))
+    } else {
+        Ok(Err(ConfigError::Invalid(problems)))
+    }
+}
+
+/// Why a configuration could not be loaded
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The source is not valid TOML
+    Parse(String),
+    /// The values do not match the schema
+    Invalid(Vec<ConfigProblem>),
+}
+
+impl ConfigError {
+    /// Convert to a Script `Error`, naming `source` in the message
+    fn to_script_value(&self, source: &str) -> ScriptValue {
+        match self {
+            ConfigError::Parse(message) => error::error_object(
+                ErrorKind::Parse,
+                &format!("Invalid TOML in {}: {}", source, message),
+                None,
+                None,
+            ),
+            ConfigError::Invalid(problems) => {
+                let summary: Vec<String> = problems
+                    .iter()
+                    .map(|problem| format!("{}: {}", problem.path, problem.message))
+                    .collect();
+                let error = error::error_object(
+                    ErrorKind::InvalidData,
+                    &format!(
+                        "Invalid configuration in {}: {}",
+                        source,
+                        summary.join("; ")
+                    ),
+                    None,
+                    None,
+                );
+
+                let problems = problems
+                    .iter()
+                    .map(|problem| {
+                        object(HashMap::from([
+                            ("path".to_string(), string_value(&problem.path)),
+                            ("message".to_string(), string_value(&problem.message)),
+                        ]))
+                    })
+                    .collect();
+                let ScriptValue::Object(fields) = error else {
+                    return error;
+                };
+                let mut fields = (*fields).clone();
+                fields.insert(
+                    "problems".to_string(),
+                    ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(problems))),
+                );
+                object(fields)
+            }
+        }
+    }
+}
+
+fn to_result(loaded: Result<ScriptValue, ConfigError>, source: &str) -> ScriptValue {
+    let result = match loaded {
+        Ok(config) => ScriptResult::ok(config),
+        Err(e) => ScriptResult::err(e.to_script_value(source)),
+    };
+    ScriptValue::Result(ScriptRc::new(result))
+}
+
+/// Load a TOML configuration file and check it against a schema
+pub fn config_load_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "config_load")?;
+    let path = string_arg(args, 0, "config_load")?;
+
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(e) => {
+            let error = error::io_error_from_std(e)
+                .context(format!("Failed to read config file '{}'", path));
+            let result = ScriptResult::err(error.to_script_value());
+            return Ok(ScriptValue::Result(ScriptRc::new(result)));
+        }
+    };
+    let loaded = load_config_str(&source, &args[1])?;
+    Ok(to_result(loaded, &format!("'{}'", path)))
+}
+
+/// Parse a TOML configuration string and check it against a schema
+pub fn config_parse_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "config_parse")?;
+    let source = string_arg(args, 0, "config_parse")?;
+
+    let loaded = load_config_str(source, &args[1])?;
+    Ok(to_result(loaded, "config"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(fields: &[(&str, ScriptValue)]) -> ScriptValue {
+        object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    fn schema() -> ScriptValue {
+        spec(&[
+            (
+                "name",
+                spec(&[
+                    ("type", string_value("string")),
+                    ("required", ScriptValue::Bool(true)),
+                ]),
+            ),
+            (
+                "port",
+                spec(&[
+                    ("type", string_value("i32")),
+                    ("default", ScriptValue::I32(8080)),
+                ]),
+            ),
+            ("debug", string_value("bool")),
+            (
+                "tags",
+                spec(&[
+                    ("type", string_value("array")),
+                    ("items", string_value("string")),
+                ]),
+            ),
+            (
+                "server",
+                spec(&[
+                    ("type", string_value("table")),
+                    (
+                        "fields",
+                        spec(&[(
+                            "host",
+                            spec(&[
+                                ("type", string_value("string")),
+                                ("default", string_value("localhost")),
+                            ]),
+                        )]),
+                    ),
+                ]),
+            ),
+        ])
+    }
+
+    fn field(config: &ScriptValue, key: &str) -> ScriptValue {
+        match config {
+            ScriptValue::Object(fields) => fields[key].clone(),
+            other => panic!("expected an object, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_defaults_and_nested_tables() {
+        let config = load_config_str("name = \"app\"\ntags = [\"a\", \"b\"]\n", &schema())
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(field(&config, "name"), string_value("app"));
+        assert_eq!(field(&config, "port"), ScriptValue::I32(8080));
+        assert_eq!(
+            field(&field(&config, "server"), "host"),
+            string_value("localhost")
+        );
+        let ScriptValue::Object(fields) = &config else {
+            unreachable!()
+        };
+        assert!(!fields.contains_key("debug"));
+    }
+
+    #[test]
+    fn test_problems_name_key_paths() {
+        let source = "port = \"80\"\ntags = [\"a\", 2]\nverbose = true\n[server]\nhost = 1\n";
+        let Err(ConfigError::Invalid(problems)) = load_config_str(source, &schema()).unwrap()
+        else {
+            panic!("expected validation problems");
+        };
+
+        let found: Vec<_> = problems
+            .iter()
+            .map(|problem| (problem.path.as_str(), problem.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("verbose", "unknown key"),
+                ("name", "missing required key"),
+                ("port", "expected i32, found string"),
+                ("server.host", "expected string, found integer"),
+                ("tags[1]", "expected string, found integer"),
+            ]
+        );
+
+        let error = ConfigError::Invalid(problems).to_script_value("'app.toml'");
+        assert!(matches!(field(&error, "problems"), ScriptValue::Array(items) if items.len() == 5));
+    }
+
+    #[test]
+    fn test_parse_and_schema_errors() {
+        let parsed = load_config_str("name = \"app\"\nport = ", &schema()).unwrap();
+        assert!(
+            matches!(parsed, Err(ConfigError::Parse(message)) if message.starts_with("line 2"))
+        );
+
+        let bad_schema = spec(&[("port", string_value("integer"))]);
+        assert!(load_config_str("", &bad_schema).is_err());
+        let bad_spec = spec(&[(
+            "port",
+            spec(&[("type", string_value("i32")), ("min", ScriptValue::I32(1))]),
+        )]);
+        assert!(load_config_str("", &bad_spec).is_err());
+    }
+}
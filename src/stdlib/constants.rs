@@ -0,0 +1,65 @@
+//! Global constants provided by the standard library
+//!
+//! Constants such as `PI` and `MAX_I32` are names rather than functions:
+//! semantic analysis resolves them like immutable variables and lowering
+//! replaces each use with the constant value, so they can appear anywhere a
+//! literal can, including `@const` functions.
+//!
+//! Float constants are `f32`, matching unsuffixed float literals.
+
+use crate::stdlib::ScriptValue;
+use crate::types::Type;
+
+/// Names of all standard library constants
+pub const CONSTANT_NAMES: &[&str] = &[
+    "PI", "TAU", "E", "EPSILON", "INFINITY", "NAN", "MAX_F32", "MIN_F32", "MAX_I32", "MIN_I32",
+    "MAX_I64", "MIN_I64", "MAX_U32", "MAX_U64",
+];
+
+/// Value of the standard library constant `name`
+pub fn constant_value(name: &str) -> Option<ScriptValue> {
+    let value = match name {
+        "PI" => ScriptValue::F32(std::f32::consts::PI),
+        "TAU" => ScriptValue::F32(std::f32::consts::TAU),
+        "E" => ScriptValue::F32(std::f32::consts::E),
+        "EPSILON" => ScriptValue::F32(f32::EPSILON),
+        "INFINITY" => ScriptValue::F32(f32::INFINITY),
+        "NAN" => ScriptValue::F32(f32::NAN),
+        "MAX_F32" => ScriptValue::F32(f32::MAX),
+        "MIN_F32" => ScriptValue::F32(f32::MIN),
+        "MAX_I32" => ScriptValue::I32(i32::MAX),
+        "MIN_I32" => ScriptValue::I32(i32::MIN),
+        "MAX_I64" => ScriptValue::I64(i64::MAX),
+        "MIN_I64" => ScriptValue::I64(i64::MIN),
+        "MAX_U32" => ScriptValue::U32(u32::MAX),
+        "MAX_U64" => ScriptValue::U64(u64::MAX),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Type of the standard library constant `name`
+pub fn constant_type(name: &str) -> Option<Type> {
+    constant_value(name).map(|value| value.get_type())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_constant_has_a_value() {
+        for name in CONSTANT_NAMES {
+            assert!(constant_value(name).is_some(), "{} has no value", name);
+        }
+        assert!(constant_value("pi").is_none());
+    }
+
+    #[test]
+    fn test_constant_types() {
+        assert_eq!(constant_value("MAX_I32"), Some(ScriptValue::I32(i32::MAX)));
+        assert_eq!(constant_type("TAU"), Some(Type::F32));
+        assert_eq!(constant_type("MAX_U64"), Some(Type::U64));
+        assert_eq!(constant_type("UNKNOWN"), None);
+    }
+}
@@ -0,0 +1,285 @@
+//! Conversions between Script values
+//!
+//! This module provides the runtime side of the language's conversions:
+//! - Checked numeric conversions that fail instead of losing information
+//! - Parsing integers and floats from strings
+//! - Formatting any value as a string
+//!
+//! Lossy numeric conversions are written `cast<T>(value)` and compile to a
+//! single IR `Cast` instruction; see [`crate::types::conversion::cast_result_type`]
+//! for their semantics.
+
+use crate::runtime::{RuntimeError, ScriptRc};
+use crate::stdlib::error::{self, ErrorKind};
+use crate::stdlib::{ScriptOption, ScriptResult, ScriptString, ScriptValue};
+use crate::types::Type;
+
+/// Numeric value widened to a common representation for checked conversions
+enum Numeric {
+    Int(i128),
+    Float(f64),
+}
+
+impl Numeric {
+    fn of(value: &ScriptValue) -> Option<Self> {
+        match value {
+            ScriptValue::I32(val) => Some(Numeric::Int(*val as i128)),
+            ScriptValue::I64(val) => Some(Numeric::Int(*val as i128)),
+            ScriptValue::U32(val) => Some(Numeric::Int(*val as i128)),
+            ScriptValue::U64(val) => Some(Numeric::Int(*val as i128)),
+            ScriptValue::F32(val) => Some(Numeric::Float(*val as f64)),
+            ScriptValue::F64(val) => Some(Numeric::Float(*val)),
+            _ => None,
+        }
+    }
+}
+
+/// Convert a numeric value to `target`, failing instead of truncating or wrapping
+///
+/// Integer targets reject values outside their range and floats with a
+/// fractional part; float targets reject finite values that would overflow.
+pub fn convert_checked(value: &ScriptValue, target: &Type) -> Result<ScriptValue, String> {
+    let numeric = Numeric::of(value)
+        .ok_or_else(|| format!("Cannot convert {} to {}", value.get_type(), target))?;
+    let out_of_range = || format!("{} is out of range for {}", display_numeric(value), target);
+
+    let int = match (&numeric, target) {
+        (Numeric::Int(val), Type::F32) => return Ok(ScriptValue::F32(*val as f32)),
+        (Numeric::Int(val), Type::F64) => return Ok(ScriptValue::F64(*val as f64)),
+        (Numeric::Float(val), Type::F32) => {
+            let narrowed = *val as f32;
+            if val.is_finite() && !narrowed.is_finite() {
+                return Err(out_of_range());
+            }
+            return Ok(ScriptValue::F32(narrowed));
+        }
+        (Numeric::Float(val), Type::F64) => return Ok(ScriptValue::F64(*val)),
+        (Numeric::Int(val), _) => *val,
+        (Numeric::Float(val), _) => {
+            if !val.is_finite() || val.fract() != 0.0 {
+                return Err(format!(
+                    "{} is not an integer and cannot be converted to {}",
+                    display_numeric(value),
+                    target
+                ));
+            }
+            if val.abs() > i128::MAX as f64 {
+                return Err(out_of_range());
+            }
+            *val as i128
+        }
+    };
+
+    match target {
+        Type::I32 => i32::try_from(int)
+            .map(ScriptValue::I32)
+            .map_err(|_| out_of_range()),
+        Type::I64 => i64::try_from(int)
+            .map(ScriptValue::I64)
+            .map_err(|_| out_of_range()),
+        Type::U32 => u32::try_from(int)
+            .map(ScriptValue::U32)
+            .map_err(|_| out_of_range()),
+        Type::U64 => u64::try_from(int)
+            .map(ScriptValue::U64)
+            .map_err(|_| out_of_range()),
+        _ => Err(format!("{} is not a numeric type", target)),
+    }
+}
+
+fn display_numeric(value: &ScriptValue) -> String {
+    match value {
+        ScriptValue::I32(val) => val.to_string(),
+        ScriptValue::I64(val) => val.to_string(),
+        ScriptValue::U32(val) => val.to_string(),
+        ScriptValue::U64(val) => val.to_string(),
+        ScriptValue::F32(val) => val.to_string(),
+        ScriptValue::F64(val) => val.to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// Wrap a conversion outcome in a Script `Result`, failing with `kind`
+fn script_result(result: Result<ScriptValue, String>, kind: ErrorKind) -> ScriptValue {
+    let result = match result {
+        Ok(value) => ScriptResult::ok(value),
+        Err(message) => ScriptResult::err(error::error_object(kind, &message, None, None)),
+    };
+    ScriptValue::Result(ScriptRc::new(result))
+}
+
+fn checked_conversion_impl(
+    name: &str,
+    target: Type,
+    args: &[ScriptValue],
+) -> Result<ScriptValue, RuntimeError> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "{} expects 1 argument, got {}",
+            name,
+            args.len()
+        )));
+    }
+
+    let kind = match Numeric::of(&args[0]) {
+        Some(_) => ErrorKind::OutOfRange,
+        None => ErrorKind::InvalidInput,
+    };
+    Ok(script_result(convert_checked(&args[0], &target), kind))
+}
+
+/// Checked conversion to i32
+pub(crate) fn as_i32_checked_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    checked_conversion_impl("as_i32_checked", Type::I32, args)
+}
+
+/// Checked conversion to i64
+pub(crate) fn as_i64_checked_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    checked_conversion_impl("as_i64_checked", Type::I64, args)
+}
+
+/// Checked conversion to u32
+pub(crate) fn as_u32_checked_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    checked_conversion_impl("as_u32_checked", Type::U32, args)
+}
+
+/// Checked conversion to u64
+pub(crate) fn as_u64_checked_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    checked_conversion_impl("as_u64_checked", Type::U64, args)
+}
+
+/// Checked conversion to f32
+pub(crate) fn as_f32_checked_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    checked_conversion_impl("as_f32_checked", Type::F32, args)
+}
+
+/// Checked conversion to f64
+pub(crate) fn as_f64_checked_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    checked_conversion_impl("as_f64_checked", Type::F64, args)
+}
+
+/// Parse an i32 written in `radix`, returning `Result<i32, Error>`
+///
+/// Surrounding whitespace and a leading `+` or `-` are accepted; prefixes
+/// such as `0x` are not.
+pub(crate) fn parse_int_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    if args.len() != 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "parse_int expects 2 arguments, got {}",
+            args.len()
+        )));
+    }
+
+    let (ScriptValue::String(text), ScriptValue::I32(radix)) = (&args[0], &args[1]) else {
+        return Err(RuntimeError::InvalidOperation(
+            "parse_int expects a string and an i32 radix".to_string(),
+        ));
+    };
+    if !(2..=36).contains(radix) {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "parse_int radix must be between 2 and 36, got {}",
+            radix
+        )));
+    }
+
+    let result = i32::from_str_radix(text.as_str().trim(), *radix as u32)
+        .map(ScriptValue::I32)
+        .map_err(|e| {
+            format!(
+                "Failed to parse '{}' as a base {} integer: {}",
+                text.as_str(),
+                radix,
+                e
+            )
+        });
+    Ok(script_result(result, ErrorKind::Parse))
+}
+
+/// Parse an f32, returning `Result<f32, Error>`
+pub(crate) fn parse_float_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "parse_float expects 1 argument, got {}",
+            args.len()
+        )));
+    }
+
+    match &args[0] {
+        ScriptValue::String(text) => Ok(script_result(
+            text.parse_f32().map(ScriptValue::F32),
+            ErrorKind::Parse,
+        )),
+        _ => Err(RuntimeError::InvalidOperation(
+            "parse_float expects a string argument".to_string(),
+        )),
+    }
+}
+
+/// Format a value the way `to_string` shows it
+///
+/// Strings are shown without quotes at the top level and quoted inside
+/// containers.
+pub fn format_value(value: &ScriptValue) -> String {
+    match value {
+        ScriptValue::String(s) => s.as_str().to_string(),
+        other => format_nested(other),
+    }
+}
+
+fn format_nested(value: &ScriptValue) -> String {
+    match value {
+        ScriptValue::I32(val) => val.to_string(),
+        ScriptValue::I64(val) => val.to_string(),
+        ScriptValue::U32(val) => val.to_string(),
+        ScriptValue::U64(val) => val.to_string(),
+        ScriptValue::F32(val) => val.to_string(),
+        ScriptValue::F64(val) => val.to_string(),
+        ScriptValue::Bool(val) => val.to_string(),
+        ScriptValue::String(s) => format!("{:?}", s.as_str()),
+        ScriptValue::Unit => "()".to_string(),
+        ScriptValue::Array(vec) => {
+            let items: Vec<String> = vec
+                .to_vec()
+                .unwrap_or_default()
+                .iter()
+                .map(format_nested)
+                .collect();
+            format!("[{}]", items.join(", "))
+        }
+        ScriptValue::Option(option) => match &**option {
+            ScriptOption::Some(inner) => format!("Some({})", format_nested(inner)),
+            ScriptOption::None => "None".to_string(),
+        },
+        ScriptValue::Result(result) => match &**result {
+            ScriptResult::Ok(inner) => format!("Ok({})", format_nested(inner)),
+            ScriptResult::Err(inner) => format!("Err({})", format_nested(inner)),
+        },
+        ScriptValue::Enum(value) => match &value.data {
+            Some(inner) => format!(
+                "{}::{}({})",
+                value.type_name,
+                value.variant,
+                format_nested(inner)
+            ),
+            None => format!("{}::{}", value.type_name, value.variant),
+        },
+        ScriptValue::Object(_) => {
+            error::format_error(value).unwrap_or_else(|| format!("<{}>", value.get_type()))
+        }
+        other => format!("<{}>", other.get_type()),
+    }
+}
+
+/// Format any value as a string
+pub(crate) fn to_string_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "to_string expects 1 argument, got {}",
+            args.len()
+        )));
+    }
+
+    Ok(ScriptValue::String(ScriptRc::new(ScriptString::from_str(
+        &format_value(&args[0]),
+    ))))
+}
@@ -659,6 +659,46 @@ impl fmt::Display for ScriptResult {
     }
 }
 
+/// Value of a built-in enum other than Option and Result, such as `ErrorKind`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScriptEnum {
+    /// Name of the enum type
+    pub type_name: String,
+    /// Name of the variant
+    pub variant: String,
+    /// Payload of a tuple variant
+    pub data: Option<ScriptValue>,
+}
+
+impl ScriptEnum {
+    /// Create a unit variant
+    pub fn unit(type_name: &str, variant: &str) -> Self {
+        ScriptEnum {
+            type_name: type_name.to_string(),
+            variant: variant.to_string(),
+            data: None,
+        }
+    }
+
+    /// Create a variant carrying a value
+    pub fn with_data(type_name: &str, variant: &str, data: ScriptValue) -> Self {
+        ScriptEnum {
+            type_name: type_name.to_string(),
+            variant: variant.to_string(),
+            data: Some(data),
+        }
+    }
+}
+
+impl fmt::Display for ScriptEnum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.data {
+            Some(data) => write!(f, "{}::{}({:?})", self.type_name, self.variant, data),
+            None => write!(f, "{}::{}", self.type_name, self.variant),
+        }
+    }
+}
+
 // Implementation functions for stdlib registry
 
 /// Create a Some Option
@@ -0,0 +1,604 @@
+//! Line diffs and patches for Script
+//!
+//! `diff_lines(a, b)` compares two texts line by line and returns the
+//! changes as hunks, each an object with the position of the hunk in both
+//! texts and its lines:
+//!
+//! ```text
+//! { old_start: 2, old_count: 3, new_start: 2, new_count: 3,
+//!   lines: [{ kind: "equal", text: "b", newline: true },
+//!           { kind: "delete", text: "c", newline: true },
+//!           { kind: "insert", text: "C", newline: true },
+//!           { kind: "equal", text: "d", newline: true }] }
+//! ```
+//!
+//! Hunks keep up to three unchanged lines of context around each change, as
+//! in `diff -u`. `newline` is false only for a last line without a line
+//! break. `apply_patch(text, hunks)` applies hunks to a text, checking that
+//! the context and deleted lines still match, and `diff_unified(a, b,
+//! color)` renders the changes as a unified diff.
+//!
+//! The Rust side of this module is also used by the test runner to show
+//! multi-line `expect` mismatches and the changes `--update-expect` makes.
+
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::error::{self, ErrorKind};
+use crate::stdlib::{string_arg, string_value, ScriptResult, ScriptValue, ScriptVec};
+use colored::*;
+use std::collections::HashMap;
+
+/// Unchanged lines kept around each change
+pub const DEFAULT_CONTEXT: usize = 3;
+
+/// How a line differs between the two texts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineChange {
+    /// In both texts
+    Equal,
+    /// Only in the new text
+    Insert,
+    /// Only in the old text
+    Delete,
+}
+
+impl LineChange {
+    fn name(self) -> &'static str {
+        match self {
+            LineChange::Equal => "equal",
+            LineChange::Insert => "insert",
+            LineChange::Delete => "delete",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "equal" => Some(LineChange::Equal),
+            "insert" => Some(LineChange::Insert),
+            "delete" => Some(LineChange::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// One line of a hunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffLine {
+    pub change: LineChange,
+    /// Text of the line without its line break
+    pub text: String,
+    /// Whether the line ends with a line break
+    pub newline: bool,
+}
+
+impl DiffLine {
+    fn new(change: LineChange, line: &str) -> Self {
+        let text = line.strip_suffix('\n');
+        DiffLine {
+            change,
+            text: text.unwrap_or(line).to_string(),
+            newline: text.is_some(),
+        }
+    }
+
+    /// The line as it appears in the text
+    fn raw(&self) -> String {
+        if self.newline {
+            format!("{}\n", self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+/// A run of changed lines with their context
+///
+/// Starts are 1-based line numbers. A hunk that removes or adds nothing on
+/// one side starts after the line given for that side, as in `diff -u`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Lines of a text, each with its line break
+fn split_lines(text: &str) -> Vec<&str> {
+    text.split_inclusive('\n').collect()
+}
+
+/// The shortest edit turning `a` into `b` (Myers' algorithm), as the change
+/// of each line in order
+fn edit_script(a: &[&str], b: &[&str]) -> Vec<LineChange> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    if max == 0 {
+        return Vec::new();
+    }
+    let index = |k: isize| (k + max) as usize;
+
+    // Furthest x reached on each diagonal k = x - y, before each round
+    let mut v = vec![0isize; 2 * max as usize + 2];
+    let mut trace = Vec::new();
+    'search: for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Walk back from the end through the rounds
+    let mut changes = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let previous_k = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = v[index(previous_k)];
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            changes.push(LineChange::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            changes.push(if x == previous_x {
+                LineChange::Insert
+            } else {
+                LineChange::Delete
+            });
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    changes.reverse();
+    changes
+}
+
+/// Compare two texts line by line, keeping `context` unchanged lines
+/// around each change
+pub fn diff_lines(a: &str, b: &str, context: usize) -> Vec<Hunk> {
+    let (old, new) = (split_lines(a), split_lines(b));
+
+    // Each line with the number of old and new lines before it
+    let mut lines = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    for change in edit_script(&old, &new) {
+        let line = match change {
+            LineChange::Equal | LineChange::Delete => old[x],
+            LineChange::Insert => new[y],
+        };
+        lines.push((DiffLine::new(change, line), x, y));
+        match change {
+            LineChange::Equal => {
+                x += 1;
+                y += 1;
+            }
+            LineChange::Delete => x += 1,
+            LineChange::Insert => y += 1,
+        }
+    }
+
+    let is_change = |i: &usize| lines[*i].0.change != LineChange::Equal;
+    let mut hunks = Vec::new();
+    let mut next = 0;
+    while let Some(first) = (next..lines.len()).find(is_change) {
+        // Changes separated by at most twice the context share a hunk
+        let mut last = first;
+        while let Some(change) = (last + 1..lines.len()).find(is_change) {
+            if change - last - 1 > 2 * context {
+                break;
+            }
+            last = change;
+        }
+        let start = first.saturating_sub(context).max(next);
+        let end = (last + context + 1).min(lines.len());
+
+        let hunk_lines: Vec<DiffLine> = lines[start..end]
+            .iter()
+            .map(|(line, _, _)| line.clone())
+            .collect();
+        let count = |side: LineChange| {
+            hunk_lines
+                .iter()
+                .filter(|line| line.change == LineChange::Equal || line.change == side)
+                .count()
+        };
+        let (old_count, new_count) = (count(LineChange::Delete), count(LineChange::Insert));
+        let (_, old_before, new_before) = &lines[start];
+        hunks.push(Hunk {
+            old_start: *old_before + usize::from(old_count > 0),
+            old_count,
+            new_start: *new_before + usize::from(new_count > 0),
+            new_count,
+            lines: hunk_lines,
+        });
+        next = end;
+    }
+    hunks
+}
+
+/// Apply hunks made by `diff_lines` to a text
+///
+/// Fails if a hunk's context or deleted lines don't match the text, or if
+/// the hunks are out of order.
+pub fn apply_patch(text: &str, hunks: &[Hunk]) -> Result<String, String> {
+    let lines = split_lines(text);
+    let mut output = String::new();
+    let mut position = 0;
+
+    for (number, hunk) in hunks.iter().enumerate() {
+        let number = number + 1;
+        let before = if hunk.old_count == 0 {
+            hunk.old_start
+        } else {
+            hunk.old_start
+                .checked_sub(1)
+                .ok_or_else(|| format!("Hunk {} starts at line 0, but lines start at 1", number))?
+        };
+        if before < position || before > lines.len() {
+            return Err(format!(
+                "Hunk {} starts at line {}, which is {}",
+                number,
+                hunk.old_start,
+                if before < position {
+                    "before the end of the previous hunk"
+                } else {
+                    "past the end of the text"
+                }
+            ));
+        }
+        output.extend(lines[position..before].iter().copied());
+        position = before;
+
+        for line in &hunk.lines {
+            if line.change == LineChange::Insert {
+                output.push_str(&line.raw());
+                continue;
+            }
+            let expected = line.raw();
+            match lines.get(position) {
+                Some(actual) if *actual == expected => {}
+                actual => {
+                    return Err(format!(
+                        "Hunk {} doesn't match line {}: expected {:?}, found {:?}",
+                        number,
+                        position + 1,
+                        expected,
+                        actual.copied().unwrap_or("end of text")
+                    ))
+                }
+            }
+            if line.change == LineChange::Equal {
+                output.push_str(&expected);
+            }
+            position += 1;
+        }
+    }
+    output.extend(lines[position..].iter().copied());
+    Ok(output)
+}
+
+/// Render hunks as a unified diff between files named `old_name` and
+/// `new_name`, colored for a terminal if `color` is set
+///
+/// Identical texts render as an empty string.
+pub fn unified_diff(hunks: &[Hunk], old_name: &str, new_name: &str, color: bool) -> String {
+    if hunks.is_empty() {
+        return String::new();
+    }
+    let paint = |text: String, style: fn(ColoredString) -> ColoredString| {
+        if color {
+            style(text.normal()).to_string()
+        } else {
+            text
+        }
+    };
+    let range = |start: usize, count: usize| {
+        if count == 1 {
+            start.to_string()
+        } else {
+            format!("{},{}", start, count)
+        }
+    };
+
+    let mut output = String::new();
+    output += &paint(format!("--- {}", old_name), |s| s.bold());
+    output.push('\n');
+    output += &paint(format!("+++ {}", new_name), |s| s.bold());
+    output.push('\n');
+    for hunk in hunks {
+        output += &paint(
+            format!(
+                "@@ -{} +{} @@",
+                range(hunk.old_start, hunk.old_count),
+                range(hunk.new_start, hunk.new_count)
+            ),
+            |s| s.cyan(),
+        );
+        output.push('\n');
+        for line in &hunk.lines {
+            output += &match line.change {
+                LineChange::Equal => format!(" {}", line.text),
+                LineChange::Delete => paint(format!("-{}", line.text), |s| s.red()),
+                LineChange::Insert => paint(format!("+{}", line.text), |s| s.green()),
+            };
+            output.push('\n');
+            if !line.newline {
+                output.push_str("\\ No newline at end of file\n");
+            }
+        }
+    }
+    output
+}
+
+fn hunk_to_value(hunk: &Hunk) -> ScriptValue {
+    let lines = hunk
+        .lines
+        .iter()
+        .map(|line| {
+            let mut fields = HashMap::new();
+            fields.insert("kind".to_string(), string_value(line.change.name()));
+            fields.insert("text".to_string(), string_value(&line.text));
+            fields.insert("newline".to_string(), ScriptValue::Bool(line.newline));
+            ScriptValue::Object(ScriptRc::new(fields))
+        })
+        .collect();
+
+    let mut fields = HashMap::new();
+    for (name, value) in [
+        ("old_start", hunk.old_start),
+        ("old_count", hunk.old_count),
+        ("new_start", hunk.new_start),
+        ("new_count", hunk.new_count),
+    ] {
+        fields.insert(name.to_string(), ScriptValue::I32(value as i32));
+    }
+    fields.insert(
+        "lines".to_string(),
+        ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(lines))),
+    );
+    ScriptValue::Object(ScriptRc::new(fields))
+}
+
+/// Read a hunk object as returned by `diff_lines`
+fn hunk_from_value(value: &ScriptValue) -> Result<Hunk, String> {
+    let ScriptValue::Object(fields) = value else {
+        return Err(format!(
+            "Expected a hunk object, got {:?}",
+            value.get_type()
+        ));
+    };
+    let number = |name: &str| match fields.get(name) {
+        Some(ScriptValue::I32(n)) if *n >= 0 => Ok(*n as usize),
+        Some(ScriptValue::I64(n)) if *n >= 0 => Ok(*n as usize),
+        _ => Err(format!(
+            "Hunk field '{}' must be a non-negative integer",
+            name
+        )),
+    };
+    let Some(ScriptValue::Array(items)) = fields.get("lines") else {
+        return Err("Hunk field 'lines' must be an array".to_string());
+    };
+
+    let lines = items
+        .to_vec()
+        .unwrap_or_default()
+        .iter()
+        .map(|item| {
+            let ScriptValue::Object(line) = item else {
+                return Err(format!("Expected a line object, got {:?}", item.get_type()));
+            };
+            let change = match line.get("kind") {
+                Some(ScriptValue::String(kind)) => LineChange::from_name(kind.as_str()),
+                _ => None,
+            }
+            .ok_or("Line field 'kind' must be \"equal\", \"insert\" or \"delete\"")?;
+            let Some(ScriptValue::String(text)) = line.get("text") else {
+                return Err("Line field 'text' must be a string".to_string());
+            };
+            let newline = !matches!(line.get("newline"), Some(ScriptValue::Bool(false)));
+            Ok(DiffLine {
+                change,
+                text: text.as_str().to_string(),
+                newline,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(Hunk {
+        old_start: number("old_start")?,
+        old_count: number("old_count")?,
+        new_start: number("new_start")?,
+        new_count: number("new_count")?,
+        lines,
+    })
+}
+
+/// Implementation of diff_lines for the stdlib registry
+pub fn diff_lines_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "diff_lines expects 2 arguments, got {}",
+            args.len()
+        )));
+    }
+    let a = string_arg(args, 0, "diff_lines")?;
+    let b = string_arg(args, 1, "diff_lines")?;
+
+    let hunks = diff_lines(a, b, DEFAULT_CONTEXT)
+        .iter()
+        .map(hunk_to_value)
+        .collect();
+    Ok(ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(
+        hunks,
+    ))))
+}
+
+/// Implementation of apply_patch for the stdlib registry
+pub fn apply_patch_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "apply_patch expects 2 arguments, got {}",
+            args.len()
+        )));
+    }
+    let text = string_arg(args, 0, "apply_patch")?;
+    let ScriptValue::Array(items) = &args[1] else {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "apply_patch expects an array of hunks, got {:?}",
+            args[1].get_type()
+        )));
+    };
+
+    let hunks = items
+        .to_vec()
+        .unwrap_or_default()
+        .iter()
+        .map(hunk_from_value)
+        .collect::<Result<Vec<_>, String>>();
+    let result = match hunks {
+        Ok(hunks) => match apply_patch(text, &hunks) {
+            Ok(patched) => ScriptResult::ok(string_value(&patched)),
+            Err(message) => ScriptResult::err(error::error_object(
+                ErrorKind::InvalidData,
+                &message,
+                None,
+                None,
+            )),
+        },
+        Err(message) => ScriptResult::err(error::error_object(
+            ErrorKind::InvalidInput,
+            &message,
+            None,
+            None,
+        )),
+    };
+    Ok(ScriptValue::Result(ScriptRc::new(result)))
+}
+
+/// Implementation of diff_unified for the stdlib registry
+pub fn diff_unified_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 3 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "diff_unified expects 3 arguments, got {}",
+            args.len()
+        )));
+    }
+    let a = string_arg(args, 0, "diff_unified")?;
+    let b = string_arg(args, 1, "diff_unified")?;
+    let ScriptValue::Bool(color) = args[2] else {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "diff_unified expects a boolean for argument 3, got {:?}",
+            args[2].get_type()
+        )));
+    };
+
+    let hunks = diff_lines(a, b, DEFAULT_CONTEXT);
+    Ok(string_value(&unified_diff(&hunks, "a", "b", color)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\n";
+    const NEW: &str = "a\nb\nC\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm";
+
+    #[test]
+    fn test_diff_hunks_and_unified_rendering() {
+        let hunks = diff_lines(OLD, NEW, 2);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!(
+            (hunks[0].old_start, hunks[0].old_count),
+            (hunks[0].new_start, hunks[0].new_count)
+        );
+        assert_eq!((hunks[0].old_start, hunks[0].old_count), (1, 5));
+        assert_eq!(
+            (hunks[1].old_start, hunks[1].old_count, hunks[1].new_count),
+            (11, 2, 3)
+        );
+
+        assert_eq!(
+            unified_diff(&hunks, "old", "new", false),
+            "--- old\n+++ new\n\
+             @@ -1,5 +1,5 @@\n a\n b\n-c\n+C\n d\n e\n\
+             @@ -11,2 +11,3 @@\n k\n l\n+m\n\\ No newline at end of file\n"
+        );
+
+        // Close changes share a hunk
+        assert_eq!(diff_lines(OLD, NEW, 5).len(), 1);
+        assert!(diff_lines(OLD, OLD, 3).is_empty());
+        assert_eq!(unified_diff(&[], "old", "new", false), "");
+
+        let inserted = diff_lines("", "x\n", 3);
+        assert_eq!((inserted[0].old_start, inserted[0].old_count), (0, 0));
+    }
+
+    #[test]
+    fn test_apply_patch_round_trip() {
+        for (a, b) in [
+            (OLD, NEW),
+            (NEW, OLD),
+            ("", "one\ntwo\n"),
+            ("one\ntwo\n", ""),
+            ("x\ny\nz", "y\nz\nx\n"),
+        ] {
+            let hunks = diff_lines(a, b, DEFAULT_CONTEXT);
+            assert_eq!(apply_patch(a, &hunks).unwrap(), b);
+        }
+
+        let hunks = diff_lines(OLD, NEW, 1);
+        let error = apply_patch("a\nb\nX\nd\n", &hunks).unwrap_err();
+        assert!(error.starts_with("Hunk 1 doesn't match line 3"));
+    }
+
+    #[test]
+    fn test_script_functions() {
+        let hunks = diff_lines_impl(&[string_value(OLD), string_value(NEW)]).unwrap();
+        let ScriptValue::Array(items) = &hunks else {
+            panic!("expected an array of hunks");
+        };
+        assert_eq!(items.len(), 2);
+
+        let patched = apply_patch_impl(&[string_value(OLD), hunks.clone()]).unwrap();
+        let ScriptValue::Result(result) = patched else {
+            panic!("expected a result");
+        };
+        assert!(matches!(&*result, ScriptResult::Ok(ScriptValue::String(s)) if s.as_str() == NEW));
+
+        let mismatched = apply_patch_impl(&[string_value("z\n"), hunks]).unwrap();
+        let ScriptValue::Result(result) = mismatched else {
+            panic!("expected a result");
+        };
+        assert!(result.is_err());
+
+        let rendered = diff_unified_impl(&[
+            string_value("a\n"),
+            string_value("b\n"),
+            ScriptValue::Bool(false),
+        ])
+        .unwrap();
+        assert!(
+            matches!(rendered, ScriptValue::String(s) if s.as_str() == "--- a\n+++ b\n@@ -1 +1 @@\n-a\n+b\n")
+        );
+    }
+}
@@ -2,10 +2,19 @@
 //!
 //! This module provides a unified error handling system that allows custom error types
 //! to implement the Script Error trait for consistent error reporting and handling.
+//!
+//! Stdlib functions that can fail return `Result<T, Error>`, where `Error` is a
+//! built-in struct with these fields:
+//! - `kind`: an `ErrorKind` variant, for matching on the failure category
+//! - `message`: a description of what failed
+//! - `code`: the operating system error code, if there is one
+//! - `source`: the lower-level `Error` this one was raised from, if any
 
 use crate::runtime::{RuntimeError, ScriptRc};
-use crate::stdlib::ScriptValue;
+use crate::stdlib::{ScriptEnum, ScriptOption, ScriptString, ScriptValue};
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
 
 /// Standard Error trait for Script custom error types
 pub trait ScriptError: fmt::Debug + fmt::Display + Send + Sync + 'static {
@@ -39,33 +48,102 @@ pub trait ScriptError: fmt::Debug + fmt::Display + Send + Sync + 'static {
         true
     }
 
+    /// Get the failure category Script programs match on
+    fn error_kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+
     /// Convert to ScriptValue for Script language consumption
+    ///
+    /// The error becomes a Script `Error` object, with its causes converted
+    /// into the `source` chain.
     fn to_script_value(&self) -> ScriptValue {
-        let _error_map = std::collections::HashMap::from([
-            (
-                "message".to_string(),
-                ScriptValue::String(ScriptRc::new(crate::stdlib::ScriptString::new(
-                    self.message(),
-                ))),
-            ),
-            (
-                "kind".to_string(),
-                ScriptValue::String(ScriptRc::new(crate::stdlib::ScriptString::new(self.kind()))),
-            ),
-            (
-                "recoverable".to_string(),
-                ScriptValue::Bool(self.is_recoverable()),
-            ),
-        ]);
+        error_object(
+            self.error_kind(),
+            &self.message(),
+            self.code(),
+            self.cause().map(|cause| cause.to_script_value()),
+        )
+    }
+}
+
+/// Failure category of errors returned by the standard library
+///
+/// Script programs see this as the built-in `ErrorKind` enum, stored in the
+/// `kind` field of an `Error`, so failures can be matched by category with
+/// `match error.kind { ErrorKind::NotFound => ... }` instead of by comparing
+/// message strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    NotFound,
+    PermissionDenied,
+    ConnectionRefused,
+    TimedOut,
+    InvalidInput,
+    InvalidData,
+    OutOfRange,
+    Parse,
+    UnexpectedEof,
+    Network,
+    Other,
+}
+
+impl ErrorKind {
+    /// Name of the enum in Script programs
+    pub const TYPE_NAME: &'static str = "ErrorKind";
+
+    /// All variants in declaration order
+    pub const ALL: [ErrorKind; 11] = [
+        ErrorKind::NotFound,
+        ErrorKind::PermissionDenied,
+        ErrorKind::ConnectionRefused,
+        ErrorKind::TimedOut,
+        ErrorKind::InvalidInput,
+        ErrorKind::InvalidData,
+        ErrorKind::OutOfRange,
+        ErrorKind::Parse,
+        ErrorKind::UnexpectedEof,
+        ErrorKind::Network,
+        ErrorKind::Other,
+    ];
+
+    /// Name of the variant
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::NotFound => "NotFound",
+            ErrorKind::PermissionDenied => "PermissionDenied",
+            ErrorKind::ConnectionRefused => "ConnectionRefused",
+            ErrorKind::TimedOut => "TimedOut",
+            ErrorKind::InvalidInput => "InvalidInput",
+            ErrorKind::InvalidData => "InvalidData",
+            ErrorKind::OutOfRange => "OutOfRange",
+            ErrorKind::Parse => "Parse",
+            ErrorKind::UnexpectedEof => "UnexpectedEof",
+            ErrorKind::Network => "Network",
+            ErrorKind::Other => "Other",
+        }
+    }
 
-        // For now, return a simple string representation
-        // In a full implementation, this would return a proper error object
-        ScriptValue::String(ScriptRc::new(crate::stdlib::ScriptString::new(
-            self.message(),
+    /// Look up a variant by name
+    pub fn from_name(name: &str) -> Option<Self> {
+        ErrorKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Build the Script value of this kind
+    pub fn to_script_value(self) -> ScriptValue {
+        ScriptValue::Enum(ScriptRc::new(ScriptEnum::unit(
+            Self::TYPE_NAME,
+            self.name(),
         )))
     }
 }
 
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 /// Standard error types provided by the Script standard library
 
 /// I/O Error for file operations, network operations, etc.
@@ -74,6 +152,8 @@ pub struct IoError {
     pub message: String,
     pub kind: IoErrorKind,
     pub code: Option<i32>,
+    /// The lower-level error this one was raised from
+    pub source: Option<Arc<dyn ScriptError>>,
 }
 
 #[derive(Debug, Clone)]
@@ -126,6 +206,25 @@ impl ScriptError for IoError {
     fn kind(&self) -> String {
         format!("IoError::{}", self.kind)
     }
+
+    fn cause(&self) -> Option<&dyn ScriptError> {
+        self.source.as_deref()
+    }
+
+    fn error_kind(&self) -> ErrorKind {
+        match self.kind {
+            IoErrorKind::NotFound => ErrorKind::NotFound,
+            IoErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            IoErrorKind::ConnectionRefused => ErrorKind::ConnectionRefused,
+            IoErrorKind::InvalidInput => ErrorKind::InvalidInput,
+            IoErrorKind::InvalidData => ErrorKind::InvalidData,
+            IoErrorKind::TimedOut => ErrorKind::TimedOut,
+            IoErrorKind::UnexpectedEof => ErrorKind::UnexpectedEof,
+            IoErrorKind::Interrupted | IoErrorKind::WriteZero | IoErrorKind::Other => {
+                ErrorKind::Other
+            }
+        }
+    }
 }
 
 impl IoError {
@@ -134,6 +233,7 @@ impl IoError {
             message,
             kind,
             code: None,
+            source: None,
         }
     }
 
@@ -142,6 +242,17 @@ impl IoError {
             message,
             kind,
             code: Some(code),
+            source: None,
+        }
+    }
+
+    /// Wrap this error in one of the same kind describing what was being done
+    pub fn context(self, message: impl Into<String>) -> Self {
+        IoError {
+            message: message.into(),
+            kind: self.kind.clone(),
+            code: None,
+            source: Some(Arc::new(self)),
         }
     }
 
@@ -199,6 +310,10 @@ impl ScriptError for ValidationError {
         "ValidationError".to_string()
     }
 
+    fn error_kind(&self) -> ErrorKind {
+        ErrorKind::InvalidInput
+    }
+
     fn is_recoverable(&self) -> bool {
         true // Validation errors are typically recoverable
     }
@@ -292,6 +407,15 @@ impl ScriptError for NetworkError {
         format!("NetworkError::{}", self.kind)
     }
 
+    fn error_kind(&self) -> ErrorKind {
+        match self.kind {
+            NetworkErrorKind::ConnectionTimeout => ErrorKind::TimedOut,
+            NetworkErrorKind::ConnectionRefused => ErrorKind::ConnectionRefused,
+            NetworkErrorKind::InvalidUrl => ErrorKind::InvalidInput,
+            _ => ErrorKind::Network,
+        }
+    }
+
     fn is_recoverable(&self) -> bool {
         match self.kind {
             NetworkErrorKind::ConnectionTimeout => true,
@@ -348,6 +472,10 @@ impl ScriptError for ParseError {
         format!("ParseError::{}", self.format)
     }
 
+    fn error_kind(&self) -> ErrorKind {
+        ErrorKind::Parse
+    }
+
     fn is_recoverable(&self) -> bool {
         false // Parse errors are typically not recoverable
     }
@@ -370,7 +498,70 @@ pub fn io_error_from_std(err: std::io::Error) -> IoError {
         _ => IoErrorKind::Other,
     };
 
-    IoError::new(kind, err.to_string())
+    match err.raw_os_error() {
+        Some(code) => IoError::with_code(kind, err.to_string(), code),
+        None => IoError::new(kind, err.to_string()),
+    }
+}
+
+/// Build a Script `Error` object
+pub fn error_object(
+    kind: ErrorKind,
+    message: &str,
+    code: Option<i32>,
+    source: Option<ScriptValue>,
+) -> ScriptValue {
+    let option = |value: Option<ScriptValue>| {
+        ScriptValue::Option(ScriptRc::new(match value {
+            Some(value) => ScriptOption::some(value),
+            None => ScriptOption::none(),
+        }))
+    };
+
+    let fields = HashMap::from([
+        ("kind".to_string(), kind.to_script_value()),
+        (
+            "message".to_string(),
+            ScriptValue::String(ScriptRc::new(ScriptString::from_str(message))),
+        ),
+        ("code".to_string(), option(code.map(ScriptValue::I32))),
+        ("source".to_string(), option(source)),
+    ]);
+    ScriptValue::Object(ScriptRc::new(fields))
+}
+
+/// Format a Script `Error` object for display
+///
+/// The kind is followed by the messages of the error and each of its
+/// sources, as in `NotFound: Failed to read file 'a.txt': No such file or
+/// directory (os error 2)`. Returns `None` if `value` is not an `Error`.
+pub fn format_error(value: &ScriptValue) -> Option<String> {
+    let ScriptValue::Object(fields) = value else {
+        return None;
+    };
+    let ScriptValue::Enum(kind) = fields.get("kind")? else {
+        return None;
+    };
+    if kind.type_name != ErrorKind::TYPE_NAME {
+        return None;
+    }
+
+    let mut out = kind.variant.clone();
+    let mut current = Some(fields.clone());
+    while let Some(fields) = current {
+        if let Some(message) = fields.get("message").and_then(|m| m.as_string()) {
+            out.push_str(": ");
+            out.push_str(message.as_str());
+        }
+        current = match fields.get("source") {
+            Some(ScriptValue::Option(source)) => match &**source {
+                ScriptOption::Some(ScriptValue::Object(inner)) => Some(inner.clone()),
+                _ => None,
+            },
+            _ => None,
+        };
+    }
+    Some(out)
 }
 
 /// Implementation functions for stdlib registry
@@ -479,10 +670,71 @@ mod tests {
         let error = ValidationError::new("Test error".to_string());
         let script_value = error.to_script_value();
 
-        // Verify it returns a ScriptValue
-        match script_value {
-            ScriptValue::String(_) => {} // Expected
-            _ => panic!("Expected string ScriptValue"),
+        // Errors become Error objects with a matchable kind
+        match &script_value {
+            ScriptValue::Object(fields) => {
+                assert_eq!(
+                    fields.get("kind"),
+                    Some(&ErrorKind::InvalidInput.to_script_value())
+                );
+                assert_eq!(
+                    fields
+                        .get("message")
+                        .and_then(|m| m.as_string())
+                        .map(|s| s.as_str()),
+                    Some("Test error")
+                );
+            }
+            _ => panic!("Expected Error object ScriptValue"),
+        }
+        assert_eq!(
+            format_error(&script_value).as_deref(),
+            Some("InvalidInput: Test error")
+        );
+    }
+
+    #[test]
+    fn test_error_source_chain() {
+        let os_error = io_error_from_std(std::io::Error::from_raw_os_error(2));
+        assert_eq!(os_error.code(), Some(2));
+        let error = os_error.context("Failed to read file 'a.txt'");
+        assert_eq!(error.code(), None);
+        assert_eq!(error.cause().and_then(|cause| cause.code()), Some(2));
+
+        let script_value = error.to_script_value();
+        let ScriptValue::Object(fields) = &script_value else {
+            panic!("Expected Error object ScriptValue");
+        };
+        let Some(ScriptValue::Option(source)) = fields.get("source") else {
+            panic!("Expected source field");
+        };
+        let ScriptOption::Some(ScriptValue::Object(source)) = &**source else {
+            panic!("Expected a source error");
+        };
+        let code = ScriptValue::Option(ScriptRc::new(ScriptOption::some(ScriptValue::I32(2))));
+        assert_eq!(source.get("code"), Some(&code));
+
+        let display = format_error(&script_value).unwrap();
+        assert!(display.starts_with("NotFound: Failed to read file 'a.txt': "));
+        assert!(display.ends_with("(os error 2)"));
+        assert_eq!(format_error(&ScriptValue::I32(2)), None);
+    }
+
+    #[test]
+    fn test_error_kind_categories() {
+        let error = io_error_from_std(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert_eq!(error.error_kind(), ErrorKind::NotFound);
+
+        let error = NetworkError {
+            message: "timed out".to_string(),
+            kind: NetworkErrorKind::ConnectionTimeout,
+            status_code: None,
+        };
+        assert_eq!(error.error_kind(), ErrorKind::TimedOut);
+
+        for kind in ErrorKind::ALL {
+            assert_eq!(ErrorKind::from_name(kind.name()), Some(kind));
         }
+        assert_eq!(ErrorKind::from_name("Missing"), None);
     }
 }
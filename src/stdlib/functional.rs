@@ -11,6 +11,7 @@ use crate::runtime::closure::debug::{
 use crate::runtime::closure::{Closure, ClosureRuntime};
 use crate::runtime::RuntimeError;
 use crate::runtime::{ScriptRc, Value};
+use crate::stdlib::iterators::ScriptIterator;
 use crate::stdlib::{ScriptOption, ScriptResult, ScriptValue, ScriptVec};
 use std::sync::{Arc, RwLock};
 
@@ -796,7 +797,16 @@ impl FunctionComposition {
 fn script_value_to_runtime_value(script_val: &ScriptValue) -> Result<Value> {
     match script_val {
         ScriptValue::I32(n) => Ok(Value::I32(*n)),
+        ScriptValue::I64(n) => Ok(Value::I64(*n)),
+        ScriptValue::U32(n) => Ok(Value::I64(*n as i64)),
+        ScriptValue::U64(n) => i64::try_from(*n).map(Value::I64).map_err(|_| {
+            Error::new(
+                ErrorKind::TypeError,
+                format!("u64 value {} does not fit in a runtime integer", n),
+            )
+        }),
         ScriptValue::F32(f) => Ok(Value::F32(*f)),
+        ScriptValue::F64(f) => Ok(Value::F64(*f)),
         ScriptValue::Bool(b) => Ok(Value::Bool(*b)),
         ScriptValue::String(s) => Ok(Value::String(s.to_string())),
         ScriptValue::Unit => Ok(Value::Null),
@@ -830,6 +840,17 @@ fn script_value_to_runtime_value(script_val: &ScriptValue) -> Result<Value> {
                 Ok(Value::err(runtime_err))
             }
         },
+        ScriptValue::Enum(value) => {
+            let data = match &value.data {
+                Some(inner) => Some(ScriptRc::new(script_value_to_runtime_value(inner)?)),
+                None => None,
+            };
+            Ok(Value::Enum {
+                type_name: value.type_name.clone(),
+                variant: value.variant.clone(),
+                data,
+            })
+        }
         ScriptValue::Closure(closure) => {
             // Create a runtime closure value
             Ok(Value::Closure(closure.clone()))
@@ -841,6 +862,21 @@ fn script_value_to_runtime_value(script_val: &ScriptValue) -> Result<Value> {
                 "Iterator conversion not yet implemented",
             ))
         }
+        ScriptValue::Weak(_) => Err(Error::new(
+            ErrorKind::TypeError,
+            "Weak reference conversion not yet implemented",
+        )),
+        ScriptValue::Channel(_) => Err(Error::new(
+            ErrorKind::TypeError,
+            "Channel conversion not yet implemented",
+        )),
+        ScriptValue::Mutex(_)
+        | ScriptValue::RwLock(_)
+        | ScriptValue::LockGuard(_)
+        | ScriptValue::AtomicI32(_) => Err(Error::new(
+            ErrorKind::TypeError,
+            "Lock and atomic conversion not yet implemented",
+        )),
         ScriptValue::Object(_) => {
             // For now, convert objects to a generic representation
             Err(Error::new(
@@ -862,7 +898,9 @@ fn script_value_to_runtime_value(script_val: &ScriptValue) -> Result<Value> {
 fn runtime_value_to_script_value(runtime_val: &Value) -> Result<ScriptValue> {
     match runtime_val {
         Value::I32(n) => Ok(ScriptValue::I32(*n)),
+        Value::I64(n) => Ok(ScriptValue::I64(*n)),
         Value::F32(f) => Ok(ScriptValue::F32(*f)),
+        Value::F64(f) => Ok(ScriptValue::F64(*f)),
         Value::Bool(b) => Ok(ScriptValue::Bool(*b)),
         Value::String(s) => Ok(ScriptValue::String(ScriptRc::new(
             crate::stdlib::ScriptString::from_str(s),
@@ -1387,6 +1425,51 @@ pub(crate) fn iter_skip_impl(
     Ok(ScriptValue::Iterator(ScriptRc::new(Box::new(skip_iter))))
 }
 
+/// Implementation of iter_peek for stdlib registry
+///
+/// Returns the next item without consuming it; for-loops pair this with
+/// `iter_advance` to step through immutable iterator values.
+pub(crate) fn iter_peek_impl(
+    args: &[ScriptValue],
+) -> std::result::Result<ScriptValue, RuntimeError> {
+    let iterator = single_iterator_arg("iter_peek", args)?;
+    let next = (**iterator).clone_box().next();
+
+    Ok(ScriptValue::Option(ScriptRc::new(match next {
+        Some(value) => ScriptOption::some(value),
+        None => ScriptOption::none(),
+    })))
+}
+
+/// Implementation of iter_advance for stdlib registry
+pub(crate) fn iter_advance_impl(
+    args: &[ScriptValue],
+) -> std::result::Result<ScriptValue, RuntimeError> {
+    let iterator = single_iterator_arg("iter_advance", args)?;
+    let mut advanced = (**iterator).clone_box();
+    advanced.next();
+
+    Ok(ScriptValue::Iterator(ScriptRc::new(advanced)))
+}
+
+fn single_iterator_arg<'a>(
+    name: &str,
+    args: &'a [ScriptValue],
+) -> std::result::Result<&'a ScriptRc<Box<dyn ScriptIterator>>, RuntimeError> {
+    match args {
+        [ScriptValue::Iterator(iter)] => Ok(iter),
+        [_] => Err(RuntimeError::InvalidOperation(format!(
+            "Argument to {} must be an iterator",
+            name
+        ))),
+        _ => Err(RuntimeError::InvalidOperation(format!(
+            "{} expects 1 argument, got {}",
+            name,
+            args.len()
+        ))),
+    }
+}
+
 /// Implementation of vec_flat_map for stdlib registry
 pub(crate) fn vec_flat_map_impl(
     args: &[ScriptValue],
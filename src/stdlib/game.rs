@@ -6,7 +6,7 @@
 //! - Random number generation
 //! - Time utilities
 
-use crate::runtime::{RuntimeError, ScriptRc};
+use crate::runtime::{deterministic, RuntimeError, ScriptRc};
 use crate::stdlib::ScriptValue;
 use std::collections::HashMap;
 
@@ -230,8 +230,8 @@ pub(crate) fn random(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError>
     }
 
     use rand::Rng;
-    let mut rng = rand::thread_rng();
-    Ok(ScriptValue::F32(rng.gen::<f32>()))
+    let value = deterministic::with_rng(|rng| rng.gen::<f32>());
+    Ok(ScriptValue::F32(value))
 }
 
 /// Generate a random float between min and max
@@ -247,8 +247,7 @@ pub(crate) fn random_range(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeE
     let max = args[1].to_f32()?;
 
     use rand::Rng;
-    let mut rng = rand::thread_rng();
-    let value = min + (max - min) * rng.gen::<f32>();
+    let value = min + (max - min) * deterministic::with_rng(|rng| rng.gen::<f32>());
 
     Ok(ScriptValue::F32(value))
 }
@@ -266,8 +265,7 @@ pub(crate) fn random_int(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeErr
     let max = args[1].to_i32()?;
 
     use rand::Rng;
-    let mut rng = rand::thread_rng();
-    let value = rng.gen_range(min..=max);
+    let value = deterministic::with_rng(|rng| rng.gen_range(min..=max));
 
     Ok(ScriptValue::I32(value))
 }
@@ -281,12 +279,7 @@ pub(crate) fn time_now(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError
         )));
     }
 
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| RuntimeError::InvalidOperation(format!("Time error: {}", e)))?;
-
-    Ok(ScriptValue::F32(duration.as_secs_f32()))
+    Ok(ScriptValue::F32(deterministic::system_now().as_secs_f32()))
 }
 
 /// Convert degrees to radians
@@ -0,0 +1,740 @@
+//! Graph data structure and algorithms for Script
+//!
+//! This module provides a `Graph` of named nodes joined by weighted edges,
+//! either directed or undirected, along with the algorithms game logic and
+//! build tooling tend to need:
+//! - Breadth-first and depth-first traversal
+//! - Topological sorting of directed acyclic graphs
+//! - Shortest paths (Dijkstra's algorithm)
+//! - Strongly connected components (Tarjan's algorithm)
+//!
+//! Nodes are identified by strings; numbers and booleans are converted to
+//! their string form. Traversals visit neighbors in the order their edges
+//! were added, so results are deterministic.
+
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::functional::execute_script_closure;
+use crate::stdlib::{
+    expect_args, handle_id, string_value, ScriptOption, ScriptResult, ScriptValue, ScriptVec,
+};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// Graphs created by scripts, keyed by the id stored in their handle
+fn graphs() -> MutexGuard<'static, HashMap<i32, Graph>> {
+    static GRAPHS: OnceLock<Mutex<HashMap<i32, Graph>>> = OnceLock::new();
+    GRAPHS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Object key holding a graph's id
+const GRAPH_ID_KEY: &str = "_graph_id";
+
+/// A graph of named nodes joined by weighted edges
+#[derive(Debug, Clone)]
+pub struct Graph {
+    directed: bool,
+    /// Node names by index
+    nodes: Vec<String>,
+    /// Node indices by name
+    index: HashMap<String, usize>,
+    /// Outgoing edges of each node as (target, weight), in insertion order
+    edges: Vec<Vec<(usize, f64)>>,
+    edge_count: usize,
+}
+
+impl Graph {
+    /// Create an empty graph
+    pub fn new(directed: bool) -> Self {
+        Graph {
+            directed,
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            edges: Vec::new(),
+            edge_count: 0,
+        }
+    }
+
+    /// Whether edges only go from their source to their target
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    /// Add a node, returning its index; adding an existing node is a no-op
+    pub fn add_node(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.index.get(name) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(name.to_string());
+        self.index.insert(name.to_string(), id);
+        self.edges.push(Vec::new());
+        id
+    }
+
+    /// Add an edge, creating missing nodes
+    ///
+    /// Adding an edge that already exists replaces its weight. Weights must
+    /// be finite and non-negative so shortest paths are well defined.
+    pub fn add_edge(&mut self, from: &str, to: &str, weight: f64) -> Result<(), String> {
+        if !weight.is_finite() || weight < 0.0 {
+            return Err(format!(
+                "Edge weights must be finite and non-negative, got {}",
+                weight
+            ));
+        }
+
+        let from = self.add_node(from);
+        let to = self.add_node(to);
+        if self.insert_edge(from, to, weight) {
+            self.edge_count += 1;
+        }
+        if !self.directed && from != to {
+            self.insert_edge(to, from, weight);
+        }
+        Ok(())
+    }
+
+    /// Insert or update an edge, returning whether it is new
+    fn insert_edge(&mut self, from: usize, to: usize, weight: f64) -> bool {
+        match self.edges[from]
+            .iter_mut()
+            .find(|(target, _)| *target == to)
+        {
+            Some(edge) => {
+                edge.1 = weight;
+                false
+            }
+            None => {
+                self.edges[from].push((to, weight));
+                true
+            }
+        }
+    }
+
+    pub fn contains_node(&self, name: &str) -> bool {
+        self.index.contains_key(name)
+    }
+
+    pub fn contains_edge(&self, from: &str, to: &str) -> bool {
+        match (self.index.get(from), self.index.get(to)) {
+            (Some(&from), Some(&to)) => self.edges[from].iter().any(|(target, _)| *target == to),
+            _ => false,
+        }
+    }
+
+    /// Names of all nodes, in the order they were added
+    pub fn nodes(&self) -> &[String] {
+        &self.nodes
+    }
+
+    /// Nodes reachable from `name` by a single edge
+    pub fn neighbors(&self, name: &str) -> Option<Vec<&str>> {
+        let id = *self.index.get(name)?;
+        Some(
+            self.edges[id]
+                .iter()
+                .map(|(target, _)| self.nodes[*target].as_str())
+                .collect(),
+        )
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+
+    fn names(&self, ids: impl IntoIterator<Item = usize>) -> Vec<String> {
+        ids.into_iter().map(|id| self.nodes[id].clone()).collect()
+    }
+
+    /// Nodes reachable from `start` in breadth-first order
+    pub fn bfs(&self, start: &str) -> Option<Vec<String>> {
+        let start = *self.index.get(start)?;
+        let mut visited = vec![false; self.nodes.len()];
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([start]);
+        visited[start] = true;
+
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            for &(next, _) in &self.edges[node] {
+                if !visited[next] {
+                    visited[next] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+        Some(self.names(order))
+    }
+
+    /// Nodes reachable from `start` in depth-first (preorder) order
+    pub fn dfs(&self, start: &str) -> Option<Vec<String>> {
+        let start = *self.index.get(start)?;
+        let mut visited = vec![false; self.nodes.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![start];
+
+        while let Some(node) = stack.pop() {
+            if visited[node] {
+                continue;
+            }
+            visited[node] = true;
+            order.push(node);
+            // Push in reverse so the first neighbor is explored first
+            for &(next, _) in self.edges[node].iter().rev() {
+                if !visited[next] {
+                    stack.push(next);
+                }
+            }
+        }
+        Some(self.names(order))
+    }
+
+    /// Order nodes so every edge goes from an earlier node to a later one
+    ///
+    /// Fails for undirected graphs and for graphs containing a cycle. Among
+    /// nodes that could come next, the one added first is chosen.
+    pub fn topological_sort(&self) -> Result<Vec<String>, String> {
+        if !self.directed {
+            return Err("Topological sort requires a directed graph".to_string());
+        }
+
+        let mut in_degree = vec![0usize; self.nodes.len()];
+        for edges in &self.edges {
+            for &(target, _) in edges {
+                in_degree[target] += 1;
+            }
+        }
+
+        // A min-heap of ready nodes keeps the order stable
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..self.nodes.len())
+            .filter(|&id| in_degree[id] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(Reverse(node)) = ready.pop() {
+            order.push(node);
+            for &(target, _) in &self.edges[node] {
+                in_degree[target] -= 1;
+                if in_degree[target] == 0 {
+                    ready.push(Reverse(target));
+                }
+            }
+        }
+
+        if order.len() < self.nodes.len() {
+            let cyclic = self.names((0..self.nodes.len()).filter(|&id| in_degree[id] > 0));
+            return Err(format!(
+                "Graph contains a cycle involving: {}",
+                cyclic.join(", ")
+            ));
+        }
+        Ok(self.names(order))
+    }
+
+    /// The lowest-weight path from `from` to `to` and its total weight
+    pub fn shortest_path(&self, from: &str, to: &str) -> Option<(Vec<String>, f64)> {
+        let from = *self.index.get(from)?;
+        let to = *self.index.get(to)?;
+
+        let mut distance = vec![f64::INFINITY; self.nodes.len()];
+        let mut previous: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        let mut frontier = BinaryHeap::new();
+        distance[from] = 0.0;
+        frontier.push(Frontier {
+            distance: 0.0,
+            node: from,
+        });
+
+        while let Some(Frontier { distance: d, node }) = frontier.pop() {
+            if node == to {
+                break;
+            }
+            if d > distance[node] {
+                continue;
+            }
+            for &(next, weight) in &self.edges[node] {
+                let candidate = d + weight;
+                if candidate < distance[next] {
+                    distance[next] = candidate;
+                    previous[next] = Some(node);
+                    frontier.push(Frontier {
+                        distance: candidate,
+                        node: next,
+                    });
+                }
+            }
+        }
+
+        if distance[to].is_infinite() {
+            return None;
+        }
+        let mut path = vec![to];
+        let mut node = to;
+        while let Some(prev) = previous[node] {
+            path.push(prev);
+            node = prev;
+        }
+        path.reverse();
+        Some((self.names(path), distance[to]))
+    }
+
+    /// Groups of nodes that can all reach each other
+    ///
+    /// For undirected graphs these are the connected components. Components
+    /// are returned in reverse topological order of the condensed graph.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<String>> {
+        let count = self.nodes.len();
+        let mut index = vec![usize::MAX; count];
+        let mut low_link = vec![0; count];
+        let mut on_stack = vec![false; count];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+
+        for root in 0..count {
+            if index[root] != usize::MAX {
+                continue;
+            }
+
+            // Iterative Tarjan: each frame is (node, next edge to explore)
+            let mut frames = vec![(root, 0)];
+            index[root] = next_index;
+            low_link[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some((node, edge)) = frames.last_mut() {
+                let node = *node;
+                if let Some(&(next, _)) = self.edges[node].get(*edge) {
+                    *edge += 1;
+                    if index[next] == usize::MAX {
+                        index[next] = next_index;
+                        low_link[next] = next_index;
+                        next_index += 1;
+                        stack.push(next);
+                        on_stack[next] = true;
+                        frames.push((next, 0));
+                    } else if on_stack[next] {
+                        low_link[node] = low_link[node].min(index[next]);
+                    }
+                    continue;
+                }
+
+                frames.pop();
+                if let Some(&(parent, _)) = frames.last() {
+                    low_link[parent] = low_link[parent].min(low_link[node]);
+                }
+                if low_link[node] == index[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    component.reverse();
+                    components.push(self.names(component));
+                }
+            }
+        }
+        components
+    }
+}
+
+/// A node waiting to be settled by Dijkstra's algorithm
+#[derive(Debug, PartialEq)]
+struct Frontier {
+    distance: f64,
+    node: usize,
+}
+
+impl Eq for Frontier {}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so the max-heap pops the closest node first
+        other
+            .distance
+            .total_cmp(&self.distance)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn string_array(items: &[String]) -> ScriptValue {
+    ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(
+        items.iter().map(|item| string_value(item)).collect(),
+    )))
+}
+
+/// Read a node name; numbers and booleans use their string form
+fn node_name(value: &ScriptValue) -> RuntimeResult<String> {
+    match value {
+        ScriptValue::String(s) => Ok(s.as_str().to_string()),
+        ScriptValue::I32(n) => Ok(n.to_string()),
+        ScriptValue::F32(n) => Ok(n.to_string()),
+        ScriptValue::Bool(b) => Ok(b.to_string()),
+        _ => Err(RuntimeError::InvalidOperation(
+            "Graph nodes must be strings, numbers or booleans".to_string(),
+        )),
+    }
+}
+
+fn with_graph<T>(value: &ScriptValue, f: impl FnOnce(&mut Graph) -> T) -> RuntimeResult<T> {
+    let id = handle_id(value, GRAPH_ID_KEY, "a graph created by graph_new")?;
+    graphs()
+        .get_mut(&id)
+        .map(f)
+        .ok_or_else(|| RuntimeError::InvalidOperation("Unknown graph".to_string()))
+}
+
+fn unknown_node(name: &str) -> RuntimeError {
+    RuntimeError::InvalidOperation(format!("Graph has no node '{}'", name))
+}
+
+/// Create a graph; `true` makes it directed
+pub fn graph_new_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "graph_new")?;
+
+    let directed = args[0]
+        .as_bool()
+        .ok_or_else(|| RuntimeError::InvalidOperation("graph_new expects a boolean".to_string()))?;
+    let mut graphs = graphs();
+    let id = graphs.len() as i32;
+    graphs.insert(id, Graph::new(directed));
+
+    let mut fields = HashMap::new();
+    fields.insert(GRAPH_ID_KEY.to_string(), ScriptValue::I32(id));
+    Ok(ScriptValue::Object(ScriptRc::new(fields)))
+}
+
+/// Add a node
+pub fn graph_add_node_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "graph_add_node")?;
+
+    let name = node_name(&args[1])?;
+    with_graph(&args[0], |graph| graph.add_node(&name))?;
+    Ok(ScriptValue::Unit)
+}
+
+/// Add an edge with weight 1
+pub fn graph_add_edge_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 3, "graph_add_edge")?;
+
+    let from = node_name(&args[1])?;
+    let to = node_name(&args[2])?;
+    with_graph(&args[0], |graph| graph.add_edge(&from, &to, 1.0))?
+        .map_err(RuntimeError::InvalidOperation)?;
+    Ok(ScriptValue::Unit)
+}
+
+/// Add an edge with the given weight
+pub fn graph_add_weighted_edge_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 4, "graph_add_weighted_edge")?;
+
+    let from = node_name(&args[1])?;
+    let to = node_name(&args[2])?;
+    let weight = args[3].to_f32()? as f64;
+    with_graph(&args[0], |graph| graph.add_edge(&from, &to, weight))?
+        .map_err(RuntimeError::InvalidOperation)?;
+    Ok(ScriptValue::Unit)
+}
+
+/// Check whether an edge exists
+pub fn graph_has_edge_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 3, "graph_has_edge")?;
+
+    let from = node_name(&args[1])?;
+    let to = node_name(&args[2])?;
+    Ok(ScriptValue::Bool(with_graph(&args[0], |graph| {
+        graph.contains_edge(&from, &to)
+    })?))
+}
+
+/// Get the names of all nodes
+pub fn graph_nodes_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "graph_nodes")?;
+
+    with_graph(&args[0], |graph| string_array(graph.nodes()))
+}
+
+/// Get the nodes one edge away from a node
+pub fn graph_neighbors_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "graph_neighbors")?;
+
+    let name = node_name(&args[1])?;
+    with_graph(&args[0], |graph| {
+        graph.neighbors(&name).map(|neighbors| {
+            let neighbors: Vec<String> = neighbors.into_iter().map(String::from).collect();
+            string_array(&neighbors)
+        })
+    })?
+    .ok_or_else(|| unknown_node(&name))
+}
+
+/// Call a closure with each node in `order` until it returns false,
+/// returning the nodes visited
+fn visit_nodes(order: Vec<String>, visit: &ScriptValue) -> RuntimeResult<ScriptValue> {
+    if !matches!(visit, ScriptValue::Closure(_)) {
+        return Err(RuntimeError::InvalidOperation(
+            "Graph traversal expects a closure to visit nodes".to_string(),
+        ));
+    }
+
+    let mut visited = Vec::with_capacity(order.len());
+    for name in order {
+        let keep_going = execute_script_closure(visit, &[string_value(&name)])
+            .map_err(|e| RuntimeError::InvalidOperation(e.to_string()))?;
+        visited.push(name);
+        if matches!(keep_going, ScriptValue::Bool(false)) {
+            break;
+        }
+    }
+    Ok(string_array(&visited))
+}
+
+/// Visit nodes breadth-first from a start node
+pub fn graph_bfs_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 3, "graph_bfs")?;
+
+    let start = node_name(&args[1])?;
+    let order =
+        with_graph(&args[0], |graph| graph.bfs(&start))?.ok_or_else(|| unknown_node(&start))?;
+    visit_nodes(order, &args[2])
+}
+
+/// Visit nodes depth-first from a start node
+pub fn graph_dfs_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 3, "graph_dfs")?;
+
+    let start = node_name(&args[1])?;
+    let order =
+        with_graph(&args[0], |graph| graph.dfs(&start))?.ok_or_else(|| unknown_node(&start))?;
+    visit_nodes(order, &args[2])
+}
+
+/// Sort the nodes of a directed acyclic graph, returning a Result
+pub fn graph_topo_sort_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "graph_topo_sort")?;
+
+    let result = match with_graph(&args[0], |graph| graph.topological_sort())? {
+        Ok(order) => ScriptResult::ok(string_array(&order)),
+        Err(message) => ScriptResult::err(string_value(&message)),
+    };
+    Ok(ScriptValue::Result(ScriptRc::new(result)))
+}
+
+/// Find the shortest path between two nodes, returning an Option of
+/// `{ path, distance }`
+pub fn graph_shortest_path_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 3, "graph_shortest_path")?;
+
+    let from = node_name(&args[1])?;
+    let to = node_name(&args[2])?;
+    let path = with_graph(&args[0], |graph| graph.shortest_path(&from, &to))?;
+
+    let option = match path {
+        Some((path, distance)) => {
+            let mut fields = HashMap::new();
+            fields.insert("path".to_string(), string_array(&path));
+            fields.insert("distance".to_string(), ScriptValue::F32(distance as f32));
+            ScriptOption::some(ScriptValue::Object(ScriptRc::new(fields)))
+        }
+        None => ScriptOption::none(),
+    };
+    Ok(ScriptValue::Option(ScriptRc::new(option)))
+}
+
+/// Group nodes into strongly connected components
+pub fn graph_scc_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "graph_scc")?;
+
+    let components = with_graph(&args[0], |graph| graph.strongly_connected_components())?;
+    Ok(ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(
+        components
+            .iter()
+            .map(|component| string_array(component))
+            .collect(),
+    ))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(directed: bool, edges: &[(&str, &str, f64)]) -> Graph {
+        let mut graph = Graph::new(directed);
+        for &(from, to, weight) in edges {
+            graph.add_edge(from, to, weight).unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn test_traversal_order() {
+        let graph = build(
+            true,
+            &[
+                ("a", "b", 1.0),
+                ("a", "c", 1.0),
+                ("b", "d", 1.0),
+                ("c", "d", 1.0),
+                ("d", "a", 1.0),
+            ],
+        );
+
+        assert_eq!(graph.bfs("a").unwrap(), vec!["a", "b", "c", "d"]);
+        assert_eq!(graph.dfs("a").unwrap(), vec!["a", "b", "d", "c"]);
+        assert_eq!(graph.bfs("d").unwrap(), vec!["d", "a", "b", "c"]);
+        assert!(graph.bfs("z").is_none());
+    }
+
+    #[test]
+    fn test_undirected_edges() {
+        let mut graph = build(false, &[("a", "b", 2.0)]);
+        assert!(graph.contains_edge("b", "a"));
+        assert_eq!(graph.edge_count(), 1);
+
+        // Re-adding an edge updates its weight in both directions
+        graph.add_edge("b", "a", 5.0).unwrap();
+        assert_eq!(graph.edge_count(), 1);
+        assert_eq!(graph.shortest_path("a", "b").unwrap().1, 5.0);
+
+        assert!(graph.add_edge("a", "c", -1.0).is_err());
+        assert!(graph.topological_sort().is_err());
+    }
+
+    #[test]
+    fn test_topological_sort() {
+        let graph = build(
+            true,
+            &[
+                ("compile", "link", 1.0),
+                ("fetch", "compile", 1.0),
+                ("link", "package", 1.0),
+                ("fetch", "test", 1.0),
+                ("compile", "test", 1.0),
+            ],
+        );
+        assert_eq!(
+            graph.topological_sort().unwrap(),
+            vec!["fetch", "compile", "link", "package", "test"]
+        );
+
+        let cyclic = build(true, &[("a", "b", 1.0), ("b", "a", 1.0), ("c", "a", 1.0)]);
+        let error = cyclic.topological_sort().unwrap_err();
+        assert!(error.contains("a, b"), "{}", error);
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let graph = build(
+            true,
+            &[
+                ("start", "a", 4.0),
+                ("start", "b", 1.0),
+                ("b", "a", 2.0),
+                ("a", "goal", 1.0),
+                ("b", "goal", 5.0),
+            ],
+        );
+
+        let (path, distance) = graph.shortest_path("start", "goal").unwrap();
+        assert_eq!(path, vec!["start", "b", "a", "goal"]);
+        assert_eq!(distance, 4.0);
+        assert_eq!(graph.shortest_path("goal", "start"), None);
+        assert_eq!(
+            graph.shortest_path("start", "start"),
+            Some((vec!["start".to_string()], 0.0))
+        );
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let graph = build(
+            true,
+            &[
+                ("a", "b", 1.0),
+                ("b", "c", 1.0),
+                ("c", "a", 1.0),
+                ("c", "d", 1.0),
+                ("d", "e", 1.0),
+                ("e", "d", 1.0),
+            ],
+        );
+
+        let mut components = graph.strongly_connected_components();
+        for component in &mut components {
+            component.sort();
+        }
+        assert_eq!(
+            components,
+            vec![
+                vec!["d".to_string(), "e".to_string()],
+                vec!["a".to_string(), "b".to_string(), "c".to_string()]
+            ]
+        );
+
+        let forest = build(false, &[("a", "b", 1.0), ("c", "d", 1.0)]);
+        assert_eq!(forest.strongly_connected_components().len(), 2);
+    }
+
+    #[test]
+    fn test_graph_script_functions() {
+        let graph = graph_new_impl(&[ScriptValue::Bool(true)]).unwrap();
+        let node = |name: &str| string_value(name);
+        graph_add_edge_impl(&[graph.clone(), node("a"), node("b")]).unwrap();
+        graph_add_weighted_edge_impl(&[
+            graph.clone(),
+            node("b"),
+            ScriptValue::I32(3),
+            ScriptValue::F32(2.5),
+        ])
+        .unwrap();
+        graph_add_node_impl(&[graph.clone(), node("lonely")]).unwrap();
+
+        let has_edge = graph_has_edge_impl(&[graph.clone(), node("b"), node("3")]).unwrap();
+        assert_eq!(has_edge.as_bool(), Some(true));
+
+        match graph_neighbors_impl(&[graph.clone(), node("a")]).unwrap() {
+            ScriptValue::Array(neighbors) => assert_eq!(neighbors.len(), 1),
+            other => panic!("expected array, got {:?}", other),
+        }
+        assert!(graph_neighbors_impl(&[graph.clone(), node("missing")]).is_err());
+
+        match graph_shortest_path_impl(&[graph.clone(), node("a"), node("3")]).unwrap() {
+            ScriptValue::Option(path) => match &*path {
+                ScriptOption::Some(ScriptValue::Object(fields)) => {
+                    assert!(
+                        matches!(fields.get("distance"), Some(ScriptValue::F32(d)) if *d == 3.5)
+                    );
+                }
+                other => panic!("expected path, got {:?}", other),
+            },
+            other => panic!("expected option, got {:?}", other),
+        }
+
+        match graph_topo_sort_impl(&[graph.clone()]).unwrap() {
+            ScriptValue::Result(result) => assert!(result.is_ok()),
+            other => panic!("expected result, got {:?}", other),
+        }
+
+        assert!(graph_bfs_impl(&[graph, node("a"), ScriptValue::Unit]).is_err());
+        assert!(graph_nodes_impl(&[ScriptValue::Unit]).is_err());
+    }
+}
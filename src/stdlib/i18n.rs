@@ -0,0 +1,1036 @@
+//! Localization for Script
+//!
+//! This module provides what user-facing programs need to be translated:
+//! - Message catalogs in a subset of the Fluent syntax, loaded per locale
+//!   with `i18n_load` and formatted with `i18n_message`
+//! - CLDR plural rules for common languages
+//! - Locale-aware number and date formatting
+//!
+//! A catalog is a list of messages, one per line, with indented lines
+//! continuing the message above:
+//!
+//! ```text
+//! # Comments start with '#'
+//! -brand = Script
+//! welcome = Welcome to { -brand }, { $name }!
+//! emails = { $count ->
+//!     [0] You have no new email.
+//!     [one] You have one new email.
+//!    *[other] You have { $count } new emails.
+//! }
+//! ```
+//!
+//! Placeables in braces insert a variable (`$name`), another message or a
+//! term (`-brand`), or a string literal (`"{"`). A select expression picks
+//! the variant whose key equals the selector, or for numbers the key naming
+//! its plural category, falling back to the variant marked `*`. Numbers are
+//! formatted for the message's locale.
+//!
+//! Locales are BCP 47 tags such as `en-US` or `pt_BR`. Lookups fall back
+//! from a regional locale to its language (`de-AT` to `de`); formatting
+//! rules are chosen by language and, where it matters, region. Dates are
+//! formatted in UTC.
+
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::conversion::format_value;
+use crate::stdlib::error::{self, ErrorKind};
+use crate::stdlib::{
+    expect_args, number_arg, number_value, string_arg, string_value, ScriptResult, ScriptValue,
+};
+use chrono::{DateTime, Datelike};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// How deeply messages may reference other messages
+const MAX_REFERENCE_DEPTH: usize = 8;
+
+/// CLDR plural category
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    /// The category's name as used in variant keys
+    pub fn name(self) -> &'static str {
+        match self {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+/// Language and region of a locale tag, lowercased
+fn split_locale(locale: &str) -> (String, String) {
+    let normalized = locale.trim().to_lowercase().replace('_', "-");
+    let mut parts = normalized.split('-');
+    let language = parts.next().unwrap_or_default().to_string();
+    // Skip a script subtag such as `Hant` to reach the region
+    let region = parts.find(|part| part.len() == 2).unwrap_or_default();
+    (language, region.to_string())
+}
+
+/// The plural category of a number in a locale
+///
+/// Languages without rules here use the English rules.
+pub fn plural_category(locale: &str, n: f64) -> PluralCategory {
+    use PluralCategory::*;
+
+    let (language, _) = split_locale(locale);
+    let n = n.abs();
+    let integer = n.fract() == 0.0;
+    // Integer digits, for the rules that look at the last one or two
+    let i = n.trunc() as u64;
+    let (i10, i100) = (i % 10, i % 100);
+
+    match language.as_str() {
+        "ja" | "zh" | "ko" | "th" | "vi" | "id" | "ms" => Other,
+        "fr" | "pt" if i <= 1 => One,
+        "fr" | "pt" => Other,
+        "ru" | "uk" | "be" if !integer => Other,
+        "ru" | "uk" | "be" if i10 == 1 && i100 != 11 => One,
+        "ru" | "uk" | "be" if (2..=4).contains(&i10) && !(12..=14).contains(&i100) => Few,
+        "ru" | "uk" | "be" => Many,
+        "pl" if !integer => Other,
+        "pl" if i == 1 => One,
+        "pl" if (2..=4).contains(&i10) && !(12..=14).contains(&i100) => Few,
+        "pl" => Many,
+        "cs" | "sk" if !integer => Many,
+        "cs" | "sk" if i == 1 => One,
+        "cs" | "sk" if (2..=4).contains(&i) => Few,
+        "cs" | "sk" => Other,
+        "ar" if !integer => Other,
+        "ar" => match i {
+            0 => Zero,
+            1 => One,
+            2 => Two,
+            _ if (3..=10).contains(&i100) => Few,
+            _ if (11..=99).contains(&i100) => Many,
+            _ => Other,
+        },
+        _ if integer && i == 1 => One,
+        _ => Other,
+    }
+}
+
+/// Separators and grouping of numbers in a locale
+struct NumberSymbols {
+    decimal: &'static str,
+    group: &'static str,
+    /// Group by two digits after the first three, as in 12,34,567
+    indian: bool,
+    /// Integer digits needed before grouping starts, beyond the first group
+    min_grouping: usize,
+}
+
+fn number_symbols(locale: &str) -> NumberSymbols {
+    let (language, region) = split_locale(locale);
+    let symbols = |decimal, group| NumberSymbols {
+        decimal,
+        group,
+        indian: false,
+        min_grouping: 1,
+    };
+
+    match language.as_str() {
+        "hi" | "bn" => NumberSymbols {
+            indian: true,
+            ..symbols(".", ",")
+        },
+        "en" if region == "in" => NumberSymbols {
+            indian: true,
+            ..symbols(".", ",")
+        },
+        "es" | "pl" => NumberSymbols {
+            min_grouping: 2,
+            ..symbols(",", if language == "pl" { "\u{a0}" } else { "." })
+        },
+        "de" if region == "ch" => symbols(".", "\u{2019}"),
+        "de" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" => symbols(",", "."),
+        "fr" => symbols(",", "\u{202f}"),
+        "ru" | "uk" | "be" | "cs" | "sk" | "sv" | "fi" | "nb" | "no" | "bg" => {
+            symbols(",", "\u{a0}")
+        }
+        _ => symbols(".", ","),
+    }
+}
+
+/// Format a number with `decimals` fraction digits in a locale
+pub fn format_number(value: f64, decimals: usize, locale: &str) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value < 0.0 { "-∞" } else { "∞" }.to_string();
+    }
+
+    let symbols = number_symbols(locale);
+    let digits = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = digits.split_once('.').unwrap_or((&digits, ""));
+    // Rounding can turn a small negative number into zero
+    let negative = value < 0.0 && digits.chars().any(|c| c.is_ascii_digit() && c != '0');
+
+    let mut result = String::new();
+    if negative {
+        result.push('-');
+    }
+    result.push_str(&group_digits(integer, &symbols));
+    if !fraction.is_empty() {
+        result.push_str(symbols.decimal);
+        result.push_str(fraction);
+    }
+    result
+}
+
+fn group_digits(integer: &str, symbols: &NumberSymbols) -> String {
+    if integer.len() < 3 + symbols.min_grouping {
+        return integer.to_string();
+    }
+
+    let (head, last) = integer.split_at(integer.len() - 3);
+    let size = if symbols.indian { 2 } else { 3 };
+    let mut groups = Vec::new();
+    let mut end = head.len();
+    while end > 0 {
+        let start = end.saturating_sub(size);
+        groups.push(&head[start..end]);
+        end = start;
+    }
+    groups.reverse();
+    groups.push(last);
+    groups.join(symbols.group)
+}
+
+/// Format a number as a message argument: integers without a fraction,
+/// other numbers with up to three fraction digits
+fn format_argument_number(value: f64, locale: &str) -> String {
+    if value.fract() == 0.0 {
+        return format_number(value, 0, locale);
+    }
+    let formatted = format_number(value, 3, locale);
+    let decimal = number_symbols(locale).decimal;
+    match formatted.rsplit_once(decimal) {
+        Some((integer, fraction)) => {
+            let fraction = fraction.trim_end_matches('0');
+            if fraction.is_empty() {
+                integer.to_string()
+            } else {
+                format!("{}{}{}", integer, decimal, fraction)
+            }
+        }
+        None => formatted,
+    }
+}
+
+/// How much of a date to spell out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateStyle {
+    /// Digits only, such as 1/15/2024
+    Short,
+    /// With the month name, such as January 15, 2024
+    Long,
+}
+
+impl DateStyle {
+    pub fn parse(style: &str) -> Option<Self> {
+        match style {
+            "short" => Some(DateStyle::Short),
+            "long" => Some(DateStyle::Long),
+            _ => None,
+        }
+    }
+}
+
+fn month_names(language: &str) -> Option<[&'static str; 12]> {
+    Some(match language {
+        "en" => [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ],
+        "de" => [
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ],
+        "fr" => [
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+        "es" => [
+            "enero",
+            "febrero",
+            "marzo",
+            "abril",
+            "mayo",
+            "junio",
+            "julio",
+            "agosto",
+            "septiembre",
+            "octubre",
+            "noviembre",
+            "diciembre",
+        ],
+        "it" => [
+            "gennaio",
+            "febbraio",
+            "marzo",
+            "aprile",
+            "maggio",
+            "giugno",
+            "luglio",
+            "agosto",
+            "settembre",
+            "ottobre",
+            "novembre",
+            "dicembre",
+        ],
+        "pt" => [
+            "janeiro",
+            "fevereiro",
+            "março",
+            "abril",
+            "maio",
+            "junho",
+            "julho",
+            "agosto",
+            "setembro",
+            "outubro",
+            "novembro",
+            "dezembro",
+        ],
+        "nl" => [
+            "januari",
+            "februari",
+            "maart",
+            "april",
+            "mei",
+            "juni",
+            "juli",
+            "augustus",
+            "september",
+            "oktober",
+            "november",
+            "december",
+        ],
+        _ => return None,
+    })
+}
+
+/// Format a Unix timestamp in seconds as a UTC date in a locale
+///
+/// Locales without known conventions get ISO 8601 dates. Returns `None` for
+/// timestamps outside the supported range.
+pub fn format_date(timestamp: i64, locale: &str, style: DateStyle) -> Option<String> {
+    let date = DateTime::from_timestamp(timestamp, 0)?.date_naive();
+    let (year, month, day) = (date.year(), date.month(), date.day());
+    let (language, region) = split_locale(locale);
+    let day_first_english = matches!(region.as_str(), "gb" | "au" | "nz" | "ie" | "in" | "za");
+    let iso = format!("{:04}-{:02}-{:02}", year, month, day);
+
+    if style == DateStyle::Long {
+        if let Some(names) = month_names(&language) {
+            let name = names[month as usize - 1];
+            return Some(match language.as_str() {
+                "en" if day_first_english => format!("{} {} {}", day, name, year),
+                "en" => format!("{} {}, {}", name, day, year),
+                "de" => format!("{}. {} {}", day, name, year),
+                "es" | "pt" => format!("{} de {} de {}", day, name, year),
+                _ => format!("{} {} {}", day, name, year),
+            });
+        }
+    }
+
+    Some(match language.as_str() {
+        "ja" | "zh" if style == DateStyle::Long => format!("{}年{}月{}日", year, month, day),
+        "ko" if style == DateStyle::Long => format!("{}년 {}월 {}일", year, month, day),
+        "en" if day_first_english => format!("{:02}/{:02}/{}", day, month, year),
+        "en" => format!("{}/{}/{}", month, day, year),
+        "fr" | "es" | "it" | "pt" => format!("{:02}/{:02}/{}", day, month, year),
+        "de" | "ru" | "uk" | "pl" | "cs" | "fi" | "nb" | "no" | "da" | "tr" => {
+            format!("{:02}.{:02}.{}", day, month, year)
+        }
+        "nl" => format!("{:02}-{:02}-{}", day, month, year),
+        "ja" | "zh" => format!("{}/{:02}/{:02}", year, month, day),
+        "ko" => format!("{}. {}. {}.", year, month, day),
+        _ => iso,
+    })
+}
+
+/// An expression inside a placeable
+#[derive(Debug, Clone, PartialEq)]
+enum Expression {
+    Variable(String),
+    /// A message or, with a leading `-`, a term
+    Reference(String),
+    Literal(String),
+    Number(f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Element {
+    Text(String),
+    Placeable(Expression),
+    Select {
+        selector: Expression,
+        variants: Vec<(String, Pattern)>,
+        default: usize,
+    },
+}
+
+type Pattern = Vec<Element>;
+
+/// Parser for the value of one message
+struct PatternParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl PatternParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(match self.peek() {
+                Some(found) => format!("Expected '{}', found '{}'", expected, found),
+                None => format!("Expected '{}' before the end of the message", expected),
+            })
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Parse text and placeables, up to the end of the line for a variant
+    fn parse_pattern(&mut self, in_variant: bool) -> Result<Pattern, String> {
+        let mut elements = Vec::new();
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None => break,
+                Some('\n') if in_variant => break,
+                Some('{') => {
+                    self.pos += 1;
+                    if !text.is_empty() {
+                        elements.push(Element::Text(std::mem::take(&mut text)));
+                    }
+                    elements.push(self.parse_placeable()?);
+                }
+                Some('}') => return Err("Unbalanced '}'".to_string()),
+                Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        let text = if in_variant { text.trim_end() } else { &text };
+        if !text.is_empty() {
+            elements.push(Element::Text(text.to_string()));
+        }
+        Ok(elements)
+    }
+
+    fn parse_placeable(&mut self) -> Result<Element, String> {
+        self.skip_whitespace();
+        let expression = self.parse_expression()?;
+        self.skip_whitespace();
+
+        if !(self.eat('-') && self.eat('>')) {
+            self.expect('}')?;
+            return Ok(Element::Placeable(expression));
+        }
+
+        let mut variants = Vec::new();
+        let mut default = None;
+        loop {
+            self.skip_whitespace();
+            if self.eat('}') {
+                break;
+            }
+            if self.eat('*') {
+                if default.is_some() {
+                    return Err("Select has more than one default variant".to_string());
+                }
+                default = Some(variants.len());
+            }
+            self.expect('[')?;
+            let key: String = self.take_while(|c| c != ']' && c != '\n');
+            self.expect(']')?;
+            self.skip_blank();
+            variants.push((key.trim().to_string(), self.parse_pattern(true)?));
+        }
+
+        let default = default.ok_or("Select has no default variant marked with '*'")?;
+        Ok(Element::Select {
+            selector: expression,
+            variants,
+            default,
+        })
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, String> {
+        let identifier = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+        match self.peek() {
+            Some('$') => {
+                self.pos += 1;
+                let name = self.take_while(identifier);
+                if name.is_empty() {
+                    return Err("Expected a variable name after '$'".to_string());
+                }
+                Ok(Expression::Variable(name))
+            }
+            Some('"') => {
+                self.pos += 1;
+                let literal = self.take_while(|c| c != '"' && c != '\n');
+                self.expect('"')?;
+                Ok(Expression::Literal(literal))
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let number = self.take_while(|c| c.is_ascii_digit() || c == '.');
+                number
+                    .parse()
+                    .map(Expression::Number)
+                    .map_err(|_| format!("Invalid number '{}'", number))
+            }
+            Some(c) if identifier(c) => Ok(Expression::Reference(self.take_while(identifier))),
+            Some(c) => Err(format!("Unexpected '{}' in placeable", c)),
+            None => Err("Unclosed placeable".to_string()),
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if predicate(c)) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+}
+
+/// A value a placeable resolved to
+enum Resolved {
+    Number(f64),
+    Text(String),
+}
+
+/// The messages of one locale
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    messages: HashMap<String, Pattern>,
+}
+
+impl Catalog {
+    /// Parse a catalog; errors name the line of the failing message
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut catalog = Catalog::default();
+        let mut current: Option<(usize, String, String)> = None;
+
+        let mut finish = |current: Option<(usize, String, String)>| -> Result<(), String> {
+            if let Some((line, id, value)) = current {
+                let mut parser = PatternParser {
+                    chars: value.trim_end().chars().collect(),
+                    pos: 0,
+                };
+                let pattern = parser
+                    .parse_pattern(false)
+                    .map_err(|e| format!("line {}: {} in message '{}'", line, e, id))?;
+                catalog.messages.insert(id, pattern);
+            }
+            Ok(())
+        };
+
+        for (index, line) in source.lines().enumerate() {
+            let trimmed = line.trim();
+            // A select expression may close at the start of a line
+            let continues = line.starts_with([' ', '\t']) || line.starts_with('}');
+            if continues && !trimmed.is_empty() {
+                match &mut current {
+                    Some((_, _, value)) => {
+                        value.push('\n');
+                        value.push_str(trimmed);
+                    }
+                    None => {
+                        return Err(format!(
+                            "line {}: Indented line outside a message",
+                            index + 1
+                        ))
+                    }
+                }
+                continue;
+            }
+
+            finish(current.take())?;
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let (id, value) = trimmed
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: Expected 'id = message'", index + 1))?;
+            let id = id.trim();
+            let valid = id.trim_start_matches('-').starts_with(char::is_alphabetic)
+                && id
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+            if !valid {
+                return Err(format!("line {}: Invalid message id '{}'", index + 1, id));
+            }
+            current = Some((index + 1, id.to_string(), value.trim_start().to_string()));
+        }
+        finish(current)?;
+
+        Ok(catalog)
+    }
+
+    /// Add the messages of another catalog, replacing ones with the same id
+    pub fn merge(&mut self, other: Catalog) {
+        self.messages.extend(other.messages);
+    }
+
+    /// Number of messages and terms
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Whether the catalog has a message
+    pub fn contains(&self, id: &str) -> bool {
+        self.messages.contains_key(id)
+    }
+
+    /// Format a message with arguments, or `None` if it does not exist
+    ///
+    /// Missing arguments and references show as `{$name}` and `{name}`.
+    pub fn format(
+        &self,
+        id: &str,
+        locale: &str,
+        args: &HashMap<String, ScriptValue>,
+    ) -> Option<String> {
+        self.format_depth(id, locale, args, 0)
+    }
+
+    fn format_depth(
+        &self,
+        id: &str,
+        locale: &str,
+        args: &HashMap<String, ScriptValue>,
+        depth: usize,
+    ) -> Option<String> {
+        let pattern = self.messages.get(id)?;
+        let mut out = String::new();
+        self.format_pattern(pattern, locale, args, depth, &mut out);
+        Some(out)
+    }
+
+    fn format_pattern(
+        &self,
+        pattern: &Pattern,
+        locale: &str,
+        args: &HashMap<String, ScriptValue>,
+        depth: usize,
+        out: &mut String,
+    ) {
+        for element in pattern {
+            match element {
+                Element::Text(text) => out.push_str(text),
+                Element::Placeable(expression) => {
+                    match self.resolve(expression, locale, args, depth) {
+                        Resolved::Number(n) => out.push_str(&format_argument_number(n, locale)),
+                        Resolved::Text(text) => out.push_str(&text),
+                    }
+                }
+                Element::Select {
+                    selector,
+                    variants,
+                    default,
+                } => {
+                    let chosen = match self.resolve(selector, locale, args, depth) {
+                        Resolved::Number(n) => {
+                            let category = plural_category(locale, n).name();
+                            variants
+                                .iter()
+                                .position(|(key, _)| key.parse::<f64>() == Ok(n))
+                                .or_else(|| variants.iter().position(|(key, _)| key == category))
+                        }
+                        Resolved::Text(text) => variants.iter().position(|(key, _)| *key == text),
+                    };
+                    let (_, variant) = &variants[chosen.unwrap_or(*default)];
+                    self.format_pattern(variant, locale, args, depth, out);
+                }
+            }
+        }
+    }
+
+    fn resolve(
+        &self,
+        expression: &Expression,
+        locale: &str,
+        args: &HashMap<String, ScriptValue>,
+        depth: usize,
+    ) -> Resolved {
+        match expression {
+            Expression::Variable(name) => match args.get(name) {
+                Some(value) => match number_value(value) {
+                    Some(n) => Resolved::Number(n),
+                    None => Resolved::Text(format_value(value)),
+                },
+                None => Resolved::Text(format!("{{${}}}", name)),
+            },
+            Expression::Reference(id) => {
+                let formatted = (depth < MAX_REFERENCE_DEPTH)
+                    .then(|| self.format_depth(id, locale, args, depth + 1))
+                    .flatten();
+                Resolved::Text(formatted.unwrap_or_else(|| format!("{{{}}}", id)))
+            }
+            Expression::Literal(text) => Resolved::Text(text.clone()),
+            Expression::Number(n) => Resolved::Number(*n),
+        }
+    }
+}
+
+/// Loaded catalogs by normalized locale tag
+fn catalogs() -> MutexGuard<'static, HashMap<String, Catalog>> {
+    static CATALOGS: OnceLock<Mutex<HashMap<String, Catalog>>> = OnceLock::new();
+    CATALOGS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The locale and the locales it falls back to, most specific first
+fn fallback_chain(locale: &str) -> Vec<String> {
+    let normalized = locale.trim().to_lowercase().replace('_', "-");
+    let mut chain = vec![normalized.clone()];
+    let mut tag = normalized.as_str();
+    while let Some((parent, _)) = tag.rsplit_once('-') {
+        chain.push(parent.to_string());
+        tag = parent;
+    }
+    chain
+}
+
+/// Load a catalog's messages for a locale, returning how many it had
+pub fn i18n_load_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "i18n_load")?;
+    let locale = string_arg(args, 0, "i18n_load")?;
+    let source = string_arg(args, 1, "i18n_load")?;
+
+    let result = match Catalog::parse(source) {
+        Ok(catalog) => {
+            let count = catalog.len();
+            let key = fallback_chain(locale).swap_remove(0);
+            catalogs().entry(key).or_default().merge(catalog);
+            ScriptResult::ok(ScriptValue::I32(count as i32))
+        }
+        Err(message) => {
+            ScriptResult::err(error::error_object(ErrorKind::Parse, &message, None, None))
+        }
+    };
+    Ok(ScriptValue::Result(ScriptRc::new(result)))
+}
+
+/// Format a message for a locale
+///
+/// The arguments are an object of variables. A message missing from the
+/// locale and its fallbacks formats as its id.
+pub fn i18n_message_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 3, "i18n_message")?;
+    let locale = string_arg(args, 0, "i18n_message")?;
+    let id = string_arg(args, 1, "i18n_message")?;
+    let variables = match &args[2] {
+        ScriptValue::Object(fields) => (**fields).clone(),
+        ScriptValue::Unit => HashMap::new(),
+        other => {
+            return Err(RuntimeError::InvalidOperation(format!(
+                "i18n_message expects an object of arguments, got {:?}",
+                other.get_type()
+            )))
+        }
+    };
+
+    let catalogs = catalogs();
+    let formatted = fallback_chain(locale).iter().find_map(|tag| {
+        catalogs
+            .get(tag)
+            .and_then(|catalog| catalog.format(id, locale, &variables))
+    });
+    Ok(string_value(formatted.as_deref().unwrap_or(id)))
+}
+
+/// Whether a locale or one of its fallbacks has a message
+pub fn i18n_has_message_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "i18n_has_message")?;
+    let locale = string_arg(args, 0, "i18n_has_message")?;
+    let id = string_arg(args, 1, "i18n_has_message")?;
+
+    let catalogs = catalogs();
+    let found = fallback_chain(locale).iter().any(|tag| {
+        catalogs
+            .get(tag)
+            .is_some_and(|catalog| catalog.contains(id))
+    });
+    Ok(ScriptValue::Bool(found))
+}
+
+/// The plural category of a number in a locale
+pub fn i18n_plural_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "i18n_plural")?;
+    let locale = string_arg(args, 0, "i18n_plural")?;
+    let n = number_arg(args, 1, "i18n_plural")?;
+
+    Ok(string_value(plural_category(locale, n).name()))
+}
+
+/// Format a number with a fixed number of fraction digits in a locale
+pub fn i18n_format_number_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 3, "i18n_format_number")?;
+    let locale = string_arg(args, 0, "i18n_format_number")?;
+    let value = number_arg(args, 1, "i18n_format_number")?;
+    let decimals = args[2].to_i32()?;
+    if !(0..=20).contains(&decimals) {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "i18n_format_number expects 0 to 20 decimals, got {}",
+            decimals
+        )));
+    }
+
+    Ok(string_value(&format_number(
+        value,
+        decimals as usize,
+        locale,
+    )))
+}
+
+/// Format a Unix timestamp as a date in a locale, in the "short" or
+/// "long" style
+pub fn i18n_format_date_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 3, "i18n_format_date")?;
+    let locale = string_arg(args, 0, "i18n_format_date")?;
+    let timestamp = number_arg(args, 1, "i18n_format_date")?;
+    let style = string_arg(args, 2, "i18n_format_date")?;
+    let style = DateStyle::parse(style).ok_or_else(|| {
+        RuntimeError::InvalidOperation(format!(
+            "Unknown date style '{}' (expected short or long)",
+            style
+        ))
+    })?;
+
+    format_date(timestamp.floor() as i64, locale, style)
+        .map(|date| string_value(&date))
+        .ok_or_else(|| {
+            RuntimeError::InvalidOperation(format!("Timestamp {} is out of range", timestamp))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CATALOG: &str = r#"
+# Shared terms
+-brand = Script
+welcome = Welcome to { -brand }, { $name }!
+emails = { $count ->
+    [0] You have no new email.
+    [one] You have one new email.
+   *[other] You have { $count } new emails.
+}
+braces = Use { "{" } and { "}" }
+"#;
+
+    fn format(catalog: &Catalog, locale: &str, id: &str, args: &[(&str, ScriptValue)]) -> String {
+        let args = args
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        catalog.format(id, locale, &args).unwrap()
+    }
+
+    #[test]
+    fn test_catalog_messages() {
+        let catalog = Catalog::parse(CATALOG).unwrap();
+        assert_eq!(catalog.len(), 4);
+        assert_eq!(
+            format(&catalog, "en", "welcome", &[("name", string_value("Ada"))]),
+            "Welcome to Script, Ada!"
+        );
+        assert_eq!(
+            format(&catalog, "en", "welcome", &[]),
+            "Welcome to Script, {$name}!"
+        );
+
+        let emails = |n: i32| format(&catalog, "en", "emails", &[("count", ScriptValue::I32(n))]);
+        assert_eq!(emails(0), "You have no new email.");
+        assert_eq!(emails(1), "You have one new email.");
+        assert_eq!(emails(1200), "You have 1,200 new emails.");
+        assert_eq!(
+            format(
+                &catalog,
+                "de",
+                "emails",
+                &[("count", ScriptValue::I32(1200))]
+            ),
+            "You have 1.200 new emails."
+        );
+        assert_eq!(format(&catalog, "en", "braces", &[]), "Use { and }");
+        assert!(catalog.format("missing", "en", &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn test_catalog_errors() {
+        assert!(Catalog::parse("no equals sign")
+            .unwrap_err()
+            .starts_with("line 1"));
+        assert!(Catalog::parse("a = ok\nb = { $x")
+            .unwrap_err()
+            .starts_with("line 2"));
+        assert!(Catalog::parse("a = { $n ->\n  [one] x\n}").is_err());
+        assert!(Catalog::parse("a = }").is_err());
+        assert!(Catalog::parse("  indented = x").is_err());
+    }
+
+    #[test]
+    fn test_plural_rules() {
+        use PluralCategory::*;
+
+        assert_eq!(plural_category("en-US", 1.0), One);
+        assert_eq!(plural_category("en", 1.5), Other);
+        assert_eq!(plural_category("fr", 0.0), One);
+        assert_eq!(plural_category("ja", 1.0), Other);
+        let russian: Vec<_> = [1.0, 3.0, 5.0, 11.0, 21.0, 22.0, 112.0]
+            .iter()
+            .map(|n| plural_category("ru", *n))
+            .collect();
+        assert_eq!(russian, vec![One, Few, Many, Many, One, Few, Many]);
+        assert_eq!(plural_category("pl", 22.0), Few);
+        assert_eq!(plural_category("cs", 3.0), Few);
+        assert_eq!(plural_category("ar", 0.0), Zero);
+        assert_eq!(plural_category("ar", 2.0), Two);
+        assert_eq!(plural_category("ar", 105.0), Few);
+    }
+
+    #[test]
+    fn test_number_formatting() {
+        assert_eq!(format_number(1234567.891, 2, "en-US"), "1,234,567.89");
+        assert_eq!(format_number(1234567.891, 2, "de-DE"), "1.234.567,89");
+        assert_eq!(format_number(1234.5, 1, "fr"), "1\u{202f}234,5");
+        assert_eq!(format_number(1234.0, 0, "es"), "1234");
+        assert_eq!(format_number(12345.0, 0, "es"), "12.345");
+        assert_eq!(format_number(1234567.0, 0, "en-IN"), "12,34,567");
+        assert_eq!(format_number(-0.001, 2, "en"), "0.00");
+        assert_eq!(format_number(-42.0, 0, "en"), "-42");
+        assert_eq!(format_argument_number(2.50, "de"), "2,5");
+    }
+
+    #[test]
+    fn test_date_formatting() {
+        // 2024-01-15T12:00:00Z
+        let timestamp = 1_705_320_000;
+        let date = |locale, style| format_date(timestamp, locale, style).unwrap();
+
+        assert_eq!(date("en-US", DateStyle::Short), "1/15/2024");
+        assert_eq!(date("en-GB", DateStyle::Short), "15/01/2024");
+        assert_eq!(date("de", DateStyle::Short), "15.01.2024");
+        assert_eq!(date("ja", DateStyle::Short), "2024/01/15");
+        assert_eq!(date("xx", DateStyle::Short), "2024-01-15");
+        assert_eq!(date("en", DateStyle::Long), "January 15, 2024");
+        assert_eq!(date("de-AT", DateStyle::Long), "15. Januar 2024");
+        assert_eq!(date("es", DateStyle::Long), "15 de enero de 2024");
+        assert_eq!(date("ja", DateStyle::Long), "2024年1月15日");
+    }
+
+    #[test]
+    fn test_script_functions() {
+        let locale = string_value("zz-Test");
+        let loaded =
+            i18n_load_impl(&[string_value("zz"), string_value("hi = Hi { $who }")]).unwrap();
+        assert!(matches!(loaded, ScriptValue::Result(r) if matches!(*r, ScriptResult::Ok(_))));
+
+        let mut fields = HashMap::new();
+        fields.insert("who".to_string(), string_value("there"));
+        let args = ScriptValue::Object(ScriptRc::new(fields));
+        assert_eq!(
+            i18n_message_impl(&[locale.clone(), string_value("hi"), args.clone()]).unwrap(),
+            string_value("Hi there")
+        );
+        assert_eq!(
+            i18n_message_impl(&[locale.clone(), string_value("bye"), args]).unwrap(),
+            string_value("bye")
+        );
+        assert_eq!(
+            i18n_has_message_impl(&[locale, string_value("hi")]).unwrap(),
+            ScriptValue::Bool(true)
+        );
+
+        let failed = i18n_load_impl(&[string_value("zz"), string_value("bad")]).unwrap();
+        assert!(matches!(failed, ScriptValue::Result(r) if matches!(*r, ScriptResult::Err(_))));
+        assert!(i18n_format_date_impl(&[
+            string_value("en"),
+            ScriptValue::I32(0),
+            string_value("medium")
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,459 @@
+//! Pretty-printing of values for debugging
+//!
+//! `inspect` renders a value close to how it would be written in Script
+//! source: strings are quoted, floats keep their decimal point and options
+//! and results show their variant. A collection that fits within the line
+//! width stays on one line; a longer one is broken into one entry per line
+//! and indented. Nesting deeper than the depth limit is elided as `...`, and
+//! a collection that contains itself is shown as `<cycle>`.
+//!
+//! Both stdlib [`ScriptValue`]s and interpreter [`Value`]s can be inspected,
+//! so the REPL and test assertions print values the same way as the
+//! `inspect` function.
+
+use crate::runtime::{RuntimeError, ScriptRc, Value};
+use crate::stdlib::{ScriptOption, ScriptResult, ScriptString, ScriptValue};
+use std::sync::Arc;
+
+/// Options controlling how values are rendered
+#[derive(Debug, Clone)]
+pub struct InspectOptions {
+    /// Collections nested deeper than this are elided
+    pub max_depth: usize,
+    /// Spaces added per nesting level in multi-line output
+    pub indent: usize,
+    /// Collections longer than this are broken across lines
+    pub max_width: usize,
+}
+
+impl Default for InspectOptions {
+    fn default() -> Self {
+        InspectOptions {
+            max_depth: 6,
+            indent: 2,
+            max_width: 80,
+        }
+    }
+}
+
+/// Render a value with the default options
+pub fn inspect(value: &ScriptValue) -> String {
+    inspect_with(value, &InspectOptions::default())
+}
+
+/// Render a value with custom options
+pub fn inspect_with(value: &ScriptValue, options: &InspectOptions) -> String {
+    let node = ScriptValueWalker {
+        options,
+        open: Vec::new(),
+    }
+    .node(value, 0);
+    render(&node, options)
+}
+
+/// Render an interpreter value with the default options
+pub fn inspect_value(value: &Value) -> String {
+    inspect_value_with(value, &InspectOptions::default())
+}
+
+/// Render an interpreter value with custom options
+pub fn inspect_value_with(value: &Value, options: &InspectOptions) -> String {
+    render(&runtime_node(value, 0, options), options)
+}
+
+/// Layout tree built from a value before rendering
+enum Node {
+    /// Text that is never broken
+    Atom(String),
+    /// Delimited list of entries, broken across lines if too long
+    Group {
+        open: String,
+        close: &'static str,
+        entries: Vec<(Option<String>, Node)>,
+    },
+}
+
+impl Node {
+    fn group(open: impl Into<String>, close: &'static str) -> Self {
+        Node::Group {
+            open: open.into(),
+            close,
+            entries: Vec::new(),
+        }
+    }
+
+    fn wrap(open: &str, inner: Node) -> Self {
+        Node::Group {
+            open: format!("{}(", open),
+            close: ")",
+            entries: vec![(None, inner)],
+        }
+    }
+
+    fn push(&mut self, key: Option<String>, node: Node) {
+        if let Node::Group { entries, .. } = self {
+            entries.push((key, node));
+        }
+    }
+}
+
+/// Builds layout trees from stdlib values, tracking the collections being
+/// visited so that cycles through shared storage are detected
+struct ScriptValueWalker<'a> {
+    options: &'a InspectOptions,
+    open: Vec<usize>,
+}
+
+impl ScriptValueWalker<'_> {
+    fn node(&mut self, value: &ScriptValue, depth: usize) -> Node {
+        match value {
+            ScriptValue::I32(val) => Node::Atom(val.to_string()),
+            ScriptValue::I64(val) => Node::Atom(val.to_string()),
+            ScriptValue::U32(val) => Node::Atom(val.to_string()),
+            ScriptValue::U64(val) => Node::Atom(val.to_string()),
+            ScriptValue::F32(val) => Node::Atom(format!("{:?}", val)),
+            ScriptValue::F64(val) => Node::Atom(format!("{:?}", val)),
+            ScriptValue::Bool(val) => Node::Atom(val.to_string()),
+            ScriptValue::String(s) => Node::Atom(format!("{:?}", s.as_str())),
+            ScriptValue::Unit => Node::Atom("()".to_string()),
+            ScriptValue::Array(vec) => {
+                let id = Arc::as_ptr(&vec.data) as usize;
+                self.collection(id, depth, "[", "]", |walker, group| {
+                    for item in vec.to_vec().unwrap_or_default() {
+                        let node = walker.node(&item, depth + 1);
+                        group.push(None, node);
+                    }
+                })
+            }
+            ScriptValue::HashMap(map) => {
+                let id = map.as_raw() as usize;
+                self.collection(id, depth, "{", "}", |walker, group| {
+                    let mut entries = map.iter().unwrap_or_default();
+                    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    for (key, item) in entries {
+                        let node = walker.node(&item, depth + 1);
+                        group.push(Some(format!("{:?}", key)), node);
+                    }
+                })
+            }
+            ScriptValue::HashSet(set) => {
+                let id = set.as_raw() as usize;
+                self.collection(id, depth, "#{", "}", |walker, group| {
+                    let items = set
+                        .values()
+                        .and_then(|values| values.to_vec())
+                        .unwrap_or_default();
+                    // Sets are unordered, so order by rendering for stable output
+                    let mut nodes: Vec<_> = items
+                        .iter()
+                        .map(|item| {
+                            let node = walker.node(item, depth + 1);
+                            (render_flat(&node), node)
+                        })
+                        .collect();
+                    nodes.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    for (_, node) in nodes {
+                        group.push(None, node);
+                    }
+                })
+            }
+            ScriptValue::Object(fields) => {
+                let id = fields.as_raw() as usize;
+                self.collection(id, depth, "{", "}", |walker, group| {
+                    let mut entries: Vec<_> = fields.iter().collect();
+                    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+                    for (key, item) in entries {
+                        let node = walker.node(item, depth + 1);
+                        group.push(Some(key.clone()), node);
+                    }
+                })
+            }
+            ScriptValue::Option(option) => match &**option {
+                ScriptOption::Some(inner) => Node::wrap("Some", self.node(inner, depth)),
+                ScriptOption::None => Node::Atom("None".to_string()),
+            },
+            ScriptValue::Result(result) => match &**result {
+                ScriptResult::Ok(inner) => Node::wrap("Ok", self.node(inner, depth)),
+                ScriptResult::Err(inner) => Node::wrap("Err", self.node(inner, depth)),
+            },
+            ScriptValue::Enum(value) => {
+                let name = format!("{}::{}", value.type_name, value.variant);
+                match &value.data {
+                    Some(inner) => Node::wrap(&name, self.node(inner, depth)),
+                    None => Node::Atom(name),
+                }
+            }
+            ScriptValue::Iterator(_) => Node::Atom("<iterator>".to_string()),
+            ScriptValue::Weak(weak) => match weak.upgrade() {
+                Some(value) => Node::wrap("Weak", self.node(&value, depth)),
+                None => Node::Atom("Weak(<released>)".to_string()),
+            },
+            ScriptValue::Channel(channel) => Node::Atom(format!(
+                "<channel {}/{}{}>",
+                channel.len(),
+                channel.capacity(),
+                if channel.is_closed() { ", closed" } else { "" }
+            )),
+            ScriptValue::Mutex(mutex) => Node::Atom(format!("<mutex #{}>", mutex.id())),
+            ScriptValue::RwLock(lock) => Node::Atom(format!("<rwlock #{}>", lock.id())),
+            ScriptValue::LockGuard(guard) => Node::Atom(format!(
+                "<{} guard{}>",
+                if guard.is_read_only() {
+                    "read"
+                } else {
+                    "write"
+                },
+                if guard.is_held() { "" } else { ", unlocked" }
+            )),
+            ScriptValue::AtomicI32(atomic) => Node::Atom(format!(
+                "AtomicI32({})",
+                atomic.load(std::sync::atomic::Ordering::SeqCst)
+            )),
+            ScriptValue::Closure(closure) => {
+                Node::Atom(format!("<closure {}>", closure.function_id))
+            }
+        }
+    }
+
+    /// Build a collection node unless it is too deep or already being visited
+    fn collection(
+        &mut self,
+        id: usize,
+        depth: usize,
+        open: &str,
+        close: &'static str,
+        fill: impl FnOnce(&mut Self, &mut Node),
+    ) -> Node {
+        if self.open.contains(&id) {
+            return Node::Atom("<cycle>".to_string());
+        }
+        if depth >= self.options.max_depth {
+            return Node::Atom(format!("{}...{}", open, close));
+        }
+
+        self.open.push(id);
+        let mut group = Node::group(open, close);
+        fill(self, &mut group);
+        self.open.pop();
+        group
+    }
+}
+
+/// Build a layout tree from an interpreter value
+///
+/// Interpreter collections are immutable once built, so they cannot contain
+/// themselves and only the depth limit applies.
+fn runtime_node(value: &Value, depth: usize, options: &InspectOptions) -> Node {
+    let too_deep = depth >= options.max_depth;
+    match value {
+        Value::String(s) => Node::Atom(format!("{:?}", s)),
+        Value::F32(val) => Node::Atom(format!("{:?}", val)),
+        Value::F64(val) | Value::Number(val) => Node::Atom(format!("{:?}", val)),
+        Value::Array(_) | Value::Object(_) if too_deep => Node::Atom(match value {
+            Value::Array(_) => "[...]".to_string(),
+            _ => "{...}".to_string(),
+        }),
+        Value::Array(items) => {
+            let mut group = Node::group("[", "]");
+            for item in items {
+                group.push(None, runtime_node(item, depth + 1, options));
+            }
+            group
+        }
+        Value::Object(fields) => {
+            let mut entries: Vec<_> = fields.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let mut group = Node::group("{", "}");
+            for (key, item) in entries {
+                group.push(Some(key.clone()), runtime_node(item, depth + 1, options));
+            }
+            group
+        }
+        Value::Enum {
+            type_name,
+            variant,
+            data,
+        } => {
+            let name = match type_name.as_str() {
+                "Option" | "Result" => variant.clone(),
+                _ => format!("{}::{}", type_name, variant),
+            };
+            match data {
+                Some(inner) => Node::wrap(&name, runtime_node(inner, depth, options)),
+                None => Node::Atom(name),
+            }
+        }
+        other => Node::Atom(other.to_string()),
+    }
+}
+
+/// Render a node on a single line
+fn render_flat(node: &Node) -> String {
+    match node {
+        Node::Atom(text) => text.clone(),
+        Node::Group {
+            open,
+            close,
+            entries,
+        } => {
+            let entries: Vec<String> = entries
+                .iter()
+                .map(|(key, node)| match key {
+                    Some(key) => format!("{}: {}", key, render_flat(node)),
+                    None => render_flat(node),
+                })
+                .collect();
+            format!("{}{}{}", open, entries.join(", "), close)
+        }
+    }
+}
+
+fn render(node: &Node, options: &InspectOptions) -> String {
+    let mut out = String::new();
+    render_into(node, 0, 0, options, &mut out);
+    out
+}
+
+/// Render a node starting at `column`, breaking groups that do not fit
+fn render_into(
+    node: &Node,
+    level: usize,
+    column: usize,
+    options: &InspectOptions,
+    out: &mut String,
+) {
+    let flat = render_flat(node);
+    let (open, close, entries) = match node {
+        Node::Group {
+            open,
+            close,
+            entries,
+        } if !entries.is_empty() && column + flat.len() > options.max_width => {
+            (open, close, entries)
+        }
+        _ => {
+            out.push_str(&flat);
+            return;
+        }
+    };
+
+    let inner = " ".repeat((level + 1) * options.indent);
+    out.push_str(open);
+    out.push('\n');
+    for (key, entry) in entries {
+        out.push_str(&inner);
+        let mut entry_column = inner.len();
+        if let Some(key) = key {
+            out.push_str(key);
+            out.push_str(": ");
+            entry_column += key.len() + 2;
+        }
+        render_into(entry, level + 1, entry_column, options, out);
+        out.push_str(",\n");
+    }
+    out.push_str(&" ".repeat(level * options.indent));
+    out.push_str(close);
+}
+
+/// Render any value as a multi-line debugging string
+pub(crate) fn inspect_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "inspect expects 1 argument, got {}",
+            args.len()
+        )));
+    }
+
+    Ok(ScriptValue::String(ScriptRc::new(ScriptString::from_str(
+        &inspect(&args[0]),
+    ))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::{ScriptHashMap, ScriptVec};
+
+    fn string(text: &str) -> ScriptValue {
+        ScriptValue::String(ScriptRc::new(ScriptString::from_str(text)))
+    }
+
+    fn array(items: Vec<ScriptValue>) -> ScriptValue {
+        ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(items)))
+    }
+
+    #[test]
+    fn test_inspect_inline() {
+        let map = ScriptHashMap::new();
+        map.insert("b".to_string(), ScriptValue::F32(1.0)).unwrap();
+        map.insert("a".to_string(), string("x")).unwrap();
+        let value = array(vec![
+            ScriptValue::I32(1),
+            ScriptValue::HashMap(ScriptRc::new(map)),
+            ScriptValue::Option(ScriptRc::new(ScriptOption::None)),
+            ScriptValue::Result(ScriptRc::new(ScriptResult::Ok(ScriptValue::Unit))),
+        ]);
+
+        assert_eq!(
+            inspect(&value),
+            "[1, {\"a\": \"x\", \"b\": 1.0}, None, Ok(())]"
+        );
+    }
+
+    #[test]
+    fn test_inspect_breaks_long_values() {
+        let row = |n: i32| array((0..n).map(ScriptValue::I32).collect());
+        let value = array(vec![row(3), row(30)]);
+        let options = InspectOptions {
+            max_width: 40,
+            ..InspectOptions::default()
+        };
+
+        let expected = format!(
+            "[\n  [0, 1, 2],\n  [\n{}  ],\n]",
+            (0..30).map(|n| format!("    {},\n", n)).collect::<String>()
+        );
+        assert_eq!(inspect_with(&value, &options), expected);
+    }
+
+    #[test]
+    fn test_inspect_depth_limit() {
+        let mut value = ScriptValue::I32(0);
+        for _ in 0..4 {
+            value = array(vec![value]);
+        }
+        let options = InspectOptions {
+            max_depth: 2,
+            ..InspectOptions::default()
+        };
+
+        assert_eq!(inspect_with(&value, &options), "[[[...]]]");
+    }
+
+    #[test]
+    fn test_inspect_cycle() {
+        let vec = ScriptVec::new();
+        vec.push(ScriptValue::I32(1)).unwrap();
+        let value = ScriptValue::Array(ScriptRc::new(vec.clone()));
+        vec.push(value.clone()).unwrap();
+
+        assert_eq!(inspect(&value), "[1, <cycle>]");
+    }
+
+    #[test]
+    fn test_inspect_runtime_value() {
+        let fields = [
+            (
+                "name".to_string(),
+                ScriptRc::new(Value::String("Ada".to_string())),
+            ),
+            (
+                "score".to_string(),
+                ScriptRc::new(Value::some(Value::F64(2.0))),
+            ),
+        ];
+        let value = Value::Object(fields.into_iter().collect());
+
+        assert_eq!(inspect_value(&value), "{name: \"Ada\", score: Some(2.0)}");
+    }
+}
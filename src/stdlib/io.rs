@@ -59,8 +59,7 @@ pub fn read_file(path: &str) -> Result<String, IoError> {
     match fs::read_to_string(path) {
         Ok(contents) => Ok(contents),
         Err(e) => {
-            let mut io_err = io_error_from_std(e);
-            io_err.message = format!("Failed to read file '{}': {}", path, io_err.message);
+            let io_err = io_error_from_std(e).context(format!("Failed to read file '{}'", path));
             Err(io_err)
         }
     }
@@ -73,12 +72,8 @@ pub fn write_file(path: &str, contents: &str) -> Result<(), IoError> {
     if let Some(parent) = Path::new(path).parent() {
         if !parent.exists() {
             if let Err(e) = fs::create_dir_all(parent) {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!(
-                    "Failed to create directory '{}': {}",
-                    parent.display(),
-                    io_err.message
-                );
+                let io_err = io_error_from_std(e)
+                    .context(format!("Failed to create directory '{}'", parent.display()));
                 return Err(io_err);
             }
         }
@@ -87,8 +82,7 @@ pub fn write_file(path: &str, contents: &str) -> Result<(), IoError> {
     match fs::write(path, contents) {
         Ok(()) => Ok(()),
         Err(e) => {
-            let mut io_err = io_error_from_std(e);
-            io_err.message = format!("Failed to write file '{}': {}", path, io_err.message);
+            let io_err = io_error_from_std(e).context(format!("Failed to write file '{}'", path));
             Err(io_err)
         }
     }
@@ -265,8 +259,8 @@ pub fn create_dir(path: &str) -> Result<(), IoError> {
     match fs::create_dir_all(path) {
         Ok(()) => Ok(()),
         Err(e) => {
-            let mut io_err = io_error_from_std(e);
-            io_err.message = format!("Failed to create directory '{}': {}", path, io_err.message);
+            let io_err =
+                io_error_from_std(e).context(format!("Failed to create directory '{}'", path));
             Err(io_err)
         }
     }
@@ -278,8 +272,7 @@ pub fn delete_file(path: &str) -> Result<(), IoError> {
     match fs::remove_file(path) {
         Ok(()) => Ok(()),
         Err(e) => {
-            let mut io_err = io_error_from_std(e);
-            io_err.message = format!("Failed to delete file '{}': {}", path, io_err.message);
+            let io_err = io_error_from_std(e).context(format!("Failed to delete file '{}'", path));
             Err(io_err)
         }
     }
@@ -291,8 +284,8 @@ pub fn copy_file(from: &str, to: &str) -> Result<(), IoError> {
     match fs::copy(from, to) {
         Ok(_) => Ok(()),
         Err(e) => {
-            let mut io_err = io_error_from_std(e);
-            io_err.message = format!("Failed to copy '{}' to '{}': {}", from, to, io_err.message);
+            let io_err =
+                io_error_from_std(e).context(format!("Failed to copy '{}' to '{}'", from, to));
             Err(io_err)
         }
     }
@@ -308,12 +301,8 @@ pub fn append_file(path: &str, contents: &str) -> Result<(), IoError> {
     if let Some(parent) = Path::new(path).parent() {
         if !parent.exists() {
             if let Err(e) = fs::create_dir_all(parent) {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!(
-                    "Failed to create directory '{}': {}",
-                    parent.display(),
-                    io_err.message
-                );
+                let io_err = io_error_from_std(e)
+                    .context(format!("Failed to create directory '{}'", parent.display()));
                 return Err(io_err);
             }
         }
@@ -323,17 +312,14 @@ pub fn append_file(path: &str, contents: &str) -> Result<(), IoError> {
         Ok(mut file) => match file.write_all(contents.as_bytes()) {
             Ok(()) => Ok(()),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to append to file '{}': {}", path, io_err.message);
+                let io_err =
+                    io_error_from_std(e).context(format!("Failed to append to file '{}'", path));
                 Err(io_err)
             }
         },
         Err(e) => {
-            let mut io_err = io_error_from_std(e);
-            io_err.message = format!(
-                "Failed to open file '{}' for appending: {}",
-                path, io_err.message
-            );
+            let io_err = io_error_from_std(e)
+                .context(format!("Failed to open file '{}' for appending", path));
             Err(io_err)
         }
     }
@@ -345,8 +331,8 @@ pub fn delete_dir(path: &str) -> Result<(), IoError> {
     match fs::remove_dir_all(path) {
         Ok(()) => Ok(()),
         Err(e) => {
-            let mut io_err = io_error_from_std(e);
-            io_err.message = format!("Failed to delete directory '{}': {}", path, io_err.message);
+            let io_err =
+                io_error_from_std(e).context(format!("Failed to delete directory '{}'", path));
             Err(io_err)
         }
     }
@@ -366,11 +352,8 @@ pub fn list_dir(path: &str) -> Result<Vec<String>, IoError> {
                         }
                     }
                     Err(e) => {
-                        let mut io_err = io_error_from_std(e);
-                        io_err.message = format!(
-                            "Failed to read directory entry in '{}': {}",
-                            path, io_err.message
-                        );
+                        let io_err = io_error_from_std(e)
+                            .context(format!("Failed to read directory entry in '{}'", path));
                         return Err(io_err);
                     }
                 }
@@ -378,8 +361,8 @@ pub fn list_dir(path: &str) -> Result<Vec<String>, IoError> {
             Ok(files)
         }
         Err(e) => {
-            let mut io_err = io_error_from_std(e);
-            io_err.message = format!("Failed to read directory '{}': {}", path, io_err.message);
+            let io_err =
+                io_error_from_std(e).context(format!("Failed to read directory '{}'", path));
             Err(io_err)
         }
     }
@@ -414,8 +397,8 @@ pub fn file_metadata(
             Ok(info)
         }
         Err(e) => {
-            let mut io_err = io_error_from_std(e);
-            io_err.message = format!("Failed to get metadata for '{}': {}", path, io_err.message);
+            let io_err =
+                io_error_from_std(e).context(format!("Failed to get metadata for '{}'", path));
             Err(io_err)
         }
     }
@@ -5,9 +5,10 @@
 //! - Trigonometry (sin, cos, tan, etc.)
 //! - Exponential and logarithmic functions
 //! - Rounding functions (floor, ceil, round)
+//! - Integer arithmetic with explicit overflow behavior
 
-use crate::runtime::RuntimeError;
-use crate::stdlib::ScriptValue;
+use crate::runtime::{RuntimeError, ScriptRc};
+use crate::stdlib::{ScriptOption, ScriptValue};
 
 // Implementation functions for the stdlib registry
 
@@ -22,7 +23,11 @@ pub(crate) fn abs_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError
 
     match &args[0] {
         ScriptValue::I32(val) => Ok(ScriptValue::I32(val.abs())),
+        ScriptValue::I64(val) => Ok(ScriptValue::I64(val.abs())),
+        ScriptValue::U32(val) => Ok(ScriptValue::U32(*val)),
+        ScriptValue::U64(val) => Ok(ScriptValue::U64(*val)),
         ScriptValue::F32(val) => Ok(ScriptValue::F32(val.abs())),
+        ScriptValue::F64(val) => Ok(ScriptValue::F64(val.abs())),
         _ => Err(RuntimeError::InvalidOperation(
             "abs expects a number argument".to_string(),
         )),
@@ -38,9 +43,17 @@ pub(crate) fn min_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError
         )));
     }
 
-    let a = args[0].to_f32()?;
-    let b = args[1].to_f32()?;
-    Ok(ScriptValue::F32(a.min(b)))
+    match (&args[0], &args[1]) {
+        (ScriptValue::I64(a), ScriptValue::I64(b)) => Ok(ScriptValue::I64(*a.min(b))),
+        (ScriptValue::U32(a), ScriptValue::U32(b)) => Ok(ScriptValue::U32(*a.min(b))),
+        (ScriptValue::U64(a), ScriptValue::U64(b)) => Ok(ScriptValue::U64(*a.min(b))),
+        (ScriptValue::F64(a), ScriptValue::F64(b)) => Ok(ScriptValue::F64(a.min(*b))),
+        _ => {
+            let a = args[0].to_f32()?;
+            let b = args[1].to_f32()?;
+            Ok(ScriptValue::F32(a.min(b)))
+        }
+    }
 }
 
 /// Maximum of two values
@@ -52,9 +65,17 @@ pub(crate) fn max_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError
         )));
     }
 
-    let a = args[0].to_f32()?;
-    let b = args[1].to_f32()?;
-    Ok(ScriptValue::F32(a.max(b)))
+    match (&args[0], &args[1]) {
+        (ScriptValue::I64(a), ScriptValue::I64(b)) => Ok(ScriptValue::I64(*a.max(b))),
+        (ScriptValue::U32(a), ScriptValue::U32(b)) => Ok(ScriptValue::U32(*a.max(b))),
+        (ScriptValue::U64(a), ScriptValue::U64(b)) => Ok(ScriptValue::U64(*a.max(b))),
+        (ScriptValue::F64(a), ScriptValue::F64(b)) => Ok(ScriptValue::F64(a.max(*b))),
+        _ => {
+            let a = args[0].to_f32()?;
+            let b = args[1].to_f32()?;
+            Ok(ScriptValue::F32(a.max(b)))
+        }
+    }
 }
 
 /// Sign of a number (-1, 0, or 1)
@@ -351,3 +372,126 @@ pub(crate) fn trunc_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeErr
     let val = args[0].to_f32()?;
     Ok(ScriptValue::F32(val.trunc()))
 }
+
+/// Integer operation performed by an overflow intrinsic
+#[derive(Debug, Clone, Copy)]
+enum IntegerOp {
+    Add,
+    Sub,
+    Mul,
+}
+
+/// What an overflow intrinsic does when the result does not fit
+#[derive(Debug, Clone, Copy)]
+enum Overflow {
+    /// Return `None` instead of a result
+    Checked,
+    /// Wrap around in two's complement
+    Wrapping,
+    /// Clamp to the bounds of the type
+    Saturating,
+}
+
+/// Apply `op` to two integers of the same type with the given overflow behavior
+fn integer_intrinsic(
+    name: &str,
+    op: IntegerOp,
+    overflow: Overflow,
+    args: &[ScriptValue],
+) -> Result<ScriptValue, RuntimeError> {
+    if args.len() != 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "{} expects 2 arguments, got {}",
+            name,
+            args.len()
+        )));
+    }
+
+    macro_rules! apply {
+        ($a:expr, $b:expr, $variant:path) => {{
+            let (a, b) = ($a, $b);
+            match overflow {
+                Overflow::Checked => {
+                    let result = match op {
+                        IntegerOp::Add => a.checked_add(b),
+                        IntegerOp::Sub => a.checked_sub(b),
+                        IntegerOp::Mul => a.checked_mul(b),
+                    };
+                    let option = match result {
+                        Some(value) => ScriptOption::some($variant(value)),
+                        None => ScriptOption::none(),
+                    };
+                    ScriptValue::Option(ScriptRc::new(option))
+                }
+                Overflow::Wrapping => $variant(match op {
+                    IntegerOp::Add => a.wrapping_add(b),
+                    IntegerOp::Sub => a.wrapping_sub(b),
+                    IntegerOp::Mul => a.wrapping_mul(b),
+                }),
+                Overflow::Saturating => $variant(match op {
+                    IntegerOp::Add => a.saturating_add(b),
+                    IntegerOp::Sub => a.saturating_sub(b),
+                    IntegerOp::Mul => a.saturating_mul(b),
+                }),
+            }
+        }};
+    }
+
+    match (&args[0], &args[1]) {
+        (ScriptValue::I32(a), ScriptValue::I32(b)) => Ok(apply!(*a, *b, ScriptValue::I32)),
+        (ScriptValue::I64(a), ScriptValue::I64(b)) => Ok(apply!(*a, *b, ScriptValue::I64)),
+        (ScriptValue::U32(a), ScriptValue::U32(b)) => Ok(apply!(*a, *b, ScriptValue::U32)),
+        (ScriptValue::U64(a), ScriptValue::U64(b)) => Ok(apply!(*a, *b, ScriptValue::U64)),
+        (a, b) => Err(RuntimeError::InvalidOperation(format!(
+            "{} expects two integers of the same type, got {} and {}",
+            name,
+            a.get_type(),
+            b.get_type()
+        ))),
+    }
+}
+
+/// Addition returning `None` on overflow
+pub(crate) fn checked_add_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    integer_intrinsic("checked_add", IntegerOp::Add, Overflow::Checked, args)
+}
+
+/// Subtraction returning `None` on overflow
+pub(crate) fn checked_sub_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    integer_intrinsic("checked_sub", IntegerOp::Sub, Overflow::Checked, args)
+}
+
+/// Multiplication returning `None` on overflow
+pub(crate) fn checked_mul_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    integer_intrinsic("checked_mul", IntegerOp::Mul, Overflow::Checked, args)
+}
+
+/// Addition wrapping around on overflow
+pub(crate) fn wrapping_add_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    integer_intrinsic("wrapping_add", IntegerOp::Add, Overflow::Wrapping, args)
+}
+
+/// Subtraction wrapping around on overflow
+pub(crate) fn wrapping_sub_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    integer_intrinsic("wrapping_sub", IntegerOp::Sub, Overflow::Wrapping, args)
+}
+
+/// Multiplication wrapping around on overflow
+pub(crate) fn wrapping_mul_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    integer_intrinsic("wrapping_mul", IntegerOp::Mul, Overflow::Wrapping, args)
+}
+
+/// Addition clamped to the bounds of the type
+pub(crate) fn saturating_add_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    integer_intrinsic("saturating_add", IntegerOp::Add, Overflow::Saturating, args)
+}
+
+/// Subtraction clamped to the bounds of the type
+pub(crate) fn saturating_sub_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    integer_intrinsic("saturating_sub", IntegerOp::Sub, Overflow::Saturating, args)
+}
+
+/// Multiplication clamped to the bounds of the type
+pub(crate) fn saturating_mul_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
+    integer_intrinsic("saturating_mul", IntegerOp::Mul, Overflow::Saturating, args)
+}
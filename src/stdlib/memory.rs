@@ -0,0 +1,151 @@
+//! Heap statistics and collector control for Script
+//!
+//! Scripts can inspect the runtime's memory with `heap_stats`, which returns
+//! an object with:
+//! - `gc_mode`: `"rc"`, `"cycle"` or `"generational"`
+//! - `tracked_objects`, `young_roots`, `old_roots` and `remembered`, the
+//!   objects the cycle collector currently tracks
+//! - `collections`, `minor_collections`, `major_collections`,
+//!   `objects_collected` and `objects_promoted`, totals since startup
+//! - `bytes_in_use` and `peak_bytes`, reported when profiling is enabled
+//!
+//! `gc_collect` forces a full collection and returns how many objects it
+//! reclaimed.
+//!
+//! `memory_report` lists the allocation sites counted while the runtime
+//! tracks allocations (`--heap-profile` or
+//! `RuntimeConfig::track_allocations`), most bytes first.
+
+use crate::runtime::{gc, heap_profiler, profiler, Result as RuntimeResult, ScriptRc};
+use crate::stdlib::{expect_args, string_value, ScriptValue, ScriptVec};
+use std::collections::HashMap;
+
+/// Get the runtime's heap statistics as an object
+pub fn heap_stats_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 0, "heap_stats")?;
+
+    let heap = gc::heap_stats();
+    let mut fields = HashMap::new();
+    fields.insert("gc_mode".to_string(), string_value(heap.mode.name()));
+    for (name, count) in [
+        ("tracked_objects", heap.tracked_objects),
+        ("young_roots", heap.young_roots),
+        ("old_roots", heap.old_roots),
+        ("remembered", heap.remembered),
+        ("collections", heap.collections.collections),
+        ("minor_collections", heap.collections.minor_collections),
+        ("major_collections", heap.collections.major_collections),
+        ("objects_collected", heap.collections.objects_collected),
+        ("objects_promoted", heap.collections.objects_promoted),
+    ] {
+        fields.insert(name.to_string(), ScriptValue::I64(count as i64));
+    }
+
+    let (bytes_in_use, peak_bytes) = profiler::get_stats()
+        .map(|stats| {
+            (
+                stats.allocations.current_memory,
+                stats.allocations.peak_memory,
+            )
+        })
+        .unwrap_or((0, 0));
+    fields.insert(
+        "bytes_in_use".to_string(),
+        ScriptValue::I64(bytes_in_use as i64),
+    );
+    fields.insert(
+        "peak_bytes".to_string(),
+        ScriptValue::I64(peak_bytes as i64),
+    );
+
+    Ok(ScriptValue::Object(ScriptRc::new(fields)))
+}
+
+/// Get the allocation sites counted so far as an object
+pub fn memory_report_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 0, "memory_report")?;
+
+    let tracking = heap_profiler::is_enabled();
+    let report = heap_profiler::report();
+
+    let sites = report
+        .sites
+        .iter()
+        .map(|site| {
+            let mut fields = HashMap::new();
+            fields.insert("site".to_string(), string_value(&site.location));
+            fields.insert("type".to_string(), string_value(&site.type_name));
+            fields.insert("count".to_string(), ScriptValue::I64(site.count as i64));
+            fields.insert("bytes".to_string(), ScriptValue::I64(site.bytes as i64));
+            ScriptValue::Object(ScriptRc::new(fields))
+        })
+        .collect();
+
+    let mut fields = HashMap::new();
+    fields.insert("tracking".to_string(), ScriptValue::Bool(tracking));
+    fields.insert(
+        "allocations".to_string(),
+        ScriptValue::I64(report.total_count as i64),
+    );
+    fields.insert(
+        "bytes".to_string(),
+        ScriptValue::I64(report.total_bytes as i64),
+    );
+    fields.insert(
+        "sites".to_string(),
+        ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(sites))),
+    );
+
+    Ok(ScriptValue::Object(ScriptRc::new(fields)))
+}
+
+/// Run a full cycle collection, returning the number of objects reclaimed
+pub fn gc_collect_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 0, "gc_collect")?;
+
+    let collected = |stats: Option<gc::CollectionStats>| {
+        stats.map(|stats| stats.objects_collected).unwrap_or(0)
+    };
+    let before = collected(gc::get_stats());
+    gc::collect_cycles_full();
+    let after = collected(gc::get_stats());
+
+    Ok(ScriptValue::I64(after.saturating_sub(before) as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heap_stats_object() {
+        match heap_stats_impl(&[]).unwrap() {
+            ScriptValue::Object(stats) => {
+                assert!(matches!(stats.get("gc_mode"), Some(ScriptValue::String(_))));
+                assert!(matches!(
+                    stats.get("major_collections"),
+                    Some(ScriptValue::I64(_))
+                ));
+                assert!(stats.contains_key("bytes_in_use"));
+            }
+            _ => panic!("expected stats object"),
+        }
+
+        assert!(heap_stats_impl(&[ScriptValue::I32(1)]).is_err());
+        assert!(matches!(gc_collect_impl(&[]), Ok(ScriptValue::I64(_))));
+    }
+
+    #[test]
+    fn test_memory_report_object() {
+        match memory_report_impl(&[]).unwrap() {
+            ScriptValue::Object(report) => {
+                assert!(matches!(report.get("tracking"), Some(ScriptValue::Bool(_))));
+                assert!(matches!(report.get("bytes"), Some(ScriptValue::I64(_))));
+                assert!(matches!(report.get("sites"), Some(ScriptValue::Array(_))));
+            }
+            _ => panic!("expected report object"),
+        }
+
+        assert!(memory_report_impl(&[ScriptValue::I32(1)]).is_err());
+    }
+}
@@ -8,38 +8,66 @@
 
 pub mod async_functional;
 pub mod async_std;
+pub mod cache;
+pub mod channel;
 pub mod closure_helpers;
 pub mod collections;
+pub mod config;
+pub mod constants;
+pub mod conversion;
 pub mod core_types;
+pub mod diff;
 pub mod error;
 pub mod functional;
 pub mod functional_advanced;
 pub mod game;
+pub mod graph;
+pub mod i18n;
+pub mod inspect;
 pub mod io;
 pub mod iterators;
 pub mod math;
+pub mod memory;
+pub mod ndarray;
 pub mod network;
 pub mod parallel;
+pub mod process;
+pub mod prompt;
 pub mod random;
+pub mod rate_limit;
+pub mod resilience;
 pub mod string;
+pub mod sync;
+pub mod template;
+pub mod thread;
 pub mod time;
+pub mod version;
+pub mod walk;
+pub mod weak;
 
 // Re-export commonly used items
 pub use async_functional::{AsyncFunctionalConfig, AsyncFunctionalOps, FutureCombinators};
 pub use async_std::{interval, join_all, race, sleep, timeout, yield_now};
+pub use cache::{CacheStats, DiskCache, LruCache};
 pub use collections::{ScriptHashMap, ScriptHashSet, ScriptVec};
-pub use core_types::{ScriptOption, ScriptResult};
+pub use core_types::{ScriptEnum, ScriptOption, ScriptResult};
 pub use functional::{FunctionComposition, FunctionalExecutor, FunctionalOps};
+pub use graph::Graph;
+pub use inspect::{inspect, InspectOptions};
 pub use io::{
     append_file, copy_file, create_dir, delete_dir, delete_file, dir_exists, eprintln, file_exists,
     file_metadata, list_dir, print, println, read_file, read_line, write_file,
 };
 pub use iterators::{Generators, RangeIterator, ScriptIterator, VecIterator};
+pub use ndarray::NdArray;
 pub use network::{ScriptTcpListener, ScriptTcpStream, ScriptUdpSocket};
 pub use parallel::{ParallelConfig, ParallelExecutor};
+pub use rate_limit::{RateLimiter, RateLimiterMetrics, TokenBucket};
+pub use resilience::{Backoff, CircuitBreaker, CircuitState, RetryFuture};
 pub use string::{ScriptString, StringOps};
+pub use weak::WeakValue;
 
-use crate::runtime::{RuntimeError, ScriptRc};
+use crate::runtime::{profile, RuntimeError, RuntimeProfile, ScriptRc};
 use crate::types::Type;
 use std::collections::HashMap;
 
@@ -64,8 +92,16 @@ pub struct StdLibFunction {
 pub enum ScriptValue {
     /// 32-bit integer
     I32(i32),
+    /// 64-bit integer
+    I64(i64),
+    /// 32-bit unsigned integer
+    U32(u32),
+    /// 64-bit unsigned integer
+    U64(u64),
     /// 32-bit float
     F32(f32),
+    /// 64-bit float
+    F64(f64),
     /// Boolean
     Bool(bool),
     /// String
@@ -80,6 +116,8 @@ pub enum ScriptValue {
     Option(ScriptRc<ScriptOption>),
     /// Result type
     Result(ScriptRc<ScriptResult>),
+    /// Other built-in enums, such as `ErrorKind`
+    Enum(ScriptRc<ScriptEnum>),
     /// Unit/void type
     Unit,
     /// Object type (for vectors, matrices, etc.)
@@ -88,6 +126,18 @@ pub enum ScriptValue {
     Iterator(ScriptRc<Box<dyn iterators::ScriptIterator>>),
     /// Closure type for functional programming
     Closure(ScriptRc<crate::runtime::closure::Closure>),
+    /// Weak reference to a heap value
+    Weak(weak::WeakValue),
+    /// Channel for passing values between tasks and threads
+    Channel(crate::runtime::Channel<ScriptValue>),
+    /// Mutex shared between threads
+    Mutex(crate::runtime::sync::ScriptMutex<ScriptValue>),
+    /// Reader-writer lock shared between threads
+    RwLock(crate::runtime::sync::ScriptRwLock<ScriptValue>),
+    /// Hold on a mutex or reader-writer lock
+    LockGuard(crate::runtime::sync::LockGuard<ScriptValue>),
+    /// Integer updated atomically across threads
+    AtomicI32(std::sync::Arc<std::sync::atomic::AtomicI32>),
 }
 
 impl ScriptValue {
@@ -95,7 +145,11 @@ impl ScriptValue {
     pub fn get_type(&self) -> Type {
         match self {
             ScriptValue::I32(_) => Type::I32,
+            ScriptValue::I64(_) => Type::I64,
+            ScriptValue::U32(_) => Type::U32,
+            ScriptValue::U64(_) => Type::U64,
             ScriptValue::F32(_) => Type::F32,
+            ScriptValue::F64(_) => Type::F64,
             ScriptValue::Bool(_) => Type::Bool,
             ScriptValue::String(_) => Type::String,
             ScriptValue::Array(_) => Type::Named("Array".to_string()),
@@ -103,9 +157,31 @@ impl ScriptValue {
             ScriptValue::HashSet(_) => Type::Named("HashSet".to_string()),
             ScriptValue::Option(_) => Type::Named("Option".to_string()),
             ScriptValue::Result(_) => Type::Named("Result".to_string()),
+            ScriptValue::Enum(value) => Type::Named(value.type_name.clone()),
             ScriptValue::Unit => Type::Named("unit".to_string()),
             ScriptValue::Object(_) => Type::Named("Object".to_string()),
             ScriptValue::Iterator(_) => Type::Named("Iterator".to_string()),
+            ScriptValue::Weak(_) => Type::Generic {
+                name: "Weak".to_string(),
+                args: vec![Type::Unknown],
+            },
+            ScriptValue::Channel(_) => Type::Generic {
+                name: "Channel".to_string(),
+                args: vec![Type::Unknown],
+            },
+            ScriptValue::Mutex(_) => Type::Generic {
+                name: "Mutex".to_string(),
+                args: vec![Type::Unknown],
+            },
+            ScriptValue::RwLock(_) => Type::Generic {
+                name: "RwLock".to_string(),
+                args: vec![Type::Unknown],
+            },
+            ScriptValue::LockGuard(_) => Type::Generic {
+                name: "Guard".to_string(),
+                args: vec![Type::Unknown],
+            },
+            ScriptValue::AtomicI32(_) => Type::Named("AtomicI32".to_string()),
             ScriptValue::Closure(_) => Type::Function {
                 params: vec![Type::Unknown],  // TODO: Extract actual parameter types
                 ret: Box::new(Type::Unknown), // TODO: Extract actual return type
@@ -121,6 +197,14 @@ impl ScriptValue {
         }
     }
 
+    /// Convert to i64 if possible
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            ScriptValue::I64(val) => Some(*val),
+            _ => None,
+        }
+    }
+
     /// Convert to f32 if possible
     pub fn as_f32(&self) -> Option<f32> {
         match self {
@@ -129,6 +213,14 @@ impl ScriptValue {
         }
     }
 
+    /// Convert to f64 if possible
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            ScriptValue::F64(val) => Some(*val),
+            _ => None,
+        }
+    }
+
     /// Convert to bool if possible
     pub fn as_bool(&self) -> Option<bool> {
         match self {
@@ -179,6 +271,10 @@ impl ScriptValue {
         match self {
             ScriptValue::F32(val) => Ok(*val),
             ScriptValue::I32(val) => Ok(*val as f32),
+            ScriptValue::I64(val) => Ok(*val as f32),
+            ScriptValue::U32(val) => Ok(*val as f32),
+            ScriptValue::U64(val) => Ok(*val as f32),
+            ScriptValue::F64(val) => Ok(*val as f32),
             _ => Err(RuntimeError::InvalidOperation(format!(
                 "Cannot convert {:?} to f32",
                 self.get_type()
@@ -191,6 +287,17 @@ impl ScriptValue {
         match self {
             ScriptValue::I32(val) => Ok(*val),
             ScriptValue::F32(val) => Ok(*val as i32),
+            ScriptValue::I64(_)
+            | ScriptValue::U32(_)
+            | ScriptValue::U64(_)
+            | ScriptValue::F64(_) => {
+                match conversion::convert_checked(self, &Type::I32)
+                    .map_err(RuntimeError::InvalidOperation)?
+                {
+                    ScriptValue::I32(val) => Ok(val),
+                    _ => unreachable!("checked conversion to i32 returned another type"),
+                }
+            }
             _ => Err(RuntimeError::InvalidOperation(format!(
                 "Cannot convert {:?} to i32",
                 self.get_type()
@@ -203,7 +310,11 @@ impl PartialEq for ScriptValue {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (ScriptValue::I32(a), ScriptValue::I32(b)) => a == b,
+            (ScriptValue::I64(a), ScriptValue::I64(b)) => a == b,
+            (ScriptValue::U32(a), ScriptValue::U32(b)) => a == b,
+            (ScriptValue::U64(a), ScriptValue::U64(b)) => a == b,
             (ScriptValue::F32(a), ScriptValue::F32(b)) => a == b,
+            (ScriptValue::F64(a), ScriptValue::F64(b)) => a == b,
             (ScriptValue::Bool(a), ScriptValue::Bool(b)) => a == b,
             (ScriptValue::String(a), ScriptValue::String(b)) => a == b,
             (ScriptValue::Array(a), ScriptValue::Array(b)) => a == b,
@@ -211,8 +322,14 @@ impl PartialEq for ScriptValue {
             (ScriptValue::HashSet(a), ScriptValue::HashSet(b)) => a == b,
             (ScriptValue::Option(a), ScriptValue::Option(b)) => a == b,
             (ScriptValue::Result(a), ScriptValue::Result(b)) => a == b,
+            (ScriptValue::Enum(a), ScriptValue::Enum(b)) => a == b,
             (ScriptValue::Unit, ScriptValue::Unit) => true,
             (ScriptValue::Object(a), ScriptValue::Object(b)) => a == b,
+            (ScriptValue::Channel(a), ScriptValue::Channel(b)) => a.same_channel(b),
+            (ScriptValue::Mutex(a), ScriptValue::Mutex(b)) => a.same_lock(b),
+            (ScriptValue::RwLock(a), ScriptValue::RwLock(b)) => a.same_lock(b),
+            (ScriptValue::LockGuard(a), ScriptValue::LockGuard(b)) => a.same_guard(b),
+            (ScriptValue::AtomicI32(a), ScriptValue::AtomicI32(b)) => std::sync::Arc::ptr_eq(a, b),
             // Iterators and Closures cannot be compared for equality
             (ScriptValue::Iterator(_), ScriptValue::Iterator(_)) => false,
             (ScriptValue::Closure(_), ScriptValue::Closure(_)) => false,
@@ -222,23 +339,57 @@ impl PartialEq for ScriptValue {
 }
 
 impl StdLib {
-    /// Create a new standard library instance with all built-in functions
+    /// Create a new standard library instance with the built-in functions
+    /// of the active runtime profile
     pub fn new() -> Self {
+        Self::with_profile(profile::current())
+    }
+
+    /// Create a standard library instance with the built-in functions
+    /// available in `profile`
+    ///
+    /// The embedded profile only gets the core type, math, string,
+    /// conversion and collection functions, none of which touch files, the
+    /// network, threads or the async executor.
+    pub fn with_profile(profile: RuntimeProfile) -> Self {
         let mut stdlib = StdLib {
             functions: HashMap::new(),
         };
 
-        // Register all standard library functions
-        stdlib.register_io_functions();
         stdlib.register_string_functions();
         stdlib.register_core_type_functions();
         stdlib.register_collection_functions();
-        stdlib.register_functional_programming_functions();
         stdlib.register_math_functions();
+        stdlib.register_conversion_functions();
+        if profile == RuntimeProfile::Embedded {
+            return stdlib;
+        }
+
+        // Register the rest of the standard library
+        stdlib.register_io_functions();
+        stdlib.register_functional_programming_functions();
         stdlib.register_game_functions();
         stdlib.register_network_functions();
         stdlib.register_random_functions();
         stdlib.register_time_functions();
+        stdlib.register_resilience_functions();
+        stdlib.register_rate_limit_functions();
+        stdlib.register_cache_functions();
+        stdlib.register_graph_functions();
+        stdlib.register_ndarray_functions();
+        stdlib.register_memory_functions();
+        stdlib.register_weak_functions();
+        stdlib.register_channel_functions();
+        stdlib.register_thread_functions();
+        stdlib.register_sync_functions();
+        stdlib.register_i18n_functions();
+        stdlib.register_config_functions();
+        stdlib.register_template_functions();
+        stdlib.register_diff_functions();
+        stdlib.register_walk_functions();
+        stdlib.register_version_functions();
+        stdlib.register_process_functions();
+        stdlib.register_prompt_functions();
 
         stdlib
     }
@@ -309,7 +460,7 @@ impl StdLib {
                 params: vec![],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::String),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::read_line_impl,
@@ -322,7 +473,7 @@ impl StdLib {
                 params: vec![Type::String],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::String),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::read_file_impl,
@@ -335,7 +486,7 @@ impl StdLib {
                 params: vec![Type::String, Type::String],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::Named("unit".to_string())),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::write_file_impl,
@@ -348,7 +499,7 @@ impl StdLib {
                 params: vec![Type::String],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::Bool),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::file_exists_impl,
@@ -361,7 +512,7 @@ impl StdLib {
                 params: vec![Type::String],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::Bool),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::dir_exists_impl,
@@ -374,7 +525,7 @@ impl StdLib {
                 params: vec![Type::String],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::Named("unit".to_string())),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::create_dir_impl,
@@ -387,7 +538,7 @@ impl StdLib {
                 params: vec![Type::String],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::Named("unit".to_string())),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::delete_file_impl,
@@ -400,7 +551,7 @@ impl StdLib {
                 params: vec![Type::String, Type::String],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::Named("unit".to_string())),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::copy_file_impl,
@@ -413,7 +564,7 @@ impl StdLib {
                 params: vec![Type::String, Type::String],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::Named("unit".to_string())),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::append_file_impl,
@@ -426,7 +577,7 @@ impl StdLib {
                 params: vec![Type::String],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::Named("unit".to_string())),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::delete_dir_impl,
@@ -439,7 +590,7 @@ impl StdLib {
                 params: vec![Type::String],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::Array(Box::new(Type::String))),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::list_dir_impl,
@@ -452,7 +603,7 @@ impl StdLib {
                 params: vec![Type::String],
                 ret: Box::new(Type::Result {
                     ok: Box::new(Type::Named("Object".to_string())),
-                    err: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
                 }),
             },
             io::file_metadata_impl,
@@ -1043,6 +1194,24 @@ impl StdLib {
             functional::iter_skip_impl,
         );
 
+        self.register_function(
+            "iter_peek",
+            Type::Function {
+                params: vec![Type::Named("Iterator".to_string())],
+                ret: Box::new(Type::Named("Option".to_string())),
+            },
+            functional::iter_peek_impl,
+        );
+
+        self.register_function(
+            "iter_advance",
+            Type::Function {
+                params: vec![Type::Named("Iterator".to_string())],
+                ret: Box::new(Type::Named("Iterator".to_string())),
+            },
+            functional::iter_advance_impl,
+        );
+
         // Advanced combinators
         self.register_function(
             "vec_flat_map",
@@ -1867,6 +2036,209 @@ impl StdLib {
             },
             math::trunc_impl,
         );
+
+        // Integer arithmetic with explicit overflow behavior, for any integer
+        // type as long as both operands have the same one
+        self.register_function(
+            "checked_add",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Named("Option".to_string())),
+            },
+            math::checked_add_impl,
+        );
+
+        self.register_function(
+            "checked_sub",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Named("Option".to_string())),
+            },
+            math::checked_sub_impl,
+        );
+
+        self.register_function(
+            "checked_mul",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Named("Option".to_string())),
+            },
+            math::checked_mul_impl,
+        );
+
+        self.register_function(
+            "wrapping_add",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Unknown),
+            },
+            math::wrapping_add_impl,
+        );
+
+        self.register_function(
+            "wrapping_sub",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Unknown),
+            },
+            math::wrapping_sub_impl,
+        );
+
+        self.register_function(
+            "wrapping_mul",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Unknown),
+            },
+            math::wrapping_mul_impl,
+        );
+
+        self.register_function(
+            "saturating_add",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Unknown),
+            },
+            math::saturating_add_impl,
+        );
+
+        self.register_function(
+            "saturating_sub",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Unknown),
+            },
+            math::saturating_sub_impl,
+        );
+
+        self.register_function(
+            "saturating_mul",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Unknown),
+            },
+            math::saturating_mul_impl,
+        );
+    }
+
+    /// Register conversion and parsing functions
+    fn register_conversion_functions(&mut self) {
+        // Checked numeric conversions
+        self.register_function(
+            "as_i32_checked",
+            Type::Function {
+                params: vec![Type::Unknown],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::I32),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            conversion::as_i32_checked_impl,
+        );
+
+        self.register_function(
+            "as_i64_checked",
+            Type::Function {
+                params: vec![Type::Unknown],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::I64),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            conversion::as_i64_checked_impl,
+        );
+
+        self.register_function(
+            "as_u32_checked",
+            Type::Function {
+                params: vec![Type::Unknown],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::U32),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            conversion::as_u32_checked_impl,
+        );
+
+        self.register_function(
+            "as_u64_checked",
+            Type::Function {
+                params: vec![Type::Unknown],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::U64),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            conversion::as_u64_checked_impl,
+        );
+
+        self.register_function(
+            "as_f32_checked",
+            Type::Function {
+                params: vec![Type::Unknown],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::F32),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            conversion::as_f32_checked_impl,
+        );
+
+        self.register_function(
+            "as_f64_checked",
+            Type::Function {
+                params: vec![Type::Unknown],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::F64),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            conversion::as_f64_checked_impl,
+        );
+
+        // Parsing
+        self.register_function(
+            "parse_int",
+            Type::Function {
+                params: vec![Type::String, Type::I32],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::I32),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            conversion::parse_int_impl,
+        );
+
+        self.register_function(
+            "parse_float",
+            Type::Function {
+                params: vec![Type::String],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::F32),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            conversion::parse_float_impl,
+        );
+
+        // Formatting
+        self.register_function(
+            "to_string",
+            Type::Function {
+                params: vec![Type::Unknown],
+                ret: Box::new(Type::String),
+            },
+            conversion::to_string_impl,
+        );
+
+        self.register_function(
+            "inspect",
+            Type::Function {
+                params: vec![Type::Unknown],
+                ret: Box::new(Type::String),
+            },
+            inspect::inspect_impl,
+        );
     }
 
     /// Register game-oriented utility functions
@@ -2218,6 +2590,15 @@ impl StdLib {
             time::sleep_impl,
         );
 
+        self.register_function(
+            "time_advance",
+            Type::Function {
+                params: vec![Type::F32],
+                ret: Box::new(Type::Named("unit".to_string())),
+            },
+            time::time_advance_impl,
+        );
+
         // Stopwatch functions
         self.register_function(
             "stopwatch_new",
@@ -2339,40 +2720,1267 @@ impl StdLib {
             time::measure_time_impl,
         );
     }
-}
 
-impl Default for StdLib {
-    fn default() -> Self {
-        Self::new()
-    }
-}
+    /// Register retry, backoff and circuit-breaker functions
+    fn register_resilience_functions(&mut self) {
+        self.register_function(
+            "backoff",
+            Type::Function {
+                params: vec![Type::F32, Type::F32, Type::F32, Type::F32],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            resilience::backoff_impl,
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        self.register_function(
+            "retry",
+            Type::Function {
+                params: vec![
+                    Type::Named("Closure".to_string()),
+                    Type::I32,
+                    Type::Unknown, // Delay in milliseconds or backoff object
+                ],
+                ret: Box::new(Type::Unknown),
+            },
+            resilience::retry_impl,
+        );
 
-    #[test]
-    fn test_stdlib_creation() {
-        let stdlib = StdLib::new();
+        self.register_function(
+            "circuit_breaker",
+            Type::Function {
+                params: vec![Type::I32, Type::F32],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            resilience::circuit_breaker_impl,
+        );
 
-        // Check that basic I/O functions are registered
-        assert!(stdlib.get_function("print").is_some());
-        assert!(stdlib.get_function("println").is_some());
-        assert!(stdlib.get_function("eprintln").is_some());
-        assert!(stdlib.get_function("read_line").is_some());
-        assert!(stdlib.get_function("read_file").is_some());
-        assert!(stdlib.get_function("write_file").is_some());
+        self.register_function(
+            "circuit_call",
+            Type::Function {
+                params: vec![
+                    Type::Named("Object".to_string()),
+                    Type::Named("Closure".to_string()),
+                ],
+                ret: Box::new(Type::Unknown),
+            },
+            resilience::circuit_call_impl,
+        );
+
+        self.register_function(
+            "circuit_state",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string())],
+                ret: Box::new(Type::String),
+            },
+            resilience::circuit_state_impl,
+        );
     }
 
-    #[test]
-    fn test_script_value_types() {
-        let int_val = ScriptValue::I32(42);
-        assert_eq!(int_val.get_type(), Type::I32);
-        assert_eq!(int_val.as_i32(), Some(42));
-        assert_eq!(int_val.as_f32(), None);
+    /// Register rate limiting functions
+    fn register_rate_limit_functions(&mut self) {
+        self.register_function(
+            "rate_limiter",
+            Type::Function {
+                params: vec![Type::F32, Type::F32],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            rate_limit::rate_limiter_impl,
+        );
 
-        let float_val = ScriptValue::F32(3.14);
-        assert_eq!(float_val.get_type(), Type::F32);
+        self.register_function(
+            "rate_limit_try",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string())],
+                ret: Box::new(Type::Bool),
+            },
+            rate_limit::rate_limit_try_impl,
+        );
+
+        self.register_function(
+            "rate_limit_acquire",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string())],
+                ret: Box::new(Type::Named("unit".to_string())),
+            },
+            rate_limit::rate_limit_acquire_impl,
+        );
+
+        self.register_function(
+            "rate_limiter_stats",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string())],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            rate_limit::rate_limiter_stats_impl,
+        );
+    }
+
+    /// Register LRU and disk cache functions
+    fn register_cache_functions(&mut self) {
+        self.register_function(
+            "lru_new",
+            Type::Function {
+                params: vec![Type::I32],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            cache::lru_new_impl,
+        );
+
+        self.register_function(
+            "disk_cache_new",
+            Type::Function {
+                params: vec![Type::String],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::Named("Object".to_string())),
+                    err: Box::new(Type::String),
+                }),
+            },
+            cache::disk_cache_new_impl,
+        );
+
+        // Cache keys may be strings, numbers or booleans
+        self.register_function(
+            "cache_get",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string()), Type::Unknown],
+                ret: Box::new(Type::Option(Box::new(Type::Unknown))),
+            },
+            cache::cache_get_impl,
+        );
+
+        self.register_function(
+            "cache_put",
+            Type::Function {
+                params: vec![
+                    Type::Named("Object".to_string()),
+                    Type::Unknown,
+                    Type::Unknown,
+                ],
+                ret: Box::new(Type::Named("unit".to_string())),
+            },
+            cache::cache_put_impl,
+        );
+
+        self.register_function(
+            "cache_contains",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string()), Type::Unknown],
+                ret: Box::new(Type::Bool),
+            },
+            cache::cache_contains_impl,
+        );
+
+        self.register_function(
+            "cached",
+            Type::Function {
+                params: vec![
+                    Type::Named("Object".to_string()),
+                    Type::Unknown,
+                    Type::Named("Closure".to_string()),
+                ],
+                ret: Box::new(Type::Unknown),
+            },
+            cache::cached_impl,
+        );
+
+        self.register_function(
+            "cache_stats",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string())],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            cache::cache_stats_impl,
+        );
+    }
+
+    /// Register graph data structure and algorithm functions
+    fn register_graph_functions(&mut self) {
+        self.register_function(
+            "graph_new",
+            Type::Function {
+                params: vec![Type::Bool],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            graph::graph_new_impl,
+        );
+
+        // Node names may be strings, numbers or booleans
+        self.register_function(
+            "graph_add_node",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string()), Type::Unknown],
+                ret: Box::new(Type::Named("unit".to_string())),
+            },
+            graph::graph_add_node_impl,
+        );
+
+        self.register_function(
+            "graph_add_edge",
+            Type::Function {
+                params: vec![
+                    Type::Named("Object".to_string()),
+                    Type::Unknown,
+                    Type::Unknown,
+                ],
+                ret: Box::new(Type::Named("unit".to_string())),
+            },
+            graph::graph_add_edge_impl,
+        );
+
+        self.register_function(
+            "graph_add_weighted_edge",
+            Type::Function {
+                params: vec![
+                    Type::Named("Object".to_string()),
+                    Type::Unknown,
+                    Type::Unknown,
+                    Type::F32,
+                ],
+                ret: Box::new(Type::Named("unit".to_string())),
+            },
+            graph::graph_add_weighted_edge_impl,
+        );
+
+        self.register_function(
+            "graph_has_edge",
+            Type::Function {
+                params: vec![
+                    Type::Named("Object".to_string()),
+                    Type::Unknown,
+                    Type::Unknown,
+                ],
+                ret: Box::new(Type::Bool),
+            },
+            graph::graph_has_edge_impl,
+        );
+
+        self.register_function(
+            "graph_nodes",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string())],
+                ret: Box::new(Type::Array(Box::new(Type::String))),
+            },
+            graph::graph_nodes_impl,
+        );
+
+        self.register_function(
+            "graph_neighbors",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string()), Type::Unknown],
+                ret: Box::new(Type::Array(Box::new(Type::String))),
+            },
+            graph::graph_neighbors_impl,
+        );
+
+        self.register_function(
+            "graph_bfs",
+            Type::Function {
+                params: vec![
+                    Type::Named("Object".to_string()),
+                    Type::Unknown,
+                    Type::Named("Closure".to_string()),
+                ],
+                ret: Box::new(Type::Array(Box::new(Type::String))),
+            },
+            graph::graph_bfs_impl,
+        );
+
+        self.register_function(
+            "graph_dfs",
+            Type::Function {
+                params: vec![
+                    Type::Named("Object".to_string()),
+                    Type::Unknown,
+                    Type::Named("Closure".to_string()),
+                ],
+                ret: Box::new(Type::Array(Box::new(Type::String))),
+            },
+            graph::graph_dfs_impl,
+        );
+
+        self.register_function(
+            "graph_topo_sort",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string())],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::Array(Box::new(Type::String))),
+                    err: Box::new(Type::String),
+                }),
+            },
+            graph::graph_topo_sort_impl,
+        );
+
+        self.register_function(
+            "graph_shortest_path",
+            Type::Function {
+                params: vec![
+                    Type::Named("Object".to_string()),
+                    Type::Unknown,
+                    Type::Unknown,
+                ],
+                ret: Box::new(Type::Option(Box::new(Type::Named("Object".to_string())))),
+            },
+            graph::graph_shortest_path_impl,
+        );
+
+        self.register_function(
+            "graph_scc",
+            Type::Function {
+                params: vec![Type::Named("Object".to_string())],
+                ret: Box::new(Type::Array(Box::new(Type::Array(Box::new(Type::String))))),
+            },
+            graph::graph_scc_impl,
+        );
+    }
+
+    /// Register n-dimensional array functions
+    fn register_ndarray_functions(&mut self) {
+        // Numbers and arrays of numbers are accepted wherever an ndarray is expected
+        self.register_function(
+            "ndarray",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Array(Box::new(Type::I32))],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_new_impl,
+        );
+
+        self.register_function(
+            "ndarray_zeros",
+            Type::Function {
+                params: vec![Type::Array(Box::new(Type::I32))],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_zeros_impl,
+        );
+
+        self.register_function(
+            "ndarray_ones",
+            Type::Function {
+                params: vec![Type::Array(Box::new(Type::I32))],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_ones_impl,
+        );
+
+        self.register_function(
+            "ndarray_arange",
+            Type::Function {
+                params: vec![Type::I32],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_arange_impl,
+        );
+
+        self.register_function(
+            "ndarray_shape",
+            Type::Function {
+                params: vec![Type::Unknown],
+                ret: Box::new(Type::Array(Box::new(Type::I32))),
+            },
+            ndarray::ndarray_shape_impl,
+        );
+
+        self.register_function(
+            "ndarray_get",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Array(Box::new(Type::I32))],
+                ret: Box::new(Type::F32),
+            },
+            ndarray::ndarray_get_impl,
+        );
+
+        self.register_function(
+            "ndarray_reshape",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Array(Box::new(Type::I32))],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_reshape_impl,
+        );
+
+        self.register_function(
+            "ndarray_transpose",
+            Type::Function {
+                params: vec![Type::Unknown],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_transpose_impl,
+        );
+
+        self.register_function(
+            "ndarray_slice",
+            Type::Function {
+                params: vec![Type::Unknown, Type::I32, Type::I32, Type::I32],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_slice_impl,
+        );
+
+        self.register_function(
+            "ndarray_add",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_add_impl,
+        );
+
+        self.register_function(
+            "ndarray_sub",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_sub_impl,
+        );
+
+        self.register_function(
+            "ndarray_mul",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_mul_impl,
+        );
+
+        self.register_function(
+            "ndarray_div",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_div_impl,
+        );
+
+        self.register_function(
+            "ndarray_matmul",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Unknown],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_matmul_impl,
+        );
+
+        self.register_function(
+            "ndarray_sum",
+            Type::Function {
+                params: vec![Type::Unknown],
+                ret: Box::new(Type::F32),
+            },
+            ndarray::ndarray_sum_impl,
+        );
+
+        self.register_function(
+            "ndarray_sum_axis",
+            Type::Function {
+                params: vec![Type::Unknown, Type::I32],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_sum_axis_impl,
+        );
+
+        self.register_function(
+            "ndarray_map",
+            Type::Function {
+                params: vec![Type::Unknown, Type::Named("Closure".to_string())],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            ndarray::ndarray_map_impl,
+        );
+    }
+
+    /// Register heap statistics and garbage collector functions
+    fn register_memory_functions(&mut self) {
+        self.register_function(
+            "heap_stats",
+            Type::Function {
+                params: vec![],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            memory::heap_stats_impl,
+        );
+
+        self.register_function(
+            "gc_collect",
+            Type::Function {
+                params: vec![],
+                ret: Box::new(Type::I64),
+            },
+            memory::gc_collect_impl,
+        );
+
+        self.register_function(
+            "memory_report",
+            Type::Function {
+                params: vec![],
+                ret: Box::new(Type::Named("Object".to_string())),
+            },
+            memory::memory_report_impl,
+        );
+    }
+
+    /// Register weak reference and finalizer functions
+    fn register_weak_functions(&mut self) {
+        self.register_function(
+            "weak_ref",
+            Type::Function {
+                params: vec![Type::TypeParam("T".to_string())],
+                ret: Box::new(Type::Generic {
+                    name: "Weak".to_string(),
+                    args: vec![Type::TypeParam("T".to_string())],
+                }),
+            },
+            weak::weak_ref_impl,
+        );
+
+        self.register_function(
+            "weak_upgrade",
+            Type::Function {
+                params: vec![Type::Generic {
+                    name: "Weak".to_string(),
+                    args: vec![Type::TypeParam("T".to_string())],
+                }],
+                ret: Box::new(Type::Generic {
+                    name: "Option".to_string(),
+                    args: vec![Type::TypeParam("T".to_string())],
+                }),
+            },
+            weak::weak_upgrade_impl,
+        );
+
+        self.register_function(
+            "on_finalize",
+            Type::Function {
+                params: vec![
+                    Type::TypeParam("T".to_string()),
+                    Type::Function {
+                        params: vec![],
+                        ret: Box::new(Type::Unknown),
+                    },
+                ],
+                ret: Box::new(Type::Named("unit".to_string())),
+            },
+            weak::on_finalize_impl,
+        );
+    }
+
+    /// Register channel functions
+    fn register_channel_functions(&mut self) {
+        let channel_of_t = || Type::Generic {
+            name: "Channel".to_string(),
+            args: vec![Type::TypeParam("T".to_string())],
+        };
+        let option_of = |inner: Type| Type::Generic {
+            name: "Option".to_string(),
+            args: vec![inner],
+        };
+
+        self.register_function(
+            "channel",
+            Type::Function {
+                params: vec![Type::I32],
+                ret: Box::new(Type::Generic {
+                    name: "Channel".to_string(),
+                    args: vec![Type::Unknown],
+                }),
+            },
+            channel::channel_impl,
+        );
+
+        self.register_function(
+            "send",
+            Type::Function {
+                params: vec![channel_of_t(), Type::TypeParam("T".to_string())],
+                ret: Box::new(Type::Named("unit".to_string())),
+            },
+            channel::send_impl,
+        );
+
+        self.register_function(
+            "try_send",
+            Type::Function {
+                params: vec![channel_of_t(), Type::TypeParam("T".to_string())],
+                ret: Box::new(Type::Bool),
+            },
+            channel::try_send_impl,
+        );
+
+        self.register_function(
+            "recv",
+            Type::Function {
+                params: vec![channel_of_t()],
+                ret: Box::new(option_of(Type::TypeParam("T".to_string()))),
+            },
+            channel::recv_impl,
+        );
+
+        self.register_function(
+            "try_recv",
+            Type::Function {
+                params: vec![channel_of_t()],
+                ret: Box::new(option_of(Type::TypeParam("T".to_string()))),
+            },
+            channel::try_recv_impl,
+        );
+
+        self.register_function(
+            "close_channel",
+            Type::Function {
+                params: vec![channel_of_t()],
+                ret: Box::new(Type::Named("unit".to_string())),
+            },
+            channel::close_channel_impl,
+        );
+
+        self.register_function(
+            "select",
+            Type::Function {
+                params: vec![Type::Array(Box::new(channel_of_t()))],
+                ret: Box::new(option_of(Type::Named("Object".to_string()))),
+            },
+            channel::select_impl,
+        );
+    }
+
+    /// Register thread functions
+    fn register_thread_functions(&mut self) {
+        let thread_of = |inner: &str| Type::Generic {
+            name: "Thread".to_string(),
+            args: vec![Type::TypeParam(inner.to_string())],
+        };
+        let closure_returning = |inner: &str| Type::Function {
+            params: vec![],
+            ret: Box::new(Type::TypeParam(inner.to_string())),
+        };
+
+        self.register_function(
+            "thread_spawn",
+            Type::Function {
+                params: vec![closure_returning("T")],
+                ret: Box::new(thread_of("T")),
+            },
+            thread::thread_spawn_impl,
+        );
+
+        self.register_function(
+            "thread_join",
+            Type::Function {
+                params: vec![thread_of("T")],
+                ret: Box::new(Type::TypeParam("T".to_string())),
+            },
+            thread::thread_join_impl,
+        );
+
+        self.register_function(
+            "thread_scope",
+            Type::Function {
+                params: vec![Type::Function {
+                    params: vec![Type::Named("ThreadScope".to_string())],
+                    ret: Box::new(Type::TypeParam("T".to_string())),
+                }],
+                ret: Box::new(Type::TypeParam("T".to_string())),
+            },
+            thread::thread_scope_impl,
+        );
+
+        self.register_function(
+            "scope_spawn",
+            Type::Function {
+                params: vec![
+                    Type::Named("ThreadScope".to_string()),
+                    closure_returning("T"),
+                ],
+                ret: Box::new(thread_of("T")),
+            },
+            thread::scope_spawn_impl,
+        );
+    }
+
+    /// Register lock and atomic functions
+    fn register_sync_functions(&mut self) {
+        let generic = |name: &str| Type::Generic {
+            name: name.to_string(),
+            args: vec![Type::TypeParam("T".to_string())],
+        };
+        let t = || Type::TypeParam("T".to_string());
+        let atomic = || Type::Named("AtomicI32".to_string());
+        let unit = || Type::Named("unit".to_string());
+
+        self.register_function(
+            "mutex_new",
+            Type::Function {
+                params: vec![t()],
+                ret: Box::new(generic("Mutex")),
+            },
+            sync::mutex_new_impl,
+        );
+
+        self.register_function(
+            "mutex_lock",
+            Type::Function {
+                params: vec![generic("Mutex")],
+                ret: Box::new(generic("Guard")),
+            },
+            sync::mutex_lock_impl,
+        );
+
+        self.register_function(
+            "mutex_try_lock",
+            Type::Function {
+                params: vec![generic("Mutex")],
+                ret: Box::new(Type::Generic {
+                    name: "Option".to_string(),
+                    args: vec![generic("Guard")],
+                }),
+            },
+            sync::mutex_try_lock_impl,
+        );
+
+        self.register_function(
+            "rwlock_new",
+            Type::Function {
+                params: vec![t()],
+                ret: Box::new(generic("RwLock")),
+            },
+            sync::rwlock_new_impl,
+        );
+
+        self.register_function(
+            "rwlock_read",
+            Type::Function {
+                params: vec![generic("RwLock")],
+                ret: Box::new(generic("Guard")),
+            },
+            sync::rwlock_read_impl,
+        );
+
+        self.register_function(
+            "rwlock_write",
+            Type::Function {
+                params: vec![generic("RwLock")],
+                ret: Box::new(generic("Guard")),
+            },
+            sync::rwlock_write_impl,
+        );
+
+        self.register_function(
+            "guard_get",
+            Type::Function {
+                params: vec![generic("Guard")],
+                ret: Box::new(t()),
+            },
+            sync::guard_get_impl,
+        );
+
+        self.register_function(
+            "guard_set",
+            Type::Function {
+                params: vec![generic("Guard"), t()],
+                ret: Box::new(unit()),
+            },
+            sync::guard_set_impl,
+        );
+
+        self.register_function(
+            "unlock",
+            Type::Function {
+                params: vec![generic("Guard")],
+                ret: Box::new(unit()),
+            },
+            sync::unlock_impl,
+        );
+
+        self.register_function(
+            "atomic_i32",
+            Type::Function {
+                params: vec![Type::I32],
+                ret: Box::new(atomic()),
+            },
+            sync::atomic_i32_impl,
+        );
+
+        self.register_function(
+            "atomic_load",
+            Type::Function {
+                params: vec![atomic()],
+                ret: Box::new(Type::I32),
+            },
+            sync::atomic_load_impl,
+        );
+
+        self.register_function(
+            "atomic_store",
+            Type::Function {
+                params: vec![atomic(), Type::I32],
+                ret: Box::new(unit()),
+            },
+            sync::atomic_store_impl,
+        );
+
+        self.register_function(
+            "atomic_add",
+            Type::Function {
+                params: vec![atomic(), Type::I32],
+                ret: Box::new(Type::I32),
+            },
+            sync::atomic_add_impl,
+        );
+
+        self.register_function(
+            "atomic_compare_exchange",
+            Type::Function {
+                params: vec![atomic(), Type::I32, Type::I32],
+                ret: Box::new(Type::Bool),
+            },
+            sync::atomic_compare_exchange_impl,
+        );
+    }
+    fn register_i18n_functions(&mut self) {
+        self.register_function(
+            "i18n_load",
+            Type::Function {
+                params: vec![Type::String, Type::String],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::I32),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            i18n::i18n_load_impl,
+        );
+
+        self.register_function(
+            "i18n_message",
+            Type::Function {
+                params: vec![Type::String, Type::String, Type::Unknown],
+                ret: Box::new(Type::String),
+            },
+            i18n::i18n_message_impl,
+        );
+
+        self.register_function(
+            "i18n_has_message",
+            Type::Function {
+                params: vec![Type::String, Type::String],
+                ret: Box::new(Type::Bool),
+            },
+            i18n::i18n_has_message_impl,
+        );
+
+        self.register_function(
+            "i18n_plural",
+            Type::Function {
+                params: vec![Type::String, Type::F32],
+                ret: Box::new(Type::String),
+            },
+            i18n::i18n_plural_impl,
+        );
+
+        self.register_function(
+            "i18n_format_number",
+            Type::Function {
+                params: vec![Type::String, Type::F32, Type::I32],
+                ret: Box::new(Type::String),
+            },
+            i18n::i18n_format_number_impl,
+        );
+
+        self.register_function(
+            "i18n_format_date",
+            Type::Function {
+                params: vec![Type::String, Type::F32, Type::String],
+                ret: Box::new(Type::String),
+            },
+            i18n::i18n_format_date_impl,
+        );
+    }
+
+    fn register_config_functions(&mut self) {
+        let config_result = || Type::Result {
+            ok: Box::new(Type::Named("Object".to_string())),
+            err: Box::new(Type::Named("Error".to_string())),
+        };
+
+        // The schema is an object of type names and specs
+        self.register_function(
+            "config_load",
+            Type::Function {
+                params: vec![Type::String, Type::Unknown],
+                ret: Box::new(config_result()),
+            },
+            config::config_load_impl,
+        );
+
+        self.register_function(
+            "config_parse",
+            Type::Function {
+                params: vec![Type::String, Type::Unknown],
+                ret: Box::new(config_result()),
+            },
+            config::config_parse_impl,
+        );
+    }
+
+    fn register_template_functions(&mut self) {
+        // The variables are an object or a map
+        self.register_function(
+            "template_render",
+            Type::Function {
+                params: vec![Type::String, Type::Unknown],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            template::template_render_impl,
+        );
+    }
+
+    fn register_diff_functions(&mut self) {
+        // Hunks are objects, see the diff module
+        self.register_function(
+            "diff_lines",
+            Type::Function {
+                params: vec![Type::String, Type::String],
+                ret: Box::new(Type::Array(Box::new(Type::Unknown))),
+            },
+            diff::diff_lines_impl,
+        );
+
+        self.register_function(
+            "apply_patch",
+            Type::Function {
+                params: vec![Type::String, Type::Array(Box::new(Type::Unknown))],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            diff::apply_patch_impl,
+        );
+
+        self.register_function(
+            "diff_unified",
+            Type::Function {
+                params: vec![Type::String, Type::String, Type::Bool],
+                ret: Box::new(Type::String),
+            },
+            diff::diff_unified_impl,
+        );
+    }
+
+    /// Register glob matching and file tree walking functions
+    fn register_walk_functions(&mut self) {
+        self.register_function(
+            "glob",
+            Type::Function {
+                params: vec![Type::String],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::Array(Box::new(Type::String))),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            walk::glob_impl,
+        );
+
+        // Entries are objects, see the walk module
+        self.register_function(
+            "walk_dir",
+            Type::Function {
+                params: vec![Type::String, Type::Named("Closure".to_string())],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::I32),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            walk::walk_dir_impl,
+        );
+
+        self.register_function(
+            "walk_dir_with",
+            Type::Function {
+                params: vec![
+                    Type::String,
+                    Type::Named("Object".to_string()),
+                    Type::Named("Closure".to_string()),
+                ],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::I32),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            walk::walk_dir_with_impl,
+        );
+    }
+
+    /// Register semantic version functions
+    fn register_version_functions(&mut self) {
+        // Versions are objects, see the version module
+        self.register_function(
+            "semver_parse",
+            Type::Function {
+                params: vec![Type::String],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::Named("Object".to_string())),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            version::semver_parse_impl,
+        );
+
+        self.register_function(
+            "semver_compare",
+            Type::Function {
+                params: vec![Type::String, Type::String],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::I32),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            version::semver_compare_impl,
+        );
+
+        self.register_function(
+            "semver_matches",
+            Type::Function {
+                params: vec![Type::String, Type::String],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::Bool),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            version::semver_matches_impl,
+        );
+    }
+
+    /// Register shutdown hook functions
+    fn register_process_functions(&mut self) {
+        self.register_function(
+            "on_shutdown",
+            Type::Function {
+                params: vec![Type::Function {
+                    params: vec![],
+                    ret: Box::new(Type::Unknown),
+                }],
+                ret: Box::new(Type::Named("unit".to_string())),
+            },
+            process::on_shutdown_impl,
+        );
+
+        self.register_function(
+            "shutdown_requested",
+            Type::Function {
+                params: vec![],
+                ret: Box::new(Type::Bool),
+            },
+            process::shutdown_requested_impl,
+        );
+    }
+
+    /// Register interactive prompt functions
+    fn register_prompt_functions(&mut self) {
+        // prompt function: (string, Closure?) -> Result<string, Error>
+        self.register_function(
+            "prompt",
+            Type::Function {
+                params: vec![Type::String, Type::Named("Closure".to_string())],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            prompt::prompt_impl,
+        );
+
+        // prompt_password function: (string, Closure?) -> Result<string, Error>
+        self.register_function(
+            "prompt_password",
+            Type::Function {
+                params: vec![Type::String, Type::Named("Closure".to_string())],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::String),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            prompt::prompt_password_impl,
+        );
+
+        // confirm function: (string, bool?) -> Result<bool, Error>
+        self.register_function(
+            "confirm",
+            Type::Function {
+                params: vec![Type::String, Type::Bool],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::Bool),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            prompt::confirm_impl,
+        );
+
+        // prompt_select function: ([T], string?) -> Result<i32, Error>, the
+        // index chosen
+        self.register_function(
+            "prompt_select",
+            Type::Function {
+                params: vec![Type::Array(Box::new(Type::Unknown)), Type::String],
+                ret: Box::new(Type::Result {
+                    ok: Box::new(Type::I32),
+                    err: Box::new(Type::Named("Error".to_string())),
+                }),
+            },
+            prompt::select_impl,
+        );
+    }
+}
+
+impl Default for StdLib {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Check that `function` was called with exactly `count` arguments
+pub(crate) fn expect_args(
+    args: &[ScriptValue],
+    count: usize,
+    function: &str,
+) -> Result<(), RuntimeError> {
+    if args.len() == count {
+        Ok(())
+    } else {
+        Err(RuntimeError::InvalidOperation(format!(
+            "{} expects {} argument{}, got {}",
+            function,
+            count,
+            if count == 1 { "" } else { "s" },
+            args.len()
+        )))
+    }
+}
+
+/// Read argument `index` of `function` as a string
+pub(crate) fn string_arg<'a>(
+    args: &'a [ScriptValue],
+    index: usize,
+    function: &str,
+) -> Result<&'a str, RuntimeError> {
+    match &args[index] {
+        ScriptValue::String(s) => Ok(s.as_str()),
+        other => Err(RuntimeError::InvalidOperation(format!(
+            "{} expects a string for argument {}, got {:?}",
+            function,
+            index + 1,
+            other.get_type()
+        ))),
+    }
+}
+
+/// Read argument `index` of `function` as a number of any width
+pub(crate) fn number_arg(
+    args: &[ScriptValue],
+    index: usize,
+    function: &str,
+) -> Result<f64, RuntimeError> {
+    number_value(&args[index]).ok_or_else(|| {
+        RuntimeError::InvalidOperation(format!(
+            "{} expects a number for argument {}, got {:?}",
+            function,
+            index + 1,
+            args[index].get_type()
+        ))
+    })
+}
+
+/// Check that `value`, an argument of `function`, is a closure
+pub(crate) fn expect_closure(value: &ScriptValue, function: &str) -> Result<(), RuntimeError> {
+    match value {
+        ScriptValue::Closure(_) => Ok(()),
+        _ => Err(RuntimeError::InvalidOperation(format!(
+            "{} expects a closure",
+            function
+        ))),
+    }
+}
+
+/// Read the id stored under `key` in a handle object, such as the one a
+/// cache or circuit breaker is returned as
+///
+/// `expected` describes the handle for the error, e.g. "a circuit breaker".
+pub(crate) fn handle_id(
+    value: &ScriptValue,
+    key: &str,
+    expected: &str,
+) -> Result<i32, RuntimeError> {
+    match value {
+        ScriptValue::Object(fields) => match fields.get(key) {
+            Some(ScriptValue::I32(id)) => Some(*id),
+            _ => None,
+        },
+        _ => None,
+    }
+    .ok_or_else(|| RuntimeError::InvalidOperation(format!("Expected {}", expected)))
+}
+
+/// Wrap `text` in a Script string value
+pub(crate) fn string_value(text: &str) -> ScriptValue {
+    ScriptValue::String(ScriptRc::new(ScriptString::from_str(text)))
+}
+
+/// The value of a number of any width as an `f64`
+pub(crate) fn number_value(value: &ScriptValue) -> Option<f64> {
+    match value {
+        ScriptValue::I32(n) => Some(*n as f64),
+        ScriptValue::I64(n) => Some(*n as f64),
+        ScriptValue::U32(n) => Some(*n as f64),
+        ScriptValue::U64(n) => Some(*n as f64),
+        ScriptValue::F32(n) => Some(*n as f64),
+        ScriptValue::F64(n) => Some(*n),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stdlib_creation() {
+        let stdlib = StdLib::new();
+
+        // Check that basic I/O functions are registered
+        assert!(stdlib.get_function("print").is_some());
+        assert!(stdlib.get_function("println").is_some());
+        assert!(stdlib.get_function("eprintln").is_some());
+        assert!(stdlib.get_function("read_line").is_some());
+        assert!(stdlib.get_function("read_file").is_some());
+        assert!(stdlib.get_function("write_file").is_some());
+    }
+
+    #[test]
+    fn test_embedded_profile_leaves_out_host_functions() {
+        let embedded = StdLib::with_profile(RuntimeProfile::Embedded);
+        for name in ["string_len", "sqrt", "vec_push", "parse_int"] {
+            assert!(embedded.get_function(name).is_some(), "missing {}", name);
+        }
+        for name in ["print", "read_file", "tcp_connect", "thread_spawn", "sleep"] {
+            assert!(embedded.get_function(name).is_none(), "has {}", name);
+        }
+
+        let full = StdLib::with_profile(RuntimeProfile::Full);
+        assert!(full.get_function("read_file").is_some());
+        assert!(full.function_names().len() > embedded.function_names().len());
+    }
+
+    #[test]
+    fn test_script_value_types() {
+        let int_val = ScriptValue::I32(42);
+        assert_eq!(int_val.get_type(), Type::I32);
+        assert_eq!(int_val.as_i32(), Some(42));
+        assert_eq!(int_val.as_f32(), None);
+
+        let float_val = ScriptValue::F32(3.14);
+        assert_eq!(float_val.get_type(), Type::F32);
         assert_eq!(float_val.as_f32(), Some(3.14));
         assert_eq!(float_val.as_i32(), None);
 
@@ -2382,5 +3990,165 @@ mod tests {
 
         let unit_val = ScriptValue::Unit;
         assert!(unit_val.is_unit());
+
+        let wide_val = ScriptValue::I64(1 << 40);
+        assert_eq!(wide_val.get_type(), Type::I64);
+        assert_eq!(wide_val.as_i64(), Some(1 << 40));
+        assert!(wide_val.to_i32().is_err());
+
+        let double_val = ScriptValue::F64(2.5);
+        assert_eq!(double_val.get_type(), Type::F64);
+        assert_eq!(double_val.as_f64(), Some(2.5));
+    }
+
+    #[test]
+    fn test_overflow_intrinsics() {
+        let stdlib = StdLib::new();
+        let call = |name: &str, a: ScriptValue, b: ScriptValue| {
+            let function = stdlib.get_function(name).unwrap();
+            (function.implementation)(&[a, b]).unwrap()
+        };
+
+        assert_eq!(
+            call("checked_add", ScriptValue::I32(1), ScriptValue::I32(2)),
+            ScriptValue::Option(ScriptRc::new(ScriptOption::some(ScriptValue::I32(3))))
+        );
+        assert_eq!(
+            call(
+                "checked_add",
+                ScriptValue::I32(i32::MAX),
+                ScriptValue::I32(1)
+            ),
+            ScriptValue::Option(ScriptRc::new(ScriptOption::none()))
+        );
+        assert_eq!(
+            call(
+                "wrapping_mul",
+                ScriptValue::I32(i32::MAX),
+                ScriptValue::I32(2)
+            ),
+            ScriptValue::I32(-2)
+        );
+        assert_eq!(
+            call("saturating_sub", ScriptValue::U32(1), ScriptValue::U32(2)),
+            ScriptValue::U32(0)
+        );
+        assert_eq!(
+            call(
+                "saturating_add",
+                ScriptValue::I64(i64::MAX),
+                ScriptValue::I64(1)
+            ),
+            ScriptValue::I64(i64::MAX)
+        );
+
+        let function = stdlib.get_function("wrapping_add").unwrap();
+        assert!((function.implementation)(&[ScriptValue::I32(1), ScriptValue::I64(1)]).is_err());
+    }
+
+    #[test]
+    fn test_checked_numeric_conversions() {
+        let stdlib = StdLib::new();
+        let convert = |name: &str, value: ScriptValue| {
+            let function = stdlib.get_function(name).unwrap();
+            match (function.implementation)(&[value]).unwrap() {
+                ScriptValue::Result(result) => (*result).clone(),
+                other => panic!("expected a Result, got {:?}", other),
+            }
+        };
+
+        assert_eq!(
+            convert("as_i64_checked", ScriptValue::I32(-7)),
+            ScriptResult::ok(ScriptValue::I64(-7))
+        );
+        assert_eq!(
+            convert("as_u64_checked", ScriptValue::I64(i64::MAX)),
+            ScriptResult::ok(ScriptValue::U64(i64::MAX as u64))
+        );
+        assert_eq!(
+            convert("as_f64_checked", ScriptValue::U32(3)),
+            ScriptResult::ok(ScriptValue::F64(3.0))
+        );
+        assert_eq!(
+            convert("as_i32_checked", ScriptValue::F64(4.0)),
+            ScriptResult::ok(ScriptValue::I32(4))
+        );
+
+        assert!(convert("as_u32_checked", ScriptValue::I32(-1)).is_err());
+        assert!(convert("as_i32_checked", ScriptValue::I64(1 << 40)).is_err());
+        assert!(convert("as_i64_checked", ScriptValue::F64(1.5)).is_err());
+        assert!(convert("as_f32_checked", ScriptValue::F64(f64::MAX)).is_err());
+
+        let error_kind = |result: ScriptResult| match result {
+            ScriptResult::Err(ScriptValue::Object(fields)) => match fields.get("kind") {
+                Some(ScriptValue::Enum(kind)) => kind.variant.clone(),
+                other => panic!("expected an ErrorKind, got {:?}", other),
+            },
+            other => panic!("expected an Error, got {:?}", other),
+        };
+        assert_eq!(
+            error_kind(convert("as_u32_checked", ScriptValue::I32(-1))),
+            "OutOfRange"
+        );
+        assert_eq!(
+            error_kind(convert("as_i32_checked", ScriptValue::Bool(true))),
+            "InvalidInput"
+        );
+    }
+
+    #[test]
+    fn test_parse_and_format_functions() {
+        let stdlib = StdLib::new();
+        let call = |name: &str, args: &[ScriptValue]| {
+            let function = stdlib.get_function(name).unwrap();
+            (function.implementation)(args)
+        };
+        let string = |text: &str| ScriptValue::String(ScriptRc::new(ScriptString::from_str(text)));
+        let result = |value: ScriptValue| match value {
+            ScriptValue::Result(result) => (*result).clone(),
+            other => panic!("expected a Result, got {:?}", other),
+        };
+
+        assert_eq!(
+            result(call("parse_int", &[string(" -ff "), ScriptValue::I32(16)]).unwrap()),
+            ScriptResult::ok(ScriptValue::I32(-255))
+        );
+        assert_eq!(
+            result(call("parse_int", &[string("12"), ScriptValue::I32(2)]).unwrap()),
+            ScriptResult::err(error::error_object(
+                error::ErrorKind::Parse,
+                "Failed to parse '12' as a base 2 integer: invalid digit found in string",
+                None,
+                None,
+            ))
+        );
+        assert!(call("parse_int", &[string("1"), ScriptValue::I32(1)]).is_err());
+        assert_eq!(
+            result(call("parse_float", &[string("2.5")]).unwrap()),
+            ScriptResult::ok(ScriptValue::F32(2.5))
+        );
+
+        let some = ScriptValue::Option(ScriptRc::new(ScriptOption::some(string("a"))));
+        assert_eq!(call("to_string", &[some]).unwrap(), string("Some(\"a\")"));
+        assert_eq!(
+            call("to_string", &[ScriptValue::U64(7)]).unwrap(),
+            string("7")
+        );
+        assert_eq!(
+            call("to_string", &[string("plain")]).unwrap(),
+            string("plain")
+        );
+
+        let cause = error::error_object(error::ErrorKind::NotFound, "missing", Some(2), None);
+        let error = error::error_object(
+            error::ErrorKind::NotFound,
+            "Failed to read file 'a.txt'",
+            None,
+            Some(cause),
+        );
+        assert_eq!(
+            call("to_string", &[error]).unwrap(),
+            string("NotFound: Failed to read file 'a.txt': missing")
+        );
     }
 }
@@ -0,0 +1,865 @@
+//! N-dimensional arrays for Script
+//!
+//! This module provides `NdArray`, an array of `f32` with any number of
+//! dimensions, for data processing scripts and ML experiments:
+//! - Shape and stride metadata, so transposes and slices are views that
+//!   share their data instead of copying it
+//! - Element-wise arithmetic with NumPy-style broadcasting
+//! - Matrix multiplication
+//! - Reshaping, reductions and mapping with closures
+//!
+//! With the `simd` feature, element-wise operations on contiguous arrays of
+//! the same shape use SSE on x86_64.
+//!
+//! In scripts an array is an object with a `shape` array and a row-major
+//! `data` array. Numbers and flat arrays of numbers are accepted wherever an
+//! array is expected, as a scalar and a one-dimensional array respectively.
+
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::functional::execute_script_closure;
+use crate::stdlib::{expect_args, ScriptValue, ScriptVec};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+/// An n-dimensional array of `f32`
+#[derive(Debug, Clone)]
+pub struct NdArray {
+    data: Rc<Vec<f32>>,
+    shape: Vec<usize>,
+    /// Distance in `data` between consecutive indices along each axis
+    strides: Vec<usize>,
+    /// Position of the first element in `data`
+    offset: usize,
+}
+
+/// An element-wise arithmetic operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl ElementOp {
+    fn apply(self, a: f32, b: f32) -> f32 {
+        match self {
+            ElementOp::Add => a + b,
+            ElementOp::Sub => a - b,
+            ElementOp::Mul => a * b,
+            ElementOp::Div => a / b,
+        }
+    }
+}
+
+/// Row-major strides for a shape
+fn contiguous_strides(shape: &[usize]) -> Vec<usize> {
+    let mut strides = vec![1; shape.len()];
+    for axis in (0..shape.len().saturating_sub(1)).rev() {
+        strides[axis] = strides[axis + 1] * shape[axis + 1];
+    }
+    strides
+}
+
+fn format_shape(shape: &[usize]) -> String {
+    let dims: Vec<String> = shape.iter().map(|dim| dim.to_string()).collect();
+    format!("[{}]", dims.join(", "))
+}
+
+/// Call `f` with the data offset of every element, in row-major order
+fn for_each_offset(shape: &[usize], strides: &[usize], offset: usize, mut f: impl FnMut(usize)) {
+    if shape.contains(&0) {
+        return;
+    }
+
+    let mut index = vec![0; shape.len()];
+    let mut position = offset;
+    loop {
+        f(position);
+
+        // Advance the index like an odometer, last axis fastest
+        let mut axis = shape.len();
+        loop {
+            if axis == 0 {
+                return;
+            }
+            axis -= 1;
+            index[axis] += 1;
+            position += strides[axis];
+            if index[axis] < shape[axis] {
+                break;
+            }
+            position -= strides[axis] * shape[axis];
+            index[axis] = 0;
+        }
+    }
+}
+
+/// The shape two arrays broadcast to
+///
+/// Shapes are aligned at their last axis; each pair of dimensions must be
+/// equal or one of them must be 1.
+pub fn broadcast_shape(a: &[usize], b: &[usize]) -> Result<Vec<usize>, String> {
+    let ndim = a.len().max(b.len());
+    let dim = |shape: &[usize], axis: usize| {
+        let leading = ndim - shape.len();
+        if axis < leading {
+            1
+        } else {
+            shape[axis - leading]
+        }
+    };
+
+    let mut shape = vec![0; ndim];
+    for (axis, out) in shape.iter_mut().enumerate() {
+        *out = match (dim(a, axis), dim(b, axis)) {
+            (x, y) if x == y => x,
+            (1, y) => y,
+            (x, 1) => x,
+            _ => {
+                return Err(format!(
+                    "Cannot broadcast shapes {} and {}",
+                    format_shape(a),
+                    format_shape(b)
+                ))
+            }
+        };
+    }
+    Ok(shape)
+}
+
+impl NdArray {
+    /// Create an array from row-major data
+    pub fn from_vec(data: Vec<f32>, shape: Vec<usize>) -> Result<Self, String> {
+        let len: usize = shape.iter().product();
+        if data.len() != len {
+            return Err(format!(
+                "Shape {} needs {} elements, got {}",
+                format_shape(&shape),
+                len,
+                data.len()
+            ));
+        }
+        Ok(NdArray {
+            data: Rc::new(data),
+            strides: contiguous_strides(&shape),
+            shape,
+            offset: 0,
+        })
+    }
+
+    /// Create an array with every element set to `value`
+    pub fn filled(shape: Vec<usize>, value: f32) -> Self {
+        let len = shape.iter().product();
+        NdArray {
+            data: Rc::new(vec![value; len]),
+            strides: contiguous_strides(&shape),
+            shape,
+            offset: 0,
+        }
+    }
+
+    pub fn zeros(shape: Vec<usize>) -> Self {
+        Self::filled(shape, 0.0)
+    }
+
+    /// Create a zero-dimensional array holding one value
+    pub fn scalar(value: f32) -> Self {
+        Self::filled(Vec::new(), value)
+    }
+
+    /// Create a one-dimensional array of `0, 1, ..., n - 1`
+    pub fn arange(n: usize) -> Self {
+        NdArray {
+            data: Rc::new((0..n).map(|i| i as f32).collect()),
+            shape: vec![n],
+            strides: vec![1],
+            offset: 0,
+        }
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn strides(&self) -> &[usize] {
+        &self.strides
+    }
+
+    pub fn ndim(&self) -> usize {
+        self.shape.len()
+    }
+
+    /// Number of elements
+    pub fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the elements are stored in row-major order without gaps
+    pub fn is_contiguous(&self) -> bool {
+        self.strides == contiguous_strides(&self.shape)
+    }
+
+    /// The elements as a slice, if the array is contiguous
+    fn as_slice(&self) -> Option<&[f32]> {
+        if self.is_contiguous() {
+            Some(&self.data[self.offset..self.offset + self.len()])
+        } else {
+            None
+        }
+    }
+
+    /// The elements in row-major order
+    pub fn to_vec(&self) -> Vec<f32> {
+        if let Some(slice) = self.as_slice() {
+            return slice.to_vec();
+        }
+        let mut values = Vec::with_capacity(self.len());
+        for_each_offset(&self.shape, &self.strides, self.offset, |i| {
+            values.push(self.data[i])
+        });
+        values
+    }
+
+    /// Get the element at a multi-dimensional index
+    pub fn get(&self, index: &[usize]) -> Option<f32> {
+        if index.len() != self.shape.len() {
+            return None;
+        }
+        let mut position = self.offset;
+        for ((&i, &dim), &stride) in index.iter().zip(&self.shape).zip(&self.strides) {
+            if i >= dim {
+                return None;
+            }
+            position += i * stride;
+        }
+        Some(self.data[position])
+    }
+
+    /// The same elements arranged in a different shape
+    pub fn reshape(&self, shape: Vec<usize>) -> Result<Self, String> {
+        let len: usize = shape.iter().product();
+        if len != self.len() {
+            return Err(format!(
+                "Cannot reshape {} into {}",
+                format_shape(&self.shape),
+                format_shape(&shape)
+            ));
+        }
+        if self.is_contiguous() {
+            return Ok(NdArray {
+                data: Rc::clone(&self.data),
+                strides: contiguous_strides(&shape),
+                shape,
+                offset: self.offset,
+            });
+        }
+        Self::from_vec(self.to_vec(), shape)
+    }
+
+    /// A view with the axes reversed
+    pub fn transpose(&self) -> Self {
+        NdArray {
+            data: Rc::clone(&self.data),
+            shape: self.shape.iter().rev().copied().collect(),
+            strides: self.strides.iter().rev().copied().collect(),
+            offset: self.offset,
+        }
+    }
+
+    /// A view of indices `start..end` along one axis
+    pub fn slice(&self, axis: usize, start: usize, end: usize) -> Result<Self, String> {
+        let dim = *self.shape.get(axis).ok_or_else(|| {
+            format!(
+                "Axis {} is out of range for an array with {} dimensions",
+                axis,
+                self.ndim()
+            )
+        })?;
+        if start > end || end > dim {
+            return Err(format!(
+                "Slice {}..{} is out of range for axis {} of length {}",
+                start, end, axis, dim
+            ));
+        }
+
+        let mut shape = self.shape.clone();
+        shape[axis] = end - start;
+        Ok(NdArray {
+            data: Rc::clone(&self.data),
+            shape,
+            strides: self.strides.clone(),
+            offset: self.offset + start * self.strides[axis],
+        })
+    }
+
+    /// Apply a function to every element
+    pub fn map(&self, f: impl FnMut(f32) -> f32) -> Self {
+        NdArray {
+            data: Rc::new(self.to_vec().into_iter().map(f).collect()),
+            strides: contiguous_strides(&self.shape),
+            shape: self.shape.clone(),
+            offset: 0,
+        }
+    }
+
+    /// Strides for reading this array as if it had the broadcast `shape`
+    fn broadcast_strides(&self, shape: &[usize]) -> Vec<usize> {
+        let leading = shape.len() - self.shape.len();
+        (0..shape.len())
+            .map(|axis| {
+                if axis < leading || self.shape[axis - leading] != shape[axis] {
+                    0
+                } else {
+                    self.strides[axis - leading]
+                }
+            })
+            .collect()
+    }
+
+    /// Combine two arrays element by element, broadcasting their shapes
+    pub fn zip_with(&self, other: &NdArray, op: ElementOp) -> Result<Self, String> {
+        if self.shape == other.shape {
+            if let (Some(a), Some(b)) = (self.as_slice(), other.as_slice()) {
+                return Self::from_vec(zip_slices(a, b, op), self.shape.clone());
+            }
+        }
+
+        let shape = broadcast_shape(&self.shape, &other.shape)?;
+        let mut a = Vec::with_capacity(shape.iter().product());
+        for_each_offset(&shape, &self.broadcast_strides(&shape), self.offset, |i| {
+            a.push(self.data[i])
+        });
+        let mut values = Vec::with_capacity(a.len());
+        let mut a = a.into_iter();
+        for_each_offset(
+            &shape,
+            &other.broadcast_strides(&shape),
+            other.offset,
+            |i| {
+                if let Some(x) = a.next() {
+                    values.push(op.apply(x, other.data[i]));
+                }
+            },
+        );
+        Self::from_vec(values, shape)
+    }
+
+    pub fn add(&self, other: &NdArray) -> Result<Self, String> {
+        self.zip_with(other, ElementOp::Add)
+    }
+
+    pub fn sub(&self, other: &NdArray) -> Result<Self, String> {
+        self.zip_with(other, ElementOp::Sub)
+    }
+
+    pub fn mul(&self, other: &NdArray) -> Result<Self, String> {
+        self.zip_with(other, ElementOp::Mul)
+    }
+
+    pub fn div(&self, other: &NdArray) -> Result<Self, String> {
+        self.zip_with(other, ElementOp::Div)
+    }
+
+    /// Matrix product of two 2-D arrays
+    pub fn matmul(&self, other: &NdArray) -> Result<Self, String> {
+        let (n, k, m) = match (self.shape.as_slice(), other.shape.as_slice()) {
+            (&[n, k], &[k2, m]) if k == k2 => (n, k, m),
+            _ => {
+                return Err(format!(
+                    "Cannot multiply matrices of shapes {} and {}",
+                    format_shape(&self.shape),
+                    format_shape(&other.shape)
+                ))
+            }
+        };
+
+        let a = self.to_vec();
+        let b = other.to_vec();
+        let mut values = vec![0.0; n * m];
+        // i-k-j order walks both `b` and the output row sequentially
+        for i in 0..n {
+            let row = &mut values[i * m..(i + 1) * m];
+            for p in 0..k {
+                let scale = a[i * k + p];
+                for (out, &x) in row.iter_mut().zip(&b[p * m..(p + 1) * m]) {
+                    *out += scale * x;
+                }
+            }
+        }
+        Self::from_vec(values, vec![n, m])
+    }
+
+    /// Sum of all elements
+    pub fn sum(&self) -> f32 {
+        self.to_vec().iter().sum()
+    }
+
+    /// Sum along one axis, removing it from the shape
+    pub fn sum_axis(&self, axis: usize) -> Result<Self, String> {
+        if axis >= self.ndim() {
+            return Err(format!(
+                "Axis {} is out of range for an array with {} dimensions",
+                axis,
+                self.ndim()
+            ));
+        }
+
+        let mut shape = self.shape.clone();
+        shape.remove(axis);
+        let mut total = NdArray::zeros(shape.clone());
+        for i in 0..self.shape[axis] {
+            let layer = self.slice(axis, i, i + 1)?.reshape(shape.clone())?;
+            total = total.add(&layer)?;
+        }
+        Ok(total)
+    }
+}
+
+impl PartialEq for NdArray {
+    fn eq(&self, other: &Self) -> bool {
+        self.shape == other.shape && self.to_vec() == other.to_vec()
+    }
+}
+
+impl fmt::Display for NdArray {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ndarray{} {:?}",
+            format_shape(&self.shape),
+            self.to_vec()
+        )
+    }
+}
+
+/// Combine two equal-length slices element by element
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+fn zip_slices(a: &[f32], b: &[f32], op: ElementOp) -> Vec<f32> {
+    a.iter().zip(b).map(|(&x, &y)| op.apply(x, y)).collect()
+}
+
+/// Combine two equal-length slices element by element, four lanes at a time
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+fn zip_slices(a: &[f32], b: &[f32], op: ElementOp) -> Vec<f32> {
+    use std::arch::x86_64::*;
+
+    let lanes = a.len() / 4 * 4;
+    let mut values = Vec::with_capacity(a.len());
+    for (x, y) in a[..lanes].chunks_exact(4).zip(b[..lanes].chunks_exact(4)) {
+        let mut out = [0.0f32; 4];
+        // SAFETY: SSE is part of the x86_64 baseline, and each chunk holds
+        // exactly four f32 values for the unaligned loads and store
+        unsafe {
+            let x = _mm_loadu_ps(x.as_ptr());
+            let y = _mm_loadu_ps(y.as_ptr());
+            let result = match op {
+                ElementOp::Add => _mm_add_ps(x, y),
+                ElementOp::Sub => _mm_sub_ps(x, y),
+                ElementOp::Mul => _mm_mul_ps(x, y),
+                ElementOp::Div => _mm_div_ps(x, y),
+            };
+            _mm_storeu_ps(out.as_mut_ptr(), result);
+        }
+        values.extend_from_slice(&out);
+    }
+    values.extend(
+        a[lanes..]
+            .iter()
+            .zip(&b[lanes..])
+            .map(|(&x, &y)| op.apply(x, y)),
+    );
+    values
+}
+
+fn invalid(message: impl Into<String>) -> RuntimeError {
+    RuntimeError::InvalidOperation(message.into())
+}
+
+fn read_array(value: &ScriptValue) -> RuntimeResult<Vec<ScriptValue>> {
+    match value {
+        ScriptValue::Array(items) => items.to_vec().map_err(|e| invalid(e.to_string())),
+        _ => Err(invalid("Expected an array")),
+    }
+}
+
+/// Read a shape or index: an array of non-negative integers
+fn read_dims(value: &ScriptValue) -> RuntimeResult<Vec<usize>> {
+    read_array(value)?
+        .iter()
+        .map(|dim| match dim.to_i32()? {
+            dim if dim >= 0 => Ok(dim as usize),
+            dim => Err(invalid(format!(
+                "Dimensions cannot be negative, got {}",
+                dim
+            ))),
+        })
+        .collect()
+}
+
+fn read_index(value: &ScriptValue, what: &str) -> RuntimeResult<usize> {
+    match value.to_i32()? {
+        n if n >= 0 => Ok(n as usize),
+        n => Err(invalid(format!("{} cannot be negative, got {}", what, n))),
+    }
+}
+
+/// Convert a script value to an array
+pub fn from_value(value: &ScriptValue) -> RuntimeResult<NdArray> {
+    match value {
+        ScriptValue::I32(_) | ScriptValue::F32(_) => Ok(NdArray::scalar(value.to_f32()?)),
+        ScriptValue::Array(_) => {
+            let data = read_array(value)?
+                .iter()
+                .map(ScriptValue::to_f32)
+                .collect::<RuntimeResult<Vec<_>>>()?;
+            let len = data.len();
+            NdArray::from_vec(data, vec![len]).map_err(invalid)
+        }
+        ScriptValue::Object(fields) => match (fields.get("shape"), fields.get("data")) {
+            (Some(shape), Some(data)) => {
+                let shape = read_dims(shape)?;
+                let data = read_array(data)?
+                    .iter()
+                    .map(ScriptValue::to_f32)
+                    .collect::<RuntimeResult<Vec<_>>>()?;
+                NdArray::from_vec(data, shape).map_err(invalid)
+            }
+            _ => Err(invalid("Expected an ndarray with shape and data")),
+        },
+        _ => Err(invalid(format!(
+            "Expected an ndarray, got {:?}",
+            value.get_type()
+        ))),
+    }
+}
+
+/// Convert an array to a script object with `shape` and `data`
+pub fn to_value(array: &NdArray) -> ScriptValue {
+    let shape = array
+        .shape()
+        .iter()
+        .map(|&dim| ScriptValue::I32(dim as i32))
+        .collect();
+    let data = array.to_vec().into_iter().map(ScriptValue::F32).collect();
+
+    let mut fields = HashMap::new();
+    fields.insert(
+        "shape".to_string(),
+        ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(shape))),
+    );
+    fields.insert(
+        "data".to_string(),
+        ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(data))),
+    );
+    ScriptValue::Object(ScriptRc::new(fields))
+}
+
+/// Create an array from row-major data and a shape
+pub fn ndarray_new_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "ndarray")?;
+
+    let data = from_value(&args[0])?.to_vec();
+    let array = NdArray::from_vec(data, read_dims(&args[1])?).map_err(invalid)?;
+    Ok(to_value(&array))
+}
+
+/// Create an array of zeros
+pub fn ndarray_zeros_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "ndarray_zeros")?;
+    Ok(to_value(&NdArray::zeros(read_dims(&args[0])?)))
+}
+
+/// Create an array of ones
+pub fn ndarray_ones_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "ndarray_ones")?;
+    Ok(to_value(&NdArray::filled(read_dims(&args[0])?, 1.0)))
+}
+
+/// Create the array `0, 1, ..., n - 1`
+pub fn ndarray_arange_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "ndarray_arange")?;
+    Ok(to_value(&NdArray::arange(read_index(&args[0], "Length")?)))
+}
+
+/// Get an array's shape
+pub fn ndarray_shape_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "ndarray_shape")?;
+
+    let shape = from_value(&args[0])?
+        .shape()
+        .iter()
+        .map(|&dim| ScriptValue::I32(dim as i32))
+        .collect();
+    Ok(ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(
+        shape,
+    ))))
+}
+
+/// Get the element at an index
+pub fn ndarray_get_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "ndarray_get")?;
+
+    let array = from_value(&args[0])?;
+    let index = read_dims(&args[1])?;
+    array.get(&index).map(ScriptValue::F32).ok_or_else(|| {
+        invalid(format!(
+            "Index {} is out of bounds for shape {}",
+            format_shape(&index),
+            format_shape(array.shape())
+        ))
+    })
+}
+
+/// Arrange an array's elements in a new shape
+pub fn ndarray_reshape_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "ndarray_reshape")?;
+
+    let array = from_value(&args[0])?
+        .reshape(read_dims(&args[1])?)
+        .map_err(invalid)?;
+    Ok(to_value(&array))
+}
+
+/// Reverse an array's axes
+pub fn ndarray_transpose_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "ndarray_transpose")?;
+    Ok(to_value(&from_value(&args[0])?.transpose()))
+}
+
+/// Take indices `start..end` along an axis
+pub fn ndarray_slice_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 4, "ndarray_slice")?;
+
+    let array = from_value(&args[0])?
+        .slice(
+            read_index(&args[1], "Axis")?,
+            read_index(&args[2], "Slice start")?,
+            read_index(&args[3], "Slice end")?,
+        )
+        .map_err(invalid)?;
+    Ok(to_value(&array))
+}
+
+fn element_wise(args: &[ScriptValue], op: ElementOp, name: &str) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, name)?;
+
+    let result = from_value(&args[0])?
+        .zip_with(&from_value(&args[1])?, op)
+        .map_err(invalid)?;
+    Ok(to_value(&result))
+}
+
+/// Add arrays element by element
+pub fn ndarray_add_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    element_wise(args, ElementOp::Add, "ndarray_add")
+}
+
+/// Subtract arrays element by element
+pub fn ndarray_sub_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    element_wise(args, ElementOp::Sub, "ndarray_sub")
+}
+
+/// Multiply arrays element by element
+pub fn ndarray_mul_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    element_wise(args, ElementOp::Mul, "ndarray_mul")
+}
+
+/// Divide arrays element by element
+pub fn ndarray_div_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    element_wise(args, ElementOp::Div, "ndarray_div")
+}
+
+/// Multiply two matrices
+pub fn ndarray_matmul_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "ndarray_matmul")?;
+
+    let result = from_value(&args[0])?
+        .matmul(&from_value(&args[1])?)
+        .map_err(invalid)?;
+    Ok(to_value(&result))
+}
+
+/// Sum all elements
+pub fn ndarray_sum_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "ndarray_sum")?;
+    Ok(ScriptValue::F32(from_value(&args[0])?.sum()))
+}
+
+/// Sum along an axis
+pub fn ndarray_sum_axis_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "ndarray_sum_axis")?;
+
+    let result = from_value(&args[0])?
+        .sum_axis(read_index(&args[1], "Axis")?)
+        .map_err(invalid)?;
+    Ok(to_value(&result))
+}
+
+/// Apply a closure to every element
+pub fn ndarray_map_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "ndarray_map")?;
+    if !matches!(args[1], ScriptValue::Closure(_)) {
+        return Err(invalid("ndarray_map expects a closure"));
+    }
+
+    let array = from_value(&args[0])?;
+    let values = array
+        .to_vec()
+        .into_iter()
+        .map(|x| {
+            execute_script_closure(&args[1], &[ScriptValue::F32(x)])
+                .map_err(|e| invalid(e.to_string()))?
+                .to_f32()
+        })
+        .collect::<RuntimeResult<Vec<_>>>()?;
+    let result = NdArray::from_vec(values, array.shape().to_vec()).map_err(invalid)?;
+    Ok(to_value(&result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(rows: usize, cols: usize) -> NdArray {
+        NdArray::arange(rows * cols)
+            .reshape(vec![rows, cols])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_views_share_data() {
+        let m = matrix(2, 3);
+        assert_eq!(m.strides(), &[3, 1]);
+
+        let t = m.transpose();
+        assert_eq!(t.shape(), &[3, 2]);
+        assert!(!t.is_contiguous());
+        assert_eq!(t.get(&[2, 1]), Some(5.0));
+        assert_eq!(t.to_vec(), vec![0.0, 3.0, 1.0, 4.0, 2.0, 5.0]);
+
+        let column = m.slice(1, 1, 2).unwrap();
+        assert_eq!(column.shape(), &[2, 1]);
+        assert_eq!(column.to_vec(), vec![1.0, 4.0]);
+        assert!(Rc::ptr_eq(&column.data, &m.data));
+
+        assert!(m.slice(1, 2, 4).is_err());
+        assert!(m.slice(2, 0, 1).is_err());
+        assert_eq!(m.get(&[2, 0]), None);
+    }
+
+    #[test]
+    fn test_reshape() {
+        let m = matrix(2, 3);
+        let flat = m.transpose().reshape(vec![6]).unwrap();
+        assert_eq!(flat.to_vec(), vec![0.0, 3.0, 1.0, 4.0, 2.0, 5.0]);
+        assert!(m.reshape(vec![4]).is_err());
+    }
+
+    #[test]
+    fn test_broadcasting() {
+        assert_eq!(broadcast_shape(&[2, 3], &[3]), Ok(vec![2, 3]));
+        assert_eq!(broadcast_shape(&[4, 1], &[1, 5]), Ok(vec![4, 5]));
+        assert!(broadcast_shape(&[2, 3], &[2]).is_err());
+
+        let m = matrix(2, 3);
+        let row = NdArray::from_vec(vec![10.0, 20.0, 30.0], vec![3]).unwrap();
+        assert_eq!(
+            m.add(&row).unwrap().to_vec(),
+            vec![10.0, 21.0, 32.0, 13.0, 24.0, 35.0]
+        );
+
+        let column = NdArray::from_vec(vec![1.0, 2.0], vec![2, 1]).unwrap();
+        assert_eq!(
+            m.mul(&column).unwrap().to_vec(),
+            vec![0.0, 1.0, 2.0, 6.0, 8.0, 10.0]
+        );
+
+        let halved = m.div(&NdArray::scalar(2.0)).unwrap();
+        assert_eq!(halved.shape(), &[2, 3]);
+        assert_eq!(halved.sum(), 7.5);
+
+        // Views broadcast too
+        let diff = m.transpose().sub(&m.transpose()).unwrap();
+        assert_eq!(diff, NdArray::zeros(vec![3, 2]));
+    }
+
+    #[test]
+    fn test_element_wise_same_shape() {
+        // Long enough to cover both the four-lane and the remainder paths
+        let a = NdArray::arange(7);
+        let b = NdArray::filled(vec![7], 2.0);
+        assert_eq!(
+            a.mul(&b).unwrap().to_vec(),
+            vec![0.0, 2.0, 4.0, 6.0, 8.0, 10.0, 12.0]
+        );
+        assert_eq!(a.sub(&b).unwrap().get(&[6]), Some(4.0));
+    }
+
+    #[test]
+    fn test_matmul() {
+        let a = matrix(2, 3);
+        let b = matrix(3, 2);
+        let product = a.matmul(&b).unwrap();
+        assert_eq!(product.shape(), &[2, 2]);
+        assert_eq!(product.to_vec(), vec![10.0, 13.0, 28.0, 40.0]);
+
+        // Transposed views multiply like their copies
+        let gram = a.matmul(&a.transpose()).unwrap();
+        assert_eq!(gram.to_vec(), vec![5.0, 14.0, 14.0, 50.0]);
+
+        assert!(a.matmul(&a).is_err());
+    }
+
+    #[test]
+    fn test_sum_axis() {
+        let m = matrix(2, 3);
+        assert_eq!(m.sum_axis(0).unwrap().to_vec(), vec![3.0, 5.0, 7.0]);
+        assert_eq!(m.sum_axis(1).unwrap().to_vec(), vec![3.0, 12.0]);
+        assert!(m.sum_axis(2).is_err());
+    }
+
+    #[test]
+    fn test_ndarray_script_functions() {
+        let shape = |dims: &[i32]| {
+            ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(
+                dims.iter().map(|&d| ScriptValue::I32(d)).collect(),
+            )))
+        };
+
+        let a = ndarray_arange_impl(&[ScriptValue::I32(6)]).unwrap();
+        let m = ndarray_reshape_impl(&[a, shape(&[2, 3])]).unwrap();
+        let shifted = ndarray_add_impl(&[m.clone(), ScriptValue::I32(1)]).unwrap();
+        assert_eq!(from_value(&shifted).unwrap().sum(), 21.0);
+
+        let t = ndarray_transpose_impl(&[m.clone()]).unwrap();
+        let product = ndarray_matmul_impl(&[m.clone(), t]).unwrap();
+        match ndarray_get_impl(&[product.clone(), shape(&[1, 1])]).unwrap() {
+            ScriptValue::F32(x) => assert_eq!(x, 50.0),
+            other => panic!("expected f32, got {:?}", other),
+        }
+        match ndarray_shape_impl(&[product]).unwrap() {
+            ScriptValue::Array(dims) => assert_eq!(dims.len(), 2),
+            other => panic!("expected shape array, got {:?}", other),
+        }
+
+        let row = ndarray_slice_impl(&[
+            m.clone(),
+            ScriptValue::I32(0),
+            ScriptValue::I32(1),
+            ScriptValue::I32(2),
+        ])
+        .unwrap();
+        assert_eq!(from_value(&row).unwrap().to_vec(), vec![3.0, 4.0, 5.0]);
+
+        assert!(ndarray_new_impl(&[ScriptValue::I32(1), shape(&[2])]).is_err());
+        assert!(ndarray_get_impl(&[m.clone(), shape(&[2, 0])]).is_err());
+        assert!(ndarray_matmul_impl(&[m.clone(), m.clone()]).is_err());
+        assert!(ndarray_map_impl(&[m, ScriptValue::Unit]).is_err());
+    }
+}
@@ -6,7 +6,7 @@
 //! All functions are designed to be called from Script code and handle
 //! errors using the Script Result type.
 
-use crate::runtime::{RuntimeError, ScriptRc};
+use crate::runtime::{ResourceKind, RuntimeError, ScriptRc, TrackedResource};
 use crate::stdlib::error::{io_error_from_std, IoError, IoErrorKind, ScriptError};
 use crate::stdlib::{ScriptResult, ScriptString, ScriptValue};
 use std::io::{Read, Write};
@@ -16,27 +16,34 @@ use std::time::Duration;
 /// TCP connection handle for Script
 pub struct ScriptTcpStream {
     stream: TcpStream,
+    _resource: TrackedResource,
 }
 
 impl ScriptTcpStream {
     /// Create a new TCP stream from a standard TcpStream
+    #[track_caller]
     pub fn new(stream: TcpStream) -> Self {
-        ScriptTcpStream { stream }
+        let _resource = TrackedResource::new(ResourceKind::Socket, || {
+            describe_socket("TCP stream to", stream.peer_addr())
+        });
+        ScriptTcpStream { stream, _resource }
     }
 
     /// Connect to a TCP server
+    #[track_caller]
     pub fn connect(addr: &str) -> Result<Self, IoError> {
         match TcpStream::connect(addr) {
             Ok(stream) => Ok(ScriptTcpStream::new(stream)),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to connect to '{}': {}", addr, io_err.message);
+                let io_err =
+                    io_error_from_std(e).context(format!("Failed to connect to '{}'", addr));
                 Err(io_err)
             }
         }
     }
 
     /// Connect with a timeout
+    #[track_caller]
     pub fn connect_timeout(addr: &str, timeout_ms: u64) -> Result<Self, IoError> {
         let timeout = Duration::from_millis(timeout_ms);
 
@@ -54,13 +61,13 @@ impl ScriptTcpStream {
                                 message: format!("Failed to resolve address: {}", addr),
                                 kind: IoErrorKind::InvalidInput,
                                 code: None,
+                                source: None,
                             });
                         }
                     }
                     Err(e) => {
-                        let mut io_err = io_error_from_std(e);
-                        io_err.message =
-                            format!("Failed to resolve address '{}': {}", addr, io_err.message);
+                        let io_err = io_error_from_std(e)
+                            .context(format!("Failed to resolve address '{}'", addr));
                         return Err(io_err);
                     }
                 }
@@ -70,11 +77,8 @@ impl ScriptTcpStream {
         match TcpStream::connect_timeout(&socket_addr, timeout) {
             Ok(stream) => Ok(ScriptTcpStream::new(stream)),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!(
-                    "Failed to connect to '{}' with timeout: {}",
-                    addr, io_err.message
-                );
+                let io_err = io_error_from_std(e)
+                    .context(format!("Failed to connect to '{}' with timeout", addr));
                 Err(io_err)
             }
         }
@@ -89,8 +93,7 @@ impl ScriptTcpStream {
                 Ok(buffer)
             }
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to read from TCP stream: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to read from TCP stream");
                 Err(io_err)
             }
         }
@@ -116,8 +119,7 @@ impl ScriptTcpStream {
                 Ok(line)
             }
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to read line from TCP stream: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to read line from TCP stream");
                 Err(io_err)
             }
         }
@@ -128,15 +130,13 @@ impl ScriptTcpStream {
         match self.stream.write(data) {
             Ok(n) => {
                 if let Err(e) = self.stream.flush() {
-                    let mut io_err = io_error_from_std(e);
-                    io_err.message = format!("Failed to flush TCP stream: {}", io_err.message);
+                    let io_err = io_error_from_std(e).context("Failed to flush TCP stream");
                     return Err(io_err);
                 }
                 Ok(n)
             }
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to write to TCP stream: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to write to TCP stream");
                 Err(io_err)
             }
         }
@@ -153,8 +153,7 @@ impl ScriptTcpStream {
         match self.stream.set_read_timeout(timeout) {
             Ok(()) => Ok(()),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to set read timeout: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to set read timeout");
                 Err(io_err)
             }
         }
@@ -166,8 +165,7 @@ impl ScriptTcpStream {
         match self.stream.set_write_timeout(timeout) {
             Ok(()) => Ok(()),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to set write timeout: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to set write timeout");
                 Err(io_err)
             }
         }
@@ -178,8 +176,7 @@ impl ScriptTcpStream {
         match self.stream.local_addr() {
             Ok(addr) => Ok(addr.to_string()),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to get local address: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to get local address");
                 Err(io_err)
             }
         }
@@ -190,8 +187,7 @@ impl ScriptTcpStream {
         match self.stream.peer_addr() {
             Ok(addr) => Ok(addr.to_string()),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to get peer address: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to get peer address");
                 Err(io_err)
             }
         }
@@ -213,6 +209,7 @@ impl ScriptTcpStream {
                     ),
                     kind: IoErrorKind::InvalidInput,
                     code: None,
+                    source: None,
                 });
             }
         };
@@ -220,8 +217,7 @@ impl ScriptTcpStream {
         match self.stream.shutdown(shutdown_type) {
             Ok(()) => Ok(()),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to shutdown connection: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to shutdown connection");
                 Err(io_err)
             }
         }
@@ -231,33 +227,41 @@ impl ScriptTcpStream {
 /// TCP listener handle for Script
 pub struct ScriptTcpListener {
     listener: TcpListener,
+    _resource: TrackedResource,
 }
 
 impl ScriptTcpListener {
     /// Create a new TCP listener
+    #[track_caller]
     pub fn new(listener: TcpListener) -> Self {
-        ScriptTcpListener { listener }
+        let _resource = TrackedResource::new(ResourceKind::Socket, || {
+            describe_socket("TCP listener on", listener.local_addr())
+        });
+        ScriptTcpListener {
+            listener,
+            _resource,
+        }
     }
 
     /// Bind to an address and start listening
+    #[track_caller]
     pub fn bind(addr: &str) -> Result<Self, IoError> {
         match TcpListener::bind(addr) {
             Ok(listener) => Ok(ScriptTcpListener::new(listener)),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to bind to '{}': {}", addr, io_err.message);
+                let io_err = io_error_from_std(e).context(format!("Failed to bind to '{}'", addr));
                 Err(io_err)
             }
         }
     }
 
     /// Accept a new connection
+    #[track_caller]
     pub fn accept(&self) -> Result<(ScriptTcpStream, String), IoError> {
         match self.listener.accept() {
             Ok((stream, addr)) => Ok((ScriptTcpStream::new(stream), addr.to_string())),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to accept connection: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to accept connection");
                 Err(io_err)
             }
         }
@@ -268,8 +272,7 @@ impl ScriptTcpListener {
         match self.listener.local_addr() {
             Ok(addr) => Ok(addr.to_string()),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to get local address: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to get local address");
                 Err(io_err)
             }
         }
@@ -279,24 +282,27 @@ impl ScriptTcpListener {
 /// UDP socket handle for Script
 pub struct ScriptUdpSocket {
     socket: UdpSocket,
+    _resource: TrackedResource,
 }
 
 impl ScriptUdpSocket {
     /// Create a new UDP socket
+    #[track_caller]
     pub fn new(socket: UdpSocket) -> Self {
-        ScriptUdpSocket { socket }
+        let _resource = TrackedResource::new(ResourceKind::Socket, || {
+            describe_socket("UDP socket on", socket.local_addr())
+        });
+        ScriptUdpSocket { socket, _resource }
     }
 
     /// Bind to an address
+    #[track_caller]
     pub fn bind(addr: &str) -> Result<Self, IoError> {
         match UdpSocket::bind(addr) {
             Ok(socket) => Ok(ScriptUdpSocket::new(socket)),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!(
-                    "Failed to bind UDP socket to '{}': {}",
-                    addr, io_err.message
-                );
+                let io_err = io_error_from_std(e)
+                    .context(format!("Failed to bind UDP socket to '{}'", addr));
                 Err(io_err)
             }
         }
@@ -307,11 +313,8 @@ impl ScriptUdpSocket {
         match self.socket.connect(addr) {
             Ok(()) => Ok(()),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!(
-                    "Failed to connect UDP socket to '{}': {}",
-                    addr, io_err.message
-                );
+                let io_err = io_error_from_std(e)
+                    .context(format!("Failed to connect UDP socket to '{}'", addr));
                 Err(io_err)
             }
         }
@@ -322,8 +325,7 @@ impl ScriptUdpSocket {
         match self.socket.send(data) {
             Ok(n) => Ok(n),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to send UDP data: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to send UDP data");
                 Err(io_err)
             }
         }
@@ -338,8 +340,7 @@ impl ScriptUdpSocket {
                 Ok(buffer)
             }
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to receive UDP data: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to receive UDP data");
                 Err(io_err)
             }
         }
@@ -350,9 +351,8 @@ impl ScriptUdpSocket {
         match self.socket.send_to(data, addr) {
             Ok(n) => Ok(n),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message =
-                    format!("Failed to send UDP data to '{}': {}", addr, io_err.message);
+                let io_err =
+                    io_error_from_std(e).context(format!("Failed to send UDP data to '{}'", addr));
                 Err(io_err)
             }
         }
@@ -367,8 +367,7 @@ impl ScriptUdpSocket {
                 Ok((buffer, addr.to_string()))
             }
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to receive UDP data: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to receive UDP data");
                 Err(io_err)
             }
         }
@@ -379,8 +378,7 @@ impl ScriptUdpSocket {
         match self.socket.local_addr() {
             Ok(addr) => Ok(addr.to_string()),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to get local address: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to get local address");
                 Err(io_err)
             }
         }
@@ -392,8 +390,7 @@ impl ScriptUdpSocket {
         match self.socket.set_read_timeout(timeout) {
             Ok(()) => Ok(()),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to set read timeout: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to set read timeout");
                 Err(io_err)
             }
         }
@@ -405,8 +402,7 @@ impl ScriptUdpSocket {
         match self.socket.set_write_timeout(timeout) {
             Ok(()) => Ok(()),
             Err(e) => {
-                let mut io_err = io_error_from_std(e);
-                io_err.message = format!("Failed to set write timeout: {}", io_err.message);
+                let io_err = io_error_from_std(e).context("Failed to set write timeout");
                 Err(io_err)
             }
         }
@@ -415,6 +411,14 @@ impl ScriptUdpSocket {
 
 // Implementation functions for stdlib registry
 
+/// Description of a socket for leak reports
+fn describe_socket(what: &str, addr: std::io::Result<std::net::SocketAddr>) -> String {
+    match addr {
+        Ok(addr) => format!("{} {}", what, addr),
+        Err(_) => format!("{} unknown address", what),
+    }
+}
+
 /// TCP connect implementation
 pub(crate) fn tcp_connect_impl(args: &[ScriptValue]) -> Result<ScriptValue, RuntimeError> {
     if args.len() != 1 {
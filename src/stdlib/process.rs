@@ -0,0 +1,65 @@
+//! Shutdown hooks for Script
+//!
+//! - `on_shutdown(f)` runs the closure `f` when the program exits, whether
+//!   it returns normally or is stopped by SIGINT or SIGTERM. Hooks run most
+//!   recently registered first, after async tasks have been cancelled.
+//! - `shutdown_requested()` is true once a shutdown signal has arrived, so
+//!   long-running loops can stop at a convenient point.
+//!
+//! An error from a hook is reported on stderr and the remaining hooks still
+//! run. See `runtime::shutdown` for the exit codes.
+
+use crate::runtime::{shutdown, Result as RuntimeResult, RuntimeError};
+use crate::stdlib::functional::execute_script_closure;
+use crate::stdlib::ScriptValue;
+
+/// Run a closure when the program shuts down
+pub fn on_shutdown_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "on_shutdown expects 1 argument, got {}",
+            args.len()
+        )));
+    }
+    if !matches!(args[0], ScriptValue::Closure(_)) {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "Expected a closure, got {:?}",
+            args[0].get_type()
+        )));
+    }
+
+    let closure = args[0].clone();
+    shutdown::on_shutdown(move || {
+        if let Err(error) = execute_script_closure(&closure, &[]) {
+            eprintln!("Error in shutdown hook: {}", error);
+        }
+    });
+    Ok(ScriptValue::Unit)
+}
+
+/// Whether SIGINT or SIGTERM has started a shutdown
+pub fn shutdown_requested_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if !args.is_empty() {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "shutdown_requested expects 0 arguments, got {}",
+            args.len()
+        )));
+    }
+
+    Ok(ScriptValue::Bool(shutdown::shutdown_requested()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_on_shutdown_requires_a_closure() {
+        assert!(on_shutdown_impl(&[]).is_err());
+        assert!(on_shutdown_impl(&[ScriptValue::I32(1)]).is_err());
+        assert_eq!(
+            shutdown_requested_impl(&[]).unwrap(),
+            ScriptValue::Bool(false)
+        );
+    }
+}
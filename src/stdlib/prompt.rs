@@ -0,0 +1,378 @@
+//! Interactive prompts for command-line scripts
+//!
+//! - `prompt(message)` asks for a line of text
+//! - `prompt_password(message)` asks for a line without echoing it
+//! - `confirm(message)` asks a yes/no question; `confirm(message, default)`
+//!   also accepts an empty answer as `default`
+//! - `prompt_select(options)` asks for one of a list and returns its index;
+//!   `prompt_select(options, message)` shows a message above the list. The
+//!   name keeps it apart from the channel `select`
+//!
+//! `prompt` and `prompt_password` take an optional closure as a second
+//! argument that validates the answer. Returning `true` or `Ok` accepts
+//! it, `false` rejects it, and `Err(message)` rejects it with that message.
+//! A rejected answer is asked for again.
+//!
+//! On a terminal the prompts are interactive: passwords are hidden and the
+//! arrow keys move through `prompt_select`'s options. When stdin is not a
+//! terminal, as when another program pipes answers in, each prompt reads a
+//! plain line instead. Prompts are written to stderr so they never mix with
+//! the script's own output. Every function returns a Result, which is an
+//! `UnexpectedEof` error when input runs out.
+
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::error::{io_error_from_std, ScriptError};
+use crate::stdlib::functional::execute_script_closure;
+use crate::stdlib::inspect::inspect;
+use crate::stdlib::{string_arg, string_value, ScriptResult, ScriptValue};
+use dialoguer::{Confirm, Input, Password, Select};
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// Message shown when a validation closure returns `false`
+const INVALID_ANSWER: &str = "Invalid answer, please try again";
+
+/// Ask for a line of text, asking again until `validate` accepts it
+pub fn prompt_lines(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    message: &str,
+    validate: impl Fn(&str) -> Result<(), String>,
+) -> io::Result<String> {
+    loop {
+        write!(output, "{}: ", message)?;
+        output.flush()?;
+        let answer = read_answer(input)?;
+        match validate(&answer) {
+            Ok(()) => return Ok(answer),
+            Err(reason) => writeln!(output, "{}", reason)?,
+        }
+    }
+}
+
+/// Ask a yes/no question; an empty answer is `default` when there is one
+pub fn confirm_lines(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    message: &str,
+    default: Option<bool>,
+) -> io::Result<bool> {
+    let choices = match default {
+        Some(true) => "[Y/n]",
+        Some(false) => "[y/N]",
+        None => "[y/n]",
+    };
+    loop {
+        write!(output, "{} {} ", message, choices)?;
+        output.flush()?;
+        let answer = read_answer(input)?;
+        match (answer.trim().to_lowercase().as_str(), default) {
+            ("y" | "yes", _) => return Ok(true),
+            ("n" | "no", _) => return Ok(false),
+            ("", Some(default)) => return Ok(default),
+            _ => writeln!(output, "Please answer y or n")?,
+        }
+    }
+}
+
+/// Ask for one of `options` by its number or its text, returning its index
+pub fn select_lines(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    message: Option<&str>,
+    options: &[String],
+) -> io::Result<usize> {
+    if let Some(message) = message {
+        writeln!(output, "{}", message)?;
+    }
+    for (index, option) in options.iter().enumerate() {
+        writeln!(output, "  {}) {}", index + 1, option)?;
+    }
+
+    loop {
+        write!(output, "Choose 1-{}: ", options.len())?;
+        output.flush()?;
+        let answer = read_answer(input)?;
+        let answer = answer.trim();
+        let by_number = answer
+            .parse::<usize>()
+            .ok()
+            .filter(|number| (1..=options.len()).contains(number))
+            .map(|number| number - 1);
+        match by_number.or_else(|| options.iter().position(|option| option == answer)) {
+            Some(index) => return Ok(index),
+            None => writeln!(output, "Please enter a number from 1 to {}", options.len())?,
+        }
+    }
+}
+
+/// Read one line without its line ending, failing at the end of input
+fn read_answer(input: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    if input.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Input ended before an answer was given",
+        ));
+    }
+    Ok(line.trim_end_matches(['\n', '\r']).to_string())
+}
+
+/// Whether the user can answer prompts with the keyboard
+fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+fn dialog_error(error: dialoguer::Error) -> io::Error {
+    match error {
+        dialoguer::Error::IO(error) => error,
+    }
+}
+
+/// Judge an answer with an optional Script validation closure
+fn check_answer(validate: Option<&ScriptValue>, answer: &str) -> Result<(), String> {
+    let Some(validate) = validate else {
+        return Ok(());
+    };
+    match execute_script_closure(validate, &[string_value(answer)]) {
+        Ok(ScriptValue::Bool(false)) => Err(INVALID_ANSWER.to_string()),
+        Ok(ScriptValue::Result(result)) if result.is_err() => Err(match result.get_err() {
+            Some(ScriptValue::String(reason)) => reason.as_str().to_string(),
+            Some(reason) => inspect(reason),
+            None => INVALID_ANSWER.to_string(),
+        }),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Wrap the outcome of a prompt in a Script Result
+fn to_result(answer: io::Result<ScriptValue>) -> ScriptValue {
+    let result = match answer {
+        Ok(value) => ScriptResult::ok(value),
+        Err(e) => ScriptResult::err(io_error_from_std(e).to_script_value()),
+    };
+    ScriptValue::Result(ScriptRc::new(result))
+}
+
+/// The message and optional validation closure of `prompt` and
+/// `prompt_password`
+fn text_prompt_args<'a>(
+    function: &str,
+    args: &'a [ScriptValue],
+) -> RuntimeResult<(&'a str, Option<&'a ScriptValue>)> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "{} expects 1 or 2 arguments (message, validate), got {}",
+            function,
+            args.len()
+        )));
+    }
+    let message = string_arg(args, 0, function)?;
+    let validate = args.get(1);
+    if let Some(validate) = validate {
+        if !matches!(validate, ScriptValue::Closure(_)) {
+            return Err(RuntimeError::InvalidOperation(format!(
+                "{} expects a closure to validate the answer",
+                function
+            )));
+        }
+    }
+    Ok((message, validate))
+}
+
+/// Implementation of prompt for Script
+pub(crate) fn prompt_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    let (message, validate) = text_prompt_args("prompt", args)?;
+
+    let answer = if is_interactive() {
+        let validate = validate.cloned();
+        Input::<String>::new()
+            .with_prompt(message)
+            .allow_empty(true)
+            .validate_with(move |answer: &String| check_answer(validate.as_ref(), answer))
+            .interact_text()
+            .map_err(dialog_error)
+    } else {
+        prompt_lines(
+            &mut io::stdin().lock(),
+            &mut io::stderr(),
+            message,
+            |answer| check_answer(validate, answer),
+        )
+    };
+    Ok(to_result(answer.map(|answer| string_value(&answer))))
+}
+
+/// Implementation of prompt_password for Script
+pub(crate) fn prompt_password_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    let (message, validate) = text_prompt_args("prompt_password", args)?;
+
+    let answer = if is_interactive() {
+        let validate = validate.cloned();
+        Password::new()
+            .with_prompt(message)
+            .allow_empty_password(true)
+            .validate_with(move |answer: &String| check_answer(validate.as_ref(), answer))
+            .interact()
+            .map_err(dialog_error)
+    } else {
+        prompt_lines(
+            &mut io::stdin().lock(),
+            &mut io::stderr(),
+            message,
+            |answer| check_answer(validate, answer),
+        )
+    };
+    Ok(to_result(answer.map(|answer| string_value(&answer))))
+}
+
+/// Implementation of confirm for Script
+pub(crate) fn confirm_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "confirm expects 1 or 2 arguments (message, default), got {}",
+            args.len()
+        )));
+    }
+    let message = string_arg(args, 0, "confirm")?;
+    let default = match args.get(1) {
+        None => None,
+        Some(ScriptValue::Bool(default)) => Some(*default),
+        Some(other) => {
+            return Err(RuntimeError::InvalidOperation(format!(
+                "confirm expects a bool default, got {:?}",
+                other.get_type()
+            )))
+        }
+    };
+
+    let answer = if is_interactive() {
+        let mut dialog = Confirm::new().with_prompt(message);
+        if let Some(default) = default {
+            dialog = dialog.default(default);
+        }
+        dialog.interact().map_err(dialog_error)
+    } else {
+        confirm_lines(&mut io::stdin().lock(), &mut io::stderr(), message, default)
+    };
+    Ok(to_result(answer.map(ScriptValue::Bool)))
+}
+
+/// Implementation of prompt_select for Script
+pub(crate) fn select_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "prompt_select expects 1 or 2 arguments (options, message), got {}",
+            args.len()
+        )));
+    }
+    let ScriptValue::Array(options) = &args[0] else {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "prompt_select expects an array of options, got {:?}",
+            args[0].get_type()
+        )));
+    };
+    let options: Vec<String> = options
+        .to_vec()
+        .map_err(|e| RuntimeError::InvalidOperation(e.to_string()))?
+        .iter()
+        .map(|option| match option {
+            ScriptValue::String(text) => text.as_str().to_string(),
+            other => inspect(other),
+        })
+        .collect();
+    if options.is_empty() {
+        return Err(RuntimeError::InvalidOperation(
+            "prompt_select expects at least one option".to_string(),
+        ));
+    }
+    let message = if args.len() > 1 {
+        Some(string_arg(args, 1, "prompt_select")?)
+    } else {
+        None
+    };
+
+    let answer = if is_interactive() {
+        let mut dialog = Select::new().items(&options).default(0);
+        if let Some(message) = message {
+            dialog = dialog.with_prompt(message);
+        }
+        dialog.interact().map_err(dialog_error)
+    } else {
+        select_lines(
+            &mut io::stdin().lock(),
+            &mut io::stderr(),
+            message,
+            &options,
+        )
+    };
+    Ok(to_result(
+        answer.map(|index| ScriptValue::I32(index as i32)),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn run<T>(
+        answers: &str,
+        ask: impl FnOnce(&mut Cursor<&[u8]>, &mut Vec<u8>) -> io::Result<T>,
+    ) -> (io::Result<T>, String) {
+        let mut input = Cursor::new(answers.as_bytes());
+        let mut output = Vec::new();
+        let result = ask(&mut input, &mut output);
+        (result, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_prompt_asks_again_until_valid() {
+        let (answer, output) = run("\nab\r\nabc\n", |input, output| {
+            prompt_lines(input, output, "Name", |answer| {
+                if answer.len() >= 3 {
+                    Ok(())
+                } else {
+                    Err("At least 3 characters".to_string())
+                }
+            })
+        });
+        assert_eq!(answer.unwrap(), "abc");
+        assert_eq!(
+            output,
+            "Name: At least 3 characters\nName: At least 3 characters\nName: "
+        );
+
+        let (answer, _) = run("", |input, output| {
+            prompt_lines(input, output, "Name", |_| Ok(()))
+        });
+        assert_eq!(answer.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn test_confirm_and_select() {
+        let (answer, output) = run("maybe\nYes\n", |input, output| {
+            confirm_lines(input, output, "Deploy?", None)
+        });
+        assert!(answer.unwrap());
+        assert_eq!(output, "Deploy? [y/n] Please answer y or n\nDeploy? [y/n] ");
+        let (answer, _) = run("\n", |input, output| {
+            confirm_lines(input, output, "Deploy?", Some(false))
+        });
+        assert!(!answer.unwrap());
+
+        let options = vec!["dev".to_string(), "prod".to_string()];
+        let (answer, output) = run("3\n2\n", |input, output| {
+            select_lines(input, output, Some("Environment"), &options)
+        });
+        assert_eq!(answer.unwrap(), 1);
+        assert_eq!(
+            output,
+            "Environment\n  1) dev\n  2) prod\nChoose 1-2: Please enter a number from 1 to 2\nChoose 1-2: "
+        );
+        let (answer, _) = run("dev\n", |input, output| {
+            select_lines(input, output, None, &options)
+        });
+        assert_eq!(answer.unwrap(), 0);
+    }
+}
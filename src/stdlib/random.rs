@@ -7,18 +7,13 @@
 //! - Shuffle operations
 //! - Common game-oriented random utilities
 
-use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::runtime::{deterministic, Result as RuntimeResult, RuntimeError, ScriptRc};
 use crate::stdlib::{ScriptString, ScriptValue};
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 
-thread_local! {
-    /// Thread-local RNG instance
-    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
-}
-
 /// Script-accessible RNG handle
 pub struct ScriptRng {
     rng: Rc<RefCell<StdRng>>,
@@ -26,9 +21,11 @@ pub struct ScriptRng {
 
 impl ScriptRng {
     /// Create a new RNG with a random seed
+    ///
+    /// In deterministic mode the seed comes from the runtime's seed instead.
     pub fn new() -> Self {
         ScriptRng {
-            rng: Rc::new(RefCell::new(StdRng::from_entropy())),
+            rng: Rc::new(RefCell::new(deterministic::new_rng())),
         }
     }
 
@@ -153,11 +150,11 @@ impl ScriptRng {
     }
 }
 
-// Global RNG functions (using thread-local RNG)
+// Global RNG functions (using the runtime's thread-local RNG)
 
 /// Generate a random f32 between 0.0 and 1.0
 pub fn random_impl(_args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
-    RNG.with(|rng| Ok(ScriptValue::F32(rng.borrow_mut().gen::<f32>())))
+    deterministic::with_rng(|rng| Ok(ScriptValue::F32(rng.gen::<f32>())))
 }
 
 /// Generate a random f32 in the given range
@@ -176,7 +173,7 @@ pub fn random_range_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
         return Ok(ScriptValue::F32(min));
     }
 
-    RNG.with(|rng| Ok(ScriptValue::F32(rng.borrow_mut().gen_range(min..max))))
+    deterministic::with_rng(|rng| Ok(ScriptValue::F32(rng.gen_range(min..max))))
 }
 
 /// Generate a random i32 in the given range (inclusive)
@@ -195,7 +192,7 @@ pub fn random_int_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
         return Ok(ScriptValue::I32(min));
     }
 
-    RNG.with(|rng| Ok(ScriptValue::I32(rng.borrow_mut().gen_range(min..=max))))
+    deterministic::with_rng(|rng| Ok(ScriptValue::I32(rng.gen_range(min..=max))))
 }
 
 /// Generate a random boolean with given probability
@@ -211,11 +208,7 @@ pub fn random_bool_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
         )));
     };
 
-    RNG.with(|rng| {
-        Ok(ScriptValue::Bool(
-            rng.borrow_mut().gen::<f32>() < probability,
-        ))
-    })
+    deterministic::with_rng(|rng| Ok(ScriptValue::Bool(rng.gen::<f32>() < probability)))
 }
 
 /// Set the global RNG seed
@@ -229,9 +222,7 @@ pub fn random_seed_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
 
     let seed = args[0].to_i32()? as u64;
 
-    RNG.with(|rng| {
-        *rng.borrow_mut() = StdRng::seed_from_u64(seed);
-    });
+    deterministic::reseed(seed);
 
     Ok(ScriptValue::Unit)
 }
@@ -319,9 +310,9 @@ pub fn shuffle_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
         ScriptValue::Array(arr) => {
             let mut items = (**arr).clone();
 
-            RNG.with(|rng| {
+            deterministic::with_rng(|rng| {
                 use rand::seq::SliceRandom;
-                items.shuffle(&mut *rng.borrow_mut());
+                items.shuffle(rng);
             });
 
             Ok(ScriptValue::Array(ScriptRc::new(items)))
@@ -349,7 +340,7 @@ pub fn pick_random_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
                 ));
             }
 
-            let index = RNG.with(|rng| rng.borrow_mut().gen_range(0..arr.len()));
+            let index = deterministic::with_rng(|rng| rng.gen_range(0..arr.len()));
 
             arr.get(index)
                 .map_err(|_| RuntimeError::InvalidOperation("Failed to access array".to_string()))?
@@ -363,8 +354,8 @@ pub fn pick_random_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
 
 /// Generate a random unit vector 2D
 pub fn random_unit_vec2_impl(_args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
-    let (x, y) = RNG.with(|rng| {
-        let angle = rng.borrow_mut().gen_range(0.0..std::f32::consts::TAU);
+    let (x, y) = deterministic::with_rng(|rng| {
+        let angle = rng.gen_range(0.0..std::f32::consts::TAU);
         (angle.cos(), angle.sin())
     });
 
@@ -376,8 +367,8 @@ pub fn random_unit_vec2_impl(_args: &[ScriptValue]) -> RuntimeResult<ScriptValue
 
 /// Generate a random unit vector 3D
 pub fn random_unit_vec3_impl(_args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
-    let (x, y, z) = RNG.with(|rng| {
-        let mut r = &mut *rng.borrow_mut();
+    let (x, y, z) = deterministic::with_rng(|rng| {
+        let r = rng;
         let theta = r.gen_range(0.0..std::f32::consts::TAU);
         let phi = (1.0 - 2.0 * r.gen::<f32>()).acos();
 
@@ -405,8 +396,8 @@ pub fn random_in_circle_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue>
         )));
     };
 
-    let (x, y) = RNG.with(|rng| {
-        let mut r = &mut *rng.borrow_mut();
+    let (x, y) = deterministic::with_rng(|rng| {
+        let r = rng;
         loop {
             let x = r.gen_range(-radius..radius);
             let y = r.gen_range(-radius..radius);
@@ -484,7 +475,7 @@ pub fn weighted_random_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue>
     }
 
     // Pick random value and find corresponding item
-    let pick = RNG.with(|rng| rng.borrow_mut().gen_range(0.0..total_weight));
+    let pick = deterministic::with_rng(|rng| rng.gen_range(0.0..total_weight));
 
     let mut accumulated = 0.0;
     for i in 0..weights.len() {
@@ -0,0 +1,413 @@
+//! Rate limiting for Script
+//!
+//! Rate limiters are token buckets: tokens refill at a steady rate up to a
+//! burst capacity and every action spends one. They can be used from:
+//! - Synchronous code, with `try_acquire` or the blocking `acquire`
+//! - Async code, with `acquire_async` which waits on the runtime's timers
+//!
+//! Each limiter keeps `RateLimiterMetrics` that can be logged or read from
+//! scripts with `rate_limiter_stats`.
+
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptFuture, ScriptRc, Timer};
+use crate::stdlib::{handle_id, ScriptValue};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::task::{Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// Rate limiters created by scripts, keyed by the id stored in their handle
+fn limiters() -> MutexGuard<'static, HashMap<i32, Arc<RateLimiter>>> {
+    static LIMITERS: OnceLock<Mutex<HashMap<i32, Arc<RateLimiter>>>> = OnceLock::new();
+    LIMITERS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Object key holding a rate limiter's id
+const LIMITER_ID_KEY: &str = "_rate_limiter_id";
+
+/// Tokens refilled at a steady rate up to a burst capacity
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Create a full bucket refilling `tokens_per_sec` up to `burst` tokens
+    pub fn new(tokens_per_sec: f64, burst: f64) -> Self {
+        let burst = burst.max(1.0);
+        TokenBucket {
+            rate: tokens_per_sec,
+            burst,
+            tokens: burst,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = now;
+    }
+
+    /// Tokens currently available
+    pub fn available(&mut self) -> f64 {
+        self.refill();
+        self.tokens
+    }
+
+    /// Take `tokens` if they are available
+    pub fn try_take(&mut self, tokens: f64) -> bool {
+        self.refill();
+        if self.tokens >= tokens {
+            self.tokens -= tokens;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Time until `tokens` will be available
+    pub fn time_until(&mut self, tokens: f64) -> Duration {
+        self.refill();
+        let missing = tokens - self.tokens;
+        if missing <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(missing / self.rate)
+        }
+    }
+}
+
+/// Counters describing how a rate limiter has been used
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimiterMetrics {
+    /// Actions that were let through
+    pub acquired: u64,
+    /// Actions that could not proceed immediately
+    pub throttled: u64,
+    /// Total time spent waiting for tokens
+    pub waited: Duration,
+}
+
+impl fmt::Display for RateLimiterMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} acquired, {} throttled, {}ms waited",
+            self.acquired,
+            self.throttled,
+            self.waited.as_millis()
+        )
+    }
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    bucket: TokenBucket,
+    metrics: RateLimiterMetrics,
+}
+
+/// A token-bucket rate limiter that can be shared between threads and tasks
+#[derive(Debug)]
+pub struct RateLimiter {
+    state: Mutex<LimiterState>,
+}
+
+impl RateLimiter {
+    /// Allow `tokens_per_sec` actions per second, with bursts of up to `burst`
+    pub fn new(tokens_per_sec: f64, burst: f64) -> Self {
+        RateLimiter {
+            state: Mutex::new(LimiterState {
+                bucket: TokenBucket::new(tokens_per_sec, burst),
+                metrics: RateLimiterMetrics::default(),
+            }),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LimiterState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Take a token if one is available, without waiting
+    pub fn try_acquire(&self) -> bool {
+        let mut state = self.lock();
+        if state.bucket.try_take(1.0) {
+            state.metrics.acquired += 1;
+            true
+        } else {
+            state.metrics.throttled += 1;
+            false
+        }
+    }
+
+    /// Take a token, blocking the current thread until one is available
+    pub fn acquire(&self) {
+        let start = Instant::now();
+        let mut throttled = false;
+        loop {
+            let wait = {
+                let mut state = self.lock();
+                if state.bucket.try_take(1.0) {
+                    state.metrics.acquired += 1;
+                    if throttled {
+                        state.metrics.throttled += 1;
+                        state.metrics.waited += start.elapsed();
+                    }
+                    return;
+                }
+                state.bucket.time_until(1.0)
+            };
+            throttled = true;
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// Take a token from async code, waiting on a timer while throttled
+    pub fn acquire_async(self: &Arc<Self>) -> Acquire {
+        Acquire {
+            limiter: Arc::clone(self),
+            throttled_at: None,
+            timer: None,
+        }
+    }
+
+    /// Tokens currently available
+    pub fn available(&self) -> f64 {
+        self.lock().bucket.available()
+    }
+
+    /// Snapshot of the limiter's metrics
+    pub fn metrics(&self) -> RateLimiterMetrics {
+        self.lock().metrics.clone()
+    }
+}
+
+/// Future returned by `RateLimiter::acquire_async`
+pub struct Acquire {
+    limiter: Arc<RateLimiter>,
+    throttled_at: Option<Instant>,
+    timer: Option<Timer>,
+}
+
+impl ScriptFuture for Acquire {
+    type Output = ();
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Output> {
+        loop {
+            if let Some(timer) = &mut self.timer {
+                match timer.poll(waker) {
+                    Poll::Ready(()) => self.timer = None,
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+
+            let mut state = self.limiter.lock();
+            if state.bucket.try_take(1.0) {
+                state.metrics.acquired += 1;
+                if let Some(started) = self.throttled_at {
+                    state.metrics.throttled += 1;
+                    state.metrics.waited += started.elapsed();
+                }
+                return Poll::Ready(());
+            }
+            let wait = state.bucket.time_until(1.0);
+            drop(state);
+            self.throttled_at.get_or_insert_with(Instant::now);
+            self.timer = Some(Timer::new(wait));
+        }
+    }
+}
+
+fn limiter_handle(value: &ScriptValue) -> RuntimeResult<Arc<RateLimiter>> {
+    let id = handle_id(value, LIMITER_ID_KEY, "a rate limiter")?;
+    limiters()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| RuntimeError::InvalidOperation("Expected a rate limiter".to_string()))
+}
+
+/// Create a rate limiter
+pub fn rate_limiter_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "rate_limiter expects 2 arguments (tokens_per_sec, burst), got {}",
+            args.len()
+        )));
+    }
+
+    let rate = args[0].to_f32()?;
+    let burst = args[1].to_f32()?;
+    if !(rate > 0.0 && rate.is_finite()) {
+        return Err(RuntimeError::InvalidOperation(
+            "tokens_per_sec must be a positive number".to_string(),
+        ));
+    }
+    if !(burst >= 1.0 && burst.is_finite()) {
+        return Err(RuntimeError::InvalidOperation(
+            "burst must be at least 1".to_string(),
+        ));
+    }
+
+    let limiter = Arc::new(RateLimiter::new(rate as f64, burst as f64));
+    let mut limiters = limiters();
+    let id = limiters.len() as i32;
+    limiters.insert(id, limiter);
+
+    let mut fields = HashMap::new();
+    fields.insert(LIMITER_ID_KEY.to_string(), ScriptValue::I32(id));
+    Ok(ScriptValue::Object(ScriptRc::new(fields)))
+}
+
+/// Take a token if available, returning whether the action may proceed
+pub fn rate_limit_try_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "rate_limit_try expects 1 argument (limiter), got {}",
+            args.len()
+        )));
+    }
+
+    Ok(ScriptValue::Bool(limiter_handle(&args[0])?.try_acquire()))
+}
+
+/// Take a token, waiting until one is available
+pub fn rate_limit_acquire_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "rate_limit_acquire expects 1 argument (limiter), got {}",
+            args.len()
+        )));
+    }
+
+    limiter_handle(&args[0])?.acquire();
+    Ok(ScriptValue::Unit)
+}
+
+/// Get a rate limiter's metrics as an object
+pub fn rate_limiter_stats_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "rate_limiter_stats expects 1 argument (limiter), got {}",
+            args.len()
+        )));
+    }
+
+    let limiter = limiter_handle(&args[0])?;
+    let metrics = limiter.metrics();
+
+    let mut fields = HashMap::new();
+    fields.insert(
+        "acquired".to_string(),
+        ScriptValue::I32(metrics.acquired as i32),
+    );
+    fields.insert(
+        "throttled".to_string(),
+        ScriptValue::I32(metrics.throttled as i32),
+    );
+    fields.insert(
+        "wait_ms".to_string(),
+        ScriptValue::F32(metrics.waited.as_secs_f32() * 1000.0),
+    );
+    fields.insert(
+        "available".to_string(),
+        ScriptValue::F32(limiter.available() as f32),
+    );
+    Ok(ScriptValue::Object(ScriptRc::new(fields)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::task::Wake;
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn test_token_bucket_burst_and_refill() {
+        let mut bucket = TokenBucket::new(100.0, 3.0);
+        assert!(bucket.try_take(1.0));
+        assert!(bucket.try_take(1.0));
+        assert!(bucket.try_take(1.0));
+        assert!(!bucket.try_take(1.0));
+        assert!(bucket.time_until(1.0) <= Duration::from_millis(10));
+
+        std::thread::sleep(Duration::from_millis(15));
+        assert!(bucket.try_take(1.0));
+        assert!(bucket.available() <= 3.0);
+    }
+
+    #[test]
+    fn test_rate_limiter_metrics() {
+        let limiter = RateLimiter::new(200.0, 1.0);
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+
+        // Blocks roughly 5ms for the next token
+        limiter.acquire();
+
+        let metrics = limiter.metrics();
+        assert_eq!(metrics.acquired, 2);
+        assert_eq!(metrics.throttled, 2);
+        assert!(metrics.waited > Duration::ZERO);
+        assert!(metrics.to_string().starts_with("2 acquired, 2 throttled"));
+    }
+
+    #[test]
+    fn test_acquire_async() {
+        let limiter = Arc::new(RateLimiter::new(200.0, 1.0));
+        let waker = Waker::from(Arc::new(NoopWaker));
+
+        let mut first = limiter.acquire_async();
+        assert_eq!(first.poll(&waker), Poll::Ready(()));
+
+        let mut second = limiter.acquire_async();
+        loop {
+            match second.poll(&waker) {
+                Poll::Ready(()) => break,
+                Poll::Pending => std::thread::sleep(Duration::from_millis(1)),
+            }
+        }
+        let metrics = limiter.metrics();
+        assert_eq!(metrics.acquired, 2);
+        assert_eq!(metrics.throttled, 1);
+    }
+
+    #[test]
+    fn test_rate_limiter_script_functions() {
+        let handle = rate_limiter_impl(&[ScriptValue::F32(1.0), ScriptValue::I32(2)]).unwrap();
+        let allowed = |handle: &ScriptValue| {
+            rate_limit_try_impl(&[handle.clone()])
+                .unwrap()
+                .as_bool()
+                .unwrap()
+        };
+        assert!(allowed(&handle));
+        assert!(allowed(&handle));
+        assert!(!allowed(&handle));
+
+        let stats = rate_limiter_stats_impl(&[handle]).unwrap();
+        match stats {
+            ScriptValue::Object(fields) => {
+                assert_eq!(fields.get("acquired"), Some(&ScriptValue::I32(2)));
+                assert_eq!(fields.get("throttled"), Some(&ScriptValue::I32(1)));
+            }
+            _ => panic!("expected stats object"),
+        }
+
+        assert!(rate_limiter_impl(&[ScriptValue::F32(0.0), ScriptValue::I32(1)]).is_err());
+        assert!(rate_limit_try_impl(&[ScriptValue::I32(0)]).is_err());
+    }
+}
@@ -0,0 +1,608 @@
+//! Retry, backoff and circuit-breaker utilities for Script
+//!
+//! This module helps scripts cope with flaky operations such as network calls:
+//! - Exponential backoff with jitter
+//! - `retry` for re-running a closure until it succeeds
+//! - Circuit breakers that stop calling an operation that keeps failing
+//! - `RetryFuture` for retrying async operations on the runtime's timers
+
+use crate::runtime::{
+    deterministic, BoxedFuture, Result as RuntimeResult, RuntimeError, ScriptFuture, ScriptRc,
+    Timer,
+};
+use crate::stdlib::functional::execute_script_closure;
+use crate::stdlib::{expect_closure, handle_id, string_value, ScriptResult, ScriptValue};
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::task::{Poll, Waker};
+use std::time::{Duration, Instant};
+
+/// Circuit breakers created by scripts, keyed by the id stored in their handle
+fn breakers() -> MutexGuard<'static, HashMap<i32, CircuitBreaker>> {
+    static BREAKERS: OnceLock<Mutex<HashMap<i32, CircuitBreaker>>> = OnceLock::new();
+    BREAKERS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Object key holding a circuit breaker's id
+const BREAKER_ID_KEY: &str = "_circuit_breaker_id";
+
+/// Exponential backoff between retry attempts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backoff {
+    /// Delay before the first retry
+    pub initial: Duration,
+    /// Factor the delay grows by after each retry
+    pub multiplier: f32,
+    /// Upper bound for any single delay
+    pub max: Duration,
+    /// Random spread applied to each delay, as a fraction of it (0.0 - 1.0)
+    pub jitter: f32,
+}
+
+impl Backoff {
+    /// Backoff starting at `initial`, doubling up to 30 seconds with 10% jitter
+    pub fn new(initial: Duration) -> Self {
+        Backoff {
+            initial,
+            multiplier: 2.0,
+            max: Duration::from_secs(30),
+            jitter: 0.1,
+        }
+    }
+
+    /// Delay before retry number `retry` (0-based), without jitter
+    pub fn base_delay(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.max(1.0).powi(retry as i32);
+        let millis = self.initial.as_secs_f64() * 1000.0 * factor as f64;
+        Duration::from_secs_f64(millis.min(self.max.as_secs_f64() * 1000.0) / 1000.0)
+    }
+
+    /// Delay before retry number `retry` (0-based), with jitter applied
+    pub fn delay(&self, retry: u32) -> Duration {
+        let base = self.base_delay(retry);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return base;
+        }
+        let spread = deterministic::with_rng(|rng| rng.gen_range(-jitter..=jitter));
+        base.mul_f32(1.0 + spread).min(self.max)
+    }
+
+    /// Read a backoff from a Script value
+    ///
+    /// A number is the initial delay in milliseconds; an object may set
+    /// `initial_ms`, `multiplier`, `max_ms` and `jitter`.
+    pub fn from_value(value: &ScriptValue) -> RuntimeResult<Self> {
+        match value {
+            ScriptValue::I32(_) | ScriptValue::F32(_) => {
+                Ok(Backoff::new(millis_arg(value, "backoff")?))
+            }
+            ScriptValue::Object(fields) => {
+                let mut backoff = Backoff::new(Duration::from_millis(100));
+                if let Some(initial) = fields.get("initial_ms") {
+                    backoff.initial = millis_arg(initial, "initial_ms")?;
+                }
+                if let Some(multiplier) = fields.get("multiplier") {
+                    backoff.multiplier = multiplier.to_f32()?;
+                }
+                if let Some(max) = fields.get("max_ms") {
+                    backoff.max = millis_arg(max, "max_ms")?;
+                }
+                if let Some(jitter) = fields.get("jitter") {
+                    backoff.jitter = jitter.to_f32()?;
+                }
+                Ok(backoff)
+            }
+            _ => Err(RuntimeError::InvalidOperation(
+                "backoff must be a delay in milliseconds or a backoff object".to_string(),
+            )),
+        }
+    }
+
+    /// Convert to a Script object accepted by `retry`
+    pub fn to_value(&self) -> ScriptValue {
+        let mut fields = HashMap::new();
+        fields.insert(
+            "initial_ms".to_string(),
+            ScriptValue::F32(self.initial.as_secs_f32() * 1000.0),
+        );
+        fields.insert("multiplier".to_string(), ScriptValue::F32(self.multiplier));
+        fields.insert(
+            "max_ms".to_string(),
+            ScriptValue::F32(self.max.as_secs_f32() * 1000.0),
+        );
+        fields.insert("jitter".to_string(), ScriptValue::F32(self.jitter));
+        ScriptValue::Object(ScriptRc::new(fields))
+    }
+}
+
+/// Run `operation` up to `attempts` times, sleeping between failures
+///
+/// The operation receives the 0-based attempt number. The last error is
+/// returned if every attempt fails.
+pub fn retry<T, E>(
+    attempts: u32,
+    backoff: &Backoff,
+    mut operation: impl FnMut(u32) -> Result<T, E>,
+) -> Result<T, E> {
+    let mut attempt = 0;
+    loop {
+        match operation(attempt) {
+            Ok(value) => return Ok(value),
+            Err(error) if attempt + 1 >= attempts => return Err(error),
+            Err(_) => {
+                deterministic::sleep(backoff.delay(attempt));
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// State of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CircuitState {
+    /// Calls go through
+    Closed,
+    /// Calls are rejected until the reset timeout has passed
+    Open,
+    /// A trial call is allowed; its outcome closes or re-opens the circuit
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// Name of the state as seen by scripts
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+}
+
+/// Stops calling an operation after repeated failures
+///
+/// After `failure_threshold` consecutive failures the circuit opens and
+/// calls are rejected. Once `reset_timeout` has passed one trial call is let
+/// through: success closes the circuit, failure opens it again.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    reset_timeout: Duration,
+    failures: u32,
+    opened_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed circuit breaker
+    pub fn new(failure_threshold: u32, reset_timeout: Duration) -> Self {
+        CircuitBreaker {
+            failure_threshold: failure_threshold.max(1),
+            reset_timeout,
+            failures: 0,
+            opened_at: None,
+        }
+    }
+
+    /// Current state
+    pub fn state(&self) -> CircuitState {
+        match self.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() >= self.reset_timeout => CircuitState::HalfOpen,
+            Some(_) => CircuitState::Open,
+        }
+    }
+
+    /// Whether a call may go through now
+    pub fn allows_call(&self) -> bool {
+        self.state() != CircuitState::Open
+    }
+
+    /// Number of consecutive failures recorded
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Record a successful call, closing the circuit
+    pub fn record_success(&mut self) {
+        self.failures = 0;
+        self.opened_at = None;
+    }
+
+    /// Record a failed call, opening the circuit at the threshold
+    pub fn record_failure(&mut self) {
+        self.failures += 1;
+        if self.state() == CircuitState::HalfOpen || self.failures >= self.failure_threshold {
+            self.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Run `operation` through the breaker
+    ///
+    /// Returns `None` without calling it while the circuit is open.
+    pub fn call<T, E>(&mut self, operation: impl FnOnce() -> Result<T, E>) -> Option<Result<T, E>> {
+        if !self.allows_call() {
+            return None;
+        }
+        let result = operation();
+        match &result {
+            Ok(_) => self.record_success(),
+            Err(_) => self.record_failure(),
+        }
+        Some(result)
+    }
+}
+
+/// Retries an async operation, waiting on runtime timers between attempts
+pub struct RetryFuture<T, E> {
+    make_attempt: Box<dyn FnMut() -> BoxedFuture<Result<T, E>> + Send>,
+    backoff: Backoff,
+    attempts: u32,
+    attempt: u32,
+    state: RetryState<T, E>,
+}
+
+enum RetryState<T, E> {
+    Running(BoxedFuture<Result<T, E>>),
+    Waiting(Timer),
+}
+
+impl<T, E> RetryFuture<T, E> {
+    /// Start the first attempt; `make_attempt` creates the future for each try
+    pub fn new(
+        attempts: u32,
+        backoff: Backoff,
+        mut make_attempt: impl FnMut() -> BoxedFuture<Result<T, E>> + Send + 'static,
+    ) -> Self {
+        let first = make_attempt();
+        RetryFuture {
+            make_attempt: Box::new(make_attempt),
+            backoff,
+            attempts: attempts.max(1),
+            attempt: 0,
+            state: RetryState::Running(first),
+        }
+    }
+}
+
+impl<T, E> ScriptFuture for RetryFuture<T, E> {
+    type Output = Result<T, E>;
+
+    fn poll(&mut self, waker: &Waker) -> Poll<Self::Output> {
+        loop {
+            match &mut self.state {
+                RetryState::Running(future) => match future.poll(waker) {
+                    Poll::Ready(Ok(value)) => return Poll::Ready(Ok(value)),
+                    Poll::Ready(Err(error)) => {
+                        if self.attempt + 1 >= self.attempts {
+                            return Poll::Ready(Err(error));
+                        }
+                        let delay = self.backoff.delay(self.attempt);
+                        self.attempt += 1;
+                        self.state = RetryState::Waiting(Timer::new(delay));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                RetryState::Waiting(timer) => match timer.poll(waker) {
+                    Poll::Ready(()) => {
+                        self.state = RetryState::Running((self.make_attempt)());
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+            }
+        }
+    }
+}
+
+/// Retry an async operation with backoff
+pub fn retry_async<T, E>(
+    attempts: u32,
+    backoff: Backoff,
+    make_attempt: impl FnMut() -> BoxedFuture<Result<T, E>> + Send + 'static,
+) -> RetryFuture<T, E> {
+    RetryFuture::new(attempts, backoff, make_attempt)
+}
+
+/// Read a non-negative millisecond count
+fn millis_arg(value: &ScriptValue, name: &str) -> RuntimeResult<Duration> {
+    let millis = value.to_f32()?;
+    if millis < 0.0 || !millis.is_finite() {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "{} must be a non-negative number of milliseconds",
+            name
+        )));
+    }
+    Ok(Duration::from_secs_f32(millis / 1000.0))
+}
+
+/// Call a Script closure, treating `Err` results as failures
+fn call_closure(closure: &ScriptValue) -> Result<ScriptValue, ScriptValue> {
+    match execute_script_closure(closure, &[]) {
+        Ok(ScriptValue::Result(result)) if result.is_err() => Err(ScriptValue::Result(result)),
+        Ok(value) => Ok(value),
+        Err(error) => Err(error_value(&error.to_string())),
+    }
+}
+
+fn error_value(message: &str) -> ScriptValue {
+    ScriptValue::Result(ScriptRc::new(ScriptResult::err(string_value(message))))
+}
+
+fn with_breaker<T>(
+    handle: &ScriptValue,
+    f: impl FnOnce(&mut CircuitBreaker) -> T,
+) -> RuntimeResult<T> {
+    let id = handle_id(handle, BREAKER_ID_KEY, "a circuit breaker")?;
+    breakers()
+        .get_mut(&id)
+        .map(f)
+        .ok_or_else(|| RuntimeError::InvalidOperation("Unknown circuit breaker".to_string()))
+}
+
+/// Create a backoff configuration
+pub fn backoff_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.is_empty() || args.len() > 4 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "backoff expects 1 to 4 arguments (initial_ms, multiplier, max_ms, jitter), got {}",
+            args.len()
+        )));
+    }
+
+    let mut backoff = Backoff::new(millis_arg(&args[0], "initial_ms")?);
+    if let Some(multiplier) = args.get(1) {
+        backoff.multiplier = multiplier.to_f32()?;
+    }
+    if let Some(max) = args.get(2) {
+        backoff.max = millis_arg(max, "max_ms")?;
+    }
+    if let Some(jitter) = args.get(3) {
+        backoff.jitter = jitter.to_f32()?;
+    }
+    Ok(backoff.to_value())
+}
+
+/// Call a closure until it succeeds, up to a number of attempts
+pub fn retry_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 3 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "retry expects 3 arguments (closure, attempts, backoff), got {}",
+            args.len()
+        )));
+    }
+
+    expect_closure(&args[0], "retry")?;
+    let attempts = args[1].to_i32()?;
+    if attempts < 1 {
+        return Err(RuntimeError::InvalidOperation(
+            "retry attempts must be at least 1".to_string(),
+        ));
+    }
+    let backoff = Backoff::from_value(&args[2])?;
+
+    let result = retry(attempts as u32, &backoff, |_| call_closure(&args[0]));
+    Ok(result.unwrap_or_else(|error| error))
+}
+
+/// Create a circuit breaker
+pub fn circuit_breaker_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "circuit_breaker expects 2 arguments (failure_threshold, reset_timeout_ms), got {}",
+            args.len()
+        )));
+    }
+
+    let threshold = args[0].to_i32()?;
+    if threshold < 1 {
+        return Err(RuntimeError::InvalidOperation(
+            "failure_threshold must be at least 1".to_string(),
+        ));
+    }
+    let breaker = CircuitBreaker::new(threshold as u32, millis_arg(&args[1], "reset_timeout_ms")?);
+
+    let mut breakers = breakers();
+    let id = breakers.len() as i32;
+    breakers.insert(id, breaker);
+
+    let mut fields = HashMap::new();
+    fields.insert(BREAKER_ID_KEY.to_string(), ScriptValue::I32(id));
+    Ok(ScriptValue::Object(ScriptRc::new(fields)))
+}
+
+/// Call a closure through a circuit breaker
+///
+/// Returns an `Err` without calling the closure while the circuit is open.
+pub fn circuit_call_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "circuit_call expects 2 arguments (breaker, closure), got {}",
+            args.len()
+        )));
+    }
+
+    expect_closure(&args[1], "circuit_call")?;
+    if !with_breaker(&args[0], |breaker| breaker.allows_call())? {
+        return Ok(error_value("circuit open"));
+    }
+
+    // The closure runs outside the registry lock so it may use breakers too
+    let result = call_closure(&args[1]);
+    with_breaker(&args[0], |breaker| match &result {
+        Ok(_) => breaker.record_success(),
+        Err(_) => breaker.record_failure(),
+    })?;
+    Ok(result.unwrap_or_else(|error| error))
+}
+
+/// Get the state of a circuit breaker: "closed", "open" or "half_open"
+pub fn circuit_state_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "circuit_state expects 1 argument (breaker), got {}",
+            args.len()
+        )));
+    }
+
+    let state = with_breaker(&args[0], |breaker| breaker.state())?;
+    Ok(string_value(state.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::task::Wake;
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// A future that is immediately ready with `value`
+    struct Ready<T>(Option<T>);
+
+    impl<T> ScriptFuture for Ready<T> {
+        type Output = T;
+
+        fn poll(&mut self, _waker: &Waker) -> Poll<T> {
+            Poll::Ready(self.0.take().expect("polled after completion"))
+        }
+    }
+
+    fn no_jitter(initial_ms: u64) -> Backoff {
+        Backoff {
+            jitter: 0.0,
+            ..Backoff::new(Duration::from_millis(initial_ms))
+        }
+    }
+
+    #[test]
+    fn test_backoff_grows_and_caps() {
+        let backoff = Backoff {
+            max: Duration::from_millis(500),
+            ..no_jitter(100)
+        };
+        assert_eq!(backoff.delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.delay(1), Duration::from_millis(200));
+        assert_eq!(backoff.delay(2), Duration::from_millis(400));
+        assert_eq!(backoff.delay(3), Duration::from_millis(500));
+
+        let jittered = Backoff::new(Duration::from_millis(100));
+        for _ in 0..20 {
+            let delay = jittered.delay(0);
+            assert!(delay >= Duration::from_millis(90) && delay <= Duration::from_millis(110));
+        }
+    }
+
+    #[test]
+    fn test_backoff_from_value() {
+        let value = backoff_impl(&[
+            ScriptValue::I32(50),
+            ScriptValue::F32(3.0),
+            ScriptValue::I32(1000),
+            ScriptValue::F32(0.0),
+        ])
+        .unwrap();
+        let backoff = Backoff::from_value(&value).unwrap();
+        assert_eq!(backoff.base_delay(1), Duration::from_millis(150));
+        assert_eq!(backoff.max, Duration::from_millis(1000));
+
+        let backoff = Backoff::from_value(&ScriptValue::I32(20)).unwrap();
+        assert_eq!(backoff.initial, Duration::from_millis(20));
+        assert!(Backoff::from_value(&ScriptValue::Bool(true)).is_err());
+    }
+
+    #[test]
+    fn test_retry_until_success() {
+        let mut calls = 0;
+        let result: Result<u32, &str> = retry(5, &no_jitter(1), |attempt| {
+            calls += 1;
+            if attempt < 2 {
+                Err("flaky")
+            } else {
+                Ok(attempt)
+            }
+        });
+        assert_eq!(result, Ok(2));
+        assert_eq!(calls, 3);
+
+        let result: Result<(), &str> = retry(2, &no_jitter(1), |_| Err("down"));
+        assert_eq!(result, Err("down"));
+    }
+
+    #[test]
+    fn test_retry_requires_closure() {
+        let result = retry_impl(&[
+            ScriptValue::I32(1),
+            ScriptValue::I32(3),
+            ScriptValue::I32(10),
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_circuit_breaker_opens_and_recovers() {
+        let mut breaker = CircuitBreaker::new(2, Duration::from_millis(20));
+        assert_eq!(breaker.call(|| Err::<(), _>("boom")), Some(Err("boom")));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.call(|| Err::<(), _>("boom")), Some(Err("boom")));
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // Open circuits reject calls without running them
+        assert_eq!(
+            breaker.call(|| -> Result<(), ()> { panic!("called") }),
+            None
+        );
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        // A failed trial call re-opens the circuit immediately
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert_eq!(breaker.call(|| Ok::<_, ()>(1)), Some(Ok(1)));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.failures(), 0);
+    }
+
+    #[test]
+    fn test_circuit_breaker_handles() {
+        let handle =
+            circuit_breaker_impl(&[ScriptValue::I32(1), ScriptValue::I32(10_000)]).unwrap();
+        with_breaker(&handle, |breaker| breaker.record_failure()).unwrap();
+
+        let state = circuit_state_impl(&[handle.clone()]).unwrap();
+        assert_eq!(state.as_string().unwrap().as_str(), "open");
+        assert!(circuit_state_impl(&[ScriptValue::I32(0)]).is_err());
+    }
+
+    #[test]
+    fn test_retry_future() {
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut attempts = 0;
+        let mut future = retry_async(3, no_jitter(1), move || {
+            attempts += 1;
+            let result = if attempts < 3 {
+                Err(attempts)
+            } else {
+                Ok(attempts)
+            };
+            Box::new(Ready(Some(result))) as BoxedFuture<Result<i32, i32>>
+        });
+
+        let result = loop {
+            match future.poll(&waker) {
+                Poll::Ready(result) => break result,
+                Poll::Pending => std::thread::sleep(Duration::from_millis(1)),
+            }
+        };
+        assert_eq!(result, Ok(3));
+    }
+}
@@ -0,0 +1,260 @@
+//! Locks and atomics for Script
+//!
+//! Shared state between threads goes through one of:
+//! - `mutex_new(value)`, locked with `mutex_lock` or `mutex_try_lock`
+//! - `rwlock_new(value)`, locked with `rwlock_read` or `rwlock_write`
+//! - `atomic_i32(value)`, for counters and flags updated without a lock
+//!
+//! Locking returns a `Guard<T>`: `guard_get` reads the value, `guard_set`
+//! replaces it and `unlock` releases the lock. A lock call that would
+//! deadlock fails instead of hanging; see `crate::runtime::sync`.
+
+use crate::runtime::sync::{LockGuard, ScriptMutex, ScriptRwLock};
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::{expect_args, ScriptOption, ScriptValue};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::Arc;
+
+/// Create a mutex holding a value
+pub fn mutex_new_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "mutex_new")?;
+
+    Ok(ScriptValue::Mutex(ScriptMutex::new(args[0].clone())))
+}
+
+/// Lock a mutex, waiting while another thread holds it
+pub fn mutex_lock_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "mutex_lock")?;
+
+    let guard = mutex_arg(&args[0], "mutex_lock")?
+        .lock()
+        .map_err(|e| RuntimeError::InvalidOperation(e.to_string()))?;
+    Ok(ScriptValue::LockGuard(guard))
+}
+
+/// Lock a mutex if it is free
+pub fn mutex_try_lock_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "mutex_try_lock")?;
+
+    let option = match mutex_arg(&args[0], "mutex_try_lock")?.try_lock() {
+        Some(guard) => ScriptOption::some(ScriptValue::LockGuard(guard)),
+        None => ScriptOption::none(),
+    };
+    Ok(ScriptValue::Option(ScriptRc::new(option)))
+}
+
+/// Create a reader-writer lock holding a value
+pub fn rwlock_new_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "rwlock_new")?;
+
+    Ok(ScriptValue::RwLock(ScriptRwLock::new(args[0].clone())))
+}
+
+/// Lock a reader-writer lock for reading
+pub fn rwlock_read_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "rwlock_read")?;
+
+    let guard = rwlock_arg(&args[0], "rwlock_read")?
+        .read()
+        .map_err(|e| RuntimeError::InvalidOperation(e.to_string()))?;
+    Ok(ScriptValue::LockGuard(guard))
+}
+
+/// Lock a reader-writer lock for writing
+pub fn rwlock_write_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "rwlock_write")?;
+
+    let guard = rwlock_arg(&args[0], "rwlock_write")?
+        .write()
+        .map_err(|e| RuntimeError::InvalidOperation(e.to_string()))?;
+    Ok(ScriptValue::LockGuard(guard))
+}
+
+/// Read the value behind a guard
+pub fn guard_get_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "guard_get")?;
+
+    guard_arg(&args[0], "guard_get")?
+        .get()
+        .map_err(|e| RuntimeError::InvalidOperation(e.to_string()))
+}
+
+/// Replace the value behind a write guard
+pub fn guard_set_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "guard_set")?;
+
+    guard_arg(&args[0], "guard_set")?
+        .set(args[1].clone())
+        .map_err(|e| RuntimeError::InvalidOperation(e.to_string()))?;
+    Ok(ScriptValue::Unit)
+}
+
+/// Release the lock held by a guard
+pub fn unlock_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "unlock")?;
+
+    guard_arg(&args[0], "unlock")?
+        .unlock()
+        .map_err(|e| RuntimeError::InvalidOperation(e.to_string()))?;
+    Ok(ScriptValue::Unit)
+}
+
+/// Create an atomic integer
+pub fn atomic_i32_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "atomic_i32")?;
+
+    Ok(ScriptValue::AtomicI32(Arc::new(AtomicI32::new(
+        args[0].to_i32()?,
+    ))))
+}
+
+/// Read an atomic integer
+pub fn atomic_load_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "atomic_load")?;
+
+    let atomic = atomic_arg(&args[0], "atomic_load")?;
+    Ok(ScriptValue::I32(atomic.load(Ordering::SeqCst)))
+}
+
+/// Set an atomic integer
+pub fn atomic_store_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "atomic_store")?;
+
+    atomic_arg(&args[0], "atomic_store")?.store(args[1].to_i32()?, Ordering::SeqCst);
+    Ok(ScriptValue::Unit)
+}
+
+/// Add to an atomic integer, returning the previous value
+///
+/// Overflow wraps around.
+pub fn atomic_add_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "atomic_add")?;
+
+    let atomic = atomic_arg(&args[0], "atomic_add")?;
+    Ok(ScriptValue::I32(
+        atomic.fetch_add(args[1].to_i32()?, Ordering::SeqCst),
+    ))
+}
+
+/// Set an atomic integer to `new` if it equals `current`, returning whether
+/// it did
+pub fn atomic_compare_exchange_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 3, "atomic_compare_exchange")?;
+
+    let atomic = atomic_arg(&args[0], "atomic_compare_exchange")?;
+    let exchanged = atomic
+        .compare_exchange(
+            args[1].to_i32()?,
+            args[2].to_i32()?,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        )
+        .is_ok();
+    Ok(ScriptValue::Bool(exchanged))
+}
+
+fn mutex_arg<'a>(
+    value: &'a ScriptValue,
+    function: &str,
+) -> RuntimeResult<&'a ScriptMutex<ScriptValue>> {
+    match value {
+        ScriptValue::Mutex(mutex) => Ok(mutex),
+        other => Err(wrong_type(function, "a mutex", other)),
+    }
+}
+
+fn rwlock_arg<'a>(
+    value: &'a ScriptValue,
+    function: &str,
+) -> RuntimeResult<&'a ScriptRwLock<ScriptValue>> {
+    match value {
+        ScriptValue::RwLock(lock) => Ok(lock),
+        other => Err(wrong_type(function, "a reader-writer lock", other)),
+    }
+}
+
+fn guard_arg<'a>(
+    value: &'a ScriptValue,
+    function: &str,
+) -> RuntimeResult<&'a LockGuard<ScriptValue>> {
+    match value {
+        ScriptValue::LockGuard(guard) => Ok(guard),
+        other => Err(wrong_type(function, "a lock guard", other)),
+    }
+}
+
+fn atomic_arg<'a>(value: &'a ScriptValue, function: &str) -> RuntimeResult<&'a AtomicI32> {
+    match value {
+        ScriptValue::AtomicI32(atomic) => Ok(atomic),
+        other => Err(wrong_type(function, "an atomic integer", other)),
+    }
+}
+
+fn wrong_type(function: &str, expected: &str, found: &ScriptValue) -> RuntimeError {
+    RuntimeError::InvalidOperation(format!(
+        "{} expects {}, got {:?}",
+        function,
+        expected,
+        found.get_type()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_mutex_script_functions() {
+        let mutex = mutex_new_impl(&[ScriptValue::I32(1)]).unwrap();
+        let guard = mutex_lock_impl(&[mutex.clone()]).unwrap();
+        assert!(matches!(
+            mutex_try_lock_impl(&[mutex.clone()]).unwrap(),
+            ScriptValue::Option(option) if matches!(*option, ScriptOption::None)
+        ));
+        // Locking twice on one thread is reported instead of hanging
+        assert!(mutex_lock_impl(&[mutex.clone()]).is_err());
+
+        guard_set_impl(&[guard.clone(), ScriptValue::I32(5)]).unwrap();
+        unlock_impl(&[guard.clone()]).unwrap();
+        assert!(guard_get_impl(&[guard]).is_err());
+
+        let guard = mutex_lock_impl(&[mutex]).unwrap();
+        assert_eq!(guard_get_impl(&[guard]).unwrap(), ScriptValue::I32(5));
+        assert!(mutex_lock_impl(&[ScriptValue::I32(1)]).is_err());
+    }
+
+    #[test]
+    fn test_atomic_counter_across_threads() {
+        let counter = atomic_i32_impl(&[ScriptValue::I32(0)]).unwrap();
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        atomic_add_impl(&[counter.clone(), ScriptValue::I32(1)]).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        assert_eq!(
+            atomic_load_impl(&[counter.clone()]).unwrap(),
+            ScriptValue::I32(400)
+        );
+
+        let exchange = |current, new| {
+            atomic_compare_exchange_impl(&[
+                counter.clone(),
+                ScriptValue::I32(current),
+                ScriptValue::I32(new),
+            ])
+            .unwrap()
+        };
+        assert_eq!(exchange(1, 2), ScriptValue::Bool(false));
+        assert_eq!(exchange(400, 0), ScriptValue::Bool(true));
+        assert_eq!(atomic_load_impl(&[counter]).unwrap(), ScriptValue::I32(0));
+    }
+}
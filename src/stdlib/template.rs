@@ -0,0 +1,1228 @@
+//! Text templates for Script
+//!
+//! `template_render` fills a template with values from an object, for
+//! generating code, documentation and web pages. Templates use three kinds
+//! of tags:
+//!
+//! ```text
+//! {# Comments are dropped #}
+//! Hello, {{ user.name | upper }}!
+//! {% if items %}
+//! {% for item in items %}
+//!   {{ loop.index }}. {{ item.title | default("untitled") }}
+//! {% endfor %}
+//! {% elif archived and not hidden %}
+//! Nothing left, {{ archived | length }} archived.
+//! {% else %}
+//! Nothing here.
+//! {% endif %}
+//! ```
+//!
+//! Paths look up a variable and then fields of objects and maps or indexes
+//! of arrays (`rows.0.name`). Filters transform the value before it is
+//! inserted: `upper`, `lower`, `trim`, `html` (escape for HTML),
+//! `length`, `join(separator)` and `default(value)`, which replaces an
+//! undefined value or `None`. Inserting an undefined value is an error.
+//!
+//! Conditions are tests joined by `and` and `or` (`and` binds tighter),
+//! where a test is an optionally negated value or a comparison with `==`,
+//! `!=`, `<`, `<=`, `>` or `>=`. False, zero, empty strings and
+//! collections, `None` and undefined values are false.
+//!
+//! `{% for item in items %}` loops over an array's items or a map's keys in
+//! order, and `{% for key, value in items %}` also binds the index or key.
+//! Inside, `loop` has `index` (from 1), `index0`, `first`, `last` and
+//! `length`. An `{% else %}` branch renders when there is nothing to loop
+//! over.
+//!
+//! A block tag or comment alone on its line removes the whole line, so
+//! loops and conditions leave no blank lines behind. A `-` inside a tag's
+//! delimiters (`{%-`, `-%}`) trims all whitespace on that side.
+
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::conversion::format_value;
+use crate::stdlib::core_types::ScriptOption;
+use crate::stdlib::error::{self, ErrorKind};
+use crate::stdlib::{string_value, ScriptResult, ScriptString, ScriptValue};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TagKind {
+    Output,
+    Block,
+    Comment,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Tag {
+        kind: TagKind,
+        content: String,
+        line: usize,
+    },
+}
+
+/// Split a template into text and tags
+fn lex(source: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+    let mut trim_next = false;
+
+    loop {
+        let start = ["{{", "{%", "{#"]
+            .iter()
+            .filter_map(|open| rest.find(open))
+            .min();
+        let text_end = start.unwrap_or(rest.len());
+        let raw_text = &rest[..text_end];
+        let mut text = if trim_next {
+            raw_text.trim_start()
+        } else {
+            raw_text
+        };
+        line += raw_text.matches('\n').count();
+
+        let Some(start) = start else {
+            if !text.is_empty() {
+                segments.push(Segment::Text(text.to_string()));
+            }
+            return Ok(segments);
+        };
+
+        let (kind, close) = match &rest[start..start + 2] {
+            "{{" => (TagKind::Output, "}}"),
+            "{%" => (TagKind::Block, "%}"),
+            _ => (TagKind::Comment, "#}"),
+        };
+        let mut inner = &rest[start + 2..];
+        if let Some(trimmed) = inner.strip_prefix('-') {
+            text = text.trim_end();
+            inner = trimmed;
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text.to_string()));
+        }
+
+        let end = inner.find(close).ok_or_else(|| {
+            format!(
+                "line {}: '{}' is never closed with '{}'",
+                line,
+                &rest[start..start + 2],
+                close
+            )
+        })?;
+        let mut content = &inner[..end];
+        trim_next = false;
+        if let Some(trimmed) = content.strip_suffix('-') {
+            content = trimmed;
+            trim_next = true;
+        }
+
+        segments.push(Segment::Tag {
+            kind,
+            content: content.trim().to_string(),
+            line,
+        });
+        line += content.matches('\n').count();
+        rest = &inner[end + close.len()..];
+    }
+}
+
+/// Remove the lines of block tags and comments that stand alone on them
+fn strip_standalone_lines(segments: &mut [Segment]) {
+    let text = |index: Option<usize>| match index.and_then(|i| segments.get(i)) {
+        Some(Segment::Text(text)) => Some(text.as_str()),
+        _ => None,
+    };
+
+    let standalone: Vec<bool> = (0..segments.len())
+        .map(|i| {
+            if !matches!(
+                segments[i],
+                Segment::Tag {
+                    kind: TagKind::Block | TagKind::Comment,
+                    ..
+                }
+            ) {
+                return false;
+            }
+            let before = match text(i.checked_sub(1)) {
+                _ if i == 0 => true,
+                Some(text) => {
+                    let tail = &text[text.rfind('\n').map_or(0, |p| p + 1)..];
+                    tail.trim().is_empty() && (text.contains('\n') || i == 1)
+                }
+                None => false,
+            };
+            let after = match text(Some(i + 1)) {
+                _ if i + 1 == segments.len() => true,
+                Some(text) => {
+                    let head = &text[..text.find('\n').unwrap_or(text.len())];
+                    head.trim().is_empty() && (text.contains('\n') || i + 2 == segments.len())
+                }
+                None => false,
+            };
+            before && after
+        })
+        .collect();
+
+    for i in 0..segments.len() {
+        let after_tag = i > 0 && standalone[i - 1];
+        let before_tag = standalone.get(i + 1).copied().unwrap_or(false);
+        if let Segment::Text(text) = &mut segments[i] {
+            let start = if after_tag {
+                text.find('\n').map_or(text.len(), |p| p + 1)
+            } else {
+                0
+            };
+            let end = if before_tag {
+                text.rfind('\n').map_or(0, |p| p + 1)
+            } else {
+                text.len()
+            };
+            *text = text
+                .get(start..end.max(start))
+                .unwrap_or_default()
+                .to_string();
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A name or dotted path
+    Name(String),
+    Str(String),
+    Number(f64),
+    Op(&'static str),
+}
+
+fn tokenize(content: &str, line: usize) -> Result<Vec<Token>, String> {
+    const OPS: [&str; 10] = ["==", "!=", "<=", ">=", "<", ">", "|", "(", ")", ","];
+
+    let mut tokens = Vec::new();
+    let mut chars = content.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_' || c == '.') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Name(content[start..end].to_string()));
+        } else if c.is_ascii_digit()
+            || (c == '-' && content[start + 1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            chars.next();
+            let mut end = start + 1;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_ascii_digit() || c == '.') {
+                    break;
+                }
+                end = i + 1;
+                chars.next();
+            }
+            let number = content[start..end]
+                .parse()
+                .map_err(|_| format!("line {}: Invalid number '{}'", line, &content[start..end]))?;
+            tokens.push(Token::Number(number));
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut value = String::new();
+            loop {
+                match chars.next() {
+                    Some((_, quote)) if quote == c => break,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => value.push('\n'),
+                        Some((_, 't')) => value.push('\t'),
+                        Some((_, escaped)) => value.push(escaped),
+                        None => break,
+                    },
+                    Some((_, other)) => value.push(other),
+                    None => return Err(format!("line {}: Unterminated string", line)),
+                }
+            }
+            tokens.push(Token::Str(value));
+        } else if let Some(op) = OPS.iter().find(|op| content[start..].starts_with(**op)) {
+            for _ in 0..op.len() {
+                chars.next();
+            }
+            tokens.push(Token::Op(op));
+        } else {
+            return Err(format!("line {}: Unexpected character '{}'", line, c));
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Path(String),
+    Literal(ScriptValue),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Filter {
+    name: String,
+    arg: Option<Operand>,
+}
+
+const FILTERS: [&str; 7] = [
+    "upper", "lower", "trim", "html", "length", "join", "default",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Compare {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Test {
+    negate: bool,
+    left: Operand,
+    compare: Option<(Compare, Operand)>,
+}
+
+/// Tests joined by `or` of tests joined by `and`
+type Condition = Vec<Vec<Test>>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Output {
+        value: Operand,
+        filters: Vec<Filter>,
+        line: usize,
+    },
+    If {
+        branches: Vec<(Condition, Vec<Node>)>,
+        otherwise: Vec<Node>,
+        line: usize,
+    },
+    For {
+        key: Option<String>,
+        value: String,
+        items: Operand,
+        body: Vec<Node>,
+        empty: Vec<Node>,
+        line: usize,
+    },
+}
+
+const KEYWORDS: [&str; 9] = [
+    "if", "elif", "else", "endif", "for", "in", "endfor", "and", "or",
+];
+
+/// Tokens of one tag
+struct Tokens {
+    tokens: Vec<Token>,
+    pos: usize,
+    line: usize,
+}
+
+impl Tokens {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Name(name)) if name == keyword)
+    }
+
+    fn is_op(&self, op: &str) -> bool {
+        matches!(self.peek(), Some(Token::Op(found)) if *found == op)
+    }
+
+    fn error(&self, expected: &str) -> String {
+        match self.peek() {
+            Some(token) => format!(
+                "line {}: Expected {}, found {}",
+                self.line,
+                expected,
+                describe(token)
+            ),
+            None => format!("line {}: Expected {}", self.line, expected),
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), String> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("the end of the tag")),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Name(name))
+                if !name.contains('.') && !KEYWORDS.contains(&name.as_str()) =>
+            {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.error("a variable name")),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        let operand = match self.peek() {
+            Some(Token::Name(name)) if name == "true" || name == "false" => {
+                Operand::Literal(ScriptValue::Bool(name == "true"))
+            }
+            Some(Token::Name(name)) if !KEYWORDS.contains(&name.as_str()) && name != "not" => {
+                if name.split('.').any(str::is_empty) {
+                    return Err(format!("line {}: Invalid path '{}'", self.line, name));
+                }
+                Operand::Path(name.clone())
+            }
+            Some(Token::Str(value)) => Operand::Literal(string_value(value)),
+            Some(Token::Number(number)) if number.fract() == 0.0 && number.abs() < 1e15 => {
+                Operand::Literal(ScriptValue::I64(*number as i64))
+            }
+            Some(Token::Number(number)) => Operand::Literal(ScriptValue::F64(*number)),
+            _ => return Err(self.error("a value")),
+        };
+        self.pos += 1;
+        Ok(operand)
+    }
+
+    fn filters(&mut self) -> Result<Vec<Filter>, String> {
+        let mut filters = Vec::new();
+        while self.is_op("|") {
+            self.pos += 1;
+            let name = match self.next() {
+                Some(Token::Name(name)) if FILTERS.contains(&name.as_str()) => name,
+                Some(Token::Name(name)) => {
+                    return Err(format!("line {}: Unknown filter '{}'", self.line, name))
+                }
+                _ => {
+                    self.pos -= 1;
+                    return Err(self.error("a filter name"));
+                }
+            };
+            let arg = if self.is_op("(") {
+                self.pos += 1;
+                let arg = self.operand()?;
+                if !self.is_op(")") {
+                    return Err(self.error("')'"));
+                }
+                self.pos += 1;
+                Some(arg)
+            } else {
+                None
+            };
+            if name == "default" && arg.is_none() {
+                return Err(format!(
+                    "line {}: The default filter needs a value, as in default(\"none\")",
+                    self.line
+                ));
+            }
+            filters.push(Filter { name, arg });
+        }
+        Ok(filters)
+    }
+
+    fn condition(&mut self) -> Result<Condition, String> {
+        let mut alternatives = Vec::new();
+        loop {
+            let mut tests = vec![self.test()?];
+            while self.is_keyword("and") {
+                self.pos += 1;
+                tests.push(self.test()?);
+            }
+            alternatives.push(tests);
+            if !self.is_keyword("or") {
+                return Ok(alternatives);
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn test(&mut self) -> Result<Test, String> {
+        let negate = self.is_keyword("not");
+        if negate {
+            self.pos += 1;
+        }
+        let left = self.operand()?;
+        let compare = match self.peek() {
+            Some(Token::Op(op)) => {
+                let compare = match *op {
+                    "==" => Compare::Eq,
+                    "!=" => Compare::Ne,
+                    "<" => Compare::Lt,
+                    "<=" => Compare::Le,
+                    ">" => Compare::Gt,
+                    ">=" => Compare::Ge,
+                    _ => return Err(self.error("a comparison")),
+                };
+                self.pos += 1;
+                Some((compare, self.operand()?))
+            }
+            _ => None,
+        };
+        Ok(Test {
+            negate,
+            left,
+            compare,
+        })
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Name(name) => format!("'{}'", name),
+        Token::Str(value) => format!("{:?}", value),
+        Token::Number(number) => number.to_string(),
+        Token::Op(op) => format!("'{}'", op),
+    }
+}
+
+/// A block tag that ended a list of nodes
+struct EndTag {
+    keyword: String,
+    tokens: Tokens,
+}
+
+struct Parser {
+    segments: std::vec::IntoIter<Segment>,
+}
+
+impl Parser {
+    /// Parse nodes until one of the `ends` block tags, which is returned
+    fn nodes(&mut self, ends: &[&str]) -> Result<(Vec<Node>, Option<EndTag>), String> {
+        let mut nodes = Vec::new();
+        while let Some(segment) = self.segments.next() {
+            let (kind, content, line) = match segment {
+                Segment::Text(text) => {
+                    nodes.push(Node::Text(text));
+                    continue;
+                }
+                Segment::Tag {
+                    kind,
+                    content,
+                    line,
+                } => (kind, content, line),
+            };
+            let mut tokens = Tokens {
+                tokens: tokenize(&content, line)?,
+                pos: 0,
+                line,
+            };
+
+            match kind {
+                TagKind::Comment => {}
+                TagKind::Output => {
+                    let value = tokens.operand()?;
+                    let filters = tokens.filters()?;
+                    tokens.expect_end()?;
+                    nodes.push(Node::Output {
+                        value,
+                        filters,
+                        line,
+                    });
+                }
+                TagKind::Block => {
+                    let keyword = match tokens.next() {
+                        Some(Token::Name(keyword)) => keyword,
+                        _ => return Err(format!("line {}: Expected a tag name", line)),
+                    };
+                    if ends.contains(&keyword.as_str()) {
+                        return Ok((nodes, Some(EndTag { keyword, tokens })));
+                    }
+                    match keyword.as_str() {
+                        "if" => nodes.push(self.if_block(tokens)?),
+                        "for" => nodes.push(self.for_block(tokens)?),
+                        "elif" | "else" | "endif" | "endfor" => {
+                            return Err(format!("line {}: Unexpected '{{% {} %}}'", line, keyword))
+                        }
+                        _ => return Err(format!("line {}: Unknown tag '{}'", line, keyword)),
+                    }
+                }
+            }
+        }
+        Ok((nodes, None))
+    }
+
+    /// Parse the rest of a block, failing if the template ends inside it
+    fn block(
+        &mut self,
+        ends: &[&str],
+        opener: &str,
+        line: usize,
+    ) -> Result<(Vec<Node>, EndTag), String> {
+        let (nodes, end) = self.nodes(ends)?;
+        let end = end.ok_or_else(|| {
+            format!(
+                "line {}: '{{% {} %}}' is never closed with '{{% {} %}}'",
+                line,
+                opener,
+                ends[ends.len() - 1]
+            )
+        })?;
+        Ok((nodes, end))
+    }
+
+    fn if_block(&mut self, mut tokens: Tokens) -> Result<Node, String> {
+        let line = tokens.line;
+        let mut condition = tokens.condition()?;
+        tokens.expect_end()?;
+
+        let mut branches = Vec::new();
+        loop {
+            let (body, mut end) = self.block(&["elif", "else", "endif"], "if", line)?;
+            branches.push((condition, body));
+            match end.keyword.as_str() {
+                "elif" => {
+                    condition = end.tokens.condition()?;
+                    end.tokens.expect_end()?;
+                }
+                "else" => {
+                    end.tokens.expect_end()?;
+                    let (otherwise, end) = self.block(&["endif"], "if", line)?;
+                    end.tokens.expect_end()?;
+                    return Ok(Node::If {
+                        branches,
+                        otherwise,
+                        line,
+                    });
+                }
+                _ => {
+                    end.tokens.expect_end()?;
+                    return Ok(Node::If {
+                        branches,
+                        otherwise: Vec::new(),
+                        line,
+                    });
+                }
+            }
+        }
+    }
+
+    fn for_block(&mut self, mut tokens: Tokens) -> Result<Node, String> {
+        let line = tokens.line;
+        let first = tokens.name()?;
+        let (key, value) = if tokens.is_op(",") {
+            tokens.pos += 1;
+            (Some(first), tokens.name()?)
+        } else {
+            (None, first)
+        };
+        if !tokens.is_keyword("in") {
+            return Err(tokens.error("'in'"));
+        }
+        tokens.pos += 1;
+        let items = tokens.operand()?;
+        tokens.expect_end()?;
+
+        let (body, end) = self.block(&["else", "endfor"], "for", line)?;
+        end.tokens.expect_end()?;
+        let empty = if end.keyword == "else" {
+            let (empty, end) = self.block(&["endfor"], "for", line)?;
+            end.tokens.expect_end()?;
+            empty
+        } else {
+            Vec::new()
+        };
+
+        Ok(Node::For {
+            key,
+            value,
+            items,
+            body,
+            empty,
+            line,
+        })
+    }
+}
+
+/// A parsed template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// Parse a template, reporting syntax errors with their line
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut segments = lex(source)?;
+        strip_standalone_lines(&mut segments);
+        let mut parser = Parser {
+            segments: segments.into_iter(),
+        };
+        let (nodes, _) = parser.nodes(&[])?;
+        Ok(Template { nodes })
+    }
+
+    /// Render the template with the variables in `data`
+    pub fn render(&self, data: &HashMap<String, ScriptValue>) -> Result<String, String> {
+        let mut scope = Scope {
+            frames: vec![data.clone()],
+        };
+        let mut out = String::new();
+        scope.render(&self.nodes, &mut out)?;
+        Ok(out)
+    }
+}
+
+/// Variables visible while rendering, innermost loop last
+struct Scope {
+    frames: Vec<HashMap<String, ScriptValue>>,
+}
+
+impl Scope {
+    fn lookup(&self, path: &str) -> Option<ScriptValue> {
+        let mut parts = path.split('.');
+        let name = parts.next()?;
+        let mut value = self
+            .frames
+            .iter()
+            .rev()
+            .find_map(|frame| frame.get(name))?
+            .clone();
+        for part in parts {
+            value = field(&value, part)?;
+        }
+        Some(value)
+    }
+
+    fn resolve(&self, operand: &Operand) -> Option<ScriptValue> {
+        match operand {
+            Operand::Path(path) => self.lookup(path),
+            Operand::Literal(value) => Some(value.clone()),
+        }
+    }
+
+    fn render(&mut self, nodes: &[Node], out: &mut String) -> Result<(), String> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Output {
+                    value,
+                    filters,
+                    line,
+                } => {
+                    let mut current = self.resolve(value);
+                    for filter in filters {
+                        current = self.filter(filter, current, value, *line)?;
+                    }
+                    let value = current.ok_or_else(|| undefined(value, *line))?;
+                    out.push_str(&display(&value));
+                }
+                Node::If {
+                    branches,
+                    otherwise,
+                    line,
+                } => {
+                    let mut taken = None;
+                    for (condition, body) in branches {
+                        if self.condition(condition, *line)? {
+                            taken = Some(body);
+                            break;
+                        }
+                    }
+                    self.render(taken.unwrap_or(otherwise), out)?;
+                }
+                Node::For {
+                    key,
+                    value,
+                    items,
+                    body,
+                    empty,
+                    line,
+                } => {
+                    let collection = self.resolve(items).ok_or_else(|| undefined(items, *line))?;
+                    let entries = entries(&collection).ok_or_else(|| {
+                        format!(
+                            "line {}: Cannot loop over {:?}",
+                            line,
+                            collection.get_type()
+                        )
+                    })?;
+                    if entries.is_empty() {
+                        self.render(empty, out)?;
+                        continue;
+                    }
+
+                    let length = entries.len();
+                    for (index, (entry_key, entry_value)) in entries.into_iter().enumerate() {
+                        let mut frame = HashMap::new();
+                        match key {
+                            Some(key) => {
+                                frame.insert(key.clone(), entry_key);
+                                frame.insert(value.clone(), entry_value);
+                            }
+                            // A single name gets an array's items and a map's keys
+                            None if matches!(entry_key, ScriptValue::String(_)) => {
+                                frame.insert(value.clone(), entry_key);
+                            }
+                            None => {
+                                frame.insert(value.clone(), entry_value);
+                            }
+                        }
+                        frame.insert("loop".to_string(), loop_object(index, length));
+
+                        self.frames.push(frame);
+                        let rendered = self.render(body, out);
+                        self.frames.pop();
+                        rendered?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn condition(&self, condition: &Condition, line: usize) -> Result<bool, String> {
+        for tests in condition {
+            let mut all = true;
+            for test in tests {
+                if !self.test(test, line)? {
+                    all = false;
+                    break;
+                }
+            }
+            if all {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn test(&self, test: &Test, line: usize) -> Result<bool, String> {
+        let result = match &test.compare {
+            None => self.resolve(&test.left).is_some_and(|value| truthy(&value)),
+            Some((compare, right)) => {
+                let left_value = self
+                    .resolve(&test.left)
+                    .ok_or_else(|| undefined(&test.left, line))?;
+                let right_value = self.resolve(right).ok_or_else(|| undefined(right, line))?;
+                compare_values(&left_value, *compare, &right_value).ok_or_else(|| {
+                    format!(
+                        "line {}: Cannot order {:?} and {:?}",
+                        line,
+                        left_value.get_type(),
+                        right_value.get_type()
+                    )
+                })?
+            }
+        };
+        Ok(result != test.negate)
+    }
+
+    fn filter(
+        &self,
+        filter: &Filter,
+        value: Option<ScriptValue>,
+        operand: &Operand,
+        line: usize,
+    ) -> Result<Option<ScriptValue>, String> {
+        if filter.name == "default" {
+            let missing = match &value {
+                None => true,
+                Some(ScriptValue::Option(option)) => matches!(**option, ScriptOption::None),
+                Some(_) => false,
+            };
+            return Ok(if missing {
+                filter.arg.as_ref().and_then(|arg| self.resolve(arg))
+            } else {
+                value
+            });
+        }
+
+        let value = value.ok_or_else(|| undefined(operand, line))?;
+        let result = match filter.name.as_str() {
+            "upper" => string_value(&display(&value).to_uppercase()),
+            "lower" => string_value(&display(&value).to_lowercase()),
+            "trim" => string_value(display(&value).trim()),
+            "html" => string_value(&escape_html(&display(&value))),
+            "length" => {
+                let length = match &value {
+                    ScriptValue::String(s) => s.as_str().chars().count(),
+                    other => entries(other).map(|entries| entries.len()).ok_or_else(|| {
+                        format!("line {}: {:?} has no length", line, other.get_type())
+                    })?,
+                };
+                ScriptValue::I64(length as i64)
+            }
+            _ => {
+                let separator = match filter.arg.as_ref().and_then(|arg| self.resolve(arg)) {
+                    Some(separator) => display(&separator),
+                    None => String::new(),
+                };
+                let ScriptValue::Array(items) = &value else {
+                    return Err(format!(
+                        "line {}: join expects an array, got {:?}",
+                        line,
+                        value.get_type()
+                    ));
+                };
+                let items: Vec<String> = items
+                    .to_vec()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(display)
+                    .collect();
+                string_value(&items.join(&separator))
+            }
+        };
+        Ok(Some(result))
+    }
+}
+
+fn undefined(operand: &Operand, line: usize) -> String {
+    match operand {
+        Operand::Path(path) => format!("line {}: Undefined variable '{}'", line, path),
+        Operand::Literal(_) => format!("line {}: Undefined value", line),
+    }
+}
+
+/// A field of an object or map, or an item of an array
+fn field(value: &ScriptValue, key: &str) -> Option<ScriptValue> {
+    match value {
+        ScriptValue::Object(fields) => fields.get(key).cloned(),
+        ScriptValue::HashMap(map) => map.get(key).ok().flatten(),
+        ScriptValue::Array(items) => items.get(key.parse().ok()?).ok().flatten(),
+        ScriptValue::Option(option) => match &**option {
+            ScriptOption::Some(inner) => field(inner, key),
+            ScriptOption::None => None,
+        },
+        _ => None,
+    }
+}
+
+/// The index or key and value of each item of a collection, maps in key
+/// order
+fn entries(value: &ScriptValue) -> Option<Vec<(ScriptValue, ScriptValue)>> {
+    let mut pairs: Vec<(String, ScriptValue)> = match value {
+        ScriptValue::Array(items) => {
+            return Some(
+                items
+                    .to_vec()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, item)| (ScriptValue::I64(index as i64), item))
+                    .collect(),
+            )
+        }
+        ScriptValue::Object(fields) => fields
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect(),
+        ScriptValue::HashMap(map) => map.iter().unwrap_or_default(),
+        _ => return None,
+    };
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    Some(
+        pairs
+            .into_iter()
+            .map(|(key, value)| (string_value(&key), value))
+            .collect(),
+    )
+}
+
+fn loop_object(index: usize, length: usize) -> ScriptValue {
+    let mut fields = HashMap::new();
+    fields.insert("index".to_string(), ScriptValue::I64(index as i64 + 1));
+    fields.insert("index0".to_string(), ScriptValue::I64(index as i64));
+    fields.insert("first".to_string(), ScriptValue::Bool(index == 0));
+    fields.insert("last".to_string(), ScriptValue::Bool(index + 1 == length));
+    fields.insert("length".to_string(), ScriptValue::I64(length as i64));
+    ScriptValue::Object(ScriptRc::new(fields))
+}
+
+fn truthy(value: &ScriptValue) -> bool {
+    match value {
+        ScriptValue::Bool(b) => *b,
+        ScriptValue::Unit => false,
+        ScriptValue::String(s) => !s.as_str().is_empty(),
+        ScriptValue::Option(option) => match &**option {
+            ScriptOption::Some(inner) => truthy(inner),
+            ScriptOption::None => false,
+        },
+        other => match (number(other), entries(other)) {
+            (Some(n), _) => n != 0.0,
+            (_, Some(entries)) => !entries.is_empty(),
+            _ => true,
+        },
+    }
+}
+
+fn number(value: &ScriptValue) -> Option<f64> {
+    match value {
+        ScriptValue::I32(n) => Some(*n as f64),
+        ScriptValue::I64(n) => Some(*n as f64),
+        ScriptValue::U32(n) => Some(*n as f64),
+        ScriptValue::U64(n) => Some(*n as f64),
+        ScriptValue::F32(n) => Some(*n as f64),
+        ScriptValue::F64(n) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Compare two values, or `None` if they cannot be ordered
+fn compare_values(left: &ScriptValue, compare: Compare, right: &ScriptValue) -> Option<bool> {
+    let ordering = match (left, right) {
+        (ScriptValue::String(a), ScriptValue::String(b)) => Some(a.as_str().cmp(b.as_str())),
+        (ScriptValue::Bool(a), ScriptValue::Bool(b)) if a == b => Some(std::cmp::Ordering::Equal),
+        _ => match (number(left), number(right)) {
+            (Some(a), Some(b)) => a.partial_cmp(&b),
+            _ => None,
+        },
+    };
+    match (compare, ordering) {
+        (Compare::Eq, ordering) => Some(ordering == Some(std::cmp::Ordering::Equal)),
+        (Compare::Ne, ordering) => Some(ordering != Some(std::cmp::Ordering::Equal)),
+        (_, None) => None,
+        (Compare::Lt, Some(ordering)) => Some(ordering.is_lt()),
+        (Compare::Le, Some(ordering)) => Some(ordering.is_le()),
+        (Compare::Gt, Some(ordering)) => Some(ordering.is_gt()),
+        (Compare::Ge, Some(ordering)) => Some(ordering.is_ge()),
+    }
+}
+
+/// Text inserted for a value; `Some` inserts its value and `None` nothing
+fn display(value: &ScriptValue) -> String {
+    match value {
+        ScriptValue::Unit => String::new(),
+        ScriptValue::Option(option) => match &**option {
+            ScriptOption::Some(inner) => display(inner),
+            ScriptOption::None => String::new(),
+        },
+        other => format_value(other),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    ScriptString::from_str(text)
+        .escape_html()
+        .as_str()
+        .to_string()
+}
+
+/// Render a template with an object or map of variables
+pub fn template_render_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "template_render expects 2 arguments, got {}",
+            args.len()
+        )));
+    }
+    let source = match &args[0] {
+        ScriptValue::String(s) => s.as_str(),
+        other => {
+            return Err(RuntimeError::InvalidOperation(format!(
+                "template_render expects a string template, got {:?}",
+                other.get_type()
+            )))
+        }
+    };
+    let data = match &args[1] {
+        ScriptValue::Object(fields) => (**fields).clone(),
+        ScriptValue::HashMap(map) => map.iter().unwrap_or_default().into_iter().collect(),
+        ScriptValue::Unit => HashMap::new(),
+        other => {
+            return Err(RuntimeError::InvalidOperation(format!(
+                "template_render expects an object or map of variables, got {:?}",
+                other.get_type()
+            )))
+        }
+    };
+
+    let result = match Template::parse(source) {
+        Ok(template) => match template.render(&data) {
+            Ok(text) => ScriptResult::ok(string_value(&text)),
+            Err(message) => ScriptResult::err(error::error_object(
+                ErrorKind::InvalidData,
+                &message,
+                None,
+                None,
+            )),
+        },
+        Err(message) => {
+            ScriptResult::err(error::error_object(ErrorKind::Parse, &message, None, None))
+        }
+    };
+    Ok(ScriptValue::Result(ScriptRc::new(result)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::ScriptVec;
+
+    fn render(source: &str, data: &[(&str, ScriptValue)]) -> Result<String, String> {
+        let data = data
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        Template::parse(source)?.render(&data)
+    }
+
+    fn array(items: Vec<ScriptValue>) -> ScriptValue {
+        ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(items)))
+    }
+
+    fn object(fields: &[(&str, ScriptValue)]) -> ScriptValue {
+        ScriptValue::Object(ScriptRc::new(
+            fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+        ))
+    }
+
+    #[test]
+    fn test_interpolation_and_filters() {
+        let user = object(&[
+            ("name", string_value("  Ada ")),
+            ("tags", array(vec![string_value("a"), string_value("b")])),
+        ]);
+        let data = [("user", user), ("html", string_value("<b>&</b>"))];
+
+        assert_eq!(
+            render("Hi {{ user.name | trim | upper }}!", &data).unwrap(),
+            "Hi ADA!"
+        );
+        assert_eq!(
+            render("{{ html | html }}", &data).unwrap(),
+            "&lt;b&gt;&amp;&lt;/b&gt;"
+        );
+        assert_eq!(
+            render(
+                "{{ user.tags | join(\", \") }} {{ user.tags | length }} {{ user.tags.1 }}",
+                &data
+            )
+            .unwrap(),
+            "a, b 2 b"
+        );
+        assert_eq!(
+            render("{{ user.email | default(\"none\") }}{# hidden #}", &data).unwrap(),
+            "none"
+        );
+        assert_eq!(render("{{ \"{{\" }}", &data).unwrap(), "{{");
+
+        assert_eq!(
+            render("\n{{ user.email }}", &data).unwrap_err(),
+            "line 2: Undefined variable 'user.email'"
+        );
+        assert!(render("{{ name | shout }}", &data)
+            .unwrap_err()
+            .contains("Unknown filter 'shout'"));
+        assert!(render("{{ name", &data)
+            .unwrap_err()
+            .contains("never closed"));
+    }
+
+    #[test]
+    fn test_conditions() {
+        let data = [
+            ("count", ScriptValue::I32(3)),
+            ("name", string_value("ada")),
+            ("items", array(Vec::new())),
+        ];
+        let check = |condition: &str| {
+            render(
+                &format!("{{% if {} %}}yes{{% else %}}no{{% endif %}}", condition),
+                &data,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(check("count > 2 and name == \"ada\""), "yes");
+        assert_eq!(check("count >= 4 or not items"), "yes");
+        assert_eq!(check("items or missing"), "no");
+        assert_eq!(check("count != 3"), "no");
+        assert_eq!(
+            render(
+                "{% if count < 1 %}none{% elif count < 5 %}few{% else %}many{% endif %}",
+                &data
+            )
+            .unwrap(),
+            "few"
+        );
+        assert!(render("{% if name < 3 %}{% endif %}", &data)
+            .unwrap_err()
+            .contains("Cannot order"));
+        assert_eq!(
+            render("{% if count %}\n{% for x in items %}{% endif %}", &data).unwrap_err(),
+            "line 2: Unexpected '{% endif %}'"
+        );
+        assert_eq!(
+            render("{% if count %}", &data).unwrap_err(),
+            "line 1: '{% if %}' is never closed with '{% endif %}'"
+        );
+    }
+
+    #[test]
+    fn test_loops_and_standalone_lines() {
+        let fields = object(&[
+            ("id", string_value("i32")),
+            ("name", string_value("String")),
+        ]);
+        let data = [
+            ("fields", fields),
+            (
+                "items",
+                array(vec![
+                    ScriptValue::I32(1),
+                    ScriptValue::I32(2),
+                    ScriptValue::I32(3),
+                ]),
+            ),
+            ("none", array(Vec::new())),
+        ];
+
+        let source = "struct User {\n    {% for name, type in fields %}\n    {{ name }}: {{ type }},\n    {% endfor %}\n}\n";
+        assert_eq!(
+            render(source, &data).unwrap(),
+            "struct User {\n    id: i32,\n    name: String,\n}\n"
+        );
+        assert_eq!(
+            render(
+                "{% for n in items %}{{ loop.index }}={{ n }}{% if not loop.last %}, {% endif %}{% endfor %}",
+                &data
+            )
+            .unwrap(),
+            "1=1, 2=2, 3=3"
+        );
+        assert_eq!(
+            render("{% for key in fields %}{{ key }} {% endfor %}", &data).unwrap(),
+            "id name "
+        );
+        assert_eq!(
+            render(
+                "{% for n in none %}{{ n }}{% else %}empty{% endfor %}",
+                &data
+            )
+            .unwrap(),
+            "empty"
+        );
+        assert_eq!(
+            render("a  {%- for n in items -%}  {{ n }} {%- endfor %}", &data).unwrap(),
+            "a123"
+        );
+        assert!(render("{% for n in fields.id %}{% endfor %}", &data)
+            .unwrap_err()
+            .contains("Cannot loop over"));
+    }
+}
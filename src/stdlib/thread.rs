@@ -0,0 +1,218 @@
+//! Threads for Script
+//!
+//! Closures run on OS threads from the runtime's `ThreadPool`:
+//! - `thread_spawn(f)` starts `f` and returns a `Thread<T>` handle
+//! - `thread_join(t)` waits for the thread and returns its result
+//! - `thread_scope(body)` calls `body` with a `ThreadScope`; threads started
+//!   with `scope_spawn(scope, f)` are all joined before `thread_scope`
+//!   returns, so none of them outlive the scope
+//!
+//! How many threads may run at once is limited by the `allow_threads` and
+//! `max_threads` fields of the active `SecurityPolicy`; spawning past the
+//! limit is an error rather than a wait.
+
+use crate::runtime::{
+    deterministic, JoinHandle, ResourceKind, Result as RuntimeResult, RuntimeError, ScriptRc,
+    ThreadPool, TrackedResource,
+};
+use crate::stdlib::functional::execute_script_closure;
+use crate::stdlib::{handle_id, ScriptValue};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+/// Object key holding a thread's id
+const THREAD_ID_KEY: &str = "_thread_id";
+
+/// Object key holding a thread scope's id
+const SCOPE_ID_KEY: &str = "_scope_id";
+
+static NEXT_ID: AtomicI32 = AtomicI32::new(1);
+
+type ThreadResult = crate::error::Result<ScriptValue>;
+
+/// Threads that have not been joined yet, keyed by the id in their handle
+fn threads() -> MutexGuard<'static, HashMap<i32, JoinHandle<ThreadResult>>> {
+    static THREADS: OnceLock<Mutex<HashMap<i32, JoinHandle<ThreadResult>>>> = OnceLock::new();
+    THREADS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Open scopes and the threads started in each
+fn scopes() -> MutexGuard<'static, HashMap<i32, Vec<i32>>> {
+    static SCOPES: OnceLock<Mutex<HashMap<i32, Vec<i32>>>> = OnceLock::new();
+    SCOPES
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Start `closure` on a pool thread and register its handle
+fn spawn_closure(closure: &ScriptValue) -> RuntimeResult<i32> {
+    if !matches!(closure, ScriptValue::Closure(_)) {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "Expected a closure, got {:?}",
+            closure.get_type()
+        )));
+    }
+
+    let closure = closure.clone();
+    // A thread started under a virtual clock (as in a `virtual_time` test)
+    // shares that clock
+    let clock = deterministic::virtual_clock();
+    let resource = TrackedResource::new(ResourceKind::Task, || "thread".to_string());
+    let handle = ThreadPool::global().spawn(move || {
+        let _resource = resource;
+        let previous = deterministic::install_clock(clock);
+        let result = execute_script_closure(&closure, &[]);
+        deterministic::install_clock(previous);
+        result
+    })?;
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    threads().insert(id, handle);
+    Ok(id)
+}
+
+fn handle(key: &str, id: i32) -> ScriptValue {
+    let mut fields = HashMap::new();
+    fields.insert(key.to_string(), ScriptValue::I32(id));
+    ScriptValue::Object(ScriptRc::new(fields))
+}
+
+/// Wait for a thread and return its result
+fn join_thread(id: i32) -> RuntimeResult<ScriptValue> {
+    let handle = threads().remove(&id).ok_or_else(|| {
+        RuntimeError::InvalidOperation("Thread has already been joined".to_string())
+    })?;
+    handle
+        .join()?
+        .map_err(|e| RuntimeError::InvalidOperation(e.to_string()))
+}
+
+/// Start a closure on a new thread
+pub fn thread_spawn_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "thread_spawn expects 1 argument, got {}",
+            args.len()
+        )));
+    }
+
+    let id = spawn_closure(&args[0])?;
+    Ok(handle(THREAD_ID_KEY, id))
+}
+
+/// Wait for a thread to finish and return its result
+///
+/// An error or panic in the thread is returned as the error of `thread_join`.
+pub fn thread_join_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "thread_join expects 1 argument, got {}",
+            args.len()
+        )));
+    }
+
+    join_thread(handle_id(&args[0], THREAD_ID_KEY, "a thread")?)
+}
+
+/// Call a closure with a scope and join every thread started in it
+///
+/// Returns the closure's result, or the first error from the closure or
+/// from a scoped thread. Threads are joined even when the closure fails.
+pub fn thread_scope_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "thread_scope expects 1 argument, got {}",
+            args.len()
+        )));
+    }
+
+    let scope_id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    scopes().insert(scope_id, Vec::new());
+
+    let mut result = execute_script_closure(&args[0], &[handle(SCOPE_ID_KEY, scope_id)])
+        .map_err(|e| RuntimeError::InvalidOperation(e.to_string()));
+
+    // Scoped threads may start more scoped threads, so keep joining until
+    // none are left; the scope closes in the same step that finds it empty
+    loop {
+        let pending = {
+            let mut scopes = scopes();
+            let pending = scopes
+                .get_mut(&scope_id)
+                .map(std::mem::take)
+                .unwrap_or_default();
+            if pending.is_empty() {
+                scopes.remove(&scope_id);
+                break;
+            }
+            pending
+        };
+
+        for id in pending {
+            // Threads already joined by the body are no longer registered
+            if !threads().contains_key(&id) {
+                continue;
+            }
+            if let Err(error) = join_thread(id) {
+                if result.is_ok() {
+                    result = Err(error);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Start a closure on a thread that is joined when its scope ends
+pub fn scope_spawn_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "scope_spawn expects 2 arguments, got {}",
+            args.len()
+        )));
+    }
+
+    let scope_id = handle_id(&args[0], SCOPE_ID_KEY, "a thread scope")?;
+    // Hold the scope lock while spawning so the scope cannot close in between
+    let mut scopes = scopes();
+    let threads = scopes.get_mut(&scope_id).ok_or_else(|| {
+        RuntimeError::InvalidOperation("Thread scope has already ended".to_string())
+    })?;
+    let id = spawn_closure(&args[1])?;
+    threads.push(id);
+    Ok(handle(THREAD_ID_KEY, id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_handles() {
+        assert!(thread_spawn_impl(&[ScriptValue::I32(1)]).is_err());
+        assert!(thread_join_impl(&[ScriptValue::I32(1)]).is_err());
+        assert!(thread_join_impl(&[handle(THREAD_ID_KEY, -1)]).is_err());
+        assert!(scope_spawn_impl(&[handle(SCOPE_ID_KEY, -1), ScriptValue::Unit]).is_err());
+    }
+
+    #[test]
+    fn test_join_runs_each_thread_once() {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let join = ThreadPool::global()
+            .spawn(|| Ok(ScriptValue::I32(42)))
+            .unwrap();
+        threads().insert(id, join);
+
+        let thread = handle(THREAD_ID_KEY, id);
+        assert_eq!(
+            thread_join_impl(&[thread.clone()]).unwrap(),
+            ScriptValue::I32(42)
+        );
+        assert!(thread_join_impl(&[thread]).is_err());
+    }
+}
@@ -6,21 +6,23 @@
 //! - Frame rate helpers
 //! - Stopwatch/timer functionality
 //! - Time formatting utilities
+//!
+//! All readings come from the runtime clock in
+//! `crate::runtime::deterministic`, so under a virtual clock (deterministic
+//! mode or `@test(virtual_time)`) they follow that clock and `sleep`
+//! advances it instead of blocking. `time_advance` moves a virtual clock
+//! forward explicitly, firing any async timers that come due.
 
-use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::runtime::{deterministic, Result as RuntimeResult, RuntimeError, ScriptRc};
 use crate::stdlib::{ScriptString, ScriptValue};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
-use std::sync::OnceLock;
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-
-/// Global application start time
-static APP_START_TIME: OnceLock<Instant> = OnceLock::new();
+use std::time::Duration;
 
 /// Stopwatch for measuring elapsed time
 pub struct Stopwatch {
-    start_time: Option<Instant>,
+    start_time: Option<Duration>,
     accumulated: Duration,
     is_running: bool,
 }
@@ -38,7 +40,7 @@ impl Stopwatch {
     /// Start or resume the stopwatch
     pub fn start(&mut self) {
         if !self.is_running {
-            self.start_time = Some(Instant::now());
+            self.start_time = Some(deterministic::monotonic_now());
             self.is_running = true;
         }
     }
@@ -47,7 +49,7 @@ impl Stopwatch {
     pub fn stop(&mut self) {
         if self.is_running {
             if let Some(start) = self.start_time {
-                self.accumulated += start.elapsed();
+                self.accumulated += deterministic::monotonic_now().saturating_sub(start);
             }
             self.is_running = false;
             self.start_time = None;
@@ -57,7 +59,7 @@ impl Stopwatch {
     /// Reset the stopwatch
     pub fn reset(&mut self) {
         self.start_time = if self.is_running {
-            Some(Instant::now())
+            Some(deterministic::monotonic_now())
         } else {
             None
         };
@@ -68,7 +70,7 @@ impl Stopwatch {
     pub fn elapsed(&self) -> f32 {
         let current = if self.is_running {
             if let Some(start) = self.start_time {
-                self.accumulated + start.elapsed()
+                self.accumulated + deterministic::monotonic_now().saturating_sub(start)
             } else {
                 self.accumulated
             }
@@ -92,18 +94,18 @@ impl Stopwatch {
 
 /// Frame timer for game loops
 pub struct FrameTimer {
-    last_frame_time: Instant,
+    last_frame_time: Duration,
     delta_time: f32,
     fps: f32,
     frame_count: u32,
-    fps_update_time: Instant,
+    fps_update_time: Duration,
     fps_frame_count: u32,
 }
 
 impl FrameTimer {
     /// Create a new frame timer
     pub fn new() -> Self {
-        let now = Instant::now();
+        let now = deterministic::monotonic_now();
         FrameTimer {
             last_frame_time: now,
             delta_time: 0.0,
@@ -116,15 +118,15 @@ impl FrameTimer {
 
     /// Update the frame timer (call once per frame)
     pub fn update(&mut self) {
-        let now = Instant::now();
-        let elapsed = now.duration_since(self.last_frame_time);
+        let now = deterministic::monotonic_now();
+        let elapsed = now.saturating_sub(self.last_frame_time);
         self.delta_time = elapsed.as_secs_f32();
         self.last_frame_time = now;
         self.frame_count += 1;
         self.fps_frame_count += 1;
 
         // Update FPS every second
-        let fps_elapsed = now.duration_since(self.fps_update_time);
+        let fps_elapsed = now.saturating_sub(self.fps_update_time);
         if fps_elapsed.as_secs_f32() >= 1.0 {
             self.fps = self.fps_frame_count as f32 / fps_elapsed.as_secs_f32();
             self.fps_update_time = now;
@@ -152,31 +154,25 @@ impl FrameTimer {
 
 /// Get current time in seconds since application start
 pub fn time_now_impl(_args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
-    let start_time = APP_START_TIME.get_or_init(|| Instant::now());
-    let elapsed = start_time.elapsed().as_secs_f32();
+    let elapsed = deterministic::monotonic_now().as_secs_f32();
     Ok(ScriptValue::F32(elapsed))
 }
 
 /// Get current time in milliseconds since application start
 pub fn time_now_millis_impl(_args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
-    let start_time = APP_START_TIME.get_or_init(|| Instant::now());
-    let elapsed = start_time.elapsed().as_secs_f32() * 1000.0;
+    let elapsed = deterministic::monotonic_now().as_secs_f32() * 1000.0;
     Ok(ScriptValue::F32(elapsed))
 }
 
 /// Get current Unix timestamp in seconds
 pub fn time_unix_impl(_args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| RuntimeError::InvalidOperation(format!("Time error: {}", e)))?;
+    let duration = deterministic::system_now();
     Ok(ScriptValue::F32(duration.as_secs_f32()))
 }
 
 /// Get current Unix timestamp in milliseconds
 pub fn time_unix_millis_impl(_args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
-    let duration = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map_err(|e| RuntimeError::InvalidOperation(format!("Time error: {}", e)))?;
+    let duration = deterministic::system_now();
     Ok(ScriptValue::F32(duration.as_millis() as f32))
 }
 
@@ -211,10 +207,38 @@ pub fn sleep_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
         ));
     }
 
-    std::thread::sleep(Duration::from_millis(millis as u64));
+    deterministic::sleep(Duration::from_millis(millis as u64));
     Ok(ScriptValue::Unit)
 }
 
+/// Move the virtual clock forward by the given milliseconds
+///
+/// Fails when the clock is real, since real time cannot be skipped.
+pub fn time_advance_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "time_advance expects 1 argument, got {}",
+            args.len()
+        )));
+    }
+
+    let millis = args[0].to_f32()?;
+    if millis < 0.0 {
+        return Err(RuntimeError::InvalidOperation(
+            "Time can only be advanced forward".to_string(),
+        ));
+    }
+
+    if deterministic::advance_clock(Duration::from_secs_f64(millis as f64 / 1000.0)) {
+        Ok(ScriptValue::Unit)
+    } else {
+        Err(RuntimeError::InvalidOperation(
+            "time_advance needs a virtual clock; use @test(virtual_time) or --deterministic"
+                .to_string(),
+        ))
+    }
+}
+
 /// Create a new stopwatch
 pub fn stopwatch_new_impl(_args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
     let _stopwatch = Rc::new(RefCell::new(Stopwatch::new()));
@@ -392,8 +416,7 @@ pub fn format_time_millis_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValu
 
 /// Performance counter for precise timing
 pub fn perf_counter_impl(_args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
-    // High precision timer using Instant
-    let elapsed = Instant::now().duration_since(Instant::now() - Duration::from_secs(0));
+    let elapsed = deterministic::monotonic_now();
     Ok(ScriptValue::F32(elapsed.as_secs_f64() as f32))
 }
 
@@ -434,6 +457,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_time_advance() {
+        assert!(time_advance_impl(&[ScriptValue::F32(10.0)]).is_err());
+
+        let clock = std::sync::Arc::new(deterministic::VirtualClock::new());
+        let previous = deterministic::install_clock(Some(clock.clone()));
+        time_advance_impl(&[ScriptValue::F32(1500.0)]).unwrap();
+        sleep_impl(&[ScriptValue::F32(500.0)]).unwrap();
+        assert_eq!(time_now_impl(&[]).unwrap(), ScriptValue::F32(2.0));
+        assert!(time_advance_impl(&[ScriptValue::F32(-1.0)]).is_err());
+        deterministic::install_clock(previous);
+    }
+
     #[test]
     fn test_time_delta() {
         let t1 = ScriptValue::F32(100.0);
@@ -0,0 +1,175 @@
+//! Semantic versions for Script
+//!
+//! These functions expose the package manager's `Version` and
+//! `VersionConstraint` so that release scripts and other tooling agree with
+//! `manuscript` about what a version means. `semver_parse("1.4.0-rc.1+abc")`
+//! returns an object:
+//!
+//! ```text
+//! { major: 1, minor: 4, patch: 0, pre: "rc.1", build: "abc",
+//!   text: "1.4.0-rc.1+abc" }
+//! ```
+//!
+//! `pre` and `build` are empty strings when absent. `semver_compare` orders
+//! versions by semver precedence, so a prerelease comes before its release,
+//! and `semver_matches` checks a version against a constraint written as in
+//! a manifest, such as `"^1.2.0"`, `">=1.0.0, <2.0.0"` or `"1.*"`.
+
+use crate::package::{PackageError, Version, VersionConstraint};
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::error::{self, ErrorKind};
+use crate::stdlib::{expect_args, string_arg, string_value, ScriptResult, ScriptValue};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Parse a version, describing why it is invalid
+fn parse_version(text: &str) -> Result<Version, String> {
+    Version::parse(text.trim()).map_err(|e| format!("Invalid version '{}': {}", text, e))
+}
+
+/// Parse a constraint, describing why it is invalid
+fn parse_constraint(text: &str) -> Result<VersionConstraint, String> {
+    VersionConstraint::parse(text).map_err(|e| {
+        let reason = match e {
+            PackageError::ManifestParse(message) => message,
+            PackageError::SemVer(e) => e.to_string(),
+            other => other.to_string(),
+        };
+        format!("Invalid version constraint '{}': {}", text, reason)
+    })
+}
+
+fn join<T: ToString>(parts: &[T]) -> String {
+    parts
+        .iter()
+        .map(|part| part.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn version_to_value(version: &Version) -> ScriptValue {
+    let mut fields = HashMap::new();
+    for (name, value) in [
+        ("major", version.major),
+        ("minor", version.minor),
+        ("patch", version.patch),
+    ] {
+        fields.insert(name.to_string(), ScriptValue::I32(value as i32));
+    }
+    fields.insert("pre".to_string(), string_value(&join(&version.pre)));
+    fields.insert("build".to_string(), string_value(&join(&version.build)));
+    fields.insert("text".to_string(), string_value(&version.to_string()));
+    ScriptValue::Object(ScriptRc::new(fields))
+}
+
+/// Wrap a value or a parse failure in a Script Result
+fn parse_result(result: Result<ScriptValue, String>) -> ScriptValue {
+    let result = match result {
+        Ok(value) => ScriptResult::ok(value),
+        Err(message) => {
+            ScriptResult::err(error::error_object(ErrorKind::Parse, &message, None, None))
+        }
+    };
+    ScriptValue::Result(ScriptRc::new(result))
+}
+
+/// Implementation of semver_parse for the stdlib registry
+pub fn semver_parse_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "semver_parse expects 1 argument, got {}",
+            args.len()
+        )));
+    }
+    let text = string_arg(args, 0, "semver_parse")?;
+    Ok(parse_result(
+        parse_version(text).map(|version| version_to_value(&version)),
+    ))
+}
+
+/// Implementation of semver_compare for the stdlib registry
+pub fn semver_compare_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "semver_compare")?;
+    let a = string_arg(args, 0, "semver_compare")?;
+    let b = string_arg(args, 1, "semver_compare")?;
+
+    let ordering = parse_version(a).and_then(|a| Ok(a.cmp(&parse_version(b)?)));
+    Ok(parse_result(ordering.map(|ordering| {
+        ScriptValue::I32(match ordering {
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
+            Ordering::Greater => 1,
+        })
+    })))
+}
+
+/// Implementation of semver_matches for the stdlib registry
+pub fn semver_matches_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "semver_matches")?;
+    let version = string_arg(args, 0, "semver_matches")?;
+    let constraint = string_arg(args, 1, "semver_matches")?;
+
+    let matches = parse_version(version)
+        .and_then(|version| Ok(parse_constraint(constraint)?.matches(&version)));
+    Ok(parse_result(matches.map(ScriptValue::Bool)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok_value(result: ScriptValue) -> ScriptValue {
+        match result {
+            ScriptValue::Result(result) => match &*result {
+                ScriptResult::Ok(value) => value.clone(),
+                ScriptResult::Err(error) => panic!("expected Ok, got {:?}", error),
+            },
+            other => panic!("expected a result, got {:?}", other),
+        }
+    }
+
+    fn is_err(result: ScriptValue) -> bool {
+        matches!(result, ScriptValue::Result(result) if result.is_err())
+    }
+
+    #[test]
+    fn test_semver_functions() {
+        let ScriptValue::Object(fields) =
+            ok_value(semver_parse_impl(&[string_value("1.4.0-rc.1+abc")]).unwrap())
+        else {
+            panic!("expected a version object");
+        };
+        assert!(matches!(fields.get("minor"), Some(ScriptValue::I32(4))));
+        assert!(matches!(fields.get("pre"), Some(ScriptValue::String(s)) if s.as_str() == "rc.1"));
+        assert!(matches!(fields.get("build"), Some(ScriptValue::String(s)) if s.as_str() == "abc"));
+        assert!(is_err(semver_parse_impl(&[string_value("1.4")]).unwrap()));
+
+        let compare = |a: &str, b: &str| match ok_value(
+            semver_compare_impl(&[string_value(a), string_value(b)]).unwrap(),
+        ) {
+            ScriptValue::I32(n) => n,
+            other => panic!("expected an i32, got {:?}", other),
+        };
+        assert_eq!(compare("1.2.3", "1.10.0"), -1);
+        assert_eq!(compare("2.0.0", "2.0.0-beta"), 1);
+        assert_eq!(compare("0.3.1", "0.3.1"), 0);
+
+        let matches = |version: &str, constraint: &str| {
+            semver_matches_impl(&[string_value(version), string_value(constraint)]).unwrap()
+        };
+        assert!(matches!(
+            ok_value(matches("1.5.2", "^1.2.0")),
+            ScriptValue::Bool(true)
+        ));
+        assert!(matches!(
+            ok_value(matches("2.0.0", ">=1.0.0, <2.0.0")),
+            ScriptValue::Bool(false)
+        ));
+        assert!(matches!(
+            ok_value(matches("1.9.0", "1.*")),
+            ScriptValue::Bool(true)
+        ));
+        assert!(is_err(matches("1.0.0", ">=banana")));
+        assert!(is_err(matches("one", "^1.0.0")));
+    }
+}
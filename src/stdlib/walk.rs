@@ -0,0 +1,687 @@
+//! Glob matching and file tree walking for Script
+//!
+//! `glob(pattern)` returns the sorted paths matching a pattern such as
+//! `"src/**/*.script"`. Patterns are split on `/` and each component may use
+//! `*` (any characters), `?` (one character), `[abc]`, `[a-z]` and `[!abc]`;
+//! a `\` makes the next character literal. A `**` component matches any
+//! number of directories, including none. Names starting with a dot are
+//! only matched by a component that starts with a literal dot, so
+//! `"**/*.script"` skips `.git` and other hidden directories.
+//!
+//! `walk_dir(path, visit)` calls a closure with every entry below `path`,
+//! parents before children and siblings by name. Each entry is an object:
+//!
+//! ```text
+//! { path: "src/lib.script", name: "lib.script", depth: 1,
+//!   is_dir: false, is_file: true, is_symlink: false }
+//! ```
+//!
+//! Returning `false` for a directory skips its contents. By default hidden
+//! entries are skipped, the whole tree is walked and symlinks are not
+//! followed; `walk_dir_with(path, options, visit)` takes an object with
+//! `hidden`, `max_depth` and `follow_symlinks` fields to change that.
+
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc};
+use crate::stdlib::error::{self, io_error_from_std, ErrorKind, IoError, ScriptError};
+use crate::stdlib::functional::execute_script_closure;
+use crate::stdlib::{string_value, ScriptResult, ScriptValue, ScriptVec};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// How `walk_dir` traverses a tree
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WalkOptions {
+    /// Visit entries whose names start with a dot
+    pub include_hidden: bool,
+    /// Deepest level to visit, 1 being the entries of the root itself
+    pub max_depth: Option<usize>,
+    /// Descend into directories behind symlinks
+    pub follow_symlinks: bool,
+}
+
+/// An entry found while walking a tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalkEntry {
+    pub path: String,
+    pub name: String,
+    /// Levels below the root, 1 for the entries of the root itself
+    pub depth: usize,
+    pub is_dir: bool,
+    pub is_file: bool,
+    pub is_symlink: bool,
+}
+
+/// What to do after visiting an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    Continue,
+    /// Don't descend into the directory just visited
+    SkipDir,
+    Stop,
+}
+
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+/// Walk the tree below `root`, calling `visit` with each entry, and return
+/// the number of entries visited
+pub fn walk_dir(
+    root: &Path,
+    options: &WalkOptions,
+    mut visit: impl FnMut(&WalkEntry) -> Visit,
+) -> io::Result<usize> {
+    let mut walker = WalkDir::new(root)
+        .min_depth(1)
+        .follow_links(options.follow_symlinks)
+        .sort_by_file_name();
+    if let Some(max_depth) = options.max_depth {
+        walker = walker.max_depth(max_depth);
+    }
+    let include_hidden = options.include_hidden;
+    let mut entries = walker.into_iter().filter_entry(move |entry| {
+        include_hidden || entry.depth() == 0 || !is_hidden(&entry.file_name().to_string_lossy())
+    });
+
+    let mut visited = 0;
+    while let Some(entry) = entries.next() {
+        let entry = entry?;
+        let file_type = entry.file_type();
+        let walk_entry = WalkEntry {
+            path: entry.path().to_string_lossy().into_owned(),
+            name: entry.file_name().to_string_lossy().into_owned(),
+            depth: entry.depth(),
+            is_dir: file_type.is_dir(),
+            is_file: file_type.is_file(),
+            is_symlink: entry.path_is_symlink(),
+        };
+        visited += 1;
+        match visit(&walk_entry) {
+            Visit::Continue => {}
+            Visit::SkipDir => {
+                if walk_entry.is_dir {
+                    entries.skip_current_dir();
+                }
+            }
+            Visit::Stop => break,
+        }
+    }
+    Ok(visited)
+}
+
+/// A part of a pattern component
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(char),
+    /// `*`
+    Any,
+    /// `?`
+    One,
+    /// `[...]`, as inclusive ranges
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+impl Token {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Literal(literal) => *literal == c,
+            Token::Any | Token::One => true,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|(low, high)| (*low..=*high).contains(&c)) != *negated
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// `**`
+    AnyDepth,
+    Pattern(Vec<Token>),
+}
+
+/// A parsed glob pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glob {
+    /// The leading components without wildcards, where matching starts
+    base: String,
+    segments: Vec<Segment>,
+}
+
+impl Glob {
+    /// Parse a pattern, describing why it is invalid
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        if pattern.is_empty() {
+            return Err("Glob pattern is empty".to_string());
+        }
+
+        let mut base = Vec::new();
+        let mut segments = Vec::new();
+        for component in pattern.split('/') {
+            if component == "**" {
+                segments.push(Segment::AnyDepth);
+                continue;
+            }
+            let tokens = parse_component(component)?;
+            let literal: Option<String> = tokens
+                .iter()
+                .map(|token| match token {
+                    Token::Literal(c) => Some(*c),
+                    _ => None,
+                })
+                .collect();
+            match literal {
+                Some(literal) if segments.is_empty() => base.push(literal),
+                _ => segments.push(Segment::Pattern(tokens)),
+            }
+        }
+
+        // Skip empty components from repeated or trailing slashes, keeping
+        // the leading one of an absolute path
+        segments
+            .retain(|segment| !matches!(segment, Segment::Pattern(tokens) if tokens.is_empty()));
+        let base = if pattern.starts_with('/') {
+            format!("/{}", join_non_empty(&base[1..]))
+        } else {
+            join_non_empty(&base)
+        };
+        Ok(Glob { base, segments })
+    }
+
+    /// Whether `path`, relative to where matching starts, matches the
+    /// wildcard part of the pattern
+    fn matches_relative(&self, path: &str) -> bool {
+        let components: Vec<&str> = path.split('/').collect();
+        match_segments(&self.segments, &components)
+    }
+
+    /// Whether a path matches the whole pattern
+    pub fn matches(&self, path: &str) -> bool {
+        let relative = if self.base.is_empty() {
+            Some(path)
+        } else {
+            path.strip_prefix(&self.base).and_then(|rest| {
+                if rest.is_empty() || self.base.ends_with('/') {
+                    Some(rest)
+                } else {
+                    rest.strip_prefix('/')
+                }
+            })
+        };
+        match relative {
+            Some("") => self.segments.is_empty(),
+            Some(relative) => !self.segments.is_empty() && self.matches_relative(relative),
+            None => false,
+        }
+    }
+
+    /// Whether any component of the pattern asks for hidden names
+    fn allows_hidden(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Pattern(tokens) if tokens.first() == Some(&Token::Literal('.'))))
+    }
+}
+
+fn join_non_empty(components: &[String]) -> String {
+    components
+        .iter()
+        .filter(|component| !component.is_empty())
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn parse_component(component: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = component.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let token = match chars[i] {
+            '*' if tokens.last() == Some(&Token::Any) => {
+                i += 1;
+                continue;
+            }
+            '*' => Token::Any,
+            '?' => Token::One,
+            '\\' => {
+                i += 1;
+                Token::Literal(
+                    *chars
+                        .get(i)
+                        .ok_or("Glob pattern ends with an escape character")?,
+                )
+            }
+            '[' => {
+                let (class, end) = parse_class(&chars, i + 1)?;
+                i = end;
+                class
+            }
+            c => Token::Literal(c),
+        };
+        tokens.push(token);
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+/// Parse a character class starting after its `[`, returning it with the
+/// index of its closing `]`
+fn parse_class(chars: &[char], start: usize) -> Result<(Token, usize), String> {
+    let mut i = start;
+    let negated = matches!(chars.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut ranges = Vec::new();
+    let first = i;
+    loop {
+        let low = *chars.get(i).ok_or("Unclosed '[' in glob pattern")?;
+        // A `]` right after the opening bracket is a member
+        if low == ']' && i > first {
+            return Ok((Token::Class { negated, ranges }, i));
+        }
+        match (chars.get(i + 1), chars.get(i + 2)) {
+            (Some('-'), Some(&high)) if high != ']' => {
+                if high < low {
+                    return Err(format!("Invalid range '{}-{}' in glob pattern", low, high));
+                }
+                ranges.push((low, high));
+                i += 3;
+            }
+            _ => {
+                ranges.push((low, low));
+                i += 1;
+            }
+        }
+    }
+}
+
+fn match_segments(segments: &[Segment], components: &[&str]) -> bool {
+    match segments.split_first() {
+        None => components.is_empty(),
+        Some((Segment::AnyDepth, rest)) => (0..=components.len()).any(|skip| {
+            components[..skip].iter().all(|name| !is_hidden(name))
+                && match_segments(rest, &components[skip..])
+        }),
+        Some((Segment::Pattern(tokens), rest)) => match components.split_first() {
+            Some((name, others)) => match_component(tokens, name) && match_segments(rest, others),
+            None => false,
+        },
+    }
+}
+
+fn match_component(tokens: &[Token], name: &str) -> bool {
+    if is_hidden(name) && tokens.first() != Some(&Token::Literal('.')) {
+        return false;
+    }
+    let chars: Vec<char> = name.chars().collect();
+    match_tokens(tokens, &chars)
+}
+
+fn match_tokens(tokens: &[Token], chars: &[char]) -> bool {
+    match tokens.split_first() {
+        None => chars.is_empty(),
+        Some((Token::Any, rest)) => {
+            (0..=chars.len()).any(|skip| match_tokens(rest, &chars[skip..]))
+        }
+        Some((token, rest)) => match chars.split_first() {
+            Some((c, others)) => token.matches(*c) && match_tokens(rest, others),
+            None => false,
+        },
+    }
+}
+
+/// Find the paths matching a pattern, sorted
+///
+/// A pattern without wildcards matches the path itself if it exists. A
+/// missing base directory matches nothing.
+pub fn glob(pattern: &str) -> Result<Vec<String>, String> {
+    let glob = Glob::new(pattern)?;
+    let root = if glob.base.is_empty() {
+        "."
+    } else {
+        glob.base.as_str()
+    };
+    if glob.segments.is_empty() {
+        return Ok(if Path::new(root).exists() {
+            vec![glob.base]
+        } else {
+            Vec::new()
+        });
+    }
+
+    let options = WalkOptions {
+        include_hidden: glob.allows_hidden(),
+        max_depth: if glob.segments.contains(&Segment::AnyDepth) {
+            None
+        } else {
+            Some(glob.segments.len())
+        },
+        follow_symlinks: false,
+    };
+    let mut matches = Vec::new();
+    let result = walk_dir(Path::new(root), &options, |entry| {
+        let relative = entry
+            .path
+            .strip_prefix(root)
+            .unwrap_or(&entry.path)
+            .trim_start_matches(['/', std::path::MAIN_SEPARATOR]);
+        let relative = relative.replace(std::path::MAIN_SEPARATOR, "/");
+        if glob.matches_relative(&relative) {
+            matches.push(if glob.base.is_empty() {
+                relative
+            } else if glob.base.ends_with('/') {
+                format!("{}{}", glob.base, relative)
+            } else {
+                format!("{}/{}", glob.base, relative)
+            });
+        }
+        Visit::Continue
+    });
+    match result {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !Path::new(root).exists() => {}
+        Err(e) => return Err(format!("Failed to read '{}': {}", root, e)),
+    }
+
+    matches.sort();
+    Ok(matches)
+}
+
+fn entry_to_value(entry: &WalkEntry) -> ScriptValue {
+    let mut fields = HashMap::new();
+    fields.insert("path".to_string(), string_value(&entry.path));
+    fields.insert("name".to_string(), string_value(&entry.name));
+    fields.insert("depth".to_string(), ScriptValue::I32(entry.depth as i32));
+    fields.insert("is_dir".to_string(), ScriptValue::Bool(entry.is_dir));
+    fields.insert("is_file".to_string(), ScriptValue::Bool(entry.is_file));
+    fields.insert(
+        "is_symlink".to_string(),
+        ScriptValue::Bool(entry.is_symlink),
+    );
+    ScriptValue::Object(ScriptRc::new(fields))
+}
+
+/// Read a `walk_dir_with` options object
+fn options_from_value(value: &ScriptValue) -> RuntimeResult<WalkOptions> {
+    let ScriptValue::Object(fields) = value else {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "walk_dir_with expects an options object, got {:?}",
+            value.get_type()
+        )));
+    };
+
+    let mut options = WalkOptions::default();
+    for (name, value) in fields.iter() {
+        match (name.as_str(), value) {
+            ("hidden", ScriptValue::Bool(hidden)) => options.include_hidden = *hidden,
+            ("follow_symlinks", ScriptValue::Bool(follow)) => options.follow_symlinks = *follow,
+            ("max_depth", ScriptValue::I32(depth)) if *depth >= 0 => {
+                options.max_depth = Some(*depth as usize)
+            }
+            ("max_depth", ScriptValue::I64(depth)) if *depth >= 0 => {
+                options.max_depth = Some(*depth as usize)
+            }
+            ("hidden" | "follow_symlinks", _) => {
+                return Err(RuntimeError::InvalidOperation(format!(
+                    "walk_dir_with option '{}' must be a boolean",
+                    name
+                )))
+            }
+            ("max_depth", _) => {
+                return Err(RuntimeError::InvalidOperation(
+                    "walk_dir_with option 'max_depth' must be a non-negative integer".to_string(),
+                ))
+            }
+            _ => {
+                return Err(RuntimeError::InvalidOperation(format!(
+                "Unknown walk_dir_with option '{}', expected hidden, max_depth or follow_symlinks",
+                name
+            )))
+            }
+        }
+    }
+    Ok(options)
+}
+
+/// Walk a tree with a Script closure, returning a Result with the number
+/// of entries visited
+fn walk_with_closure(
+    function: &str,
+    path: &ScriptValue,
+    options: &WalkOptions,
+    visit: &ScriptValue,
+) -> RuntimeResult<ScriptValue> {
+    let ScriptValue::String(path) = path else {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "{} expects a string path, got {:?}",
+            function,
+            path.get_type()
+        )));
+    };
+    if !matches!(visit, ScriptValue::Closure(_)) {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "{} expects a closure to visit entries",
+            function
+        )));
+    }
+
+    let mut closure_error = None;
+    let walked = walk_dir(
+        Path::new(path.as_str()),
+        options,
+        |entry| match execute_script_closure(visit, &[entry_to_value(entry)]) {
+            Ok(ScriptValue::Bool(false)) => Visit::SkipDir,
+            Ok(_) => Visit::Continue,
+            Err(e) => {
+                closure_error = Some(e);
+                Visit::Stop
+            }
+        },
+    );
+    if let Some(e) = closure_error {
+        return Err(RuntimeError::InvalidOperation(e.to_string()));
+    }
+
+    let result = match walked {
+        Ok(count) => ScriptResult::ok(ScriptValue::I32(count as i32)),
+        Err(e) => {
+            let io_err: IoError = io_error_from_std(e)
+                .context(format!("Failed to walk directory '{}'", path.as_str()));
+            ScriptResult::err(io_err.to_script_value())
+        }
+    };
+    Ok(ScriptValue::Result(ScriptRc::new(result)))
+}
+
+/// Implementation of glob for the stdlib registry
+pub fn glob_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 1 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "glob expects 1 argument, got {}",
+            args.len()
+        )));
+    }
+    let ScriptValue::String(pattern) = &args[0] else {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "glob expects a string pattern, got {:?}",
+            args[0].get_type()
+        )));
+    };
+
+    let result = match glob(pattern.as_str()) {
+        Ok(paths) => {
+            let paths = paths.iter().map(|path| string_value(path)).collect();
+            ScriptResult::ok(ScriptValue::Array(ScriptRc::new(ScriptVec::from_vec(
+                paths,
+            ))))
+        }
+        Err(message) => ScriptResult::err(error::error_object(
+            ErrorKind::InvalidInput,
+            &message,
+            None,
+            None,
+        )),
+    };
+    Ok(ScriptValue::Result(ScriptRc::new(result)))
+}
+
+/// Implementation of walk_dir for the stdlib registry
+pub fn walk_dir_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 2 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "walk_dir expects 2 arguments, got {}",
+            args.len()
+        )));
+    }
+    walk_with_closure("walk_dir", &args[0], &WalkOptions::default(), &args[1])
+}
+
+/// Implementation of walk_dir_with for the stdlib registry
+pub fn walk_dir_with_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    if args.len() != 3 {
+        return Err(RuntimeError::InvalidOperation(format!(
+            "walk_dir_with expects 3 arguments, got {}",
+            args.len()
+        )));
+    }
+    let options = options_from_value(&args[1])?;
+    walk_with_closure("walk_dir_with", &args[0], &options, &args[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_glob_patterns() {
+        let glob = Glob::new("src/**/*.script").unwrap();
+        assert!(glob.matches("src/main.script"));
+        assert!(glob.matches("src/a/b/lib.script"));
+        assert!(!glob.matches("src/a/lib.rs"));
+        assert!(!glob.matches("src/.cache/lib.script"));
+        assert!(!glob.matches("tests/main.script"));
+
+        let glob = Glob::new("data/file?.[a-c]sv").unwrap();
+        assert!(glob.matches("data/file1.csv"));
+        assert!(glob.matches("data/fileX.bsv"));
+        assert!(!glob.matches("data/file10.csv"));
+        assert!(!glob.matches("data/file1.tsv"));
+
+        let glob = Glob::new("*.[!t]*").unwrap();
+        assert!(glob.matches("notes.md"));
+        assert!(!glob.matches("notes.txt"));
+        assert!(!glob.matches(".hidden.md"));
+        assert!(Glob::new(".*").unwrap().matches(".hidden"));
+        assert!(Glob::new("a\\*b").unwrap().matches("a*b"));
+        assert!(!Glob::new("a\\*b").unwrap().matches("axb"));
+
+        assert!(Glob::new("[abc").is_err());
+        assert!(Glob::new("[z-a]").is_err());
+        assert!(Glob::new("").is_err());
+    }
+
+    fn test_tree(name: &str) -> PathBuf {
+        let root = PathBuf::from("target").join(name);
+        let _ = fs::remove_dir_all(&root);
+        for dir in ["src/util", "src/.cache", "docs"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "src/main.script",
+            "src/util/strings.script",
+            "src/util/notes.txt",
+            "src/.cache/old.script",
+            "docs/guide.md",
+        ] {
+            fs::write(root.join(file), "").unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_glob_and_walk_dir() {
+        let root = test_tree("test_walk");
+        let base = root.to_str().unwrap();
+
+        assert_eq!(
+            glob(&format!("{}/src/**/*.script", base)).unwrap(),
+            vec![
+                format!("{}/src/main.script", base),
+                format!("{}/src/util/strings.script", base),
+            ]
+        );
+        assert_eq!(
+            glob(&format!("{}/src/.*/*", base)).unwrap(),
+            vec![format!("{}/src/.cache/old.script", base)]
+        );
+        assert_eq!(
+            glob(&format!("{}/docs/guide.md", base)).unwrap(),
+            vec![format!("{}/docs/guide.md", base)]
+        );
+        assert!(glob(&format!("{}/missing/*", base)).unwrap().is_empty());
+
+        let walk = |options: &WalkOptions, skip: Option<&str>| {
+            let mut names = Vec::new();
+            walk_dir(&root, options, |entry| {
+                names.push(format!("{}:{}", entry.depth, entry.name));
+                if Some(entry.name.as_str()) == skip {
+                    Visit::SkipDir
+                } else {
+                    Visit::Continue
+                }
+            })
+            .unwrap();
+            names
+        };
+        assert_eq!(
+            walk(&WalkOptions::default(), None),
+            vec![
+                "1:docs",
+                "2:guide.md",
+                "1:src",
+                "2:main.script",
+                "2:util",
+                "3:notes.txt",
+                "3:strings.script",
+            ]
+        );
+        assert_eq!(
+            walk(&WalkOptions::default(), Some("util")),
+            vec!["1:docs", "2:guide.md", "1:src", "2:main.script", "2:util"]
+        );
+        let options = WalkOptions {
+            include_hidden: true,
+            max_depth: Some(2),
+            ..WalkOptions::default()
+        };
+        assert_eq!(
+            walk(&options, None),
+            vec![
+                "1:docs",
+                "2:guide.md",
+                "1:src",
+                "2:.cache",
+                "2:main.script",
+                "2:util",
+            ]
+        );
+
+        assert!(
+            walk_dir(&root.join("missing"), &WalkOptions::default(), |_| {
+                Visit::Continue
+            })
+            .is_err()
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+}
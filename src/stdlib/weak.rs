@@ -0,0 +1,195 @@
+//! Weak references and finalizers for Script
+//!
+//! `weak_ref(value)` returns a `Weak<T>` that does not keep `value` alive,
+//! and `weak_upgrade(weak)` turns it back into an `Option<T>`: `Some` while
+//! another strong reference exists, `None` once the value has been released.
+//! Caches and observer lists can hold weak references without keeping what
+//! they track alive.
+//!
+//! `on_finalize(value, closure)` calls `closure` with no arguments once
+//! `value` is released. A finalizer that captures `value` itself keeps it
+//! alive, so it never runs.
+//!
+//! Only values that live on the heap (strings, arrays, maps, sets, options,
+//! results, enums, objects, iterators and closures) can be referenced
+//! weakly or finalized; numbers and booleans are copied and have no lifetime
+//! to track.
+
+use crate::runtime::closure::Closure;
+use crate::runtime::finalizer::register_finalizer;
+use crate::runtime::{Result as RuntimeResult, RuntimeError, ScriptRc, ScriptWeak};
+use crate::stdlib::functional::execute_script_closure;
+use crate::stdlib::{
+    expect_args, expect_closure, ScriptEnum, ScriptHashMap, ScriptHashSet, ScriptIterator,
+    ScriptOption, ScriptResult, ScriptString, ScriptValue, ScriptVec,
+};
+use std::collections::HashMap;
+
+/// A weak reference to a heap-allocated Script value
+#[derive(Debug, Clone)]
+pub enum WeakValue {
+    String(ScriptWeak<ScriptString>),
+    Array(ScriptWeak<ScriptVec>),
+    HashMap(ScriptWeak<ScriptHashMap>),
+    HashSet(ScriptWeak<ScriptHashSet>),
+    Option(ScriptWeak<ScriptOption>),
+    Result(ScriptWeak<ScriptResult>),
+    Enum(ScriptWeak<ScriptEnum>),
+    Object(ScriptWeak<HashMap<String, ScriptValue>>),
+    Iterator(ScriptWeak<Box<dyn ScriptIterator>>),
+    Closure(ScriptWeak<Closure>),
+}
+
+impl WeakValue {
+    /// Create a weak reference to a heap value; `None` for copied values
+    pub fn downgrade(value: &ScriptValue) -> Option<Self> {
+        Some(match value {
+            ScriptValue::String(rc) => WeakValue::String(rc.downgrade()),
+            ScriptValue::Array(rc) => WeakValue::Array(rc.downgrade()),
+            ScriptValue::HashMap(rc) => WeakValue::HashMap(rc.downgrade()),
+            ScriptValue::HashSet(rc) => WeakValue::HashSet(rc.downgrade()),
+            ScriptValue::Option(rc) => WeakValue::Option(rc.downgrade()),
+            ScriptValue::Result(rc) => WeakValue::Result(rc.downgrade()),
+            ScriptValue::Enum(rc) => WeakValue::Enum(rc.downgrade()),
+            ScriptValue::Object(rc) => WeakValue::Object(rc.downgrade()),
+            ScriptValue::Iterator(rc) => WeakValue::Iterator(rc.downgrade()),
+            ScriptValue::Closure(rc) => WeakValue::Closure(rc.downgrade()),
+            _ => return None,
+        })
+    }
+
+    /// The referenced value, if it is still alive
+    pub fn upgrade(&self) -> Option<ScriptValue> {
+        Some(match self {
+            WeakValue::String(weak) => ScriptValue::String(weak.upgrade()?),
+            WeakValue::Array(weak) => ScriptValue::Array(weak.upgrade()?),
+            WeakValue::HashMap(weak) => ScriptValue::HashMap(weak.upgrade()?),
+            WeakValue::HashSet(weak) => ScriptValue::HashSet(weak.upgrade()?),
+            WeakValue::Option(weak) => ScriptValue::Option(weak.upgrade()?),
+            WeakValue::Result(weak) => ScriptValue::Result(weak.upgrade()?),
+            WeakValue::Enum(weak) => ScriptValue::Enum(weak.upgrade()?),
+            WeakValue::Object(weak) => ScriptValue::Object(weak.upgrade()?),
+            WeakValue::Iterator(weak) => ScriptValue::Iterator(weak.upgrade()?),
+            WeakValue::Closure(weak) => ScriptValue::Closure(weak.upgrade()?),
+        })
+    }
+}
+
+/// Create a weak reference to a value
+pub fn weak_ref_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "weak_ref")?;
+
+    WeakValue::downgrade(&args[0])
+        .map(ScriptValue::Weak)
+        .ok_or_else(|| not_heap_value("weak_ref", &args[0]))
+}
+
+/// Get the value behind a weak reference as an `Option`
+pub fn weak_upgrade_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 1, "weak_upgrade")?;
+
+    let weak = match &args[0] {
+        ScriptValue::Weak(weak) => weak,
+        other => {
+            return Err(RuntimeError::InvalidOperation(format!(
+                "weak_upgrade expects a weak reference, got {:?}",
+                other.get_type()
+            )))
+        }
+    };
+    let option = match weak.upgrade() {
+        Some(value) => ScriptOption::some(value),
+        None => ScriptOption::none(),
+    };
+    Ok(ScriptValue::Option(ScriptRc::new(option)))
+}
+
+/// Call a closure once a value is released
+pub fn on_finalize_impl(args: &[ScriptValue]) -> RuntimeResult<ScriptValue> {
+    expect_args(args, 2, "on_finalize")?;
+
+    expect_closure(&args[1], "on_finalize")?;
+    let address = heap_address(&args[0]).ok_or_else(|| not_heap_value("on_finalize", &args[0]))?;
+
+    let closure = args[1].clone();
+    register_finalizer(address, move || {
+        // Nothing is left to return an error to once the value is gone
+        if let Err(error) = execute_script_closure(&closure, &[]) {
+            eprintln!("Finalizer failed: {}", error);
+        }
+    });
+    Ok(ScriptValue::Unit)
+}
+
+/// Address of the allocation holding a heap value
+fn heap_address(value: &ScriptValue) -> Option<*const ()> {
+    Some(match value {
+        ScriptValue::String(rc) => rc.as_raw(),
+        ScriptValue::Array(rc) => rc.as_raw(),
+        ScriptValue::HashMap(rc) => rc.as_raw(),
+        ScriptValue::HashSet(rc) => rc.as_raw(),
+        ScriptValue::Option(rc) => rc.as_raw(),
+        ScriptValue::Result(rc) => rc.as_raw(),
+        ScriptValue::Enum(rc) => rc.as_raw(),
+        ScriptValue::Object(rc) => rc.as_raw(),
+        ScriptValue::Iterator(rc) => rc.as_raw(),
+        ScriptValue::Closure(rc) => rc.as_raw(),
+        _ => return None,
+    })
+}
+
+fn not_heap_value(function: &str, value: &ScriptValue) -> RuntimeError {
+    RuntimeError::InvalidOperation(format!(
+        "{} expects a heap value such as an object, array or string, got {:?}",
+        function,
+        value.get_type()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::finalizer::pending_finalizers;
+
+    fn upgraded(weak: &ScriptValue) -> Option<ScriptValue> {
+        match weak_upgrade_impl(&[weak.clone()]).unwrap() {
+            ScriptValue::Option(option) => match &*option {
+                ScriptOption::Some(value) => Some(value.clone()),
+                ScriptOption::None => None,
+            },
+            other => panic!("expected an option, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_weak_ref_does_not_keep_value_alive() {
+        let value = ScriptValue::String(ScriptRc::new(ScriptString::from_str("cached")));
+        let weak = weak_ref_impl(&[value.clone()]).unwrap();
+        assert_eq!(upgraded(&weak), Some(value.clone()));
+
+        drop(value);
+        assert_eq!(upgraded(&weak), None);
+
+        assert!(weak_ref_impl(&[ScriptValue::I32(1)]).is_err());
+        assert!(weak_upgrade_impl(&[ScriptValue::Unit]).is_err());
+    }
+
+    #[test]
+    fn test_on_finalize_registers_closure() {
+        let value = ScriptValue::Object(ScriptRc::new(HashMap::new()));
+        let closure = ScriptValue::Closure(ScriptRc::new(Closure::new(
+            "on_drop".to_string(),
+            vec![],
+            HashMap::new(),
+        )));
+        let before = pending_finalizers();
+
+        on_finalize_impl(&[value.clone(), closure.clone()]).unwrap();
+        assert_eq!(pending_finalizers(), before + 1);
+        assert!(on_finalize_impl(&[ScriptValue::Bool(true), closure]).is_err());
+        assert!(on_finalize_impl(&[value.clone(), ScriptValue::I32(0)]).is_err());
+
+        drop(value);
+        assert_eq!(pending_finalizers(), before);
+    }
+}
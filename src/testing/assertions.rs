@@ -5,12 +5,17 @@ use std::fmt;
 /// Assertion library for Script tests
 pub struct Assertion;
 
+/// Default tolerance for approximate float comparisons
+pub const DEFAULT_EPSILON: f64 = 1e-9;
+
 /// Error type for assertion failures
 #[derive(Debug, Clone)]
 pub struct AssertionError {
     pub message: String,
     pub expected: Option<String>,
     pub actual: Option<String>,
+    /// Individual differences found by structural comparison
+    pub diff: Vec<String>,
     pub location: Option<Span>,
 }
 
@@ -20,6 +25,7 @@ impl AssertionError {
             message: message.into(),
             expected: None,
             actual: None,
+            diff: Vec::new(),
             location: None,
         }
     }
@@ -30,6 +36,11 @@ impl AssertionError {
         self
     }
 
+    pub fn with_diff(mut self, diff: Vec<String>) -> Self {
+        self.diff = diff;
+        self
+    }
+
     pub fn with_location(mut self, span: Span) -> Self {
         self.location = Some(span);
         self
@@ -42,6 +53,12 @@ impl fmt::Display for AssertionError {
         if let (Some(expected), Some(actual)) = (&self.expected, &self.actual) {
             write!(f, "\n  Expected: {}\n  Actual: {}", expected, actual)?;
         }
+        if !self.diff.is_empty() {
+            write!(f, "\n  Differences:")?;
+            for line in &self.diff {
+                write!(f, "\n    {}", line)?;
+            }
+        }
         Ok(())
     }
 }
@@ -170,6 +187,37 @@ impl Assertion {
         }
     }
 
+    /// Assert that a collection contains an item
+    pub fn assert_contains_item<T: PartialEq + fmt::Debug>(
+        collection: &[T],
+        item: &T,
+    ) -> Result<()> {
+        if !collection.contains(item) {
+            Err(AssertionError::new("Collection does not contain item")
+                .with_values(format!("contains {:?}", item), format!("{:?}", collection))
+                .into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Assert that a string matches a regular expression
+    pub fn assert_matches(text: &str, pattern: &str) -> Result<()> {
+        let regex = regex::Regex::new(pattern).map_err(|err| {
+            AssertionError::new(format!("Invalid regular expression '{}': {}", pattern, err))
+        })?;
+
+        if !regex.is_match(text) {
+            Err(
+                AssertionError::new(format!("String does not match /{}/", pattern))
+                    .with_values(format!("matches /{}/", pattern), format!("'{}'", text))
+                    .into(),
+            )
+        } else {
+            Ok(())
+        }
+    }
+
     /// Assert that a collection is empty
     pub fn assert_empty<T>(collection: &[T]) -> Result<()> {
         if !collection.is_empty() {
@@ -194,16 +242,20 @@ impl Assertion {
     }
 
     /// Assert that a value is approximately equal to another (for floating point)
-    pub fn assert_approx_eq(expected: f64, actual: f64, tolerance: Option<f64>) -> Result<()> {
-        let tolerance = tolerance.unwrap_or(1e-10);
+    ///
+    /// `epsilon` is the largest allowed absolute difference and defaults to
+    /// `DEFAULT_EPSILON`. NaN is never approximately equal to anything.
+    pub fn assert_approx_eq(expected: f64, actual: f64, epsilon: Option<f64>) -> Result<()> {
+        let epsilon = epsilon.unwrap_or(DEFAULT_EPSILON);
         let diff = (expected - actual).abs();
 
-        if diff > tolerance {
-            Err(
-                AssertionError::new(format!("Values differ by more than {}", tolerance))
-                    .with_values(expected, actual)
-                    .into(),
-            )
+        if !approx_eq(expected, actual, epsilon) {
+            Err(AssertionError::new(format!(
+                "Values differ by {}, more than epsilon {}",
+                diff, epsilon
+            ))
+            .with_values(expected, actual)
+            .into())
         } else {
             Ok(())
         }
@@ -248,35 +300,256 @@ impl Assertion {
     }
 }
 
+/// Check if two floats are within `epsilon` of each other
+fn approx_eq(a: f64, b: f64, epsilon: f64) -> bool {
+    a == b || (a - b).abs() <= epsilon
+}
+
 /// Runtime assertion functions for Script values
 pub mod runtime_assertions {
     use super::*;
     use crate::runtime::Value;
+    use crate::stdlib::inspect::inspect_value;
 
-    /// Assert equality for Script runtime values
+    /// Maximum number of differences listed in a failure message
+    const MAX_DIFFERENCES: usize = 20;
+
+    /// Assert deep structural equality for Script runtime values
+    ///
+    /// On failure the error lists each differing element, field or key by
+    /// its path within the values.
     pub fn assert_value_eq(expected: &Value, actual: &Value) -> Result<()> {
-        if !values_equal(expected, actual) {
-            Err(AssertionError::new("Values are not equal")
-                .with_values(format!("{:?}", expected), format!("{:?}", actual))
+        assert_structurally_eq(expected, actual, f64::EPSILON)
+    }
+
+    /// Assert deep structural equality, allowing numbers to differ by `epsilon`
+    pub fn assert_value_approx_eq(expected: &Value, actual: &Value, epsilon: f64) -> Result<()> {
+        assert_structurally_eq(expected, actual, epsilon)
+    }
+
+    /// Assert that a string contains a substring, an array contains an
+    /// element, or an object contains a key
+    pub fn assert_value_contains(container: &Value, item: &Value) -> Result<()> {
+        let contains = match (container, item) {
+            (Value::String(haystack), Value::String(needle)) => haystack.contains(needle.as_str()),
+            (Value::Array(items), _) => items
+                .iter()
+                .any(|element| values_equal(element, item, f64::EPSILON)),
+            (Value::Object(fields), Value::String(key)) => fields.contains_key(key),
+            _ => {
+                return Err(AssertionError::new(format!(
+                    "Cannot check whether {} contains {}",
+                    container.type_name(),
+                    item.type_name()
+                ))
+                .into())
+            }
+        };
+
+        if !contains {
+            Err(AssertionError::new("Value does not contain item")
+                .with_values(
+                    format!("contains {}", format_value(item)),
+                    format_value(container),
+                )
                 .into())
         } else {
             Ok(())
         }
     }
 
+    /// Assert that a string value matches a regular expression
+    pub fn assert_value_matches(value: &Value, pattern: &str) -> Result<()> {
+        match value {
+            Value::String(text) => Assertion::assert_matches(text, pattern),
+            _ => Err(AssertionError::new(format!(
+                "Expected a string to match /{}/, got {}",
+                pattern,
+                value.type_name()
+            ))
+            .into()),
+        }
+    }
+
+    fn assert_structurally_eq(expected: &Value, actual: &Value, epsilon: f64) -> Result<()> {
+        let mut diff = Vec::new();
+        collect_differences(expected, actual, "value", epsilon, &mut diff);
+
+        if diff.is_empty() {
+            return Ok(());
+        }
+
+        if diff.len() > MAX_DIFFERENCES {
+            let remaining = diff.len() - MAX_DIFFERENCES;
+            diff.truncate(MAX_DIFFERENCES);
+            diff.push(format!("... and {} more", remaining));
+        }
+
+        Err(AssertionError::new("Values are not equal")
+            .with_values(format_value(expected), format_value(actual))
+            .with_diff(diff)
+            .into())
+    }
+
     /// Check if two Script values are equal
-    fn values_equal(a: &Value, b: &Value) -> bool {
-        match (a, b) {
-            (Value::Number(a), Value::Number(b)) => (a - b).abs() < f64::EPSILON,
-            (Value::String(a), Value::String(b)) => a == b,
-            (Value::Boolean(a), Value::Boolean(b)) => a == b,
-            (Value::Null, Value::Null) => true,
+    fn values_equal(a: &Value, b: &Value, epsilon: f64) -> bool {
+        let mut diff = Vec::new();
+        collect_differences(a, b, "", epsilon, &mut diff);
+        diff.is_empty()
+    }
+
+    /// Record every difference between `expected` and `actual` below `path`
+    fn collect_differences(
+        expected: &Value,
+        actual: &Value,
+        path: &str,
+        epsilon: f64,
+        diff: &mut Vec<String>,
+    ) {
+        let mismatch = |diff: &mut Vec<String>| {
+            diff.push(format!(
+                "{}: expected {}, got {}",
+                path,
+                format_value(expected),
+                format_value(actual)
+            ));
+        };
+
+        if let (Some(a), Some(b)) = (as_number(expected), as_number(actual)) {
+            if !approx_eq(a, b, epsilon) {
+                mismatch(diff);
+            }
+            return;
+        }
+        if let (Some(a), Some(b)) = (as_bool(expected), as_bool(actual)) {
+            if a != b {
+                mismatch(diff);
+            }
+            return;
+        }
+
+        match (expected, actual) {
+            (Value::Null, Value::Null) => {}
+            (Value::String(a), Value::String(b)) => {
+                if a != b {
+                    mismatch(diff);
+                }
+            }
             (Value::Array(a), Value::Array(b)) => {
-                a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| values_equal(x, y))
+                if a.len() != b.len() {
+                    diff.push(format!(
+                        "{}: expected length {}, got {}",
+                        path,
+                        a.len(),
+                        b.len()
+                    ));
+                }
+                for (i, (x, y)) in a.iter().zip(b.iter()).enumerate() {
+                    collect_differences(x, y, &format!("{}[{}]", path, i), epsilon, diff);
+                }
+                for (i, missing) in a.iter().enumerate().skip(b.len()) {
+                    diff.push(format!(
+                        "{}[{}]: missing element {}",
+                        path,
+                        i,
+                        format_value(missing)
+                    ));
+                }
+                for (i, extra) in b.iter().enumerate().skip(a.len()) {
+                    diff.push(format!(
+                        "{}[{}]: unexpected element {}",
+                        path,
+                        i,
+                        format_value(extra)
+                    ));
+                }
+            }
+            (Value::Object(a), Value::Object(b)) => {
+                let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+                keys.sort();
+                keys.dedup();
+
+                for key in keys {
+                    let field_path = format!("{}.{}", path, key);
+                    match (a.get(key), b.get(key)) {
+                        (Some(x), Some(y)) => collect_differences(x, y, &field_path, epsilon, diff),
+                        (Some(x), None) => diff.push(format!(
+                            "{}: missing field, expected {}",
+                            field_path,
+                            format_value(x)
+                        )),
+                        (None, Some(y)) => diff.push(format!(
+                            "{}: unexpected field {}",
+                            field_path,
+                            format_value(y)
+                        )),
+                        (None, None) => {}
+                    }
+                }
             }
-            _ => false,
+            (
+                Value::Enum {
+                    type_name: type_a,
+                    variant: variant_a,
+                    data: data_a,
+                },
+                Value::Enum {
+                    type_name: type_b,
+                    variant: variant_b,
+                    data: data_b,
+                },
+            ) => {
+                if type_a != type_b || variant_a != variant_b {
+                    mismatch(diff);
+                    return;
+                }
+                match (data_a, data_b) {
+                    (Some(x), Some(y)) => collect_differences(
+                        x,
+                        y,
+                        &format!("{}::{}", path, variant_a),
+                        epsilon,
+                        diff,
+                    ),
+                    (None, None) => {}
+                    _ => mismatch(diff),
+                }
+            }
+            (Value::Function(a), Value::Function(b)) => {
+                if a != b {
+                    mismatch(diff);
+                }
+            }
+            _ => mismatch(diff),
+        }
+    }
+
+    /// Numeric value of any of the number variants
+    fn as_number(value: &Value) -> Option<f64> {
+        match value {
+            Value::I32(n) => Some(*n as f64),
+            Value::I64(n) => Some(*n as f64),
+            Value::F32(n) => Some(*n as f64),
+            Value::F64(n) | Value::Number(n) => Some(*n),
+            _ => None,
         }
     }
+
+    /// Boolean value of either boolean variant
+    fn as_bool(value: &Value) -> Option<bool> {
+        match value {
+            Value::Bool(b) | Value::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Format a value for a failure message
+    ///
+    /// Unlike `Display`, strings are quoted and object keys are sorted so
+    /// messages are stable between runs.
+    fn format_value(value: &Value) -> String {
+        inspect_value(value)
+    }
 }
 
 #[cfg(test)]
@@ -308,4 +581,112 @@ mod tests {
         assert!(Assertion::assert_approx_eq(1.0, 1.1, Some(0.05)).is_err()); // 0.1 > 0.05 tolerance
         assert!(Assertion::assert_approx_eq(1.0, 1.01, Some(0.1)).is_ok()); // 0.01 < 0.1 tolerance
     }
+
+    #[test]
+    fn test_assert_approx_eq_nan() {
+        assert!(Assertion::assert_approx_eq(f64::NAN, f64::NAN, Some(1.0)).is_err());
+        assert!(Assertion::assert_approx_eq(f64::INFINITY, f64::INFINITY, None).is_ok());
+
+        let message = Assertion::assert_approx_eq(1.0, 2.0, Some(0.5))
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("Expected: 1"));
+        assert!(message.contains("Actual: 2"));
+    }
+
+    #[test]
+    fn test_assert_contains_item() {
+        assert!(Assertion::assert_contains_item(&[1, 2, 3], &2).is_ok());
+        assert!(Assertion::assert_contains_item(&[1, 2, 3], &4).is_err());
+    }
+
+    #[test]
+    fn test_assert_matches() {
+        assert!(Assertion::assert_matches("version 1.2.3", r"\d+\.\d+\.\d+").is_ok());
+        assert!(Assertion::assert_matches("version one", r"^\d+$").is_err());
+
+        let message = Assertion::assert_matches("abc", "(")
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("Invalid regular expression"));
+    }
+
+    mod runtime_values {
+        use super::super::runtime_assertions::*;
+        use crate::runtime::{ScriptRc, Value};
+        use std::collections::HashMap;
+
+        fn object(fields: Vec<(&str, Value)>) -> Value {
+            Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), ScriptRc::new(value)))
+                    .collect::<HashMap<_, _>>(),
+            )
+        }
+
+        fn array(items: Vec<Value>) -> Value {
+            Value::Array(items.into_iter().map(ScriptRc::new).collect())
+        }
+
+        #[test]
+        fn test_deep_equality() {
+            let expected = object(vec![
+                ("name", Value::String("Ada".to_string())),
+                ("scores", array(vec![Value::I32(1), Value::I32(2)])),
+            ]);
+            let actual = object(vec![
+                ("name", Value::String("Ada".to_string())),
+                ("scores", array(vec![Value::F64(1.0), Value::Number(2.0)])),
+            ]);
+            assert!(assert_value_eq(&expected, &actual).is_ok());
+        }
+
+        #[test]
+        fn test_deep_equality_diff() {
+            let expected = object(vec![
+                ("name", Value::String("Ada".to_string())),
+                ("level", Value::I32(3)),
+                ("tags", array(vec![Value::String("a".to_string())])),
+            ]);
+            let actual = object(vec![
+                ("name", Value::String("Grace".to_string())),
+                (
+                    "tags",
+                    array(vec![Value::String("a".to_string()), Value::Null]),
+                ),
+                ("extra", Value::Bool(true)),
+            ]);
+
+            let message = assert_value_eq(&expected, &actual).unwrap_err().to_string();
+            assert!(message.contains("value.name: expected \"Ada\", got \"Grace\""));
+            assert!(message.contains("value.level: missing field, expected 3"));
+            assert!(message.contains("value.extra: unexpected field true"));
+            assert!(message.contains("value.tags: expected length 1, got 2"));
+            assert!(message.contains("value.tags[1]: unexpected element null"));
+            assert!(message.contains("Expected: {level: 3, name: \"Ada\", tags: [\"a\"]}"));
+        }
+
+        #[test]
+        fn test_value_approx_eq() {
+            let expected = array(vec![Value::F64(0.1), Value::F64(0.2)]);
+            let actual = array(vec![Value::F64(0.1000001), Value::F64(0.2)]);
+            assert!(assert_value_eq(&expected, &actual).is_err());
+            assert!(assert_value_approx_eq(&expected, &actual, 1e-3).is_ok());
+        }
+
+        #[test]
+        fn test_value_contains_and_matches() {
+            let text = Value::String("hello world".to_string());
+            let items = array(vec![Value::I32(1), object(vec![("id", Value::I32(7))])]);
+
+            assert!(assert_value_contains(&text, &Value::String("world".to_string())).is_ok());
+            assert!(assert_value_contains(&items, &object(vec![("id", Value::I32(7))])).is_ok());
+            assert!(assert_value_contains(&items, &Value::I32(2)).is_err());
+            assert!(assert_value_contains(&Value::I32(1), &Value::I32(1)).is_err());
+
+            assert!(assert_value_matches(&text, "^hello").is_ok());
+            assert!(assert_value_matches(&Value::I32(1), "1").is_err());
+        }
+    }
 }
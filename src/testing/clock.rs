@@ -0,0 +1,90 @@
+//! Mock clock for tests
+//!
+//! `MockClock::install` puts a virtual clock on the current thread. Until the
+//! `MockClock` is dropped, `sleep`, `time_now`, stopwatches and async timers
+//! created on that thread use the virtual clock, so time-dependent code runs
+//! without real waiting. Time moves when the test calls `advance` (or
+//! `time_advance` in Script), when code sleeps, and when an executor has
+//! nothing to do but wait for a timer.
+
+use crate::runtime::deterministic::{self, VirtualClock};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A virtual clock installed on the current thread
+pub struct MockClock {
+    clock: Arc<VirtualClock>,
+    previous: Option<Arc<VirtualClock>>,
+    /// The clock is installed on one thread, so it is removed on that thread
+    _not_send: PhantomData<*const ()>,
+}
+
+impl MockClock {
+    /// Install a clock reading zero on the current thread
+    pub fn install() -> Self {
+        let clock = Arc::new(VirtualClock::new());
+        let previous = deterministic::install_clock(Some(Arc::clone(&clock)));
+        MockClock {
+            clock,
+            previous,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Time elapsed on the clock since it was installed
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    /// Move the clock forward, returning how many timers fired
+    pub fn advance(&self, by: Duration) -> usize {
+        self.clock.advance(by)
+    }
+
+    /// The underlying clock, for sharing with other threads
+    pub fn clock(&self) -> Arc<VirtualClock> {
+        Arc::clone(&self.clock)
+    }
+}
+
+impl Drop for MockClock {
+    fn drop(&mut self) {
+        deterministic::install_clock(self.previous.take());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::async_runtime::BlockingExecutor;
+    use crate::runtime::{ScriptFuture, Timer};
+
+    #[test]
+    fn test_sleep_is_instant() {
+        let clock = MockClock::install();
+        let start = std::time::Instant::now();
+        deterministic::sleep(Duration::from_secs(60));
+        assert_eq!(clock.now(), Duration::from_secs(60));
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        drop(clock);
+        assert!(deterministic::virtual_clock().is_none());
+    }
+
+    #[test]
+    fn test_timers_follow_the_mock_clock() {
+        let clock = MockClock::install();
+        let mut timer = Timer::new(Duration::from_secs(5));
+        let waker = futures::task::noop_waker();
+        assert!(timer.poll(&waker).is_pending());
+        assert_eq!(clock.advance(Duration::from_secs(5)), 1);
+        assert!(timer.poll(&waker).is_ready());
+
+        // A blocked executor skips ahead to the timer instead of waiting
+        let start = std::time::Instant::now();
+        BlockingExecutor::block_on(Box::new(Timer::new(Duration::from_secs(3600)))).unwrap();
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(clock.now(), Duration::from_secs(3605));
+    }
+}
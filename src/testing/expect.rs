@@ -0,0 +1,176 @@
+//! Inline expect-tests
+//!
+//! `expect(value, "expected")` compares the display form of `value` with a
+//! string literal written in the test itself. A normal test run fails on a
+//! mismatch. With `--update-expect` the mismatches are collected instead and
+//! [`apply_expect_updates`] rewrites each literal in place, using the span the
+//! parser recorded for it, so golden output is refreshed by rerunning the
+//! tests and reviewing the diff.
+
+use crate::error::{Error, ErrorKind, Result};
+use crate::source::Span;
+use std::fmt;
+
+/// An `expect` call whose value didn't match its expected literal
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExpectMismatch {
+    /// Span of the expected string literal, including its quotes
+    pub span: Span,
+    /// Text of the expected literal
+    pub expected: String,
+    /// Display form of the actual value
+    pub actual: String,
+}
+
+impl ExpectMismatch {
+    pub fn new(span: Span, expected: impl Into<String>, actual: impl Into<String>) -> Self {
+        Self {
+            span,
+            expected: expected.into(),
+            actual: actual.into(),
+        }
+    }
+}
+
+impl fmt::Display for ExpectMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "expect failed at {}: expected {}, got {}",
+            self.span.start,
+            escape_literal(&self.expected),
+            escape_literal(&self.actual)
+        )
+    }
+}
+
+/// Rewrite the expected literal of every mismatch with its actual value
+///
+/// Literals are replaced from the end of the file backwards so earlier byte
+/// offsets stay valid. A literal reached several times (e.g. inside a loop)
+/// may only be updated if every run produced the same value.
+pub fn apply_expect_updates(source: &str, updates: &[ExpectMismatch]) -> Result<String> {
+    let mut updates: Vec<&ExpectMismatch> = updates.iter().collect();
+    updates.sort_by_key(|update| std::cmp::Reverse(update.span.start.byte_offset));
+    updates.dedup_by(|a, b| a.span == b.span && a.actual == b.actual);
+
+    let mut output = source.to_string();
+    let mut previous: Option<&ExpectMismatch> = None;
+    for update in updates {
+        if let Some(previous) = previous {
+            if previous.span == update.span {
+                return Err(Error::new(
+                    ErrorKind::RuntimeError,
+                    format!(
+                        "Cannot update expectation at {}: it produced different values ({} and {})",
+                        update.span.start,
+                        escape_literal(&previous.actual),
+                        escape_literal(&update.actual)
+                    ),
+                ));
+            }
+        }
+
+        let range = update.span.start.byte_offset..update.span.end.byte_offset;
+        let is_literal = source
+            .get(range.clone())
+            .is_some_and(|text| text.len() >= 2 && text.starts_with('"') && text.ends_with('"'));
+        if !is_literal {
+            return Err(Error::new(
+                ErrorKind::RuntimeError,
+                format!(
+                    "Cannot update expectation at {}: the source doesn't contain a string literal there",
+                    update.span.start
+                ),
+            ));
+        }
+
+        output.replace_range(range, &escape_literal(&update.actual));
+        previous = Some(update);
+    }
+
+    Ok(output)
+}
+
+/// Quote `text` as a Script string literal
+pub fn escape_literal(text: &str) -> String {
+    let mut literal = String::with_capacity(text.len() + 2);
+    literal.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            _ => literal.push(ch),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::SourceLocation;
+
+    fn span_of(source: &str, needle: &str) -> Span {
+        let start = source.find(needle).unwrap();
+        let end = start + needle.len();
+        Span::new(
+            SourceLocation::new(1, start + 1, start),
+            SourceLocation::new(1, end + 1, end),
+        )
+    }
+
+    #[test]
+    fn test_rewrites_literals_in_place() {
+        let source = r#"expect(a, "1"); expect(b, "old")"#;
+        let updates = vec![
+            ExpectMismatch::new(span_of(source, r#""1""#), "1", "2"),
+            ExpectMismatch::new(span_of(source, r#""old""#), "old", "new value"),
+        ];
+
+        let updated = apply_expect_updates(source, &updates).unwrap();
+        assert_eq!(updated, r#"expect(a, "2"); expect(b, "new value")"#);
+    }
+
+    #[test]
+    fn test_escapes_actual_value() {
+        assert_eq!(escape_literal("a\n\"b\"\\"), r#""a\n\"b\"\\""#);
+
+        let source = r#"expect(s, "")"#;
+        let updates = vec![ExpectMismatch::new(span_of(source, r#""""#), "", "x\ty")];
+        let updated = apply_expect_updates(source, &updates).unwrap();
+        assert_eq!(updated, r#"expect(s, "x\ty")"#);
+    }
+
+    #[test]
+    fn test_repeated_literal() {
+        let source = r#"expect(i, "0")"#;
+        let span = span_of(source, r#""0""#);
+
+        let same = vec![
+            ExpectMismatch::new(span, "0", "1"),
+            ExpectMismatch::new(span, "0", "1"),
+        ];
+        assert_eq!(
+            apply_expect_updates(source, &same).unwrap(),
+            r#"expect(i, "1")"#
+        );
+
+        let conflicting = vec![
+            ExpectMismatch::new(span, "0", "1"),
+            ExpectMismatch::new(span, "0", "2"),
+        ];
+        assert!(apply_expect_updates(source, &conflicting).is_err());
+    }
+
+    #[test]
+    fn test_rejects_span_without_literal() {
+        let source = r#"expect(a, "1")"#;
+        let updates = vec![ExpectMismatch::new(span_of(source, "a"), "1", "2")];
+        assert!(apply_expect_updates(source, &updates).is_err());
+    }
+}
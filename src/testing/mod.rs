@@ -1,13 +1,17 @@
 mod assertions;
+mod clock;
+mod expect;
 mod test_case;
 mod test_discovery;
 mod test_reporter;
 mod test_runner;
 
-pub use assertions::{Assertion, AssertionError};
+pub use assertions::{runtime_assertions, Assertion, AssertionError, DEFAULT_EPSILON};
+pub use clock::MockClock;
+pub use expect::{apply_expect_updates, escape_literal, ExpectMismatch};
 pub use test_case::{TestCase, TestFailure, TestResult, TestStatus};
 pub use test_discovery::{TestCollector, TestDiscovery};
-pub use test_reporter::{ConsoleReporter, ReportFormat, TestReporter};
+pub use test_reporter::{junit_report, ConsoleReporter, ReportFormat, TestReporter};
 pub use test_runner::{TestRunOptions, TestRunner, TestSuite};
 
 use crate::error::Result;
@@ -20,6 +24,10 @@ pub struct TestingFramework {
     discovery: TestDiscovery,
     runner: TestRunner,
     reporter: Box<dyn TestReporter>,
+    /// Results of the last run
+    results: Vec<TestResult>,
+    /// `expect` literals to rewrite, collected by the last run
+    expect_updates: Vec<ExpectMismatch>,
 }
 
 impl TestingFramework {
@@ -28,9 +36,21 @@ impl TestingFramework {
             discovery: TestDiscovery::new(),
             runner: TestRunner::new(),
             reporter: Box::new(ConsoleReporter::new()),
+            results: Vec::new(),
+            expect_updates: Vec::new(),
         }
     }
 
+    pub fn with_discovery(mut self, discovery: TestDiscovery) -> Self {
+        self.discovery = discovery;
+        self
+    }
+
+    pub fn with_run_options(mut self, options: TestRunOptions) -> Self {
+        self.runner = TestRunner::with_options(options);
+        self
+    }
+
     pub fn with_reporter(mut self, reporter: Box<dyn TestReporter>) -> Self {
         self.reporter = reporter;
         self
@@ -47,8 +67,25 @@ impl TestingFramework {
         // Report results
         self.reporter.report_results(&results)?;
 
+        self.expect_updates = results
+            .iter()
+            .flat_map(|result| result.expect_updates.iter().cloned())
+            .collect();
+
         // Return summary
-        Ok(TestSummary::from_results(&results))
+        let summary = TestSummary::from_results(&results);
+        self.results = results;
+        Ok(summary)
+    }
+
+    /// Results of the last run
+    pub fn results(&self) -> &[TestResult] {
+        &self.results
+    }
+
+    /// `expect` mismatches from the last run, when updating expectations
+    pub fn expect_updates(&self) -> &[ExpectMismatch] {
+        &self.expect_updates
     }
 
     /// Check if a statement is a test function
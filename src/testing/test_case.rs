@@ -1,5 +1,6 @@
 use crate::parser::{Block, Stmt, StmtKind};
 use crate::source::Span;
+use crate::testing::ExpectMismatch;
 use std::fmt;
 use std::time::Duration;
 
@@ -25,6 +26,8 @@ pub struct TestAttributes {
     pub should_panic: Option<String>,
     /// Test timeout in milliseconds
     pub timeout: Option<u64>,
+    /// Run the test on a virtual clock (see `MockClock`)
+    pub virtual_time: bool,
     /// Test tags for filtering
     pub tags: Vec<String>,
 }
@@ -52,6 +55,8 @@ impl TestAttributes {
                 }
             } else if arg.trim() == "should_panic" {
                 attrs.should_panic = Some(String::new());
+            } else if arg.trim() == "virtual_time" {
+                attrs.virtual_time = true;
             }
         }
 
@@ -70,6 +75,8 @@ pub struct TestResult {
     pub duration: Duration,
     /// Any output produced by the test
     pub output: String,
+    /// `expect` literals to rewrite, collected when updating expectations
+    pub expect_updates: Vec<ExpectMismatch>,
 }
 
 /// Status of a test execution
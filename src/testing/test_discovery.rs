@@ -53,6 +53,7 @@ impl TestDiscovery {
         Ok(TestSuite {
             name: "main".to_string(),
             tests,
+            items: collector.items,
             setup: collector.setup,
             teardown: collector.teardown,
         })
@@ -62,6 +63,8 @@ impl TestDiscovery {
 /// Collects tests from AST nodes
 pub struct TestCollector {
     pub tests: Vec<TestCase>,
+    /// Module-level statements that aren't tests, setup or teardown
+    pub items: Vec<Stmt>,
     pub setup: Option<Stmt>,
     pub teardown: Option<Stmt>,
     _current_module: String,
@@ -71,6 +74,7 @@ impl TestCollector {
     pub fn new() -> Self {
         Self {
             tests: Vec::new(),
+            items: Vec::new(),
             setup: None,
             teardown: None,
             _current_module: String::new(),
@@ -80,6 +84,12 @@ impl TestCollector {
     /// Collect tests from a program
     pub fn collect_from_program(&mut self, program: &Program) -> Result<()> {
         for stmt in &program.statements {
+            let is_test_item = self.check_for_test(stmt).is_some()
+                || self.check_for_setup(stmt)
+                || self.check_for_teardown(stmt);
+            if !is_test_item {
+                self.items.push(stmt.clone());
+            }
             self.visit_statement(stmt)?;
         }
         Ok(())
@@ -247,6 +257,7 @@ mod tests {
     fn test_collector_finds_test_functions() {
         let program = Program {
             statements: vec![create_test_stmt("test_foo"), create_test_stmt("test_bar")],
+            comments: Vec::new(),
         };
 
         let mut collector = TestCollector::new();
@@ -1,4 +1,5 @@
 use crate::error::Result;
+use crate::stdlib::diff::{diff_lines, unified_diff, DEFAULT_CONTEXT};
 use crate::testing::{TestFailure, TestResult, TestStatus};
 use colored::*;
 use std::io::{self, Write};
@@ -144,8 +145,14 @@ impl ConsoleReporter {
 
         if let (Some(expected), Some(actual)) = (&failure.expected, &failure.actual) {
             println!("\n{}", "comparison:".dimmed());
-            println!("  {}: {}", "expected".green(), expected);
-            println!("  {}: {}", "actual".red(), actual);
+            if expected.contains('\n') || actual.contains('\n') {
+                // Multi-line values are easier to compare as a diff
+                let hunks = diff_lines(expected, actual, DEFAULT_CONTEXT);
+                print!("{}", unified_diff(&hunks, "expected", "actual", true));
+            } else {
+                println!("  {}: {}", "expected".green(), expected);
+                println!("  {}: {}", "actual".red(), actual);
+            }
         }
 
         if let Some(location) = &failure.location {
@@ -314,15 +321,32 @@ impl ConsoleReporter {
     }
 
     fn report_junit(&self, results: &[TestResult]) -> Result<()> {
-        println!(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
-        println!(r#"<testsuites>"#);
-        println!(
-            r#"  <testsuite name="script-tests" tests="{}" failures="{}" skipped="{}" time="{:.3}">"#,
+        print!("{}", junit_report(&[("script-tests", results)]));
+        Ok(())
+    }
+}
+
+/// JUnit XML for named suites of test results, one `<testsuite>` each
+pub fn junit_report(suites: &[(&str, &[TestResult])]) -> String {
+    use std::fmt::Write as _;
+
+    let mut xml = String::new();
+    let _ = writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(xml, r#"<testsuites>"#);
+    for (name, results) in suites {
+        let _ = writeln!(
+            xml,
+            r#"  <testsuite name="{}" tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}">"#,
+            xml_escape(name),
             results.len(),
             results
                 .iter()
                 .filter(|r| matches!(r.status, TestStatus::Failed(_)))
                 .count(),
+            results
+                .iter()
+                .filter(|r| matches!(r.status, TestStatus::Panicked(_)))
+                .count(),
             results
                 .iter()
                 .filter(|r| matches!(r.status, TestStatus::Skipped(_)))
@@ -333,52 +357,54 @@ impl ConsoleReporter {
                 .sum::<f64>()
         );
 
-        for result in results {
-            match &result.status {
-                TestStatus::Passed => {
-                    println!(
-                        r#"    <testcase name="{}" time="{:.3}"/>"#,
-                        result.test.name,
-                        result.duration.as_secs_f64()
-                    );
-                }
-                TestStatus::Failed(failure) => {
-                    println!(
-                        r#"    <testcase name="{}" time="{:.3}">"#,
-                        result.test.name,
-                        result.duration.as_secs_f64()
-                    );
-                    println!(
-                        r#"      <failure message="{}">{}</failure>"#,
+        for result in results.iter() {
+            let test_name = xml_escape(&result.test.name);
+            let time = result.duration.as_secs_f64();
+            let (time, detail) = match &result.status {
+                TestStatus::Passed => (time, None),
+                TestStatus::Failed(failure) => (
+                    time,
+                    Some(format!(
+                        r#"<failure message="{}">{}</failure>"#,
                         xml_escape(&failure.message),
                         xml_escape(&format!("{:?}", failure))
+                    )),
+                ),
+                TestStatus::Skipped(reason) => (
+                    0.0,
+                    Some(format!(r#"<skipped message="{}"/>"#, xml_escape(reason))),
+                ),
+                TestStatus::Panicked(msg) => (
+                    time,
+                    Some(format!(
+                        r#"<error message="panic">{}</error>"#,
+                        xml_escape(msg)
+                    )),
+                ),
+            };
+            match detail {
+                None => {
+                    let _ = writeln!(
+                        xml,
+                        r#"    <testcase name="{}" time="{:.3}"/>"#,
+                        test_name, time
                     );
-                    println!(r#"    </testcase>"#);
-                }
-                TestStatus::Skipped(reason) => {
-                    println!(r#"    <testcase name="{}" time="0">"#, result.test.name);
-                    println!(r#"      <skipped message="{}"/>"#, xml_escape(reason));
-                    println!(r#"    </testcase>"#);
                 }
-                TestStatus::Panicked(msg) => {
-                    println!(
+                Some(detail) => {
+                    let _ = writeln!(
+                        xml,
                         r#"    <testcase name="{}" time="{:.3}">"#,
-                        result.test.name,
-                        result.duration.as_secs_f64()
-                    );
-                    println!(
-                        r#"      <error message="panic">{}</error>"#,
-                        xml_escape(msg)
+                        test_name, time
                     );
-                    println!(r#"    </testcase>"#);
+                    let _ = writeln!(xml, "      {}", detail);
+                    let _ = writeln!(xml, r#"    </testcase>"#);
                 }
             }
         }
-
-        println!(r#"  </testsuite>"#);
-        println!(r#"</testsuites>"#);
-        Ok(())
+        let _ = writeln!(xml, r#"  </testsuite>"#);
     }
+    let _ = writeln!(xml, r#"</testsuites>"#);
+    xml
 }
 
 fn xml_escape(s: &str) -> String {
@@ -449,3 +475,53 @@ impl TestReporter for ProgressReporter {
             .report_summary(total, passed, failed, skipped, duration)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Block;
+    use crate::source::Span;
+    use crate::testing::TestCase;
+
+    fn result(name: &str, status: TestStatus) -> TestResult {
+        TestResult {
+            test: TestCase {
+                name: name.to_string(),
+                body: Block {
+                    statements: vec![],
+                    final_expr: None,
+                },
+                span: Span::dummy(),
+                attributes: Default::default(),
+            },
+            status,
+            duration: Duration::from_millis(5),
+            output: String::new(),
+            expect_updates: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_junit_report_has_a_suite_per_name() {
+        let core = vec![
+            result("test_add", TestStatus::Passed),
+            result(
+                "test_parse",
+                TestStatus::Failed(TestFailure::new("expected <1>")),
+            ),
+        ];
+        let net = vec![result(
+            "test_io",
+            TestStatus::Skipped("offline".to_string()),
+        )];
+
+        let xml = junit_report(&[("core", &core), ("net", &net)]);
+        assert!(xml.contains(r#"<testsuite name="core" tests="2" failures="1" errors="0""#));
+        assert!(
+            xml.contains(r#"<testsuite name="net" tests="1" failures="0" errors="0" skipped="1""#)
+        );
+        assert!(xml.contains(r#"<testcase name="test_add" time="0.005"/>"#));
+        assert!(xml.contains(r#"<failure message="expected &lt;1&gt;">"#));
+        assert_eq!(xml.matches("</testsuite>").count(), 2);
+    }
+}
@@ -1,8 +1,10 @@
+use crate::debugger::{DebugInterpreter, Debugger, PauseState, StepAction, StepController};
 use crate::error::Result;
 use crate::parser::Stmt;
-use crate::runtime::{Runtime, RuntimeConfig};
-use crate::testing::{TestCase, TestFailure, TestResult, TestStatus};
-use std::sync::{Arc, Mutex};
+use crate::runtime::{GcMode, Runtime, RuntimeConfig, RuntimeProfile};
+use crate::testing::{ExpectMismatch, MockClock, TestCase, TestFailure, TestResult, TestStatus};
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
@@ -19,6 +21,8 @@ pub struct TestRunOptions {
     pub capture_output: bool,
     /// Verbose output
     pub verbose: bool,
+    /// Collect `expect` mismatches for rewriting instead of failing on them
+    pub update_expect: bool,
 }
 
 impl Default for TestRunOptions {
@@ -31,6 +35,7 @@ impl Default for TestRunOptions {
             fail_fast: false,
             capture_output: true,
             verbose: false,
+            update_expect: false,
         }
     }
 }
@@ -40,6 +45,8 @@ impl Default for TestRunOptions {
 pub struct TestSuite {
     pub name: String,
     pub tests: Vec<TestCase>,
+    /// Module-level statements that aren't tests, shared by every test
+    pub items: Vec<Stmt>,
     pub setup: Option<Stmt>,
     pub teardown: Option<Stmt>,
 }
@@ -62,7 +69,14 @@ impl TestRunner {
             gc_threshold: 8 * 1024 * 1024,
             enable_profiling: false,
             enable_gc: true,
+            gc_mode: GcMode::CycleCollector,
             enable_panic_handler: true,
+            deterministic: false,
+            seed: 0,
+            track_allocations: false,
+            check_leaks: false,
+            profile: RuntimeProfile::Full,
+            arena_size: 0,
         };
 
         Self {
@@ -92,6 +106,7 @@ impl TestRunner {
                     status: TestStatus::Skipped(reason.to_string()),
                     duration: Duration::ZERO,
                     output: String::new(),
+                    expect_updates: Vec::new(),
                 });
                 continue;
             }
@@ -102,7 +117,7 @@ impl TestRunner {
             }
 
             // Run the test
-            let result = self.run_single_test(test);
+            let result = self.run_single_test(test, &suite.items);
 
             // Check fail-fast
             if self.options.fail_fast && matches!(result.status, TestStatus::Failed(_)) {
@@ -133,6 +148,7 @@ impl TestRunner {
         for test in &suite.tests {
             let tx = tx.clone();
             let test = test.clone();
+            let items = suite.items.clone();
             let options = self.options.clone();
             let setup = suite.setup.clone();
             let teardown = suite.teardown.clone();
@@ -147,6 +163,7 @@ impl TestRunner {
                         status: TestStatus::Skipped(reason.to_string()),
                         duration: Duration::ZERO,
                         output: String::new(),
+                        expect_updates: Vec::new(),
                     };
                     tx.send(result).unwrap();
                     return;
@@ -160,6 +177,7 @@ impl TestRunner {
                             status: TestStatus::Failed(TestFailure::new("Setup failed")),
                             duration: Duration::ZERO,
                             output: String::new(),
+                            expect_updates: Vec::new(),
                         };
                         tx.send(result).unwrap();
                         return;
@@ -167,7 +185,7 @@ impl TestRunner {
                 }
 
                 // Run test
-                let result = runner.run_single_test(&test);
+                let result = runner.run_single_test(&test, &items);
                 tx.send(result).unwrap();
 
                 // Run teardown
@@ -191,52 +209,63 @@ impl TestRunner {
     }
 
     /// Run a single test case
-    fn run_single_test(&mut self, test: &TestCase) -> TestResult {
+    ///
+    /// The test runs in the AST interpreter on a thread of its own so that a
+    /// hung test can be abandoned once its timeout expires.
+    fn run_single_test(&mut self, test: &TestCase, items: &[Stmt]) -> TestResult {
         let start = Instant::now();
-        let mut output = String::new();
-
-        // Set up output capture if needed
-        let _guard = if self.options.capture_output {
-            Some(OutputCapture::new())
-        } else {
-            None
-        };
-
-        // Create a test-specific program with the test function
-        let _test_program = self.create_test_program(test);
+        let program = self.create_test_program(test, items);
 
         // Execute the test with timeout
         let timeout = test.timeout();
         let (tx, rx) = std::sync::mpsc::channel();
 
-        let _test_clone = test.clone();
         let should_panic = test.should_panic().map(|s| s.to_string());
+        let capture_output = self.options.capture_output;
+        let update_expect = self.options.update_expect;
+        let virtual_time = test.attributes.virtual_time;
 
         thread::spawn(move || {
-            let result = std::panic::catch_unwind(|| {
-                // This would normally compile and execute the test
-                // For now, we'll simulate the execution
-                // In a real implementation, this would:
-                // 1. Compile the test program
-                // 2. Execute it in the runtime
-                // 3. Check assertions
-                Ok::<(), crate::error::Error>(())
-            });
-
-            match result {
-                Ok(Ok(())) => {
+            // Installed on the test's thread, so parallel tests keep real time
+            let _clock = virtual_time.then(MockClock::install);
+            let mut controller = TestController {
+                capture_output,
+                update_expect,
+                output: String::new(),
+                mismatches: Vec::new(),
+            };
+            // A private debugger keeps test runs away from any breakpoints
+            // set on the global one
+            let debugger = Arc::new(Debugger::new());
+            let result = {
+                let mut interpreter = DebugInterpreter::new(debugger, "<test>", &mut controller);
+                interpreter.set_time_limit(timeout);
+                std::panic::catch_unwind(AssertUnwindSafe(|| interpreter.run_incremental(&program)))
+            };
+
+            let status = match result {
+                Ok(Ok(_)) => {
                     if should_panic.is_some() {
-                        tx.send(TestStatus::Failed(TestFailure::new(
+                        TestStatus::Failed(TestFailure::new(
                             "Expected panic but test completed successfully",
-                        )))
-                        .unwrap();
+                        ))
                     } else {
-                        tx.send(TestStatus::Passed).unwrap();
+                        TestStatus::Passed
                     }
                 }
                 Ok(Err(e)) => {
-                    tx.send(TestStatus::Failed(TestFailure::new(e.to_string())))
-                        .unwrap();
+                    // Runtime errors abort the test the same way a panic does
+                    if let Some(expected) = &should_panic {
+                        expected_panic_status(expected, &e.message)
+                    } else {
+                        let mut failure = TestFailure::new(e.message);
+                        if let Some(mismatch) = controller.mismatches.last() {
+                            failure = failure
+                                .with_location(mismatch.span)
+                                .with_comparison(&mismatch.expected, &mismatch.actual);
+                        }
+                        TestStatus::Failed(failure)
+                    }
                 }
                 Err(panic) => {
                     let msg = if let Some(s) = panic.downcast_ref::<String>() {
@@ -247,37 +276,35 @@ impl TestRunner {
                         "Unknown panic".to_string()
                     };
 
-                    if let Some(expected) = should_panic {
-                        if expected.is_empty() || msg.contains(&expected) {
-                            tx.send(TestStatus::Passed).unwrap();
-                        } else {
-                            tx.send(TestStatus::Failed(TestFailure::new(format!(
-                                "Expected panic with '{}', got '{}'",
-                                expected, msg
-                            ))))
-                            .unwrap();
-                        }
+                    if let Some(expected) = &should_panic {
+                        expected_panic_status(expected, &msg)
                     } else {
-                        tx.send(TestStatus::Panicked(msg)).unwrap();
+                        TestStatus::Panicked(msg)
                     }
                 }
-            }
+            };
+
+            let expect_updates = if update_expect {
+                controller.mismatches
+            } else {
+                Vec::new()
+            };
+            let _ = tx.send((status, controller.output, expect_updates));
         });
 
         // Wait for result with timeout
-        let status = match rx.recv_timeout(timeout) {
-            Ok(status) => status,
-            Err(_) => TestStatus::Failed(TestFailure::new(format!(
-                "Test timed out after {:?}",
-                timeout
-            ))),
+        let (status, output, expect_updates) = match rx.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(_) => (
+                TestStatus::Failed(TestFailure::new(format!(
+                    "Test timed out after {:?}",
+                    timeout
+                ))),
+                String::new(),
+                Vec::new(),
+            ),
         };
 
-        // Capture output if guard is active
-        if let Some(guard) = _guard {
-            output = guard.get_output();
-        }
-
         let duration = start.elapsed();
 
         TestResult {
@@ -285,6 +312,7 @@ impl TestRunner {
             status,
             duration,
             output,
+            expect_updates,
         }
     }
 
@@ -295,28 +323,12 @@ impl TestRunner {
         Ok(())
     }
 
-    /// Create a test program that includes the test function and assertions
-    fn create_test_program(&self, test: &TestCase) -> crate::parser::Program {
+    /// Create a program that defines the module's items and the test
+    /// function, then calls the test
+    fn create_test_program(&self, test: &TestCase, items: &[Stmt]) -> crate::parser::Program {
         use crate::parser::{Program, Stmt, StmtKind};
 
-        // Create a program with:
-        // 1. Import assertion functions
-        // 2. The test function
-        // 3. A main function that calls the test
-
-        let mut statements = vec![];
-
-        // Add import for assertions
-        statements.push(Stmt {
-            kind: StmtKind::Import {
-                imports: vec![crate::parser::ImportSpecifier::Namespace {
-                    alias: "assert".to_string(),
-                }],
-                module: "std::testing::assertions".to_string(),
-            },
-            span: test.span,
-            attributes: vec![],
-        });
+        let mut statements = items.to_vec();
 
         // Add the test function
         statements.push(Stmt {
@@ -333,67 +345,65 @@ impl TestRunner {
             attributes: vec![],
         });
 
-        // Add main function that calls the test
+        // Call the test
         statements.push(Stmt {
-            kind: StmtKind::Function {
-                name: "main".to_string(),
-                generic_params: None,
-                params: vec![],
-                ret_type: None,
-                body: crate::parser::Block {
-                    statements: vec![Stmt {
-                        kind: StmtKind::Expression(crate::parser::Expr {
-                            kind: crate::parser::ExprKind::Call {
-                                callee: Box::new(crate::parser::Expr {
-                                    kind: crate::parser::ExprKind::Identifier(test.name.clone()),
-                                    span: test.span,
-                                    id: 0, // Temporary ID for generated code
-                                }),
-                                args: vec![],
-                            },
-                            span: test.span,
-                            id: 0, // Temporary ID for generated code
-                        }),
+            kind: StmtKind::Expression(crate::parser::Expr {
+                kind: crate::parser::ExprKind::Call {
+                    callee: Box::new(crate::parser::Expr {
+                        kind: crate::parser::ExprKind::Identifier(test.name.clone()),
                         span: test.span,
-                        attributes: vec![],
-                    }],
-                    final_expr: None,
+                        id: 0, // Temporary ID for generated code
+                    }),
+                    args: vec![],
                 },
-                is_async: false,
-                where_clause: None,
-            },
+                span: test.span,
+                id: 0, // Temporary ID for generated code
+            }),
             span: test.span,
             attributes: vec![],
         });
 
-        Program { statements }
+        Program {
+            statements,
+            comments: Vec::new(),
+        }
     }
 }
 
-/// Output capture for tests
-struct OutputCapture {
-    original_stdout: Option<Box<dyn std::io::Write + Send>>,
-    buffer: Arc<Mutex<Vec<u8>>>,
+fn expected_panic_status(expected: &str, msg: &str) -> TestStatus {
+    if expected.is_empty() || msg.contains(expected) {
+        TestStatus::Passed
+    } else {
+        TestStatus::Failed(TestFailure::new(format!(
+            "Expected panic with '{}', got '{}'",
+            expected, msg
+        )))
+    }
 }
 
-impl OutputCapture {
-    fn new() -> Self {
-        let buffer = Arc::new(Mutex::new(Vec::new()));
-        // In a real implementation, we would redirect stdout/stderr here
-        Self {
-            original_stdout: None,
-            buffer,
-        }
+/// Runs a test to completion, capturing its output and `expect` mismatches
+struct TestController {
+    capture_output: bool,
+    update_expect: bool,
+    output: String,
+    mismatches: Vec<ExpectMismatch>,
+}
+
+impl StepController for TestController {
+    fn on_pause(&mut self, _state: &PauseState<'_>) -> StepAction {
+        StepAction::Continue
     }
 
-    fn get_output(&self) -> String {
-        let buffer = self.buffer.lock().unwrap();
-        String::from_utf8_lossy(&buffer).to_string()
+    fn on_output(&mut self, text: &str) {
+        if self.capture_output {
+            self.output.push_str(text);
+        } else {
+            print!("{}", text);
+        }
     }
-}
 
-impl Drop for OutputCapture {
-    fn drop(&mut self) {
-        // Restore original stdout/stderr
+    fn on_expect_mismatch(&mut self, mismatch: &ExpectMismatch) -> bool {
+        self.mismatches.push(mismatch.clone());
+        self.update_expect
     }
 }
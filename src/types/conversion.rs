@@ -1,4 +1,5 @@
 use super::Type;
+use crate::lexer::NumberSuffix;
 use crate::parser::{BinaryOp, Literal, TypeAnn, TypeKind, UnaryOp};
 
 /// Convert AST type annotations to internal type representation
@@ -6,7 +7,11 @@ pub fn type_from_ast(type_ann: &TypeAnn) -> Type {
     match &type_ann.kind {
         TypeKind::Named(name) => match name.as_str() {
             "i32" => Type::I32,
+            "i64" => Type::I64,
+            "u32" => Type::U32,
+            "u64" => Type::U64,
             "f32" => Type::F32,
+            "f64" => Type::F64,
             "bool" => Type::Bool,
             "string" => Type::String,
             "unknown" => Type::Unknown,
@@ -52,20 +57,32 @@ pub fn type_ann_to_type(type_ann: &TypeAnn) -> Type {
 pub fn infer_literal_type(literal: &Literal) -> Type {
     match literal {
         Literal::Number(_) => Type::F32, // Default to f32 for now
+        Literal::TypedNumber(_, suffix) => suffix_type(*suffix),
         Literal::String(_) => Type::String,
         Literal::Boolean(_) => Type::Bool,
         Literal::Null => Type::Option(Box::new(Type::Unknown)), // Null represents an optional type
     }
 }
 
+/// Type named by the suffix of a numeric literal
+pub fn suffix_type(suffix: NumberSuffix) -> Type {
+    match suffix {
+        NumberSuffix::I32 => Type::I32,
+        NumberSuffix::I64 => Type::I64,
+        NumberSuffix::U32 => Type::U32,
+        NumberSuffix::U64 => Type::U64,
+        NumberSuffix::F32 => Type::F32,
+        NumberSuffix::F64 => Type::F64,
+    }
+}
+
 /// Get the result type of a binary operation
 pub fn binary_op_result_type(left: &Type, right: &Type, op: &BinaryOp) -> Result<Type, String> {
     match op {
         // Arithmetic operations
         BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => {
             match (left, right) {
-                (Type::I32, Type::I32) => Ok(Type::I32),
-                (Type::F32, Type::F32) => Ok(Type::F32),
+                (left, right) if left.is_numeric() && left == right => Ok(left.clone()),
                 (Type::Unknown, _) | (_, Type::Unknown) => Ok(Type::Unknown),
                 _ => Err(format!(
                     "Cannot apply arithmetic operation to types {} and {}",
@@ -111,14 +128,31 @@ pub fn unary_op_result_type(operand: &Type, op: &UnaryOp) -> Result<Type, String
             _ => Err(format!("Cannot apply logical NOT to type {}", operand)),
         },
         UnaryOp::Minus => match operand {
-            Type::I32 => Ok(Type::I32),
-            Type::F32 => Ok(Type::F32),
+            Type::I32 | Type::I64 | Type::F32 | Type::F64 => Ok(operand.clone()),
             Type::Unknown => Ok(Type::Unknown),
             _ => Err(format!("Cannot negate type {}", operand)),
         },
     }
 }
 
+/// Get the result type of `cast<target>(value)` for a value of type `source`
+///
+/// Casts convert between numeric types, and from `bool` to an integer
+/// (`false` is 0, `true` is 1). Integer to integer casts wrap, float to
+/// integer casts truncate toward zero and saturate at the target's bounds
+/// (NaN becomes 0), and casts to a float round to the nearest value.
+pub fn cast_result_type(source: &Type, target: &Type) -> Result<Type, String> {
+    if !target.is_numeric() {
+        return Err(format!("Cannot cast to non-numeric type {}", target));
+    }
+    match source {
+        source if source.is_numeric() => Ok(target.clone()),
+        Type::Bool if target.is_integer() => Ok(target.clone()),
+        Type::Unknown | Type::TypeVar(_) => Ok(target.clone()),
+        _ => Err(format!("Cannot cast {} to {}", source, target)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -274,4 +308,15 @@ mod tests {
         assert!(unary_op_result_type(&Type::String, &UnaryOp::Not).is_err());
         assert!(unary_op_result_type(&Type::Bool, &UnaryOp::Minus).is_err());
     }
+
+    #[test]
+    fn test_cast_result_type() {
+        assert_eq!(cast_result_type(&Type::F64, &Type::I32), Ok(Type::I32));
+        assert_eq!(cast_result_type(&Type::Bool, &Type::U64), Ok(Type::U64));
+        assert_eq!(cast_result_type(&Type::Unknown, &Type::F32), Ok(Type::F32));
+
+        assert!(cast_result_type(&Type::Bool, &Type::F32).is_err());
+        assert!(cast_result_type(&Type::String, &Type::I32).is_err());
+        assert!(cast_result_type(&Type::I32, &Type::Bool).is_err());
+    }
 }
@@ -172,7 +172,11 @@ impl TypeDefinitionRegistry {
 fn mangle_type(ty: &Type) -> String {
     match ty {
         Type::I32 => "i32".to_string(),
+        Type::I64 => "i64".to_string(),
+        Type::U32 => "u32".to_string(),
+        Type::U64 => "u64".to_string(),
         Type::F32 => "f32".to_string(),
+        Type::F64 => "f64".to_string(),
         Type::Bool => "bool".to_string(),
         Type::String => "string".to_string(),
         Type::Unknown => "unknown".to_string(),
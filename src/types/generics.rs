@@ -226,6 +226,24 @@ impl GenericParams {
         self
     }
 
+    /// Convert the generic parameters of a parsed declaration
+    pub fn from_ast(generics: &crate::parser::GenericParams) -> Self {
+        let params = generics
+            .params
+            .iter()
+            .map(|param| TypeParam {
+                name: param.name.clone(),
+                bounds: param
+                    .bounds
+                    .iter()
+                    .map(|bound| TraitBound::new(bound.trait_name.clone(), bound.span))
+                    .collect(),
+                span: param.span,
+            })
+            .collect();
+        GenericParams::new(params, generics.span)
+    }
+
     /// Check if these generic parameters are empty
     pub fn is_empty(&self) -> bool {
         self.params.is_empty() && self.where_clause.is_none()
@@ -322,7 +340,16 @@ impl GenericEnv {
 
     /// Initialize implementations of built-in traits for primitive types
     fn init_builtin_impls(&mut self) {
-        let primitive_types = vec![Type::I32, Type::F32, Type::Bool, Type::String];
+        let primitive_types = vec![
+            Type::I32,
+            Type::I64,
+            Type::U32,
+            Type::U64,
+            Type::F32,
+            Type::F64,
+            Type::Bool,
+            Type::String,
+        ];
 
         for type_ in primitive_types {
             // All primitives implement basic traits
@@ -340,13 +367,13 @@ impl GenericEnv {
                 .insert((type_.clone(), BuiltinTrait::Hash), true);
 
             // Numeric types implement Ord
-            if matches!(type_, Type::I32 | Type::F32) {
+            if type_.is_numeric() {
                 self.trait_impls
                     .insert((type_.clone(), BuiltinTrait::Ord), true);
             }
 
             // Simple types implement Copy
-            if matches!(type_, Type::I32 | Type::F32 | Type::Bool) {
+            if type_.is_numeric() || type_ == Type::Bool {
                 self.trait_impls
                     .insert((type_.clone(), BuiltinTrait::Copy), true);
             }
@@ -10,8 +10,16 @@ pub mod generics;
 pub enum Type {
     /// 32-bit signed integer
     I32,
+    /// 64-bit signed integer
+    I64,
+    /// 32-bit unsigned integer
+    U32,
+    /// 64-bit unsigned integer
+    U64,
     /// 32-bit floating point
     F32,
+    /// 64-bit floating point
+    F64,
     /// Boolean type
     Bool,
     /// String type
@@ -58,7 +66,11 @@ impl Type {
 
             // Basic types must match exactly
             (Type::I32, Type::I32) => true,
+            (Type::I64, Type::I64) => true,
+            (Type::U32, Type::U32) => true,
+            (Type::U64, Type::U64) => true,
             (Type::F32, Type::F32) => true,
+            (Type::F64, Type::F64) => true,
             (Type::Bool, Type::Bool) => true,
             (Type::String, Type::String) => true,
 
@@ -172,12 +184,22 @@ impl Type {
 
     /// Check if this type is a numeric type
     pub fn is_numeric(&self) -> bool {
-        matches!(self, Type::I32 | Type::F32)
+        self.is_integer() || self.is_float()
+    }
+
+    /// Check if this type is an integer type
+    pub fn is_integer(&self) -> bool {
+        matches!(self, Type::I32 | Type::I64 | Type::U32 | Type::U64)
+    }
+
+    /// Check if this type is a floating point type
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::F32 | Type::F64)
     }
 
     /// Check if this type is comparable (for comparison operators)
     pub fn is_comparable(&self) -> bool {
-        matches!(self, Type::I32 | Type::F32 | Type::Bool | Type::String)
+        self.is_numeric() || matches!(self, Type::Bool | Type::String)
     }
 
     /// Get the return type of a function type
@@ -227,7 +249,11 @@ impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Type::I32 => write!(f, "i32"),
+            Type::I64 => write!(f, "i64"),
+            Type::U32 => write!(f, "u32"),
+            Type::U64 => write!(f, "u64"),
             Type::F32 => write!(f, "f32"),
+            Type::F64 => write!(f, "f64"),
             Type::Bool => write!(f, "bool"),
             Type::String => write!(f, "string"),
             Type::Unknown => write!(f, "unknown"),
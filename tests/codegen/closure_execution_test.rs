@@ -12,13 +12,16 @@ fn test_simple_closure_execution() {
     // Create the closure function body
     let add_one = builder.create_function(
         "add_one_closure".to_string(),
-        vec![Parameter { name: "x".to_string(), ty: Type::I32 }],
+        vec![
+            Parameter { name: "env".to_string(), ty: Type::Unknown },
+            Parameter { name: "x".to_string(), ty: Type::I32 }
+        ],
         Type::I32
     );
     builder.set_current_function(add_one);
     
-    // Get parameter and add 1
-    let param = script::ir::ValueId(1000); // First parameter
+    // Get parameter and add 1 (the environment is parameter 0)
+    let param = script::ir::ValueId(1001);
     let one = builder.const_value(script::ir::Constant::I32(1));
     let result = builder.build_binary(BinaryOp::Add, param, one, Type::I32).unwrap();
     builder.build_return(Some(result));
@@ -34,6 +37,7 @@ fn test_simple_closure_execution() {
     // Create closure
     let closure = builder.build_create_closure(
         "add_one_closure".to_string(),
+        add_one,
         vec!["x".to_string()],
         vec![],
         false
@@ -65,6 +69,23 @@ fn test_closure_with_captures() {
     // Create a closure that captures a value
     let mut builder = IrBuilder::new();
     
+    // Create the closure function, reading the capture from its environment
+    let add_captured = builder.create_function(
+        "add_captured".to_string(),
+        vec![
+            Parameter { name: "env".to_string(), ty: Type::Unknown },
+            Parameter { name: "x".to_string(), ty: Type::I32 }
+        ],
+        Type::I32
+    );
+    builder.set_current_function(add_captured);
+    
+    let env = script::ir::ValueId(1000);
+    let x = script::ir::ValueId(1001);
+    let captured = builder.build_load_capture(env, 0, Type::I32).unwrap();
+    let result = builder.build_binary(BinaryOp::Add, x, captured, Type::I32).unwrap();
+    builder.build_return(Some(result));
+    
     // Create main function
     let main = builder.create_function(
         "main".to_string(),
@@ -79,6 +100,7 @@ fn test_closure_with_captures() {
     // Create closure that adds captured value to its argument
     let closure = builder.build_create_closure(
         "add_captured".to_string(),
+        add_captured,
         vec!["x".to_string()],
         vec![("captured".to_string(), captured_value)],
         false
@@ -103,14 +125,15 @@ fn test_closure_with_captures() {
 }
 
 #[test]
-fn test_closure_fast_path() {
-    // Test that fast-path optimization works for small closures
+fn test_closure_direct_call() {
+    // Test that invoking a closure with a known target calls it directly
     let mut builder = IrBuilder::new();
     
     // Create a closure function that multiplies two arguments
     let multiply = builder.create_function(
         "multiply".to_string(),
         vec![
+            Parameter { name: "env".to_string(), ty: Type::Unknown },
             Parameter { name: "x".to_string(), ty: Type::I32 },
             Parameter { name: "y".to_string(), ty: Type::I32 }
         ],
@@ -118,8 +141,8 @@ fn test_closure_fast_path() {
     );
     builder.set_current_function(multiply);
     
-    let x = script::ir::ValueId(1000); // First parameter
-    let y = script::ir::ValueId(1001); // Second parameter
+    let x = script::ir::ValueId(1001); // First parameter after the environment
+    let y = script::ir::ValueId(1002); // Second parameter
     let result = builder.build_binary(BinaryOp::Mul, x, y, Type::I32).unwrap();
     builder.build_return(Some(result));
     
@@ -134,12 +157,13 @@ fn test_closure_fast_path() {
     // Create closure
     let closure = builder.build_create_closure(
         "multiply".to_string(),
+        multiply,
         vec!["x".to_string(), "y".to_string()],
         vec![],
         false
     ).unwrap();
     
-    // Invoke with 2 arguments (should use fast path)
+    // Invoke with 2 arguments
     let arg1 = builder.const_value(script::ir::Constant::I32(6));
     let arg2 = builder.const_value(script::ir::Constant::I32(7));
     let result = builder.build_invoke_closure(closure, vec![arg1, arg2], Type::I32).unwrap();
@@ -59,6 +59,7 @@ fn test_dependency_analyzer_basic() {
 
     let program = Program {
         statements: vec![import_stmt],
+        comments: Vec::new(),
     };
 
     let deps = analyzer.analyze(&program, None);
@@ -106,6 +107,7 @@ fn test_dependency_analyzer_multiple_imports() {
 
     let program = Program {
         statements: vec![import1, import2],
+        comments: Vec::new(),
     };
 
     let deps = analyzer.analyze(&program, None);
@@ -1,5 +1,6 @@
 use script::{
     parser::{Expr, ExprKind, Literal, MatchArm, Pattern, PatternKind, Program, Stmt, StmtKind},
+    semantic::SemanticWarningKind,
     Lexer, Parser, Result, SemanticAnalyzer,
 };
 
@@ -790,3 +791,174 @@ fn test_nested_enum_patterns() {
         }
     }
 }
+
+// Test guards on enum patterns that use the variant's bindings
+#[test]
+fn test_enum_pattern_guards() {
+    let input = r#"
+        enum Reading {
+            Value(i32),
+            Missing
+        }
+
+        fn describe(reading: Reading) -> i32 {
+            match reading {
+                Value(x) if x > 0 => x,
+                Value(_) => 0,
+                Missing => -1
+            }
+        }
+    "#;
+
+    let program = parse_and_analyze(input).unwrap();
+    assert_eq!(program.statements.len(), 2);
+}
+
+// Test tuple patterns nested inside enum variants
+#[test]
+fn test_tuple_patterns_in_enum_variants() {
+    let input = r#"
+        let result = match shape {
+            Segment((0, y), end) => y,
+            Segment((x, _), (a,)) => x,
+            Point(p) => (p)
+        }
+    "#;
+
+    let program = parse_program(input).unwrap();
+
+    if let StmtKind::Let {
+        init: Some(expr), ..
+    } = &program.statements[0].kind
+    {
+        if let ExprKind::Match { arms, .. } = &expr.kind {
+            let PatternKind::EnumConstructor {
+                args: Some(args), ..
+            } = &arms[0].pattern.kind
+            else {
+                panic!("Expected enum pattern");
+            };
+            let PatternKind::Tuple(elements) = &args[0].kind else {
+                panic!("Expected tuple pattern");
+            };
+            assert_eq!(elements.len(), 2);
+            assert!(matches!(
+                elements[0].kind,
+                PatternKind::Literal(Literal::Number(n)) if n == 0.0
+            ));
+            assert!(matches!(elements[1].kind, PatternKind::Identifier(ref name) if name == "y"));
+            assert!(matches!(args[1].kind, PatternKind::Identifier(ref name) if name == "end"));
+
+            // A trailing comma makes a one-element tuple
+            let PatternKind::EnumConstructor {
+                args: Some(args), ..
+            } = &arms[1].pattern.kind
+            else {
+                panic!("Expected enum pattern");
+            };
+            assert!(matches!(&args[1].kind, PatternKind::Tuple(elements) if elements.len() == 1));
+            assert_eq!(arms[1].pattern.to_string(), "Segment((x, _), (a,))");
+        }
+    }
+}
+
+// Test `name @ pattern` bindings
+#[test]
+fn test_binding_patterns() {
+    let input = r#"
+        let x = 5
+        let result = match x {
+            small @ (1 | 2 | 3) => small,
+            n @ _ if n > 100 => 100,
+            other => other
+        }
+    "#;
+
+    let program = parse_program(input).unwrap();
+
+    if let StmtKind::Let {
+        init: Some(expr), ..
+    } = &program.statements[1].kind
+    {
+        if let ExprKind::Match { arms, .. } = &expr.kind {
+            let PatternKind::Binding { name, pattern } = &arms[0].pattern.kind else {
+                panic!("Expected binding pattern");
+            };
+            assert_eq!(name, "small");
+            assert!(matches!(&pattern.kind, PatternKind::Or(patterns) if patterns.len() == 3));
+
+            assert!(matches!(arms[1].pattern.kind, PatternKind::Binding { .. }));
+            assert!(arms[1].guard.is_some());
+        }
+    }
+
+    // Bindings are in scope in both the guard and the arm body
+    parse_and_analyze(input).unwrap();
+}
+
+// Test that a binding around a wildcard still makes a match exhaustive
+#[test]
+fn test_binding_pattern_exhaustiveness() {
+    let input = r#"
+        let flag = true
+        let result = match flag {
+            true => 1,
+            rest @ _ => 0
+        }
+    "#;
+
+    parse_and_analyze(input).unwrap();
+}
+
+// Test that arms covered by earlier arms are reported as unreachable
+#[test]
+fn test_unreachable_arm_warning() {
+    let input = r#"
+        let flag = true
+        let result = match flag {
+            true => 1,
+            _ => 0,
+            false => 2
+        }
+    "#;
+
+    let program = parse_program(input).unwrap();
+    let mut analyzer = SemanticAnalyzer::new();
+    analyzer.analyze_program(&program).unwrap();
+
+    let unreachable: Vec<_> = analyzer
+        .warnings()
+        .iter()
+        .filter_map(|warning| match warning.kind {
+            SemanticWarningKind::UnreachablePattern { arm } => Some(arm),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(unreachable, vec![2]);
+}
+
+// Test that a match missing a nested pattern is rejected
+#[test]
+fn test_nested_non_exhaustive_match_error() {
+    let input = r#"
+        enum Option<T> {
+            Some(T),
+            None
+        }
+
+        let x = Option::Some(true)
+        let result = match x {
+            Some(true) => 1,
+            None => 0
+            // Missing Some(false) - should error
+        }
+    "#;
+
+    let result = parse_and_analyze(input);
+    assert!(result.is_err());
+
+    if let Err(err) = result {
+        let error_msg = format!("{}", err);
+        assert!(error_msg.contains("exhaustive") || error_msg.contains("Some(false)"));
+    }
+}
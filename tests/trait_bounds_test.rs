@@ -0,0 +1,171 @@
+use script::{
+    semantic::{SemanticError, SemanticErrorKind},
+    Lexer, Parser, SemanticAnalyzer,
+};
+
+fn analyze(input: &str) -> Vec<SemanticError> {
+    let lexer = Lexer::new(input).unwrap();
+    let (tokens, errors) = lexer.scan_tokens();
+    assert!(errors.is_empty(), "lexer errors: {:?}", errors);
+
+    let mut parser = Parser::new(tokens);
+    let program = parser.parse().unwrap();
+
+    let mut analyzer = SemanticAnalyzer::new();
+    let _ = analyzer.analyze_program(&program);
+    analyzer.errors().to_vec()
+}
+
+const DOUBLE: &str = r#"
+    trait Double {
+        fn double(self) -> i32;
+    }
+
+    impl Double for i32 {
+        fn double(self) -> i32 {
+            self * 2
+        }
+    }
+"#;
+
+#[test]
+fn test_trait_method_on_bounded_type_param() {
+    let errors = analyze(&format!(
+        "{DOUBLE}
+        fn twice<T: Double>(x: T) -> i32 {{
+            x.double()
+        }}
+        let y = twice(21)
+        "
+    ));
+    assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+}
+
+#[test]
+fn test_trait_method_without_bound() {
+    let errors = analyze(&format!(
+        "{DOUBLE}
+        fn twice<T>(x: T) -> i32 {{
+            x.double()
+        }}
+        "
+    ));
+    let error = errors
+        .iter()
+        .find(|e| matches!(e.kind, SemanticErrorKind::MethodNotFound { .. }))
+        .expect("expected a missing method error");
+    assert!(error.notes.iter().any(|note| note.contains("T: Double")));
+}
+
+#[test]
+fn test_bound_not_satisfied_at_call_site() {
+    let errors = analyze(&format!(
+        "{DOUBLE}
+        fn twice<T: Double>(x: T) -> i32 {{
+            x.double()
+        }}
+        let y = twice(true)
+        "
+    ));
+    assert!(errors.iter().any(|e| matches!(
+        &e.kind,
+        SemanticErrorKind::TraitBoundNotSatisfied { trait_name, .. } if trait_name == "Double"
+    )));
+}
+
+#[test]
+fn test_impl_missing_trait_method() {
+    let errors = analyze(
+        r#"
+        trait Shape {
+            fn area(self) -> i32;
+            fn sides(self) -> i32;
+        }
+
+        impl Shape for i32 {
+            fn area(self) -> i32 {
+                self * self
+            }
+        }
+        "#,
+    );
+    assert!(errors.iter().any(|e| matches!(
+        &e.kind,
+        SemanticErrorKind::MissingTraitMethod { method_name, .. } if method_name == "sides"
+    )));
+}
+
+#[test]
+fn test_impl_method_not_in_trait() {
+    let errors = analyze(
+        r#"
+        trait Double {
+            fn double(self) -> i32;
+        }
+
+        impl Double for i32 {
+            fn double(self) -> i32 {
+                self * 2
+            }
+            fn triple(self) -> i32 {
+                self * 3
+            }
+        }
+        "#,
+    );
+    assert!(errors.iter().any(|e| matches!(
+        &e.kind,
+        SemanticErrorKind::MethodNotInTrait { method_name, .. } if method_name == "triple"
+    )));
+}
+
+#[test]
+fn test_default_method_is_inherited() {
+    let errors = analyze(
+        r#"
+        trait Scale {
+            fn factor(self) -> i32;
+            fn scaled(self, by: i32) -> i32 {
+                self.factor() * by
+            }
+        }
+
+        impl Scale for i32 {
+            fn factor(self) -> i32 {
+                self
+            }
+        }
+
+        let x = 4
+        let y = x.scaled(3)
+        "#,
+    );
+    assert!(errors.is_empty(), "unexpected errors: {:?}", errors);
+}
+
+#[test]
+fn test_undefined_trait() {
+    let errors = analyze(
+        r#"
+        impl Missing for i32 {
+            fn go(self) -> i32 {
+                self
+            }
+        }
+        "#,
+    );
+    assert!(errors
+        .iter()
+        .any(|e| matches!(&e.kind, SemanticErrorKind::UndefinedTrait(name) if name == "Missing")));
+
+    let errors = analyze(
+        r#"
+        fn show<T: Missing>(x: T) -> T {
+            x
+        }
+        "#,
+    );
+    assert!(errors
+        .iter()
+        .any(|e| matches!(&e.kind, SemanticErrorKind::UndefinedTrait(name) if name == "Missing")));
+}